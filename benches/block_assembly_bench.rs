@@ -0,0 +1,491 @@
+#[macro_use]
+extern crate criterion;
+extern crate subnet_lib;
+
+use std::fs;
+use std::time::{Duration, Instant};
+
+use criterion::{BenchmarkId, Criterion};
+
+use subnet_lib::address::AddressHashMode;
+use subnet_lib::burnchains::{Burnchain, BurnchainBlockHeader, Txid};
+use subnet_lib::chainstate::burn::db::sortdb::SortitionDB;
+use subnet_lib::chainstate::burn::operations::{BlockstackOperationType, LeaderBlockCommitOp};
+use subnet_lib::chainstate::burn::{BlockSnapshot, ConsensusHash};
+use subnet_lib::chainstate::stacks::db::blocks::DummyEventDispatcher;
+use subnet_lib::chainstate::stacks::db::{ChainStateBootData, StacksChainState, StacksHeaderInfo};
+use subnet_lib::chainstate::stacks::miner::{BlockBuilderSettings, StacksBlockBuilder};
+use subnet_lib::chainstate::stacks::{
+    CoinbasePayload, StacksBlock, StacksPrivateKey, StacksPublicKey, StacksTransaction,
+    StacksTransactionSigner, TokenTransferMemo, TransactionAnchorMode, TransactionAuth,
+    TransactionPayload, TransactionVersion, C32_ADDRESS_VERSION_TESTNET_SINGLESIG,
+};
+use subnet_lib::core::mempool::MemPoolDB;
+use subnet_lib::core::{
+    FIRST_BURNCHAIN_CONSENSUS_HASH, SUBNET_BLOCK_LIMIT, SUBNET_CHAIN_ID, SUBNET_EPOCHS,
+};
+use subnet_lib::cost_estimates::metrics::UnitMetric;
+use subnet_lib::cost_estimates::UnitEstimator;
+use subnet_lib::types::chainstate::{BurnchainHeaderHash, StacksAddress};
+use subnet_lib::util::hash::Hash160;
+use subnet_lib::util::vrf::{VRFPrivateKey, VRF};
+use subnet_lib::vm::types::{PrincipalData, QualifiedContractIdentifier};
+use subnet_lib::vm::{ClarityVersion, Value};
+
+/// Number of funded accounts submitting to the synthetic mempool
+const NUM_SENDERS: usize = 1000;
+/// Number of chained transactions per account; must stay below the mempool's chaining limit
+const TXS_PER_SENDER: u64 = 20;
+
+const BENCH_CONTRACT_NAME: &str = "block-bench";
+
+/// Contract exercised by the contract-call workloads.  `ping` is nearly free, `store` is
+/// dominated by MARF writes and `crunch` by interpreter runtime.
+const BENCH_CONTRACT: &str = "
+(define-map entries uint uint)
+(define-constant SLOTS (list u0 u1 u2 u3 u4 u5 u6 u7 u8 u9 u10 u11 u12 u13 u14 u15))
+
+(define-private (store-one (slot uint) (seed uint))
+  (begin
+    (map-set entries (+ (* seed u16) slot) seed)
+    seed))
+
+(define-private (crunch-one (slot uint) (acc (buff 32)))
+  (sha256 (concat acc (sha256 (+ slot (len acc))))))
+
+(define-public (ping)
+  (ok true))
+
+(define-public (store (seed uint))
+  (ok (fold store-one SLOTS seed)))
+
+(define-public (crunch (seed uint))
+  (ok (fold crunch-one SLOTS (fold crunch-one SLOTS (sha256 seed)))))
+";
+
+/// The kinds of transactions that fill the synthetic mempool
+#[derive(Clone, Copy, Debug)]
+enum Workload {
+    TokenTransfers,
+    LightContractCalls,
+    StorageContractCalls,
+    ComputeContractCalls,
+    Mixed,
+}
+
+impl Workload {
+    fn all() -> [Workload; 5] {
+        [
+            Workload::TokenTransfers,
+            Workload::LightContractCalls,
+            Workload::StorageContractCalls,
+            Workload::ComputeContractCalls,
+            Workload::Mixed,
+        ]
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Workload::TokenTransfers => "token-transfers",
+            Workload::LightContractCalls => "light-contract-calls",
+            Workload::StorageContractCalls => "storage-contract-calls",
+            Workload::ComputeContractCalls => "compute-contract-calls",
+            Workload::Mixed => "mixed",
+        }
+    }
+
+    fn payload(
+        &self,
+        sender_index: usize,
+        nonce: u64,
+        contract_addr: &StacksAddress,
+    ) -> TransactionPayload {
+        let seed = Value::UInt((sender_index as u128) * (TXS_PER_SENDER as u128) + nonce as u128);
+        let workload = match self {
+            Workload::Mixed => match (sender_index as u64 + nonce) % 4 {
+                0 => Workload::TokenTransfers,
+                1 => Workload::LightContractCalls,
+                2 => Workload::StorageContractCalls,
+                _ => Workload::ComputeContractCalls,
+            },
+            other => *other,
+        };
+        match workload {
+            Workload::TokenTransfers => TransactionPayload::TokenTransfer(
+                PrincipalData::Contract(QualifiedContractIdentifier::new(
+                    contract_addr.clone().into(),
+                    BENCH_CONTRACT_NAME.into(),
+                )),
+                1,
+                TokenTransferMemo([0u8; 34]),
+            ),
+            Workload::LightContractCalls => TransactionPayload::new_contract_call(
+                contract_addr.clone(),
+                BENCH_CONTRACT_NAME,
+                "ping",
+                vec![],
+            )
+            .unwrap(),
+            Workload::StorageContractCalls => TransactionPayload::new_contract_call(
+                contract_addr.clone(),
+                BENCH_CONTRACT_NAME,
+                "store",
+                vec![seed],
+            )
+            .unwrap(),
+            Workload::ComputeContractCalls => TransactionPayload::new_contract_call(
+                contract_addr.clone(),
+                BENCH_CONTRACT_NAME,
+                "crunch",
+                vec![seed],
+            )
+            .unwrap(),
+            Workload::Mixed => unreachable!(),
+        }
+    }
+}
+
+fn to_addr(sk: &StacksPrivateKey) -> StacksAddress {
+    StacksAddress::from_public_keys(
+        C32_ADDRESS_VERSION_TESTNET_SINGLESIG,
+        &AddressHashMode::SerializeP2PKH,
+        1,
+        &vec![StacksPublicKey::from_private(sk)],
+    )
+    .unwrap()
+}
+
+fn make_tx(
+    sk: &StacksPrivateKey,
+    nonce: u64,
+    fee: u64,
+    anchor_mode: TransactionAnchorMode,
+    payload: TransactionPayload,
+) -> StacksTransaction {
+    let mut tx = StacksTransaction::new(
+        TransactionVersion::Testnet,
+        TransactionAuth::from_p2pkh(sk).unwrap(),
+        payload,
+    );
+    tx.chain_id = SUBNET_CHAIN_ID;
+    tx.anchor_mode = anchor_mode;
+    tx.set_tx_fee(fee);
+    tx.set_origin_nonce(nonce);
+
+    let mut signer = StacksTransactionSigner::new(&tx);
+    signer.sign_origin(sk).unwrap();
+    signer.get_tx().unwrap()
+}
+
+fn make_coinbase(sk: &StacksPrivateKey, nonce: u64) -> StacksTransaction {
+    make_tx(
+        sk,
+        nonce,
+        0,
+        TransactionAnchorMode::OnChainOnly,
+        TransactionPayload::Coinbase(CoinbasePayload([0u8; 32])),
+    )
+}
+
+/// A subnet chainstate with one processed block (which deploys the bench contract) and a
+/// mempool full of transactions that build on top of it.
+struct BenchChain {
+    burnchain: Burnchain,
+    sortdb: SortitionDB,
+    chainstate: StacksChainState,
+    mempool: MemPoolDB,
+    miner_sk: StacksPrivateKey,
+    vrf_key: VRFPrivateKey,
+    /// Sortition which elected the processed block
+    tip_snapshot: BlockSnapshot,
+    /// Header of the processed block, which assembled blocks build on
+    tip_header: StacksHeaderInfo,
+    /// Used to derive unique burnchain block hashes and commit txids
+    burn_nonce: u64,
+}
+
+impl BenchChain {
+    fn new(workload: Workload) -> BenchChain {
+        let path = format!("/tmp/stacks-subnets-benches/{}", workload.name());
+        if fs::metadata(&path).is_ok() {
+            fs::remove_dir_all(&path).unwrap();
+        }
+
+        let miner_sk = StacksPrivateKey::new();
+        let vrf_key = VRFPrivateKey::new();
+        let senders: Vec<_> = (0..NUM_SENDERS).map(|_| StacksPrivateKey::new()).collect();
+        let initial_balances = senders
+            .iter()
+            .map(|sk| (PrincipalData::from(to_addr(sk)), 1_000_000_000_000))
+            .collect();
+
+        let burnchain = Burnchain::regtest(&format!("{}/burnchain/db/", &path));
+        let sortdb = SortitionDB::connect(
+            &burnchain.get_db_path(),
+            burnchain.first_block_height,
+            &SUBNET_EPOCHS[..],
+            true,
+        )
+        .unwrap();
+
+        let chainstate_path = format!("{}/chainstate/", &path);
+        let mut boot_data = ChainStateBootData::new(&burnchain, initial_balances, None);
+        let (chainstate, _) = StacksChainState::open_and_exec(
+            false,
+            SUBNET_CHAIN_ID,
+            &chainstate_path,
+            Some(&mut boot_data),
+            None,
+        )
+        .unwrap();
+
+        let mempool = MemPoolDB::open(
+            false,
+            SUBNET_CHAIN_ID,
+            &chainstate_path,
+            Box::new(UnitEstimator),
+            Box::new(UnitMetric),
+        )
+        .unwrap();
+
+        let first_snapshot = SortitionDB::get_first_block_snapshot(sortdb.conn()).unwrap();
+        let mut chain = BenchChain {
+            burnchain,
+            sortdb,
+            chainstate,
+            mempool,
+            miner_sk,
+            vrf_key,
+            tip_header: StacksHeaderInfo::regtest_genesis(),
+            tip_snapshot: first_snapshot,
+            burn_nonce: 0,
+        };
+
+        // the first block deploys the contract that the mempool transactions call
+        let deployer_sk = &senders[0];
+        let block = chain.make_first_block(make_tx(
+            deployer_sk,
+            0,
+            100_000,
+            TransactionAnchorMode::OnChainOnly,
+            TransactionPayload::new_smart_contract(
+                BENCH_CONTRACT_NAME,
+                BENCH_CONTRACT,
+                Some(ClarityVersion::Clarity2),
+            )
+            .unwrap(),
+        ));
+        let parent_snapshot = chain.tip_snapshot.clone();
+        let snapshot = chain.commit_block(&parent_snapshot, &block);
+        chain.preprocess_block(&snapshot, &FIRST_BURNCHAIN_CONSENSUS_HASH, &block);
+        let (header, _) = chain.process_block(&snapshot);
+        chain.tip_header = header;
+        chain.tip_snapshot = snapshot;
+
+        chain.fill_mempool(workload, &senders, &to_addr(deployer_sk));
+        chain
+    }
+
+    /// Build the first block off of the boot state: the coinbase plus `tx`.
+    fn make_first_block(&mut self, tx: StacksTransaction) -> StacksBlock {
+        let proof = VRF::prove(&self.vrf_key, self.tip_snapshot.sortition_hash.as_bytes());
+        let mut builder = StacksBlockBuilder::make_regtest_block_builder(
+            &self.tip_header,
+            proof,
+            0,
+            Hash160::from_node_public_key(&StacksPublicKey::from_private(&self.miner_sk)),
+        )
+        .unwrap();
+
+        let ic = self.sortdb.index_conn();
+        let mut miner_epoch_info = builder.pre_epoch_begin(&mut self.chainstate, &ic).unwrap();
+        let mut epoch_tx = builder.epoch_begin(&ic, &mut miner_epoch_info).unwrap().0;
+
+        builder
+            .try_mine_tx(&mut epoch_tx, &make_coinbase(&self.miner_sk, 0))
+            .unwrap();
+        builder.try_mine_tx(&mut epoch_tx, &tx).unwrap();
+
+        let block = builder.mine_anchored_block(&mut epoch_tx);
+        builder.epoch_finish(epoch_tx);
+        block
+    }
+
+    /// Submit `TXS_PER_SENDER` chained transactions from every sender.  Fees vary so that the
+    /// mempool walk has to order candidates.
+    fn fill_mempool(
+        &mut self,
+        workload: Workload,
+        senders: &[StacksPrivateKey],
+        contract_addr: &StacksAddress,
+    ) {
+        let epoch_id =
+            SortitionDB::get_stacks_epoch(self.sortdb.conn(), self.tip_snapshot.block_height + 1)
+                .unwrap()
+                .expect("no epoch defined for the next block")
+                .epoch_id;
+
+        for (sender_index, sk) in senders.iter().enumerate() {
+            // the contract deployment already consumed the deployer's first nonce
+            let first_nonce = if sender_index == 0 { 1 } else { 0 };
+            for nonce in first_nonce..(first_nonce + TXS_PER_SENDER) {
+                let fee = 10_000 + ((sender_index as u64 * 7919 + nonce) % 1000);
+                let tx = make_tx(
+                    sk,
+                    nonce,
+                    fee,
+                    TransactionAnchorMode::Any,
+                    workload.payload(sender_index, nonce, contract_addr),
+                );
+                self.mempool
+                    .submit(
+                        &mut self.chainstate,
+                        &self.tip_header.consensus_hash,
+                        &self.tip_header.anchored_header.block_hash(),
+                        &tx,
+                        None,
+                        &SUBNET_BLOCK_LIMIT,
+                        &epoch_id,
+                    )
+                    .unwrap();
+            }
+        }
+    }
+
+    /// Assemble a block from the mempool on top of the tip.  Assembly happens in a
+    /// transaction that is rolled back, so this can be repeated.
+    fn assemble_block(&mut self) -> StacksBlock {
+        let proof = VRF::prove(&self.vrf_key, self.tip_snapshot.sortition_hash.as_bytes());
+        let (block, _, _) = StacksBlockBuilder::build_anchored_block(
+            &self.chainstate,
+            &self.sortdb.index_conn(),
+            &mut self.mempool,
+            &self.tip_header,
+            self.tip_snapshot.total_burn,
+            proof,
+            Hash160::from_node_public_key(&StacksPublicKey::from_private(&self.miner_sk)),
+            &make_coinbase(&self.miner_sk, 1),
+            BlockBuilderSettings::limited(),
+            None,
+        )
+        .unwrap();
+        block
+    }
+
+    /// Produce a burnchain block whose parent is `parent` and which commits to `block`.
+    /// Every call yields a new sibling sortition, so the same Stacks block can be committed and
+    /// processed repeatedly.
+    fn commit_block(&mut self, parent: &BlockSnapshot, block: &StacksBlock) -> BlockSnapshot {
+        self.burn_nonce += 1;
+        let mut hash_bytes = [0u8; 32];
+        hash_bytes[0..8].copy_from_slice(&self.burn_nonce.to_be_bytes());
+        hash_bytes[31] = 1;
+        let header = BurnchainBlockHeader {
+            block_height: parent.block_height + 1,
+            block_hash: BurnchainHeaderHash(hash_bytes.clone()),
+            parent_block_hash: parent.burn_header_hash.clone(),
+            num_txs: 1,
+            timestamp: parent.burn_header_timestamp + 1,
+        };
+
+        let mut op = BlockstackOperationType::LeaderBlockCommit(LeaderBlockCommitOp {
+            block_header_hash: block.block_hash(),
+            withdrawal_merkle_root: block.header.withdrawal_merkle_root.clone(),
+            txid: Txid(hash_bytes),
+            burn_header_hash: header.block_hash.clone(),
+        });
+        op.set_block_height(header.block_height);
+        op.set_burn_header_hash(header.block_hash.clone());
+
+        let (snapshot, _) = self
+            .sortdb
+            .evaluate_sortition(
+                &header,
+                vec![op],
+                &self.burnchain,
+                &parent.sortition_id,
+                None,
+                |_| {},
+            )
+            .unwrap();
+        assert!(snapshot.sortition);
+        snapshot
+    }
+
+    fn preprocess_block(
+        &mut self,
+        snapshot: &BlockSnapshot,
+        parent_consensus_hash: &ConsensusHash,
+        block: &StacksBlock,
+    ) {
+        let stored = self
+            .chainstate
+            .preprocess_anchored_block(
+                &self.sortdb.index_conn(),
+                &snapshot.consensus_hash,
+                block,
+                parent_consensus_hash,
+                0,
+            )
+            .unwrap();
+        assert!(stored);
+    }
+
+    /// Process the single pending staging block, returning its header and how long
+    /// `process_next_staging_block` took.
+    fn process_block(&mut self, snapshot: &BlockSnapshot) -> (StacksHeaderInfo, Duration) {
+        let mut sort_tx = self.sortdb.tx_handle_begin(&snapshot.sortition_id).unwrap();
+        let start = Instant::now();
+        let (receipt, _) = self
+            .chainstate
+            .process_next_staging_block(&mut sort_tx, None::<&DummyEventDispatcher>)
+            .unwrap();
+        let elapsed = start.elapsed();
+        sort_tx.commit().unwrap();
+        (
+            receipt.expect("staging block was not processed").header,
+            elapsed,
+        )
+    }
+}
+
+/// Assemble a block at subnet block limits from a full mempool.
+fn bench_block_assembly(c: &mut Criterion) {
+    let mut group = c.benchmark_group("build_anchored_block");
+    group.sample_size(10);
+    for workload in Workload::all().iter() {
+        let mut chain = BenchChain::new(*workload);
+        group.bench_function(BenchmarkId::from_parameter(workload.name()), |b| {
+            b.iter(|| chain.assemble_block())
+        });
+    }
+    group.finish();
+}
+
+/// Process a block assembled at subnet block limits.  Each iteration commits the same block
+/// in a fresh sibling sortition and times only `process_next_staging_block`.
+fn bench_block_processing(c: &mut Criterion) {
+    let mut group = c.benchmark_group("process_next_staging_block");
+    group.sample_size(10);
+    for workload in Workload::all().iter() {
+        let mut chain = BenchChain::new(*workload);
+        let block = chain.assemble_block();
+        let parent_snapshot = chain.tip_snapshot.clone();
+        group.bench_function(BenchmarkId::from_parameter(workload.name()), |b| {
+            b.iter_custom(|iters| {
+                let mut total = Duration::from_secs(0);
+                for _ in 0..iters {
+                    let snapshot = chain.commit_block(&parent_snapshot, &block);
+                    chain.preprocess_block(&snapshot, &parent_snapshot.consensus_hash, &block);
+                    let (_, elapsed) = chain.process_block(&snapshot);
+                    total += elapsed;
+                }
+                total
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_block_assembly, bench_block_processing);
+criterion_main!(benches);
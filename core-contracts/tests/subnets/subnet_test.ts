@@ -20,6 +20,32 @@ function fromHex(input: string) {
   return decHex(hexBytes);
 }
 
+// Commit a subnet block whose withdrawal Merkle tree holds only `leaf_hash`, so that the
+// withdrawal root is the leaf hash itself, and the withdrawal needs no sibling hashes.
+function commitWithdrawalLeaf(chain: Chain, miner: Account, leaf_hash: string) {
+  const id_header_hash = chain
+    .callReadOnlyFn("test-helpers", "get-id-header-hash", [], miner.address)
+    .result.expectOk()
+    .toString();
+  const block = chain.mineBlock([
+    Tx.contractCall(
+      config.subnet_contract,
+      "commit-block",
+      [
+        types.buff(new Uint8Array([0, 1, 1, 1, 1])),
+        types.uint(0),
+        id_header_hash,
+        types.uint(chain.blockHeight - 1),
+        leaf_hash,
+      ],
+      miner.address
+    ),
+  ]);
+  block.receipts[0].result
+    .expectOk()
+    .expectBuff(new Uint8Array([0, 1, 1, 1, 1]));
+}
+
 Clarinet.test({
   name: "Unit test the withdrawal leaf hash calculations using test vectors",
   fn(
@@ -89,6 +115,26 @@ Clarinet.test({
       leaf_hash_3,
       "0x56c3dcca6e8900359d7172be38a74da7a350a7af2ab102fbb3fd251d57f76316"
     );
+
+    const leaf_hash_4 = chain
+      .callReadOnlyFn(
+        config.subnet_contract,
+        "leaf-hash-transfer-stx-to-subnet",
+        [
+          types.uint(5),
+          types.principal(recipient),
+          types.principal("ST2REHHS5J3CERCRBEPMGH7921Q6PYKAADT7JP2VB"),
+          types.principal("ST1PQHQKV0RJXZFY1DGX8MNSNYVE3VGZJSRTPGZGM.subnet-b"),
+          types.uint(3),
+          types.uint(7),
+        ],
+        alice
+      )
+      .result.toString();
+    assertEquals(
+      leaf_hash_4,
+      "0x8c9e79329b21e5d975a5edfb540947e4df4e855a4bd81b6e5c390298394c8a2b"
+    );
   },
 });
 
@@ -464,6 +510,194 @@ Clarinet.test({
   },
 });
 
+Clarinet.test({
+  name: "Ensure that the miner can rotate to a new key, with both keys accepted during the switch",
+  fn(
+    chain: Chain,
+    accounts: Map<string, Account>,
+    _contracts: Map<string, Contract>
+  ) {
+    // contract deployer
+    const deployer = accounts.get("deployer")!;
+    // current miner
+    const alice = accounts.get("wallet_1")!;
+    // next miner
+    const bob = accounts.get("wallet_2")!;
+
+    function commitBlock(miner: Account, blockByte: number) {
+      const id_header_hash = chain
+        .callReadOnlyFn("test-helpers", "get-id-header-hash", [], miner.address)
+        .result.expectOk()
+        .toString();
+      return chain.mineBlock([
+        Tx.contractCall(
+          config.subnet_contract,
+          "commit-block",
+          [
+            types.buff(new Uint8Array([0, blockByte, 1, 1, 1])),
+            types.uint(blockByte),
+            id_header_hash,
+            types.uint(chain.blockHeight - 1),
+            types.buff(new Uint8Array([0, blockByte, 1, 1, 2])),
+          ],
+          miner.address
+        ),
+      ]).receipts[0].result;
+    }
+
+    let block = chain.mineBlock([
+      Tx.contractCall(
+        config.subnet_contract,
+        "update-miner",
+        [types.principal(alice.address)],
+        deployer.address
+      ),
+    ]);
+    block.receipts[0].result.expectOk().expectBool(true);
+
+    const switch_height = chain.blockHeight + 2;
+    block = chain.mineBlock([
+      // bob is neither the miner nor the admin, so can't schedule a rotation
+      Tx.contractCall(
+        config.subnet_contract,
+        "rotate-miner",
+        [types.principal(bob.address), types.uint(switch_height)],
+        bob.address
+      ),
+      // the switch height must be in the future
+      Tx.contractCall(
+        config.subnet_contract,
+        "rotate-miner",
+        [types.principal(bob.address), types.uint(1)],
+        alice.address
+      ),
+      // alice hands over to bob
+      Tx.contractCall(
+        config.subnet_contract,
+        "rotate-miner",
+        [types.principal(bob.address), types.uint(switch_height)],
+        alice.address
+      ),
+      // only one rotation at a time
+      Tx.contractCall(
+        config.subnet_contract,
+        "rotate-miner",
+        [types.principal(deployer.address), types.uint(switch_height)],
+        alice.address
+      ),
+    ]);
+    // should return (err ERR_UNAUTHORIZED)
+    block.receipts[0].result.expectErr().expectInt(17);
+    // should return (err ERR_INVALID_SWITCH_HEIGHT)
+    block.receipts[1].result.expectErr().expectInt(19);
+    block.receipts[2].result.expectOk().expectBool(true);
+    // should return (err ERR_MINER_ROTATION_IN_PROGRESS)
+    block.receipts[3].result.expectErr().expectInt(18);
+
+    // both keys can commit until the grace period after the switch ends
+    commitBlock(alice, 1).expectOk();
+    commitBlock(bob, 2).expectOk();
+    chain.mineEmptyBlockUntil(switch_height + 1);
+    commitBlock(alice, 3).expectOk();
+
+    // then only the new key can
+    chain.mineEmptyBlockUntil(switch_height + 6);
+    // should return (err ERR_INVALID_MINER)
+    commitBlock(alice, 4).expectErr().expectInt(2);
+    commitBlock(bob, 5).expectOk();
+
+    // bob is now the miner, and can rotate again
+    block = chain.mineBlock([
+      Tx.contractCall(
+        config.subnet_contract,
+        "rotate-miner",
+        [types.principal(alice.address), types.uint(chain.blockHeight + 10)],
+        bob.address
+      ),
+    ]);
+    block.receipts[0].result.expectOk().expectBool(true);
+  },
+});
+
+Clarinet.test({
+  name: "Ensure that only the admin can set the block limit, for a future height",
+  fn(
+    chain: Chain,
+    accounts: Map<string, Account>,
+    _contracts: Map<string, Contract>
+  ) {
+    // contract deployer, and admin
+    const deployer = accounts.get("deployer")!;
+    const alice = accounts.get("wallet_1")!;
+
+    function setBlockLimit(sender: Account, activationHeight: number) {
+      return Tx.contractCall(
+        config.subnet_contract,
+        "set-block-limit",
+        [
+          types.uint(activationHeight),
+          types.uint(5000000000),
+          types.uint(15000),
+          types.uint(100000000),
+          types.uint(15000),
+          types.uint(15000000),
+        ],
+        sender.address
+      );
+    }
+
+    const activation_height = chain.blockHeight + 10;
+    const block = chain.mineBlock([
+      // alice is not the admin
+      setBlockLimit(alice, activation_height),
+      // the activation height must be in the future
+      setBlockLimit(deployer, 1),
+      setBlockLimit(deployer, activation_height),
+    ]);
+    // should return (err ERR_UNAUTHORIZED)
+    block.receipts[0].result.expectErr().expectInt(17);
+    // should return (err ERR_INVALID_ACTIVATION_HEIGHT)
+    block.receipts[1].result.expectErr().expectInt(20);
+    block.receipts[2].result.expectOk().expectBool(true);
+  },
+});
+
+Clarinet.test({
+  name: "Ensure that only the admin can signal an upgrade, for a future height",
+  fn(
+    chain: Chain,
+    accounts: Map<string, Account>,
+    _contracts: Map<string, Contract>
+  ) {
+    // contract deployer, and admin
+    const deployer = accounts.get("deployer")!;
+    const alice = accounts.get("wallet_1")!;
+
+    function signalUpgrade(sender: Account, targetHeight: number) {
+      return Tx.contractCall(
+        config.subnet_contract,
+        "signal-upgrade",
+        [types.uint(targetHeight), types.ascii("0.5.0")],
+        sender.address
+      );
+    }
+
+    const target_height = chain.blockHeight + 10;
+    const block = chain.mineBlock([
+      // alice is not the admin
+      signalUpgrade(alice, target_height),
+      // the target height must be in the future
+      signalUpgrade(deployer, 1),
+      signalUpgrade(deployer, target_height),
+    ]);
+    // should return (err ERR_UNAUTHORIZED)
+    block.receipts[0].result.expectErr().expectInt(17);
+    // should return (err ERR_INVALID_UPGRADE_HEIGHT)
+    block.receipts[1].result.expectErr().expectInt(21);
+    block.receipts[2].result.expectOk().expectBool(true);
+  },
+});
+
 Clarinet.test({
   name: "Ensure that user can deposit NFT & miner can withdraw it",
   fn(
@@ -2902,3 +3136,517 @@ Clarinet.test({
     assertEquals(nft_amount, 1);
   },
 });
+
+Clarinet.test({
+  name: "Ensure that STX can be transferred to a registered destination subnet",
+  fn(
+    chain: Chain,
+    accounts: Map<string, Account>,
+    _contracts: Map<string, Contract>
+  ) {
+    // contract deployer
+    const deployer = accounts.get("deployer")!;
+    // valid miner
+    const alice = accounts.get("wallet_1")!;
+    // invalid admin
+    const bob = accounts.get("wallet_2")!;
+    // user
+    const charlie = accounts.get("wallet_3")!;
+
+    const subnet_contract_id = `${deployer.address}.${config.subnet_contract}`;
+    const destination_subnet = `${deployer.address}.second-subnet`;
+
+    // set alice as a miner
+    let block = chain.mineBlock([
+      Tx.contractCall(
+        config.subnet_contract,
+        "update-miner",
+        [types.principal(alice.address)],
+        deployer.address
+      ),
+    ]);
+    block.receipts[0].result.expectOk().expectBool(true);
+
+    // User deposits STX into the subnet's escrow
+    block = chain.mineBlock([
+      Tx.contractCall(
+        config.subnet_contract,
+        "deposit-stx",
+        [types.uint(1000), types.principal(charlie.address)],
+        charlie.address
+      ),
+    ]);
+    block.receipts[0].result.expectOk().expectBool(true);
+
+    // Invalid admin can't register a destination subnet
+    block = chain.mineBlock([
+      Tx.contractCall(
+        config.subnet_contract,
+        "register-destination-subnet",
+        [types.principal(destination_subnet)],
+        bob.address
+      ),
+    ]);
+    // should return (err ERR_UNAUTHORIZED)
+    block.receipts[0].result.expectErr().expectInt(17);
+
+    // Deployer registers the destination subnet
+    block = chain.mineBlock([
+      Tx.contractCall(
+        config.subnet_contract,
+        "register-destination-subnet",
+        [types.principal(destination_subnet)],
+        deployer.address
+      ),
+    ]);
+    block.receipts[0].result.expectOk().expectBool(true);
+    chain
+      .callReadOnlyFn(
+        config.subnet_contract,
+        "is-destination-subnet",
+        [types.principal(destination_subnet)],
+        deployer.address
+      )
+      .result.expectBool(true);
+
+    // A destination subnet can only be registered once
+    block = chain.mineBlock([
+      Tx.contractCall(
+        config.subnet_contract,
+        "register-destination-subnet",
+        [types.principal(destination_subnet)],
+        deployer.address
+      ),
+    ]);
+    // should return (err ERR_ASSET_ALREADY_ALLOWED)
+    block.receipts[0].result.expectErr().expectInt(6);
+
+    const leaf_hash = chain
+      .callReadOnlyFn(
+        config.subnet_contract,
+        "leaf-hash-transfer-stx-to-subnet",
+        [
+          types.uint(100),
+          types.principal(charlie.address),
+          types.principal(charlie.address),
+          types.principal(destination_subnet),
+          types.uint(0),
+          types.uint(0),
+        ],
+        charlie.address
+      )
+      .result.toString();
+    commitWithdrawalLeaf(chain, alice, leaf_hash);
+
+    const transfer = () =>
+      Tx.contractCall(
+        config.subnet_contract,
+        "transfer-stx-to-subnet",
+        [
+          types.uint(100),
+          types.principal(charlie.address),
+          types.principal(charlie.address),
+          types.principal(destination_subnet),
+          types.uint(0),
+          types.uint(0),
+          leaf_hash,
+          leaf_hash,
+          types.list([]),
+        ],
+        charlie.address
+      );
+
+    // The STX move from this subnet's escrow into the destination subnet's
+    block = chain.mineBlock([transfer()]);
+    block.receipts[0].result.expectOk().expectBool(true);
+    block.receipts[0].events.expectSTXTransferEvent(
+      100,
+      subnet_contract_id,
+      destination_subnet
+    );
+
+    // The transfer can't be claimed a second time
+    block = chain.mineBlock([transfer()]);
+    // should return (err ERR_WITHDRAWAL_ALREADY_PROCESSED)
+    block.receipts[0].result.expectErr().expectInt(9);
+  },
+});
+
+Clarinet.test({
+  name: "Ensure that STX can't be transferred to an unregistered destination subnet",
+  fn(
+    chain: Chain,
+    accounts: Map<string, Account>,
+    _contracts: Map<string, Contract>
+  ) {
+    // contract deployer
+    const deployer = accounts.get("deployer")!;
+    // valid miner
+    const alice = accounts.get("wallet_1")!;
+    // user
+    const charlie = accounts.get("wallet_3")!;
+
+    const destination_subnet = `${deployer.address}.second-subnet`;
+
+    // set alice as a miner
+    let block = chain.mineBlock([
+      Tx.contractCall(
+        config.subnet_contract,
+        "update-miner",
+        [types.principal(alice.address)],
+        deployer.address
+      ),
+    ]);
+    block.receipts[0].result.expectOk().expectBool(true);
+
+    block = chain.mineBlock([
+      Tx.contractCall(
+        config.subnet_contract,
+        "deposit-stx",
+        [types.uint(1000), types.principal(charlie.address)],
+        charlie.address
+      ),
+    ]);
+    block.receipts[0].result.expectOk().expectBool(true);
+
+    chain
+      .callReadOnlyFn(
+        config.subnet_contract,
+        "is-destination-subnet",
+        [types.principal(destination_subnet)],
+        deployer.address
+      )
+      .result.expectBool(false);
+
+    // The transfer is committed, but its destination was never registered
+    const leaf_hash = chain
+      .callReadOnlyFn(
+        config.subnet_contract,
+        "leaf-hash-transfer-stx-to-subnet",
+        [
+          types.uint(100),
+          types.principal(charlie.address),
+          types.principal(charlie.address),
+          types.principal(destination_subnet),
+          types.uint(0),
+          types.uint(0),
+        ],
+        charlie.address
+      )
+      .result.toString();
+    commitWithdrawalLeaf(chain, alice, leaf_hash);
+
+    block = chain.mineBlock([
+      Tx.contractCall(
+        config.subnet_contract,
+        "transfer-stx-to-subnet",
+        [
+          types.uint(100),
+          types.principal(charlie.address),
+          types.principal(charlie.address),
+          types.principal(destination_subnet),
+          types.uint(0),
+          types.uint(0),
+          leaf_hash,
+          leaf_hash,
+          types.list([]),
+        ],
+        charlie.address
+      ),
+    ]);
+    // should return (err ERR_UNREGISTERED_SUBNET)
+    block.receipts[0].result.expectErr().expectInt(22);
+    assertEquals(block.receipts[0].events.length, 0);
+  },
+});
+
+Clarinet.test({
+  name: "Ensure that a malicious destination subnet contract can't take the escrowed STX",
+  fn(
+    chain: Chain,
+    accounts: Map<string, Account>,
+    _contracts: Map<string, Contract>
+  ) {
+    // contract deployer
+    const deployer = accounts.get("deployer")!;
+    // valid miner
+    const alice = accounts.get("wallet_1")!;
+    // attacker
+    const bob = accounts.get("wallet_2")!;
+    // user
+    const charlie = accounts.get("wallet_3")!;
+
+    const malicious_destination = `${deployer.address}.malicious-stx-receiver`;
+
+    // set alice as a miner
+    let block = chain.mineBlock([
+      Tx.contractCall(
+        config.subnet_contract,
+        "update-miner",
+        [types.principal(alice.address)],
+        deployer.address
+      ),
+    ]);
+    block.receipts[0].result.expectOk().expectBool(true);
+
+    // Another user's STX are escrowed by the subnet
+    block = chain.mineBlock([
+      Tx.contractCall(
+        config.subnet_contract,
+        "deposit-stx",
+        [types.uint(1000000), types.principal(charlie.address)],
+        charlie.address
+      ),
+    ]);
+    block.receipts[0].result.expectOk().expectBool(true);
+
+    // The attacker has one valid transfer, to a contract which tries to take the escrow
+    const leaf_hash = chain
+      .callReadOnlyFn(
+        config.subnet_contract,
+        "leaf-hash-transfer-stx-to-subnet",
+        [
+          types.uint(1),
+          types.principal(bob.address),
+          types.principal(bob.address),
+          types.principal(malicious_destination),
+          types.uint(0),
+          types.uint(0),
+        ],
+        bob.address
+      )
+      .result.toString();
+    commitWithdrawalLeaf(chain, alice, leaf_hash);
+
+    const bob_balance = chain.getAssetsMaps().assets["STX"][bob.address];
+
+    block = chain.mineBlock([
+      Tx.contractCall(
+        config.subnet_contract,
+        "transfer-stx-to-subnet",
+        [
+          types.uint(1),
+          types.principal(bob.address),
+          types.principal(bob.address),
+          types.principal(malicious_destination),
+          types.uint(0),
+          types.uint(0),
+          leaf_hash,
+          leaf_hash,
+          types.list([]),
+        ],
+        bob.address
+      ),
+    ]);
+    // should return (err ERR_UNREGISTERED_SUBNET), without running the destination contract
+    block.receipts[0].result.expectErr().expectInt(22);
+    assertEquals(block.receipts[0].events.length, 0);
+    assertEquals(chain.getAssetsMaps().assets["STX"][bob.address], bob_balance);
+  },
+});
+
+Clarinet.test({
+  name: "Ensure that user can deposit SFT & miner can withdraw it",
+  fn(
+    chain: Chain,
+    accounts: Map<string, Account>,
+    contracts: Map<string, Contract>
+  ) {
+    // contract deployer
+    const deployer = accounts.get("deployer")!;
+    // valid miner
+    const alice = accounts.get("wallet_1")!;
+    // invalid admin
+    const bob = accounts.get("wallet_2")!;
+    // user
+    const charlie = accounts.get("wallet_3")!;
+
+    // sft contract
+    const sft_contract = contracts.get(
+      "ST1PQHQKV0RJXZFY1DGX8MNSNYVE3VGZJSRTPGZGM.simple-sft"
+    )!;
+
+    const sft_balance = (id: number, owner: string) =>
+      chain
+        .callReadOnlyFn(
+          "simple-sft",
+          "get-balance",
+          [types.uint(id), types.principal(owner)],
+          owner
+        )
+        .result.expectOk();
+
+    // set alice as a miner
+    let block = chain.mineBlock([
+      Tx.contractCall(
+        config.subnet_contract,
+        "update-miner",
+        [types.principal(alice.address)],
+        deployer.address
+      ),
+    ]);
+    block.receipts[0].result.expectOk().expectBool(true);
+
+    // User mints 10 of token 1
+    block = chain.mineBlock([
+      Tx.contractCall(
+        "simple-sft",
+        "test-mint",
+        [types.uint(1), types.uint(10), types.principal(charlie.address)],
+        charlie.address
+      ),
+    ]);
+    block.receipts[0].result.expectOk().expectBool(true);
+
+    // User should not be able to deposit SFT assets if they are not registered
+    block = chain.mineBlock([
+      Tx.contractCall(
+        config.subnet_contract,
+        "deposit-sft-asset",
+        [
+          types.principal(sft_contract.contract_id),
+          types.uint(1),
+          types.uint(4),
+          types.principal(charlie.address),
+        ],
+        charlie.address
+      ),
+    ]);
+    // should return (err ERR_DISALLOWED_ASSET)
+    block.receipts[0].result.expectErr().expectInt(5);
+    sft_balance(1, charlie.address).expectUint(10);
+
+    // Invalid admin can't register new contracts
+    block = chain.mineBlock([
+      Tx.contractCall(
+        config.subnet_contract,
+        "register-new-sft-contract",
+        [
+          types.principal(sft_contract.contract_id),
+          types.principal(sft_contract.contract_id),
+        ],
+        bob.address
+      ),
+    ]);
+    // should return (err ERR_UNAUTHORIZED)
+    block.receipts[0].result.expectErr().expectInt(17);
+
+    // Deployer sets up allowed assets
+    block = chain.mineBlock([
+      Tx.contractCall(
+        config.subnet_contract,
+        "register-new-sft-contract",
+        [
+          types.principal(sft_contract.contract_id),
+          types.principal(sft_contract.contract_id),
+        ],
+        deployer.address
+      ),
+    ]);
+    block.receipts[0].result.expectOk().expectBool(true);
+
+    // A contract can only be registered once
+    block = chain.mineBlock([
+      Tx.contractCall(
+        config.subnet_contract,
+        "register-new-sft-contract",
+        [
+          types.principal(sft_contract.contract_id),
+          types.principal(sft_contract.contract_id),
+        ],
+        deployer.address
+      ),
+    ]);
+    // should return (err ERR_ASSET_ALREADY_ALLOWED)
+    block.receipts[0].result.expectErr().expectInt(6);
+
+    // User should be able to deposit a registered SFT
+    block = chain.mineBlock([
+      Tx.contractCall(
+        config.subnet_contract,
+        "deposit-sft-asset",
+        [
+          types.principal(sft_contract.contract_id),
+          types.uint(1),
+          types.uint(4),
+          types.principal(charlie.address),
+        ],
+        charlie.address
+      ),
+    ]);
+    block.receipts[0].result.expectOk().expectBool(true);
+    sft_balance(1, charlie.address).expectUint(6);
+    sft_balance(1, `${deployer.address}.${config.subnet_contract}`).expectUint(4);
+
+    // User should not be able to deposit more than they own
+    block = chain.mineBlock([
+      Tx.contractCall(
+        config.subnet_contract,
+        "deposit-sft-asset",
+        [
+          types.principal(sft_contract.contract_id),
+          types.uint(1),
+          types.uint(7),
+          types.principal(charlie.address),
+        ],
+        charlie.address
+      ),
+    ]);
+    // should return (err ERR_CONTRACT_CALL_FAILED)
+    block.receipts[0].result.expectErr().expectInt(3);
+
+    const leaf_hash = chain
+      .callReadOnlyFn(
+        config.subnet_contract,
+        "leaf-hash-withdraw-sft",
+        [
+          types.principal(sft_contract.contract_id),
+          types.uint(1),
+          types.uint(3),
+          types.principal(charlie.address),
+          types.uint(0),
+          types.uint(0),
+        ],
+        charlie.address
+      )
+      .result.toString();
+    commitWithdrawalLeaf(chain, alice, leaf_hash);
+
+    const withdraw = (id: number, amount: number) =>
+      Tx.contractCall(
+        config.subnet_contract,
+        "withdraw-sft-asset",
+        [
+          types.principal(sft_contract.contract_id),
+          types.uint(id),
+          types.uint(amount),
+          types.principal(charlie.address),
+          types.uint(0),
+          types.uint(0),
+          leaf_hash,
+          leaf_hash,
+          types.list([]),
+        ],
+        charlie.address
+      );
+
+    // The amount and id must match the ones in the withdrawal leaf
+    block = chain.mineBlock([withdraw(1, 4), withdraw(2, 3)]);
+    // should return (err ERR_VALIDATION_LEAF_FAILED)
+    block.receipts[0].result.expectErr().expectInt(30);
+    block.receipts[1].result.expectErr().expectInt(30);
+    sft_balance(1, charlie.address).expectUint(6);
+
+    // User should be able to withdraw the SFT
+    block = chain.mineBlock([withdraw(1, 3)]);
+    block.receipts[0].result.expectOk().expectBool(true);
+    sft_balance(1, charlie.address).expectUint(9);
+    sft_balance(1, `${deployer.address}.${config.subnet_contract}`).expectUint(1);
+
+    // The withdrawal can't be replayed
+    block = chain.mineBlock([withdraw(1, 3)]);
+    // should return (err ERR_WITHDRAWAL_ALREADY_PROCESSED)
+    block.receipts[0].result.expectErr().expectInt(9);
+    sft_balance(1, charlie.address).expectUint(9);
+  },
+});
@@ -0,0 +1,31 @@
+//! Feeds arbitrary bytes to the parsers which turn an L1 node's events into subnet burnchain
+//! operations, both as a consensus-serialized Clarity value printed by the subnet contract and
+//! as the JSON of a whole block event.  Neither may panic.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use stacks::burnchains::events::NewBlock;
+use stacks::burnchains::{StacksSubnetBlock, StacksSubnetOp, Txid};
+use stacks::types::chainstate::StacksBlockId;
+use stacks::util::hash::to_hex;
+use stacks::vm::types::Value;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(value) = Value::try_deserialize_hex_untyped(&to_hex(data)) {
+        let _ =
+            StacksSubnetOp::try_from_clar_value(value, Txid([0; 32]), 0, &StacksBlockId([0; 32]));
+    }
+
+    if let Ok(block) = serde_json::from_slice::<NewBlock>(data) {
+        // watch the contract of the first contract event, so its events get parsed
+        let watched_contract = block
+            .events
+            .iter()
+            .find_map(|event| event.contract_event.as_ref())
+            .map(|event| event.contract_identifier.clone());
+        if let Some(watched_contract) = watched_contract {
+            let _ = StacksSubnetBlock::from_new_block_event(&watched_contract, block);
+        }
+    }
+});
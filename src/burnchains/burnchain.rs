@@ -44,8 +44,9 @@ use crate::chainstate::burn::db::sortdb::{SortitionDB, SortitionHandleConn, Sort
 use crate::chainstate::burn::operations::RegisterAssetOp;
 use crate::chainstate::burn::operations::{
     leader_block_commit::MissedBlockCommit, BlockstackOperationType, DepositFtOp, DepositNftOp,
-    DepositStxOp, LeaderBlockCommitOp, LeaderKeyRegisterOp, PreStxOp, StackStxOp, TransferStxOp,
-    UserBurnSupportOp, WithdrawFtOp, WithdrawNftOp, WithdrawStxOp,
+    DepositSftOp, DepositStxOp, LeaderBlockCommitOp, LeaderKeyRegisterOp, PreStxOp,
+    SetBlockLimitOp, StackStxOp, TransferStxOp, UpgradeSignalOp, UserBurnSupportOp, WithdrawFtOp,
+    WithdrawNftOp, WithdrawStxOp,
 };
 use crate::chainstate::burn::{BlockSnapshot, Opcodes};
 use crate::chainstate::coordinator::comm::CoordinatorChannels;
@@ -112,6 +113,9 @@ impl BurnchainStateTransition {
                 BlockstackOperationType::DepositNft(op) => {
                     accepted_ops.push(op.clone().into());
                 }
+                BlockstackOperationType::DepositSft(op) => {
+                    accepted_ops.push(op.clone().into());
+                }
                 BlockstackOperationType::WithdrawStx(op) => {
                     accepted_ops.push(op.clone().into());
                 }
@@ -121,6 +125,12 @@ impl BurnchainStateTransition {
                 BlockstackOperationType::WithdrawNft(op) => {
                     accepted_ops.push(op.clone().into());
                 }
+                BlockstackOperationType::SetBlockLimit(op) => {
+                    accepted_ops.push(op.clone().into());
+                }
+                BlockstackOperationType::UpgradeSignal(op) => {
+                    accepted_ops.push(op.clone().into());
+                }
             };
         }
 
@@ -351,6 +361,15 @@ impl Burnchain {
         db_path
     }
 
+    pub fn get_coordinator_journal_path(&self) -> String {
+        let chainstate_dir = Burnchain::get_chainstate_path_str(&self.working_dir);
+        let mut db_pathbuf = PathBuf::from(&chainstate_dir);
+        db_pathbuf.push("coordinator_journal.sqlite");
+
+        let db_path = db_pathbuf.to_str().unwrap().to_string();
+        db_path
+    }
+
     /// Connect to the burnchain databases.  They may or may not already exist.
     pub fn connect_db<I: BurnchainIndexer>(
         &self,
@@ -463,6 +482,17 @@ impl Burnchain {
                         None
                     }
                 },
+                StacksSubnetOpType::DepositSft { .. } => match DepositSftOp::try_from(event) {
+                    Ok(op) => Some(BlockstackOperationType::from(op)),
+                    Err(e) => {
+                        warn!(
+                            "Failed to parse deposit semi-fungible token operation";
+                            "txid" => %burn_tx.txid(),
+                            "error" => ?e,
+                        );
+                        None
+                    }
+                },
                 StacksSubnetOpType::WithdrawStx { .. } => match WithdrawStxOp::try_from(event) {
                     Ok(op) => Some(BlockstackOperationType::from(op)),
                     Err(e) => {
@@ -496,6 +526,30 @@ impl Burnchain {
                         None
                     }
                 },
+                StacksSubnetOpType::SetBlockLimit { .. } => {
+                    match SetBlockLimitOp::try_from(event) {
+                        Ok(op) => Some(BlockstackOperationType::from(op)),
+                        Err(e) => {
+                            warn!(
+                                "Failed to parse set block limit operation";
+                                "txid" => %burn_tx.txid(),
+                                "error" => ?e,
+                            );
+                            None
+                        }
+                    }
+                }
+                StacksSubnetOpType::Upgrade { .. } => match UpgradeSignalOp::try_from(event) {
+                    Ok(op) => Some(BlockstackOperationType::from(op)),
+                    Err(e) => {
+                        warn!(
+                            "Failed to parse upgrade signal operation";
+                            "txid" => %burn_tx.txid(),
+                            "error" => ?e,
+                        );
+                        None
+                    }
+                },
             },
         }
     }
@@ -336,7 +336,8 @@ impl BurnchainDB {
         Ok(blockstack_ops)
     }
 
-    #[cfg(test)]
+    /// Store a burnchain block whose operations were already extracted, e.g. one delivered by a
+    /// test or read back from a recorded trace.
     pub fn raw_store_burnchain_block(
         &mut self,
         header: BurnchainBlockHeader,
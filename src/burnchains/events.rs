@@ -3,8 +3,9 @@ use std::fmt::Display;
 use std::fmt::Formatter;
 
 use crate::burnchains::Txid;
+use clarity::vm::costs::ExecutionCost;
 use clarity::vm::types::Value as ClarityValue;
-use clarity::vm::types::{QualifiedContractIdentifier, TraitIdentifier};
+use clarity::vm::types::{QualifiedContractIdentifier, TraitIdentifier, TupleData};
 use serde::de::Error as DeserError;
 use serde::Deserialize;
 use serde::Deserializer;
@@ -194,6 +195,59 @@ where
     serializer.serialize_str(as_str)
 }
 
+/// Get the `name` field of an event tuple
+fn get_field<'a>(tuple: &'a TupleData, name: &str) -> Result<&'a ClarityValue, String> {
+    tuple
+        .get(name)
+        .map_err(|_| format!("No '{}' field in Clarity tuple", name))
+}
+
+fn get_u128_field(tuple: &TupleData, name: &str) -> Result<u128, String> {
+    match get_field(tuple, name)? {
+        ClarityValue::UInt(value) => Ok(*value),
+        _ => Err(format!("Expected '{}' type to be uint", name)),
+    }
+}
+
+/// Get the `name` field of an event tuple, which should be a uint that fits in a u64
+fn get_u64_field(tuple: &TupleData, name: &str) -> Result<u64, String> {
+    u64::try_from(get_u128_field(tuple, name)?)
+        .map_err(|_| format!("Expected '{}' to fit in a u64", name))
+}
+
+fn get_principal_field(tuple: &TupleData, name: &str) -> Result<PrincipalData, String> {
+    match get_field(tuple, name)? {
+        ClarityValue::Principal(principal) => Ok(principal.clone()),
+        _ => Err(format!("Expected '{}' type to be principal", name)),
+    }
+}
+
+fn get_ascii_field(tuple: &TupleData, name: &str) -> Result<String, String> {
+    ascii_string(get_field(tuple, name)?, name)
+}
+
+/// The contents of `value`, which should be the ASCII string field `name`
+fn ascii_string(value: &ClarityValue, name: &str) -> Result<String, String> {
+    match value {
+        ClarityValue::Sequence(SequenceData::String(CharType::ASCII(data))) => {
+            String::from_utf8(data.data.clone())
+                .map_err(|_| format!("Expected '{}' to be an ASCII string", name))
+        }
+        _ => Err(format!("Expected '{}' type to be string-ascii", name)),
+    }
+}
+
+/// The contents of `value`, which should be the optional field `name`
+fn optional_value<'a>(
+    value: &'a ClarityValue,
+    name: &str,
+) -> Result<Option<&'a ClarityValue>, String> {
+    match value {
+        ClarityValue::Optional(data) => Ok(data.data.as_deref()),
+        _ => Err(format!("Expected '{}' type to be optional", name)),
+    }
+}
+
 impl StacksSubnetOp {
     /// This method tries to parse a `StacksSubnetOp` from a Clarity value: this should be a tuple
     /// emitted from the subnet contract in a statement like:
@@ -273,32 +327,20 @@ impl StacksSubnetOp {
             }
             "\"register-contract\"" => {
                 // Parse 3 fields: asset-type, l1-contract, l2-contract
-                let asset_type_string = tuple
-                    .get("asset-type")
-                    .map_err(|_| "No 'asset-type' field in Clarity tuple")?
-                    .clone()
-                    .expect_ascii();
+                let asset_type_string = get_ascii_field(&tuple, "asset-type")?;
                 let asset_type = asset_type_string.parse().map_err(|_| {
                     format!(
                         "Expected 'asset-type' to be a valid asset type, found '{}'",
                         asset_type_string
                     )
                 })?;
-                let l1_contract = tuple
-                    .get("l1-contract")
-                    .map_err(|_| "No 'l1-contract' field in Clarity tuple")?
-                    .clone()
-                    .expect_principal();
+                let l1_contract = get_principal_field(&tuple, "l1-contract")?;
                 let l1_contract_id = if let PrincipalData::Contract(id) = l1_contract {
                     Ok(id)
                 } else {
                     Err("Expected 'l1-contract-id' to be a contract principal")
                 }?;
-                let l2_contract = tuple
-                    .get("l2-contract")
-                    .map_err(|_| "No 'l2-contract' field in Clarity tuple")?
-                    .clone()
-                    .expect_principal();
+                let l2_contract = get_principal_field(&tuple, "l2-contract")?;
                 let l2_contract_id = if let PrincipalData::Contract(id) = l2_contract {
                     Ok(id)
                 } else {
@@ -318,16 +360,8 @@ impl StacksSubnetOp {
             }
             "\"deposit-stx\"" => {
                 // Parse 2 fields: amount and sender
-                let amount = tuple
-                    .get("amount")
-                    .map_err(|_| "No 'amount' field in Clarity tuple")?
-                    .clone()
-                    .expect_u128();
-                let sender = tuple
-                    .get("sender")
-                    .map_err(|_| "No 'sender' field in Clarity tuple")?
-                    .clone()
-                    .expect_principal();
+                let amount = get_u128_field(&tuple, "amount")?;
+                let sender = get_principal_field(&tuple, "sender")?;
 
                 Ok(Self {
                     txid,
@@ -338,36 +372,16 @@ impl StacksSubnetOp {
             }
             "\"deposit-ft\"" => {
                 // Parse 5 fields: l1-contract-id, ft-name, ft-amount, sender, and subnet-contract-id
-                let l1_contract_id = tuple
-                    .get("l1-contract-id")
-                    .map_err(|_| "No 'l1-contract-id' field in Clarity tuple")?
-                    .clone()
-                    .expect_principal();
+                let l1_contract_id = get_principal_field(&tuple, "l1-contract-id")?;
                 let l1_contract_id = if let PrincipalData::Contract(id) = l1_contract_id {
                     Ok(id)
                 } else {
                     Err("Expected 'l1-contract-id' to be a contract principal")
                 }?;
-                let name = tuple
-                    .get("ft-name")
-                    .map_err(|_| "No 'ft-name' field in Clarity tuple")?
-                    .clone()
-                    .expect_ascii();
-                let amount = tuple
-                    .get("ft-amount")
-                    .map_err(|_| "No 'ft-amount' field in Clarity tuple")?
-                    .clone()
-                    .expect_u128();
-                let sender = tuple
-                    .get("sender")
-                    .map_err(|_| "No 'sender' field in Clarity tuple")?
-                    .clone()
-                    .expect_principal();
-                let subnet_contract_id = tuple
-                    .get("subnet-contract-id")
-                    .map_err(|_| "No 'subnet-contract-id' field in Clarity tuple")?
-                    .clone()
-                    .expect_principal();
+                let name = get_ascii_field(&tuple, "ft-name")?;
+                let amount = get_u128_field(&tuple, "ft-amount")?;
+                let sender = get_principal_field(&tuple, "sender")?;
+                let subnet_contract_id = get_principal_field(&tuple, "subnet-contract-id")?;
                 let subnet_contract_id = if let PrincipalData::Contract(id) = subnet_contract_id {
                     Ok(id)
                 } else {
@@ -388,38 +402,31 @@ impl StacksSubnetOp {
                 })
             }
             "\"deposit-nft\"" => {
-                // Parse 4 fields: l1-contract-id, nft-id, sender, and subnet-contract-id
+                // Parse 4 fields: l1-contract-id, nft-id, sender, and subnet-contract-id,
+                // plus the optional nft-uri field
                 // check that this is a valid way of getting the ID of the L1 contract.
-                let l1_contract_id = tuple
-                    .get("l1-contract-id")
-                    .map_err(|_| "No 'l1-contract-id' field in Clarity tuple")?
-                    .clone()
-                    .expect_principal();
+                let l1_contract_id = get_principal_field(&tuple, "l1-contract-id")?;
                 let l1_contract_id = if let PrincipalData::Contract(id) = l1_contract_id {
                     Ok(id)
                 } else {
                     Err("Expected 'l1-contract-id' to be a contract principal")
                 }?;
-                let id = tuple
-                    .get("nft-id")
-                    .map_err(|_| "No 'nft-id' field in Clarity tuple")?
-                    .clone()
-                    .expect_u128();
-                let sender = tuple
-                    .get("sender")
-                    .map_err(|_| "No 'sender' field in Clarity tuple")?
-                    .clone()
-                    .expect_principal();
-                let subnet_contract_id = tuple
-                    .get("subnet-contract-id")
-                    .map_err(|_| "No 'subnet-contract-id' field in Clarity tuple")?
-                    .clone()
-                    .expect_principal();
+                let id = get_u128_field(&tuple, "nft-id")?;
+                let sender = get_principal_field(&tuple, "sender")?;
+                let subnet_contract_id = get_principal_field(&tuple, "subnet-contract-id")?;
                 let subnet_contract_id = if let PrincipalData::Contract(id) = subnet_contract_id {
                     Ok(id)
                 } else {
                     Err("Expected 'subnet-contract-id' to be a contract principal")
                 }?;
+                // The L1 contract reads the token URI when the deposit is made; older
+                //  contracts do not emit it at all.
+                let token_uri = match tuple.get("nft-uri") {
+                    Ok(value) => optional_value(value, "nft-uri")?
+                        .map(|uri| ascii_string(uri, "nft-uri"))
+                        .transpose()?,
+                    Err(_) => None,
+                };
 
                 Ok(Self {
                     txid,
@@ -430,21 +437,45 @@ impl StacksSubnetOp {
                         subnet_contract_id,
                         id,
                         sender,
+                        token_uri,
+                    },
+                })
+            }
+            "\"deposit-sft\"" => {
+                // Parse 5 fields: l1-contract-id, sft-id, sft-amount, sender, and subnet-contract-id
+                let l1_contract_id = get_principal_field(&tuple, "l1-contract-id")?;
+                let l1_contract_id = if let PrincipalData::Contract(id) = l1_contract_id {
+                    Ok(id)
+                } else {
+                    Err("Expected 'l1-contract-id' to be a contract principal")
+                }?;
+                let id = get_u128_field(&tuple, "sft-id")?;
+                let amount = get_u128_field(&tuple, "sft-amount")?;
+                let sender = get_principal_field(&tuple, "sender")?;
+                let subnet_contract_id = get_principal_field(&tuple, "subnet-contract-id")?;
+                let subnet_contract_id = if let PrincipalData::Contract(id) = subnet_contract_id {
+                    Ok(id)
+                } else {
+                    Err("Expected 'subnet-contract-id' to be a contract principal")
+                }?;
+
+                Ok(Self {
+                    txid,
+                    event_index,
+                    in_block: in_block.clone(),
+                    event: StacksSubnetOpType::DepositSft {
+                        l1_contract_id,
+                        subnet_contract_id,
+                        id,
+                        amount,
+                        sender,
                     },
                 })
             }
             "\"withdraw-stx\"" => {
                 // Parse 2 fields: amount and recipient
-                let amount = tuple
-                    .get("amount")
-                    .map_err(|_| "No 'amount' field in Clarity tuple")?
-                    .clone()
-                    .expect_u128();
-                let recipient = tuple
-                    .get("recipient")
-                    .map_err(|_| "No 'recipient' field in Clarity tuple")?
-                    .clone()
-                    .expect_principal();
+                let amount = get_u128_field(&tuple, "amount")?;
+                let recipient = get_principal_field(&tuple, "recipient")?;
 
                 Ok(Self {
                     txid,
@@ -455,31 +486,15 @@ impl StacksSubnetOp {
             }
             "\"withdraw-ft\"" => {
                 // Parse 4 fields: ft-amount, ft-name, l1-contract-id, and recipient
-                let amount = tuple
-                    .get("ft-amount")
-                    .map_err(|_| "No 'ft-amount' field in Clarity tuple")?
-                    .clone()
-                    .expect_u128();
-                let l1_contract_id = tuple
-                    .get("l1-contract-id")
-                    .map_err(|_| "No 'l1-contract-id' field in Clarity tuple")?
-                    .clone()
-                    .expect_principal();
+                let amount = get_u128_field(&tuple, "ft-amount")?;
+                let l1_contract_id = get_principal_field(&tuple, "l1-contract-id")?;
                 let l1_contract_id = if let PrincipalData::Contract(id) = l1_contract_id {
                     Ok(id)
                 } else {
                     Err("Expected 'l1-contract-id' to be a contract principal")
                 }?;
-                let name = tuple
-                    .get("ft-name")
-                    .map_err(|_| "No 'ft-name' field in Clarity tuple")?
-                    .clone()
-                    .expect_ascii();
-                let recipient = tuple
-                    .get("recipient")
-                    .map_err(|_| "No 'recipient' field in Clarity tuple")?
-                    .clone()
-                    .expect_principal();
+                let name = get_ascii_field(&tuple, "ft-name")?;
+                let recipient = get_principal_field(&tuple, "recipient")?;
                 Ok(Self {
                     txid,
                     event_index,
@@ -494,27 +509,15 @@ impl StacksSubnetOp {
             }
             "\"withdraw-nft\"" => {
                 // Parse 3 fields: nft-id, l1-contract-id, and recipient
-                let id = tuple
-                    .get("nft-id")
-                    .map_err(|_| "No 'nft-id' field in Clarity tuple")?
-                    .clone()
-                    .expect_u128();
+                let id = get_u128_field(&tuple, "nft-id")?;
                 // check that this is a valid way of getting the ID of the L1 contract.
-                let l1_contract_id = tuple
-                    .get("l1-contract-id")
-                    .map_err(|_| "No 'l1-contract-id' field in Clarity tuple")?
-                    .clone()
-                    .expect_principal();
+                let l1_contract_id = get_principal_field(&tuple, "l1-contract-id")?;
                 let l1_contract_id = if let PrincipalData::Contract(id) = l1_contract_id {
                     Ok(id)
                 } else {
                     Err("Expected 'l1-contract-id' to be a contract principal")
                 }?;
-                let recipient = tuple
-                    .get("recipient")
-                    .map_err(|_| "No 'recipient' field in Clarity tuple")?
-                    .clone()
-                    .expect_principal();
+                let recipient = get_principal_field(&tuple, "recipient")?;
 
                 Ok(Self {
                     txid,
@@ -527,6 +530,42 @@ impl StacksSubnetOp {
                     },
                 })
             }
+            "\"set-block-limit\"" => {
+                // Parse 6 fields: activation-height, and the 5 dimensions of the block limit
+                let activation_height = get_u64_field(&tuple, "activation-height")?;
+                let block_limit = ExecutionCost {
+                    runtime: get_u64_field(&tuple, "runtime")?,
+                    read_count: get_u64_field(&tuple, "read-count")?,
+                    read_length: get_u64_field(&tuple, "read-length")?,
+                    write_count: get_u64_field(&tuple, "write-count")?,
+                    write_length: get_u64_field(&tuple, "write-length")?,
+                };
+
+                Ok(Self {
+                    txid,
+                    event_index,
+                    in_block: in_block.clone(),
+                    event: StacksSubnetOpType::SetBlockLimit {
+                        activation_height,
+                        block_limit,
+                    },
+                })
+            }
+            "\"upgrade\"" => {
+                // Parse 2 fields: target-height and required-version
+                let target_height = get_u64_field(&tuple, "target-height")?;
+                let required_version = get_ascii_field(&tuple, "required-version")?;
+
+                Ok(Self {
+                    txid,
+                    event_index,
+                    in_block: in_block.clone(),
+                    event: StacksSubnetOpType::Upgrade {
+                        target_height,
+                        required_version,
+                    },
+                })
+            }
             event_type => Err(format!("Unexpected 'event' string: {}", event_type)),
         }
     }
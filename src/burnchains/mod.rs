@@ -45,6 +45,7 @@ use stacks_common::types::chainstate::StacksAddress;
 use stacks_common::types::chainstate::TrieHash;
 use stacks_common::types::chainstate::{BlockHeaderHash, BurnchainHeaderHash, StacksBlockId};
 
+use clarity::vm::costs::ExecutionCost;
 use clarity::vm::types::PrincipalData;
 use clarity::vm::types::{QualifiedContractIdentifier, StandardPrincipalData};
 use clarity::vm::ClarityName;
@@ -206,6 +207,11 @@ pub enum AssetType {
     FungibleToken,
     #[serde(rename = "nft")]
     NonFungibleToken,
+    #[serde(rename = "sft")]
+    SemiFungibleToken,
+    /// another subnet's L1 contract, registered as a destination of STX transfers
+    #[serde(rename = "subnet")]
+    Subnet,
 }
 
 impl std::str::FromStr for AssetType {
@@ -215,6 +221,8 @@ impl std::str::FromStr for AssetType {
         match s {
             "ft" => Ok(AssetType::FungibleToken),
             "nft" => Ok(AssetType::NonFungibleToken),
+            "sft" => Ok(AssetType::SemiFungibleToken),
+            "subnet" => Ok(AssetType::Subnet),
             _ => Err(format!("Invalid asset type: {}", s)),
         }
     }
@@ -225,6 +233,8 @@ impl Display for AssetType {
         match self {
             AssetType::FungibleToken => write!(f, "ft"),
             AssetType::NonFungibleToken => write!(f, "nft"),
+            AssetType::SemiFungibleToken => write!(f, "sft"),
+            AssetType::Subnet => write!(f, "subnet"),
         }
     }
 }
@@ -258,6 +268,14 @@ pub enum StacksSubnetOpType {
         subnet_contract_id: QualifiedContractIdentifier,
         id: u128,
         sender: PrincipalData,
+        token_uri: Option<String>,
+    },
+    DepositSft {
+        l1_contract_id: QualifiedContractIdentifier,
+        subnet_contract_id: QualifiedContractIdentifier,
+        id: u128,
+        amount: u128,
+        sender: PrincipalData,
     },
     WithdrawStx {
         amount: u128,
@@ -274,6 +292,14 @@ pub enum StacksSubnetOpType {
         id: u128,
         recipient: PrincipalData,
     },
+    SetBlockLimit {
+        activation_height: u64,
+        block_limit: ExecutionCost,
+    },
+    Upgrade {
+        target_height: u64,
+        required_version: String,
+    },
 }
 
 #[derive(Debug, PartialEq, Clone)]
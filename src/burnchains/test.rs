@@ -1102,6 +1102,19 @@ fn create_stacks_events_failures_deposit_nft() {
             subnet-contract-id: 'STTHM8422MZMP02R6KHPSCBAHKDTZZ6Y4FRH7CSH, sender: 'ST000000000000000000002AMW42H  }"#,
             "Expected 'subnet-contract-id' to be a contract principal",
         ),
+        // malformed metadata fields must be rejected, not panic the node
+        (
+            r#"{ event: "deposit-nft", nft-id: u100, l1-contract-id: 'ST000000000000000000002AMW42H.simple-ft,
+            subnet-contract-id: 'STTHM8422MZMP02R6KHPSCBAHKDTZZ6Y4FRH7CSH.simple-ft, sender: 'ST000000000000000000002AMW42H,
+            nft-uri: "ipfs://token/100" }"#,
+            "Expected 'nft-uri' type to be optional",
+        ),
+        (
+            r#"{ event: "deposit-nft", nft-id: u100, l1-contract-id: 'ST000000000000000000002AMW42H.simple-ft,
+            subnet-contract-id: 'STTHM8422MZMP02R6KHPSCBAHKDTZZ6Y4FRH7CSH.simple-ft, sender: 'ST000000000000000000002AMW42H,
+            nft-uri: (some u100) }"#,
+            "Expected 'nft-uri' type to be string-ascii",
+        ),
     ];
 
     for (test_input, expected_err) in inputs.iter() {
@@ -1118,6 +1131,42 @@ fn create_stacks_events_failures_deposit_nft() {
     }
 }
 
+#[test]
+fn create_stacks_events_deposit_nft_metadata() {
+    let inputs = [
+        (
+            r#"{ event: "deposit-nft", nft-id: u100, l1-contract-id: 'ST000000000000000000002AMW42H.simple-nft,
+            subnet-contract-id: 'STTHM8422MZMP02R6KHPSCBAHKDTZZ6Y4FRH7CSH.simple-nft, sender: 'ST000000000000000000002AMW42H }"#,
+            None,
+        ),
+        (
+            r#"{ event: "deposit-nft", nft-id: u100, l1-contract-id: 'ST000000000000000000002AMW42H.simple-nft,
+            subnet-contract-id: 'STTHM8422MZMP02R6KHPSCBAHKDTZZ6Y4FRH7CSH.simple-nft, sender: 'ST000000000000000000002AMW42H,
+            nft-uri: (some "ipfs://token/100") }"#,
+            Some("ipfs://token/100".to_string()),
+        ),
+        (
+            r#"{ event: "deposit-nft", nft-id: u100, l1-contract-id: 'ST000000000000000000002AMW42H.simple-nft,
+            subnet-contract-id: 'STTHM8422MZMP02R6KHPSCBAHKDTZZ6Y4FRH7CSH.simple-nft, sender: 'ST000000000000000000002AMW42H,
+            nft-uri: none }"#,
+            None,
+        ),
+    ];
+
+    for (test_input, expected_uri) in inputs.iter() {
+        let value = execute(test_input).unwrap().unwrap();
+        let op =
+            StacksSubnetOp::try_from_clar_value(value, Txid([0; 32]), 0, &StacksBlockId([0; 32]))
+                .unwrap();
+        match op.event {
+            StacksSubnetOpType::DepositNft { token_uri, .. } => {
+                assert_eq!(&token_uri, expected_uri);
+            }
+            _ => panic!("Expected a deposit-nft op, got {:?}", op.event),
+        }
+    }
+}
+
 #[test]
 fn create_stacks_events_failures_withdraw_stx() {
     let inputs = [
@@ -1908,3 +1957,524 @@ fn test_num_sync_cycles_to_height() {
     assert_eq!(3, PoxConstants::num_sync_cycles_to_height_internal(5, 2));
     assert_eq!(4, PoxConstants::num_sync_cycles_to_height_internal(7, 2));
 }
+
+/// Property tests for the parser which turns the subnet contract's events into burnchain
+/// operations.  Each strategy builds the fields of a well-formed event, as the contract prints
+/// them, along with the operation they should parse to.
+mod event_parsing {
+    use std::collections::BTreeMap;
+    use std::convert::TryFrom;
+
+    use proptest::prelude::*;
+    use proptest::sample::Index;
+
+    use crate::burnchains::events::{ContractEvent, NewBlock, NewBlockTxEvent, TxEventType};
+    use crate::burnchains::Txid;
+    use crate::burnchains::{AssetType, StacksSubnetBlock, StacksSubnetOp, StacksSubnetOpType};
+    use crate::types::chainstate::{BlockHeaderHash, StacksBlockId};
+    use crate::vm::costs::ExecutionCost;
+    use crate::vm::types::{
+        PrincipalData, QualifiedContractIdentifier, SequenceData, StandardPrincipalData, TupleData,
+        Value as ClarityValue,
+    };
+    use crate::vm::{ClarityName, ContractName};
+    use stacks_common::util::hash::Sha512Trunc256Sum;
+
+    type EventFields = Vec<(&'static str, ClarityValue)>;
+
+    const EVENT_NAMES: &[&str] = &[
+        "block-commit",
+        "register-contract",
+        "deposit-stx",
+        "deposit-ft",
+        "deposit-nft",
+        "deposit-sft",
+        "withdraw-stx",
+        "withdraw-ft",
+        "withdraw-nft",
+        "set-block-limit",
+        "upgrade",
+    ];
+
+    const FIELD_NAMES: &[&str] = &[
+        "block-commit",
+        "withdrawal-root",
+        "asset-type",
+        "l1-contract",
+        "l2-contract",
+        "amount",
+        "sender",
+        "recipient",
+        "l1-contract-id",
+        "subnet-contract-id",
+        "ft-name",
+        "ft-amount",
+        "nft-id",
+        "nft-uri",
+        "sft-id",
+        "sft-amount",
+        "activation-height",
+        "runtime",
+        "read-count",
+        "read-length",
+        "write-count",
+        "write-length",
+        "target-height",
+        "required-version",
+    ];
+
+    /// Fields which an event may leave out
+    const OPTIONAL_FIELDS: &[&str] = &["nft-uri"];
+
+    fn ascii(s: &str) -> ClarityValue {
+        ClarityValue::string_ascii_from_bytes(s.as_bytes().to_vec()).unwrap()
+    }
+
+    fn buff(bytes: &[u8]) -> ClarityValue {
+        ClarityValue::buff_from(bytes.to_vec()).unwrap()
+    }
+
+    fn contract(id: &QualifiedContractIdentifier) -> ClarityValue {
+        ClarityValue::Principal(PrincipalData::Contract(id.clone()))
+    }
+
+    fn to_tuple(fields: EventFields) -> ClarityValue {
+        ClarityValue::Tuple(
+            TupleData::from_data(
+                fields
+                    .into_iter()
+                    .map(|(name, value)| (ClarityName::from(name), value))
+                    .collect(),
+            )
+            .unwrap(),
+        )
+    }
+
+    fn parse(value: ClarityValue) -> Result<StacksSubnetOp, String> {
+        StacksSubnetOp::try_from_clar_value(value, Txid([1; 32]), 2, &StacksBlockId([3; 32]))
+    }
+
+    /// The name of the event which `op` is parsed from
+    fn event_name(op: &StacksSubnetOpType) -> &'static str {
+        match op {
+            StacksSubnetOpType::BlockCommit { .. } => "block-commit",
+            StacksSubnetOpType::RegisterAsset { .. } => "register-contract",
+            StacksSubnetOpType::DepositStx { .. } => "deposit-stx",
+            StacksSubnetOpType::DepositFt { .. } => "deposit-ft",
+            StacksSubnetOpType::DepositNft { .. } => "deposit-nft",
+            StacksSubnetOpType::DepositSft { .. } => "deposit-sft",
+            StacksSubnetOpType::WithdrawStx { .. } => "withdraw-stx",
+            StacksSubnetOpType::WithdrawFt { .. } => "withdraw-ft",
+            StacksSubnetOpType::WithdrawNft { .. } => "withdraw-nft",
+            StacksSubnetOpType::SetBlockLimit { .. } => "set-block-limit",
+            StacksSubnetOpType::Upgrade { .. } => "upgrade",
+        }
+    }
+
+    /// Could a field of type `a` hold a value like `b`?
+    fn same_kind(a: &ClarityValue, b: &ClarityValue) -> bool {
+        use std::mem::discriminant;
+        match (a, b) {
+            (
+                ClarityValue::Sequence(SequenceData::String(a)),
+                ClarityValue::Sequence(SequenceData::String(b)),
+            ) => discriminant(a) == discriminant(b),
+            (ClarityValue::Sequence(a), ClarityValue::Sequence(b)) => {
+                discriminant(a) == discriminant(b)
+            }
+            _ => discriminant(a) == discriminant(b),
+        }
+    }
+
+    fn arb_standard_principal() -> impl Strategy<Value = StandardPrincipalData> {
+        (0u8..32, any::<[u8; 20]>())
+            .prop_map(|(version, bytes)| StandardPrincipalData(version, bytes))
+    }
+
+    fn arb_contract_id() -> impl Strategy<Value = QualifiedContractIdentifier> {
+        (arb_standard_principal(), "[a-z][a-z0-9-]{0,39}").prop_map(|(issuer, name)| {
+            QualifiedContractIdentifier::new(issuer, ContractName::try_from(name).unwrap())
+        })
+    }
+
+    fn arb_principal() -> impl Strategy<Value = PrincipalData> {
+        prop_oneof![
+            arb_standard_principal().prop_map(PrincipalData::Standard),
+            arb_contract_id().prop_map(PrincipalData::Contract),
+        ]
+    }
+
+    fn arb_asset_name() -> impl Strategy<Value = String> {
+        "[a-zA-Z0-9-]{1,32}"
+    }
+
+    /// A value of any of the types the subnet contract prints
+    fn arb_leaf_value() -> impl Strategy<Value = ClarityValue> {
+        prop_oneof![
+            any::<u128>().prop_map(ClarityValue::UInt),
+            any::<i128>().prop_map(ClarityValue::Int),
+            any::<bool>().prop_map(ClarityValue::Bool),
+            prop::collection::vec(any::<u8>(), 0..40).prop_map(|bytes| buff(&bytes)),
+            "[ -~]{0,20}".prop_map(|s| ascii(&s)),
+            "\\PC{0,10}"
+                .prop_map(|s| ClarityValue::string_utf8_from_bytes(s.into_bytes()).unwrap()),
+            arb_principal().prop_map(ClarityValue::Principal),
+            Just(ClarityValue::none()),
+            any::<u128>().prop_map(|x| ClarityValue::some(ClarityValue::UInt(x)).unwrap()),
+        ]
+    }
+
+    /// A well-formed event's fields, and the operation it should parse to
+    fn arb_event() -> impl Strategy<Value = (EventFields, StacksSubnetOpType)> {
+        prop_oneof![
+            (any::<[u8; 32]>(), any::<[u8; 32]>()).prop_map(|(block_hash, withdrawal_root)| {
+                (
+                    vec![
+                        ("event", ascii("block-commit")),
+                        ("block-commit", buff(&block_hash)),
+                        ("withdrawal-root", buff(&withdrawal_root)),
+                    ],
+                    StacksSubnetOpType::BlockCommit {
+                        subnet_block_hash: BlockHeaderHash(block_hash),
+                        withdrawal_merkle_root: Sha512Trunc256Sum(withdrawal_root),
+                    },
+                )
+            }),
+            (
+                prop_oneof![
+                    Just(AssetType::FungibleToken),
+                    Just(AssetType::NonFungibleToken),
+                    Just(AssetType::SemiFungibleToken),
+                    Just(AssetType::Subnet),
+                ],
+                arb_contract_id(),
+                arb_contract_id(),
+            )
+                .prop_map(|(asset_type, l1_contract_id, l2_contract_id)| {
+                    (
+                        vec![
+                            ("event", ascii("register-contract")),
+                            ("asset-type", ascii(&asset_type.to_string())),
+                            ("l1-contract", contract(&l1_contract_id)),
+                            ("l2-contract", contract(&l2_contract_id)),
+                        ],
+                        StacksSubnetOpType::RegisterAsset {
+                            asset_type,
+                            l1_contract_id,
+                            l2_contract_id,
+                        },
+                    )
+                }),
+            (any::<u128>(), arb_principal()).prop_map(|(amount, sender)| {
+                (
+                    vec![
+                        ("event", ascii("deposit-stx")),
+                        ("amount", ClarityValue::UInt(amount)),
+                        ("sender", ClarityValue::Principal(sender.clone())),
+                    ],
+                    StacksSubnetOpType::DepositStx { amount, sender },
+                )
+            }),
+            (
+                arb_contract_id(),
+                arb_contract_id(),
+                arb_asset_name(),
+                any::<u128>(),
+                arb_principal(),
+            )
+                .prop_map(
+                    |(l1_contract_id, subnet_contract_id, name, amount, sender)| {
+                        (
+                            vec![
+                                ("event", ascii("deposit-ft")),
+                                ("l1-contract-id", contract(&l1_contract_id)),
+                                ("ft-name", ascii(&name)),
+                                ("ft-amount", ClarityValue::UInt(amount)),
+                                ("sender", ClarityValue::Principal(sender.clone())),
+                                ("subnet-contract-id", contract(&subnet_contract_id)),
+                            ],
+                            StacksSubnetOpType::DepositFt {
+                                l1_contract_id,
+                                subnet_contract_id,
+                                name,
+                                amount,
+                                sender,
+                            },
+                        )
+                    }
+                ),
+            (
+                arb_contract_id(),
+                arb_contract_id(),
+                any::<u128>(),
+                arb_principal(),
+                // the outer `None` leaves the field out, as older contracts do
+                proptest::option::of(proptest::option::of("[ -~]{0,64}")),
+            )
+                .prop_map(
+                    |(l1_contract_id, subnet_contract_id, id, sender, token_uri)| {
+                        let mut fields = vec![
+                            ("event", ascii("deposit-nft")),
+                            ("l1-contract-id", contract(&l1_contract_id)),
+                            ("nft-id", ClarityValue::UInt(id)),
+                            ("sender", ClarityValue::Principal(sender.clone())),
+                            ("subnet-contract-id", contract(&subnet_contract_id)),
+                        ];
+                        if let Some(ref token_uri) = token_uri {
+                            let value = match token_uri {
+                                Some(uri) => ClarityValue::some(ascii(uri)).unwrap(),
+                                None => ClarityValue::none(),
+                            };
+                            fields.push(("nft-uri", value));
+                        }
+                        (
+                            fields,
+                            StacksSubnetOpType::DepositNft {
+                                l1_contract_id,
+                                subnet_contract_id,
+                                id,
+                                sender,
+                                token_uri: token_uri.flatten(),
+                            },
+                        )
+                    }
+                ),
+            (
+                arb_contract_id(),
+                arb_contract_id(),
+                any::<u128>(),
+                any::<u128>(),
+                arb_principal(),
+            )
+                .prop_map(
+                    |(l1_contract_id, subnet_contract_id, id, amount, sender)| {
+                        (
+                            vec![
+                                ("event", ascii("deposit-sft")),
+                                ("l1-contract-id", contract(&l1_contract_id)),
+                                ("sft-id", ClarityValue::UInt(id)),
+                                ("sft-amount", ClarityValue::UInt(amount)),
+                                ("sender", ClarityValue::Principal(sender.clone())),
+                                ("subnet-contract-id", contract(&subnet_contract_id)),
+                            ],
+                            StacksSubnetOpType::DepositSft {
+                                l1_contract_id,
+                                subnet_contract_id,
+                                id,
+                                amount,
+                                sender,
+                            },
+                        )
+                    }
+                ),
+            (any::<u128>(), arb_principal()).prop_map(|(amount, recipient)| {
+                (
+                    vec![
+                        ("event", ascii("withdraw-stx")),
+                        ("amount", ClarityValue::UInt(amount)),
+                        ("recipient", ClarityValue::Principal(recipient.clone())),
+                    ],
+                    StacksSubnetOpType::WithdrawStx { amount, recipient },
+                )
+            }),
+            (
+                arb_contract_id(),
+                arb_asset_name(),
+                any::<u128>(),
+                arb_principal(),
+            )
+                .prop_map(|(l1_contract_id, name, amount, recipient)| {
+                    (
+                        vec![
+                            ("event", ascii("withdraw-ft")),
+                            ("ft-amount", ClarityValue::UInt(amount)),
+                            ("l1-contract-id", contract(&l1_contract_id)),
+                            ("ft-name", ascii(&name)),
+                            ("recipient", ClarityValue::Principal(recipient.clone())),
+                        ],
+                        StacksSubnetOpType::WithdrawFt {
+                            l1_contract_id,
+                            name,
+                            amount,
+                            recipient,
+                        },
+                    )
+                }),
+            (arb_contract_id(), any::<u128>(), arb_principal()).prop_map(
+                |(l1_contract_id, id, recipient)| {
+                    (
+                        vec![
+                            ("event", ascii("withdraw-nft")),
+                            ("nft-id", ClarityValue::UInt(id)),
+                            ("l1-contract-id", contract(&l1_contract_id)),
+                            ("recipient", ClarityValue::Principal(recipient.clone())),
+                        ],
+                        StacksSubnetOpType::WithdrawNft {
+                            l1_contract_id,
+                            id,
+                            recipient,
+                        },
+                    )
+                }
+            ),
+            (any::<u64>(), any::<[u64; 5]>()).prop_map(|(activation_height, limit)| {
+                (
+                    vec![
+                        ("event", ascii("set-block-limit")),
+                        (
+                            "activation-height",
+                            ClarityValue::UInt(activation_height.into()),
+                        ),
+                        ("runtime", ClarityValue::UInt(limit[0].into())),
+                        ("read-count", ClarityValue::UInt(limit[1].into())),
+                        ("read-length", ClarityValue::UInt(limit[2].into())),
+                        ("write-count", ClarityValue::UInt(limit[3].into())),
+                        ("write-length", ClarityValue::UInt(limit[4].into())),
+                    ],
+                    StacksSubnetOpType::SetBlockLimit {
+                        activation_height,
+                        block_limit: ExecutionCost {
+                            runtime: limit[0],
+                            read_count: limit[1],
+                            read_length: limit[2],
+                            write_count: limit[3],
+                            write_length: limit[4],
+                        },
+                    },
+                )
+            }),
+            (any::<u64>(), "[0-9.]{1,20}").prop_map(|(target_height, required_version)| {
+                (
+                    vec![
+                        ("event", ascii("upgrade")),
+                        ("target-height", ClarityValue::UInt(target_height.into())),
+                        ("required-version", ascii(&required_version)),
+                    ],
+                    StacksSubnetOpType::Upgrade {
+                        target_height,
+                        required_version,
+                    },
+                )
+            }),
+        ]
+    }
+
+    proptest! {
+        #[test]
+        fn well_formed_events_parse((fields, expected) in arb_event()) {
+            let op = parse(to_tuple(fields)).unwrap();
+            prop_assert_eq!(op.txid, Txid([1; 32]));
+            prop_assert_eq!(op.event_index, 2);
+            prop_assert_eq!(op.in_block, StacksBlockId([3; 32]));
+            prop_assert_eq!(op.event, expected);
+        }
+
+        #[test]
+        fn events_missing_a_field_are_rejected(
+            (mut fields, _) in arb_event(),
+            index in any::<Index>(),
+        ) {
+            let (name, _) = fields.remove(index.index(fields.len()));
+            prop_assume!(!OPTIONAL_FIELDS.contains(&name));
+            prop_assert!(parse(to_tuple(fields)).is_err(), "accepted event without '{}'", name);
+        }
+
+        #[test]
+        fn events_with_a_mistyped_field_are_rejected(
+            (mut fields, _) in arb_event(),
+            index in any::<Index>(),
+            value in arb_leaf_value(),
+        ) {
+            let index = index.index(fields.len());
+            prop_assume!(!same_kind(&fields[index].1, &value));
+            fields[index].1 = value;
+            prop_assert!(
+                parse(to_tuple(fields.clone())).is_err(),
+                "accepted event with mistyped '{}'",
+                fields[index].0
+            );
+        }
+
+        /// Events with any mix of fields, of any type, are parsed without panicking, and are
+        /// only ever parsed to the operation their `event` names.
+        #[test]
+        fn arbitrary_events_never_panic(
+            event in prop_oneof![
+                prop::sample::select(EVENT_NAMES).prop_map(ascii),
+                arb_leaf_value(),
+            ],
+            fields in prop::collection::vec((prop::sample::select(FIELD_NAMES), arb_leaf_value()), 0..8),
+        ) {
+            let fields: BTreeMap<_, _> = fields.into_iter().collect();
+            let mut fields: EventFields = fields.into_iter().collect();
+            fields.push(("event", event.clone()));
+            if let Ok(op) = parse(to_tuple(fields)) {
+                prop_assert_eq!(event, ascii(event_name(&op.event)));
+            }
+        }
+
+        /// Only committed events printed by the watched contract become operations.
+        #[test]
+        fn block_events_are_filtered(
+            events in prop::collection::vec(
+                (any::<bool>(), any::<bool>(), any::<bool>(), arb_event()),
+                0..8,
+            ),
+        ) {
+            let watched_contract = QualifiedContractIdentifier::new(
+                StandardPrincipalData(1, [3; 20]),
+                "subnet-contract".into(),
+            );
+            let other_contract = QualifiedContractIdentifier::new(
+                StandardPrincipalData(1, [2; 20]),
+                "subnet-contract".into(),
+            );
+
+            let mut expected = vec![];
+            let mut tx_events = vec![];
+            for (i, (committed, watched, is_contract_event, (fields, op))) in
+                events.into_iter().enumerate()
+            {
+                if committed && watched && is_contract_event {
+                    expected.push((i as u32, op));
+                }
+                tx_events.push(NewBlockTxEvent {
+                    txid: Txid([i as u8; 32]),
+                    event_index: i,
+                    committed,
+                    event_type: if is_contract_event {
+                        TxEventType::ContractEvent
+                    } else {
+                        TxEventType::Other
+                    },
+                    contract_event: Some(ContractEvent {
+                        contract_identifier: if watched {
+                            watched_contract.clone()
+                        } else {
+                            other_contract.clone()
+                        },
+                        topic: "print".into(),
+                        value: to_tuple(fields),
+                    }),
+                });
+            }
+
+            let block = StacksSubnetBlock::from_new_block_event(
+                &watched_contract,
+                NewBlock {
+                    block_height: 1,
+                    burn_block_time: 0,
+                    index_block_hash: StacksBlockId([1; 32]),
+                    parent_index_block_hash: StacksBlockId([0; 32]),
+                    events: tx_events,
+                },
+            );
+            let ops: Vec<_> = block
+                .ops
+                .into_iter()
+                .map(|op| (op.event_index, op.event))
+                .collect();
+            prop_assert_eq!(ops, expected);
+        }
+    }
+}
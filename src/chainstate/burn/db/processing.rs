@@ -112,6 +112,20 @@ impl<'a> SortitionHandleTx<'a> {
                 );
                 BurnchainError::OpError(e)
             }),
+            BlockstackOperationType::DepositSft(ref op) => op.check(burnchain, self).map_err(|e| {
+                warn!(
+                    "REJECTED burnchain operation";
+                    "op" => "deposit_sft",
+                    "l1_stacks_block_id" => %op.burn_header_hash,
+                    "txid" => %op.txid,
+                    "l1_contract_id" => %op.l1_contract_id,
+                    "subnet_contract_id" => %op.subnet_contract_id,
+                    "id" => %op.id,
+                    "amount" => %op.amount,
+                    "sender" => %op.sender,
+                );
+                BurnchainError::OpError(e)
+            }),
             BlockstackOperationType::WithdrawFt(ref op) => op.check(burnchain, self).map_err(|e| {
                 warn!(
                     "REJECTED burnchain operation";
@@ -152,6 +166,33 @@ impl<'a> SortitionHandleTx<'a> {
                     BurnchainError::OpError(e)
                 })
             }
+            BlockstackOperationType::SetBlockLimit(ref op) => {
+                op.check(burnchain, self).map_err(|e| {
+                    warn!(
+                        "REJECTED burnchain operation";
+                        "op" => "set_block_limit",
+                        "l1_stacks_block_id" => %op.burn_header_hash,
+                        "txid" => %op.txid,
+                        "activation_height" => op.activation_height,
+                        "error" => %e,
+                    );
+                    BurnchainError::OpError(e)
+                })
+            }
+            BlockstackOperationType::UpgradeSignal(ref op) => {
+                op.check(burnchain, self).map_err(|e| {
+                    warn!(
+                        "REJECTED burnchain operation";
+                        "op" => "upgrade_signal",
+                        "l1_stacks_block_id" => %op.burn_header_hash,
+                        "txid" => %op.txid,
+                        "target_height" => op.target_height,
+                        "required_version" => %op.required_version,
+                        "error" => %e,
+                    );
+                    BurnchainError::OpError(e)
+                })
+            }
         }
     }
 
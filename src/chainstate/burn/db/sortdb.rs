@@ -41,11 +41,13 @@ use crate::burnchains::{
     Burnchain, BurnchainBlockHeader, BurnchainRecipient, BurnchainStateTransition,
     BurnchainTransaction, BurnchainView, Error as BurnchainError, PoxConstants,
 };
+use crate::chainstate::burn::operations::upgrade_signal::version_meets;
 use crate::chainstate::burn::operations::RegisterAssetOp;
 use crate::chainstate::burn::operations::{
     leader_block_commit::{MissedBlockCommit, RewardSetInfo, OUTPUTS_PER_COMMIT},
-    BlockstackOperationType, DepositFtOp, DepositNftOp, DepositStxOp, LeaderBlockCommitOp,
-    LeaderKeyRegisterOp, PreStxOp, StackStxOp, TransferStxOp, UserBurnSupportOp,
+    BlockstackOperationType, DepositFtOp, DepositNftOp, DepositSftOp, DepositStxOp,
+    LeaderBlockCommitOp, LeaderKeyRegisterOp, PreStxOp, StackStxOp, TransferStxOp,
+    UserBurnSupportOp,
 };
 use crate::chainstate::burn::Opcodes;
 use crate::chainstate::burn::{BlockSnapshot, ConsensusHash, OpsHash, SortitionHash};
@@ -64,6 +66,7 @@ use crate::chainstate::ChainstateDB;
 use crate::core::FIRST_BURNCHAIN_CONSENSUS_HASH;
 use crate::core::FIRST_STACKS_BLOCK_HASH;
 use crate::core::{StacksEpoch, StacksEpochId, STACKS_EPOCH_MAX};
+use crate::monitoring;
 use crate::net::neighbors::MAX_NEIGHBOR_BLOCK_DELAY;
 use crate::net::{Error as NetError, Error};
 use crate::util_lib::db::tx_begin_immediate;
@@ -72,7 +75,7 @@ use crate::util_lib::db::DBTx;
 use crate::util_lib::db::Error as db_error;
 use crate::util_lib::db::{
     db_mkdirs, query_count, query_row, query_row_columns, query_row_panic, query_rows, sql_pragma,
-    u64_to_sql, DBConn, FromColumn, FromRow, IndexDBConn, IndexDBTx,
+    u64_to_sql, DBConn, FromColumn, FromRow, IndexDBConn, IndexDBRead, IndexDBTx,
 };
 use clarity::vm::representations::{ClarityName, ContractName};
 use clarity::vm::types::{PrincipalData, QualifiedContractIdentifier, Value};
@@ -353,6 +356,7 @@ impl FromRow<DepositNftOp> for DepositNftOp {
         let id_str: String = row.get_unwrap("id");
         let id = u128::from_str_radix(&id_str, 10).expect("CORRUPTION: bad u128 written to sortdb");
         let sender = StacksAddress::from_column(row, "sender")?;
+        let token_uri: Option<String> = row.get_unwrap("token_uri");
 
         Ok(DepositNftOp {
             txid,
@@ -361,11 +365,39 @@ impl FromRow<DepositNftOp> for DepositNftOp {
             subnet_contract_id,
             id,
             sender: PrincipalData::from(sender),
+            token_uri,
         })
     }
 }
 
-pub const SORTITION_DB_VERSION: &'static str = "3";
+impl FromRow<DepositSftOp> for DepositSftOp {
+    fn from_row<'a>(row: &'a Row) -> Result<DepositSftOp, db_error> {
+        let txid = Txid::from_column(row, "txid")?;
+        let burn_header_hash = BurnchainHeaderHash::from_column(row, "l1_block_id")?;
+
+        let l1_contract_id = QualifiedContractIdentifier::from_column(row, "l1_contract_id")?;
+        let subnet_contract_id =
+            QualifiedContractIdentifier::from_column(row, "subnet_contract_id")?;
+        let id_str: String = row.get_unwrap("id");
+        let id = u128::from_str_radix(&id_str, 10).expect("CORRUPTION: bad u128 written to sortdb");
+        let amount_str: String = row.get_unwrap("amount");
+        let amount =
+            u128::from_str_radix(&amount_str, 10).expect("CORRUPTION: bad u128 written to sortdb");
+        let sender = StacksAddress::from_column(row, "sender")?;
+
+        Ok(DepositSftOp {
+            txid,
+            burn_header_hash,
+            l1_contract_id,
+            subnet_contract_id,
+            id,
+            amount,
+            sender: PrincipalData::from(sender),
+        })
+    }
+}
+
+pub const SORTITION_DB_VERSION: &'static str = "5";
 
 const SORTITION_DB_INITIAL_SCHEMA: &'static [&'static str] = &[
     r#"
@@ -578,6 +610,24 @@ const SORTITION_DB_SCHEMA_3: &'static [&'static str] = &[r#"
         FOREIGN KEY(block_commit_txid,block_commit_sortition_id) REFERENCES block_commits(txid,sortition_id)
     );"#];
 
+const SORTITION_DB_SCHEMA_4: &'static [&'static str] = &[r#"
+     CREATE TABLE deposit_sft(
+         txid TEXT NOT NULL,
+         l1_block_id TEXT NOT NULL,
+         l1_contract_id TEXT NOT NULL,
+         subnet_contract_id TEXT NOT NULL,
+         id TEXT NOT NULL,
+         amount TEXT NOT NULL,
+         sender TEXT NOT NULL,
+         sortition_id TEXT NOT NULL,
+
+         PRIMARY KEY(txid,sortition_id),
+         FOREIGN KEY(sortition_id) REFERENCES snapshots(sortition_id)
+     );"#];
+
+const SORTITION_DB_SCHEMA_5: &'static [&'static str] =
+    &[r#"ALTER TABLE deposit_nft ADD COLUMN token_uri TEXT;"#];
+
 // update this to add new indexes
 const LAST_SORTITION_DB_INDEX: &'static str = "index_parent_sortition_id";
 
@@ -687,6 +737,16 @@ fn get_ancestor_sort_id_tx<C: SortitionContext>(
     ic.get_ancestor_block_hash(adjusted_height, &tip_block_hash)
 }
 
+/// Decode the block limit updates stored under `db_keys::block_limit_updates()`
+fn block_limit_updates_from_str(value: &str) -> Vec<(u64, ExecutionCost)> {
+    serde_json::from_str(value).expect("CORRUPTION: bad block limit updates stored in DB")
+}
+
+/// Decode the upgrade signals stored under `db_keys::upgrade_signals()`
+fn upgrade_signals_from_str(value: &str) -> Vec<(u64, String)> {
+    serde_json::from_str(value).expect("CORRUPTION: bad upgrade signals stored in DB")
+}
+
 fn get_adjusted_block_height<C: SortitionContext>(context: &C, block_height: u64) -> Option<u64> {
     let first_block_height = context.first_block_height();
     if block_height < first_block_height {
@@ -728,6 +788,18 @@ impl db_keys {
         "sortition_db::initial_mining::per_block"
     }
 
+    /// MARF index key for the block limit updates the L1 contract has made in this fork.  Maps
+    /// to a JSON list of (activation height, block limit) pairs, in the order they were made.
+    pub fn block_limit_updates() -> &'static str {
+        "sortition_db::block_limit_updates"
+    }
+
+    /// MARF index key for the upgrade signals the L1 contract has made in this fork.  Maps to a
+    /// JSON list of (target height, required version) pairs, in the order they were made.
+    pub fn upgrade_signals() -> &'static str {
+        "sortition_db::upgrade_signals"
+    }
+
     pub fn sortition_id_for_bhh(bhh: &BurnchainHeaderHash) -> String {
         format!("sortition_db::sortition_id_for_bhh::{}", bhh)
     }
@@ -1761,6 +1833,12 @@ impl SortitionDB {
         for row_text in SORTITION_DB_SCHEMA_3 {
             db_tx.execute_batch(row_text)?;
         }
+        for row_text in SORTITION_DB_SCHEMA_4 {
+            db_tx.execute_batch(row_text)?;
+        }
+        for row_text in SORTITION_DB_SCHEMA_5 {
+            db_tx.execute_batch(row_text)?;
+        }
 
         SortitionDB::validate_and_insert_epochs(&db_tx, epochs_ref)?;
 
@@ -1962,6 +2040,28 @@ impl SortitionDB {
         Ok(())
     }
 
+    fn apply_schema_4(tx: &DBTx) -> Result<(), db_error> {
+        for sql_exec in SORTITION_DB_SCHEMA_4 {
+            tx.execute_batch(sql_exec)?;
+        }
+        tx.execute(
+            "INSERT OR REPLACE INTO db_config (version) VALUES (?1)",
+            &["4"],
+        )?;
+        Ok(())
+    }
+
+    fn apply_schema_5(tx: &DBTx) -> Result<(), db_error> {
+        for sql_exec in SORTITION_DB_SCHEMA_5 {
+            tx.execute_batch(sql_exec)?;
+        }
+        tx.execute(
+            "INSERT OR REPLACE INTO db_config (version) VALUES (?1)",
+            &["5"],
+        )?;
+        Ok(())
+    }
+
     fn check_schema_version_or_error(&mut self) -> Result<(), db_error> {
         match SortitionDB::get_schema_version(self.conn()) {
             Ok(Some(version)) => {
@@ -1996,6 +2096,16 @@ impl SortitionDB {
                         let tx = self.tx_begin()?;
                         SortitionDB::apply_schema_3(&tx.deref())?;
                         tx.commit()?;
+                    } else if version == "3" {
+                        // add the deposit_sft table; no earlier L1 block could have had any
+                        let tx = self.tx_begin()?;
+                        SortitionDB::apply_schema_4(&tx.deref())?;
+                        tx.commit()?;
+                    } else if version == "4" {
+                        // add the NFT token URI column; existing deposits carry none
+                        let tx = self.tx_begin()?;
+                        SortitionDB::apply_schema_5(&tx.deref())?;
+                        tx.commit()?;
                     } else if version == expected_version {
                         return Ok(());
                     } else {
@@ -2592,6 +2702,47 @@ impl SortitionDB {
         Ok(ops.into_iter().flatten().collect())
     }
 
+    /// Get the hash of the L1 block `depth` blocks before `block`, or `block` itself if `depth`
+    /// is 0.  Walking back past the first L1 block gives `BurnchainHeaderHash::zero()`.
+    pub fn get_ancestor_burn_header_hash(
+        conn: &Connection,
+        block: &BurnchainHeaderHash,
+        depth: u64,
+    ) -> Result<BurnchainHeaderHash, db_error> {
+        let mut curr_block_id = block.clone();
+        for _ in 0..depth {
+            if curr_block_id == BurnchainHeaderHash::zero() {
+                break;
+            }
+            let curr_snapshot = SortitionDB::get_block_snapshot(
+                conn,
+                &SortitionId::stubbed(&curr_block_id),
+            )?
+            .ok_or_else(|| {
+                warn!("Could not find snapshot in `get_ancestor_burn_header_hash` traversal";
+                      "block" => %block,
+                      "current_block" => %curr_block_id);
+                db_error::NotFoundError
+            })?;
+            curr_block_id = curr_snapshot.parent_burn_header_hash;
+        }
+        Ok(curr_block_id)
+    }
+
+    /// Get the L1 contract most recently registered for the subnet asset contract
+    /// `l2_contract_id`, if any
+    pub fn get_l1_asset_contract(
+        conn: &Connection,
+        l2_contract_id: &QualifiedContractIdentifier,
+    ) -> Result<Option<QualifiedContractIdentifier>, db_error> {
+        let op: Option<RegisterAssetOp> = query_row(
+            conn,
+            "SELECT * FROM register_asset WHERE l2_contract_id = ?1 ORDER BY rowid DESC LIMIT 1",
+            &[&l2_contract_id.to_string()],
+        )?;
+        Ok(op.map(|op| op.l1_contract_id))
+    }
+
     pub fn get_register_asset_ops(
         conn: &Connection,
         l1_block_id: &BurnchainHeaderHash,
@@ -2636,6 +2787,17 @@ impl SortitionDB {
         )
     }
 
+    pub fn get_deposit_sft_ops(
+        conn: &Connection,
+        l1_block_id: &BurnchainHeaderHash,
+    ) -> Result<Vec<DepositSftOp>, db_error> {
+        query_rows(
+            conn,
+            "SELECT * FROM deposit_sft WHERE l1_block_id = ?",
+            &[l1_block_id],
+        )
+    }
+
     pub fn index_handle_at_tip<'a>(&'a self) -> SortitionHandleConn<'a> {
         let sortition_id = SortitionDB::get_canonical_sortition_tip(self.conn()).unwrap();
         self.index_handle(&sortition_id)
@@ -2829,6 +2991,68 @@ impl SortitionDB {
             .map_err(db_error::from)
     }
 
+    /// Get the block limit which the L1 contract set for Stacks blocks built at burn height
+    /// `burn_height` in the fork of `tip`.  Returns None if the L1 contract has not set one that
+    /// is active by then, in which case the epoch's block limit applies.
+    pub fn get_block_limit_at<I: IndexDBRead<SortitionId>>(
+        ic: &I,
+        tip: &SortitionId,
+        burn_height: u64,
+    ) -> Result<Option<ExecutionCost>, db_error> {
+        let block_limit_updates = match ic.read_indexed(tip, db_keys::block_limit_updates())? {
+            Some(value) => block_limit_updates_from_str(&value),
+            None => return Ok(None),
+        };
+        // of the updates active at `burn_height`, the one with the latest activation height
+        //  wins, and ties go to the update made last.
+        let mut block_limit: Option<(u64, ExecutionCost)> = None;
+        for (activation_height, limit) in block_limit_updates.into_iter() {
+            if activation_height > burn_height {
+                continue;
+            }
+            match block_limit {
+                Some((active_height, _)) if active_height > activation_height => {}
+                _ => block_limit = Some((activation_height, limit)),
+            }
+        }
+        Ok(block_limit.map(|(_, limit)| limit))
+    }
+
+    /// Get the upgrade signals the L1 contract has made in the fork of `tip`, as (target height,
+    /// required version) pairs, in the order they were made.
+    pub fn get_upgrade_signals<C: SortitionContext>(
+        ic: &IndexDBConn<'_, C, SortitionId>,
+        tip: &SortitionId,
+    ) -> Result<Vec<(u64, String)>, db_error> {
+        Ok(ic
+            .get_indexed(tip, db_keys::upgrade_signals())?
+            .map(|value| upgrade_signals_from_str(&value))
+            .unwrap_or_default())
+    }
+
+    /// Get the upgrade signal in effect for Stacks blocks built at burn height `burn_height` in
+    /// the fork of `tip`, as its (target height, required version).  Returns None if no signal
+    /// in this fork targets `burn_height` or earlier.
+    pub fn get_upgrade_signal_at<C: SortitionContext>(
+        ic: &IndexDBConn<'_, C, SortitionId>,
+        tip: &SortitionId,
+        burn_height: u64,
+    ) -> Result<Option<(u64, String)>, db_error> {
+        // as with block limit updates, of the signals in effect at `burn_height`, the one with
+        //  the latest target height wins, and ties go to the signal made last.
+        let mut upgrade_signal: Option<(u64, String)> = None;
+        for (target_height, required_version) in Self::get_upgrade_signals(ic, tip)?.into_iter() {
+            if target_height > burn_height {
+                continue;
+            }
+            match upgrade_signal {
+                Some((active_height, _)) if active_height > target_height => {}
+                _ => upgrade_signal = Some((target_height, required_version)),
+            }
+        }
+        Ok(upgrade_signal)
+    }
+
     /// Given the fork index hash of a chain tip, and a block height that is an ancestor of the last
     /// block in this fork, find the snapshot of the block at that height.
     pub fn get_ancestor_snapshot<C: SortitionContext>(
@@ -3199,6 +3423,21 @@ impl<'a> SortitionHandleTx<'a> {
 
                 self.insert_deposit_nft(op, sort_id)
             }
+            BlockstackOperationType::DepositSft(ref op) => {
+                info!(
+                    "ACCEPTED burnchain operation";
+                    "op" => "deposit_sft",
+                    "l1_stacks_block_id" => %op.burn_header_hash,
+                    "txid" => %op.txid,
+                    "l1_contract_id" => %op.l1_contract_id,
+                    "subnet_contract_id" => %op.subnet_contract_id,
+                    "id" => %op.id,
+                    "amount" => %op.amount,
+                    "sender" => %op.sender,
+                );
+
+                self.insert_deposit_sft(op, sort_id)
+            }
             BlockstackOperationType::WithdrawStx(ref op) => {
                 info!(
                     "ACCEPTED burnchain operation";
@@ -3240,6 +3479,44 @@ impl<'a> SortitionHandleTx<'a> {
                 // TODO(subnets) - store operation!
                 Ok(())
             }
+            BlockstackOperationType::SetBlockLimit(ref op) => {
+                info!(
+                    "ACCEPTED burnchain operation";
+                    "op" => "set_block_limit",
+                    "l1_stacks_block_id" => %op.burn_header_hash,
+                    "txid" => %op.txid,
+                    "activation_height" => op.activation_height,
+                    "block_limit" => %op.block_limit,
+                );
+
+                // the update is stored in the MARF by `index_add_fork_info`
+                Ok(())
+            }
+            BlockstackOperationType::UpgradeSignal(ref op) => {
+                info!(
+                    "ACCEPTED burnchain operation";
+                    "op" => "upgrade_signal",
+                    "l1_stacks_block_id" => %op.burn_header_hash,
+                    "txid" => %op.txid,
+                    "target_height" => op.target_height,
+                    "required_version" => %op.required_version,
+                );
+                let node_version = crate::subnet_node_version();
+                let version_met =
+                    version_meets(node_version, &op.required_version).unwrap_or(false);
+                if !version_met {
+                    warn!(
+                        "The L1 contract requires a newer subnet node version; upgrade this node before the target height";
+                        "target_height" => op.target_height,
+                        "required_version" => %op.required_version,
+                        "node_version" => node_version,
+                    );
+                }
+                monitoring::update_upgrade_signal(op.target_height, version_met);
+
+                // the signal is stored in the MARF by `index_add_fork_info`
+                Ok(())
+            }
         }
     }
 
@@ -3344,9 +3621,32 @@ impl<'a> SortitionHandleTx<'a> {
             &op.id.to_string(),
             &op.sender.to_string(),
             sort_id,
+            &op.token_uri,
         ];
 
-        self.execute("REPLACE INTO deposit_nft (txid, l1_block_id, l1_contract_id, subnet_contract_id, id, sender, sortition_id) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)", args)?;
+        self.execute("REPLACE INTO deposit_nft (txid, l1_block_id, l1_contract_id, subnet_contract_id, id, sender, sortition_id, token_uri) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)", args)?;
+
+        Ok(())
+    }
+
+    /// Insert a deposit sft op
+    fn insert_deposit_sft(
+        &mut self,
+        op: &DepositSftOp,
+        sort_id: &SortitionId,
+    ) -> Result<(), db_error> {
+        let args: &[&dyn ToSql] = &[
+            &op.txid,
+            &op.burn_header_hash,
+            &op.l1_contract_id.to_string(),
+            &op.subnet_contract_id.to_string(),
+            &op.id.to_string(),
+            &op.amount.to_string(),
+            &op.sender.to_string(),
+            sort_id,
+        ];
+
+        self.execute("REPLACE INTO deposit_sft (txid, l1_block_id, l1_contract_id, subnet_contract_id, id, amount, sender, sortition_id) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)", args)?;
 
         Ok(())
     }
@@ -3422,7 +3722,7 @@ impl<'a> SortitionHandleTx<'a> {
         &mut self,
         parent_snapshot: &mut BlockSnapshot,
         snapshot: &BlockSnapshot,
-        _block_ops: &Vec<BlockstackOperationType>,
+        block_ops: &Vec<BlockstackOperationType>,
         initialize_bonus: Option<InitialMiningBonus>,
     ) -> Result<TrieHash, db_error> {
         if !snapshot.is_initial() {
@@ -3486,6 +3786,56 @@ impl<'a> SortitionHandleTx<'a> {
             }
         }
 
+        // record block limit updates from the L1 contract, so that they are only seen in the
+        //  forks which include them
+        let mut new_block_limits = block_ops
+            .iter()
+            .filter_map(|op| match op {
+                BlockstackOperationType::SetBlockLimit(ref op) => {
+                    Some((op.activation_height, op.block_limit.clone()))
+                }
+                _ => None,
+            })
+            .peekable();
+        if new_block_limits.peek().is_some() {
+            let mut block_limit_updates = self
+                .get_indexed(
+                    &parent_snapshot.sortition_id,
+                    db_keys::block_limit_updates(),
+                )?
+                .map(|value| block_limit_updates_from_str(&value))
+                .unwrap_or_default();
+            block_limit_updates.extend(new_block_limits);
+            keys.push(db_keys::block_limit_updates().to_string());
+            values.push(
+                serde_json::to_string(&block_limit_updates)
+                    .expect("FATAL: failed to serialize block limit updates"),
+            );
+        }
+
+        // likewise for upgrade signals
+        let mut new_upgrade_signals = block_ops
+            .iter()
+            .filter_map(|op| match op {
+                BlockstackOperationType::UpgradeSignal(ref op) => {
+                    Some((op.target_height, op.required_version.clone()))
+                }
+                _ => None,
+            })
+            .peekable();
+        if new_upgrade_signals.peek().is_some() {
+            let mut upgrade_signals = self
+                .get_indexed(&parent_snapshot.sortition_id, db_keys::upgrade_signals())?
+                .map(|value| upgrade_signals_from_str(&value))
+                .unwrap_or_default();
+            upgrade_signals.extend(new_upgrade_signals);
+            keys.push(db_keys::upgrade_signals().to_string());
+            values.push(
+                serde_json::to_string(&upgrade_signals)
+                    .expect("FATAL: failed to serialize upgrade signals"),
+            );
+        }
+
         // storing null PoX info
         keys.push(db_keys::pox_reward_set_size().to_string());
         values.push(db_keys::reward_set_size_to_string(0));
@@ -5,7 +5,7 @@ use crate::burnchains::*;
 use crate::chainstate::burn::db::sortdb::db_keys;
 use crate::chainstate::burn::operations::{
     leader_block_commit::BURN_BLOCK_MINED_AT_MODULUS, BlockstackOperationType, LeaderBlockCommitOp,
-    LeaderKeyRegisterOp, UserBurnSupportOp,
+    LeaderKeyRegisterOp, SetBlockLimitOp, UpgradeSignalOp, UserBurnSupportOp,
 };
 use crate::chainstate::burn::ConsensusHash;
 use crate::chainstate::stacks::index::TrieHashExtension;
@@ -21,6 +21,7 @@ use stacks_common::util::vrf::*;
 use crate::chainstate::burn::*;
 use crate::util::hash::to_hex;
 use crate::vm::costs::ExecutionCost;
+use crate::vm::database::BurnStateDB;
 use stacks_common::types::chainstate::*;
 
 use super::sortdb::*;
@@ -2181,6 +2182,238 @@ fn test_set_stacks_block_accepted() {
     assert_eq!(last_snapshot.canonical_stacks_tip_height, 8);
 }
 
+#[test]
+fn test_block_limit_updates() {
+    let first_block_height = 123;
+    let mut db = SortitionDB::connect_test(first_block_height).unwrap();
+    let first_snapshot = SortitionDB::get_canonical_burn_chain_tip(db.conn()).unwrap();
+    let epoch_block_limit = SortitionDB::get_stacks_epoch(db.conn(), first_block_height)
+        .unwrap()
+        .unwrap()
+        .block_limit;
+
+    let set_block_limit = |txid_byte: u8, activation_height: u64, runtime: u64| {
+        let block_limit = ExecutionCost {
+            runtime,
+            ..epoch_block_limit.clone()
+        };
+        let op = BlockstackOperationType::SetBlockLimit(SetBlockLimitOp {
+            txid: Txid([txid_byte; 32]),
+            burn_header_hash: BurnchainHeaderHash([txid_byte; 32]),
+            activation_height,
+            block_limit: block_limit.clone(),
+        });
+        (op, block_limit)
+    };
+    let (op_a, limit_a) = set_block_limit(0x01, 126, 1000);
+    let (op_b, limit_b) = set_block_limit(0x03, 128, 2000);
+
+    // height 124 schedules A at 126, and height 126 schedules B at 128
+    let snapshot_a = test_append_snapshot(&mut db, BurnchainHeaderHash([0x01; 32]), &vec![op_a]);
+    test_append_snapshot(&mut db, BurnchainHeaderHash([0x02; 32]), &vec![]);
+    test_append_snapshot(&mut db, BurnchainHeaderHash([0x03; 32]), &vec![op_b]);
+    test_append_snapshot(&mut db, BurnchainHeaderHash([0x04; 32]), &vec![]);
+
+    let block_limit_at = |db: &SortitionDB, height: u32| {
+        db.index_conn()
+            .get_stacks_epoch(height)
+            .unwrap()
+            .block_limit
+    };
+    assert_eq!(block_limit_at(&db, 124), epoch_block_limit);
+    assert_eq!(block_limit_at(&db, 125), epoch_block_limit);
+    assert_eq!(block_limit_at(&db, 126), limit_a);
+    assert_eq!(block_limit_at(&db, 127), limit_a);
+    assert_eq!(block_limit_at(&db, 128), limit_b);
+    assert_eq!(block_limit_at(&db, 200), limit_b);
+
+    // a fork which does not include B only sees A
+    let fork_snapshot = {
+        let mut sn = snapshot_a.clone();
+        let next_hash = BurnchainHeaderHash([0x13; 32]);
+        let mut tx = SortitionHandleTx::begin(&mut db, &sn.sortition_id).unwrap();
+
+        let sn_parent = sn.clone();
+        sn.parent_burn_header_hash = sn.burn_header_hash.clone();
+        sn.sortition_id = SortitionId(next_hash.0.clone());
+        sn.parent_sortition_id = sn_parent.sortition_id.clone();
+        sn.burn_header_hash = next_hash;
+        sn.block_height += 1;
+        sn.num_sortitions += 1;
+        sn.consensus_hash = ConsensusHash([0x23; 20]);
+
+        let index_root = tx
+            .append_chain_tip_snapshot(&sn_parent, &sn, &vec![], None, None)
+            .unwrap();
+        sn.index_root = index_root;
+
+        tx.commit().unwrap();
+        sn
+    };
+    let ic = db.index_conn();
+    assert_eq!(
+        SortitionDB::get_block_limit_at(&ic, &fork_snapshot.sortition_id, 200).unwrap(),
+        Some(limit_a)
+    );
+    assert_eq!(
+        SortitionDB::get_block_limit_at(&ic, &first_snapshot.sortition_id, 200).unwrap(),
+        None
+    );
+}
+
+#[test]
+fn test_block_limit_updates_in_tx() {
+    let first_block_height = 123;
+    let mut db = SortitionDB::connect_test(first_block_height).unwrap();
+    let epoch_block_limit = SortitionDB::get_stacks_epoch(db.conn(), first_block_height)
+        .unwrap()
+        .unwrap()
+        .block_limit;
+    let block_limit = ExecutionCost {
+        runtime: 1000,
+        ..epoch_block_limit.clone()
+    };
+    let op = BlockstackOperationType::SetBlockLimit(SetBlockLimitOp {
+        txid: Txid([0x01; 32]),
+        burn_header_hash: BurnchainHeaderHash([0x01; 32]),
+        activation_height: 125,
+        block_limit: block_limit.clone(),
+    });
+
+    let mut sn = SortitionDB::get_canonical_burn_chain_tip(db.conn()).unwrap();
+    let mut tx = SortitionHandleTx::begin(&mut db, &sn.sortition_id).unwrap();
+
+    let sn_parent = sn.clone();
+    sn.parent_burn_header_hash = sn.burn_header_hash.clone();
+    sn.parent_sortition_id = sn.sortition_id.clone();
+    sn.burn_header_hash = BurnchainHeaderHash([0x01; 32]);
+    sn.block_height += 1;
+    sn.num_sortitions += 1;
+    sn.sortition_id = SortitionId::stubbed(&sn.burn_header_hash);
+    sn.consensus_hash = ConsensusHash([0x01; 20]);
+    tx.append_chain_tip_snapshot(&sn_parent, &sn, &vec![op], None, None)
+        .unwrap();
+
+    // the transaction sees the block limit update it has not committed yet
+    tx.context.chain_tip = sn.sortition_id.clone();
+    assert_eq!(
+        SortitionDB::get_block_limit_at(&tx, &sn.sortition_id, 125).unwrap(),
+        Some(block_limit.clone())
+    );
+    assert_eq!(
+        tx.get_stacks_epoch(124).unwrap().block_limit,
+        epoch_block_limit
+    );
+    assert_eq!(tx.get_stacks_epoch(125).unwrap().block_limit, block_limit);
+
+    // and once it commits, so does everyone else
+    tx.commit().unwrap();
+    assert_eq!(
+        db.index_conn().get_stacks_epoch(125).unwrap().block_limit,
+        block_limit
+    );
+}
+
+#[test]
+fn test_get_ancestor_burn_header_hash() {
+    let first_block_height = 123;
+    let mut db = SortitionDB::connect_test(first_block_height).unwrap();
+    let first_snapshot = SortitionDB::get_canonical_burn_chain_tip(db.conn()).unwrap();
+    assert_eq!(first_snapshot.burn_header_hash, BurnchainHeaderHash::zero());
+
+    let snapshot_1 = test_append_snapshot(&mut db, BurnchainHeaderHash([0x01; 32]), &vec![]);
+    test_append_snapshot(&mut db, BurnchainHeaderHash([0x02; 32]), &vec![]);
+    test_append_snapshot(&mut db, BurnchainHeaderHash([0x03; 32]), &vec![]);
+
+    // a fork off of 0x01
+    {
+        let mut sn = snapshot_1.clone();
+        let next_hash = BurnchainHeaderHash([0x12; 32]);
+        let mut tx = SortitionHandleTx::begin(&mut db, &sn.sortition_id).unwrap();
+
+        let sn_parent = sn.clone();
+        sn.parent_burn_header_hash = sn.burn_header_hash.clone();
+        sn.sortition_id = SortitionId::stubbed(&next_hash);
+        sn.parent_sortition_id = sn_parent.sortition_id.clone();
+        sn.burn_header_hash = next_hash;
+        sn.block_height += 1;
+        sn.num_sortitions += 1;
+        sn.consensus_hash = ConsensusHash([0x22; 20]);
+
+        tx.append_chain_tip_snapshot(&sn_parent, &sn, &vec![], None, None)
+            .unwrap();
+        tx.commit().unwrap();
+    }
+
+    let ancestor = |block_byte: u8, depth: u64| {
+        SortitionDB::get_ancestor_burn_header_hash(
+            db.conn(),
+            &BurnchainHeaderHash([block_byte; 32]),
+            depth,
+        )
+    };
+    assert_eq!(ancestor(0x03, 0).unwrap(), BurnchainHeaderHash([0x03; 32]));
+    assert_eq!(ancestor(0x03, 1).unwrap(), BurnchainHeaderHash([0x02; 32]));
+    assert_eq!(ancestor(0x03, 2).unwrap(), BurnchainHeaderHash([0x01; 32]));
+    assert_eq!(ancestor(0x03, 3).unwrap(), BurnchainHeaderHash::zero());
+
+    // walking back past the first block stops at it
+    assert_eq!(ancestor(0x03, 4).unwrap(), BurnchainHeaderHash::zero());
+    assert_eq!(ancestor(0x03, 100).unwrap(), BurnchainHeaderHash::zero());
+    assert_eq!(ancestor(0x00, 1).unwrap(), BurnchainHeaderHash::zero());
+
+    // ancestors are found in the block's own fork
+    assert_eq!(ancestor(0x12, 1).unwrap(), BurnchainHeaderHash([0x01; 32]));
+    assert_eq!(ancestor(0x12, 3).unwrap(), BurnchainHeaderHash::zero());
+
+    // an unknown block is its own ancestor at depth 0, and has no others
+    assert_eq!(ancestor(0xfe, 0).unwrap(), BurnchainHeaderHash([0xfe; 32]));
+    match ancestor(0xfe, 1) {
+        Err(db_error::NotFoundError) => {}
+        x => panic!("Expected NotFoundError, got {:?}", x),
+    }
+}
+
+#[test]
+fn test_upgrade_signals() {
+    let first_block_height = 123;
+    let mut db = SortitionDB::connect_test(first_block_height).unwrap();
+
+    let signal_upgrade = |txid_byte: u8, target_height: u64, required_version: &str| {
+        BlockstackOperationType::UpgradeSignal(UpgradeSignalOp {
+            txid: Txid([txid_byte; 32]),
+            burn_header_hash: BurnchainHeaderHash([txid_byte; 32]),
+            target_height,
+            required_version: required_version.to_string(),
+        })
+    };
+
+    // height 124 signals 0.5.0 by 126, and height 125 signals 0.6.0 by 128
+    test_append_snapshot(
+        &mut db,
+        BurnchainHeaderHash([0x01; 32]),
+        &vec![signal_upgrade(0x01, 126, "0.5.0")],
+    );
+    let tip = test_append_snapshot(
+        &mut db,
+        BurnchainHeaderHash([0x02; 32]),
+        &vec![signal_upgrade(0x02, 128, "0.6.0")],
+    );
+
+    let ic = db.index_conn();
+    assert_eq!(
+        SortitionDB::get_upgrade_signals(&ic, &tip.sortition_id).unwrap(),
+        vec![(126, "0.5.0".to_string()), (128, "0.6.0".to_string())]
+    );
+    let signal_at =
+        |height: u64| SortitionDB::get_upgrade_signal_at(&ic, &tip.sortition_id, height).unwrap();
+    assert_eq!(signal_at(125), None);
+    assert_eq!(signal_at(126), Some((126, "0.5.0".to_string())));
+    assert_eq!(signal_at(127), Some((126, "0.5.0".to_string())));
+    assert_eq!(signal_at(128), Some((128, "0.6.0".to_string())));
+    assert_eq!(signal_at(200), Some((128, "0.6.0".to_string())));
+}
+
 #[test]
 fn test_epoch_switch() {
     let db_path_dir = random_sortdb_test_dir();
@@ -14,6 +14,7 @@ impl TryFrom<&StacksSubnetOp> for DepositNftOp {
             ref subnet_contract_id,
             ref id,
             ref sender,
+            ref token_uri,
         } = value.event
         {
             Ok(DepositNftOp {
@@ -24,6 +25,7 @@ impl TryFrom<&StacksSubnetOp> for DepositNftOp {
                 subnet_contract_id: subnet_contract_id.clone(),
                 id: id.clone(),
                 sender: sender.clone(),
+                token_uri: token_uri.clone(),
             })
         } else {
             Err(op_error::InvalidInput)
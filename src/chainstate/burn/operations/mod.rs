@@ -44,6 +44,7 @@ use crate::util_lib::db::DBConn;
 use crate::util_lib::db::DBTx;
 use crate::util_lib::db::Error as db_error;
 use clarity::util::HexError;
+use clarity::vm::costs::ExecutionCost;
 use serde::Deserialize;
 use stacks_common::util::hash::Hash160;
 use stacks_common::util::hash::Sha512Trunc256Sum;
@@ -56,9 +57,12 @@ use clarity::vm::ClarityName;
 
 pub mod deposit_ft;
 pub mod deposit_nft;
+pub mod deposit_sft;
 pub mod deposit_stx;
 pub mod leader_block_commit;
 pub mod register_asset;
+pub mod set_block_limit;
+pub mod upgrade_signal;
 pub mod withdraw_ft;
 pub mod withdraw_nft;
 pub mod withdraw_stx;
@@ -98,6 +102,10 @@ pub enum Error {
 
     StackStxMustBePositive,
     StackStxInvalidCycles,
+
+    SetBlockLimitActivationPassed,
+
+    UpgradeSignalTargetPassed,
 }
 
 impl fmt::Display for Error {
@@ -153,6 +161,14 @@ impl fmt::Display for Error {
                 f,
                 "Stack STX must set num cycles between 1 and max num cycles"
             ),
+            Error::SetBlockLimitActivationPassed => write!(
+                f,
+                "Set block limit must activate after the block it is made in"
+            ),
+            Error::UpgradeSignalTargetPassed => write!(
+                f,
+                "Upgrade signal must target a block after the block it is made in"
+            ),
         }
     }
 }
@@ -172,7 +188,7 @@ impl From<db_error> for Error {
     }
 }
 
-trait HexSerialization<T, E: fmt::Display> {
+pub(crate) trait HexSerialization<T, E: fmt::Display> {
     fn ser_to_hex(&self) -> String;
     fn deser_from_hex(s: &str) -> Result<T, E>;
 }
@@ -196,7 +212,7 @@ impl_hex_serialization!(BurnchainHeaderHash);
 impl_hex_serialization!(Txid);
 impl_hex_serialization!(Sha512Trunc256Sum);
 
-fn hex_serialize<S: serde::Serializer, T: HexSerialization<T, E>, E: fmt::Display>(
+pub(crate) fn hex_serialize<S: serde::Serializer, T: HexSerialization<T, E>, E: fmt::Display>(
     bhh: &T,
     s: S,
 ) -> Result<S::Ok, S::Error> {
@@ -204,7 +220,12 @@ fn hex_serialize<S: serde::Serializer, T: HexSerialization<T, E>, E: fmt::Displa
     s.serialize_str(inst.as_str())
 }
 
-fn hex_deserialize<'de, D: serde::Deserializer<'de>, T: HexSerialization<T, E>, E: fmt::Display>(
+pub(crate) fn hex_deserialize<
+    'de,
+    D: serde::Deserializer<'de>,
+    T: HexSerialization<T, E>,
+    E: fmt::Display,
+>(
     d: D,
 ) -> Result<T, D::Error> {
     let inst_str = String::deserialize(d)?;
@@ -373,6 +394,33 @@ pub struct DepositNftOp {
     /// The principal that performed the deposit
     #[serde(serialize_with = "pd_serialize", deserialize_with = "pd_deserialize")]
     pub sender: PrincipalData,
+    /// The token URI reported by the L1 contract at deposit time, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token_uri: Option<String>,
+}
+
+#[derive(Debug, PartialEq, Clone, Eq, Serialize, Deserialize)]
+pub struct DepositSftOp {
+    /// Transaction ID of this commit op
+    #[serde(serialize_with = "hex_serialize", deserialize_with = "hex_deserialize")]
+    pub txid: Txid,
+    /// Hash of the base chain block that produced this commit op.
+    #[serde(serialize_with = "hex_serialize", deserialize_with = "hex_deserialize")]
+    pub burn_header_hash: BurnchainHeaderHash,
+
+    /// Contract ID on L1 chain for this semi-fungible token
+    #[serde(serialize_with = "qc_serialize", deserialize_with = "qc_deserialize")]
+    pub l1_contract_id: QualifiedContractIdentifier,
+    /// Contract ID on subnet for this semi-fungible token
+    #[serde(serialize_with = "qc_serialize", deserialize_with = "qc_deserialize")]
+    pub subnet_contract_id: QualifiedContractIdentifier,
+    /// The token ID of the semi-fungible token transferred
+    pub id: u128,
+    /// Amount of the token ID that was deposited
+    pub amount: u128,
+    /// The principal that performed the deposit
+    #[serde(serialize_with = "pd_serialize", deserialize_with = "pd_deserialize")]
+    pub sender: PrincipalData,
 }
 
 #[derive(Debug, PartialEq, Clone, Eq, Serialize, Deserialize)]
@@ -431,6 +479,36 @@ pub struct WithdrawNftOp {
     pub recipient: PrincipalData,
 }
 
+#[derive(Debug, PartialEq, Clone, Eq, Serialize, Deserialize)]
+pub struct SetBlockLimitOp {
+    /// Transaction ID of this op
+    #[serde(serialize_with = "hex_serialize", deserialize_with = "hex_deserialize")]
+    pub txid: Txid,
+    /// Hash of the base chain block that produced this op.
+    #[serde(serialize_with = "hex_serialize", deserialize_with = "hex_deserialize")]
+    pub burn_header_hash: BurnchainHeaderHash,
+
+    /// The first burn block height whose Stacks blocks are held to `block_limit`
+    pub activation_height: u64,
+    /// The new subnet block limit
+    pub block_limit: ExecutionCost,
+}
+
+#[derive(Debug, PartialEq, Clone, Eq, Serialize, Deserialize)]
+pub struct UpgradeSignalOp {
+    /// Transaction ID of this op
+    #[serde(serialize_with = "hex_serialize", deserialize_with = "hex_deserialize")]
+    pub txid: Txid,
+    /// Hash of the base chain block that produced this op.
+    #[serde(serialize_with = "hex_serialize", deserialize_with = "hex_deserialize")]
+    pub burn_header_hash: BurnchainHeaderHash,
+
+    /// The first burn block height whose Stacks blocks must be produced by `required_version`
+    pub target_height: u64,
+    /// The subnet node version required from `target_height` on, e.g. `0.5.0`
+    pub required_version: String,
+}
+
 #[derive(Debug, PartialEq, Clone, Eq, Serialize, Deserialize)]
 pub struct LeaderKeyRegisterOp {
     pub consensus_hash: ConsensusHash, // consensus hash at time of issuance
@@ -471,9 +549,12 @@ pub enum BlockstackOperationType {
     DepositStx(DepositStxOp),
     DepositFt(DepositFtOp),
     DepositNft(DepositNftOp),
+    DepositSft(DepositSftOp),
     WithdrawStx(WithdrawStxOp),
     WithdrawFt(WithdrawFtOp),
     WithdrawNft(WithdrawNftOp),
+    SetBlockLimit(SetBlockLimitOp),
+    UpgradeSignal(UpgradeSignalOp),
 }
 
 impl From<LeaderBlockCommitOp> for BlockstackOperationType {
@@ -506,6 +587,12 @@ impl From<DepositNftOp> for BlockstackOperationType {
     }
 }
 
+impl From<DepositSftOp> for BlockstackOperationType {
+    fn from(op: DepositSftOp) -> Self {
+        BlockstackOperationType::DepositSft(op)
+    }
+}
+
 impl From<WithdrawStxOp> for BlockstackOperationType {
     fn from(op: WithdrawStxOp) -> Self {
         BlockstackOperationType::WithdrawStx(op)
@@ -524,6 +611,18 @@ impl From<WithdrawNftOp> for BlockstackOperationType {
     }
 }
 
+impl From<SetBlockLimitOp> for BlockstackOperationType {
+    fn from(op: SetBlockLimitOp) -> Self {
+        BlockstackOperationType::SetBlockLimit(op)
+    }
+}
+
+impl From<UpgradeSignalOp> for BlockstackOperationType {
+    fn from(op: UpgradeSignalOp) -> Self {
+        BlockstackOperationType::UpgradeSignal(op)
+    }
+}
+
 impl BlockstackOperationType {
     pub fn txid(&self) -> Txid {
         self.txid_ref().clone()
@@ -536,9 +635,12 @@ impl BlockstackOperationType {
             BlockstackOperationType::DepositStx(ref data) => &data.txid,
             BlockstackOperationType::DepositFt(ref data) => &data.txid,
             BlockstackOperationType::DepositNft(ref data) => &data.txid,
+            BlockstackOperationType::DepositSft(ref data) => &data.txid,
             BlockstackOperationType::WithdrawStx(ref data) => &data.txid,
             BlockstackOperationType::WithdrawFt(ref data) => &data.txid,
             BlockstackOperationType::WithdrawNft(ref data) => &data.txid,
+            BlockstackOperationType::SetBlockLimit(ref data) => &data.txid,
+            BlockstackOperationType::UpgradeSignal(ref data) => &data.txid,
         }
     }
 
@@ -557,9 +659,12 @@ impl BlockstackOperationType {
             BlockstackOperationType::DepositStx(ref data) => data.burn_header_hash.clone(),
             BlockstackOperationType::DepositFt(ref data) => data.burn_header_hash.clone(),
             BlockstackOperationType::DepositNft(ref data) => data.burn_header_hash.clone(),
+            BlockstackOperationType::DepositSft(ref data) => data.burn_header_hash.clone(),
             BlockstackOperationType::WithdrawStx(ref data) => data.burn_header_hash.clone(),
             BlockstackOperationType::WithdrawFt(ref data) => data.burn_header_hash.clone(),
             BlockstackOperationType::WithdrawNft(ref data) => data.burn_header_hash.clone(),
+            BlockstackOperationType::SetBlockLimit(ref data) => data.burn_header_hash.clone(),
+            BlockstackOperationType::UpgradeSignal(ref data) => data.burn_header_hash.clone(),
         }
     }
 
@@ -573,9 +678,12 @@ impl BlockstackOperationType {
             BlockstackOperationType::DepositStx(ref mut data) => data.set_burn_height(height),
             BlockstackOperationType::DepositFt(ref mut data) => data.set_burn_height(height),
             BlockstackOperationType::DepositNft(ref mut data) => data.set_burn_height(height),
+            BlockstackOperationType::DepositSft(ref mut data) => data.set_burn_height(height),
             BlockstackOperationType::WithdrawStx(ref mut data) => data.set_burn_height(height),
             BlockstackOperationType::WithdrawFt(ref mut data) => data.set_burn_height(height),
             BlockstackOperationType::WithdrawNft(ref mut data) => data.set_burn_height(height),
+            BlockstackOperationType::SetBlockLimit(ref mut data) => data.set_burn_height(height),
+            BlockstackOperationType::UpgradeSignal(ref mut data) => data.set_burn_height(height),
         };
     }
 
@@ -589,9 +697,12 @@ impl BlockstackOperationType {
             BlockstackOperationType::DepositStx(ref mut data) => data.burn_header_hash = hash,
             BlockstackOperationType::DepositFt(ref mut data) => data.burn_header_hash = hash,
             BlockstackOperationType::DepositNft(ref mut data) => data.burn_header_hash = hash,
+            BlockstackOperationType::DepositSft(ref mut data) => data.burn_header_hash = hash,
             BlockstackOperationType::WithdrawStx(ref mut data) => data.burn_header_hash = hash,
             BlockstackOperationType::WithdrawFt(ref mut data) => data.burn_header_hash = hash,
             BlockstackOperationType::WithdrawNft(ref mut data) => data.burn_header_hash = hash,
+            BlockstackOperationType::SetBlockLimit(ref mut data) => data.burn_header_hash = hash,
+            BlockstackOperationType::UpgradeSignal(ref mut data) => data.burn_header_hash = hash,
         };
     }
 
@@ -609,9 +720,12 @@ impl fmt::Display for BlockstackOperationType {
             BlockstackOperationType::DepositStx(ref op) => write!(f, "{:?}", op),
             BlockstackOperationType::DepositFt(ref op) => write!(f, "{:?}", op),
             BlockstackOperationType::DepositNft(ref op) => write!(f, "{:?}", op),
+            BlockstackOperationType::DepositSft(ref op) => write!(f, "{:?}", op),
             BlockstackOperationType::WithdrawStx(ref op) => write!(f, "{:?}", op),
             BlockstackOperationType::WithdrawFt(ref op) => write!(f, "{:?}", op),
             BlockstackOperationType::WithdrawNft(ref op) => write!(f, "{:?}", op),
+            BlockstackOperationType::SetBlockLimit(ref op) => write!(f, "{:?}", op),
+            BlockstackOperationType::UpgradeSignal(ref op) => write!(f, "{:?}", op),
         }
     }
 }
@@ -712,6 +826,7 @@ mod json_tests {
             .unwrap(),
             sender: PrincipalData::parse("SP000000000000000000002Q6VF78.bns").unwrap(),
             id: 123123,
+            token_uri: None,
         }
         .into();
 
@@ -733,6 +848,78 @@ mod json_tests {
         );
     }
 
+    #[test]
+    fn deposit_nft_with_metadata() {
+        let deposit_nft = DepositNftOp {
+            txid: Txid([0xf1; 32]),
+            burn_header_hash: BurnchainHeaderHash([0xcc; 32]),
+            l1_contract_id: QualifiedContractIdentifier::parse("SP000000000000000000002Q6VF78.bns")
+                .unwrap(),
+            subnet_contract_id: QualifiedContractIdentifier::parse(
+                "SP000000000000000000002Q6VF78.bns",
+            )
+            .unwrap(),
+            sender: PrincipalData::parse("SP000000000000000000002Q6VF78.bns").unwrap(),
+            id: 123123,
+            token_uri: Some("ipfs://token/123123".to_string()),
+        }
+        .into();
+
+        let expected = r#"
+        {
+          "deposit_nft": {
+            "burn_header_hash": "cccccccccccccccccccccccccccccccccccccccccccccccccccccccccccccccc",
+            "id": 123123,
+            "l1_contract_id": "SP000000000000000000002Q6VF78.bns",
+            "sender": "SP000000000000000000002Q6VF78.bns",
+            "subnet_contract_id": "SP000000000000000000002Q6VF78.bns",
+            "token_uri": "ipfs://token/123123",
+            "txid": "f1f1f1f1f1f1f1f1f1f1f1f1f1f1f1f1f1f1f1f1f1f1f1f1f1f1f1f1f1f1f1f1"
+          }
+        }"#;
+
+        assert_eq!(
+            BlockstackOperationType::blockstack_op_to_json(&deposit_nft),
+            serde_json::from_str::<serde_json::Value>(expected).unwrap()
+        );
+    }
+
+    #[test]
+    fn deposit_sft() {
+        let deposit_sft = DepositSftOp {
+            txid: Txid([0xf2; 32]),
+            burn_header_hash: BurnchainHeaderHash([0xcc; 32]),
+            l1_contract_id: QualifiedContractIdentifier::parse("SP000000000000000000002Q6VF78.bns")
+                .unwrap(),
+            subnet_contract_id: QualifiedContractIdentifier::parse(
+                "SP000000000000000000002Q6VF78.bns",
+            )
+            .unwrap(),
+            sender: PrincipalData::parse("SP000000000000000000002Q6VF78.bns").unwrap(),
+            id: 123123,
+            amount: 42,
+        }
+        .into();
+
+        let expected = r#"
+        {
+          "deposit_sft": {
+            "amount": 42,
+            "burn_header_hash": "cccccccccccccccccccccccccccccccccccccccccccccccccccccccccccccccc",
+            "id": 123123,
+            "l1_contract_id": "SP000000000000000000002Q6VF78.bns",
+            "sender": "SP000000000000000000002Q6VF78.bns",
+            "subnet_contract_id": "SP000000000000000000002Q6VF78.bns",
+            "txid": "f2f2f2f2f2f2f2f2f2f2f2f2f2f2f2f2f2f2f2f2f2f2f2f2f2f2f2f2f2f2f2f2"
+          }
+        }"#;
+
+        assert_eq!(
+            BlockstackOperationType::blockstack_op_to_json(&deposit_sft),
+            serde_json::from_str::<serde_json::Value>(expected).unwrap()
+        );
+    }
+
     #[test]
     fn deposit_stx() {
         let deposit_stx = DepositStxOp {
@@ -0,0 +1,48 @@
+use crate::burnchains::{Burnchain, StacksSubnetOp, StacksSubnetOpType};
+use crate::chainstate::burn::db::sortdb::{SortitionDB, SortitionHandleTx};
+use crate::chainstate::burn::operations::Error as op_error;
+use crate::chainstate::burn::operations::SetBlockLimitOp;
+use clarity::types::chainstate::BurnchainHeaderHash;
+use std::convert::TryFrom;
+
+impl TryFrom<&StacksSubnetOp> for SetBlockLimitOp {
+    type Error = op_error;
+
+    fn try_from(value: &StacksSubnetOp) -> Result<Self, Self::Error> {
+        if let StacksSubnetOpType::SetBlockLimit {
+            ref activation_height,
+            ref block_limit,
+        } = value.event
+        {
+            Ok(SetBlockLimitOp {
+                txid: value.txid.clone(),
+                // use the StacksBlockId in the L1 event as the burnchain header hash
+                burn_header_hash: BurnchainHeaderHash(value.in_block.0.clone()),
+                activation_height: *activation_height,
+                block_limit: block_limit.clone(),
+            })
+        } else {
+            Err(op_error::InvalidInput)
+        }
+    }
+}
+
+impl SetBlockLimitOp {
+    pub fn check(
+        &self,
+        _burnchain: &Burnchain,
+        tx: &mut SortitionHandleTx,
+    ) -> Result<(), op_error> {
+        // the L1 contract only accepts activation heights after the block the update is made
+        //  in, so that the limit never changes for blocks that were already processed.
+        let parent_snapshot = SortitionDB::get_block_snapshot(tx.tx(), &tx.context.chain_tip)?
+            .ok_or(op_error::InvalidInput)?;
+        if self.activation_height <= parent_snapshot.block_height + 1 {
+            return Err(op_error::SetBlockLimitActivationPassed);
+        }
+        Ok(())
+    }
+
+    #[cfg(test)]
+    pub fn set_burn_height(&mut self, _height: u64) {}
+}
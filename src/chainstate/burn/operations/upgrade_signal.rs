@@ -0,0 +1,107 @@
+use crate::burnchains::{Burnchain, StacksSubnetOp, StacksSubnetOpType};
+use crate::chainstate::burn::db::sortdb::{SortitionDB, SortitionHandleTx};
+use crate::chainstate::burn::operations::Error as op_error;
+use crate::chainstate::burn::operations::UpgradeSignalOp;
+use clarity::types::chainstate::BurnchainHeaderHash;
+use std::cmp::Ordering;
+use std::convert::TryFrom;
+
+impl TryFrom<&StacksSubnetOp> for UpgradeSignalOp {
+    type Error = op_error;
+
+    fn try_from(value: &StacksSubnetOp) -> Result<Self, Self::Error> {
+        if let StacksSubnetOpType::Upgrade {
+            ref target_height,
+            ref required_version,
+        } = value.event
+        {
+            Ok(UpgradeSignalOp {
+                txid: value.txid.clone(),
+                // use the StacksBlockId in the L1 event as the burnchain header hash
+                burn_header_hash: BurnchainHeaderHash(value.in_block.0.clone()),
+                target_height: *target_height,
+                required_version: required_version.clone(),
+            })
+        } else {
+            Err(op_error::InvalidInput)
+        }
+    }
+}
+
+impl UpgradeSignalOp {
+    pub fn check(
+        &self,
+        _burnchain: &Burnchain,
+        tx: &mut SortitionHandleTx,
+    ) -> Result<(), op_error> {
+        // the L1 contract only accepts target heights after the block the signal is made in,
+        //  so that nodes always have a chance to see the signal before it takes effect.
+        let parent_snapshot = SortitionDB::get_block_snapshot(tx.tx(), &tx.context.chain_tip)?
+            .ok_or(op_error::InvalidInput)?;
+        if self.target_height <= parent_snapshot.block_height + 1 {
+            return Err(op_error::UpgradeSignalTargetPassed);
+        }
+        Ok(())
+    }
+
+    #[cfg(test)]
+    pub fn set_burn_height(&mut self, _height: u64) {}
+}
+
+/// Parse the leading dotted numeric components of a version, e.g. `0.5.0` or `2.05.0.1-rc1`.
+/// Returns None if it doesn't start with a number.
+fn parse_version(version: &str) -> Option<Vec<u64>> {
+    let mut components = vec![];
+    for part in version.trim().split('.') {
+        let digits: String = part.chars().take_while(|c| c.is_ascii_digit()).collect();
+        if digits.is_empty() {
+            break;
+        }
+        components.push(digits.parse::<u64>().ok()?);
+        if digits.len() < part.len() {
+            // a suffix, like `-rc1`, ends the version number
+            break;
+        }
+    }
+    if components.is_empty() {
+        None
+    } else {
+        Some(components)
+    }
+}
+
+/// Is the node version `running` at least `required`?  Missing components count as 0, so
+/// `0.5` meets `0.5.0`.  Returns None if either isn't a version number.
+pub fn version_meets(running: &str, required: &str) -> Option<bool> {
+    let running = parse_version(running)?;
+    let required = parse_version(required)?;
+    let len = running.len().max(required.len());
+    for i in 0..len {
+        let a = running.get(i).cloned().unwrap_or(0);
+        let b = required.get(i).cloned().unwrap_or(0);
+        match a.cmp(&b) {
+            Ordering::Greater => return Some(true),
+            Ordering::Less => return Some(false),
+            Ordering::Equal => {}
+        }
+    }
+    Some(true)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_version_meets() {
+        assert_eq!(version_meets("0.5.0", "0.5.0"), Some(true));
+        assert_eq!(version_meets("0.5", "0.5.0"), Some(true));
+        assert_eq!(version_meets("0.5.1", "0.5.0"), Some(true));
+        assert_eq!(version_meets("0.10.0", "0.9.9"), Some(true));
+        assert_eq!(version_meets("1.0.0-rc1", "0.9"), Some(true));
+        assert_eq!(version_meets("0.4.9", "0.5.0"), Some(false));
+        assert_eq!(version_meets("0.5.0", "0.5.0.1"), Some(false));
+        assert_eq!(version_meets("0.5.0", "latest"), None);
+        assert_eq!(version_meets("", "0.5.0"), None);
+    }
+}
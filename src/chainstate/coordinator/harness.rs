@@ -0,0 +1,344 @@
+// Copyright (C) 2013-2020 Blockstack PBC, a public benefit corporation
+// Copyright (C) 2020 Stacks Open Internet Foundation
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! An in-process harness that runs several subnet nodes against one simulated burnchain.  Each
+//! node has its own coordinator, chainstate, and mempool, and only sees the burnchain blocks and
+//! Stacks blocks that are explicitly delivered to it, so tests can partition the nodes, let them
+//! fork, and heal the partition again.
+
+use std::collections::HashMap;
+use std::fs;
+
+use crate::burnchains::db::BurnchainBlockData;
+use crate::burnchains::BurnchainBlockHeader;
+use crate::chainstate::burn::db::sortdb::SortitionDB;
+use crate::chainstate::burn::operations::BlockstackOperationType;
+use crate::chainstate::coordinator::tests::{
+    eval_at_chain_tip, get_chainstate_path_str, make_coordinator, make_genesis_block,
+    make_stacks_block, next_burn_header_hash, preprocess_block, setup_states, NullEventDispatcher,
+};
+use crate::chainstate::coordinator::{ChainsCoordinator, OnChainRewardSetProvider};
+use crate::chainstate::stacks::{StacksBlock, StacksPrivateKey, StacksTransaction};
+use crate::core::mempool::{MemPoolDB, MemPoolRejection};
+use crate::core::FIRST_STACKS_BLOCK_HASH;
+use clarity::vm::types::PrincipalData;
+use clarity::vm::Value;
+use stacks_common::types::chainstate::{
+    BlockHeaderHash, BurnchainHeaderHash, ConsensusHash, SortitionId,
+};
+use stacks_common::types::StacksEpochId;
+use stacks_common::util::vrf::VRFPrivateKey;
+
+pub type TestCoordinator =
+    ChainsCoordinator<'static, NullEventDispatcher, (), OnChainRewardSetProvider, (), ()>;
+
+/// One subnet node in a `SubnetTestHarness`
+pub struct SubnetTestNode {
+    pub path: String,
+    pub coord: TestCoordinator,
+    pub mempool: MemPoolDB,
+}
+
+pub struct SubnetTestHarness {
+    pub nodes: Vec<SubnetTestNode>,
+    /// Every burnchain block produced so far, whether or not it was delivered to any node
+    burn_blocks: HashMap<BurnchainHeaderHash, BurnchainBlockData>,
+    /// The burnchain blocks carrying a commit to each Stacks block.  A block can be committed to
+    /// on more than one burnchain fork.
+    commits: HashMap<BlockHeaderHash, Vec<BurnchainHeaderHash>>,
+}
+
+impl SubnetTestHarness {
+    /// Instantiate `num_nodes` nodes with fresh state under
+    /// `/tmp/stacks-node-tests/unit-tests/<name>.<node index>`.  All nodes start out having seen
+    /// the same first burnchain block.
+    pub fn new(
+        name: &str,
+        num_nodes: usize,
+        initial_balances: Option<Vec<(PrincipalData, u64)>>,
+    ) -> SubnetTestHarness {
+        let paths: Vec<_> = (0..num_nodes)
+            .map(|ix| format!("/tmp/stacks-node-tests/unit-tests/{}.{}", name, ix))
+            .collect();
+        for path in paths.iter() {
+            let _r = fs::remove_dir_all(path);
+        }
+        let path_refs: Vec<_> = paths.iter().map(|path| path.as_str()).collect();
+        setup_states(
+            &path_refs,
+            &[],
+            &[],
+            None,
+            initial_balances,
+            StacksEpochId::Epoch21,
+        );
+
+        let nodes: Vec<_> = paths
+            .into_iter()
+            .map(|path| {
+                let mut coord = make_coordinator(&path, None);
+                coord.handle_new_burnchain_block().unwrap();
+                let mempool =
+                    MemPoolDB::open_test(false, 0x80000000, &get_chainstate_path_str(&path))
+                        .unwrap();
+                SubnetTestNode {
+                    path,
+                    coord,
+                    mempool,
+                }
+            })
+            .collect();
+
+        let mut harness = SubnetTestHarness {
+            nodes,
+            burn_blocks: HashMap::new(),
+            commits: HashMap::new(),
+        };
+
+        // remember the burnchain history every node starts out with
+        let mut burn_block = harness.burn_tip(0).block_hash;
+        while let Ok(block_data) = harness.nodes[0]
+            .coord
+            .burnchain_blocks_db
+            .get_burnchain_block(&burn_block)
+        {
+            let parent = block_data.header.parent_block_hash.clone();
+            harness.burn_blocks.insert(burn_block.clone(), block_data);
+            if parent == burn_block {
+                break;
+            }
+            burn_block = parent;
+        }
+        harness
+    }
+
+    /// The canonical burnchain tip that `node` has seen
+    pub fn burn_tip(&self, node: usize) -> BurnchainBlockHeader {
+        self.nodes[node]
+            .coord
+            .burnchain_blocks_db
+            .get_canonical_chain_tip()
+            .unwrap()
+    }
+
+    /// The canonical Stacks chain tip of `node`
+    pub fn stacks_tip(&self, node: usize) -> (ConsensusHash, BlockHeaderHash) {
+        SortitionDB::get_canonical_stacks_chain_tip_hash(self.nodes[node].coord.sortition_db.conn())
+            .unwrap()
+    }
+
+    /// Produce a burnchain block with `ops` on top of `parent`, without delivering it to any
+    /// node.
+    pub fn produce_burn_block(
+        &mut self,
+        parent: &BurnchainHeaderHash,
+        mut ops: Vec<BlockstackOperationType>,
+    ) -> BurnchainHeaderHash {
+        let parent_header = self
+            .burn_blocks
+            .get(parent)
+            .expect("BUG: parent burnchain block was never produced")
+            .header
+            .clone();
+        let block_hash = next_burn_header_hash();
+        let header = BurnchainBlockHeader {
+            block_height: parent_header.block_height + 1,
+            timestamp: parent_header.timestamp + 1,
+            num_txs: ops.len() as u64,
+            block_hash: block_hash.clone(),
+            parent_block_hash: parent.clone(),
+        };
+
+        for op in ops.iter_mut() {
+            op.set_block_height(header.block_height);
+            op.set_burn_header_hash(block_hash.clone());
+            if let BlockstackOperationType::LeaderBlockCommit(commit) = op {
+                self.commits
+                    .entry(commit.block_header_hash.clone())
+                    .or_insert_with(Vec::new)
+                    .push(block_hash.clone());
+            }
+        }
+
+        self.burn_blocks
+            .insert(block_hash.clone(), BurnchainBlockData { header, ops });
+        block_hash
+    }
+
+    /// Deliver the burnchain block `burn_block` to `node`, along with any of its ancestors that
+    /// the node hasn't seen yet, and process the resulting sortitions.
+    pub fn deliver_burn_block(&mut self, node: usize, burn_block: &BurnchainHeaderHash) {
+        let coord = &mut self.nodes[node].coord;
+
+        let mut missing = vec![];
+        let mut cursor = burn_block.clone();
+        while coord
+            .burnchain_blocks_db
+            .get_burnchain_block(&cursor)
+            .is_err()
+        {
+            let block_data = self
+                .burn_blocks
+                .get(&cursor)
+                .expect("BUG: burnchain block was never produced");
+            cursor = block_data.header.parent_block_hash.clone();
+            missing.push(block_data);
+        }
+
+        for block_data in missing.into_iter().rev() {
+            coord
+                .burnchain_blocks_db
+                .raw_store_burnchain_block(block_data.header.clone(), block_data.ops.clone())
+                .unwrap();
+        }
+        coord.handle_new_burnchain_block().unwrap();
+    }
+
+    /// Produce a burnchain block with `ops` on top of `parent`, and deliver it to each of
+    /// `nodes`.
+    pub fn produce_and_deliver_burn_block(
+        &mut self,
+        parent: &BurnchainHeaderHash,
+        ops: Vec<BlockstackOperationType>,
+        nodes: &[usize],
+    ) -> BurnchainHeaderHash {
+        let burn_block = self.produce_burn_block(parent, ops);
+        for node in nodes.iter() {
+            self.deliver_burn_block(*node, &burn_block);
+        }
+        burn_block
+    }
+
+    /// Mine a Stacks block with just a coinbase on top of `node`'s canonical Stacks chain tip.
+    /// Returns the block, and the commit to put in a burnchain block so it can be processed.
+    pub fn mine_stacks_block(
+        &mut self,
+        node: usize,
+        miner: &StacksPrivateKey,
+    ) -> (BlockstackOperationType, StacksBlock) {
+        let (_, parent) = self.stacks_tip(node);
+        let burn_tip_height = self.burn_tip(node).block_height;
+        let vrf_key = VRFPrivateKey::new();
+        let coord = &mut self.nodes[node].coord;
+        if parent == FIRST_STACKS_BLOCK_HASH {
+            make_genesis_block(
+                &coord.sortition_db,
+                &mut coord.chain_state_db,
+                &parent,
+                miner,
+                10000,
+                &vrf_key,
+                0,
+            )
+        } else {
+            make_stacks_block(
+                &coord.sortition_db,
+                &mut coord.chain_state_db,
+                &coord.burnchain,
+                &parent,
+                burn_tip_height,
+                miner,
+                10000,
+                &vrf_key,
+                0,
+            )
+        }
+    }
+
+    /// Deliver the Stacks block `block` to `node`, and process it.  The node must already have
+    /// processed a burnchain block committing to it.
+    pub fn deliver_stacks_block(&mut self, node: usize, block: &StacksBlock) {
+        let coord = &mut self.nodes[node].coord;
+        let commit_burn_blocks = self
+            .commits
+            .get(&block.block_hash())
+            .expect("BUG: Stacks block was never committed to");
+
+        let mut preprocessed = false;
+        for burn_block in commit_burn_blocks.iter() {
+            let sortition_id = SortitionId(burn_block.0.clone());
+            let sortition =
+                match SortitionDB::get_block_snapshot(coord.sortition_db.conn(), &sortition_id)
+                    .unwrap()
+                {
+                    Some(sortition) => sortition,
+                    None => continue,
+                };
+            preprocess_block(
+                &mut coord.chain_state_db,
+                &coord.sortition_db,
+                &sortition,
+                block.clone(),
+            );
+            preprocessed = true;
+        }
+        assert!(
+            preprocessed,
+            "Node {} has not processed a commit to Stacks block {}",
+            node,
+            &block.block_hash()
+        );
+        coord.handle_new_stacks_block().unwrap();
+    }
+
+    /// Mine a Stacks block on `miner_node`, commit to it in a burnchain block on top of
+    /// `miner_node`'s burnchain tip, and deliver both blocks to each of `nodes`.  Returns the
+    /// burnchain block and the Stacks block.
+    pub fn mine_and_deliver(
+        &mut self,
+        miner_node: usize,
+        miner: &StacksPrivateKey,
+        nodes: &[usize],
+    ) -> (BurnchainHeaderHash, StacksBlock) {
+        let (commit, block) = self.mine_stacks_block(miner_node, miner);
+        let parent = self.burn_tip(miner_node).block_hash;
+        let burn_block = self.produce_and_deliver_burn_block(&parent, vec![commit], nodes);
+        for node in nodes.iter() {
+            self.deliver_stacks_block(*node, &block);
+        }
+        (burn_block, block)
+    }
+
+    /// Submit `tx` to `node`'s mempool, checked against its canonical Stacks chain tip.
+    pub fn submit_tx(
+        &mut self,
+        node: usize,
+        tx: &StacksTransaction,
+    ) -> Result<(), MemPoolRejection> {
+        let (consensus_hash, block_hash) = self.stacks_tip(node);
+        let burn_tip_height = self.burn_tip(node).block_height;
+        let node = &mut self.nodes[node];
+        let stacks_epoch =
+            SortitionDB::get_stacks_epoch(node.coord.sortition_db.conn(), burn_tip_height)
+                .unwrap()
+                .expect("BUG: no epoch for the burnchain tip");
+        node.mempool.submit(
+            &mut node.coord.chain_state_db,
+            &consensus_hash,
+            &block_hash,
+            tx,
+            None,
+            &stacks_epoch.block_limit,
+            &stacks_epoch.epoch_id,
+        )
+    }
+
+    /// Evaluate `expr` at `node`'s canonical Stacks chain tip
+    pub fn eval_at_chain_tip(&self, node: usize, expr: &str) -> Value {
+        let node = &self.nodes[node];
+        eval_at_chain_tip(&node.path, &node.coord.sortition_db, expr)
+    }
+}
@@ -0,0 +1,158 @@
+// Copyright (C) 2013-2020 Blockstack PBC, a public benefit corporation
+// Copyright (C) 2020 Stacks Open Internet Foundation
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! A write-ahead journal of the coordinator's progress through each burnchain block.
+//!
+//! Processing a burnchain block commits to two databases in turn: the sortition DB (when the
+//! sortition is evaluated) and then the chainstate (when the Stacks blocks it makes ready are
+//! processed).  The coordinator records each step here before and after taking it, so that if
+//! the node dies between the two commits, it can tell on restart which burnchain blocks were left
+//! half-processed and finish them.
+
+use rusqlite::{Connection, OpenFlags, Row, NO_PARAMS};
+
+use crate::burnchains::BurnchainBlockHeader;
+use crate::util_lib::db::{
+    query_rows, sqlite_open, table_exists, tx_begin_immediate, u64_to_sql, Error as DBError,
+    FromColumn, FromRow,
+};
+use stacks_common::types::chainstate::BurnchainHeaderHash;
+
+const JOURNAL_SCHEMA: &'static str = "
+CREATE TABLE coordinator_journal (
+    burn_header_hash TEXT PRIMARY KEY,
+    burn_block_height INTEGER NOT NULL,
+    step INTEGER NOT NULL
+)";
+
+/// How far the coordinator got in processing a burnchain block
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JournalStep {
+    /// The sortition is being evaluated, and may or may not have been committed
+    SortitionPending = 0,
+    /// The sortition was committed, but the Stacks blocks it made ready may not have been
+    /// processed yet
+    SortitionProcessed = 1,
+}
+
+impl JournalStep {
+    fn from_u8(step: u8) -> Option<JournalStep> {
+        match step {
+            0 => Some(JournalStep::SortitionPending),
+            1 => Some(JournalStep::SortitionProcessed),
+            _ => None,
+        }
+    }
+}
+
+/// A burnchain block the coordinator started processing but hasn't finished
+#[derive(Debug, Clone, PartialEq)]
+pub struct JournalEntry {
+    pub burn_header_hash: BurnchainHeaderHash,
+    pub burn_block_height: u64,
+    pub step: JournalStep,
+}
+
+impl FromRow<JournalEntry> for JournalEntry {
+    fn from_row<'a>(row: &'a Row) -> Result<JournalEntry, DBError> {
+        let burn_header_hash = BurnchainHeaderHash::from_column(row, "burn_header_hash")?;
+        let burn_block_height = u64::from_column(row, "burn_block_height")?;
+        let step: u8 = row.get_unwrap("step");
+        let step = JournalStep::from_u8(step).ok_or(DBError::ParseError)?;
+        Ok(JournalEntry {
+            burn_header_hash,
+            burn_block_height,
+            step,
+        })
+    }
+}
+
+pub struct CoordinatorJournal {
+    conn: Connection,
+}
+
+impl CoordinatorJournal {
+    /// Open the journal at `path`, creating it if it doesn't exist.
+    pub fn open(path: &str) -> Result<CoordinatorJournal, DBError> {
+        let mut conn = sqlite_open(
+            path,
+            OpenFlags::SQLITE_OPEN_CREATE | OpenFlags::SQLITE_OPEN_READ_WRITE,
+            false,
+        )?;
+
+        let tx = tx_begin_immediate(&mut conn)?;
+        if !table_exists(&tx, "coordinator_journal")? {
+            tx.execute(JOURNAL_SCHEMA, NO_PARAMS)?;
+        }
+        tx.commit()?;
+
+        Ok(CoordinatorJournal { conn })
+    }
+
+    fn set_step(
+        &mut self,
+        burn_header_hash: &BurnchainHeaderHash,
+        burn_block_height: u64,
+        step: JournalStep,
+    ) -> Result<(), DBError> {
+        let args: &[&dyn rusqlite::ToSql] = &[
+            burn_header_hash,
+            &u64_to_sql(burn_block_height)?,
+            &(step as u8),
+        ];
+        self.conn.execute(
+            "INSERT OR REPLACE INTO coordinator_journal (burn_header_hash, burn_block_height, step) VALUES (?1, ?2, ?3)",
+            args,
+        )?;
+        Ok(())
+    }
+
+    /// Record that the sortition for `header` is about to be evaluated.
+    pub fn begin_sortition(&mut self, header: &BurnchainBlockHeader) -> Result<(), DBError> {
+        self.set_step(
+            &header.block_hash,
+            header.block_height,
+            JournalStep::SortitionPending,
+        )
+    }
+
+    /// Record that the sortition for `header` was committed.
+    pub fn sortition_processed(&mut self, header: &BurnchainBlockHeader) -> Result<(), DBError> {
+        self.set_step(
+            &header.block_hash,
+            header.block_height,
+            JournalStep::SortitionProcessed,
+        )
+    }
+
+    /// Record that the burnchain block `burn_header_hash` was fully processed.
+    pub fn finish(&mut self, burn_header_hash: &BurnchainHeaderHash) -> Result<(), DBError> {
+        self.conn.execute(
+            "DELETE FROM coordinator_journal WHERE burn_header_hash = ?1",
+            &[burn_header_hash],
+        )?;
+        Ok(())
+    }
+
+    /// The burnchain blocks whose processing was started but never finished, lowest first.
+    pub fn get_interrupted(&self) -> Result<Vec<JournalEntry>, DBError> {
+        query_rows(
+            &self.conn,
+            "SELECT * FROM coordinator_journal ORDER BY burn_block_height ASC",
+            NO_PARAMS,
+        )
+    }
+}
@@ -35,8 +35,8 @@ use crate::chainstate::coordinator::comm::{
 use crate::chainstate::stacks::index::MarfTrieId;
 use crate::chainstate::stacks::{
     db::{
-        accounts::MinerReward, ChainStateBootData, ClarityTx, MinerRewardInfo, StacksChainState,
-        StacksHeaderInfo,
+        accounts::MinerReward, withdrawal_policy::QueuedWithdrawal, ChainStateBootData, ClarityTx,
+        MinerRewardInfo, StacksChainState, StacksHeaderInfo,
     },
     events::{StacksTransactionEvent, StacksTransactionReceipt, TransactionOrigin},
     Error as ChainstateError, StacksBlock, TransactionPayload,
@@ -62,10 +62,15 @@ use clarity::vm::database::BurnStateDB;
 use crate::chainstate::stacks::index::marf::MARFOpenOpts;
 
 pub use self::comm::CoordinatorCommunication;
+use self::journal::{CoordinatorJournal, JournalStep};
 
 pub mod comm;
 #[cfg(test)]
+pub mod harness;
+pub mod journal;
+#[cfg(test)]
 pub mod tests;
+pub mod trace;
 
 /// The 3 different states for the current
 ///  reward cycle's relationship to its PoX anchor
@@ -129,6 +134,8 @@ pub trait BlockEventDispatcher {
         parent_burn_block_timestamp: u64,
         anchored_consumed: &ExecutionCost,
         mblock_confirmed_consumed: &ExecutionCost,
+        withdrawals_queued: &Vec<QueuedWithdrawal>,
+        withdrawals_released: &Vec<QueuedWithdrawal>,
     );
 
     /// called whenever a burn block is about to be
@@ -144,6 +151,15 @@ pub trait BlockEventDispatcher {
         reward_recipients: Vec<StacksAddress>,
     );
 
+    /// called whenever a block is found to be invalid and will never be processed, with the
+    ///  reason why
+    fn announce_rejected_block(
+        &self,
+        consensus_hash: &ConsensusHash,
+        block_hash: &BlockHeaderHash,
+        reason: &str,
+    );
+
     fn dispatch_boot_receipts(&mut self, receipts: Vec<StacksTransactionReceipt>);
 }
 
@@ -168,6 +184,7 @@ pub struct ChainsCoordinator<
     reward_set_provider: R,
     notifier: N,
     atlas_config: AtlasConfig,
+    journal: CoordinatorJournal,
 }
 
 #[derive(Debug)]
@@ -252,6 +269,7 @@ impl<'a, T: BlockEventDispatcher, CE: CostEstimator + ?Sized, FE: FeeEstimator +
 
         let canonical_sortition_tip =
             SortitionDB::get_canonical_sortition_tip(sortition_db.conn()).unwrap();
+        let journal = CoordinatorJournal::open(&burnchain.get_coordinator_journal_path()).unwrap();
 
         let arc_notices = ArcCounterCoordinatorNotices {
             stacks_blocks_processed,
@@ -272,8 +290,13 @@ impl<'a, T: BlockEventDispatcher, CE: CostEstimator + ?Sized, FE: FeeEstimator +
             cost_estimator,
             fee_estimator,
             atlas_config,
+            journal,
         };
 
+        if let Err(e) = inst.recover_interrupted_burn_blocks() {
+            warn!("Error recovering interrupted burn blocks: {:?}", e);
+        }
+
         loop {
             // timeout so that we handle Ctrl-C a little gracefully
             match comms.wait_on() {
@@ -344,6 +367,7 @@ impl<'a, T: BlockEventDispatcher, U: RewardSetProvider> ChainsCoordinator<'a, T,
         .unwrap();
         let canonical_sortition_tip =
             SortitionDB::get_canonical_sortition_tip(sortition_db.conn()).unwrap();
+        let journal = CoordinatorJournal::open(&burnchain.get_coordinator_journal_path()).unwrap();
 
         ChainsCoordinator {
             canonical_chain_tip: None,
@@ -359,6 +383,7 @@ impl<'a, T: BlockEventDispatcher, U: RewardSetProvider> ChainsCoordinator<'a, T,
             notifier: (),
             attachments_tx,
             atlas_config: AtlasConfig::default(false),
+            journal,
         }
     }
 }
@@ -402,12 +427,12 @@ pub fn get_reward_cycle_info<U: RewardSetProvider>(
     Ok(None)
 }
 
-struct PaidRewards {
+pub(crate) struct PaidRewards {
     pox: Vec<(StacksAddress, u64)>,
-    burns: u64,
+    pub(crate) burns: u64,
 }
 
-fn calculate_paid_rewards(_ops: &[BlockstackOperationType]) -> PaidRewards {
+pub(crate) fn calculate_paid_rewards(_ops: &[BlockstackOperationType]) -> PaidRewards {
     PaidRewards {
         pox: vec![],
         burns: 1,
@@ -528,6 +553,7 @@ impl<
             // at this point, we need to figure out if the sortition we are
             //  about to process is the first block in reward cycle.
             let reward_cycle_info = self.get_reward_cycle_info(&header)?;
+            self.journal.begin_sortition(&header)?;
             // bind a reference here to avoid tripping up the borrow-checker
             let dispatcher_ref = &self.dispatcher;
             let (next_snapshot, _) = self
@@ -555,6 +581,7 @@ impl<
                 })?;
 
             let sortition_id = next_snapshot.sortition_id;
+            self.journal.sortition_processed(&header)?;
 
             self.notifier.notify_sortition_processed();
 
@@ -571,7 +598,9 @@ impl<
             self.canonical_sortition_tip = Some(sortition_id.clone());
             last_processed_ancestor = sortition_id;
 
-            if let Some(pox_anchor) = self.process_ready_blocks()? {
+            let pox_anchor = self.process_ready_blocks()?;
+            self.journal.finish(&header.block_hash)?;
+            if let Some(pox_anchor) = pox_anchor {
                 return self.process_new_pox_anchor(pox_anchor);
             }
         }
@@ -579,6 +608,37 @@ impl<
         Ok(())
     }
 
+    /// Finish processing any burnchain blocks that the journal shows were interrupted, e.g. by
+    /// the node dying after committing a sortition but before processing the Stacks blocks it
+    /// made ready.  Sortitions that were never committed are evaluated again, and Stacks blocks
+    /// that were never processed are processed.  Entries for burnchain blocks that are no longer
+    /// on the canonical burnchain fork are just dropped.
+    pub fn recover_interrupted_burn_blocks(&mut self) -> Result<(), Error> {
+        let interrupted = self.journal.get_interrupted()?;
+        if interrupted.is_empty() {
+            return Ok(());
+        }
+
+        for entry in interrupted.iter() {
+            let step = match entry.step {
+                JournalStep::SortitionPending => "sortition",
+                JournalStep::SortitionProcessed => "stacks blocks",
+            };
+            info!("Recovering interrupted burn block";
+                  "burn_header_hash" => %entry.burn_header_hash,
+                  "burn_height" => entry.burn_block_height,
+                  "step" => step);
+        }
+
+        self.handle_new_burnchain_block()?;
+        self.handle_new_stacks_block()?;
+
+        for entry in interrupted.iter() {
+            self.journal.finish(&entry.burn_header_hash)?;
+        }
+        Ok(())
+    }
+
     /// returns None if this burnchain block is _not_ the start of a reward cycle
     ///         otherwise, returns the required reward cycle info for this burnchain block
     ///                     in our current sortition view:
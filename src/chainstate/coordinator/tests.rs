@@ -31,9 +31,11 @@ use crate::chainstate::burn::db::sortdb::SortitionDB;
 use crate::chainstate::burn::operations::leader_block_commit::*;
 use crate::chainstate::burn::operations::*;
 use crate::chainstate::burn::*;
+use crate::chainstate::coordinator::harness::SubnetTestHarness;
 use crate::chainstate::coordinator::{Error as CoordError, *};
 use crate::chainstate::stacks::db::{
-    accounts::MinerReward, ClarityTx, StacksChainState, StacksHeaderInfo,
+    accounts::MinerReward, withdrawal_policy::QueuedWithdrawal, ClarityTx, StacksChainState,
+    StacksHeaderInfo,
 };
 use crate::chainstate::stacks::*;
 use crate::clarity_vm::clarity::ClarityConnection;
@@ -152,7 +154,7 @@ fn produce_burn_block_do_not_set_height<'a, I: Iterator<Item = &'a mut Burnchain
     block_hash
 }
 
-fn p2pkh_from(sk: &StacksPrivateKey) -> StacksAddress {
+pub fn p2pkh_from(sk: &StacksPrivateKey) -> StacksAddress {
     let pk = StacksPublicKey::from_private(sk);
     StacksAddress::from_public_keys(
         chainstate::stacks::C32_ADDRESS_VERSION_TESTNET_SINGLESIG,
@@ -254,6 +256,8 @@ impl BlockEventDispatcher for NullEventDispatcher {
         _parent_burn_block_timestamp: u64,
         _anchor_block_cost: &ExecutionCost,
         _confirmed_mblock_cost: &ExecutionCost,
+        _withdrawals_queued: &Vec<QueuedWithdrawal>,
+        _withdrawals_released: &Vec<QueuedWithdrawal>,
     ) {
         assert!(
             false,
@@ -271,6 +275,14 @@ impl BlockEventDispatcher for NullEventDispatcher {
     ) {
     }
 
+    fn announce_rejected_block(
+        &self,
+        _consensus_hash: &ConsensusHash,
+        _block_hash: &BlockHeaderHash,
+        _reason: &str,
+    ) {
+    }
+
     fn dispatch_boot_receipts(&mut self, _receipts: Vec<StacksTransactionReceipt>) {}
 }
 
@@ -344,7 +356,7 @@ pub fn get_chainstate(path: &str) -> StacksChainState {
     chainstate
 }
 
-fn make_genesis_block(
+pub fn make_genesis_block(
     sort_db: &SortitionDB,
     state: &mut StacksChainState,
     parent_block: &BlockHeaderHash,
@@ -427,7 +439,7 @@ fn make_genesis_block_with_recipients(
     (BlockstackOperationType::LeaderBlockCommit(commit_op), block)
 }
 
-fn make_stacks_block(
+pub fn make_stacks_block(
     sort_db: &SortitionDB,
     state: &mut StacksChainState,
     burnchain: &Burnchain,
@@ -755,7 +767,213 @@ fn test_simple_setup() {
     }
 }
 
-fn eval_at_chain_tip(chainstate_path: &str, sort_db: &SortitionDB, eval: &str) -> Value {
+/// Two nodes agree on a chain, get partitioned and fork, and converge again once the partition
+/// heals.
+#[test]
+fn test_harness_partition_and_heal() {
+    let mut harness = SubnetTestHarness::new("stacks-blockchain-harness-partition", 2, None);
+    let miner = StacksPrivateKey::new();
+    let recipient = p2pkh_from(&StacksPrivateKey::new());
+    let balance_expr = format!("(stx-get-balance '{})", &recipient);
+
+    // both nodes see the first two blocks
+    for _ in 0..2 {
+        harness.mine_and_deliver(0, &miner, &[0, 1]);
+    }
+    assert_eq!(harness.stacks_tip(0), harness.stacks_tip(1));
+    assert_eq!(harness.eval_at_chain_tip(1, "block-height"), Value::UInt(2));
+
+    // partition: each node mines a block the other doesn't see, and only node 1 sees a deposit
+    harness.mine_and_deliver(0, &miner, &[0]);
+
+    let deposit = DepositStxOp {
+        txid: next_txid(),
+        burn_header_hash: BurnchainHeaderHash([0; 32]),
+        amount: 1000,
+        sender: recipient.clone().into(),
+    };
+    let parent = harness.burn_tip(1).block_hash;
+    harness.produce_and_deliver_burn_block(&parent, vec![deposit.into()], &[1]);
+    let (_, fork_block) = harness.mine_and_deliver(1, &miner, &[1]);
+
+    assert_ne!(harness.stacks_tip(0), harness.stacks_tip(1));
+    assert_eq!(harness.eval_at_chain_tip(0, "block-height"), Value::UInt(3));
+    assert_eq!(harness.eval_at_chain_tip(1, "block-height"), Value::UInt(3));
+    assert_eq!(harness.eval_at_chain_tip(0, &balance_expr), Value::UInt(0));
+    assert_eq!(
+        harness.eval_at_chain_tip(1, &balance_expr),
+        Value::UInt(1000)
+    );
+
+    // heal: node 0 learns about node 1's fork, which then grows longer
+    let fork_burn_tip = harness.burn_tip(1).block_hash;
+    harness.deliver_burn_block(0, &fork_burn_tip);
+    harness.deliver_stacks_block(0, &fork_block);
+    harness.mine_and_deliver(1, &miner, &[0, 1]);
+
+    assert_eq!(
+        harness.burn_tip(0).block_hash,
+        harness.burn_tip(1).block_hash
+    );
+    assert_eq!(harness.stacks_tip(0), harness.stacks_tip(1));
+    assert_eq!(harness.eval_at_chain_tip(0, "block-height"), Value::UInt(4));
+    assert_eq!(
+        harness.eval_at_chain_tip(0, &balance_expr),
+        Value::UInt(1000)
+    );
+}
+
+/// A node that dies after committing a sortition, but before processing the Stacks block it
+/// made ready, finishes processing it on restart.
+#[test]
+fn test_recover_interrupted_burn_block() {
+    let mut harness = SubnetTestHarness::new("stacks-blockchain-recover-interrupted", 1, None);
+    let miner = StacksPrivateKey::new();
+    harness.mine_and_deliver(0, &miner, &[0]);
+    assert!(harness.nodes[0]
+        .coord
+        .journal
+        .get_interrupted()
+        .unwrap()
+        .is_empty());
+
+    // the sortition gets committed, but the node dies before processing the Stacks block
+    let (commit, block) = harness.mine_stacks_block(0, &miner);
+    let parent = harness.burn_tip(0).block_hash;
+    let burn_block = harness.produce_and_deliver_burn_block(&parent, vec![commit], &[0]);
+    let burn_header = harness.burn_tip(0);
+    assert_eq!(burn_header.block_hash, burn_block);
+
+    let node = &mut harness.nodes[0];
+    node.coord
+        .journal
+        .sortition_processed(&burn_header)
+        .unwrap();
+    let sortition =
+        SortitionDB::get_block_snapshot(node.coord.sortition_db.conn(), &SortitionId(burn_block.0))
+            .unwrap()
+            .unwrap();
+    preprocess_block(
+        &mut node.coord.chain_state_db,
+        &node.coord.sortition_db,
+        &sortition,
+        block.clone(),
+    );
+    assert_eq!(harness.eval_at_chain_tip(0, "block-height"), Value::UInt(1));
+
+    // on restart, the node finishes the interrupted burn block
+    let path = harness.nodes[0].path.clone();
+    harness.nodes[0].coord = make_coordinator(&path, None);
+    let interrupted = harness.nodes[0].coord.journal.get_interrupted().unwrap();
+    assert_eq!(interrupted.len(), 1);
+    assert_eq!(interrupted[0].burn_header_hash, burn_block);
+    assert_eq!(
+        interrupted[0].step,
+        journal::JournalStep::SortitionProcessed
+    );
+
+    harness.nodes[0]
+        .coord
+        .recover_interrupted_burn_blocks()
+        .unwrap();
+    assert_eq!(harness.eval_at_chain_tip(0, "block-height"), Value::UInt(2));
+    assert_eq!(harness.stacks_tip(0).1, block.block_hash());
+    assert!(harness.nodes[0]
+        .coord
+        .journal
+        .get_interrupted()
+        .unwrap()
+        .is_empty());
+}
+
+/// A trace recorded from a node replays to the same sortitions and state roots, and a trace
+/// whose recorded state root differs from the replayed one is reported as a mismatch.
+#[test]
+fn test_record_and_replay_trace() {
+    let mut harness = SubnetTestHarness::new("stacks-blockchain-record-trace", 1, None);
+    let miner = StacksPrivateKey::new();
+    let recipient = p2pkh_from(&StacksPrivateKey::new());
+
+    harness.mine_and_deliver(0, &miner, &[0]);
+    let deposit = DepositStxOp {
+        txid: next_txid(),
+        burn_header_hash: BurnchainHeaderHash([0; 32]),
+        amount: 1000,
+        sender: recipient.into(),
+    };
+    let parent = harness.burn_tip(0).block_hash;
+    harness.produce_and_deliver_burn_block(&parent, vec![deposit.into()], &[0]);
+    for _ in 0..2 {
+        harness.mine_and_deliver(0, &miner, &[0]);
+    }
+
+    let coord = &harness.nodes[0].coord;
+    let trace = trace::record_trace(
+        &coord.burnchain,
+        vec![],
+        &coord.burnchain_blocks_db,
+        &coord.sortition_db,
+        &coord.chain_state_db,
+    )
+    .unwrap();
+    let num_burn_blocks = trace
+        .iter()
+        .filter(|entry| matches!(entry, trace::TraceEntry::BurnBlock(_)))
+        .count();
+    assert_eq!(num_burn_blocks, 5);
+
+    let mut serialized = vec![];
+    trace::write_trace(&mut serialized, &trace).unwrap();
+    let trace = trace::read_trace(&serialized[..]).unwrap();
+
+    let epochs = StacksEpoch::unit_test(StacksEpochId::Epoch21, coord.burnchain.first_block_height);
+    let replay_dir = "/tmp/stacks-node-tests/unit-tests/stacks-blockchain-record-trace.replay";
+    let _r = std::fs::remove_dir_all(replay_dir);
+    assert_eq!(trace::replay_trace(&trace, replay_dir, &epochs).unwrap(), 3);
+
+    let mut tampered = trace.clone();
+    match tampered.last_mut() {
+        Some(trace::TraceEntry::StacksBlock(block)) => block.state_root = TrieHash([0x01; 32]),
+        _ => panic!("trace does not end with a Stacks block"),
+    }
+    let tampered_dir = "/tmp/stacks-node-tests/unit-tests/stacks-blockchain-record-trace.tampered";
+    let _r = std::fs::remove_dir_all(tampered_dir);
+    match trace::replay_trace(&tampered, tampered_dir, &epochs) {
+        Err(trace::TraceError::Mismatch(_)) => {}
+        res => panic!("expected a mismatch, got {:?}", res),
+    }
+}
+
+/// Every trace fixture in `trace_fixtures/` replays with the current consensus rules
+#[test]
+fn test_replay_trace_fixtures() {
+    let fixtures_dir = concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/src/chainstate/coordinator/trace_fixtures"
+    );
+    let mut fixtures: Vec<_> = std::fs::read_dir(fixtures_dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().map(|ext| ext == "jsonl").unwrap_or(false))
+        .collect();
+    fixtures.sort();
+
+    for fixture in fixtures.iter() {
+        let name = fixture.file_stem().unwrap().to_str().unwrap();
+        let file = std::fs::File::open(fixture).unwrap();
+        let trace = trace::read_trace(std::io::BufReader::new(file)).unwrap();
+        let replay_dir = format!(
+            "/tmp/stacks-node-tests/unit-tests/stacks-blockchain-trace-fixture.{}",
+            name
+        );
+        let _r = std::fs::remove_dir_all(&replay_dir);
+        if let Err(e) = trace::replay_trace(&trace, &replay_dir, &SUBNET_EPOCHS[..]) {
+            panic!("Trace fixture {} failed to replay: {}", name, e);
+        }
+    }
+}
+
+pub fn eval_at_chain_tip(chainstate_path: &str, sort_db: &SortitionDB, eval: &str) -> Value {
     let stacks_tip = SortitionDB::get_canonical_stacks_chain_tip_hash(sort_db.conn()).unwrap();
     let mut chainstate = get_chainstate(chainstate_path);
     chainstate
@@ -793,7 +1011,7 @@ fn reveal_block<T: BlockEventDispatcher, N: CoordinatorNotices, U: RewardSetProv
     coord.handle_new_stacks_block().unwrap();
 }
 
-fn preprocess_block(
+pub fn preprocess_block(
     chain_state: &mut StacksChainState,
     sort_db: &SortitionDB,
     my_sortition: &BlockSnapshot,
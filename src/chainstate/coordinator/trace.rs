@@ -0,0 +1,536 @@
+// Copyright (C) 2013-2020 Blockstack PBC, a public benefit corporation
+// Copyright (C) 2020 Stacks Open Internet Foundation
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Recorded traces of the burnchain blocks and Stacks blocks a node processed.
+//!
+//! A trace holds the parameters the node was booted with, then each burnchain block on the
+//! node's canonical sortition fork, in order, with the operations the node extracted from it.
+//! Each burnchain block is followed by the Stacks blocks its sortition elected (and the parent
+//! microblocks they confirmed).  Alongside the data, the trace records what the node computed
+//! from it: the consensus hash of each sortition, and the state root and withdrawal root of each
+//! Stacks block.
+//!
+//! `replay_trace` feeds a trace through a fresh coordinator and checks that it computes the same
+//! values, so that changes to consensus-critical code (e.g. the epochs in `core::SUBNET_EPOCHS`)
+//! can be checked against the history of a real subnet.  Traces are stored as JSON lines, one
+//! `TraceEntry` per line.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::io::{BufRead, Write};
+use std::sync::mpsc::sync_channel;
+
+use crate::burnchains::db::{BurnchainBlockData, BurnchainDB};
+use crate::burnchains::{Burnchain, BurnchainBlockHeader, Error as BurnchainError};
+use crate::chainstate::burn::db::sortdb::SortitionDB;
+use crate::chainstate::burn::operations::{
+    hex_deserialize, hex_serialize, BlockstackOperationType,
+};
+use crate::chainstate::burn::ConsensusHash;
+use crate::chainstate::coordinator::journal::CoordinatorJournal;
+use crate::chainstate::coordinator::{ChainsCoordinator, Error, OnChainRewardSetProvider};
+use crate::chainstate::stacks::db::blocks::{DummyEventDispatcher, StagingBlock};
+use crate::chainstate::stacks::db::withdrawal_policy::WithdrawalPolicy;
+use crate::chainstate::stacks::db::{ChainStateBootData, StacksChainState};
+use crate::chainstate::stacks::{
+    Error as ChainstateError, StacksBlock, StacksBlockHeader, StacksMicroblock,
+};
+use crate::codec::StacksMessageCodec;
+use crate::core::{
+    StacksEpoch, EMPTY_MICROBLOCK_PARENT_HASH, FIRST_BURNCHAIN_CONSENSUS_HASH,
+    FIRST_STACKS_BLOCK_HASH,
+};
+use crate::net::atlas::AtlasConfig;
+use crate::util_lib::db::Error as DBError;
+use clarity::vm::types::PrincipalData;
+use stacks_common::types::chainstate::{BurnchainHeaderHash, StacksBlockId, TrieHash};
+use stacks_common::util::hash::{hex_bytes, to_hex, Sha512Trunc256Sum};
+
+/// The parameters a node was booted with.  Replaying a trace boots a chainstate with the same
+/// parameters.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TraceBoot {
+    pub burnchain: Burnchain,
+    pub mainnet: bool,
+    pub chain_id: u32,
+    pub initial_balances: Vec<(PrincipalData, u64)>,
+    pub withdrawal_policy: WithdrawalPolicy,
+    /// state root of the boot block
+    pub genesis_state_root: TrieHash,
+}
+
+/// A burnchain block on the recorded node's canonical sortition fork
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TraceBurnBlock {
+    pub block_height: u64,
+    #[serde(serialize_with = "hex_serialize", deserialize_with = "hex_deserialize")]
+    pub block_hash: BurnchainHeaderHash,
+    #[serde(serialize_with = "hex_serialize", deserialize_with = "hex_deserialize")]
+    pub parent_block_hash: BurnchainHeaderHash,
+    pub timestamp: u64,
+    pub ops: Vec<BlockstackOperationType>,
+    /// consensus hash of the sortition the node evaluated for this block
+    pub consensus_hash: ConsensusHash,
+}
+
+/// A Stacks block the recorded node processed
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TraceStacksBlock {
+    pub index_block_hash: StacksBlockId,
+    pub consensus_hash: ConsensusHash,
+    pub parent_consensus_hash: ConsensusHash,
+    /// the consensus-serialized block, hex-encoded
+    pub block: String,
+    /// the parent microblocks the block confirmed, in sequence order, each consensus-serialized
+    /// and hex-encoded
+    pub microblocks: Vec<String>,
+    /// state root the node computed for the block
+    pub state_root: TrieHash,
+    /// withdrawal root the node computed for the block
+    #[serde(serialize_with = "hex_serialize", deserialize_with = "hex_deserialize")]
+    pub withdrawal_root: Sha512Trunc256Sum,
+}
+
+/// One line of a trace.  The first entry of a trace is always `Boot`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TraceEntry {
+    Boot(TraceBoot),
+    BurnBlock(TraceBurnBlock),
+    StacksBlock(TraceStacksBlock),
+}
+
+#[derive(Debug)]
+pub enum TraceError {
+    /// The trace could not be read, or is not a valid trace
+    Malformed(String),
+    /// Replaying the trace computed something different from what the recorded node computed
+    Mismatch(String),
+    Coordinator(Error),
+    IOError(io::Error),
+}
+
+impl fmt::Display for TraceError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TraceError::Malformed(msg) => write!(f, "malformed trace: {}", msg),
+            TraceError::Mismatch(msg) => write!(f, "trace mismatch: {}", msg),
+            TraceError::Coordinator(e) => write!(f, "coordinator error: {:?}", e),
+            TraceError::IOError(e) => write!(f, "I/O error: {}", e),
+        }
+    }
+}
+
+impl From<Error> for TraceError {
+    fn from(e: Error) -> TraceError {
+        TraceError::Coordinator(e)
+    }
+}
+
+impl From<BurnchainError> for TraceError {
+    fn from(e: BurnchainError) -> TraceError {
+        TraceError::Coordinator(Error::BurnchainError(e))
+    }
+}
+
+impl From<ChainstateError> for TraceError {
+    fn from(e: ChainstateError) -> TraceError {
+        TraceError::Coordinator(Error::ChainstateError(e))
+    }
+}
+
+impl From<DBError> for TraceError {
+    fn from(e: DBError) -> TraceError {
+        TraceError::Coordinator(Error::DBError(e))
+    }
+}
+
+impl From<io::Error> for TraceError {
+    fn from(e: io::Error) -> TraceError {
+        TraceError::IOError(e)
+    }
+}
+
+/// Write `trace` as JSON lines
+pub fn write_trace<W: Write>(out: &mut W, trace: &[TraceEntry]) -> Result<(), TraceError> {
+    for entry in trace.iter() {
+        let line = serde_json::to_string(entry)
+            .map_err(|e| TraceError::Malformed(format!("failed to serialize entry: {}", &e)))?;
+        writeln!(out, "{}", &line)?;
+    }
+    out.flush()?;
+    Ok(())
+}
+
+/// Read a trace written by `write_trace`.  Blank lines are skipped.
+pub fn read_trace<R: BufRead>(input: R) -> Result<Vec<TraceEntry>, TraceError> {
+    let mut trace = vec![];
+    for (line_num, line) in input.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let entry = serde_json::from_str(&line)
+            .map_err(|e| TraceError::Malformed(format!("line {}: {}", line_num + 1, &e)))?;
+        trace.push(entry);
+    }
+    Ok(trace)
+}
+
+fn genesis_block_id() -> StacksBlockId {
+    StacksBlockHeader::make_index_block_hash(
+        &FIRST_BURNCHAIN_CONSENSUS_HASH,
+        &FIRST_STACKS_BLOCK_HASH,
+    )
+}
+
+/// Record the history a node has processed.  `burnchain` and `initial_balances` must be the
+/// ones the node was booted with.  The databases are only read, so this can run against the
+/// databases of a live node.
+pub fn record_trace(
+    burnchain: &Burnchain,
+    initial_balances: Vec<(PrincipalData, u64)>,
+    burnchain_db: &BurnchainDB,
+    sortdb: &SortitionDB,
+    chainstate: &StacksChainState,
+) -> Result<Vec<TraceEntry>, TraceError> {
+    let genesis = StacksChainState::get_stacks_block_header_info_by_index_block_hash(
+        chainstate.db(),
+        &genesis_block_id(),
+    )?
+    .ok_or(ChainstateError::NoSuchBlockError)?;
+
+    let mut trace = vec![TraceEntry::Boot(TraceBoot {
+        burnchain: burnchain.clone(),
+        mainnet: chainstate.mainnet,
+        chain_id: chainstate.chain_id,
+        initial_balances,
+        withdrawal_policy: chainstate.get_withdrawal_policy().clone(),
+        genesis_state_root: genesis.index_root,
+    })];
+
+    // walk the canonical sortition fork back to the first burnchain block, which every node
+    // boots with
+    let mut sortitions = vec![];
+    let mut cursor = SortitionDB::get_canonical_burn_chain_tip(sortdb.conn())?;
+    while cursor.block_height > sortdb.first_block_height {
+        let parent_sortition_id = cursor.parent_sortition_id.clone();
+        sortitions.push(cursor);
+        cursor = SortitionDB::get_block_snapshot(sortdb.conn(), &parent_sortition_id)?
+            .ok_or(DBError::NotFoundError)?;
+    }
+    sortitions.reverse();
+
+    // file each processed Stacks block under the burnchain block which elected it
+    let mut stacks_blocks: HashMap<BurnchainHeaderHash, Vec<StagingBlock>> = HashMap::new();
+    for staging_block in StacksChainState::get_replayable_blocks(chainstate.db(), None)? {
+        let sortition = SortitionDB::get_block_snapshot_consensus(
+            sortdb.conn(),
+            &staging_block.consensus_hash,
+        )?
+        .ok_or(DBError::NotFoundError)?;
+        stacks_blocks
+            .entry(sortition.burn_header_hash)
+            .or_insert_with(Vec::new)
+            .push(staging_block);
+    }
+
+    for sortition in sortitions.iter() {
+        let BurnchainBlockData { header, ops } =
+            burnchain_db.get_burnchain_block(&sortition.burn_header_hash)?;
+        trace.push(TraceEntry::BurnBlock(TraceBurnBlock {
+            block_height: header.block_height,
+            block_hash: header.block_hash,
+            parent_block_hash: header.parent_block_hash,
+            timestamp: header.timestamp,
+            ops,
+            consensus_hash: sortition.consensus_hash.clone(),
+        }));
+
+        if let Some(staging_blocks) = stacks_blocks.get(&sortition.burn_header_hash) {
+            for staging_block in staging_blocks.iter() {
+                trace.push(TraceEntry::StacksBlock(record_stacks_block(
+                    chainstate,
+                    staging_block,
+                )?));
+            }
+        }
+    }
+
+    Ok(trace)
+}
+
+fn record_stacks_block(
+    chainstate: &StacksChainState,
+    staging_block: &StagingBlock,
+) -> Result<TraceStacksBlock, TraceError> {
+    let index_block_hash = StacksBlockHeader::make_index_block_hash(
+        &staging_block.consensus_hash,
+        &staging_block.anchored_block_hash,
+    );
+    let block = StacksChainState::load_block(
+        &chainstate.blocks_path,
+        &staging_block.consensus_hash,
+        &staging_block.anchored_block_hash,
+    )?
+    .ok_or_else(|| {
+        TraceError::Malformed(format!(
+            "block {} has no data in the chainstate; was it pruned?",
+            &index_block_hash
+        ))
+    })?;
+
+    let microblocks = if staging_block.parent_microblock_hash != EMPTY_MICROBLOCK_PARENT_HASH {
+        StacksChainState::load_processed_microblock_stream_fork(
+            chainstate.db(),
+            &staging_block.parent_consensus_hash,
+            &staging_block.parent_anchored_block_hash,
+            &staging_block.parent_microblock_hash,
+        )?
+        .ok_or_else(|| {
+            TraceError::Malformed(format!(
+                "parent microblocks of block {} are missing from the chainstate",
+                &index_block_hash
+            ))
+        })?
+    } else {
+        vec![]
+    };
+
+    let header = StacksChainState::get_stacks_block_header_info_by_index_block_hash(
+        chainstate.db(),
+        &index_block_hash,
+    )?
+    .ok_or(ChainstateError::NoSuchBlockError)?;
+
+    Ok(TraceStacksBlock {
+        index_block_hash,
+        consensus_hash: staging_block.consensus_hash.clone(),
+        parent_consensus_hash: staging_block.parent_consensus_hash.clone(),
+        block: to_hex(&block.serialize_to_vec()),
+        microblocks: microblocks
+            .iter()
+            .map(|microblock| to_hex(&microblock.serialize_to_vec()))
+            .collect(),
+        state_root: header.index_root,
+        withdrawal_root: header.withdrawal_tree.root(),
+    })
+}
+
+fn decode_hex<T: StacksMessageCodec>(what: &str, hex: &str) -> Result<T, TraceError> {
+    let bytes = hex_bytes(hex)
+        .map_err(|e| TraceError::Malformed(format!("{} is not valid hex: {:?}", what, &e)))?;
+    T::consensus_deserialize(&mut &bytes[..])
+        .map_err(|e| TraceError::Malformed(format!("failed to decode {}: {:?}", what, &e)))
+}
+
+type ReplayCoordinator<'a> =
+    ChainsCoordinator<'a, DummyEventDispatcher, (), OnChainRewardSetProvider, (), ()>;
+
+/// Replay `trace` through a coordinator whose databases are created under `working_dir`, which
+/// must not exist, using `epochs` for the sortition DB.  Each sortition and Stacks block must
+/// come out the same as it did on the recorded node.  Returns the number of Stacks blocks
+/// replayed.
+pub fn replay_trace(
+    trace: &[TraceEntry],
+    working_dir: &str,
+    epochs: &[StacksEpoch],
+) -> Result<u64, TraceError> {
+    let boot = match trace.first() {
+        Some(TraceEntry::Boot(boot)) => boot,
+        _ => {
+            return Err(TraceError::Malformed(
+                "the first entry is not a boot entry".into(),
+            ))
+        }
+    };
+    if fs::metadata(working_dir).is_ok() {
+        return Err(TraceError::IOError(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("{} already exists", working_dir),
+        )));
+    }
+
+    let mut burnchain = boot.burnchain.clone();
+    burnchain.working_dir = working_dir.to_string();
+    Burnchain::setup_chainstate_dirs(&burnchain.working_dir)?;
+    let sortition_db = SortitionDB::connect(
+        &burnchain.get_db_path(),
+        burnchain.first_block_height,
+        epochs,
+        true,
+    )?;
+    let burnchain_blocks_db = BurnchainDB::connect(
+        &burnchain.get_burnchaindb_path(),
+        burnchain.first_block_height,
+        true,
+    )?;
+
+    let mut boot_data = ChainStateBootData::new(&burnchain, boot.initial_balances.clone(), None);
+    boot_data.withdrawal_policy = boot.withdrawal_policy.clone();
+    let (chain_state_db, _) = StacksChainState::open_and_exec(
+        boot.mainnet,
+        boot.chain_id,
+        &format!("{}/chainstate/", working_dir),
+        Some(&mut boot_data),
+        None,
+    )?;
+
+    let genesis = StacksChainState::get_stacks_block_header_info_by_index_block_hash(
+        chain_state_db.db(),
+        &genesis_block_id(),
+    )?
+    .ok_or(ChainstateError::NoSuchBlockError)?;
+    if genesis.index_root != boot.genesis_state_root {
+        return Err(TraceError::Mismatch(format!(
+            "genesis state root is {}, but the trace has {}",
+            &genesis.index_root, &boot.genesis_state_root
+        )));
+    }
+
+    let canonical_sortition_tip = SortitionDB::get_canonical_sortition_tip(sortition_db.conn())?;
+    let journal = CoordinatorJournal::open(&burnchain.get_coordinator_journal_path())?;
+    // the receiver is drained as the trace is replayed, so the coordinator never blocks on it
+    let (attachments_tx, attachments_rx) = sync_channel(1024);
+    let mut coord: ReplayCoordinator = ChainsCoordinator {
+        canonical_chain_tip: None,
+        canonical_sortition_tip: Some(canonical_sortition_tip),
+        burnchain_blocks_db,
+        chain_state_db,
+        sortition_db,
+        burnchain,
+        attachments_tx,
+        dispatcher: None,
+        cost_estimator: None,
+        fee_estimator: None,
+        reward_set_provider: OnChainRewardSetProvider(),
+        notifier: (),
+        atlas_config: AtlasConfig::default(boot.mainnet),
+        journal,
+    };
+
+    let mut num_replayed = 0;
+    for entry in trace[1..].iter() {
+        match entry {
+            TraceEntry::Boot(_) => {
+                return Err(TraceError::Malformed(
+                    "a boot entry follows the first entry".into(),
+                ))
+            }
+            TraceEntry::BurnBlock(burn_block) => replay_burn_block(&mut coord, burn_block)?,
+            TraceEntry::StacksBlock(stacks_block) => {
+                replay_stacks_block(&mut coord, stacks_block)?;
+                num_replayed += 1;
+            }
+        }
+        for _ in attachments_rx.try_iter() {}
+    }
+    Ok(num_replayed)
+}
+
+fn replay_burn_block(
+    coord: &mut ReplayCoordinator,
+    burn_block: &TraceBurnBlock,
+) -> Result<(), TraceError> {
+    let header = BurnchainBlockHeader {
+        block_height: burn_block.block_height,
+        block_hash: burn_block.block_hash.clone(),
+        parent_block_hash: burn_block.parent_block_hash.clone(),
+        num_txs: burn_block.ops.len() as u64,
+        timestamp: burn_block.timestamp,
+    };
+    coord
+        .burnchain_blocks_db
+        .raw_store_burnchain_block(header, burn_block.ops.clone())?;
+    coord.handle_new_burnchain_block()?;
+
+    let tip = SortitionDB::get_canonical_burn_chain_tip(coord.sortition_db.conn())?;
+    if tip.burn_header_hash != burn_block.block_hash {
+        return Err(TraceError::Mismatch(format!(
+            "burnchain block {} at height {} did not become the canonical burnchain tip",
+            &burn_block.block_hash, burn_block.block_height
+        )));
+    }
+    if tip.consensus_hash != burn_block.consensus_hash {
+        return Err(TraceError::Mismatch(format!(
+            "burnchain block {} at height {} has consensus hash {}, but the trace has {}",
+            &burn_block.block_hash,
+            burn_block.block_height,
+            &tip.consensus_hash,
+            &burn_block.consensus_hash
+        )));
+    }
+    Ok(())
+}
+
+fn replay_stacks_block(
+    coord: &mut ReplayCoordinator,
+    stacks_block: &TraceStacksBlock,
+) -> Result<(), TraceError> {
+    let block: StacksBlock = decode_hex("block", &stacks_block.block)?;
+    let index_block_hash =
+        StacksBlockHeader::make_index_block_hash(&stacks_block.consensus_hash, &block.block_hash());
+    if index_block_hash != stacks_block.index_block_hash {
+        return Err(TraceError::Malformed(format!(
+            "block data of {} hashes to {}",
+            &stacks_block.index_block_hash, &index_block_hash
+        )));
+    }
+
+    for microblock_hex in stacks_block.microblocks.iter() {
+        let microblock: StacksMicroblock = decode_hex("microblock", microblock_hex)?;
+        coord.chain_state_db.preprocess_streamed_microblock(
+            &stacks_block.parent_consensus_hash,
+            &block.header.parent_block,
+            &microblock,
+        )?;
+    }
+    coord.chain_state_db.preprocess_anchored_block(
+        &coord.sortition_db.index_conn(),
+        &stacks_block.consensus_hash,
+        &block,
+        &stacks_block.parent_consensus_hash,
+        0,
+    )?;
+    coord.handle_new_stacks_block()?;
+
+    let header = StacksChainState::get_stacks_block_header_info_by_index_block_hash(
+        coord.chain_state_db.db(),
+        &index_block_hash,
+    )?
+    .ok_or_else(|| {
+        TraceError::Mismatch(format!(
+            "block {} was not accepted; it was either invalid or never became processable",
+            &index_block_hash
+        ))
+    })?;
+    if header.index_root != stacks_block.state_root
+        || header.withdrawal_tree.root() != stacks_block.withdrawal_root
+    {
+        return Err(TraceError::Mismatch(format!(
+            "block {} at height {} replayed to state root {} and withdrawal root {}, but the trace has {} and {}",
+            &index_block_hash,
+            header.stacks_block_height,
+            &header.index_root,
+            &header.withdrawal_tree.root(),
+            &stacks_block.state_root,
+            &stacks_block.withdrawal_root
+        )));
+    }
+    Ok(())
+}
@@ -671,6 +671,7 @@ mod tests {
                         BlockBuilderSettings {
                             max_miner_time_ms: 15_000,
                             mempool_settings: MemPoolWalkSettings::default(),
+                            ..BlockBuilderSettings::limited()
                         },
                         None,
                     )
@@ -876,6 +877,7 @@ mod tests {
                         BlockBuilderSettings {
                             max_miner_time_ms: 15_000,
                             mempool_settings: MemPoolWalkSettings::default(),
+                            ..BlockBuilderSettings::limited()
                         },
                         None,
                     )
@@ -46,6 +46,7 @@ use crate::types::chainstate::BurnchainHeaderHash;
 use crate::types::chainstate::StacksBlockId;
 use crate::types::chainstate::TrieHash;
 use crate::types::chainstate::{BlockHeaderHash, StacksWorkScore, VRFSeed};
+use clarity::vm::ClarityVersion;
 
 impl StacksMessageCodec for StacksBlockHeader {
     fn consensus_serialize<W: Write>(&self, fd: &mut W) -> Result<(), codec_error> {
@@ -457,6 +458,40 @@ impl StacksBlock {
         return true;
     }
 
+    /// verify that `tx` does not publish a contract in a Clarity version which blocks evaluated
+    /// in `epoch_id` do not support, and say why it is invalid if it does
+    pub fn validate_transaction_clarity_version(
+        tx: &StacksTransaction,
+        epoch_id: StacksEpochId,
+    ) -> Result<(), String> {
+        if let TransactionPayload::SmartContract(ref smart_contract, Some(version)) = tx.payload {
+            let max_version = max_clarity_version(epoch_id);
+            if version > max_version {
+                return Err(format!(
+                    "Tx {} publishes contract {} in {}, but blocks evaluated in epoch {} support at most {}",
+                    tx.txid(),
+                    &smart_contract.name,
+                    version,
+                    epoch_id,
+                    max_version
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// verify that no tx publishes a contract in a Clarity version which blocks evaluated in
+    /// `epoch_id` do not support, and say why the first one which does is invalid
+    pub fn validate_transactions_clarity_version(
+        txs: &[StacksTransaction],
+        epoch_id: StacksEpochId,
+    ) -> Result<(), String> {
+        for tx in txs.iter() {
+            StacksBlock::validate_transaction_clarity_version(tx, epoch_id)?;
+        }
+        Ok(())
+    }
+
     /// verify that a coinbase is present and is on-chain only, or is absent
     pub fn validate_coinbase(txs: &Vec<StacksTransaction>, check_present: bool) -> bool {
         let mut found_coinbase = false;
@@ -851,6 +886,10 @@ mod test {
     use std::error::Error;
 
     use crate::types::chainstate::StacksAddress;
+    use crate::util_lib::strings::StacksString;
+    use clarity::vm::ClarityVersion;
+    use clarity::vm::ContractName;
+    use std::convert::TryFrom;
 
     use super::*;
 
@@ -1535,6 +1574,59 @@ mod test {
         }
     }
 
+    #[test]
+    fn stacks_block_clarity_version() {
+        let privk = StacksPrivateKey::from_hex(
+            "6d430bb91222408e7706c9001cfaeb91b08c2be6d5ac95779ab52c6b431950e001",
+        )
+        .unwrap();
+        let origin_auth = TransactionAuth::Standard(
+            TransactionSpendingCondition::new_singlesig_p2pkh(StacksPublicKey::from_private(
+                &privk,
+            ))
+            .unwrap(),
+        );
+        let make_contract_tx = |version: Option<ClarityVersion>| {
+            StacksTransaction::new(
+                TransactionVersion::Testnet,
+                origin_auth.clone(),
+                TransactionPayload::SmartContract(
+                    TransactionSmartContract {
+                        name: ContractName::try_from("hello-world").unwrap(),
+                        code_body: StacksString::from_str("(+ 1 1)").unwrap(),
+                    },
+                    version,
+                ),
+            )
+        };
+        let tx_unversioned = make_contract_tx(None);
+        let tx_clarity1 = make_contract_tx(Some(ClarityVersion::Clarity1));
+        let tx_clarity2 = make_contract_tx(Some(ClarityVersion::Clarity2));
+
+        // subnets evaluate blocks in an epoch which supports their Clarity version
+        let subnet_epoch = SUBNET_EPOCHS.last().unwrap().epoch_id;
+        assert_eq!(max_clarity_version(subnet_epoch), SUBNETS_CLARITY_VERSION);
+        let txs = vec![
+            tx_unversioned.clone(),
+            tx_clarity1.clone(),
+            tx_clarity2.clone(),
+        ];
+        assert!(StacksBlock::validate_transactions_clarity_version(&txs, subnet_epoch).is_ok());
+
+        // but a contract declared in a version newer than the epoch's is rejected, and the
+        // reason names it
+        assert_eq!(
+            max_clarity_version(StacksEpochId::Epoch2_05),
+            ClarityVersion::Clarity1
+        );
+        let txs = vec![tx_unversioned, tx_clarity1, tx_clarity2.clone()];
+        let reason =
+            StacksBlock::validate_transactions_clarity_version(&txs, StacksEpochId::Epoch2_05)
+                .unwrap_err();
+        assert!(reason.contains(&tx_clarity2.txid().to_string()));
+        assert!(reason.contains("hello-world"));
+    }
+
     // TODO:
     // * size limits
 }
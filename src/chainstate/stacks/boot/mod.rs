@@ -60,6 +60,12 @@ pub const COSTS_1_NAME: &'static str = "costs";
 pub const COSTS_2_NAME: &'static str = "costs-2";
 pub const COSTS_3_NAME: &'static str = "costs-3";
 pub const BOOT_CODE_SUBNET: &'static str = std::include_str!("subnet.clar");
+pub const BOOT_CODE_TIME_ORACLE: &'static str = std::include_str!("time-oracle.clar");
+pub const TIME_ORACLE_NAME: &'static str = "time-oracle";
+pub const BOOT_CODE_SUBNET_TRANSFERS: &'static str = std::include_str!("subnet-transfers.clar");
+pub const SUBNET_TRANSFERS_NAME: &'static str = "subnet-transfers";
+pub const BOOT_CODE_SUBNET_TOKEN: &'static str = std::include_str!("subnet-token.clar");
+pub const SUBNET_TOKEN_NAME: &'static str = "subnet-token";
 
 pub mod docs;
 
@@ -0,0 +1,381 @@
+// Copyright (C) 2013-2020 Blockstack PBC, a public benefit corporation
+// Copyright (C) 2020 Stacks Open Internet Foundation
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::{BTreeSet, HashMap};
+use std::convert::TryFrom;
+
+use rusqlite::types::ToSql;
+use rusqlite::Row;
+
+use crate::burnchains::Txid;
+use crate::chainstate::stacks::db::StacksChainState;
+use crate::chainstate::stacks::events::{StacksTransactionReceipt, TransactionOrigin};
+use crate::chainstate::stacks::{Error, TransactionPayload};
+use crate::util_lib::db::Error as db_error;
+use crate::util_lib::db::{query_rows, u64_to_sql, DBTx, FromColumn, FromRow};
+use clarity::vm::events::{FTEventType, NFTEventType, STXEventType, StacksTransactionEvent};
+use clarity::vm::types::{PrincipalData, QualifiedContractIdentifier, StandardPrincipalData};
+use stacks_common::types::chainstate::StacksBlockId;
+
+/// How a transaction touches a principal
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum AccountRole {
+    /// the principal sent the transaction
+    Origin,
+    /// the principal paid the transaction's fee
+    Sponsor,
+    /// the transaction published or called the principal's contract
+    Contract,
+    /// an asset was transferred or burned out of the principal's account
+    Sender,
+    /// an asset was transferred or minted into the principal's account
+    Recipient,
+}
+
+impl AccountRole {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AccountRole::Origin => "origin",
+            AccountRole::Sponsor => "sponsor",
+            AccountRole::Contract => "contract",
+            AccountRole::Sender => "sender",
+            AccountRole::Recipient => "recipient",
+        }
+    }
+
+    pub fn from_name(role: &str) -> Option<AccountRole> {
+        match role {
+            "origin" => Some(AccountRole::Origin),
+            "sponsor" => Some(AccountRole::Sponsor),
+            "contract" => Some(AccountRole::Contract),
+            "sender" => Some(AccountRole::Sender),
+            "recipient" => Some(AccountRole::Recipient),
+            _ => None,
+        }
+    }
+}
+
+/// A transaction touching a principal, as recorded in the chainstate's account index
+#[derive(Debug, Clone, PartialEq)]
+pub struct AccountTransaction {
+    pub principal: PrincipalData,
+    /// the Stacks transaction, or the L1 transaction of a burnchain operation such as a deposit
+    pub txid: Txid,
+    pub index_block_hash: StacksBlockId,
+    pub block_height: u64,
+    pub tx_index: u32,
+    pub roles: Vec<AccountRole>,
+}
+
+impl AccountTransaction {
+    /// Find the principals a transaction's receipt touches, and how.  Asset events are only
+    /// considered if the transaction's post-conditions held, since otherwise they were rolled
+    /// back.
+    pub fn principal_roles(
+        receipt: &StacksTransactionReceipt,
+    ) -> HashMap<PrincipalData, BTreeSet<AccountRole>> {
+        let mut roles: HashMap<PrincipalData, BTreeSet<AccountRole>> = HashMap::new();
+        let mut add = |principal: &PrincipalData, role: AccountRole| {
+            roles
+                .entry(principal.clone())
+                .or_insert_with(BTreeSet::new)
+                .insert(role);
+        };
+        let events: &[StacksTransactionEvent] = if receipt.post_condition_aborted {
+            &[]
+        } else {
+            &receipt.events
+        };
+
+        if let TransactionOrigin::Stacks(tx) = &receipt.transaction {
+            add(&tx.origin_address().into(), AccountRole::Origin);
+            if let Some(sponsor) = tx.sponsor_address() {
+                add(&sponsor.into(), AccountRole::Sponsor);
+            }
+            match &tx.payload {
+                TransactionPayload::TokenTransfer(recipient, ..) => {
+                    add(recipient, AccountRole::Recipient);
+                }
+                TransactionPayload::ContractCall(contract_call) => {
+                    add(
+                        &contract_call.to_clarity_contract_id().into(),
+                        AccountRole::Contract,
+                    );
+                }
+                TransactionPayload::SmartContract(smart_contract, _) => {
+                    let contract_id = QualifiedContractIdentifier::new(
+                        StandardPrincipalData::from(tx.origin_address()),
+                        smart_contract.name.clone(),
+                    );
+                    add(&contract_id.into(), AccountRole::Contract);
+                }
+                TransactionPayload::PoisonMicroblock(..) | TransactionPayload::Coinbase(..) => {}
+            }
+        }
+
+        for event in events.iter() {
+            match event {
+                StacksTransactionEvent::STXEvent(STXEventType::STXTransferEvent(data)) => {
+                    add(&data.sender, AccountRole::Sender);
+                    add(&data.recipient, AccountRole::Recipient);
+                }
+                StacksTransactionEvent::STXEvent(STXEventType::STXMintEvent(data)) => {
+                    add(&data.recipient, AccountRole::Recipient);
+                }
+                StacksTransactionEvent::STXEvent(STXEventType::STXBurnEvent(data)) => {
+                    add(&data.sender, AccountRole::Sender);
+                }
+                StacksTransactionEvent::FTEvent(FTEventType::FTTransferEvent(data)) => {
+                    add(&data.sender, AccountRole::Sender);
+                    add(&data.recipient, AccountRole::Recipient);
+                }
+                StacksTransactionEvent::FTEvent(FTEventType::FTMintEvent(data)) => {
+                    add(&data.recipient, AccountRole::Recipient);
+                }
+                StacksTransactionEvent::FTEvent(FTEventType::FTBurnEvent(data)) => {
+                    add(&data.sender, AccountRole::Sender);
+                }
+                StacksTransactionEvent::NFTEvent(NFTEventType::NFTTransferEvent(data)) => {
+                    add(&data.sender, AccountRole::Sender);
+                    add(&data.recipient, AccountRole::Recipient);
+                }
+                StacksTransactionEvent::NFTEvent(NFTEventType::NFTMintEvent(data)) => {
+                    add(&data.recipient, AccountRole::Recipient);
+                }
+                StacksTransactionEvent::NFTEvent(NFTEventType::NFTBurnEvent(data)) => {
+                    add(&data.sender, AccountRole::Sender);
+                }
+                StacksTransactionEvent::STXEvent(STXEventType::STXLockEvent(..))
+                | StacksTransactionEvent::SmartContractEvent(..) => {}
+            }
+        }
+        roles
+    }
+}
+
+impl FromRow<AccountTransaction> for AccountTransaction {
+    fn from_row<'a>(row: &'a Row) -> Result<AccountTransaction, db_error> {
+        let principal_str: String = row.get_unwrap("principal");
+        let principal = PrincipalData::parse(&principal_str).map_err(|_| db_error::ParseError)?;
+        let txid = Txid::from_column(row, "txid")?;
+        let index_block_hash = StacksBlockId::from_column(row, "index_block_hash")?;
+        let block_height = u64::from_column(row, "block_height")?;
+        let tx_index: i64 = row.get_unwrap("tx_index");
+        let tx_index = u32::try_from(tx_index).map_err(|_| db_error::ParseError)?;
+        let roles_str: String = row.get_unwrap("roles");
+        let roles = roles_str
+            .split(',')
+            .map(|role| AccountRole::from_name(role).ok_or(db_error::ParseError))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(AccountTransaction {
+            principal,
+            txid,
+            index_block_hash,
+            block_height,
+            tx_index,
+            roles,
+        })
+    }
+}
+
+impl StacksChainState {
+    /// Keep (or stop keeping) an index of the transactions touching each principal as blocks
+    /// are processed.  Blocks processed while the index is off are never indexed.
+    pub fn set_account_index(&mut self, enabled: bool) {
+        self.account_index = enabled;
+    }
+
+    pub fn has_account_index(&self) -> bool {
+        self.account_index
+    }
+
+    /// Record the principals touched by the receipts of the block `index_block_hash`
+    pub fn index_account_transactions<'a>(
+        tx: &DBTx,
+        index_block_hash: &StacksBlockId,
+        block_height: u64,
+        tx_receipts: impl Iterator<Item = &'a StacksTransactionReceipt>,
+    ) -> Result<(), Error> {
+        for receipt in tx_receipts {
+            let txid = receipt.transaction.txid();
+            for (principal, roles) in AccountTransaction::principal_roles(receipt).into_iter() {
+                let roles: Vec<_> = roles.iter().map(|role| role.as_str()).collect();
+                let args: &[&dyn ToSql] = &[
+                    &principal.to_string(),
+                    &txid,
+                    index_block_hash,
+                    &u64_to_sql(block_height)?,
+                    &receipt.tx_index,
+                    &roles.join(","),
+                ];
+                tx.execute(
+                    "INSERT OR REPLACE INTO account_transactions (principal, txid, index_block_hash, block_height, tx_index, roles) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                    args,
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Get the transactions touching `principal` in the fork ending at `tip`, newest first.
+    /// Skips the first `offset` of them, and returns at most `limit`.
+    pub fn get_account_transactions(
+        &self,
+        tip: &StacksBlockId,
+        principal: &PrincipalData,
+        offset: u64,
+        limit: u64,
+    ) -> Result<Vec<AccountTransaction>, Error> {
+        let sql = "SELECT * FROM account_transactions WHERE principal = ?1 ORDER BY block_height DESC, tx_index DESC";
+        let args: &[&dyn ToSql] = &[&principal.to_string()];
+        let rows: Vec<AccountTransaction> = query_rows(self.db(), sql, args)?;
+
+        // every fork's blocks are indexed, so only keep the ones which are ancestors of `tip`
+        let index_conn = self.index_conn()?;
+        let mut ancestors: HashMap<u64, Option<StacksBlockId>> = HashMap::new();
+        let mut account_txs = vec![];
+        let mut skipped = 0;
+        for account_tx in rows.into_iter() {
+            if account_txs.len() as u64 >= limit {
+                break;
+            }
+            let ancestor = match ancestors.get(&account_tx.block_height) {
+                Some(ancestor) => ancestor.clone(),
+                None => {
+                    let ancestor =
+                        index_conn.get_ancestor_block_hash(account_tx.block_height, tip)?;
+                    ancestors.insert(account_tx.block_height, ancestor.clone());
+                    ancestor
+                }
+            };
+            if ancestor.as_ref() != Some(&account_tx.index_block_hash) {
+                continue;
+            }
+            if skipped < offset {
+                skipped += 1;
+                continue;
+            }
+            account_txs.push(account_tx);
+        }
+        Ok(account_txs)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::chainstate::stacks::*;
+    use clarity::vm::costs::ExecutionCost;
+    use clarity::vm::events::{FTTransferEventData, STXMintEventData};
+    use clarity::vm::types::AssetIdentifier;
+    use clarity::vm::{ClarityName, ContractName, Value};
+    use stacks_common::types::chainstate::StacksAddress;
+
+    fn make_receipt(
+        tx: StacksTransaction,
+        events: Vec<StacksTransactionEvent>,
+        post_condition_aborted: bool,
+    ) -> StacksTransactionReceipt {
+        StacksTransactionReceipt {
+            transaction: TransactionOrigin::Stacks(tx),
+            events,
+            post_condition_aborted,
+            result: Value::okay_true(),
+            stx_burned: 0,
+            contract_analysis: None,
+            execution_cost: ExecutionCost::zero(),
+            microblock_header: None,
+            tx_index: 0,
+        }
+    }
+
+    #[test]
+    fn test_principal_roles() {
+        let origin_privk = StacksPrivateKey::new();
+        let auth = TransactionAuth::from_p2pkh(&origin_privk).unwrap();
+        let origin: PrincipalData = auth.origin().address_testnet().into();
+        let recipient = PrincipalData::parse("ST1PQHQKV0RJXZFY1DGX8MNSNYVE3VGZJSRTPGZGM").unwrap();
+        let other = PrincipalData::parse("ST2JHG361ZXG51QTKY2NQCVBPPRRE2KZB1HR05NNC").unwrap();
+        let contract_id = QualifiedContractIdentifier::parse(
+            "ST1PQHQKV0RJXZFY1DGX8MNSNYVE3VGZJSRTPGZGM.simple-ft",
+        )
+        .unwrap();
+
+        let transfer = StacksTransaction::new(
+            TransactionVersion::Testnet,
+            auth.clone(),
+            TransactionPayload::TokenTransfer(recipient.clone(), 10, TokenTransferMemo([0; 34])),
+        );
+        let roles = AccountTransaction::principal_roles(&make_receipt(transfer, vec![], false));
+        assert_eq!(roles.len(), 2);
+        assert_eq!(
+            roles[&origin],
+            vec![AccountRole::Origin].into_iter().collect()
+        );
+        assert_eq!(
+            roles[&recipient],
+            vec![AccountRole::Recipient].into_iter().collect()
+        );
+
+        let call = StacksTransaction::new(
+            TransactionVersion::Testnet,
+            auth.clone(),
+            TransactionPayload::ContractCall(TransactionContractCall {
+                address: StacksAddress::from(contract_id.issuer.clone()),
+                contract_name: ContractName::from("simple-ft"),
+                function_name: ClarityName::from("transfer"),
+                function_args: vec![],
+            }),
+        );
+        let events = vec![
+            StacksTransactionEvent::FTEvent(FTEventType::FTTransferEvent(FTTransferEventData {
+                asset_identifier: AssetIdentifier {
+                    contract_identifier: contract_id.clone(),
+                    asset_name: ClarityName::from("ft-token"),
+                },
+                sender: origin.clone(),
+                recipient: other.clone(),
+                amount: 1,
+            })),
+            StacksTransactionEvent::STXEvent(STXEventType::STXMintEvent(STXMintEventData {
+                recipient: other.clone(),
+                amount: 1,
+            })),
+        ];
+        let roles =
+            AccountTransaction::principal_roles(&make_receipt(call.clone(), events.clone(), false));
+        assert_eq!(roles.len(), 3);
+        assert_eq!(
+            roles[&origin],
+            vec![AccountRole::Origin, AccountRole::Sender]
+                .into_iter()
+                .collect()
+        );
+        assert_eq!(
+            roles[&PrincipalData::from(contract_id.clone())],
+            vec![AccountRole::Contract].into_iter().collect()
+        );
+        assert_eq!(
+            roles[&other],
+            vec![AccountRole::Recipient].into_iter().collect()
+        );
+
+        // the events of an aborted transaction were rolled back
+        let roles = AccountTransaction::principal_roles(&make_receipt(call, events, true));
+        assert_eq!(roles.len(), 2);
+        assert!(roles.get(&other).is_none());
+    }
+}
@@ -0,0 +1,280 @@
+// Copyright (C) 2013-2020 Blockstack PBC, a public benefit corporation
+// Copyright (C) 2020 Stacks Open Internet Foundation
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Consensus changes made after a subnet launched take effect at a subnet block height, so that
+//! the blocks before it still replay to the state roots and withdrawal roots they committed to.
+//! Every node of a subnet must use the same heights, so they are recorded in the chainstate when
+//! it is booted, and the recorded heights are used whatever the node is configured with.
+
+use std::convert::TryFrom;
+
+use rusqlite::types::ToSql;
+use rusqlite::Row;
+use rusqlite::NO_PARAMS;
+
+use crate::chainstate::stacks::db::{ClarityTx, StacksChainState};
+use crate::chainstate::stacks::events::StacksTransactionReceipt;
+use crate::chainstate::stacks::{
+    Error, StacksTransaction, TransactionPayload, TransactionSmartContract, TransactionVersion,
+};
+use crate::clarity_vm::clarity::ClarityConnection;
+use crate::util_lib::boot::{boot_code_acc, boot_code_addr, boot_code_tx_auth};
+use crate::util_lib::db::Error as db_error;
+use crate::util_lib::db::{query_row, u64_to_sql, DBConn, DBTx, FromColumn, FromRow};
+use crate::util_lib::strings::StacksString;
+use clarity::vm::costs::LimitedCostTracker;
+use clarity::vm::{ContractName, Value};
+
+/// The subnet block height at which each consensus change takes effect.  None never activates
+/// it.  The first block after genesis is at height 1, so heights below that activate there.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct FeatureActivations {
+    /// the block at this height installs `.time-oracle`, and it and every later block record
+    /// the timestamp of their L1 block in it
+    pub time_oracle: Option<u64>,
+    /// the block at this height installs `.subnet-transfers`, whose `stx-transfer-to-subnet?`
+    /// moves STX to another subnet through the L1 contracts' escrow
+    pub subnet_transfers: Option<u64>,
+    /// from this height on, FT, NFT and SFT deposits whose subnet deposit function can't be run
+    /// at all are refunded with a withdrawal event instead of being dropped
+    pub deposit_refunds: Option<u64>,
+}
+
+impl FromRow<FeatureActivations> for FeatureActivations {
+    fn from_row<'a>(row: &'a Row) -> Result<FeatureActivations, db_error> {
+        let time_oracle = u64::from_column(row, "time_oracle")?;
+        let subnet_transfers = u64::from_column(row, "subnet_transfers")?;
+        let deposit_refunds = u64::from_column(row, "deposit_refunds")?;
+        Ok(FeatureActivations {
+            time_oracle,
+            subnet_transfers,
+            deposit_refunds,
+        })
+    }
+}
+
+fn opt_u64_to_sql(height: Option<u64>) -> Result<Option<i64>, db_error> {
+    match height {
+        Some(height) => Ok(Some(u64_to_sql(height)?)),
+        None => Ok(None),
+    }
+}
+
+/// Is a change activated at `activation_height` in effect at `block_height`?
+fn is_active(activation_height: Option<u64>, block_height: u64) -> bool {
+    match activation_height {
+        Some(height) => block_height >= height.max(1),
+        None => false,
+    }
+}
+
+/// Is `block_height` the first at which a change activated at `activation_height` is in effect?
+fn activates_at(activation_height: Option<u64>, block_height: u64) -> bool {
+    match activation_height {
+        Some(height) => block_height == height.max(1),
+        None => false,
+    }
+}
+
+impl FeatureActivations {
+    /// Does the block at `block_height` record its L1 block's timestamp in `.time-oracle`?
+    pub fn time_oracle_active(&self, block_height: u64) -> bool {
+        is_active(self.time_oracle, block_height)
+    }
+
+    /// Does the block at `block_height` install `.time-oracle`?
+    pub fn installs_time_oracle(&self, block_height: u64) -> bool {
+        activates_at(self.time_oracle, block_height)
+    }
+
+    /// Does the block at `block_height` install `.subnet-transfers`?
+    pub fn installs_subnet_transfers(&self, block_height: u64) -> bool {
+        activates_at(self.subnet_transfers, block_height)
+    }
+
+    /// Is `.subnet-transfers` installed by the block at `block_height` or an earlier one?
+    pub fn subnet_transfers_active(&self, block_height: u64) -> bool {
+        is_active(self.subnet_transfers, block_height)
+    }
+
+    /// Does the block at `block_height` refund deposits whose deposit function can't be run?
+    pub fn deposit_refunds_active(&self, block_height: u64) -> bool {
+        is_active(self.deposit_refunds, block_height)
+    }
+}
+
+impl StacksChainState {
+    /// Record the feature activation heights of a chainstate being booted, or of one booted
+    /// before they were recorded
+    pub fn insert_feature_activations(
+        tx: &DBTx,
+        activations: &FeatureActivations,
+    ) -> Result<(), Error> {
+        let time_oracle = opt_u64_to_sql(activations.time_oracle)?;
+        let subnet_transfers = opt_u64_to_sql(activations.subnet_transfers)?;
+        let deposit_refunds = opt_u64_to_sql(activations.deposit_refunds)?;
+        let args: &[&dyn ToSql] = &[&time_oracle, &subnet_transfers, &deposit_refunds];
+        tx.execute(
+            "INSERT INTO feature_activations (time_oracle,subnet_transfers,deposit_refunds) VALUES (?1,?2,?3)",
+            args,
+        )?;
+        Ok(())
+    }
+
+    /// Get the feature activation heights recorded in the chainstate.  Chainstates booted before
+    /// they were recorded have none until the node records the ones it is configured with.
+    pub fn get_recorded_feature_activations(
+        conn: &DBConn,
+    ) -> Result<Option<FeatureActivations>, Error> {
+        let activations = query_row(conn, "SELECT * FROM feature_activations LIMIT 1", NO_PARAMS)?;
+        Ok(activations)
+    }
+
+    /// Get the feature activation heights in effect.  Nothing activates in a chainstate which has
+    /// none recorded.
+    pub fn get_feature_activations(conn: &DBConn) -> Result<FeatureActivations, Error> {
+        Ok(StacksChainState::get_recorded_feature_activations(conn)?.unwrap_or_default())
+    }
+
+    /// Deploy the boot contract `name` in the block being built, as the boot address and free
+    /// of cost, for changes which add a boot contract once they activate.
+    pub fn install_boot_contract(
+        clarity_tx: &mut ClarityTx,
+        name: &str,
+        code: &str,
+    ) -> Result<StacksTransactionReceipt, Error> {
+        let mainnet = clarity_tx.config.mainnet;
+        let tx_version = if mainnet {
+            TransactionVersion::Mainnet
+        } else {
+            TransactionVersion::Testnet
+        };
+        let boot_code_address = boot_code_addr(mainnet);
+        let boot_code_nonce = clarity_tx
+            .with_clarity_db_readonly(|db| db.get_account_nonce(&boot_code_address.clone().into()));
+        let boot_code_account = boot_code_acc(boot_code_address.clone(), boot_code_nonce);
+
+        let payload = TransactionPayload::SmartContract(
+            TransactionSmartContract {
+                name: ContractName::try_from(name.to_string())
+                    .expect("FATAL: invalid boot-code contract name"),
+                code_body: StacksString::from_str(code).expect("FATAL: invalid boot code body"),
+            },
+            None,
+        );
+        let tx = StacksTransaction::new(tx_version, boot_code_tx_auth(boot_code_address), payload);
+
+        debug!("Instantiate .{} contract", name);
+        let (receipt, _) =
+            clarity_tx.with_temporary_cost_tracker(LimitedCostTracker::new_free(), |clarity_tx| {
+                clarity_tx.connection().as_transaction(|tx_conn| {
+                    StacksChainState::process_transaction_payload(tx_conn, &tx, &boot_code_account)
+                })
+            });
+        let receipt = receipt?;
+        if receipt.result != Value::okay_true() || receipt.post_condition_aborted {
+            return Err(Error::InvalidStacksBlock(format!(
+                "Failed to instantiate .{}: {}",
+                name, &receipt.result
+            )));
+        }
+        Ok(receipt)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::chainstate::stacks::db::test::instantiate_chainstate;
+
+    #[test]
+    fn test_feature_activation_heights() {
+        let activations = FeatureActivations {
+            time_oracle: Some(10),
+            ..FeatureActivations::default()
+        };
+        assert!(!activations.time_oracle_active(9));
+        assert!(!activations.installs_time_oracle(9));
+        assert!(activations.time_oracle_active(10));
+        assert!(activations.installs_time_oracle(10));
+        assert!(activations.time_oracle_active(11));
+        assert!(!activations.installs_time_oracle(11));
+
+        // nothing activates at genesis
+        let activations = FeatureActivations {
+            time_oracle: Some(0),
+            ..FeatureActivations::default()
+        };
+        assert!(!activations.time_oracle_active(0));
+        assert!(activations.installs_time_oracle(1));
+        assert!(activations.time_oracle_active(1));
+        assert!(!activations.installs_subnet_transfers(1));
+        assert!(!activations.deposit_refunds_active(1));
+
+        let activations = FeatureActivations {
+            subnet_transfers: Some(5),
+            ..FeatureActivations::default()
+        };
+        assert!(!activations.subnet_transfers_active(4));
+        assert!(activations.installs_subnet_transfers(5));
+        assert!(activations.subnet_transfers_active(5));
+        assert!(!activations.installs_subnet_transfers(6));
+        assert!(activations.subnet_transfers_active(6));
+
+        let activations = FeatureActivations::default();
+        for height in 0..3 {
+            assert!(!activations.time_oracle_active(height));
+            assert!(!activations.installs_time_oracle(height));
+        }
+    }
+
+    #[test]
+    fn test_record_feature_activations() {
+        let mut chainstate =
+            instantiate_chainstate(false, 0x80000000, "test_record_feature_activations");
+
+        // booted chainstates activate nothing by default
+        assert_eq!(
+            StacksChainState::get_recorded_feature_activations(chainstate.db()).unwrap(),
+            Some(FeatureActivations::default())
+        );
+
+        // chainstates booted before activation heights were recorded have none
+        let tx = chainstate.db_tx_begin().unwrap();
+        tx.execute("DELETE FROM feature_activations", NO_PARAMS)
+            .unwrap();
+        assert_eq!(
+            StacksChainState::get_recorded_feature_activations(&tx).unwrap(),
+            None
+        );
+        assert_eq!(
+            StacksChainState::get_feature_activations(&tx).unwrap(),
+            FeatureActivations::default()
+        );
+        let activations = FeatureActivations {
+            time_oracle: Some(100),
+            subnet_transfers: Some(5),
+            deposit_refunds: None,
+        };
+        StacksChainState::insert_feature_activations(&tx, &activations).unwrap();
+        tx.commit().unwrap();
+
+        assert_eq!(
+            StacksChainState::get_feature_activations(chainstate.db()).unwrap(),
+            activations
+        );
+    }
+}
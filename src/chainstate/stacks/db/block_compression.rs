@@ -0,0 +1,341 @@
+// Copyright (C) 2013-2020 Blockstack PBC, a public benefit corporation
+// Copyright (C) 2020 Stacks Open Internet Foundation
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Compression of the anchored block and microblock bytes the chainstate keeps at rest.
+//!
+//! Compressed data is a zstd frame, and is told apart from uncompressed data by the zstd magic
+//! number it starts with.  Serialized blocks and microblocks start with their header's version
+//! byte, which is never 0x28, so data written before compression was turned on (or after it was
+//! turned off) keeps being read as it is.  Blocks and microblocks are always decompressed before
+//! they are served to peers.
+
+use std::fs;
+use std::io;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::PathBuf;
+
+use rusqlite::types::ToSql;
+use rusqlite::{OpenFlags, NO_PARAMS};
+
+use crate::chainstate::stacks::db::blocks::StagingBlock;
+use crate::chainstate::stacks::db::StacksChainState;
+use crate::chainstate::stacks::Error;
+use crate::codec::MAX_MESSAGE_LEN;
+use crate::util_lib::db::Error as db_error;
+use crate::util_lib::db::{query_row_columns, query_rows, sqlite_open, tx_begin_immediate};
+use stacks_common::types::chainstate::BlockHeaderHash;
+use stacks_common::util::get_epoch_time_ms;
+
+/// The first bytes of every zstd frame
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xb5, 0x2f, 0xfd];
+
+/// zstd's own default level, which compresses blocks well at a small fraction of the cost of
+/// processing them
+const ZSTD_LEVEL: i32 = 3;
+
+/// How the bytes of newly stored blocks and microblocks are compressed
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BlockCompression {
+    None,
+    Zstd,
+}
+
+impl Default for BlockCompression {
+    fn default() -> BlockCompression {
+        BlockCompression::None
+    }
+}
+
+impl BlockCompression {
+    /// The compression called `name` in the node's config, if there is one
+    pub fn from_name(name: &str) -> Option<BlockCompression> {
+        match name {
+            "none" => Some(BlockCompression::None),
+            "zstd" => Some(BlockCompression::Zstd),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            BlockCompression::None => "none",
+            BlockCompression::Zstd => "zstd",
+        }
+    }
+}
+
+/// What a call to `StacksChainState::compact_blocks` rewrote.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct CompactionStats {
+    /// number of anchored blocks whose bytes were rewritten
+    pub blocks_compacted: u64,
+    /// number of microblocks whose bytes were rewritten
+    pub microblocks_compacted: u64,
+    /// bytes of anchored block and microblock data stored before, counting only the blocks and
+    /// microblocks which have any
+    pub bytes_before: u64,
+    /// bytes of the same data stored after
+    pub bytes_after: u64,
+}
+
+impl CompactionStats {
+    /// The share of `bytes_before` which was saved, as a percentage
+    pub fn percent_saved(&self) -> f64 {
+        if self.bytes_before == 0 {
+            return 0.0;
+        }
+        100.0 * (self.bytes_before as f64 - self.bytes_after as f64) / (self.bytes_before as f64)
+    }
+}
+
+/// Is this stored block or microblock data compressed?
+pub fn is_compressed(bytes: &[u8]) -> bool {
+    bytes.starts_with(&ZSTD_MAGIC)
+}
+
+/// Is the stored block or microblock data read from `input` compressed?  `input` is rewound to
+/// its start.
+pub fn is_compressed_stream<R: Read + Seek>(input: &mut R) -> Result<bool, io::Error> {
+    let mut magic = Vec::with_capacity(ZSTD_MAGIC.len());
+    input
+        .by_ref()
+        .take(ZSTD_MAGIC.len() as u64)
+        .read_to_end(&mut magic)?;
+    input.seek(SeekFrom::Start(0))?;
+    Ok(is_compressed(&magic))
+}
+
+/// Compress serialized block or microblock data for storage
+pub fn compress_block_data(
+    compression: BlockCompression,
+    bytes: Vec<u8>,
+) -> Result<Vec<u8>, Error> {
+    match compression {
+        BlockCompression::None => Ok(bytes),
+        BlockCompression::Zstd => zstd::stream::encode_all(&bytes[..], ZSTD_LEVEL)
+            .map_err(|e| Error::DBError(db_error::IOError(e))),
+    }
+}
+
+/// Get back the serialized block or microblock from stored data, which may or may not be
+/// compressed.  Data which decompresses to more than `MAX_MESSAGE_LEN` bytes is corrupt.
+pub fn decompress_block_data(bytes: Vec<u8>) -> Result<Vec<u8>, Error> {
+    if !is_compressed(&bytes) {
+        return Ok(bytes);
+    }
+    let decoder = zstd::stream::read::Decoder::new(&bytes[..])
+        .map_err(|e| Error::DBError(db_error::IOError(e)))?;
+    let mut ret = vec![];
+    decoder
+        .take(MAX_MESSAGE_LEN as u64 + 1)
+        .read_to_end(&mut ret)
+        .map_err(|e| Error::DBError(db_error::IOError(e)))?;
+    if ret.len() > MAX_MESSAGE_LEN as usize {
+        warn!(
+            "Compressed block data decompresses to more than {} bytes",
+            MAX_MESSAGE_LEN
+        );
+        return Err(Error::DBError(db_error::Corruption));
+    }
+    Ok(ret)
+}
+
+/// Re-encode stored block or microblock data with `compression`.  Returns None if it is stored
+/// that way already.
+fn recompress_block_data(
+    compression: BlockCompression,
+    bytes: Vec<u8>,
+) -> Result<Option<Vec<u8>>, Error> {
+    if is_compressed(&bytes) == (compression == BlockCompression::Zstd) {
+        return Ok(None);
+    }
+    compress_block_data(compression, decompress_block_data(bytes)?).map(Some)
+}
+
+impl StacksChainState {
+    pub fn set_block_compression(&mut self, compression: BlockCompression) {
+        self.block_compression = compression;
+    }
+
+    pub fn get_block_compression(&self) -> BlockCompression {
+        self.block_compression
+    }
+
+    /// Rewrite the stored bytes of every anchored block and microblock in the chainstate at
+    /// `path_str` with `compression`, so that storage written before compression was turned on
+    /// takes less space (or, with `BlockCompression::None`, so that it is turned off for good).
+    /// Data already stored with `compression` is left as it is, but still counted.  Invalid and
+    /// pruned blocks have no bytes, and are skipped.
+    ///
+    /// Freed microblock pages are reused by the chainstate database, but are not given back to
+    /// the filesystem until it is vacuumed.
+    ///
+    /// This opens the chainstate's databases directly, so it must not be called while a
+    /// `StacksChainState` instance is open on the same path.
+    pub fn compact_blocks(
+        path_str: &str,
+        compression: BlockCompression,
+    ) -> Result<CompactionStats, Error> {
+        let path = PathBuf::from(path_str);
+        let blocks_path = StacksChainState::blocks_path(path.clone());
+        let blocks_dir = blocks_path
+            .to_str()
+            .ok_or_else(|| Error::DBError(db_error::ParseError))?
+            .to_string();
+        let index_path = StacksChainState::header_index_root_path(path);
+
+        let mut stats = CompactionStats::default();
+        let mut index_conn = sqlite_open(&index_path, OpenFlags::SQLITE_OPEN_READ_WRITE, true)?;
+        let ts_start = get_epoch_time_ms();
+
+        // anchored block bytes
+        let blocks: Vec<StagingBlock> =
+            query_rows(&index_conn, "SELECT * FROM staging_blocks", NO_PARAMS)?;
+        for block in blocks.iter() {
+            let block_path = StacksChainState::get_block_path(
+                &blocks_dir,
+                &block.consensus_hash,
+                &block.anchored_block_hash,
+            )?;
+            let bytes = match fs::read(&block_path) {
+                Ok(bytes) => bytes,
+                Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+                Err(e) => return Err(Error::DBError(db_error::IOError(e))),
+            };
+            if bytes.len() == 0 {
+                // invalid or pruned
+                continue;
+            }
+            let size = bytes.len() as u64;
+            stats.bytes_before += size;
+            match recompress_block_data(compression, bytes)? {
+                Some(new_bytes) => {
+                    StacksChainState::atomic_file_write(&block_path, &new_bytes)?;
+                    stats.blocks_compacted += 1;
+                    stats.bytes_after += new_bytes.len() as u64;
+                }
+                None => {
+                    stats.bytes_after += size;
+                }
+            }
+        }
+
+        // microblock bytes
+        {
+            let tx = tx_begin_immediate(&mut index_conn)?;
+            let microblock_hashes = query_row_columns::<BlockHeaderHash, _>(
+                &tx,
+                "SELECT block_hash FROM staging_microblocks_data WHERE length(block_data) > 0",
+                NO_PARAMS,
+                "block_hash",
+            )?;
+            for microblock_hash in microblock_hashes.iter() {
+                let bytes: Vec<u8> = tx.query_row(
+                    "SELECT block_data FROM staging_microblocks_data WHERE block_hash = ?1",
+                    &[microblock_hash as &dyn ToSql],
+                    |row| row.get(0),
+                )?;
+                let size = bytes.len() as u64;
+                stats.bytes_before += size;
+                match recompress_block_data(compression, bytes)? {
+                    Some(new_bytes) => {
+                        tx.execute(
+                            "UPDATE staging_microblocks_data SET block_data = ?1 WHERE block_hash = ?2",
+                            &[&new_bytes as &dyn ToSql, microblock_hash],
+                        )?;
+                        stats.microblocks_compacted += 1;
+                        stats.bytes_after += new_bytes.len() as u64;
+                    }
+                    None => {
+                        stats.bytes_after += size;
+                    }
+                }
+            }
+            tx.commit()?;
+        }
+
+        info!("Compacted block storage";
+              "compression" => compression.name(),
+              "blocks_compacted" => stats.blocks_compacted,
+              "microblocks_compacted" => stats.microblocks_compacted,
+              "bytes_before" => stats.bytes_before,
+              "bytes_after" => stats.bytes_after,
+              "compact_time_ms" => get_epoch_time_ms().saturating_sub(ts_start));
+
+        Ok(stats)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::chainstate::stacks::db::test::instantiate_chainstate;
+
+    #[test]
+    fn test_block_data_compression_roundtrip() {
+        // serialized blocks start with a version byte of 0
+        let bytes: Vec<u8> = [0u8].iter().chain([0xab; 4096].iter()).cloned().collect();
+
+        let stored = compress_block_data(BlockCompression::None, bytes.clone()).unwrap();
+        assert_eq!(stored, bytes);
+        assert!(!is_compressed(&stored));
+        assert_eq!(decompress_block_data(stored).unwrap(), bytes);
+
+        let stored = compress_block_data(BlockCompression::Zstd, bytes.clone()).unwrap();
+        assert!(is_compressed(&stored));
+        assert!(stored.len() < bytes.len());
+        assert!(is_compressed_stream(&mut io::Cursor::new(&stored)).unwrap());
+        assert_eq!(decompress_block_data(stored.clone()).unwrap(), bytes);
+
+        // switching back decompresses, and data stored as asked for is left alone
+        assert_eq!(
+            recompress_block_data(BlockCompression::None, stored.clone()).unwrap(),
+            Some(bytes.clone())
+        );
+        assert_eq!(
+            recompress_block_data(BlockCompression::Zstd, stored).unwrap(),
+            None
+        );
+        assert_eq!(
+            recompress_block_data(BlockCompression::None, bytes).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_decompress_block_data_bounded() {
+        let bytes = vec![0u8; MAX_MESSAGE_LEN as usize + 1];
+        let stored = compress_block_data(BlockCompression::Zstd, bytes).unwrap();
+        match decompress_block_data(stored) {
+            Err(Error::DBError(db_error::Corruption)) => {}
+            x => panic!(
+                "expected corrupt block data, got {:?}",
+                x.map(|bytes| bytes.len())
+            ),
+        }
+    }
+
+    #[test]
+    fn test_compact_blocks_empty() {
+        let chainstate = instantiate_chainstate(false, 0x80000000, "test_compact_blocks_empty");
+        let path = chainstate.root_path.clone();
+        drop(chainstate);
+
+        let stats = StacksChainState::compact_blocks(&path, BlockCompression::Zstd).unwrap();
+        assert_eq!(stats, CompactionStats::default());
+        assert_eq!(stats.percent_saved(), 0.0);
+    }
+}
@@ -0,0 +1,599 @@
+// Copyright (C) 2013-2020 Blockstack PBC, a public benefit corporation
+// Copyright (C) 2020 Stacks Open Internet Foundation
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::HashMap;
+
+use rusqlite::types::ToSql;
+use rusqlite::Row;
+use serde::de::Error as DeserError;
+use serde::{Deserialize, Deserializer, Serializer};
+
+use crate::chainstate::burn::db::sortdb::SortitionDB;
+use crate::chainstate::stacks::db::StacksChainState;
+use crate::chainstate::stacks::events::{StacksTransactionReceipt, TransactionOrigin};
+use crate::chainstate::stacks::{
+    Error, StacksBlockHeader, StacksTransaction, TransactionPayload, MINER_BLOCK_CONSENSUS_HASH,
+    MINER_BLOCK_HEADER_HASH,
+};
+use crate::util_lib::db::Error as db_error;
+use crate::util_lib::db::{query_rows, u64_to_sql, DBConn, DBTx, FromColumn, FromRow};
+use clarity::vm::costs::ExecutionCost;
+use clarity::vm::types::{QualifiedContractIdentifier, StandardPrincipalData};
+use stacks_common::types::chainstate::{BlockHeaderHash, ConsensusHash, StacksBlockId};
+
+/// How many blocks of per-contract costs the `contract_costs` table keeps.  Each processed
+/// block deletes the rows of blocks this far below it.
+pub const CONTRACT_COSTS_HISTORY_BLOCKS: u64 = 1024;
+
+/// The fraction of a block limit used in each cost dimension
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CostUtilization {
+    pub runtime: f64,
+    pub read_count: f64,
+    pub read_length: f64,
+    pub write_count: f64,
+    pub write_length: f64,
+}
+
+impl CostUtilization {
+    pub fn new(cost: &ExecutionCost, limit: &ExecutionCost) -> CostUtilization {
+        let fraction = |used: u64, limit: u64| {
+            if limit == 0 {
+                0.0
+            } else {
+                used as f64 / limit as f64
+            }
+        };
+        CostUtilization {
+            runtime: fraction(cost.runtime, limit.runtime),
+            read_count: fraction(cost.read_count, limit.read_count),
+            read_length: fraction(cost.read_length, limit.read_length),
+            write_count: fraction(cost.write_count, limit.write_count),
+            write_length: fraction(cost.write_length, limit.write_length),
+        }
+    }
+
+    /// The utilization of the most constrained dimension
+    pub fn max(&self) -> f64 {
+        [
+            self.runtime,
+            self.read_count,
+            self.read_length,
+            self.write_count,
+            self.write_length,
+        ]
+        .iter()
+        .fold(0.0, |acc, x| if *x > acc { *x } else { acc })
+    }
+}
+
+/// The stored execution cost of one anchored block
+#[derive(Debug, Clone, PartialEq)]
+pub struct BlockCost {
+    pub index_block_hash: StacksBlockId,
+    pub consensus_hash: ConsensusHash,
+    pub block_hash: BlockHeaderHash,
+    pub parent_consensus_hash: ConsensusHash,
+    pub parent_block_hash: BlockHeaderHash,
+    pub block_height: u64,
+    pub cost: ExecutionCost,
+}
+
+/// The execution cost attributed to one contract over a range of blocks
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ContractCost {
+    #[serde(
+        serialize_with = "ser_contract_id",
+        deserialize_with = "deser_contract_id"
+    )]
+    pub contract_id: QualifiedContractIdentifier,
+    /// number of transactions which published or called the contract
+    pub num_txs: u64,
+    pub cost: ExecutionCost,
+}
+
+/// The contracts which consumed the most of the canonical fork's recent blocks
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecentContractCosts {
+    pub canonical_stacks_tip: StacksBlockId,
+    /// how many blocks back from the tip the costs were summed over
+    pub num_blocks: u64,
+    /// highest runtime first
+    pub contracts: Vec<ContractCost>,
+}
+
+impl ContractCost {
+    /// The contract `tx` publishes or calls, if any
+    pub fn contract_of(tx: &StacksTransaction) -> Option<QualifiedContractIdentifier> {
+        match tx.payload {
+            TransactionPayload::ContractCall(ref contract_call) => {
+                Some(contract_call.to_clarity_contract_id())
+            }
+            TransactionPayload::SmartContract(ref smart_contract, _) => {
+                Some(QualifiedContractIdentifier::new(
+                    StandardPrincipalData::from(tx.origin_address()),
+                    smart_contract.name.clone(),
+                ))
+            }
+            _ => None,
+        }
+    }
+
+    /// Sum the execution costs of `txs` by the contract each one publishes or calls, highest
+    /// runtime first.  Transactions which touch no contract are left out.
+    pub fn aggregate<'a, I>(txs: I) -> Vec<ContractCost>
+    where
+        I: IntoIterator<Item = (&'a StacksTransaction, &'a ExecutionCost)>,
+    {
+        let mut contract_costs = HashMap::new();
+        for (tx, cost) in txs {
+            if let Some(contract_id) = ContractCost::contract_of(tx) {
+                add_contract_cost(&mut contract_costs, contract_id, 1, cost);
+            }
+        }
+        sort_by_runtime(contract_costs)
+    }
+}
+
+impl FromRow<ContractCost> for ContractCost {
+    fn from_row<'a>(row: &'a Row) -> Result<ContractCost, db_error> {
+        let contract_id_str: String = row.get_unwrap("contract_id");
+        let contract_id = QualifiedContractIdentifier::parse(&contract_id_str)
+            .map_err(|_| db_error::ParseError)?;
+        Ok(ContractCost {
+            contract_id,
+            num_txs: u64::from_column(row, "num_txs")?,
+            cost: ExecutionCost {
+                runtime: u64::from_column(row, "runtime")?,
+                read_count: u64::from_column(row, "read_count")?,
+                read_length: u64::from_column(row, "read_length")?,
+                write_count: u64::from_column(row, "write_count")?,
+                write_length: u64::from_column(row, "write_length")?,
+            },
+        })
+    }
+}
+
+impl StacksChainState {
+    /// Get the stored anchored-block costs of up to `num_blocks` blocks, walking back from the
+    /// canonical Stacks chain tip.  Blocks are returned tip first.  The boot block is never
+    /// included.
+    pub fn get_recent_block_costs(
+        &self,
+        sortdb: &SortitionDB,
+        num_blocks: u64,
+    ) -> Result<Vec<BlockCost>, Error> {
+        let (tip_consensus_hash, tip_block_hash) =
+            SortitionDB::get_canonical_stacks_chain_tip_hash(sortdb.conn())?;
+        let mut block_id =
+            StacksBlockHeader::make_index_block_hash(&tip_consensus_hash, &tip_block_hash);
+
+        let mut block_costs = vec![];
+        while (block_costs.len() as u64) < num_blocks {
+            let header = match StacksChainState::get_stacks_block_header_info_by_index_block_hash(
+                self.db(),
+                &block_id,
+            )? {
+                Some(header) => header,
+                None => break,
+            };
+            if header.stacks_block_height == 0 {
+                break;
+            }
+            let cost = StacksChainState::get_stacks_block_anchored_cost(self.db(), &block_id)?
+                .ok_or(Error::NoSuchBlockError)?;
+            let parent_id = StacksChainState::get_parent_block_id(self.db(), &block_id)?
+                .ok_or(Error::NoSuchBlockError)?;
+            let parent_header = StacksChainState::get_stacks_block_header_info_by_index_block_hash(
+                self.db(),
+                &parent_id,
+            )?
+            .ok_or(Error::NoSuchBlockError)?;
+
+            block_costs.push(BlockCost {
+                index_block_hash: block_id,
+                consensus_hash: header.consensus_hash.clone(),
+                block_hash: header.anchored_header.block_hash(),
+                parent_consensus_hash: parent_header.consensus_hash.clone(),
+                parent_block_hash: parent_header.anchored_header.block_hash(),
+                block_height: header.stacks_block_height,
+                cost,
+            });
+            block_id = parent_id;
+        }
+        Ok(block_costs)
+    }
+
+    /// Attribute the cost of the transactions in `blocks` to the contracts they published or
+    /// called.  Each block's anchored transactions are re-executed against its parent's state
+    /// and the changes are rolled back, so this also covers blocks older than the recorded
+    /// costs `get_recent_contract_costs` reads.  This is an
+    /// approximation: the block's L1 operations and the parent microblocks it confirmed are not
+    /// applied first.  Contracts are returned most expensive first, as measured by the share of
+    /// `limit` used in their most constrained dimension.
+    pub fn get_contract_costs(
+        &mut self,
+        sortdb: &SortitionDB,
+        blocks: &[BlockCost],
+        limit: &ExecutionCost,
+    ) -> Result<Vec<ContractCost>, Error> {
+        let mut contract_costs: HashMap<QualifiedContractIdentifier, ContractCost> = HashMap::new();
+        for block_cost in blocks.iter() {
+            let block = match StacksChainState::load_block(
+                &self.blocks_path,
+                &block_cost.consensus_hash,
+                &block_cost.block_hash,
+            ) {
+                Ok(Some(block)) => block,
+                Ok(None) | Err(_) => {
+                    warn!(
+                        "Block {} has no data; skipping it in contract costs",
+                        &block_cost.index_block_hash
+                    );
+                    continue;
+                }
+            };
+
+            let burn_dbconn = sortdb.index_conn();
+            let mut clarity_tx = self.block_begin(
+                &burn_dbconn,
+                &block_cost.parent_consensus_hash,
+                &block_cost.parent_block_hash,
+                &MINER_BLOCK_CONSENSUS_HASH,
+                &MINER_BLOCK_HEADER_HASH,
+            );
+            for tx in block.txs.iter() {
+                let contract_id = match ContractCost::contract_of(tx) {
+                    Some(contract_id) => contract_id,
+                    None => {
+                        continue;
+                    }
+                };
+                match StacksChainState::process_transaction(&mut clarity_tx, tx, true) {
+                    Ok((_, receipt)) => {
+                        add_contract_cost(
+                            &mut contract_costs,
+                            contract_id,
+                            1,
+                            &receipt.execution_cost,
+                        );
+                    }
+                    Err(e) => {
+                        debug!(
+                            "Transaction {} in block {} did not re-execute: {:?}",
+                            &tx.txid(),
+                            &block_cost.index_block_hash,
+                            &e
+                        );
+                    }
+                }
+            }
+            clarity_tx.rollback_block();
+        }
+
+        let mut contract_costs: Vec<_> = contract_costs.into_iter().map(|(_, v)| v).collect();
+        contract_costs.sort_by(|a, b| {
+            let a_share = CostUtilization::new(&a.cost, limit).max();
+            let b_share = CostUtilization::new(&b.cost, limit).max();
+            b_share
+                .partial_cmp(&a_share)
+                .unwrap_or(std::cmp::Ordering::Equal)
+                .then_with(|| a.contract_id.to_string().cmp(&b.contract_id.to_string()))
+        });
+        Ok(contract_costs)
+    }
+
+    /// Record the execution cost of each contract's transactions in the block `index_block_hash`
+    /// at `block_height`, from the receipts of the transactions it processed, and delete the
+    /// rows of blocks `CONTRACT_COSTS_HISTORY_BLOCKS` or more below it.
+    pub fn store_contract_costs<'a, I>(
+        tx: &DBTx,
+        index_block_hash: &StacksBlockId,
+        block_height: u64,
+        receipts: I,
+    ) -> Result<(), Error>
+    where
+        I: Iterator<Item = &'a StacksTransactionReceipt>,
+    {
+        let contract_costs =
+            ContractCost::aggregate(receipts.filter_map(|receipt| match receipt.transaction {
+                TransactionOrigin::Stacks(ref stacks_tx) => {
+                    Some((stacks_tx, &receipt.execution_cost))
+                }
+                TransactionOrigin::Burn(..) => None,
+            }));
+        for contract_cost in contract_costs.iter() {
+            let args: &[&dyn ToSql] = &[
+                index_block_hash,
+                &u64_to_sql(block_height)?,
+                &contract_cost.contract_id.to_string(),
+                &u64_to_sql(contract_cost.num_txs)?,
+                &u64_to_sql(contract_cost.cost.runtime)?,
+                &u64_to_sql(contract_cost.cost.read_count)?,
+                &u64_to_sql(contract_cost.cost.read_length)?,
+                &u64_to_sql(contract_cost.cost.write_count)?,
+                &u64_to_sql(contract_cost.cost.write_length)?,
+            ];
+            tx.execute(
+                "INSERT OR REPLACE INTO contract_costs (index_block_hash, block_height, contract_id, num_txs, runtime, read_count, read_length, write_count, write_length) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+                args,
+            )?;
+        }
+
+        let min_height = block_height.saturating_sub(CONTRACT_COSTS_HISTORY_BLOCKS - 1);
+        let args: &[&dyn ToSql] = &[&u64_to_sql(min_height)?];
+        tx.execute("DELETE FROM contract_costs WHERE block_height < ?1", args)?;
+        Ok(())
+    }
+
+    /// Get the execution cost recorded for each contract in the block `index_block_hash`,
+    /// highest runtime first.  Blocks older than the table's history have none.
+    pub fn get_block_contract_costs(
+        conn: &DBConn,
+        index_block_hash: &StacksBlockId,
+    ) -> Result<Vec<ContractCost>, db_error> {
+        let sql = "SELECT * FROM contract_costs WHERE index_block_hash = ?1 ORDER BY runtime DESC, contract_id ASC";
+        let args: &[&dyn ToSql] = &[index_block_hash];
+        query_rows(conn, sql, args)
+    }
+
+    /// Sum the execution cost recorded for each contract over up to `num_blocks` blocks, walking
+    /// back from the canonical Stacks chain tip.  At most `CONTRACT_COSTS_HISTORY_BLOCKS` blocks
+    /// are summed, and the boot block is never included.  Unlike `get_contract_costs`, nothing is
+    /// re-executed: the costs were recorded when the blocks were processed.
+    pub fn get_recent_contract_costs(
+        &self,
+        sortdb: &SortitionDB,
+        num_blocks: u64,
+    ) -> Result<RecentContractCosts, Error> {
+        let (tip_consensus_hash, tip_block_hash) =
+            SortitionDB::get_canonical_stacks_chain_tip_hash(sortdb.conn())?;
+        let canonical_stacks_tip =
+            StacksBlockHeader::make_index_block_hash(&tip_consensus_hash, &tip_block_hash);
+        let num_blocks = num_blocks.min(CONTRACT_COSTS_HISTORY_BLOCKS);
+
+        let mut contract_costs = HashMap::new();
+        let mut blocks_summed = 0;
+        let mut block_id = canonical_stacks_tip.clone();
+        while blocks_summed < num_blocks {
+            let header = match StacksChainState::get_stacks_block_header_info_by_index_block_hash(
+                self.db(),
+                &block_id,
+            )? {
+                Some(header) => header,
+                None => break,
+            };
+            if header.stacks_block_height == 0 {
+                break;
+            }
+            for contract_cost in
+                StacksChainState::get_block_contract_costs(self.db(), &block_id)?.into_iter()
+            {
+                add_contract_cost(
+                    &mut contract_costs,
+                    contract_cost.contract_id,
+                    contract_cost.num_txs,
+                    &contract_cost.cost,
+                );
+            }
+            blocks_summed += 1;
+            block_id = match StacksChainState::get_parent_block_id(self.db(), &block_id)? {
+                Some(parent_id) => parent_id,
+                None => break,
+            };
+        }
+
+        Ok(RecentContractCosts {
+            canonical_stacks_tip,
+            num_blocks: blocks_summed,
+            contracts: sort_by_runtime(contract_costs),
+        })
+    }
+}
+
+fn add_contract_cost(
+    contract_costs: &mut HashMap<QualifiedContractIdentifier, ContractCost>,
+    contract_id: QualifiedContractIdentifier,
+    num_txs: u64,
+    cost: &ExecutionCost,
+) {
+    let entry = contract_costs
+        .entry(contract_id.clone())
+        .or_insert_with(|| ContractCost {
+            contract_id,
+            num_txs: 0,
+            cost: ExecutionCost::zero(),
+        });
+    entry.num_txs += num_txs;
+    entry.cost = sum_costs(&entry.cost, cost);
+}
+
+fn sort_by_runtime(
+    contract_costs: HashMap<QualifiedContractIdentifier, ContractCost>,
+) -> Vec<ContractCost> {
+    let mut contract_costs: Vec<_> = contract_costs.into_iter().map(|(_, v)| v).collect();
+    contract_costs.sort_by(|a, b| {
+        b.cost
+            .runtime
+            .cmp(&a.cost.runtime)
+            .then_with(|| a.contract_id.to_string().cmp(&b.contract_id.to_string()))
+    });
+    contract_costs
+}
+
+fn ser_contract_id<S>(contract_id: &QualifiedContractIdentifier, s: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    s.serialize_str(&contract_id.to_string())
+}
+
+fn deser_contract_id<'de, D>(deser: D) -> Result<QualifiedContractIdentifier, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let contract_id = String::deserialize(deser)?;
+    QualifiedContractIdentifier::parse(&contract_id).map_err(DeserError::custom)
+}
+
+fn sum_costs(a: &ExecutionCost, b: &ExecutionCost) -> ExecutionCost {
+    ExecutionCost {
+        runtime: a.runtime.saturating_add(b.runtime),
+        read_count: a.read_count.saturating_add(b.read_count),
+        read_length: a.read_length.saturating_add(b.read_length),
+        write_count: a.write_count.saturating_add(b.write_count),
+        write_length: a.write_length.saturating_add(b.write_length),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::chainstate::stacks::db::test::instantiate_chainstate;
+    use crate::chainstate::stacks::*;
+    use crate::core::SUBNET_BLOCK_LIMIT;
+    use clarity::vm::{ClarityName, ContractName, Value};
+    use stacks_common::types::chainstate::StacksAddress;
+
+    fn make_call_receipt(contract_name: &str, runtime: u64) -> StacksTransactionReceipt {
+        let auth = TransactionAuth::from_p2pkh(&StacksPrivateKey::new()).unwrap();
+        let call = StacksTransaction::new(
+            TransactionVersion::Testnet,
+            auth,
+            TransactionPayload::ContractCall(TransactionContractCall {
+                address: StacksAddress::from_string("ST1PQHQKV0RJXZFY1DGX8MNSNYVE3VGZJSRTPGZGM")
+                    .unwrap(),
+                contract_name: ContractName::from(contract_name),
+                function_name: ClarityName::from("transfer"),
+                function_args: vec![],
+            }),
+        );
+        StacksTransactionReceipt {
+            transaction: TransactionOrigin::Stacks(call),
+            events: vec![],
+            post_condition_aborted: false,
+            result: Value::okay_true(),
+            stx_burned: 0,
+            contract_analysis: None,
+            execution_cost: ExecutionCost {
+                runtime,
+                read_count: 1,
+                read_length: 0,
+                write_count: 0,
+                write_length: 0,
+            },
+            microblock_header: None,
+            tx_index: 0,
+        }
+    }
+
+    #[test]
+    fn test_cost_utilization() {
+        let cost = ExecutionCost {
+            runtime: SUBNET_BLOCK_LIMIT.runtime / 4,
+            read_count: SUBNET_BLOCK_LIMIT.read_count / 2,
+            read_length: 0,
+            write_count: 0,
+            write_length: SUBNET_BLOCK_LIMIT.write_length,
+        };
+        let utilization = CostUtilization::new(&cost, &SUBNET_BLOCK_LIMIT);
+        assert_eq!(utilization.read_length, 0.0);
+        assert_eq!(utilization.write_length, 1.0);
+        assert_eq!(utilization.max(), 1.0);
+        assert!((utilization.runtime - 0.25).abs() < 1e-6);
+        assert!((utilization.read_count - 0.5).abs() < 1e-6);
+
+        // a zero limit is not a division by zero
+        let utilization = CostUtilization::new(&cost, &ExecutionCost::zero());
+        assert_eq!(utilization.max(), 0.0);
+    }
+
+    #[test]
+    fn test_recent_block_costs_genesis() {
+        let mut chainstate =
+            instantiate_chainstate(false, 0x80000000, "test_recent_block_costs_genesis");
+        let sortdb = SortitionDB::connect_test(0).unwrap();
+
+        // only the boot block has been processed, so there is nothing to report
+        let block_costs = chainstate.get_recent_block_costs(&sortdb, 10).unwrap();
+        assert!(block_costs.is_empty());
+        let contract_costs = chainstate
+            .get_contract_costs(&sortdb, &block_costs, &SUBNET_BLOCK_LIMIT)
+            .unwrap();
+        assert!(contract_costs.is_empty());
+        let recent = chainstate.get_recent_contract_costs(&sortdb, 10).unwrap();
+        assert_eq!(recent.num_blocks, 0);
+        assert!(recent.contracts.is_empty());
+    }
+
+    #[test]
+    fn test_store_contract_costs() {
+        let mut chainstate = instantiate_chainstate(false, 0x80000000, "test_store_contract_costs");
+        let old_block = StacksBlockId([0x01; 32]);
+        let new_block = StacksBlockId([0x02; 32]);
+        let receipts = vec![
+            make_call_receipt("cheap", 10),
+            make_call_receipt("costly", 100),
+            make_call_receipt("cheap", 20),
+        ];
+
+        let tx = chainstate.db_tx_begin().unwrap();
+        StacksChainState::store_contract_costs(&tx, &old_block, 1, receipts.iter()).unwrap();
+        tx.commit().unwrap();
+
+        let contract_costs =
+            StacksChainState::get_block_contract_costs(chainstate.db(), &old_block).unwrap();
+        assert_eq!(contract_costs.len(), 2);
+        assert_eq!(contract_costs[0].contract_id.name.as_str(), "costly");
+        assert_eq!(contract_costs[0].num_txs, 1);
+        assert_eq!(contract_costs[0].cost.runtime, 100);
+        assert_eq!(contract_costs[1].contract_id.name.as_str(), "cheap");
+        assert_eq!(contract_costs[1].num_txs, 2);
+        assert_eq!(contract_costs[1].cost.runtime, 30);
+        assert_eq!(contract_costs[1].cost.read_count, 2);
+
+        // the costs round-trip through JSON with the contract as a string
+        let json = serde_json::to_value(&contract_costs[1]).unwrap();
+        assert_eq!(
+            json["contract_id"],
+            "ST1PQHQKV0RJXZFY1DGX8MNSNYVE3VGZJSRTPGZGM.cheap"
+        );
+        let parsed: ContractCost = serde_json::from_value(json).unwrap();
+        assert_eq!(parsed, contract_costs[1]);
+
+        // a block out of the history's reach deletes the old block's rows
+        let tx = chainstate.db_tx_begin().unwrap();
+        StacksChainState::store_contract_costs(
+            &tx,
+            &new_block,
+            1 + CONTRACT_COSTS_HISTORY_BLOCKS,
+            receipts[..1].iter(),
+        )
+        .unwrap();
+        tx.commit().unwrap();
+        assert!(
+            StacksChainState::get_block_contract_costs(chainstate.db(), &old_block)
+                .unwrap()
+                .is_empty()
+        );
+        assert_eq!(
+            StacksChainState::get_block_contract_costs(chainstate.db(), &new_block)
+                .unwrap()
+                .len(),
+            1
+        );
+    }
+}
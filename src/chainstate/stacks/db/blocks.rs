@@ -36,7 +36,17 @@ use crate::chainstate::burn::db::sortdb::*;
 use crate::chainstate::burn::operations::*;
 use crate::chainstate::burn::BlockSnapshot;
 use crate::chainstate::stacks::db::accounts::MinerReward;
+use crate::chainstate::stacks::db::block_compression::{
+    compress_block_data, decompress_block_data, is_compressed_stream, BlockCompression,
+};
+use crate::chainstate::stacks::db::coinbase_schedule::CoinbaseSchedule;
+use crate::chainstate::stacks::db::commits::{commit_confirmations, BlockCommitLink};
+use crate::chainstate::stacks::db::fee_market::FeeRateFloor;
+use crate::chainstate::stacks::db::prevalidate::BlockPrevalidator;
 use crate::chainstate::stacks::db::transactions::TransactionNonceMismatch;
+use crate::chainstate::stacks::db::withdrawal_policy::{
+    create_withdrawal_merkle_tree_with_policy, QueuedWithdrawal,
+};
 use crate::chainstate::stacks::db::*;
 use crate::chainstate::stacks::index::MarfTrieId;
 use crate::chainstate::stacks::Error;
@@ -80,20 +90,29 @@ use stacks_common::util::retry::BoundReader;
 
 use crate::chainstate::coordinator::BlockEventDispatcher;
 use crate::chainstate::stacks::address::StacksAddressExtensions;
+use crate::chainstate::stacks::boot::{
+    BOOT_CODE_SUBNET_TOKEN, BOOT_CODE_SUBNET_TRANSFERS, BOOT_CODE_TIME_ORACLE, SUBNET_TOKEN_NAME,
+    SUBNET_TRANSFERS_NAME, TIME_ORACLE_NAME,
+};
 use crate::chainstate::stacks::Error::NoSuchBlockError;
 use crate::chainstate::stacks::StacksBlockHeader;
 use crate::chainstate::stacks::StacksMicroblockHeader;
-use crate::clarity_vm::withdrawal::create_withdrawal_merkle_tree;
 use crate::monitoring::set_last_execution_cost_observed;
 use crate::util_lib::boot::boot_code_id;
 use crate::{types, util};
 
 use clarity::vm::ClarityVersion;
 use rusqlite::types::ToSqlOutput;
-use stacks_common::types::chainstate::{StacksAddress, StacksBlockId};
+use stacks_common::types::chainstate::{SortitionId, StacksAddress, StacksBlockId};
 
 static DEPOSIT_FUNCTION_NAME: &str = "deposit-from-burnchain";
+static DEPOSIT_NFT_WITH_METADATA_FUNCTION_NAME: &str = "deposit-from-burnchain-with-metadata";
+/// Longest token URI accepted by `deposit-from-burnchain-with-metadata`
+const MAX_NFT_TOKEN_URI_LEN: usize = 256;
 static REGISTER_ASSET_FUNCTION_NAME: &str = "register-asset-contract";
+static REGISTER_DESTINATION_SUBNET_FUNCTION_NAME: &str = "register-destination-subnet";
+static SET_BURN_BLOCK_TIME_FUNCTION_NAME: &str = "set-burn-block-time";
+static MINT_COINBASE_FUNCTION_NAME: &str = "mint-coinbase";
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct StagingMicroblock {
@@ -163,9 +182,31 @@ pub enum MemPoolRejection {
     BadTransactionVersion,
     TransferRecipientIsSender(PrincipalData),
     TransferAmountMustBePositive,
+    ContractPublishNotAllowed(StacksAddress),
+    /// the transaction's size, and the most the mempool admits
+    TransactionTooLarge(u64, u64),
+    /// the name of the payload type the mempool does not admit
+    PayloadTypeNotAllowed(String),
+    /// the contract's code size, and the most the mempool admits
+    ContractCodeTooLarge(u64, u64),
+    /// the contract's code size, which publishing it writes at least, and the most the miner
+    /// lets a transaction write
+    ContractPublishTooExpensive(u64, u64),
+    /// the expiration height the transaction was submitted with, and the chain tip's height
+    AlreadyExpired(u64, u64),
+    /// the bundle ID the transaction was submitted with, and the origin of the transactions
+    /// already in that bundle
+    BundleOriginMismatch(String, StacksAddress),
+    /// the ID of the operator's filter which matched the transaction, and the filter's reason
+    Filtered(u64, String),
     DBError(db_error),
     EstimatorError(EstimatorError),
     Other(String),
+    /// A rejection remembered by the mempool's admission cache, by its reason code and data
+    Cached {
+        reason: String,
+        reason_data: Option<serde_json::Value>,
+    },
 }
 
 pub struct SetupBlockResult<'a, 'b> {
@@ -181,6 +222,51 @@ pub struct SetupBlockResult<'a, 'b> {
     pub applied_epoch_transition: bool,
 }
 
+/// What processing a proposed block would do, from `StacksChainState::validate_proposed_block`
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProposedBlockVerdict {
+    /// why the block would be rejected, or `None` if it would be accepted
+    pub invalid_reason: Option<String>,
+    /// index into the block's transactions, txid and error of the transaction that failed
+    pub first_invalid_tx: Option<(u32, Txid, String)>,
+    /// the state root the block produces, if processing got far enough to compute it
+    pub state_index_root: Option<TrieHash>,
+    /// the withdrawal root the block produces, if processing got far enough to compute it
+    pub withdrawal_merkle_root: Option<Sha512Trunc256Sum>,
+    /// the execution cost of the block, up to where processing stopped
+    pub cost: ExecutionCost,
+}
+
+/// What re-executing a processed block with `StacksChainState::reexecute_block_for_events`
+/// produced: what `append_block` announced to the event dispatcher when it processed the block
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReexecutedBlock {
+    /// in the order `append_block` reports them
+    pub tx_receipts: Vec<StacksTransactionReceipt>,
+    pub matured_rewards: Vec<MinerReward>,
+    pub matured_rewards_info: Option<MinerRewardInfo>,
+    pub anchored_block_cost: ExecutionCost,
+    pub parent_microblocks_cost: ExecutionCost,
+    pub withdrawals_queued: Vec<QueuedWithdrawal>,
+    pub withdrawals_released: Vec<QueuedWithdrawal>,
+}
+
+impl ProposedBlockVerdict {
+    fn rejected(reason: String) -> ProposedBlockVerdict {
+        ProposedBlockVerdict {
+            invalid_reason: Some(reason),
+            first_invalid_tx: None,
+            state_index_root: None,
+            withdrawal_merkle_root: None,
+            cost: ExecutionCost::zero(),
+        }
+    }
+
+    pub fn is_valid(&self) -> bool {
+        self.invalid_reason.is_none()
+    }
+}
+
 pub struct DummyEventDispatcher;
 
 impl BlockEventDispatcher for DummyEventDispatcher {
@@ -198,6 +284,8 @@ impl BlockEventDispatcher for DummyEventDispatcher {
         _parent_burn_block_timestamp: u64,
         _anchor_block_cost: &ExecutionCost,
         _confirmed_mblock_cost: &ExecutionCost,
+        _withdrawals_queued: &Vec<QueuedWithdrawal>,
+        _withdrawals_released: &Vec<QueuedWithdrawal>,
     ) {
         assert!(
             false,
@@ -219,6 +307,18 @@ impl BlockEventDispatcher for DummyEventDispatcher {
         );
     }
 
+    fn announce_rejected_block(
+        &self,
+        _consensus_hash: &ConsensusHash,
+        _block_hash: &BlockHeaderHash,
+        _reason: &str,
+    ) {
+        assert!(
+            false,
+            "We should never try to announce to the dummy dispatcher"
+        );
+    }
+
     fn dispatch_boot_receipts(&mut self, _receipts: Vec<StacksTransactionReceipt>) {
         assert!(
             false,
@@ -228,9 +328,35 @@ impl BlockEventDispatcher for DummyEventDispatcher {
 }
 
 impl MemPoolRejection {
-    pub fn into_json(self, txid: &Txid) -> serde_json::Value {
+    /// Can this rejection be remembered, and given again for the same transaction at the same
+    /// chain tip?  Rejections which depend on the rest of the mempool, on the node's settings,
+    /// or on transient failures cannot.
+    pub fn is_cacheable(&self) -> bool {
+        use self::MemPoolRejection::*;
+        match self {
+            ConflictingNonceInMempool
+            | TooMuchChaining { .. }
+            | ContractPublishNotAllowed(_)
+            | TransactionTooLarge(..)
+            | PayloadTypeNotAllowed(_)
+            | ContractCodeTooLarge(..)
+            | ContractPublishTooExpensive(..)
+            | AlreadyExpired(..)
+            | BundleOriginMismatch(..)
+            | Filtered(..)
+            | NoSuchChainTip(..)
+            | DBError(_)
+            | EstimatorError(_)
+            | Other(_)
+            | Cached { .. } => false,
+            _ => true,
+        }
+    }
+
+    /// Get the reason code and data reported for this rejection
+    pub fn reason(&self) -> (&str, Option<serde_json::Value>) {
         use self::MemPoolRejection::*;
-        let (reason_code, reason_data) = match self {
+        match self {
             SerializationFailure(e) => ("Serialization", Some(json!({"message": e.to_string()}))),
             DeserializationFailure(e) => {
                 ("Deserialization", Some(json!({"message": e.to_string()})))
@@ -268,6 +394,38 @@ impl MemPoolRejection {
                 Some(json!({"recipient": recipient.to_string()})),
             ),
             TransferAmountMustBePositive => ("TransferAmountMustBePositive", None),
+            ContractPublishNotAllowed(publisher) => (
+                "ContractPublishNotAllowed",
+                Some(json!({ "publisher": publisher.to_string() })),
+            ),
+            TransactionTooLarge(actual, max) => (
+                "TransactionTooLarge",
+                Some(json!({"actual": actual, "max": max})),
+            ),
+            PayloadTypeNotAllowed(payload_type) => (
+                "PayloadTypeNotAllowed",
+                Some(json!({ "payload_type": payload_type })),
+            ),
+            ContractCodeTooLarge(actual, max) => (
+                "ContractCodeTooLarge",
+                Some(json!({"actual": actual, "max": max})),
+            ),
+            ContractPublishTooExpensive(estimated, max) => (
+                "ContractPublishTooExpensive",
+                Some(json!({"estimated_write_length": estimated, "max_write_length": max})),
+            ),
+            AlreadyExpired(expiration_height, tip_height) => (
+                "AlreadyExpired",
+                Some(json!({"expiration_height": expiration_height, "tip_height": tip_height})),
+            ),
+            BundleOriginMismatch(bundle_id, origin) => (
+                "BundleOriginMismatch",
+                Some(json!({"bundle_id": bundle_id, "origin": origin.to_string()})),
+            ),
+            Filtered(filter_id, reason) => (
+                "Filtered",
+                Some(json!({"filter_id": filter_id, "reason": reason})),
+            ),
             BadNonces(TransactionNonceMismatch {
                 expected,
                 actual,
@@ -314,7 +472,15 @@ impl MemPoolRejection {
                 Some(json!({"message": e.to_string()})),
             ),
             Other(s) => ("ServerFailureOther", Some(json!({ "message": s }))),
-        };
+            Cached {
+                reason,
+                reason_data,
+            } => (reason.as_str(), reason_data.clone()),
+        }
+    }
+
+    pub fn into_json(self, txid: &Txid) -> serde_json::Value {
+        let (reason_code, reason_data) = self.reason();
         let mut result = json!({
             "txid": format!("{}", txid.to_hex()),
             "error": "transaction rejected",
@@ -754,6 +920,7 @@ impl Streamer for MicroblockStreamData {
 enum Token {
     Nft { id: u128 },
     Ft { amount: u128 },
+    Sft { id: u128, amount: u128 },
 }
 
 fn make_withdrawal_event(
@@ -762,12 +929,19 @@ fn make_withdrawal_event(
     token: Token,
     mainnet: bool,
 ) -> StacksTransactionEvent {
-    let (withdrawal_type, withdrawal_value) = match token {
-        Token::Nft { id } => ("nft", ("id".into(), Value::UInt(id))),
-        Token::Ft { amount } => ("ft", ("amount".into(), Value::UInt(amount))),
+    let (withdrawal_type, withdrawal_values) = match token {
+        Token::Nft { id } => ("nft", vec![("id".into(), Value::UInt(id))]),
+        Token::Ft { amount } => ("ft", vec![("amount".into(), Value::UInt(amount))]),
+        Token::Sft { id, amount } => (
+            "sft",
+            vec![
+                ("id".into(), Value::UInt(id)),
+                ("amount".into(), Value::UInt(amount)),
+            ],
+        ),
     };
 
-    let values = vec![
+    let mut values = vec![
         ("sender".into(), Value::Principal(sender)),
         (
             "event".into(),
@@ -783,8 +957,8 @@ fn make_withdrawal_event(
             "asset-contract".into(),
             Value::Principal(PrincipalData::Contract(subnet_contract_id)),
         ),
-        withdrawal_value,
     ];
+    values.extend(withdrawal_values);
 
     StacksTransactionEvent::SmartContractEvent(SmartContractEventData {
         key: (boot_code_id("subnet", mainnet), "print".into()),
@@ -932,7 +1106,17 @@ impl StacksChainState {
                 }
             })?;
 
+        let is_compressed =
+            is_compressed_stream(&mut fd).map_err(|e| Error::DBError(db_error::IOError(e)))?;
         let mut bound_reader = BoundReader::from_reader(&mut fd, MAX_MESSAGE_LEN as u64);
+        if is_compressed {
+            let mut bytes = vec![];
+            bound_reader
+                .read_to_end(&mut bytes)
+                .map_err(|e| Error::DBError(db_error::IOError(e)))?;
+            let bytes = decompress_block_data(bytes)?;
+            return T::consensus_deserialize(&mut &bytes[..]).map_err(Error::CodecError);
+        }
         let inst = T::consensus_deserialize(&mut bound_reader).map_err(Error::CodecError)?;
         Ok(inst)
     }
@@ -980,11 +1164,12 @@ impl StacksChainState {
         StacksChainState::has_block_indexed(blocks_dir, &index_block_hash)
     }
 
-    /// Store a block to the chunk store, named by its hash
+    /// Store a block to the chunk store, named by its hash, compressed with `compression`
     pub fn store_block(
         blocks_dir: &str,
         consensus_hash: &ConsensusHash,
         block: &StacksBlock,
+        compression: BlockCompression,
     ) -> Result<(), Error> {
         let block_hash = block.block_hash();
         let block_path = StacksChainState::make_block_dir(blocks_dir, consensus_hash, &block_hash)?;
@@ -995,8 +1180,14 @@ impl StacksChainState {
             &block_hash,
             &block_path
         );
+        let mut block_bytes = vec![];
+        block
+            .consensus_serialize(&mut block_bytes)
+            .map_err(Error::CodecError)?;
+        let block_bytes = compress_block_data(compression, block_bytes)?;
         StacksChainState::atomic_file_store(&block_path, true, |ref mut fd| {
-            block.consensus_serialize(fd).map_err(Error::CodecError)
+            fd.write_all(&block_bytes)
+                .map_err(|e| Error::DBError(db_error::IOError(e)))
         })
     }
 
@@ -1148,7 +1339,7 @@ impl StacksChainState {
         let mut ret = vec![];
         fd.read_to_end(&mut ret)
             .map_err(|e| Error::DBError(db_error::IOError(e)))?;
-        Ok(Some(ret))
+        decompress_block_data(ret).map(Some)
     }
 
     /// Load up a block from the chunk store (staging or confirmed)
@@ -1280,11 +1471,14 @@ impl StacksChainState {
         block_conn: &DBConn,
         block_hash: &BlockHeaderHash,
     ) -> Result<Option<Vec<u8>>, Error> {
-        StacksChainState::inner_load_staging_block_bytes(
+        match StacksChainState::inner_load_staging_block_bytes(
             block_conn,
             "staging_microblocks_data",
             block_hash,
-        )
+        )? {
+            Some(bytes) => decompress_block_data(bytes).map(Some),
+            None => Ok(None),
+        }
     }
 
     /// Load up a preprocessed (queued) but still unprocessed block.
@@ -1831,6 +2025,7 @@ impl StacksChainState {
         commit_burn: u64,
         sortition_burn: u64,
         download_time: u64,
+        compression: BlockCompression,
     ) -> Result<(), Error> {
         debug!(
             "Store anchored block {}/{}, parent in {}",
@@ -1924,7 +2119,7 @@ impl StacksChainState {
         tx.execute(&sql, args)
             .map_err(|e| Error::DBError(db_error::SqliteError(e)))?;
 
-        StacksChainState::store_block(blocks_path, consensus_hash, block)?;
+        StacksChainState::store_block(blocks_path, consensus_hash, block, compression)?;
 
         // mark all children of this new block as unattachable -- need to attach this block first!
         // this should be done across all burnchains.
@@ -1944,11 +2139,13 @@ impl StacksChainState {
     /// order, this method does not check that.
     /// The consensus_hash and anchored_block_hash correspond to the _parent_ Stacks block.
     /// Microblocks ought to only be stored if they are first confirmed to have been signed.
+    /// The microblock's bytes are compressed with `compression`.
     pub fn store_staging_microblock<'a>(
         tx: &mut DBTx<'a>,
         parent_consensus_hash: &ConsensusHash,
         parent_anchored_block_hash: &BlockHeaderHash,
         microblock: &StacksMicroblock,
+        compression: BlockCompression,
     ) -> Result<(), Error> {
         test_debug!(
             "Store staging microblock {}/{}-{}",
@@ -1961,6 +2158,7 @@ impl StacksChainState {
         microblock
             .consensus_serialize(&mut microblock_bytes)
             .map_err(Error::CodecError)?;
+        let microblock_bytes = compress_block_data(compression, microblock_bytes)?;
 
         let index_block_hash = StacksBlockHeader::make_index_block_hash(
             parent_consensus_hash,
@@ -3300,7 +3498,15 @@ impl StacksChainState {
                 }
             })?;
 
-        let num_bytes = StacksChainState::stream_data(fd, stream, &mut blob, count)?;
+        let is_compressed = is_compressed_stream(&mut blob).map_err(Error::ReadError)?;
+        let num_bytes = if is_compressed {
+            let mut bytes = vec![];
+            blob.read_to_end(&mut bytes).map_err(Error::ReadError)?;
+            let bytes = decompress_block_data(bytes)?;
+            StacksChainState::stream_data(fd, stream, &mut io::Cursor::new(bytes), count)?
+        } else {
+            StacksChainState::stream_data(fd, stream, &mut blob, count)?
+        };
         test_debug!(
             "Stream microblock rowid={} hash={} offset={} total_bytes={}, num_bytes={}",
             rowid,
@@ -3409,6 +3615,16 @@ impl StacksChainState {
                 }
             })?;
 
+        // compressed blocks are decompressed in full on each call, since their bytes can't be
+        // sought into
+        if is_compressed_stream(&mut file_fd).map_err(Error::ReadError)? {
+            let mut bytes = vec![];
+            BoundReader::from_reader(&mut file_fd, MAX_MESSAGE_LEN as u64)
+                .read_to_end(&mut bytes)
+                .map_err(Error::ReadError)?;
+            let bytes = decompress_block_data(bytes)?;
+            return StacksChainState::stream_data(fd, stream, &mut io::Cursor::new(bytes), count);
+        }
         StacksChainState::stream_data(fd, stream, &mut file_fd, count)
     }
 
@@ -3817,6 +4033,7 @@ impl StacksChainState {
         let mainnet = self.mainnet;
         let chain_id = self.chain_id;
         let blocks_path = self.blocks_path.clone();
+        let compression = self.block_compression;
         let mut block_tx = self.db_tx_begin()?;
 
         // already in queue or already processed?
@@ -3905,6 +4122,7 @@ impl StacksChainState {
             commit_burn,
             sortition_burn,
             download_time,
+            compression,
         )?;
 
         // store users who burned for this block so they'll get rewarded if we process it
@@ -3970,6 +4188,7 @@ impl StacksChainState {
         let mainnet = self.mainnet;
         let chain_id = self.chain_id;
         let blocks_path = self.blocks_path.clone();
+        let compression = self.block_compression;
 
         let mut blocks_tx = self.db_tx_begin()?;
 
@@ -4014,6 +4233,7 @@ impl StacksChainState {
             parent_consensus_hash,
             parent_anchored_block_hash,
             microblock,
+            compression,
         )?;
 
         blocks_tx.commit()?;
@@ -4059,11 +4279,12 @@ impl StacksChainState {
         0
     }
 
-    /// Create the block reward.
+    /// Create the block reward, paid to the recipient named by `coinbase_schedule`.
     /// `coinbase_reward_ustx` is the total coinbase reward for this block, including any
     ///    accumulated rewards from missed sortitions or initial mining rewards.
     fn make_scheduled_miner_reward(
         mainnet: bool,
+        coinbase_schedule: &CoinbaseSchedule,
         parent_block_hash: &BlockHeaderHash,
         parent_consensus_hash: &ConsensusHash,
         block: &StacksBlock,
@@ -4087,7 +4308,7 @@ impl StacksChainState {
         };
 
         let miner_reward = MinerPaymentSchedule {
-            address: miner_addr,
+            address: coinbase_schedule.recipient_address(&miner_addr),
             block_hash: block.block_hash(),
             consensus_hash: block_consensus_hash.clone(),
             parent_block_hash: parent_block_hash.clone(),
@@ -4275,13 +4496,17 @@ impl StacksChainState {
     }
 
     /// Given access to the chain state (headers) and the staging blocks, find a staging block we
-    /// can process, as well as its parent microblocks that it confirms
+    /// can process, as well as its parent microblocks that it confirms.  Blocks whose commits
+    /// have fewer than `required_confirmations` as of the L1 block at `burn_tip_height` are
+    /// passed over until they have enough.
     /// Returns Some(microblocks, staging block) if we found a sequence of blocks to process.
     /// Returns None if not.
     fn find_next_staging_block<'a>(
         blocks_tx: &mut StacksDBTx<'a>,
         blocks_path: &str,
         sort_tx: &mut SortitionHandleTx,
+        burn_tip_height: u64,
+        required_confirmations: u64,
     ) -> Result<Option<(Vec<StacksMicroblock>, StagingBlock)>, Error> {
         test_debug!("Find next staging block");
 
@@ -4330,6 +4555,18 @@ impl StacksChainState {
                         ));
                         continue;
                     }
+                    let confirmations = commit_confirmations(sn.block_height, burn_tip_height);
+                    if confirmations < required_confirmations {
+                        debug!(
+                            "Block {}/{} waits for its commit {} to be confirmed ({} of {} confirmations)",
+                            &candidate.consensus_hash,
+                            &candidate.anchored_block_hash,
+                            &sn.winning_block_txid,
+                            confirmations,
+                            required_confirmations
+                        );
+                        continue;
+                    }
                 }
 
                 debug!(
@@ -4625,7 +4862,8 @@ impl StacksChainState {
     }
 
     /// Process any register asset operations that haven't been processed in this
-    /// subnet fork yet.
+    /// subnet fork yet.  Destination subnets of STX transfers are registered in
+    /// `.subnet-transfers`, and other assets in `.subnet`.
     pub fn process_register_asset_ops(
         clarity_tx: &mut ClarityTx,
         operations: Vec<RegisterAssetOp>,
@@ -4650,19 +4888,32 @@ impl StacksChainState {
                         .expect("BUG: failed to convert asset type to ascii string");
                 let txid_buff = Value::buff_from(txid.as_bytes().to_vec())
                     .expect("BUG: failed to convert txid to buffer");
-                // call the register asset function in the subnet contract
-                let result = clarity_tx.connection().as_transaction(|tx| {
-                    tx.run_contract_call(
-                        &boot_code_addr(mainnet).into(),
-                        None,
-                        &boot_code_id("subnet", mainnet),
+                let (contract_id, function_name, args) = match asset_type {
+                    // a destination subnet is identified by its L1 contract
+                    AssetType::Subnet => (
+                        boot_code_id(SUBNET_TRANSFERS_NAME, mainnet),
+                        REGISTER_DESTINATION_SUBNET_FUNCTION_NAME,
+                        vec![Value::Principal(l1_contract_id.into()), txid_buff],
+                    ),
+                    _ => (
+                        boot_code_id("subnet", mainnet),
                         REGISTER_ASSET_FUNCTION_NAME,
-                        &[
+                        vec![
                             asset_type_ascii,
                             Value::Principal(l1_contract_id.into()),
                             Value::Principal(l2_contract_id.into()),
                             txid_buff,
                         ],
+                    ),
+                };
+                // call the register function in the boot contract
+                let result = clarity_tx.connection().as_transaction(|tx| {
+                    tx.run_contract_call(
+                        &boot_code_addr(mainnet).into(),
+                        None,
+                        &contract_id,
+                        function_name,
+                        &args,
                         |_, _| false,
                     )
                 });
@@ -4744,10 +4995,13 @@ impl StacksChainState {
     }
 
     /// Process any deposit fungible token operations that haven't been processed in this
-    /// subnet fork yet.
+    /// subnet fork yet.  Deposits whose subnet contract rejects them are refunded with a
+    /// withdrawal event.  So are those whose deposit function can't be run at all (e.g. because
+    /// the contract doesn't exist) if `refund_unprocessable` is set; otherwise they are dropped.
     pub fn process_deposit_ft_ops(
         clarity_tx: &mut ClarityTx,
         operations: Vec<DepositFtOp>,
+        refund_unprocessable: bool,
     ) -> Vec<StacksTransactionReceipt> {
         let mainnet = clarity_tx.config.mainnet;
         let cost_so_far = clarity_tx.cost_so_far();
@@ -4793,7 +5047,7 @@ impl StacksChainState {
 
                         // If deposit fails, create a withdrawal event to send NFT back to user
                         if deposit_op_failed {
-                            info!("DepositFt op failed. Issue withdrawal tx");
+                            info!("DepositFt op failed. Refund it with a withdrawal event");
                             events.push(make_withdrawal_event(
                                 subnet_contract_id,
                                 sender,
@@ -4815,22 +5069,64 @@ impl StacksChainState {
                         })
                     }
                     Err(e) => {
-                        info!("DepositFt op processing error.";
+                        if !refund_unprocessable {
+                            info!("DepositFt op processing error.";
+                                  "error" => ?e,
+                                  "txid" => %txid,
+                                  "burn_block" => %burn_header_hash);
+                            return None;
+                        }
+                        // The deposit function could not be run at all, so refund the deposit
+                        info!("DepositFt op processing error. Refund it with a withdrawal event";
                               "error" => ?e,
                               "txid" => %txid,
                               "burn_block" => %burn_header_hash);
-                        None
+                        Some(StacksTransactionReceipt {
+                            transaction: TransactionOrigin::Burn(deposit_ft_op.into()),
+                            events: vec![make_withdrawal_event(
+                                subnet_contract_id,
+                                sender,
+                                Token::Ft { amount },
+                                mainnet,
+                            )],
+                            result: Value::err_none(),
+                            post_condition_aborted: false,
+                            stx_burned: 0,
+                            contract_analysis: None,
+                            execution_cost,
+                            microblock_header: None,
+                            tx_index: 0,
+                        })
                     }
                 }
             })
             .collect()
     }
 
+    /// Does the subnet NFT contract accept the L1 token metadata on deposit?  Such contracts
+    /// implement `(deposit-from-burnchain-with-metadata (id uint) (recipient principal)
+    /// (token-uri (optional (string-ascii 256))))`.
+    fn accepts_nft_metadata(
+        clarity_tx: &mut ClarityTx,
+        subnet_contract_id: &QualifiedContractIdentifier,
+    ) -> bool {
+        match StacksChainState::get_contract(clarity_tx, subnet_contract_id) {
+            Ok(Some(contract)) => contract
+                .contract_context
+                .lookup_function(DEPOSIT_NFT_WITH_METADATA_FUNCTION_NAME)
+                .is_some(),
+            _ => false,
+        }
+    }
+
     /// Process any deposit NFT operations that haven't been processed in this
-    /// subnet fork yet.
+    /// subnet fork yet.  If the op carries L1 token metadata and the subnet contract
+    /// accepts it, the metadata is passed along with the deposit.  Failed deposits are refunded
+    /// as in `process_deposit_ft_ops`.
     pub fn process_deposit_nft_ops(
         clarity_tx: &mut ClarityTx,
         operations: Vec<DepositNftOp>,
+        refund_unprocessable: bool,
     ) -> Vec<StacksTransactionReceipt> {
         let mainnet = clarity_tx.config.mainnet;
         let cost_so_far = clarity_tx.cost_so_far();
@@ -4844,15 +5140,49 @@ impl StacksChainState {
                     subnet_contract_id,
                     id,
                     sender,
+                    token_uri,
                     ..
                 } = deposit_nft_op.clone();
+                let has_token_uri = token_uri.is_some();
+                let (function_name, args) = if has_token_uri
+                    && StacksChainState::accepts_nft_metadata(clarity_tx, &subnet_contract_id)
+                {
+                    // the subnet contract takes a `(string-ascii 256)`, so a URI that does
+                    //  not fit is dropped rather than failing the whole deposit
+                    let token_uri = match token_uri
+                        .filter(|uri| uri.len() <= MAX_NFT_TOKEN_URI_LEN)
+                        .map(|uri| Value::string_ascii_from_bytes(uri.into_bytes()))
+                    {
+                        Some(Ok(uri)) => {
+                            Value::some(uri).expect("BUG: failed to construct an optional value")
+                        }
+                        Some(Err(_)) | None => {
+                            warn!("DepositNft op has an unusable token URI; ignoring it";
+                                  "txid" => %txid);
+                            Value::none()
+                        }
+                    };
+                    (
+                        DEPOSIT_NFT_WITH_METADATA_FUNCTION_NAME,
+                        vec![
+                            Value::UInt(id),
+                            Value::Principal(sender.clone()),
+                            token_uri,
+                        ],
+                    )
+                } else {
+                    (
+                        DEPOSIT_FUNCTION_NAME,
+                        vec![Value::UInt(id), Value::Principal(sender.clone())],
+                    )
+                };
                 let result = clarity_tx.connection().as_transaction(|tx| {
                     tx.run_contract_call(
                         &boot_code_addr(mainnet).into(),
                         None,
                         &subnet_contract_id,
-                        DEPOSIT_FUNCTION_NAME,
-                        &[Value::UInt(id), Value::Principal(sender.clone())],
+                        function_name,
+                        &args,
                         |_, _| false,
                     )
                 });
@@ -4875,7 +5205,7 @@ impl StacksChainState {
 
                         // If deposit fails, create a withdrawal event to send NFT back to user
                         if deposit_op_failed {
-                            info!("DepositNft op failed. Issue withdrawal tx");
+                            info!("DepositNft op failed. Refund it with a withdrawal event");
                             events.push(make_withdrawal_event(
                                 subnet_contract_id,
                                 sender,
@@ -4897,13 +5227,147 @@ impl StacksChainState {
                         })
                     }
                     Err(e) => {
-                        // Deposit was not processed, log and do nothing else?
-                        info!("DepositNft op processing error.";
+                        if !refund_unprocessable {
+                            info!("DepositNft op processing error.";
+                                  "error" => ?e,
+                                  "txid" => %txid,
+                                  "burn_block" => %burn_header_hash);
+                            return None;
+                        }
+                        // The deposit function could not be run at all, so refund the deposit
+                        info!("DepositNft op processing error. Refund it with a withdrawal event";
                               "error" => ?e,
                               "txid" => %txid,
                               "burn_block" => %burn_header_hash);
+                        Some(StacksTransactionReceipt {
+                            transaction: TransactionOrigin::Burn(deposit_nft_op.into()),
+                            events: vec![make_withdrawal_event(
+                                subnet_contract_id,
+                                sender,
+                                Token::Nft { id },
+                                mainnet,
+                            )],
+                            result: Value::err_none(),
+                            post_condition_aborted: false,
+                            stx_burned: 0,
+                            contract_analysis: None,
+                            execution_cost,
+                            microblock_header: None,
+                            tx_index: 0,
+                        })
+                    }
+                }
+            })
+            .collect()
+    }
 
-                        None
+    /// Process any deposit semi-fungible token operations that haven't been processed in this
+    /// subnet fork yet.  The subnet contract must implement
+    /// `(deposit-from-burnchain (id uint) (amount uint) (recipient principal))`.  Failed deposits
+    /// are refunded as in `process_deposit_ft_ops`.
+    pub fn process_deposit_sft_ops(
+        clarity_tx: &mut ClarityTx,
+        operations: Vec<DepositSftOp>,
+        refund_unprocessable: bool,
+    ) -> Vec<StacksTransactionReceipt> {
+        let mainnet = clarity_tx.config.mainnet;
+        let cost_so_far = clarity_tx.cost_so_far();
+        // return valid receipts
+        operations
+            .into_iter()
+            .filter_map(|deposit_sft_op| {
+                let DepositSftOp {
+                    txid,
+                    burn_header_hash,
+                    subnet_contract_id,
+                    id,
+                    amount,
+                    sender,
+                    ..
+                } = deposit_sft_op.clone();
+                let result = clarity_tx.connection().as_transaction(|tx| {
+                    tx.run_contract_call(
+                        &boot_code_addr(mainnet).into(),
+                        None,
+                        &subnet_contract_id,
+                        DEPOSIT_FUNCTION_NAME,
+                        &[
+                            Value::UInt(id),
+                            Value::UInt(amount),
+                            Value::Principal(sender.clone()),
+                        ],
+                        |_, _| false,
+                    )
+                });
+                let mut execution_cost = clarity_tx.cost_so_far();
+                execution_cost
+                    .sub(&cost_so_far)
+                    .expect("BUG: cost declined between executions");
+
+                match result {
+                    Ok((value, _, mut events)) => {
+                        // Examine response to see if transaction failed
+                        let deposit_op_failed = match &value {
+                            Value::Response(r) => r.committed == false,
+                            _ => {
+                                // Public functions should always return type `Response`
+                                error!("DepositSft op returned unexpected value"; "value" => %value);
+                                false
+                            }
+                        };
+
+                        // If deposit fails, create a withdrawal event to send the tokens back to user
+                        if deposit_op_failed {
+                            info!("DepositSft op failed. Refund it with a withdrawal event");
+                            events.push(make_withdrawal_event(
+                                subnet_contract_id,
+                                sender,
+                                Token::Sft { id, amount },
+                                mainnet,
+                            ));
+                        };
+
+                        Some(StacksTransactionReceipt {
+                            transaction: TransactionOrigin::Burn(deposit_sft_op.into()),
+                            events,
+                            result: value,
+                            post_condition_aborted: false,
+                            stx_burned: 0,
+                            contract_analysis: None,
+                            execution_cost,
+                            microblock_header: None,
+                            tx_index: 0,
+                        })
+                    }
+                    Err(e) => {
+                        if !refund_unprocessable {
+                            info!("DepositSft op processing error.";
+                                  "error" => ?e,
+                                  "txid" => %txid,
+                                  "burn_block" => %burn_header_hash);
+                            return None;
+                        }
+                        // The deposit function could not be run at all, so refund the deposit
+                        info!("DepositSft op processing error. Refund it with a withdrawal event";
+                              "error" => ?e,
+                              "txid" => %txid,
+                              "burn_block" => %burn_header_hash);
+                        Some(StacksTransactionReceipt {
+                            transaction: TransactionOrigin::Burn(deposit_sft_op.into()),
+                            events: vec![make_withdrawal_event(
+                                subnet_contract_id,
+                                sender,
+                                Token::Sft { id, amount },
+                                mainnet,
+                            )],
+                            result: Value::err_none(),
+                            post_condition_aborted: false,
+                            stx_burned: 0,
+                            contract_analysis: None,
+                            execution_cost,
+                            microblock_header: None,
+                            tx_index: 0,
+                        })
                     }
                 }
             })
@@ -4935,12 +5399,20 @@ impl StacksChainState {
     }
 
     /// Process a single matured miner reward.
-    /// Grant it STX tokens.
+    /// Grant it its fees in STX, and its coinbase in `.subnet-token`.
     fn process_matured_miner_reward(
         clarity_tx: &mut ClarityTx,
         miner_reward: &MinerReward,
     ) -> Result<(), Error> {
-        let miner_reward_total = miner_reward.total();
+        if miner_reward.coinbase > 0 {
+            StacksChainState::process_coinbase_mint(
+                clarity_tx,
+                &miner_reward.address,
+                miner_reward.coinbase,
+            )?;
+        }
+
+        let miner_reward_total = miner_reward.total() - miner_reward.coinbase;
         clarity_tx
             .connection()
             .as_transaction(|x| {
@@ -4965,8 +5437,46 @@ impl StacksChainState {
         Ok(())
     }
 
+    /// Mint `amount` of `.subnet-token` to `recipient`, for the coinbase of a block whose
+    /// reward matured.  Minted STX would not be backed by the STX escrowed in the L1 contract,
+    /// so miners could withdraw depositors' STX with them; the subnet token can't be withdrawn.
+    fn process_coinbase_mint(
+        clarity_tx: &mut ClarityTx,
+        recipient: &StacksAddress,
+        amount: u128,
+    ) -> Result<(), Error> {
+        let mainnet = clarity_tx.config.mainnet;
+        let sender: PrincipalData = boot_code_addr(mainnet).into();
+        let contract_id = boot_code_id(SUBNET_TOKEN_NAME, mainnet);
+        let (result, _) =
+            clarity_tx.with_temporary_cost_tracker(LimitedCostTracker::new_free(), |clarity_tx| {
+                clarity_tx.connection().as_transaction(|tx| {
+                    tx.run_contract_call(
+                        &sender,
+                        None,
+                        &contract_id,
+                        MINT_COINBASE_FUNCTION_NAME,
+                        &[
+                            Value::UInt(amount),
+                            Value::Principal(PrincipalData::from(recipient.clone())),
+                        ],
+                        |_, _| false,
+                    )
+                })
+            });
+
+        match result? {
+            (Value::Response(ref response), _, _) if response.committed => Ok(()),
+            (value, _, _) => Err(Error::InvalidStacksBlock(format!(
+                "Subnet token rejected the coinbase of {}: {}",
+                recipient, value
+            ))),
+        }
+    }
+
     /// Process matured miner rewards for this block.
-    /// Returns the number of liquid uSTX created -- i.e. the coinbase
+    /// Returns the coinbase minted, in `.subnet-token`.  No liquid uSTX are created, since the
+    /// fees paid out were already liquid.
     pub fn process_matured_miner_rewards<'a, 'b>(
         clarity_tx: &mut ClarityTx<'a, 'b>,
         miner_share: &MinerReward,
@@ -5024,6 +5534,43 @@ impl StacksChainState {
         Ok(parent_miner)
     }
 
+    /// Record the height and timestamp of the L1 block that this block builds on in the
+    /// `.time-oracle` boot contract.  Called in both follower and miner block assembly paths
+    /// once the time oracle is active, so the clock is part of the block's state root.
+    pub fn process_time_oracle_update(
+        clarity_tx: &mut ClarityTx,
+        burn_block_height: u64,
+        burn_header_timestamp: u64,
+    ) -> Result<(), Error> {
+        let mainnet = clarity_tx.config.mainnet;
+        let sender: PrincipalData = boot_code_addr(mainnet).into();
+        let contract_id = boot_code_id(TIME_ORACLE_NAME, mainnet);
+        let (result, _) =
+            clarity_tx.with_temporary_cost_tracker(LimitedCostTracker::new_free(), |clarity_tx| {
+                clarity_tx.connection().as_transaction(|tx| {
+                    tx.run_contract_call(
+                        &sender,
+                        None,
+                        &contract_id,
+                        SET_BURN_BLOCK_TIME_FUNCTION_NAME,
+                        &[
+                            Value::UInt(burn_block_height.into()),
+                            Value::UInt(burn_header_timestamp.into()),
+                        ],
+                        |_, _| false,
+                    )
+                })
+            });
+
+        match result? {
+            (Value::Response(ref response), _, _) if response.committed => Ok(()),
+            (value, _, _) => Err(Error::InvalidStacksBlock(format!(
+                "Time oracle rejected the burn block time: {}",
+                value
+            ))),
+        }
+    }
+
     /// Called in both follower and miner block assembly paths.
     /// Returns clarity_tx, list of receipts, microblock execution cost,
     /// microblock fees, microblock burns, list of microblock tx receipts,
@@ -5060,6 +5607,13 @@ impl StacksChainState {
             (latest_miners, parent_miner)
         };
 
+        let burn_tip_snapshot =
+            SortitionDB::get_block_snapshot(conn, &SortitionId::stubbed(&burn_tip))?.ok_or(
+                Error::InvalidStacksBlock(format!(
+                    "Failed to load burn tip snapshot. burn_tip = {}",
+                    &burn_tip
+                )),
+            )?;
         let parent_block_burn_block =
             SortitionDB::get_block_snapshot_consensus(conn, &parent_consensus_hash)?
                 .ok_or(Error::InvalidStacksBlock(format!(
@@ -5073,24 +5627,65 @@ impl StacksChainState {
             &burn_tip,
             SortitionDB::get_register_asset_ops,
         )?;
-        let deposit_stx_ops = SortitionDB::get_ops_between(
+
+        // deposits are materialized once they have enough L1 confirmations, so this block
+        // takes those of the L1 blocks `deposit_confirmations` behind its own
+        let deposit_confirmations = StacksChainState::get_deposit_confirmations(&chainstate_tx.tx)?;
+        let activations = StacksChainState::get_feature_activations(&chainstate_tx.tx)?;
+        let coinbase_schedule = StacksChainState::get_coinbase_schedule(&chainstate_tx.tx)?;
+        let block_height = chain_tip.stacks_block_height + 1;
+
+        // destination subnets of STX transfers are registered in `.subnet-transfers`, so the block
+        // which installs it registers every destination registered on the L1 so far
+        let (register_subnet_ops, register_asset_ops): (Vec<_>, Vec<_>) = register_asset_ops
+            .into_iter()
+            .partition(|op| op.asset_type == AssetType::Subnet);
+        let register_subnet_ops = if activations.installs_subnet_transfers(block_height) {
+            SortitionDB::get_ops_between(
+                conn,
+                &BurnchainHeaderHash::zero(),
+                &burn_tip,
+                SortitionDB::get_register_asset_ops,
+            )?
+            .into_iter()
+            .filter(|op| op.asset_type == AssetType::Subnet)
+            .collect()
+        } else if activations.subnet_transfers_active(block_height) {
+            register_subnet_ops
+        } else {
+            vec![]
+        };
+
+        let (deposit_window_start, deposit_window_end) = StacksChainState::get_deposit_window(
             conn,
             &parent_block_burn_block,
             &burn_tip,
+            deposit_confirmations,
+        )?;
+        let deposit_stx_ops = SortitionDB::get_ops_between(
+            conn,
+            &deposit_window_start,
+            &deposit_window_end,
             SortitionDB::get_deposit_stx_ops,
         )?;
         let deposit_ft_ops = SortitionDB::get_ops_between(
             conn,
-            &parent_block_burn_block,
-            &burn_tip,
+            &deposit_window_start,
+            &deposit_window_end,
             SortitionDB::get_deposit_ft_ops,
         )?;
         let deposit_nft_ops = SortitionDB::get_ops_between(
             conn,
-            &parent_block_burn_block,
-            &burn_tip,
+            &deposit_window_start,
+            &deposit_window_end,
             SortitionDB::get_deposit_nft_ops,
         )?;
+        let deposit_sft_ops = SortitionDB::get_ops_between(
+            conn,
+            &deposit_window_start,
+            &deposit_window_end,
+            SortitionDB::get_deposit_sft_ops,
+        )?;
 
         // load the execution cost of the parent block if the executor is the follower.
         // otherwise, if the executor is the miner, only load the parent cost if the parent
@@ -5159,6 +5754,27 @@ impl StacksChainState {
 
         let t1 = get_epoch_time_ms();
 
+        // no microblock may publish a contract in a Clarity version this epoch does not support
+        for mblock in parent_microblocks.iter() {
+            if let Err(reason) =
+                StacksBlock::validate_transactions_clarity_version(&mblock.txs, evaluated_epoch)
+            {
+                let msg = format!(
+                    "Invalid Stacks microblocks {},{} (offender {}): {}",
+                    parent_consensus_hash,
+                    parent_header_hash,
+                    mblock.block_hash(),
+                    &reason
+                );
+                warn!("{}", &msg);
+
+                if miner_id_opt.is_none() {
+                    clarity_tx.rollback_block();
+                }
+                return Err(Error::InvalidStacksMicroblock(msg, mblock.block_hash()));
+            }
+        }
+
         // process microblock stream.
         // If we go over-budget, then we can't process this block either (which is by design)
         let (microblock_fees, microblock_burns, microblock_txs_receipts) =
@@ -5216,15 +5832,110 @@ impl StacksChainState {
         ));
 
         // Process asset deposits
+        let refund_unprocessable_deposits = activations.deposit_refunds_active(block_height);
         tx_receipts.extend(StacksChainState::process_deposit_ft_ops(
             &mut clarity_tx,
             deposit_ft_ops,
+            refund_unprocessable_deposits,
         ));
         tx_receipts.extend(StacksChainState::process_deposit_nft_ops(
             &mut clarity_tx,
             deposit_nft_ops,
+            refund_unprocessable_deposits,
+        ));
+        tx_receipts.extend(StacksChainState::process_deposit_sft_ops(
+            &mut clarity_tx,
+            deposit_sft_ops,
+            refund_unprocessable_deposits,
+        ));
+
+        if activations.installs_time_oracle(block_height) {
+            match StacksChainState::install_boot_contract(
+                &mut clarity_tx,
+                TIME_ORACLE_NAME,
+                BOOT_CODE_TIME_ORACLE,
+            ) {
+                Ok(receipt) => tx_receipts.push(receipt),
+                Err(e) => {
+                    if let Some(_) = miner_id_opt {
+                        return Err(e);
+                    } else {
+                        let msg = format!("Failed to install the time oracle: {:?}", &e);
+                        warn!("{}", &msg);
+
+                        clarity_tx.rollback_block();
+                        return Err(Error::InvalidStacksBlock(msg));
+                    }
+                }
+            }
+        }
+
+        if activations.installs_subnet_transfers(block_height) {
+            match StacksChainState::install_boot_contract(
+                &mut clarity_tx,
+                SUBNET_TRANSFERS_NAME,
+                BOOT_CODE_SUBNET_TRANSFERS,
+            ) {
+                Ok(receipt) => tx_receipts.push(receipt),
+                Err(e) => {
+                    if let Some(_) = miner_id_opt {
+                        return Err(e);
+                    } else {
+                        let msg =
+                            format!("Failed to install the subnet transfers contract: {:?}", &e);
+                        warn!("{}", &msg);
+
+                        clarity_tx.rollback_block();
+                        return Err(Error::InvalidStacksBlock(msg));
+                    }
+                }
+            }
+        }
+
+        tx_receipts.extend(StacksChainState::process_register_asset_ops(
+            &mut clarity_tx,
+            register_subnet_ops,
         ));
 
+        if coinbase_schedule.installs_subnet_token(block_height) {
+            match StacksChainState::install_boot_contract(
+                &mut clarity_tx,
+                SUBNET_TOKEN_NAME,
+                BOOT_CODE_SUBNET_TOKEN,
+            ) {
+                Ok(receipt) => tx_receipts.push(receipt),
+                Err(e) => {
+                    if let Some(_) = miner_id_opt {
+                        return Err(e);
+                    } else {
+                        let msg = format!("Failed to install the subnet token: {:?}", &e);
+                        warn!("{}", &msg);
+
+                        clarity_tx.rollback_block();
+                        return Err(Error::InvalidStacksBlock(msg));
+                    }
+                }
+            }
+        }
+
+        if activations.time_oracle_active(block_height) {
+            if let Err(e) = StacksChainState::process_time_oracle_update(
+                &mut clarity_tx,
+                burn_tip_snapshot.block_height,
+                burn_tip_snapshot.burn_header_timestamp,
+            ) {
+                if let Some(_) = miner_id_opt {
+                    return Err(e);
+                } else {
+                    let msg = format!("Failed to update the time oracle: {:?}", &e);
+                    warn!("{}", &msg);
+
+                    clarity_tx.rollback_block();
+                    return Err(Error::InvalidStacksBlock(msg));
+                }
+            }
+        }
+
         Ok(SetupBlockResult {
             clarity_tx,
             tx_receipts,
@@ -5254,14 +5965,16 @@ impl StacksChainState {
             miner_payouts.as_ref()
         {
             // grant in order by miner, then users
-            let matured_ustx = StacksChainState::process_matured_miner_rewards(
+            StacksChainState::process_matured_miner_rewards(
                 clarity_tx,
                 miner_reward,
                 user_rewards,
                 parent_reward,
             )?;
 
-            clarity_tx.increment_ustx_liquid_supply(matured_ustx);
+            // the coinbase is minted in `.subnet-token`, so the liquid uSTX supply is unchanged,
+            //  but it is still written as it was before, so blocks keep their state roots.
+            clarity_tx.increment_ustx_liquid_supply(0);
         }
 
         // mark microblock public key as used
@@ -5325,6 +6038,7 @@ impl StacksChainState {
         burnchain_commit_burn: u64,
         burnchain_sortition_burn: u64,
         user_burns: &Vec<StagingUserBurnSupport>,
+        prevalidated_txids: HashSet<Txid>,
     ) -> Result<(StacksEpochReceipt, PreCommitClarityBlock<'a>), Error> {
         debug!(
             "Process block {:?} with {} transactions",
@@ -5408,6 +6122,15 @@ impl StacksChainState {
         .expect("BUG: Failed to load snapshot for block snapshot during Stacks block processing")
         .parent_burn_header_hash;
 
+        // withdrawals held back by an ancestor which this block must include
+        let mut withdrawals_released = StacksChainState::get_released_withdrawals(
+            chainstate_tx,
+            &parent_chain_tip.index_block_hash(),
+            next_block_height,
+        )?;
+        let withdrawal_policy = chainstate_tx.withdrawal_policy.clone();
+        let coinbase_schedule = StacksChainState::get_coinbase_schedule(&chainstate_tx.tx)?;
+
         let SetupBlockResult {
             mut clarity_tx,
             mut tx_receipts,
@@ -5433,6 +6156,27 @@ impl StacksChainState {
             None,
         )?;
 
+        // only the anchored block's transactions are prevalidated, and they run after the
+        // parent microblocks'
+        clarity_tx.prevalidated_txids = prevalidated_txids;
+
+        // the anchored block runs in the epoch the parent microblocks ran in, unless this block
+        // starts a new one
+        if let Err(reason) =
+            StacksBlock::validate_transactions_clarity_version(&block.txs, clarity_tx.get_epoch())
+        {
+            let msg = format!(
+                "Invalid Stacks block {}/{}: {}",
+                chain_tip_consensus_hash,
+                block.block_hash(),
+                &reason
+            );
+            warn!("{}", &msg);
+
+            clarity_tx.rollback_block();
+            return Err(Error::InvalidStacksBlock(msg));
+        }
+
         let block_limit = clarity_tx.block_limit().unwrap_or_else(|| {
             warn!("Failed to read transaction block limit");
             ExecutionCost::max_value()
@@ -5448,6 +6192,7 @@ impl StacksChainState {
             parent_burn_block_timestamp,
             clarity_commit,
             withdrawal_tree,
+            withdrawals_queued,
         ) = {
             // get previous burn block stats
             let (parent_burn_block_hash, parent_burn_block_height, parent_burn_block_timestamp) =
@@ -5614,8 +6359,12 @@ impl StacksChainState {
 
             // Check withdrawal state merkle root
             // Process withdrawal events
-            let withdrawal_tree =
-                create_withdrawal_merkle_tree(tx_receipts.iter_mut(), block.header.total_work.work);
+            let (withdrawal_tree, withdrawals_queued) = create_withdrawal_merkle_tree_with_policy(
+                &withdrawal_policy,
+                &mut withdrawals_released,
+                tx_receipts.iter_mut(),
+                block.header.total_work.work,
+            );
             let withdrawal_root_hash = withdrawal_tree.root();
 
             if withdrawal_root_hash != block.header.withdrawal_merkle_root {
@@ -5644,16 +6393,14 @@ impl StacksChainState {
             .expect("CORRUPTION: failed to load snapshot that elected processed block")
             .accumulated_coinbase_ustx;
 
-            let coinbase_at_block = StacksChainState::get_coinbase_reward(
-                chain_tip_burn_header_height as u64,
-                burn_dbconn.context.first_block_height,
-            );
+            let coinbase_at_block = coinbase_schedule.reward_at(next_block_height);
 
             let total_coinbase = coinbase_at_block.saturating_add(accumulated_rewards);
 
             // calculate reward for this block's miner
             let scheduled_miner_reward = StacksChainState::make_scheduled_miner_reward(
                 mainnet,
+                &coinbase_schedule,
                 &parent_block_hash,
                 &parent_consensus_hash,
                 &block,
@@ -5678,6 +6425,7 @@ impl StacksChainState {
                 parent_burn_block_timestamp,
                 clarity_commit,
                 withdrawal_tree,
+                withdrawals_queued,
             )
         };
 
@@ -5705,6 +6453,48 @@ impl StacksChainState {
         )
         .expect("FATAL: failed to advance chain tip");
 
+        StacksChainState::store_queued_withdrawals(
+            &chainstate_tx.tx,
+            &new_tip.index_block_hash(),
+            &withdrawals_queued,
+        )?;
+        StacksChainState::index_block_withdrawals(
+            &chainstate_tx.tx,
+            &new_tip.index_block_hash(),
+            block.header.total_work.work,
+            &block.header.withdrawal_merkle_root,
+            &withdrawals_released,
+            tx_receipts.iter_mut(),
+        )?;
+        StacksChainState::index_failed_deposits(
+            &chainstate_tx.tx,
+            &new_tip.index_block_hash(),
+            new_tip.stacks_block_height,
+            tx_receipts.iter_mut(),
+        )?;
+        if chainstate_tx.account_index {
+            StacksChainState::index_account_transactions(
+                &chainstate_tx.tx,
+                &new_tip.index_block_hash(),
+                new_tip.stacks_block_height,
+                tx_receipts.iter(),
+            )?;
+        }
+        StacksChainState::store_contract_costs(
+            &chainstate_tx.tx,
+            &new_tip.index_block_hash(),
+            new_tip.stacks_block_height,
+            tx_receipts.iter(),
+        )?;
+
+        StacksChainState::store_fee_rate_floor(
+            &chainstate_tx.tx,
+            &parent_chain_tip.index_block_hash(),
+            &new_tip.index_block_hash(),
+            new_tip.stacks_block_height,
+            FeeRateFloor::block_utilization(&block_execution_cost, &block_limit, block_size),
+        )?;
+
         chainstate_tx.log_transactions_processed(&new_tip.index_block_hash(), &tx_receipts);
 
         set_last_execution_cost_observed(&block_execution_cost, &block_limit);
@@ -5720,95 +6510,446 @@ impl StacksChainState {
             parent_burn_block_height,
             parent_burn_block_timestamp,
             evaluated_epoch,
+            withdrawals_queued,
+            withdrawals_released,
         };
 
         Ok((epoch_receipt, clarity_commit))
     }
 
-    /// Verify that a Stacks anchored block attaches to its parent anchored block.
-    /// * checks .header.total_work.work
-    /// * checks .header.parent_block
-    fn check_block_attachment(
-        parent_block_header: &StacksBlockHeader,
-        block_header: &StacksBlockHeader,
-    ) -> bool {
-        // must have the right height
-        if parent_block_header
-            .total_work
-            .work
-            .checked_add(1)
-            .expect("Blockchain height overflow")
-            != block_header.total_work.work
-        {
-            return false;
-        }
+    /// Run a proposed anchored block through the same steps as `append_block`, on top of its
+    /// parent `parent_consensus_hash`/`block.header.parent_block`, and then roll everything back,
+    /// so nothing is persisted.  The block has not been elected by a sortition yet, so it is
+    /// evaluated at the canonical burnchain tip, the same way the miner evaluates the blocks it
+    /// assembles.
+    ///
+    /// Errors if the parent block has not been processed or the chainstate can't be read; the
+    /// reasons the block itself is invalid are reported in the returned verdict.
+    pub fn validate_proposed_block(
+        &mut self,
+        burn_dbconn: &SortitionDBConn,
+        parent_consensus_hash: &ConsensusHash,
+        block: &StacksBlock,
+    ) -> Result<ProposedBlockVerdict, Error> {
+        let burn_tip = SortitionDB::get_canonical_burn_chain_tip(burn_dbconn.conn())?;
+        self.evaluate_block(
+            burn_dbconn,
+            parent_consensus_hash,
+            block,
+            burn_tip.burn_header_hash,
+            burn_tip.block_height as u32,
+            None,
+        )
+    }
 
-        // must have right hash linkage
-        if parent_block_header.block_hash() != block_header.parent_block {
-            return false;
+    /// Re-execute the already-processed anchored block `consensus_hash`/`block`, at the same
+    /// burnchain tip `append_block` evaluated it at, and roll everything back.  The verdict
+    /// reports whether the recomputed state and withdrawal roots still match the block's header.
+    pub fn reexecute_processed_block(
+        &mut self,
+        burn_dbconn: &SortitionDBConn,
+        consensus_hash: &ConsensusHash,
+        block: &StacksBlock,
+    ) -> Result<ProposedBlockVerdict, Error> {
+        self.reexecute_processed_block_inner(burn_dbconn, consensus_hash, block, None)
+    }
+
+    /// Re-execute the already-processed anchored block `consensus_hash`/`block` as
+    /// `reexecute_processed_block` does, and return what `append_block` announced to the event
+    /// dispatcher when it processed the block.  Errors if re-executing the block doesn't
+    /// reproduce its header's state and withdrawal roots.
+    pub fn reexecute_block_for_events(
+        &mut self,
+        burn_dbconn: &SortitionDBConn,
+        consensus_hash: &ConsensusHash,
+        block: &StacksBlock,
+    ) -> Result<ReexecutedBlock, Error> {
+        let mut reexecuted = None;
+        let verdict = self.reexecute_processed_block_inner(
+            burn_dbconn,
+            consensus_hash,
+            block,
+            Some(&mut reexecuted),
+        )?;
+        match (verdict.invalid_reason, reexecuted) {
+            (None, Some(reexecuted)) => Ok(reexecuted),
+            (reason, _) => Err(Error::InvalidStacksBlock(format!(
+                "Re-executing {}/{} failed: {}",
+                consensus_hash,
+                &block.block_hash(),
+                reason.unwrap_or_else(|| "no outcome".into())
+            ))),
         }
+    }
 
-        return true;
+    fn reexecute_processed_block_inner(
+        &mut self,
+        burn_dbconn: &SortitionDBConn,
+        consensus_hash: &ConsensusHash,
+        block: &StacksBlock,
+        reexecuted_out: Option<&mut Option<ReexecutedBlock>>,
+    ) -> Result<ProposedBlockVerdict, Error> {
+        let snapshot =
+            SortitionDB::get_block_snapshot_consensus(burn_dbconn.conn(), consensus_hash)?
+                .ok_or(Error::NoSuchBlockError)?;
+        let block_id =
+            StacksBlockHeader::make_index_block_hash(consensus_hash, &block.block_hash());
+        let parent_block_id = StacksChainState::get_parent_block_id(self.db(), &block_id)?
+            .ok_or(Error::NoSuchBlockError)?;
+        let parent_header = StacksChainState::get_stacks_block_header_info_by_index_block_hash(
+            self.db(),
+            &parent_block_id,
+        )?
+        .ok_or(Error::NoSuchBlockError)?;
+        self.evaluate_block(
+            burn_dbconn,
+            &parent_header.consensus_hash,
+            block,
+            snapshot.parent_burn_header_hash,
+            snapshot.block_height as u32,
+            reexecuted_out,
+        )
     }
 
-    /// Get the parent header info for a block we're processing, if it's known.
-    /// The header info will be pulled from the headers DB, so this method only succeeds if the
-    /// parent block has been processed.
-    /// If it's not known, return None.
-    fn get_parent_header_info(
-        chainstate_tx: &mut ChainstateTx,
-        next_staging_block: &StagingBlock,
-    ) -> Result<Option<StacksHeaderInfo>, Error> {
-        let parent_block_header_info = match StacksChainState::get_anchored_block_header_info(
-            &chainstate_tx.tx,
-            &next_staging_block.parent_consensus_hash,
-            &next_staging_block.parent_anchored_block_hash,
-        )? {
-            Some(parent_info) => {
-                debug!(
-                    "Found parent info {}/{}",
-                    next_staging_block.parent_consensus_hash,
-                    next_staging_block.parent_anchored_block_hash
-                );
-                parent_info
+    /// Evaluate `block` on top of its parent as of the burnchain tip `burn_tip`, for
+    /// `validate_proposed_block` and `reexecute_processed_block`.  If the block is valid and
+    /// `reexecuted_out` is given, it is set to what processing the block produced.
+    fn evaluate_block(
+        &mut self,
+        burn_dbconn: &SortitionDBConn,
+        parent_consensus_hash: &ConsensusHash,
+        block: &StacksBlock,
+        burn_tip: BurnchainHeaderHash,
+        burn_tip_height: u32,
+        reexecuted_out: Option<&mut Option<ReexecutedBlock>>,
+    ) -> Result<ProposedBlockVerdict, Error> {
+        let mainnet = self.mainnet;
+        let parent_chain_tip = StacksChainState::get_anchored_block_header_info(
+            self.db(),
+            parent_consensus_hash,
+            &block.header.parent_block,
+        )?
+        .ok_or(Error::NoSuchBlockError)?;
+
+        if !StacksChainState::check_block_attachment(
+            &parent_chain_tip.anchored_header,
+            &block.header,
+        ) {
+            return Ok(ProposedBlockVerdict::rejected(format!(
+                "Block does not attach to parent {}/{} at height {}",
+                parent_consensus_hash,
+                &block.header.parent_block,
+                parent_chain_tip.stacks_block_height
+            )));
+        }
+
+        if !block.validate_transactions_static(mainnet, self.chain_id) {
+            return Ok(ProposedBlockVerdict::rejected(
+                "Transactions failed static checks".into(),
+            ));
+        }
+
+        let parent_microblocks = if block.has_microblock_parent() {
+            if StacksChainState::block_crosses_epoch_boundary(
+                self.db(),
+                parent_consensus_hash,
+                &block.header.parent_block,
+            )? {
+                return Ok(ProposedBlockVerdict::rejected(
+                    "Mined in different epoch than parent but confirms microblocks".into(),
+                ));
             }
-            None => {
-                if next_staging_block.is_first_mined() {
-                    // this is the first-ever mined block
-                    debug!("This is the first-ever block in this fork.  Parent is 00000000..00000000/00000000..00000000");
-                    StacksChainState::get_anchored_block_header_info(
-                        &chainstate_tx.tx,
-                        &FIRST_BURNCHAIN_CONSENSUS_HASH,
-                        &FIRST_STACKS_BLOCK_HASH,
-                    )
-                    .expect("FATAL: failed to load initial block header")
-                    .expect("FATAL: initial block header not found in headers DB")
-                } else {
-                    // no parent stored
-                    debug!(
-                        "No parent block for {}/{} processed yet",
-                        next_staging_block.consensus_hash, next_staging_block.anchored_block_hash
-                    );
-                    return Ok(None);
+            match StacksChainState::load_microblock_stream_fork(
+                self.db(),
+                parent_consensus_hash,
+                &block.header.parent_block,
+                &block.header.parent_microblock,
+            )? {
+                Some(microblocks)
+                    if microblocks.last().map(|mblock| mblock.header.sequence)
+                        == Some(block.header.parent_microblock_sequence) =>
+                {
+                    microblocks
+                }
+                _ => {
+                    return Ok(ProposedBlockVerdict::rejected(format!(
+                        "Parent microblock stream ending in {} (seq {}) is not available",
+                        &block.header.parent_microblock, block.header.parent_microblock_sequence
+                    )));
                 }
             }
+        } else {
+            vec![]
         };
-        Ok(Some(parent_block_header_info))
-    }
 
-    /// Extract and parse the block from a loaded staging block, and verify its integrity.
-    fn extract_stacks_block(next_staging_block: &StagingBlock) -> Result<StacksBlock, Error> {
-        let block = {
-            StacksBlock::consensus_deserialize(&mut &next_staging_block.block_data[..])
-                .map_err(Error::CodecError)?
+        let (parent_consensus_hash, parent_block_hash) = if block.is_first_mined() {
+            (
+                FIRST_BURNCHAIN_CONSENSUS_HASH.clone(),
+                FIRST_STACKS_BLOCK_HASH.clone(),
+            )
+        } else {
+            (
+                parent_chain_tip.consensus_hash.clone(),
+                parent_chain_tip.anchored_header.block_hash(),
+            )
         };
 
-        let block_hash = block.block_hash();
-        if block_hash != next_staging_block.anchored_block_hash {
-            // database corruption
-            error!(
-                "Staging DB corruption: expected block {}, got {} from disk",
-                next_staging_block.anchored_block_hash, block_hash
+        let next_block_height = block.header.total_work.work;
+
+        let (mut chainstate_tx, clarity_instance) = self.chainstate_tx_begin()?;
+        let mut withdrawals_released = StacksChainState::get_released_withdrawals(
+            &mut chainstate_tx,
+            &parent_chain_tip.index_block_hash(),
+            next_block_height,
+        )?;
+        let withdrawal_policy = chainstate_tx.withdrawal_policy.clone();
+
+        let SetupBlockResult {
+            mut clarity_tx,
+            mut tx_receipts,
+            microblock_execution_cost,
+            microblock_txs_receipts,
+            matured_miner_rewards_opt,
+            ..
+        } = StacksChainState::setup_block(
+            &mut chainstate_tx,
+            clarity_instance,
+            burn_dbconn,
+            burn_dbconn.conn(),
+            &parent_chain_tip,
+            burn_tip,
+            burn_tip_height,
+            parent_consensus_hash,
+            parent_block_hash,
+            &parent_microblocks,
+            mainnet,
+            None,
+        )?;
+
+        match StacksChainState::has_microblock_pubkey_hash(
+            &mut clarity_tx,
+            &block.header.microblock_pubkey_hash,
+        ) {
+            Ok(None) => {}
+            Ok(Some(height)) => {
+                clarity_tx.rollback_block();
+                return Ok(ProposedBlockVerdict::rejected(format!(
+                    "Already used microblock pubkey hash {} at height {}",
+                    &block.header.microblock_pubkey_hash, height
+                )));
+            }
+            Err(e) => {
+                clarity_tx.rollback_block();
+                return Err(e);
+            }
+        }
+
+        // process the anchored block's transactions one at a time, instead of through
+        // `process_block_transactions`, so that the first invalid one can be reported
+        let mut tx_index = microblock_txs_receipts.len() as u32;
+        for (i, tx) in block.txs.iter().enumerate() {
+            match StacksChainState::process_transaction(&mut clarity_tx, tx, false) {
+                Ok((_fee, mut tx_receipt)) => {
+                    tx_receipt.tx_index = tx_index;
+                    tx_receipts.push(tx_receipt);
+                    tx_index += 1;
+                }
+                Err(e) => {
+                    let cost = clarity_tx.cost_so_far();
+                    clarity_tx.rollback_block();
+                    return Ok(ProposedBlockVerdict {
+                        invalid_reason: Some(format!("Transaction {} is invalid", &tx.txid())),
+                        first_invalid_tx: Some((i as u32, tx.txid(), e.to_string())),
+                        state_index_root: None,
+                        withdrawal_merkle_root: None,
+                        cost,
+                    });
+                }
+            }
+        }
+
+        let cost = clarity_tx.cost_so_far();
+        let (matured_rewards, matured_rewards_info, miner_payouts_opt) =
+            if let Some((miner_reward, user_rewards, parent_reward, reward_ptr)) =
+                matured_miner_rewards_opt
+            {
+                let mut matured_rewards = vec![miner_reward.clone()];
+                matured_rewards.extend(user_rewards.iter().cloned());
+                matured_rewards.push(parent_reward.clone());
+                (
+                    matured_rewards,
+                    Some(reward_ptr),
+                    Some((miner_reward, user_rewards, parent_reward)),
+                )
+            } else {
+                (vec![], None, None)
+            };
+        match StacksChainState::finish_block(
+            &mut clarity_tx,
+            miner_payouts_opt,
+            block.header.total_work.work as u32,
+            block.header.microblock_pubkey_hash,
+        ) {
+            Ok(mut lockup_events) => {
+                // as `append_block` does, attach any lockup events to the coinbase receipt
+                if let Some(receipt) = tx_receipts.get_mut(0) {
+                    if receipt.is_coinbase_tx() {
+                        receipt.events.append(&mut lockup_events);
+                    }
+                }
+            }
+            Err(Error::InvalidStacksBlock(msg)) => {
+                clarity_tx.rollback_block();
+                let mut verdict = ProposedBlockVerdict::rejected(msg);
+                verdict.cost = cost;
+                return Ok(verdict);
+            }
+            Err(e) => {
+                clarity_tx.rollback_block();
+                return Err(e);
+            }
+        }
+
+        // same receipt order as `append_block`, so the withdrawal keys come out the same
+        tx_receipts.extend(microblock_txs_receipts.into_iter());
+
+        let state_index_root = clarity_tx.seal();
+        let (withdrawal_tree, withdrawals_queued) = create_withdrawal_merkle_tree_with_policy(
+            &withdrawal_policy,
+            &mut withdrawals_released,
+            tx_receipts.iter_mut(),
+            next_block_height,
+        );
+        let withdrawal_merkle_root = withdrawal_tree.root();
+        clarity_tx.rollback_block();
+
+        let invalid_reason = if state_index_root != block.header.state_index_root {
+            Some(format!(
+                "State root mismatch: expected {}, got {}",
+                &block.header.state_index_root, &state_index_root
+            ))
+        } else if withdrawal_merkle_root != block.header.withdrawal_merkle_root {
+            Some(format!(
+                "Withdrawal root mismatch: expected {}, got {}",
+                &block.header.withdrawal_merkle_root, &withdrawal_merkle_root
+            ))
+        } else {
+            None
+        };
+
+        debug!("Validated proposed block {}", &block.block_hash();
+               "parent_block" => %format!("{}/{}", &parent_chain_tip.consensus_hash, &block.header.parent_block),
+               "state_index_root" => %state_index_root,
+               "withdrawal_merkle_root" => %withdrawal_merkle_root,
+               "invalid_reason" => ?invalid_reason);
+
+        if let Some(reexecuted_out) = reexecuted_out {
+            if invalid_reason.is_none() {
+                *reexecuted_out = Some(ReexecutedBlock {
+                    tx_receipts,
+                    matured_rewards,
+                    matured_rewards_info,
+                    anchored_block_cost: cost.clone(),
+                    parent_microblocks_cost: microblock_execution_cost,
+                    withdrawals_queued,
+                    withdrawals_released,
+                });
+            }
+        }
+
+        Ok(ProposedBlockVerdict {
+            invalid_reason,
+            first_invalid_tx: None,
+            state_index_root: Some(state_index_root),
+            withdrawal_merkle_root: Some(withdrawal_merkle_root),
+            cost,
+        })
+    }
+
+    /// Verify that a Stacks anchored block attaches to its parent anchored block.
+    /// * checks .header.total_work.work
+    /// * checks .header.parent_block
+    fn check_block_attachment(
+        parent_block_header: &StacksBlockHeader,
+        block_header: &StacksBlockHeader,
+    ) -> bool {
+        // must have the right height
+        if parent_block_header
+            .total_work
+            .work
+            .checked_add(1)
+            .expect("Blockchain height overflow")
+            != block_header.total_work.work
+        {
+            return false;
+        }
+
+        // must have right hash linkage
+        if parent_block_header.block_hash() != block_header.parent_block {
+            return false;
+        }
+
+        return true;
+    }
+
+    /// Get the parent header info for a block we're processing, if it's known.
+    /// The header info will be pulled from the headers DB, so this method only succeeds if the
+    /// parent block has been processed.
+    /// If it's not known, return None.
+    fn get_parent_header_info(
+        chainstate_tx: &mut ChainstateTx,
+        next_staging_block: &StagingBlock,
+    ) -> Result<Option<StacksHeaderInfo>, Error> {
+        let parent_block_header_info = match StacksChainState::get_anchored_block_header_info(
+            &chainstate_tx.tx,
+            &next_staging_block.parent_consensus_hash,
+            &next_staging_block.parent_anchored_block_hash,
+        )? {
+            Some(parent_info) => {
+                debug!(
+                    "Found parent info {}/{}",
+                    next_staging_block.parent_consensus_hash,
+                    next_staging_block.parent_anchored_block_hash
+                );
+                parent_info
+            }
+            None => {
+                if next_staging_block.is_first_mined() {
+                    // this is the first-ever mined block
+                    debug!("This is the first-ever block in this fork.  Parent is 00000000..00000000/00000000..00000000");
+                    StacksChainState::get_anchored_block_header_info(
+                        &chainstate_tx.tx,
+                        &FIRST_BURNCHAIN_CONSENSUS_HASH,
+                        &FIRST_STACKS_BLOCK_HASH,
+                    )
+                    .expect("FATAL: failed to load initial block header")
+                    .expect("FATAL: initial block header not found in headers DB")
+                } else {
+                    // no parent stored
+                    debug!(
+                        "No parent block for {}/{} processed yet",
+                        next_staging_block.consensus_hash, next_staging_block.anchored_block_hash
+                    );
+                    return Ok(None);
+                }
+            }
+        };
+        Ok(Some(parent_block_header_info))
+    }
+
+    /// Extract and parse the block from a loaded staging block, and verify its integrity.
+    fn extract_stacks_block(next_staging_block: &StagingBlock) -> Result<StacksBlock, Error> {
+        let block = {
+            StacksBlock::consensus_deserialize(&mut &next_staging_block.block_data[..])
+                .map_err(Error::CodecError)?
+        };
+
+        let block_hash = block.block_hash();
+        if block_hash != next_staging_block.anchored_block_hash {
+            // database corruption
+            error!(
+                "Staging DB corruption: expected block {}, got {} from disk",
+                next_staging_block.anchored_block_hash, block_hash
             );
             return Err(Error::DBError(db_error::Corruption));
         }
@@ -5867,8 +7008,38 @@ impl StacksChainState {
         &mut self,
         sort_tx: &mut SortitionHandleTx,
         dispatcher_opt: Option<&'a T>,
+    ) -> Result<(Option<StacksEpochReceipt>, Option<TransactionPayload>), Error> {
+        let mut prevalidator = self.block_prevalidator.take();
+        let res = self.process_next_prevalidated_staging_block(
+            sort_tx,
+            dispatcher_opt,
+            prevalidator.as_mut(),
+        );
+        self.block_prevalidator = prevalidator;
+        res
+    }
+
+    /// Find and process the next staging block, as `process_next_staging_block`.  If
+    /// `prevalidator` has already checked the block's transactions, they are not checked again.
+    fn process_next_prevalidated_staging_block<'a, T: BlockEventDispatcher>(
+        &mut self,
+        sort_tx: &mut SortitionHandleTx,
+        dispatcher_opt: Option<&'a T>,
+        prevalidator: Option<&mut BlockPrevalidator>,
     ) -> Result<(Option<StacksEpochReceipt>, Option<TransactionPayload>), Error> {
         let blocks_path = self.blocks_path.clone();
+        let required_confirmations = self.required_commit_confirmations;
+        let burn_tip_height =
+            match SortitionDB::get_block_snapshot(sort_tx.tx(), &sort_tx.context.chain_tip)? {
+                Some(sn) => sn.block_height,
+                None => {
+                    // shouldn't happen
+                    panic!(
+                        "CORRUPTION: no snapshot for the sortition tip {}",
+                        &sort_tx.context.chain_tip
+                    );
+                }
+            };
         let (mut chainstate_tx, clarity_instance) = self.chainstate_tx_begin()?;
 
         // this is a transaction against both the headers and staging blocks databases!
@@ -5877,6 +7048,8 @@ impl StacksChainState {
                 &mut chainstate_tx.tx,
                 &blocks_path,
                 sort_tx,
+                burn_tip_height,
+                required_confirmations,
             )? {
                 Some((next_microblocks, next_staging_block)) => {
                     (next_microblocks, next_staging_block)
@@ -6015,6 +7188,15 @@ impl StacksChainState {
             &next_staging_block.anchored_block_hash,
         )?;
 
+        let prevalidated_txids = prevalidator
+            .and_then(|prevalidator| {
+                prevalidator.take_prevalidated(&StacksBlockId::new(
+                    &next_staging_block.consensus_hash,
+                    &next_staging_block.anchored_block_hash,
+                ))
+            })
+            .unwrap_or_default();
+
         // attach the block to the chain state and calculate the next chain tip.
         // Execute the confirmed microblocks' transactions against the chain state, and then
         // execute the anchored block's transactions against the chain state.
@@ -6033,6 +7215,7 @@ impl StacksChainState {
             next_staging_block.commit_burn,
             next_staging_block.sortition_burn,
             &user_supports,
+            prevalidated_txids,
         ) {
             Ok(next_chain_tip_info) => next_chain_tip_info,
             Err(e) => {
@@ -6086,6 +7269,14 @@ impl StacksChainState {
 
                 chainstate_tx.commit().map_err(Error::DBError)?;
 
+                if let Some(dispatcher) = dispatcher_opt {
+                    dispatcher.announce_rejected_block(
+                        &next_staging_block.consensus_hash,
+                        &block.block_hash(),
+                        &e.to_string(),
+                    );
+                }
+
                 return Err(e);
             }
         };
@@ -6130,6 +7321,18 @@ impl StacksChainState {
             )?;
         }
 
+        StacksChainState::record_block_commit_link(
+            &chainstate_tx.tx,
+            &BlockCommitLink {
+                index_block_hash: epoch_receipt.header.index_block_hash(),
+                block_height: epoch_receipt.header.stacks_block_height,
+                l1_txid: winning_block_txid.clone(),
+                l1_block_hash: burn_header_hash.clone(),
+                l1_block_height: burn_header_height as u64,
+                confirmations: commit_confirmations(burn_header_height as u64, burn_tip_height),
+            },
+        )?;
+
         if let Some(dispatcher) = dispatcher_opt {
             let parent_id = StacksBlockId::new(
                 &next_staging_block.parent_consensus_hash,
@@ -6148,6 +7351,8 @@ impl StacksChainState {
                 epoch_receipt.parent_burn_block_timestamp,
                 &epoch_receipt.anchored_block_cost,
                 &epoch_receipt.parent_microblocks_cost,
+                &epoch_receipt.withdrawals_queued,
+                &epoch_receipt.withdrawals_released,
             );
         }
 
@@ -6207,6 +7412,11 @@ impl StacksChainState {
             return Ok(vec![]);
         }
 
+        // keep the block prevalidator's workers busy while we execute blocks
+        if let Err(e) = self.queue_blocks_for_prevalidation() {
+            warn!("Failed to queue staging blocks for prevalidation: {:?}", &e);
+        }
+
         for i in 0..max_blocks {
             // process up to max_blocks pending blocks
             match self.process_next_staging_block(&mut sort_tx, dispatcher_opt) {
@@ -6339,6 +7549,11 @@ impl StacksChainState {
     ) -> Result<(), MemPoolRejection> {
         let is_mainnet = self.clarity_state.is_mainnet();
         StacksChainState::can_admit_mempool_semantic(tx, is_mainnet)?;
+        if !self.contract_publish_policy.admits(tx) {
+            return Err(MemPoolRejection::ContractPublishNotAllowed(
+                tx.origin_address(),
+            ));
+        }
 
         let conf = self.config();
         let _staging_height =
@@ -6361,6 +7576,19 @@ impl StacksChainState {
 
         let current_tip =
             StacksChainState::get_parent_index_block(current_consensus_hash, current_block);
+
+        // the fee market's floor only gates admission; miners may still include cheaper
+        // transactions
+        let min_fee_rate = StacksChainState::get_min_fee_rate(self.db(), &current_tip)
+            .map_err(MemPoolRejection::DBError)?;
+        let fee = tx.get_tx_fee();
+        if tx_size > 0 && fee / tx_size < min_fee_rate {
+            return Err(MemPoolRejection::FeeTooLow(
+                fee,
+                tx_size.saturating_mul(min_fee_rate),
+            ));
+        }
+
         let res = match self.with_read_only_clarity_tx(&NULL_BURN_STATE_DB, &current_tip, |conn| {
             StacksChainState::can_include_tx(conn, &conf, true, tx, tx_size)
         }) {
@@ -6427,7 +7655,15 @@ impl StacksChainState {
             MemPoolRejection::ConflictingNonceInMempool |
             MemPoolRejection::BadTransactionVersion |
             MemPoolRejection::TransferAmountMustBePositive |
-            MemPoolRejection::TransferRecipientIsSender(_) => Err(e)
+            MemPoolRejection::TransferRecipientIsSender(_) |
+            MemPoolRejection::ContractPublishNotAllowed(_) |
+            MemPoolRejection::TransactionTooLarge(..) |
+            MemPoolRejection::PayloadTypeNotAllowed(_) |
+            MemPoolRejection::ContractCodeTooLarge(..) |
+            MemPoolRejection::ContractPublishTooExpensive(..) |
+            MemPoolRejection::AlreadyExpired(..) |
+            MemPoolRejection::BundleOriginMismatch(..) |
+            MemPoolRejection::Cached { .. } => Err(e)
         })
     }
 
@@ -6720,6 +7956,7 @@ pub mod test {
     use crate::chainstate::stacks::test::*;
     use crate::chainstate::stacks::Error as chainstate_error;
     use crate::chainstate::stacks::*;
+    use crate::clarity_vm::withdrawal::generate_key_from_event;
     use crate::core::mempool::*;
     use crate::net::test::*;
     use crate::net::ExtendedStacksHeader;
@@ -7223,6 +8460,7 @@ pub mod test {
         sortition_burn: u64,
     ) {
         let blocks_path = chainstate.blocks_path.clone();
+        let compression = chainstate.get_block_compression();
         let mut tx = chainstate.db_tx_begin().unwrap();
         StacksChainState::store_staging_block(
             &mut tx,
@@ -7233,6 +8471,7 @@ pub mod test {
             commit_burn,
             sortition_burn,
             5,
+            compression,
         )
         .unwrap();
         tx.commit().unwrap();
@@ -7251,12 +8490,14 @@ pub mod test {
         parent_anchored_block_hash: &BlockHeaderHash,
         microblock: &StacksMicroblock,
     ) {
+        let compression = chainstate.get_block_compression();
         let mut tx = chainstate.db_tx_begin().unwrap();
         StacksChainState::store_staging_microblock(
             &mut tx,
             parent_consensus_hash,
             parent_anchored_block_hash,
             microblock,
+            compression,
         )
         .unwrap();
         tx.commit().unwrap();
@@ -7462,8 +8703,13 @@ pub mod test {
         )
         .unwrap());
 
-        StacksChainState::store_block(&chainstate.blocks_path, &ConsensusHash([1u8; 20]), &block)
-            .unwrap();
+        StacksChainState::store_block(
+            &chainstate.blocks_path,
+            &ConsensusHash([1u8; 20]),
+            &block,
+            BlockCompression::None,
+        )
+        .unwrap();
         assert!(fs::metadata(&path).is_ok());
         assert!(StacksChainState::has_stored_block(
             &chainstate.db(),
@@ -7530,61 +8776,137 @@ pub mod test {
     }
 
     #[test]
-    fn stacks_db_staging_block_load_store_accept() {
-        let mut chainstate = instantiate_chainstate(
-            false,
-            0x80000000,
-            "stacks_db_staging_block_load_store_accept",
-        );
+    fn stacks_db_block_load_store_compressed() {
+        let chainstate =
+            instantiate_chainstate(false, 0x80000000, "stacks_db_block_load_store_compressed");
         let privk = StacksPrivateKey::from_hex(
             "eb05c83546fdd2c79f10f5ad5434a90dd28f7e3acb7c092157aa1bc3656b012c01",
         )
         .unwrap();
 
         let block = make_empty_coinbase_block(&privk);
+        let mut block_bytes = vec![];
+        block.consensus_serialize(&mut block_bytes).unwrap();
 
-        assert!(StacksChainState::load_staging_block_data(
-            &chainstate.db(),
+        StacksChainState::store_block(
             &chainstate.blocks_path,
-            &ConsensusHash([2u8; 20]),
-            &block.block_hash()
-        )
-        .unwrap()
-        .is_none());
-
-        store_staging_block(
-            &mut chainstate,
-            &ConsensusHash([2u8; 20]),
+            &ConsensusHash([1u8; 20]),
             &block,
+            BlockCompression::Zstd,
+        )
+        .unwrap();
+        let path = StacksChainState::get_block_path(
+            &chainstate.blocks_path,
             &ConsensusHash([1u8; 20]),
-            1,
-            2,
-        );
-
-        assert_block_staging_not_processed(&mut chainstate, &ConsensusHash([2u8; 20]), &block);
-        assert_block_not_stored(&mut chainstate, &ConsensusHash([2u8; 20]), &block);
-
-        set_block_processed(
-            &mut chainstate,
-            &ConsensusHash([2u8; 20]),
-            &block.block_hash(),
-            true,
-        );
-
-        assert_block_stored_not_staging(&mut chainstate, &ConsensusHash([2u8; 20]), &block);
-
-        // should be idempotent
-        set_block_processed(
-            &mut chainstate,
-            &ConsensusHash([2u8; 20]),
             &block.block_hash(),
-            true,
-        );
-
-        assert_block_stored_not_staging(&mut chainstate, &ConsensusHash([2u8; 20]), &block);
-    }
+        )
+        .unwrap();
+        assert_ne!(fs::read(&path).unwrap(), block_bytes);
 
-    #[test]
+        // everything reading the block sees it decompressed
+        assert_eq!(
+            StacksChainState::load_block(
+                &chainstate.blocks_path,
+                &ConsensusHash([1u8; 20]),
+                &block.block_hash()
+            )
+            .unwrap()
+            .unwrap(),
+            block
+        );
+        assert_eq!(
+            StacksChainState::load_block_header(
+                &chainstate.blocks_path,
+                &ConsensusHash([1u8; 20]),
+                &block.block_hash()
+            )
+            .unwrap()
+            .unwrap(),
+            block.header
+        );
+        assert_eq!(
+            StacksChainState::load_block_bytes(
+                &chainstate.blocks_path,
+                &ConsensusHash([1u8; 20]),
+                &block.block_hash()
+            )
+            .unwrap()
+            .unwrap(),
+            block_bytes
+        );
+
+        let index_block_hash = StacksBlockHeader::make_index_block_hash(
+            &ConsensusHash([1u8; 20]),
+            &block.block_hash(),
+        );
+        let mut stream = StreamCursor::new_block(index_block_hash);
+        let mut streamed = vec![];
+        loop {
+            let next_bytes = stream_chunk_to_vec(&chainstate.blocks_path, &mut stream, 16).unwrap();
+            if next_bytes.len() == 0 {
+                break;
+            }
+            streamed.extend_from_slice(&next_bytes);
+        }
+        assert_eq!(streamed, block_bytes);
+    }
+
+    #[test]
+    fn stacks_db_staging_block_load_store_accept() {
+        let mut chainstate = instantiate_chainstate(
+            false,
+            0x80000000,
+            "stacks_db_staging_block_load_store_accept",
+        );
+        let privk = StacksPrivateKey::from_hex(
+            "eb05c83546fdd2c79f10f5ad5434a90dd28f7e3acb7c092157aa1bc3656b012c01",
+        )
+        .unwrap();
+
+        let block = make_empty_coinbase_block(&privk);
+
+        assert!(StacksChainState::load_staging_block_data(
+            &chainstate.db(),
+            &chainstate.blocks_path,
+            &ConsensusHash([2u8; 20]),
+            &block.block_hash()
+        )
+        .unwrap()
+        .is_none());
+
+        store_staging_block(
+            &mut chainstate,
+            &ConsensusHash([2u8; 20]),
+            &block,
+            &ConsensusHash([1u8; 20]),
+            1,
+            2,
+        );
+
+        assert_block_staging_not_processed(&mut chainstate, &ConsensusHash([2u8; 20]), &block);
+        assert_block_not_stored(&mut chainstate, &ConsensusHash([2u8; 20]), &block);
+
+        set_block_processed(
+            &mut chainstate,
+            &ConsensusHash([2u8; 20]),
+            &block.block_hash(),
+            true,
+        );
+
+        assert_block_stored_not_staging(&mut chainstate, &ConsensusHash([2u8; 20]), &block);
+
+        // should be idempotent
+        set_block_processed(
+            &mut chainstate,
+            &ConsensusHash([2u8; 20]),
+            &block.block_hash(),
+            true,
+        );
+
+        assert_block_stored_not_staging(&mut chainstate, &ConsensusHash([2u8; 20]), &block);
+    }
+
+    #[test]
     fn stacks_db_staging_block_load_store_reject() {
         let mut chainstate = instantiate_chainstate(
             false,
@@ -11553,9 +12875,21 @@ pub mod test {
         ];
 
         // process ops
-        let processed_ops = StacksChainState::process_deposit_ft_ops(&mut conn, ops);
+        let processed_ops = StacksChainState::process_deposit_ft_ops(&mut conn, ops.clone(), false);
 
         assert_eq!(processed_ops.len(), 1);
+
+        // once deposit refunds activate, the deposit into the missing contract is refunded
+        let processed_ops = StacksChainState::process_deposit_ft_ops(&mut conn, ops, true);
+
+        assert_eq!(processed_ops.len(), 2);
+        assert_eq!(processed_ops[0].result, Value::okay_true());
+        assert_eq!(processed_ops[1].result, Value::err_none());
+        assert_eq!(processed_ops[1].events.len(), 1);
+        assert!(matches!(
+            processed_ops[1].events[0],
+            StacksTransactionEvent::SmartContractEvent(_)
+        ));
     }
 
     #[test]
@@ -11587,6 +12921,16 @@ pub mod test {
           (nft-mint? nft-token id recipient)
         )
 
+        (define-public (deposit-from-burnchain-with-metadata
+            (id uint)
+            (recipient principal)
+            (token-uri (optional (string-ascii 256))))
+          (begin
+            (try! (nft-mint? nft-token id recipient))
+            (ok token-uri)
+          )
+        )
+
         (define-public (burn-for-withdrawal (id uint) (owner principal))
           (nft-burn? nft-token id owner)
         )
@@ -11632,13 +12976,170 @@ pub mod test {
                 ),
                 id: 2,
                 sender: PrincipalData::from(addr_publisher),
+                token_uri: None,
+            },
+            // this op carries the L1 token URI
+            DepositNftOp {
+                txid: Txid([2; 32]),
+                burn_header_hash: BurnchainHeaderHash([0; 32]),
+                l1_contract_id: QualifiedContractIdentifier::local("l1-contract").unwrap(),
+                subnet_contract_id: QualifiedContractIdentifier::new(
+                    StandardPrincipalData::from(addr_publisher),
+                    ContractName::from("subnet-deposit-contract"),
+                ),
+                id: 3,
+                sender: PrincipalData::from(addr_publisher),
+                token_uri: Some("ipfs://token/3".to_string()),
             },
         ];
 
         // process ops
-        let processed_ops = StacksChainState::process_deposit_nft_ops(&mut conn, ops);
+        let processed_ops = StacksChainState::process_deposit_nft_ops(&mut conn, ops, false);
 
-        assert_eq!(processed_ops.len(), 1);
+        assert_eq!(processed_ops.len(), 2);
+        assert_eq!(processed_ops[0].result, Value::okay_true());
+        // the metadata-aware deposit function was called with the token URI
+        assert_eq!(
+            processed_ops[1].result,
+            Value::okay(
+                Value::some(
+                    Value::string_ascii_from_bytes("ipfs://token/3".as_bytes().to_vec()).unwrap()
+                )
+                .unwrap()
+            )
+            .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_process_deposit_sft_ops() {
+        let mut chainstate =
+            instantiate_chainstate(false, 0x80000000, "test_process_deposit_sft_ops");
+
+        let privk_user = StacksPrivateKey::from_hex(
+            "027682d2f7b05c3801fe4467883ab4cff0568b5e36412b5289e83ea5b519de8a01",
+        )
+        .unwrap();
+        let auth_user = TransactionAuth::from_p2pkh(&privk_user).unwrap();
+        let addr_publisher = auth_user.origin().address_testnet();
+
+        let mut conn = chainstate.block_begin(
+            &TEST_BURN_STATE_DB,
+            &FIRST_BURNCHAIN_CONSENSUS_HASH,
+            &FIRST_STACKS_BLOCK_HASH,
+            &ConsensusHash([1u8; 20]),
+            &BlockHeaderHash([1u8; 32]),
+        );
+
+        let subnet_simple_sft = "
+        (define-fungible-token sft-token)
+        (define-map balances { id: uint, owner: principal } uint)
+
+        (define-read-only (get-balance (id uint) (who principal))
+            (ok (default-to u0 (map-get? balances { id: id, owner: who })))
+        )
+
+        (define-public (deposit-from-burnchain (id uint) (amount uint) (recipient principal))
+          (begin
+            (try! (ft-mint? sft-token amount recipient))
+            (map-set balances { id: id, owner: recipient }
+              (+ amount (unwrap-panic (get-balance id recipient))))
+            (ok true)
+          )
+        )
+        ";
+
+        let mut subnet_deposit_contract_tx = StacksTransaction::new(
+            TransactionVersion::Testnet,
+            auth_user.clone(),
+            TransactionPayload::new_smart_contract(
+                "subnet-deposit-contract",
+                subnet_simple_sft,
+                None,
+            )
+            .unwrap(),
+        );
+
+        subnet_deposit_contract_tx.chain_id = 0x80000000;
+        subnet_deposit_contract_tx.set_tx_fee(0);
+
+        let mut signer = StacksTransactionSigner::new(&subnet_deposit_contract_tx);
+        signer.sign_origin(&privk_user).unwrap();
+
+        let signed_contract_tx = signer.get_tx().unwrap();
+
+        // publish contract on the subnet
+        let _ =
+            StacksChainState::process_transaction(&mut conn, &signed_contract_tx, false).unwrap();
+
+        // create deposit sft ops
+        let ops = vec![
+            // this op is well formed
+            DepositSftOp {
+                txid: Txid([1; 32]),
+                burn_header_hash: BurnchainHeaderHash([0; 32]),
+                l1_contract_id: QualifiedContractIdentifier::local("l1-contract").unwrap(),
+                subnet_contract_id: QualifiedContractIdentifier::new(
+                    StandardPrincipalData::from(addr_publisher),
+                    ContractName::from("subnet-deposit-contract"),
+                ),
+                id: 2,
+                amount: 10,
+                sender: PrincipalData::from(addr_publisher),
+            },
+            // this op fails in the contract (zero-amount mint), so it is refunded
+            DepositSftOp {
+                txid: Txid([2; 32]),
+                burn_header_hash: BurnchainHeaderHash([0; 32]),
+                l1_contract_id: QualifiedContractIdentifier::local("l1-contract").unwrap(),
+                subnet_contract_id: QualifiedContractIdentifier::new(
+                    StandardPrincipalData::from(addr_publisher),
+                    ContractName::from("subnet-deposit-contract"),
+                ),
+                id: 3,
+                amount: 0,
+                sender: PrincipalData::from(addr_publisher),
+            },
+            // this op tries to call a function in an unregistered contract
+            DepositSftOp {
+                txid: Txid([3; 32]),
+                burn_header_hash: BurnchainHeaderHash([0; 32]),
+                l1_contract_id: QualifiedContractIdentifier::local("l1-contract").unwrap(),
+                subnet_contract_id: QualifiedContractIdentifier::new(
+                    StandardPrincipalData::from(addr_publisher),
+                    ContractName::from("subnet-deposit-contract-DNE"),
+                ),
+                id: 2,
+                amount: 10,
+                sender: PrincipalData::from(addr_publisher),
+            },
+        ];
+
+        // process ops
+        let processed_ops =
+            StacksChainState::process_deposit_sft_ops(&mut conn, ops.clone(), false);
+
+        assert_eq!(processed_ops.len(), 2);
+        // the failed deposit emits a refund withdrawal event
+        assert_eq!(
+            processed_ops[0].result,
+            Value::okay(Value::Bool(true)).unwrap()
+        );
+        assert_eq!(
+            processed_ops[1].result,
+            Value::error(Value::UInt(1)).unwrap()
+        );
+        assert!(processed_ops[1]
+            .events
+            .iter()
+            .any(|e| matches!(e, StacksTransactionEvent::SmartContractEvent(_))));
+
+        // once deposit refunds activate, so does the deposit into the missing contract
+        let processed_ops = StacksChainState::process_deposit_sft_ops(&mut conn, ops, true);
+
+        assert_eq!(processed_ops.len(), 3);
+        assert_eq!(processed_ops[2].result, Value::err_none());
+        assert_eq!(processed_ops[2].events.len(), 1);
     }
 
     #[test]
@@ -11684,6 +13185,194 @@ pub mod test {
         assert_eq!(orig_balance + 2, account.stx_balance.amount_unlocked());
     }
 
+    #[test]
+    fn test_process_time_oracle_update() {
+        let mut chainstate =
+            instantiate_chainstate(false, 0x80000000, "test_process_time_oracle_update");
+
+        let mut conn = chainstate.block_begin(
+            &TEST_BURN_STATE_DB,
+            &FIRST_BURNCHAIN_CONSENSUS_HASH,
+            &FIRST_STACKS_BLOCK_HASH,
+            &ConsensusHash([1u8; 20]),
+            &BlockHeaderHash([1u8; 32]),
+        );
+
+        // the time oracle only exists once it activates
+        assert!(
+            StacksChainState::process_time_oracle_update(&mut conn, 12, 1_650_000_000).is_err()
+        );
+        let receipt = StacksChainState::install_boot_contract(
+            &mut conn,
+            TIME_ORACLE_NAME,
+            BOOT_CODE_TIME_ORACLE,
+        )
+        .unwrap();
+        assert_eq!(receipt.result, Value::okay_true());
+
+        StacksChainState::process_time_oracle_update(&mut conn, 12, 1_650_000_000).unwrap();
+
+        let sender: PrincipalData = boot_code_addr(false).into();
+        let contract_id = boot_code_id(TIME_ORACLE_NAME, false);
+        let mut read_oracle = |function: &str, args: &[Value]| {
+            conn.connection()
+                .as_transaction(|tx| {
+                    tx.run_contract_call(&sender, None, &contract_id, function, args, |_, _| false)
+                })
+                .unwrap()
+                .0
+        };
+        assert_eq!(read_oracle("get-time", &[]), Value::UInt(1_650_000_000));
+        assert_eq!(read_oracle("get-burn-block-height", &[]), Value::UInt(12));
+        assert_eq!(
+            read_oracle("get-time-at-height", &[Value::UInt(1)]),
+            Value::some(Value::UInt(1_650_000_000)).unwrap()
+        );
+        assert_eq!(
+            read_oracle("get-time-at-height", &[Value::UInt(2)]),
+            Value::none()
+        );
+
+        // nobody but the boot address can set the clock
+        let user_principal: PrincipalData = StacksAddress {
+            version: C32_ADDRESS_VERSION_TESTNET_SINGLESIG,
+            bytes: Hash160([1u8; 20]),
+        }
+        .into();
+        let result = conn
+            .connection()
+            .as_transaction(|tx| {
+                tx.run_contract_call(
+                    &user_principal,
+                    None,
+                    &contract_id,
+                    "set-burn-block-time",
+                    &[Value::UInt(13), Value::UInt(0)],
+                    |_, _| false,
+                )
+            })
+            .unwrap()
+            .0;
+        assert_eq!(result, Value::err_uint(17));
+    }
+
+    #[test]
+    fn test_install_subnet_transfers() {
+        let mut chainstate =
+            instantiate_chainstate(false, 0x80000000, "test_install_subnet_transfers");
+
+        let privk_user = StacksPrivateKey::from_hex(
+            "027682d2f7b05c3801fe4467883ab4cff0568b5e36412b5289e83ea5b519de8a01",
+        )
+        .unwrap();
+        let auth_user = TransactionAuth::from_p2pkh(&privk_user).unwrap();
+        let addr_user = auth_user.origin().address_testnet();
+        let user_principal: PrincipalData = addr_user.into();
+        let destination_subnet = Value::Principal(PrincipalData::Contract(
+            QualifiedContractIdentifier::new(addr_user.into(), "subnet-b".into()),
+        ));
+
+        let mut conn = chainstate.block_begin(
+            &TEST_BURN_STATE_DB,
+            &FIRST_BURNCHAIN_CONSENSUS_HASH,
+            &FIRST_STACKS_BLOCK_HASH,
+            &ConsensusHash([1u8; 20]),
+            &BlockHeaderHash([1u8; 32]),
+        );
+        StacksChainState::process_deposit_stx_ops(
+            &mut conn,
+            vec![DepositStxOp {
+                txid: Txid([1; 32]),
+                burn_header_hash: BurnchainHeaderHash([0; 32]),
+                amount: 1000,
+                sender: user_principal.clone(),
+            }],
+        );
+
+        let contract_id = boot_code_id(SUBNET_TRANSFERS_NAME, false);
+        let transfer = |conn: &mut ClarityTx, destination_subnet: &Value| {
+            conn.connection().as_transaction(|tx| {
+                tx.run_contract_call(
+                    &user_principal,
+                    None,
+                    &contract_id,
+                    "stx-transfer-to-subnet?",
+                    &[
+                        Value::UInt(400),
+                        Value::Principal(user_principal.clone()),
+                        Value::Principal(user_principal.clone()),
+                        destination_subnet.clone(),
+                    ],
+                    |_, _| false,
+                )
+            })
+        };
+
+        // the contract only exists once it activates
+        assert!(transfer(&mut conn, &destination_subnet).is_err());
+        let receipt = StacksChainState::install_boot_contract(
+            &mut conn,
+            SUBNET_TRANSFERS_NAME,
+            BOOT_CODE_SUBNET_TRANSFERS,
+        )
+        .unwrap();
+        assert_eq!(receipt.result, Value::okay_true());
+
+        // the destination subnet must have been registered on the L1
+        let (result, _, _) =
+            transfer(&mut conn, &Value::Principal(user_principal.clone())).unwrap();
+        assert_eq!(result, Value::err_uint(18));
+        let (result, _, _) = transfer(&mut conn, &destination_subnet).unwrap();
+        assert_eq!(result, Value::err_uint(18));
+
+        // only the node registers destination subnets
+        let (result, _, _) = conn
+            .connection()
+            .as_transaction(|tx| {
+                tx.run_contract_call(
+                    &user_principal,
+                    None,
+                    &contract_id,
+                    "register-destination-subnet",
+                    &[
+                        destination_subnet.clone(),
+                        Value::buff_from(vec![0; 32]).unwrap(),
+                    ],
+                    |_, _| false,
+                )
+            })
+            .unwrap();
+        assert_eq!(result, Value::err_uint(17));
+
+        let destination_subnet_id = match destination_subnet {
+            Value::Principal(PrincipalData::Contract(ref id)) => id.clone(),
+            _ => unreachable!(),
+        };
+        let receipts = StacksChainState::process_register_asset_ops(
+            &mut conn,
+            vec![RegisterAssetOp {
+                txid: Txid([2; 32]),
+                burn_header_hash: BurnchainHeaderHash([0; 32]),
+                asset_type: AssetType::Subnet,
+                l1_contract_id: destination_subnet_id.clone(),
+                l2_contract_id: destination_subnet_id,
+            }],
+        );
+        assert_eq!(receipts.len(), 1);
+        assert_eq!(receipts[0].result, Value::okay_true());
+
+        let (result, _, mut events) = transfer(&mut conn, &destination_subnet).unwrap();
+        assert!(matches!(result, Value::Response(ref response) if response.committed));
+        let withdrawal_keys: Vec<_> = events
+            .iter_mut()
+            .filter_map(|event| generate_key_from_event(event, 0, 1))
+            .collect();
+        assert_eq!(withdrawal_keys.len(), 1);
+
+        let account = StacksChainState::get_account(&mut conn, &user_principal);
+        assert_eq!(account.stx_balance.amount_unlocked(), 600);
+    }
+
     #[cfg(test)]
     fn make_deposit_stx_op(
         addr: &StacksAddress,
@@ -11700,6 +13389,147 @@ pub mod test {
         deposit_op
     }
 
+    #[test]
+    fn test_deposit_confirmations_window() {
+        let deposit_confirmations = 2;
+        let mut peer_config =
+            TestPeerConfig::new("test_deposit_confirmations_window", 21317, 21318);
+        peer_config.deposit_confirmations = deposit_confirmations;
+        let privk = StacksPrivateKey::from_hex(
+            "eb05c83546fdd2c79f10f5ad5434a90dd28f7e3acb7c092157aa1bc3656b012c01",
+        )
+        .unwrap();
+        let addr = StacksAddress::from_public_keys(
+            C32_ADDRESS_VERSION_TESTNET_SINGLESIG,
+            &AddressHashMode::SerializeP2PKH,
+            1,
+            &vec![StacksPublicKey::from_private(&privk)],
+        )
+        .unwrap();
+
+        let initial_balance = 1000000000;
+        peer_config.initial_balances = vec![(addr.to_account_principal(), initial_balance)];
+
+        let mut peer = TestPeer::new(peer_config);
+
+        let chainstate_path = peer.chainstate_path.clone();
+
+        let num_blocks = 8;
+        let first_stacks_block_height = {
+            let sn =
+                SortitionDB::get_canonical_burn_chain_tip(&peer.sortdb.as_ref().unwrap().conn())
+                    .unwrap();
+            sn.block_height
+        };
+
+        // the deposit is sent in the L1 block after the one the tenure's block is anchored to
+        let deposit_tenure_id = 1;
+        let deposit_burn_height = first_stacks_block_height + deposit_tenure_id as u64 + 1;
+        let deposit_op = make_deposit_stx_op(&addr, &addr, deposit_burn_height, deposit_tenure_id);
+
+        for tenure_id in 0..num_blocks {
+            let tip =
+                SortitionDB::get_canonical_burn_chain_tip(&peer.sortdb.as_ref().unwrap().conn())
+                    .unwrap();
+
+            assert_eq!(
+                tip.block_height,
+                first_stacks_block_height + (tenure_id as u64)
+            );
+
+            let (mut burn_ops, stacks_block, microblocks) = peer.make_tenure(
+                |ref mut miner,
+                 ref mut sortdb,
+                 ref mut chainstate,
+                 vrf_proof,
+                 ref parent_opt,
+                 ref parent_microblock_header_opt| {
+                    let parent_tip = match parent_opt {
+                        None => StacksChainState::get_genesis_header_info(chainstate.db()).unwrap(),
+                        Some(block) => {
+                            let ic = sortdb.index_conn();
+                            let snapshot =
+                                SortitionDB::get_block_snapshot_for_winning_stacks_block(
+                                    &ic,
+                                    &tip.sortition_id,
+                                    &block.block_hash(),
+                                )
+                                .unwrap()
+                                .unwrap(); // succeeds because we don't fork
+                            StacksChainState::get_anchored_block_header_info(
+                                chainstate.db(),
+                                &snapshot.consensus_hash,
+                                &snapshot.winning_stacks_block_hash,
+                            )
+                            .unwrap()
+                            .unwrap()
+                        }
+                    };
+
+                    let mut mempool =
+                        MemPoolDB::open_test(false, 0x80000000, &chainstate_path).unwrap();
+                    let coinbase_tx = make_coinbase(miner, tenure_id);
+
+                    let anchored_block = StacksBlockBuilder::build_anchored_block(
+                        chainstate,
+                        &sortdb.index_conn(),
+                        &mut mempool,
+                        &parent_tip,
+                        tip.total_burn,
+                        vrf_proof,
+                        Hash160([tenure_id as u8; 20]),
+                        &coinbase_tx,
+                        BlockBuilderSettings::max_value(),
+                        None,
+                    )
+                    .unwrap();
+
+                    (anchored_block.0, vec![])
+                },
+            );
+
+            if tenure_id == deposit_tenure_id {
+                burn_ops.push(BlockstackOperationType::DepositStx(deposit_op.clone()));
+            }
+
+            peer.next_burnchain_block(burn_ops.clone());
+            peer.process_stacks_epoch_at_tip(&stacks_block, &microblocks);
+
+            let sortdb = peer.sortdb.take().unwrap();
+            let (consensus_hash, block_bhh) =
+                SortitionDB::get_canonical_stacks_chain_tip_hash(sortdb.conn()).unwrap();
+            let tip_hash = StacksBlockHeader::make_index_block_hash(&consensus_hash, &block_bhh);
+            let account = peer
+                .chainstate()
+                .with_read_only_clarity_tx(&sortdb.index_conn(), &tip_hash, |conn| {
+                    StacksChainState::get_account(conn, &addr.to_account_principal())
+                })
+                .unwrap();
+            peer.sortdb.replace(sortdb);
+
+            // the deposit is held until the block anchored `deposit_confirmations` L1 blocks
+            // after it, and is credited only once
+            let anchor_height = tip.block_height;
+            let expected_balance = if anchor_height >= deposit_burn_height + deposit_confirmations {
+                initial_balance as u128 + deposit_op.amount
+            } else {
+                initial_balance as u128
+            };
+            assert_eq!(
+                account.stx_balance.get_total_balance(),
+                expected_balance,
+                "unexpected balance in the block anchored at L1 height {}",
+                anchor_height
+            );
+        }
+
+        // the last block is anchored well past the deposit's window
+        assert!(
+            first_stacks_block_height + num_blocks as u64 - 1
+                > deposit_burn_height + deposit_confirmations
+        );
+    }
+
     #[test]
     fn test_get_ops_between_curr_block_and_parent() {
         let mut peer_config =
@@ -0,0 +1,232 @@
+// Copyright (C) 2013-2020 Blockstack PBC, a public benefit corporation
+// Copyright (C) 2020 Stacks Open Internet Foundation
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use rusqlite::types::ToSql;
+use rusqlite::Row;
+use rusqlite::NO_PARAMS;
+
+use crate::chainstate::stacks::db::StacksChainState;
+use crate::chainstate::stacks::Error;
+use crate::util_lib::db::Error as db_error;
+use crate::util_lib::db::{query_row, u64_to_sql, DBConn, DBTx, FromColumn, FromRow};
+use stacks_common::types::chainstate::StacksAddress;
+
+/// Who is paid the reward of each block
+#[derive(Debug, Clone, PartialEq)]
+pub enum CoinbaseRecipient {
+    /// the origin of the block's coinbase transaction
+    Miner,
+    /// a fixed address, e.g. a subnet treasury
+    Fixed(StacksAddress),
+}
+
+/// The coinbase each subnet block mints, in the subnet's native `.subnet-token`.  A block at
+/// height `h` mints `amount_per_block` halved once every `halving_interval` blocks, and the
+/// block's reward -- its coinbase and transaction fees -- is paid to `recipient` once it
+/// matures.
+///
+/// The schedule is recorded in the chainstate when it is booted, and decides the state root of
+/// every block, so every node of a subnet must boot with the same one.  The subnet token is not
+/// backed by any asset escrowed on the L1 and can't be withdrawn, so the coinbase can't drain
+/// the STX that depositors escrowed.  The default schedule mints nothing, and never installs
+/// `.subnet-token`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CoinbaseSchedule {
+    pub amount_per_block: u128,
+    /// 0 never halves the coinbase
+    pub halving_interval: u64,
+    pub recipient: CoinbaseRecipient,
+}
+
+impl Default for CoinbaseSchedule {
+    fn default() -> CoinbaseSchedule {
+        CoinbaseSchedule {
+            amount_per_block: 0,
+            halving_interval: 0,
+            recipient: CoinbaseRecipient::Miner,
+        }
+    }
+}
+
+impl FromRow<CoinbaseSchedule> for CoinbaseSchedule {
+    fn from_row<'a>(row: &'a Row) -> Result<CoinbaseSchedule, db_error> {
+        let amount_text: String = row.get_unwrap("amount_per_block");
+        let amount_per_block = amount_text
+            .parse::<u128>()
+            .map_err(|_e| db_error::ParseError)?;
+        let halving_interval = u64::from_column(row, "halving_interval")?;
+        let recipient_text: Option<String> = row.get_unwrap("recipient");
+        let recipient = match recipient_text {
+            Some(addr) => CoinbaseRecipient::Fixed(
+                StacksAddress::from_string(&addr).ok_or(db_error::ParseError)?,
+            ),
+            None => CoinbaseRecipient::Miner,
+        };
+        Ok(CoinbaseSchedule {
+            amount_per_block,
+            halving_interval,
+            recipient,
+        })
+    }
+}
+
+impl CoinbaseSchedule {
+    /// The coinbase minted by the block at `block_height`
+    pub fn reward_at(&self, block_height: u64) -> u128 {
+        if self.halving_interval == 0 {
+            return self.amount_per_block;
+        }
+        let halvings = block_height / self.halving_interval;
+        if halvings >= 128 {
+            return 0;
+        }
+        self.amount_per_block >> halvings
+    }
+
+    /// Does the block at `block_height` install `.subnet-token`, which the coinbase is minted in?
+    /// Schedules which mint nothing never install it.
+    pub fn installs_subnet_token(&self, block_height: u64) -> bool {
+        self.amount_per_block > 0 && block_height == 1
+    }
+
+    /// The address paid the reward of a block mined by `miner`
+    pub fn recipient_address(&self, miner: &StacksAddress) -> StacksAddress {
+        match self.recipient {
+            CoinbaseRecipient::Miner => miner.clone(),
+            CoinbaseRecipient::Fixed(ref addr) => addr.clone(),
+        }
+    }
+}
+
+impl StacksChainState {
+    /// Record the coinbase schedule of a chainstate being booted
+    pub fn insert_coinbase_schedule(tx: &DBTx, schedule: &CoinbaseSchedule) -> Result<(), Error> {
+        let recipient = match schedule.recipient {
+            CoinbaseRecipient::Miner => None,
+            CoinbaseRecipient::Fixed(ref addr) => Some(addr.to_string()),
+        };
+        let args: &[&dyn ToSql] = &[
+            &schedule.amount_per_block.to_string(),
+            &u64_to_sql(schedule.halving_interval)?,
+            &recipient,
+        ];
+        tx.execute(
+            "INSERT INTO coinbase_schedule (amount_per_block,halving_interval,recipient) VALUES (?1,?2,?3)",
+            args,
+        )?;
+        Ok(())
+    }
+
+    /// Get the coinbase schedule recorded when the chainstate was booted.  Chainstates booted
+    /// before schedules were recorded mint nothing.
+    pub fn get_coinbase_schedule(conn: &DBConn) -> Result<CoinbaseSchedule, Error> {
+        let schedule = query_row(conn, "SELECT * FROM coinbase_schedule LIMIT 1", NO_PARAMS)?;
+        Ok(schedule.unwrap_or_default())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::chainstate::stacks::db::test::instantiate_chainstate;
+
+    #[test]
+    fn test_coinbase_reward_at() {
+        let flat = CoinbaseSchedule {
+            amount_per_block: 1000,
+            halving_interval: 0,
+            recipient: CoinbaseRecipient::Miner,
+        };
+        assert_eq!(flat.reward_at(0), 1000);
+        assert_eq!(flat.reward_at(u64::MAX), 1000);
+
+        let halving = CoinbaseSchedule {
+            amount_per_block: 1000,
+            halving_interval: 10,
+            recipient: CoinbaseRecipient::Miner,
+        };
+        assert_eq!(halving.reward_at(0), 1000);
+        assert_eq!(halving.reward_at(9), 1000);
+        assert_eq!(halving.reward_at(10), 500);
+        assert_eq!(halving.reward_at(25), 250);
+        assert_eq!(halving.reward_at(100), 0);
+        assert_eq!(halving.reward_at(u64::MAX), 0);
+
+        assert_eq!(CoinbaseSchedule::default().reward_at(1), 0);
+    }
+
+    #[test]
+    fn test_installs_subnet_token() {
+        let schedule = CoinbaseSchedule {
+            amount_per_block: 1000,
+            halving_interval: 0,
+            recipient: CoinbaseRecipient::Miner,
+        };
+        assert!(!schedule.installs_subnet_token(0));
+        assert!(schedule.installs_subnet_token(1));
+        assert!(!schedule.installs_subnet_token(2));
+
+        // schedules which mint nothing don't need it
+        for height in 0..3 {
+            assert!(!CoinbaseSchedule::default().installs_subnet_token(height));
+        }
+    }
+
+    #[test]
+    fn test_coinbase_recipient_address() {
+        let miner =
+            StacksAddress::from_string("ST2ZRX0K27GW0SP3GJCEMHD95TQGJMKB7G9Y0X1MH").unwrap();
+        let treasury =
+            StacksAddress::from_string("ST1PQHQKV0RJXZFY1DGX8MNSNYVE3VGZJSRTPGZGM").unwrap();
+        assert_eq!(CoinbaseSchedule::default().recipient_address(&miner), miner);
+        let schedule = CoinbaseSchedule {
+            amount_per_block: 1000,
+            halving_interval: 0,
+            recipient: CoinbaseRecipient::Fixed(treasury.clone()),
+        };
+        assert_eq!(schedule.recipient_address(&miner), treasury);
+    }
+
+    #[test]
+    fn test_store_coinbase_schedule() {
+        let mut chainstate =
+            instantiate_chainstate(false, 0x80000000, "test_store_coinbase_schedule");
+
+        // booted chainstates record the default schedule
+        assert_eq!(
+            StacksChainState::get_coinbase_schedule(chainstate.db()).unwrap(),
+            CoinbaseSchedule::default()
+        );
+
+        let schedule = CoinbaseSchedule {
+            amount_per_block: 1_000_000,
+            halving_interval: 210_000,
+            recipient: CoinbaseRecipient::Fixed(
+                StacksAddress::from_string("ST1PQHQKV0RJXZFY1DGX8MNSNYVE3VGZJSRTPGZGM").unwrap(),
+            ),
+        };
+        let tx = chainstate.db_tx_begin().unwrap();
+        tx.execute("DELETE FROM coinbase_schedule", NO_PARAMS)
+            .unwrap();
+        StacksChainState::insert_coinbase_schedule(&tx, &schedule).unwrap();
+        tx.commit().unwrap();
+
+        assert_eq!(
+            StacksChainState::get_coinbase_schedule(chainstate.db()).unwrap(),
+            schedule
+        );
+    }
+}
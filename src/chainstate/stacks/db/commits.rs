@@ -0,0 +1,306 @@
+// Copyright (C) 2013-2020 Blockstack PBC, a public benefit corporation
+// Copyright (C) 2020 Stacks Open Internet Foundation
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! The L1 block-commit of each L2 block on the canonical chain, for light clients which check
+//! L2 blocks against the L1 without following the sortition DB themselves.
+//!
+//! When a node processes a block, it records the commit which chose it in the headers DB, along
+//! with how many L1 confirmations that commit had, so that which commit the node acted on can be
+//! audited later.  A node can also be made to wait for a number of confirmations before it
+//! processes a block at all, rather than acting on a block as soon as its commit is seen.
+
+use rusqlite::types::ToSql;
+use rusqlite::Row;
+
+use crate::burnchains::Txid;
+use crate::chainstate::burn::db::sortdb::SortitionDB;
+use crate::chainstate::stacks::db::StacksChainState;
+use crate::chainstate::stacks::{Error, StacksBlockHeader};
+use crate::util_lib::db::Error as db_error;
+use crate::util_lib::db::{query_row, u64_to_sql, DBConn, DBTx, FromColumn, FromRow};
+use stacks_common::types::chainstate::{
+    BlockHeaderHash, BurnchainHeaderHash, ConsensusHash, StacksBlockId,
+};
+
+/// The most L2 heights whose commits can be asked for at once
+pub const MAX_BLOCK_COMMITS: u64 = 100;
+
+/// How many L1 blocks, starting with the one at `commit_height`, a node whose L1 tip is at
+/// `burn_tip_height` has seen
+pub fn commit_confirmations(commit_height: u64, burn_tip_height: u64) -> u64 {
+    burn_tip_height
+        .saturating_add(1)
+        .saturating_sub(commit_height)
+}
+
+/// The L1 block-commit a processed block was recorded with
+#[derive(Debug, Clone, PartialEq)]
+pub struct BlockCommitLink {
+    pub index_block_hash: StacksBlockId,
+    pub block_height: u64,
+    pub l1_txid: Txid,
+    pub l1_block_hash: BurnchainHeaderHash,
+    pub l1_block_height: u64,
+    /// the commit's confirmations when the block was processed
+    pub confirmations: u64,
+}
+
+impl FromRow<BlockCommitLink> for BlockCommitLink {
+    fn from_row<'a>(row: &'a Row) -> Result<BlockCommitLink, db_error> {
+        let index_block_hash = StacksBlockId::from_column(row, "index_block_hash")?;
+        let block_height = u64::from_column(row, "block_height")?;
+        let l1_txid = Txid::from_column(row, "l1_txid")?;
+        let l1_block_hash = BurnchainHeaderHash::from_column(row, "l1_block_hash")?;
+        let l1_block_height = u64::from_column(row, "l1_block_height")?;
+        let confirmations = u64::from_column(row, "confirmations")?;
+        Ok(BlockCommitLink {
+            index_block_hash,
+            block_height,
+            l1_txid,
+            l1_block_hash,
+            l1_block_height,
+            confirmations,
+        })
+    }
+}
+
+/// The L1 block-commit which chose an L2 block
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct L2BlockCommit {
+    pub l2_height: u64,
+    pub index_block_hash: StacksBlockId,
+    /// the block hash the commit committed to
+    pub block_hash: BlockHeaderHash,
+    pub consensus_hash: ConsensusHash,
+    /// the commit transaction
+    pub l1_txid: Txid,
+    /// the L1 block which included the commit
+    pub l1_block_height: u64,
+    pub l1_block_hash: BurnchainHeaderHash,
+    /// the commit's confirmations now, counting the L1 block which included it
+    pub l1_confirmations: u64,
+    /// the commit's confirmations when this node processed the block, if it recorded them
+    pub confirmations_at_processing: Option<u64>,
+}
+
+/// The commits of a range of L2 heights on the canonical chain
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BlockCommits {
+    pub canonical_stacks_tip: StacksBlockId,
+    pub canonical_stacks_tip_height: u64,
+    /// how many confirmations this node waits for before it processes a block
+    pub required_confirmations: u64,
+    /// lowest height first.  Heights above the canonical tip, and the boot block, which no
+    /// commit chose, are left out.
+    pub commits: Vec<L2BlockCommit>,
+}
+
+impl StacksChainState {
+    /// Only process a block once the commit which chose it has `confirmations` confirmations.
+    /// A commit has one confirmation as soon as it is seen, so 0 and 1 both process blocks right
+    /// away.
+    pub fn set_required_commit_confirmations(&mut self, confirmations: u64) {
+        self.required_commit_confirmations = confirmations;
+    }
+
+    pub fn get_required_commit_confirmations(&self) -> u64 {
+        self.required_commit_confirmations
+    }
+
+    /// Record the commit which chose the block `link.index_block_hash`.  Recording a block's
+    /// commit again replaces it.
+    pub fn record_block_commit_link(tx: &DBTx, link: &BlockCommitLink) -> Result<(), Error> {
+        let args: &[&dyn ToSql] = &[
+            &link.index_block_hash,
+            &u64_to_sql(link.block_height)?,
+            &link.l1_txid,
+            &link.l1_block_hash,
+            &u64_to_sql(link.l1_block_height)?,
+            &u64_to_sql(link.confirmations)?,
+        ];
+        tx.execute(
+            "INSERT OR REPLACE INTO block_commit_links (index_block_hash, block_height, l1_txid, l1_block_hash, l1_block_height, confirmations) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            args,
+        )?;
+        Ok(())
+    }
+
+    /// Get the commit recorded for the block `index_block_hash`.  Blocks processed before
+    /// commits were recorded have none.
+    pub fn get_block_commit_link(
+        conn: &DBConn,
+        index_block_hash: &StacksBlockId,
+    ) -> Result<Option<BlockCommitLink>, Error> {
+        let sql = "SELECT * FROM block_commit_links WHERE index_block_hash = ?1";
+        let args: &[&dyn ToSql] = &[index_block_hash];
+        Ok(query_row(conn, sql, args)?)
+    }
+
+    /// Get the L1 commits of the `count` L2 blocks on the canonical chain starting at height
+    /// `l2_height`
+    pub fn get_block_commits(
+        &self,
+        sortdb: &SortitionDB,
+        l2_height: u64,
+        count: u64,
+    ) -> Result<BlockCommits, Error> {
+        let (tip_consensus_hash, tip_block_hash) =
+            SortitionDB::get_canonical_stacks_chain_tip_hash(sortdb.conn())?;
+        let canonical_stacks_tip =
+            StacksBlockHeader::make_index_block_hash(&tip_consensus_hash, &tip_block_hash);
+        let canonical_stacks_tip_height =
+            match StacksChainState::get_stacks_block_header_info_by_index_block_hash(
+                self.db(),
+                &canonical_stacks_tip,
+            )? {
+                Some(header) => header.stacks_block_height,
+                None => 0,
+            };
+
+        let burn_tip_height =
+            SortitionDB::get_canonical_burn_chain_tip(sortdb.conn())?.block_height;
+
+        let mut commits = vec![];
+        let index_conn = self.index_conn()?;
+        let first_height = l2_height.max(1);
+        let last_height = l2_height
+            .saturating_add(count.min(MAX_BLOCK_COMMITS))
+            .min(canonical_stacks_tip_height.saturating_add(1));
+        for height in first_height..last_height {
+            let block_id =
+                match index_conn.get_ancestor_block_hash(height, &canonical_stacks_tip)? {
+                    Some(block_id) => block_id,
+                    None => break,
+                };
+            let header = match StacksChainState::get_stacks_block_header_info_by_index_block_hash(
+                self.db(),
+                &block_id,
+            )? {
+                Some(header) => header,
+                None => break,
+            };
+            // prefer the commit recorded when the block was processed, and fall back to the
+            //  sortition DB for blocks processed before commits were recorded
+            let (l1_txid, l1_block_height, l1_block_hash, confirmations_at_processing) =
+                match StacksChainState::get_block_commit_link(self.db(), &block_id)? {
+                    Some(link) => (
+                        link.l1_txid,
+                        link.l1_block_height,
+                        link.l1_block_hash,
+                        Some(link.confirmations),
+                    ),
+                    None => match SortitionDB::get_block_snapshot_consensus(
+                        sortdb.conn(),
+                        &header.consensus_hash,
+                    )? {
+                        Some(snapshot) => (
+                            snapshot.winning_block_txid,
+                            snapshot.block_height,
+                            snapshot.burn_header_hash,
+                            None,
+                        ),
+                        None => {
+                            warn!(
+                                "No sortition for L2 block {} at height {}",
+                                &block_id, height
+                            );
+                            break;
+                        }
+                    },
+                };
+            commits.push(L2BlockCommit {
+                l2_height: height,
+                index_block_hash: block_id,
+                block_hash: header.anchored_header.block_hash(),
+                consensus_hash: header.consensus_hash,
+                l1_txid,
+                l1_block_height,
+                l1_block_hash,
+                l1_confirmations: commit_confirmations(l1_block_height, burn_tip_height),
+                confirmations_at_processing,
+            });
+        }
+
+        Ok(BlockCommits {
+            canonical_stacks_tip,
+            canonical_stacks_tip_height,
+            required_confirmations: self.required_commit_confirmations,
+            commits,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::chainstate::stacks::db::test::instantiate_chainstate;
+
+    #[test]
+    fn test_block_commits_genesis() {
+        let chainstate = instantiate_chainstate(false, 0x80000000, "test_block_commits_genesis");
+        let sortdb = SortitionDB::connect_test(0).unwrap();
+
+        // only the boot block exists, and no commit chose it
+        let commits = chainstate.get_block_commits(&sortdb, 0, 10).unwrap();
+        assert_eq!(commits.canonical_stacks_tip_height, 0);
+        assert!(commits.commits.is_empty());
+    }
+
+    #[test]
+    fn test_block_commit_links() {
+        let mut chainstate = instantiate_chainstate(false, 0x80000000, "test_block_commit_links");
+        let block_id = StacksBlockId([0x11; 32]);
+        assert_eq!(
+            StacksChainState::get_block_commit_link(chainstate.db(), &block_id).unwrap(),
+            None
+        );
+
+        let mut link = BlockCommitLink {
+            index_block_hash: block_id.clone(),
+            block_height: 3,
+            l1_txid: Txid([0x22; 32]),
+            l1_block_hash: BurnchainHeaderHash([0x33; 32]),
+            l1_block_height: 40,
+            confirmations: 1,
+        };
+        let tx = chainstate.db_tx_begin().unwrap();
+        StacksChainState::record_block_commit_link(&tx, &link).unwrap();
+        tx.commit().unwrap();
+        assert_eq!(
+            StacksChainState::get_block_commit_link(chainstate.db(), &block_id).unwrap(),
+            Some(link.clone())
+        );
+
+        // recording it again replaces it
+        link.confirmations = 6;
+        let tx = chainstate.db_tx_begin().unwrap();
+        StacksChainState::record_block_commit_link(&tx, &link).unwrap();
+        tx.commit().unwrap();
+        assert_eq!(
+            StacksChainState::get_block_commit_link(chainstate.db(), &block_id).unwrap(),
+            Some(link)
+        );
+    }
+
+    #[test]
+    fn test_commit_confirmations() {
+        // the commit's own L1 block is its first confirmation
+        assert_eq!(commit_confirmations(40, 40), 1);
+        assert_eq!(commit_confirmations(40, 45), 6);
+        // a tip behind the commit, e.g. after a reorg, has not confirmed it at all
+        assert_eq!(commit_confirmations(40, 38), 0);
+    }
+}
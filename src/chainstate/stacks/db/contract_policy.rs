@@ -0,0 +1,161 @@
+// Copyright (C) 2013-2020 Blockstack PBC, a public benefit corporation
+// Copyright (C) 2020 Stacks Open Internet Foundation
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::HashSet;
+
+use crate::chainstate::stacks::db::StacksChainState;
+use crate::chainstate::stacks::{StacksTransaction, TransactionPayload};
+use crate::types::chainstate::StacksAddress;
+use stacks_common::util::hash::Sha512Trunc256Sum;
+
+/// Restricts who may publish smart contracts through this node.  When `restricted`, a
+/// `TransactionSmartContract` is only admitted to the mempool, and only mined, if its origin is
+/// in `allowed_publishers` or the SHA512/256 hash of its code body is in `allowed_code_hashes`.
+///
+/// This is node policy, not consensus: blocks from other miners which publish contracts are
+/// processed regardless of it.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ContractPublishPolicy {
+    /// if false, anyone may publish contracts
+    pub restricted: bool,
+    pub allowed_publishers: HashSet<StacksAddress>,
+    pub allowed_code_hashes: HashSet<Sha512Trunc256Sum>,
+}
+
+impl ContractPublishPolicy {
+    /// Hash a contract's code body the way the policy's `allowed_code_hashes` are given.
+    /// This is the same hash Clarity records for a published contract.
+    pub fn hash_code_body(code_body: &str) -> Sha512Trunc256Sum {
+        Sha512Trunc256Sum::from_data(code_body.as_bytes())
+    }
+
+    /// May this transaction be admitted and mined?  Only contract-publish transactions are
+    /// subject to the policy.
+    pub fn admits(&self, tx: &StacksTransaction) -> bool {
+        if !self.restricted {
+            return true;
+        }
+        match tx.payload {
+            TransactionPayload::SmartContract(ref smart_contract, _) => {
+                if self.allowed_publishers.contains(&tx.origin_address()) {
+                    return true;
+                }
+                let code_hash =
+                    ContractPublishPolicy::hash_code_body(&smart_contract.code_body.to_string());
+                self.allowed_code_hashes.contains(&code_hash)
+            }
+            _ => true,
+        }
+    }
+}
+
+impl StacksChainState {
+    /// Set the policy for which contract publishes the mempool admits.  See
+    /// `ContractPublishPolicy`.
+    pub fn set_contract_publish_policy(&mut self, policy: ContractPublishPolicy) {
+        self.contract_publish_policy = policy;
+    }
+
+    pub fn get_contract_publish_policy(&self) -> &ContractPublishPolicy {
+        &self.contract_publish_policy
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::chainstate::stacks::*;
+    use crate::util_lib::strings::StacksString;
+    use clarity::vm::ContractName;
+    use stacks_common::address::AddressHashMode;
+    use std::convert::TryFrom;
+
+    fn make_tx(privk: &StacksPrivateKey, payload: TransactionPayload) -> StacksTransaction {
+        let auth = TransactionAuth::from_p2pkh(privk).unwrap();
+        let mut tx = StacksTransaction::new(TransactionVersion::Testnet, auth, payload);
+        tx.chain_id = 0x80000000;
+        tx
+    }
+
+    fn make_publish(privk: &StacksPrivateKey, code_body: &str) -> StacksTransaction {
+        make_tx(
+            privk,
+            TransactionPayload::SmartContract(
+                TransactionSmartContract {
+                    name: ContractName::try_from("hello-world").unwrap(),
+                    code_body: StacksString::from_str(code_body).unwrap(),
+                },
+                None,
+            ),
+        )
+    }
+
+    fn address_of(privk: &StacksPrivateKey) -> StacksAddress {
+        StacksAddress::from_public_keys(
+            C32_ADDRESS_VERSION_TESTNET_SINGLESIG,
+            &AddressHashMode::SerializeP2PKH,
+            1,
+            &vec![StacksPublicKey::from_private(privk)],
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_contract_publish_policy() {
+        let allowed_privk = StacksPrivateKey::new();
+        let other_privk = StacksPrivateKey::new();
+        let allowed_code = "(define-read-only (hello) u1)";
+        let other_code = "(define-read-only (hello) u2)";
+
+        let transfer = make_tx(
+            &other_privk,
+            TransactionPayload::TokenTransfer(
+                address_of(&allowed_privk).into(),
+                100,
+                TokenTransferMemo([0u8; 34]),
+            ),
+        );
+
+        // the default policy admits everything
+        let policy = ContractPublishPolicy::default();
+        assert!(policy.admits(&make_publish(&other_privk, other_code)));
+        assert!(policy.admits(&transfer));
+
+        let policy = ContractPublishPolicy {
+            restricted: true,
+            allowed_publishers: vec![address_of(&allowed_privk)].into_iter().collect(),
+            allowed_code_hashes: vec![ContractPublishPolicy::hash_code_body(allowed_code)]
+                .into_iter()
+                .collect(),
+        };
+
+        // allowed by sender
+        assert!(policy.admits(&make_publish(&allowed_privk, other_code)));
+        // allowed by code hash
+        assert!(policy.admits(&make_publish(&other_privk, allowed_code)));
+        // neither
+        assert!(!policy.admits(&make_publish(&other_privk, other_code)));
+        // other payloads are not restricted
+        assert!(policy.admits(&transfer));
+
+        // a restricted policy with empty lists forbids every publish
+        let policy = ContractPublishPolicy {
+            restricted: true,
+            ..ContractPublishPolicy::default()
+        };
+        assert!(!policy.admits(&make_publish(&allowed_privk, allowed_code)));
+    }
+}
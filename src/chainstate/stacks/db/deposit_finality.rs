@@ -0,0 +1,103 @@
+// Copyright (C) 2013-2020 Blockstack PBC, a public benefit corporation
+// Copyright (C) 2020 Stacks Open Internet Foundation
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Deposits wait for L1 confirmations before they are materialized on the subnet, so that a
+//! shallow L1 reorg cannot take back a deposit the subnet already credited.  With
+//! `confirmations = K`, a deposit observed in L1 block H is materialized by the subnet block
+//! anchored to L1 height H+K.  Until then, it waits in the sortition DB with the rest of the
+//! ops of its L1 block.
+
+use rusqlite::types::ToSql;
+use rusqlite::Connection;
+use rusqlite::NO_PARAMS;
+
+use crate::chainstate::burn::db::sortdb::SortitionDB;
+use crate::chainstate::stacks::db::StacksChainState;
+use crate::chainstate::stacks::Error;
+use crate::util_lib::db::{query_row, u64_to_sql, DBConn, DBTx};
+use stacks_common::types::chainstate::BurnchainHeaderHash;
+
+impl StacksChainState {
+    /// Record how many confirmations deposits need, in a chainstate being booted
+    pub fn insert_deposit_confirmations(tx: &DBTx, confirmations: u64) -> Result<(), Error> {
+        let args: &[&dyn ToSql] = &[&u64_to_sql(confirmations)?];
+        tx.execute(
+            "INSERT INTO deposit_finality (confirmations) VALUES (?1)",
+            args,
+        )?;
+        Ok(())
+    }
+
+    /// Get how many confirmations deposits need, as recorded when the chainstate was booted.
+    /// Chainstates booted before this was recorded materialize deposits right away.
+    pub fn get_deposit_confirmations(conn: &DBConn) -> Result<u64, Error> {
+        let confirmations = query_row(
+            conn,
+            "SELECT confirmations FROM deposit_finality LIMIT 1",
+            NO_PARAMS,
+        )?;
+        Ok(confirmations.unwrap_or(0))
+    }
+
+    /// Get the L1 blocks, from `parent_burn_block` up to `burn_tip`, whose deposits a block
+    /// whose parent was chosen at `parent_burn_block` materializes at `burn_tip`: each end is
+    /// moved back by `confirmations` L1 blocks.
+    pub fn get_deposit_window(
+        sortdb_conn: &Connection,
+        parent_burn_block: &BurnchainHeaderHash,
+        burn_tip: &BurnchainHeaderHash,
+        confirmations: u64,
+    ) -> Result<(BurnchainHeaderHash, BurnchainHeaderHash), Error> {
+        let window_start = SortitionDB::get_ancestor_burn_header_hash(
+            sortdb_conn,
+            parent_burn_block,
+            confirmations,
+        )?;
+        let window_end =
+            SortitionDB::get_ancestor_burn_header_hash(sortdb_conn, burn_tip, confirmations)?;
+        Ok((window_start, window_end))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::chainstate::stacks::db::test::instantiate_chainstate;
+
+    #[test]
+    fn test_store_deposit_confirmations() {
+        let mut chainstate =
+            instantiate_chainstate(false, 0x80000000, "test_store_deposit_confirmations");
+
+        // booted chainstates materialize deposits right away by default
+        assert_eq!(
+            StacksChainState::get_deposit_confirmations(chainstate.db()).unwrap(),
+            0
+        );
+
+        let tx = chainstate.db_tx_begin().unwrap();
+        tx.execute("DELETE FROM deposit_finality", NO_PARAMS)
+            .unwrap();
+        assert_eq!(StacksChainState::get_deposit_confirmations(&tx).unwrap(), 0);
+        StacksChainState::insert_deposit_confirmations(&tx, 6).unwrap();
+        tx.commit().unwrap();
+
+        assert_eq!(
+            StacksChainState::get_deposit_confirmations(chainstate.db()).unwrap(),
+            6
+        );
+    }
+}
@@ -0,0 +1,243 @@
+// Copyright (C) 2013-2020 Blockstack PBC, a public benefit corporation
+// Copyright (C) 2020 Stacks Open Internet Foundation
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Regenerating, from the chainstate, what the event dispatcher was told about the canonical L2
+//! blocks in a range of heights, so that event observers which join late can backfill.  The
+//! chainstate does not keep transaction receipts, so each block is re-executed on top of its
+//! parent and rolled back, one at a time, as `fsck` does.
+//!
+//! Turning these into the JSON payloads observers receive is up to the node, whose event
+//! dispatcher defines them, through an `EventReplayFormatter`.
+
+use crate::burnchains::Txid;
+use crate::chainstate::burn::db::sortdb::SortitionDB;
+use crate::chainstate::coordinator::calculate_paid_rewards;
+use crate::chainstate::stacks::db::blocks::ReexecutedBlock;
+use crate::chainstate::stacks::db::{StacksChainState, StacksHeaderInfo};
+use crate::chainstate::stacks::{Error, StacksBlock, StacksBlockHeader};
+use stacks_common::types::chainstate::{BurnchainHeaderHash, StacksBlockId};
+
+/// The most L2 heights whose events can be replayed at once over RPC.  Each block is
+/// re-executed, so this bounds how long a replay request holds up the RPC thread.
+pub const MAX_EVENT_REPLAY_BLOCKS: u64 = 20;
+
+/// A burn block, as the coordinator announced it before processing its sortition
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReplayedBurnBlock {
+    pub burn_header_hash: BurnchainHeaderHash,
+    pub block_height: u64,
+    pub burns: u64,
+}
+
+/// An L2 block, with everything `append_block` announced about it
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReplayedBlock {
+    pub block: StacksBlock,
+    pub header: StacksHeaderInfo,
+    pub parent_block_id: StacksBlockId,
+    /// the block-commit which won the block's sortition
+    pub winner_txid: Txid,
+    pub parent_burn_block_hash: BurnchainHeaderHash,
+    pub parent_burn_block_height: u32,
+    pub parent_burn_block_timestamp: u64,
+    pub reexecuted: ReexecutedBlock,
+}
+
+/// An announcement to the event dispatcher, in the order it was made
+#[derive(Debug, Clone, PartialEq)]
+pub enum ReplayedEvent {
+    BurnBlock(ReplayedBurnBlock),
+    Block(Box<ReplayedBlock>),
+}
+
+/// One payload the event dispatcher would have sent to an observer
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EventReplayPayload {
+    /// the observer endpoint path the payload would have been posted to, e.g. `new_block`
+    pub path: String,
+    pub payload: serde_json::Value,
+}
+
+/// The payloads for the canonical L2 blocks in a range of heights
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EventReplay {
+    pub canonical_stacks_tip: StacksBlockId,
+    pub from_height: u64,
+    /// the last height replayed, which is lower than the one asked for if the canonical chain
+    /// doesn't reach it yet, but never lower than `from_height`
+    pub to_height: u64,
+    pub payloads: Vec<EventReplayPayload>,
+}
+
+/// Turns replayed events into the payloads of the node's event dispatcher.  Implemented by the
+/// node.
+pub trait EventReplayFormatter {
+    /// Check that `keys` are event keys an observer could subscribe with, as in an event
+    /// observer's `events_keys`
+    fn check_keys(&self, keys: &[String]) -> Result<(), String>;
+
+    /// The payloads the event dispatcher would have sent, for `events`, to an observer
+    /// subscribed with `keys`
+    fn make_payloads(
+        &self,
+        events: &[ReplayedEvent],
+        keys: &[String],
+    ) -> Result<Vec<EventReplayPayload>, String>;
+}
+
+impl StacksChainState {
+    /// Regenerate what the event dispatcher was told about the canonical L2 blocks at heights
+    /// `from_height` up to and including `to_height`: each block, preceded by the burn blocks
+    /// processed since the block before it.  The boot block, which is never announced, is left
+    /// out, as are the boot receipts announced with the first block.  Returns the canonical tip
+    /// and the last height replayed, with the events.
+    ///
+    /// Nothing is written: each block is re-executed and rolled back, so this errors if a
+    /// block's data has been pruned.
+    pub fn replay_events(
+        &mut self,
+        sortdb: &SortitionDB,
+        from_height: u64,
+        to_height: u64,
+    ) -> Result<(StacksBlockId, u64, Vec<ReplayedEvent>), Error> {
+        if from_height > to_height {
+            return Err(Error::InvalidStacksBlock(format!(
+                "event replay heights must not decrease, but {} > {}",
+                from_height, to_height
+            )));
+        }
+        let (tip_consensus_hash, tip_block_hash) =
+            SortitionDB::get_canonical_stacks_chain_tip_hash(sortdb.conn())?;
+        let canonical_stacks_tip =
+            StacksBlockHeader::make_index_block_hash(&tip_consensus_hash, &tip_block_hash);
+        let canonical_stacks_tip_height =
+            match StacksChainState::get_stacks_block_header_info_by_index_block_hash(
+                self.db(),
+                &canonical_stacks_tip,
+            )? {
+                Some(header) => header.stacks_block_height,
+                None => 0,
+            };
+        let to_height = to_height.min(canonical_stacks_tip_height).max(from_height);
+
+        let mut events = vec![];
+        let mut last_burn_height = None;
+        for height in from_height.max(1)..=to_height {
+            let block_id = match self
+                .index_conn()?
+                .get_ancestor_block_hash(height, &canonical_stacks_tip)?
+            {
+                Some(block_id) => block_id,
+                None => break,
+            };
+            let header = StacksChainState::get_stacks_block_header_info_by_index_block_hash(
+                self.db(),
+                &block_id,
+            )?
+            .ok_or(Error::NoSuchBlockError)?;
+            let block_hash = header.anchored_header.block_hash();
+            let block = StacksChainState::load_block(
+                &self.blocks_path,
+                &header.consensus_hash,
+                &block_hash,
+            )?
+            .ok_or(Error::NoSuchBlockError)?;
+            let snapshot =
+                SortitionDB::get_block_snapshot_consensus(sortdb.conn(), &header.consensus_hash)?
+                    .ok_or(Error::NoSuchBlockError)?;
+
+            // the burn blocks since the last block, or just this block's for the first one
+            let first_burn_height = match last_burn_height {
+                Some(last_burn_height) => last_burn_height + 1,
+                None => snapshot.block_height,
+            };
+            for burn_height in first_burn_height..=snapshot.block_height {
+                let burn_snapshot = SortitionDB::get_ancestor_snapshot(
+                    &sortdb.index_conn(),
+                    burn_height,
+                    &snapshot.sortition_id,
+                )?
+                .ok_or(Error::NoSuchBlockError)?;
+                events.push(ReplayedEvent::BurnBlock(ReplayedBurnBlock {
+                    burn_header_hash: burn_snapshot.burn_header_hash,
+                    block_height: burn_snapshot.block_height,
+                    burns: calculate_paid_rewards(&[]).burns,
+                }));
+            }
+            last_burn_height = Some(snapshot.block_height);
+
+            let parent_block_id = StacksChainState::get_parent_block_id(self.db(), &block_id)?
+                .ok_or(Error::NoSuchBlockError)?;
+            let (parent_burn_block_hash, parent_burn_block_height, parent_burn_block_timestamp) =
+                if block.is_first_mined() {
+                    (BurnchainHeaderHash([0; 32]), 0, 0)
+                } else {
+                    let parent_header =
+                        StacksChainState::get_stacks_block_header_info_by_index_block_hash(
+                            self.db(),
+                            &parent_block_id,
+                        )?
+                        .ok_or(Error::NoSuchBlockError)?;
+                    (
+                        parent_header.burn_header_hash,
+                        parent_header.burn_header_height,
+                        parent_header.burn_header_timestamp,
+                    )
+                };
+
+            let reexecuted = self.reexecute_block_for_events(
+                &sortdb.index_conn(),
+                &header.consensus_hash,
+                &block,
+            )?;
+            events.push(ReplayedEvent::Block(Box::new(ReplayedBlock {
+                block,
+                header,
+                parent_block_id,
+                winner_txid: snapshot.winning_block_txid,
+                parent_burn_block_hash,
+                parent_burn_block_height,
+                parent_burn_block_timestamp,
+                reexecuted,
+            })));
+        }
+
+        Ok((canonical_stacks_tip, to_height, events))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::chainstate::stacks::db::test::instantiate_chainstate;
+
+    #[test]
+    fn test_replay_events_genesis() {
+        let mut chainstate =
+            instantiate_chainstate(false, 0x80000000, "test_replay_events_genesis");
+        let sortdb = SortitionDB::connect_test(0).unwrap();
+
+        match chainstate.replay_events(&sortdb, 2, 1) {
+            Err(Error::InvalidStacksBlock(_)) => {}
+            x => panic!("expected an invalid range, got {:?}", x),
+        }
+
+        // only the boot block exists, and it is never announced
+        let (_, to_height, events) = chainstate.replay_events(&sortdb, 0, 10).unwrap();
+        assert_eq!(to_height, 0);
+        assert!(events.is_empty());
+    }
+}
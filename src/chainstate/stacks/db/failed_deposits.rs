@@ -0,0 +1,288 @@
+// Copyright (C) 2013-2020 Blockstack PBC, a public benefit corporation
+// Copyright (C) 2020 Stacks Open Internet Foundation
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::HashMap;
+use std::convert::TryFrom;
+
+use rusqlite::types::ToSql;
+use rusqlite::Row;
+
+use crate::burnchains::Txid;
+use crate::chainstate::burn::operations::BlockstackOperationType;
+use crate::chainstate::stacks::db::StacksChainState;
+use crate::chainstate::stacks::events::{StacksTransactionReceipt, TransactionOrigin};
+use crate::chainstate::stacks::Error;
+use crate::clarity_vm::withdrawal::get_withdrawal_event_data;
+use crate::util_lib::db::Error as db_error;
+use crate::util_lib::db::{query_rows, u64_to_sql, DBTx, FromColumn, FromRow};
+use clarity::vm::types::{PrincipalData, QualifiedContractIdentifier};
+use clarity::vm::Value;
+use stacks_common::types::chainstate::{BurnchainHeaderHash, StacksBlockId};
+
+/// A deposit of an L1 asset that could not be materialized on the subnet, either because the
+/// subnet contract rejected it or because the contract call could not be run at all (e.g. the
+/// contract doesn't exist, or doesn't implement the deposit function).  Such deposits are
+/// refunded with a withdrawal in the processing block's withdrawal root, which the depositor
+/// claims on the L1 like any other withdrawal.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FailedDeposit {
+    /// the block that processed the deposit
+    pub index_block_hash: StacksBlockId,
+    pub block_height: u64,
+    /// the L1 transaction that made the deposit
+    pub txid: Txid,
+    pub burn_header_hash: BurnchainHeaderHash,
+    pub sender: PrincipalData,
+    /// "ft", "nft", or "sft"
+    pub asset_type: String,
+    pub l1_contract: QualifiedContractIdentifier,
+    pub subnet_contract: QualifiedContractIdentifier,
+    /// the token ID, for NFTs and SFTs
+    pub asset_id: Option<u128>,
+    /// the amount deposited, for FTs and SFTs
+    pub amount: Option<u128>,
+    /// the deposit's result, e.g. `(err u1)`.  Deposits whose contract call could not be run
+    /// have the result `(err none)`.
+    pub result: String,
+    /// the ID of the refund in the block's withdrawal root, or `None` if the withdrawal policy
+    /// held the refund back for a later block
+    pub refund_withdrawal_id: Option<u32>,
+}
+
+impl FailedDeposit {
+    /// Read a failed deposit out of a deposit op's receipt, if the deposit failed.  The refund's
+    /// withdrawal ID is only known once `create_withdrawal_merkle_tree_with_policy` has run.
+    pub fn from_receipt(
+        receipt: &mut StacksTransactionReceipt,
+        index_block_hash: &StacksBlockId,
+        block_height: u64,
+    ) -> Option<FailedDeposit> {
+        match &receipt.result {
+            Value::Response(response) if !response.committed => {}
+            _ => return None,
+        }
+
+        let op = match &receipt.transaction {
+            TransactionOrigin::Burn(op) => op,
+            TransactionOrigin::Stacks(_) => return None,
+        };
+        let (
+            txid,
+            burn_header_hash,
+            sender,
+            asset_type,
+            l1_contract,
+            subnet_contract,
+            asset_id,
+            amount,
+        ) = match op {
+            BlockstackOperationType::DepositFt(op) => (
+                &op.txid,
+                &op.burn_header_hash,
+                &op.sender,
+                "ft",
+                &op.l1_contract_id,
+                &op.subnet_contract_id,
+                None,
+                Some(op.amount),
+            ),
+            BlockstackOperationType::DepositNft(op) => (
+                &op.txid,
+                &op.burn_header_hash,
+                &op.sender,
+                "nft",
+                &op.l1_contract_id,
+                &op.subnet_contract_id,
+                Some(op.id),
+                None,
+            ),
+            BlockstackOperationType::DepositSft(op) => (
+                &op.txid,
+                &op.burn_header_hash,
+                &op.sender,
+                "sft",
+                &op.l1_contract_id,
+                &op.subnet_contract_id,
+                Some(op.id),
+                Some(op.amount),
+            ),
+            _ => return None,
+        };
+
+        let refund_withdrawal_id = receipt.events.iter_mut().find_map(|event| {
+            match get_withdrawal_event_data(event)?
+                .data_map
+                .get("withdrawal-id")
+            {
+                Some(Value::UInt(id)) => u32::try_from(*id).ok(),
+                _ => None,
+            }
+        });
+
+        Some(FailedDeposit {
+            index_block_hash: index_block_hash.clone(),
+            block_height,
+            txid: txid.clone(),
+            burn_header_hash: burn_header_hash.clone(),
+            sender: sender.clone(),
+            asset_type: asset_type.to_string(),
+            l1_contract: l1_contract.clone(),
+            subnet_contract: subnet_contract.clone(),
+            asset_id,
+            amount,
+            result: receipt.result.to_string(),
+            refund_withdrawal_id,
+        })
+    }
+}
+
+impl FromRow<FailedDeposit> for FailedDeposit {
+    fn from_row<'a>(row: &'a Row) -> Result<FailedDeposit, db_error> {
+        let index_block_hash = StacksBlockId::from_column(row, "index_block_hash")?;
+        let block_height = u64::from_column(row, "block_height")?;
+        let txid = Txid::from_column(row, "txid")?;
+        let burn_header_hash = BurnchainHeaderHash::from_column(row, "burn_header_hash")?;
+        let sender_str: String = row.get_unwrap("sender");
+        let sender = PrincipalData::parse(&sender_str).map_err(|_| db_error::ParseError)?;
+        let asset_type: String = row.get_unwrap("asset_type");
+        let l1_contract_str: String = row.get_unwrap("l1_contract");
+        let l1_contract = QualifiedContractIdentifier::parse(&l1_contract_str)
+            .map_err(|_| db_error::ParseError)?;
+        let subnet_contract_str: String = row.get_unwrap("subnet_contract");
+        let subnet_contract = QualifiedContractIdentifier::parse(&subnet_contract_str)
+            .map_err(|_| db_error::ParseError)?;
+        let asset_id = match row.get_unwrap::<_, Option<String>>("asset_id") {
+            Some(id_str) => Some(id_str.parse::<u128>().map_err(|_| db_error::ParseError)?),
+            None => None,
+        };
+        let amount = match row.get_unwrap::<_, Option<String>>("amount") {
+            Some(amount_str) => Some(
+                amount_str
+                    .parse::<u128>()
+                    .map_err(|_| db_error::ParseError)?,
+            ),
+            None => None,
+        };
+        let result: String = row.get_unwrap("result");
+        let refund_withdrawal_id = match row.get_unwrap::<_, Option<i64>>("refund_withdrawal_id") {
+            Some(id) => Some(u32::try_from(id).map_err(|_| db_error::ParseError)?),
+            None => None,
+        };
+        Ok(FailedDeposit {
+            index_block_hash,
+            block_height,
+            txid,
+            burn_header_hash,
+            sender,
+            asset_type,
+            l1_contract,
+            subnet_contract,
+            asset_id,
+            amount,
+            result,
+            refund_withdrawal_id,
+        })
+    }
+}
+
+impl StacksChainState {
+    /// Record the failed deposits among the receipts of the block `index_block_hash`.  Must be
+    /// called after `create_withdrawal_merkle_tree_with_policy` has given their refunds IDs.
+    pub fn index_failed_deposits<'a>(
+        tx: &DBTx,
+        index_block_hash: &StacksBlockId,
+        block_height: u64,
+        tx_receipts: impl Iterator<Item = &'a mut StacksTransactionReceipt>,
+    ) -> Result<(), Error> {
+        for receipt in tx_receipts {
+            let failed_deposit =
+                match FailedDeposit::from_receipt(receipt, index_block_hash, block_height) {
+                    Some(failed_deposit) => failed_deposit,
+                    None => continue,
+                };
+            info!("Deposit failed and was refunded";
+                  "txid" => %failed_deposit.txid,
+                  "sender" => %failed_deposit.sender,
+                  "subnet_contract" => %failed_deposit.subnet_contract,
+                  "result" => &failed_deposit.result,
+                  "refund_withdrawal_id" => ?failed_deposit.refund_withdrawal_id);
+
+            let args: &[&dyn ToSql] = &[
+                &failed_deposit.index_block_hash,
+                &u64_to_sql(failed_deposit.block_height)?,
+                &failed_deposit.txid,
+                &failed_deposit.burn_header_hash,
+                &failed_deposit.sender.to_string(),
+                &failed_deposit.asset_type,
+                &failed_deposit.l1_contract.to_string(),
+                &failed_deposit.subnet_contract.to_string(),
+                &failed_deposit.asset_id.map(|id| id.to_string()),
+                &failed_deposit.amount.map(|amount| amount.to_string()),
+                &failed_deposit.result,
+                &failed_deposit.refund_withdrawal_id,
+            ];
+            tx.execute(
+                "INSERT INTO failed_deposits (index_block_hash, block_height, txid, burn_header_hash, sender, asset_type, l1_contract, subnet_contract, asset_id, amount, result, refund_withdrawal_id) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
+                args,
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Get the failed deposits made by `sender` in the fork ending at `tip`, newest block first.
+    /// Skips the first `offset` of them, and returns at most `limit`.
+    pub fn get_failed_deposits_by_sender(
+        &self,
+        tip: &StacksBlockId,
+        sender: &PrincipalData,
+        offset: u64,
+        limit: u64,
+    ) -> Result<Vec<FailedDeposit>, Error> {
+        let sql =
+            "SELECT * FROM failed_deposits WHERE sender = ?1 ORDER BY block_height DESC, rowid ASC";
+        let args: &[&dyn ToSql] = &[&sender.to_string()];
+        let rows: Vec<FailedDeposit> = query_rows(self.db(), sql, args)?;
+
+        // every fork's blocks are indexed, so only keep the ones which are ancestors of `tip`
+        let index_conn = self.index_conn()?;
+        let mut ancestors: HashMap<u64, Option<StacksBlockId>> = HashMap::new();
+        let mut failed_deposits = vec![];
+        let mut skipped = 0;
+        for failed_deposit in rows.into_iter() {
+            if failed_deposits.len() as u64 >= limit {
+                break;
+            }
+            let ancestor = match ancestors.get(&failed_deposit.block_height) {
+                Some(ancestor) => ancestor.clone(),
+                None => {
+                    let ancestor =
+                        index_conn.get_ancestor_block_hash(failed_deposit.block_height, tip)?;
+                    ancestors.insert(failed_deposit.block_height, ancestor.clone());
+                    ancestor
+                }
+            };
+            if ancestor.as_ref() != Some(&failed_deposit.index_block_hash) {
+                continue;
+            }
+            if skipped < offset {
+                skipped += 1;
+                continue;
+            }
+            failed_deposits.push(failed_deposit);
+        }
+        Ok(failed_deposits)
+    }
+}
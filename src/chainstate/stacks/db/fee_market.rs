@@ -0,0 +1,210 @@
+// Copyright (C) 2013-2020 Blockstack PBC, a public benefit corporation
+// Copyright (C) 2020 Stacks Open Internet Foundation
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::cmp;
+
+use rusqlite::types::ToSql;
+use rusqlite::Row;
+
+use crate::chainstate::stacks::db::blocks::MINIMUM_TX_FEE_RATE_PER_BYTE;
+use crate::chainstate::stacks::db::StacksChainState;
+use crate::chainstate::stacks::Error;
+use crate::chainstate::stacks::MAX_BLOCK_LEN;
+use crate::util_lib::db::Error as db_error;
+use crate::util_lib::db::{query_row, u64_to_sql, DBConn, DBTx, FromColumn, FromRow};
+use clarity::vm::costs::ExecutionCost;
+use stacks_common::types::chainstate::StacksBlockId;
+
+/// The percentage of a block's budget the fee market aims for.  Fuller blocks raise the minimum
+/// fee rate, emptier ones lower it.
+pub const FEE_MARKET_TARGET_UTILIZATION: u64 = 50;
+
+/// The minimum fee rate moves by at most 1/8th from one block to the next, as in EIP-1559.
+pub const FEE_MARKET_MAX_CHANGE_DENOMINATOR: u64 = 8;
+
+/// The fee market's state after a block: how full the block was, and the minimum fee rate the
+/// mempool admits for transactions building on it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FeeRateFloor {
+    pub index_block_hash: StacksBlockId,
+    pub block_height: u64,
+    /// percentage of the block's budget it used, in its fullest dimension
+    pub utilization: u64,
+    /// microSTX per byte
+    pub min_fee_rate: u64,
+}
+
+impl FeeRateFloor {
+    /// How full a block was, as a percentage: the larger of its fullest execution cost dimension
+    /// and its size, each relative to the block limit.
+    pub fn block_utilization(
+        block_cost: &ExecutionCost,
+        block_limit: &ExecutionCost,
+        block_size: u64,
+    ) -> u64 {
+        let cost_utilization = block_limit.proportion_largest_dimension(block_cost);
+        let size_utilization = block_size.saturating_mul(100) / u64::from(MAX_BLOCK_LEN);
+        cmp::min(cmp::max(cost_utilization, size_utilization), 100)
+    }
+
+    /// The minimum fee rate after a block with the given `utilization`, whose parent's minimum
+    /// fee rate was `parent_min_fee_rate`.  The rate moves in proportion to how far the block was
+    /// from the target utilization, by at least 1 microSTX per byte unless the block hit the
+    /// target exactly, and never drops below `MINIMUM_TX_FEE_RATE_PER_BYTE`.
+    pub fn next_min_fee_rate(parent_min_fee_rate: u64, utilization: u64) -> u64 {
+        let parent_min_fee_rate = cmp::max(parent_min_fee_rate, MINIMUM_TX_FEE_RATE_PER_BYTE);
+        let target = FEE_MARKET_TARGET_UTILIZATION;
+        if utilization > target {
+            let delta = parent_min_fee_rate.saturating_mul(utilization - target)
+                / target
+                / FEE_MARKET_MAX_CHANGE_DENOMINATOR;
+            parent_min_fee_rate.saturating_add(cmp::max(delta, 1))
+        } else if utilization < target {
+            let delta = parent_min_fee_rate.saturating_mul(target - utilization)
+                / target
+                / FEE_MARKET_MAX_CHANGE_DENOMINATOR;
+            cmp::max(
+                parent_min_fee_rate.saturating_sub(cmp::max(delta, 1)),
+                MINIMUM_TX_FEE_RATE_PER_BYTE,
+            )
+        } else {
+            parent_min_fee_rate
+        }
+    }
+}
+
+impl FromRow<FeeRateFloor> for FeeRateFloor {
+    fn from_row<'a>(row: &'a Row) -> Result<FeeRateFloor, db_error> {
+        let index_block_hash = StacksBlockId::from_column(row, "index_block_hash")?;
+        let block_height = u64::from_column(row, "block_height")?;
+        let utilization = u64::from_column(row, "utilization")?;
+        let min_fee_rate = u64::from_column(row, "min_fee_rate")?;
+        Ok(FeeRateFloor {
+            index_block_hash,
+            block_height,
+            utilization,
+            min_fee_rate,
+        })
+    }
+}
+
+impl StacksChainState {
+    /// Record the fee market's state after the block `index_block_hash`, whose parent is
+    /// `parent_index_block_hash`.  Blocks whose parent has no recorded state (such as the first
+    /// ones after upgrading a node) start from `MINIMUM_TX_FEE_RATE_PER_BYTE`.
+    pub fn store_fee_rate_floor(
+        tx: &DBTx,
+        parent_index_block_hash: &StacksBlockId,
+        index_block_hash: &StacksBlockId,
+        block_height: u64,
+        utilization: u64,
+    ) -> Result<FeeRateFloor, Error> {
+        let parent_min_fee_rate = StacksChainState::get_min_fee_rate(tx, parent_index_block_hash)?;
+        let floor = FeeRateFloor {
+            index_block_hash: index_block_hash.clone(),
+            block_height,
+            utilization,
+            min_fee_rate: FeeRateFloor::next_min_fee_rate(parent_min_fee_rate, utilization),
+        };
+        let args: &[&dyn ToSql] = &[
+            &floor.index_block_hash,
+            &u64_to_sql(floor.block_height)?,
+            &u64_to_sql(floor.utilization)?,
+            &u64_to_sql(floor.min_fee_rate)?,
+        ];
+        tx.execute(
+            "INSERT OR REPLACE INTO fee_rate_floors (index_block_hash, block_height, utilization, min_fee_rate) VALUES (?1, ?2, ?3, ?4)",
+            args,
+        )?;
+        Ok(floor)
+    }
+
+    /// Get the fee market's state after the block `index_block_hash`, if it was recorded.
+    pub fn get_fee_rate_floor(
+        conn: &DBConn,
+        index_block_hash: &StacksBlockId,
+    ) -> Result<Option<FeeRateFloor>, db_error> {
+        let sql = "SELECT * FROM fee_rate_floors WHERE index_block_hash = ?1";
+        let args: &[&dyn ToSql] = &[index_block_hash];
+        query_row(conn, sql, args)
+    }
+
+    /// Get the minimum fee rate, in microSTX per byte, for transactions building on `tip`.
+    pub fn get_min_fee_rate(conn: &DBConn, tip: &StacksBlockId) -> Result<u64, db_error> {
+        Ok(StacksChainState::get_fee_rate_floor(conn, tip)?
+            .map(|floor| floor.min_fee_rate)
+            .unwrap_or(MINIMUM_TX_FEE_RATE_PER_BYTE))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_next_min_fee_rate() {
+        let min = MINIMUM_TX_FEE_RATE_PER_BYTE;
+
+        // never drops below the minimum
+        assert_eq!(FeeRateFloor::next_min_fee_rate(min, 0), min);
+        assert_eq!(FeeRateFloor::next_min_fee_rate(0, 0), min);
+
+        // unchanged at the target
+        assert_eq!(
+            FeeRateFloor::next_min_fee_rate(800, FEE_MARKET_TARGET_UTILIZATION),
+            800
+        );
+
+        // full blocks raise it by 1/8th, empty ones lower it by 1/8th
+        assert_eq!(FeeRateFloor::next_min_fee_rate(800, 100), 900);
+        assert_eq!(FeeRateFloor::next_min_fee_rate(800, 0), 700);
+        assert_eq!(FeeRateFloor::next_min_fee_rate(800, 75), 850);
+        assert_eq!(FeeRateFloor::next_min_fee_rate(800, 25), 750);
+
+        // small rates still move
+        assert_eq!(FeeRateFloor::next_min_fee_rate(min, 51), min + 1);
+        assert_eq!(FeeRateFloor::next_min_fee_rate(min + 1, 49), min);
+
+        assert_eq!(FeeRateFloor::next_min_fee_rate(u64::MAX, 100), u64::MAX);
+    }
+
+    #[test]
+    fn test_block_utilization() {
+        let limit = ExecutionCost {
+            write_length: 1000,
+            write_count: 1000,
+            read_length: 1000,
+            read_count: 1000,
+            runtime: 1000,
+        };
+        let mut cost = ExecutionCost::zero();
+        assert_eq!(FeeRateFloor::block_utilization(&cost, &limit, 0), 0);
+
+        cost.read_count = 300;
+        cost.runtime = 600;
+        assert_eq!(FeeRateFloor::block_utilization(&cost, &limit, 0), 60);
+
+        // size counts too
+        assert_eq!(
+            FeeRateFloor::block_utilization(&cost, &limit, u64::from(MAX_BLOCK_LEN) * 3 / 4),
+            75
+        );
+
+        // capped at 100
+        cost.runtime = 5000;
+        assert_eq!(FeeRateFloor::block_utilization(&cost, &limit, 0), 100);
+    }
+}
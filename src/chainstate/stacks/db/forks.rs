@@ -0,0 +1,279 @@
+// Copyright (C) 2013-2020 Blockstack PBC, a public benefit corporation
+// Copyright (C) 2020 Stacks Open Internet Foundation
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! The tree of L2 blocks committed over recent L1 blocks, for debugging forks.  Every anchored
+//! block the node receives stays in `staging_blocks`, whether or not it ends up on the canonical
+//! chain, so the tree includes the blocks on non-canonical L2 forks and L1 forks alike.
+
+use std::collections::{HashMap, HashSet};
+
+use crate::chainstate::burn::db::sortdb::SortitionDB;
+use crate::chainstate::burn::BlockSnapshot;
+use crate::chainstate::stacks::db::blocks::StagingBlock;
+use crate::chainstate::stacks::db::StacksChainState;
+use crate::chainstate::stacks::{Error, StacksBlockHeader};
+use crate::util_lib::db::{query_rows, u64_to_sql, DBConn};
+use stacks_common::types::chainstate::{
+    BlockHeaderHash, BurnchainHeaderHash, ConsensusHash, StacksBlockId,
+};
+
+/// Why an L2 block is, or is not, on the canonical chain
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ForkStatus {
+    /// on the canonical chain
+    Canonical,
+    /// committed in an L1 block which is not on the canonical L1 fork
+    L1Fork,
+    /// can never be attached: the block is invalid, or builds on a block which is
+    Orphaned,
+    /// received, but not processed yet
+    Unprocessed,
+    /// valid and committed on the canonical L1 fork, but a block committed after it built a
+    /// longer chain on a different parent.  Of two chains of the same height, the one whose
+    /// tip was committed first is canonical.
+    Superseded,
+}
+
+impl ForkStatus {
+    pub fn new(
+        on_canonical_chain: bool,
+        on_canonical_l1_fork: bool,
+        processed: bool,
+        orphaned: bool,
+    ) -> ForkStatus {
+        if on_canonical_chain {
+            ForkStatus::Canonical
+        } else if !on_canonical_l1_fork {
+            ForkStatus::L1Fork
+        } else if orphaned {
+            ForkStatus::Orphaned
+        } else if !processed {
+            ForkStatus::Unprocessed
+        } else {
+            ForkStatus::Superseded
+        }
+    }
+}
+
+/// An L2 block received for an L1 block's commit
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ForkTreeBlock {
+    pub index_block_hash: StacksBlockId,
+    pub block_hash: BlockHeaderHash,
+    pub parent_index_block_hash: StacksBlockId,
+    pub height: u64,
+    pub status: ForkStatus,
+    /// the canonical block at the same height, if this block is not canonical and the
+    /// canonical chain reaches its height within the tree
+    pub superseded_by: Option<StacksBlockId>,
+}
+
+/// One L1 block, on any L1 fork, and the L2 blocks committed in it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ForkTreeBurnBlock {
+    pub burn_block_height: u64,
+    pub burn_header_hash: BurnchainHeaderHash,
+    pub parent_burn_header_hash: BurnchainHeaderHash,
+    pub consensus_hash: ConsensusHash,
+    /// true if this L1 block is on the canonical L1 fork
+    pub canonical: bool,
+    /// the hash of the L2 block committed in this L1 block, if any
+    pub committed_block_hash: Option<BlockHeaderHash>,
+    /// the committed L2 blocks this node has received
+    pub blocks: Vec<ForkTreeBlock>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ForkTree {
+    pub canonical_burn_tip: BurnchainHeaderHash,
+    pub canonical_stacks_tip: StacksBlockId,
+    /// lowest first
+    pub burn_blocks: Vec<ForkTreeBurnBlock>,
+}
+
+impl StacksChainState {
+    /// Get the tree of L2 blocks committed in the last `num_burn_blocks` L1 blocks, on every L1
+    /// fork this node has seen, with the reason each block is or isn't canonical.
+    /// `chainstate_conn` is the chainstate's index DB, and `sortdb_conn` the sortition DB.
+    pub fn get_fork_tree(
+        chainstate_conn: &DBConn,
+        sortdb_conn: &DBConn,
+        num_burn_blocks: u64,
+    ) -> Result<ForkTree, Error> {
+        let burn_tip = SortitionDB::get_canonical_burn_chain_tip(sortdb_conn)?;
+        let min_height = burn_tip
+            .block_height
+            .saturating_sub(num_burn_blocks.saturating_sub(1));
+
+        let snapshots: Vec<BlockSnapshot> = query_rows(
+            sortdb_conn,
+            "SELECT * FROM snapshots WHERE block_height >= ?1 ORDER BY block_height ASC, burn_header_hash ASC",
+            &[&u64_to_sql(min_height)?],
+        )?;
+
+        // the canonical L1 fork, walking back from the canonical burn tip
+        let snapshots_by_hash: HashMap<_, _> = snapshots
+            .iter()
+            .map(|sn| (sn.burn_header_hash.clone(), sn))
+            .collect();
+        let mut canonical_l1_fork = HashSet::new();
+        let mut cursor = Some(&burn_tip);
+        while let Some(sn) = cursor {
+            canonical_l1_fork.insert(sn.burn_header_hash.clone());
+            cursor = snapshots_by_hash.get(&sn.parent_burn_header_hash).copied();
+        }
+
+        // the canonical L2 chain, walking back from the canonical Stacks tip to the first block
+        // committed before the tree starts
+        let canonical_stacks_tip = StacksBlockHeader::make_index_block_hash(
+            &burn_tip.canonical_stacks_tip_consensus_hash,
+            &burn_tip.canonical_stacks_tip_hash,
+        );
+        let mut canonical_chain = HashSet::new();
+        let mut canonical_by_height = HashMap::new();
+        let mut block_id = canonical_stacks_tip.clone();
+        loop {
+            let header = match StacksChainState::get_stacks_block_header_info_by_index_block_hash(
+                chainstate_conn,
+                &block_id,
+            )? {
+                Some(header) => header,
+                None => break,
+            };
+            if header.stacks_block_height == 0 || header.burn_header_height < min_height as u32 {
+                break;
+            }
+            canonical_chain.insert(block_id.clone());
+            canonical_by_height.insert(header.stacks_block_height, block_id.clone());
+            block_id = match StacksChainState::get_parent_block_id(chainstate_conn, &block_id)? {
+                Some(parent_id) => parent_id,
+                None => break,
+            };
+        }
+
+        let mut burn_blocks = vec![];
+        for sn in snapshots.iter() {
+            let on_canonical_l1_fork = canonical_l1_fork.contains(&sn.burn_header_hash);
+            let staging_blocks: Vec<StagingBlock> = query_rows(
+                chainstate_conn,
+                "SELECT * FROM staging_blocks WHERE consensus_hash = ?1 ORDER BY height ASC",
+                &[&sn.consensus_hash],
+            )?;
+            let blocks = staging_blocks
+                .into_iter()
+                .map(|block| {
+                    let index_block_hash = StacksBlockHeader::make_index_block_hash(
+                        &block.consensus_hash,
+                        &block.anchored_block_hash,
+                    );
+                    let status = ForkStatus::new(
+                        canonical_chain.contains(&index_block_hash),
+                        on_canonical_l1_fork,
+                        block.processed,
+                        block.orphaned,
+                    );
+                    let superseded_by = match status {
+                        ForkStatus::Canonical => None,
+                        _ => canonical_by_height.get(&block.height).cloned(),
+                    };
+                    ForkTreeBlock {
+                        parent_index_block_hash: StacksBlockHeader::make_index_block_hash(
+                            &block.parent_consensus_hash,
+                            &block.parent_anchored_block_hash,
+                        ),
+                        index_block_hash,
+                        block_hash: block.anchored_block_hash,
+                        height: block.height,
+                        status,
+                        superseded_by,
+                    }
+                })
+                .collect();
+
+            burn_blocks.push(ForkTreeBurnBlock {
+                burn_block_height: sn.block_height,
+                burn_header_hash: sn.burn_header_hash.clone(),
+                parent_burn_header_hash: sn.parent_burn_header_hash.clone(),
+                consensus_hash: sn.consensus_hash.clone(),
+                canonical: on_canonical_l1_fork,
+                committed_block_hash: if sn.sortition {
+                    Some(sn.winning_stacks_block_hash.clone())
+                } else {
+                    None
+                },
+                blocks,
+            });
+        }
+
+        Ok(ForkTree {
+            canonical_burn_tip: burn_tip.burn_header_hash.clone(),
+            canonical_stacks_tip,
+            burn_blocks,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::chainstate::stacks::db::test::instantiate_chainstate;
+
+    #[test]
+    fn test_fork_status() {
+        // being on the canonical chain trumps everything else
+        assert_eq!(
+            ForkStatus::new(true, true, true, false),
+            ForkStatus::Canonical
+        );
+        // an L1 fork explains any other status
+        assert_eq!(
+            ForkStatus::new(false, false, true, false),
+            ForkStatus::L1Fork
+        );
+        assert_eq!(
+            ForkStatus::new(false, false, true, true),
+            ForkStatus::L1Fork
+        );
+        assert_eq!(
+            ForkStatus::new(false, true, true, true),
+            ForkStatus::Orphaned
+        );
+        assert_eq!(
+            ForkStatus::new(false, true, false, false),
+            ForkStatus::Unprocessed
+        );
+        assert_eq!(
+            ForkStatus::new(false, true, true, false),
+            ForkStatus::Superseded
+        );
+    }
+
+    #[test]
+    fn test_fork_tree_genesis() {
+        let chainstate = instantiate_chainstate(false, 0x80000000, "test_fork_tree_genesis");
+        let sortdb = SortitionDB::connect_test(0).unwrap();
+        let burn_tip = SortitionDB::get_canonical_burn_chain_tip(sortdb.conn()).unwrap();
+
+        // only the first L1 block is known, and it commits no L2 block
+        let tree = StacksChainState::get_fork_tree(chainstate.db(), sortdb.conn(), 10).unwrap();
+        assert_eq!(tree.canonical_burn_tip, burn_tip.burn_header_hash);
+        assert_eq!(tree.burn_blocks.len(), 1);
+        assert!(tree.burn_blocks[0].canonical);
+        assert_eq!(tree.burn_blocks[0].committed_block_hash, None);
+        assert!(tree.burn_blocks[0].blocks.is_empty());
+    }
+}
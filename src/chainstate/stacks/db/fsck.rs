@@ -0,0 +1,452 @@
+// Copyright (C) 2013-2020 Blockstack PBC, a public benefit corporation
+// Copyright (C) 2020 Stacks Open Internet Foundation
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Consistency checks between the sortition DB, the staging blocks, the block headers and the
+//! MARF, for diagnosing a node whose chainstate was damaged by a crash or a disk problem.
+
+use std::collections::{HashMap, HashSet};
+
+use rusqlite::{Row, NO_PARAMS};
+
+use crate::chainstate::burn::db::sortdb::SortitionDB;
+use crate::chainstate::burn::BlockSnapshot;
+use crate::chainstate::stacks::db::blocks::StagingBlock;
+use crate::chainstate::stacks::db::StacksChainState;
+use crate::chainstate::stacks::{Error, StacksBlockHeader};
+use crate::util_lib::db::Error as db_error;
+use crate::util_lib::db::{query_rows, FromColumn, FromRow};
+use stacks_common::types::chainstate::{BlockHeaderHash, ConsensusHash, StacksBlockId};
+
+/// An inconsistency found by `StacksChainState::fsck`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case", tag = "kind")]
+pub enum FsckProblem {
+    /// a staged block was committed in an L1 block the sortition DB does not know about
+    UnknownSortition {
+        index_block_hash: StacksBlockId,
+        height: u64,
+    },
+    /// a staged block is not the block its L1 block's sortition elected
+    NotSortitionWinner {
+        index_block_hash: StacksBlockId,
+        height: u64,
+    },
+    /// an unprocessed staged block builds on an orphaned block, but is not orphaned itself
+    OrphanedParent {
+        index_block_hash: StacksBlockId,
+        height: u64,
+    },
+    /// a staged block is marked processed, but has no header
+    MissingHeader {
+        index_block_hash: StacksBlockId,
+        height: u64,
+    },
+    /// the sortition DB records a block as accepted, but the chainstate has no header for it
+    MissingAcceptedBlock {
+        index_block_hash: StacksBlockId,
+        height: u64,
+    },
+    /// a header does not link up with its parent header
+    BrokenHeaderChain {
+        index_block_hash: StacksBlockId,
+        height: u64,
+        reason: String,
+    },
+    /// re-executing a canonical block did not reproduce the roots committed to by its header
+    ReexecutionMismatch {
+        index_block_hash: StacksBlockId,
+        height: u64,
+        reason: String,
+    },
+}
+
+impl FsckProblem {
+    pub fn index_block_hash(&self) -> &StacksBlockId {
+        match self {
+            FsckProblem::UnknownSortition {
+                index_block_hash, ..
+            }
+            | FsckProblem::NotSortitionWinner {
+                index_block_hash, ..
+            }
+            | FsckProblem::OrphanedParent {
+                index_block_hash, ..
+            }
+            | FsckProblem::MissingHeader {
+                index_block_hash, ..
+            }
+            | FsckProblem::MissingAcceptedBlock {
+                index_block_hash, ..
+            }
+            | FsckProblem::BrokenHeaderChain {
+                index_block_hash, ..
+            }
+            | FsckProblem::ReexecutionMismatch {
+                index_block_hash, ..
+            } => index_block_hash,
+        }
+    }
+
+    pub fn height(&self) -> u64 {
+        match self {
+            FsckProblem::UnknownSortition { height, .. }
+            | FsckProblem::NotSortitionWinner { height, .. }
+            | FsckProblem::OrphanedParent { height, .. }
+            | FsckProblem::MissingHeader { height, .. }
+            | FsckProblem::MissingAcceptedBlock { height, .. }
+            | FsckProblem::BrokenHeaderChain { height, .. }
+            | FsckProblem::ReexecutionMismatch { height, .. } => *height,
+        }
+    }
+}
+
+/// What to do about the problems `StacksChainState::fsck` found
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct RepairPlan {
+    /// staged blocks which can never be processed, and should be orphaned so that their
+    /// descendants are dropped too
+    pub orphan_cleanup: Vec<StacksBlockId>,
+    /// the lowest Stacks block height whose processed state can't be trusted.  The chainstate
+    /// should be rebuilt from the blocks below this height, e.g. with `replay-blocks
+    /// --until-height`, and the blocks from this height up processed again.
+    pub reprocess_from_height: Option<u64>,
+}
+
+impl RepairPlan {
+    pub fn from_problems(problems: &[FsckProblem]) -> RepairPlan {
+        let mut plan = RepairPlan::default();
+        for problem in problems.iter() {
+            match problem {
+                FsckProblem::UnknownSortition { .. }
+                | FsckProblem::NotSortitionWinner { .. }
+                | FsckProblem::OrphanedParent { .. } => {
+                    plan.orphan_cleanup.push(problem.index_block_hash().clone());
+                }
+                FsckProblem::MissingHeader { .. }
+                | FsckProblem::MissingAcceptedBlock { .. }
+                | FsckProblem::BrokenHeaderChain { .. }
+                | FsckProblem::ReexecutionMismatch { .. } => {
+                    plan.reprocess_from_height = Some(
+                        plan.reprocess_from_height
+                            .map_or(problem.height(), |h| h.min(problem.height())),
+                    );
+                }
+            }
+        }
+        plan
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.orphan_cleanup.is_empty() && self.reprocess_from_height.is_none()
+    }
+}
+
+/// The outcome of `StacksChainState::fsck`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FsckReport {
+    pub canonical_stacks_tip: StacksBlockId,
+    pub staging_blocks_checked: u64,
+    pub headers_checked: u64,
+    pub blocks_reexecuted: u64,
+    /// canonical blocks which could not be re-executed because their data has been pruned
+    pub blocks_skipped: u64,
+    pub problems: Vec<FsckProblem>,
+    pub repair_plan: RepairPlan,
+}
+
+/// Just enough of a block header to check that it links up with its parent.
+struct HeaderLink {
+    index_block_hash: StacksBlockId,
+    parent_block_id: StacksBlockId,
+    consensus_hash: ConsensusHash,
+    block_hash: BlockHeaderHash,
+    parent_block: BlockHeaderHash,
+    block_height: u64,
+}
+
+impl FromRow<HeaderLink> for HeaderLink {
+    fn from_row<'a>(row: &'a Row) -> Result<HeaderLink, db_error> {
+        Ok(HeaderLink {
+            index_block_hash: StacksBlockId::from_column(row, "index_block_hash")?,
+            parent_block_id: StacksBlockId::from_column(row, "parent_block_id")?,
+            consensus_hash: ConsensusHash::from_column(row, "consensus_hash")?,
+            block_hash: BlockHeaderHash::from_column(row, "block_hash")?,
+            parent_block: BlockHeaderHash::from_column(row, "parent_block")?,
+            block_height: u64::from_column(row, "block_height")?,
+        })
+    }
+}
+
+/// Check that `header` links up with `parent`, if it is known.
+fn check_header_link(header: &HeaderLink, parent: Option<&HeaderLink>) -> Option<String> {
+    let index_block_hash =
+        StacksBlockHeader::make_index_block_hash(&header.consensus_hash, &header.block_hash);
+    if index_block_hash != header.index_block_hash {
+        return Some(format!(
+            "index block hash should be {}/{} = {}",
+            &header.consensus_hash, &header.block_hash, &index_block_hash
+        ));
+    }
+    if header.block_height == 0 {
+        return None;
+    }
+    let parent = match parent {
+        Some(parent) => parent,
+        None => return Some(format!("parent {} has no header", &header.parent_block_id)),
+    };
+    if parent.block_hash != header.parent_block {
+        Some(format!(
+            "parent block is {}, but parent header {} is for {}",
+            &header.parent_block, &header.parent_block_id, &parent.block_hash
+        ))
+    } else if parent.block_height + 1 != header.block_height {
+        Some(format!(
+            "parent {} is at height {}",
+            &header.parent_block_id, parent.block_height
+        ))
+    } else {
+        None
+    }
+}
+
+impl StacksChainState {
+    /// Check this chainstate against `sortdb`, and itself:
+    /// * every staged block was elected by a sortition the sortition DB knows about, and every
+    /// processed one has a header;
+    /// * every block the sortition DB records as accepted has a header;
+    /// * every header links up with its parent header, by block hash and height;
+    /// * re-executing the last `num_blocks` blocks of the canonical chain reproduces the MARF
+    /// and withdrawal roots committed to by their headers.
+    ///
+    /// Nothing is written: re-executed blocks are rolled back.  Returns the problems found, and a
+    /// plan for repairing them.
+    pub fn fsck(&mut self, sortdb: &SortitionDB, num_blocks: u64) -> Result<FsckReport, Error> {
+        let mut problems = vec![];
+
+        // staged blocks vs. sortitions
+        let staging_blocks: Vec<StagingBlock> = query_rows(
+            self.db(),
+            "SELECT * FROM staging_blocks ORDER BY height ASC",
+            NO_PARAMS,
+        )?;
+        let orphaned_blocks: HashSet<_> = staging_blocks
+            .iter()
+            .filter(|block| block.orphaned)
+            .map(|block| {
+                StacksBlockHeader::make_index_block_hash(
+                    &block.consensus_hash,
+                    &block.anchored_block_hash,
+                )
+            })
+            .collect();
+        for block in staging_blocks.iter() {
+            let index_block_hash = StacksBlockHeader::make_index_block_hash(
+                &block.consensus_hash,
+                &block.anchored_block_hash,
+            );
+            let height = block.height;
+            if !block.orphaned {
+                match SortitionDB::get_block_snapshot_consensus(
+                    sortdb.conn(),
+                    &block.consensus_hash,
+                )? {
+                    None => problems.push(FsckProblem::UnknownSortition {
+                        index_block_hash: index_block_hash.clone(),
+                        height,
+                    }),
+                    Some(sn)
+                        if !sn.sortition
+                            || sn.winning_stacks_block_hash != block.anchored_block_hash =>
+                    {
+                        problems.push(FsckProblem::NotSortitionWinner {
+                            index_block_hash: index_block_hash.clone(),
+                            height,
+                        })
+                    }
+                    Some(_) => {}
+                }
+                let parent_block_id = StacksBlockHeader::make_index_block_hash(
+                    &block.parent_consensus_hash,
+                    &block.parent_anchored_block_hash,
+                );
+                if !block.processed && orphaned_blocks.contains(&parent_block_id) {
+                    problems.push(FsckProblem::OrphanedParent {
+                        index_block_hash: index_block_hash.clone(),
+                        height,
+                    });
+                }
+            }
+            if block.processed
+                && !block.orphaned
+                && !StacksChainState::has_stacks_block(self.db(), &index_block_hash)?
+            {
+                problems.push(FsckProblem::MissingHeader {
+                    index_block_hash,
+                    height,
+                });
+            }
+        }
+
+        // accepted sortitions vs. headers
+        let accepted: Vec<BlockSnapshot> = query_rows(
+            sortdb.conn(),
+            "SELECT * FROM snapshots WHERE stacks_block_accepted = 1 AND pox_valid = 1",
+            NO_PARAMS,
+        )?;
+        for sn in accepted.iter() {
+            let index_block_hash = StacksBlockHeader::make_index_block_hash(
+                &sn.consensus_hash,
+                &sn.winning_stacks_block_hash,
+            );
+            if !StacksChainState::has_stacks_block(self.db(), &index_block_hash)? {
+                problems.push(FsckProblem::MissingAcceptedBlock {
+                    index_block_hash,
+                    height: sn.stacks_block_height,
+                });
+            }
+        }
+
+        // header hash chains
+        let headers: Vec<HeaderLink> = query_rows(
+            self.db(),
+            "SELECT index_block_hash, parent_block_id, consensus_hash, block_hash, parent_block, block_height FROM block_headers",
+            NO_PARAMS,
+        )?;
+        let headers_by_id: HashMap<_, _> = headers
+            .iter()
+            .map(|header| (header.index_block_hash.clone(), header))
+            .collect();
+        for header in headers.iter() {
+            let parent = headers_by_id.get(&header.parent_block_id).copied();
+            if let Some(reason) = check_header_link(header, parent) {
+                problems.push(FsckProblem::BrokenHeaderChain {
+                    index_block_hash: header.index_block_hash.clone(),
+                    height: header.block_height,
+                    reason,
+                });
+            }
+        }
+
+        // MARF roots of the last blocks of the canonical chain
+        let burn_tip = SortitionDB::get_canonical_burn_chain_tip(sortdb.conn())?;
+        let canonical_stacks_tip = StacksBlockHeader::make_index_block_hash(
+            &burn_tip.canonical_stacks_tip_consensus_hash,
+            &burn_tip.canonical_stacks_tip_hash,
+        );
+        let mut blocks_reexecuted = 0;
+        let mut blocks_skipped = 0;
+        let mut cursor = headers_by_id.get(&canonical_stacks_tip).copied();
+        while let Some(header) = cursor {
+            if header.block_height == 0 || blocks_reexecuted + blocks_skipped >= num_blocks {
+                break;
+            }
+            let block = match StacksChainState::load_block(
+                &self.blocks_path,
+                &header.consensus_hash,
+                &header.block_hash,
+            ) {
+                Ok(Some(block)) => block,
+                Ok(None) | Err(_) => {
+                    debug!(
+                        "Not re-executing {}: block data is unavailable",
+                        &header.index_block_hash
+                    );
+                    blocks_skipped += 1;
+                    cursor = headers_by_id.get(&header.parent_block_id).copied();
+                    continue;
+                }
+            };
+            let reason = match self.reexecute_processed_block(
+                &sortdb.index_conn(),
+                &header.consensus_hash,
+                &block,
+            ) {
+                Ok(verdict) => verdict.invalid_reason,
+                Err(e) => Some(format!("failed to re-execute: {}", &e)),
+            };
+            if let Some(reason) = reason {
+                problems.push(FsckProblem::ReexecutionMismatch {
+                    index_block_hash: header.index_block_hash.clone(),
+                    height: header.block_height,
+                    reason,
+                });
+            }
+            blocks_reexecuted += 1;
+            cursor = headers_by_id.get(&header.parent_block_id).copied();
+        }
+
+        let repair_plan = RepairPlan::from_problems(&problems);
+        Ok(FsckReport {
+            canonical_stacks_tip,
+            staging_blocks_checked: staging_blocks.len() as u64,
+            headers_checked: headers.len() as u64,
+            blocks_reexecuted,
+            blocks_skipped,
+            problems,
+            repair_plan,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::chainstate::stacks::db::test::instantiate_chainstate;
+
+    #[test]
+    fn test_repair_plan() {
+        let block_id = |b: u8| StacksBlockId([b; 32]);
+        let problems = vec![
+            FsckProblem::UnknownSortition {
+                index_block_hash: block_id(1),
+                height: 7,
+            },
+            FsckProblem::ReexecutionMismatch {
+                index_block_hash: block_id(2),
+                height: 12,
+                reason: "State root mismatch".into(),
+            },
+            FsckProblem::BrokenHeaderChain {
+                index_block_hash: block_id(3),
+                height: 9,
+                reason: "parent has no header".into(),
+            },
+            FsckProblem::OrphanedParent {
+                index_block_hash: block_id(4),
+                height: 8,
+            },
+        ];
+        let plan = RepairPlan::from_problems(&problems);
+        assert_eq!(plan.orphan_cleanup, vec![block_id(1), block_id(4)]);
+        // reprocess from the lowest height whose state can't be trusted
+        assert_eq!(plan.reprocess_from_height, Some(9));
+
+        assert!(RepairPlan::from_problems(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_fsck_genesis() {
+        let mut chainstate = instantiate_chainstate(false, 0x80000000, "test_fsck_genesis");
+        let sortdb = SortitionDB::connect_test(0).unwrap();
+
+        // only the boot block exists, and it is never re-executed
+        let report = chainstate.fsck(&sortdb, 10).unwrap();
+        assert_eq!(report.headers_checked, 1);
+        assert_eq!(report.blocks_reexecuted, 0);
+        assert!(report.problems.is_empty());
+        assert!(report.repair_plan.is_empty());
+    }
+}
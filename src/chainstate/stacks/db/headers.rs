@@ -206,6 +206,13 @@ impl StacksChainState {
                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20, ?21, ?22, ?23, ?24)", args)
             .map_err(|e| Error::DBError(db_error::SqliteError(e)))?;
 
+        StacksChainState::archive_withdrawal_tree(
+            tx,
+            &index_block_hash,
+            block_height,
+            &tip_info.withdrawal_tree,
+        )?;
+
         Ok(())
     }
 
@@ -0,0 +1,199 @@
+// Copyright (C) 2013-2020 Blockstack PBC, a public benefit corporation
+// Copyright (C) 2020 Stacks Open Internet Foundation
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! The microblock stream built on an anchored L2 block, and how far the next anchored block on
+//! the canonical fork confirmed it, so that explorers can show transactions before their
+//! anchored block is mined.  The chainstate does not keep transaction receipts, so the receipts
+//! of a confirmed stream come from re-executing the anchored block which confirmed it, and an
+//! unconfirmed stream has none.
+
+use crate::chainstate::burn::db::sortdb::SortitionDB;
+use crate::chainstate::stacks::db::StacksChainState;
+use crate::chainstate::stacks::events::StacksTransactionReceipt;
+use crate::chainstate::stacks::{Error, StacksBlockHeader, StacksMicroblock};
+use stacks_common::types::chainstate::StacksBlockId;
+
+/// How far the microblock stream built on an anchored block has been confirmed
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MicroblockStreamStatus {
+    /// the next anchored block on the canonical fork confirmed the stream, up to its last
+    /// microblock
+    Confirmed,
+    /// the anchored block is the canonical tip, so the stream may still grow
+    Unconfirmed,
+    /// the next anchored block on the canonical fork confirmed none of the stream, or the
+    /// anchored block is not on the canonical fork
+    Orphaned,
+}
+
+impl MicroblockStreamStatus {
+    pub fn name(&self) -> &'static str {
+        match self {
+            MicroblockStreamStatus::Confirmed => "confirmed",
+            MicroblockStreamStatus::Unconfirmed => "unconfirmed",
+            MicroblockStreamStatus::Orphaned => "orphaned",
+        }
+    }
+}
+
+/// The microblock stream built on an anchored block
+#[derive(Debug, Clone, PartialEq)]
+pub struct MicroblockStream {
+    /// the anchored block the stream builds on
+    pub parent_block_id: StacksBlockId,
+    pub status: MicroblockStreamStatus,
+    /// the anchored block which confirmed the stream, if `Confirmed`
+    pub confirmed_by: Option<StacksBlockId>,
+    /// the confirmed microblocks if `Confirmed`, and otherwise the ones received so far, in
+    /// sequence order
+    pub microblocks: Vec<StacksMicroblock>,
+    /// the receipts of the microblocks' transactions, in the order they were mined, if
+    /// `Confirmed`
+    pub tx_receipts: Vec<StacksTransactionReceipt>,
+}
+
+impl StacksChainState {
+    /// The microblock stream built on the anchored block `parent_block_id`, or `None` if there
+    /// is no such block.  If the stream is confirmed, the anchored block which confirmed it is
+    /// re-executed and rolled back to get the receipts, so this errors if that block's data has
+    /// been pruned.
+    pub fn get_microblock_stream(
+        &mut self,
+        sortdb: &SortitionDB,
+        parent_block_id: &StacksBlockId,
+    ) -> Result<Option<MicroblockStream>, Error> {
+        let parent = match StacksChainState::get_stacks_block_header_info_by_index_block_hash(
+            self.db(),
+            parent_block_id,
+        )? {
+            Some(header) => header,
+            None => return Ok(None),
+        };
+        let (tip_consensus_hash, tip_block_hash) =
+            SortitionDB::get_canonical_stacks_chain_tip_hash(sortdb.conn())?;
+        let canonical_stacks_tip =
+            StacksBlockHeader::make_index_block_hash(&tip_consensus_hash, &tip_block_hash);
+
+        let on_canonical_fork = self
+            .index_conn()?
+            .get_ancestor_block_hash(parent.stacks_block_height, &canonical_stacks_tip)?
+            .as_ref()
+            == Some(parent_block_id);
+        let child_block_id = if on_canonical_fork {
+            self.index_conn()?
+                .get_ancestor_block_hash(parent.stacks_block_height + 1, &canonical_stacks_tip)?
+        } else {
+            None
+        };
+
+        let child_block_id = match child_block_id {
+            Some(child_block_id) => child_block_id,
+            None => {
+                let microblocks = StacksChainState::load_descendant_staging_microblock_stream(
+                    self.db(),
+                    parent_block_id,
+                    0,
+                    u16::MAX,
+                )?
+                .unwrap_or_default();
+                let status = if on_canonical_fork {
+                    MicroblockStreamStatus::Unconfirmed
+                } else {
+                    MicroblockStreamStatus::Orphaned
+                };
+                return Ok(Some(MicroblockStream {
+                    parent_block_id: parent_block_id.clone(),
+                    status,
+                    confirmed_by: None,
+                    microblocks,
+                    tx_receipts: vec![],
+                }));
+            }
+        };
+
+        let child = StacksChainState::get_stacks_block_header_info_by_index_block_hash(
+            self.db(),
+            &child_block_id,
+        )?
+        .ok_or(Error::NoSuchBlockError)?;
+        let block = StacksChainState::load_block(
+            &self.blocks_path,
+            &child.consensus_hash,
+            &child.anchored_header.block_hash(),
+        )?
+        .ok_or(Error::NoSuchBlockError)?;
+        if !block.has_microblock_parent() {
+            return Ok(Some(MicroblockStream {
+                parent_block_id: parent_block_id.clone(),
+                status: MicroblockStreamStatus::Orphaned,
+                confirmed_by: None,
+                microblocks: vec![],
+                tx_receipts: vec![],
+            }));
+        }
+
+        let microblocks = StacksChainState::load_microblock_stream_fork(
+            self.db(),
+            &parent.consensus_hash,
+            &parent.anchored_header.block_hash(),
+            &block.header.parent_microblock,
+        )?
+        .ok_or(Error::NoSuchBlockError)?;
+        let tx_receipts = self
+            .reexecute_block_for_events(&sortdb.index_conn(), &child.consensus_hash, &block)?
+            .tx_receipts
+            .into_iter()
+            .filter(|receipt| receipt.microblock_header.is_some())
+            .collect();
+
+        Ok(Some(MicroblockStream {
+            parent_block_id: parent_block_id.clone(),
+            status: MicroblockStreamStatus::Confirmed,
+            confirmed_by: Some(child_block_id),
+            microblocks,
+            tx_receipts,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::chainstate::stacks::db::test::instantiate_chainstate;
+    use crate::core::FIRST_STACKS_BLOCK_ID;
+
+    #[test]
+    fn test_get_microblock_stream_genesis() {
+        let mut chainstate =
+            instantiate_chainstate(false, 0x80000000, "test_get_microblock_stream_genesis");
+        let sortdb = SortitionDB::connect_test(0).unwrap();
+
+        assert!(chainstate
+            .get_microblock_stream(&sortdb, &StacksBlockId([0x11; 32]))
+            .unwrap()
+            .is_none());
+
+        // the boot block is the canonical tip, and nothing has been built on it
+        let stream = chainstate
+            .get_microblock_stream(&sortdb, &FIRST_STACKS_BLOCK_ID)
+            .unwrap()
+            .unwrap();
+        assert_eq!(stream.status, MicroblockStreamStatus::Unconfirmed);
+        assert_eq!(stream.confirmed_by, None);
+        assert!(stream.microblocks.is_empty());
+        assert!(stream.tx_receipts.is_empty());
+    }
+}
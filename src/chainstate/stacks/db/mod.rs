@@ -14,7 +14,7 @@
 // You should have received a copy of the GNU General Public License
 // along with this program.  If not, see <http://www.gnu.org/licenses/>.
 
-use std::collections::{btree_map::Entry, BTreeMap};
+use std::collections::{btree_map::Entry, BTreeMap, HashSet};
 use std::fmt;
 use std::fs;
 use std::io;
@@ -37,8 +37,14 @@ use crate::chainstate::burn::db::sortdb::{SortitionDB, SortitionDBConn};
 use crate::chainstate::burn::ConsensusHash;
 use crate::chainstate::stacks::boot::*;
 use crate::chainstate::stacks::db::accounts::*;
+use crate::chainstate::stacks::db::activations::FeatureActivations;
+use crate::chainstate::stacks::db::block_compression::BlockCompression;
 use crate::chainstate::stacks::db::blocks::*;
+use crate::chainstate::stacks::db::coinbase_schedule::CoinbaseSchedule;
+use crate::chainstate::stacks::db::contract_policy::ContractPublishPolicy;
+use crate::chainstate::stacks::db::prevalidate::BlockPrevalidator;
 use crate::chainstate::stacks::db::unconfirmed::UnconfirmedState;
+use crate::chainstate::stacks::db::withdrawal_policy::{QueuedWithdrawal, WithdrawalPolicy};
 use crate::chainstate::stacks::events::*;
 use crate::chainstate::stacks::index::marf::{
     MARFOpenOpts, MarfConnection, BLOCK_HASH_TO_HEIGHT_MAPPING_KEY,
@@ -94,12 +100,34 @@ use crate::clarity_vm::database::HeadersDBConn;
 use crate::util_lib::boot::{boot_code_acc, boot_code_addr, boot_code_id, boot_code_tx_auth};
 use clarity::vm::Value;
 use stacks_common::types::chainstate::{StacksAddress, StacksBlockId, TrieHash};
+pub mod account_index;
 pub mod accounts;
+pub mod activations;
+pub mod block_compression;
+pub mod block_costs;
 pub mod blocks;
+pub mod coinbase_schedule;
+pub mod commits;
+pub mod contract_policy;
 pub mod contracts;
+pub mod deposit_finality;
+pub mod event_replay;
+pub mod failed_deposits;
+pub mod fee_market;
+pub mod forks;
+pub mod fsck;
 pub mod headers;
+pub mod microblock_stream;
+pub mod prevalidate;
+pub mod prune;
+pub mod replay;
+pub mod rewards_report;
+pub mod state_diff;
 pub mod transactions;
 pub mod unconfirmed;
+pub mod withdrawal_archive;
+pub mod withdrawal_index;
+pub mod withdrawal_policy;
 
 lazy_static! {
     pub static ref TRANSACTION_LOG: bool =
@@ -117,6 +145,12 @@ pub struct StacksChainState {
     pub root_path: String,
     pub unconfirmed_state: Option<UnconfirmedState>,
     marf_opts: Option<MARFOpenOpts>,
+    block_prevalidator: Option<BlockPrevalidator>,
+    withdrawal_policy: WithdrawalPolicy,
+    contract_publish_policy: ContractPublishPolicy,
+    account_index: bool,
+    block_compression: BlockCompression,
+    required_commit_confirmations: u64,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -180,6 +214,10 @@ pub struct StacksEpochReceipt {
     /// which is the Stacks epoch that this block's parent was elected
     /// in.
     pub evaluated_epoch: StacksEpochId,
+    /// withdrawals this block held back from its withdrawal root
+    pub withdrawals_queued: Vec<QueuedWithdrawal>,
+    /// withdrawals held back by an ancestor which this block's withdrawal root includes
+    pub withdrawals_released: Vec<QueuedWithdrawal>,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -191,14 +229,24 @@ pub struct DBConfig {
 
 impl DBConfig {
     pub fn supports_epoch(&self, epoch_id: StacksEpochId) -> bool {
+        if epoch_id == StacksEpochId::Epoch10 {
+            return true;
+        }
+        let version = match self.version.parse::<u32>() {
+            Ok(version) => version,
+            Err(_) => return false,
+        };
+        let latest = CHAINSTATE_VERSION
+            .parse::<u32>()
+            .expect("FATAL: CHAINSTATE_VERSION is not a number");
         match epoch_id {
             StacksEpochId::Epoch10 => true,
-            StacksEpochId::Epoch20 => self.version == "1" || self.version == "2",
-            StacksEpochId::Epoch2_05 => self.version == "2",
-            StacksEpochId::Epoch21 => self.version == "2",
-            StacksEpochId::Epoch22 => self.version == "2",
-            StacksEpochId::Epoch23 => self.version == "2",
-            StacksEpochId::Epoch24 => self.version == "2",
+            StacksEpochId::Epoch20 => (1..=latest).contains(&version),
+            StacksEpochId::Epoch2_05
+            | StacksEpochId::Epoch21
+            | StacksEpochId::Epoch22
+            | StacksEpochId::Epoch23
+            | StacksEpochId::Epoch24 => (2..=latest).contains(&version),
         }
     }
 }
@@ -308,6 +356,8 @@ pub type StacksDBConn<'a> = IndexDBConn<'a, (), StacksBlockId>;
 pub struct ClarityTx<'a, 'b> {
     block: ClarityBlockConnection<'a, 'b>,
     pub config: DBConfig,
+    /// transactions which already passed `process_transaction_precheck` off-thread
+    prevalidated_txids: HashSet<Txid>,
 }
 
 impl<'a, 'b> ClarityConnection for ClarityTx<'a, 'b> {
@@ -435,6 +485,9 @@ pub struct ChainstateTx<'a> {
     pub blocks_path: String,
     pub tx: StacksDBTx<'a>,
     pub root_path: String,
+    pub withdrawal_policy: WithdrawalPolicy,
+    /// whether to record the transactions touching each principal as blocks are processed
+    pub account_index: bool,
 }
 
 impl<'a> ChainstateTx<'a> {
@@ -443,12 +496,16 @@ impl<'a> ChainstateTx<'a> {
         blocks_path: String,
         root_path: String,
         config: DBConfig,
+        withdrawal_policy: WithdrawalPolicy,
+        account_index: bool,
     ) -> ChainstateTx<'a> {
         ChainstateTx {
             config,
             blocks_path,
             tx,
             root_path,
+            withdrawal_policy,
+            account_index,
         }
     }
 
@@ -590,7 +647,7 @@ pub struct TxStreamData {
     pub corked: bool,
 }
 
-pub const CHAINSTATE_VERSION: &'static str = "2";
+pub const CHAINSTATE_VERSION: &'static str = "14";
 
 const CHAINSTATE_INITIAL_SCHEMA: &'static [&'static str] = &[
     "PRAGMA foreign_keys = ON;",
@@ -747,6 +804,215 @@ const CHAINSTATE_SCHEMA_2: &'static [&'static str] = &[
     "#,
 ];
 
+const CHAINSTATE_SCHEMA_3: &'static [&'static str] = &[
+    // new in schema version 3
+    // large withdrawals held back by the withdrawal policy, keyed by the block that queued them
+    r#"
+    CREATE TABLE queued_withdrawals(
+        index_block_hash TEXT NOT NULL,
+        queue_index INTEGER NOT NULL,
+        withdrawal TEXT NOT NULL,           -- hex-encoded withdraw event data tuple
+        queued_height INTEGER NOT NULL,
+        release_height INTEGER NOT NULL,
+        PRIMARY KEY(index_block_hash,queue_index)
+    );"#,
+    r#"
+    UPDATE db_config SET version = "3";
+    "#,
+];
+
+const CHAINSTATE_SCHEMA_4: &'static [&'static str] = &[
+    // new in schema version 4
+    // withdrawals in each block's withdrawal root, so they can be looked up by sender
+    r#"
+    CREATE TABLE withdrawals(
+        index_block_hash TEXT NOT NULL,     -- block whose withdrawal root includes the withdrawal
+        block_height INTEGER NOT NULL,
+        withdrawal_id INTEGER NOT NULL,
+        sender TEXT NOT NULL,
+        asset_type TEXT NOT NULL,           -- "stx", "ft", "nft", "sft", or "stx-transfer"
+        asset_contract TEXT,                -- NULL for STX
+        asset_id TEXT,                      -- encodes u128; NULL for STX and FTs
+        amount TEXT,                        -- encodes u128; NULL for NFTs
+        withdrawal_root TEXT NOT NULL,
+        PRIMARY KEY(index_block_hash,withdrawal_id)
+    );"#,
+    "CREATE INDEX withdrawals_by_sender ON withdrawals(sender,block_height);",
+    r#"
+    UPDATE db_config SET version = "4";
+    "#,
+];
+
+const CHAINSTATE_SCHEMA_5: &'static [&'static str] = &[
+    // new in schema version 5
+    // the fee market's minimum fee rate for transactions building on each block
+    r#"
+    CREATE TABLE fee_rate_floors(
+        index_block_hash TEXT PRIMARY KEY NOT NULL,
+        block_height INTEGER NOT NULL,
+        utilization INTEGER NOT NULL,       -- percentage of the block's budget it used
+        min_fee_rate INTEGER NOT NULL       -- microSTX per byte
+    );"#,
+    r#"
+    UPDATE db_config SET version = "5";
+    "#,
+];
+
+const CHAINSTATE_SCHEMA_6: &'static [&'static str] = &[
+    // new in schema version 6
+    // deposits that could not be materialized on the subnet, and were refunded instead
+    r#"
+    CREATE TABLE failed_deposits(
+        index_block_hash TEXT NOT NULL,     -- block that processed (and refunded) the deposit
+        block_height INTEGER NOT NULL,
+        txid TEXT NOT NULL,                 -- L1 transaction that made the deposit
+        burn_header_hash TEXT NOT NULL,
+        sender TEXT NOT NULL,
+        asset_type TEXT NOT NULL,           -- "ft", "nft", or "sft"
+        l1_contract TEXT NOT NULL,
+        subnet_contract TEXT NOT NULL,
+        asset_id TEXT,                      -- encodes u128; NULL for FTs
+        amount TEXT,                        -- encodes u128; NULL for NFTs
+        result TEXT NOT NULL,               -- the deposit's result, e.g. "(err u1)"
+        refund_withdrawal_id INTEGER        -- NULL if the withdrawal policy held the refund back
+    );"#,
+    "CREATE INDEX failed_deposits_by_sender ON failed_deposits(sender,block_height);",
+    r#"
+    UPDATE db_config SET version = "6";
+    "#,
+];
+
+const CHAINSTATE_SCHEMA_7: &'static [&'static str] = &[
+    // new in schema version 7
+    // the transactions touching each principal, if the node keeps an account index
+    r#"
+    CREATE TABLE account_transactions(
+        principal TEXT NOT NULL,
+        txid TEXT NOT NULL,
+        index_block_hash TEXT NOT NULL,
+        block_height INTEGER NOT NULL,
+        tx_index INTEGER NOT NULL,
+        roles TEXT NOT NULL,                -- comma-separated, e.g. "origin,recipient"
+        PRIMARY KEY(principal,txid,index_block_hash)
+    );"#,
+    "CREATE INDEX account_transactions_by_principal ON account_transactions(principal,block_height);",
+    r#"
+    UPDATE db_config SET version = "7";
+    "#,
+];
+
+const CHAINSTATE_SCHEMA_8: &'static [&'static str] = &[
+    // new in schema version 8
+    // the execution cost of each contract's transactions in each recent block
+    r#"
+    CREATE TABLE contract_costs(
+        index_block_hash TEXT NOT NULL,
+        block_height INTEGER NOT NULL,
+        contract_id TEXT NOT NULL,
+        num_txs INTEGER NOT NULL,
+        runtime INTEGER NOT NULL,
+        read_count INTEGER NOT NULL,
+        read_length INTEGER NOT NULL,
+        write_count INTEGER NOT NULL,
+        write_length INTEGER NOT NULL,
+        PRIMARY KEY(index_block_hash,contract_id)
+    );"#,
+    "CREATE INDEX contract_costs_by_height ON contract_costs(block_height);",
+    r#"
+    UPDATE db_config SET version = "8";
+    "#,
+];
+
+const CHAINSTATE_SCHEMA_9: &'static [&'static str] = &[
+    // new in schema version 9
+    // the coinbase schedule the chainstate was booted with
+    r#"
+    CREATE TABLE coinbase_schedule(
+        amount_per_block TEXT NOT NULL,     -- encodes u128
+        halving_interval INTEGER NOT NULL,
+        recipient TEXT                      -- NULL pays the miner
+    );"#,
+    r#"
+    UPDATE db_config SET version = "9";
+    "#,
+];
+
+const CHAINSTATE_SCHEMA_10: &'static [&'static str] = &[
+    // new in schema version 10
+    // how many L1 confirmations a deposit needs before it is materialized
+    r#"
+    CREATE TABLE deposit_finality(
+        confirmations INTEGER NOT NULL
+    );"#,
+    r#"
+    UPDATE db_config SET version = "10";
+    "#,
+];
+
+const CHAINSTATE_SCHEMA_11: &'static [&'static str] = &[
+    // new in schema version 11
+    // every block's withdrawal Merkle tree, which pruning never removes, so that withdrawal
+    // proofs can always be made.  Seeded from the trees in the block headers.
+    r#"
+    CREATE TABLE withdrawal_tree_archive(
+        index_block_hash TEXT PRIMARY KEY,
+        block_height INTEGER NOT NULL,
+        withdrawal_root TEXT NOT NULL,
+        withdrawal_tree TEXT NOT NULL       -- JSON-encoded MerkleTree
+    );"#,
+    r#"
+    INSERT INTO withdrawal_tree_archive (index_block_hash, block_height, withdrawal_root, withdrawal_tree)
+        SELECT index_block_hash, block_height, withdrawal_merkle_root, withdrawal_tree FROM block_headers;
+    "#,
+    r#"
+    UPDATE db_config SET version = "11";
+    "#,
+];
+
+const CHAINSTATE_SCHEMA_12: &'static [&'static str] = &[
+    // new in schema version 12
+    // the L1 block-commit which chose each processed block
+    r#"
+    CREATE TABLE block_commit_links(
+        index_block_hash TEXT PRIMARY KEY,
+        block_height INTEGER NOT NULL,
+        l1_txid TEXT NOT NULL,
+        l1_block_hash TEXT NOT NULL,
+        l1_block_height INTEGER NOT NULL,
+        confirmations INTEGER NOT NULL      -- L1 confirmations when the block was processed
+    );"#,
+    r#"
+    UPDATE db_config SET version = "12";
+    "#,
+];
+
+const CHAINSTATE_SCHEMA_13: &'static [&'static str] = &[
+    // new in schema version 13
+    // the withdrawal policy the chainstate was booted with
+    r#"
+    CREATE TABLE withdrawal_policy(
+        large_withdrawal_threshold TEXT NOT NULL,   -- encodes u128
+        large_withdrawal_delay INTEGER NOT NULL
+    );"#,
+    r#"
+    UPDATE db_config SET version = "13";
+    "#,
+];
+
+const CHAINSTATE_SCHEMA_14: &'static [&'static str] = &[
+    // new in schema version 14
+    // the subnet block heights at which consensus changes activate (NULL if never)
+    r#"
+    CREATE TABLE feature_activations(
+        time_oracle INTEGER,
+        subnet_transfers INTEGER,
+        deposit_refunds INTEGER
+    );"#,
+    r#"
+    UPDATE db_config SET version = "14";
+    "#,
+];
+
 const CHAINSTATE_INDEXES: &'static [&'static str] = &[
     "CREATE INDEX IF NOT EXISTS index_block_hash_to_primary_key ON block_headers(index_block_hash,consensus_hash,block_hash);",
     "CREATE INDEX IF NOT EXISTS block_headers_hash_index ON block_headers(block_hash,block_height);",
@@ -841,6 +1107,19 @@ pub struct ChainStateBootData {
         Option<Box<dyn FnOnce() -> Box<dyn Iterator<Item = ChainstateBNSNamespace>>>>,
     pub get_bulk_initial_names:
         Option<Box<dyn FnOnce() -> Box<dyn Iterator<Item = ChainstateBNSName>>>>,
+    /// the coinbase each block mints, recorded in the chainstate at genesis
+    pub coinbase_schedule: CoinbaseSchedule,
+    /// how many L1 blocks must confirm a deposit before a block materializes it, recorded in
+    /// the chainstate at genesis
+    pub deposit_confirmations: u64,
+    /// the policy for holding back large withdrawals, recorded in the chainstate at genesis
+    pub withdrawal_policy: WithdrawalPolicy,
+    /// the subnet block heights at which consensus changes activate, recorded in the chainstate
+    /// at genesis
+    pub feature_activations: FeatureActivations,
+    /// whether a mainnet boot must produce `SUBNET_GENESIS_ROOT_HASH`.  Only callers which
+    /// compare the genesis root hash with their own expectation may turn this off.
+    pub check_genesis_root_hash: bool,
 }
 
 impl ChainStateBootData {
@@ -860,6 +1139,11 @@ impl ChainStateBootData {
             get_bulk_initial_balances: None,
             get_bulk_initial_namespaces: None,
             get_bulk_initial_names: None,
+            coinbase_schedule: CoinbaseSchedule::default(),
+            deposit_confirmations: 0,
+            withdrawal_policy: WithdrawalPolicy::default(),
+            feature_activations: FeatureActivations::default(),
+            check_genesis_root_hash: true,
         }
     }
 }
@@ -958,6 +1242,90 @@ impl StacksChainState {
                         }
                     }
                     "2" => {
+                        // migrate to 3
+                        info!("Migrating chainstate schema from version 2 to 3");
+                        for cmd in CHAINSTATE_SCHEMA_3.iter() {
+                            tx.execute_batch(cmd)?;
+                        }
+                    }
+                    "3" => {
+                        // migrate to 4
+                        info!("Migrating chainstate schema from version 3 to 4");
+                        for cmd in CHAINSTATE_SCHEMA_4.iter() {
+                            tx.execute_batch(cmd)?;
+                        }
+                    }
+                    "4" => {
+                        // migrate to 5
+                        info!("Migrating chainstate schema from version 4 to 5");
+                        for cmd in CHAINSTATE_SCHEMA_5.iter() {
+                            tx.execute_batch(cmd)?;
+                        }
+                    }
+                    "5" => {
+                        // migrate to 6
+                        info!("Migrating chainstate schema from version 5 to 6");
+                        for cmd in CHAINSTATE_SCHEMA_6.iter() {
+                            tx.execute_batch(cmd)?;
+                        }
+                    }
+                    "6" => {
+                        // migrate to 7
+                        info!("Migrating chainstate schema from version 6 to 7");
+                        for cmd in CHAINSTATE_SCHEMA_7.iter() {
+                            tx.execute_batch(cmd)?;
+                        }
+                    }
+                    "7" => {
+                        // migrate to 8
+                        info!("Migrating chainstate schema from version 7 to 8");
+                        for cmd in CHAINSTATE_SCHEMA_8.iter() {
+                            tx.execute_batch(cmd)?;
+                        }
+                    }
+                    "8" => {
+                        // migrate to 9
+                        info!("Migrating chainstate schema from version 8 to 9");
+                        for cmd in CHAINSTATE_SCHEMA_9.iter() {
+                            tx.execute_batch(cmd)?;
+                        }
+                    }
+                    "9" => {
+                        // migrate to 10
+                        info!("Migrating chainstate schema from version 9 to 10");
+                        for cmd in CHAINSTATE_SCHEMA_10.iter() {
+                            tx.execute_batch(cmd)?;
+                        }
+                    }
+                    "10" => {
+                        // migrate to 11
+                        info!("Migrating chainstate schema from version 10 to 11");
+                        for cmd in CHAINSTATE_SCHEMA_11.iter() {
+                            tx.execute_batch(cmd)?;
+                        }
+                    }
+                    "11" => {
+                        // migrate to 12
+                        info!("Migrating chainstate schema from version 11 to 12");
+                        for cmd in CHAINSTATE_SCHEMA_12.iter() {
+                            tx.execute_batch(cmd)?;
+                        }
+                    }
+                    "12" => {
+                        // migrate to 13
+                        info!("Migrating chainstate schema from version 12 to 13");
+                        for cmd in CHAINSTATE_SCHEMA_13.iter() {
+                            tx.execute_batch(cmd)?;
+                        }
+                    }
+                    "13" => {
+                        // migrate to 14
+                        info!("Migrating chainstate schema from version 13 to 14");
+                        for cmd in CHAINSTATE_SCHEMA_14.iter() {
+                            tx.execute_batch(cmd)?;
+                        }
+                    }
+                    "14" => {
                         // done
                         break;
                     }
@@ -1075,6 +1443,19 @@ impl StacksChainState {
         return principal;
     }
 
+    /// Get the root hash of the Clarity state at the genesis block, i.e. once the boot code,
+    /// initial balances, and any post-flight callback have been applied.
+    pub fn get_genesis_root_hash(&mut self) -> Result<TrieHash, Error> {
+        let index_block_hash = StacksBlockHeader::make_index_block_hash(
+            &FIRST_BURNCHAIN_CONSENSUS_HASH,
+            &FIRST_STACKS_BLOCK_HASH,
+        );
+        let root_hash = self
+            .clarity_state
+            .with_marf(|marf| marf.get_root_hash_at(&index_block_hash))?;
+        Ok(root_hash)
+    }
+
     /// Install the boot code into the chain history.
     fn install_boot_code(
         chainstate: &mut StacksChainState,
@@ -1459,17 +1840,11 @@ impl StacksChainState {
 
         // verify that genesis root hash is as expected
         {
-            let genesis_root_hash = chainstate.clarity_state.with_marf(|marf| {
-                let index_block_hash = StacksBlockHeader::make_index_block_hash(
-                    &FIRST_BURNCHAIN_CONSENSUS_HASH,
-                    &FIRST_STACKS_BLOCK_HASH,
-                );
-                marf.get_root_hash_at(&index_block_hash).unwrap()
-            });
+            let genesis_root_hash = chainstate.get_genesis_root_hash()?;
 
             info!("Computed Clarity state genesis"; "root_hash" => %genesis_root_hash);
 
-            if mainnet {
+            if mainnet && boot_data.check_genesis_root_hash {
                 assert_eq!(
                     &genesis_root_hash.to_string(),
                     SUBNET_GENESIS_ROOT_HASH,
@@ -1517,6 +1892,10 @@ impl StacksChainState {
                 &first_tip_info,
                 &ExecutionCost::zero(),
             )?;
+            StacksChainState::insert_coinbase_schedule(&tx, &boot_data.coinbase_schedule)?;
+            StacksChainState::insert_deposit_confirmations(&tx, boot_data.deposit_confirmations)?;
+            StacksChainState::insert_withdrawal_policy(&tx, &boot_data.withdrawal_policy)?;
+            StacksChainState::insert_feature_activations(&tx, &boot_data.feature_activations)?;
             tx.commit()?;
         }
 
@@ -1533,15 +1912,100 @@ impl StacksChainState {
         StacksChainState::open_and_exec(mainnet, chain_id, path_str, None, marf_opts)
     }
 
+    /// Open an existing chainstate without instantiating or migrating any of its databases, so
+    /// that a separate process can serve reads from a chainstate which a running node writes to.
+    /// Fails if the chainstate does not exist, or was made by a different version of the node.
+    pub fn open_readonly(
+        mainnet: bool,
+        chain_id: u32,
+        path_str: &str,
+        marf_opts: Option<MARFOpenOpts>,
+    ) -> Result<StacksChainState, Error> {
+        let path = PathBuf::from(path_str);
+        let path_to_string = |path: PathBuf| {
+            path.to_str()
+                .map(|path| path.to_string())
+                .ok_or_else(|| Error::DBError(db_error::ParseError))
+        };
+        let blocks_path_root = path_to_string(StacksChainState::blocks_path(path.clone()))?;
+        let clarity_state_index_root =
+            path_to_string(StacksChainState::vm_state_index_root_path(path.clone()))?;
+        let clarity_state_index_marf =
+            path_to_string(StacksChainState::vm_state_index_marf_path(path.clone()))?;
+        let header_index_root = path_to_string(StacksChainState::header_index_root_path(path))?;
+
+        if fs::metadata(&clarity_state_index_marf).is_err()
+            || fs::metadata(&header_index_root).is_err()
+        {
+            return Err(Error::DBError(db_error::NoDBError));
+        }
+
+        let state_index = StacksChainState::open_index(&header_index_root)?;
+        let db_config = StacksChainState::load_db_config(state_index.sqlite_conn())?;
+        if db_config.mainnet != mainnet
+            || db_config.chain_id != chain_id
+            || db_config.version != CHAINSTATE_VERSION
+        {
+            error!(
+                "Invalid chain state database: expected mainnet = {}, chain ID = {}, version = {}; got {}, {}, {}",
+                mainnet, chain_id, CHAINSTATE_VERSION, db_config.mainnet, db_config.chain_id, db_config.version
+            );
+            return Err(Error::InvalidChainstateDB);
+        }
+
+        let vm_state = MarfedKV::open(
+            &clarity_state_index_root,
+            Some(&StacksBlockHeader::make_index_block_hash(
+                &MINER_BLOCK_CONSENSUS_HASH,
+                &MINER_BLOCK_HEADER_HASH,
+            )),
+            marf_opts.clone(),
+        )
+        .map_err(|e| Error::ClarityError(e.into()))?;
+
+        let withdrawal_policy =
+            StacksChainState::get_recorded_withdrawal_policy(state_index.sqlite_conn())?
+                .unwrap_or_default();
+
+        Ok(StacksChainState {
+            mainnet,
+            chain_id,
+            clarity_state: ClarityInstance::new(mainnet, chain_id, vm_state),
+            state_index,
+            blocks_path: blocks_path_root,
+            clarity_state_index_path: clarity_state_index_marf,
+            clarity_state_index_root,
+            root_path: path_str.to_string(),
+            unconfirmed_state: None,
+            marf_opts,
+            block_prevalidator: None,
+            withdrawal_policy,
+            contract_publish_policy: ContractPublishPolicy::default(),
+            account_index: false,
+            block_compression: BlockCompression::default(),
+            required_commit_confirmations: 0,
+        })
+    }
+
     /// Re-open the chainstate -- i.e. to get a new handle to it using an existing chain state's
     /// parameters
+    /// Open another handle to this chainstate.  The new handle shares this one's withdrawal
+    /// policy, since blocks built or processed through it must commit to the same withdrawal root,
+    /// its contract publish policy, whether it keeps an account index, how it compresses the
+    /// blocks it stores, and how many L1 confirmations it waits for before processing a block.
     pub fn reopen(&self) -> Result<(StacksChainState, Vec<StacksTransactionReceipt>), Error> {
-        StacksChainState::open(
+        let (mut chainstate, receipts) = StacksChainState::open(
             self.mainnet,
             self.chain_id,
             &self.root_path,
             self.marf_opts.clone(),
-        )
+        )?;
+        chainstate.withdrawal_policy = self.withdrawal_policy.clone();
+        chainstate.contract_publish_policy = self.contract_publish_policy.clone();
+        chainstate.account_index = self.account_index;
+        chainstate.block_compression = self.block_compression;
+        chainstate.required_commit_confirmations = self.required_commit_confirmations;
+        Ok((chainstate, receipts))
     }
 
     pub fn blocks_path(mut path: PathBuf) -> PathBuf {
@@ -1649,6 +2113,12 @@ impl StacksChainState {
             root_path: path_str.to_string(),
             unconfirmed_state: None,
             marf_opts: marf_opts,
+            block_prevalidator: None,
+            withdrawal_policy: WithdrawalPolicy::default(),
+            contract_publish_policy: ContractPublishPolicy::default(),
+            account_index: false,
+            block_compression: BlockCompression::default(),
+            required_commit_confirmations: 0,
         };
 
         let mut receipts = vec![];
@@ -1665,6 +2135,9 @@ impl StacksChainState {
             }
             (false, _) => {}
         }
+        if let Some(policy) = StacksChainState::get_recorded_withdrawal_policy(chainstate.db())? {
+            chainstate.withdrawal_policy = policy;
+        }
 
         Ok((chainstate, receipts))
     }
@@ -1703,8 +2176,14 @@ impl StacksChainState {
         let clarity_instance = &mut self.clarity_state;
         let inner_tx = StacksDBTx::new(&mut self.state_index, ());
 
-        let chainstate_tx =
-            ChainstateTx::new(inner_tx, blocks_path, self.root_path.clone(), config);
+        let chainstate_tx = ChainstateTx::new(
+            inner_tx,
+            blocks_path,
+            self.root_path.clone(),
+            config,
+            self.withdrawal_policy.clone(),
+            self.account_index,
+        );
 
         Ok((chainstate_tx, clarity_instance))
     }
@@ -1848,6 +2327,7 @@ impl StacksChainState {
         ClarityTx {
             block: inner_clarity_tx,
             config: conf,
+            prevalidated_txids: HashSet::new(),
         }
     }
 
@@ -1984,6 +2464,7 @@ impl StacksChainState {
         ClarityTx {
             block: inner_clarity_tx,
             config: conf,
+            prevalidated_txids: HashSet::new(),
         }
     }
 
@@ -2060,6 +2541,7 @@ impl StacksChainState {
         ClarityTx {
             block: inner_clarity_tx,
             config: conf,
+            prevalidated_txids: HashSet::new(),
         }
     }
 
@@ -2257,6 +2739,11 @@ pub mod test {
             get_bulk_initial_balances: None,
             get_bulk_initial_names: None,
             get_bulk_initial_namespaces: None,
+            coinbase_schedule: CoinbaseSchedule::default(),
+            deposit_confirmations: 0,
+            withdrawal_policy: WithdrawalPolicy::default(),
+            feature_activations: FeatureActivations::default(),
+            check_genesis_root_hash: true,
         };
 
         StacksChainState::open_and_exec(mainnet, chain_id, &path, Some(&mut boot_data), None)
@@ -2298,4 +2785,46 @@ pub mod test {
             assert!(contract_res.is_some());
         }
     }
+
+    /// Boot a mainnet chainstate which seeds a balance, so its genesis root hash is not
+    /// `SUBNET_GENESIS_ROOT_HASH`
+    fn instantiate_custom_mainnet_chainstate(
+        test_name: &str,
+        check_genesis_root_hash: bool,
+    ) -> StacksChainState {
+        let path = chainstate_path(test_name);
+        if fs::metadata(&path).is_ok() {
+            fs::remove_dir_all(&path).unwrap();
+        }
+
+        let burnchain = Burnchain::default_unittest(0, &BurnchainHeaderHash::zero());
+        let addr = StacksAddress {
+            version: C32_ADDRESS_VERSION_MAINNET_SINGLESIG,
+            bytes: Hash160([0x01; 20]),
+        };
+        let mut boot_data =
+            ChainStateBootData::new(&burnchain, vec![(PrincipalData::from(addr), 1000)], None);
+        boot_data.check_genesis_root_hash = check_genesis_root_hash;
+
+        StacksChainState::open_and_exec(true, SUBNET_CHAIN_ID, &path, Some(&mut boot_data), None)
+            .unwrap()
+            .0
+    }
+
+    #[test]
+    #[should_panic(expected = "Incorrect root hash for genesis block computed")]
+    fn test_custom_mainnet_genesis_checks_root_hash() {
+        // seeding balances doesn't skip the check by itself
+        instantiate_custom_mainnet_chainstate("custom-mainnet-genesis-checked", true);
+    }
+
+    #[test]
+    fn test_custom_mainnet_genesis_unchecked_root_hash() {
+        let mut chainstate =
+            instantiate_custom_mainnet_chainstate("custom-mainnet-genesis-unchecked", false);
+        assert_ne!(
+            chainstate.get_genesis_root_hash().unwrap().to_string(),
+            SUBNET_GENESIS_ROOT_HASH
+        );
+    }
 }
@@ -0,0 +1,331 @@
+// Copyright (C) 2013-2020 Blockstack PBC, a public benefit corporation
+// Copyright (C) 2020 Stacks Open Internet Foundation
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use rusqlite::types::ToSql;
+
+use crate::burnchains::Txid;
+use crate::chainstate::stacks::db::blocks::StagingBlock;
+use crate::chainstate::stacks::db::{DBConfig, StacksChainState};
+use crate::chainstate::stacks::{Error, StacksBlock};
+use crate::util_lib::db::{query_rows, u64_to_sql};
+use stacks_common::types::chainstate::StacksBlockId;
+
+/// How many queued staging blocks each worker thread is kept ahead of the chains coordinator
+const PREVALIDATION_LOOKAHEAD_PER_THREAD: usize = 4;
+
+/// Runs the context-free checks on the transactions of queued staging blocks -- signatures,
+/// chain ID, and network version (see `StacksChainState::process_transaction_precheck`) -- on a
+/// pool of worker threads, so that the chains coordinator does not have to do so while it
+/// processes them.  Everything that depends on chain state, including Clarity execution, stays
+/// serialized on the coordinator thread.
+pub struct BlockPrevalidator {
+    job_tx: Sender<(StacksBlockId, StacksBlock)>,
+    result_rx: Receiver<(StacksBlockId, HashSet<Txid>)>,
+    /// blocks handed to the workers whose results have not been collected yet
+    in_flight: HashSet<StacksBlockId>,
+    /// for each prevalidated block, the transactions which passed their prechecks
+    prevalidated: HashMap<StacksBlockId, HashSet<Txid>>,
+    /// how many blocks to have in flight or prevalidated at once
+    lookahead: usize,
+}
+
+/// Run `StacksChainState::process_transaction_precheck` on each of the block's transactions, and
+/// return the txids of those that pass.
+fn prevalidate_block_txs(config: &DBConfig, block: &StacksBlock) -> HashSet<Txid> {
+    block
+        .txs
+        .iter()
+        .filter(|tx| StacksChainState::process_transaction_precheck(config, tx).is_ok())
+        .map(|tx| tx.txid())
+        .collect()
+}
+
+impl BlockPrevalidator {
+    /// Start `num_threads` worker threads.  They exit once this prevalidator is dropped.
+    pub fn new(config: DBConfig, num_threads: usize) -> BlockPrevalidator {
+        let (job_tx, job_rx) = channel::<(StacksBlockId, StacksBlock)>();
+        let (result_tx, result_rx) = channel();
+        let job_rx = Arc::new(Mutex::new(job_rx));
+
+        for i in 0..num_threads {
+            let job_rx = job_rx.clone();
+            let result_tx = result_tx.clone();
+            let config = config.clone();
+            thread::Builder::new()
+                .name(format!("block-prevalidator-{}", i))
+                .spawn(move || loop {
+                    let next_job = job_rx
+                        .lock()
+                        .expect("FATAL: block prevalidator job lock is poisoned")
+                        .recv();
+                    let (block_id, block) = match next_job {
+                        Ok(job) => job,
+                        Err(_) => {
+                            // prevalidator was dropped
+                            return;
+                        }
+                    };
+                    let prevalidated_txids = prevalidate_block_txs(&config, &block);
+                    if result_tx.send((block_id, prevalidated_txids)).is_err() {
+                        return;
+                    }
+                })
+                .expect("FATAL: failed to start block prevalidator thread");
+        }
+
+        BlockPrevalidator {
+            job_tx,
+            result_rx,
+            in_flight: HashSet::new(),
+            prevalidated: HashMap::new(),
+            lookahead: num_threads * PREVALIDATION_LOOKAHEAD_PER_THREAD,
+        }
+    }
+
+    /// Store the results of any finished jobs.
+    fn collect(&mut self) {
+        while let Ok((block_id, prevalidated_txids)) = self.result_rx.try_recv() {
+            if self.in_flight.remove(&block_id) {
+                self.prevalidated.insert(block_id, prevalidated_txids);
+            }
+        }
+    }
+
+    /// Is this block already in flight or prevalidated?
+    fn has_block(&self, block_id: &StacksBlockId) -> bool {
+        self.in_flight.contains(block_id) || self.prevalidated.contains_key(block_id)
+    }
+
+    /// Hand a block to the workers
+    fn submit(&mut self, block_id: StacksBlockId, block: StacksBlock) {
+        if self.job_tx.send((block_id.clone(), block)).is_ok() {
+            self.in_flight.insert(block_id);
+        }
+    }
+
+    /// Forget about blocks which are no longer queued for processing, such as orphans
+    fn retain_blocks(&mut self, queued: &HashSet<StacksBlockId>) {
+        self.in_flight.retain(|block_id| queued.contains(block_id));
+        self.prevalidated
+            .retain(|block_id, _| queued.contains(block_id));
+    }
+
+    /// Get the txids of the block's transactions which passed their prechecks, and forget the
+    /// block.  If the block is still in flight, this waits for it.  Returns None if the block
+    /// was never submitted.
+    pub fn take_prevalidated(&mut self, block_id: &StacksBlockId) -> Option<HashSet<Txid>> {
+        self.collect();
+        while self.in_flight.contains(block_id) {
+            match self.result_rx.recv() {
+                Ok((done_block_id, prevalidated_txids)) => {
+                    if self.in_flight.remove(&done_block_id) {
+                        self.prevalidated.insert(done_block_id, prevalidated_txids);
+                    }
+                }
+                Err(_) => {
+                    // workers are gone
+                    self.in_flight.clear();
+                }
+            }
+        }
+        self.prevalidated.remove(block_id)
+    }
+}
+
+impl StacksChainState {
+    /// Check the transactions of queued staging blocks on `num_threads` worker threads ahead of
+    /// processing them.  0 (the default) checks them on the calling thread as they are processed.
+    pub fn set_block_validation_threads(&mut self, num_threads: usize) {
+        self.block_prevalidator = if num_threads > 0 {
+            Some(BlockPrevalidator::new(self.config(), num_threads))
+        } else {
+            None
+        };
+    }
+
+    /// Hand the next queued staging blocks, lowest first, to the block prevalidator (if there
+    /// is one).
+    pub(crate) fn queue_blocks_for_prevalidation(&mut self) -> Result<(), Error> {
+        let lookahead = match self.block_prevalidator.as_mut() {
+            Some(prevalidator) => {
+                prevalidator.collect();
+                prevalidator.lookahead
+            }
+            None => {
+                return Ok(());
+            }
+        };
+
+        let sql = "SELECT * FROM staging_blocks WHERE processed = 0 AND orphaned = 0 ORDER BY height ASC LIMIT ?1";
+        let args: &[&dyn ToSql] = &[&u64_to_sql(lookahead as u64)?];
+        let queued: Vec<StagingBlock> = query_rows(self.db(), sql, args)?;
+
+        let mut prevalidator = match self.block_prevalidator.take() {
+            Some(prevalidator) => prevalidator,
+            None => {
+                return Ok(());
+            }
+        };
+
+        let queued_ids = queued
+            .iter()
+            .map(|block| StacksBlockId::new(&block.consensus_hash, &block.anchored_block_hash))
+            .collect();
+        prevalidator.retain_blocks(&queued_ids);
+
+        for staging_block in queued.iter() {
+            let block_id = StacksBlockId::new(
+                &staging_block.consensus_hash,
+                &staging_block.anchored_block_hash,
+            );
+            if prevalidator.has_block(&block_id) {
+                continue;
+            }
+            match StacksChainState::load_block(
+                &self.blocks_path,
+                &staging_block.consensus_hash,
+                &staging_block.anchored_block_hash,
+            ) {
+                Ok(Some(block)) => {
+                    prevalidator.submit(block_id, block);
+                }
+                Ok(None) => {
+                    // known-invalid
+                }
+                Err(e) => {
+                    debug!(
+                        "Failed to load staging block {} for prevalidation: {:?}",
+                        &block_id, &e
+                    );
+                }
+            }
+        }
+
+        self.block_prevalidator = Some(prevalidator);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::chainstate::stacks::db::test::instantiate_chainstate;
+    use crate::chainstate::stacks::*;
+    use stacks_common::consts::CHAIN_ID_TESTNET;
+    use stacks_common::util::hash::Hash160;
+
+    fn make_coinbase(privk: &StacksPrivateKey, nonce: u64) -> StacksTransaction {
+        let mut tx = StacksTransaction::new(
+            TransactionVersion::Testnet,
+            TransactionAuth::from_p2pkh(privk).unwrap(),
+            TransactionPayload::Coinbase(CoinbasePayload([nonce as u8; 32])),
+        );
+        tx.chain_id = CHAIN_ID_TESTNET;
+        tx.set_origin_nonce(nonce);
+
+        let mut signer = StacksTransactionSigner::new(&tx);
+        signer.sign_origin(privk).unwrap();
+        signer.get_tx().unwrap()
+    }
+
+    fn make_block(txs: Vec<StacksTransaction>) -> StacksBlock {
+        let mut block = StacksBlock::genesis_block();
+        block.txs = txs;
+        block
+    }
+
+    #[test]
+    fn test_prevalidate_block_txs() {
+        let config = DBConfig {
+            version: "1".to_string(),
+            mainnet: false,
+            chain_id: CHAIN_ID_TESTNET,
+        };
+        let privk = StacksPrivateKey::new();
+        let good_tx = make_coinbase(&privk, 0);
+
+        // wrong chain ID
+        let mut wrong_chain_tx = make_coinbase(&privk, 1);
+        wrong_chain_tx.chain_id = CHAIN_ID_TESTNET + 1;
+
+        // signed by a different key than the one it claims
+        let mut bad_sig_tx = make_coinbase(&privk, 2);
+        if let TransactionAuth::Standard(TransactionSpendingCondition::Singlesig(ref mut data)) =
+            bad_sig_tx.auth
+        {
+            data.signer = Hash160([0x11; 20]);
+        } else {
+            panic!("expected a singlesig auth");
+        }
+
+        let block = make_block(vec![
+            good_tx.clone(),
+            wrong_chain_tx.clone(),
+            bad_sig_tx.clone(),
+        ]);
+        let prevalidated = prevalidate_block_txs(&config, &block);
+        assert_eq!(prevalidated, HashSet::from([good_tx.txid()]));
+    }
+
+    #[test]
+    fn test_block_prevalidator() {
+        let config = DBConfig {
+            version: "1".to_string(),
+            mainnet: false,
+            chain_id: CHAIN_ID_TESTNET,
+        };
+        let privk = StacksPrivateKey::new();
+        let mut prevalidator = BlockPrevalidator::new(config, 2);
+
+        let block_ids: Vec<_> = (0..4u8).map(|i| StacksBlockId([i; 32])).collect();
+        for (i, block_id) in block_ids.iter().enumerate() {
+            let block = make_block(vec![make_coinbase(&privk, i as u64)]);
+            prevalidator.submit(block_id.clone(), block);
+        }
+
+        // a block that's no longer queued is forgotten
+        prevalidator.retain_blocks(&block_ids[1..].iter().cloned().collect());
+        assert!(prevalidator.take_prevalidated(&block_ids[0]).is_none());
+
+        for (i, block_id) in block_ids[1..].iter().enumerate() {
+            let txid = make_coinbase(&privk, (i + 1) as u64).txid();
+            assert_eq!(
+                prevalidator.take_prevalidated(block_id),
+                Some(HashSet::from([txid]))
+            );
+            assert!(!prevalidator.has_block(block_id));
+        }
+    }
+
+    #[test]
+    fn test_queue_blocks_for_prevalidation() {
+        let mut chainstate =
+            instantiate_chainstate(false, 0x80000000, "test_queue_blocks_for_prevalidation");
+        chainstate.queue_blocks_for_prevalidation().unwrap();
+        assert!(chainstate.block_prevalidator.is_none());
+
+        chainstate.set_block_validation_threads(2);
+        chainstate.queue_blocks_for_prevalidation().unwrap();
+        let prevalidator = chainstate.block_prevalidator.as_ref().unwrap();
+        assert!(prevalidator.in_flight.is_empty());
+        assert!(prevalidator.prevalidated.is_empty());
+    }
+}
@@ -0,0 +1,290 @@
+// Copyright (C) 2013-2020 Blockstack PBC, a public benefit corporation
+// Copyright (C) 2020 Stacks Open Internet Foundation
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::HashSet;
+use std::fs;
+use std::path::PathBuf;
+
+use rusqlite::types::ToSql;
+use rusqlite::{OpenFlags, Row, NO_PARAMS};
+
+use crate::chainstate::stacks::db::blocks::StagingBlock;
+use crate::chainstate::stacks::db::StacksChainState;
+use crate::chainstate::stacks::Error;
+use crate::util_lib::db::Error as db_error;
+use crate::util_lib::db::{
+    query_rows, sqlite_open, tx_begin_immediate, u64_to_sql, FromColumn, FromRow,
+};
+use stacks_common::types::chainstate::StacksBlockId;
+use stacks_common::util::get_epoch_time_ms;
+
+/// What a call to `StacksChainState::prune` removed.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct PruneStats {
+    /// blocks below this height were considered for pruning
+    pub cutoff_height: u64,
+    /// number of anchored blocks whose bytes were dropped
+    pub blocks_pruned: u64,
+    /// number of bytes of anchored block data dropped
+    pub block_bytes_pruned: u64,
+    /// number of microblocks whose bytes were dropped
+    pub microblocks_pruned: u64,
+    /// number of non-canonical MARF tries dropped (counted once per MARF)
+    pub tries_pruned: u64,
+}
+
+/// Just enough of a block header to find the blocks that can never be built upon again.
+#[derive(Clone)]
+struct PrunableHeader {
+    index_block_hash: StacksBlockId,
+    parent_block_id: StacksBlockId,
+    block_height: u64,
+}
+
+impl FromRow<PrunableHeader> for PrunableHeader {
+    fn from_row<'a>(row: &'a Row) -> Result<PrunableHeader, db_error> {
+        let index_block_hash = StacksBlockId::from_column(row, "index_block_hash")?;
+        let parent_block_id = StacksBlockId::from_column(row, "parent_block_id")?;
+        let block_height = u64::from_column(row, "block_height")?;
+        Ok(PrunableHeader {
+            index_block_hash,
+            parent_block_id,
+            block_height,
+        })
+    }
+}
+
+/// Find the blocks below `cutoff_height` which are not an ancestor of any block at or above
+/// it.  No chain tip that a node could still build on descends from these blocks.
+/// `headers` must be ordered by descending height.
+fn find_orphaned_blocks(headers: Vec<PrunableHeader>, cutoff_height: u64) -> Vec<StacksBlockId> {
+    // every child is visited before its parent, so a block is live iff a live child put its
+    // id in here
+    let mut live_parents = HashSet::new();
+    let mut orphaned = vec![];
+    for header in headers.into_iter() {
+        if header.block_height >= cutoff_height || live_parents.remove(&header.index_block_hash) {
+            live_parents.insert(header.parent_block_id);
+        } else {
+            orphaned.push(header.index_block_hash);
+        }
+    }
+    orphaned
+}
+
+impl StacksChainState {
+    /// Drop chainstate data for blocks more than `horizon` blocks below the highest processed
+    /// block in the chainstate at `path_str`:
+    /// * the bytes of processed anchored blocks are truncated away, exactly as an invalid
+    /// block's would be.  The node keeps treating them as stored, so it won't re-download them,
+    /// but it will no longer serve them to peers.
+    /// * the bytes of processed and orphaned microblocks confirmed by those blocks are deleted.
+    /// * the MARF tries of blocks which are not an ancestor of any block within the horizon are
+    /// deleted from both the headers index and the Clarity MARF.  Tries stored in an external
+    /// `.blobs` file are unlinked, but the file itself is not compacted.
+    ///
+    /// Block headers are always retained, as are the tries of every ancestor of a block within
+    /// the horizon, so state queries against recent tips keep working.  So is the withdrawal tree
+    /// archive, so withdrawals of any age can still be proven.
+    ///
+    /// This opens the chainstate's databases directly, so it must not be called while a
+    /// `StacksChainState` instance is open on the same path.
+    pub fn prune(path_str: &str, horizon: u64) -> Result<PruneStats, Error> {
+        let path = PathBuf::from(path_str);
+        let blocks_path = StacksChainState::blocks_path(path.clone());
+        let blocks_dir = blocks_path
+            .to_str()
+            .ok_or_else(|| Error::DBError(db_error::ParseError))?
+            .to_string();
+        let index_path = StacksChainState::header_index_root_path(path.clone());
+        let clarity_marf_path = StacksChainState::vm_state_index_marf_path(path);
+
+        let mut stats = PruneStats::default();
+        let mut index_conn = sqlite_open(&index_path, OpenFlags::SQLITE_OPEN_READ_WRITE, true)?;
+
+        let max_height: Option<i64> = index_conn.query_row(
+            "SELECT MAX(block_height) FROM block_headers",
+            NO_PARAMS,
+            |row| row.get(0),
+        )?;
+        stats.cutoff_height = (max_height.unwrap_or(0) as u64).saturating_sub(horizon);
+        if stats.cutoff_height == 0 {
+            debug!("Nothing to prune: chainstate is within the pruning horizon";
+                   "max_height" => max_height.unwrap_or(0),
+                   "horizon" => horizon);
+            return Ok(stats);
+        }
+
+        let cutoff_height = u64_to_sql(stats.cutoff_height)?;
+        let ts_start = get_epoch_time_ms();
+
+        // anchored block bytes
+        let blocks: Vec<StagingBlock> = query_rows(
+            &index_conn,
+            "SELECT * FROM staging_blocks WHERE processed = 1 AND orphaned = 0 AND height < ?1",
+            &[&cutoff_height as &dyn ToSql],
+        )?;
+        for block in blocks.iter() {
+            let block_path = StacksChainState::get_block_path(
+                &blocks_dir,
+                &block.consensus_hash,
+                &block.anchored_block_hash,
+            )?;
+            let sz = match StacksChainState::get_file_size(&block_path) {
+                Ok(sz) => sz,
+                Err(Error::DBError(db_error::NotFoundError)) => 0,
+                Err(e) => return Err(e),
+            };
+            if sz == 0 {
+                // already pruned, or never stored
+                continue;
+            }
+            fs::OpenOptions::new()
+                .read(false)
+                .write(true)
+                .truncate(true)
+                .open(&block_path)
+                .map_err(|e| Error::DBError(db_error::IOError(e)))?;
+            stats.blocks_pruned += 1;
+            stats.block_bytes_pruned += sz;
+        }
+
+        let headers: Vec<PrunableHeader> = query_rows(
+            &index_conn,
+            "SELECT index_block_hash, parent_block_id, block_height FROM block_headers ORDER BY block_height DESC",
+            NO_PARAMS,
+        )?;
+        let orphaned = find_orphaned_blocks(headers, stats.cutoff_height);
+
+        {
+            let tx = tx_begin_immediate(&mut index_conn)?;
+
+            // microblock bytes
+            stats.microblocks_pruned = tx.execute(
+                "DELETE FROM staging_microblocks_data WHERE block_hash IN (
+                    SELECT staging_microblocks.microblock_hash FROM staging_microblocks
+                    JOIN staging_blocks ON staging_microblocks.index_block_hash = staging_blocks.index_block_hash
+                    WHERE staging_blocks.height < ?1
+                    AND (staging_microblocks.processed = 1 OR staging_microblocks.orphaned = 1))",
+                &[&cutoff_height as &dyn ToSql],
+            )? as u64;
+
+            // headers index tries
+            for block_id in orphaned.iter() {
+                stats.tries_pruned += tx.execute(
+                    "DELETE FROM marf_data WHERE block_hash = ?1",
+                    &[block_id as &dyn ToSql],
+                )? as u64;
+            }
+            tx.commit()?;
+        }
+
+        // Clarity MARF tries
+        let mut clarity_conn =
+            sqlite_open(&clarity_marf_path, OpenFlags::SQLITE_OPEN_READ_WRITE, true)?;
+        {
+            let tx = tx_begin_immediate(&mut clarity_conn)?;
+            for block_id in orphaned.iter() {
+                stats.tries_pruned += tx.execute(
+                    "DELETE FROM marf_data WHERE block_hash = ?1",
+                    &[block_id as &dyn ToSql],
+                )? as u64;
+            }
+            tx.commit()?;
+        }
+
+        info!("Pruned chainstate";
+              "cutoff_height" => stats.cutoff_height,
+              "blocks_pruned" => stats.blocks_pruned,
+              "block_bytes_pruned" => stats.block_bytes_pruned,
+              "microblocks_pruned" => stats.microblocks_pruned,
+              "tries_pruned" => stats.tries_pruned,
+              "prune_time_ms" => get_epoch_time_ms().saturating_sub(ts_start));
+
+        Ok(stats)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::chainstate::stacks::db::test::instantiate_chainstate;
+
+    fn header(id: u8, parent: u8, block_height: u64) -> PrunableHeader {
+        PrunableHeader {
+            index_block_hash: StacksBlockId([id; 32]),
+            parent_block_id: StacksBlockId([parent; 32]),
+            block_height,
+        }
+    }
+
+    #[test]
+    fn test_find_orphaned_blocks() {
+        // 1 <- 2 <- 3 <- 4 <- 5
+        //   |    |    \- 9
+        //   |    \- 6 <- 7
+        //   \- 8
+        let mut headers = vec![
+            header(1, 0, 1),
+            header(2, 1, 2),
+            header(3, 2, 3),
+            header(4, 3, 4),
+            header(5, 4, 5),
+            header(6, 2, 3),
+            header(7, 6, 4),
+            header(8, 1, 2),
+            header(9, 3, 4),
+        ];
+        headers.sort_by(|a, b| b.block_height.cmp(&a.block_height));
+
+        // nothing is below the cutoff
+        assert!(find_orphaned_blocks(headers.clone(), 1).is_empty());
+
+        // every fork but 8 has a block at height 4
+        let orphaned: HashSet<_> = find_orphaned_blocks(headers.clone(), 4)
+            .into_iter()
+            .collect();
+        assert_eq!(orphaned, HashSet::from([StacksBlockId([8; 32])]));
+
+        // only the main chain reaches height 5
+        let orphaned: HashSet<_> = find_orphaned_blocks(headers, 5).into_iter().collect();
+        assert_eq!(
+            orphaned,
+            HashSet::from([
+                StacksBlockId([6; 32]),
+                StacksBlockId([7; 32]),
+                StacksBlockId([8; 32]),
+                StacksBlockId([9; 32]),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_prune_within_horizon() {
+        let chainstate = instantiate_chainstate(false, 0x80000000, "test_prune_within_horizon");
+        let path = chainstate.root_path.clone();
+        drop(chainstate);
+
+        let stats = StacksChainState::prune(&path, 100).unwrap();
+        assert_eq!(
+            stats,
+            PruneStats {
+                cutoff_height: 0,
+                ..PruneStats::default()
+            }
+        );
+    }
+}
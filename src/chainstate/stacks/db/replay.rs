@@ -0,0 +1,269 @@
+// Copyright (C) 2013-2020 Blockstack PBC, a public benefit corporation
+// Copyright (C) 2020 Stacks Open Internet Foundation
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use rusqlite::types::ToSql;
+
+use crate::chainstate::burn::db::sortdb::SortitionDB;
+use crate::chainstate::stacks::db::blocks::{DummyEventDispatcher, StagingBlock};
+use crate::chainstate::stacks::db::StacksChainState;
+use crate::chainstate::stacks::events::StacksTransactionReceipt;
+use crate::chainstate::stacks::{Error, StacksBlockHeader};
+use crate::core::{
+    EMPTY_MICROBLOCK_PARENT_HASH, FIRST_BURNCHAIN_CONSENSUS_HASH, FIRST_STACKS_BLOCK_HASH,
+};
+use crate::util_lib::db::{query_rows, u64_to_sql, DBConn};
+use clarity::vm::costs::ExecutionCost;
+use stacks_common::types::chainstate::StacksBlockId;
+use stacks_common::util::get_epoch_time_ms;
+
+/// The outcome of re-executing one block with `StacksChainState::replay_blocks`
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReplayedBlock {
+    pub index_block_hash: StacksBlockId,
+    pub block_height: u64,
+    /// number of transactions in the block and the parent microblocks it confirmed
+    pub num_txs: usize,
+    pub anchored_block_cost: ExecutionCost,
+    pub parent_microblocks_cost: ExecutionCost,
+    /// time taken to store and process the block
+    pub wall_time_ms: u128,
+    /// receipts of the block's L1 operations, the parent microblock transactions it confirmed,
+    /// and its anchored transactions, in the order they were processed
+    pub tx_receipts: Vec<StacksTransactionReceipt>,
+}
+
+impl StacksChainState {
+    /// Get the staging rows of every processed, valid block at or below `until_height`, in
+    /// height order, so that each block comes after its parent.  This includes blocks on
+    /// non-canonical forks.
+    pub fn get_replayable_blocks(
+        blocks_conn: &DBConn,
+        until_height: Option<u64>,
+    ) -> Result<Vec<StagingBlock>, Error> {
+        let sql = "SELECT * FROM staging_blocks WHERE processed = 1 AND orphaned = 0 AND height <= ?1 ORDER BY height ASC";
+        let args: &[&dyn ToSql] = &[&u64_to_sql(until_height.unwrap_or(i64::MAX as u64))?];
+        query_rows::<StagingBlock, _>(blocks_conn, sql, args).map_err(Error::DBError)
+    }
+
+    /// Re-execute `source`'s processed blocks, in order, on top of this chainstate, which must
+    /// have just been booted with the same boot data as `source`.  Each block is stored and
+    /// processed just as a downloaded block would be, so processing fails if the recomputed
+    /// state root does not match the one committed to by the block's header; the resulting
+    /// header is also checked against the one `source` stored.  `on_block` is called after each
+    /// block.  Returns the number of blocks replayed.
+    ///
+    /// The sortition DB is only read: the changes block processing makes to it are rolled back.
+    pub fn replay_blocks<F>(
+        &mut self,
+        source: &StacksChainState,
+        sortdb: &mut SortitionDB,
+        until_height: Option<u64>,
+        mut on_block: F,
+    ) -> Result<u64, Error>
+    where
+        F: FnMut(&ReplayedBlock),
+    {
+        let genesis_block_id = StacksBlockHeader::make_index_block_hash(
+            &FIRST_BURNCHAIN_CONSENSUS_HASH,
+            &FIRST_STACKS_BLOCK_HASH,
+        );
+        let source_genesis = StacksChainState::get_stacks_block_header_info_by_index_block_hash(
+            source.db(),
+            &genesis_block_id,
+        )?
+        .ok_or(Error::NoSuchBlockError)?;
+        let genesis = StacksChainState::get_stacks_block_header_info_by_index_block_hash(
+            self.db(),
+            &genesis_block_id,
+        )?
+        .ok_or(Error::NoSuchBlockError)?;
+        if genesis.index_root != source_genesis.index_root {
+            return Err(Error::InvalidStacksBlock(format!(
+                "Genesis state root {} does not match the source chainstate's {}; was it booted with the same configuration?",
+                &genesis.index_root, &source_genesis.index_root
+            )));
+        }
+
+        let staging_blocks = StacksChainState::get_replayable_blocks(source.db(), until_height)?;
+        let mut num_replayed = 0;
+        for staging_block in staging_blocks.iter() {
+            let replayed = self.replay_block(source, sortdb, staging_block)?;
+            on_block(&replayed);
+            num_replayed += 1;
+        }
+        Ok(num_replayed)
+    }
+
+    /// Store and process one of `source`'s blocks, and the parent microblocks it confirmed.
+    fn replay_block(
+        &mut self,
+        source: &StacksChainState,
+        sortdb: &mut SortitionDB,
+        staging_block: &StagingBlock,
+    ) -> Result<ReplayedBlock, Error> {
+        let index_block_hash = StacksBlockHeader::make_index_block_hash(
+            &staging_block.consensus_hash,
+            &staging_block.anchored_block_hash,
+        );
+        let block = match StacksChainState::load_block(
+            &source.blocks_path,
+            &staging_block.consensus_hash,
+            &staging_block.anchored_block_hash,
+        ) {
+            Ok(Some(block)) => block,
+            Ok(None) | Err(_) => {
+                return Err(Error::InvalidStacksBlock(format!(
+                    "Block {} has no data in the source chainstate; was it pruned?",
+                    &index_block_hash
+                )));
+            }
+        };
+
+        let start = get_epoch_time_ms();
+
+        if staging_block.parent_microblock_hash != EMPTY_MICROBLOCK_PARENT_HASH {
+            let microblocks = StacksChainState::load_processed_microblock_stream_fork(
+                source.db(),
+                &staging_block.parent_consensus_hash,
+                &staging_block.parent_anchored_block_hash,
+                &staging_block.parent_microblock_hash,
+            )?
+            .ok_or_else(|| {
+                Error::InvalidStacksBlock(format!(
+                    "Parent microblocks of block {} are missing from the source chainstate",
+                    &index_block_hash
+                ))
+            })?;
+            for microblock in microblocks.iter() {
+                self.preprocess_streamed_microblock(
+                    &staging_block.parent_consensus_hash,
+                    &staging_block.parent_anchored_block_hash,
+                    microblock,
+                )?;
+            }
+        }
+
+        self.preprocess_anchored_block(
+            &sortdb.index_conn(),
+            &staging_block.consensus_hash,
+            &block,
+            &staging_block.parent_consensus_hash,
+            0,
+        )?;
+
+        let sortition_tip = SortitionDB::get_canonical_burn_chain_tip(sortdb.conn())?.sortition_id;
+        let receipt = {
+            let mut sort_tx = sortdb.tx_handle_begin(&sortition_tip)?;
+            let null_event_dispatcher: Option<&DummyEventDispatcher> = None;
+            let (receipt_opt, _) =
+                self.process_next_staging_block(&mut sort_tx, null_event_dispatcher)?;
+            // dropping `sort_tx` rolls it back
+            receipt_opt
+        }
+        .ok_or_else(|| {
+            Error::InvalidStacksBlock(format!(
+                "Block {} could not be processed",
+                &index_block_hash
+            ))
+        })?;
+
+        let wall_time_ms = get_epoch_time_ms().saturating_sub(start);
+
+        let processed_block_id = StacksBlockHeader::make_index_block_hash(
+            &receipt.header.consensus_hash,
+            &receipt.header.anchored_header.block_hash(),
+        );
+        if processed_block_id != index_block_hash {
+            return Err(Error::InvalidStacksBlock(format!(
+                "Processed block {} instead of {}",
+                &processed_block_id, &index_block_hash
+            )));
+        }
+
+        let source_header = StacksChainState::get_stacks_block_header_info_by_index_block_hash(
+            source.db(),
+            &index_block_hash,
+        )?
+        .ok_or(Error::NoSuchBlockError)?;
+        if receipt.header.index_root != source_header.index_root
+            || receipt.header.withdrawal_tree.root() != source_header.withdrawal_tree.root()
+        {
+            return Err(Error::InvalidStacksBlock(format!(
+                "Block {} at height {} replayed to state root {} and withdrawal root {}, but the source chainstate has {} and {}",
+                &index_block_hash,
+                staging_block.height,
+                &receipt.header.index_root,
+                &receipt.header.withdrawal_tree.root(),
+                &source_header.index_root,
+                &source_header.withdrawal_tree.root()
+            )));
+        }
+
+        Ok(ReplayedBlock {
+            index_block_hash,
+            block_height: staging_block.height,
+            num_txs: receipt.tx_receipts.len(),
+            anchored_block_cost: receipt.anchored_block_cost,
+            parent_microblocks_cost: receipt.parent_microblocks_cost,
+            wall_time_ms,
+            tx_receipts: receipt.tx_receipts,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::chainstate::stacks::db::test::{
+        instantiate_chainstate, instantiate_chainstate_with_balances,
+    };
+    use crate::chainstate::stacks::*;
+    use stacks_common::types::chainstate::StacksAddress;
+
+    #[test]
+    fn test_replay_genesis() {
+        let source = instantiate_chainstate(false, 0x80000000, "test_replay_genesis_source");
+        let mut dest = instantiate_chainstate(false, 0x80000000, "test_replay_genesis_dest");
+        let mut sortdb = SortitionDB::connect_test(0).unwrap();
+
+        // nothing to replay past the boot block, but the genesis states must agree
+        let mut replayed = vec![];
+        let num_replayed = dest
+            .replay_blocks(&source, &mut sortdb, None, |block| {
+                replayed.push(block.clone())
+            })
+            .unwrap();
+        assert_eq!(num_replayed, 0);
+        assert!(replayed.is_empty());
+
+        // a chainstate booted with different balances has a different genesis state
+        let mut other = instantiate_chainstate_with_balances(
+            false,
+            0x80000000,
+            "test_replay_genesis_other",
+            vec![(
+                StacksAddress::from_string("ST2DS4MSWSGJ3W9FBC6BVT0Y92S345HY8N3T6AV7R").unwrap(),
+                1000,
+            )],
+        );
+        match other.replay_blocks(&source, &mut sortdb, None, |_| {}) {
+            Err(Error::InvalidStacksBlock(msg)) => {
+                assert!(msg.contains("Genesis state root"));
+            }
+            res => panic!("Expected a genesis mismatch, got {:?}", res),
+        }
+    }
+}
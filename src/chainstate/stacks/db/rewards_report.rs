@@ -0,0 +1,232 @@
+// Copyright (C) 2013-2020 Blockstack PBC, a public benefit corporation
+// Copyright (C) 2020 Stacks Open Internet Foundation
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! What each miner of the canonical L2 chain earned over a range of heights, for operators who
+//! account for their mining income.
+//!
+//! A block's miner earns its coinbase, the fees of its anchored transactions, and 3/5 of the
+//! fees of the microblocks it confirms; the miner of its parent earns the other 2/5 of those.
+//! Rewards are counted at the height of the block which earned them, although they are only
+//! paid once that block matures.  Where the node kept the L1 fees of the block-commits it sent,
+//! these are reported too, so that a miner's net income can be worked out.
+
+use std::collections::BTreeMap;
+
+use crate::burnchains::Txid;
+use crate::chainstate::burn::db::sortdb::SortitionDB;
+use crate::chainstate::stacks::db::coinbase_schedule::CoinbaseRecipient;
+use crate::chainstate::stacks::db::StacksChainState;
+use crate::chainstate::stacks::{Error, StacksBlockHeader};
+use stacks_common::types::chainstate::{StacksAddress, StacksBlockId};
+
+/// The most L2 heights whose rewards can be asked for at once over RPC
+pub const MAX_REWARDS_REPORT_BLOCKS: u64 = 1_000;
+
+/// The L1 fees of the block-commits a miner sent.  Implemented by the node, which records each
+/// commit it broadcasts.
+pub trait CommitFeeLedger {
+    /// The fee, in uSTX, paid on the L1 for the block-commit `txid`, if it is a commit this node
+    /// sent
+    fn get_commit_fee(&self, txid: &Txid) -> Option<u64>;
+}
+
+/// What one miner earned over the range of a rewards report
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct MinerRewards {
+    pub address: String,
+    pub blocks_mined: u64,
+    pub coinbase: u128,
+    pub tx_fees_anchored: u128,
+    /// the miner's shares of the fees of the microblocks it confirmed and of those it produced
+    pub tx_fees_streamed: u128,
+    pub total: u128,
+    /// how many of the miner's blocks' commits the L1 fee is known of
+    pub commits_with_known_fee: u64,
+    /// the L1 fees of those commits, in uSTX
+    pub commit_fees: u64,
+    /// `total` less `commit_fees`, if the L1 fee of every one of the miner's blocks' commits is
+    /// known
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub net: Option<i128>,
+}
+
+/// What each miner earned from the canonical L2 blocks in a range of heights
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RewardsReport {
+    pub canonical_stacks_tip: StacksBlockId,
+    pub from_height: u64,
+    /// the last height counted, which is lower than the one asked for if the canonical chain
+    /// doesn't reach it yet, but never lower than `from_height`
+    pub to_height: u64,
+    /// if set, every block's reward is paid to this address rather than to its miner
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub coinbase_recipient: Option<String>,
+    /// ordered by address
+    pub miners: Vec<MinerRewards>,
+}
+
+/// The rewards of `address` in `miners`, which are added if there aren't any yet
+fn miner_rewards<'a>(
+    miners: &'a mut BTreeMap<String, MinerRewards>,
+    address: &StacksAddress,
+) -> &'a mut MinerRewards {
+    miners
+        .entry(address.to_string())
+        .or_insert_with(|| MinerRewards {
+            address: address.to_string(),
+            ..MinerRewards::default()
+        })
+}
+
+impl StacksChainState {
+    /// Report what each miner earned from the canonical L2 blocks at heights `from_height` up to
+    /// and including `to_height`, and, if `commit_fees` is given, what their L1 block-commits
+    /// cost.  The boot block, which no miner mined, is left out.
+    pub fn get_rewards_report(
+        &self,
+        sortdb: &SortitionDB,
+        from_height: u64,
+        to_height: u64,
+        commit_fees: Option<&dyn CommitFeeLedger>,
+    ) -> Result<RewardsReport, Error> {
+        if from_height > to_height {
+            return Err(Error::InvalidStacksBlock(format!(
+                "rewards report heights must not decrease, but {} > {}",
+                from_height, to_height
+            )));
+        }
+        let (tip_consensus_hash, tip_block_hash) =
+            SortitionDB::get_canonical_stacks_chain_tip_hash(sortdb.conn())?;
+        let canonical_stacks_tip =
+            StacksBlockHeader::make_index_block_hash(&tip_consensus_hash, &tip_block_hash);
+        let canonical_stacks_tip_height =
+            match StacksChainState::get_stacks_block_header_info_by_index_block_hash(
+                self.db(),
+                &canonical_stacks_tip,
+            )? {
+                Some(header) => header.stacks_block_height,
+                None => 0,
+            };
+        let to_height = to_height.min(canonical_stacks_tip_height).max(from_height);
+        let coinbase_recipient = match StacksChainState::get_coinbase_schedule(self.db())?.recipient
+        {
+            CoinbaseRecipient::Miner => None,
+            CoinbaseRecipient::Fixed(addr) => Some(addr.to_string()),
+        };
+
+        let mut miners: BTreeMap<String, MinerRewards> = BTreeMap::new();
+        let index_conn = self.index_conn()?;
+        for height in from_height.max(1)..=to_height {
+            let block_id =
+                match index_conn.get_ancestor_block_hash(height, &canonical_stacks_tip)? {
+                    Some(block_id) => block_id,
+                    None => break,
+                };
+            let header = StacksChainState::get_stacks_block_header_info_by_index_block_hash(
+                self.db(),
+                &block_id,
+            )?
+            .ok_or(Error::NoSuchBlockError)?;
+
+            let payment = match StacksChainState::get_miner_info(
+                self.db(),
+                &header.consensus_hash,
+                &header.anchored_header.block_hash(),
+            )? {
+                Some(payment) => payment,
+                None => {
+                    warn!(
+                        "No miner payment for L2 block {} at height {}",
+                        &block_id, height
+                    );
+                    continue;
+                }
+            };
+
+            if payment.tx_fees_streamed > 0 {
+                if let Some(parent) = StacksChainState::get_miner_info(
+                    self.db(),
+                    &payment.parent_consensus_hash,
+                    &payment.parent_block_hash,
+                )? {
+                    miner_rewards(&mut miners, &parent.address).tx_fees_streamed +=
+                        payment.streamed_tx_fees_produced();
+                }
+            }
+
+            let rewards = miner_rewards(&mut miners, &payment.address);
+            rewards.blocks_mined += 1;
+            rewards.coinbase += payment.coinbase;
+            rewards.tx_fees_anchored += payment.tx_fees_anchored;
+            rewards.tx_fees_streamed += payment.streamed_tx_fees_confirmed();
+
+            if let Some(ledger) = commit_fees {
+                let commit_fee = SortitionDB::get_block_snapshot_consensus(
+                    sortdb.conn(),
+                    &header.consensus_hash,
+                )?
+                .and_then(|snapshot| ledger.get_commit_fee(&snapshot.winning_block_txid));
+                if let Some(fee) = commit_fee {
+                    rewards.commits_with_known_fee += 1;
+                    rewards.commit_fees += fee;
+                }
+            }
+        }
+
+        let miners = miners
+            .into_values()
+            .map(|mut rewards| {
+                rewards.total =
+                    rewards.coinbase + rewards.tx_fees_anchored + rewards.tx_fees_streamed;
+                if commit_fees.is_some() && rewards.commits_with_known_fee == rewards.blocks_mined {
+                    rewards.net = Some(rewards.total as i128 - rewards.commit_fees as i128);
+                }
+                rewards
+            })
+            .collect();
+
+        Ok(RewardsReport {
+            canonical_stacks_tip,
+            from_height,
+            to_height,
+            coinbase_recipient,
+            miners,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::chainstate::stacks::db::test::instantiate_chainstate;
+
+    #[test]
+    fn test_rewards_report_genesis() {
+        let chainstate = instantiate_chainstate(false, 0x80000000, "test_rewards_report_genesis");
+        let sortdb = SortitionDB::connect_test(0).unwrap();
+
+        match chainstate.get_rewards_report(&sortdb, 2, 1, None) {
+            Err(Error::InvalidStacksBlock(_)) => {}
+            x => panic!("expected an invalid range, got {:?}", x),
+        }
+
+        // only the boot block exists, and no miner mined it
+        let report = chainstate.get_rewards_report(&sortdb, 0, 10, None).unwrap();
+        assert_eq!(report.to_height, 0);
+        assert_eq!(report.coinbase_recipient, None);
+        assert!(report.miners.is_empty());
+    }
+}
@@ -0,0 +1,447 @@
+// Copyright (C) 2013-2020 Blockstack PBC, a public benefit corporation
+// Copyright (C) 2020 Stacks Open Internet Foundation
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! The Clarity state written between two heights of the canonical L2 chain, for auditors who
+//! need to see exactly what a range of blocks changed.
+//!
+//! The MARF only stores the hashes of its keys, so the keys written in a range are found by
+//! walking the tries of the blocks in it, and are then named by hashing the keys the blocks'
+//! transactions could have written: the balances and nonces of the principals they mention,
+//! the contracts they publish, and the data vars, map entries and token balances of the
+//! contracts they call.  Map entries are only named if their key is one of the arguments a
+//! transaction passed to the contract, or a principal it mentions.  Keys that can't be named
+//! are still reported, by their hash.
+
+use std::collections::{HashMap, HashSet};
+
+use crate::chainstate::burn::db::sortdb::SortitionDB;
+use crate::chainstate::stacks::boot::{
+    STACKS_BOOT_CODE_MAINNET, SUBNET_TOKEN_NAME, SUBNET_TRANSFERS_NAME, TIME_ORACLE_NAME,
+};
+use crate::chainstate::stacks::db::{StacksChainState, StacksHeaderInfo};
+use crate::chainstate::stacks::index::marf::MarfConnection;
+use crate::chainstate::stacks::index::node::TriePath;
+use crate::chainstate::stacks::{Error, StacksBlockHeader, StacksTransaction, TransactionPayload};
+use crate::util_lib::boot::boot_code_id;
+use clarity::vm::database::clarity_store::make_contract_hash_key;
+use clarity::vm::database::{ClarityDatabase, ClaritySerializable, SqliteConnection, StoreType};
+use clarity::vm::types::{PrincipalData, QualifiedContractIdentifier};
+use clarity::vm::Value;
+use stacks_common::types::chainstate::StacksBlockId;
+
+/// What a key in a state diff holds
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StateKeyKind {
+    StxBalance,
+    Nonce,
+    Contract,
+    DataVar,
+    DataMapEntry,
+    FtBalance,
+    FtSupply,
+    /// a key which could not be named
+    Unknown,
+}
+
+/// A named Clarity key
+#[derive(Debug, Clone, PartialEq)]
+struct StateKey {
+    kind: StateKeyKind,
+    key: String,
+    contract: Option<QualifiedContractIdentifier>,
+    principal: Option<PrincipalData>,
+}
+
+/// A key added or changed between the two heights of a state diff
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StateDiffEntry {
+    pub kind: StateKeyKind,
+    /// the Clarity key, if it could be named
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub key: Option<String>,
+    /// the MARF path of the key: the hash of the Clarity key
+    pub key_hash: String,
+    /// the contract whose state, or which, the key holds
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub contract: Option<String>,
+    /// the principal whose balance or nonce the key holds
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub principal: Option<String>,
+    /// the stored value at the lower height, if the key was set then
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub before: Option<String>,
+    /// the stored value at the higher height
+    pub after: String,
+}
+
+/// The keys added and changed between two heights of the canonical chain
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StateDiff {
+    pub from_height: u64,
+    pub from_block: StacksBlockId,
+    pub to_height: u64,
+    pub to_block: StacksBlockId,
+    pub added: Vec<StateDiffEntry>,
+    pub changed: Vec<StateDiffEntry>,
+}
+
+/// The principals, contracts and Clarity values mentioned by a range of blocks' transactions
+#[derive(Default)]
+struct KeyCandidates {
+    principals: HashSet<PrincipalData>,
+    contracts: HashSet<QualifiedContractIdentifier>,
+    /// the arguments passed to each contract
+    contract_args: HashMap<QualifiedContractIdentifier, Vec<Value>>,
+}
+
+impl KeyCandidates {
+    fn add_tx(&mut self, tx: &StacksTransaction) {
+        self.principals
+            .insert(PrincipalData::from(tx.origin_address()));
+        if let Some(sponsor) = tx.sponsor_address() {
+            self.principals.insert(PrincipalData::from(sponsor));
+        }
+        match tx.payload {
+            TransactionPayload::TokenTransfer(ref recipient, ..) => {
+                self.principals.insert(recipient.clone());
+            }
+            TransactionPayload::ContractCall(ref call) => {
+                let contract_id = call.to_clarity_contract_id();
+                for arg in call.function_args.iter() {
+                    if let Value::Principal(ref principal) = arg {
+                        self.principals.insert(principal.clone());
+                    }
+                }
+                self.contract_args
+                    .entry(contract_id.clone())
+                    .or_insert_with(Vec::new)
+                    .extend(call.function_args.iter().cloned());
+                self.principals
+                    .insert(PrincipalData::Contract(contract_id.clone()));
+                self.contracts.insert(contract_id);
+            }
+            TransactionPayload::SmartContract(ref contract, _) => {
+                let contract_id = QualifiedContractIdentifier::new(
+                    tx.origin_address().into(),
+                    contract.name.clone(),
+                );
+                self.principals
+                    .insert(PrincipalData::Contract(contract_id.clone()));
+                self.contracts.insert(contract_id);
+            }
+            _ => {}
+        }
+    }
+}
+
+impl StacksChainState {
+    /// Get the Clarity keys added or changed by the canonical blocks above `from_height`, up to
+    /// and including `to_height`, with their values at both heights.  If `contract_prefix` is
+    /// given, only keys of contracts whose identifier starts with it are reported.
+    pub fn get_state_diff(
+        &mut self,
+        sortdb: &SortitionDB,
+        from_height: u64,
+        to_height: u64,
+        contract_prefix: Option<&str>,
+    ) -> Result<StateDiff, Error> {
+        if from_height >= to_height {
+            return Err(Error::InvalidStacksBlock(format!(
+                "state diff heights must increase, but {} >= {}",
+                from_height, to_height
+            )));
+        }
+        let (tip_consensus_hash, tip_block_hash) =
+            SortitionDB::get_canonical_stacks_chain_tip_hash(sortdb.conn())?;
+        let canonical_tip =
+            StacksBlockHeader::make_index_block_hash(&tip_consensus_hash, &tip_block_hash);
+
+        // the blocks from `from_height` to `to_height`, and the candidate keys their
+        // transactions could have written
+        let mut block_ids = vec![];
+        let mut candidates = KeyCandidates::default();
+        let mut parent_header: Option<StacksHeaderInfo> = None;
+        for height in from_height..=to_height {
+            let block_id = self
+                .index_conn()?
+                .get_ancestor_block_hash(height, &canonical_tip)?
+                .ok_or(Error::NoSuchBlockError)?;
+            let header = StacksChainState::get_stacks_block_header_info_by_index_block_hash(
+                self.db(),
+                &block_id,
+            )?
+            .ok_or(Error::NoSuchBlockError)?;
+            if height > from_height {
+                if let Some(block) = StacksChainState::load_block(
+                    &self.blocks_path,
+                    &header.consensus_hash,
+                    &header.anchored_header.block_hash(),
+                )? {
+                    match parent_header {
+                        Some(ref parent) if block.has_microblock_parent() => {
+                            let microblocks = StacksChainState::load_microblock_stream_fork(
+                                self.db(),
+                                &parent.consensus_hash,
+                                &parent.anchored_header.block_hash(),
+                                &block.header.parent_microblock,
+                            )?
+                            .unwrap_or_default();
+                            for microblock in microblocks.iter() {
+                                microblock.txs.iter().for_each(|tx| candidates.add_tx(tx));
+                            }
+                        }
+                        _ => {}
+                    }
+                    block.txs.iter().for_each(|tx| candidates.add_tx(tx));
+                } else {
+                    warn!(
+                        "Block data of {} is unavailable; keys it wrote may not be named",
+                        &block_id
+                    );
+                }
+            }
+            parent_header = Some(header);
+            block_ids.push(block_id);
+        }
+        for (name, _) in STACKS_BOOT_CODE_MAINNET.iter() {
+            candidates
+                .contracts
+                .insert(boot_code_id(name, self.mainnet));
+        }
+        // installed after genesis, if at all, so they may not exist yet
+        for name in [TIME_ORACLE_NAME, SUBNET_TRANSFERS_NAME, SUBNET_TOKEN_NAME].iter() {
+            candidates
+                .contracts
+                .insert(boot_code_id(name, self.mainnet));
+        }
+
+        let from_block = block_ids.first().cloned().ok_or(Error::NoSuchBlockError)?;
+        let to_block = block_ids.last().cloned().ok_or(Error::NoSuchBlockError)?;
+        let names = self.name_candidate_keys(sortdb, &to_block, &candidates);
+
+        let (mut added, mut changed) = self.with_clarity_marf(|marf| {
+            let mut paths = vec![];
+            let mut seen = HashSet::new();
+            for block_id in block_ids.iter().skip(1) {
+                for path in marf.get_block_leaf_paths(block_id)?.into_iter() {
+                    if seen.insert(path.clone()) {
+                        paths.push(path);
+                    }
+                }
+            }
+
+            let mut added = vec![];
+            let mut changed = vec![];
+            for path in paths.into_iter() {
+                let after_hash = match marf.get_by_path(&to_block, &path)? {
+                    Some(value) => value,
+                    None => continue,
+                };
+                let before_hash = marf.get_by_path(&from_block, &path)?;
+                if before_hash.as_ref() == Some(&after_hash) {
+                    continue;
+                }
+                // only Clarity's own keys have their values in the side store; the MARF's
+                // block height bookkeeping does not
+                let after = match SqliteConnection::get(marf.sqlite_conn(), &after_hash.to_hex()) {
+                    Some(value) => value,
+                    None => continue,
+                };
+                let before = before_hash
+                    .and_then(|hash| SqliteConnection::get(marf.sqlite_conn(), &hash.to_hex()));
+
+                let name = names.get(&path);
+                let contract = name.and_then(|name| name.contract.as_ref());
+                if let Some(prefix) = contract_prefix {
+                    match contract {
+                        Some(contract_id) if contract_id.to_string().starts_with(prefix) => {}
+                        _ => continue,
+                    }
+                }
+                let entry = StateDiffEntry {
+                    kind: name.map(|name| name.kind).unwrap_or(StateKeyKind::Unknown),
+                    key: name.map(|name| name.key.clone()),
+                    key_hash: path.to_hex(),
+                    contract: contract.map(|contract_id| contract_id.to_string()),
+                    principal: name
+                        .and_then(|name| name.principal.as_ref())
+                        .map(|principal| principal.to_string()),
+                    before,
+                    after,
+                };
+                if entry.before.is_some() {
+                    changed.push(entry);
+                } else {
+                    added.push(entry);
+                }
+            }
+            Ok::<_, Error>((added, changed))
+        })?;
+
+        let sort_entries = |entries: &mut Vec<StateDiffEntry>| {
+            entries
+                .sort_by(|a, b| (a.kind, &a.key, &a.key_hash).cmp(&(b.kind, &b.key, &b.key_hash)))
+        };
+        sort_entries(&mut added);
+        sort_entries(&mut changed);
+
+        Ok(StateDiff {
+            from_height,
+            from_block,
+            to_height,
+            to_block,
+            added,
+            changed,
+        })
+    }
+
+    /// Hash the keys the candidates could have written, so that the MARF paths found in the
+    /// diffed blocks can be named.  Contracts are inspected as of `tip`.
+    fn name_candidate_keys(
+        &mut self,
+        sortdb: &SortitionDB,
+        tip: &StacksBlockId,
+        candidates: &KeyCandidates,
+    ) -> HashMap<TriePath, StateKey> {
+        let mut keys = vec![];
+        for principal in candidates.principals.iter() {
+            let contract = match principal {
+                PrincipalData::Contract(ref contract_id) => Some(contract_id.clone()),
+                PrincipalData::Standard(_) => None,
+            };
+            keys.push(StateKey {
+                kind: StateKeyKind::StxBalance,
+                key: ClarityDatabase::make_key_for_account_balance(principal),
+                contract: contract.clone(),
+                principal: Some(principal.clone()),
+            });
+            keys.push(StateKey {
+                kind: StateKeyKind::Nonce,
+                key: ClarityDatabase::make_key_for_account_nonce(principal),
+                contract,
+                principal: Some(principal.clone()),
+            });
+        }
+
+        for contract_id in candidates.contracts.iter() {
+            keys.push(StateKey {
+                kind: StateKeyKind::Contract,
+                key: make_contract_hash_key(contract_id),
+                contract: Some(contract_id.clone()),
+                principal: None,
+            });
+            let analysis = self
+                .with_read_only_clarity_tx(&sortdb.index_conn(), tip, |conn| {
+                    conn.with_clarity_db_readonly(|db| db.load_contract_analysis(contract_id))
+                })
+                .flatten();
+            let analysis = match analysis {
+                Some(analysis) => analysis,
+                None => continue,
+            };
+
+            for var_name in analysis.persisted_variable_types.keys() {
+                keys.push(StateKey {
+                    kind: StateKeyKind::DataVar,
+                    key: ClarityDatabase::make_key_for_trip(
+                        contract_id,
+                        StoreType::Variable,
+                        var_name,
+                    ),
+                    contract: Some(contract_id.clone()),
+                    principal: None,
+                });
+            }
+
+            let mut map_keys: Vec<Value> = candidates
+                .principals
+                .iter()
+                .map(|principal| Value::Principal(principal.clone()))
+                .collect();
+            if let Some(args) = candidates.contract_args.get(contract_id) {
+                map_keys.extend(args.iter().cloned());
+            }
+            for map_name in analysis.map_types.keys() {
+                for map_key in map_keys.iter() {
+                    keys.push(StateKey {
+                        kind: StateKeyKind::DataMapEntry,
+                        key: ClarityDatabase::make_key_for_data_map_entry(
+                            contract_id,
+                            map_name,
+                            map_key,
+                        ),
+                        contract: Some(contract_id.clone()),
+                        principal: None,
+                    });
+                }
+            }
+
+            for token_name in analysis.fungible_tokens.iter() {
+                keys.push(StateKey {
+                    kind: StateKeyKind::FtSupply,
+                    key: ClarityDatabase::make_key_for_trip(
+                        contract_id,
+                        StoreType::CirculatingSupply,
+                        token_name,
+                    ),
+                    contract: Some(contract_id.clone()),
+                    principal: None,
+                });
+                for principal in candidates.principals.iter() {
+                    keys.push(StateKey {
+                        kind: StateKeyKind::FtBalance,
+                        key: ClarityDatabase::make_key_for_quad(
+                            contract_id,
+                            StoreType::FungibleToken,
+                            token_name,
+                            &principal.serialize(),
+                        ),
+                        contract: Some(contract_id.clone()),
+                        principal: Some(principal.clone()),
+                    });
+                }
+            }
+        }
+
+        keys.into_iter()
+            .map(|key| (TriePath::from_key(&key.key), key))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::chainstate::stacks::db::test::instantiate_chainstate;
+
+    #[test]
+    fn test_state_diff_heights() {
+        let mut chainstate = instantiate_chainstate(false, 0x80000000, "test_state_diff_heights");
+        let sortdb = SortitionDB::connect_test(0).unwrap();
+
+        match chainstate.get_state_diff(&sortdb, 1, 1, None) {
+            Err(Error::InvalidStacksBlock(_)) => {}
+            x => panic!("expected an invalid range, got {:?}", x),
+        }
+
+        // only the boot block exists
+        match chainstate.get_state_diff(&sortdb, 0, 1, None) {
+            Err(Error::NoSuchBlockError) => {}
+            x => panic!("expected a missing block, got {:?}", x),
+        }
+    }
+}
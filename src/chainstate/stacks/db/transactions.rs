@@ -993,7 +993,11 @@ impl StacksChainState {
     ) -> Result<(u64, StacksTransactionReceipt), Error> {
         debug!("Process transaction {} ({})", tx.txid(), tx.payload.name());
 
-        StacksChainState::process_transaction_precheck(&clarity_block.config, tx)?;
+        // the precheck only depends on the transaction itself, so it can be skipped if a block
+        // prevalidator already ran it on these exact bytes
+        if !clarity_block.prevalidated_txids.contains(&tx.txid()) {
+            StacksChainState::process_transaction_precheck(&clarity_block.config, tx)?;
+        }
 
         let mut transaction = clarity_block.connection().start_transaction_processing();
         let (origin_account, payer_account) =
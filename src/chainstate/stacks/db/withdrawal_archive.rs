@@ -0,0 +1,263 @@
+// Copyright (C) 2013-2020 Blockstack PBC, a public benefit corporation
+// Copyright (C) 2020 Stacks Open Internet Foundation
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! The withdrawal tree archive: every processed block's withdrawal Merkle tree, kept in a table
+//! of its own which pruning never touches, so that the Merkle proof of a withdrawal can be made
+//! for as long as the node runs, however old the withdrawal is.  The archive can be exported to
+//! a file and imported into another node's chainstate, so proofs survive a node migration.
+
+use std::io::{BufRead, Write};
+
+use rusqlite::types::ToSql;
+use rusqlite::{Row, NO_PARAMS};
+
+use crate::chainstate::stacks::db::StacksChainState;
+use crate::chainstate::stacks::Error;
+use crate::util_lib::db::Error as db_error;
+use crate::util_lib::db::{query_row, query_rows, u64_to_sql, DBConn, DBTx, FromColumn, FromRow};
+use stacks_common::types::chainstate::StacksBlockId;
+use stacks_common::util::hash::{MerkleTree, Sha512Trunc256Sum};
+
+/// A block's withdrawal Merkle tree, as archived.  This is also the format of each line of an
+/// exported archive.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ArchivedWithdrawalTree {
+    pub index_block_hash: StacksBlockId,
+    pub block_height: u64,
+    pub withdrawal_root: Sha512Trunc256Sum,
+    pub withdrawal_tree: MerkleTree<Sha512Trunc256Sum>,
+}
+
+/// What `StacksChainState::import_withdrawal_archive` did with the trees it read
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct WithdrawalArchiveImportSummary {
+    pub imported: u64,
+    /// trees the archive already had
+    pub already_present: u64,
+}
+
+impl FromRow<ArchivedWithdrawalTree> for ArchivedWithdrawalTree {
+    fn from_row<'a>(row: &'a Row) -> Result<ArchivedWithdrawalTree, db_error> {
+        let index_block_hash = StacksBlockId::from_column(row, "index_block_hash")?;
+        let block_height = u64::from_column(row, "block_height")?;
+        let withdrawal_root_str: String = row.get_unwrap("withdrawal_root");
+        let withdrawal_root =
+            Sha512Trunc256Sum::from_hex(&withdrawal_root_str).map_err(|_| db_error::ParseError)?;
+        let withdrawal_tree_str: String = row.get_unwrap("withdrawal_tree");
+        let withdrawal_tree =
+            serde_json::from_str(&withdrawal_tree_str).map_err(|_| db_error::ParseError)?;
+        Ok(ArchivedWithdrawalTree {
+            index_block_hash,
+            block_height,
+            withdrawal_root,
+            withdrawal_tree,
+        })
+    }
+}
+
+impl StacksChainState {
+    /// Archive the withdrawal tree of the block `index_block_hash`.  Archiving a block's tree
+    /// again replaces it.
+    pub fn archive_withdrawal_tree(
+        tx: &DBTx,
+        index_block_hash: &StacksBlockId,
+        block_height: u64,
+        withdrawal_tree: &MerkleTree<Sha512Trunc256Sum>,
+    ) -> Result<(), Error> {
+        let withdrawal_tree_str =
+            serde_json::to_string(withdrawal_tree).expect("Failed to serialize merkle tree");
+        let args: &[&dyn ToSql] = &[
+            index_block_hash,
+            &u64_to_sql(block_height)?,
+            &withdrawal_tree.root().to_hex(),
+            &withdrawal_tree_str,
+        ];
+        tx.execute(
+            "INSERT OR REPLACE INTO withdrawal_tree_archive (index_block_hash, block_height, withdrawal_root, withdrawal_tree) VALUES (?1, ?2, ?3, ?4)",
+            args,
+        )?;
+        Ok(())
+    }
+
+    /// Get the archived withdrawal tree of the block `index_block_hash`, if there is one
+    pub fn get_archived_withdrawal_tree(
+        conn: &DBConn,
+        index_block_hash: &StacksBlockId,
+    ) -> Result<Option<ArchivedWithdrawalTree>, Error> {
+        let sql = "SELECT * FROM withdrawal_tree_archive WHERE index_block_hash = ?1";
+        let args: &[&dyn ToSql] = &[index_block_hash];
+        Ok(query_row(conn, sql, args)?)
+    }
+
+    /// Get the withdrawal tree of the block `index_block_hash`, from the archive if it has it,
+    /// or else from the block's header
+    pub fn get_withdrawal_tree(
+        conn: &DBConn,
+        index_block_hash: &StacksBlockId,
+    ) -> Result<Option<MerkleTree<Sha512Trunc256Sum>>, Error> {
+        if let Some(archived) =
+            StacksChainState::get_archived_withdrawal_tree(conn, index_block_hash)?
+        {
+            return Ok(Some(archived.withdrawal_tree));
+        }
+        Ok(
+            StacksChainState::get_stacks_block_header_info_by_index_block_hash(
+                conn,
+                index_block_hash,
+            )?
+            .map(|header_info| header_info.withdrawal_tree),
+        )
+    }
+
+    /// Write every archived withdrawal tree to `fd`, one `ArchivedWithdrawalTree` per line, in
+    /// height order.  Returns the number of trees written.
+    pub fn export_withdrawal_archive<W: Write>(&self, fd: &mut W) -> Result<u64, Error> {
+        let sql =
+            "SELECT * FROM withdrawal_tree_archive ORDER BY block_height ASC, index_block_hash ASC";
+        let trees: Vec<ArchivedWithdrawalTree> = query_rows(self.db(), sql, NO_PARAMS)?;
+        for tree in trees.iter() {
+            let line = serde_json::to_string(tree).map_err(db_error::SerializationError)?;
+            writeln!(fd, "{}", line).map_err(db_error::IOError)?;
+        }
+        Ok(trees.len() as u64)
+    }
+
+    /// Read withdrawal trees written by `export_withdrawal_archive` from `fd`, and archive the
+    /// ones this chainstate does not already have.  Fails without archiving anything if `fd`
+    /// holds anything but archived trees, if a tree does not hash to its recorded root, or if a
+    /// tree's root differs from that of a block this chainstate has processed.
+    pub fn import_withdrawal_archive<R: BufRead>(
+        &mut self,
+        fd: R,
+    ) -> Result<WithdrawalArchiveImportSummary, Error> {
+        let mut trees = vec![];
+        for line in fd.lines() {
+            let line = line.map_err(db_error::IOError)?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let tree: ArchivedWithdrawalTree =
+                serde_json::from_str(&line).map_err(db_error::SerializationError)?;
+            if tree.withdrawal_tree.root() != tree.withdrawal_root {
+                return Err(Error::InvalidStacksBlock(format!(
+                    "archived withdrawal tree of {} has root {}, not {}",
+                    &tree.index_block_hash,
+                    &tree.withdrawal_tree.root(),
+                    &tree.withdrawal_root
+                )));
+            }
+            if let Some(header_info) =
+                StacksChainState::get_stacks_block_header_info_by_index_block_hash(
+                    self.db(),
+                    &tree.index_block_hash,
+                )?
+            {
+                if header_info.anchored_header.withdrawal_merkle_root != tree.withdrawal_root {
+                    return Err(Error::InvalidStacksBlock(format!(
+                        "archived withdrawal tree of {} has root {}, but the block's is {}",
+                        &tree.index_block_hash,
+                        &tree.withdrawal_root,
+                        &header_info.anchored_header.withdrawal_merkle_root
+                    )));
+                }
+            }
+            trees.push(tree);
+        }
+
+        let mut summary = WithdrawalArchiveImportSummary::default();
+        let tx = self.db_tx_begin()?;
+        for tree in trees.iter() {
+            if StacksChainState::get_archived_withdrawal_tree(&tx, &tree.index_block_hash)?
+                .is_some()
+            {
+                summary.already_present += 1;
+                continue;
+            }
+            StacksChainState::archive_withdrawal_tree(
+                &tx,
+                &tree.index_block_hash,
+                tree.block_height,
+                &tree.withdrawal_tree,
+            )?;
+            summary.imported += 1;
+        }
+        tx.commit()?;
+        Ok(summary)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::chainstate::stacks::db::test::instantiate_chainstate;
+
+    #[test]
+    fn test_withdrawal_archive_export_import() {
+        let mut chainstate =
+            instantiate_chainstate(false, 0x80000000, "test_withdrawal_archive_export");
+        let block_id = StacksBlockId([0x01; 32]);
+        let withdrawal_tree = MerkleTree::<Sha512Trunc256Sum>::new(&vec![
+            vec![0x01, 0x02],
+            vec![0x03, 0x04],
+            vec![0x05],
+        ]);
+
+        let tx = chainstate.db_tx_begin().unwrap();
+        StacksChainState::archive_withdrawal_tree(&tx, &block_id, 5, &withdrawal_tree).unwrap();
+        tx.commit().unwrap();
+
+        // the archive takes precedence over the (missing) block header
+        let archived = StacksChainState::get_archived_withdrawal_tree(chainstate.db(), &block_id)
+            .unwrap()
+            .unwrap();
+        assert_eq!(archived.block_height, 5);
+        assert_eq!(archived.withdrawal_root, withdrawal_tree.root());
+        assert_eq!(
+            StacksChainState::get_withdrawal_tree(chainstate.db(), &block_id).unwrap(),
+            Some(withdrawal_tree.clone())
+        );
+
+        let mut exported = vec![];
+        let num_exported = chainstate.export_withdrawal_archive(&mut exported).unwrap();
+
+        let mut other = instantiate_chainstate(false, 0x80000000, "test_withdrawal_archive_import");
+        assert!(
+            StacksChainState::get_archived_withdrawal_tree(other.db(), &block_id)
+                .unwrap()
+                .is_none()
+        );
+        // both chainstates archived the same boot block's tree
+        let summary = other.import_withdrawal_archive(&exported[..]).unwrap();
+        assert_eq!(summary.imported, 1);
+        assert_eq!(summary.imported + summary.already_present, num_exported);
+
+        let imported = StacksChainState::get_archived_withdrawal_tree(other.db(), &block_id)
+            .unwrap()
+            .unwrap();
+        assert_eq!(imported, archived);
+        assert!(imported.withdrawal_tree.path(&[0x03, 0x04]).is_some());
+
+        // importing again changes nothing
+        let summary = other.import_withdrawal_archive(&exported[..]).unwrap();
+        assert_eq!(summary.imported, 0);
+
+        // a tree which doesn't hash to its root is refused
+        let mut tampered = archived.clone();
+        tampered.withdrawal_root = Sha512Trunc256Sum([0x00; 32]);
+        let line = serde_json::to_string(&tampered).unwrap();
+        assert!(other.import_withdrawal_archive(line.as_bytes()).is_err());
+    }
+}
@@ -0,0 +1,385 @@
+// Copyright (C) 2013-2020 Blockstack PBC, a public benefit corporation
+// Copyright (C) 2020 Stacks Open Internet Foundation
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::HashMap;
+use std::convert::TryFrom;
+
+use rusqlite::types::ToSql;
+use rusqlite::Row;
+
+use crate::chainstate::stacks::db::withdrawal_policy::QueuedWithdrawal;
+use crate::chainstate::stacks::db::StacksChainState;
+use crate::chainstate::stacks::events::StacksTransactionReceipt;
+use crate::chainstate::stacks::Error;
+use crate::clarity_vm::withdrawal;
+use crate::clarity_vm::withdrawal::get_withdrawal_event_data;
+use crate::util_lib::db::Error as db_error;
+use crate::util_lib::db::{query_row, query_rows, u64_to_sql, DBTx, FromColumn, FromRow};
+use clarity::vm::types::{PrincipalData, QualifiedContractIdentifier, TupleData};
+use clarity::vm::Value;
+use stacks_common::types::chainstate::StacksBlockId;
+use stacks_common::util::hash::Sha512Trunc256Sum;
+
+/// A withdrawal included in a block's withdrawal Merkle root, as recorded in the chainstate's
+/// withdrawal index.  This is what a user needs to look up the withdrawal's Merkle proof and
+/// claim it on the L1.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WithdrawalEntry {
+    /// the block whose withdrawal root includes the withdrawal
+    pub index_block_hash: StacksBlockId,
+    pub block_height: u64,
+    pub withdrawal_id: u32,
+    pub sender: PrincipalData,
+    /// "stx", "ft", "nft", "sft", or "stx-transfer"
+    pub asset_type: String,
+    /// the asset's contract, for anything but STX
+    pub asset_contract: Option<QualifiedContractIdentifier>,
+    /// the token ID, for NFTs and SFTs
+    pub asset_id: Option<u128>,
+    /// the amount withdrawn, for anything but NFTs
+    pub amount: Option<u128>,
+    pub withdrawal_root: Sha512Trunc256Sum,
+}
+
+impl WithdrawalEntry {
+    /// Read a withdrawal out of a withdraw event's data tuple.  The tuple must already have been
+    /// given its withdrawal ID by `create_withdrawal_merkle_tree_with_policy`; tuples without one
+    /// (such as withdrawals held back by the withdrawal policy) are not in the block's root.
+    pub fn from_withdrawal_data(
+        data: &TupleData,
+        index_block_hash: &StacksBlockId,
+        block_height: u64,
+        withdrawal_root: &Sha512Trunc256Sum,
+    ) -> Option<WithdrawalEntry> {
+        let data_map = &data.data_map;
+        let withdrawal_id = match data_map.get("withdrawal-id") {
+            Some(Value::UInt(id)) => u32::try_from(*id).ok()?,
+            _ => return None,
+        };
+        let asset_type = data_map.get("type")?.clone().expect_ascii();
+        let sender = match data_map.get("sender") {
+            Some(Value::Principal(sender)) => sender.clone(),
+            _ => return None,
+        };
+        let asset_contract = match data_map.get("asset-contract") {
+            Some(Value::Principal(PrincipalData::Contract(contract))) => Some(contract.clone()),
+            _ => None,
+        };
+        let asset_id = match data_map.get("id") {
+            Some(Value::UInt(id)) => Some(*id),
+            _ => None,
+        };
+        let amount = match data_map.get("amount") {
+            Some(Value::UInt(amount)) => Some(*amount),
+            _ => None,
+        };
+        let complete = match asset_type.as_str() {
+            "stx" | "stx-transfer" => amount.is_some(),
+            "ft" => asset_contract.is_some() && amount.is_some(),
+            "nft" => asset_contract.is_some() && asset_id.is_some(),
+            "sft" => asset_contract.is_some() && asset_id.is_some() && amount.is_some(),
+            _ => false,
+        };
+        if !complete {
+            return None;
+        }
+
+        Some(WithdrawalEntry {
+            index_block_hash: index_block_hash.clone(),
+            block_height,
+            withdrawal_id,
+            sender,
+            asset_type,
+            asset_contract,
+            asset_id,
+            amount,
+            withdrawal_root: withdrawal_root.clone(),
+        })
+    }
+}
+
+impl WithdrawalEntry {
+    /// Make the key this withdrawal has in its block's withdrawal Merkle tree.  Returns `None`
+    /// for STX transfers to another subnet, whose key holds the recipient and destination
+    /// subnet, which the index does not record.
+    pub fn make_key(&self) -> Option<Value> {
+        match self.asset_type.as_str() {
+            "stx" => Some(withdrawal::make_key_for_stx_withdrawal(
+                &self.sender,
+                self.withdrawal_id,
+                self.amount?,
+                self.block_height,
+            )),
+            "ft" => Some(withdrawal::make_key_for_ft_withdrawal(
+                &self.sender,
+                self.withdrawal_id,
+                self.asset_contract.as_ref()?,
+                self.amount?,
+                self.block_height,
+            )),
+            "nft" => Some(withdrawal::make_key_for_nft_withdrawal(
+                &self.sender,
+                self.withdrawal_id,
+                self.asset_contract.as_ref()?,
+                self.asset_id?,
+                self.block_height,
+            )),
+            "sft" => Some(withdrawal::make_key_for_sft_withdrawal(
+                &self.sender,
+                self.withdrawal_id,
+                self.asset_contract.as_ref()?,
+                self.asset_id?,
+                self.amount?,
+                self.block_height,
+            )),
+            _ => None,
+        }
+    }
+
+    /// Make the name and arguments of the subnet contract function which claims this withdrawal
+    /// on the L1, given its Merkle proof.  Assets other than STX are claimed from
+    /// `l1_asset_contract`, the L1 contract registered for the withdrawn subnet asset, so there
+    /// is no call if it is unknown.  Returns `None` for STX transfers to another subnet, which
+    /// are not claimed by the recipient.
+    pub fn make_claim_call(
+        &self,
+        l1_asset_contract: Option<&QualifiedContractIdentifier>,
+        withdrawal_root: Value,
+        withdrawal_leaf_hash: Value,
+        sibling_hashes: Value,
+    ) -> Option<(&'static str, Vec<Value>)> {
+        let recipient = Value::Principal(self.sender.clone());
+        let withdrawal_id = Value::UInt(u128::from(self.withdrawal_id));
+        let height = Value::UInt(u128::from(self.block_height));
+        let asset_contract =
+            || l1_asset_contract.map(|contract| Value::Principal(contract.clone().into()));
+        let (function_name, mut args) = match self.asset_type.as_str() {
+            "stx" => (
+                "withdraw-stx",
+                vec![Value::UInt(self.amount?), recipient, withdrawal_id, height],
+            ),
+            "ft" => (
+                "withdraw-ft-asset",
+                vec![
+                    asset_contract()?,
+                    Value::UInt(self.amount?),
+                    recipient,
+                    withdrawal_id,
+                    height,
+                    // memo
+                    Value::none(),
+                    // ft-mint-contract
+                    Value::none(),
+                ],
+            ),
+            "nft" => (
+                "withdraw-nft-asset",
+                vec![
+                    asset_contract()?,
+                    Value::UInt(self.asset_id?),
+                    recipient,
+                    withdrawal_id,
+                    height,
+                    // nft-mint-contract
+                    Value::none(),
+                ],
+            ),
+            "sft" => (
+                "withdraw-sft-asset",
+                vec![
+                    asset_contract()?,
+                    Value::UInt(self.asset_id?),
+                    Value::UInt(self.amount?),
+                    recipient,
+                    withdrawal_id,
+                    height,
+                ],
+            ),
+            _ => return None,
+        };
+        args.extend(vec![withdrawal_root, withdrawal_leaf_hash, sibling_hashes]);
+        Some((function_name, args))
+    }
+}
+
+impl FromRow<WithdrawalEntry> for WithdrawalEntry {
+    fn from_row<'a>(row: &'a Row) -> Result<WithdrawalEntry, db_error> {
+        let index_block_hash = StacksBlockId::from_column(row, "index_block_hash")?;
+        let block_height = u64::from_column(row, "block_height")?;
+        let withdrawal_id = u32::try_from(u64::from_column(row, "withdrawal_id")?)
+            .map_err(|_| db_error::ParseError)?;
+        let sender_str: String = row.get_unwrap("sender");
+        let sender = PrincipalData::parse(&sender_str).map_err(|_| db_error::ParseError)?;
+        let asset_type: String = row.get_unwrap("asset_type");
+        let asset_contract = match row.get_unwrap::<_, Option<String>>("asset_contract") {
+            Some(contract_str) => Some(
+                QualifiedContractIdentifier::parse(&contract_str)
+                    .map_err(|_| db_error::ParseError)?,
+            ),
+            None => None,
+        };
+        let asset_id = match row.get_unwrap::<_, Option<String>>("asset_id") {
+            Some(id_str) => Some(id_str.parse::<u128>().map_err(|_| db_error::ParseError)?),
+            None => None,
+        };
+        let amount = match row.get_unwrap::<_, Option<String>>("amount") {
+            Some(amount_str) => Some(
+                amount_str
+                    .parse::<u128>()
+                    .map_err(|_| db_error::ParseError)?,
+            ),
+            None => None,
+        };
+        let withdrawal_root_str: String = row.get_unwrap("withdrawal_root");
+        let withdrawal_root =
+            Sha512Trunc256Sum::from_hex(&withdrawal_root_str).map_err(|_| db_error::ParseError)?;
+        Ok(WithdrawalEntry {
+            index_block_hash,
+            block_height,
+            withdrawal_id,
+            sender,
+            asset_type,
+            asset_contract,
+            asset_id,
+            amount,
+            withdrawal_root,
+        })
+    }
+}
+
+impl StacksChainState {
+    /// Index the withdrawals in the withdrawal root of the block `index_block_hash`: the
+    /// `released` withdrawals an ancestor held back, and the ones in its own receipts.  Must be
+    /// called after `create_withdrawal_merkle_tree_with_policy` has given them their IDs.
+    pub fn index_block_withdrawals<'a>(
+        tx: &DBTx,
+        index_block_hash: &StacksBlockId,
+        block_height: u64,
+        withdrawal_root: &Sha512Trunc256Sum,
+        released: &[QueuedWithdrawal],
+        tx_receipts: impl Iterator<Item = &'a mut StacksTransactionReceipt>,
+    ) -> Result<(), Error> {
+        let mut entries = vec![];
+        for withdrawal in released.iter() {
+            if let Some(entry) = WithdrawalEntry::from_withdrawal_data(
+                &withdrawal.data,
+                index_block_hash,
+                block_height,
+                withdrawal_root,
+            ) {
+                entries.push(entry);
+            }
+        }
+        for receipt in tx_receipts {
+            for event in receipt.events.iter_mut() {
+                let data = match get_withdrawal_event_data(event) {
+                    Some(data) => data,
+                    None => continue,
+                };
+                if let Some(entry) = WithdrawalEntry::from_withdrawal_data(
+                    data,
+                    index_block_hash,
+                    block_height,
+                    withdrawal_root,
+                ) {
+                    entries.push(entry);
+                }
+            }
+        }
+
+        for entry in entries.iter() {
+            let args: &[&dyn ToSql] = &[
+                &entry.index_block_hash,
+                &u64_to_sql(entry.block_height)?,
+                &u64_to_sql(entry.withdrawal_id.into())?,
+                &entry.sender.to_string(),
+                &entry.asset_type,
+                &entry.asset_contract.as_ref().map(|c| c.to_string()),
+                &entry.asset_id.map(|id| id.to_string()),
+                &entry.amount.map(|amount| amount.to_string()),
+                &entry.withdrawal_root.to_hex(),
+            ];
+            tx.execute(
+                "INSERT INTO withdrawals (index_block_hash, block_height, withdrawal_id, sender, asset_type, asset_contract, asset_id, amount, withdrawal_root) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+                args,
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Get the withdrawals made by `sender` in the fork ending at `tip`, newest block first and in
+    /// withdrawal ID order within a block.  Skips the first `offset` of them, and returns at most
+    /// `limit`.
+    pub fn get_withdrawals_by_sender(
+        &self,
+        tip: &StacksBlockId,
+        sender: &PrincipalData,
+        offset: u64,
+        limit: u64,
+    ) -> Result<Vec<WithdrawalEntry>, Error> {
+        let sql = "SELECT * FROM withdrawals WHERE sender = ?1 ORDER BY block_height DESC, withdrawal_id ASC";
+        let args: &[&dyn ToSql] = &[&sender.to_string()];
+        let rows: Vec<WithdrawalEntry> = query_rows(self.db(), sql, args)?;
+
+        // every fork's blocks are indexed, so only keep the ones which are ancestors of `tip`
+        let index_conn = self.index_conn()?;
+        let mut ancestors: HashMap<u64, Option<StacksBlockId>> = HashMap::new();
+        let mut entries = vec![];
+        let mut skipped = 0;
+        for entry in rows.into_iter() {
+            if entries.len() as u64 >= limit {
+                break;
+            }
+            let ancestor = match ancestors.get(&entry.block_height) {
+                Some(ancestor) => ancestor.clone(),
+                None => {
+                    let ancestor = index_conn.get_ancestor_block_hash(entry.block_height, tip)?;
+                    ancestors.insert(entry.block_height, ancestor.clone());
+                    ancestor
+                }
+            };
+            if ancestor.as_ref() != Some(&entry.index_block_hash) {
+                continue;
+            }
+            if skipped < offset {
+                skipped += 1;
+                continue;
+            }
+            entries.push(entry);
+        }
+        Ok(entries)
+    }
+
+    /// Get the withdrawal with ID `withdrawal_id` in the block at `block_height` in the fork
+    /// ending at `tip`, if it is indexed
+    pub fn get_withdrawal(
+        &self,
+        tip: &StacksBlockId,
+        block_height: u64,
+        withdrawal_id: u32,
+    ) -> Result<Option<WithdrawalEntry>, Error> {
+        let block_id = match self
+            .index_conn()?
+            .get_ancestor_block_hash(block_height, tip)?
+        {
+            Some(block_id) => block_id,
+            None => return Ok(None),
+        };
+        let sql = "SELECT * FROM withdrawals WHERE index_block_hash = ?1 AND withdrawal_id = ?2";
+        let args: &[&dyn ToSql] = &[&block_id, &u64_to_sql(withdrawal_id.into())?];
+        Ok(query_row(self.db(), sql, args)?)
+    }
+}
@@ -0,0 +1,433 @@
+// Copyright (C) 2013-2020 Blockstack PBC, a public benefit corporation
+// Copyright (C) 2020 Stacks Open Internet Foundation
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use rusqlite::types::ToSql;
+use rusqlite::Row;
+use rusqlite::NO_PARAMS;
+
+use crate::chainstate::stacks::db::{ChainstateTx, StacksChainState};
+use crate::chainstate::stacks::events::StacksTransactionReceipt;
+use crate::chainstate::stacks::Error;
+use crate::clarity_vm::withdrawal::{
+    convert_withdrawal_key_to_bytes, generate_key_from_withdrawal_data, get_withdrawal_event_data,
+};
+use crate::util_lib::db::Error as db_error;
+use crate::util_lib::db::{query_row, query_rows, u64_to_sql, DBConn, DBTx, FromColumn, FromRow};
+use clarity::codec::StacksMessageCodec;
+use clarity::vm::types::TupleData;
+use clarity::vm::Value;
+use stacks_common::types::chainstate::StacksBlockId;
+use stacks_common::util::hash::{to_hex, MerkleTree, Sha512Trunc256Sum};
+
+/// Holds back large withdrawals, optimistic-rollup style: an STX or FT withdrawal of more than
+/// `large_withdrawal_threshold` is left out of the withdrawal Merkle root of the block that
+/// produced it, and is instead included in the root of its descendant
+/// `large_withdrawal_delay` blocks later.  NFT withdrawals are never delayed.
+///
+/// The policy decides which withdrawal root a block must commit to, so every node of a subnet
+/// must use the same one, and it must not change once the subnet has produced blocks.  It is
+/// recorded in the chainstate when it is booted, and a node uses the recorded policy whatever
+/// it is configured with.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct WithdrawalPolicy {
+    pub large_withdrawal_threshold: u128,
+    /// 0 disables the policy
+    pub large_withdrawal_delay: u64,
+}
+
+/// A withdrawal held back by the `WithdrawalPolicy`
+#[derive(Debug, Clone, PartialEq)]
+pub struct QueuedWithdrawal {
+    /// the withdraw event's data tuple, as printed by the subnet contract.  Once released, this
+    /// includes the withdrawal ID it was given in the releasing block.
+    pub data: TupleData,
+    /// height of the block which queued the withdrawal
+    pub queued_height: u64,
+    /// height of the block whose withdrawal root includes the withdrawal
+    pub release_height: u64,
+}
+
+impl FromRow<QueuedWithdrawal> for QueuedWithdrawal {
+    fn from_row<'a>(row: &'a Row) -> Result<QueuedWithdrawal, db_error> {
+        let withdrawal_hex: String = row.get_unwrap("withdrawal");
+        let data = match Value::try_deserialize_hex_untyped(&withdrawal_hex) {
+            Ok(Value::Tuple(data)) => data,
+            _ => {
+                return Err(db_error::ParseError);
+            }
+        };
+        let queued_height = u64::from_column(row, "queued_height")?;
+        let release_height = u64::from_column(row, "release_height")?;
+        Ok(QueuedWithdrawal {
+            data,
+            queued_height,
+            release_height,
+        })
+    }
+}
+
+impl FromRow<WithdrawalPolicy> for WithdrawalPolicy {
+    fn from_row<'a>(row: &'a Row) -> Result<WithdrawalPolicy, db_error> {
+        let threshold_text: String = row.get_unwrap("large_withdrawal_threshold");
+        let large_withdrawal_threshold = threshold_text
+            .parse::<u128>()
+            .map_err(|_e| db_error::ParseError)?;
+        let large_withdrawal_delay = u64::from_column(row, "large_withdrawal_delay")?;
+        Ok(WithdrawalPolicy {
+            large_withdrawal_threshold,
+            large_withdrawal_delay,
+        })
+    }
+}
+
+impl WithdrawalPolicy {
+    pub fn is_enabled(&self) -> bool {
+        self.large_withdrawal_delay > 0
+    }
+
+    /// Should the withdrawal with this event data tuple be held back?
+    pub fn should_delay(&self, data: &TupleData) -> bool {
+        if !self.is_enabled() {
+            return false;
+        }
+        let event_type = match data.data_map.get("type") {
+            Some(event_type) => event_type.clone().expect_ascii(),
+            None => {
+                return false;
+            }
+        };
+        if event_type != "stx" && event_type != "ft" && event_type != "stx-transfer" {
+            return false;
+        }
+        match data.data_map.get("amount") {
+            Some(Value::UInt(amount)) => *amount > self.large_withdrawal_threshold,
+            _ => false,
+        }
+    }
+}
+
+/// Put a block's withdrawals into its withdrawal Merkle tree, applying `policy`.
+/// The `released` withdrawals come first, and are given the first withdrawal IDs; then come
+/// the withdrawals in the block's receipts which `policy` does not hold back.  Returns the tree
+/// and the withdrawals this block holds back.
+///
+/// With no released withdrawals and the policy disabled, this builds the same tree as
+/// `create_withdrawal_merkle_tree`.
+pub fn create_withdrawal_merkle_tree_with_policy<'a>(
+    policy: &WithdrawalPolicy,
+    released: &mut [QueuedWithdrawal],
+    tx_receipts: impl Iterator<Item = &'a mut StacksTransactionReceipt>,
+    block_height: u64,
+) -> (MerkleTree<Sha512Trunc256Sum>, Vec<QueuedWithdrawal>) {
+    let mut items = Vec::new();
+    let mut queued = Vec::new();
+    let mut withdrawal_id = 0;
+    for withdrawal in released.iter_mut() {
+        if let Some(key) =
+            generate_key_from_withdrawal_data(&mut withdrawal.data, withdrawal_id, block_height)
+        {
+            withdrawal_id += 1;
+            items.push(convert_withdrawal_key_to_bytes(&key));
+        }
+    }
+    for receipt in tx_receipts {
+        for event in receipt.events.iter_mut() {
+            let data = match get_withdrawal_event_data(event) {
+                Some(data) => data,
+                None => continue,
+            };
+            if policy.should_delay(data) {
+                info!("Queued large L2 withdrawal";
+                      "block_height" => block_height,
+                      "release_height" => block_height + policy.large_withdrawal_delay);
+                queued.push(QueuedWithdrawal {
+                    data: data.clone(),
+                    queued_height: block_height,
+                    release_height: block_height + policy.large_withdrawal_delay,
+                });
+                continue;
+            }
+            if let Some(key) = generate_key_from_withdrawal_data(data, withdrawal_id, block_height)
+            {
+                withdrawal_id += 1;
+                items.push(convert_withdrawal_key_to_bytes(&key));
+            }
+        }
+    }
+
+    (MerkleTree::<Sha512Trunc256Sum>::new(&items), queued)
+}
+
+impl StacksChainState {
+    /// Set the policy for holding back large withdrawals, in place of the recorded one.  See
+    /// `WithdrawalPolicy`.
+    pub fn set_withdrawal_policy(&mut self, policy: WithdrawalPolicy) {
+        self.withdrawal_policy = policy;
+    }
+
+    pub fn get_withdrawal_policy(&self) -> &WithdrawalPolicy {
+        &self.withdrawal_policy
+    }
+
+    /// Record the withdrawal policy of a chainstate being booted, or of one booted before
+    /// policies were recorded
+    pub fn insert_withdrawal_policy(tx: &DBTx, policy: &WithdrawalPolicy) -> Result<(), Error> {
+        let args: &[&dyn ToSql] = &[
+            &policy.large_withdrawal_threshold.to_string(),
+            &u64_to_sql(policy.large_withdrawal_delay)?,
+        ];
+        tx.execute(
+            "INSERT INTO withdrawal_policy (large_withdrawal_threshold,large_withdrawal_delay) VALUES (?1,?2)",
+            args,
+        )?;
+        Ok(())
+    }
+
+    /// Get the withdrawal policy recorded in the chainstate.  Chainstates booted before policies
+    /// were recorded have none until the node records the one it is configured with.
+    pub fn get_recorded_withdrawal_policy(
+        conn: &DBConn,
+    ) -> Result<Option<WithdrawalPolicy>, Error> {
+        let policy = query_row(conn, "SELECT * FROM withdrawal_policy LIMIT 1", NO_PARAMS)?;
+        Ok(policy)
+    }
+
+    /// Load the withdrawals which the block at `block_height` on top of `parent_block_id`
+    /// releases: those queued by its ancestor `large_withdrawal_delay` blocks back.
+    pub fn get_released_withdrawals(
+        chainstate_tx: &mut ChainstateTx,
+        parent_block_id: &StacksBlockId,
+        block_height: u64,
+    ) -> Result<Vec<QueuedWithdrawal>, Error> {
+        let delay = chainstate_tx.withdrawal_policy.large_withdrawal_delay;
+        if delay == 0 || block_height <= delay {
+            return Ok(vec![]);
+        }
+        let queuing_block_id = match StacksChainState::get_index_tip_ancestor(
+            &mut chainstate_tx.tx,
+            parent_block_id,
+            block_height - delay,
+        )? {
+            Some(header) => header.index_block_hash(),
+            None => {
+                return Ok(vec![]);
+            }
+        };
+
+        let sql =
+            "SELECT * FROM queued_withdrawals WHERE index_block_hash = ?1 ORDER BY queue_index ASC";
+        let args: &[&dyn ToSql] = &[&queuing_block_id];
+        let released = query_rows(&chainstate_tx.tx, sql, args)?;
+        Ok(released)
+    }
+
+    /// Record the withdrawals which the block `index_block_hash` held back
+    pub fn store_queued_withdrawals(
+        tx: &DBTx,
+        index_block_hash: &StacksBlockId,
+        withdrawals: &[QueuedWithdrawal],
+    ) -> Result<(), Error> {
+        for (queue_index, withdrawal) in withdrawals.iter().enumerate() {
+            let withdrawal_hex = to_hex(&Value::Tuple(withdrawal.data.clone()).serialize_to_vec());
+            let args: &[&dyn ToSql] = &[
+                index_block_hash,
+                &u64_to_sql(queue_index as u64)?,
+                &withdrawal_hex,
+                &u64_to_sql(withdrawal.queued_height)?,
+                &u64_to_sql(withdrawal.release_height)?,
+            ];
+            tx.execute(
+                "INSERT INTO queued_withdrawals (index_block_hash, queue_index, withdrawal, queued_height, release_height) VALUES (?1, ?2, ?3, ?4, ?5)",
+                args,
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::chainstate::stacks::db::test::{instantiate_chainstate, open_chainstate};
+    use crate::chainstate::stacks::events::TransactionOrigin;
+    use crate::chainstate::stacks::*;
+    use crate::clarity_vm::withdrawal::create_withdrawal_merkle_tree;
+    use clarity::boot_util::boot_code_id;
+    use clarity::vm::costs::ExecutionCost;
+    use clarity::vm::events::{SmartContractEventData, StacksTransactionEvent};
+    use clarity::vm::types::StandardPrincipalData;
+
+    fn make_stx_withdraw_event(amount: u128) -> StacksTransactionEvent {
+        StacksTransactionEvent::SmartContractEvent(SmartContractEventData {
+            key: (boot_code_id("subnet", false), "print".into()),
+            value: Value::Tuple(
+                TupleData::from_data(vec![
+                    (
+                        "type".into(),
+                        Value::string_ascii_from_bytes("stx".to_string().into_bytes()).unwrap(),
+                    ),
+                    (
+                        "sender".into(),
+                        Value::from(StandardPrincipalData::transient()),
+                    ),
+                    ("amount".into(), Value::UInt(amount)),
+                ])
+                .unwrap(),
+            ),
+        })
+    }
+
+    fn make_receipt(events: Vec<StacksTransactionEvent>) -> StacksTransactionReceipt {
+        let privk = StacksPrivateKey::new();
+        StacksTransactionReceipt {
+            transaction: TransactionOrigin::Stacks(StacksTransaction::new(
+                TransactionVersion::Testnet,
+                TransactionAuth::from_p2pkh(&privk).unwrap(),
+                TransactionPayload::Coinbase(CoinbasePayload([0u8; 32])),
+            )),
+            events,
+            post_condition_aborted: false,
+            result: Value::err_none(),
+            stx_burned: 0,
+            contract_analysis: None,
+            execution_cost: ExecutionCost::zero(),
+            microblock_header: None,
+            tx_index: 0,
+        }
+    }
+
+    fn event_data(event: &StacksTransactionEvent) -> TupleData {
+        match event {
+            StacksTransactionEvent::SmartContractEvent(data) => data.value.clone().expect_tuple(),
+            _ => panic!("expected a smart contract event"),
+        }
+    }
+
+    #[test]
+    fn test_should_delay() {
+        let policy = WithdrawalPolicy {
+            large_withdrawal_threshold: 100,
+            large_withdrawal_delay: 5,
+        };
+        assert!(!policy.should_delay(&event_data(&make_stx_withdraw_event(100))));
+        assert!(policy.should_delay(&event_data(&make_stx_withdraw_event(101))));
+        assert!(
+            !WithdrawalPolicy::default().should_delay(&event_data(&make_stx_withdraw_event(101)))
+        );
+
+        let mut nft_data = event_data(&make_stx_withdraw_event(101));
+        nft_data.data_map.insert(
+            "type".into(),
+            Value::string_ascii_from_bytes("nft".to_string().into_bytes()).unwrap(),
+        );
+        assert!(!policy.should_delay(&nft_data));
+    }
+
+    #[test]
+    fn test_withdrawal_tree_with_policy() {
+        let policy = WithdrawalPolicy {
+            large_withdrawal_threshold: 100,
+            large_withdrawal_delay: 5,
+        };
+
+        // disabled policy and nothing released: same tree as without the policy
+        let mut receipts = vec![make_receipt(vec![
+            make_stx_withdraw_event(1),
+            make_stx_withdraw_event(1000),
+        ])];
+        let mut expected_receipts = receipts.clone();
+        let (tree, queued) = create_withdrawal_merkle_tree_with_policy(
+            &WithdrawalPolicy::default(),
+            &mut [],
+            receipts.iter_mut(),
+            10,
+        );
+        assert!(queued.is_empty());
+        assert_eq!(
+            tree.root(),
+            create_withdrawal_merkle_tree(expected_receipts.iter_mut(), 10).root()
+        );
+
+        // the large withdrawal is held back
+        let mut receipts = vec![make_receipt(vec![
+            make_stx_withdraw_event(1),
+            make_stx_withdraw_event(1000),
+        ])];
+        let (tree, mut queued) =
+            create_withdrawal_merkle_tree_with_policy(&policy, &mut [], receipts.iter_mut(), 10);
+        assert_eq!(queued.len(), 1);
+        assert_eq!(queued[0].queued_height, 10);
+        assert_eq!(queued[0].release_height, 15);
+        let mut expected_receipts = vec![make_receipt(vec![make_stx_withdraw_event(1)])];
+        assert_eq!(
+            tree.root(),
+            create_withdrawal_merkle_tree(expected_receipts.iter_mut(), 10).root()
+        );
+
+        // ...and released ahead of the releasing block's own withdrawals
+        let mut receipts = vec![make_receipt(vec![make_stx_withdraw_event(2)])];
+        let (tree, requeued) = create_withdrawal_merkle_tree_with_policy(
+            &policy,
+            &mut queued,
+            receipts.iter_mut(),
+            15,
+        );
+        assert!(requeued.is_empty());
+        assert_eq!(
+            queued[0].data.get("withdrawal-id").unwrap(),
+            &Value::UInt(0)
+        );
+        let mut expected_receipts = vec![make_receipt(vec![
+            make_stx_withdraw_event(1000),
+            make_stx_withdraw_event(2),
+        ])];
+        assert_eq!(
+            tree.root(),
+            create_withdrawal_merkle_tree(expected_receipts.iter_mut(), 15).root()
+        );
+    }
+
+    #[test]
+    fn test_record_withdrawal_policy() {
+        let mut chainstate =
+            instantiate_chainstate(false, 0x80000000, "test_record_withdrawal_policy");
+
+        // booted chainstates record the default policy by default
+        assert_eq!(
+            StacksChainState::get_recorded_withdrawal_policy(chainstate.db()).unwrap(),
+            Some(WithdrawalPolicy::default())
+        );
+
+        // chainstates booted before policies were recorded have none
+        let tx = chainstate.db_tx_begin().unwrap();
+        tx.execute("DELETE FROM withdrawal_policy", NO_PARAMS)
+            .unwrap();
+        assert_eq!(
+            StacksChainState::get_recorded_withdrawal_policy(&tx).unwrap(),
+            None
+        );
+        let policy = WithdrawalPolicy {
+            large_withdrawal_threshold: u128::MAX - 1,
+            large_withdrawal_delay: 5,
+        };
+        StacksChainState::insert_withdrawal_policy(&tx, &policy).unwrap();
+        tx.commit().unwrap();
+
+        // the recorded policy is used when the chainstate is opened
+        let chainstate = open_chainstate(false, 0x80000000, "test_record_withdrawal_policy");
+        assert_eq!(chainstate.get_withdrawal_policy(), &policy);
+    }
+}
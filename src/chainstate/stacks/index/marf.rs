@@ -314,6 +314,15 @@ impl<'a, T: MarfTrieId> MarfTransaction<'a, T> {
         self.storage.sqlite_tx_mut()
     }
 
+    /// Resolve a key from the MARF to a MARFValue with respect to the given block height, through
+    /// a shared reference.  Unlike a view made with `reopen_readonly()`, this reads through this
+    /// transaction's own SQLite transaction, so pending operations are visible.
+    pub fn get_readonly(&self, block_hash: &T, key: &str) -> Result<Option<MARFValue>, Error> {
+        let mut read_state = self.storage.read_state()?;
+        let mut conn = read_state.connection(self.storage.sqlite_tx());
+        MARF::get_by_key(&mut conn, block_hash, key)
+    }
+
     /// Reopen this MARF transaction with readonly storage.
     ///   NOTE: any pending operations in the SQLite transaction _will not_
     ///         have materialized in the reopened view.
@@ -1540,4 +1549,61 @@ impl<T: MarfTrieId> MARF<T> {
     pub fn get_root_hash_at(&mut self, block_hash: &T) -> Result<TrieHash, Error> {
         self.storage.connection().get_root_hash_at(block_hash)
     }
+
+    /// Resolve a path, rather than the key it is the hash of, to a MARFValue with respect to
+    /// the given block.  Returns `None` if the path has no leaf in that block's view.
+    pub fn get_by_path(
+        &mut self,
+        block_hash: &T,
+        path: &TriePath,
+    ) -> Result<Option<MARFValue>, Error> {
+        let mut conn = self.storage.connection();
+        let (cur_block_hash, cur_block_id) = conn.get_cur_block_and_id();
+        let result = MARF::get_path(&mut conn, block_hash, path).or_else(|e| match e {
+            Error::NotFoundError => Ok(None),
+            _ => Err(e),
+        });
+        conn.open_block_maybe_id(&cur_block_hash, cur_block_id)?;
+        result.map(|option_result| option_result.map(|leaf| leaf.data))
+    }
+
+    /// Get the paths of the leaves stored in the given block's own trie.  These are the keys
+    /// written in that block, along with any leaves the block copied from its ancestors while
+    /// writing them; leaves only reachable through back-pointers are left out.
+    pub fn get_block_leaf_paths(&mut self, block_hash: &T) -> Result<Vec<TriePath>, Error> {
+        let mut conn = self.storage.connection();
+        let (cur_block_hash, cur_block_id) = conn.get_cur_block_and_id();
+        conn.open_block(block_hash)?;
+
+        let mut paths = vec![];
+        let mut result = Ok(());
+        let mut stack = vec![(conn.root_trieptr(), vec![])];
+        while let Some((ptr, mut path)) = stack.pop() {
+            let node = match conn.read_nodetype(&ptr) {
+                Ok((node, _)) => node,
+                Err(e) => {
+                    result = Err(e);
+                    break;
+                }
+            };
+            path.extend_from_slice(node.path_bytes());
+            if node.is_leaf() {
+                if let Some(leaf_path) = TriePath::from_bytes(&path) {
+                    paths.push(leaf_path);
+                }
+                continue;
+            }
+            for child in node.ptrs() {
+                if child.id == TrieNodeID::Empty as u8 || is_backptr(child.id) {
+                    continue;
+                }
+                let mut child_path = path.clone();
+                child_path.push(child.chr);
+                stack.push((child.clone(), child_path));
+            }
+        }
+
+        conn.open_block_maybe_id(&cur_block_hash, cur_block_id)?;
+        result.map(|_| paths)
+    }
 }
@@ -1591,7 +1591,85 @@ impl<T: MarfTrieId> TrieFileStorage<T> {
     }
 }
 
+/// The transient state needed to read a `TrieStorageTransaction` through a shared reference.
+/// Connections made from it read through the transaction's own SQLite transaction, so unlike a
+/// reopened read-only view, they see the tries written in the transaction so far.
+pub struct TrieStorageReadState<T: MarfTrieId> {
+    db_path: String,
+    blobs: Option<TrieFile>,
+    data: TrieStorageTransientData<T>,
+    cache: TrieCache<T>,
+    bench: TrieBenchmark,
+    hash_calculation_mode: TrieHashCalculationMode,
+
+    // used in testing in order to short-circuit block-height lookups
+    //   when the trie struct is tested outside of marf.rs usage
+    #[cfg(test)]
+    test_genesis_block: Option<T>,
+}
+
+impl<T: MarfTrieId> TrieStorageReadState<T> {
+    /// Make a read-only connection which reads through `db`.  `db` must be the SQLite
+    /// transaction of the storage transaction this state was made from.
+    pub fn connection<'a>(&'a mut self, db: &'a Connection) -> TrieStorageConnection<'a, T> {
+        TrieStorageConnection {
+            db: SqliteConnection::ConnRef(db),
+            db_path: &self.db_path,
+            data: &mut self.data,
+            blobs: self.blobs.as_mut(),
+            cache: &mut self.cache,
+            bench: &mut self.bench,
+            hash_calculation_mode: self.hash_calculation_mode,
+            unconfirmed_block_id: None,
+
+            #[cfg(test)]
+            test_genesis_block: &mut self.test_genesis_block,
+        }
+    }
+}
+
 impl<'a, T: MarfTrieId> TrieStorageTransaction<'a, T> {
+    /// Make the state for reading this transaction through a shared reference.  Preserves the
+    /// cur_block and any uncommitted trie.
+    pub fn read_state(&self) -> Result<TrieStorageReadState<T>, Error> {
+        let blobs = if self.blobs.is_some() {
+            Some(TrieFile::from_db_path(&self.db_path, true)?)
+        } else {
+            None
+        };
+
+        // TODO: borrow self.uncommitted_writes; don't copy them
+        Ok(TrieStorageReadState {
+            db_path: self.db_path.to_string(),
+            blobs,
+            data: TrieStorageTransientData {
+                uncommitted_writes: self.data.uncommitted_writes.clone(),
+                cur_block: self.data.cur_block.clone(),
+                cur_block_id: self.data.cur_block_id.clone(),
+
+                read_count: 0,
+                read_backptr_count: 0,
+                read_node_count: 0,
+                read_leaf_count: 0,
+
+                write_count: 0,
+                write_node_count: 0,
+                write_leaf_count: 0,
+
+                trie_ancestor_hash_bytes_cache: None,
+
+                readonly: true,
+                unconfirmed: self.unconfirmed(),
+            },
+            cache: TrieCache::default(),
+            bench: TrieBenchmark::new(),
+            hash_calculation_mode: self.hash_calculation_mode,
+
+            #[cfg(test)]
+            test_genesis_block: self.test_genesis_block.clone(),
+        })
+    }
+
     /// reopen this transaction as a read-only marf.
     ///  _does not_ preserve the cur_block/open tip
     pub fn reopen_readonly(&self) -> Result<TrieFileStorage<T>, Error> {
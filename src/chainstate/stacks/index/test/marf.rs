@@ -2310,3 +2310,57 @@ fn test_marf_unconfirmed() {
         assert!(false);
     }
 }
+
+#[test]
+fn marf_get_block_leaf_paths() {
+    let f = TrieFileStorage::new_memory(MARFOpenOpts::default()).unwrap();
+    let mut marf = MARF::from_storage(f);
+    let block_1 = BlockHeaderHash::from_bytes(&[1u8; 32]).unwrap();
+    let block_2 = BlockHeaderHash::from_bytes(&[2u8; 32]).unwrap();
+
+    let path_1 = TriePath::from_bytes(&[0x11; 32]).unwrap();
+    let path_2 = TriePath::from_bytes(&[0x22; 32]).unwrap();
+    let mut path_3_bytes = [0x11; 32];
+    path_3_bytes[31] = 0x33;
+    let path_3 = TriePath::from_bytes(&path_3_bytes).unwrap();
+
+    marf.begin(&BlockHeaderHash::sentinel(), &block_1).unwrap();
+    marf.insert_raw(path_1.clone(), TrieLeaf::new(&vec![], &[1u8; 40].to_vec()))
+        .unwrap();
+    marf.insert_raw(path_2.clone(), TrieLeaf::new(&vec![], &[2u8; 40].to_vec()))
+        .unwrap();
+    marf.commit().unwrap();
+
+    // overwrite one leaf, and add one which splits the path of another
+    marf.begin(&block_1, &block_2).unwrap();
+    marf.insert_raw(path_2.clone(), TrieLeaf::new(&vec![], &[3u8; 40].to_vec()))
+        .unwrap();
+    marf.insert_raw(path_3.clone(), TrieLeaf::new(&vec![], &[4u8; 40].to_vec()))
+        .unwrap();
+    marf.commit().unwrap();
+
+    let paths_1 = marf.get_block_leaf_paths(&block_1).unwrap();
+    assert!(paths_1.contains(&path_1));
+    assert!(paths_1.contains(&path_2));
+    assert!(!paths_1.contains(&path_3));
+
+    let paths_2 = marf.get_block_leaf_paths(&block_2).unwrap();
+    assert!(paths_2.contains(&path_2));
+    assert!(paths_2.contains(&path_3));
+
+    assert_eq!(
+        marf.get_by_path(&block_1, &path_2)
+            .unwrap()
+            .unwrap()
+            .to_vec(),
+        [2u8; 40].to_vec()
+    );
+    assert_eq!(
+        marf.get_by_path(&block_2, &path_2)
+            .unwrap()
+            .unwrap()
+            .to_vec(),
+        [3u8; 40].to_vec()
+    );
+    assert_eq!(marf.get_by_path(&block_1, &path_3).unwrap(), None);
+}
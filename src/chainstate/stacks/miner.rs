@@ -19,10 +19,13 @@ use std::collections::HashSet;
 use std::convert::From;
 use std::fs;
 use std::mem;
+use std::sync::Arc;
 
 use crate::burnchains::PrivateKey;
 use crate::burnchains::PublicKey;
-use crate::chainstate::burn::db::sortdb::{SortitionDB, SortitionDBConn, SortitionHandleTx};
+use crate::chainstate::burn::db::sortdb::{
+    SortitionDB, SortitionDBConn, SortitionHandleConn, SortitionHandleTx,
+};
 use crate::chainstate::burn::operations::*;
 use crate::chainstate::burn::*;
 use crate::chainstate::stacks::db::unconfirmed::UnconfirmedState;
@@ -40,6 +43,7 @@ use crate::cost_estimates::metrics::CostMetric;
 use crate::cost_estimates::CostEstimator;
 use crate::net::Error as net_error;
 use crate::types::StacksPublicKeyBuffer;
+use crate::util_lib::clock::{system_clock, Clock};
 use clarity::util::hash::to_hex;
 use clarity::util::hash::Sha256Sum;
 use clarity::vm::database::BurnStateDB;
@@ -54,11 +58,14 @@ use stacks_common::util::vrf::*;
 
 use crate::chainstate::stacks::address::StacksAddressExtensions;
 use crate::chainstate::stacks::db::blocks::SetupBlockResult;
+use crate::chainstate::stacks::db::withdrawal_policy::{
+    create_withdrawal_merkle_tree_with_policy, WithdrawalPolicy,
+};
 use crate::chainstate::stacks::StacksBlockHeader;
 use crate::chainstate::stacks::StacksMicroblockHeader;
-use crate::clarity_vm::withdrawal::create_withdrawal_merkle_tree;
 use crate::codec::{read_next, write_next, StacksMessageCodec};
 use crate::types::chainstate::BurnchainHeaderHash;
+use crate::types::chainstate::SortitionId;
 use crate::types::chainstate::StacksBlockId;
 use crate::types::chainstate::TrieHash;
 use crate::types::chainstate::{BlockHeaderHash, StacksAddress, StacksWorkScore};
@@ -73,6 +80,11 @@ pub const SIP18_DATA_PREFIX_HEX: &'static str =
 pub struct BlockBuilderSettings {
     pub max_miner_time_ms: u64,
     pub mempool_settings: MemPoolWalkSettings,
+    /// times `max_miner_time_ms`
+    pub clock: Arc<dyn Clock>,
+    /// if set, the most of the block limit, in each dimension, that a single transaction in an
+    /// anchored block may cost.  Transactions which would cost more are skipped.
+    pub max_tx_cost_fraction: Option<f64>,
 }
 
 impl BlockBuilderSettings {
@@ -80,6 +92,8 @@ impl BlockBuilderSettings {
         BlockBuilderSettings {
             max_miner_time_ms: u64::max_value(),
             mempool_settings: MemPoolWalkSettings::default(),
+            clock: system_clock(),
+            max_tx_cost_fraction: None,
         }
     }
 
@@ -87,10 +101,24 @@ impl BlockBuilderSettings {
         BlockBuilderSettings {
             max_miner_time_ms: u64::max_value(),
             mempool_settings: MemPoolWalkSettings::zero(),
+            clock: system_clock(),
+            max_tx_cost_fraction: None,
         }
     }
 }
 
+/// `fraction` of `block_limit`, in each dimension
+pub fn max_tx_cost(block_limit: &ExecutionCost, fraction: f64) -> ExecutionCost {
+    let scale = |limit: u64| (limit as f64 * fraction) as u64;
+    ExecutionCost {
+        write_length: scale(block_limit.write_length),
+        write_count: scale(block_limit.write_count),
+        read_length: scale(block_limit.read_length),
+        read_count: scale(block_limit.read_count),
+        runtime: scale(block_limit.runtime),
+    }
+}
+
 #[derive(Clone)]
 struct MicroblockMinerRuntime {
     bytes_so_far: u64,
@@ -123,6 +151,9 @@ pub struct MinerEpochInfo<'a> {
     pub clarity_instance: &'a mut ClarityInstance,
     pub burn_tip: BurnchainHeaderHash,
     pub burn_tip_height: u32,
+    /// the sortition DB as of the burn tip, which the block's costs and burnchain state are
+    ///  evaluated against
+    pub burn_tip_handle: SortitionHandleConn<'a>,
     pub parent_microblocks: Vec<StacksMicroblock>,
     pub mainnet: bool,
 }
@@ -707,6 +738,14 @@ impl<'a> StacksMicroblockBuilder<'a> {
                 Error::BlockTooBigError,
             ));
         }
+        if let Err(reason) =
+            StacksBlock::validate_transaction_clarity_version(&tx, clarity_tx.get_epoch())
+        {
+            return Ok(TransactionResult::error(
+                &tx,
+                Error::InvalidStacksTransaction(reason, false),
+            ));
+        }
         match limit_behavior {
             BlockLimitFunction::CONTRACT_LIMIT_HIT => {
                 match &tx.payload {
@@ -913,8 +952,10 @@ impl<'a> StacksMicroblockBuilder<'a> {
         let mut num_txs = self.runtime.num_mined;
         let mut num_selected = 0;
         let mut tx_events = Vec::new();
-        let deadline = get_epoch_time_ms() + (self.settings.max_miner_time_ms as u128);
+        let clock = self.settings.clock.clone();
+        let deadline = clock.now_ms() + (self.settings.max_miner_time_ms as u128);
         let mut block_limit_hit = BlockLimitFunction::NO_LIMIT_HIT;
+        let contract_publish_policy = self.header_reader.get_contract_publish_policy().clone();
 
         mem_pool.reset_nonce_cache()?;
         let stacks_epoch_id = clarity_tx.get_epoch();
@@ -940,7 +981,7 @@ impl<'a> StacksMicroblockBuilder<'a> {
                         let mempool_tx = &to_consider.tx;
                         let update_estimator = to_consider.update_estimate;
 
-                        if get_epoch_time_ms() >= deadline {
+                        if clock.now_ms() >= deadline {
                             debug!(
                                 "Microblock miner deadline exceeded ({} ms)",
                                 self.settings.max_miner_time_ms
@@ -955,6 +996,17 @@ impl<'a> StacksMicroblockBuilder<'a> {
                             considered.insert(mempool_tx.tx.txid());
                         }
 
+                        if !contract_publish_policy.admits(&mempool_tx.tx) {
+                            return Ok(Some(TransactionResult::skipped(
+                                &mempool_tx.tx, "Contract publish not allowed by policy.".to_string()).convert_to_event()));
+                        }
+
+                        // a microblock stream may be orphaned in part, so bundles only go in anchored blocks
+                        if mempool_tx.metadata.bundle_id.is_some() {
+                            return Ok(Some(TransactionResult::skipped(
+                                &mempool_tx.tx, "Bundled transactions are only mined in anchored blocks.".to_string()).convert_to_event()));
+                        }
+
                         match StacksMicroblockBuilder::mine_next_transaction(
                             clarity_tx,
                             mempool_tx.tx.clone(),
@@ -1160,6 +1212,61 @@ impl StacksBlockBuilder {
             miner_payouts: None,
             miner_id: miner_id,
             microblock_tx_receipts: vec![],
+            withdrawals_released: vec![],
+            withdrawal_policy: WithdrawalPolicy::default(),
+            max_tx_cost: None,
+        }
+    }
+
+    /// Cap what a single transaction in the anchored block may cost.  See
+    /// `BlockBuilderSettings::max_tx_cost_fraction`.
+    pub fn set_max_tx_cost(&mut self, max_tx_cost: Option<ExecutionCost>) {
+        self.max_tx_cost = max_tx_cost;
+    }
+
+    /// Where to start charging a transaction, instead of at `cost_before`, so that it runs out
+    /// of budget once it costs more than `max_tx_cost`: that far short of the block limit, in
+    /// each dimension where that is closer than what the block has left.  `None` if the block's
+    /// own budget is the tighter one in every dimension.
+    fn capped_tx_start_cost(
+        &self,
+        clarity_tx: &ClarityTx,
+        cost_before: &ExecutionCost,
+    ) -> Option<ExecutionCost> {
+        let max_tx_cost = self.max_tx_cost.as_ref()?;
+        let block_limit = clarity_tx.block_limit()?;
+        let start = |limit: u64, max: u64, before: u64| before.max(limit.saturating_sub(max));
+        let start_cost = ExecutionCost {
+            write_length: start(
+                block_limit.write_length,
+                max_tx_cost.write_length,
+                cost_before.write_length,
+            ),
+            write_count: start(
+                block_limit.write_count,
+                max_tx_cost.write_count,
+                cost_before.write_count,
+            ),
+            read_length: start(
+                block_limit.read_length,
+                max_tx_cost.read_length,
+                cost_before.read_length,
+            ),
+            read_count: start(
+                block_limit.read_count,
+                max_tx_cost.read_count,
+                cost_before.read_count,
+            ),
+            runtime: start(
+                block_limit.runtime,
+                max_tx_cost.runtime,
+                cost_before.runtime,
+            ),
+        };
+        if start_cost == *cost_before {
+            None
+        } else {
+            Some(start_cost)
         }
     }
 
@@ -1298,6 +1405,47 @@ impl StacksBlockBuilder {
         }
     }
 
+    /// Check that all of a mempool bundle's transactions fit in what is left of the block, by
+    /// their lengths and by the costs `estimator` estimates for them.  Transactions without an
+    /// estimate are left out of the cost check.  Returns why the bundle does not fit otherwise.
+    fn check_bundle_fits(
+        &self,
+        clarity_tx: &ClarityTx,
+        bundle: &[MemPoolTxInfo],
+        block_limit: &ExecutionCost,
+        limit_behavior: &BlockLimitFunction,
+        estimator: &dyn CostEstimator,
+        epoch_id: &StacksEpochId,
+    ) -> Result<(), String> {
+        if *limit_behavior != BlockLimitFunction::NO_LIMIT_HIT {
+            return Err("Bundles are not mined once the block limit is hit.".to_string());
+        }
+
+        let bundle_len: u64 = bundle.iter().map(|member| member.metadata.len).sum();
+        if self.bytes_so_far + bundle_len >= MAX_EPOCH_SIZE.into() {
+            return Err(format!(
+                "Bundle of {} bytes does not fit in the block.",
+                bundle_len
+            ));
+        }
+
+        let mut cost = clarity_tx.cost_so_far();
+        for member in bundle.iter() {
+            if let Ok(estimate) = estimator.estimate_cost(&member.tx.payload, epoch_id) {
+                if cost.add(&estimate).is_err() {
+                    return Err("Bundle's estimated cost overflows.".to_string());
+                }
+            }
+        }
+        if cost.exceeds(block_limit) {
+            return Err(format!(
+                "Bundle's estimated cost does not fit in the block: {} exceeds {}.",
+                &cost, block_limit
+            ));
+        }
+        Ok(())
+    }
+
     /// Append a transaction if doing so won't exceed the epoch data size.
     /// Errors out if we exceed budget, or the transaction is invalid.
     fn try_mine_tx_with_len(
@@ -1310,6 +1458,13 @@ impl StacksBlockBuilder {
         if self.bytes_so_far + tx_len >= MAX_EPOCH_SIZE.into() {
             return TransactionResult::skipped_due_to_error(&tx, Error::BlockTooBigError);
         }
+        // blocks which publish contracts in a Clarity version the epoch does not support are
+        // invalid
+        if let Err(reason) =
+            StacksBlock::validate_transaction_clarity_version(tx, clarity_tx.get_epoch())
+        {
+            return TransactionResult::error(&tx, Error::InvalidStacksTransaction(reason, false));
+        }
 
         match limit_behavior {
             BlockLimitFunction::CONTRACT_LIMIT_HIT => {
@@ -1357,9 +1512,59 @@ impl StacksBlockBuilder {
                 );
             }
 
+            // if the miner caps what one transaction may cost, charge it from where it would
+            // run out of budget at the cap, and then charge the block what it really cost
+            let cost_before = clarity_tx.cost_so_far();
+            let capped_start_cost = self.capped_tx_start_cost(clarity_tx, &cost_before);
+            if let Some(ref start_cost) = capped_start_cost {
+                clarity_tx.reset_cost(start_cost.clone());
+            }
+
             let (fee, receipt) = match StacksChainState::process_transaction(clarity_tx, tx, quiet)
             {
-                Ok((fee, receipt)) => (fee, receipt),
+                Ok((fee, receipt)) => {
+                    if capped_start_cost.is_some() {
+                        let mut cost_after = cost_before.clone();
+                        cost_after
+                            .add(&receipt.execution_cost)
+                            .expect("BUG: transaction cost overflowed the block limit");
+                        clarity_tx.reset_cost(cost_after);
+                    }
+                    (fee, receipt)
+                }
+                Err(Error::CostOverflowError(start_cost, cost_after, total_budget))
+                    if capped_start_cost.is_some() =>
+                {
+                    clarity_tx.reset_cost(cost_before.clone());
+                    // did the transaction run out of its own budget, or of the block's?
+                    let mut tx_cost = cost_after.clone();
+                    let mut block_cost = cost_before.clone();
+                    let fits_block = tx_cost.sub(&start_cost).is_ok()
+                        && block_cost.add(&tx_cost).is_ok()
+                        && !block_cost.exceeds(&total_budget);
+                    if fits_block {
+                        warn!(
+                            "Transaction {} costs more than the miner allows one transaction; skipping",
+                            tx.txid()
+                        );
+                        return TransactionResult::skipped(
+                            &tx,
+                            format!(
+                                "Transaction costs more than the miner's per-transaction limit of {}.",
+                                self.max_tx_cost
+                                    .as_ref()
+                                    .expect("BUG: capped transaction without a cap")
+                            ),
+                        );
+                    }
+                    warn!(
+                        "Transaction {} reached block cost {}; budget was {}",
+                        tx.txid(),
+                        &block_cost,
+                        &total_budget
+                    );
+                    return TransactionResult::skipped_due_to_error(&tx, Error::BlockTooBigError);
+                }
                 Err(e) => match e {
                     Error::CostOverflowError(cost_before, cost_after, total_budget) => {
                         clarity_tx.reset_cost(cost_before.clone());
@@ -1538,8 +1743,12 @@ impl StacksBlockBuilder {
             .microblock_tx_receipts
             .iter_mut()
             .chain(self.tx_receipts.iter_mut());
-        let withdrawal_tree =
-            create_withdrawal_merkle_tree(all_receipts_iter, self.header.total_work.work);
+        let (withdrawal_tree, _) = create_withdrawal_merkle_tree_with_policy(
+            &self.withdrawal_policy,
+            &mut self.withdrawals_released,
+            all_receipts_iter,
+            self.header.total_work.work,
+        );
         let withdrawal_merkle_root = withdrawal_tree.root();
         self.header.withdrawal_merkle_root = withdrawal_merkle_root;
 
@@ -1718,6 +1927,8 @@ impl StacksBlockBuilder {
 
         let burn_tip_height = burn_tip_info.block_height as u32;
         let burn_tip = burn_tip_info.burn_header_hash;
+        let burn_tip_handle =
+            SortitionHandleConn::open_reader(burn_dbconn, &burn_tip_info.sortition_id)?;
 
         let parent_microblocks = if StacksChainState::block_crosses_epoch_boundary(
             chainstate.db(),
@@ -1773,6 +1984,7 @@ impl StacksBlockBuilder {
             clarity_instance,
             burn_tip,
             burn_tip_height: burn_tip_height + 1,
+            burn_tip_handle,
             parent_microblocks,
             mainnet,
         })
@@ -1792,6 +2004,13 @@ impl StacksBlockBuilder {
         burn_dbconn: &'a SortitionDBConn,
         info: &'b mut MinerEpochInfo<'a>,
     ) -> Result<(ClarityTx<'b, 'b>, ExecutionCost), Error> {
+        self.withdrawals_released = StacksChainState::get_released_withdrawals(
+            &mut info.chainstate_tx,
+            &self.chain_tip.index_block_hash(),
+            self.header.total_work.work,
+        )?;
+        self.withdrawal_policy = info.chainstate_tx.withdrawal_policy.clone();
+
         let SetupBlockResult {
             clarity_tx,
             microblock_execution_cost,
@@ -1803,7 +2022,7 @@ impl StacksBlockBuilder {
         } = StacksChainState::setup_block(
             &mut info.chainstate_tx,
             info.clarity_instance,
-            burn_dbconn,
+            &info.burn_tip_handle,
             burn_dbconn.conn(),
             &self.chain_tip,
             info.burn_tip,
@@ -1991,8 +2210,39 @@ impl StacksBlockBuilder {
         Ok(builder)
     }
 
-    #[cfg(test)]
-    /// Used only for testing. Standard anchor blocks only confirm transactions from previous microblocks.
+    /// Execute `tx` the way the miner would when building a block on top of
+    /// `parent_stacks_header` -- through `try_mine_tx` -- and then throw the block away, so
+    /// nothing is persisted.  The outer `Result` is for failures to set up the block; the inner
+    /// one is the transaction's outcome.
+    pub fn simulate_tx(
+        chainstate: &mut StacksChainState,
+        burn_dbconn: &SortitionDBConn,
+        parent_stacks_header: &StacksHeaderInfo,
+        tx: &StacksTransaction,
+    ) -> Result<Result<TransactionSuccess, Error>, Error> {
+        let mut builder = StacksBlockBuilder::make_block_builder(
+            chainstate.mainnet,
+            parent_stacks_header,
+            VRFProof::empty(),
+            0,
+            Hash160([0u8; 20]),
+            &MessageSignatureList::empty(),
+        )?;
+        let mut miner_epoch_info = builder.pre_epoch_begin(chainstate, burn_dbconn)?;
+        let (mut epoch_tx, _) = builder.epoch_begin(burn_dbconn, &mut miner_epoch_info)?;
+
+        let result = match builder.try_mine_tx(&mut epoch_tx, tx) {
+            Ok(TransactionResult::Success(success)) => Ok(success),
+            Ok(TransactionResult::ProcessingError(TransactionError { error, .. }))
+            | Ok(TransactionResult::Skipped(TransactionSkipped { error, .. }))
+            | Err(error) => Err(error),
+        };
+        epoch_tx.rollback_block();
+        Ok(result)
+    }
+
+    /// Used only for testing and benchmarking. Standard anchor blocks only confirm transactions
+    /// from previous microblocks.
     /// Given access to the mempool, mine an anchored block with no more than the given execution cost.
     ///   returns the assembled block, the consumed execution budget, and the block size.
     pub fn build_anchored_block(
@@ -2022,7 +2272,6 @@ impl StacksBlockBuilder {
         .map(|r| (r.block, r.block_execution_cost, r.block_size))
     }
 
-    #[cfg(test)]
     pub fn build_anchored_block_full_info(
         chainstate_handle: &StacksChainState, // not directly used; used as a handle to open other chainstates
         burn_dbconn: &SortitionDBConn,
@@ -2037,6 +2286,7 @@ impl StacksBlockBuilder {
     ) -> Result<AssembledBlockInfo, Error> {
         let mempool_settings = settings.mempool_settings;
         let max_miner_time_ms = settings.max_miner_time_ms;
+        let clock = settings.clock;
 
         if let TransactionPayload::Coinbase(..) = coinbase_tx.payload {
         } else {
@@ -2057,6 +2307,7 @@ impl StacksBlockBuilder {
         );
 
         let (mut chainstate, _) = chainstate_handle.reopen()?;
+        let contract_publish_policy = chainstate.get_contract_publish_policy().clone();
 
         let mut builder = StacksBlockBuilder::make_block_builder(
             chainstate.mainnet,
@@ -2067,7 +2318,7 @@ impl StacksBlockBuilder {
             &MessageSignatureList::empty(),
         )?;
 
-        let ts_start = get_epoch_time_ms();
+        let ts_start = clock.now_ms();
 
         let mut miner_epoch_info = builder.pre_epoch_begin(&mut chainstate, burn_dbconn)?;
 
@@ -2078,6 +2329,11 @@ impl StacksBlockBuilder {
         let block_limit = epoch_tx
             .block_limit()
             .expect("Failed to obtain block limit from miner's block connection");
+        builder.set_max_tx_cost(
+            settings
+                .max_tx_cost_fraction
+                .map(|fraction| max_tx_cost(&block_limit, fraction)),
+        );
 
         let mut tx_events = Vec::new();
         tx_events.push(
@@ -2120,7 +2376,7 @@ impl StacksBlockBuilder {
                         if block_limit_hit == BlockLimitFunction::LIMIT_REACHED {
                             return Ok(None);
                         }
-                        if get_epoch_time_ms() >= deadline {
+                        if clock.now_ms() >= deadline {
                             debug!("Miner mining time exceeded ({} ms)", max_miner_time_ms);
                             return Ok(None);
                         }
@@ -2173,6 +2429,33 @@ impl StacksBlockBuilder {
                         considered.insert(txinfo.tx.txid());
                         num_considered += 1;
 
+                        if !contract_publish_policy.admits(&txinfo.tx) {
+                            return Ok(Some(
+                                TransactionResult::skipped(
+                                    &txinfo.tx,
+                                    "Contract publish not allowed by policy.".to_string(),
+                                )
+                                .convert_to_event(),
+                            ));
+                        }
+
+                        // a bundle is mined whole or not at all, so it must all fit
+                        if !to_consider.bundle.is_empty() {
+                            if let Err(reason) = builder.check_bundle_fits(
+                                epoch_tx,
+                                &to_consider.bundle,
+                                &block_limit,
+                                &block_limit_hit,
+                                estimator,
+                                &stacks_epoch_id,
+                            ) {
+                                return Ok(Some(
+                                    TransactionResult::skipped(&txinfo.tx, reason)
+                                        .convert_to_event(),
+                                ));
+                            }
+                        }
+
                         let tx_result = builder.try_mine_tx_with_len(
                             epoch_tx,
                             &txinfo.tx,
@@ -2283,7 +2566,7 @@ impl StacksBlockBuilder {
         let size = builder.bytes_so_far;
         let consumed = builder.epoch_finish(epoch_tx);
 
-        let ts_end = get_epoch_time_ms();
+        let ts_end = clock.now_ms();
 
         if let Some(observer) = event_observer {
             observer.mined_block_event(
@@ -2322,6 +2605,14 @@ impl StacksBlockBuilder {
         })
     }
 
+    /// Mine an anchored block containing only the coinbase.  Mempool transactions are mined into
+    /// the microblock stream that follows it.
+    ///
+    /// Burnchain-derived operations (asset registrations and deposits) are not subject to miner
+    /// policy: `epoch_begin` applies every such operation since the parent block, against a
+    /// freshly-reset block budget, before anything else is mined.  They therefore always land in
+    /// the next anchored block, however congested the mempool is, and there is no need to
+    /// reserve budget for them here.
     pub fn build_empty_anchored_block(
         chainstate_handle: &StacksChainState, // not directly used; used as a handle to open other chainstates
         burn_dbconn: &SortitionDBConn,
@@ -2566,12 +2857,16 @@ impl Proposal {
         // Setup the MinerEpochInfo that would normally be done by pre_epoch_begin
         // but we must do so manually because we use the provided parameters in the proposal
         let (chainstate_tx, clarity_instance) = chainstate.chainstate_tx_begin()?;
+        // a subnet's sortition ids are its burn header hashes
+        let burn_tip_handle =
+            SortitionHandleConn::open_reader(burn_dbconn, &SortitionId::stubbed(&self.burn_tip))?;
 
         let mut miner_epoch_info = MinerEpochInfo {
             chainstate_tx,
             clarity_instance,
             burn_tip: self.burn_tip,
             burn_tip_height: self.burn_tip_height,
+            burn_tip_handle,
             parent_microblocks: self.microblocks_confirmed.clone(),
             mainnet: self.is_mainnet,
         };
@@ -2742,7 +3037,9 @@ pub mod test {
     };
     use crate::chainstate::burn::*;
     use crate::chainstate::coordinator::Error as CoordinatorError;
+    use crate::chainstate::stacks::db::activations::FeatureActivations;
     use crate::chainstate::stacks::db::blocks::test::store_staging_block;
+    use crate::chainstate::stacks::db::coinbase_schedule::{CoinbaseRecipient, CoinbaseSchedule};
     use crate::chainstate::stacks::db::test::*;
     use crate::chainstate::stacks::db::*;
     use crate::chainstate::stacks::test::codec_all_transactions;
@@ -2758,10 +3055,11 @@ pub mod test {
     use stacks_common::util::sleep_ms;
     use stacks_common::util::vrf::VRFProof;
 
+    use crate::chainstate::stacks::boot::{SUBNET_TOKEN_NAME, TIME_ORACLE_NAME};
     use crate::cost_estimates::metrics::UnitMetric;
     use crate::cost_estimates::UnitEstimator;
     use crate::types::chainstate::SortitionId;
-    use crate::util_lib::boot::boot_code_addr;
+    use crate::util_lib::boot::{boot_code_addr, boot_code_id};
 
     use super::*;
 
@@ -5027,6 +5325,376 @@ pub mod test {
         }
     }
 
+    #[test]
+    fn test_build_anchored_blocks_time_oracle_activation() {
+        let mut peer_config = TestPeerConfig::new(
+            "test_build_anchored_blocks_time_oracle_activation",
+            2040,
+            2041,
+        );
+        peer_config.feature_activations = FeatureActivations {
+            time_oracle: Some(3),
+            ..FeatureActivations::default()
+        };
+        let mut peer = TestPeer::new(peer_config);
+
+        let chainstate_path = peer.chainstate_path.clone();
+        let contract_id = boot_code_id(TIME_ORACLE_NAME, false);
+
+        let num_blocks = 5;
+        for tenure_id in 0..num_blocks {
+            let tip =
+                SortitionDB::get_canonical_burn_chain_tip(&peer.sortdb.as_ref().unwrap().conn())
+                    .unwrap();
+
+            let (burn_ops, stacks_block, microblocks) = peer.make_tenure(
+                |ref mut miner,
+                 ref mut sortdb,
+                 ref mut chainstate,
+                 vrf_proof,
+                 ref parent_opt,
+                 ref parent_microblock_header_opt| {
+                    let parent_tip = match parent_opt {
+                        None => StacksChainState::get_genesis_header_info(chainstate.db()).unwrap(),
+                        Some(block) => {
+                            let ic = sortdb.index_conn();
+                            let snapshot =
+                                SortitionDB::get_block_snapshot_for_winning_stacks_block(
+                                    &ic,
+                                    &tip.sortition_id,
+                                    &block.block_hash(),
+                                )
+                                .unwrap()
+                                .unwrap(); // succeeds because we don't fork
+                            StacksChainState::get_anchored_block_header_info(
+                                chainstate.db(),
+                                &snapshot.consensus_hash,
+                                &snapshot.winning_stacks_block_hash,
+                            )
+                            .unwrap()
+                            .unwrap()
+                        }
+                    };
+
+                    let mut mempool =
+                        MemPoolDB::open_test(false, 0x80000000, &chainstate_path).unwrap();
+
+                    let coinbase_tx = make_coinbase(miner, tenure_id);
+
+                    let anchored_block = StacksBlockBuilder::build_anchored_block(
+                        chainstate,
+                        &sortdb.index_conn(),
+                        &mut mempool,
+                        &parent_tip,
+                        tip.total_burn,
+                        vrf_proof,
+                        Hash160([tenure_id as u8; 20]),
+                        &coinbase_tx,
+                        BlockBuilderSettings::max_value(),
+                        None,
+                    )
+                    .unwrap();
+                    (anchored_block.0, vec![])
+                },
+            );
+
+            peer.next_burnchain_block(burn_ops.clone());
+            peer.process_stacks_epoch_at_tip(&stacks_block, &microblocks);
+
+            // the block at height 3 installs the time oracle, and it and every later block
+            // record their L1 block's timestamp in it
+            let block_height = tenure_id as u64 + 1;
+            let (consensus_hash, block_bhh) =
+                SortitionDB::get_canonical_stacks_chain_tip_hash(peer.sortdb().conn()).unwrap();
+            let tip_id = StacksBlockHeader::make_index_block_hash(&consensus_hash, &block_bhh);
+            let sortdb = peer.sortdb.take().unwrap();
+            let block_time = peer.chainstate().clarity_eval_read_only_checked(
+                &sortdb.index_conn(),
+                &tip_id,
+                &contract_id,
+                &format!("(get-time-at-height u{})", block_height),
+            );
+            peer.sortdb = Some(sortdb);
+
+            if block_height < 3 {
+                assert!(block_time.is_err());
+            } else {
+                assert!(block_time.unwrap().expect_optional().is_some());
+            }
+        }
+    }
+
+    #[test]
+    fn test_build_anchored_blocks_block_limit_update() {
+        let privk = StacksPrivateKey::from_hex(
+            "42faca653724860da7a41bfcef7e6ba78db55146f6900de8cb2a9f760ffac70c01",
+        )
+        .unwrap();
+        let addr = StacksAddress::from_public_keys(
+            C32_ADDRESS_VERSION_TESTNET_SINGLESIG,
+            &AddressHashMode::SerializeP2PKH,
+            1,
+            &vec![StacksPublicKey::from_private(&privk)],
+        )
+        .unwrap();
+
+        let mut peer_config =
+            TestPeerConfig::new("test_build_anchored_blocks_block_limit_update", 2050, 2051);
+        peer_config.initial_balances = vec![(addr.to_account_principal(), 1000000000)];
+        let mut peer = TestPeer::new(peer_config);
+
+        let chainstate_path = peer.chainstate_path.clone();
+
+        // the L1 contract sets a block limit which no contract-publish fits in, for the blocks
+        //  whose parents were mined two burn blocks after the update.
+        let first_burn_height = {
+            let sn =
+                SortitionDB::get_canonical_burn_chain_tip(&peer.sortdb.as_ref().unwrap().conn())
+                    .unwrap();
+            sn.block_height
+        };
+        let set_block_limit_op = SetBlockLimitOp {
+            txid: Txid([0x58; 32]),
+            burn_header_hash: BurnchainHeaderHash([0u8; 32]),
+            activation_height: first_burn_height + 3,
+            block_limit: ExecutionCost {
+                write_length: 1,
+                ..ExecutionCost::max_value()
+            },
+        };
+
+        let mut sender_nonce = 0;
+        let mut pending_tx = false;
+
+        let num_blocks = 5;
+        for tenure_id in 0..num_blocks {
+            let tip =
+                SortitionDB::get_canonical_burn_chain_tip(&peer.sortdb.as_ref().unwrap().conn())
+                    .unwrap();
+
+            let (mut burn_ops, stacks_block, microblocks) = peer.make_tenure(
+                |ref mut miner,
+                 ref mut sortdb,
+                 ref mut chainstate,
+                 vrf_proof,
+                 ref parent_opt,
+                 ref parent_microblock_header_opt| {
+                    let parent_tip = match parent_opt {
+                        None => StacksChainState::get_genesis_header_info(chainstate.db()).unwrap(),
+                        Some(block) => {
+                            let ic = sortdb.index_conn();
+                            let snapshot =
+                                SortitionDB::get_block_snapshot_for_winning_stacks_block(
+                                    &ic,
+                                    &tip.sortition_id,
+                                    &block.block_hash(),
+                                )
+                                .unwrap()
+                                .unwrap(); // succeeds because we don't fork
+                            StacksChainState::get_anchored_block_header_info(
+                                chainstate.db(),
+                                &snapshot.consensus_hash,
+                                &snapshot.winning_stacks_block_hash,
+                            )
+                            .unwrap()
+                            .unwrap()
+                        }
+                    };
+
+                    let parent_header_hash = parent_tip.anchored_header.block_hash();
+                    let parent_consensus_hash = parent_tip.consensus_hash.clone();
+
+                    let mut mempool =
+                        MemPoolDB::open_test(false, 0x80000000, &chainstate_path).unwrap();
+
+                    let coinbase_tx = make_coinbase(miner, tenure_id);
+
+                    if tenure_id > 0 && !pending_tx {
+                        let contract_tx = make_user_contract_publish(
+                            &privk,
+                            sender_nonce,
+                            200,
+                            &format!("hello-world-{}", tenure_id),
+                            "(define-data-var x int 0)",
+                        );
+                        mempool
+                            .submit(
+                                chainstate,
+                                &parent_consensus_hash,
+                                &parent_header_hash,
+                                &contract_tx,
+                                None,
+                                &ExecutionCost::max_value(),
+                                &StacksEpochId::Epoch20,
+                            )
+                            .unwrap();
+                        pending_tx = true;
+                    }
+
+                    let anchored_block = StacksBlockBuilder::build_anchored_block(
+                        chainstate,
+                        &sortdb.index_conn(),
+                        &mut mempool,
+                        &parent_tip,
+                        tip.total_burn,
+                        vrf_proof,
+                        Hash160([tenure_id as u8; 20]),
+                        &coinbase_tx,
+                        BlockBuilderSettings::max_value(),
+                        None,
+                    )
+                    .unwrap();
+                    (anchored_block.0, vec![])
+                },
+            );
+
+            if tenure_id == 0 {
+                burn_ops.push(BlockstackOperationType::SetBlockLimit(
+                    set_block_limit_op.clone(),
+                ));
+            }
+
+            peer.next_burnchain_block(burn_ops.clone());
+            peer.process_stacks_epoch_at_tip(&stacks_block, &microblocks);
+
+            if tenure_id == 0 {
+                assert_eq!(stacks_block.txs.len(), 1);
+                continue;
+            }
+
+            // this block's parent was mined at burn height first_burn_height + tenure_id
+            if first_burn_height + (tenure_id as u64) < set_block_limit_op.activation_height {
+                // the contract-publish was mined under the epoch's block limit
+                assert_eq!(stacks_block.txs.len(), 2);
+                assert!(matches!(
+                    stacks_block.txs[1].payload,
+                    TransactionPayload::SmartContract(..)
+                ));
+                sender_nonce += 1;
+                pending_tx = false;
+            } else {
+                // the contract-publish doesn't fit in the new block limit
+                assert_eq!(stacks_block.txs.len(), 1);
+                assert!(pending_tx);
+            }
+        }
+    }
+
+    #[test]
+    fn test_build_anchored_blocks_coinbase_schedule() {
+        let treasury =
+            StacksAddress::from_string("ST1PQHQKV0RJXZFY1DGX8MNSNYVE3VGZJSRTPGZGM").unwrap();
+
+        let mut peer_config =
+            TestPeerConfig::new("test_build_anchored_blocks_coinbase_schedule", 2052, 2053);
+        peer_config.coinbase_schedule = CoinbaseSchedule {
+            amount_per_block: 1000,
+            halving_interval: 0,
+            recipient: CoinbaseRecipient::Fixed(treasury.clone()),
+        };
+        let mut peer = TestPeer::new(peer_config);
+
+        let chainstate_path = peer.chainstate_path.clone();
+        let contract_id = boot_code_id(SUBNET_TOKEN_NAME, false);
+
+        let num_blocks = 6;
+        for tenure_id in 0..num_blocks {
+            let tip =
+                SortitionDB::get_canonical_burn_chain_tip(&peer.sortdb.as_ref().unwrap().conn())
+                    .unwrap();
+
+            let (burn_ops, stacks_block, microblocks) = peer.make_tenure(
+                |ref mut miner,
+                 ref mut sortdb,
+                 ref mut chainstate,
+                 vrf_proof,
+                 ref parent_opt,
+                 ref parent_microblock_header_opt| {
+                    let parent_tip = match parent_opt {
+                        None => StacksChainState::get_genesis_header_info(chainstate.db()).unwrap(),
+                        Some(block) => {
+                            let ic = sortdb.index_conn();
+                            let snapshot =
+                                SortitionDB::get_block_snapshot_for_winning_stacks_block(
+                                    &ic,
+                                    &tip.sortition_id,
+                                    &block.block_hash(),
+                                )
+                                .unwrap()
+                                .unwrap(); // succeeds because we don't fork
+                            StacksChainState::get_anchored_block_header_info(
+                                chainstate.db(),
+                                &snapshot.consensus_hash,
+                                &snapshot.winning_stacks_block_hash,
+                            )
+                            .unwrap()
+                            .unwrap()
+                        }
+                    };
+
+                    let mut mempool =
+                        MemPoolDB::open_test(false, 0x80000000, &chainstate_path).unwrap();
+
+                    let coinbase_tx = make_coinbase(miner, tenure_id);
+
+                    let anchored_block = StacksBlockBuilder::build_anchored_block(
+                        chainstate,
+                        &sortdb.index_conn(),
+                        &mut mempool,
+                        &parent_tip,
+                        tip.total_burn,
+                        vrf_proof,
+                        Hash160([tenure_id as u8; 20]),
+                        &coinbase_tx,
+                        BlockBuilderSettings::max_value(),
+                        None,
+                    )
+                    .unwrap();
+                    (anchored_block.0, vec![])
+                },
+            );
+
+            peer.next_burnchain_block(burn_ops.clone());
+            peer.process_stacks_epoch_at_tip(&stacks_block, &microblocks);
+
+            // the block at height h pays out the reward of the block at height
+            // h - MINER_REWARD_MATURITY - 1, minting its coinbase in the subnet token
+            let block_height = tenure_id as u64 + 1;
+            let matured_blocks = block_height.saturating_sub(MINER_REWARD_MATURITY + 1);
+
+            let (consensus_hash, block_bhh) =
+                SortitionDB::get_canonical_stacks_chain_tip_hash(peer.sortdb().conn()).unwrap();
+            let tip_id = StacksBlockHeader::make_index_block_hash(&consensus_hash, &block_bhh);
+            let sortdb = peer.sortdb.take().unwrap();
+            let token_balance = peer
+                .chainstate()
+                .clarity_eval_read_only_checked(
+                    &sortdb.index_conn(),
+                    &tip_id,
+                    &contract_id,
+                    &format!("(get-balance '{})", &treasury),
+                )
+                .unwrap()
+                .expect_result_ok()
+                .expect_u128();
+            let stx_balance = peer
+                .chainstate()
+                .clarity_eval_read_only_checked(
+                    &sortdb.index_conn(),
+                    &tip_id,
+                    &contract_id,
+                    &format!("(stx-get-balance '{})", &treasury),
+                )
+                .unwrap()
+                .expect_u128();
+            peer.sortdb = Some(sortdb);
+
+            assert_eq!(token_balance, 1000 * u128::from(matured_blocks));
+            // no STX are minted, so none can be withdrawn from the L1 contract's escrow
+            assert_eq!(stx_balance, 0);
+        }
+    }
+
     #[test]
     fn test_build_anchored_blocks_stx_transfers_single() {
         let privk = StacksPrivateKey::from_hex(
@@ -6426,6 +7094,161 @@ pub mod test {
         }
     }
 
+    #[test]
+    fn test_build_anchored_blocks_max_tx_cost_fraction() {
+        let privk = StacksPrivateKey::from_hex(
+            "42faca653724860da7a41bfcef7e6ba78db55146f6900de8cb2a9f760ffac70c01",
+        )
+        .unwrap();
+        let privk_expensive = StacksPrivateKey::from_hex(
+            "f67c7437f948ca1834602b28595c12ac744f287a4efaf70d437042a6afed81bc01",
+        )
+        .unwrap();
+        let initial_balances = [&privk, &privk_expensive]
+            .iter()
+            .map(|pk| {
+                let addr = StacksAddress::from_public_keys(
+                    C32_ADDRESS_VERSION_TESTNET_SINGLESIG,
+                    &AddressHashMode::SerializeP2PKH,
+                    1,
+                    &vec![StacksPublicKey::from_private(pk)],
+                )
+                .unwrap();
+                (addr.to_account_principal(), 100000000000)
+            })
+            .collect();
+
+        let mut peer_config = TestPeerConfig::new(
+            "test_build_anchored_blocks_max_tx_cost_fraction",
+            2012,
+            2013,
+        );
+        peer_config.initial_balances = initial_balances;
+        let mut peer = TestPeer::new(peer_config);
+        let chainstate_path = peer.chainstate_path.clone();
+
+        let recipient =
+            StacksAddress::from_string("ST1RFD5Q2QPK3E0F08HG9XDX7SSC7CNRS0QR0SGEV").unwrap();
+
+        for tenure_id in 0..2 {
+            let tip =
+                SortitionDB::get_canonical_burn_chain_tip(&peer.sortdb.as_ref().unwrap().conn())
+                    .unwrap();
+
+            let (burn_ops, stacks_block, microblocks) = peer.make_tenure(
+                |ref mut miner,
+                 ref mut sortdb,
+                 ref mut chainstate,
+                 vrf_proof,
+                 ref parent_opt,
+                 ref parent_microblock_header_opt| {
+                    let parent_tip = match parent_opt {
+                        None => StacksChainState::get_genesis_header_info(chainstate.db()).unwrap(),
+                        Some(block) => {
+                            let ic = sortdb.index_conn();
+                            let snapshot =
+                                SortitionDB::get_block_snapshot_for_winning_stacks_block(
+                                    &ic,
+                                    &tip.sortition_id,
+                                    &block.block_hash(),
+                                )
+                                .unwrap()
+                                .unwrap(); // succeeds because we don't fork
+                            StacksChainState::get_anchored_block_header_info(
+                                chainstate.db(),
+                                &snapshot.consensus_hash,
+                                &snapshot.winning_stacks_block_hash,
+                            )
+                            .unwrap()
+                            .unwrap()
+                        }
+                    };
+
+                    let parent_header_hash = parent_tip.anchored_header.block_hash();
+                    let parent_consensus_hash = parent_tip.consensus_hash.clone();
+                    let coinbase_tx = make_coinbase(miner, tenure_id);
+
+                    let mut mempool =
+                        MemPoolDB::open_test(false, 0x80000000, &chainstate_path).unwrap();
+
+                    let mut expensive_txid = None;
+                    if tenure_id > 0 {
+                        // far more writes than 0.1% of the block limit allows one transaction
+                        let mut contract = vec![];
+                        for i in 0..100 {
+                            contract.push(format!("(define-data-var var-{} int 0)", i));
+                        }
+                        let contract = contract.join("\n");
+
+                        let contract_tx = make_user_contract_publish(
+                            &privk_expensive,
+                            0,
+                            (2 * contract.len()) as u64,
+                            "hello-world",
+                            &contract,
+                        );
+                        let stx_transfer = make_user_stacks_transfer(
+                            &privk,
+                            0,
+                            300,
+                            &recipient.to_account_principal(),
+                            1,
+                        );
+                        for tx in [&contract_tx, &stx_transfer] {
+                            mempool
+                                .submit(
+                                    chainstate,
+                                    &parent_consensus_hash,
+                                    &parent_header_hash,
+                                    tx,
+                                    None,
+                                    &ExecutionCost::max_value(),
+                                    &StacksEpochId::Epoch20,
+                                )
+                                .unwrap();
+                        }
+                        expensive_txid = Some(contract_tx.txid());
+                    }
+
+                    let anchored_block = StacksBlockBuilder::build_anchored_block(
+                        chainstate,
+                        &sortdb.index_conn(),
+                        &mut mempool,
+                        &parent_tip,
+                        tip.total_burn,
+                        vrf_proof,
+                        Hash160([tenure_id as u8; 20]),
+                        &coinbase_tx,
+                        BlockBuilderSettings {
+                            max_tx_cost_fraction: Some(0.001),
+                            ..BlockBuilderSettings::limited()
+                        },
+                        None,
+                    )
+                    .unwrap();
+
+                    // the expensive transaction is skipped, not dropped
+                    if let Some(txid) = expensive_txid {
+                        assert!(MemPoolDB::get_tx(&mempool.db, &txid).unwrap().is_some());
+                    }
+                    (anchored_block.0, vec![])
+                },
+            );
+
+            peer.next_burnchain_block(burn_ops.clone());
+            peer.process_stacks_epoch_at_tip(&stacks_block, &microblocks);
+
+            if tenure_id > 0 {
+                // the contract publish was not mined, but the stx-transfer was
+                assert_eq!(stacks_block.txs.len(), 2);
+                match stacks_block.txs[1].payload {
+                    TransactionPayload::TokenTransfer(..) => {}
+                    ref payload => panic!("Expected a token transfer, got {:?}", payload),
+                }
+            }
+        }
+    }
+
     #[test]
     fn test_build_anchored_blocks_multiple_chaintips() {
         let mut privks = vec![];
@@ -6948,6 +7771,11 @@ pub mod test {
             get_bulk_initial_balances: None,
             get_bulk_initial_names: None,
             get_bulk_initial_namespaces: None,
+            coinbase_schedule: CoinbaseSchedule::default(),
+            deposit_confirmations: 0,
+            withdrawal_policy: WithdrawalPolicy::default(),
+            feature_activations: FeatureActivations::default(),
+            check_genesis_root_hash: true,
         };
 
         StacksChainState::open_and_exec(mainnet, chain_id, &path, Some(&mut boot_data), None)
@@ -32,6 +32,7 @@ use crate::chainstate::burn::operations::LeaderBlockCommitOp;
 use crate::chainstate::burn::ConsensusHash;
 use crate::chainstate::stacks::db::accounts::MinerReward;
 use crate::chainstate::stacks::db::blocks::MemPoolRejection;
+use crate::chainstate::stacks::db::withdrawal_policy::{QueuedWithdrawal, WithdrawalPolicy};
 use crate::chainstate::stacks::db::StacksHeaderInfo;
 use crate::chainstate::stacks::index::Error as marf_error;
 use crate::clarity_vm::clarity::Error as clarity_error;
@@ -873,6 +874,9 @@ pub struct StacksBlockBuilder {
     /// Receipts of confirmed microblocks. These must be included
     /// when building the withdrawal merkle tree.
     microblock_tx_receipts: Vec<StacksTransactionReceipt>,
+    /// Withdrawals held back by an ancestor, which this block's withdrawal tree must include
+    withdrawals_released: Vec<QueuedWithdrawal>,
+    withdrawal_policy: WithdrawalPolicy,
     anchored_done: bool,
     bytes_so_far: u64,
     prev_microblock_header: StacksMicroblockHeader,
@@ -882,6 +886,8 @@ pub struct StacksBlockBuilder {
     parent_header_hash: BlockHeaderHash,
     parent_microblock_hash: Option<BlockHeaderHash>,
     miner_id: usize,
+    /// the most a single transaction in the anchored block may cost, if the miner caps it
+    max_tx_cost: Option<ExecutionCost>,
 }
 
 // maximum amount of data a leader can send during its epoch (2MB)
@@ -32,7 +32,11 @@ use rusqlite::Row;
 use rusqlite::Transaction;
 use rusqlite::{Connection, OpenFlags, NO_PARAMS};
 
+use crate::chainstate::burn::db::sortdb::SortitionDB;
+use crate::chainstate::stacks::db::StacksChainState;
 use crate::chainstate::stacks::index::{storage::TrieFileStorage, MarfTrieId};
+use crate::chainstate::stacks::StacksBlockHeader;
+use crate::clarity_vm::clarity::ClarityConnection;
 use crate::core::SUBNET_BLOCK_LIMIT;
 use crate::util_lib::db::sqlite_open;
 use crate::util_lib::db::FromColumn;
@@ -53,9 +57,9 @@ use clarity::{
     vm::database::{
         BurnStateDB, ClarityDatabase, HeadersDB, STXBalance, SqliteConnection, NULL_BURN_STATE_DB,
     },
-    vm::errors::{Error, InterpreterResult, RuntimeErrorType},
+    vm::errors::{Error, InterpreterError, InterpreterResult, RuntimeErrorType},
     vm::eval_all,
-    vm::types::{OptionalData, PrincipalData, QualifiedContractIdentifier},
+    vm::types::{OptionalData, PrincipalData, QualifiedContractIdentifier, StandardPrincipalData},
     vm::ClarityVersion,
     vm::ContractContext,
     vm::ContractName,
@@ -70,6 +74,7 @@ use stacks_common::types::chainstate::*;
 use crate::chainstate::stacks::boot::{
     BOOT_CODE_COSTS, BOOT_CODE_COSTS_2, BOOT_CODE_COSTS_2_TESTNET, BOOT_CODE_COSTS_3,
     BOOT_CODE_COST_VOTING_MAINNET, BOOT_CODE_COST_VOTING_TESTNET, BOOT_CODE_SUBNET,
+    BOOT_CODE_TIME_ORACLE, TIME_ORACLE_NAME,
 };
 
 use crate::util_lib::boot::{boot_code_addr, boot_code_id};
@@ -101,19 +106,21 @@ use stacks_common::types::chainstate::VRFSeed;
 use std::str::FromStr;
 
 lazy_static! {
-    pub static ref STACKS_BOOT_CODE_MAINNET_2_1: [(&'static str, &'static str); 5] = [
+    pub static ref STACKS_BOOT_CODE_MAINNET_2_1: [(&'static str, &'static str); 6] = [
         ("subnet", &BOOT_CODE_SUBNET),
         ("costs", BOOT_CODE_COSTS),
         ("cost-voting", BOOT_CODE_COST_VOTING_MAINNET),
         ("costs-2", BOOT_CODE_COSTS_2),
         ("costs-3", BOOT_CODE_COSTS_3),
+        (TIME_ORACLE_NAME, BOOT_CODE_TIME_ORACLE),
     ];
-    pub static ref STACKS_BOOT_CODE_TESTNET_2_1: [(&'static str, &'static str); 5] = [
+    pub static ref STACKS_BOOT_CODE_TESTNET_2_1: [(&'static str, &'static str); 6] = [
         ("subnet", &BOOT_CODE_SUBNET),
         ("costs", BOOT_CODE_COSTS),
         ("cost-voting", &BOOT_CODE_COST_VOTING_TESTNET),
         ("costs-2", BOOT_CODE_COSTS_2_TESTNET),
         ("costs-3", BOOT_CODE_COSTS_3),
+        (TIME_ORACLE_NAME, BOOT_CODE_TIME_ORACLE),
     ];
 }
 
@@ -144,7 +151,8 @@ where command is one of:
   eval_at_block      like `eval_at_chaintip`, but accepts a index-block-hash to evaluate at,
                      must be passed eval string via stdin.
   eval_raw           to typecheck and evaluate an expression without a contract or database context.
-  repl               to typecheck and evaluate expressions in a stdin/stdout loop.
+  repl               to typecheck and evaluate expressions in a stdin/stdout loop.  Given a
+                     subnet node's working directory, evaluates at its canonical chain tip.
   execute            to execute a public function of a defined contract.
   generate_address   to generate a random Stacks public address for testing purposes.
 ",
@@ -931,6 +939,187 @@ pub fn add_serialized_output(result: &mut serde_json::Value, value: Value) {
     result["output_serialized"] = serde_json::to_value(result_raw.as_str()).unwrap();
 }
 
+const CHAINSTATE_REPL_HELP: &str =
+    "Enter a Clarity expression to evaluate it at the chain tip, or one of:
+  ::help                        to show this message.
+  ::set_tx_sender <principal>   to set the `tx-sender` of later expressions.
+  ::get_costs <expression>      to evaluate an expression and report its execution cost.
+Nothing is written to the chainstate: each expression's changes are discarded after it runs.";
+
+/// A line entered into the chainstate REPL
+#[derive(Debug, PartialEq)]
+enum ChainstateReplCommand {
+    Help,
+    SetTxSender(PrincipalData),
+    /// Evaluate a program, and report its cost if `costs` is set
+    Eval {
+        program: String,
+        costs: bool,
+    },
+}
+
+fn parse_chainstate_repl_command(line: &str) -> Result<ChainstateReplCommand, String> {
+    let line = line.trim();
+    if !line.starts_with("::") {
+        return Ok(ChainstateReplCommand::Eval {
+            program: line.to_string(),
+            costs: false,
+        });
+    }
+    let (command, arg) = match line.find(char::is_whitespace) {
+        Some(idx) => (&line[..idx], line[idx..].trim()),
+        None => (line, ""),
+    };
+    match command {
+        "::help" => Ok(ChainstateReplCommand::Help),
+        "::set_tx_sender" => {
+            let sender = PrincipalData::parse(arg)
+                .map_err(|e| format!("Invalid principal `{}`: {}", arg, e))?;
+            Ok(ChainstateReplCommand::SetTxSender(sender))
+        }
+        "::get_costs" if !arg.is_empty() => Ok(ChainstateReplCommand::Eval {
+            program: arg.to_string(),
+            costs: true,
+        }),
+        "::get_costs" => Err("Usage: ::get_costs <expression>".to_string()),
+        _ => Err(format!(
+            "Unknown command `{}`; enter ::help for the list of commands",
+            command
+        )),
+    }
+}
+
+/// Evaluate `program` as `sender` at the Stacks chain tip `tip`, and return its value and
+/// execution cost.  Any writes it makes are rolled back.
+fn eval_at_chainstate_tip(
+    chainstate: &mut StacksChainState,
+    sortdb: &SortitionDB,
+    tip: &StacksBlockId,
+    sender: &PrincipalData,
+    program: &str,
+) -> Result<(Value, ExecutionCost), String> {
+    let mainnet = chainstate.mainnet;
+    let chain_id = chainstate.chain_id;
+    let result = chainstate.maybe_read_only_clarity_tx(&sortdb.index_conn(), tip, |clarity_tx| {
+        let epoch = clarity_tx.get_epoch();
+        let cost_track = clarity_tx
+            .with_clarity_db_readonly(|clarity_db| {
+                LimitedCostTracker::new_mid_block(
+                    mainnet,
+                    chain_id,
+                    SUBNET_BLOCK_LIMIT,
+                    clarity_db,
+                    epoch,
+                )
+            })
+            .map_err(|_| Error::from(InterpreterError::CostContractLoadFailure))?;
+        clarity_tx.with_readonly_clarity_env(
+            mainnet,
+            chain_id,
+            SUBNETS_CLARITY_VERSION,
+            sender.clone(),
+            None,
+            cost_track,
+            |env| {
+                let value = env.eval_raw_with_rules(program, ASTRules::PrecheckSize)?;
+                Ok((value, env.global_context.cost_track.get_total()))
+            },
+        )
+    });
+    match result {
+        Ok(Some(Ok(evaluated))) => Ok(evaluated),
+        Ok(Some(Err(error))) => Err(format!("Execution error:\n{}", error)),
+        Ok(None) => Err(format!("Chain tip {} not found", tip)),
+        Err(error) => Err(format!("Chainstate error: {:?}", error)),
+    }
+}
+
+/// Evaluate expressions read from stdin at the canonical Stacks chain tip of the subnet node
+/// whose working directory is `working_dir`.  Expressions are not type-checked before they run,
+/// so that they can call the contracts deployed in the chainstate.
+#[cfg_attr(tarpaulin, skip)]
+fn chainstate_repl(working_dir: &str) {
+    let sort_db_path = format!("{}/mainnet/burnchain/sortition", working_dir);
+    let chain_state_path = format!("{}/mainnet/chainstate/", working_dir);
+
+    let db_config = friendly_expect(
+        StacksChainState::get_db_config_from_path(&chain_state_path),
+        &format!(
+            "Failed to load chainstate DB config from {}",
+            &chain_state_path
+        ),
+    );
+    let sortdb = friendly_expect(
+        SortitionDB::open(&sort_db_path, false),
+        &format!("Failed to open {}", &sort_db_path),
+    );
+    let (mut chainstate, _) = friendly_expect(
+        StacksChainState::open(
+            db_config.mainnet,
+            db_config.chain_id,
+            &chain_state_path,
+            None,
+        ),
+        &format!("Failed to open chainstate at {}", &chain_state_path),
+    );
+    let tip = friendly_expect_opt(
+        friendly_expect(
+            chainstate.get_stacks_chain_tip(&sortdb),
+            "Failed to load the canonical Stacks chain tip",
+        ),
+        "The chainstate has no Stacks blocks yet",
+    );
+    let tip_id =
+        StacksBlockHeader::make_index_block_hash(&tip.consensus_hash, &tip.anchored_block_hash);
+    println!(
+        "Evaluating at Stacks chain tip {} (height {}); enter ::help for help",
+        &tip_id, tip.height
+    );
+
+    let mut sender = PrincipalData::Standard(StandardPrincipalData::transient());
+    let mut stdout = io::stdout();
+    loop {
+        let mut buffer = String::new();
+        stdout.write(b"> ").unwrap_or_else(|e| {
+            panic!("Failed to write stdout prompt string:\n{}", e);
+        });
+        stdout.flush().unwrap_or_else(|e| {
+            panic!("Failed to flush stdout prompt string:\n{}", e);
+        });
+        match io::stdin().read_line(&mut buffer) {
+            Ok(0) => return,
+            Ok(_) => {}
+            Err(error) => {
+                eprintln!("Error reading from stdin:\n{}", error);
+                panic_test!();
+            }
+        }
+        if buffer.trim().is_empty() {
+            continue;
+        }
+
+        match parse_chainstate_repl_command(&buffer) {
+            Ok(ChainstateReplCommand::Help) => println!("{}", CHAINSTATE_REPL_HELP),
+            Ok(ChainstateReplCommand::SetTxSender(new_sender)) => {
+                println!("tx-sender set to {}", &new_sender);
+                sender = new_sender;
+            }
+            Ok(ChainstateReplCommand::Eval { program, costs }) => {
+                match eval_at_chainstate_tip(&mut chainstate, &sortdb, &tip_id, &sender, &program) {
+                    Ok((value, cost)) => {
+                        println!("{}", value);
+                        if costs {
+                            println!("{}", serde_json::to_string_pretty(&cost).unwrap());
+                        }
+                    }
+                    Err(error) => println!("{}", error),
+                }
+            }
+            Err(error) => println!("{}", error),
+        }
+    }
+}
+
 /// Returns (process-exit-code, Option<json-output>)
 pub fn invoke_command(invoked_by: &str, args: &[String]) -> (i32, Option<serde_json::Value>) {
     if args.len() < 1 {
@@ -1196,6 +1385,10 @@ pub fn invoke_command(invoked_by: &str, args: &[String]) -> (i32, Option<serde_j
             } else {
                 true
             };
+            if let Some(working_dir) = argv.get(1) {
+                chainstate_repl(working_dir);
+                return (0, None);
+            }
             let mut marf = MemoryBackingStore::new();
             let mut vm_env = OwnedEnvironment::new_free(
                 mainnet,
@@ -1884,6 +2077,39 @@ pub fn invoke_command(invoked_by: &str, args: &[String]) -> (i32, Option<serde_j
 mod test {
     use super::*;
 
+    #[test]
+    fn test_parse_chainstate_repl_command() {
+        assert_eq!(
+            parse_chainstate_repl_command("(+ 1 2)\n"),
+            Ok(ChainstateReplCommand::Eval {
+                program: "(+ 1 2)".into(),
+                costs: false,
+            })
+        );
+        assert_eq!(
+            parse_chainstate_repl_command("::help"),
+            Ok(ChainstateReplCommand::Help)
+        );
+        assert_eq!(
+            parse_chainstate_repl_command("::get_costs  (contract-call? .foo bar)\n"),
+            Ok(ChainstateReplCommand::Eval {
+                program: "(contract-call? .foo bar)".into(),
+                costs: true,
+            })
+        );
+        assert!(parse_chainstate_repl_command("::get_costs").is_err());
+
+        let sender = "ST1PQHQKV0RJXZFY1DGX8MNSNYVE3VGZJSRTPGZGM.subnet";
+        assert_eq!(
+            parse_chainstate_repl_command(&format!("::set_tx_sender {}", sender)),
+            Ok(ChainstateReplCommand::SetTxSender(
+                PrincipalData::parse(sender).unwrap()
+            ))
+        );
+        assert!(parse_chainstate_repl_command("::set_tx_sender not-a-principal").is_err());
+        assert!(parse_chainstate_repl_command("::get_balance").is_err());
+    }
+
     #[test]
     fn test_initial_alloc() {
         let db_name = format!("/tmp/db_{}", rand::thread_rng().gen::<i32>());
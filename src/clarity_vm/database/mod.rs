@@ -236,8 +236,15 @@ impl BurnStateDB for SortitionHandleTx<'_> {
     }
 
     fn get_stacks_epoch(&self, height: u32) -> Option<StacksEpoch> {
-        SortitionDB::get_stacks_epoch(self.tx(), height as u64)
-            .expect("BUG: failed to get epoch for burn block height")
+        let mut epoch = SortitionDB::get_stacks_epoch(self.tx(), height as u64)
+            .expect("BUG: failed to get epoch for burn block height")?;
+        if let Some(block_limit) =
+            SortitionDB::get_block_limit_at(self, &self.context.chain_tip, height as u64)
+                .expect("BUG: failed to get block limit for burn block height")
+        {
+            epoch.block_limit = block_limit;
+        }
+        Some(epoch)
     }
 
     fn get_stacks_epoch_by_epoch_id(&self, epoch_id: &StacksEpochId) -> Option<StacksEpoch> {
@@ -316,8 +323,103 @@ impl BurnStateDB for SortitionDBConn<'_> {
     }
 
     fn get_stacks_epoch(&self, height: u32) -> Option<StacksEpoch> {
-        SortitionDB::get_stacks_epoch(self.conn(), height as u64)
-            .expect("BUG: failed to get epoch for burn block height")
+        let mut epoch = SortitionDB::get_stacks_epoch(self.conn(), height as u64)
+            .expect("BUG: failed to get epoch for burn block height")?;
+        // this connection isn't bound to a sortition, so it can only go by the canonical fork.
+        //  Blocks are assembled against a SortitionHandleConn at the miner's burn tip instead.
+        let tip = SortitionDB::get_canonical_burn_chain_tip(self.conn())
+            .expect("BUG: failed to get canonical burn chain tip");
+        if let Some(block_limit) =
+            SortitionDB::get_block_limit_at(self, &tip.sortition_id, height as u64)
+                .expect("BUG: failed to get block limit for burn block height")
+        {
+            epoch.block_limit = block_limit;
+        }
+        Some(epoch)
+    }
+
+    fn get_stacks_epoch_by_epoch_id(&self, epoch_id: &StacksEpochId) -> Option<StacksEpoch> {
+        SortitionDB::get_stacks_epoch_by_epoch_id(self.conn(), epoch_id)
+            .expect("BUG: failed to get epoch for epoch id")
+    }
+
+    fn get_v1_unlock_height(&self) -> u32 {
+        self.context.pox_constants.v1_unlock_height
+    }
+    fn get_v2_unlock_height(&self) -> u32 {
+        self.context.pox_constants.v2_unlock_height
+    }
+    fn get_pox_3_activation_height(&self) -> u32 {
+        self.context.pox_constants.pox_3_activation_height
+    }
+    fn get_burn_start_height(&self) -> u32 {
+        self.context.first_block_height as u32
+    }
+    fn get_pox_prepare_length(&self) -> u32 {
+        self.context.pox_constants.prepare_length
+    }
+    fn get_pox_reward_cycle_length(&self) -> u32 {
+        self.context.pox_constants.reward_cycle_length
+    }
+    fn get_pox_rejection_fraction(&self) -> u64 {
+        self.context.pox_constants.pox_rejection_fraction
+    }
+
+    fn get_sortition_id_from_consensus_hash(
+        &self,
+        consensus_hash: &ConsensusHash,
+    ) -> Option<SortitionId> {
+        match SortitionDB::get_block_snapshot_consensus(self.conn(), consensus_hash) {
+            Ok(Some(x)) => Some(x.sortition_id),
+            _ => return None,
+        }
+    }
+    fn get_ast_rules(&self, height: u32) -> clarity::vm::ast::ASTRules {
+        SortitionDB::get_ast_rules(self.conn(), height.into())
+            .expect("BUG: failed to get AST rules")
+    }
+
+    fn get_pox_payout_addrs(
+        &self,
+        _: u32,
+        _: &SortitionId,
+    ) -> std::option::Option<(Vec<TupleData>, u128)> {
+        todo!()
+    }
+}
+
+impl BurnStateDB for SortitionHandleConn<'_> {
+    fn get_burn_block_height(&self, sortition_id: &SortitionId) -> Option<u32> {
+        match SortitionDB::get_block_snapshot(self.conn(), sortition_id) {
+            Ok(Some(x)) => Some(x.block_height as u32),
+            _ => return None,
+        }
+    }
+
+    fn get_burn_header_hash(
+        &self,
+        height: u32,
+        sortition_id: &SortitionId,
+    ) -> Option<BurnchainHeaderHash> {
+        let mut context = self.context.clone();
+        context.chain_tip = sortition_id.clone();
+        let db_handle = SortitionHandleConn::new(self.index, context);
+        match db_handle.get_block_snapshot_by_height(height as u64) {
+            Ok(Some(x)) => Some(BurnchainHeaderHash(x.burn_header_hash.0)),
+            _ => return None,
+        }
+    }
+
+    fn get_stacks_epoch(&self, height: u32) -> Option<StacksEpoch> {
+        let mut epoch = SortitionDB::get_stacks_epoch(self.conn(), height as u64)
+            .expect("BUG: failed to get epoch for burn block height")?;
+        if let Some(block_limit) =
+            SortitionDB::get_block_limit_at(self, &self.context.chain_tip, height as u64)
+                .expect("BUG: failed to get block limit for burn block height")
+        {
+            epoch.block_limit = block_limit;
+        }
+        Some(epoch)
     }
 
     fn get_stacks_epoch_by_epoch_id(&self, epoch_id: &StacksEpochId) -> Option<StacksEpoch> {
@@ -115,12 +115,19 @@ fn test_vm_epoch_switch() {
         end_height = cur_snapshot.block_height as u32;
     }
 
-    // impl BurnStateDB for SortitionHandleConn
+    // impl BurnStateDB for SortitionDBConn
     {
         let burndb = db.index_conn();
         test_burnstatedb_epoch(&burndb, start_height, end_height, 8, 12);
     }
 
+    // impl BurnStateDB for SortitionHandleConn
+    {
+        let tip = SortitionDB::get_canonical_burn_chain_tip(db.conn()).unwrap();
+        let burndb = db.index_handle(&tip.sortition_id);
+        test_burnstatedb_epoch(&burndb, start_height, end_height, 8, 12);
+    }
+
     // impl BurnStateDB for SortitionHandleTx
     {
         let tip = SortitionDB::get_canonical_burn_chain_tip(db.conn()).unwrap();
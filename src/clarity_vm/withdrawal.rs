@@ -1,3 +1,4 @@
+use crate::chainstate::stacks::boot::SUBNET_TRANSFERS_NAME;
 use crate::chainstate::stacks::events::StacksTransactionReceipt;
 use clarity::boot_util::boot_code_id;
 use clarity::codec::StacksMessageCodec;
@@ -16,7 +17,10 @@ fn is_subnet_contract_event(contract: &QualifiedContractIdentifier, function: &S
         return false;
     }
     // TODO: Do we know if we are in mainnet or testnet?
-    contract == &boot_code_id("subnet", true) || contract == &boot_code_id("subnet", false)
+    contract == &boot_code_id("subnet", true)
+        || contract == &boot_code_id("subnet", false)
+        || contract == &boot_code_id(SUBNET_TRANSFERS_NAME, true)
+        || contract == &boot_code_id(SUBNET_TRANSFERS_NAME, false)
 }
 
 fn clarity_ascii_str(input: &str) -> Value {
@@ -60,48 +64,94 @@ pub fn buffer_from_hash(hash: Sha512Trunc256Sum) -> Value {
 ///     recipient: principal,
 ///     amount: u128 }
 /// ```
+///
+/// ```javascript
+///   { type: "sft",
+///     asset-contract: principal,
+///     height: u128,
+///     withdrawal-id: u128,
+///     recipient: principal,
+///     sft-id: u128,
+///     amount: u128 }
+/// ```
+///
+/// ```javascript
+///   { type: "stx-transfer",
+///     height: u128,
+///     withdrawal-id: u128,
+///     sender: principal,
+///     recipient: principal,
+///     destination-subnet: principal,
+///     amount: u128 }
+/// ```
 
 pub fn generate_key_from_event(
     event: &mut StacksTransactionEvent,
     withdrawal_id: u32,
     block_height: u64,
 ) -> Option<Value> {
+    let data = get_withdrawal_event_data(event)?;
+    generate_key_from_withdrawal_data(data, withdrawal_id, block_height)
+}
+
+/// Get the data tuple of an event printed by the subnet contract, if `event` is one.
+/// Withdrawal events' tuples carry a `type` of "stx", "ft", "nft", "sft", or "stx-transfer".
+pub fn get_withdrawal_event_data(event: &mut StacksTransactionEvent) -> Option<&mut TupleData> {
     if let StacksTransactionEvent::SmartContractEvent(event_data) = event {
         if !is_subnet_contract_event(&event_data.key.0, &event_data.key.1) {
             return None;
         }
-
         if let Value::Tuple(ref mut data) = event_data.value {
-            let data_map = &mut data.data_map;
-            data_map.insert(
-                "withdrawal-id".into(),
-                Value::UInt(u128::from(withdrawal_id)),
-            );
-            let event_type = data_map.get("type")?.clone().expect_ascii();
-
-            return match event_type.as_str() {
-                "stx" => Some(make_key_for_stx_withdrawal_event(
-                    data_map,
-                    withdrawal_id,
-                    block_height,
-                )),
-                "ft" => Some(make_key_for_ft_withdrawal_event(
-                    data_map,
-                    withdrawal_id,
-                    block_height,
-                )),
-                "nft" => Some(make_key_for_nft_withdrawal_event(
-                    data_map,
-                    withdrawal_id,
-                    block_height,
-                )),
-                _ => None,
-            };
+            return Some(data);
         }
     }
     None
 }
 
+/// Like `generate_key_from_event`, but for the data tuple of a withdrawal event.
+/// The supplied withdrawal ID is inserted into the tuple.
+pub fn generate_key_from_withdrawal_data(
+    data: &mut TupleData,
+    withdrawal_id: u32,
+    block_height: u64,
+) -> Option<Value> {
+    let data_map = &mut data.data_map;
+    data_map.insert(
+        "withdrawal-id".into(),
+        Value::UInt(u128::from(withdrawal_id)),
+    );
+    let event_type = data_map.get("type")?.clone().expect_ascii();
+
+    match event_type.as_str() {
+        "stx" => Some(make_key_for_stx_withdrawal_event(
+            data_map,
+            withdrawal_id,
+            block_height,
+        )),
+        "ft" => Some(make_key_for_ft_withdrawal_event(
+            data_map,
+            withdrawal_id,
+            block_height,
+        )),
+        "nft" => Some(make_key_for_nft_withdrawal_event(
+            data_map,
+            withdrawal_id,
+            block_height,
+        )),
+        "sft" => Some(make_key_for_sft_withdrawal_event(
+            data_map,
+            withdrawal_id,
+            block_height,
+        )),
+        "stx-transfer" => Some(make_key_for_stx_transfer_withdrawal_event(
+            data_map,
+            withdrawal_id,
+            block_height,
+        )),
+        _ => None,
+    }
+}
+
 pub fn make_key_for_ft_withdrawal_event(
     data: &mut BTreeMap<ClarityName, Value>,
     withdrawal_id: u32,
@@ -172,6 +222,44 @@ pub fn make_key_for_nft_withdrawal_event(
     )
 }
 
+pub fn make_key_for_sft_withdrawal_event(
+    data: &mut BTreeMap<ClarityName, Value>,
+    withdrawal_id: u32,
+    block_height: u64,
+) -> Value {
+    let sender = data.get("sender").unwrap().clone().expect_principal();
+    let id = data.get("id").unwrap().clone().expect_u128();
+    let amount = data.get("amount").unwrap().clone().expect_u128();
+    let contract_identifier = match data
+        .get("asset-contract")
+        .unwrap()
+        .clone()
+        .expect_principal()
+    {
+        PrincipalData::Standard(_) => {
+            unreachable!("invalid principal in withdraw event")
+        }
+        PrincipalData::Contract(contract_principal) => contract_principal,
+    };
+
+    info!("Parsed L2 withdrawal event";
+          "type" => "sft",
+          "block_height" => block_height,
+          "sender" => %sender.to_string(),
+          "withdrawal_id" => withdrawal_id,
+          "id" => id,
+          "amount" => amount,
+          "asset_contract" => %contract_identifier.to_string());
+    make_key_for_sft_withdrawal(
+        &sender,
+        withdrawal_id,
+        &contract_identifier,
+        id,
+        amount,
+        block_height,
+    )
+}
+
 pub fn make_key_for_stx_withdrawal_event(
     data: &mut BTreeMap<ClarityName, Value>,
     withdrawal_id: u32,
@@ -189,6 +277,44 @@ pub fn make_key_for_stx_withdrawal_event(
     make_key_for_stx_withdrawal(&sender, withdrawal_id, amount, block_height)
 }
 
+pub fn make_key_for_stx_transfer_withdrawal_event(
+    data: &mut BTreeMap<ClarityName, Value>,
+    withdrawal_id: u32,
+    block_height: u64,
+) -> Value {
+    let sender = data.get("sender").unwrap().clone().expect_principal();
+    let recipient = data.get("recipient").unwrap().clone().expect_principal();
+    let amount = data.get("amount").unwrap().clone().expect_u128();
+    let destination_subnet = match data
+        .get("destination-subnet")
+        .unwrap()
+        .clone()
+        .expect_principal()
+    {
+        PrincipalData::Standard(_) => {
+            unreachable!("invalid principal in withdraw event")
+        }
+        PrincipalData::Contract(contract_principal) => contract_principal,
+    };
+
+    info!("Parsed L2 withdrawal event";
+          "type" => "stx-transfer",
+          "block_height" => block_height,
+          "sender" => %sender.to_string(),
+          "recipient" => %recipient.to_string(),
+          "destination_subnet" => %destination_subnet.to_string(),
+          "withdrawal_id" => withdrawal_id,
+          "amount" => amount);
+    make_key_for_stx_transfer_withdrawal(
+        &sender,
+        &recipient,
+        &destination_subnet,
+        withdrawal_id,
+        amount,
+        block_height,
+    )
+}
+
 pub fn make_key_for_stx_withdrawal(
     recipient: &PrincipalData,
     withdrawal_id: u32,
@@ -209,6 +335,31 @@ pub fn make_key_for_stx_withdrawal(
     .into()
 }
 
+pub fn make_key_for_stx_transfer_withdrawal(
+    sender: &PrincipalData,
+    recipient: &PrincipalData,
+    destination_subnet: &QualifiedContractIdentifier,
+    withdrawal_id: u32,
+    amount: u128,
+    block_height: u64,
+) -> Value {
+    let destination_subnet = Value::Principal(PrincipalData::from(destination_subnet.clone()));
+    TupleData::from_data(vec![
+        ("type".into(), clarity_ascii_str("stx-transfer")),
+        ("height".into(), Value::UInt(u128::from(block_height))),
+        (
+            "withdrawal-id".into(),
+            Value::UInt(u128::from(withdrawal_id)),
+        ),
+        ("sender".into(), Value::Principal(sender.clone())),
+        ("recipient".into(), Value::Principal(recipient.clone())),
+        ("destination-subnet".into(), destination_subnet),
+        ("amount".into(), Value::UInt(amount)),
+    ])
+    .expect("Withdrawal key tuple is too large for Clarity")
+    .into()
+}
+
 pub fn make_key_for_nft_withdrawal(
     sender: &PrincipalData,
     withdrawal_id: u32,
@@ -255,6 +406,31 @@ pub fn make_key_for_ft_withdrawal(
     .into()
 }
 
+pub fn make_key_for_sft_withdrawal(
+    sender: &PrincipalData,
+    withdrawal_id: u32,
+    contract_identifier: &QualifiedContractIdentifier,
+    id: u128,
+    amount: u128,
+    block_height: u64,
+) -> Value {
+    let asset_contract = Value::Principal(PrincipalData::from(contract_identifier.clone()));
+    TupleData::from_data(vec![
+        ("type".into(), clarity_ascii_str("sft")),
+        ("asset-contract".into(), asset_contract),
+        ("height".into(), Value::UInt(u128::from(block_height))),
+        (
+            "withdrawal-id".into(),
+            Value::UInt(u128::from(withdrawal_id)),
+        ),
+        ("recipient".into(), Value::Principal(sender.clone())),
+        ("sft-id".into(), Value::UInt(id)),
+        ("amount".into(), Value::UInt(amount)),
+    ])
+    .expect("Withdrawal key tuple is too large for Clarity")
+    .into()
+}
+
 pub fn convert_withdrawal_key_to_bytes(key: &Value) -> Vec<u8> {
     key.serialize_to_vec()
 }
@@ -302,6 +478,7 @@ mod test {
     use clarity::vm::events::SmartContractEventData;
     use clarity::vm::types::{PrincipalData, StandardPrincipalData, TupleData};
 
+    use crate::chainstate::stacks::boot::SUBNET_TRANSFERS_NAME;
     use crate::chainstate::stacks::events::{StacksTransactionReceipt, TransactionOrigin};
     use crate::chainstate::stacks::{
         CoinbasePayload, StacksTransaction, TransactionAuth, TransactionPayload,
@@ -309,6 +486,7 @@ mod test {
     };
     use crate::clarity_vm::withdrawal::{
         convert_withdrawal_key_to_bytes, create_withdrawal_merkle_tree, generate_key_from_event,
+        make_key_for_stx_transfer_withdrawal, make_key_for_stx_withdrawal,
     };
     use crate::net::test::to_addr;
     use crate::vm::ClarityName;
@@ -491,4 +669,85 @@ mod test {
         );
         assert_eq!(root_hash, calculated_root_hash);
     }
+
+    #[test]
+    fn test_stx_transfer_withdrawal_key() {
+        let pk: StacksPrivateKey = StacksPrivateKey::from_hex(
+            "aaf57b4730f713cf942bc63f0801c4a62abe5a6ac8e3da10389f9ca3420b0dc701",
+        )
+        .unwrap();
+        let user_addr = to_addr(&pk);
+        let recipient_addr =
+            StacksAddress::from_string("ST2REHHS5J3CERCRBEPMGH7921Q6PYKAADT7JP2VB").unwrap();
+        let destination_subnet = QualifiedContractIdentifier::new(
+            StacksAddress::from_string("ST1PQHQKV0RJXZFY1DGX8MNSNYVE3VGZJSRTPGZGM")
+                .unwrap()
+                .into(),
+            ContractName::from("subnet-b"),
+        );
+
+        let mut transfer_event =
+            StacksTransactionEvent::SmartContractEvent(SmartContractEventData {
+                key: (boot_code_id(SUBNET_TRANSFERS_NAME, false), "print".into()),
+                value: Value::Tuple(
+                    TupleData::from_data(vec![
+                        (
+                            "type".into(),
+                            Value::string_ascii_from_bytes("stx-transfer".to_string().into_bytes())
+                                .unwrap(),
+                        ),
+                        (
+                            "sender".into(),
+                            Value::from(StandardPrincipalData::from(user_addr)),
+                        ),
+                        (
+                            "recipient".into(),
+                            Value::from(StandardPrincipalData::from(recipient_addr)),
+                        ),
+                        (
+                            "destination-subnet".into(),
+                            Value::Principal(PrincipalData::Contract(destination_subnet.clone())),
+                        ),
+                        ("amount".into(), Value::UInt(5)),
+                    ])
+                    .expect("failed to create event tuple"),
+                ),
+            });
+
+        let key = generate_key_from_event(&mut transfer_event, 3, 7).unwrap();
+        assert_eq!(
+            key,
+            make_key_for_stx_transfer_withdrawal(
+                &PrincipalData::from(user_addr),
+                &PrincipalData::from(recipient_addr),
+                &destination_subnet,
+                3,
+                5,
+                7,
+            )
+        );
+
+        let key_data = key.expect_tuple();
+        assert_eq!(
+            key_data.get("type").unwrap().clone().expect_ascii(),
+            "stx-transfer"
+        );
+        assert_eq!(key_data.get("height").unwrap(), &Value::UInt(7));
+        assert_eq!(key_data.get("withdrawal-id").unwrap(), &Value::UInt(3));
+        assert_eq!(
+            key_data.get("destination-subnet").unwrap(),
+            &Value::Principal(PrincipalData::Contract(destination_subnet))
+        );
+
+        // the L1 contract's `leaf-hash-transfer-stx-to-subnet` must compute the same leaf hash
+        let key_bytes = convert_withdrawal_key_to_bytes(&key_data.clone().into());
+        assert_eq!(
+            to_hex(MerkleTree::<Sha512Trunc256Sum>::get_leaf_hash(&key_bytes).as_bytes()),
+            "8c9e79329b21e5d975a5edfb540947e4df4e855a4bd81b6e5c390298394c8a2b"
+        );
+
+        // the L1 leaf for an STX transfer must never collide with a plain STX withdrawal's
+        let stx_key = make_key_for_stx_withdrawal(&PrincipalData::from(recipient_addr), 3, 5, 7);
+        assert_ne!(convert_withdrawal_key_to_bytes(&stx_key), key_bytes);
+    }
 }
@@ -15,13 +15,14 @@
 // along with this program.  If not, see <http://www.gnu.org/licenses/>.
 
 use std::cmp::{self, Ordering};
-use std::collections::{HashMap, HashSet, VecDeque};
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
 use std::fs;
 use std::hash::Hasher;
-use std::io::{Read, Write};
+use std::io::{BufRead, Read, Write};
 use std::ops::Deref;
 use std::ops::DerefMut;
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
 use rand::distributions::Uniform;
 use rand::prelude::Distribution;
@@ -43,7 +44,9 @@ use crate::chainstate::stacks::{
     db::blocks::MemPoolRejection, db::ClarityTx, db::StacksChainState, db::TxStreamData,
     index::Error as MarfError, Error as ChainstateError, StacksTransaction,
 };
-use crate::chainstate::stacks::{StacksMicroblock, TransactionPayload};
+use crate::chainstate::stacks::{StacksBlockHeader, StacksMicroblock, TransactionPayload};
+use crate::core::mempool_journal::MemPoolArrivalJournal;
+use crate::core::mempool_rejections::RejectedTxsRetention;
 use crate::core::ExecutionCost;
 use crate::core::StacksEpochId;
 use crate::core::FIRST_BURNCHAIN_CONSENSUS_HASH;
@@ -61,20 +64,23 @@ use crate::util_lib::db::FromColumn;
 use crate::util_lib::db::{query_row, Error};
 use crate::util_lib::db::{sql_pragma, DBConn, DBTx, FromRow};
 use clarity::vm::types::PrincipalData;
+use clarity::vm::Value;
 use stacks_common::util::get_epoch_time_ms;
 use stacks_common::util::get_epoch_time_secs;
-use stacks_common::util::hash::to_hex;
 use stacks_common::util::hash::Sha512Trunc256Sum;
-use std::time::Instant;
+use stacks_common::util::hash::{hex_bytes, to_hex};
 
 use crate::net::MemPoolSyncData;
 
 use crate::util_lib::bloom::{BloomCounter, BloomFilter, BloomNodeHasher};
+use crate::util_lib::clock::{system_clock, Clock};
 
 use crate::clarity_vm::clarity::ClarityConnection;
 
 use crate::chainstate::stacks::events::StacksTransactionReceipt;
-use crate::chainstate::stacks::miner::TransactionEvent;
+use crate::chainstate::stacks::miner::{
+    TransactionEvent, TransactionSkippedEvent, TransactionSuccessEvent,
+};
 use crate::chainstate::stacks::StacksBlock;
 use crate::codec::Error as codec_error;
 use crate::codec::StacksMessageCodec;
@@ -109,6 +115,21 @@ pub const BLOOM_COUNTER_DEPTH: usize = 2;
 // loading the bloom filter, even though the bloom filter is larger.
 const DEFAULT_MAX_TX_TAGS: u32 = 2048;
 
+// how many admission check results the mempool remembers
+pub const DEFAULT_ADMISSION_CACHE_SIZE: usize = 4096;
+
+// longest bundle ID a transaction can be submitted with
+pub const MAX_BUNDLE_ID_LEN: usize = 64;
+
+/// Bundle IDs are 1 to `MAX_BUNDLE_ID_LEN` ASCII letters, digits, `-` or `_`
+pub fn is_valid_bundle_id(bundle_id: &str) -> bool {
+    !bundle_id.is_empty()
+        && bundle_id.len() <= MAX_BUNDLE_ID_LEN
+        && bundle_id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
 /// A node-specific transaction tag -- the first 8 bytes of siphash(local-seed,txid)
 #[derive(Debug, Clone, PartialEq, Hash, Eq)]
 pub struct TxTag(pub [u8; 8]);
@@ -146,6 +167,170 @@ impl StacksMessageCodec for TxTag {
 pub struct MemPoolAdmitter {
     cur_block: BlockHeaderHash,
     cur_consensus_hash: ConsensusHash,
+    cache: MemPoolAdmissionCache,
+    limits: MemPoolAdmissionLimits,
+}
+
+/// The names of the transaction payload types, as `TransactionPayload::name` gives them
+pub const TRANSACTION_PAYLOAD_NAMES: &[&str] = &[
+    "TokenTransfer",
+    "ContractCall",
+    "SmartContract",
+    "PoisonMicroblock",
+    "Coinbase",
+];
+
+/// Limits on which transactions the mempool admits, so that operators can keep out
+/// transactions which make no sense on their subnet.  Like the contract publish policy, these
+/// are node policy, not consensus: they are not applied to blocks from other miners.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct MemPoolAdmissionLimits {
+    /// the largest serialized transaction admitted, in bytes
+    pub max_tx_size: Option<u64>,
+    /// if set, only transactions whose payload type is named here are admitted
+    pub allowed_payload_types: Option<HashSet<String>>,
+    /// the largest contract code body admitted, in bytes
+    pub max_contract_code_size: Option<u64>,
+    /// the most a transaction may write, in bytes, if the miner caps what one transaction may
+    /// cost.  Storing a contract writes at least its code, so contract publishes with more code
+    /// than this could never be mined, and are not admitted.
+    pub max_tx_write_length: Option<u64>,
+}
+
+impl MemPoolAdmissionLimits {
+    /// Check a transaction, whose serialized size is `tx_size`, against the limits
+    pub fn check(&self, tx: &StacksTransaction, tx_size: u64) -> Result<(), MemPoolRejection> {
+        if let Some(max_tx_size) = self.max_tx_size {
+            if tx_size > max_tx_size {
+                return Err(MemPoolRejection::TransactionTooLarge(tx_size, max_tx_size));
+            }
+        }
+        if let Some(ref allowed_payload_types) = self.allowed_payload_types {
+            let payload_type = tx.payload.name();
+            if !allowed_payload_types.contains(payload_type) {
+                return Err(MemPoolRejection::PayloadTypeNotAllowed(
+                    payload_type.to_string(),
+                ));
+            }
+        }
+        if let Some(max_contract_code_size) = self.max_contract_code_size {
+            if let TransactionPayload::SmartContract(ref smart_contract, _) = tx.payload {
+                let code_size = smart_contract.code_body.len() as u64;
+                if code_size > max_contract_code_size {
+                    return Err(MemPoolRejection::ContractCodeTooLarge(
+                        code_size,
+                        max_contract_code_size,
+                    ));
+                }
+            }
+        }
+        if let Some(max_tx_write_length) = self.max_tx_write_length {
+            if let TransactionPayload::SmartContract(ref smart_contract, _) = tx.payload {
+                let code_size = smart_contract.code_body.len() as u64;
+                if code_size > max_tx_write_length {
+                    return Err(MemPoolRejection::ContractPublishTooExpensive(
+                        code_size,
+                        max_tx_write_length,
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// What an admission check result depends on: the transaction, the chain tip it was checked
+/// against, and the unconfirmed state (chain tip and last microblock) it may have been re-checked
+/// against.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct AdmissionCacheKey {
+    pub txid: Txid,
+    pub tip: StacksBlockId,
+    pub unconfirmed_tip: Option<(StacksBlockId, Option<BlockHeaderHash>)>,
+}
+
+/// A remembered admission check result
+#[derive(Debug, Clone, PartialEq)]
+pub enum AdmissionCacheResult {
+    Admitted,
+    Rejected {
+        reason: String,
+        reason_data: Option<serde_json::Value>,
+    },
+}
+
+/// A least-recently-used cache of admission check results, so that a transaction which is
+/// submitted again at the same chain tip does not have to be checked again.
+pub struct MemPoolAdmissionCache {
+    entries: HashMap<AdmissionCacheKey, (AdmissionCacheResult, u64)>,
+    /// the keys of `entries`, by when they were last used
+    last_used: BTreeMap<u64, AdmissionCacheKey>,
+    next_use: u64,
+    max_size: usize,
+    hits: u64,
+    misses: u64,
+}
+
+impl MemPoolAdmissionCache {
+    pub fn new(max_size: usize) -> MemPoolAdmissionCache {
+        MemPoolAdmissionCache {
+            entries: HashMap::new(),
+            last_used: BTreeMap::new(),
+            next_use: 0,
+            max_size,
+            hits: 0,
+            misses: 0,
+        }
+    }
+
+    /// Look up a result, marking it as the most recently used
+    pub fn get(&mut self, key: &AdmissionCacheKey) -> Option<AdmissionCacheResult> {
+        let next_use = self.next_use;
+        let result = match self.entries.get_mut(key) {
+            Some((result, last_used)) => {
+                self.last_used.remove(last_used);
+                *last_used = next_use;
+                Some(result.clone())
+            }
+            None => None,
+        };
+        if result.is_some() {
+            self.last_used.insert(next_use, key.clone());
+            self.next_use += 1;
+            self.hits += 1;
+        } else {
+            self.misses += 1;
+        }
+        monitoring::increment_mempool_admission_cache_lookups(result.is_some());
+        result
+    }
+
+    /// Remember a result, evicting the least recently used one if the cache is full
+    pub fn insert(&mut self, key: AdmissionCacheKey, result: AdmissionCacheResult) {
+        if self.max_size == 0 {
+            return;
+        }
+        if let Some((_, last_used)) = self.entries.remove(&key) {
+            self.last_used.remove(&last_used);
+        }
+        while self.entries.len() >= self.max_size {
+            let oldest = match self.last_used.keys().next() {
+                Some(oldest) => *oldest,
+                None => break,
+            };
+            if let Some(evicted) = self.last_used.remove(&oldest) {
+                self.entries.remove(&evicted);
+            }
+        }
+        self.last_used.insert(self.next_use, key.clone());
+        self.entries.insert(key, (result, self.next_use));
+        self.next_use += 1;
+    }
+
+    /// Get the number of lookups which found a result, and the number which did not
+    pub fn get_hits_and_misses(&self) -> (u64, u64) {
+        (self.hits, self.misses)
+    }
 }
 
 enum MemPoolWalkResult {
@@ -159,6 +344,8 @@ impl MemPoolAdmitter {
         MemPoolAdmitter {
             cur_block,
             cur_consensus_hash,
+            cache: MemPoolAdmissionCache::new(DEFAULT_ADMISSION_CACHE_SIZE),
+            limits: MemPoolAdmissionLimits::default(),
         }
     }
 
@@ -172,7 +359,68 @@ impl MemPoolAdmitter {
         tx: &StacksTransaction,
         tx_size: u64,
     ) -> Result<(), MemPoolRejection> {
-        chainstate.will_admit_mempool_tx(&self.cur_consensus_hash, &self.cur_block, tx, tx_size)
+        self.limits.check(tx, tx_size)?;
+        let key = AdmissionCacheKey {
+            txid: tx.txid(),
+            tip: StacksBlockHeader::make_index_block_hash(
+                &self.cur_consensus_hash,
+                &self.cur_block,
+            ),
+            unconfirmed_tip: chainstate.unconfirmed_state.as_ref().map(|unconfirmed| {
+                (
+                    unconfirmed.unconfirmed_chain_tip.clone(),
+                    unconfirmed
+                        .last_mblock
+                        .as_ref()
+                        .map(|mblock| mblock.block_hash()),
+                )
+            }),
+        };
+        match self.cache.get(&key) {
+            Some(AdmissionCacheResult::Admitted) => return Ok(()),
+            Some(AdmissionCacheResult::Rejected {
+                reason,
+                reason_data,
+            }) => {
+                return Err(MemPoolRejection::Cached {
+                    reason,
+                    reason_data,
+                })
+            }
+            None => {}
+        }
+
+        let result = chainstate.will_admit_mempool_tx(
+            &self.cur_consensus_hash,
+            &self.cur_block,
+            tx,
+            tx_size,
+        );
+        match result {
+            Ok(()) => self.cache.insert(key, AdmissionCacheResult::Admitted),
+            Err(ref rejection) if rejection.is_cacheable() => {
+                let (reason, reason_data) = rejection.reason();
+                self.cache.insert(
+                    key,
+                    AdmissionCacheResult::Rejected {
+                        reason: reason.to_string(),
+                        reason_data,
+                    },
+                );
+            }
+            Err(_) => {}
+        }
+        result
+    }
+
+    /// Get the admission cache's number of hits and misses
+    pub fn get_cache_hits_and_misses(&self) -> (u64, u64) {
+        self.cache.get_hits_and_misses()
+    }
+
+    /// Set the limits on which transactions are admitted.  See `MemPoolAdmissionLimits`.
+    pub fn set_limits(&mut self, limits: MemPoolAdmissionLimits) {
+        self.limits = limits;
     }
 }
 
@@ -181,6 +429,7 @@ pub enum MemPoolDropReason {
     REPLACE_BY_FEE,
     STALE_COLLECT,
     TOO_EXPENSIVE,
+    EXPIRED,
 }
 
 #[derive(Debug)]
@@ -190,6 +439,11 @@ pub struct ConsiderTransaction {
     /// If `update_estimator` is set, the iteration should update the estimator
     /// after considering the tx.
     pub update_estimate: bool,
+    /// The transaction's estimated fee rate, if it has one
+    pub fee_rate: Option<f64>,
+    /// If the transaction is the first of a bundle, the bundle's transactions in the order
+    /// they will be considered, starting with this one.  Otherwise empty.
+    pub bundle: Vec<MemPoolTxInfo>,
 }
 
 #[derive(Debug)]
@@ -207,6 +461,7 @@ impl std::fmt::Display for MemPoolDropReason {
             MemPoolDropReason::TOO_EXPENSIVE => write!(f, "TooExpensive"),
             MemPoolDropReason::REPLACE_ACROSS_FORK => write!(f, "ReplaceAcrossFork"),
             MemPoolDropReason::REPLACE_BY_FEE => write!(f, "ReplaceByFee"),
+            MemPoolDropReason::EXPIRED => write!(f, "Expired"),
         }
     }
 }
@@ -237,6 +492,72 @@ pub struct MemPoolTxInfo {
     pub metadata: MemPoolTxMetadata,
 }
 
+/// A mempool transaction as written by `MemPoolDB::export_txs`, one JSON object per line: the
+/// transaction's consensus serialization, hex-encoded, and the metadata the mempool kept for it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MemPoolExportEntry {
+    pub txid: String,
+    pub tx: String,
+    pub origin_address: String,
+    pub origin_nonce: u64,
+    pub sponsor_address: String,
+    pub sponsor_nonce: u64,
+    pub tx_fee: u64,
+    /// the chain tip the transaction was admitted at
+    pub consensus_hash: String,
+    pub block_header_hash: String,
+    pub block_height: u64,
+    pub accept_time: u64,
+    /// the last Stacks block height the transaction may be mined at, if its sender set one
+    #[serde(default)]
+    pub expiration_height: Option<u64>,
+    /// the bundle the transaction was submitted in, if any
+    #[serde(default)]
+    pub bundle_id: Option<String>,
+}
+
+impl MemPoolExportEntry {
+    pub fn from_tx_info(info: &MemPoolTxInfo) -> MemPoolExportEntry {
+        MemPoolExportEntry {
+            txid: format!("{}", &info.metadata.txid),
+            tx: to_hex(&info.tx.serialize_to_vec()),
+            origin_address: info.metadata.origin_address.to_string(),
+            origin_nonce: info.metadata.origin_nonce,
+            sponsor_address: info.metadata.sponsor_address.to_string(),
+            sponsor_nonce: info.metadata.sponsor_nonce,
+            tx_fee: info.metadata.tx_fee,
+            consensus_hash: format!("{}", &info.metadata.consensus_hash),
+            block_header_hash: format!("{}", &info.metadata.block_header_hash),
+            block_height: info.metadata.block_height,
+            accept_time: info.metadata.accept_time,
+            expiration_height: info.metadata.expiration_height,
+            bundle_id: info.metadata.bundle_id.clone(),
+        }
+    }
+
+    /// Decode the entry's transaction, checking it against the entry's txid
+    pub fn decode_tx(&self) -> Result<StacksTransaction, db_error> {
+        let tx_bytes = hex_bytes(&self.tx).map_err(|_| db_error::ParseError)?;
+        let tx = StacksTransaction::consensus_deserialize(&mut &tx_bytes[..])
+            .map_err(|_| db_error::ParseError)?;
+        if format!("{}", &tx.txid()) != self.txid {
+            return Err(db_error::ParseError);
+        }
+        Ok(tx)
+    }
+}
+
+/// What `MemPoolDB::import_txs` did with the transactions it read
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MemPoolImportSummary {
+    pub imported: u64,
+    /// transactions the mempool already had
+    pub already_present: u64,
+    /// transactions which no longer pass admission at the chain tip, such as ones mined since
+    /// they were exported
+    pub rejected: u64,
+}
+
 /// This class is a minimal version of `MemPoolTxInfo`. It contains
 /// just enough information to 1) filter by nonce readiness, 2) sort by fee rate.
 #[derive(Debug, Clone)]
@@ -247,6 +568,7 @@ pub struct MemPoolTxInfoPartial {
     pub origin_nonce: u64,
     pub sponsor_address: StacksAddress,
     pub sponsor_nonce: u64,
+    pub bundle_id: Option<String>,
 }
 
 #[derive(Debug, PartialEq, Clone)]
@@ -264,6 +586,11 @@ pub struct MemPoolTxMetadata {
     pub last_known_origin_nonce: Option<u64>,
     pub last_known_sponsor_nonce: Option<u64>,
     pub accept_time: u64,
+    /// The last Stacks block height this transaction may be mined at, if its sender set one
+    pub expiration_height: Option<u64>,
+    /// The bundle this transaction was submitted in, if any.  The transactions of a bundle
+    /// share an origin account, and are mined in the same block or not at all.
+    pub bundle_id: Option<String>,
 }
 
 impl MemPoolTxMetadata {
@@ -339,6 +666,8 @@ impl FromRow<MemPoolTxMetadata> for MemPoolTxMetadata {
         let sponsor_nonce = u64::from_column(row, "sponsor_nonce")?;
         let last_known_sponsor_nonce = u64::from_column(row, "last_known_sponsor_nonce")?;
         let last_known_origin_nonce = u64::from_column(row, "last_known_origin_nonce")?;
+        let expiration_height = u64::from_column(row, "expiration_height")?;
+        let bundle_id: Option<String> = row.get_unwrap("bundle_id");
 
         Ok(MemPoolTxMetadata {
             txid,
@@ -354,6 +683,8 @@ impl FromRow<MemPoolTxMetadata> for MemPoolTxMetadata {
             last_known_origin_nonce,
             last_known_sponsor_nonce,
             accept_time,
+            expiration_height,
+            bundle_id,
         })
     }
 }
@@ -387,6 +718,7 @@ impl FromRow<MemPoolTxInfoPartial> for MemPoolTxInfoPartial {
         let origin_nonce = u64::from_column(row, "origin_nonce")?;
         let sponsor_address = StacksAddress::from_column(row, "sponsor_address")?;
         let sponsor_nonce = u64::from_column(row, "sponsor_nonce")?;
+        let bundle_id: Option<String> = row.get_unwrap("bundle_id");
 
         Ok(MemPoolTxInfoPartial {
             txid,
@@ -395,6 +727,7 @@ impl FromRow<MemPoolTxInfoPartial> for MemPoolTxInfoPartial {
             origin_nonce,
             sponsor_address,
             sponsor_nonce,
+            bundle_id,
         })
     }
 }
@@ -543,6 +876,78 @@ const MEMPOOL_SCHEMA_6_NONCES: &'static [&'static str] = &[
     "#,
 ];
 
+const MEMPOOL_SCHEMA_7_EXPIRATION: &'static [&'static str] = &[
+    r#"
+    ALTER TABLE mempool ADD COLUMN expiration_height INTEGER;
+    "#,
+    r#"
+    INSERT INTO schema_version (version) VALUES (7)
+    "#,
+];
+
+const MEMPOOL_SCHEMA_8_BUNDLES: &'static [&'static str] = &[
+    r#"
+    ALTER TABLE mempool ADD COLUMN bundle_id TEXT;
+    "#,
+    r#"
+    INSERT INTO schema_version (version) VALUES (8)
+    "#,
+];
+
+const MEMPOOL_SCHEMA_9_FILTERS: &'static [&'static str] = &[
+    r#"
+    -- Filters installed by the node's operator.  A transaction which matches an unexpired filter
+    -- is neither admitted to the mempool nor mined.
+    CREATE TABLE mempool_filters(
+        filter_id INTEGER PRIMARY KEY AUTOINCREMENT,
+        -- one of 'origin', 'contract' or 'function'
+        kind TEXT NOT NULL,
+        value TEXT NOT NULL,
+        reason TEXT NOT NULL,
+        created_at INTEGER NOT NULL,
+        expires_at INTEGER NOT NULL
+    );
+    "#,
+    r#"
+    INSERT INTO schema_version (version) VALUES (9)
+    "#,
+];
+
+const MEMPOOL_SCHEMA_10_REJECTED_TXS: &'static [&'static str] = &[
+    r#"
+    -- The transactions the mempool refused, and why, kept for a while if the node is configured
+    -- to.  Only a transaction's latest rejection is kept.
+    CREATE TABLE rejected_txs(
+        txid TEXT PRIMARY KEY NOT NULL,
+        origin_address TEXT NOT NULL,
+        sponsor_address TEXT NOT NULL,
+        -- the MemPoolRejection reason code, and its JSON-encoded details, if it has any
+        reason TEXT NOT NULL,
+        reason_data TEXT,
+        -- the chain tip the transaction was checked against
+        consensus_hash TEXT NOT NULL,
+        block_header_hash TEXT NOT NULL,
+        rejected_at INTEGER NOT NULL
+    );
+    "#,
+    r#"
+    INSERT INTO schema_version (version) VALUES (10)
+    "#,
+];
+
+const MEMPOOL_SCHEMA_11_PAYLOAD_TYPE: &'static [&'static str] = &[
+    r#"
+    -- the transaction's payload type, as `TransactionPayload::name` gives it
+    ALTER TABLE mempool ADD COLUMN payload_type TEXT;
+    "#,
+    r#"
+    INSERT INTO schema_version (version) VALUES (11)
+    "#,
+];
+
+/// The latest mempool schema version
+pub const MEMPOOL_SCHEMA_VERSION: i64 = 11;
+
 const MEMPOOL_INDEXES: &'static [&'static str] = &[
     "CREATE INDEX IF NOT EXISTS by_txid ON mempool(txid);",
     "CREATE INDEX IF NOT EXISTS by_height ON mempool(height);",
@@ -555,6 +960,13 @@ const MEMPOOL_INDEXES: &'static [&'static str] = &[
     "CREATE INDEX IF NOT EXISTS by_ordered_hashed_txid ON randomized_txids(hashed_txid ASC);",
     "CREATE INDEX IF NOT EXISTS by_hashed_txid ON randomized_txids(txid,hashed_txid);",
     "CREATE INDEX IF NOT EXISTS by_arrival_time_desc ON tx_blacklist(arrival_time DESC);",
+    "CREATE INDEX IF NOT EXISTS by_expiration_height ON mempool(expiration_height);",
+    "CREATE INDEX IF NOT EXISTS by_bundle_id ON mempool(bundle_id, origin_nonce);",
+    "CREATE INDEX IF NOT EXISTS by_filter_expiry ON mempool_filters(expires_at);",
+    "CREATE INDEX IF NOT EXISTS rejected_by_origin ON rejected_txs(origin_address, rejected_at);",
+    "CREATE INDEX IF NOT EXISTS rejected_by_sponsor ON rejected_txs(sponsor_address, rejected_at);",
+    "CREATE INDEX IF NOT EXISTS rejected_by_time ON rejected_txs(rejected_at);",
+    "CREATE INDEX IF NOT EXISTS by_payload_type ON mempool(payload_type);",
 ];
 
 pub struct MemPoolDB {
@@ -565,12 +977,19 @@ pub struct MemPoolDB {
     max_tx_tags: u32,
     cost_estimator: Box<dyn CostEstimator>,
     metric: Box<dyn CostMetric>,
+    /// stamps the time each transaction is accepted, and times mempool walks
+    clock: Arc<dyn Clock>,
+    /// how long rejected transactions are kept for, if they are kept at all
+    rejected_txs_retention: Option<RejectedTxsRetention>,
+    /// where every submitted transaction is journaled, if anywhere
+    pub(crate) arrival_journal: Option<MemPoolArrivalJournal>,
 }
 
 pub struct MemPoolTx<'a> {
     tx: DBTx<'a>,
     admitter: &'a mut MemPoolAdmitter,
     bloom_counter: Option<&'a mut BloomCounter<BloomNodeHasher>>,
+    clock: Arc<dyn Clock>,
 }
 
 impl<'a> Deref for MemPoolTx<'a> {
@@ -591,11 +1010,13 @@ impl<'a> MemPoolTx<'a> {
         tx: DBTx<'a>,
         admitter: &'a mut MemPoolAdmitter,
         bloom_counter: &'a mut BloomCounter<BloomNodeHasher>,
+        clock: Arc<dyn Clock>,
     ) -> MemPoolTx<'a> {
         MemPoolTx {
             tx,
             admitter,
             bloom_counter: Some(bloom_counter),
+            clock,
         }
     }
 
@@ -767,6 +1188,8 @@ impl MemPoolTxInfo {
             accept_time: get_epoch_time_secs(),
             last_known_origin_nonce: None,
             last_known_sponsor_nonce: None,
+            expiration_height: None,
+            bundle_id: None,
         };
         MemPoolTxInfo { tx, metadata }
     }
@@ -819,7 +1242,7 @@ impl NonceCache {
                 let opt_nonce = match db_get_nonce(mempool_db, address) {
                     Ok(opt_nonce) => opt_nonce,
                     Err(e) => {
-                        warn!("error retrieving nonce from mempool db: {}", e);
+                        component_warn!(Mempool, "error retrieving nonce from mempool db: {}", e);
                         None
                     }
                 };
@@ -838,7 +1261,7 @@ impl NonceCache {
                         let should_store_again = match db_set_nonce(mempool_db, address, nonce) {
                             Ok(_) => false,
                             Err(e) => {
-                                warn!("error caching nonce to sqlite: {}", e);
+                                component_warn!(Mempool, "error caching nonce to sqlite: {}", e);
                                 true
                             }
                         };
@@ -861,7 +1284,7 @@ impl NonceCache {
         let success = match db_set_nonce(mempool_db, &address, value) {
             Ok(_) => true,
             Err(e) => {
-                warn!("error caching nonce to sqlite: {}", e);
+                component_warn!(Mempool, "error caching nonce to sqlite: {}", e);
                 false
             }
         };
@@ -1061,6 +1484,21 @@ impl MemPoolDB {
                     MemPoolDB::instantiate_nonces(tx)?;
                 }
                 6 => {
+                    MemPoolDB::add_expiration_height(tx)?;
+                }
+                7 => {
+                    MemPoolDB::add_bundle_id(tx)?;
+                }
+                8 => {
+                    MemPoolDB::instantiate_filters(tx)?;
+                }
+                9 => {
+                    MemPoolDB::instantiate_rejected_txs(tx)?;
+                }
+                10 => {
+                    MemPoolDB::add_payload_type(tx)?;
+                }
+                MEMPOOL_SCHEMA_VERSION => {
                     break;
                 }
                 _ => {
@@ -1132,6 +1570,69 @@ impl MemPoolDB {
         Ok(())
     }
 
+    /// Add the expiration height column
+    fn add_expiration_height(tx: &DBTx) -> Result<(), db_error> {
+        for sql_exec in MEMPOOL_SCHEMA_7_EXPIRATION {
+            tx.execute_batch(sql_exec)?;
+        }
+
+        Ok(())
+    }
+
+    /// Add the bundle ID column
+    fn add_bundle_id(tx: &DBTx) -> Result<(), db_error> {
+        for sql_exec in MEMPOOL_SCHEMA_8_BUNDLES {
+            tx.execute_batch(sql_exec)?;
+        }
+
+        Ok(())
+    }
+
+    /// Add the operator's filter table
+    fn instantiate_filters(tx: &DBTx) -> Result<(), db_error> {
+        for sql_exec in MEMPOOL_SCHEMA_9_FILTERS {
+            tx.execute_batch(sql_exec)?;
+        }
+
+        Ok(())
+    }
+
+    /// Add the table of rejected transactions
+    fn instantiate_rejected_txs(tx: &DBTx) -> Result<(), db_error> {
+        for sql_exec in MEMPOOL_SCHEMA_10_REJECTED_TXS {
+            tx.execute_batch(sql_exec)?;
+        }
+
+        Ok(())
+    }
+
+    /// Add the payload type column, and fill it in for the transactions already in the mempool
+    fn add_payload_type(tx: &DBTx) -> Result<(), db_error> {
+        for sql_exec in MEMPOOL_SCHEMA_11_PAYLOAD_TYPE {
+            tx.execute_batch(sql_exec)?;
+        }
+
+        let txs: Vec<(Txid, Vec<u8>)> = {
+            let mut stmt = tx.prepare("SELECT txid, tx FROM mempool")?;
+            let rows = stmt.query_map(NO_PARAMS, |row| {
+                Ok((row.get::<_, Txid>(0)?, row.get::<_, Vec<u8>>(1)?))
+            })?;
+            rows.collect::<Result<_, _>>()?
+        };
+        for (txid, tx_bytes) in txs.into_iter() {
+            // a transaction which no longer decodes is left without a payload type
+            let payload_type = StacksTransaction::consensus_deserialize(&mut &tx_bytes[..])
+                .ok()
+                .map(|decoded| decoded.payload.name());
+            tx.execute(
+                "UPDATE mempool SET payload_type = ?1 WHERE txid = ?2",
+                rusqlite::params![payload_type, &txid],
+            )?;
+        }
+
+        Ok(())
+    }
+
     pub fn db_path(chainstate_root_path: &str) -> Result<String, db_error> {
         let mut path = PathBuf::from(chainstate_root_path);
 
@@ -1141,6 +1642,41 @@ impl MemPoolDB {
             .map(String::from)
     }
 
+    /// Get the schema version of the mempool DB within the given chainstate directory, without
+    /// applying any migrations.
+    /// Returns Some(version) if the DB exists (version 1 DBs are unversioned), and None if the
+    /// DB has not been created yet.
+    pub fn get_schema_version_from_path(
+        chainstate_root_path: &str,
+    ) -> Result<Option<i64>, db_error> {
+        let db_path = MemPoolDB::db_path(chainstate_root_path)?;
+        if fs::metadata(&db_path).is_err() {
+            return Ok(None);
+        }
+        let conn = sqlite_open(&db_path, OpenFlags::SQLITE_OPEN_READ_ONLY, false)?;
+        let version = MemPoolDB::get_schema_version(&conn)?.unwrap_or(1);
+        Ok(Some(version))
+    }
+
+    /// Apply all pending schema migrations to the mempool DB within the given chainstate
+    /// directory, without opening the chainstate.  This is used to upgrade a node's databases
+    /// offline, so the node does not need to do it (possibly slowly) on boot.
+    /// Returns the schema version of the DB before the migration ran.
+    pub fn migrate_from_path(chainstate_root_path: &str) -> Result<i64, db_error> {
+        let db_path = MemPoolDB::db_path(chainstate_root_path)?;
+        if fs::metadata(&db_path).is_err() {
+            return Err(db_error::NoDBError);
+        }
+        let mut conn = sqlite_open(&db_path, OpenFlags::SQLITE_OPEN_READ_WRITE, true)?;
+        let old_version = MemPoolDB::get_schema_version(&conn)?.unwrap_or(1);
+
+        let mut tx = tx_begin_immediate(&mut conn)?;
+        MemPoolDB::apply_schema_migrations(&mut tx)?;
+        MemPoolDB::add_indexes(&mut tx)?;
+        tx.commit().map_err(db_error::SqliteError)?;
+        Ok(old_version)
+    }
+
     #[cfg(test)]
     pub fn open_test(
         mainnet: bool,
@@ -1211,9 +1747,69 @@ impl MemPoolDB {
             max_tx_tags: DEFAULT_MAX_TX_TAGS,
             cost_estimator,
             metric,
+            clock: system_clock(),
+            rejected_txs_retention: None,
+            arrival_journal: None,
         })
     }
 
+    /// Open the existing mempool db within the chainstate directory read-only, without applying
+    /// any migrations.  Used to serve reads from a mempool which a running node writes to; any
+    /// attempt to admit a transaction through the returned handle fails.
+    pub fn open_readonly(
+        chainstate_path: &str,
+        cost_estimator: Box<dyn CostEstimator>,
+        metric: Box<dyn CostMetric>,
+    ) -> Result<MemPoolDB, db_error> {
+        let db_path = MemPoolDB::db_path(chainstate_path)?;
+        if fs::metadata(&db_path).is_err() {
+            return Err(db_error::NoDBError);
+        }
+        let conn = sqlite_open(&db_path, OpenFlags::SQLITE_OPEN_READ_ONLY, true)?;
+
+        let bloom_counter = BloomCounter::<BloomNodeHasher>::try_load(&conn, BLOOM_COUNTER_TABLE)?
+            .ok_or(db_error::Other(format!("Failed to load bloom counter")))?;
+
+        Ok(MemPoolDB {
+            db: conn,
+            path: db_path,
+            admitter: MemPoolAdmitter::new(BlockHeaderHash([0u8; 32]), ConsensusHash([0u8; 20])),
+            bloom_counter,
+            max_tx_tags: DEFAULT_MAX_TX_TAGS,
+            cost_estimator,
+            metric,
+            clock: system_clock(),
+            rejected_txs_retention: None,
+            arrival_journal: None,
+        })
+    }
+
+    /// Set the limits on which transactions this mempool admits.  See `MemPoolAdmissionLimits`.
+    pub fn set_admission_limits(&mut self, limits: MemPoolAdmissionLimits) {
+        self.admitter.set_limits(limits);
+    }
+
+    /// Set the clock which stamps the time each transaction is accepted, and times mempool walks
+    pub fn set_clock(&mut self, clock: Arc<dyn Clock>) {
+        self.clock = clock;
+    }
+
+    /// Get the clock the mempool reads the time from
+    pub fn get_clock(&self) -> Arc<dyn Clock> {
+        self.clock.clone()
+    }
+
+    /// Keep the transactions this mempool rejects, and why, for as long as `retention` allows,
+    /// or stop keeping them if it is `None`
+    pub fn set_rejected_txs_retention(&mut self, retention: Option<RejectedTxsRetention>) {
+        self.rejected_txs_retention = retention;
+    }
+
+    /// Get how long rejected transactions are kept for, if they are kept at all
+    pub fn get_rejected_txs_retention(&self) -> Option<&RejectedTxsRetention> {
+        self.rejected_txs_retention.as_ref()
+    }
+
     pub fn reset_nonce_cache(&mut self) -> Result<(), db_error> {
         let sql = "DELETE FROM nonces";
         self.db.execute(sql, rusqlite::NO_PARAMS)?;
@@ -1272,7 +1868,7 @@ impl MemPoolDB {
                 Ok(x) => Some(x),
                 Err(EstimatorError::NoEstimateAvailable) => continue,
                 Err(e) => {
-                    warn!("Error while estimating mempool tx rate";
+                    component_warn!(Mempool, "Error while estimating mempool tx rate";
                           "txid" => %txid,
                           "error" => ?e);
                     continue;
@@ -1335,11 +1931,20 @@ impl MemPoolDB {
     /// `output_events` is modified in place, adding all substantive
     /// transaction events (success and error events, but not skipped) output
     /// by `todo`.
+    ///
+    /// `tip_height` is the height of the block being built on.  Transactions
+    /// whose expiration height is below the next block's height are skipped.
+    ///
+    /// The transactions of a bundle are considered one after the other, in
+    /// nonce order, once all of them could be mined in sequence.  The first is
+    /// passed to `todo` with the whole bundle in `ConsiderTransaction::bundle`,
+    /// so that `todo` can skip it if the bundle would not fit in the block; the
+    /// rest are only considered while `todo` mines each one before them.
     pub fn iterate_candidates<F, E, C>(
         &mut self,
         clarity_tx: &mut C,
         output_events: &mut Vec<TransactionEvent>,
-        _tip_height: u64,
+        tip_height: u64,
         settings: MemPoolWalkSettings,
         mut todo: F,
     ) -> Result<u64, E>
@@ -1352,10 +1957,10 @@ impl MemPoolDB {
         ) -> Result<Option<TransactionEvent>, E>,
         E: From<db_error> + From<ChainstateError>,
     {
-        let start_time = Instant::now();
+        let start_time = self.clock.now_ms();
         let mut total_considered = 0;
 
-        debug!("Mempool walk for {}ms", settings.max_walk_time_ms,);
+        component_debug!(Mempool, "Mempool walk for {}ms", settings.max_walk_time_ms,);
 
         let tx_consideration_sampler = Uniform::new(0, 100);
         let mut rng = rand::thread_rng();
@@ -1366,23 +1971,29 @@ impl MemPoolDB {
         // single transaction.  This cannot grow to more than `settings.nonce_cache_size` entries.
         let mut retry_store = HashMap::new();
 
+        // transactions matched by the operator's filters are never mined
+        let filters = MemPoolDB::get_active_filters(self.conn(), self.clock.now_secs())?;
+
+        // transactions mined in the next block must not have expired by its height
+        let args: &[&dyn ToSql] = &[&u64_to_sql(tip_height)?];
+
         let sql = "
-             SELECT txid, origin_nonce, origin_address, sponsor_nonce, sponsor_address, fee_rate
+             SELECT txid, origin_nonce, origin_address, sponsor_nonce, sponsor_address, fee_rate, bundle_id
              FROM mempool
-             WHERE fee_rate IS NULL
+             WHERE fee_rate IS NULL AND (expiration_height IS NULL OR expiration_height > ?1)
              ";
         let mut query_stmt_null = self
             .db
             .prepare(&sql)
             .map_err(|err| Error::SqliteError(err))?;
         let mut null_iterator = query_stmt_null
-            .query(NO_PARAMS)
+            .query(args)
             .map_err(|err| Error::SqliteError(err))?;
 
         let sql = "
-            SELECT txid, origin_nonce, origin_address, sponsor_nonce, sponsor_address, fee_rate
+            SELECT txid, origin_nonce, origin_address, sponsor_nonce, sponsor_address, fee_rate, bundle_id
             FROM mempool
-            WHERE fee_rate IS NOT NULL
+            WHERE fee_rate IS NOT NULL AND (expiration_height IS NULL OR expiration_height > ?1)
             ORDER BY fee_rate DESC
             ";
         let mut query_stmt_fee = self
@@ -1390,12 +2001,12 @@ impl MemPoolDB {
             .prepare(&sql)
             .map_err(|err| Error::SqliteError(err))?;
         let mut fee_iterator = query_stmt_fee
-            .query(NO_PARAMS)
+            .query(args)
             .map_err(|err| Error::SqliteError(err))?;
 
         loop {
-            if start_time.elapsed().as_millis() > settings.max_walk_time_ms as u128 {
-                debug!("Mempool iteration deadline exceeded";
+            if self.clock.now_ms().saturating_sub(start_time) > settings.max_walk_time_ms as u128 {
+                component_debug!(Mempool, "Mempool iteration deadline exceeded";
                        "deadline_ms" => settings.max_walk_time_ms);
                 break;
             }
@@ -1436,7 +2047,10 @@ impl MemPoolDB {
                                     !start_with_no_estimate,
                                 ),
                                 None => {
-                                    debug!("No more transactions to consider in mempool");
+                                    component_debug!(
+                                        Mempool,
+                                        "No more transactions to consider in mempool"
+                                    );
                                     break;
                                 }
                             }
@@ -1477,7 +2091,8 @@ impl MemPoolDB {
                 expected_sponsor_nonce,
             ) {
                 Ordering::Less => {
-                    debug!(
+                    component_debug!(
+                        Mempool,
                         "Mempool: unexecutable: drop tx {}:{} ({})",
                         candidate.origin_address,
                         candidate.origin_nonce,
@@ -1487,7 +2102,8 @@ impl MemPoolDB {
                     continue;
                 }
                 Ordering::Greater => {
-                    debug!(
+                    component_debug!(
+                        Mempool,
                         "Mempool: nonces too high, cached for later {}:{} ({})",
                         candidate.origin_address,
                         candidate.origin_nonce,
@@ -1499,7 +2115,7 @@ impl MemPoolDB {
                 }
                 Ordering::Equal => {
                     // Candidate transaction: fall through
-                    debug!("Mempool: fall through.");
+                    component_debug!(Mempool, "Mempool: fall through.");
                 }
             };
 
@@ -1509,36 +2125,119 @@ impl MemPoolDB {
                 Some(tx) => tx,
                 None => {
                     // Note: Don't panic here because maybe the state has changed from garbage collection.
-                    warn!("Miner: could not find a tx for id {:?}", &candidate.txid);
+                    component_warn!(
+                        Mempool,
+                        "Miner: could not find a tx for id {:?}",
+                        &candidate.txid
+                    );
                     continue;
                 }
             };
 
-            let consider = ConsiderTransaction {
-                tx: tx_info,
-                update_estimate,
+            // A bundle is considered as a whole once its lowest-nonce transaction is ready.  The
+            // rest must follow it in consecutive origin nonces, and must be ready in their
+            // sponsors' nonces too, or else none of it is considered in this pass.
+            let bundle = match candidate.bundle_id {
+                Some(ref bundle_id) => {
+                    let bundle = MemPoolDB::get_bundle_txs(
+                        self.conn(),
+                        bundle_id,
+                        candidate.origin_nonce,
+                        tip_height,
+                    )?;
+                    let mut next_sponsor_nonces = HashMap::new();
+                    let mut ready = true;
+                    for (i, member) in bundle.iter().enumerate() {
+                        if member.metadata.origin_nonce != candidate.origin_nonce + (i as u64) {
+                            ready = false;
+                            break;
+                        }
+                        if member.tx.auth.is_sponsored() {
+                            let sponsor = member.metadata.sponsor_address;
+                            let expected = match next_sponsor_nonces.get(&sponsor) {
+                                Some(nonce) => *nonce,
+                                None => nonce_cache.get(&sponsor, clarity_tx, self.conn()).0,
+                            };
+                            if member.metadata.sponsor_nonce != expected {
+                                ready = false;
+                                break;
+                            }
+                            next_sponsor_nonces.insert(sponsor, expected + 1);
+                        }
+                    }
+                    if !ready {
+                        component_debug!(
+                            Mempool,
+                            "Mempool: bundle {} is not ready, skipping {}:{}",
+                            bundle_id,
+                            candidate.origin_address,
+                            candidate.origin_nonce
+                        );
+                        continue;
+                    }
+                    bundle
+                }
+                None => vec![],
             };
-            debug!("Consider mempool transaction";
-                           "txid" => %consider.tx.tx.txid(),
-                           "origin_addr" => %consider.tx.metadata.origin_address,
-                           "origin_nonce" => candidate.origin_nonce,
-                           "sponsor_addr" => %consider.tx.metadata.sponsor_address,
-                           "sponsor_nonce" => candidate.sponsor_nonce,
-                           "accept_time" => consider.tx.metadata.accept_time,
-                           "tx_fee" => consider.tx.metadata.tx_fee,
-                           "fee_rate" => candidate.fee_rate,
-                           "size" => consider.tx.metadata.len);
-            total_considered += 1;
-
-            // Run `todo` on the transaction.
-            match todo(clarity_tx, &consider, self.cost_estimator.as_mut())? {
-                Some(tx_event) => {
-                    match tx_event {
+
+            let mut to_consider: Vec<ConsiderTransaction> = bundle
+                .iter()
+                .skip(1)
+                .map(|member| ConsiderTransaction {
+                    tx: member.clone(),
+                    update_estimate: false,
+                    fee_rate: None,
+                    bundle: vec![],
+                })
+                .collect();
+            to_consider.insert(
+                0,
+                ConsiderTransaction {
+                    tx: tx_info,
+                    update_estimate,
+                    fee_rate: candidate.fee_rate,
+                    bundle,
+                },
+            );
+
+            // a bundle is left out whole if any of its transactions is filtered
+            let filtered = to_consider.iter().find_map(|consider| {
+                filters
+                    .iter()
+                    .find(|filter| filter.matches(&consider.tx.tx))
+                    .map(|filter| (consider.tx.tx.txid(), filter.filter_id))
+            });
+            if let Some((txid, filter_id)) = filtered {
+                component_debug!(Mempool, "Mempool: skip filtered tx";
+                       "txid" => %txid,
+                       "filter_id" => filter_id);
+                continue;
+            }
+
+            let mut halt = false;
+            for consider in to_consider.into_iter() {
+                component_debug!(Mempool, "Consider mempool transaction";
+                               "txid" => %consider.tx.tx.txid(),
+                               "origin_addr" => %consider.tx.metadata.origin_address,
+                               "origin_nonce" => consider.tx.metadata.origin_nonce,
+                               "sponsor_addr" => %consider.tx.metadata.sponsor_address,
+                               "sponsor_nonce" => consider.tx.metadata.sponsor_nonce,
+                               "accept_time" => consider.tx.metadata.accept_time,
+                               "tx_fee" => consider.tx.metadata.tx_fee,
+                               "fee_rate" => consider.fee_rate,
+                               "bundle_id" => ?consider.tx.metadata.bundle_id,
+                               "size" => consider.tx.metadata.len);
+                total_considered += 1;
+
+                // Run `todo` on the transaction.
+                let mined = match todo(clarity_tx, &consider, self.cost_estimator.as_mut())? {
+                    Some(tx_event) => match tx_event {
                         TransactionEvent::Success(_) => {
                             // Bump nonces in the cache for the executed transaction
+                            let next_origin_nonce = consider.tx.metadata.origin_nonce + 1;
                             let stored = nonce_cache.update(
                                 consider.tx.metadata.origin_address,
-                                expected_origin_nonce + 1,
+                                next_origin_nonce,
                                 self.conn(),
                             );
                             if !stored {
@@ -1546,14 +2245,15 @@ impl MemPoolDB {
                                     &mut retry_store,
                                     settings.nonce_cache_size,
                                     consider.tx.metadata.origin_address,
-                                    expected_origin_nonce + 1,
+                                    next_origin_nonce,
                                 );
                             }
 
                             if consider.tx.tx.auth.is_sponsored() {
+                                let next_sponsor_nonce = consider.tx.metadata.sponsor_nonce + 1;
                                 let stored = nonce_cache.update(
                                     consider.tx.metadata.sponsor_address,
-                                    expected_sponsor_nonce + 1,
+                                    next_sponsor_nonce,
                                     self.conn(),
                                 );
                                 if !stored {
@@ -1561,28 +2261,41 @@ impl MemPoolDB {
                                         &mut retry_store,
                                         settings.nonce_cache_size,
                                         consider.tx.metadata.sponsor_address,
-                                        expected_sponsor_nonce + 1,
+                                        next_sponsor_nonce,
                                     );
                                 }
                             }
                             output_events.push(tx_event);
+                            true
                         }
                         TransactionEvent::Skipped(_) => {
                             // don't push `Skipped` events to the observer
+                            false
                         }
                         _ => {
                             output_events.push(tx_event);
+                            false
                         }
+                    },
+                    None => {
+                        component_debug!(Mempool, "Mempool iteration early exit from iterator");
+                        halt = true;
+                        break;
                     }
-                }
-                None => {
-                    debug!("Mempool iteration early exit from iterator");
+                };
+
+                // the rest of a bundle cannot be mined without this transaction
+                if !mined {
                     break;
                 }
             }
+            if halt {
+                break;
+            }
 
             // Reset for finding the next transaction to process
-            debug!(
+            component_debug!(
+                Mempool,
                 "Mempool: reset: retry list has {} entries",
                 candidate_cache.len()
             );
@@ -1605,7 +2318,8 @@ impl MemPoolDB {
             tx.commit()?;
         }
 
-        debug!(
+        component_debug!(
+            Mempool,
             "Mempool iteration finished";
             "considered_txs" => total_considered,
             "elapsed_ms" => start_time.elapsed().as_millis()
@@ -1613,6 +2327,87 @@ impl MemPoolDB {
         Ok(total_considered)
     }
 
+    /// Get the transactions of the bundle `bundle_id` whose origin nonces are at least
+    /// `min_origin_nonce`, and which have not expired by the block after `tip_height`, in
+    /// origin nonce order.
+    pub fn get_bundle_txs(
+        conn: &DBConn,
+        bundle_id: &str,
+        min_origin_nonce: u64,
+        tip_height: u64,
+    ) -> Result<Vec<MemPoolTxInfo>, db_error> {
+        let sql = "SELECT * FROM mempool
+                   WHERE bundle_id = ?1 AND origin_nonce >= ?2
+                   AND (expiration_height IS NULL OR expiration_height > ?3)
+                   ORDER BY origin_nonce ASC";
+        let args: &[&dyn ToSql] = &[
+            &bundle_id,
+            &u64_to_sql(min_origin_nonce)?,
+            &u64_to_sql(tip_height)?,
+        ];
+        query_rows(conn, sql, args)
+    }
+
+    /// Preview the order in which a miner building on `clarity_tx`'s chain tip would consider
+    /// this mempool's transactions, by running the same walk as `iterate_candidates()` and
+    /// treating every candidate as mined.  Returns at most `max_results` candidates.  The walk
+    /// picks between the fee-rate and no-estimate buckets at random, so each preview is a
+    /// sample of the possible orders.  Candidates whose fee is below `settings.min_tx_fee` are
+    /// skipped, as the miner would.
+    ///
+    /// The walk's writes to the nonce cache are rolled back, so the miner's cached nonces are
+    /// left as they were.
+    pub fn preview_candidates<C: ClarityConnection>(
+        &mut self,
+        clarity_tx: &mut C,
+        tip_height: u64,
+        settings: MemPoolWalkSettings,
+        max_results: usize,
+    ) -> Result<Vec<ConsiderTransaction>, ChainstateError> {
+        self.db.execute("SAVEPOINT mempool_preview", NO_PARAMS)?;
+
+        let mut candidates = vec![];
+        let min_tx_fee = settings.min_tx_fee;
+        // start from the chain tip's nonces, as the miner does
+        let result = match self.reset_nonce_cache() {
+            Err(e) => Err(ChainstateError::from(e)),
+            Ok(()) => self.iterate_candidates(
+                clarity_tx,
+                &mut vec![],
+                tip_height,
+                settings,
+                |_clarity_tx, consider, _estimator| {
+                    if candidates.len() >= max_results {
+                        return Ok(None);
+                    }
+                    let txid = consider.tx.tx.txid();
+                    if consider.tx.metadata.tx_fee < min_tx_fee {
+                        return Ok(Some(TransactionEvent::Skipped(TransactionSkippedEvent {
+                            txid,
+                            error: "Fee too low".to_string(),
+                        })));
+                    }
+                    candidates.push(ConsiderTransaction {
+                        tx: consider.tx.clone(),
+                        update_estimate: consider.update_estimate,
+                        fee_rate: consider.fee_rate,
+                        bundle: consider.bundle.clone(),
+                    });
+                    Ok(Some(TransactionEvent::Success(TransactionSuccessEvent {
+                        txid,
+                        fee: consider.tx.metadata.tx_fee,
+                        execution_cost: ExecutionCost::zero(),
+                        result: Value::okay_true(),
+                    })))
+                },
+            ),
+        };
+
+        self.db.execute("ROLLBACK TO mempool_preview", NO_PARAMS)?;
+        self.db.execute("RELEASE mempool_preview", NO_PARAMS)?;
+        result.map(|_| candidates)
+    }
+
     pub fn conn(&self) -> &DBConn {
         &self.db
     }
@@ -1623,6 +2418,7 @@ impl MemPoolDB {
             tx,
             &mut self.admitter,
             &mut self.bloom_counter,
+            self.clock.clone(),
         ))
     }
 
@@ -1735,6 +2531,27 @@ impl MemPoolDB {
         query_row(conn, &sql, args)
     }
 
+    /// Get the metadata of every transaction originated by `addr`, ordered by origin nonce.
+    pub fn get_tx_metadata_by_origin(
+        conn: &DBConn,
+        addr: &StacksAddress,
+    ) -> Result<Vec<MemPoolTxMetadata>, db_error> {
+        let sql = "SELECT * FROM mempool WHERE origin_address = ?1 ORDER BY origin_nonce ASC";
+        let args: &[&dyn ToSql] = &[&addr.to_string()];
+        query_rows(conn, sql, args)
+    }
+
+    /// Get the pending transactions `addr` sponsors for some other origin, ordered by sponsor
+    /// nonce.  Unsponsored transactions are their origin's own, and are left out.
+    pub fn get_tx_metadata_by_sponsor(
+        conn: &DBConn,
+        addr: &StacksAddress,
+    ) -> Result<Vec<MemPoolTxMetadata>, db_error> {
+        let sql = "SELECT * FROM mempool WHERE sponsor_address = ?1 AND origin_address != ?1 ORDER BY sponsor_nonce ASC";
+        let args: &[&dyn ToSql] = &[&addr.to_string()];
+        query_rows(conn, sql, args)
+    }
+
     fn are_blocks_in_same_fork(
         chainstate: &mut StacksChainState,
         first_consensus_hash: &ConsensusHash,
@@ -1807,9 +2624,15 @@ impl MemPoolDB {
         let add_tx = if let Some(ref prior_tx) = prior_tx {
             if tx_fee > prior_tx.tx_fee {
                 // is this a replace-by-fee ?
-                debug!(
+                component_debug!(
+                    Mempool,
                     "Can replace {} with {} for {},{} by fee ({} < {})",
-                    &prior_tx.txid, &txid, origin_address, origin_nonce, &prior_tx.tx_fee, &tx_fee
+                    &prior_tx.txid,
+                    &txid,
+                    origin_address,
+                    origin_nonce,
+                    &prior_tx.tx_fee,
+                    &tx_fee
                 );
                 replace_reason = MemPoolDropReason::REPLACE_BY_FEE;
                 true
@@ -1821,15 +2644,19 @@ impl MemPoolDB {
                 block_header_hash,
             )? {
                 // is this a replace-across-fork ?
-                debug!(
+                component_debug!(
+                    Mempool,
                     "Can replace {} with {} for {},{} across fork",
-                    &prior_tx.txid, &txid, origin_address, origin_nonce
+                    &prior_tx.txid,
+                    &txid,
+                    origin_address,
+                    origin_nonce
                 );
                 replace_reason = MemPoolDropReason::REPLACE_ACROSS_FORK;
                 true
             } else {
                 // there's a >= fee tx in this fork, cannot add
-                info!("TX conflicts with sponsor/origin nonce in same fork with >= fee";
+                component_info!(Mempool, "TX conflicts with sponsor/origin nonce in same fork with >= fee";
                       "new_txid" => %txid,
                       "old_txid" => %prior_tx.txid,
                       "origin_addr" => %origin_address,
@@ -1877,7 +2704,7 @@ impl MemPoolDB {
             consensus_hash,
             block_header_hash,
             &u64_to_sql(height)?,
-            &u64_to_sql(get_epoch_time_secs())?,
+            &u64_to_sql(tx.clock.now_secs())?,
             &tx_bytes,
         ];
 
@@ -1916,6 +2743,30 @@ impl MemPoolDB {
         Ok(())
     }
 
+    /// Remove transactions which expired before they were mined: those whose expiration height
+    /// is at most `chain_height`, the height of the canonical Stacks chain tip, and so can no
+    /// longer be mined in the next block.
+    pub fn garbage_collect_expired(
+        tx: &mut MemPoolTx,
+        chain_height: u64,
+        event_observer: Option<&dyn MemPoolEventDispatcher>,
+    ) -> Result<(), db_error> {
+        let args: &[&dyn ToSql] = &[&u64_to_sql(chain_height)?];
+
+        if let Some(event_observer) = event_observer {
+            let sql = "SELECT txid FROM mempool WHERE expiration_height <= ?1";
+            let txids: Vec<Txid> = query_rows(tx, sql, args)?;
+            if !txids.is_empty() {
+                event_observer.mempool_txs_dropped(txids, MemPoolDropReason::EXPIRED);
+            }
+        }
+
+        let sql = "DELETE FROM mempool WHERE expiration_height <= ?1";
+
+        tx.execute(sql, args)?;
+        Ok(())
+    }
+
     #[cfg(test)]
     pub fn clear_before_height(&mut self, min_height: u64) -> Result<(), db_error> {
         let mut tx = self.tx_begin()?;
@@ -1962,6 +2813,8 @@ impl MemPoolDB {
         do_admission_checks: bool,
         event_observer: Option<&dyn MemPoolEventDispatcher>,
         fee_rate_estimate: Option<f64>,
+        expiration_height: Option<u64>,
+        bundle_id: Option<&str>,
     ) -> Result<(), MemPoolRejection> {
         test_debug!(
             "Mempool submit {} at {}/{}",
@@ -1990,6 +2843,13 @@ impl MemPoolDB {
             }
         };
 
+        // the next block is the first the transaction can be mined in
+        if let Some(expiration_height) = expiration_height {
+            if expiration_height <= height {
+                return Err(MemPoolRejection::AlreadyExpired(expiration_height, height));
+            }
+        }
+
         let txid = tx.txid();
         let mut tx_data = vec![];
         tx.consensus_serialize(&mut tx_data)
@@ -2006,6 +2866,26 @@ impl MemPoolDB {
                 (origin_address.clone(), origin_nonce)
             };
 
+        // a bundle's transactions are chained by their origin nonces
+        if let Some(bundle_id) = bundle_id {
+            let sql = "SELECT origin_address FROM mempool WHERE bundle_id = ?1 AND origin_address != ?2 LIMIT 1";
+            let args: &[&dyn ToSql] = &[&bundle_id, &origin_address.to_string()];
+            let other_origins: Vec<StacksAddress> =
+                query_row_columns(mempool_tx, sql, args, "origin_address")?;
+            if let Some(other_origin) = other_origins.into_iter().next() {
+                return Err(MemPoolRejection::BundleOriginMismatch(
+                    bundle_id.to_string(),
+                    other_origin,
+                ));
+            }
+        }
+
+        // the operator's filters apply to every transaction, however it arrived
+        let now = mempool_tx.clock.now_secs();
+        if let Some(filter) = MemPoolDB::find_matching_filter(mempool_tx, tx, now)? {
+            return Err(MemPoolRejection::Filtered(filter.filter_id, filter.reason));
+        }
+
         if do_admission_checks {
             mempool_tx
                 .admitter
@@ -2031,13 +2911,19 @@ impl MemPoolDB {
 
         mempool_tx
             .execute(
-                "UPDATE mempool SET fee_rate = ? WHERE txid = ?",
-                rusqlite::params![fee_rate_estimate, &txid],
+                "UPDATE mempool SET fee_rate = ?, expiration_height = ?, bundle_id = ?, payload_type = ? WHERE txid = ?",
+                rusqlite::params![
+                    fee_rate_estimate,
+                    expiration_height.map(u64_to_sql).transpose()?,
+                    bundle_id,
+                    tx.payload.name(),
+                    &txid
+                ],
             )
             .map_err(db_error::from)?;
 
         if let Err(e) = monitoring::mempool_accepted(&txid, &chainstate.root_path) {
-            warn!("Failed to monitor TX receive: {:?}", e; "txid" => %txid);
+            component_warn!(Mempool, "Failed to monitor TX receive: {:?}", e; "txid" => %txid);
         }
 
         Ok(())
@@ -2053,6 +2939,80 @@ impl MemPoolDB {
         event_observer: Option<&dyn MemPoolEventDispatcher>,
         block_limit: &ExecutionCost,
         stacks_epoch_id: &StacksEpochId,
+    ) -> Result<(), MemPoolRejection> {
+        self.submit_with_options(
+            chainstate,
+            consensus_hash,
+            block_hash,
+            tx,
+            event_observer,
+            block_limit,
+            stacks_epoch_id,
+            None,
+            None,
+        )
+    }
+
+    /// One-shot submit of a transaction which is dropped from the mempool if it has not been
+    /// mined by the Stacks block at `expiration_height`, if that is given.  If `bundle_id` is
+    /// given, the transaction is mined in the same block as the other transactions submitted
+    /// with that bundle ID, or not at all; they must all have the same origin.
+    /// If the mempool keeps rejected transactions, a rejection is recorded.  If it has an
+    /// arrival journal, the transaction is journaled first, whether or not it is admitted.
+    pub fn submit_with_options(
+        &mut self,
+        chainstate: &mut StacksChainState,
+        consensus_hash: &ConsensusHash,
+        block_hash: &BlockHeaderHash,
+        tx: &StacksTransaction,
+        event_observer: Option<&dyn MemPoolEventDispatcher>,
+        block_limit: &ExecutionCost,
+        stacks_epoch_id: &StacksEpochId,
+        expiration_height: Option<u64>,
+        bundle_id: Option<&str>,
+    ) -> Result<(), MemPoolRejection> {
+        if let Some(journal) = self.arrival_journal.as_mut() {
+            let arrival_time_ms = self.clock.now_ms() as u64;
+            if let Err(e) = journal.record(tx, arrival_time_ms, expiration_height, bundle_id) {
+                component_warn!(Mempool, "Failed to journal transaction arrival: {:?}", &e;
+                      "txid" => %tx.txid(), "journal" => journal.path());
+            }
+        }
+        let result = self.try_submit_with_options(
+            chainstate,
+            consensus_hash,
+            block_hash,
+            tx,
+            event_observer,
+            block_limit,
+            stacks_epoch_id,
+            expiration_height,
+            bundle_id,
+        );
+        let record_res = match result {
+            Ok(()) => self.clear_rejected_tx(&tx.txid()),
+            Err(ref rejection) => {
+                self.record_rejected_tx(tx, consensus_hash, block_hash, rejection)
+            }
+        };
+        if let Err(e) = record_res {
+            component_warn!(Mempool, "Failed to update rejected transactions: {:?}", &e;
+                  "txid" => %tx.txid());
+        }
+        result
+    }
+
+    fn try_submit_with_options(
+        &mut self,
+        chainstate: &mut StacksChainState,
+        consensus_hash: &ConsensusHash,
+        block_hash: &BlockHeaderHash,
+        tx: &StacksTransaction,
+        event_observer: Option<&dyn MemPoolEventDispatcher>,
+        block_limit: &ExecutionCost,
+        stacks_epoch_id: &StacksEpochId,
+        expiration_height: Option<u64>,
+        bundle_id: Option<&str>,
     ) -> Result<(), MemPoolRejection> {
         let estimator_result = cost_estimates::estimate_fee_rate(
             tx,
@@ -2068,7 +3028,7 @@ impl MemPoolDB {
             Ok(x) => Some(x),
             Err(EstimatorError::NoEstimateAvailable) => None,
             Err(e) => {
-                warn!("Error while estimating mempool tx rate";
+                component_warn!(Mempool, "Error while estimating mempool tx rate";
                       "txid" => %tx.txid(),
                       "error" => ?e);
                 return Err(MemPoolRejection::EstimatorError(e));
@@ -2084,6 +3044,8 @@ impl MemPoolDB {
             true,
             event_observer,
             fee_rate,
+            expiration_height,
+            bundle_id,
         )?;
         mempool_tx.commit().map_err(MemPoolRejection::DBError)?;
         Ok(())
@@ -2118,7 +3080,7 @@ impl MemPoolDB {
             Ok(x) => Some(x),
             Err(EstimatorError::NoEstimateAvailable) => None,
             Err(e) => {
-                warn!("Error while estimating mempool tx rate";
+                component_warn!(Mempool, "Error while estimating mempool tx rate";
                       "txid" => %tx.txid(),
                       "error" => ?e);
                 return Err(MemPoolRejection::Other(
@@ -2136,6 +3098,8 @@ impl MemPoolDB {
             false,
             None,
             fee_rate,
+            None,
+            None,
         )?;
         mempool_tx.commit().map_err(MemPoolRejection::DBError)?;
         Ok(())
@@ -2152,6 +3116,71 @@ impl MemPoolDB {
         Ok(())
     }
 
+    /// Write every transaction in the mempool to `fd`, one `MemPoolExportEntry` per line, in the
+    /// order they were accepted.  Returns the number of transactions written.
+    pub fn export_txs<W: Write>(&self, fd: &mut W) -> Result<u64, db_error> {
+        let sql = "SELECT * FROM mempool ORDER BY accept_time ASC, origin_nonce ASC";
+        let txs = query_rows::<MemPoolTxInfo, _>(&self.db, sql, NO_PARAMS)?;
+        for tx_info in txs.iter() {
+            let line = serde_json::to_string(&MemPoolExportEntry::from_tx_info(tx_info))
+                .map_err(db_error::SerializationError)?;
+            writeln!(fd, "{}", line).map_err(db_error::IOError)?;
+        }
+        Ok(txs.len() as u64)
+    }
+
+    /// Read transactions written by `export_txs` from `fd`, and submit the ones the mempool does
+    /// not already have against the chain tip `consensus_hash`/`block_hash`.  Fails without
+    /// submitting anything if `fd` holds anything but export entries.
+    pub fn import_txs<R: BufRead>(
+        &mut self,
+        chainstate: &mut StacksChainState,
+        consensus_hash: &ConsensusHash,
+        block_hash: &BlockHeaderHash,
+        fd: R,
+        block_limit: &ExecutionCost,
+        stacks_epoch_id: &StacksEpochId,
+    ) -> Result<MemPoolImportSummary, db_error> {
+        let mut txs = vec![];
+        for line in fd.lines() {
+            let line = line.map_err(db_error::IOError)?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let entry: MemPoolExportEntry =
+                serde_json::from_str(&line).map_err(db_error::SerializationError)?;
+            let tx = entry.decode_tx()?;
+            txs.push((tx, entry.expiration_height, entry.bundle_id));
+        }
+
+        let mut summary = MemPoolImportSummary::default();
+        for (tx, expiration_height, bundle_id) in txs.iter() {
+            let txid = tx.txid();
+            if self.has_tx(&txid) {
+                summary.already_present += 1;
+                continue;
+            }
+            match self.submit_with_options(
+                chainstate,
+                consensus_hash,
+                block_hash,
+                tx,
+                None,
+                block_limit,
+                stacks_epoch_id,
+                *expiration_height,
+                bundle_id.as_deref(),
+            ) {
+                Ok(()) => summary.imported += 1,
+                Err(e) => {
+                    debug!("Mempool rejected imported transaction {}: {:?}", &txid, &e);
+                    summary.rejected += 1;
+                }
+            }
+        }
+        Ok(summary)
+    }
+
     #[cfg(test)]
     pub fn dump_txs(&self) {
         let sql = "SELECT * FROM mempool";
@@ -2170,7 +3199,7 @@ impl MemPoolDB {
                 b
             }
             Err(e) => {
-                warn!("Failed to query txid: {:?}", &e);
+                component_warn!(Mempool, "Failed to query txid: {:?}", &e);
                 false
             }
         }
@@ -2375,7 +3404,8 @@ impl MemPoolDB {
 
                 if query.num_txs >= query.max_txs {
                     // no more space in this stream
-                    debug!(
+                    component_debug!(
+                        Mempool,
                         "No more space in this query after {:?}. Corking tx stream.",
                         &query.last_randomized_txid
                     );
@@ -2403,7 +3433,8 @@ impl MemPoolDB {
                         remaining,
                     )?;
 
-                debug!(
+                component_debug!(
+                    Mempool,
                     "Streaming mempool propagation stepped";
                     "rows_visited" => num_rows_visited,
                     "last_rand_txid" => %query.last_randomized_txid,
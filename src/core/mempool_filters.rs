@@ -0,0 +1,268 @@
+// Copyright (C) 2013-2020 Blockstack PBC, a public benefit corporation
+// Copyright (C) 2020 Stacks Open Internet Foundation
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Temporary filters a node's operator installs to keep transactions out of its mempool and out
+//! of the blocks it mines, e.g. while a misbehaving contract is looked into.  Each filter matches
+//! transactions by origin address, by contract, or by called function, and expires on its own.
+//! Every change to the filters is logged, so that the operator's policy can be audited.
+
+use std::convert::TryFrom;
+
+use rusqlite::types::ToSql;
+use rusqlite::Row;
+
+use crate::chainstate::stacks::{StacksTransaction, TransactionPayload};
+use crate::core::mempool::MemPoolDB;
+use crate::util_lib::db::Error as db_error;
+use crate::util_lib::db::{query_row, query_rows, u64_to_sql, DBConn, DBTx, FromColumn, FromRow};
+use clarity::vm::types::QualifiedContractIdentifier;
+use clarity::vm::ClarityName;
+use stacks_common::types::chainstate::StacksAddress;
+
+/// The longest a filter can apply for: 30 days
+pub const MAX_FILTER_TTL_SECS: u64 = 30 * 24 * 3600;
+
+/// What a mempool filter matches transactions by
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MemPoolFilterKind {
+    /// the transaction's origin address
+    Origin,
+    /// the contract a transaction calls or publishes
+    Contract,
+    /// the name of the function a transaction calls, in any contract
+    Function,
+}
+
+impl MemPoolFilterKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MemPoolFilterKind::Origin => "origin",
+            MemPoolFilterKind::Contract => "contract",
+            MemPoolFilterKind::Function => "function",
+        }
+    }
+
+    pub fn from_str(kind: &str) -> Option<MemPoolFilterKind> {
+        match kind {
+            "origin" => Some(MemPoolFilterKind::Origin),
+            "contract" => Some(MemPoolFilterKind::Contract),
+            "function" => Some(MemPoolFilterKind::Function),
+            _ => None,
+        }
+    }
+
+    /// Check that `value` is an address, contract identifier or function name, as this kind
+    /// of filter needs, and get the form it is matched in
+    pub fn parse_value(&self, value: &str) -> Result<String, String> {
+        match self {
+            MemPoolFilterKind::Origin => StacksAddress::from_string(value)
+                .map(|addr| addr.to_string())
+                .ok_or_else(|| format!("Invalid address: {}", value)),
+            MemPoolFilterKind::Contract => QualifiedContractIdentifier::parse(value)
+                .map(|contract_id| contract_id.to_string())
+                .map_err(|_e| format!("Invalid contract identifier: {}", value)),
+            MemPoolFilterKind::Function => ClarityName::try_from(value.to_string())
+                .map(|name| name.to_string())
+                .map_err(|_e| format!("Invalid function name: {}", value)),
+        }
+    }
+}
+
+/// A filter installed by the node's operator
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MemPoolFilter {
+    pub filter_id: u64,
+    pub kind: MemPoolFilterKind,
+    pub value: String,
+    /// why the operator installed the filter
+    pub reason: String,
+    /// when the filter was installed, in seconds since the Unix epoch
+    pub created_at: u64,
+    /// when the filter stops applying, in seconds since the Unix epoch
+    pub expires_at: u64,
+}
+
+impl FromRow<MemPoolFilter> for MemPoolFilter {
+    fn from_row<'a>(row: &'a Row) -> Result<MemPoolFilter, db_error> {
+        let filter_id = u64::from_column(row, "filter_id")?;
+        let kind_text: String = row.get_unwrap("kind");
+        let kind = MemPoolFilterKind::from_str(&kind_text).ok_or(db_error::ParseError)?;
+        let value: String = row.get_unwrap("value");
+        let reason: String = row.get_unwrap("reason");
+        let created_at = u64::from_column(row, "created_at")?;
+        let expires_at = u64::from_column(row, "expires_at")?;
+        Ok(MemPoolFilter {
+            filter_id,
+            kind,
+            value,
+            reason,
+            created_at,
+            expires_at,
+        })
+    }
+}
+
+impl MemPoolFilter {
+    /// Does this filter match `tx`?
+    pub fn matches(&self, tx: &StacksTransaction) -> bool {
+        match self.kind {
+            MemPoolFilterKind::Origin => tx.origin_address().to_string() == self.value,
+            MemPoolFilterKind::Contract => match tx.payload {
+                TransactionPayload::ContractCall(ref call) => {
+                    call.to_clarity_contract_id().to_string() == self.value
+                }
+                TransactionPayload::SmartContract(ref contract, _) => {
+                    format!("{}.{}", tx.origin_address(), contract.name) == self.value
+                }
+                _ => false,
+            },
+            MemPoolFilterKind::Function => match tx.payload {
+                TransactionPayload::ContractCall(ref call) => {
+                    call.function_name.as_str() == self.value
+                }
+                _ => false,
+            },
+        }
+    }
+}
+
+impl MemPoolDB {
+    /// Get the filters which have not expired by `now_secs`, oldest first
+    pub fn get_active_filters(
+        conn: &DBConn,
+        now_secs: u64,
+    ) -> Result<Vec<MemPoolFilter>, db_error> {
+        let sql = "SELECT * FROM mempool_filters WHERE expires_at > ?1 ORDER BY filter_id ASC";
+        let args: &[&dyn ToSql] = &[&u64_to_sql(now_secs)?];
+        query_rows(conn, sql, args)
+    }
+
+    /// Get the first unexpired filter which matches `tx`, if any
+    pub fn find_matching_filter(
+        conn: &DBConn,
+        tx: &StacksTransaction,
+        now_secs: u64,
+    ) -> Result<Option<MemPoolFilter>, db_error> {
+        Ok(MemPoolDB::get_active_filters(conn, now_secs)?
+            .into_iter()
+            .find(|filter| filter.matches(tx)))
+    }
+
+    /// Install a filter on `value`, which must have been checked with
+    /// `MemPoolFilterKind::parse_value`, for the next `ttl_secs` seconds, up to
+    /// `MAX_FILTER_TTL_SECS`
+    pub fn install_filter(
+        &mut self,
+        kind: MemPoolFilterKind,
+        value: &str,
+        ttl_secs: u64,
+        reason: &str,
+    ) -> Result<MemPoolFilter, db_error> {
+        let now = self.get_clock().now_secs();
+        let expires_at = now.saturating_add(ttl_secs.min(MAX_FILTER_TTL_SECS));
+        let tx = self.tx_begin()?;
+        MemPoolDB::inner_prune_expired_filters(&tx, now)?;
+
+        let args: &[&dyn ToSql] = &[
+            &kind.as_str(),
+            &value,
+            &reason,
+            &u64_to_sql(now)?,
+            &u64_to_sql(expires_at)?,
+        ];
+        tx.execute(
+            "INSERT INTO mempool_filters (kind, value, reason, created_at, expires_at) VALUES (?1, ?2, ?3, ?4, ?5)",
+            args,
+        )?;
+        let filter = MemPoolFilter {
+            filter_id: tx.last_insert_rowid() as u64,
+            kind,
+            value: value.to_string(),
+            reason: reason.to_string(),
+            created_at: now,
+            expires_at,
+        };
+        tx.commit()?;
+
+        info!("Installed mempool filter";
+              "filter_id" => filter.filter_id,
+              "kind" => filter.kind.as_str(),
+              "value" => &filter.value,
+              "reason" => &filter.reason,
+              "expires_at" => filter.expires_at);
+        Ok(filter)
+    }
+
+    /// Remove a filter before it expires.  Returns the removed filter, or `None` if there was
+    /// no such filter, or it had already expired.
+    pub fn remove_filter(&mut self, filter_id: u64) -> Result<Option<MemPoolFilter>, db_error> {
+        let now = self.get_clock().now_secs();
+        let tx = self.tx_begin()?;
+        MemPoolDB::inner_prune_expired_filters(&tx, now)?;
+
+        let args: &[&dyn ToSql] = &[&u64_to_sql(filter_id)?];
+        let filter: Option<MemPoolFilter> = query_row(
+            &tx,
+            "SELECT * FROM mempool_filters WHERE filter_id = ?1",
+            args,
+        )?;
+        if filter.is_some() {
+            tx.execute("DELETE FROM mempool_filters WHERE filter_id = ?1", args)?;
+        }
+        tx.commit()?;
+
+        if let Some(ref filter) = filter {
+            info!("Removed mempool filter";
+                  "filter_id" => filter.filter_id,
+                  "kind" => filter.kind.as_str(),
+                  "value" => &filter.value,
+                  "reason" => &filter.reason);
+        }
+        Ok(filter)
+    }
+
+    /// Delete the filters which have expired, and get the unexpired ones
+    pub fn prune_expired_filters(&mut self) -> Result<Vec<MemPoolFilter>, db_error> {
+        let now = self.get_clock().now_secs();
+        let tx = self.tx_begin()?;
+        MemPoolDB::inner_prune_expired_filters(&tx, now)?;
+        tx.commit()?;
+        MemPoolDB::get_active_filters(self.conn(), now)
+    }
+
+    fn inner_prune_expired_filters(tx: &DBTx, now_secs: u64) -> Result<(), db_error> {
+        let args: &[&dyn ToSql] = &[&u64_to_sql(now_secs)?];
+        let expired: Vec<MemPoolFilter> = query_rows(
+            tx,
+            "SELECT * FROM mempool_filters WHERE expires_at <= ?1",
+            args,
+        )?;
+        if expired.is_empty() {
+            return Ok(());
+        }
+        tx.execute("DELETE FROM mempool_filters WHERE expires_at <= ?1", args)?;
+        for filter in expired.iter() {
+            info!("Mempool filter expired";
+                  "filter_id" => filter.filter_id,
+                  "kind" => filter.kind.as_str(),
+                  "value" => &filter.value,
+                  "reason" => &filter.reason,
+                  "expires_at" => filter.expires_at);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,253 @@
+// Copyright (C) 2013-2020 Blockstack PBC, a public benefit corporation
+// Copyright (C) 2020 Stacks Open Internet Foundation
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! The mempool arrival journal: every transaction submitted to a mempool, in the order it
+//! arrived and with the time it arrived, whether or not the mempool admitted it.  Which
+//! transactions a miner picks, and in what order, can depend on the order they arrived in, so
+//! replaying a journal into a scratch mempool with `MemPoolDB::replay_arrivals` reproduces the
+//! mempool a reported block was assembled from.  Journaling is off unless the node's operator
+//! turns it on.
+
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, Write};
+use std::sync::Arc;
+
+use crate::burnchains::Txid;
+use crate::chainstate::burn::ConsensusHash;
+use crate::chainstate::stacks::db::blocks::MemPoolRejection;
+use crate::chainstate::stacks::db::StacksChainState;
+use crate::chainstate::stacks::StacksTransaction;
+use crate::codec::StacksMessageCodec;
+use crate::core::mempool::MemPoolDB;
+use crate::core::StacksEpochId;
+use crate::types::chainstate::BlockHeaderHash;
+use crate::util_lib::clock::ManualClock;
+use crate::util_lib::db::Error as db_error;
+use clarity::vm::costs::ExecutionCost;
+use stacks_common::util::hash::{hex_bytes, to_hex};
+
+/// A transaction's arrival at the mempool, as written to the journal, one JSON object per line
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MemPoolArrival {
+    pub txid: String,
+    /// when the transaction arrived, by the mempool's clock, in milliseconds since the Unix epoch
+    pub arrival_time_ms: u64,
+    /// the transaction's consensus serialization, hex-encoded
+    pub tx: String,
+    /// the last Stacks block height the transaction may be mined at, if its sender set one
+    #[serde(default)]
+    pub expiration_height: Option<u64>,
+    /// the bundle the transaction was submitted in, if any
+    #[serde(default)]
+    pub bundle_id: Option<String>,
+}
+
+impl MemPoolArrival {
+    /// Decode the arrival's transaction, checking it against the arrival's txid
+    pub fn decode_tx(&self) -> Result<StacksTransaction, db_error> {
+        let tx_bytes = hex_bytes(&self.tx).map_err(|_| db_error::ParseError)?;
+        let tx = StacksTransaction::consensus_deserialize(&mut &tx_bytes[..])
+            .map_err(|_| db_error::ParseError)?;
+        if format!("{}", &tx.txid()) != self.txid {
+            return Err(db_error::ParseError);
+        }
+        Ok(tx)
+    }
+}
+
+/// Read the arrivals in a journal, in the order they were written.  Fails if the journal holds
+/// anything but arrivals.
+pub fn read_arrival_journal<R: BufRead>(fd: R) -> Result<Vec<MemPoolArrival>, db_error> {
+    let mut arrivals = vec![];
+    for line in fd.lines() {
+        let line = line.map_err(db_error::IOError)?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let arrival: MemPoolArrival =
+            serde_json::from_str(&line).map_err(db_error::SerializationError)?;
+        arrivals.push(arrival);
+    }
+    Ok(arrivals)
+}
+
+/// Appends each transaction submitted to a mempool to a journal file.  Each arrival is written
+/// with a single write to a file opened for appending, so the mempools of a node's threads can
+/// share one journal.
+#[derive(Debug)]
+pub struct MemPoolArrivalJournal {
+    path: String,
+    file: File,
+}
+
+impl MemPoolArrivalJournal {
+    /// Open the journal at `path`, creating it if need be.  Arrivals are added to its end.
+    pub fn open(path: &str) -> Result<MemPoolArrivalJournal, db_error> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(db_error::IOError)?;
+        Ok(MemPoolArrivalJournal {
+            path: path.to_string(),
+            file,
+        })
+    }
+
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// Record that `tx` arrived at `arrival_time_ms`
+    pub fn record(
+        &mut self,
+        tx: &StacksTransaction,
+        arrival_time_ms: u64,
+        expiration_height: Option<u64>,
+        bundle_id: Option<&str>,
+    ) -> Result<(), db_error> {
+        let arrival = MemPoolArrival {
+            txid: format!("{}", &tx.txid()),
+            arrival_time_ms,
+            tx: to_hex(&tx.serialize_to_vec()),
+            expiration_height,
+            bundle_id: bundle_id.map(|bundle_id| bundle_id.to_string()),
+        };
+        let mut line = serde_json::to_string(&arrival).map_err(db_error::SerializationError)?;
+        line.push('\n');
+        self.file
+            .write_all(line.as_bytes())
+            .map_err(db_error::IOError)?;
+        Ok(())
+    }
+}
+
+impl MemPoolDB {
+    /// Journal every transaction submitted to this mempool to `journal`, or stop journaling if
+    /// it is `None`
+    pub fn set_arrival_journal(&mut self, journal: Option<MemPoolArrivalJournal>) {
+        self.arrival_journal = journal;
+    }
+
+    /// Submit `arrivals`, in order, against the chain tip `consensus_hash`/`block_hash`, as if
+    /// each arrived at its recorded time.  The mempool's clock is replaced with `clock`, which is
+    /// set to each arrival's time as it is submitted, so the mempool stamps each transaction as
+    /// the journaled mempool did.  Returns each arrival's txid, and whether it was admitted.
+    pub fn replay_arrivals(
+        &mut self,
+        chainstate: &mut StacksChainState,
+        consensus_hash: &ConsensusHash,
+        block_hash: &BlockHeaderHash,
+        arrivals: &[MemPoolArrival],
+        block_limit: &ExecutionCost,
+        stacks_epoch_id: &StacksEpochId,
+        clock: &ManualClock,
+    ) -> Result<Vec<(Txid, Result<(), MemPoolRejection>)>, db_error> {
+        let txs = arrivals
+            .iter()
+            .map(|arrival| arrival.decode_tx())
+            .collect::<Result<Vec<_>, _>>()?;
+
+        self.set_clock(Arc::new(clock.clone()));
+        let mut results = vec![];
+        for (arrival, tx) in arrivals.iter().zip(txs.iter()) {
+            clock.set_ms(arrival.arrival_time_ms);
+            let result = self.submit_with_options(
+                chainstate,
+                consensus_hash,
+                block_hash,
+                tx,
+                None,
+                block_limit,
+                stacks_epoch_id,
+                arrival.expiration_height,
+                arrival.bundle_id.as_deref(),
+            );
+            results.push((tx.txid(), result));
+        }
+        Ok(results)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::chainstate::stacks::{
+        StacksPrivateKey, StacksPublicKey, TokenTransferMemo, TransactionAuth, TransactionPayload,
+        TransactionSpendingCondition, TransactionVersion,
+    };
+    use crate::types::chainstate::StacksAddress;
+    use crate::util::hash::Hash160;
+
+    fn make_transfer(nonce: u64) -> StacksTransaction {
+        let privk = StacksPrivateKey::new();
+        let mut spending_condition = TransactionSpendingCondition::new_singlesig_p2pkh(
+            StacksPublicKey::from_private(&privk),
+        )
+        .unwrap();
+        spending_condition.set_nonce(nonce);
+        spending_condition.set_tx_fee(180);
+        StacksTransaction::new(
+            TransactionVersion::Testnet,
+            TransactionAuth::Standard(spending_condition),
+            TransactionPayload::TokenTransfer(
+                StacksAddress {
+                    version: 26,
+                    bytes: Hash160([0xff; 20]),
+                }
+                .into(),
+                100,
+                TokenTransferMemo([0u8; 34]),
+            ),
+        )
+    }
+
+    #[test]
+    fn test_arrival_journal_roundtrip() {
+        let path = "/tmp/test_arrival_journal_roundtrip.jsonl";
+        let _ = std::fs::remove_file(path);
+
+        let first = make_transfer(0);
+        let second = make_transfer(1);
+        {
+            let mut journal = MemPoolArrivalJournal::open(path).unwrap();
+            journal.record(&first, 1_000, None, None).unwrap();
+        }
+        // reopening appends
+        let mut journal = MemPoolArrivalJournal::open(path).unwrap();
+        journal
+            .record(&second, 1_250, Some(10), Some("bundle"))
+            .unwrap();
+
+        let arrivals =
+            read_arrival_journal(std::io::BufReader::new(File::open(path).unwrap())).unwrap();
+        assert_eq!(arrivals.len(), 2);
+        assert_eq!(arrivals[0].arrival_time_ms, 1_000);
+        assert_eq!(arrivals[0].decode_tx().unwrap(), first);
+        assert_eq!(arrivals[1].arrival_time_ms, 1_250);
+        assert_eq!(arrivals[1].expiration_height, Some(10));
+        assert_eq!(arrivals[1].bundle_id.as_deref(), Some("bundle"));
+        assert_eq!(arrivals[1].decode_tx().unwrap(), second);
+
+        // an arrival whose transaction doesn't match its txid is refused
+        let mut tampered = arrivals[0].clone();
+        tampered.txid = arrivals[1].txid.clone();
+        assert!(tampered.decode_tx().is_err());
+
+        // as is a journal holding anything else
+        assert!(read_arrival_journal(&b"not an arrival\n"[..]).is_err());
+    }
+}
@@ -0,0 +1,190 @@
+// Copyright (C) 2013-2020 Blockstack PBC, a public benefit corporation
+// Copyright (C) 2020 Stacks Open Internet Foundation
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! A record of the transactions a node's mempool refused, and why, so that "why wasn't my
+//! transaction mined" can be answered after the fact.  Recording is off unless the node's
+//! operator turns it on, and the record only keeps each transaction's latest rejection, for a
+//! bounded time and up to a bounded number of transactions.
+
+use rusqlite::types::ToSql;
+use rusqlite::Row;
+
+use crate::burnchains::Txid;
+use crate::chainstate::burn::ConsensusHash;
+use crate::chainstate::stacks::db::blocks::MemPoolRejection;
+use crate::chainstate::stacks::StacksTransaction;
+use crate::core::mempool::MemPoolDB;
+use crate::types::chainstate::{BlockHeaderHash, StacksAddress};
+use crate::util_lib::db::Error as db_error;
+use crate::util_lib::db::{query_row, query_rows, u64_to_sql, DBConn, DBTx, FromColumn, FromRow};
+
+/// By default, keep at most this many rejected transactions
+pub const DEFAULT_REJECTED_TXS_MAX_COUNT: u64 = 10_000;
+/// By default, keep rejected transactions for a day
+pub const DEFAULT_REJECTED_TXS_MAX_AGE_SECS: u64 = 24 * 3600;
+
+/// How long the mempool keeps rejected transactions for
+#[derive(Debug, Clone, PartialEq)]
+pub struct RejectedTxsRetention {
+    /// once there are more rejected transactions than this, the oldest are dropped
+    pub max_count: u64,
+    /// rejected transactions are dropped this many seconds after their rejection
+    pub max_age_secs: u64,
+}
+
+impl Default for RejectedTxsRetention {
+    fn default() -> RejectedTxsRetention {
+        RejectedTxsRetention {
+            max_count: DEFAULT_REJECTED_TXS_MAX_COUNT,
+            max_age_secs: DEFAULT_REJECTED_TXS_MAX_AGE_SECS,
+        }
+    }
+}
+
+/// A transaction the mempool rejected
+#[derive(Debug, Clone, PartialEq)]
+pub struct RejectedTx {
+    pub txid: Txid,
+    pub origin_address: StacksAddress,
+    /// the origin address again, if the transaction is not sponsored
+    pub sponsor_address: StacksAddress,
+    /// the rejection's reason code, as `MemPoolRejection::reason` gives it
+    pub reason: String,
+    /// the rejection's details, if it has any
+    pub reason_data: Option<serde_json::Value>,
+    /// the chain tip the transaction was checked against
+    pub consensus_hash: ConsensusHash,
+    pub block_header_hash: BlockHeaderHash,
+    /// when the transaction was rejected, in seconds since the Unix epoch
+    pub rejected_at: u64,
+}
+
+impl FromRow<RejectedTx> for RejectedTx {
+    fn from_row<'a>(row: &'a Row) -> Result<RejectedTx, db_error> {
+        let txid = Txid::from_column(row, "txid")?;
+        let origin_address = StacksAddress::from_column(row, "origin_address")?;
+        let sponsor_address = StacksAddress::from_column(row, "sponsor_address")?;
+        let reason: String = row.get_unwrap("reason");
+        let reason_data_text: Option<String> = row.get_unwrap("reason_data");
+        let reason_data = match reason_data_text {
+            Some(text) => Some(serde_json::from_str(&text).map_err(|_e| db_error::ParseError)?),
+            None => None,
+        };
+        let consensus_hash = ConsensusHash::from_column(row, "consensus_hash")?;
+        let block_header_hash = BlockHeaderHash::from_column(row, "block_header_hash")?;
+        let rejected_at = u64::from_column(row, "rejected_at")?;
+        Ok(RejectedTx {
+            txid,
+            origin_address,
+            sponsor_address,
+            reason,
+            reason_data,
+            consensus_hash,
+            block_header_hash,
+            rejected_at,
+        })
+    }
+}
+
+impl MemPoolDB {
+    /// Record that `tx`, checked against the chain tip `consensus_hash`/`block_hash`, was
+    /// rejected, replacing any earlier rejection of it, and drop the rejections the retention
+    /// limits no longer allow.  Does nothing unless the mempool keeps rejected transactions.
+    pub fn record_rejected_tx(
+        &mut self,
+        tx: &StacksTransaction,
+        consensus_hash: &ConsensusHash,
+        block_hash: &BlockHeaderHash,
+        rejection: &MemPoolRejection,
+    ) -> Result<(), db_error> {
+        let retention = match self.get_rejected_txs_retention() {
+            Some(retention) => retention.clone(),
+            None => return Ok(()),
+        };
+        let now = self.get_clock().now_secs();
+        let origin_address = tx.origin_address();
+        let sponsor_address = tx.sponsor_address().unwrap_or(origin_address.clone());
+        let (reason, reason_data) = rejection.reason();
+        let reason_data_text = reason_data.map(|data| data.to_string());
+
+        let tx_db = self.tx_begin()?;
+        let args: &[&dyn ToSql] = &[
+            &tx.txid(),
+            &origin_address.to_string(),
+            &sponsor_address.to_string(),
+            &reason,
+            &reason_data_text,
+            consensus_hash,
+            block_hash,
+            &u64_to_sql(now)?,
+        ];
+        tx_db.execute(
+            "INSERT OR REPLACE INTO rejected_txs (txid, origin_address, sponsor_address, reason, reason_data, consensus_hash, block_header_hash, rejected_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            args,
+        )?;
+        MemPoolDB::inner_prune_rejected_txs(&tx_db, &retention, now)?;
+        tx_db.commit()
+    }
+
+    /// Forget the rejection of `txid`, which the mempool has since accepted
+    pub fn clear_rejected_tx(&mut self, txid: &Txid) -> Result<(), db_error> {
+        if self.get_rejected_txs_retention().is_none() {
+            return Ok(());
+        }
+        let args: &[&dyn ToSql] = &[txid];
+        self.conn()
+            .execute("DELETE FROM rejected_txs WHERE txid = ?1", args)?;
+        Ok(())
+    }
+
+    fn inner_prune_rejected_txs(
+        tx: &DBTx,
+        retention: &RejectedTxsRetention,
+        now_secs: u64,
+    ) -> Result<(), db_error> {
+        let cutoff = now_secs.saturating_sub(retention.max_age_secs);
+        let args: &[&dyn ToSql] = &[&u64_to_sql(cutoff)?];
+        tx.execute("DELETE FROM rejected_txs WHERE rejected_at <= ?1", args)?;
+
+        let args: &[&dyn ToSql] = &[&u64_to_sql(retention.max_count)?];
+        tx.execute(
+            "DELETE FROM rejected_txs WHERE txid NOT IN (SELECT txid FROM rejected_txs ORDER BY rejected_at DESC, txid ASC LIMIT ?1)",
+            args,
+        )?;
+        Ok(())
+    }
+
+    /// Get the latest rejection of `txid`, if it is still kept
+    pub fn get_rejected_tx(conn: &DBConn, txid: &Txid) -> Result<Option<RejectedTx>, db_error> {
+        let args: &[&dyn ToSql] = &[txid];
+        query_row(conn, "SELECT * FROM rejected_txs WHERE txid = ?1", args)
+    }
+
+    /// Get up to `limit` of the kept rejections of transactions which `address` sent or
+    /// sponsored, newest first
+    pub fn get_rejected_txs_by_address(
+        conn: &DBConn,
+        address: &StacksAddress,
+        limit: u64,
+    ) -> Result<Vec<RejectedTx>, db_error> {
+        let args: &[&dyn ToSql] = &[&address.to_string(), &u64_to_sql(limit)?];
+        query_rows(
+            conn,
+            "SELECT * FROM rejected_txs WHERE origin_address = ?1 OR sponsor_address = ?1 ORDER BY rejected_at DESC, txid ASC LIMIT ?2",
+            args,
+        )
+    }
+}
@@ -0,0 +1,194 @@
+// Copyright (C) 2013-2020 Blockstack PBC, a public benefit corporation
+// Copyright (C) 2020 Stacks Open Internet Foundation
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! A summary of what is in the mempool: how much, at what fee rates, how long it has waited, and
+//! of what kinds, for dashboards and fee estimators that can't read the mempool's database.
+//! Every figure is counted by a query over one of the mempool table's indexes, so the summary
+//! stays cheap however large the mempool grows.
+
+use std::collections::BTreeMap;
+
+use rusqlite::types::ToSql;
+use rusqlite::NO_PARAMS;
+
+use crate::core::mempool::MemPoolDB;
+use crate::util_lib::db::Error as db_error;
+use crate::util_lib::db::{query_count, u64_to_sql, DBConn};
+
+/// The boundaries between the fee-rate histogram's buckets.  The first bucket counts the
+/// transactions below the first boundary, and the last those at or above the last.
+pub const MEMPOOL_STATS_FEE_RATE_BOUNDS: &'static [f64] =
+    &[1.0, 10.0, 100.0, 1_000.0, 10_000.0, 100_000.0];
+/// The boundaries between the age histogram's buckets, in seconds
+pub const MEMPOOL_STATS_AGE_BOUNDS_SECS: &'static [u64] = &[60, 600, 3_600, 6 * 3_600, 24 * 3_600];
+/// What the payload-type counts file transactions whose type isn't recorded under
+pub const UNKNOWN_PAYLOAD_TYPE: &'static str = "Unknown";
+
+/// The transactions whose estimated fee rate is at least `min` and below `max`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MemPoolFeeRateBucket {
+    pub min: f64,
+    /// None for the last bucket, which has no upper bound
+    pub max: Option<f64>,
+    pub count: u64,
+}
+
+/// The transactions which have been in the mempool for at least `min_age_secs` seconds, and
+/// less than `max_age_secs`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MemPoolAgeBucket {
+    pub min_age_secs: u64,
+    /// None for the last bucket, which has no upper bound
+    pub max_age_secs: Option<u64>,
+    pub count: u64,
+}
+
+/// A summary of the mempool's contents
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MemPoolStats {
+    pub tx_count: u64,
+    /// the transactions' total serialized size
+    pub total_bytes: u64,
+    /// the transactions which have a fee-rate estimate, by their estimate
+    pub fee_rates: Vec<MemPoolFeeRateBucket>,
+    /// the transactions which have no fee-rate estimate
+    pub no_fee_rate_estimate: u64,
+    /// the transactions, by how long ago the mempool admitted them
+    pub ages: Vec<MemPoolAgeBucket>,
+    /// the transactions, by `TransactionPayload::name` of their payload
+    pub payload_types: BTreeMap<String, u64>,
+}
+
+impl MemPoolDB {
+    /// Summarize the mempool's contents, counting transactions' ages as of `now_secs`
+    pub fn get_stats(conn: &DBConn, now_secs: u64) -> Result<MemPoolStats, db_error> {
+        let (tx_count, total_bytes) = conn.query_row(
+            "SELECT COUNT(*), IFNULL(SUM(length), 0) FROM mempool",
+            NO_PARAMS,
+            |row| Ok((row.get::<_, i64>(0)?, row.get::<_, i64>(1)?)),
+        )?;
+
+        let mut fee_rates = vec![];
+        let mut min = 0.0;
+        for max in MEMPOOL_STATS_FEE_RATE_BOUNDS
+            .iter()
+            .map(|bound| Some(*bound))
+            .chain(Some(None))
+        {
+            let count = match max {
+                Some(max) => {
+                    let args: &[&dyn ToSql] = &[&min, &max];
+                    query_count(
+                        conn,
+                        "SELECT COUNT(*) FROM mempool WHERE fee_rate >= ?1 AND fee_rate < ?2",
+                        args,
+                    )?
+                }
+                None => {
+                    let args: &[&dyn ToSql] = &[&min];
+                    query_count(
+                        conn,
+                        "SELECT COUNT(*) FROM mempool WHERE fee_rate >= ?1",
+                        args,
+                    )?
+                }
+            };
+            fee_rates.push(MemPoolFeeRateBucket {
+                min,
+                max,
+                count: count as u64,
+            });
+            if let Some(max) = max {
+                min = max;
+            }
+        }
+        let no_fee_rate_estimate = query_count(
+            conn,
+            "SELECT COUNT(*) FROM mempool WHERE fee_rate IS NULL",
+            NO_PARAMS,
+        )?;
+
+        // a transaction `age` seconds old was admitted at `now_secs - age`.  Transactions
+        //  stamped after `now_secs`, by a clock which has since gone back, count as new.
+        let mut ages = vec![];
+        let mut min_age_secs = 0;
+        for max_age_secs in MEMPOOL_STATS_AGE_BOUNDS_SECS
+            .iter()
+            .map(|bound| Some(*bound))
+            .chain(Some(None))
+        {
+            let newest = u64_to_sql(now_secs.saturating_sub(min_age_secs))?;
+            let count = match max_age_secs {
+                Some(max_age_secs) => {
+                    let oldest = u64_to_sql(now_secs.saturating_sub(max_age_secs))?;
+                    if min_age_secs == 0 {
+                        let args: &[&dyn ToSql] = &[&oldest];
+                        query_count(
+                            conn,
+                            "SELECT COUNT(*) FROM mempool WHERE accept_time > ?1",
+                            args,
+                        )?
+                    } else {
+                        let args: &[&dyn ToSql] = &[&oldest, &newest];
+                        query_count(
+                            conn,
+                            "SELECT COUNT(*) FROM mempool WHERE accept_time > ?1 AND accept_time <= ?2",
+                            args,
+                        )?
+                    }
+                }
+                None => {
+                    let args: &[&dyn ToSql] = &[&newest];
+                    query_count(
+                        conn,
+                        "SELECT COUNT(*) FROM mempool WHERE accept_time <= ?1",
+                        args,
+                    )?
+                }
+            };
+            ages.push(MemPoolAgeBucket {
+                min_age_secs,
+                max_age_secs,
+                count: count as u64,
+            });
+            if let Some(max_age_secs) = max_age_secs {
+                min_age_secs = max_age_secs;
+            }
+        }
+
+        let mut payload_types = BTreeMap::new();
+        let mut stmt =
+            conn.prepare("SELECT payload_type, COUNT(*) FROM mempool GROUP BY payload_type")?;
+        let mut rows = stmt.query(NO_PARAMS)?;
+        while let Some(row) = rows.next()? {
+            let payload_type: Option<String> = row.get(0)?;
+            let count: i64 = row.get(1)?;
+            payload_types.insert(
+                payload_type.unwrap_or_else(|| UNKNOWN_PAYLOAD_TYPE.to_string()),
+                count as u64,
+            );
+        }
+
+        Ok(MemPoolStats {
+            tx_count: tx_count as u64,
+            total_bytes: total_bytes as u64,
+            fee_rates,
+            no_fee_rate_estimate: no_fee_rate_estimate as u64,
+            ages,
+            payload_types,
+        })
+    }
+}
@@ -20,6 +20,7 @@ use crate::burnchains::Error as burnchain_error;
 use crate::chainstate::burn::ConsensusHash;
 use clarity::vm::costs::ExecutionCost;
 use clarity::vm::types::QualifiedContractIdentifier;
+use stacks_common::util::hash::Sha512Trunc256Sum;
 use stacks_common::util::log;
 use std::collections::HashSet;
 use std::convert::TryFrom;
@@ -30,6 +31,10 @@ use crate::types::chainstate::{BlockHeaderHash, BurnchainHeaderHash};
 use stacks_common::types::StacksEpoch as GenericStacksEpoch;
 pub use stacks_common::types::StacksEpochId;
 pub mod mempool;
+pub mod mempool_filters;
+pub mod mempool_journal;
+pub mod mempool_rejections;
+pub mod mempool_stats;
 
 #[cfg(test)]
 pub mod tests;
@@ -55,9 +60,20 @@ pub const SUBNETS_STACKS_EPOCH: StacksEpochId = StacksEpochId::Epoch21;
 // ClarityVersion in use.
 pub const SUBNETS_CLARITY_VERSION: ClarityVersion = ClarityVersion::Clarity2;
 
+/// The newest Clarity version a subnet block evaluated in `epoch_id` may publish contracts in.
+/// Subnets never go past `SUBNETS_CLARITY_VERSION`, even in epochs which support newer versions.
+pub fn max_clarity_version(epoch_id: StacksEpochId) -> ClarityVersion {
+    let epoch_version = ClarityVersion::default_for_epoch(epoch_id);
+    if epoch_version > SUBNETS_CLARITY_VERSION {
+        SUBNETS_CLARITY_VERSION
+    } else {
+        epoch_version
+    }
+}
+
 // peer version (big-endian)
 // first byte == major network protocol version (currently 0x18)
-// second and third bytes are unused
+// second and third bytes == network magic (see `peer_version_with_magic`)
 // fourth byte == highest epoch supported by this node
 pub const PEER_VERSION_MAINNET_MAJOR: u32 = 0x18000000;
 pub const PEER_VERSION_TESTNET_MAJOR: u32 = 0xfacade00;
@@ -82,6 +98,32 @@ pub const PEER_VERSION_TESTNET: u32 = PEER_VERSION_TESTNET_MAJOR | PEER_NETWORK_
 pub const NETWORK_ID_MAINNET: u32 = 0x17000000;
 pub const NETWORK_ID_TESTNET: u32 = 0xff000000;
 
+/// Derive a subnet's default chain ID from the identifier of its L1 contract, so that distinct
+/// subnets don't accept each other's transactions.  Never returns a layer 1 chain ID.
+pub fn subnet_chain_id_for_contract(contract: &QualifiedContractIdentifier) -> u32 {
+    let hash = Sha512Trunc256Sum::from_data(contract.to_string().as_bytes());
+    let mut chain_id_bytes = [0u8; 4];
+    chain_id_bytes.copy_from_slice(&hash.as_bytes()[0..4]);
+    let chain_id = u32::from_be_bytes(chain_id_bytes);
+    if chain_id == LAYER_1_CHAIN_ID_MAINNET || chain_id == LAYER_1_CHAIN_ID_TESTNET {
+        chain_id ^ 0x55005500
+    } else {
+        chain_id
+    }
+}
+
+/// Derive a subnet's default network magic bytes from the identifier of its L1 contract.
+pub fn subnet_network_magic_for_contract(contract: &QualifiedContractIdentifier) -> [u8; 2] {
+    let hash = Sha512Trunc256Sum::from_data(contract.to_string().as_bytes());
+    [hash.as_bytes()[4], hash.as_bytes()[5]]
+}
+
+/// Set the network magic bytes of a peer version (its second and third bytes).  Peers only
+/// complete a handshake if their peer versions agree on everything but the epoch byte.
+pub fn peer_version_with_magic(peer_version: u32, magic: [u8; 2]) -> u32 {
+    (peer_version & 0xff0000ff) | ((magic[0] as u32) << 16) | ((magic[1] as u32) << 8)
+}
+
 // default port
 pub const NETWORK_P2P_PORT: u16 = 6265;
 
@@ -295,6 +337,32 @@ fn test_ord_for_stacks_epoch() {
     assert_eq!(epochs[4].cmp(&epochs[3]), Ordering::Greater);
 }
 
+#[test]
+fn test_subnet_network_identifiers() {
+    let contract_1 =
+        QualifiedContractIdentifier::parse("ST1PQHQKV0RJXZFY1DGX8MNSNYVE3VGZJSRTPGZGM.subnet-v1")
+            .unwrap();
+    let contract_2 =
+        QualifiedContractIdentifier::parse("ST1PQHQKV0RJXZFY1DGX8MNSNYVE3VGZJSRTPGZGM.subnet-v2")
+            .unwrap();
+
+    let chain_id_1 = subnet_chain_id_for_contract(&contract_1);
+    assert_eq!(chain_id_1, subnet_chain_id_for_contract(&contract_1));
+    assert_ne!(chain_id_1, subnet_chain_id_for_contract(&contract_2));
+    assert_ne!(chain_id_1, LAYER_1_CHAIN_ID_MAINNET);
+    assert_ne!(chain_id_1, LAYER_1_CHAIN_ID_TESTNET);
+    assert_ne!(
+        subnet_network_magic_for_contract(&contract_1),
+        subnet_network_magic_for_contract(&contract_2)
+    );
+
+    // the magic replaces the middle bytes, and keeps the major version and epoch
+    assert_eq!(
+        peer_version_with_magic(PEER_VERSION_TESTNET, [0x12, 0x34]),
+        0xfa123400 | PEER_NETWORK_EPOCH
+    );
+}
+
 #[test]
 fn test_ord_for_stacks_epoch_id() {
     assert_eq!(
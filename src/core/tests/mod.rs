@@ -15,8 +15,9 @@
 // along with this program.  If not, see <http://www.gnu.org/licenses/>.
 
 use std::cmp;
-use std::collections::HashSet;
+use std::collections::{BTreeMap, HashSet};
 use std::io;
+use std::sync::Arc;
 
 use crate::burnchains::Address;
 use crate::burnchains::Txid;
@@ -39,9 +40,20 @@ use crate::chainstate::stacks::{
 use crate::chainstate::stacks::{
     C32_ADDRESS_VERSION_MAINNET_SINGLESIG, C32_ADDRESS_VERSION_TESTNET_SINGLESIG,
 };
+use crate::core::mempool::MemPoolAdmissionLimits;
+use crate::core::mempool::MemPoolExportEntry;
+use crate::core::mempool::MemPoolImportSummary;
 use crate::core::mempool::MemPoolWalkSettings;
 use crate::core::mempool::TxTag;
+use crate::core::mempool::MEMPOOL_SCHEMA_VERSION;
+use crate::core::mempool::{AdmissionCacheKey, AdmissionCacheResult, MemPoolAdmissionCache};
 use crate::core::mempool::{BLOOM_COUNTER_DEPTH, BLOOM_COUNTER_ERROR_RATE, MAX_BLOOM_COUNTER_TXS};
+use crate::core::mempool_filters::MemPoolFilterKind;
+use crate::core::mempool_rejections::RejectedTxsRetention;
+use crate::core::mempool_stats::{
+    MEMPOOL_STATS_AGE_BOUNDS_SECS, MEMPOOL_STATS_FEE_RATE_BOUNDS, UNKNOWN_PAYLOAD_TYPE,
+};
+use crate::core::StacksEpochId;
 use crate::core::FIRST_BURNCHAIN_CONSENSUS_HASH;
 use crate::core::FIRST_STACKS_BLOCK_HASH;
 use crate::net::Error as NetError;
@@ -49,6 +61,7 @@ use crate::net::HttpResponseType;
 use crate::net::MemPoolSyncData;
 use crate::util_lib::bloom::test::setup_bloom_counter;
 use crate::util_lib::bloom::*;
+use crate::util_lib::clock::ManualClock;
 use crate::util_lib::db::{tx_begin_immediate, DBConn, FromRow};
 use crate::util_lib::strings::StacksString;
 use clarity::vm::{
@@ -98,6 +111,35 @@ fn mempool_db_init() {
     let _mempool = MemPoolDB::open_test(false, 0x80000000, &chainstate_path).unwrap();
 }
 
+#[test]
+fn mempool_db_migrate_from_path() {
+    let _chainstate = instantiate_chainstate(false, 0x80000000, "mempool_db_migrate_from_path");
+    let chainstate_path = chainstate_path("mempool_db_migrate_from_path");
+
+    // no DB yet
+    assert_eq!(
+        MemPoolDB::get_schema_version_from_path(&chainstate_path).unwrap(),
+        None
+    );
+    assert!(MemPoolDB::migrate_from_path(&chainstate_path).is_err());
+
+    let _mempool = MemPoolDB::open_test(false, 0x80000000, &chainstate_path).unwrap();
+    assert_eq!(
+        MemPoolDB::get_schema_version_from_path(&chainstate_path).unwrap(),
+        Some(MEMPOOL_SCHEMA_VERSION)
+    );
+
+    // migrating an up-to-date DB is a no-op
+    assert_eq!(
+        MemPoolDB::migrate_from_path(&chainstate_path).unwrap(),
+        MEMPOOL_SCHEMA_VERSION
+    );
+    assert_eq!(
+        MemPoolDB::get_schema_version_from_path(&chainstate_path).unwrap(),
+        Some(MEMPOOL_SCHEMA_VERSION)
+    );
+}
+
 #[cfg(test)]
 pub fn make_block(
     chainstate: &mut StacksChainState,
@@ -2538,3 +2580,967 @@ fn test_decode_tx_stream() {
         }
     }
 }
+
+#[test]
+fn test_admission_cache() {
+    let key = |txid_byte: u8, tip_byte: u8| AdmissionCacheKey {
+        txid: Txid([txid_byte; 32]),
+        tip: StacksBlockId([tip_byte; 32]),
+        unconfirmed_tip: None,
+    };
+    let rejected = AdmissionCacheResult::Rejected {
+        reason: "BadNonce".into(),
+        reason_data: Some(json!({"expected": 1, "actual": 0})),
+    };
+
+    let mut cache = MemPoolAdmissionCache::new(2);
+    assert_eq!(cache.get(&key(1, 1)), None);
+    cache.insert(key(1, 1), AdmissionCacheResult::Admitted);
+    cache.insert(key(2, 1), rejected.clone());
+    assert_eq!(cache.get(&key(1, 1)), Some(AdmissionCacheResult::Admitted));
+    assert_eq!(cache.get(&key(2, 1)), Some(rejected.clone()));
+
+    // results are per chain tip
+    assert_eq!(cache.get(&key(1, 2)), None);
+
+    // the least recently used result is evicted first
+    cache.get(&key(1, 1));
+    cache.insert(key(3, 1), AdmissionCacheResult::Admitted);
+    assert_eq!(cache.get(&key(2, 1)), None);
+    assert_eq!(cache.get(&key(1, 1)), Some(AdmissionCacheResult::Admitted));
+    assert_eq!(cache.get(&key(3, 1)), Some(AdmissionCacheResult::Admitted));
+
+    // re-inserting a key replaces its result, and does not evict anything
+    cache.insert(key(3, 1), rejected.clone());
+    assert_eq!(cache.get(&key(3, 1)), Some(rejected));
+    assert_eq!(cache.get(&key(1, 1)), Some(AdmissionCacheResult::Admitted));
+
+    assert_eq!(cache.get_hits_and_misses(), (7, 3));
+}
+
+#[test]
+fn test_admission_limits() {
+    let privk = StacksPrivateKey::new();
+    let make_tx = |payload| {
+        let auth = TransactionAuth::from_p2pkh(&privk).unwrap();
+        let mut tx = StacksTransaction::new(TransactionVersion::Testnet, auth, payload);
+        tx.chain_id = 0x80000000;
+        tx
+    };
+    let transfer = make_tx(TransactionPayload::TokenTransfer(
+        PrincipalData::from(StacksAddress {
+            version: 1,
+            bytes: Hash160([0xff; 20]),
+        }),
+        123,
+        TokenTransferMemo([0u8; 34]),
+    ));
+    let publish = make_tx(TransactionPayload::SmartContract(
+        TransactionSmartContract {
+            name: ContractName::try_from("hello-world").unwrap(),
+            code_body: StacksString::from_str("(define-data-var bar int 0)").unwrap(),
+        },
+        None,
+    ));
+
+    // no limits by default
+    let limits = MemPoolAdmissionLimits::default();
+    assert!(limits.check(&transfer, 1_000_000).is_ok());
+    assert!(limits.check(&publish, 1_000_000).is_ok());
+
+    let limits = MemPoolAdmissionLimits {
+        max_tx_size: Some(1000),
+        ..MemPoolAdmissionLimits::default()
+    };
+    assert!(limits.check(&transfer, 1000).is_ok());
+    match limits.check(&transfer, 1001) {
+        Err(MemPoolRejection::TransactionTooLarge(1001, 1000)) => {}
+        x => panic!("Expected TransactionTooLarge, got {:?}", &x),
+    }
+
+    let limits = MemPoolAdmissionLimits {
+        allowed_payload_types: Some(
+            vec!["TokenTransfer".to_string(), "ContractCall".to_string()]
+                .into_iter()
+                .collect(),
+        ),
+        ..MemPoolAdmissionLimits::default()
+    };
+    assert!(limits.check(&transfer, 200).is_ok());
+    match limits.check(&publish, 200) {
+        Err(MemPoolRejection::PayloadTypeNotAllowed(payload_type)) => {
+            assert_eq!(payload_type, "SmartContract");
+        }
+        x => panic!("Expected PayloadTypeNotAllowed, got {:?}", &x),
+    }
+
+    // only contract publishes are subject to the code size limit
+    let limits = MemPoolAdmissionLimits {
+        max_contract_code_size: Some(10),
+        ..MemPoolAdmissionLimits::default()
+    };
+    assert!(limits.check(&transfer, 200).is_ok());
+    match limits.check(&publish, 200) {
+        Err(MemPoolRejection::ContractCodeTooLarge(27, 10)) => {}
+        x => panic!("Expected ContractCodeTooLarge, got {:?}", &x),
+    }
+
+    // a contract publish is kept out if its code alone writes more than one transaction may
+    let limits = MemPoolAdmissionLimits {
+        max_tx_write_length: Some(20),
+        ..MemPoolAdmissionLimits::default()
+    };
+    assert!(limits.check(&transfer, 200).is_ok());
+    match limits.check(&publish, 200) {
+        Err(MemPoolRejection::ContractPublishTooExpensive(27, 20)) => {}
+        x => panic!("Expected ContractPublishTooExpensive, got {:?}", &x),
+    }
+
+    // rejections which depend on the node's settings are not cached
+    assert!(!MemPoolRejection::PayloadTypeNotAllowed("SmartContract".into()).is_cacheable());
+}
+
+#[test]
+fn test_export_import_txs() {
+    let mut chainstate = instantiate_chainstate(false, 0x80000000, "test_export_import_txs");
+    let chainstate_path = chainstate_path("test_export_import_txs");
+    let mut mempool = MemPoolDB::open_test(false, 0x80000000, &chainstate_path).unwrap();
+
+    let addr = StacksAddress {
+        version: 1,
+        bytes: Hash160([0xff; 20]),
+    };
+    let block_height = 10;
+    let mut txids = vec![];
+
+    let mut mempool_tx = mempool.tx_begin().unwrap();
+    for _ in 0..3 {
+        let pk = StacksPrivateKey::new();
+        let mut tx = StacksTransaction {
+            version: TransactionVersion::Testnet,
+            chain_id: 0x80000000,
+            auth: TransactionAuth::from_p2pkh(&pk).unwrap(),
+            anchor_mode: TransactionAnchorMode::Any,
+            post_condition_mode: TransactionPostConditionMode::Allow,
+            post_conditions: vec![],
+            payload: TransactionPayload::TokenTransfer(
+                addr.to_account_principal(),
+                123,
+                TokenTransferMemo([0u8; 34]),
+            ),
+        };
+        tx.set_tx_fee(1000);
+        tx.set_origin_nonce(0);
+
+        let txid = tx.txid();
+        let origin_addr = tx.origin_address();
+        MemPoolDB::try_add_tx(
+            &mut mempool_tx,
+            &mut chainstate,
+            &ConsensusHash([0x1 + (block_height as u8); 20]),
+            &BlockHeaderHash([0x2 + (block_height as u8); 32]),
+            txid.clone(),
+            tx.serialize_to_vec(),
+            tx.get_tx_fee(),
+            block_height,
+            &origin_addr,
+            0,
+            &origin_addr,
+            0,
+            None,
+        )
+        .unwrap();
+        txids.push(txid);
+    }
+    mempool_tx.commit().unwrap();
+
+    let mut buf = vec![];
+    assert_eq!(mempool.export_txs(&mut buf).unwrap(), 3);
+
+    let entries: Vec<MemPoolExportEntry> = std::str::from_utf8(&buf)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(entries.len(), 3);
+    for entry in entries.iter() {
+        let tx = entry.decode_tx().unwrap();
+        assert!(txids.contains(&tx.txid()));
+        assert_eq!(entry.tx_fee, 1000);
+        assert_eq!(entry.block_height, block_height);
+    }
+
+    // the mempool already has everything it exported
+    let summary = mempool
+        .import_txs(
+            &mut chainstate,
+            &FIRST_BURNCHAIN_CONSENSUS_HASH,
+            &FIRST_STACKS_BLOCK_HASH,
+            &buf[..],
+            &ExecutionCost::max_value(),
+            &StacksEpochId::Epoch21,
+        )
+        .unwrap();
+    assert_eq!(
+        summary,
+        MemPoolImportSummary {
+            imported: 0,
+            already_present: 3,
+            rejected: 0,
+        }
+    );
+
+    // an entry whose transaction does not match its txid fails the whole import
+    let mut bad_entry = entries[0].clone();
+    bad_entry.txid = format!("{}", Txid([0x11; 32]));
+    let bad_buf = format!("{}\n", serde_json::to_string(&bad_entry).unwrap());
+    assert!(mempool
+        .import_txs(
+            &mut chainstate,
+            &FIRST_BURNCHAIN_CONSENSUS_HASH,
+            &FIRST_STACKS_BLOCK_HASH,
+            bad_buf.as_bytes(),
+            &ExecutionCost::max_value(),
+            &StacksEpochId::Epoch21,
+        )
+        .is_err());
+    assert!(mempool
+        .import_txs(
+            &mut chainstate,
+            &FIRST_BURNCHAIN_CONSENSUS_HASH,
+            &FIRST_STACKS_BLOCK_HASH,
+            "not json\n".as_bytes(),
+            &ExecutionCost::max_value(),
+            &StacksEpochId::Epoch21,
+        )
+        .is_err());
+}
+
+#[test]
+fn test_expired_txs() {
+    let mut chainstate =
+        instantiate_chainstate_with_balances(false, 0x80000000, "test_expired_txs", vec![]);
+    let chainstate_path = chainstate_path("test_expired_txs");
+    let mut mempool = MemPoolDB::open_test(false, 0x80000000, &chainstate_path).unwrap();
+    let b_1 = make_block(
+        &mut chainstate,
+        ConsensusHash([0x1; 20]),
+        &(
+            FIRST_BURNCHAIN_CONSENSUS_HASH.clone(),
+            FIRST_STACKS_BLOCK_HASH.clone(),
+        ),
+        1,
+        1,
+    );
+
+    let mut txs = codec_all_transactions(
+        &TransactionVersion::Testnet,
+        0x80000000,
+        &TransactionAnchorMode::Any,
+        &TransactionPostConditionMode::Allow,
+    );
+
+    // a transaction which could only have been mined at or before the tip is refused
+    let tx = txs.pop().unwrap();
+    let err = mempool
+        .submit_with_options(
+            &mut chainstate,
+            &b_1.0,
+            &b_1.1,
+            &tx,
+            None,
+            &ExecutionCost::max_value(),
+            &StacksEpochId::Epoch21,
+            Some(1),
+            None,
+        )
+        .unwrap_err();
+    match err {
+        MemPoolRejection::AlreadyExpired(1, 1) => {}
+        e => panic!("Unexpected rejection: {:?}", &e),
+    }
+    assert!(!mempool.has_tx(&tx.txid()));
+
+    // one transaction expiring at each of heights 5 and 6, and one which never expires
+    let mut txids = vec![];
+    for (i, expiration_height) in [Some(5), Some(6), None].iter().enumerate() {
+        let mut tx = txs.pop().unwrap();
+        tx.set_tx_fee(100);
+        let txid = tx.txid();
+        let origin_address = StacksAddress {
+            version: 1,
+            bytes: Hash160([i as u8 + 1; 20]),
+        };
+
+        let mut mempool_tx = mempool.tx_begin().unwrap();
+        MemPoolDB::try_add_tx(
+            &mut mempool_tx,
+            &mut chainstate,
+            &b_1.0,
+            &b_1.1,
+            txid.clone(),
+            tx.serialize_to_vec(),
+            tx.get_tx_fee(),
+            1,
+            &origin_address,
+            0,
+            &origin_address,
+            0,
+            None,
+        )
+        .unwrap();
+        mempool_tx
+            .execute(
+                "UPDATE mempool SET expiration_height = ?1 WHERE txid = ?2",
+                rusqlite::params![expiration_height.map(|h| h as i64), &txid],
+            )
+            .unwrap();
+        mempool_tx.commit().unwrap();
+        txids.push(txid);
+    }
+
+    // building on a block at height 5, the transaction expiring at height 5 is not considered
+    let mut considered = HashSet::new();
+    chainstate.with_read_only_clarity_tx(
+        &TEST_BURN_STATE_DB,
+        &StacksBlockHeader::make_index_block_hash(&b_1.0, &b_1.1),
+        |clarity_conn| {
+            mempool
+                .iterate_candidates::<_, ChainstateError, _>(
+                    clarity_conn,
+                    &mut vec![],
+                    5,
+                    MemPoolWalkSettings::default(),
+                    |_, available_tx, _| {
+                        considered.insert(available_tx.tx.tx.txid());
+                        Ok(Some(
+                            TransactionResult::skipped(
+                                &available_tx.tx.tx,
+                                "event not relevant to test".to_string(),
+                            )
+                            .convert_to_event(),
+                        ))
+                    },
+                )
+                .unwrap();
+        },
+    );
+    assert_eq!(
+        considered,
+        vec![txids[1].clone(), txids[2].clone()]
+            .into_iter()
+            .collect::<HashSet<_>>()
+    );
+
+    // once the chain reaches height 5, the transaction expiring there is dropped
+    let mut mempool_tx = mempool.tx_begin().unwrap();
+    MemPoolDB::garbage_collect_expired(&mut mempool_tx, 5, None).unwrap();
+    mempool_tx.commit().unwrap();
+    assert!(!mempool.has_tx(&txids[0]));
+    assert!(mempool.has_tx(&txids[1]));
+    assert!(mempool.has_tx(&txids[2]));
+
+    let mut mempool_tx = mempool.tx_begin().unwrap();
+    MemPoolDB::garbage_collect_expired(&mut mempool_tx, 100, None).unwrap();
+    mempool_tx.commit().unwrap();
+    assert!(!mempool.has_tx(&txids[1]));
+    assert!(mempool.has_tx(&txids[2]));
+}
+
+#[test]
+fn test_bundled_txs() {
+    let mut chainstate =
+        instantiate_chainstate_with_balances(false, 0x80000000, "test_bundled_txs", vec![]);
+    let chainstate_path = chainstate_path("test_bundled_txs");
+    let mut mempool = MemPoolDB::open_test(false, 0x80000000, &chainstate_path).unwrap();
+    let b_1 = make_block(
+        &mut chainstate,
+        ConsensusHash([0x1; 20]),
+        &(
+            FIRST_BURNCHAIN_CONSENSUS_HASH.clone(),
+            FIRST_STACKS_BLOCK_HASH.clone(),
+        ),
+        1,
+        1,
+    );
+
+    let mut txs: Vec<StacksTransaction> = codec_all_transactions(
+        &TransactionVersion::Testnet,
+        0x80000000,
+        &TransactionAnchorMode::Any,
+        &TransactionPostConditionMode::Allow,
+    )
+    .into_iter()
+    .filter(|tx| !tx.auth.is_sponsored())
+    .collect();
+
+    let bundle_origin = StacksAddress {
+        version: 1,
+        bytes: Hash160([0x01; 20]),
+    };
+    let other_origin = StacksAddress {
+        version: 1,
+        bytes: Hash160([0x02; 20]),
+    };
+
+    let mut add_tx = |mempool: &mut MemPoolDB,
+                      chainstate: &mut StacksChainState,
+                      origin_address: &StacksAddress,
+                      origin_nonce: u64,
+                      bundle_id: Option<&str>| {
+        let mut tx = txs.pop().unwrap();
+        tx.set_tx_fee(100);
+        let txid = tx.txid();
+
+        let mut mempool_tx = mempool.tx_begin().unwrap();
+        MemPoolDB::try_add_tx(
+            &mut mempool_tx,
+            chainstate,
+            &b_1.0,
+            &b_1.1,
+            txid.clone(),
+            tx.serialize_to_vec(),
+            tx.get_tx_fee(),
+            1,
+            origin_address,
+            origin_nonce,
+            origin_address,
+            origin_nonce,
+            None,
+        )
+        .unwrap();
+        mempool_tx
+            .execute(
+                "UPDATE mempool SET bundle_id = ?1 WHERE txid = ?2",
+                rusqlite::params![bundle_id, &txid],
+            )
+            .unwrap();
+        mempool_tx.commit().unwrap();
+        txid
+    };
+
+    // the bundle's middle transaction has yet to arrive
+    let bundled_0 = add_tx(
+        &mut mempool,
+        &mut chainstate,
+        &bundle_origin,
+        0,
+        Some("swap"),
+    );
+    let bundled_2 = add_tx(
+        &mut mempool,
+        &mut chainstate,
+        &bundle_origin,
+        2,
+        Some("swap"),
+    );
+    let unbundled = add_tx(&mut mempool, &mut chainstate, &other_origin, 0, None);
+
+    let tip = StacksBlockHeader::make_index_block_hash(&b_1.0, &b_1.1);
+    let preview = |mempool: &mut MemPoolDB, chainstate: &mut StacksChainState| {
+        chainstate.with_read_only_clarity_tx(&TEST_BURN_STATE_DB, &tip, |clarity_conn| {
+            mempool
+                .preview_candidates(clarity_conn, 1, MemPoolWalkSettings::default(), 100)
+                .unwrap()
+        })
+    };
+
+    // an incomplete bundle is not considered, even though its first transaction is ready
+    let candidates = preview(&mut mempool, &mut chainstate).unwrap();
+    let txids: Vec<Txid> = candidates.iter().map(|c| c.tx.tx.txid()).collect();
+    assert_eq!(txids, vec![unbundled.clone()]);
+
+    // once complete, the bundle is considered whole and in nonce order
+    let bundled_1 = add_tx(
+        &mut mempool,
+        &mut chainstate,
+        &bundle_origin,
+        1,
+        Some("swap"),
+    );
+    let candidates = preview(&mut mempool, &mut chainstate).unwrap();
+    let txids: Vec<Txid> = candidates.iter().map(|c| c.tx.tx.txid()).collect();
+    assert_eq!(txids.len(), 4);
+    let first = txids.iter().position(|txid| *txid == bundled_0).unwrap();
+    assert_eq!(
+        txids[first..first + 3].to_vec(),
+        vec![bundled_0.clone(), bundled_1.clone(), bundled_2.clone()]
+    );
+    assert_eq!(
+        candidates[first]
+            .bundle
+            .iter()
+            .map(|member| member.metadata.txid.clone())
+            .collect::<Vec<_>>(),
+        vec![bundled_0.clone(), bundled_1.clone(), bundled_2.clone()]
+    );
+    assert!(candidates[first + 1].bundle.is_empty());
+
+    // a bundle's transactions must share an origin
+    let tx = txs.pop().unwrap();
+    let err = mempool
+        .submit_with_options(
+            &mut chainstate,
+            &b_1.0,
+            &b_1.1,
+            &tx,
+            None,
+            &ExecutionCost::max_value(),
+            &StacksEpochId::Epoch21,
+            None,
+            Some("swap"),
+        )
+        .unwrap_err();
+    match err {
+        MemPoolRejection::BundleOriginMismatch(bundle_id, origin) => {
+            assert_eq!(bundle_id, "swap");
+            assert_eq!(origin, bundle_origin);
+        }
+        e => panic!("Unexpected rejection: {:?}", &e),
+    }
+}
+
+#[test]
+fn test_mempool_clock() {
+    let mut chainstate = instantiate_chainstate(false, 0x80000000, "test_mempool_clock");
+    let chainstate_path = chainstate_path("test_mempool_clock");
+    let mut mempool = MemPoolDB::open_test(false, 0x80000000, &chainstate_path).unwrap();
+    let clock = ManualClock::new(1_000_000);
+    mempool.set_clock(Arc::new(clock.clone()));
+
+    let mut txs = codec_all_transactions(
+        &TransactionVersion::Testnet,
+        0x80000000,
+        &TransactionAnchorMode::Any,
+        &TransactionPostConditionMode::Allow,
+    );
+    let origin_address = StacksAddress {
+        version: 22,
+        bytes: Hash160::from_data(&[0; 32]),
+    };
+
+    let mut txids = vec![];
+    for i in 0..2u64 {
+        let tx = txs.pop().unwrap();
+        let txid = tx.txid();
+        let mut mempool_tx = mempool.tx_begin().unwrap();
+        MemPoolDB::try_add_tx(
+            &mut mempool_tx,
+            &mut chainstate,
+            &ConsensusHash([0x1; 20]),
+            &BlockHeaderHash([0x2; 32]),
+            txid.clone(),
+            tx.serialize_to_vec(),
+            tx.get_tx_fee(),
+            1,
+            &origin_address,
+            i,
+            &origin_address,
+            i,
+            None,
+        )
+        .unwrap();
+        mempool_tx.commit().unwrap();
+        txids.push(txid);
+
+        // no waiting: the next transaction arrives a minute later
+        clock.advance_secs(60);
+    }
+
+    let accept_times: Vec<_> = txids
+        .iter()
+        .map(|txid| {
+            MemPoolDB::get_tx(mempool.conn(), txid)
+                .unwrap()
+                .unwrap()
+                .metadata
+                .accept_time
+        })
+        .collect();
+    assert_eq!(accept_times, vec![1_000, 1_060]);
+}
+
+#[test]
+fn test_mempool_filters() {
+    let mut chainstate = instantiate_chainstate(false, 0x80000000, "test_mempool_filters");
+    let chainstate_path = chainstate_path("test_mempool_filters");
+    let mut mempool = MemPoolDB::open_test(false, 0x80000000, &chainstate_path).unwrap();
+    let clock = ManualClock::new(1_000_000);
+    mempool.set_clock(Arc::new(clock.clone()));
+    let b_1 = make_block(
+        &mut chainstate,
+        ConsensusHash([0x1; 20]),
+        &(
+            FIRST_BURNCHAIN_CONSENSUS_HASH.clone(),
+            FIRST_STACKS_BLOCK_HASH.clone(),
+        ),
+        1,
+        1,
+    );
+
+    let txs = codec_all_transactions(
+        &TransactionVersion::Testnet,
+        0x80000000,
+        &TransactionAnchorMode::Any,
+        &TransactionPostConditionMode::Allow,
+    );
+    let contract_call = txs
+        .iter()
+        .find(|tx| match tx.payload {
+            TransactionPayload::ContractCall(_) => true,
+            _ => false,
+        })
+        .unwrap()
+        .clone();
+    let function_name = match contract_call.payload {
+        TransactionPayload::ContractCall(ref call) => call.function_name.to_string(),
+        _ => unreachable!(),
+    };
+    let token_transfer = txs
+        .iter()
+        .find(|tx| match tx.payload {
+            TransactionPayload::TokenTransfer(..) => true,
+            _ => false,
+        })
+        .unwrap()
+        .clone();
+
+    // a transaction from a filtered origin is refused
+    let origin = contract_call.origin_address().to_string();
+    let origin_filter = mempool
+        .install_filter(
+            MemPoolFilterKind::Origin,
+            &MemPoolFilterKind::Origin.parse_value(&origin).unwrap(),
+            60,
+            "spam",
+        )
+        .unwrap();
+    assert_eq!(origin_filter.created_at, 1_000);
+    assert_eq!(origin_filter.expires_at, 1_060);
+    let err = mempool
+        .submit_with_options(
+            &mut chainstate,
+            &b_1.0,
+            &b_1.1,
+            &contract_call,
+            None,
+            &ExecutionCost::max_value(),
+            &StacksEpochId::Epoch21,
+            None,
+            None,
+        )
+        .unwrap_err();
+    match err {
+        MemPoolRejection::Filtered(filter_id, reason) => {
+            assert_eq!(filter_id, origin_filter.filter_id);
+            assert_eq!(reason, "spam");
+        }
+        e => panic!("Unexpected rejection: {:?}", &e),
+    }
+    assert!(!mempool.has_tx(&contract_call.txid()));
+
+    // removed filters no longer apply
+    assert_eq!(
+        mempool.remove_filter(origin_filter.filter_id).unwrap(),
+        Some(origin_filter.clone())
+    );
+    assert_eq!(
+        mempool.remove_filter(origin_filter.filter_id).unwrap(),
+        None
+    );
+
+    // transactions already in the mempool which match a filter are not mined
+    let mut txids = vec![];
+    for (i, tx) in [&contract_call, &token_transfer].iter().enumerate() {
+        let origin_address = StacksAddress {
+            version: 1,
+            bytes: Hash160([i as u8 + 1; 20]),
+        };
+        let mut mempool_tx = mempool.tx_begin().unwrap();
+        MemPoolDB::try_add_tx(
+            &mut mempool_tx,
+            &mut chainstate,
+            &b_1.0,
+            &b_1.1,
+            tx.txid(),
+            tx.serialize_to_vec(),
+            tx.get_tx_fee(),
+            1,
+            &origin_address,
+            0,
+            &origin_address,
+            0,
+            None,
+        )
+        .unwrap();
+        mempool_tx.commit().unwrap();
+        txids.push(tx.txid());
+    }
+    let function_filter = mempool
+        .install_filter(MemPoolFilterKind::Function, &function_name, 60, "bad call")
+        .unwrap();
+
+    let mut considered = HashSet::new();
+    chainstate.with_read_only_clarity_tx(
+        &TEST_BURN_STATE_DB,
+        &StacksBlockHeader::make_index_block_hash(&b_1.0, &b_1.1),
+        |clarity_conn| {
+            mempool
+                .iterate_candidates::<_, ChainstateError, _>(
+                    clarity_conn,
+                    &mut vec![],
+                    1,
+                    MemPoolWalkSettings::default(),
+                    |_, available_tx, _| {
+                        considered.insert(available_tx.tx.tx.txid());
+                        Ok(Some(
+                            TransactionResult::skipped(
+                                &available_tx.tx.tx,
+                                "event not relevant to test".to_string(),
+                            )
+                            .convert_to_event(),
+                        ))
+                    },
+                )
+                .unwrap();
+        },
+    );
+    assert_eq!(
+        considered,
+        vec![txids[1].clone()].into_iter().collect::<HashSet<_>>()
+    );
+
+    // filters expire on their own, and are pruned once they have
+    assert_eq!(
+        MemPoolDB::get_active_filters(mempool.conn(), 1_059).unwrap(),
+        vec![function_filter]
+    );
+    clock.advance_secs(60);
+    assert!(mempool.prune_expired_filters().unwrap().is_empty());
+    let rows: i64 = mempool
+        .conn()
+        .query_row(
+            "SELECT COUNT(*) FROM mempool_filters",
+            rusqlite::NO_PARAMS,
+            |row| row.get(0),
+        )
+        .unwrap();
+    assert_eq!(rows, 0);
+
+    assert_eq!(
+        MemPoolFilterKind::Contract.parse_value("not a contract"),
+        Err("Invalid contract identifier: not a contract".to_string())
+    );
+}
+
+#[test]
+fn test_mempool_rejected_txs() {
+    let mut chainstate = instantiate_chainstate(false, 0x80000000, "test_mempool_rejected_txs");
+    let chainstate_path = chainstate_path("test_mempool_rejected_txs");
+    let mut mempool = MemPoolDB::open_test(false, 0x80000000, &chainstate_path).unwrap();
+    let clock = ManualClock::new(1_000_000);
+    mempool.set_clock(Arc::new(clock.clone()));
+
+    let txs = codec_all_transactions(
+        &TransactionVersion::Testnet,
+        0x80000000,
+        &TransactionAnchorMode::Any,
+        &TransactionPostConditionMode::Allow,
+    );
+    let missing_tip = (ConsensusHash([0x9; 20]), BlockHeaderHash([0x9; 32]));
+    let mut submit = |mempool: &mut MemPoolDB, tx: &StacksTransaction| {
+        mempool
+            .submit_with_options(
+                &mut chainstate,
+                &missing_tip.0,
+                &missing_tip.1,
+                tx,
+                None,
+                &ExecutionCost::max_value(),
+                &StacksEpochId::Epoch21,
+                None,
+                None,
+            )
+            .unwrap_err()
+    };
+
+    // rejections are not kept unless the mempool is configured to keep them
+    submit(&mut mempool, &txs[0]);
+    assert_eq!(
+        MemPoolDB::get_rejected_tx(mempool.conn(), &txs[0].txid()).unwrap(),
+        None
+    );
+
+    mempool.set_rejected_txs_retention(Some(RejectedTxsRetention {
+        max_count: 3,
+        max_age_secs: 60,
+    }));
+    let rejection = submit(&mut mempool, &txs[0]);
+    let rejected = MemPoolDB::get_rejected_tx(mempool.conn(), &txs[0].txid())
+        .unwrap()
+        .unwrap();
+    assert_eq!(rejected.reason, rejection.reason().0);
+    assert_eq!(rejected.reason, "ServerFailureNoSuchChainTip");
+    assert_eq!(rejected.origin_address, txs[0].origin_address());
+    assert_eq!(rejected.consensus_hash, missing_tip.0);
+    assert_eq!(rejected.block_header_hash, missing_tip.1);
+    assert_eq!(rejected.rejected_at, 1_000);
+    assert_eq!(rejected.reason_data, None);
+
+    // only the newest `max_count` rejections are kept
+    let mut distinct_txs: Vec<StacksTransaction> = vec![];
+    for tx in txs.iter() {
+        if distinct_txs.iter().all(|other| other.txid() != tx.txid()) {
+            distinct_txs.push(tx.clone());
+        }
+    }
+    assert!(distinct_txs.len() > 5);
+    for tx in distinct_txs[1..5].iter() {
+        clock.advance_secs(1);
+        submit(&mut mempool, tx);
+    }
+    let rows: i64 = mempool
+        .conn()
+        .query_row(
+            "SELECT COUNT(*) FROM rejected_txs",
+            rusqlite::NO_PARAMS,
+            |row| row.get(0),
+        )
+        .unwrap();
+    assert_eq!(rows, 3);
+    assert!(
+        MemPoolDB::get_rejected_tx(mempool.conn(), &distinct_txs[1].txid())
+            .unwrap()
+            .is_none()
+    );
+
+    // rejections can be looked up by sender, newest first
+    let origin = distinct_txs[4].origin_address();
+    let by_origin = MemPoolDB::get_rejected_txs_by_address(mempool.conn(), &origin, 10).unwrap();
+    assert!(!by_origin.is_empty());
+    assert_eq!(by_origin[0].txid, distinct_txs[4].txid());
+    for pair in by_origin.windows(2) {
+        assert!(pair[0].rejected_at >= pair[1].rejected_at);
+    }
+    for rejected in by_origin.iter() {
+        assert!(rejected.origin_address == origin || rejected.sponsor_address == origin);
+    }
+
+    // and are dropped once they are `max_age_secs` old
+    clock.advance_secs(60);
+    submit(&mut mempool, &distinct_txs[5]);
+    let rows: i64 = mempool
+        .conn()
+        .query_row(
+            "SELECT COUNT(*) FROM rejected_txs",
+            rusqlite::NO_PARAMS,
+            |row| row.get(0),
+        )
+        .unwrap();
+    assert_eq!(rows, 1);
+}
+
+#[test]
+fn test_mempool_stats() {
+    let mut chainstate = instantiate_chainstate(false, 0x80000000, "test_mempool_stats");
+    let chainstate_path = chainstate_path("test_mempool_stats");
+    let mut mempool = MemPoolDB::open_test(false, 0x80000000, &chainstate_path).unwrap();
+    let clock = ManualClock::new(1_000_000);
+    mempool.set_clock(Arc::new(clock.clone()));
+
+    let empty = MemPoolDB::get_stats(mempool.conn(), 1_000).unwrap();
+    assert_eq!(empty.tx_count, 0);
+    assert_eq!(empty.total_bytes, 0);
+    assert_eq!(
+        empty.fee_rates.len(),
+        MEMPOOL_STATS_FEE_RATE_BOUNDS.len() + 1
+    );
+    assert_eq!(empty.ages.len(), MEMPOOL_STATS_AGE_BOUNDS_SECS.len() + 1);
+    assert!(empty.payload_types.is_empty());
+
+    let mut txs = codec_all_transactions(
+        &TransactionVersion::Testnet,
+        0x80000000,
+        &TransactionAnchorMode::Any,
+        &TransactionPostConditionMode::Allow,
+    );
+    let origin_address = StacksAddress {
+        version: 22,
+        bytes: Hash160::from_data(&[0; 32]),
+    };
+
+    // admitted 1000s, 500s and 10s before the stats are taken, with fee rates in the second
+    //  bucket and the last bucket, or no estimate at all
+    let accept_times = [1_000, 1_500, 1_990];
+    let fee_rates = [None, Some(5.0), Some(200_000.0)];
+    let mut added = vec![];
+    for i in 0..3 {
+        let tx = txs.pop().unwrap();
+        let txid = tx.txid();
+        clock.set_ms(accept_times[i] * 1000);
+        let mut mempool_tx = mempool.tx_begin().unwrap();
+        MemPoolDB::try_add_tx(
+            &mut mempool_tx,
+            &mut chainstate,
+            &ConsensusHash([0x1; 20]),
+            &BlockHeaderHash([0x2; 32]),
+            txid.clone(),
+            tx.serialize_to_vec(),
+            tx.get_tx_fee(),
+            1,
+            &origin_address,
+            i as u64,
+            &origin_address,
+            i as u64,
+            None,
+        )
+        .unwrap();
+        // submit() records the payload type; leave the last one's unrecorded, as if it had been
+        //  admitted before the mempool recorded them
+        let payload_type = if i < 2 { Some(tx.payload.name()) } else { None };
+        mempool_tx
+            .execute(
+                "UPDATE mempool SET fee_rate = ?1, payload_type = ?2 WHERE txid = ?3",
+                rusqlite::params![fee_rates[i], payload_type, &txid],
+            )
+            .unwrap();
+        mempool_tx.commit().unwrap();
+        added.push(tx);
+    }
+
+    let stats = MemPoolDB::get_stats(mempool.conn(), 2_000).unwrap();
+    assert_eq!(stats.tx_count, 3);
+    assert_eq!(
+        stats.total_bytes,
+        added
+            .iter()
+            .map(|tx| tx.serialize_to_vec().len() as u64)
+            .sum::<u64>()
+    );
+
+    assert_eq!(stats.no_fee_rate_estimate, 1);
+    let fee_rate_counts: Vec<_> = stats.fee_rates.iter().map(|bucket| bucket.count).collect();
+    assert_eq!(fee_rate_counts, vec![0, 1, 0, 0, 0, 0, 1]);
+    assert_eq!(stats.fee_rates[1].min, 1.0);
+    assert_eq!(stats.fee_rates[1].max, Some(10.0));
+    assert_eq!(stats.fee_rates[6].max, None);
+
+    let age_counts: Vec<_> = stats.ages.iter().map(|bucket| bucket.count).collect();
+    assert_eq!(age_counts, vec![1, 1, 1, 0, 0, 0]);
+    assert_eq!(stats.ages[2].min_age_secs, 600);
+    assert_eq!(stats.ages[2].max_age_secs, Some(3_600));
+
+    let mut expected_payload_types = BTreeMap::new();
+    for tx in added[0..2].iter() {
+        *expected_payload_types
+            .entry(tx.payload.name().to_string())
+            .or_insert(0) += 1;
+    }
+    expected_payload_types.insert(UNKNOWN_PAYLOAD_TYPE.to_string(), 1);
+    assert_eq!(stats.payload_types, expected_payload_types);
+
+    // a transaction stamped after the stats are taken counts as new
+    let stats = MemPoolDB::get_stats(mempool.conn(), 1_200).unwrap();
+    let age_counts: Vec<_> = stats.ages.iter().map(|bucket| bucket.count).collect();
+    assert_eq!(age_counts, vec![2, 1, 0, 0, 0, 0]);
+}
@@ -51,5 +51,7 @@ pub fn make_block_receipt(tx_receipts: Vec<StacksTransactionReceipt>) -> StacksE
         parent_burn_block_height: 1,
         parent_burn_block_timestamp: 1,
         evaluated_epoch: StacksEpochId::Epoch20,
+        withdrawals_queued: vec![],
+        withdrawals_released: vec![],
     }
 }
@@ -40,6 +40,7 @@ extern crate sha3;
 extern crate siphasher;
 extern crate time;
 extern crate url;
+extern crate zstd;
 
 #[macro_use(o, slog_log, slog_trace, slog_debug, slog_info, slog_warn, slog_error)]
 extern crate slog;
@@ -115,6 +116,13 @@ const BUILD_TYPE: &'static str = "debug";
 #[cfg(not(debug_assertions))]
 const BUILD_TYPE: &'static str = "release";
 
+/// The subnet node's version number, which the L1 contract's upgrade signals are checked against
+pub fn subnet_node_version() -> &'static str {
+    option_env!("SUBNET_NODE_VERSION")
+        .or(option_env!("CARGO_PKG_VERSION"))
+        .unwrap_or("0.0.0.0")
+}
+
 pub fn version_string(pkg_name: &str, pkg_version: &str) -> String {
     let git_branch = GIT_BRANCH
         .map(|x| format!("{}", x))
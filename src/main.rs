@@ -45,6 +45,7 @@ use subnet_lib::burnchains::BLOCKSTACK_MAGIC_MAINNET;
 use subnet_lib::clarity_cli;
 use subnet_lib::core::mempool::MemPoolEventDispatcher;
 use subnet_lib::core::mempool::MemPoolWalkSettings;
+use subnet_lib::core::mempool::MEMPOOL_SCHEMA_VERSION;
 use subnet_lib::cost_estimates::UnitEstimator;
 use subnet_lib::types::PrivateKey;
 use subnet_lib::util::secp256k1::secp256k1_recover;
@@ -61,6 +62,7 @@ use subnet_lib::chainstate::burn::ConsensusHash;
 use subnet_lib::chainstate::stacks::db::blocks::DummyEventDispatcher;
 use subnet_lib::chainstate::stacks::db::blocks::StagingBlock;
 use subnet_lib::chainstate::stacks::db::ChainStateBootData;
+use subnet_lib::chainstate::stacks::db::CHAINSTATE_VERSION;
 use subnet_lib::chainstate::stacks::index::marf::MARFOpenOpts;
 use subnet_lib::chainstate::stacks::index::marf::MarfConnection;
 use subnet_lib::chainstate::stacks::index::marf::MARF;
@@ -86,7 +88,7 @@ use subnet_lib::util_lib::strings::UrlString;
 use subnet_lib::{
     burnchains::{db::BurnchainBlockData, PoxConstants},
     chainstate::{
-        burn::db::sortdb::SortitionDB,
+        burn::db::sortdb::{SortitionDB, SORTITION_DB_VERSION},
         stacks::db::{StacksChainState, StacksHeaderInfo},
     },
     core::MemPoolDB,
@@ -94,6 +96,10 @@ use subnet_lib::{
     util_lib::db::sqlite_open,
 };
 
+/// The subdirectory of a subnet node's working directory which holds its databases (see
+/// `SUBNET_SUBDIR_NAME` in the node's config)
+const SUBNET_SUBDIR_NAME: &str = "subnet";
+
 fn main() {
     let argv: Vec<String> = env::args().collect();
     if argv.len() < 2 {
@@ -701,6 +707,7 @@ simulating a miner.
             BlockBuilderSettings {
                 max_miner_time_ms: 15000,
                 mempool_settings: MemPoolWalkSettings::default(),
+                ..BlockBuilderSettings::limited()
             },
         )
         .expect("Failed to instantiate microblock miner");
@@ -922,6 +929,167 @@ simulating a miner.
         return;
     }
 
+    if argv[1] == "migrate-chainstate" {
+        if argv.len() < 3 {
+            eprintln!(
+                "Usage: {} migrate-chainstate <working-dir> [--dry-run]
+
+Given a <working-dir>, apply all pending schema migrations to the sortition DB, the chainstate
+DB, and the mempool DB, so that the node does not need to do so when it boots. If --dry-run is
+given, only report which migrations are pending.
+",
+                argv[0]
+            );
+            process::exit(1);
+        }
+
+        let dry_run = argv.len() >= 4 && argv[3] == "--dry-run";
+        let sort_db_path = format!("{}/{}/burnchain/sortition", &argv[2], SUBNET_SUBDIR_NAME);
+        let chain_state_path = format!("{}/{}/chainstate/", &argv[2], SUBNET_SUBDIR_NAME);
+
+        let start = get_epoch_time_ms();
+
+        // sortition DB
+        match SortitionDB::get_db_version_from_path(&sort_db_path) {
+            Ok(Some(version)) => {
+                if version == SORTITION_DB_VERSION {
+                    println!("Sortition DB is up-to-date (version {})", &version);
+                } else if dry_run {
+                    println!(
+                        "Sortition DB needs migration: version {} -> {}",
+                        &version, SORTITION_DB_VERSION
+                    );
+                } else {
+                    println!(
+                        "Migrating sortition DB: version {} -> {}",
+                        &version, SORTITION_DB_VERSION
+                    );
+                    let step_start = get_epoch_time_ms();
+                    SortitionDB::migrate_if_exists(&sort_db_path, &*SUBNET_EPOCHS)
+                        .expect("Failed to migrate sortition DB");
+                    println!(
+                        "Migrated sortition DB in {}ms",
+                        get_epoch_time_ms().saturating_sub(step_start)
+                    );
+                }
+            }
+            Ok(None) => {
+                eprintln!("Sortition DB at {} has no schema version", &sort_db_path);
+                process::exit(1);
+            }
+            Err(_) => {
+                println!("No sortition DB at {}; skipping", &sort_db_path);
+            }
+        }
+
+        // chainstate DB
+        if fs::metadata(&chain_state_path).is_ok() {
+            let db_config = StacksChainState::get_db_config_from_path(&chain_state_path)
+                .expect("Failed to load chainstate DB config");
+            if db_config.version == CHAINSTATE_VERSION {
+                println!(
+                    "Chainstate DB is up-to-date (version {})",
+                    &db_config.version
+                );
+            } else if dry_run {
+                println!(
+                    "Chainstate DB needs migration: version {} -> {}",
+                    &db_config.version, CHAINSTATE_VERSION
+                );
+            } else {
+                println!(
+                    "Migrating chainstate DB: version {} -> {}",
+                    &db_config.version, CHAINSTATE_VERSION
+                );
+                let step_start = get_epoch_time_ms();
+                // this does the migration internally
+                let _ = StacksChainState::open(
+                    db_config.mainnet,
+                    db_config.chain_id,
+                    &chain_state_path,
+                    None,
+                )
+                .expect("Failed to migrate chainstate DB");
+                println!(
+                    "Migrated chainstate DB in {}ms",
+                    get_epoch_time_ms().saturating_sub(step_start)
+                );
+            }
+        } else {
+            println!("No chainstate DB at {}; skipping", &chain_state_path);
+        }
+
+        // mempool DB
+        match MemPoolDB::get_schema_version_from_path(&chain_state_path)
+            .expect("Failed to load mempool DB schema version")
+        {
+            Some(version) => {
+                if version == MEMPOOL_SCHEMA_VERSION {
+                    println!("Mempool DB is up-to-date (version {})", version);
+                } else if dry_run {
+                    println!(
+                        "Mempool DB needs migration: version {} -> {}",
+                        version, MEMPOOL_SCHEMA_VERSION
+                    );
+                } else {
+                    println!(
+                        "Migrating mempool DB: version {} -> {}",
+                        version, MEMPOOL_SCHEMA_VERSION
+                    );
+                    let step_start = get_epoch_time_ms();
+                    MemPoolDB::migrate_from_path(&chain_state_path)
+                        .expect("Failed to migrate mempool DB");
+                    println!(
+                        "Migrated mempool DB in {}ms",
+                        get_epoch_time_ms().saturating_sub(step_start)
+                    );
+                }
+            }
+            None => {
+                println!("No mempool DB in {}; skipping", &chain_state_path);
+            }
+        }
+
+        println!(
+            "Done{} in {}ms",
+            if dry_run { " (dry run)" } else { "" },
+            get_epoch_time_ms().saturating_sub(start)
+        );
+        process::exit(0);
+    }
+
+    if argv[1] == "prune" {
+        if argv.len() < 4 {
+            eprintln!(
+                "Usage: {} prune <working-dir> <horizon>
+
+Given a <working-dir>, drop the block data, microblock data, and non-canonical MARF tries of all
+blocks more than <horizon> blocks below the chain tip. Block headers and the state of the
+remaining chain are kept. The node must not be running.
+",
+                argv[0]
+            );
+            process::exit(1);
+        }
+
+        let chain_state_path = format!("{}/{}/chainstate/", &argv[2], SUBNET_SUBDIR_NAME);
+        let horizon: u64 = argv[3].parse().expect("Failed to parse <horizon>");
+
+        let start = get_epoch_time_ms();
+        let stats = StacksChainState::prune(&chain_state_path, horizon)
+            .expect("Failed to prune chainstate");
+        println!(
+            "Pruned {} blocks ({} bytes), {} microblocks, and {} MARF tries below height {} in {}ms",
+            stats.blocks_pruned,
+            stats.block_bytes_pruned,
+            stats.microblocks_pruned,
+            stats.tries_pruned,
+            stats.cutoff_height,
+            get_epoch_time_ms().saturating_sub(start)
+        );
+        process::exit(0);
+    }
+
     if argv[1] == "process-block" {
         let path = &argv[2];
         let sort_path = &argv[3];
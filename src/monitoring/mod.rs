@@ -69,6 +69,34 @@ where
     res
 }
 
+/// Record that an RPC request of the given class was refused by the rate limiter
+#[allow(unused_variables)]
+pub fn increment_rpc_requests_throttled(class: &str) {
+    #[cfg(feature = "monitoring_prom")]
+    prometheus::RPC_REQUESTS_THROTTLED
+        .with_label_values(&[class])
+        .inc();
+}
+
+/// Record the outcome of an RPC request which needed an API key, by the name of the key
+#[allow(unused_variables)]
+pub fn increment_rpc_api_key_requests(key: &str, outcome: &str) {
+    #[cfg(feature = "monitoring_prom")]
+    prometheus::RPC_API_KEY_REQUESTS
+        .with_label_values(&[key, outcome])
+        .inc();
+}
+
+pub fn increment_block_gaps_detected() {
+    #[cfg(feature = "monitoring_prom")]
+    prometheus::BLOCK_GAPS_DETECTED.inc();
+}
+
+pub fn increment_block_gaps_filled() {
+    #[cfg(feature = "monitoring_prom")]
+    prometheus::BLOCK_GAPS_FILLED.inc();
+}
+
 pub fn increment_stx_blocks_received_counter() {
     #[cfg(feature = "monitoring_prom")]
     prometheus::STX_BLOCKS_RECEIVED_COUNTER.inc();
@@ -314,6 +342,66 @@ pub fn increment_contract_calls_processed() {
     prometheus::CONTRACT_CALLS_PROCESSED_COUNT.inc();
 }
 
+/// Count a lookup in the mempool's admission cache.  The hit rate is the share of lookups with
+/// `result="hit"`.
+#[allow(unused_variables)]
+pub fn increment_mempool_admission_cache_lookups(hit: bool) {
+    #[cfg(feature = "monitoring_prom")]
+    prometheus::MEMPOOL_ADMISSION_CACHE_LOOKUPS
+        .with_label_values(&[if hit { "hit" } else { "miss" }])
+        .inc();
+}
+
+/// Record how long, in seconds, one of this miner's block-commits took to confirm on the L1
+#[allow(unused_variables)]
+pub fn update_miner_commit_confirm_time(secs: u64) {
+    #[cfg(feature = "monitoring_prom")]
+    prometheus::MINER_COMMIT_CONFIRM_TIME.observe(secs as f64);
+}
+
+pub fn increment_miner_commit_fee_bumps() {
+    #[cfg(feature = "monitoring_prom")]
+    prometheus::MINER_COMMIT_FEE_BUMPS.inc();
+}
+
+/// Record how long, in seconds, it has been since the node last observed a new burnchain block
+#[allow(unused_variables)]
+pub fn update_burnchain_tip_age(secs: u64) {
+    #[cfg(feature = "monitoring_prom")]
+    prometheus::BURNCHAIN_TIP_AGE_GAUGE.set(secs as i64);
+}
+
+pub fn increment_stale_burnchain_tip_alerts() {
+    #[cfg(feature = "monitoring_prom")]
+    prometheus::STALE_BURNCHAIN_TIP_ALERTS.inc();
+}
+
+/// Record how many blocks the L1 event observer has received but not yet indexed
+#[allow(unused_variables)]
+pub fn update_l1_observer_queue_depth(depth: u64) {
+    #[cfg(feature = "monitoring_prom")]
+    prometheus::L1_OBSERVER_QUEUE_DEPTH.set(depth as i64);
+}
+
+/// Record how long, in seconds, the L1 event observer took to take in a block from the L1 node,
+/// including any wait for room in its queue
+#[allow(unused_variables)]
+pub fn update_l1_block_fetch_latency(secs: f64) {
+    #[cfg(feature = "monitoring_prom")]
+    prometheus::L1_BLOCK_FETCH_LATENCY.observe(secs);
+}
+
+/// Record the latest upgrade signal from the L1 contract: its target height, and whether this
+/// node's version meets its required version
+#[allow(unused_variables)]
+pub fn update_upgrade_signal(target_height: u64, version_met: bool) {
+    #[cfg(feature = "monitoring_prom")]
+    {
+        prometheus::UPGRADE_TARGET_HEIGHT.set(target_height as i64);
+        prometheus::UPGRADE_VERSION_MET.set(if version_met { 1 } else { 0 });
+    }
+}
+
 /// Given a value (type uint256), return value/uint256::max() as an f64 value.
 /// The precision of the percentage is determined by the input `precision_points`, which is capped
 /// at a max of 15.
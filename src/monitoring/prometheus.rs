@@ -31,6 +31,28 @@ lazy_static! {
         // Will use DEFAULT_BUCKETS = [0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0] by default
     ), &["path"]).unwrap();
 
+    pub static ref RPC_REQUESTS_THROTTLED: IntCounterVec = register_int_counter_vec!(
+        "stacks_node_rpc_requests_throttled",
+        "RPC requests refused because the client exceeded its rate limit, by class of request",
+        &["class"]
+    ).unwrap();
+
+    pub static ref RPC_API_KEY_REQUESTS: IntCounterVec = register_int_counter_vec!(
+        "stacks_node_rpc_api_key_requests",
+        "RPC requests which needed an API key, by key and by whether they were accepted, throttled, or unauthorized",
+        &["key", "outcome"]
+    ).unwrap();
+
+    pub static ref BLOCK_GAPS_DETECTED: IntCounter = register_int_counter!(opts!(
+        "stacks_node_block_gaps_detected_total",
+        "Total number of received blocks whose parent had never been received"
+    )).unwrap();
+
+    pub static ref BLOCK_GAPS_FILLED: IntCounter = register_int_counter!(opts!(
+        "stacks_node_block_gaps_filled_total",
+        "Total number of missing parent blocks received after the gap was detected"
+    )).unwrap();
+
     pub static ref STX_BLOCKS_RECEIVED_COUNTER: IntCounter = register_int_counter!(opts!(
         "stacks_node_stx_blocks_received_total",
         "Total number of Stacks blocks received"
@@ -178,6 +200,12 @@ lazy_static! {
         "Total count of processed contract calls"
     )).unwrap();
 
+    pub static ref MEMPOOL_ADMISSION_CACHE_LOOKUPS: IntCounterVec = register_int_counter_vec!(
+        "stacks_node_mempool_admission_cache_lookups",
+        "Lookups in the mempool's admission check cache, by whether they found a result",
+        &["result"]
+    ).unwrap();
+
     pub static ref MEMPOOL_OUTSTANDING_TXS: IntGauge = register_int_gauge!(opts!(
         "stacks_node_mempool_outstanding_txs",
         "Number of still-unprocessed transactions received by this node since it started",
@@ -191,6 +219,50 @@ lazy_static! {
         labels! {"handler".to_string() => "all".to_string(),}
     )).unwrap();
 
+    pub static ref MINER_COMMIT_CONFIRM_TIME: Histogram = register_histogram!(histogram_opts!(
+        "stacks_node_miner_commit_confirm_times",
+        "Time (seconds) between when this miner first broadcast a block-commit to the L1 and when the L1 confirmed it",
+        vec![10.0, 30.0, 60.0, 120.0, 300.0, 600.0, 900.0, 1200.0, 1800.0, 2400.0, 3600.0, 7200.0],
+        labels! {"handler".to_string() => "all".to_string(),}
+    )).unwrap();
+
+    pub static ref MINER_COMMIT_FEE_BUMPS: IntCounter = register_int_counter!(opts!(
+        "stacks_node_miner_commit_fee_bumps",
+        "Number of times this miner re-broadcast an unconfirmed block-commit with a higher L1 fee"
+    )).unwrap();
+
+    pub static ref BURNCHAIN_TIP_AGE_GAUGE: IntGauge = register_int_gauge!(opts!(
+        "stacks_node_burn_block_tip_age_seconds",
+        "Seconds since this node last observed a new burnchain block"
+    )).unwrap();
+
+    pub static ref STALE_BURNCHAIN_TIP_ALERTS: IntCounter = register_int_counter!(opts!(
+        "stacks_node_stale_burn_block_tip_alerts",
+        "Number of times no new burnchain block was observed within [burnchain] stale_tip_alert_secs"
+    )).unwrap();
+
+    pub static ref L1_OBSERVER_QUEUE_DEPTH: IntGauge = register_int_gauge!(opts!(
+        "stacks_node_l1_observer_queue_depth",
+        "Number of L1 blocks the L1 event observer has received but not yet indexed"
+    )).unwrap();
+
+    pub static ref L1_BLOCK_FETCH_LATENCY: Histogram = register_histogram!(histogram_opts!(
+        "stacks_node_l1_block_fetch_latency_seconds",
+        "Time (seconds) the L1 event observer took to take in a block from the L1 node, including any wait for room in its queue",
+        vec![0.001, 0.005, 0.01, 0.05, 0.1, 0.5, 1.0, 5.0, 10.0, 30.0, 60.0],
+        labels! {"handler".to_string() => "all".to_string(),}
+    )).unwrap();
+
+    pub static ref UPGRADE_TARGET_HEIGHT: IntGauge = register_int_gauge!(opts!(
+        "stacks_node_upgrade_target_height",
+        "L1 block height targeted by the latest upgrade signal from the L1 contract"
+    )).unwrap();
+
+    pub static ref UPGRADE_VERSION_MET: IntGauge = register_int_gauge!(opts!(
+        "stacks_node_upgrade_version_met",
+        "1 if this node's version meets the latest upgrade signal's required version, 0 if not"
+    )).unwrap();
+
     pub static ref COMPUTED_RELATIVE_MINER_SCORE: Gauge = register_gauge!(opts!(
         "stacks_node_computed_relative_miner_score",
         "Percentage of the u256 range that this miner is assigned in a particular round of sortition"
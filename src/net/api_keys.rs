@@ -0,0 +1,163 @@
+// Copyright (C) 2013-2020 Blockstack PBC, a public benefit corporation
+// Copyright (C) 2020 Stacks Open Internet Foundation
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! API keys for the RPC endpoints which submit transactions.  If any keys are configured, a
+//! request to submit or simulate a transaction must carry one of them in its `X-API-Key`
+//! header, and is charged to that key's token bucket; read-only endpoints stay open.  Only the
+//! SHA-256 hashes of the keys are configured, so the node never stores the keys themselves.
+
+use std::collections::HashMap;
+
+use crate::net::connection::ConnectionOptions;
+use crate::net::ratelimit::{RateLimit, TokenBucket};
+use crate::net::HttpRequestType;
+use stacks_common::util::hash::Sha256Sum;
+
+/// A key which may submit transactions
+#[derive(Debug, Clone, PartialEq)]
+pub struct RPCApiKey {
+    /// what the key is known as in logs and metrics
+    pub name: String,
+    /// SHA-256 of the key
+    pub key_hash: Sha256Sum,
+    /// how many requests per second the key may make once its burst is used up (0 means
+    /// unlimited), and how many it may make at once
+    pub rate_limit: RateLimit,
+}
+
+/// What became of a request which needs an API key
+#[derive(Debug, Clone, PartialEq)]
+pub enum ApiKeyCheck {
+    /// the request carried the named key, which had a token to spare
+    Accepted(String),
+    /// the request carried no key
+    Missing,
+    /// the request carried a key which is not configured
+    Invalid,
+    /// the request carried the named key, whose bucket is empty
+    Throttled(String),
+}
+
+pub struct RPCApiKeys {
+    keys: HashMap<Sha256Sum, RPCApiKey>,
+    buckets: HashMap<Sha256Sum, TokenBucket>,
+}
+
+impl RPCApiKeys {
+    pub fn new(conn_opts: &ConnectionOptions) -> RPCApiKeys {
+        RPCApiKeys {
+            keys: conn_opts
+                .rpc_api_keys
+                .iter()
+                .map(|key| (key.key_hash.clone(), key.clone()))
+                .collect(),
+            buckets: HashMap::new(),
+        }
+    }
+
+    /// Are any keys configured?  If not, no request needs one.
+    pub fn is_enabled(&self) -> bool {
+        !self.keys.is_empty()
+    }
+
+    /// Does `req` need an API key, if any are configured?
+    pub fn requires_api_key(req: &HttpRequestType) -> bool {
+        match req {
+            HttpRequestType::PostTransaction(..) | HttpRequestType::SimulateTransaction(..) => true,
+            _ => false,
+        }
+    }
+
+    /// Check the key `api_key` a request carried at time `now_ms`.  If it is accepted, the
+    /// request is charged to the key's bucket.
+    pub fn check(&mut self, api_key: Option<&str>, now_ms: u128) -> ApiKeyCheck {
+        let api_key = match api_key {
+            Some(api_key) => api_key,
+            None => return ApiKeyCheck::Missing,
+        };
+        let key_hash = Sha256Sum::from_data(api_key.as_bytes());
+        let key = match self.keys.get(&key_hash) {
+            Some(key) => key,
+            None => return ApiKeyCheck::Invalid,
+        };
+        if key.rate_limit.rate == 0 {
+            return ApiKeyCheck::Accepted(key.name.clone());
+        }
+
+        let bucket = self
+            .buckets
+            .entry(key_hash)
+            .or_insert_with(|| TokenBucket::new(&key.rate_limit, now_ms));
+        if bucket.try_take(&key.rate_limit, now_ms) {
+            ApiKeyCheck::Accepted(key.name.clone())
+        } else {
+            ApiKeyCheck::Throttled(key.name.clone())
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_rpc_api_keys() {
+        let mut conn_opts = ConnectionOptions::default();
+        assert!(!RPCApiKeys::new(&conn_opts).is_enabled());
+
+        conn_opts.rpc_api_keys = vec![
+            RPCApiKey {
+                name: "wallet".to_string(),
+                key_hash: Sha256Sum::from_data(b"wallet-key"),
+                rate_limit: RateLimit { rate: 1, burst: 2 },
+            },
+            RPCApiKey {
+                name: "indexer".to_string(),
+                key_hash: Sha256Sum::from_data(b"indexer-key"),
+                rate_limit: RateLimit { rate: 0, burst: 0 },
+            },
+        ];
+        let mut api_keys = RPCApiKeys::new(&conn_opts);
+        assert!(api_keys.is_enabled());
+
+        assert_eq!(api_keys.check(None, 1000), ApiKeyCheck::Missing);
+        assert_eq!(api_keys.check(Some("nope"), 1000), ApiKeyCheck::Invalid);
+
+        // a key's burst is admitted, then it is throttled until its bucket refills
+        for _ in 0..2 {
+            assert_eq!(
+                api_keys.check(Some("wallet-key"), 1000),
+                ApiKeyCheck::Accepted("wallet".to_string())
+            );
+        }
+        assert_eq!(
+            api_keys.check(Some("wallet-key"), 1000),
+            ApiKeyCheck::Throttled("wallet".to_string())
+        );
+        assert_eq!(
+            api_keys.check(Some("wallet-key"), 2000),
+            ApiKeyCheck::Accepted("wallet".to_string())
+        );
+
+        // a key without a rate limit is never throttled
+        for _ in 0..100 {
+            assert_eq!(
+                api_keys.check(Some("indexer-key"), 1000),
+                ApiKeyCheck::Accepted("indexer".to_string())
+            );
+        }
+    }
+}
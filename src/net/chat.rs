@@ -241,7 +241,7 @@ impl NeighborStats {
         (successful as f64) / (total as f64)
     }
 
-    fn get_bandwidth(rx_counts: &VecDeque<(u64, u64)>, lifetime: u64) -> f64 {
+    pub fn get_bandwidth(rx_counts: &VecDeque<(u64, u64)>, lifetime: u64) -> f64 {
         if rx_counts.len() < 2 {
             return 0.0;
         }
@@ -731,8 +731,8 @@ impl ConversationP2P {
             );
             return Err(net_error::InvalidMessage);
         }
-        if (msg.preamble.peer_version & 0xff000000) != (self.version & 0xff000000) {
-            // major version mismatch
+        if (msg.preamble.peer_version & 0xffffff00) != (self.version & 0xffffff00) {
+            // major version or network magic mismatch
             test_debug!(
                 "{:?}: Preamble invalid: wrong peer version: {:x} != {:x}",
                 &self,
@@ -4066,6 +4066,37 @@ mod test {
             );
         }
 
+        // network magic check
+        {
+            let mut convo_bad = ConversationP2P::new(
+                123,
+                456,
+                &burnchain,
+                &socketaddr_2,
+                &conn_opts,
+                true,
+                0,
+                StacksEpoch::unit_test_pre_2_05(0),
+            );
+
+            let ping_data = PingData::new();
+            let version = convo_bad.version;
+            convo_bad.version = peer_version_with_magic(version, [0x12, 0x34]);
+            let ping_bad = convo_bad
+                .sign_message(
+                    &chain_view,
+                    &local_peer_1.private_key,
+                    StacksMessageType::Ping(ping_data.clone()),
+                )
+                .unwrap();
+            convo_bad.version = version;
+
+            assert_eq!(
+                convo_bad.is_preamble_valid(&ping_bad, &chain_view),
+                Err(net_error::InvalidMessage)
+            );
+        }
+
         // stable block height check
         {
             let mut convo_bad = ConversationP2P::new(
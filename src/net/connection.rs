@@ -39,6 +39,7 @@ use mio::net as mio_net;
 use crate::codec::StacksMessageCodec;
 use crate::codec::MAX_MESSAGE_LEN;
 use crate::core::mempool::MAX_BLOOM_COUNTER_TXS;
+use crate::net::api_keys::RPCApiKey;
 use crate::net::codec::*;
 use crate::net::Error as net_error;
 use crate::net::HttpRequestPreamble;
@@ -339,7 +340,37 @@ pub struct ConnectionOptions {
     pub soft_max_neighbors_per_org: u64,
     pub soft_max_clients_per_host: u64,
     pub max_neighbors_of_neighbor: u64,
+    /// how many outbound p2p connections we may have open at once, including the short-lived
+    /// ones a neighbor walk opens.  Unlike `num_neighbors`, this is enforced when a connection is
+    /// registered rather than by pruning.  0 means unlimited.
+    pub max_outbound_neighbors: u64,
     pub max_http_clients: u64,
+    /// how many HTTP connections a single IP address may have open at once
+    pub max_http_clients_per_host: u64,
+    /// how many read-only RPC requests per second each client IP address may make, once its
+    /// burst is used up.  0 means unlimited.
+    pub rpc_read_rate_limit: u64,
+    /// how many read-only RPC requests each client IP address may make at once
+    pub rpc_read_burst: u64,
+    /// how many transaction, block, and microblock submissions per second each client IP
+    /// address may make, once its burst is used up.  0 means unlimited.
+    pub rpc_submit_rate_limit: u64,
+    /// how many transaction, block, and microblock submissions each client IP address may make
+    /// at once
+    pub rpc_submit_burst: u64,
+    /// how many event replays per second each client IP address may ask for, once its burst is
+    /// used up.  0 means unlimited.
+    pub rpc_replay_rate_limit: u64,
+    /// how many event replays each client IP address may ask for at once
+    pub rpc_replay_burst: u64,
+    /// the API keys which may submit and simulate transactions.  If empty, no key is needed.
+    pub rpc_api_keys: Vec<RPCApiKey>,
+    /// whether to sign the RPC responses bridges act on with the node key.  See
+    /// `net::response_signing`.
+    pub sign_rpc_responses: bool,
+    /// how many L1 blocks the node may have left to process before `/v2/health/ready` reports
+    /// it not ready
+    pub health_max_burn_block_lag: u64,
     pub neighbor_request_timeout: u64,
     pub num_initial_walks: u64,
     pub walk_retry_count: u64,
@@ -382,6 +413,13 @@ pub struct ConnectionOptions {
     pub mempool_max_tx_query: u64,
     /// how long a mempool sync is allowed to take, in total, before timing out
     pub mempool_sync_timeout: u64,
+    /// whether or not to also query inbound peers' mempools.  Without this, a node only pulls
+    /// transactions from the peers it connected to, so a node which only has inbound peers (such
+    /// as a miner that followers bootstrap from) never learns of transactions they accept.
+    pub mempool_sync_inbound: bool,
+    /// the maximum rate, in bytes/sec, at which to download transactions via mempool sync.
+    /// 0 means unlimited.
+    pub max_mempool_sync_bandwidth: u64,
 
     // fault injection
     pub disable_neighbor_walk: bool,
@@ -408,6 +446,9 @@ pub struct ConnectionOptions {
     // TODO: This should be `HashSet` or `BTreeSet` for more efficient lookup
     //       Using these types requires deriving `Hash` or `Ord` on `Secp256k1PublicKey`
     pub allowed_block_proposers: Vec<Secp256k1PublicKey>,
+    /// bearer token which authenticates requests to the admin RPC endpoints that change the
+    /// node's behavior.  If not set, those endpoints are disabled.
+    pub admin_auth_token: Option<String>,
 }
 
 impl std::default::Default for ConnectionOptions {
@@ -431,7 +472,18 @@ impl std::default::Default for ConnectionOptions {
             soft_max_neighbors_per_org: 10, // how many outbound connections we can have per AS-owning organization, before we start pruning them
             soft_max_clients_per_host: 10, // how many inbound connections we can have per IP address, before we start pruning them,
             max_neighbors_of_neighbor: 10,
+            max_outbound_neighbors: 0, // no hard cap on outbound connections by default
             max_http_clients: 10,
+            max_http_clients_per_host: 10,
+            rpc_read_rate_limit: 0, // no RPC rate limits by default
+            rpc_read_burst: 0,
+            rpc_submit_rate_limit: 0,
+            rpc_submit_burst: 0,
+            rpc_replay_rate_limit: 1, // event replays re-execute blocks, so they are limited
+            rpc_replay_burst: 1,
+            rpc_api_keys: vec![],
+            sign_rpc_responses: false,
+            health_max_burn_block_lag: 6,
             neighbor_request_timeout: NEIGHBOR_REQUEST_TIMEOUT, // how long to wait for a neighbor request
             num_initial_walks: NUM_INITIAL_WALKS,
             walk_retry_count: WALK_RETRY_COUNT,
@@ -477,6 +529,8 @@ impl std::default::Default for ConnectionOptions {
             mempool_sync_interval: 30, // number of seconds in-between mempool sync
             mempool_max_tx_query: 128, // maximum number of transactions to visit per mempool query
             mempool_sync_timeout: 180, // how long a mempool sync can go for (3 minutes)
+            mempool_sync_inbound: false, // only query outbound peers' mempools
+            max_mempool_sync_bandwidth: 0, // infinite mempool sync download bandwidth allowed
 
             // no faults on by default
             disable_neighbor_walk: false,
@@ -497,6 +551,7 @@ impl std::default::Default for ConnectionOptions {
             subnet_validator: None,
             subnet_signing_contract: None,
             allowed_block_proposers: Vec::default(),
+            admin_auth_token: None,
         }
     }
 }
@@ -456,11 +456,14 @@ impl PeerDB {
         Ok(())
     }
 
+    /// Update the local peer's port and data URL, and its address unless `p2p_addr` is the
+    /// any-network address, in which case the address it last learned is kept.
     fn update_local_peer(
         &mut self,
         network_id: u32,
         parent_network_id: u32,
         data_url: UrlString,
+        p2p_addr: PeerAddress,
         p2p_port: u16,
     ) -> Result<(), db_error> {
         let local_peer_args: &[&dyn ToSql] = &[
@@ -472,9 +475,24 @@ impl PeerDB {
 
         match self.conn.execute("UPDATE local_peer SET port = ?, data_url = ? WHERE network_id = ? AND parent_network_id = ?",
                                 local_peer_args) {
-            Ok(_) => Ok(()),
-            Err(e) => Err(db_error::SqliteError(e))
+            Ok(_) => {},
+            Err(e) => return Err(db_error::SqliteError(e))
+        };
+
+        if !p2p_addr.is_anynet() {
+            let addr_args: &[&dyn ToSql] = &[
+                &to_bin(&p2p_addr.as_bytes().to_vec()),
+                &network_id,
+                &parent_network_id,
+            ];
+            self.conn
+                .execute(
+                    "UPDATE local_peer SET addrbytes = ? WHERE network_id = ? AND parent_network_id = ?",
+                    addr_args,
+                )
+                .map_err(db_error::SqliteError)?;
         }
+        Ok(())
     }
 
     fn reset_denies<'a>(tx: &mut Transaction<'a>) -> Result<(), db_error> {
@@ -580,7 +598,7 @@ impl PeerDB {
                 }
             }
         } else {
-            db.update_local_peer(network_id, parent_network_id, data_url, p2p_port)?;
+            db.update_local_peer(network_id, parent_network_id, data_url, p2p_addr, p2p_port)?;
 
             {
                 let mut tx = db.tx_begin()?;
@@ -1416,6 +1434,8 @@ impl PeerDB {
 #[cfg(test)]
 mod test {
     use super::*;
+    use std::net::SocketAddr;
+
     use crate::net::Neighbor;
     use crate::net::NeighborKey;
     use crate::net::PeerAddress;
@@ -1595,6 +1615,48 @@ mod test {
         }
     }
 
+    #[test]
+    fn test_local_peer_address_updated_on_reopen() {
+        let path = "/tmp/test_local_peer_address_updated_on_reopen.db".to_string();
+        let _ = fs::remove_file(&path);
+
+        let connect = |addr: &str, port: u16| {
+            let addr: SocketAddr = addr.parse().unwrap();
+            PeerDB::connect(
+                &path,
+                true,
+                0x9abcdef0,
+                12345,
+                None,
+                0,
+                PeerAddress::from_socketaddr(&addr),
+                port,
+                "http://foo.com".into(),
+                &vec![],
+                None,
+            )
+            .unwrap()
+        };
+
+        let db = connect("[2001:db8::1]:20444", 20444);
+        let local_peer = PeerDB::get_local_peer(db.conn()).unwrap();
+        assert_eq!(
+            local_peer.addrbytes.to_socketaddr(local_peer.port),
+            "[2001:db8::1]:20444".parse::<SocketAddr>().unwrap()
+        );
+
+        // a new address and port are advertised once the node reboots with them
+        let db = connect("10.0.0.2:30444", 30444);
+        let local_peer = PeerDB::get_local_peer(db.conn()).unwrap();
+        assert_eq!(local_peer.addrbytes, PeerAddress::from_ipv4(10, 0, 0, 2));
+        assert_eq!(local_peer.port, 30444);
+
+        // but the any-network address doesn't replace the last one
+        let db = connect("[::]:30444", 30444);
+        let local_peer = PeerDB::get_local_peer(db.conn()).unwrap();
+        assert_eq!(local_peer.addrbytes, PeerAddress::from_ipv4(10, 0, 0, 2));
+    }
+
     #[test]
     fn test_initial_neighbors() {
         let mut initial_neighbors = vec![];
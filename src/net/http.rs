@@ -38,12 +38,18 @@ use url::{form_urlencoded, Url};
 
 use crate::burnchains::{Address, Txid};
 use crate::chainstate::burn::ConsensusHash;
+use crate::chainstate::stacks::db::block_costs::CONTRACT_COSTS_HISTORY_BLOCKS;
+use crate::chainstate::stacks::db::commits::MAX_BLOCK_COMMITS;
+use crate::chainstate::stacks::db::event_replay::MAX_EVENT_REPLAY_BLOCKS;
+use crate::chainstate::stacks::db::rewards_report::MAX_REWARDS_REPORT_BLOCKS;
 use crate::chainstate::stacks::miner::SignedProposal;
 use crate::chainstate::stacks::{
     StacksBlock, StacksMicroblock, StacksPublicKey, StacksTransaction,
 };
+use crate::core::mempool::{is_valid_bundle_id, MAX_BUNDLE_ID_LEN};
 use crate::deps::httparse;
 use crate::net::atlas::Attachment;
+use crate::net::response_signing::RPCResponseSigner;
 use crate::net::ClientError;
 use crate::net::Error as net_error;
 use crate::net::Error::ClarityError;
@@ -66,13 +72,28 @@ use crate::net::StacksHttpMessage;
 use crate::net::StacksHttpPreamble;
 use crate::net::UnconfirmedTransactionResponse;
 use crate::net::UnconfirmedTransactionStatus;
+use crate::net::DEFAULT_BLOCK_COMMITS;
+use crate::net::DEFAULT_CONTRACT_COSTS_BLOCKS;
+use crate::net::DEFAULT_FORK_TREE_BURN_BLOCKS;
+use crate::net::DEFAULT_MEMPOOL_PREVIEW_RESULTS;
 use crate::net::HTTP_PREAMBLE_MAX_ENCODED_SIZE;
 use crate::net::HTTP_PREAMBLE_MAX_NUM_HEADERS;
 use crate::net::HTTP_REQUEST_ID_RESERVED;
+use crate::net::MAX_ACCOUNT_TRANSACTIONS_PAGE_SIZE;
+use crate::net::MAX_CALL_READ_BATCH_SIZE;
+use crate::net::MAX_FAILED_DEPOSITS_PAGE_SIZE;
+use crate::net::MAX_FORK_TREE_BURN_BLOCKS;
 use crate::net::MAX_HEADERS;
+use crate::net::MAX_MEMPOOL_PREVIEW_RESULTS;
 use crate::net::MAX_MICROBLOCKS_UNCONFIRMED;
-use crate::net::{CallReadOnlyRequestBody, TipRequest};
+use crate::net::MAX_WITHDRAWALS_PAGE_SIZE;
+use crate::net::{
+    CallReadOnlyBatchEntry, CallReadOnlyBatchRequestBody, CallReadOnlyRequestBody, ReadOnlyCall,
+    TipRequest,
+};
 use crate::net::{GetAttachmentResponse, GetAttachmentsInvResponse, PostTransactionRequestBody};
+use crate::net::{MemPoolFilterRequestBody, MemPoolFiltersResponse};
+use crate::net::{RejectedTxsQuery, DEFAULT_REJECTED_TXS_RESULTS, MAX_REJECTED_TXS_RESULTS};
 use clarity::vm::ast::parser::v1::CLARITY_NAME_REGEX;
 use clarity::vm::types::{
     AssetIdentifier, QualifiedContractIdentifier, StandardPrincipalData, TraitIdentifier,
@@ -97,6 +118,7 @@ use crate::codec::{
     read_next, write_next, Error as codec_error, StacksMessageCodec, MAX_MESSAGE_LEN,
     MAX_PAYLOAD_LEN,
 };
+use crate::core::mempool_filters::MAX_FILTER_TTL_SECS;
 use crate::types::chainstate::{BlockHeaderHash, StacksAddress, StacksBlockId};
 
 use super::FeeRateEstimateRequestBody;
@@ -104,6 +126,11 @@ use super::FeeRateEstimateRequestBody;
 const MAX_BLOCK_PROPOSAL_LENGTH: u32 = 1024 * 1024 * 15;
 
 pub const PATH_STR_POST_BLOCK_PROPOSAL: &'static str = "/v2/block_proposal";
+pub const PATH_STR_POST_MINER_PAUSE: &'static str = "/v2/admin/miner/pause";
+pub const PATH_STR_POST_MINER_RESUME: &'static str = "/v2/admin/miner/resume";
+pub const PATH_STR_POST_MINER_ROTATE_KEY: &'static str = "/v2/admin/miner/rotate_key";
+pub const PATH_STR_GET_MEMPOOL_FILTERS: &'static str = "/v2/admin/mempool/filters";
+pub const PATH_STR_POST_MEMPOOL_FILTER: &'static str = "/v2/admin/mempool/filter";
 
 lazy_static! {
     static ref PATH_GETINFO: Regex = Regex::new(r#"^/v2/info$"#).unwrap();
@@ -120,14 +147,44 @@ lazy_static! {
     static ref PATH_GETTRANSACTION_UNCONFIRMED: Regex =
         Regex::new(r#"^/v2/transactions/unconfirmed/([0-9a-f]{64})$"#).unwrap();
     static ref PATH_POSTTRANSACTION: Regex = Regex::new(r#"^/v2/transactions$"#).unwrap();
+    static ref PATH_POST_SIMULATE_TRANSACTION: Regex =
+        Regex::new(r#"^/v2/transactions/simulate$"#).unwrap();
     static ref PATH_POST_FEE_RATE_ESIMATE: Regex = Regex::new(r#"^/v2/fees/transaction$"#).unwrap();
     static ref PATH_POSTBLOCK: Regex = Regex::new(r#"^/v2/blocks/upload/([0-9a-f]{40})$"#).unwrap();
+    static ref PATH_POST_VALIDATE_BLOCK: Regex =
+        Regex::new(r#"^/v2/blocks/validate/([0-9a-f]{40})$"#).unwrap();
     static ref PATH_POSTMICROBLOCK: Regex = Regex::new(r#"^/v2/microblocks$"#).unwrap();
     static ref PATH_GET_STX_WITHDRAWAL: Regex = Regex::new(&format!(
         "^/v2/withdrawal/stx/(?P<block_height>[0-9]+)/(?P<sender>{})/(?P<withdrawal_id>[0-9]+)/(?P<amount>[0-9]+)$",
         *PRINCIPAL_DATA_REGEX_STRING
     ))
     .unwrap();
+    static ref PATH_GET_WITHDRAWALS_BY_SENDER: Regex = Regex::new(&format!(
+        "^/v2/hyperchain/withdrawals/(?P<sender>{})$",
+        *PRINCIPAL_DATA_REGEX_STRING
+    ))
+    .unwrap();
+    static ref PATH_GET_WITHDRAWAL_CLAIM: Regex = Regex::new(
+        "^/v2/withdrawal/claim/(?P<block_height>[0-9]+)/(?P<withdrawal_id>[0-9]+)$"
+    )
+    .unwrap();
+    static ref PATH_GET_FAILED_DEPOSITS_BY_SENDER: Regex = Regex::new(&format!(
+        "^/v2/hyperchain/failed_deposits/(?P<sender>{})$",
+        *PRINCIPAL_DATA_REGEX_STRING
+    ))
+    .unwrap();
+    static ref PATH_GET_BURN_OPS: Regex = Regex::new("^/v2/hyperchain/burn_ops$").unwrap();
+    static ref PATH_GET_FORKS: Regex = Regex::new("^/v2/hyperchain/forks$").unwrap();
+    static ref PATH_GET_CONTRACT_COSTS: Regex =
+        Regex::new("^/v2/hyperchain/contract_costs$").unwrap();
+    static ref PATH_GET_BLOCK_COMMITS: Regex = Regex::new("^/v2/hyperchain/commits$").unwrap();
+    static ref PATH_GET_REWARDS_REPORT: Regex = Regex::new("^/v2/rewards/report$").unwrap();
+    static ref PATH_GET_EVENT_REPLAY: Regex = Regex::new("^/v2/events/replay$").unwrap();
+    static ref PATH_GET_SUBNET_INFO: Regex = Regex::new("^/v2/hyperchain/info$").unwrap();
+    static ref PATH_GET_MICROBLOCK_STREAM: Regex =
+        Regex::new("^/v2/microblocks/stream/(?P<parent_block_id>[0-9a-f]{64})$").unwrap();
+    static ref PATH_GET_HEALTH_LIVE: Regex = Regex::new("^/v2/health/live$").unwrap();
+    static ref PATH_GET_HEALTH_READY: Regex = Regex::new("^/v2/health/ready$").unwrap();
     static ref PATH_POST_BLOCK_PROPOSAL: Regex = Regex::new(&format!("^{}$", PATH_STR_POST_BLOCK_PROPOSAL))
     .unwrap();
     static ref PATH_GET_FT_WITHDRAWAL: Regex = Regex::new(&format!(
@@ -140,11 +197,26 @@ lazy_static! {
          *PRINCIPAL_DATA_REGEX_STRING,  *STANDARD_PRINCIPAL_REGEX_STRING, *CONTRACT_NAME_REGEX_STRING
      ))
      .unwrap();
+    static ref PATH_GET_STX_TRANSFER_WITHDRAWAL: Regex = Regex::new(&format!(
+         "^/v2/withdrawal/stx-transfer/(?P<block_height>[0-9]+)/(?P<sender>{})/(?P<withdrawal_id>[0-9]+)/(?P<amount>[0-9]+)/(?P<recipient>{})/(?P<contract_address>{})/(?P<contract_name>{})$",
+         *PRINCIPAL_DATA_REGEX_STRING, *PRINCIPAL_DATA_REGEX_STRING, *STANDARD_PRINCIPAL_REGEX_STRING, *CONTRACT_NAME_REGEX_STRING
+     ))
+     .unwrap();
     static ref PATH_GET_ACCOUNT: Regex = Regex::new(&format!(
         "^/v2/accounts/(?P<principal>{})$",
         *PRINCIPAL_DATA_REGEX_STRING
     ))
     .unwrap();
+    static ref PATH_GET_ACCOUNT_NEXT_NONCE: Regex = Regex::new(&format!(
+        "^/v2/accounts/(?P<address>{})/next_nonce$",
+        *STANDARD_PRINCIPAL_REGEX_STRING
+    ))
+    .unwrap();
+    static ref PATH_GET_ACCOUNT_TRANSACTIONS: Regex = Regex::new(&format!(
+        "^/v2/accounts/(?P<principal>{})/transactions$",
+        *PRINCIPAL_DATA_REGEX_STRING
+    ))
+    .unwrap();
     static ref PATH_GET_DATA_VAR: Regex = Regex::new(&format!(
         "^/v2/data_var/(?P<address>{})/(?P<contract>{})/(?P<varname>{})$",
         *STANDARD_PRINCIPAL_REGEX_STRING, *CONTRACT_NAME_REGEX_STRING, *CLARITY_NAME_REGEX
@@ -160,6 +232,8 @@ lazy_static! {
         *STANDARD_PRINCIPAL_REGEX_STRING, *CONTRACT_NAME_REGEX_STRING, *CLARITY_NAME_REGEX
     ))
     .unwrap();
+    static ref PATH_POST_CALL_READ_ONLY_BATCH: Regex =
+        Regex::new("^/v2/contracts/call-read-batch$").unwrap();
     static ref PATH_GET_CONTRACT_SRC: Regex = Regex::new(&format!(
         "^/v2/contracts/source/(?P<address>{})/(?P<contract>{})$",
         *STANDARD_PRINCIPAL_REGEX_STRING, *CONTRACT_NAME_REGEX_STRING
@@ -176,11 +250,38 @@ lazy_static! {
     ))
     .unwrap();
     static ref PATH_GET_TRANSFER_COST: Regex = Regex::new("^/v2/fees/transfer$").unwrap();
+    static ref PATH_GET_SUBNET_FEE_RATE: Regex = Regex::new("^/v2/fees/subnet$").unwrap();
     static ref PATH_GET_ATTACHMENTS_INV: Regex = Regex::new("^/v2/attachments/inv$").unwrap();
     static ref PATH_GET_ATTACHMENT: Regex =
         Regex::new(r#"^/v2/attachments/([0-9a-f]{40})$"#).unwrap();
     static ref PATH_POST_MEMPOOL_QUERY: Regex =
         Regex::new(r#"^/v2/mempool/query$"#).unwrap();
+    static ref PATH_GET_MEMPOOL_ACCOUNT: Regex = Regex::new(&format!(
+        "^/v2/mempool/account/(?P<address>{})$",
+        *STANDARD_PRINCIPAL_REGEX_STRING
+    ))
+    .unwrap();
+    static ref PATH_GET_MEMPOOL_PREVIEW: Regex =
+        Regex::new(r#"^/v2/mempool/preview$"#).unwrap();
+    static ref PATH_GET_REJECTED_TXS: Regex = Regex::new(r#"^/v2/mempool/rejected$"#).unwrap();
+    static ref PATH_GET_MEMPOOL_STATS: Regex = Regex::new(r#"^/v2/mempool/stats$"#).unwrap();
+    static ref PATH_GET_CONTRACT_PUBLISH_POLICY: Regex =
+        Regex::new(r#"^/v2/admin/contract_publish_policy$"#).unwrap();
+    static ref PATH_POST_MINER_PAUSE: Regex =
+        Regex::new(&format!("^{}$", PATH_STR_POST_MINER_PAUSE)).unwrap();
+    static ref PATH_POST_MINER_RESUME: Regex =
+        Regex::new(&format!("^{}$", PATH_STR_POST_MINER_RESUME)).unwrap();
+    static ref PATH_POST_MINER_ROTATE_KEY: Regex =
+        Regex::new(&format!("^{}$", PATH_STR_POST_MINER_ROTATE_KEY)).unwrap();
+    static ref PATH_GET_MEMPOOL_FILTERS: Regex =
+        Regex::new(&format!("^{}$", PATH_STR_GET_MEMPOOL_FILTERS)).unwrap();
+    static ref PATH_POST_MEMPOOL_FILTER: Regex =
+        Regex::new(&format!("^{}$", PATH_STR_POST_MEMPOOL_FILTER)).unwrap();
+    static ref PATH_DELETE_MEMPOOL_FILTER: Regex = Regex::new(&format!(
+        "^{}/(?P<filter_id>[0-9]{{1,20}})$",
+        PATH_STR_POST_MEMPOOL_FILTER
+    ))
+    .unwrap();
     static ref PATH_OPTIONS_WILDCARD: Regex = Regex::new("^/v2/.{0,4096}$").unwrap();
 }
 
@@ -911,6 +1012,10 @@ fn stacks_height_headers<W: Write>(
         }
         _ => {}
     }
+    if let Some(api_key) = md.api_key.as_ref() {
+        fd.write_all(format!("X-API-Key: {}\r\n", api_key).as_bytes())
+            .map_err(codec_error::WriteError)?;
+    }
     Ok(())
 }
 
@@ -1558,7 +1663,17 @@ impl HttpRequestType {
                 &PATH_POSTTRANSACTION,
                 &HttpRequestType::parse_posttransaction,
             ),
+            (
+                "POST",
+                &PATH_POST_SIMULATE_TRANSACTION,
+                &HttpRequestType::parse_post_simulate_transaction,
+            ),
             ("POST", &PATH_POSTBLOCK, &HttpRequestType::parse_postblock),
+            (
+                "POST",
+                &PATH_POST_VALIDATE_BLOCK,
+                &HttpRequestType::parse_post_validate_block,
+            ),
             (
                 "POST",
                 &PATH_POSTMICROBLOCK,
@@ -1569,6 +1684,66 @@ impl HttpRequestType {
                 &PATH_GET_ACCOUNT,
                 &HttpRequestType::parse_get_account,
             ),
+            (
+                "GET",
+                &PATH_GET_ACCOUNT_NEXT_NONCE,
+                &HttpRequestType::parse_get_account_next_nonce,
+            ),
+            (
+                "GET",
+                &PATH_GET_MEMPOOL_ACCOUNT,
+                &HttpRequestType::parse_get_mempool_account,
+            ),
+            (
+                "GET",
+                &PATH_GET_MEMPOOL_PREVIEW,
+                &HttpRequestType::parse_get_mempool_preview,
+            ),
+            (
+                "GET",
+                &PATH_GET_REJECTED_TXS,
+                &HttpRequestType::parse_get_rejected_txs,
+            ),
+            (
+                "GET",
+                &PATH_GET_MEMPOOL_STATS,
+                &HttpRequestType::parse_get_mempool_stats,
+            ),
+            (
+                "GET",
+                &PATH_GET_CONTRACT_PUBLISH_POLICY,
+                &HttpRequestType::parse_get_contract_publish_policy,
+            ),
+            (
+                "POST",
+                &PATH_POST_MINER_PAUSE,
+                &HttpRequestType::parse_post_miner_pause,
+            ),
+            (
+                "POST",
+                &PATH_POST_MINER_RESUME,
+                &HttpRequestType::parse_post_miner_resume,
+            ),
+            (
+                "POST",
+                &PATH_POST_MINER_ROTATE_KEY,
+                &HttpRequestType::parse_post_miner_rotate_key,
+            ),
+            (
+                "GET",
+                &PATH_GET_MEMPOOL_FILTERS,
+                &HttpRequestType::parse_get_mempool_filters,
+            ),
+            (
+                "POST",
+                &PATH_POST_MEMPOOL_FILTER,
+                &HttpRequestType::parse_post_mempool_filter,
+            ),
+            (
+                "DELETE",
+                &PATH_DELETE_MEMPOOL_FILTER,
+                &HttpRequestType::parse_delete_mempool_filter,
+            ),
             (
                 "GET",
                 &PATH_GET_DATA_VAR,
@@ -1584,6 +1759,11 @@ impl HttpRequestType {
                 &PATH_GET_TRANSFER_COST,
                 &HttpRequestType::parse_get_transfer_cost,
             ),
+            (
+                "GET",
+                &PATH_GET_SUBNET_FEE_RATE,
+                &HttpRequestType::parse_get_subnet_fee_rate,
+            ),
             (
                 "GET",
                 &PATH_GET_CONTRACT_SRC,
@@ -1604,6 +1784,11 @@ impl HttpRequestType {
                 &PATH_POST_CALL_READ_ONLY,
                 &HttpRequestType::parse_call_read_only,
             ),
+            (
+                "POST",
+                &PATH_POST_CALL_READ_ONLY_BATCH,
+                &HttpRequestType::parse_call_read_only_batch,
+            ),
             (
                 "OPTIONS",
                 &PATH_OPTIONS_WILDCARD,
@@ -1644,6 +1829,77 @@ impl HttpRequestType {
                 &PATH_GET_NFT_WITHDRAWAL,
                 &HttpRequestType::parse_get_nft_withdrawal,
             ),
+            (
+                "GET",
+                &PATH_GET_STX_TRANSFER_WITHDRAWAL,
+                &HttpRequestType::parse_get_stx_transfer_withdrawal,
+            ),
+            (
+                "GET",
+                &PATH_GET_WITHDRAWALS_BY_SENDER,
+                &HttpRequestType::parse_get_withdrawals_by_sender,
+            ),
+            (
+                "GET",
+                &PATH_GET_WITHDRAWAL_CLAIM,
+                &HttpRequestType::parse_get_withdrawal_claim,
+            ),
+            (
+                "GET",
+                &PATH_GET_FAILED_DEPOSITS_BY_SENDER,
+                &HttpRequestType::parse_get_failed_deposits_by_sender,
+            ),
+            (
+                "GET",
+                &PATH_GET_ACCOUNT_TRANSACTIONS,
+                &HttpRequestType::parse_get_account_transactions,
+            ),
+            (
+                "GET",
+                &PATH_GET_BURN_OPS,
+                &HttpRequestType::parse_get_burn_ops,
+            ),
+            ("GET", &PATH_GET_FORKS, &HttpRequestType::parse_get_forks),
+            (
+                "GET",
+                &PATH_GET_CONTRACT_COSTS,
+                &HttpRequestType::parse_get_contract_costs,
+            ),
+            (
+                "GET",
+                &PATH_GET_BLOCK_COMMITS,
+                &HttpRequestType::parse_get_block_commits,
+            ),
+            (
+                "GET",
+                &PATH_GET_REWARDS_REPORT,
+                &HttpRequestType::parse_get_rewards_report,
+            ),
+            (
+                "GET",
+                &PATH_GET_EVENT_REPLAY,
+                &HttpRequestType::parse_get_event_replay,
+            ),
+            (
+                "GET",
+                &PATH_GET_SUBNET_INFO,
+                &HttpRequestType::parse_get_subnet_info,
+            ),
+            (
+                "GET",
+                &PATH_GET_MICROBLOCK_STREAM,
+                &HttpRequestType::parse_get_microblock_stream,
+            ),
+            (
+                "GET",
+                &PATH_GET_HEALTH_LIVE,
+                &HttpRequestType::parse_get_health_live,
+            ),
+            (
+                "GET",
+                &PATH_GET_HEALTH_READY,
+                &HttpRequestType::parse_get_health_ready,
+            ),
         ];
 
         // use url::Url to parse path and query string
@@ -1745,6 +2001,24 @@ impl HttpRequestType {
         ))
     }
 
+    fn parse_get_subnet_fee_rate<R: Read>(
+        _protocol: &mut StacksHttp,
+        preamble: &HttpRequestPreamble,
+        _regex: &Captures,
+        _query: Option<&str>,
+        _fd: &mut R,
+    ) -> Result<HttpRequestType, net_error> {
+        if preamble.get_content_length() != 0 {
+            return Err(net_error::DeserializeError(
+                "Invalid Http request: expected 0-length body for GetSubnetFeeRate".to_string(),
+            ));
+        }
+
+        Ok(HttpRequestType::GetSubnetFeeRate(
+            HttpRequestMetadata::from_preamble(preamble),
+        ))
+    }
+
     /// Check whether the given option query string sets proof=0 (setting proof to false).
     /// Defaults to true.
     fn get_proof_query(query: Option<&str>) -> bool {
@@ -1787,6 +2061,57 @@ impl HttpRequestType {
         }
     }
 
+    /// get an optional unsigned integer query argument, such as `offset` or `limit`.
+    /// Take the first value we can parse.
+    fn get_u64_query(query: Option<&str>, name: &str) -> Option<u64> {
+        let query_string = query?;
+        form_urlencoded::parse(query_string.as_bytes())
+            .filter(|(key, _value)| key == name)
+            .find_map(|(_key, value)| value.parse::<u64>().ok())
+    }
+
+    /// get the optional bundle ID query argument (`bundle_id`) of a posted transaction.
+    /// Fails if it is given but is not a valid bundle ID.
+    fn get_bundle_id_query(query: Option<&str>) -> Result<Option<String>, net_error> {
+        let query_string = match query {
+            Some(query_string) => query_string,
+            None => return Ok(None),
+        };
+        match form_urlencoded::parse(query_string.as_bytes())
+            .find(|(key, _value)| key == "bundle_id")
+        {
+            Some((_key, value)) => {
+                if is_valid_bundle_id(&value) {
+                    Ok(Some(value.to_string()))
+                } else {
+                    Err(net_error::DeserializeError(format!(
+                        "Invalid bundle_id: expected 1 to {} letters, digits, '-' or '_'",
+                        MAX_BUNDLE_ID_LEN
+                    )))
+                }
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// get the optional event keys query argument (`keys`), a comma-separated list in the
+    /// syntax of an event observer's `events_keys`.  All events (`*`) if it isn't given.
+    fn get_event_keys_query(query: Option<&str>) -> Vec<String> {
+        let keys = query.and_then(|query_string| {
+            form_urlencoded::parse(query_string.as_bytes())
+                .find(|(key, _value)| key == "keys")
+                .map(|(_key, value)| value.to_string())
+        });
+        match keys {
+            Some(keys) => keys
+                .split(',')
+                .filter(|key| !key.is_empty())
+                .map(|key| key.to_string())
+                .collect(),
+            None => vec!["*".to_string()],
+        }
+    }
+
     /// get the mempool page ID optional query argument (`page_id`)
     /// Take the first value we can parse.
     fn get_mempool_page_id_query(query: Option<&str>) -> Option<Txid> {
@@ -1836,225 +2161,173 @@ impl HttpRequestType {
         ))
     }
 
-    fn parse_get_stx_withdrawal<R: Read>(
+    fn parse_get_mempool_account<R: Read>(
         _protocol: &mut StacksHttp,
         preamble: &HttpRequestPreamble,
         captures: &Captures,
-        _query: Option<&str>,
+        query: Option<&str>,
         _fd: &mut R,
     ) -> Result<HttpRequestType, net_error> {
         if preamble.get_content_length() != 0 {
             return Err(net_error::DeserializeError(
-                "Invalid Http request: expected 0-length body for GetAccount".to_string(),
+                "Invalid Http request: expected 0-length body for GetMempoolAccount".to_string(),
             ));
         }
 
-        let sender = PrincipalData::parse(&captures["sender"]).map_err(|_e| {
-            net_error::DeserializeError("Failed to parse account principal".into())
-        })?;
-
-        let withdraw_block_height = u64::from_str(&captures["block_height"])
-            .map_err(|_e| net_error::DeserializeError("Failed to parse block height".into()))?;
-
-        let withdrawal_id = u32::from_str(&captures["withdrawal_id"])
-            .map_err(|_e| net_error::DeserializeError("Failed to parse block height".into()))?;
+        let address = StacksAddress::from_string(&captures["address"])
+            .ok_or_else(|| net_error::DeserializeError("Failed to parse account address".into()))?;
 
-        let amount = u128::from_str(&captures["amount"])
-            .map_err(|_e| net_error::DeserializeError("Failed to parse amount".into()))?;
+        let tip = HttpRequestType::get_chain_tip_query(query);
 
-        Ok(HttpRequestType::GetWithdrawalStx {
-            metadata: HttpRequestMetadata::from_preamble(preamble),
-            withdraw_block_height,
-            sender,
-            withdrawal_id,
-            amount,
-        })
+        Ok(HttpRequestType::GetMempoolAccount(
+            HttpRequestMetadata::from_preamble(preamble),
+            address,
+            tip,
+        ))
     }
 
-    fn parse_get_ft_withdrawal<R: Read>(
+    fn parse_get_account_next_nonce<R: Read>(
         _protocol: &mut StacksHttp,
         preamble: &HttpRequestPreamble,
         captures: &Captures,
-        _query: Option<&str>,
+        query: Option<&str>,
         _fd: &mut R,
     ) -> Result<HttpRequestType, net_error> {
         if preamble.get_content_length() != 0 {
             return Err(net_error::DeserializeError(
-                "Invalid Http request: expected 0-length body for GetFTWithdrawal".to_string(),
+                "Invalid Http request: expected 0-length body for GetAccountNextNonce".to_string(),
             ));
         }
 
-        let sender = PrincipalData::parse(&captures["sender"]).map_err(|_e| {
-            net_error::DeserializeError("Failed to parse account principal".into())
-        })?;
-
-        let withdraw_block_height = u64::from_str(&captures["block_height"])
-            .map_err(|_e| net_error::DeserializeError("Failed to parse block height".into()))?;
+        let address = StacksAddress::from_string(&captures["address"])
+            .ok_or_else(|| net_error::DeserializeError("Failed to parse account address".into()))?;
 
-        let withdrawal_id = u32::from_str(&captures["withdrawal_id"])
-            .map_err(|_e| net_error::DeserializeError("Failed to parse block height".into()))?;
-        let contract_addr =
-            StacksAddress::from_string(&captures["contract_address"]).ok_or_else(|| {
-                net_error::DeserializeError("Failed to parse contract address".into())
-            })?;
-        let contract_name = ContractName::try_from(captures["contract_name"].to_string())
-            .map_err(|_e| net_error::DeserializeError("Failed to parse contract name".into()))?;
-        let amount = u128::from_str(&captures["amount"])
-            .map_err(|_e| net_error::DeserializeError("Failed to parse amount".into()))?;
+        let tip = HttpRequestType::get_chain_tip_query(query);
 
-        Ok(HttpRequestType::GetWithdrawalFt {
-            metadata: HttpRequestMetadata::from_preamble(preamble),
-            withdraw_block_height,
-            sender,
-            withdrawal_id,
-            contract_identifier: QualifiedContractIdentifier::new(
-                contract_addr.into(),
-                contract_name,
-            ),
-            amount,
-        })
+        Ok(HttpRequestType::GetAccountNextNonce(
+            HttpRequestMetadata::from_preamble(preamble),
+            address,
+            tip,
+        ))
     }
 
-    fn parse_get_nft_withdrawal<R: Read>(
+    fn parse_get_contract_publish_policy<R: Read>(
         _protocol: &mut StacksHttp,
         preamble: &HttpRequestPreamble,
-        captures: &Captures,
+        _regex: &Captures,
         _query: Option<&str>,
         _fd: &mut R,
     ) -> Result<HttpRequestType, net_error> {
         if preamble.get_content_length() != 0 {
             return Err(net_error::DeserializeError(
-                "Invalid Http request: expected 0-length body for GetNFTWithdrawal".to_string(),
+                "Invalid Http request: expected 0-length body for GetContractPublishPolicy"
+                    .to_string(),
             ));
         }
 
-        let sender = PrincipalData::parse(&captures["sender"]).map_err(|_e| {
-            net_error::DeserializeError("Failed to parse account principal".into())
-        })?;
-
-        let withdraw_block_height = u64::from_str(&captures["block_height"])
-            .map_err(|_e| net_error::DeserializeError("Failed to parse block height".into()))?;
-
-        let withdrawal_id = u32::from_str(&captures["withdrawal_id"])
-            .map_err(|_e| net_error::DeserializeError("Failed to parse block height".into()))?;
-        let contract_addr =
-            StacksAddress::from_string(&captures["contract_address"]).ok_or_else(|| {
-                net_error::DeserializeError("Failed to parse contract address".into())
-            })?;
-        let contract_name = ContractName::try_from(captures["contract_name"].to_string())
-            .map_err(|_e| net_error::DeserializeError("Failed to parse contract name".into()))?;
-        let id = u128::from_str(&captures["id"])
-            .map_err(|_e| net_error::DeserializeError("Failed to parse amount".into()))?;
+        Ok(HttpRequestType::GetContractPublishPolicy(
+            HttpRequestMetadata::from_preamble(preamble),
+        ))
+    }
 
-        Ok(HttpRequestType::GetWithdrawalNft {
-            metadata: HttpRequestMetadata::from_preamble(preamble),
-            withdraw_block_height,
-            sender,
-            withdrawal_id,
-            contract_identifier: QualifiedContractIdentifier::new(
-                contract_addr.into(),
-                contract_name,
-            ),
-            id,
-        })
+    /// Get the bearer token from a request's `Authorization` header, if it has one
+    fn get_bearer_token(preamble: &HttpRequestPreamble) -> Option<String> {
+        preamble
+            .headers
+            .get("authorization")
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .map(|token| token.trim().to_string())
     }
 
-    fn parse_get_data_var<R: Read>(
+    fn parse_post_miner_pause<R: Read>(
         _protocol: &mut StacksHttp,
         preamble: &HttpRequestPreamble,
-        captures: &Captures,
-        query: Option<&str>,
+        _regex: &Captures,
+        _query: Option<&str>,
         _fd: &mut R,
     ) -> Result<HttpRequestType, net_error> {
-        let content_len = preamble.get_content_length();
-        if content_len != 0 {
-            return Err(net_error::DeserializeError(format!(
-                "Invalid Http request: invalid body length for GetDataVar ({})",
-                content_len
-            )));
+        if preamble.get_content_length() != 0 {
+            return Err(net_error::DeserializeError(
+                "Invalid Http request: expected 0-length body for MinerPause".to_string(),
+            ));
         }
 
-        let contract_addr = StacksAddress::from_string(&captures["address"]).ok_or_else(|| {
-            net_error::DeserializeError("Failed to parse contract address".into())
-        })?;
-        let contract_name = ContractName::try_from(captures["contract"].to_string())
-            .map_err(|_e| net_error::DeserializeError("Failed to parse contract name".into()))?;
-        let var_name = ClarityName::try_from(captures["varname"].to_string())
-            .map_err(|_e| net_error::DeserializeError("Failed to parse data var name".into()))?;
-
-        let with_proof = HttpRequestType::get_proof_query(query);
-        let tip = HttpRequestType::get_chain_tip_query(query);
-
-        Ok(HttpRequestType::GetDataVar(
+        Ok(HttpRequestType::SetMinerPaused(
             HttpRequestMetadata::from_preamble(preamble),
-            contract_addr,
-            contract_name,
-            var_name,
-            tip,
-            with_proof,
+            true,
+            HttpRequestType::get_bearer_token(preamble),
         ))
     }
 
-    fn parse_get_map_entry<R: Read>(
+    fn parse_post_miner_resume<R: Read>(
         _protocol: &mut StacksHttp,
         preamble: &HttpRequestPreamble,
-        captures: &Captures,
-        query: Option<&str>,
-        fd: &mut R,
+        _regex: &Captures,
+        _query: Option<&str>,
+        _fd: &mut R,
     ) -> Result<HttpRequestType, net_error> {
-        let content_len = preamble.get_content_length();
-        if !(content_len > 0 && content_len < (BOUND_VALUE_SERIALIZATION_HEX)) {
-            return Err(net_error::DeserializeError(format!(
-                "Invalid Http request: invalid body length for GetMapEntry ({})",
-                content_len
-            )));
-        }
-
-        if preamble.content_type != Some(HttpContentType::JSON) {
+        if preamble.get_content_length() != 0 {
             return Err(net_error::DeserializeError(
-                "Invalid content-type: expected application/json".into(),
+                "Invalid Http request: expected 0-length body for MinerResume".to_string(),
             ));
         }
 
-        let contract_addr = StacksAddress::from_string(&captures["address"]).ok_or_else(|| {
-            net_error::DeserializeError("Failed to parse contract address".into())
-        })?;
-        let contract_name = ContractName::try_from(captures["contract"].to_string())
-            .map_err(|_e| net_error::DeserializeError("Failed to parse contract name".into()))?;
-        let map_name = ClarityName::try_from(captures["map"].to_string())
-            .map_err(|_e| net_error::DeserializeError("Failed to parse map name".into()))?;
+        Ok(HttpRequestType::SetMinerPaused(
+            HttpRequestMetadata::from_preamble(preamble),
+            false,
+            HttpRequestType::get_bearer_token(preamble),
+        ))
+    }
 
-        let value_hex: String = serde_json::from_reader(fd)
-            .map_err(|_e| net_error::DeserializeError("Failed to parse JSON body".into()))?;
+    fn parse_post_miner_rotate_key<R: Read>(
+        _protocol: &mut StacksHttp,
+        preamble: &HttpRequestPreamble,
+        _regex: &Captures,
+        _query: Option<&str>,
+        _fd: &mut R,
+    ) -> Result<HttpRequestType, net_error> {
+        if preamble.get_content_length() != 0 {
+            return Err(net_error::DeserializeError(
+                "Invalid Http request: expected 0-length body for RotateMiningKey".to_string(),
+            ));
+        }
 
-        let value = Value::try_deserialize_hex_untyped(&value_hex)
-            .map_err(|_e| net_error::DeserializeError("Failed to deserialize key value".into()))?;
+        Ok(HttpRequestType::RotateMiningKey(
+            HttpRequestMetadata::from_preamble(preamble),
+            HttpRequestType::get_bearer_token(preamble),
+        ))
+    }
 
-        let with_proof = HttpRequestType::get_proof_query(query);
-        let tip = HttpRequestType::get_chain_tip_query(query);
+    fn parse_get_mempool_filters<R: Read>(
+        _protocol: &mut StacksHttp,
+        preamble: &HttpRequestPreamble,
+        _regex: &Captures,
+        _query: Option<&str>,
+        _fd: &mut R,
+    ) -> Result<HttpRequestType, net_error> {
+        if preamble.get_content_length() != 0 {
+            return Err(net_error::DeserializeError(
+                "Invalid Http request: expected 0-length body for GetMemPoolFilters".to_string(),
+            ));
+        }
 
-        Ok(HttpRequestType::GetMapEntry(
+        Ok(HttpRequestType::GetMemPoolFilters(
             HttpRequestMetadata::from_preamble(preamble),
-            contract_addr,
-            contract_name,
-            map_name,
-            value,
-            tip,
-            with_proof,
+            HttpRequestType::get_bearer_token(preamble),
         ))
     }
 
-    fn parse_call_read_only<R: Read>(
-        protocol: &mut StacksHttp,
+    fn parse_post_mempool_filter<R: Read>(
+        _protocol: &mut StacksHttp,
         preamble: &HttpRequestPreamble,
-        captures: &Captures,
-        query: Option<&str>,
+        _regex: &Captures,
+        _query: Option<&str>,
         fd: &mut R,
     ) -> Result<HttpRequestType, net_error> {
         let content_len = preamble.get_content_length();
-        if !(content_len > 0 && content_len < protocol.maximum_call_argument_size) {
+        if !(content_len > 0 && content_len < MAX_PAYLOAD_LEN) {
             return Err(net_error::DeserializeError(format!(
-                "Invalid Http request: invalid body length for CallReadOnly ({})",
+                "Invalid Http request: invalid body length for InstallMemPoolFilter ({})",
                 content_len
             )));
         }
@@ -2065,165 +2338,173 @@ impl HttpRequestType {
             ));
         }
 
-        let contract_addr = StacksAddress::from_string(&captures["address"]).ok_or_else(|| {
-            net_error::DeserializeError("Failed to parse contract address".into())
-        })?;
-        let contract_name = ContractName::try_from(captures["contract"].to_string())
-            .map_err(|_e| net_error::DeserializeError("Failed to parse contract name".into()))?;
-        let func_name = ClarityName::try_from(captures["function"].to_string())
-            .map_err(|_e| net_error::DeserializeError("Failed to parse contract name".into()))?;
-
-        let body: CallReadOnlyRequestBody = serde_json::from_reader(fd)
-            .map_err(|_e| net_error::DeserializeError("Failed to parse JSON body".into()))?;
-
-        let sender = PrincipalData::parse(&body.sender)
-            .map_err(|_e| net_error::DeserializeError("Failed to parse sender principal".into()))?;
-
-        let arguments = body
-            .arguments
-            .into_iter()
-            .map(|hex| Value::try_deserialize_hex_untyped(&hex).ok())
-            .collect::<Option<Vec<Value>>>()
-            .ok_or_else(|| {
-                net_error::DeserializeError("Failed to deserialize argument value".into())
+        let bound_fd = BoundReader::from_reader(fd, content_len as u64);
+        let mut body: MemPoolFilterRequestBody =
+            serde_json::from_reader(bound_fd).map_err(|e| {
+                net_error::DeserializeError(format!("Failed to parse JSON body: {}", e))
             })?;
 
-        let tip = HttpRequestType::get_chain_tip_query(query);
+        body.value = body
+            .kind
+            .parse_value(&body.value)
+            .map_err(net_error::DeserializeError)?;
+        if body.ttl_secs == 0 || body.ttl_secs > MAX_FILTER_TTL_SECS {
+            return Err(net_error::DeserializeError(format!(
+                "Invalid ttl_secs: must be between 1 and {}",
+                MAX_FILTER_TTL_SECS
+            )));
+        }
 
-        Ok(HttpRequestType::CallReadOnlyFunction(
+        Ok(HttpRequestType::InstallMemPoolFilter(
             HttpRequestMetadata::from_preamble(preamble),
-            contract_addr,
-            contract_name,
-            sender,
-            func_name,
-            arguments,
-            tip,
+            body,
+            HttpRequestType::get_bearer_token(preamble),
         ))
     }
 
-    fn parse_block_proposal<R: Read>(
+    fn parse_delete_mempool_filter<R: Read>(
         _protocol: &mut StacksHttp,
         preamble: &HttpRequestPreamble,
-        _captures: &Captures,
+        captures: &Captures,
         _query: Option<&str>,
-        fd: &mut R,
+        _fd: &mut R,
     ) -> Result<HttpRequestType, net_error> {
-        let content_len = preamble.get_content_length();
-        if !(content_len > 0 && content_len < MAX_BLOCK_PROPOSAL_LENGTH) {
-            return Err(net_error::DeserializeError(format!(
-                "Invalid Http request: invalid body length for BlockProposal ({})",
-                content_len
-            )));
-        }
-
-        if preamble.content_type != Some(HttpContentType::JSON) {
+        if preamble.get_content_length() != 0 {
             return Err(net_error::DeserializeError(
-                "Invalid content-type: expected application/json".to_string(),
+                "Invalid Http request: expected 0-length body for RemoveMemPoolFilter".to_string(),
             ));
         }
 
-        let block_proposal: SignedProposal = serde_json::from_reader(fd).map_err(|_e| {
-            net_error::DeserializeError("Failed to parse block proposal JSON body".into())
-        })?;
+        let filter_id = captures["filter_id"]
+            .parse::<u64>()
+            .map_err(|_e| net_error::DeserializeError("Invalid filter ID".to_string()))?;
 
-        Ok(HttpRequestType::BlockProposal(
+        Ok(HttpRequestType::RemoveMemPoolFilter(
             HttpRequestMetadata::from_preamble(preamble),
-            block_proposal,
+            filter_id,
+            HttpRequestType::get_bearer_token(preamble),
         ))
     }
 
-    fn parse_get_contract_arguments(
+    fn parse_get_stx_withdrawal<R: Read>(
+        _protocol: &mut StacksHttp,
         preamble: &HttpRequestPreamble,
         captures: &Captures,
-    ) -> Result<(HttpRequestMetadata, StacksAddress, ContractName), net_error> {
+        _query: Option<&str>,
+        _fd: &mut R,
+    ) -> Result<HttpRequestType, net_error> {
         if preamble.get_content_length() != 0 {
             return Err(net_error::DeserializeError(
-                "Invalid Http request: expected 0-length body".to_string(),
+                "Invalid Http request: expected 0-length body for GetAccount".to_string(),
             ));
         }
 
-        let contract_addr = StacksAddress::from_string(&captures["address"]).ok_or_else(|| {
-            net_error::DeserializeError("Failed to parse contract address".into())
+        let sender = PrincipalData::parse(&captures["sender"]).map_err(|_e| {
+            net_error::DeserializeError("Failed to parse account principal".into())
         })?;
-        let contract_name = ContractName::try_from(captures["contract"].to_string())
-            .map_err(|_e| net_error::DeserializeError("Failed to parse contract name".into()))?;
 
-        Ok((
-            HttpRequestMetadata::from_preamble(preamble),
-            contract_addr,
-            contract_name,
-        ))
+        let withdraw_block_height = u64::from_str(&captures["block_height"])
+            .map_err(|_e| net_error::DeserializeError("Failed to parse block height".into()))?;
+
+        let withdrawal_id = u32::from_str(&captures["withdrawal_id"])
+            .map_err(|_e| net_error::DeserializeError("Failed to parse block height".into()))?;
+
+        let amount = u128::from_str(&captures["amount"])
+            .map_err(|_e| net_error::DeserializeError("Failed to parse amount".into()))?;
+
+        Ok(HttpRequestType::GetWithdrawalStx {
+            metadata: HttpRequestMetadata::from_preamble(preamble),
+            withdraw_block_height,
+            sender,
+            withdrawal_id,
+            amount,
+        })
     }
 
-    fn parse_get_contract_abi<R: Read>(
+    fn parse_get_withdrawals_by_sender<R: Read>(
         _protocol: &mut StacksHttp,
         preamble: &HttpRequestPreamble,
         captures: &Captures,
         query: Option<&str>,
         _fd: &mut R,
     ) -> Result<HttpRequestType, net_error> {
-        let tip = HttpRequestType::get_chain_tip_query(query);
-        HttpRequestType::parse_get_contract_arguments(preamble, captures).map(
-            |(preamble, addr, name)| HttpRequestType::GetContractABI(preamble, addr, name, tip),
-        )
+        if preamble.get_content_length() != 0 {
+            return Err(net_error::DeserializeError(
+                "Invalid Http request: expected 0-length body for GetWithdrawalsBySender"
+                    .to_string(),
+            ));
+        }
+
+        let sender = PrincipalData::parse(&captures["sender"])
+            .map_err(|_e| net_error::DeserializeError("Failed to parse sender".into()))?;
+        let offset = HttpRequestType::get_u64_query(query, "offset").unwrap_or(0);
+        let limit = HttpRequestType::get_u64_query(query, "limit")
+            .unwrap_or(MAX_WITHDRAWALS_PAGE_SIZE)
+            .clamp(1, MAX_WITHDRAWALS_PAGE_SIZE);
+
+        Ok(HttpRequestType::GetWithdrawalsBySender {
+            metadata: HttpRequestMetadata::from_preamble(preamble),
+            sender,
+            offset,
+            limit,
+        })
     }
 
-    fn parse_get_contract_source<R: Read>(
+    fn parse_get_withdrawal_claim<R: Read>(
         _protocol: &mut StacksHttp,
         preamble: &HttpRequestPreamble,
         captures: &Captures,
-        query: Option<&str>,
+        _query: Option<&str>,
         _fd: &mut R,
     ) -> Result<HttpRequestType, net_error> {
-        let with_proof = HttpRequestType::get_proof_query(query);
-        let tip = HttpRequestType::get_chain_tip_query(query);
-        HttpRequestType::parse_get_contract_arguments(preamble, captures).map(
-            |(preamble, addr, name)| {
-                HttpRequestType::GetContractSrc(preamble, addr, name, tip, with_proof)
-            },
-        )
+        if preamble.get_content_length() != 0 {
+            return Err(net_error::DeserializeError(
+                "Invalid Http request: expected 0-length body for GetWithdrawalClaim".to_string(),
+            ));
+        }
+
+        let block_height = u64::from_str(&captures["block_height"])
+            .map_err(|_e| net_error::DeserializeError("Failed to parse block height".into()))?;
+        let withdrawal_id = u32::from_str(&captures["withdrawal_id"])
+            .map_err(|_e| net_error::DeserializeError("Failed to parse withdrawal ID".into()))?;
+
+        Ok(HttpRequestType::GetWithdrawalClaim {
+            metadata: HttpRequestMetadata::from_preamble(preamble),
+            block_height,
+            withdrawal_id,
+        })
     }
 
-    fn parse_get_is_trait_implemented<R: Read>(
+    fn parse_get_failed_deposits_by_sender<R: Read>(
         _protocol: &mut StacksHttp,
         preamble: &HttpRequestPreamble,
         captures: &Captures,
         query: Option<&str>,
         _fd: &mut R,
     ) -> Result<HttpRequestType, net_error> {
-        let tip = HttpRequestType::get_chain_tip_query(query);
         if preamble.get_content_length() != 0 {
             return Err(net_error::DeserializeError(
-                "Invalid Http request: expected 0-length body".to_string(),
+                "Invalid Http request: expected 0-length body for GetFailedDepositsBySender"
+                    .to_string(),
             ));
         }
 
-        let contract_addr = StacksAddress::from_string(&captures["address"]).ok_or_else(|| {
-            net_error::DeserializeError("Failed to parse contract address".into())
-        })?;
-        let contract_name = ContractName::try_from(captures["contract"].to_string())
-            .map_err(|_e| net_error::DeserializeError("Failed to parse contract name".into()))?;
-        let trait_name = ClarityName::try_from(captures["traitName"].to_string())
-            .map_err(|_e| net_error::DeserializeError("Failed to parse trait name".into()))?;
-        let trait_contract_addr = StacksAddress::from_string(&captures["traitContractAddr"])
-            .ok_or_else(|| net_error::DeserializeError("Failed to parse contract address".into()))?
-            .into();
-        let trait_contract_name = ContractName::try_from(captures["traitContractName"].to_string())
-            .map_err(|_e| {
-                net_error::DeserializeError("Failed to parse trait contract name".into())
-            })?;
-        let trait_id = TraitIdentifier::new(trait_contract_addr, trait_contract_name, trait_name);
+        let sender = PrincipalData::parse(&captures["sender"])
+            .map_err(|_e| net_error::DeserializeError("Failed to parse sender".into()))?;
+        let offset = HttpRequestType::get_u64_query(query, "offset").unwrap_or(0);
+        let limit = HttpRequestType::get_u64_query(query, "limit")
+            .unwrap_or(MAX_FAILED_DEPOSITS_PAGE_SIZE)
+            .clamp(1, MAX_FAILED_DEPOSITS_PAGE_SIZE);
 
-        Ok(HttpRequestType::GetIsTraitImplemented(
-            HttpRequestMetadata::from_preamble(preamble),
-            contract_addr,
-            contract_name,
-            trait_id,
-            tip,
-        ))
+        Ok(HttpRequestType::GetFailedDepositsBySender {
+            metadata: HttpRequestMetadata::from_preamble(preamble),
+            sender,
+            offset,
+            limit,
+        })
     }
 
-    fn parse_getheaders<R: Read>(
+    fn parse_get_account_transactions<R: Read>(
         _protocol: &mut StacksHttp,
         preamble: &HttpRequestPreamble,
         captures: &Captures,
@@ -2232,464 +2513,417 @@ impl HttpRequestType {
     ) -> Result<HttpRequestType, net_error> {
         if preamble.get_content_length() != 0 {
             return Err(net_error::DeserializeError(
-                "Invalid Http request: expected 0-length body for GetBlock".to_string(),
+                "Invalid Http request: expected 0-length body for GetAccountTransactions"
+                    .to_string(),
             ));
         }
 
-        let quantity_str = captures
-            .get(1)
-            .ok_or(net_error::DeserializeError(
-                "Failed to match path to reward cycle group".to_string(),
-            ))?
-            .as_str();
+        let principal = PrincipalData::parse(&captures["principal"])
+            .map_err(|_e| net_error::DeserializeError("Failed to parse principal".into()))?;
+        let offset = HttpRequestType::get_u64_query(query, "offset").unwrap_or(0);
+        let limit = HttpRequestType::get_u64_query(query, "limit")
+            .unwrap_or(MAX_ACCOUNT_TRANSACTIONS_PAGE_SIZE)
+            .clamp(1, MAX_ACCOUNT_TRANSACTIONS_PAGE_SIZE);
 
-        let quantity: u64 = quantity_str
-            .parse()
-            .map_err(|_| net_error::DeserializeError("Failed to parse reward cycle".to_string()))?;
-
-        let tip = HttpRequestType::get_chain_tip_query(query);
-
-        Ok(HttpRequestType::GetHeaders(
-            HttpRequestMetadata::from_preamble(preamble),
-            quantity,
-            tip,
-        ))
+        Ok(HttpRequestType::GetAccountTransactions {
+            metadata: HttpRequestMetadata::from_preamble(preamble),
+            principal,
+            offset,
+            limit,
+        })
     }
 
-    fn parse_getblock<R: Read>(
+    fn parse_get_burn_ops<R: Read>(
         _protocol: &mut StacksHttp,
         preamble: &HttpRequestPreamble,
-        captures: &Captures,
-        _query: Option<&str>,
+        _captures: &Captures,
+        query: Option<&str>,
         _fd: &mut R,
     ) -> Result<HttpRequestType, net_error> {
         if preamble.get_content_length() != 0 {
             return Err(net_error::DeserializeError(
-                "Invalid Http request: expected 0-length body for GetBlock".to_string(),
+                "Invalid Http request: expected 0-length body for GetBurnOps".to_string(),
             ));
         }
 
-        let block_hash_str = captures
-            .get(1)
-            .ok_or(net_error::DeserializeError(
-                "Failed to match path to block hash group".to_string(),
-            ))?
-            .as_str();
-
-        let block_hash = StacksBlockId::from_hex(block_hash_str)
-            .map_err(|_e| net_error::DeserializeError("Failed to parse block hash".to_string()))?;
+        let burn_height =
+            HttpRequestType::get_u64_query(query, "burn_height").ok_or_else(|| {
+                net_error::DeserializeError("Missing or invalid burn_height".to_string())
+            })?;
 
-        Ok(HttpRequestType::GetBlock(
-            HttpRequestMetadata::from_preamble(preamble),
-            block_hash,
-        ))
+        Ok(HttpRequestType::GetBurnOps {
+            metadata: HttpRequestMetadata::from_preamble(preamble),
+            burn_height,
+        })
     }
 
-    fn parse_getmicroblocks_indexed<R: Read>(
+    fn parse_get_forks<R: Read>(
         _protocol: &mut StacksHttp,
         preamble: &HttpRequestPreamble,
-        captures: &Captures,
-        _query: Option<&str>,
+        _captures: &Captures,
+        query: Option<&str>,
         _fd: &mut R,
     ) -> Result<HttpRequestType, net_error> {
         if preamble.get_content_length() != 0 {
             return Err(net_error::DeserializeError(
-                "Invalid Http request: expected 0-length body for GetMicroblocksIndexed"
-                    .to_string(),
+                "Invalid Http request: expected 0-length body for GetForks".to_string(),
             ));
         }
 
-        let block_hash_str = captures
-            .get(1)
-            .ok_or(net_error::DeserializeError(
-                "Failed to match path to microblock hash group".to_string(),
-            ))?
-            .as_str();
-
-        let block_hash = StacksBlockId::from_hex(block_hash_str).map_err(|_e| {
-            net_error::DeserializeError("Failed to parse microblock hash".to_string())
-        })?;
+        let burn_blocks = HttpRequestType::get_u64_query(query, "burn_blocks")
+            .unwrap_or(DEFAULT_FORK_TREE_BURN_BLOCKS)
+            .clamp(1, MAX_FORK_TREE_BURN_BLOCKS);
 
-        Ok(HttpRequestType::GetMicroblocksIndexed(
-            HttpRequestMetadata::from_preamble(preamble),
-            block_hash,
-        ))
+        Ok(HttpRequestType::GetForks {
+            metadata: HttpRequestMetadata::from_preamble(preamble),
+            burn_blocks,
+        })
     }
 
-    fn parse_getmicroblocks_confirmed<R: Read>(
+    fn parse_get_contract_costs<R: Read>(
         _protocol: &mut StacksHttp,
         preamble: &HttpRequestPreamble,
-        captures: &Captures,
-        _query: Option<&str>,
+        _captures: &Captures,
+        query: Option<&str>,
         _fd: &mut R,
     ) -> Result<HttpRequestType, net_error> {
         if preamble.get_content_length() != 0 {
             return Err(net_error::DeserializeError(
-                "Invalid Http request: expected 0-length body for GetMicrolocks".to_string(),
+                "Invalid Http request: expected 0-length body for GetContractCosts".to_string(),
             ));
         }
 
-        let block_hash_str = captures
-            .get(1)
-            .ok_or(net_error::DeserializeError(
-                "Failed to match path to microblock hash group".to_string(),
-            ))?
-            .as_str();
-
-        let block_hash = StacksBlockId::from_hex(block_hash_str).map_err(|_e| {
-            net_error::DeserializeError("Failed to parse microblock hash".to_string())
-        })?;
+        let blocks = HttpRequestType::get_u64_query(query, "blocks")
+            .unwrap_or(DEFAULT_CONTRACT_COSTS_BLOCKS)
+            .clamp(1, CONTRACT_COSTS_HISTORY_BLOCKS);
 
-        Ok(HttpRequestType::GetMicroblocksConfirmed(
-            HttpRequestMetadata::from_preamble(preamble),
-            block_hash,
-        ))
+        Ok(HttpRequestType::GetContractCosts {
+            metadata: HttpRequestMetadata::from_preamble(preamble),
+            blocks,
+        })
     }
 
-    fn parse_getmicroblocks_unconfirmed<R: Read>(
+    fn parse_get_block_commits<R: Read>(
         _protocol: &mut StacksHttp,
         preamble: &HttpRequestPreamble,
-        captures: &Captures,
-        _query: Option<&str>,
+        _captures: &Captures,
+        query: Option<&str>,
         _fd: &mut R,
     ) -> Result<HttpRequestType, net_error> {
         if preamble.get_content_length() != 0 {
             return Err(net_error::DeserializeError(
-                "Invalid Http request: expected 0-length body for GetMicrolocksUnconfirmed"
-                    .to_string(),
+                "Invalid Http request: expected 0-length body for GetBlockCommits".to_string(),
             ));
         }
 
-        let block_hash_str = captures
-            .get(1)
-            .ok_or(net_error::DeserializeError(
-                "Failed to match path to microblock hash group".to_string(),
-            ))?
-            .as_str();
+        let l2_height = HttpRequestType::get_u64_query(query, "l2_height").ok_or_else(|| {
+            net_error::DeserializeError("Missing or invalid l2_height".to_string())
+        })?;
+        let count = HttpRequestType::get_u64_query(query, "count")
+            .unwrap_or(DEFAULT_BLOCK_COMMITS)
+            .clamp(1, MAX_BLOCK_COMMITS);
 
-        let min_seq_str = captures
-            .get(2)
-            .ok_or(net_error::DeserializeError(
-                "Failed to match path to microblock minimum sequence group".to_string(),
-            ))?
-            .as_str();
+        Ok(HttpRequestType::GetBlockCommits {
+            metadata: HttpRequestMetadata::from_preamble(preamble),
+            l2_height,
+            count,
+        })
+    }
 
-        let block_hash = StacksBlockId::from_hex(block_hash_str).map_err(|_e| {
-            net_error::DeserializeError("Failed to parse microblock hash".to_string())
-        })?;
+    /// Parse a GET for a rewards report.  A range of more than `MAX_REWARDS_REPORT_BLOCKS`
+    /// heights is cut short.
+    fn parse_get_rewards_report<R: Read>(
+        _protocol: &mut StacksHttp,
+        preamble: &HttpRequestPreamble,
+        _captures: &Captures,
+        query: Option<&str>,
+        _fd: &mut R,
+    ) -> Result<HttpRequestType, net_error> {
+        if preamble.get_content_length() != 0 {
+            return Err(net_error::DeserializeError(
+                "Invalid Http request: expected 0-length body for GetRewardsReport".to_string(),
+            ));
+        }
 
-        let min_seq = min_seq_str.parse::<u16>().map_err(|_e| {
-            net_error::DeserializeError("Failed to parse microblock minimum sequence".to_string())
+        let from_height =
+            HttpRequestType::get_u64_query(query, "from_height").ok_or_else(|| {
+                net_error::DeserializeError("Missing or invalid from_height".to_string())
+            })?;
+        let to_height = HttpRequestType::get_u64_query(query, "to_height").ok_or_else(|| {
+            net_error::DeserializeError("Missing or invalid to_height".to_string())
         })?;
+        if to_height < from_height {
+            return Err(net_error::DeserializeError(
+                "Invalid Http request: to_height is below from_height".to_string(),
+            ));
+        }
+        let to_height = to_height.min(from_height.saturating_add(MAX_REWARDS_REPORT_BLOCKS - 1));
 
-        Ok(HttpRequestType::GetMicroblocksUnconfirmed(
-            HttpRequestMetadata::from_preamble(preamble),
-            block_hash,
-            min_seq,
-        ))
+        Ok(HttpRequestType::GetRewardsReport {
+            metadata: HttpRequestMetadata::from_preamble(preamble),
+            from_height,
+            to_height,
+        })
     }
 
-    fn parse_gettransaction_unconfirmed<R: Read>(
+    /// Parse a GET for an event replay.  A range of more than `MAX_EVENT_REPLAY_BLOCKS` heights
+    /// is cut short.
+    fn parse_get_event_replay<R: Read>(
         _protocol: &mut StacksHttp,
         preamble: &HttpRequestPreamble,
-        regex: &Captures,
-        _query: Option<&str>,
+        _captures: &Captures,
+        query: Option<&str>,
         _fd: &mut R,
     ) -> Result<HttpRequestType, net_error> {
         if preamble.get_content_length() != 0 {
             return Err(net_error::DeserializeError(
-                "Invalid Http request: expected 0-length body for GetMicrolocksUnconfirmed"
-                    .to_string(),
+                "Invalid Http request: expected 0-length body for GetEventReplay".to_string(),
             ));
         }
 
-        let txid_hex = regex
-            .get(1)
-            .ok_or(net_error::DeserializeError(
-                "Failed to match path to txid group".to_string(),
-            ))?
-            .as_str();
-
-        if txid_hex.len() != 64 {
+        let from_height =
+            HttpRequestType::get_u64_query(query, "from_height").ok_or_else(|| {
+                net_error::DeserializeError("Missing or invalid from_height".to_string())
+            })?;
+        let to_height = HttpRequestType::get_u64_query(query, "to_height").ok_or_else(|| {
+            net_error::DeserializeError("Missing or invalid to_height".to_string())
+        })?;
+        if to_height < from_height {
             return Err(net_error::DeserializeError(
-                "Invalid txid: expected 64 bytes".to_string(),
+                "Invalid Http request: to_height is below from_height".to_string(),
+            ));
+        }
+        let to_height = to_height.min(from_height.saturating_add(MAX_EVENT_REPLAY_BLOCKS - 1));
+        let keys = HttpRequestType::get_event_keys_query(query);
+        if keys.is_empty() {
+            return Err(net_error::DeserializeError(
+                "Invalid Http request: no event keys".to_string(),
             ));
         }
 
-        let txid = Txid::from_hex(&txid_hex)
-            .map_err(|_e| net_error::DeserializeError("Failed to decode txid hex".to_string()))?;
+        Ok(HttpRequestType::GetEventReplay {
+            metadata: HttpRequestMetadata::from_preamble(preamble),
+            from_height,
+            to_height,
+            keys,
+        })
+    }
 
-        Ok(HttpRequestType::GetTransactionUnconfirmed(
+    fn parse_get_subnet_info<R: Read>(
+        _protocol: &mut StacksHttp,
+        preamble: &HttpRequestPreamble,
+        _captures: &Captures,
+        _query: Option<&str>,
+        _fd: &mut R,
+    ) -> Result<HttpRequestType, net_error> {
+        if preamble.get_content_length() != 0 {
+            return Err(net_error::DeserializeError(
+                "Invalid Http request: expected 0-length body for GetSubnetInfo".to_string(),
+            ));
+        }
+
+        Ok(HttpRequestType::GetSubnetInfo(
             HttpRequestMetadata::from_preamble(preamble),
-            txid,
         ))
     }
 
-    fn parse_post_fee_rate_estimate<R: Read>(
+    fn parse_get_microblock_stream<R: Read>(
         _protocol: &mut StacksHttp,
         preamble: &HttpRequestPreamble,
-        _regex: &Captures,
+        captures: &Captures,
         _query: Option<&str>,
-        fd: &mut R,
+        _fd: &mut R,
     ) -> Result<HttpRequestType, net_error> {
-        let content_len = preamble.get_content_length();
-        if !(content_len > 0 && content_len < MAX_PAYLOAD_LEN) {
-            return Err(net_error::DeserializeError(format!(
-                "Invalid Http request: invalid body length for FeeRateEstimate ({})",
-                content_len
-            )));
-        }
-
-        if preamble.content_type != Some(HttpContentType::JSON) {
+        if preamble.get_content_length() != 0 {
             return Err(net_error::DeserializeError(
-                "Invalid content-type: expected application/json".to_string(),
+                "Invalid Http request: expected 0-length body for GetMicroblockStream".to_string(),
             ));
         }
 
-        let bound_fd = BoundReader::from_reader(fd, content_len as u64);
-
-        let body: FeeRateEstimateRequestBody = serde_json::from_reader(bound_fd).map_err(|e| {
-            net_error::DeserializeError(format!("Failed to parse JSON body: {}", e))
-        })?;
-
-        let payload_hex = if body.transaction_payload.starts_with("0x") {
-            &body.transaction_payload[2..]
-        } else {
-            &body.transaction_payload
-        };
-
-        let payload_data = hex_bytes(payload_hex).map_err(|_e| {
-            net_error::DeserializeError("Bad hex string supplied for transaction payload".into())
-        })?;
-
-        let payload = TransactionPayload::consensus_deserialize(&mut payload_data.as_slice())
-            .map_err(|e| {
-                net_error::DeserializeError(format!(
-                    "Failed to deserialize transaction payload: {}",
-                    e
-                ))
+        let parent_block_id = captures
+            .name("parent_block_id")
+            .and_then(|hex| StacksBlockId::from_hex(hex.as_str()).ok())
+            .ok_or_else(|| {
+                net_error::DeserializeError("Failed to parse anchored block hash".to_string())
             })?;
 
-        let estimated_len =
-            std::cmp::max(body.estimated_len.unwrap_or(0), payload_data.len() as u64);
+        Ok(HttpRequestType::GetMicroblockStream {
+            metadata: HttpRequestMetadata::from_preamble(preamble),
+            parent_block_id,
+        })
+    }
 
-        Ok(HttpRequestType::FeeRateEstimate(
+    fn parse_get_health_live<R: Read>(
+        _protocol: &mut StacksHttp,
+        preamble: &HttpRequestPreamble,
+        _captures: &Captures,
+        _query: Option<&str>,
+        _fd: &mut R,
+    ) -> Result<HttpRequestType, net_error> {
+        if preamble.get_content_length() != 0 {
+            return Err(net_error::DeserializeError(
+                "Invalid Http request: expected 0-length body for GetHealthLive".to_string(),
+            ));
+        }
+
+        Ok(HttpRequestType::GetHealthLive(
             HttpRequestMetadata::from_preamble(preamble),
-            payload,
-            estimated_len,
         ))
     }
 
-    fn parse_posttransaction<R: Read>(
+    fn parse_get_health_ready<R: Read>(
         _protocol: &mut StacksHttp,
         preamble: &HttpRequestPreamble,
-        _regex: &Captures,
+        _captures: &Captures,
         _query: Option<&str>,
-        fd: &mut R,
+        _fd: &mut R,
     ) -> Result<HttpRequestType, net_error> {
-        if preamble.get_content_length() == 0 {
+        if preamble.get_content_length() != 0 {
             return Err(net_error::DeserializeError(
-                "Invalid Http request: expected non-zero-length body for PostTransaction"
-                    .to_string(),
+                "Invalid Http request: expected 0-length body for GetHealthReady".to_string(),
             ));
         }
 
-        if preamble.get_content_length() > MAX_PAYLOAD_LEN {
+        Ok(HttpRequestType::GetHealthReady(
+            HttpRequestMetadata::from_preamble(preamble),
+        ))
+    }
+
+    fn parse_get_mempool_preview<R: Read>(
+        _protocol: &mut StacksHttp,
+        preamble: &HttpRequestPreamble,
+        _captures: &Captures,
+        query: Option<&str>,
+        _fd: &mut R,
+    ) -> Result<HttpRequestType, net_error> {
+        if preamble.get_content_length() != 0 {
             return Err(net_error::DeserializeError(
-                "Invalid Http request: PostTransaction body is too big".to_string(),
+                "Invalid Http request: expected 0-length body for GetMempoolPreview".to_string(),
             ));
         }
 
-        let mut bound_fd = BoundReader::from_reader(fd, preamble.get_content_length() as u64);
+        let max_results = HttpRequestType::get_u64_query(query, "max_results")
+            .unwrap_or(DEFAULT_MEMPOOL_PREVIEW_RESULTS)
+            .clamp(1, MAX_MEMPOOL_PREVIEW_RESULTS);
 
-        match preamble.content_type {
-            None => {
-                return Err(net_error::DeserializeError(
-                    "Missing Content-Type for transaction".to_string(),
-                ));
-            }
-            Some(HttpContentType::Bytes) => {
-                HttpRequestType::parse_posttransaction_octets(preamble, &mut bound_fd)
-            }
-            Some(HttpContentType::JSON) => {
-                HttpRequestType::parse_posttransaction_json(preamble, &mut bound_fd)
-            }
-            _ => {
-                return Err(net_error::DeserializeError(
-                    "Wrong Content-Type for transaction; expected application/json".to_string(),
-                ));
-            }
-        }
-    }
-
-    fn parse_posttransaction_octets<R: Read>(
-        preamble: &HttpRequestPreamble,
-        fd: &mut R,
-    ) -> Result<HttpRequestType, net_error> {
-        let tx = StacksTransaction::consensus_deserialize(fd).map_err(|e| {
-            if let codec_error::DeserializeError(msg) = e {
-                net_error::ClientError(ClientError::Message(format!(
-                    "Failed to deserialize posted transaction: {}",
-                    msg
-                )))
-            } else {
-                e.into()
-            }
-        })?;
-        Ok(HttpRequestType::PostTransaction(
-            HttpRequestMetadata::from_preamble(preamble),
-            tx,
-            None,
-        ))
+        Ok(HttpRequestType::GetMempoolPreview {
+            metadata: HttpRequestMetadata::from_preamble(preamble),
+            max_results,
+        })
     }
 
-    fn parse_posttransaction_json<R: Read>(
+    /// Parse a GET for the mempool's rejected transactions, which names either a `txid` or an
+    /// `address` in its query string
+    fn parse_get_rejected_txs<R: Read>(
+        _protocol: &mut StacksHttp,
         preamble: &HttpRequestPreamble,
-        fd: &mut R,
+        _captures: &Captures,
+        query: Option<&str>,
+        _fd: &mut R,
     ) -> Result<HttpRequestType, net_error> {
-        let body: PostTransactionRequestBody = serde_json::from_reader(fd)
-            .map_err(|_e| net_error::DeserializeError("Failed to parse body".into()))?;
-
-        let tx = {
-            let tx_bytes = hex_bytes(&body.tx)
-                .map_err(|_e| net_error::DeserializeError("Failed to parse tx".into()))?;
-            StacksTransaction::consensus_deserialize(&mut &tx_bytes[..]).map_err(|e| {
-                if let codec_error::DeserializeError(msg) = e {
-                    net_error::ClientError(ClientError::Message(format!(
-                        "Failed to deserialize posted transaction: {}",
-                        msg
-                    )))
-                } else {
-                    e.into()
-                }
-            })
-        }?;
+        if preamble.get_content_length() != 0 {
+            return Err(net_error::DeserializeError(
+                "Invalid Http request: expected 0-length body for GetRejectedTxs".to_string(),
+            ));
+        }
 
-        let attachment = match body.attachment {
-            None => None,
-            Some(attachment_content) => {
-                let content = hex_bytes(&attachment_content).map_err(|_e| {
-                    net_error::DeserializeError("Failed to parse attachment".into())
-                })?;
-                Some(Attachment::new(content))
+        let mut txid = None;
+        let mut address = None;
+        for (key, value) in form_urlencoded::parse(query.unwrap_or("").as_bytes()) {
+            if key == "txid" {
+                let txid_hex = value.strip_prefix("0x").unwrap_or(&value);
+                txid = Some(Txid::from_hex(txid_hex).map_err(|_e| {
+                    net_error::DeserializeError(format!("Invalid txid: {}", &value))
+                })?);
+            } else if key == "address" {
+                address = Some(StacksAddress::from_string(&value).ok_or_else(|| {
+                    net_error::DeserializeError(format!("Invalid address: {}", &value))
+                })?);
+            }
+        }
+        let rejected_query = match (txid, address) {
+            (Some(txid), None) => RejectedTxsQuery::Txid(txid),
+            (None, Some(address)) => RejectedTxsQuery::Address(address),
+            _ => {
+                return Err(net_error::DeserializeError(
+                    "Invalid Http request: expected exactly one of txid or address".to_string(),
+                ));
             }
         };
+        let limit = HttpRequestType::get_u64_query(query, "limit")
+            .unwrap_or(DEFAULT_REJECTED_TXS_RESULTS)
+            .clamp(1, MAX_REJECTED_TXS_RESULTS);
 
-        Ok(HttpRequestType::PostTransaction(
-            HttpRequestMetadata::from_preamble(preamble),
-            tx,
-            attachment,
-        ))
+        Ok(HttpRequestType::GetRejectedTxs {
+            metadata: HttpRequestMetadata::from_preamble(preamble),
+            query: rejected_query,
+            limit,
+        })
     }
 
-    fn parse_postblock<R: Read>(
+    fn parse_get_mempool_stats<R: Read>(
         _protocol: &mut StacksHttp,
         preamble: &HttpRequestPreamble,
-        regex: &Captures,
+        _captures: &Captures,
         _query: Option<&str>,
-        fd: &mut R,
+        _fd: &mut R,
     ) -> Result<HttpRequestType, net_error> {
-        if preamble.get_content_length() == 0 {
-            return Err(net_error::DeserializeError(
-                "Invalid Http request: expected non-zero-length body for PostBlock".to_string(),
-            ));
-        }
-
-        if preamble.get_content_length() > MAX_PAYLOAD_LEN {
+        if preamble.get_content_length() != 0 {
             return Err(net_error::DeserializeError(
-                "Invalid Http request: PostBlock body is too big".to_string(),
+                "Invalid Http request: expected 0-length body for GetMempoolStats".to_string(),
             ));
         }
 
-        // content-type must be given, and must be application/octet-stream
-        match preamble.content_type {
-            None => {
-                return Err(net_error::DeserializeError(
-                    "Missing Content-Type for Stacks block".to_string(),
-                ));
-            }
-            Some(ref c) => {
-                if *c != HttpContentType::Bytes {
-                    return Err(net_error::DeserializeError(
-                        "Wrong Content-Type for Stacks block; expected application/octet-stream"
-                            .to_string(),
-                    ));
-                }
-            }
-        };
-
-        let consensus_hash_str = regex
-            .get(1)
-            .ok_or(net_error::DeserializeError(
-                "Failed to match consensus hash in path group".to_string(),
-            ))?
-            .as_str();
-
-        let consensus_hash: ConsensusHash =
-            ConsensusHash::from_hex(consensus_hash_str).map_err(|_| {
-                net_error::DeserializeError("Failed to parse consensus hash".to_string())
-            })?;
-
-        let mut bound_fd = BoundReader::from_reader(fd, preamble.get_content_length() as u64);
-        let stacks_block = StacksBlock::consensus_deserialize(&mut bound_fd)?;
-
-        Ok(HttpRequestType::PostBlock(
+        Ok(HttpRequestType::GetMempoolStats(
             HttpRequestMetadata::from_preamble(preamble),
-            consensus_hash,
-            stacks_block,
         ))
     }
 
-    fn parse_postmicroblock<R: Read>(
+    fn parse_get_ft_withdrawal<R: Read>(
         _protocol: &mut StacksHttp,
         preamble: &HttpRequestPreamble,
-        _regex: &Captures,
-        query: Option<&str>,
-        fd: &mut R,
+        captures: &Captures,
+        _query: Option<&str>,
+        _fd: &mut R,
     ) -> Result<HttpRequestType, net_error> {
-        if preamble.get_content_length() == 0 {
-            return Err(net_error::DeserializeError(
-                "Invalid Http request: expected non-zero-length body for PostMicroblock"
-                    .to_string(),
-            ));
-        }
-
-        if preamble.get_content_length() > MAX_PAYLOAD_LEN {
+        if preamble.get_content_length() != 0 {
             return Err(net_error::DeserializeError(
-                "Invalid Http request: PostMicroblock body is too big".to_string(),
+                "Invalid Http request: expected 0-length body for GetFTWithdrawal".to_string(),
             ));
         }
 
-        // content-type must be given, and must be application/octet-stream
-        match preamble.content_type {
-            None => {
-                return Err(net_error::DeserializeError(
-                    "Missing Content-Type for microblock".to_string(),
-                ));
-            }
-            Some(ref c) => {
-                if *c != HttpContentType::Bytes {
-                    return Err(net_error::DeserializeError(
-                        "Wrong Content-Type for microblock; expected application/octet-stream"
-                            .to_string(),
-                    ));
-                }
-            }
-        };
+        let sender = PrincipalData::parse(&captures["sender"]).map_err(|_e| {
+            net_error::DeserializeError("Failed to parse account principal".into())
+        })?;
 
-        let mut bound_fd = BoundReader::from_reader(fd, preamble.get_content_length() as u64);
+        let withdraw_block_height = u64::from_str(&captures["block_height"])
+            .map_err(|_e| net_error::DeserializeError("Failed to parse block height".into()))?;
 
-        let mb = StacksMicroblock::consensus_deserialize(&mut bound_fd)?;
-        let tip = HttpRequestType::get_chain_tip_query(query);
+        let withdrawal_id = u32::from_str(&captures["withdrawal_id"])
+            .map_err(|_e| net_error::DeserializeError("Failed to parse block height".into()))?;
+        let contract_addr =
+            StacksAddress::from_string(&captures["contract_address"]).ok_or_else(|| {
+                net_error::DeserializeError("Failed to parse contract address".into())
+            })?;
+        let contract_name = ContractName::try_from(captures["contract_name"].to_string())
+            .map_err(|_e| net_error::DeserializeError("Failed to parse contract name".into()))?;
+        let amount = u128::from_str(&captures["amount"])
+            .map_err(|_e| net_error::DeserializeError("Failed to parse amount".into()))?;
 
-        Ok(HttpRequestType::PostMicroblock(
-            HttpRequestMetadata::from_preamble(preamble),
-            mb,
-            tip,
-        ))
+        Ok(HttpRequestType::GetWithdrawalFt {
+            metadata: HttpRequestMetadata::from_preamble(preamble),
+            withdraw_block_height,
+            sender,
+            withdrawal_id,
+            contract_identifier: QualifiedContractIdentifier::new(
+                contract_addr.into(),
+                contract_name,
+            ),
+            amount,
+        })
     }
 
-    fn parse_get_attachment<R: Read>(
+    fn parse_get_nft_withdrawal<R: Read>(
         _protocol: &mut StacksHttp,
         preamble: &HttpRequestPreamble,
         captures: &Captures,
@@ -2698,208 +2932,1266 @@ impl HttpRequestType {
     ) -> Result<HttpRequestType, net_error> {
         if preamble.get_content_length() != 0 {
             return Err(net_error::DeserializeError(
-                "Invalid Http request: expected 0-length body".to_string(),
+                "Invalid Http request: expected 0-length body for GetNFTWithdrawal".to_string(),
             ));
         }
-        let hex_content_hash = captures
-            .get(1)
-            .ok_or(net_error::DeserializeError(
-                "Failed to match path to attachment hash group".to_string(),
-            ))?
-            .as_str();
 
-        let content_hash = Hash160::from_hex(&hex_content_hash).map_err(|_| {
-            net_error::DeserializeError("Failed to construct hash160 from inputs".to_string())
+        let sender = PrincipalData::parse(&captures["sender"]).map_err(|_e| {
+            net_error::DeserializeError("Failed to parse account principal".into())
         })?;
 
-        Ok(HttpRequestType::GetAttachment(
-            HttpRequestMetadata::from_preamble(preamble),
-            content_hash,
-        ))
+        let withdraw_block_height = u64::from_str(&captures["block_height"])
+            .map_err(|_e| net_error::DeserializeError("Failed to parse block height".into()))?;
+
+        let withdrawal_id = u32::from_str(&captures["withdrawal_id"])
+            .map_err(|_e| net_error::DeserializeError("Failed to parse block height".into()))?;
+        let contract_addr =
+            StacksAddress::from_string(&captures["contract_address"]).ok_or_else(|| {
+                net_error::DeserializeError("Failed to parse contract address".into())
+            })?;
+        let contract_name = ContractName::try_from(captures["contract_name"].to_string())
+            .map_err(|_e| net_error::DeserializeError("Failed to parse contract name".into()))?;
+        let id = u128::from_str(&captures["id"])
+            .map_err(|_e| net_error::DeserializeError("Failed to parse amount".into()))?;
+
+        Ok(HttpRequestType::GetWithdrawalNft {
+            metadata: HttpRequestMetadata::from_preamble(preamble),
+            withdraw_block_height,
+            sender,
+            withdrawal_id,
+            contract_identifier: QualifiedContractIdentifier::new(
+                contract_addr.into(),
+                contract_name,
+            ),
+            id,
+        })
     }
 
-    fn parse_get_attachments_inv<R: Read>(
+    fn parse_get_stx_transfer_withdrawal<R: Read>(
         _protocol: &mut StacksHttp,
         preamble: &HttpRequestPreamble,
-        _captures: &Captures,
-        query: Option<&str>,
+        captures: &Captures,
+        _query: Option<&str>,
         _fd: &mut R,
     ) -> Result<HttpRequestType, net_error> {
         if preamble.get_content_length() != 0 {
             return Err(net_error::DeserializeError(
-                "Invalid Http request: expected 0-length body".to_string(),
+                "Invalid Http request: expected 0-length body for GetSTXTransferWithdrawal"
+                    .to_string(),
             ));
         }
 
-        let (index_block_hash, pages_indexes) = match query {
-            None => {
-                return Err(net_error::DeserializeError(
-                    "Invalid Http request: expecting index_block_hash and pages_indexes"
-                        .to_string(),
-                ));
-            }
-            Some(query) => {
-                let mut index_block_hash = None;
-                let mut pages_indexes = HashSet::new();
+        let sender = PrincipalData::parse(&captures["sender"]).map_err(|_e| {
+            net_error::DeserializeError("Failed to parse account principal".into())
+        })?;
+        let recipient = PrincipalData::parse(&captures["recipient"]).map_err(|_e| {
+            net_error::DeserializeError("Failed to parse recipient principal".into())
+        })?;
 
-                for (key, value) in form_urlencoded::parse(query.as_bytes()) {
-                    if key == "index_block_hash" {
-                        index_block_hash = match StacksBlockId::from_hex(&value) {
-                            Ok(index_block_hash) => Some(index_block_hash),
-                            _ => None,
-                        };
-                    } else if key == "pages_indexes" {
-                        if let Ok(pages_indexes_value) = value.parse::<String>() {
-                            for entry in pages_indexes_value.split(",") {
-                                if let Ok(page_index) = entry.parse::<u32>() {
-                                    pages_indexes.insert(page_index);
-                                }
-                            }
-                        }
-                    }
-                }
+        let withdraw_block_height = u64::from_str(&captures["block_height"])
+            .map_err(|_e| net_error::DeserializeError("Failed to parse block height".into()))?;
 
-                let index_block_hash = match index_block_hash {
-                    None => {
-                        return Err(net_error::DeserializeError(
-                            "Invalid Http request: expecting index_block_hash".to_string(),
-                        ));
-                    }
-                    Some(index_block_hash) => index_block_hash,
-                };
+        let withdrawal_id = u32::from_str(&captures["withdrawal_id"])
+            .map_err(|_e| net_error::DeserializeError("Failed to parse withdrawal ID".into()))?;
+        let amount = u128::from_str(&captures["amount"])
+            .map_err(|_e| net_error::DeserializeError("Failed to parse amount".into()))?;
+        let contract_addr =
+            StacksAddress::from_string(&captures["contract_address"]).ok_or_else(|| {
+                net_error::DeserializeError("Failed to parse contract address".into())
+            })?;
+        let contract_name = ContractName::try_from(captures["contract_name"].to_string())
+            .map_err(|_e| net_error::DeserializeError("Failed to parse contract name".into()))?;
 
-                if pages_indexes.is_empty() {
-                    return Err(net_error::DeserializeError(
-                        "Invalid Http request: expecting pages_indexes".to_string(),
-                    ));
-                }
+        Ok(HttpRequestType::GetWithdrawalStxTransfer {
+            metadata: HttpRequestMetadata::from_preamble(preamble),
+            withdraw_block_height,
+            sender,
+            withdrawal_id,
+            amount,
+            recipient,
+            destination_subnet: QualifiedContractIdentifier::new(
+                contract_addr.into(),
+                contract_name,
+            ),
+        })
+    }
 
-                (index_block_hash, pages_indexes)
-            }
-        };
+    fn parse_get_data_var<R: Read>(
+        _protocol: &mut StacksHttp,
+        preamble: &HttpRequestPreamble,
+        captures: &Captures,
+        query: Option<&str>,
+        _fd: &mut R,
+    ) -> Result<HttpRequestType, net_error> {
+        let content_len = preamble.get_content_length();
+        if content_len != 0 {
+            return Err(net_error::DeserializeError(format!(
+                "Invalid Http request: invalid body length for GetDataVar ({})",
+                content_len
+            )));
+        }
 
-        Ok(HttpRequestType::GetAttachmentsInv(
+        let contract_addr = StacksAddress::from_string(&captures["address"]).ok_or_else(|| {
+            net_error::DeserializeError("Failed to parse contract address".into())
+        })?;
+        let contract_name = ContractName::try_from(captures["contract"].to_string())
+            .map_err(|_e| net_error::DeserializeError("Failed to parse contract name".into()))?;
+        let var_name = ClarityName::try_from(captures["varname"].to_string())
+            .map_err(|_e| net_error::DeserializeError("Failed to parse data var name".into()))?;
+
+        let with_proof = HttpRequestType::get_proof_query(query);
+        let tip = HttpRequestType::get_chain_tip_query(query);
+
+        Ok(HttpRequestType::GetDataVar(
             HttpRequestMetadata::from_preamble(preamble),
-            index_block_hash,
-            pages_indexes,
+            contract_addr,
+            contract_name,
+            var_name,
+            tip,
+            with_proof,
         ))
     }
 
-    fn parse_post_mempool_query<R: Read>(
+    fn parse_get_map_entry<R: Read>(
         _protocol: &mut StacksHttp,
         preamble: &HttpRequestPreamble,
-        _regex: &Captures,
+        captures: &Captures,
         query: Option<&str>,
         fd: &mut R,
     ) -> Result<HttpRequestType, net_error> {
-        if preamble.get_content_length() == 0 {
-            return Err(net_error::DeserializeError(
-                "Invalid Http request: expected non-empty body".to_string(),
-            ));
+        let content_len = preamble.get_content_length();
+        if !(content_len > 0 && content_len < (BOUND_VALUE_SERIALIZATION_HEX)) {
+            return Err(net_error::DeserializeError(format!(
+                "Invalid Http request: invalid body length for GetMapEntry ({})",
+                content_len
+            )));
         }
 
-        if preamble.get_content_length() > MAX_PAYLOAD_LEN {
+        if preamble.content_type != Some(HttpContentType::JSON) {
             return Err(net_error::DeserializeError(
-                "Invalid Http request: MemPoolQuery body is too big".to_string(),
+                "Invalid content-type: expected application/json".into(),
             ));
         }
 
-        // content-type must be given, and must be application/octet-stream
-        match preamble.content_type {
-            None => {
-                return Err(net_error::DeserializeError(
-                    "Missing Content-Type for MemPoolQuery".to_string(),
-                ));
-            }
-            Some(ref c) => {
-                if *c != HttpContentType::Bytes {
-                    return Err(net_error::DeserializeError(
-                        "Wrong Content-Type for MemPoolQuery; expected application/octet-stream"
-                            .to_string(),
-                    ));
-                }
-            }
-        };
+        let contract_addr = StacksAddress::from_string(&captures["address"]).ok_or_else(|| {
+            net_error::DeserializeError("Failed to parse contract address".into())
+        })?;
+        let contract_name = ContractName::try_from(captures["contract"].to_string())
+            .map_err(|_e| net_error::DeserializeError("Failed to parse contract name".into()))?;
+        let map_name = ClarityName::try_from(captures["map"].to_string())
+            .map_err(|_e| net_error::DeserializeError("Failed to parse map name".into()))?;
 
-        let mut bound_fd = BoundReader::from_reader(fd, preamble.get_content_length() as u64);
-        let mempool_query = MemPoolSyncData::consensus_deserialize(&mut bound_fd)?;
-        let page_id_opt = HttpRequestType::get_mempool_page_id_query(query);
+        let value_hex: String = serde_json::from_reader(fd)
+            .map_err(|_e| net_error::DeserializeError("Failed to parse JSON body".into()))?;
 
-        Ok(HttpRequestType::MemPoolQuery(
+        let value = Value::try_deserialize_hex_untyped(&value_hex)
+            .map_err(|_e| net_error::DeserializeError("Failed to deserialize key value".into()))?;
+
+        let with_proof = HttpRequestType::get_proof_query(query);
+        let tip = HttpRequestType::get_chain_tip_query(query);
+
+        Ok(HttpRequestType::GetMapEntry(
             HttpRequestMetadata::from_preamble(preamble),
-            mempool_query,
-            page_id_opt,
+            contract_addr,
+            contract_name,
+            map_name,
+            value,
+            tip,
+            with_proof,
         ))
     }
 
-    fn parse_options_preflight<R: Read>(
-        _protocol: &mut StacksHttp,
+    fn parse_call_read_only<R: Read>(
+        protocol: &mut StacksHttp,
         preamble: &HttpRequestPreamble,
-        _regex: &Captures,
-        _query: Option<&str>,
-        _fd: &mut R,
+        captures: &Captures,
+        query: Option<&str>,
+        fd: &mut R,
     ) -> Result<HttpRequestType, net_error> {
-        Ok(HttpRequestType::OptionsPreflight(
+        let content_len = preamble.get_content_length();
+        if !(content_len > 0 && content_len < protocol.maximum_call_argument_size) {
+            return Err(net_error::DeserializeError(format!(
+                "Invalid Http request: invalid body length for CallReadOnly ({})",
+                content_len
+            )));
+        }
+
+        if preamble.content_type != Some(HttpContentType::JSON) {
+            return Err(net_error::DeserializeError(
+                "Invalid content-type: expected application/json".to_string(),
+            ));
+        }
+
+        let contract_addr = StacksAddress::from_string(&captures["address"]).ok_or_else(|| {
+            net_error::DeserializeError("Failed to parse contract address".into())
+        })?;
+        let contract_name = ContractName::try_from(captures["contract"].to_string())
+            .map_err(|_e| net_error::DeserializeError("Failed to parse contract name".into()))?;
+        let func_name = ClarityName::try_from(captures["function"].to_string())
+            .map_err(|_e| net_error::DeserializeError("Failed to parse contract name".into()))?;
+
+        let body: CallReadOnlyRequestBody = serde_json::from_reader(fd)
+            .map_err(|_e| net_error::DeserializeError("Failed to parse JSON body".into()))?;
+
+        let sender = PrincipalData::parse(&body.sender)
+            .map_err(|_e| net_error::DeserializeError("Failed to parse sender principal".into()))?;
+
+        let arguments = body
+            .arguments
+            .into_iter()
+            .map(|hex| Value::try_deserialize_hex_untyped(&hex).ok())
+            .collect::<Option<Vec<Value>>>()
+            .ok_or_else(|| {
+                net_error::DeserializeError("Failed to deserialize argument value".into())
+            })?;
+
+        let tip = HttpRequestType::get_chain_tip_query(query);
+
+        Ok(HttpRequestType::CallReadOnlyFunction(
             HttpRequestMetadata::from_preamble(preamble),
-            preamble.path.to_string(),
+            contract_addr,
+            contract_name,
+            sender,
+            func_name,
+            arguments,
+            tip,
         ))
     }
 
-    pub fn metadata(&self) -> &HttpRequestMetadata {
-        match self {
-            HttpRequestType::GetInfo(md) => md,
-            HttpRequestType::GetNeighbors(md) => md,
-            HttpRequestType::GetHeaders(md, ..) => md,
-            HttpRequestType::GetBlock(md, _) => md,
-            HttpRequestType::GetMicroblocksIndexed(md, _) => md,
-            HttpRequestType::GetMicroblocksConfirmed(md, _) => md,
-            HttpRequestType::GetMicroblocksUnconfirmed(md, _, _) => md,
-            HttpRequestType::GetTransactionUnconfirmed(md, _) => md,
-            HttpRequestType::PostTransaction(md, _, _) => md,
-            HttpRequestType::PostBlock(md, ..) => md,
-            HttpRequestType::PostMicroblock(md, ..) => md,
-            HttpRequestType::GetAccount(md, ..) => md,
-            HttpRequestType::GetDataVar(md, ..) => md,
-            HttpRequestType::GetMapEntry(md, ..) => md,
-            HttpRequestType::GetTransferCost(md) => md,
-            HttpRequestType::GetContractABI(md, ..) => md,
-            HttpRequestType::GetContractSrc(md, ..) => md,
-            HttpRequestType::GetIsTraitImplemented(md, ..) => md,
-            HttpRequestType::CallReadOnlyFunction(md, ..) => md,
-            HttpRequestType::OptionsPreflight(md, ..) => md,
-            HttpRequestType::GetAttachmentsInv(md, ..) => md,
-            HttpRequestType::GetAttachment(md, ..) => md,
-            HttpRequestType::MemPoolQuery(md, ..) => md,
-            HttpRequestType::FeeRateEstimate(md, _, _) => md,
-            HttpRequestType::ClientError(md, ..) => md,
-            HttpRequestType::GetWithdrawalStx { metadata, .. } => metadata,
-            HttpRequestType::BlockProposal(metadata, ..) => metadata,
-            HttpRequestType::GetWithdrawalFt { metadata, .. } => metadata,
-            HttpRequestType::GetWithdrawalNft { metadata, .. } => metadata,
+    fn parse_call_read_only_batch<R: Read>(
+        protocol: &mut StacksHttp,
+        preamble: &HttpRequestPreamble,
+        _captures: &Captures,
+        query: Option<&str>,
+        fd: &mut R,
+    ) -> Result<HttpRequestType, net_error> {
+        let content_len = preamble.get_content_length();
+        let max_content_len =
+            protocol.maximum_call_argument_size as usize * MAX_CALL_READ_BATCH_SIZE;
+        if !(content_len > 0 && (content_len as usize) < max_content_len) {
+            return Err(net_error::DeserializeError(format!(
+                "Invalid Http request: invalid body length for CallReadOnlyBatch ({})",
+                content_len
+            )));
         }
-    }
 
-    pub fn metadata_mut(&mut self) -> &mut HttpRequestMetadata {
-        match self {
-            HttpRequestType::GetInfo(ref mut md) => md,
-            HttpRequestType::GetNeighbors(ref mut md) => md,
-            HttpRequestType::GetHeaders(ref mut md, ..) => md,
-            HttpRequestType::GetBlock(ref mut md, _) => md,
-            HttpRequestType::GetMicroblocksIndexed(ref mut md, _) => md,
-            HttpRequestType::GetMicroblocksConfirmed(ref mut md, _) => md,
+        if preamble.content_type != Some(HttpContentType::JSON) {
+            return Err(net_error::DeserializeError(
+                "Invalid content-type: expected application/json".to_string(),
+            ));
+        }
+
+        let body: CallReadOnlyBatchRequestBody = serde_json::from_reader(fd)
+            .map_err(|_e| net_error::DeserializeError("Failed to parse JSON body".into()))?;
+
+        let sender = PrincipalData::parse(&body.sender)
+            .map_err(|_e| net_error::DeserializeError("Failed to parse sender principal".into()))?;
+
+        if body.calls.is_empty() || body.calls.len() > MAX_CALL_READ_BATCH_SIZE {
+            return Err(net_error::DeserializeError(format!(
+                "Invalid Http request: expected between 1 and {} calls",
+                MAX_CALL_READ_BATCH_SIZE
+            )));
+        }
+
+        let mut calls = Vec::with_capacity(body.calls.len());
+        for call in body.calls.into_iter() {
+            let contract = QualifiedContractIdentifier::parse(&call.contract).map_err(|_e| {
+                net_error::DeserializeError("Failed to parse contract identifier".into())
+            })?;
+            let function = ClarityName::try_from(call.function).map_err(|_e| {
+                net_error::DeserializeError("Failed to parse function name".into())
+            })?;
+            let arguments = call
+                .arguments
+                .into_iter()
+                .map(|hex| Value::try_deserialize_hex_untyped(&hex).ok())
+                .collect::<Option<Vec<Value>>>()
+                .ok_or_else(|| {
+                    net_error::DeserializeError("Failed to deserialize argument value".into())
+                })?;
+            calls.push(ReadOnlyCall {
+                contract,
+                function,
+                arguments,
+            });
+        }
+
+        Ok(HttpRequestType::CallReadOnlyFunctionBatch {
+            metadata: HttpRequestMetadata::from_preamble(preamble),
+            sender,
+            calls,
+            tip: HttpRequestType::get_chain_tip_query(query),
+        })
+    }
+
+    fn parse_block_proposal<R: Read>(
+        _protocol: &mut StacksHttp,
+        preamble: &HttpRequestPreamble,
+        _captures: &Captures,
+        _query: Option<&str>,
+        fd: &mut R,
+    ) -> Result<HttpRequestType, net_error> {
+        let content_len = preamble.get_content_length();
+        if !(content_len > 0 && content_len < MAX_BLOCK_PROPOSAL_LENGTH) {
+            return Err(net_error::DeserializeError(format!(
+                "Invalid Http request: invalid body length for BlockProposal ({})",
+                content_len
+            )));
+        }
+
+        if preamble.content_type != Some(HttpContentType::JSON) {
+            return Err(net_error::DeserializeError(
+                "Invalid content-type: expected application/json".to_string(),
+            ));
+        }
+
+        let block_proposal: SignedProposal = serde_json::from_reader(fd).map_err(|_e| {
+            net_error::DeserializeError("Failed to parse block proposal JSON body".into())
+        })?;
+
+        Ok(HttpRequestType::BlockProposal(
+            HttpRequestMetadata::from_preamble(preamble),
+            block_proposal,
+        ))
+    }
+
+    fn parse_get_contract_arguments(
+        preamble: &HttpRequestPreamble,
+        captures: &Captures,
+    ) -> Result<(HttpRequestMetadata, StacksAddress, ContractName), net_error> {
+        if preamble.get_content_length() != 0 {
+            return Err(net_error::DeserializeError(
+                "Invalid Http request: expected 0-length body".to_string(),
+            ));
+        }
+
+        let contract_addr = StacksAddress::from_string(&captures["address"]).ok_or_else(|| {
+            net_error::DeserializeError("Failed to parse contract address".into())
+        })?;
+        let contract_name = ContractName::try_from(captures["contract"].to_string())
+            .map_err(|_e| net_error::DeserializeError("Failed to parse contract name".into()))?;
+
+        Ok((
+            HttpRequestMetadata::from_preamble(preamble),
+            contract_addr,
+            contract_name,
+        ))
+    }
+
+    fn parse_get_contract_abi<R: Read>(
+        _protocol: &mut StacksHttp,
+        preamble: &HttpRequestPreamble,
+        captures: &Captures,
+        query: Option<&str>,
+        _fd: &mut R,
+    ) -> Result<HttpRequestType, net_error> {
+        let tip = HttpRequestType::get_chain_tip_query(query);
+        HttpRequestType::parse_get_contract_arguments(preamble, captures).map(
+            |(preamble, addr, name)| HttpRequestType::GetContractABI(preamble, addr, name, tip),
+        )
+    }
+
+    fn parse_get_contract_source<R: Read>(
+        _protocol: &mut StacksHttp,
+        preamble: &HttpRequestPreamble,
+        captures: &Captures,
+        query: Option<&str>,
+        _fd: &mut R,
+    ) -> Result<HttpRequestType, net_error> {
+        let with_proof = HttpRequestType::get_proof_query(query);
+        let tip = HttpRequestType::get_chain_tip_query(query);
+        HttpRequestType::parse_get_contract_arguments(preamble, captures).map(
+            |(preamble, addr, name)| {
+                HttpRequestType::GetContractSrc(preamble, addr, name, tip, with_proof)
+            },
+        )
+    }
+
+    fn parse_get_is_trait_implemented<R: Read>(
+        _protocol: &mut StacksHttp,
+        preamble: &HttpRequestPreamble,
+        captures: &Captures,
+        query: Option<&str>,
+        _fd: &mut R,
+    ) -> Result<HttpRequestType, net_error> {
+        let tip = HttpRequestType::get_chain_tip_query(query);
+        if preamble.get_content_length() != 0 {
+            return Err(net_error::DeserializeError(
+                "Invalid Http request: expected 0-length body".to_string(),
+            ));
+        }
+
+        let contract_addr = StacksAddress::from_string(&captures["address"]).ok_or_else(|| {
+            net_error::DeserializeError("Failed to parse contract address".into())
+        })?;
+        let contract_name = ContractName::try_from(captures["contract"].to_string())
+            .map_err(|_e| net_error::DeserializeError("Failed to parse contract name".into()))?;
+        let trait_name = ClarityName::try_from(captures["traitName"].to_string())
+            .map_err(|_e| net_error::DeserializeError("Failed to parse trait name".into()))?;
+        let trait_contract_addr = StacksAddress::from_string(&captures["traitContractAddr"])
+            .ok_or_else(|| net_error::DeserializeError("Failed to parse contract address".into()))?
+            .into();
+        let trait_contract_name = ContractName::try_from(captures["traitContractName"].to_string())
+            .map_err(|_e| {
+                net_error::DeserializeError("Failed to parse trait contract name".into())
+            })?;
+        let trait_id = TraitIdentifier::new(trait_contract_addr, trait_contract_name, trait_name);
+
+        Ok(HttpRequestType::GetIsTraitImplemented(
+            HttpRequestMetadata::from_preamble(preamble),
+            contract_addr,
+            contract_name,
+            trait_id,
+            tip,
+        ))
+    }
+
+    fn parse_getheaders<R: Read>(
+        _protocol: &mut StacksHttp,
+        preamble: &HttpRequestPreamble,
+        captures: &Captures,
+        query: Option<&str>,
+        _fd: &mut R,
+    ) -> Result<HttpRequestType, net_error> {
+        if preamble.get_content_length() != 0 {
+            return Err(net_error::DeserializeError(
+                "Invalid Http request: expected 0-length body for GetBlock".to_string(),
+            ));
+        }
+
+        let quantity_str = captures
+            .get(1)
+            .ok_or(net_error::DeserializeError(
+                "Failed to match path to reward cycle group".to_string(),
+            ))?
+            .as_str();
+
+        let quantity: u64 = quantity_str
+            .parse()
+            .map_err(|_| net_error::DeserializeError("Failed to parse reward cycle".to_string()))?;
+
+        let tip = HttpRequestType::get_chain_tip_query(query);
+
+        Ok(HttpRequestType::GetHeaders(
+            HttpRequestMetadata::from_preamble(preamble),
+            quantity,
+            tip,
+        ))
+    }
+
+    fn parse_getblock<R: Read>(
+        _protocol: &mut StacksHttp,
+        preamble: &HttpRequestPreamble,
+        captures: &Captures,
+        _query: Option<&str>,
+        _fd: &mut R,
+    ) -> Result<HttpRequestType, net_error> {
+        if preamble.get_content_length() != 0 {
+            return Err(net_error::DeserializeError(
+                "Invalid Http request: expected 0-length body for GetBlock".to_string(),
+            ));
+        }
+
+        let block_hash_str = captures
+            .get(1)
+            .ok_or(net_error::DeserializeError(
+                "Failed to match path to block hash group".to_string(),
+            ))?
+            .as_str();
+
+        let block_hash = StacksBlockId::from_hex(block_hash_str)
+            .map_err(|_e| net_error::DeserializeError("Failed to parse block hash".to_string()))?;
+
+        Ok(HttpRequestType::GetBlock(
+            HttpRequestMetadata::from_preamble(preamble),
+            block_hash,
+        ))
+    }
+
+    fn parse_getmicroblocks_indexed<R: Read>(
+        _protocol: &mut StacksHttp,
+        preamble: &HttpRequestPreamble,
+        captures: &Captures,
+        _query: Option<&str>,
+        _fd: &mut R,
+    ) -> Result<HttpRequestType, net_error> {
+        if preamble.get_content_length() != 0 {
+            return Err(net_error::DeserializeError(
+                "Invalid Http request: expected 0-length body for GetMicroblocksIndexed"
+                    .to_string(),
+            ));
+        }
+
+        let block_hash_str = captures
+            .get(1)
+            .ok_or(net_error::DeserializeError(
+                "Failed to match path to microblock hash group".to_string(),
+            ))?
+            .as_str();
+
+        let block_hash = StacksBlockId::from_hex(block_hash_str).map_err(|_e| {
+            net_error::DeserializeError("Failed to parse microblock hash".to_string())
+        })?;
+
+        Ok(HttpRequestType::GetMicroblocksIndexed(
+            HttpRequestMetadata::from_preamble(preamble),
+            block_hash,
+        ))
+    }
+
+    fn parse_getmicroblocks_confirmed<R: Read>(
+        _protocol: &mut StacksHttp,
+        preamble: &HttpRequestPreamble,
+        captures: &Captures,
+        _query: Option<&str>,
+        _fd: &mut R,
+    ) -> Result<HttpRequestType, net_error> {
+        if preamble.get_content_length() != 0 {
+            return Err(net_error::DeserializeError(
+                "Invalid Http request: expected 0-length body for GetMicrolocks".to_string(),
+            ));
+        }
+
+        let block_hash_str = captures
+            .get(1)
+            .ok_or(net_error::DeserializeError(
+                "Failed to match path to microblock hash group".to_string(),
+            ))?
+            .as_str();
+
+        let block_hash = StacksBlockId::from_hex(block_hash_str).map_err(|_e| {
+            net_error::DeserializeError("Failed to parse microblock hash".to_string())
+        })?;
+
+        Ok(HttpRequestType::GetMicroblocksConfirmed(
+            HttpRequestMetadata::from_preamble(preamble),
+            block_hash,
+        ))
+    }
+
+    fn parse_getmicroblocks_unconfirmed<R: Read>(
+        _protocol: &mut StacksHttp,
+        preamble: &HttpRequestPreamble,
+        captures: &Captures,
+        _query: Option<&str>,
+        _fd: &mut R,
+    ) -> Result<HttpRequestType, net_error> {
+        if preamble.get_content_length() != 0 {
+            return Err(net_error::DeserializeError(
+                "Invalid Http request: expected 0-length body for GetMicrolocksUnconfirmed"
+                    .to_string(),
+            ));
+        }
+
+        let block_hash_str = captures
+            .get(1)
+            .ok_or(net_error::DeserializeError(
+                "Failed to match path to microblock hash group".to_string(),
+            ))?
+            .as_str();
+
+        let min_seq_str = captures
+            .get(2)
+            .ok_or(net_error::DeserializeError(
+                "Failed to match path to microblock minimum sequence group".to_string(),
+            ))?
+            .as_str();
+
+        let block_hash = StacksBlockId::from_hex(block_hash_str).map_err(|_e| {
+            net_error::DeserializeError("Failed to parse microblock hash".to_string())
+        })?;
+
+        let min_seq = min_seq_str.parse::<u16>().map_err(|_e| {
+            net_error::DeserializeError("Failed to parse microblock minimum sequence".to_string())
+        })?;
+
+        Ok(HttpRequestType::GetMicroblocksUnconfirmed(
+            HttpRequestMetadata::from_preamble(preamble),
+            block_hash,
+            min_seq,
+        ))
+    }
+
+    fn parse_gettransaction_unconfirmed<R: Read>(
+        _protocol: &mut StacksHttp,
+        preamble: &HttpRequestPreamble,
+        regex: &Captures,
+        _query: Option<&str>,
+        _fd: &mut R,
+    ) -> Result<HttpRequestType, net_error> {
+        if preamble.get_content_length() != 0 {
+            return Err(net_error::DeserializeError(
+                "Invalid Http request: expected 0-length body for GetMicrolocksUnconfirmed"
+                    .to_string(),
+            ));
+        }
+
+        let txid_hex = regex
+            .get(1)
+            .ok_or(net_error::DeserializeError(
+                "Failed to match path to txid group".to_string(),
+            ))?
+            .as_str();
+
+        if txid_hex.len() != 64 {
+            return Err(net_error::DeserializeError(
+                "Invalid txid: expected 64 bytes".to_string(),
+            ));
+        }
+
+        let txid = Txid::from_hex(&txid_hex)
+            .map_err(|_e| net_error::DeserializeError("Failed to decode txid hex".to_string()))?;
+
+        Ok(HttpRequestType::GetTransactionUnconfirmed(
+            HttpRequestMetadata::from_preamble(preamble),
+            txid,
+        ))
+    }
+
+    fn parse_post_fee_rate_estimate<R: Read>(
+        _protocol: &mut StacksHttp,
+        preamble: &HttpRequestPreamble,
+        _regex: &Captures,
+        _query: Option<&str>,
+        fd: &mut R,
+    ) -> Result<HttpRequestType, net_error> {
+        let content_len = preamble.get_content_length();
+        if !(content_len > 0 && content_len < MAX_PAYLOAD_LEN) {
+            return Err(net_error::DeserializeError(format!(
+                "Invalid Http request: invalid body length for FeeRateEstimate ({})",
+                content_len
+            )));
+        }
+
+        if preamble.content_type != Some(HttpContentType::JSON) {
+            return Err(net_error::DeserializeError(
+                "Invalid content-type: expected application/json".to_string(),
+            ));
+        }
+
+        let bound_fd = BoundReader::from_reader(fd, content_len as u64);
+
+        let body: FeeRateEstimateRequestBody = serde_json::from_reader(bound_fd).map_err(|e| {
+            net_error::DeserializeError(format!("Failed to parse JSON body: {}", e))
+        })?;
+
+        let payload_hex = if body.transaction_payload.starts_with("0x") {
+            &body.transaction_payload[2..]
+        } else {
+            &body.transaction_payload
+        };
+
+        let payload_data = hex_bytes(payload_hex).map_err(|_e| {
+            net_error::DeserializeError("Bad hex string supplied for transaction payload".into())
+        })?;
+
+        let payload = TransactionPayload::consensus_deserialize(&mut payload_data.as_slice())
+            .map_err(|e| {
+                net_error::DeserializeError(format!(
+                    "Failed to deserialize transaction payload: {}",
+                    e
+                ))
+            })?;
+
+        let estimated_len =
+            std::cmp::max(body.estimated_len.unwrap_or(0), payload_data.len() as u64);
+
+        Ok(HttpRequestType::FeeRateEstimate(
+            HttpRequestMetadata::from_preamble(preamble),
+            payload,
+            estimated_len,
+        ))
+    }
+
+    fn parse_posttransaction<R: Read>(
+        _protocol: &mut StacksHttp,
+        preamble: &HttpRequestPreamble,
+        _regex: &Captures,
+        query: Option<&str>,
+        fd: &mut R,
+    ) -> Result<HttpRequestType, net_error> {
+        if preamble.get_content_length() == 0 {
+            return Err(net_error::DeserializeError(
+                "Invalid Http request: expected non-zero-length body for PostTransaction"
+                    .to_string(),
+            ));
+        }
+
+        if preamble.get_content_length() > MAX_PAYLOAD_LEN {
+            return Err(net_error::DeserializeError(
+                "Invalid Http request: PostTransaction body is too big".to_string(),
+            ));
+        }
+
+        let expiration_height = HttpRequestType::get_u64_query(query, "expiration_height");
+        let bundle_id = HttpRequestType::get_bundle_id_query(query)?;
+        let mut bound_fd = BoundReader::from_reader(fd, preamble.get_content_length() as u64);
+
+        match preamble.content_type {
+            None => {
+                return Err(net_error::DeserializeError(
+                    "Missing Content-Type for transaction".to_string(),
+                ));
+            }
+            Some(HttpContentType::Bytes) => HttpRequestType::parse_posttransaction_octets(
+                preamble,
+                &mut bound_fd,
+                expiration_height,
+                bundle_id,
+            ),
+            Some(HttpContentType::JSON) => HttpRequestType::parse_posttransaction_json(
+                preamble,
+                &mut bound_fd,
+                expiration_height,
+                bundle_id,
+            ),
+            _ => {
+                return Err(net_error::DeserializeError(
+                    "Wrong Content-Type for transaction; expected application/json".to_string(),
+                ));
+            }
+        }
+    }
+
+    fn parse_posttransaction_octets<R: Read>(
+        preamble: &HttpRequestPreamble,
+        fd: &mut R,
+        expiration_height: Option<u64>,
+        bundle_id: Option<String>,
+    ) -> Result<HttpRequestType, net_error> {
+        let tx = StacksTransaction::consensus_deserialize(fd).map_err(|e| {
+            if let codec_error::DeserializeError(msg) = e {
+                net_error::ClientError(ClientError::Message(format!(
+                    "Failed to deserialize posted transaction: {}",
+                    msg
+                )))
+            } else {
+                e.into()
+            }
+        })?;
+        Ok(HttpRequestType::PostTransaction(
+            HttpRequestMetadata::from_preamble(preamble),
+            tx,
+            None,
+            expiration_height,
+            bundle_id,
+        ))
+    }
+
+    fn parse_posttransaction_json<R: Read>(
+        preamble: &HttpRequestPreamble,
+        fd: &mut R,
+        expiration_height: Option<u64>,
+        bundle_id: Option<String>,
+    ) -> Result<HttpRequestType, net_error> {
+        let body: PostTransactionRequestBody = serde_json::from_reader(fd)
+            .map_err(|_e| net_error::DeserializeError("Failed to parse body".into()))?;
+
+        let tx = {
+            let tx_bytes = hex_bytes(&body.tx)
+                .map_err(|_e| net_error::DeserializeError("Failed to parse tx".into()))?;
+            StacksTransaction::consensus_deserialize(&mut &tx_bytes[..]).map_err(|e| {
+                if let codec_error::DeserializeError(msg) = e {
+                    net_error::ClientError(ClientError::Message(format!(
+                        "Failed to deserialize posted transaction: {}",
+                        msg
+                    )))
+                } else {
+                    e.into()
+                }
+            })
+        }?;
+
+        let attachment = match body.attachment {
+            None => None,
+            Some(attachment_content) => {
+                let content = hex_bytes(&attachment_content).map_err(|_e| {
+                    net_error::DeserializeError("Failed to parse attachment".into())
+                })?;
+                Some(Attachment::new(content))
+            }
+        };
+
+        Ok(HttpRequestType::PostTransaction(
+            HttpRequestMetadata::from_preamble(preamble),
+            tx,
+            attachment,
+            expiration_height,
+            bundle_id,
+        ))
+    }
+
+    fn parse_post_simulate_transaction<R: Read>(
+        _protocol: &mut StacksHttp,
+        preamble: &HttpRequestPreamble,
+        _regex: &Captures,
+        query: Option<&str>,
+        fd: &mut R,
+    ) -> Result<HttpRequestType, net_error> {
+        if preamble.get_content_length() == 0 {
+            return Err(net_error::DeserializeError(
+                "Invalid Http request: expected non-zero-length body for SimulateTransaction"
+                    .to_string(),
+            ));
+        }
+
+        if preamble.get_content_length() > MAX_PAYLOAD_LEN {
+            return Err(net_error::DeserializeError(
+                "Invalid Http request: SimulateTransaction body is too big".to_string(),
+            ));
+        }
+
+        match preamble.content_type {
+            Some(HttpContentType::Bytes) => {}
+            _ => {
+                return Err(net_error::DeserializeError(
+                    "Wrong Content-Type for transaction; expected application/octet-stream"
+                        .to_string(),
+                ));
+            }
+        }
+
+        let mut bound_fd = BoundReader::from_reader(fd, preamble.get_content_length() as u64);
+        let tx = StacksTransaction::consensus_deserialize(&mut bound_fd).map_err(|e| {
+            if let codec_error::DeserializeError(msg) = e {
+                net_error::ClientError(ClientError::Message(format!(
+                    "Failed to deserialize transaction to simulate: {}",
+                    msg
+                )))
+            } else {
+                e.into()
+            }
+        })?;
+
+        Ok(HttpRequestType::SimulateTransaction(
+            HttpRequestMetadata::from_preamble(preamble),
+            tx,
+            HttpRequestType::get_chain_tip_query(query),
+        ))
+    }
+
+    fn parse_postblock<R: Read>(
+        _protocol: &mut StacksHttp,
+        preamble: &HttpRequestPreamble,
+        regex: &Captures,
+        _query: Option<&str>,
+        fd: &mut R,
+    ) -> Result<HttpRequestType, net_error> {
+        if preamble.get_content_length() == 0 {
+            return Err(net_error::DeserializeError(
+                "Invalid Http request: expected non-zero-length body for PostBlock".to_string(),
+            ));
+        }
+
+        if preamble.get_content_length() > MAX_PAYLOAD_LEN {
+            return Err(net_error::DeserializeError(
+                "Invalid Http request: PostBlock body is too big".to_string(),
+            ));
+        }
+
+        // content-type must be given, and must be application/octet-stream
+        match preamble.content_type {
+            None => {
+                return Err(net_error::DeserializeError(
+                    "Missing Content-Type for Stacks block".to_string(),
+                ));
+            }
+            Some(ref c) => {
+                if *c != HttpContentType::Bytes {
+                    return Err(net_error::DeserializeError(
+                        "Wrong Content-Type for Stacks block; expected application/octet-stream"
+                            .to_string(),
+                    ));
+                }
+            }
+        };
+
+        let consensus_hash_str = regex
+            .get(1)
+            .ok_or(net_error::DeserializeError(
+                "Failed to match consensus hash in path group".to_string(),
+            ))?
+            .as_str();
+
+        let consensus_hash: ConsensusHash =
+            ConsensusHash::from_hex(consensus_hash_str).map_err(|_| {
+                net_error::DeserializeError("Failed to parse consensus hash".to_string())
+            })?;
+
+        let mut bound_fd = BoundReader::from_reader(fd, preamble.get_content_length() as u64);
+        let stacks_block = StacksBlock::consensus_deserialize(&mut bound_fd)?;
+
+        Ok(HttpRequestType::PostBlock(
+            HttpRequestMetadata::from_preamble(preamble),
+            consensus_hash,
+            stacks_block,
+        ))
+    }
+
+    fn parse_post_validate_block<R: Read>(
+        _protocol: &mut StacksHttp,
+        preamble: &HttpRequestPreamble,
+        regex: &Captures,
+        _query: Option<&str>,
+        fd: &mut R,
+    ) -> Result<HttpRequestType, net_error> {
+        if preamble.get_content_length() == 0 {
+            return Err(net_error::DeserializeError(
+                "Invalid Http request: expected non-zero-length body for ValidateBlock".to_string(),
+            ));
+        }
+
+        if preamble.get_content_length() > MAX_PAYLOAD_LEN {
+            return Err(net_error::DeserializeError(
+                "Invalid Http request: ValidateBlock body is too big".to_string(),
+            ));
+        }
+
+        match preamble.content_type {
+            Some(HttpContentType::Bytes) => {}
+            _ => {
+                return Err(net_error::DeserializeError(
+                    "Wrong Content-Type for Stacks block; expected application/octet-stream"
+                        .to_string(),
+                ));
+            }
+        }
+
+        let parent_consensus_hash = regex
+            .get(1)
+            .ok_or(net_error::DeserializeError(
+                "Failed to match consensus hash in path group".to_string(),
+            ))
+            .and_then(|ch| {
+                ConsensusHash::from_hex(ch.as_str()).map_err(|_| {
+                    net_error::DeserializeError("Failed to parse consensus hash".to_string())
+                })
+            })?;
+
+        let mut bound_fd = BoundReader::from_reader(fd, preamble.get_content_length() as u64);
+        let stacks_block = StacksBlock::consensus_deserialize(&mut bound_fd).map_err(|e| {
+            if let codec_error::DeserializeError(msg) = e {
+                net_error::ClientError(ClientError::Message(format!(
+                    "Failed to deserialize block to validate: {}",
+                    msg
+                )))
+            } else {
+                e.into()
+            }
+        })?;
+
+        Ok(HttpRequestType::ValidateBlock(
+            HttpRequestMetadata::from_preamble(preamble),
+            parent_consensus_hash,
+            stacks_block,
+        ))
+    }
+
+    fn parse_postmicroblock<R: Read>(
+        _protocol: &mut StacksHttp,
+        preamble: &HttpRequestPreamble,
+        _regex: &Captures,
+        query: Option<&str>,
+        fd: &mut R,
+    ) -> Result<HttpRequestType, net_error> {
+        if preamble.get_content_length() == 0 {
+            return Err(net_error::DeserializeError(
+                "Invalid Http request: expected non-zero-length body for PostMicroblock"
+                    .to_string(),
+            ));
+        }
+
+        if preamble.get_content_length() > MAX_PAYLOAD_LEN {
+            return Err(net_error::DeserializeError(
+                "Invalid Http request: PostMicroblock body is too big".to_string(),
+            ));
+        }
+
+        // content-type must be given, and must be application/octet-stream
+        match preamble.content_type {
+            None => {
+                return Err(net_error::DeserializeError(
+                    "Missing Content-Type for microblock".to_string(),
+                ));
+            }
+            Some(ref c) => {
+                if *c != HttpContentType::Bytes {
+                    return Err(net_error::DeserializeError(
+                        "Wrong Content-Type for microblock; expected application/octet-stream"
+                            .to_string(),
+                    ));
+                }
+            }
+        };
+
+        let mut bound_fd = BoundReader::from_reader(fd, preamble.get_content_length() as u64);
+
+        let mb = StacksMicroblock::consensus_deserialize(&mut bound_fd)?;
+        let tip = HttpRequestType::get_chain_tip_query(query);
+
+        Ok(HttpRequestType::PostMicroblock(
+            HttpRequestMetadata::from_preamble(preamble),
+            mb,
+            tip,
+        ))
+    }
+
+    fn parse_get_attachment<R: Read>(
+        _protocol: &mut StacksHttp,
+        preamble: &HttpRequestPreamble,
+        captures: &Captures,
+        _query: Option<&str>,
+        _fd: &mut R,
+    ) -> Result<HttpRequestType, net_error> {
+        if preamble.get_content_length() != 0 {
+            return Err(net_error::DeserializeError(
+                "Invalid Http request: expected 0-length body".to_string(),
+            ));
+        }
+        let hex_content_hash = captures
+            .get(1)
+            .ok_or(net_error::DeserializeError(
+                "Failed to match path to attachment hash group".to_string(),
+            ))?
+            .as_str();
+
+        let content_hash = Hash160::from_hex(&hex_content_hash).map_err(|_| {
+            net_error::DeserializeError("Failed to construct hash160 from inputs".to_string())
+        })?;
+
+        Ok(HttpRequestType::GetAttachment(
+            HttpRequestMetadata::from_preamble(preamble),
+            content_hash,
+        ))
+    }
+
+    fn parse_get_attachments_inv<R: Read>(
+        _protocol: &mut StacksHttp,
+        preamble: &HttpRequestPreamble,
+        _captures: &Captures,
+        query: Option<&str>,
+        _fd: &mut R,
+    ) -> Result<HttpRequestType, net_error> {
+        if preamble.get_content_length() != 0 {
+            return Err(net_error::DeserializeError(
+                "Invalid Http request: expected 0-length body".to_string(),
+            ));
+        }
+
+        let (index_block_hash, pages_indexes) = match query {
+            None => {
+                return Err(net_error::DeserializeError(
+                    "Invalid Http request: expecting index_block_hash and pages_indexes"
+                        .to_string(),
+                ));
+            }
+            Some(query) => {
+                let mut index_block_hash = None;
+                let mut pages_indexes = HashSet::new();
+
+                for (key, value) in form_urlencoded::parse(query.as_bytes()) {
+                    if key == "index_block_hash" {
+                        index_block_hash = match StacksBlockId::from_hex(&value) {
+                            Ok(index_block_hash) => Some(index_block_hash),
+                            _ => None,
+                        };
+                    } else if key == "pages_indexes" {
+                        if let Ok(pages_indexes_value) = value.parse::<String>() {
+                            for entry in pages_indexes_value.split(",") {
+                                if let Ok(page_index) = entry.parse::<u32>() {
+                                    pages_indexes.insert(page_index);
+                                }
+                            }
+                        }
+                    }
+                }
+
+                let index_block_hash = match index_block_hash {
+                    None => {
+                        return Err(net_error::DeserializeError(
+                            "Invalid Http request: expecting index_block_hash".to_string(),
+                        ));
+                    }
+                    Some(index_block_hash) => index_block_hash,
+                };
+
+                if pages_indexes.is_empty() {
+                    return Err(net_error::DeserializeError(
+                        "Invalid Http request: expecting pages_indexes".to_string(),
+                    ));
+                }
+
+                (index_block_hash, pages_indexes)
+            }
+        };
+
+        Ok(HttpRequestType::GetAttachmentsInv(
+            HttpRequestMetadata::from_preamble(preamble),
+            index_block_hash,
+            pages_indexes,
+        ))
+    }
+
+    fn parse_post_mempool_query<R: Read>(
+        _protocol: &mut StacksHttp,
+        preamble: &HttpRequestPreamble,
+        _regex: &Captures,
+        query: Option<&str>,
+        fd: &mut R,
+    ) -> Result<HttpRequestType, net_error> {
+        if preamble.get_content_length() == 0 {
+            return Err(net_error::DeserializeError(
+                "Invalid Http request: expected non-empty body".to_string(),
+            ));
+        }
+
+        if preamble.get_content_length() > MAX_PAYLOAD_LEN {
+            return Err(net_error::DeserializeError(
+                "Invalid Http request: MemPoolQuery body is too big".to_string(),
+            ));
+        }
+
+        // content-type must be given, and must be application/octet-stream
+        match preamble.content_type {
+            None => {
+                return Err(net_error::DeserializeError(
+                    "Missing Content-Type for MemPoolQuery".to_string(),
+                ));
+            }
+            Some(ref c) => {
+                if *c != HttpContentType::Bytes {
+                    return Err(net_error::DeserializeError(
+                        "Wrong Content-Type for MemPoolQuery; expected application/octet-stream"
+                            .to_string(),
+                    ));
+                }
+            }
+        };
+
+        let mut bound_fd = BoundReader::from_reader(fd, preamble.get_content_length() as u64);
+        let mempool_query = MemPoolSyncData::consensus_deserialize(&mut bound_fd)?;
+        let page_id_opt = HttpRequestType::get_mempool_page_id_query(query);
+
+        Ok(HttpRequestType::MemPoolQuery(
+            HttpRequestMetadata::from_preamble(preamble),
+            mempool_query,
+            page_id_opt,
+        ))
+    }
+
+    fn parse_options_preflight<R: Read>(
+        _protocol: &mut StacksHttp,
+        preamble: &HttpRequestPreamble,
+        _regex: &Captures,
+        _query: Option<&str>,
+        _fd: &mut R,
+    ) -> Result<HttpRequestType, net_error> {
+        Ok(HttpRequestType::OptionsPreflight(
+            HttpRequestMetadata::from_preamble(preamble),
+            preamble.path.to_string(),
+        ))
+    }
+
+    pub fn metadata(&self) -> &HttpRequestMetadata {
+        match self {
+            HttpRequestType::GetInfo(md) => md,
+            HttpRequestType::GetNeighbors(md) => md,
+            HttpRequestType::GetHeaders(md, ..) => md,
+            HttpRequestType::GetBlock(md, _) => md,
+            HttpRequestType::GetMicroblocksIndexed(md, _) => md,
+            HttpRequestType::GetMicroblocksConfirmed(md, _) => md,
+            HttpRequestType::GetMicroblocksUnconfirmed(md, _, _) => md,
+            HttpRequestType::GetTransactionUnconfirmed(md, _) => md,
+            HttpRequestType::PostTransaction(md, ..) => md,
+            HttpRequestType::SimulateTransaction(md, ..) => md,
+            HttpRequestType::PostBlock(md, ..) => md,
+            HttpRequestType::ValidateBlock(md, ..) => md,
+            HttpRequestType::PostMicroblock(md, ..) => md,
+            HttpRequestType::GetAccount(md, ..) => md,
+            HttpRequestType::GetMempoolAccount(md, ..) => md,
+            HttpRequestType::GetAccountNextNonce(md, ..) => md,
+            HttpRequestType::GetContractPublishPolicy(md) => md,
+            HttpRequestType::SetMinerPaused(md, ..) => md,
+            HttpRequestType::RotateMiningKey(md, ..) => md,
+            HttpRequestType::GetMemPoolFilters(md, ..) => md,
+            HttpRequestType::InstallMemPoolFilter(md, ..) => md,
+            HttpRequestType::RemoveMemPoolFilter(md, ..) => md,
+            HttpRequestType::GetDataVar(md, ..) => md,
+            HttpRequestType::GetMapEntry(md, ..) => md,
+            HttpRequestType::GetTransferCost(md) => md,
+            HttpRequestType::GetSubnetFeeRate(md) => md,
+            HttpRequestType::GetContractABI(md, ..) => md,
+            HttpRequestType::GetContractSrc(md, ..) => md,
+            HttpRequestType::GetIsTraitImplemented(md, ..) => md,
+            HttpRequestType::CallReadOnlyFunction(md, ..) => md,
+            HttpRequestType::CallReadOnlyFunctionBatch { metadata, .. } => metadata,
+            HttpRequestType::OptionsPreflight(md, ..) => md,
+            HttpRequestType::GetAttachmentsInv(md, ..) => md,
+            HttpRequestType::GetAttachment(md, ..) => md,
+            HttpRequestType::MemPoolQuery(md, ..) => md,
+            HttpRequestType::FeeRateEstimate(md, _, _) => md,
+            HttpRequestType::ClientError(md, ..) => md,
+            HttpRequestType::GetWithdrawalStx { metadata, .. } => metadata,
+            HttpRequestType::BlockProposal(metadata, ..) => metadata,
+            HttpRequestType::GetWithdrawalFt { metadata, .. } => metadata,
+            HttpRequestType::GetWithdrawalNft { metadata, .. } => metadata,
+            HttpRequestType::GetWithdrawalStxTransfer { metadata, .. } => metadata,
+            HttpRequestType::GetWithdrawalsBySender { metadata, .. } => metadata,
+            HttpRequestType::GetWithdrawalClaim { metadata, .. } => metadata,
+            HttpRequestType::GetFailedDepositsBySender { metadata, .. } => metadata,
+            HttpRequestType::GetAccountTransactions { metadata, .. } => metadata,
+            HttpRequestType::GetBurnOps { metadata, .. } => metadata,
+            HttpRequestType::GetForks { metadata, .. } => metadata,
+            HttpRequestType::GetContractCosts { metadata, .. } => metadata,
+            HttpRequestType::GetBlockCommits { metadata, .. } => metadata,
+            HttpRequestType::GetRewardsReport { metadata, .. } => metadata,
+            HttpRequestType::GetEventReplay { metadata, .. } => metadata,
+            HttpRequestType::GetSubnetInfo(md) => md,
+            HttpRequestType::GetMicroblockStream { metadata, .. } => metadata,
+            HttpRequestType::GetHealthLive(md) => md,
+            HttpRequestType::GetHealthReady(md) => md,
+            HttpRequestType::GetMempoolPreview { metadata, .. } => metadata,
+            HttpRequestType::GetRejectedTxs { metadata, .. } => metadata,
+            HttpRequestType::GetMempoolStats(md) => md,
+        }
+    }
+
+    pub fn metadata_mut(&mut self) -> &mut HttpRequestMetadata {
+        match self {
+            HttpRequestType::GetInfo(ref mut md) => md,
+            HttpRequestType::GetNeighbors(ref mut md) => md,
+            HttpRequestType::GetHeaders(ref mut md, ..) => md,
+            HttpRequestType::GetBlock(ref mut md, _) => md,
+            HttpRequestType::GetMicroblocksIndexed(ref mut md, _) => md,
+            HttpRequestType::GetMicroblocksConfirmed(ref mut md, _) => md,
             HttpRequestType::GetMicroblocksUnconfirmed(ref mut md, _, _) => md,
             HttpRequestType::GetTransactionUnconfirmed(ref mut md, _) => md,
-            HttpRequestType::PostTransaction(ref mut md, _, _) => md,
+            HttpRequestType::PostTransaction(ref mut md, ..) => md,
+            HttpRequestType::SimulateTransaction(ref mut md, ..) => md,
             HttpRequestType::PostBlock(ref mut md, ..) => md,
+            HttpRequestType::ValidateBlock(ref mut md, ..) => md,
             HttpRequestType::PostMicroblock(ref mut md, ..) => md,
             HttpRequestType::GetAccount(ref mut md, ..) => md,
+            HttpRequestType::GetMempoolAccount(ref mut md, ..) => md,
+            HttpRequestType::GetAccountNextNonce(ref mut md, ..) => md,
+            HttpRequestType::GetContractPublishPolicy(ref mut md) => md,
+            HttpRequestType::SetMinerPaused(ref mut md, ..) => md,
+            HttpRequestType::RotateMiningKey(ref mut md, ..) => md,
+            HttpRequestType::GetMemPoolFilters(ref mut md, ..) => md,
+            HttpRequestType::InstallMemPoolFilter(ref mut md, ..) => md,
+            HttpRequestType::RemoveMemPoolFilter(ref mut md, ..) => md,
             HttpRequestType::GetDataVar(ref mut md, ..) => md,
             HttpRequestType::GetMapEntry(ref mut md, ..) => md,
             HttpRequestType::GetTransferCost(ref mut md) => md,
+            HttpRequestType::GetSubnetFeeRate(ref mut md) => md,
             HttpRequestType::GetContractABI(ref mut md, ..) => md,
             HttpRequestType::GetContractSrc(ref mut md, ..) => md,
             HttpRequestType::GetIsTraitImplemented(ref mut md, ..) => md,
             HttpRequestType::CallReadOnlyFunction(ref mut md, ..) => md,
+            HttpRequestType::CallReadOnlyFunctionBatch {
+                ref mut metadata, ..
+            } => metadata,
             HttpRequestType::OptionsPreflight(ref mut md, ..) => md,
             HttpRequestType::GetAttachmentsInv(ref mut md, ..) => md,
             HttpRequestType::GetAttachment(ref mut md, ..) => md,
@@ -2908,894 +4200,1766 @@ impl HttpRequestType {
             HttpRequestType::ClientError(ref mut md, ..) => md,
             HttpRequestType::BlockProposal(ref mut metadata, ..) => metadata,
             HttpRequestType::GetWithdrawalStx {
-                ref mut metadata, ..
-            } => metadata,
+                ref mut metadata, ..
+            } => metadata,
+            HttpRequestType::GetWithdrawalFt {
+                ref mut metadata, ..
+            } => metadata,
+            HttpRequestType::GetWithdrawalNft {
+                ref mut metadata, ..
+            } => metadata,
+            HttpRequestType::GetWithdrawalStxTransfer {
+                ref mut metadata, ..
+            } => metadata,
+            HttpRequestType::GetWithdrawalsBySender {
+                ref mut metadata, ..
+            } => metadata,
+            HttpRequestType::GetWithdrawalClaim {
+                ref mut metadata, ..
+            } => metadata,
+            HttpRequestType::GetFailedDepositsBySender {
+                ref mut metadata, ..
+            } => metadata,
+            HttpRequestType::GetAccountTransactions {
+                ref mut metadata, ..
+            } => metadata,
+            HttpRequestType::GetBurnOps {
+                ref mut metadata, ..
+            } => metadata,
+            HttpRequestType::GetForks {
+                ref mut metadata, ..
+            } => metadata,
+            HttpRequestType::GetContractCosts {
+                ref mut metadata, ..
+            } => metadata,
+            HttpRequestType::GetBlockCommits {
+                ref mut metadata, ..
+            } => metadata,
+            HttpRequestType::GetRewardsReport {
+                ref mut metadata, ..
+            } => metadata,
+            HttpRequestType::GetEventReplay {
+                ref mut metadata, ..
+            } => metadata,
+            HttpRequestType::GetSubnetInfo(ref mut md) => md,
+            HttpRequestType::GetMicroblockStream {
+                ref mut metadata, ..
+            } => metadata,
+            HttpRequestType::GetHealthLive(ref mut md) => md,
+            HttpRequestType::GetHealthReady(ref mut md) => md,
+            HttpRequestType::GetMempoolPreview {
+                ref mut metadata, ..
+            } => metadata,
+            HttpRequestType::GetRejectedTxs {
+                ref mut metadata, ..
+            } => metadata,
+            HttpRequestType::GetMempoolStats(ref mut md) => md,
+        }
+    }
+
+    fn make_tip_query_string(tip_req: &TipRequest, with_proof: bool) -> String {
+        match tip_req {
+            TipRequest::UseLatestUnconfirmedTip => {
+                format!("?tip=latest{}", if with_proof { "" } else { "&proof=0" })
+            }
+            TipRequest::SpecificTip(tip) => {
+                format!("?tip={}{}", tip, if with_proof { "" } else { "&proof=0" })
+            }
+            TipRequest::UseLatestAnchoredTip => {
+                if !with_proof {
+                    format!("?proof=0")
+                } else {
+                    "".to_string()
+                }
+            }
+        }
+    }
+
+    pub fn request_path(&self) -> String {
+        match self {
+            HttpRequestType::GetInfo(_md) => "/v2/info".to_string(),
+            HttpRequestType::GetNeighbors(_md) => "/v2/neighbors".to_string(),
+            HttpRequestType::GetHeaders(_md, quantity, tip_req) => format!(
+                "/v2/headers/{}{}",
+                quantity,
+                HttpRequestType::make_tip_query_string(tip_req, true)
+            ),
+            HttpRequestType::GetBlock(_md, block_hash) => {
+                format!("/v2/blocks/{}", block_hash.to_hex())
+            }
+            HttpRequestType::GetMicroblocksIndexed(_md, block_hash) => {
+                format!("/v2/microblocks/{}", block_hash.to_hex())
+            }
+            HttpRequestType::GetMicroblocksConfirmed(_md, block_hash) => {
+                format!("/v2/microblocks/confirmed/{}", block_hash.to_hex())
+            }
+            HttpRequestType::GetMicroblocksUnconfirmed(_md, block_hash, min_seq) => format!(
+                "/v2/microblocks/unconfirmed/{}/{}",
+                block_hash.to_hex(),
+                min_seq
+            ),
+            HttpRequestType::GetTransactionUnconfirmed(_md, txid) => {
+                format!("/v2/transactions/unconfirmed/{}", txid)
+            }
+            HttpRequestType::PostTransaction(_md, _, _, expiration_height, bundle_id) => {
+                let mut query = vec![];
+                if let Some(height) = expiration_height {
+                    query.push(format!("expiration_height={}", height));
+                }
+                if let Some(bundle_id) = bundle_id {
+                    query.push(format!("bundle_id={}", bundle_id));
+                }
+                if query.is_empty() {
+                    "/v2/transactions".to_string()
+                } else {
+                    format!("/v2/transactions?{}", query.join("&"))
+                }
+            }
+            HttpRequestType::SimulateTransaction(_md, _, tip_req) => format!(
+                "/v2/transactions/simulate{}",
+                HttpRequestType::make_tip_query_string(tip_req, true)
+            ),
+            HttpRequestType::PostBlock(_md, ch, ..) => format!("/v2/blocks/upload/{}", &ch),
+            HttpRequestType::ValidateBlock(_md, ch, ..) => format!("/v2/blocks/validate/{}", &ch),
+            HttpRequestType::PostMicroblock(_md, _, tip_req) => format!(
+                "/v2/microblocks{}",
+                HttpRequestType::make_tip_query_string(tip_req, true)
+            ),
+            HttpRequestType::GetAccount(_md, principal, tip_req, with_proof) => {
+                format!(
+                    "/v2/accounts/{}{}",
+                    &principal.to_string(),
+                    HttpRequestType::make_tip_query_string(tip_req, *with_proof,)
+                )
+            }
+            HttpRequestType::GetMempoolAccount(_md, address, tip_req) => format!(
+                "/v2/mempool/account/{}{}",
+                &address.to_string(),
+                HttpRequestType::make_tip_query_string(tip_req, true)
+            ),
+            HttpRequestType::GetAccountNextNonce(_md, address, tip_req) => format!(
+                "/v2/accounts/{}/next_nonce{}",
+                &address.to_string(),
+                HttpRequestType::make_tip_query_string(tip_req, true)
+            ),
+            HttpRequestType::GetContractPublishPolicy(_md) => {
+                "/v2/admin/contract_publish_policy".into()
+            }
+            HttpRequestType::SetMinerPaused(..) => self.get_path().to_string(),
+            HttpRequestType::RotateMiningKey(..) => self.get_path().to_string(),
+            HttpRequestType::GetMemPoolFilters(..) => self.get_path().to_string(),
+            HttpRequestType::InstallMemPoolFilter(..) => self.get_path().to_string(),
+            HttpRequestType::RemoveMemPoolFilter(_md, filter_id, _) => {
+                format!("{}/{}", PATH_STR_POST_MEMPOOL_FILTER, filter_id)
+            }
+            HttpRequestType::GetDataVar(
+                _md,
+                contract_addr,
+                contract_name,
+                var_name,
+                tip_req,
+                with_proof,
+            ) => format!(
+                "/v2/data_var/{}/{}/{}{}",
+                &contract_addr.to_string(),
+                contract_name.as_str(),
+                var_name.as_str(),
+                HttpRequestType::make_tip_query_string(tip_req, *with_proof)
+            ),
+            HttpRequestType::GetMapEntry(
+                _md,
+                contract_addr,
+                contract_name,
+                map_name,
+                _key,
+                tip_req,
+                with_proof,
+            ) => format!(
+                "/v2/map_entry/{}/{}/{}{}",
+                &contract_addr.to_string(),
+                contract_name.as_str(),
+                map_name.as_str(),
+                HttpRequestType::make_tip_query_string(tip_req, *with_proof)
+            ),
+            HttpRequestType::GetTransferCost(_md) => "/v2/fees/transfer".into(),
+            HttpRequestType::GetSubnetFeeRate(_md) => "/v2/fees/subnet".into(),
+            HttpRequestType::GetContractABI(_, contract_addr, contract_name, tip_req) => format!(
+                "/v2/contracts/interface/{}/{}{}",
+                contract_addr,
+                contract_name.as_str(),
+                HttpRequestType::make_tip_query_string(tip_req, true,)
+            ),
+            HttpRequestType::GetContractSrc(
+                _,
+                contract_addr,
+                contract_name,
+                tip_req,
+                with_proof,
+            ) => format!(
+                "/v2/contracts/source/{}/{}{}",
+                contract_addr,
+                contract_name.as_str(),
+                HttpRequestType::make_tip_query_string(tip_req, *with_proof)
+            ),
+            HttpRequestType::GetIsTraitImplemented(
+                _,
+                contract_addr,
+                contract_name,
+                trait_id,
+                tip_req,
+            ) => format!(
+                "/v2/traits/{}/{}/{}/{}/{}{}",
+                contract_addr,
+                contract_name.as_str(),
+                trait_id.name.to_string(),
+                StacksAddress::from(trait_id.clone().contract_identifier.issuer),
+                trait_id.contract_identifier.name.as_str(),
+                HttpRequestType::make_tip_query_string(tip_req, true)
+            ),
+            HttpRequestType::CallReadOnlyFunction(
+                _,
+                contract_addr,
+                contract_name,
+                _,
+                func_name,
+                _,
+                tip_req,
+            ) => format!(
+                "/v2/contracts/call-read/{}/{}/{}{}",
+                contract_addr,
+                contract_name.as_str(),
+                func_name.as_str(),
+                HttpRequestType::make_tip_query_string(tip_req, true)
+            ),
+            HttpRequestType::CallReadOnlyFunctionBatch { tip, .. } => format!(
+                "/v2/contracts/call-read-batch{}",
+                HttpRequestType::make_tip_query_string(tip, true)
+            ),
+            HttpRequestType::OptionsPreflight(_md, path) => path.to_string(),
+            HttpRequestType::GetAttachmentsInv(_md, index_block_hash, pages_indexes) => {
+                let pages_query = match pages_indexes.len() {
+                    0 => format!(""),
+                    _n => {
+                        let mut indexes = pages_indexes
+                            .iter()
+                            .map(|i| format!("{}", i))
+                            .collect::<Vec<String>>();
+                        indexes.sort();
+                        format!("&pages_indexes={}", indexes.join(","))
+                    }
+                };
+                let index_block_hash = format!("index_block_hash={}", index_block_hash);
+                format!("/v2/attachments/inv?{}{}", index_block_hash, pages_query,)
+            }
+            HttpRequestType::GetAttachment(_, content_hash) => {
+                format!("/v2/attachments/{}", to_hex(&content_hash.0[..]))
+            }
+            HttpRequestType::MemPoolQuery(_, _, page_id_opt) => match page_id_opt {
+                Some(page_id) => {
+                    format!("/v2/mempool/query?page_id={}", page_id)
+                }
+                None => "/v2/mempool/query".to_string(),
+            },
+            HttpRequestType::FeeRateEstimate(_, _, _) => self.get_path().to_string(),
+            HttpRequestType::ClientError(_md, e) => match e {
+                ClientError::NotFound(path) => path.to_string(),
+                _ => "error path unknown".into(),
+            },
+            HttpRequestType::GetWithdrawalStx {
+                metadata: _,
+                withdraw_block_height,
+                sender,
+                withdrawal_id,
+                amount,
+            } => format!(
+                "/v2/withdrawal/stx/{}/{}/{}/{}",
+                withdraw_block_height, sender, withdrawal_id, amount
+            ),
+            HttpRequestType::BlockProposal(..) => self.get_path().to_string(),
             HttpRequestType::GetWithdrawalFt {
-                ref mut metadata, ..
-            } => metadata,
+                metadata: _,
+                withdraw_block_height,
+                sender,
+                withdrawal_id,
+                contract_identifier,
+                amount,
+            } => format!(
+                "/v2/withdrawal/ft/{}/{}/{}/{}/{}/{}",
+                withdraw_block_height,
+                sender,
+                withdrawal_id,
+                StacksAddress::from(contract_identifier.issuer.clone()),
+                contract_identifier.name.as_str(),
+                amount
+            ),
             HttpRequestType::GetWithdrawalNft {
-                ref mut metadata, ..
-            } => metadata,
+                metadata: _,
+                withdraw_block_height,
+                sender,
+                withdrawal_id,
+                contract_identifier,
+                id,
+            } => format!(
+                "/v2/withdrawal/nft/{}/{}/{}/{}/{}/{}",
+                withdraw_block_height,
+                sender,
+                withdrawal_id,
+                StacksAddress::from(contract_identifier.issuer.clone()),
+                contract_identifier.name.as_str(),
+                id
+            ),
+            HttpRequestType::GetWithdrawalStxTransfer {
+                metadata: _,
+                withdraw_block_height,
+                sender,
+                withdrawal_id,
+                amount,
+                recipient,
+                destination_subnet,
+            } => format!(
+                "/v2/withdrawal/stx-transfer/{}/{}/{}/{}/{}/{}/{}",
+                withdraw_block_height,
+                sender,
+                withdrawal_id,
+                amount,
+                recipient,
+                StacksAddress::from(destination_subnet.issuer.clone()),
+                destination_subnet.name.as_str()
+            ),
+            HttpRequestType::GetWithdrawalsBySender {
+                metadata: _,
+                sender,
+                offset,
+                limit,
+            } => format!(
+                "/v2/hyperchain/withdrawals/{}?offset={}&limit={}",
+                sender, offset, limit
+            ),
+            HttpRequestType::GetWithdrawalClaim {
+                metadata: _,
+                block_height,
+                withdrawal_id,
+            } => format!("/v2/withdrawal/claim/{}/{}", block_height, withdrawal_id),
+            HttpRequestType::GetFailedDepositsBySender {
+                metadata: _,
+                sender,
+                offset,
+                limit,
+            } => format!(
+                "/v2/hyperchain/failed_deposits/{}?offset={}&limit={}",
+                sender, offset, limit
+            ),
+            HttpRequestType::GetAccountTransactions {
+                metadata: _,
+                principal,
+                offset,
+                limit,
+            } => format!(
+                "/v2/accounts/{}/transactions?offset={}&limit={}",
+                principal, offset, limit
+            ),
+            HttpRequestType::GetBurnOps {
+                metadata: _,
+                burn_height,
+            } => format!("/v2/hyperchain/burn_ops?burn_height={}", burn_height),
+            HttpRequestType::GetForks {
+                metadata: _,
+                burn_blocks,
+            } => format!("/v2/hyperchain/forks?burn_blocks={}", burn_blocks),
+            HttpRequestType::GetContractCosts {
+                metadata: _,
+                blocks,
+            } => format!("/v2/hyperchain/contract_costs?blocks={}", blocks),
+            HttpRequestType::GetBlockCommits {
+                metadata: _,
+                l2_height,
+                count,
+            } => format!(
+                "/v2/hyperchain/commits?l2_height={}&count={}",
+                l2_height, count
+            ),
+            HttpRequestType::GetRewardsReport {
+                metadata: _,
+                from_height,
+                to_height,
+            } => format!(
+                "/v2/rewards/report?from_height={}&to_height={}",
+                from_height, to_height
+            ),
+            HttpRequestType::GetEventReplay {
+                metadata: _,
+                from_height,
+                to_height,
+                keys,
+            } => format!(
+                "/v2/events/replay?from_height={}&to_height={}&keys={}",
+                from_height,
+                to_height,
+                form_urlencoded::byte_serialize(keys.join(",").as_bytes()).collect::<String>()
+            ),
+            HttpRequestType::GetSubnetInfo(_md) => "/v2/hyperchain/info".into(),
+            HttpRequestType::GetMicroblockStream {
+                metadata: _,
+                parent_block_id,
+            } => format!("/v2/microblocks/stream/{}", parent_block_id),
+            HttpRequestType::GetHealthLive(_md) => "/v2/health/live".into(),
+            HttpRequestType::GetHealthReady(_md) => "/v2/health/ready".into(),
+            HttpRequestType::GetMempoolPreview {
+                metadata: _,
+                max_results,
+            } => format!("/v2/mempool/preview?max_results={}", max_results),
+            HttpRequestType::GetRejectedTxs {
+                metadata: _,
+                query,
+                limit,
+            } => match query {
+                RejectedTxsQuery::Txid(txid) => {
+                    format!("/v2/mempool/rejected?txid={}&limit={}", txid, limit)
+                }
+                RejectedTxsQuery::Address(address) => {
+                    format!("/v2/mempool/rejected?address={}&limit={}", address, limit)
+                }
+            },
+            HttpRequestType::GetMempoolStats(_md) => "/v2/mempool/stats".into(),
+        }
+    }
+
+    pub fn get_path(&self) -> &'static str {
+        match self {
+            HttpRequestType::GetInfo(..) => "/v2/info",
+            HttpRequestType::GetNeighbors(..) => "/v2/neighbors",
+            HttpRequestType::GetHeaders(..) => "/v2/headers/:height",
+            HttpRequestType::GetBlock(..) => "/v2/blocks/:hash",
+            HttpRequestType::GetMicroblocksIndexed(..) => "/v2/microblocks/:hash",
+            HttpRequestType::GetMicroblocksConfirmed(..) => "/v2/microblocks/confirmed/:hash",
+            HttpRequestType::GetMicroblocksUnconfirmed(..) => {
+                "/v2/microblocks/unconfirmed/:hash/:seq"
+            }
+            HttpRequestType::GetTransactionUnconfirmed(..) => "/v2/transactions/unconfirmed/:txid",
+            HttpRequestType::PostTransaction(..) => "/v2/transactions",
+            HttpRequestType::SimulateTransaction(..) => "/v2/transactions/simulate",
+            HttpRequestType::PostBlock(..) => "/v2/blocks/upload/:block",
+            HttpRequestType::ValidateBlock(..) => "/v2/blocks/validate/:parent_consensus_hash",
+            HttpRequestType::PostMicroblock(..) => "/v2/microblocks",
+            HttpRequestType::GetAccount(..) => "/v2/accounts/:principal",
+            HttpRequestType::GetMempoolAccount(..) => "/v2/mempool/account/:principal",
+            HttpRequestType::GetAccountNextNonce(..) => "/v2/accounts/:principal/next_nonce",
+            HttpRequestType::GetContractPublishPolicy(..) => "/v2/admin/contract_publish_policy",
+            HttpRequestType::SetMinerPaused(_, true, _) => PATH_STR_POST_MINER_PAUSE,
+            HttpRequestType::SetMinerPaused(_, false, _) => PATH_STR_POST_MINER_RESUME,
+            HttpRequestType::RotateMiningKey(..) => PATH_STR_POST_MINER_ROTATE_KEY,
+            HttpRequestType::GetMemPoolFilters(..) => PATH_STR_GET_MEMPOOL_FILTERS,
+            HttpRequestType::InstallMemPoolFilter(..) => PATH_STR_POST_MEMPOOL_FILTER,
+            HttpRequestType::RemoveMemPoolFilter(..) => "/v2/admin/mempool/filter/:filter_id",
+            HttpRequestType::GetDataVar(..) => "/v2/data_var/:principal/:contract_name/:var_name",
+            HttpRequestType::GetMapEntry(..) => "/v2/map_entry/:principal/:contract_name/:map_name",
+            HttpRequestType::GetTransferCost(..) => "/v2/fees/transfer",
+            HttpRequestType::GetSubnetFeeRate(..) => "/v2/fees/subnet",
+            HttpRequestType::GetContractABI(..) => {
+                "/v2/contracts/interface/:principal/:contract_name"
+            }
+            HttpRequestType::GetContractSrc(..) => "/v2/contracts/source/:principal/:contract_name",
+            HttpRequestType::CallReadOnlyFunction(..) => {
+                "/v2/contracts/call-read/:principal/:contract_name/:func_name"
+            }
+            HttpRequestType::CallReadOnlyFunctionBatch { .. } => "/v2/contracts/call-read-batch",
+            HttpRequestType::GetAttachmentsInv(..) => "/v2/attachments/inv",
+            HttpRequestType::GetAttachment(..) => "/v2/attachments/:hash",
+            HttpRequestType::GetIsTraitImplemented(..) => "/v2/traits/:principal/:contract_name",
+            HttpRequestType::MemPoolQuery(..) => "/v2/mempool/query",
+            HttpRequestType::FeeRateEstimate(_, _, _) => "/v2/fees/transaction",
+            HttpRequestType::OptionsPreflight(..) | HttpRequestType::ClientError(..) => "/",
+            HttpRequestType::GetWithdrawalStx { .. } => {
+                "/v2/withdrawal/stx/:block-height/:sender/:withdrawal_id/:amount"
+            }
+            HttpRequestType::BlockProposal(..) => PATH_STR_POST_BLOCK_PROPOSAL,
+            HttpRequestType::GetWithdrawalFt { .. } => {
+                "/v2/withdrawal/ft/:block-height/:sender/:withdrawal_id/:contract_address/:contract_name/:amount"
+            }
+            HttpRequestType::GetWithdrawalNft { .. } => {
+                "/v2/withdrawal/nft/:block-height/:sender/:withdrawal_id/:contract_address/:contract_name/:id"
+            }
+            HttpRequestType::GetWithdrawalStxTransfer { .. } => {
+                "/v2/withdrawal/stx-transfer/:block-height/:sender/:withdrawal_id/:amount/:recipient/:contract_address/:contract_name"
+            }
+            HttpRequestType::GetWithdrawalsBySender { .. } => "/v2/hyperchain/withdrawals/:sender",
+            HttpRequestType::GetWithdrawalClaim { .. } => {
+                "/v2/withdrawal/claim/:block-height/:withdrawal_id"
+            }
+            HttpRequestType::GetFailedDepositsBySender { .. } => {
+                "/v2/hyperchain/failed_deposits/:sender"
+            }
+            HttpRequestType::GetAccountTransactions { .. } => "/v2/accounts/:principal/transactions",
+            HttpRequestType::GetBurnOps { .. } => "/v2/hyperchain/burn_ops",
+            HttpRequestType::GetForks { .. } => "/v2/hyperchain/forks",
+            HttpRequestType::GetContractCosts { .. } => "/v2/hyperchain/contract_costs",
+            HttpRequestType::GetBlockCommits { .. } => "/v2/hyperchain/commits",
+            HttpRequestType::GetRewardsReport { .. } => "/v2/rewards/report",
+            HttpRequestType::GetEventReplay { .. } => "/v2/events/replay",
+            HttpRequestType::GetSubnetInfo(..) => "/v2/hyperchain/info",
+            HttpRequestType::GetMicroblockStream { .. } => "/v2/microblocks/stream/:block_id",
+            HttpRequestType::GetHealthLive(..) => "/v2/health/live",
+            HttpRequestType::GetHealthReady(..) => "/v2/health/ready",
+            HttpRequestType::GetMempoolPreview { .. } => "/v2/mempool/preview",
+            HttpRequestType::GetRejectedTxs { .. } => "/v2/mempool/rejected",
+            HttpRequestType::GetMempoolStats(..) => "/v2/mempool/stats",
+        }
+    }
+
+    pub fn send<W: Write>(&self, _protocol: &mut StacksHttp, fd: &mut W) -> Result<(), net_error> {
+        match self {
+            HttpRequestType::PostTransaction(md, tx, attachment, ..) => {
+                let mut tx_bytes = vec![];
+                write_next(&mut tx_bytes, tx)?;
+                let tx_hex = to_hex(&tx_bytes[..]);
+
+                let (content_type, request_body_bytes) = match attachment {
+                    None => {
+                        // Transaction does not include an attachment: HttpContentType::Bytes (more compressed)
+                        (Some(&HttpContentType::Bytes), tx_bytes)
+                    }
+                    Some(attachment) => {
+                        // Transaction is including an attachment: HttpContentType::JSON
+                        let request_body = PostTransactionRequestBody {
+                            tx: tx_hex,
+                            attachment: Some(to_hex(&attachment.content[..])),
+                        };
+
+                        let mut request_body_bytes = vec![];
+                        serde_json::to_writer(&mut request_body_bytes, &request_body).map_err(
+                            |e| {
+                                net_error::SerializeError(format!(
+                                    "Failed to serialize read-only call to JSON: {e:?}"
+                                ))
+                            },
+                        )?;
+                        (Some(&HttpContentType::JSON), request_body_bytes)
+                    }
+                };
+
+                HttpRequestPreamble::new_serialized(
+                    fd,
+                    &md.version,
+                    "POST",
+                    &self.request_path(),
+                    &md.peer,
+                    md.keep_alive,
+                    Some(request_body_bytes.len() as u32),
+                    content_type,
+                    |fd| stacks_height_headers(fd, md),
+                )?;
+                fd.write_all(&request_body_bytes)
+                    .map_err(net_error::WriteError)?;
+            }
+            HttpRequestType::SimulateTransaction(md, tx, _) => {
+                let mut tx_bytes = vec![];
+                write_next(&mut tx_bytes, tx)?;
+
+                HttpRequestPreamble::new_serialized(
+                    fd,
+                    &md.version,
+                    "POST",
+                    &self.request_path(),
+                    &md.peer,
+                    md.keep_alive,
+                    Some(tx_bytes.len() as u32),
+                    Some(&HttpContentType::Bytes),
+                    |fd| stacks_height_headers(fd, md),
+                )?;
+                fd.write_all(&tx_bytes).map_err(net_error::WriteError)?;
+            }
+            HttpRequestType::PostBlock(md, _ch, block)
+            | HttpRequestType::ValidateBlock(md, _ch, block) => {
+                let mut block_bytes = vec![];
+                write_next(&mut block_bytes, block)?;
+
+                HttpRequestPreamble::new_serialized(
+                    fd,
+                    &md.version,
+                    "POST",
+                    &self.request_path(),
+                    &md.peer,
+                    md.keep_alive,
+                    Some(block_bytes.len() as u32),
+                    Some(&HttpContentType::Bytes),
+                    |fd| stacks_height_headers(fd, md),
+                )?;
+                fd.write_all(&block_bytes).map_err(net_error::WriteError)?;
+            }
+            HttpRequestType::PostMicroblock(md, mb, ..) => {
+                let mut mb_bytes = vec![];
+                write_next(&mut mb_bytes, mb)?;
+
+                HttpRequestPreamble::new_serialized(
+                    fd,
+                    &md.version,
+                    "POST",
+                    &self.request_path(),
+                    &md.peer,
+                    md.keep_alive,
+                    Some(mb_bytes.len() as u32),
+                    Some(&HttpContentType::Bytes),
+                    |fd| stacks_height_headers(fd, md),
+                )?;
+                fd.write_all(&mb_bytes).map_err(net_error::WriteError)?;
+            }
+            HttpRequestType::GetMapEntry(
+                md,
+                _contract_addr,
+                _contract_name,
+                _map_name,
+                key,
+                ..,
+            ) => {
+                let mut request_bytes = vec![];
+                key.serialize_write(&mut request_bytes)
+                    .map_err(net_error::WriteError)?;
+                let request_json = format!("\"{}\"", to_hex(&request_bytes));
+
+                HttpRequestPreamble::new_serialized(
+                    fd,
+                    &md.version,
+                    "POST",
+                    &self.request_path(),
+                    &md.peer,
+                    md.keep_alive,
+                    Some(request_json.as_bytes().len() as u32),
+                    Some(&HttpContentType::JSON),
+                    |fd| stacks_height_headers(fd, md),
+                )?;
+                fd.write_all(&request_json.as_bytes())
+                    .map_err(net_error::WriteError)?;
+            }
+            HttpRequestType::CallReadOnlyFunction(
+                md,
+                _contract_addr,
+                _contract_name,
+                sender,
+                _func_name,
+                func_args,
+                ..,
+            ) => {
+                let mut args = vec![];
+                for arg in func_args.iter() {
+                    let mut arg_bytes = vec![];
+                    arg.serialize_write(&mut arg_bytes)
+                        .map_err(net_error::WriteError)?;
+                    args.push(to_hex(&arg_bytes));
+                }
+
+                let request_body = CallReadOnlyRequestBody {
+                    sender: sender.to_string(),
+                    arguments: args,
+                };
+
+                let mut request_body_bytes = vec![];
+                serde_json::to_writer(&mut request_body_bytes, &request_body).map_err(|e| {
+                    net_error::SerializeError(format!(
+                        "Failed to serialize read-only call to JSON: {e:?}"
+                    ))
+                })?;
+
+                HttpRequestPreamble::new_serialized(
+                    fd,
+                    &md.version,
+                    "POST",
+                    &self.request_path(),
+                    &md.peer,
+                    md.keep_alive,
+                    Some(request_body_bytes.len() as u32),
+                    Some(&HttpContentType::JSON),
+                    |fd| stacks_height_headers(fd, md),
+                )?;
+                fd.write_all(&request_body_bytes)
+                    .map_err(net_error::WriteError)?;
+            }
+            HttpRequestType::CallReadOnlyFunctionBatch {
+                metadata: md,
+                sender,
+                calls,
+                ..
+            } => {
+                let mut batch_calls = vec![];
+                for call in calls.iter() {
+                    let mut args = vec![];
+                    for arg in call.arguments.iter() {
+                        let mut arg_bytes = vec![];
+                        arg.serialize_write(&mut arg_bytes)
+                            .map_err(net_error::WriteError)?;
+                        args.push(to_hex(&arg_bytes));
+                    }
+                    batch_calls.push(CallReadOnlyBatchEntry {
+                        contract: call.contract.to_string(),
+                        function: call.function.to_string(),
+                        arguments: args,
+                    });
+                }
+
+                let request_body = CallReadOnlyBatchRequestBody {
+                    sender: sender.to_string(),
+                    calls: batch_calls,
+                };
+
+                let mut request_body_bytes = vec![];
+                serde_json::to_writer(&mut request_body_bytes, &request_body).map_err(|e| {
+                    net_error::SerializeError(format!(
+                        "Failed to serialize read-only call batch to JSON: {e:?}"
+                    ))
+                })?;
+
+                HttpRequestPreamble::new_serialized(
+                    fd,
+                    &md.version,
+                    "POST",
+                    &self.request_path(),
+                    &md.peer,
+                    md.keep_alive,
+                    Some(request_body_bytes.len() as u32),
+                    Some(&HttpContentType::JSON),
+                    |fd| stacks_height_headers(fd, md),
+                )?;
+                fd.write_all(&request_body_bytes)
+                    .map_err(net_error::WriteError)?;
+            }
+            HttpRequestType::SetMinerPaused(md, _, auth_token)
+            | HttpRequestType::RotateMiningKey(md, auth_token) => {
+                HttpRequestPreamble::new_serialized(
+                    fd,
+                    &md.version,
+                    "POST",
+                    &self.request_path(),
+                    &md.peer,
+                    md.keep_alive,
+                    Some(0),
+                    None,
+                    |fd| {
+                        stacks_height_headers(fd, md)?;
+                        if let Some(token) = auth_token {
+                            fd.write_all(format!("Authorization: Bearer {}\r\n", token).as_bytes())
+                                .map_err(codec_error::WriteError)?;
+                        }
+                        Ok(())
+                    },
+                )?;
+            }
+            HttpRequestType::GetMemPoolFilters(md, auth_token)
+            | HttpRequestType::RemoveMemPoolFilter(md, _, auth_token) => {
+                let verb = match self {
+                    HttpRequestType::RemoveMemPoolFilter(..) => "DELETE",
+                    _ => "GET",
+                };
+                HttpRequestPreamble::new_serialized(
+                    fd,
+                    &md.version,
+                    verb,
+                    &self.request_path(),
+                    &md.peer,
+                    md.keep_alive,
+                    Some(0),
+                    None,
+                    |fd| {
+                        stacks_height_headers(fd, md)?;
+                        if let Some(token) = auth_token {
+                            fd.write_all(format!("Authorization: Bearer {}\r\n", token).as_bytes())
+                                .map_err(codec_error::WriteError)?;
+                        }
+                        Ok(())
+                    },
+                )?;
+            }
+            HttpRequestType::InstallMemPoolFilter(md, body, auth_token) => {
+                let mut request_body_bytes = vec![];
+                serde_json::to_writer(&mut request_body_bytes, body).map_err(|e| {
+                    net_error::SerializeError(format!(
+                        "Failed to serialize mempool filter to JSON: {:?}",
+                        &e
+                    ))
+                })?;
+                HttpRequestPreamble::new_serialized(
+                    fd,
+                    &md.version,
+                    "POST",
+                    &self.request_path(),
+                    &md.peer,
+                    md.keep_alive,
+                    Some(request_body_bytes.len() as u32),
+                    Some(&HttpContentType::JSON),
+                    |fd| {
+                        stacks_height_headers(fd, md)?;
+                        if let Some(token) = auth_token {
+                            fd.write_all(format!("Authorization: Bearer {}\r\n", token).as_bytes())
+                                .map_err(codec_error::WriteError)?;
+                        }
+                        Ok(())
+                    },
+                )?;
+                fd.write_all(&request_body_bytes)
+                    .map_err(net_error::WriteError)?;
+            }
+            HttpRequestType::MemPoolQuery(md, query, ..) => {
+                let request_body_bytes = query.serialize_to_vec();
+                HttpRequestPreamble::new_serialized(
+                    fd,
+                    &md.version,
+                    "POST",
+                    &self.request_path(),
+                    &md.peer,
+                    md.keep_alive,
+                    Some(request_body_bytes.len() as u32),
+                    Some(&HttpContentType::Bytes),
+                    empty_headers,
+                )?;
+                fd.write_all(&request_body_bytes)
+                    .map_err(net_error::WriteError)?;
+            }
+            other_type => {
+                let md = other_type.metadata();
+                let request_path = other_type.request_path();
+                HttpRequestPreamble::new_serialized(
+                    fd,
+                    &md.version,
+                    "GET",
+                    &request_path,
+                    &md.peer,
+                    md.keep_alive,
+                    None,
+                    None,
+                    |fd| stacks_height_headers(fd, md),
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl HttpResponseType {
+    fn try_parse<R: Read, F>(
+        protocol: &mut StacksHttp,
+        regex: &Regex,
+        request_version: HttpVersion,
+        preamble: &HttpResponsePreamble,
+        request_path: &String,
+        fd: &mut R,
+        len_hint: Option<usize>,
+        parser: F,
+    ) -> Result<Option<HttpResponseType>, net_error>
+    where
+        F: Fn(
+            &mut StacksHttp,
+            HttpVersion,
+            &HttpResponsePreamble,
+            &mut R,
+            Option<usize>,
+        ) -> Result<HttpResponseType, net_error>,
+    {
+        if regex.is_match(request_path) {
+            let payload = parser(protocol, request_version, preamble, fd, len_hint)?;
+            Ok(Some(payload))
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn parse_error<R: Read>(
+        _protocol: &mut StacksHttp,
+        request_version: HttpVersion,
+        preamble: &HttpResponsePreamble,
+        fd: &mut R,
+    ) -> Result<HttpResponseType, net_error> {
+        if preamble.status_code < 400 || preamble.status_code > 599 {
+            return Err(net_error::DeserializeError(
+                "Inavlid response: not an error".to_string(),
+            ));
+        }
+
+        if preamble.content_type != HttpContentType::Text
+            && preamble.content_type != HttpContentType::JSON
+        {
+            return Err(net_error::DeserializeError(format!(
+                "Invalid error response: expected text/plain or application/json, got {:?}",
+                &preamble.content_type
+            )));
         }
+
+        let mut error_text = String::new();
+        fd.read_to_string(&mut error_text)
+            .map_err(net_error::ReadError)?;
+
+        let md = HttpResponseMetadata::from_preamble(request_version, preamble);
+        let resp = match preamble.status_code {
+            400 => HttpResponseType::BadRequest(md, error_text),
+            401 => HttpResponseType::Unauthorized(md, error_text),
+            402 => HttpResponseType::PaymentRequired(md, error_text),
+            403 => HttpResponseType::Forbidden(md, error_text),
+            404 => HttpResponseType::NotFound(md, error_text),
+            429 => HttpResponseType::TooManyRequests(md, error_text),
+            500 => HttpResponseType::ServerError(md, error_text),
+            503 => HttpResponseType::ServiceUnavailable(md, error_text),
+            _ => HttpResponseType::Error(md, preamble.status_code, error_text),
+        };
+        Ok(resp)
     }
 
-    fn make_tip_query_string(tip_req: &TipRequest, with_proof: bool) -> String {
-        match tip_req {
-            TipRequest::UseLatestUnconfirmedTip => {
-                format!("?tip=latest{}", if with_proof { "" } else { "&proof=0" })
-            }
-            TipRequest::SpecificTip(tip) => {
-                format!("?tip={}{}", tip, if with_proof { "" } else { "&proof=0" })
+    fn parse_bytestream<R: Read, T: StacksMessageCodec>(
+        preamble: &HttpResponsePreamble,
+        fd: &mut R,
+        len_hint: Option<usize>,
+        max_len: u64,
+    ) -> Result<T, net_error> {
+        // content-type has to be Bytes
+        if preamble.content_type != HttpContentType::Bytes {
+            return Err(net_error::DeserializeError(
+                "Invalid content-type: expected application/octet-stream".to_string(),
+            ));
+        }
+
+        let item: T = if preamble.is_chunked() && len_hint.is_none() {
+            let mut chunked_fd = HttpChunkedTransferReader::from_reader(fd, max_len);
+            read_next(&mut chunked_fd)?
+        } else {
+            let content_length_opt = match (preamble.content_length, len_hint) {
+                (Some(l), _) => Some(l as u32),
+                (None, Some(l)) => Some(l as u32),
+                (None, None) => None,
+            };
+            if let Some(content_length) = content_length_opt {
+                if (content_length as u64) > max_len {
+                    return Err(net_error::DeserializeError(
+                        "Invalid Content-Length header: too long".to_string(),
+                    ));
+                }
+
+                let mut bound_fd = BoundReader::from_reader(fd, content_length as u64);
+                read_next(&mut bound_fd)?
+            } else {
+                // unsupported headers
+                trace!("preamble: {:?}", preamble);
+                return Err(net_error::DeserializeError(
+                    "Invalid headers: need either Transfer-Encoding or Content-Length".to_string(),
+                ));
             }
-            TipRequest::UseLatestAnchoredTip => {
-                if !with_proof {
-                    format!("?proof=0")
-                } else {
-                    "".to_string()
+        };
+
+        Ok(item)
+    }
+
+    fn parse_json<R: Read, T: serde::de::DeserializeOwned>(
+        preamble: &HttpResponsePreamble,
+        fd: &mut R,
+        len_hint: Option<usize>,
+        max_len: u64,
+    ) -> Result<T, net_error> {
+        // content-type has to be JSON
+        if preamble.content_type != HttpContentType::JSON {
+            return Err(net_error::DeserializeError(
+                "Invalid content-type: expected application/json".to_string(),
+            ));
+        }
+
+        let item_result: Result<T, serde_json::Error> = if preamble.is_chunked()
+            && len_hint.is_none()
+        {
+            let chunked_fd = HttpChunkedTransferReader::from_reader(fd, max_len);
+            serde_json::from_reader(chunked_fd)
+        } else {
+            let content_length_opt = match (preamble.content_length, len_hint) {
+                (Some(l), _) => Some(l as u32),
+                (None, Some(l)) => Some(l as u32),
+                (None, None) => None,
+            };
+            if let Some(content_length) = content_length_opt {
+                if (content_length as u64) > max_len {
+                    return Err(net_error::DeserializeError(
+                        "Invalid Content-Length header: too long".to_string(),
+                    ));
                 }
+                let bound_fd = BoundReader::from_reader(fd, content_length as u64);
+                serde_json::from_reader(bound_fd)
+            } else {
+                // unsupported headers
+                trace!("preamble: {:?}", preamble);
+                return Err(net_error::DeserializeError(
+                    "Invalid headers: need either Transfer-Encoding or Content-Length".to_string(),
+                ));
+            }
+        };
+
+        item_result.map_err(|e| {
+            if e.is_eof() {
+                net_error::UnderflowError(format!("Not enough bytes to parse JSON"))
+            } else {
+                net_error::DeserializeError(format!("Failed to parse JSON: {:?}", &e))
             }
+        })
+    }
+
+    fn parse_text<R: Read>(
+        preamble: &HttpResponsePreamble,
+        fd: &mut R,
+        len_hint: Option<usize>,
+        max_len: u64,
+    ) -> Result<Vec<u8>, net_error> {
+        // content-type has to be text/plain
+        if preamble.content_type != HttpContentType::Text {
+            return Err(net_error::DeserializeError(
+                "Invalid content-type: expected text/plain".to_string(),
+            ));
         }
+        let buf = if preamble.is_chunked() && len_hint.is_none() {
+            let mut chunked_fd = HttpChunkedTransferReader::from_reader(fd, max_len);
+            let mut buf = vec![];
+            chunked_fd
+                .read_to_end(&mut buf)
+                .map_err(net_error::ReadError)?;
+            buf
+        } else {
+            let content_length_opt = match (preamble.content_length, len_hint) {
+                (Some(l), _) => Some(l as u32),
+                (None, Some(l)) => Some(l as u32),
+                (None, None) => None,
+            };
+            if let Some(len) = content_length_opt {
+                let mut buf = vec![0u8; len as usize];
+                fd.read_exact(&mut buf).map_err(net_error::ReadError)?;
+                buf
+            } else {
+                // unsupported headers
+                trace!("preamble: {:?}", preamble);
+                return Err(net_error::DeserializeError(
+                    "Invalid headers: need either Transfer-Encoding or Content-Length".to_string(),
+                ));
+            }
+        };
+
+        Ok(buf)
     }
 
-    pub fn request_path(&self) -> String {
-        match self {
-            HttpRequestType::GetInfo(_md) => "/v2/info".to_string(),
-            HttpRequestType::GetNeighbors(_md) => "/v2/neighbors".to_string(),
-            HttpRequestType::GetHeaders(_md, quantity, tip_req) => format!(
-                "/v2/headers/{}{}",
-                quantity,
-                HttpRequestType::make_tip_query_string(tip_req, true)
+    // len_hint is given by the StacksHttp protocol implementation
+    pub fn parse<R: Read>(
+        protocol: &mut StacksHttp,
+        request_version: HttpVersion,
+        preamble: &HttpResponsePreamble,
+        request_path: String,
+        fd: &mut R,
+        len_hint: Option<usize>,
+    ) -> Result<HttpResponseType, net_error> {
+        if preamble.status_code >= 400 {
+            return HttpResponseType::parse_error(protocol, request_version, preamble, fd);
+        }
+
+        // TODO: make this static somehow
+        let RESPONSE_METHODS: &[(
+            &Regex,
+            &dyn Fn(
+                &mut StacksHttp,
+                HttpVersion,
+                &HttpResponsePreamble,
+                &mut R,
+                Option<usize>,
+            ) -> Result<HttpResponseType, net_error>,
+        )] = &[
+            (&PATH_GETINFO, &HttpResponseType::parse_peerinfo),
+            (&PATH_GETPOXINFO, &HttpResponseType::parse_poxinfo),
+            (&PATH_GETNEIGHBORS, &HttpResponseType::parse_neighbors),
+            (&PATH_GETHEADERS, &HttpResponseType::parse_headers),
+            (&PATH_GETBLOCK, &HttpResponseType::parse_block),
+            (&PATH_GET_DATA_VAR, &HttpResponseType::parse_get_data_var),
+            (&PATH_GET_MAP_ENTRY, &HttpResponseType::parse_get_map_entry),
+            (
+                &PATH_GETMICROBLOCKS_INDEXED,
+                &HttpResponseType::parse_microblocks,
             ),
-            HttpRequestType::GetBlock(_md, block_hash) => {
-                format!("/v2/blocks/{}", block_hash.to_hex())
-            }
-            HttpRequestType::GetMicroblocksIndexed(_md, block_hash) => {
-                format!("/v2/microblocks/{}", block_hash.to_hex())
-            }
-            HttpRequestType::GetMicroblocksConfirmed(_md, block_hash) => {
-                format!("/v2/microblocks/confirmed/{}", block_hash.to_hex())
-            }
-            HttpRequestType::GetMicroblocksUnconfirmed(_md, block_hash, min_seq) => format!(
-                "/v2/microblocks/unconfirmed/{}/{}",
-                block_hash.to_hex(),
-                min_seq
+            (
+                &PATH_GETMICROBLOCKS_CONFIRMED,
+                &HttpResponseType::parse_microblocks,
             ),
-            HttpRequestType::GetTransactionUnconfirmed(_md, txid) => {
-                format!("/v2/transactions/unconfirmed/{}", txid)
-            }
-            HttpRequestType::PostTransaction(_md, ..) => "/v2/transactions".to_string(),
-            HttpRequestType::PostBlock(_md, ch, ..) => format!("/v2/blocks/upload/{}", &ch),
-            HttpRequestType::PostMicroblock(_md, _, tip_req) => format!(
-                "/v2/microblocks{}",
-                HttpRequestType::make_tip_query_string(tip_req, true)
+            (
+                &PATH_GETMICROBLOCKS_UNCONFIRMED,
+                &HttpResponseType::parse_microblocks_unconfirmed,
+            ),
+            (
+                &PATH_GETTRANSACTION_UNCONFIRMED,
+                &HttpResponseType::parse_transaction_unconfirmed,
+            ),
+            (&PATH_POSTTRANSACTION, &HttpResponseType::parse_txid),
+            (
+                &PATH_POST_SIMULATE_TRANSACTION,
+                &HttpResponseType::parse_simulate_transaction,
+            ),
+            (
+                &PATH_POST_VALIDATE_BLOCK,
+                &HttpResponseType::parse_block_validation,
+            ),
+            (
+                &PATH_POSTBLOCK,
+                &HttpResponseType::parse_stacks_block_accepted,
+            ),
+            (
+                &PATH_POSTMICROBLOCK,
+                &HttpResponseType::parse_microblock_hash,
+            ),
+            (&PATH_GET_ACCOUNT, &HttpResponseType::parse_get_account),
+            (
+                &PATH_GET_MEMPOOL_ACCOUNT,
+                &HttpResponseType::parse_get_mempool_account,
+            ),
+            (
+                &PATH_GET_ACCOUNT_NEXT_NONCE,
+                &HttpResponseType::parse_get_account_next_nonce,
+            ),
+            (
+                &PATH_GET_MEMPOOL_PREVIEW,
+                &HttpResponseType::parse_get_mempool_preview,
+            ),
+            (
+                &PATH_GET_REJECTED_TXS,
+                &HttpResponseType::parse_get_rejected_txs,
+            ),
+            (
+                &PATH_GET_MEMPOOL_STATS,
+                &HttpResponseType::parse_get_mempool_stats,
+            ),
+            (
+                &PATH_GET_WITHDRAWALS_BY_SENDER,
+                &HttpResponseType::parse_get_withdrawals_by_sender,
+            ),
+            (
+                &PATH_GET_WITHDRAWAL_CLAIM,
+                &HttpResponseType::parse_get_withdrawal_claim,
+            ),
+            (
+                &PATH_GET_FAILED_DEPOSITS_BY_SENDER,
+                &HttpResponseType::parse_get_failed_deposits_by_sender,
+            ),
+            (
+                &PATH_GET_ACCOUNT_TRANSACTIONS,
+                &HttpResponseType::parse_get_account_transactions,
+            ),
+            (&PATH_GET_BURN_OPS, &HttpResponseType::parse_get_burn_ops),
+            (&PATH_GET_FORKS, &HttpResponseType::parse_get_forks),
+            (
+                &PATH_GET_CONTRACT_COSTS,
+                &HttpResponseType::parse_get_contract_costs,
+            ),
+            (
+                &PATH_GET_BLOCK_COMMITS,
+                &HttpResponseType::parse_get_block_commits,
+            ),
+            (
+                &PATH_GET_REWARDS_REPORT,
+                &HttpResponseType::parse_get_rewards_report,
+            ),
+            (
+                &PATH_GET_EVENT_REPLAY,
+                &HttpResponseType::parse_get_event_replay,
+            ),
+            (
+                &PATH_GET_SUBNET_INFO,
+                &HttpResponseType::parse_get_subnet_info,
+            ),
+            (
+                &PATH_GET_MICROBLOCK_STREAM,
+                &HttpResponseType::parse_get_microblock_stream,
+            ),
+            (&PATH_GET_HEALTH_LIVE, &HttpResponseType::parse_health_check),
+            (
+                &PATH_GET_HEALTH_READY,
+                &HttpResponseType::parse_health_check,
+            ),
+            (
+                &PATH_GET_CONTRACT_PUBLISH_POLICY,
+                &HttpResponseType::parse_get_contract_publish_policy,
+            ),
+            (
+                &PATH_GET_SUBNET_FEE_RATE,
+                &HttpResponseType::parse_get_subnet_fee_rate,
+            ),
+            (
+                &PATH_POST_MINER_PAUSE,
+                &HttpResponseType::parse_miner_pause_status,
+            ),
+            (
+                &PATH_POST_MINER_RESUME,
+                &HttpResponseType::parse_miner_pause_status,
+            ),
+            (
+                &PATH_POST_MINER_ROTATE_KEY,
+                &HttpResponseType::parse_mining_key_rotation,
+            ),
+            (
+                &PATH_GET_MEMPOOL_FILTERS,
+                &HttpResponseType::parse_mempool_filters,
             ),
-            HttpRequestType::GetAccount(_md, principal, tip_req, with_proof) => {
-                format!(
-                    "/v2/accounts/{}{}",
-                    &principal.to_string(),
-                    HttpRequestType::make_tip_query_string(tip_req, *with_proof,)
-                )
-            }
-            HttpRequestType::GetDataVar(
-                _md,
-                contract_addr,
-                contract_name,
-                var_name,
-                tip_req,
-                with_proof,
-            ) => format!(
-                "/v2/data_var/{}/{}/{}{}",
-                &contract_addr.to_string(),
-                contract_name.as_str(),
-                var_name.as_str(),
-                HttpRequestType::make_tip_query_string(tip_req, *with_proof)
+            (
+                &PATH_POST_MEMPOOL_FILTER,
+                &HttpResponseType::parse_mempool_filter,
             ),
-            HttpRequestType::GetMapEntry(
-                _md,
-                contract_addr,
-                contract_name,
-                map_name,
-                _key,
-                tip_req,
-                with_proof,
-            ) => format!(
-                "/v2/map_entry/{}/{}/{}{}",
-                &contract_addr.to_string(),
-                contract_name.as_str(),
-                map_name.as_str(),
-                HttpRequestType::make_tip_query_string(tip_req, *with_proof)
+            (
+                &PATH_DELETE_MEMPOOL_FILTER,
+                &HttpResponseType::parse_mempool_filter,
             ),
-            HttpRequestType::GetTransferCost(_md) => "/v2/fees/transfer".into(),
-            HttpRequestType::GetContractABI(_, contract_addr, contract_name, tip_req) => format!(
-                "/v2/contracts/interface/{}/{}{}",
-                contract_addr,
-                contract_name.as_str(),
-                HttpRequestType::make_tip_query_string(tip_req, true,)
+            (
+                &PATH_GET_CONTRACT_SRC,
+                &HttpResponseType::parse_get_contract_src,
             ),
-            HttpRequestType::GetContractSrc(
-                _,
-                contract_addr,
-                contract_name,
-                tip_req,
-                with_proof,
-            ) => format!(
-                "/v2/contracts/source/{}/{}{}",
-                contract_addr,
-                contract_name.as_str(),
-                HttpRequestType::make_tip_query_string(tip_req, *with_proof)
+            (
+                &PATH_GET_IS_TRAIT_IMPLEMENTED,
+                &HttpResponseType::parse_get_is_trait_implemented,
             ),
-            HttpRequestType::GetIsTraitImplemented(
-                _,
-                contract_addr,
-                contract_name,
-                trait_id,
-                tip_req,
-            ) => format!(
-                "/v2/traits/{}/{}/{}/{}/{}{}",
-                contract_addr,
-                contract_name.as_str(),
-                trait_id.name.to_string(),
-                StacksAddress::from(trait_id.clone().contract_identifier.issuer),
-                trait_id.contract_identifier.name.as_str(),
-                HttpRequestType::make_tip_query_string(tip_req, true)
+            (
+                &PATH_GET_CONTRACT_ABI,
+                &HttpResponseType::parse_get_contract_abi,
             ),
-            HttpRequestType::CallReadOnlyFunction(
-                _,
-                contract_addr,
-                contract_name,
-                _,
-                func_name,
-                _,
-                tip_req,
-            ) => format!(
-                "/v2/contracts/call-read/{}/{}/{}{}",
-                contract_addr,
-                contract_name.as_str(),
-                func_name.as_str(),
-                HttpRequestType::make_tip_query_string(tip_req, true)
+            (
+                &PATH_POST_CALL_READ_ONLY,
+                &HttpResponseType::parse_call_read_only,
             ),
-            HttpRequestType::OptionsPreflight(_md, path) => path.to_string(),
-            HttpRequestType::GetAttachmentsInv(_md, index_block_hash, pages_indexes) => {
-                let pages_query = match pages_indexes.len() {
-                    0 => format!(""),
-                    _n => {
-                        let mut indexes = pages_indexes
-                            .iter()
-                            .map(|i| format!("{}", i))
-                            .collect::<Vec<String>>();
-                        indexes.sort();
-                        format!("&pages_indexes={}", indexes.join(","))
-                    }
-                };
-                let index_block_hash = format!("index_block_hash={}", index_block_hash);
-                format!("/v2/attachments/inv?{}{}", index_block_hash, pages_query,)
-            }
-            HttpRequestType::GetAttachment(_, content_hash) => {
-                format!("/v2/attachments/{}", to_hex(&content_hash.0[..]))
-            }
-            HttpRequestType::MemPoolQuery(_, _, page_id_opt) => match page_id_opt {
-                Some(page_id) => {
-                    format!("/v2/mempool/query?page_id={}", page_id)
-                }
-                None => "/v2/mempool/query".to_string(),
-            },
-            HttpRequestType::FeeRateEstimate(_, _, _) => self.get_path().to_string(),
-            HttpRequestType::ClientError(_md, e) => match e {
-                ClientError::NotFound(path) => path.to_string(),
-                _ => "error path unknown".into(),
-            },
-            HttpRequestType::GetWithdrawalStx {
-                metadata: _,
-                withdraw_block_height,
-                sender,
-                withdrawal_id,
-                amount,
-            } => format!(
-                "/v2/withdrawal/stx/{}/{}/{}/{}",
-                withdraw_block_height, sender, withdrawal_id, amount
+            (
+                &PATH_POST_CALL_READ_ONLY_BATCH,
+                &HttpResponseType::parse_call_read_only_batch,
             ),
-            HttpRequestType::BlockProposal(..) => self.get_path().to_string(),
-            HttpRequestType::GetWithdrawalFt {
-                metadata: _,
-                withdraw_block_height,
-                sender,
-                withdrawal_id,
-                contract_identifier,
-                amount,
-            } => format!(
-                "/v2/withdrawal/ft/{}/{}/{}/{}/{}/{}",
-                withdraw_block_height,
-                sender,
-                withdrawal_id,
-                StacksAddress::from(contract_identifier.issuer.clone()),
-                contract_identifier.name.as_str(),
-                amount
+            (
+                &PATH_GET_ATTACHMENT,
+                &HttpResponseType::parse_get_attachment,
             ),
-            HttpRequestType::GetWithdrawalNft {
-                metadata: _,
-                withdraw_block_height,
-                sender,
-                withdrawal_id,
-                contract_identifier,
-                id,
-            } => format!(
-                "/v2/withdrawal/nft/{}/{}/{}/{}/{}/{}",
-                withdraw_block_height,
-                sender,
-                withdrawal_id,
-                StacksAddress::from(contract_identifier.issuer.clone()),
-                contract_identifier.name.as_str(),
-                id
+            (
+                &PATH_GET_ATTACHMENTS_INV,
+                &HttpResponseType::parse_get_attachments_inv,
             ),
-        }
-    }
-
-    pub fn get_path(&self) -> &'static str {
-        match self {
-            HttpRequestType::GetInfo(..) => "/v2/info",
-            HttpRequestType::GetNeighbors(..) => "/v2/neighbors",
-            HttpRequestType::GetHeaders(..) => "/v2/headers/:height",
-            HttpRequestType::GetBlock(..) => "/v2/blocks/:hash",
-            HttpRequestType::GetMicroblocksIndexed(..) => "/v2/microblocks/:hash",
-            HttpRequestType::GetMicroblocksConfirmed(..) => "/v2/microblocks/confirmed/:hash",
-            HttpRequestType::GetMicroblocksUnconfirmed(..) => {
-                "/v2/microblocks/unconfirmed/:hash/:seq"
-            }
-            HttpRequestType::GetTransactionUnconfirmed(..) => "/v2/transactions/unconfirmed/:txid",
-            HttpRequestType::PostTransaction(..) => "/v2/transactions",
-            HttpRequestType::PostBlock(..) => "/v2/blocks/upload/:block",
-            HttpRequestType::PostMicroblock(..) => "/v2/microblocks",
-            HttpRequestType::GetAccount(..) => "/v2/accounts/:principal",
-            HttpRequestType::GetDataVar(..) => "/v2/data_var/:principal/:contract_name/:var_name",
-            HttpRequestType::GetMapEntry(..) => "/v2/map_entry/:principal/:contract_name/:map_name",
-            HttpRequestType::GetTransferCost(..) => "/v2/fees/transfer",
-            HttpRequestType::GetContractABI(..) => {
-                "/v2/contracts/interface/:principal/:contract_name"
-            }
-            HttpRequestType::GetContractSrc(..) => "/v2/contracts/source/:principal/:contract_name",
-            HttpRequestType::CallReadOnlyFunction(..) => {
-                "/v2/contracts/call-read/:principal/:contract_name/:func_name"
-            }
-            HttpRequestType::GetAttachmentsInv(..) => "/v2/attachments/inv",
-            HttpRequestType::GetAttachment(..) => "/v2/attachments/:hash",
-            HttpRequestType::GetIsTraitImplemented(..) => "/v2/traits/:principal/:contract_name",
-            HttpRequestType::MemPoolQuery(..) => "/v2/mempool/query",
-            HttpRequestType::FeeRateEstimate(_, _, _) => "/v2/fees/transaction",
-            HttpRequestType::OptionsPreflight(..) | HttpRequestType::ClientError(..) => "/",
-            HttpRequestType::GetWithdrawalStx { .. } => {
-                "/v2/withdrawal/stx/:block-height/:sender/:withdrawal_id/:amount"
-            }
-            HttpRequestType::BlockProposal(..) => PATH_STR_POST_BLOCK_PROPOSAL,
-            HttpRequestType::GetWithdrawalFt { .. } => {
-                "/v2/withdrawal/ft/:block-height/:sender/:withdrawal_id/:contract_address/:contract_name/:amount"
-            }
-            HttpRequestType::GetWithdrawalNft { .. } => {
-                "/v2/withdrawal/nft/:block-height/:sender/:withdrawal_id/:contract_address/:contract_name/:id"
-            }
-        }
-    }
-
-    pub fn send<W: Write>(&self, _protocol: &mut StacksHttp, fd: &mut W) -> Result<(), net_error> {
-        match self {
-            HttpRequestType::PostTransaction(md, tx, attachment) => {
-                let mut tx_bytes = vec![];
-                write_next(&mut tx_bytes, tx)?;
-                let tx_hex = to_hex(&tx_bytes[..]);
+            (
+                &PATH_POST_MEMPOOL_QUERY,
+                &HttpResponseType::parse_post_mempool_query,
+            ),
+        ];
 
-                let (content_type, request_body_bytes) = match attachment {
-                    None => {
-                        // Transaction does not include an attachment: HttpContentType::Bytes (more compressed)
-                        (Some(&HttpContentType::Bytes), tx_bytes)
-                    }
-                    Some(attachment) => {
-                        // Transaction is including an attachment: HttpContentType::JSON
-                        let request_body = PostTransactionRequestBody {
-                            tx: tx_hex,
-                            attachment: Some(to_hex(&attachment.content[..])),
-                        };
+        // use url::Url to parse path and query string
+        //   Url will refuse to parse just a path, so create a dummy URL
+        let local_url = format!("http://local{}", &request_path);
+        let url = Url::parse(&local_url).map_err(|_e| {
+            net_error::DeserializeError("Http request path could not be parsed".to_string())
+        })?;
 
-                        let mut request_body_bytes = vec![];
-                        serde_json::to_writer(&mut request_body_bytes, &request_body).map_err(
-                            |e| {
-                                net_error::SerializeError(format!(
-                                    "Failed to serialize read-only call to JSON: {e:?}"
-                                ))
-                            },
-                        )?;
-                        (Some(&HttpContentType::JSON), request_body_bytes)
-                    }
-                };
+        let decoded_path = percent_decode_str(url.path()).decode_utf8().map_err(|_e| {
+            net_error::DeserializeError(
+                "Http response path could not be parsed as UTF-8".to_string(),
+            )
+        })?;
 
-                HttpRequestPreamble::new_serialized(
-                    fd,
-                    &md.version,
-                    "POST",
-                    &self.request_path(),
-                    &md.peer,
-                    md.keep_alive,
-                    Some(request_body_bytes.len() as u32),
-                    content_type,
-                    |fd| stacks_height_headers(fd, md),
-                )?;
-                fd.write_all(&request_body_bytes)
-                    .map_err(net_error::WriteError)?;
+        for (regex, parser) in RESPONSE_METHODS.iter() {
+            match HttpResponseType::try_parse(
+                protocol,
+                regex,
+                request_version,
+                preamble,
+                &decoded_path.to_string(),
+                fd,
+                len_hint,
+                parser,
+            ) {
+                Ok(Some(request)) => {
+                    return Ok(request);
+                }
+                Ok(None) => {
+                    continue;
+                }
+                Err(e) => {
+                    test_debug!("Failed to parse {}: {:?}", &request_path, &e);
+                    return Err(e);
+                }
             }
-            HttpRequestType::PostBlock(md, _ch, block) => {
-                let mut block_bytes = vec![];
-                write_next(&mut block_bytes, block)?;
+        }
 
-                HttpRequestPreamble::new_serialized(
-                    fd,
-                    &md.version,
-                    "POST",
-                    &self.request_path(),
-                    &md.peer,
-                    md.keep_alive,
-                    Some(block_bytes.len() as u32),
-                    Some(&HttpContentType::Bytes),
-                    |fd| stacks_height_headers(fd, md),
-                )?;
-                fd.write_all(&block_bytes).map_err(net_error::WriteError)?;
-            }
-            HttpRequestType::PostMicroblock(md, mb, ..) => {
-                let mut mb_bytes = vec![];
-                write_next(&mut mb_bytes, mb)?;
+        test_debug!(
+            "Failed to match request path '{}' to a handler",
+            &request_path
+        );
+        return Err(net_error::DeserializeError(
+            "Http response could not be parsed".to_string(),
+        ));
+    }
 
-                HttpRequestPreamble::new_serialized(
-                    fd,
-                    &md.version,
-                    "POST",
-                    &self.request_path(),
-                    &md.peer,
-                    md.keep_alive,
-                    Some(mb_bytes.len() as u32),
-                    Some(&HttpContentType::Bytes),
-                    |fd| stacks_height_headers(fd, md),
-                )?;
-                fd.write_all(&mb_bytes).map_err(net_error::WriteError)?;
-            }
-            HttpRequestType::GetMapEntry(
-                md,
-                _contract_addr,
-                _contract_name,
-                _map_name,
-                key,
-                ..,
-            ) => {
-                let mut request_bytes = vec![];
-                key.serialize_write(&mut request_bytes)
-                    .map_err(net_error::WriteError)?;
-                let request_json = format!("\"{}\"", to_hex(&request_bytes));
+    fn parse_peerinfo<R: Read>(
+        _protocol: &mut StacksHttp,
+        request_version: HttpVersion,
+        preamble: &HttpResponsePreamble,
+        fd: &mut R,
+        len_hint: Option<usize>,
+    ) -> Result<HttpResponseType, net_error> {
+        let peer_info =
+            HttpResponseType::parse_json(preamble, fd, len_hint, MAX_MESSAGE_LEN as u64)?;
+        Ok(HttpResponseType::PeerInfo(
+            HttpResponseMetadata::from_preamble(request_version, preamble),
+            peer_info,
+        ))
+    }
 
-                HttpRequestPreamble::new_serialized(
-                    fd,
-                    &md.version,
-                    "POST",
-                    &self.request_path(),
-                    &md.peer,
-                    md.keep_alive,
-                    Some(request_json.as_bytes().len() as u32),
-                    Some(&HttpContentType::JSON),
-                    |fd| stacks_height_headers(fd, md),
-                )?;
-                fd.write_all(&request_json.as_bytes())
-                    .map_err(net_error::WriteError)?;
-            }
-            HttpRequestType::CallReadOnlyFunction(
-                md,
-                _contract_addr,
-                _contract_name,
-                sender,
-                _func_name,
-                func_args,
-                ..,
-            ) => {
-                let mut args = vec![];
-                for arg in func_args.iter() {
-                    let mut arg_bytes = vec![];
-                    arg.serialize_write(&mut arg_bytes)
-                        .map_err(net_error::WriteError)?;
-                    args.push(to_hex(&arg_bytes));
-                }
+    fn parse_poxinfo<R: Read>(
+        _protocol: &mut StacksHttp,
+        request_version: HttpVersion,
+        preamble: &HttpResponsePreamble,
+        fd: &mut R,
+        len_hint: Option<usize>,
+    ) -> Result<HttpResponseType, net_error> {
+        let pox_info =
+            HttpResponseType::parse_json(preamble, fd, len_hint, MAX_MESSAGE_LEN as u64)?;
+        Ok(HttpResponseType::PoxInfo(
+            HttpResponseMetadata::from_preamble(request_version, preamble),
+            pox_info,
+        ))
+    }
 
-                let request_body = CallReadOnlyRequestBody {
-                    sender: sender.to_string(),
-                    arguments: args,
-                };
+    fn parse_neighbors<R: Read>(
+        _protocol: &mut StacksHttp,
+        request_version: HttpVersion,
+        preamble: &HttpResponsePreamble,
+        fd: &mut R,
+        len_hint: Option<usize>,
+    ) -> Result<HttpResponseType, net_error> {
+        let neighbors_data =
+            HttpResponseType::parse_json(preamble, fd, len_hint, MAX_MESSAGE_LEN as u64)?;
+        Ok(HttpResponseType::Neighbors(
+            HttpResponseMetadata::from_preamble(request_version, preamble),
+            neighbors_data,
+        ))
+    }
 
-                let mut request_body_bytes = vec![];
-                serde_json::to_writer(&mut request_body_bytes, &request_body).map_err(|e| {
-                    net_error::SerializeError(format!(
-                        "Failed to serialize read-only call to JSON: {e:?}"
-                    ))
-                })?;
+    fn parse_headers<R: Read>(
+        _protocol: &mut StacksHttp,
+        request_version: HttpVersion,
+        preamble: &HttpResponsePreamble,
+        fd: &mut R,
+        len_hint: Option<usize>,
+    ) -> Result<HttpResponseType, net_error> {
+        let headers: Vec<ExtendedStacksHeader> =
+            HttpResponseType::parse_json(preamble, fd, len_hint, MAX_MESSAGE_LEN as u64)?;
+        Ok(HttpResponseType::Headers(
+            HttpResponseMetadata::from_preamble(request_version, preamble),
+            headers,
+        ))
+    }
 
-                HttpRequestPreamble::new_serialized(
-                    fd,
-                    &md.version,
-                    "POST",
-                    &self.request_path(),
-                    &md.peer,
-                    md.keep_alive,
-                    Some(request_body_bytes.len() as u32),
-                    Some(&HttpContentType::JSON),
-                    |fd| stacks_height_headers(fd, md),
-                )?;
-                fd.write_all(&request_body_bytes)
-                    .map_err(net_error::WriteError)?;
-            }
-            HttpRequestType::MemPoolQuery(md, query, ..) => {
-                let request_body_bytes = query.serialize_to_vec();
-                HttpRequestPreamble::new_serialized(
-                    fd,
-                    &md.version,
-                    "POST",
-                    &self.request_path(),
-                    &md.peer,
-                    md.keep_alive,
-                    Some(request_body_bytes.len() as u32),
-                    Some(&HttpContentType::Bytes),
-                    empty_headers,
-                )?;
-                fd.write_all(&request_body_bytes)
-                    .map_err(net_error::WriteError)?;
-            }
-            other_type => {
-                let md = other_type.metadata();
-                let request_path = other_type.request_path();
-                HttpRequestPreamble::new_serialized(
-                    fd,
-                    &md.version,
-                    "GET",
-                    &request_path,
-                    &md.peer,
-                    md.keep_alive,
-                    None,
-                    None,
-                    |fd| stacks_height_headers(fd, md),
-                )?;
-            }
-        }
-        Ok(())
+    fn parse_block<R: Read>(
+        _protocol: &mut StacksHttp,
+        request_version: HttpVersion,
+        preamble: &HttpResponsePreamble,
+        fd: &mut R,
+        len_hint: Option<usize>,
+    ) -> Result<HttpResponseType, net_error> {
+        let block: StacksBlock =
+            HttpResponseType::parse_bytestream(preamble, fd, len_hint, MAX_MESSAGE_LEN as u64)?;
+        Ok(HttpResponseType::Block(
+            HttpResponseMetadata::from_preamble(request_version, preamble),
+            block,
+        ))
+    }
+
+    fn parse_microblocks<R: Read>(
+        _protocol: &mut StacksHttp,
+        request_version: HttpVersion,
+        preamble: &HttpResponsePreamble,
+        fd: &mut R,
+        len_hint: Option<usize>,
+    ) -> Result<HttpResponseType, net_error> {
+        let microblocks: Vec<StacksMicroblock> =
+            HttpResponseType::parse_bytestream(preamble, fd, len_hint, MAX_MESSAGE_LEN as u64)?;
+        Ok(HttpResponseType::Microblocks(
+            HttpResponseMetadata::from_preamble(request_version, preamble),
+            microblocks,
+        ))
     }
-}
 
-impl HttpResponseType {
-    fn try_parse<R: Read, F>(
-        protocol: &mut StacksHttp,
-        regex: &Regex,
+    fn parse_get_account<R: Read>(
+        _protocol: &mut StacksHttp,
         request_version: HttpVersion,
         preamble: &HttpResponsePreamble,
-        request_path: &String,
         fd: &mut R,
         len_hint: Option<usize>,
-        parser: F,
-    ) -> Result<Option<HttpResponseType>, net_error>
-    where
-        F: Fn(
-            &mut StacksHttp,
-            HttpVersion,
-            &HttpResponsePreamble,
-            &mut R,
-            Option<usize>,
-        ) -> Result<HttpResponseType, net_error>,
-    {
-        if regex.is_match(request_path) {
-            let payload = parser(protocol, request_version, preamble, fd, len_hint)?;
-            Ok(Some(payload))
-        } else {
-            Ok(None)
-        }
+    ) -> Result<HttpResponseType, net_error> {
+        let account_entry =
+            HttpResponseType::parse_json(preamble, fd, len_hint, MAX_MESSAGE_LEN as u64)?;
+        Ok(HttpResponseType::GetAccount(
+            HttpResponseMetadata::from_preamble(request_version, preamble),
+            account_entry,
+        ))
     }
 
-    fn parse_error<R: Read>(
+    fn parse_get_mempool_account<R: Read>(
         _protocol: &mut StacksHttp,
         request_version: HttpVersion,
         preamble: &HttpResponsePreamble,
         fd: &mut R,
+        len_hint: Option<usize>,
     ) -> Result<HttpResponseType, net_error> {
-        if preamble.status_code < 400 || preamble.status_code > 599 {
-            return Err(net_error::DeserializeError(
-                "Inavlid response: not an error".to_string(),
-            ));
-        }
+        let mempool_account =
+            HttpResponseType::parse_json(preamble, fd, len_hint, MAX_MESSAGE_LEN as u64)?;
+        Ok(HttpResponseType::GetMempoolAccount(
+            HttpResponseMetadata::from_preamble(request_version, preamble),
+            mempool_account,
+        ))
+    }
 
-        if preamble.content_type != HttpContentType::Text
-            && preamble.content_type != HttpContentType::JSON
-        {
-            return Err(net_error::DeserializeError(format!(
-                "Invalid error response: expected text/plain or application/json, got {:?}",
-                &preamble.content_type
-            )));
-        }
+    fn parse_get_account_next_nonce<R: Read>(
+        _protocol: &mut StacksHttp,
+        request_version: HttpVersion,
+        preamble: &HttpResponsePreamble,
+        fd: &mut R,
+        len_hint: Option<usize>,
+    ) -> Result<HttpResponseType, net_error> {
+        let next_nonce =
+            HttpResponseType::parse_json(preamble, fd, len_hint, MAX_MESSAGE_LEN as u64)?;
+        Ok(HttpResponseType::GetAccountNextNonce(
+            HttpResponseMetadata::from_preamble(request_version, preamble),
+            next_nonce,
+        ))
+    }
 
-        let mut error_text = String::new();
-        fd.read_to_string(&mut error_text)
-            .map_err(net_error::ReadError)?;
+    fn parse_get_withdrawals_by_sender<R: Read>(
+        _protocol: &mut StacksHttp,
+        request_version: HttpVersion,
+        preamble: &HttpResponsePreamble,
+        fd: &mut R,
+        len_hint: Option<usize>,
+    ) -> Result<HttpResponseType, net_error> {
+        let withdrawals =
+            HttpResponseType::parse_json(preamble, fd, len_hint, MAX_MESSAGE_LEN as u64)?;
+        Ok(HttpResponseType::GetWithdrawalsBySender(
+            HttpResponseMetadata::from_preamble(request_version, preamble),
+            withdrawals,
+        ))
+    }
 
-        let md = HttpResponseMetadata::from_preamble(request_version, preamble);
-        let resp = match preamble.status_code {
-            400 => HttpResponseType::BadRequest(md, error_text),
-            401 => HttpResponseType::Unauthorized(md, error_text),
-            402 => HttpResponseType::PaymentRequired(md, error_text),
-            403 => HttpResponseType::Forbidden(md, error_text),
-            404 => HttpResponseType::NotFound(md, error_text),
-            500 => HttpResponseType::ServerError(md, error_text),
-            503 => HttpResponseType::ServiceUnavailable(md, error_text),
-            _ => HttpResponseType::Error(md, preamble.status_code, error_text),
-        };
-        Ok(resp)
+    fn parse_get_withdrawal_claim<R: Read>(
+        _protocol: &mut StacksHttp,
+        request_version: HttpVersion,
+        preamble: &HttpResponsePreamble,
+        fd: &mut R,
+        len_hint: Option<usize>,
+    ) -> Result<HttpResponseType, net_error> {
+        let claim = HttpResponseType::parse_json(preamble, fd, len_hint, MAX_MESSAGE_LEN as u64)?;
+        Ok(HttpResponseType::GetWithdrawalClaim(
+            HttpResponseMetadata::from_preamble(request_version, preamble),
+            claim,
+        ))
     }
 
-    fn parse_bytestream<R: Read, T: StacksMessageCodec>(
+    fn parse_get_failed_deposits_by_sender<R: Read>(
+        _protocol: &mut StacksHttp,
+        request_version: HttpVersion,
         preamble: &HttpResponsePreamble,
         fd: &mut R,
         len_hint: Option<usize>,
-        max_len: u64,
-    ) -> Result<T, net_error> {
-        // content-type has to be Bytes
-        if preamble.content_type != HttpContentType::Bytes {
-            return Err(net_error::DeserializeError(
-                "Invalid content-type: expected application/octet-stream".to_string(),
-            ));
-        }
+    ) -> Result<HttpResponseType, net_error> {
+        let failed_deposits =
+            HttpResponseType::parse_json(preamble, fd, len_hint, MAX_MESSAGE_LEN as u64)?;
+        Ok(HttpResponseType::GetFailedDepositsBySender(
+            HttpResponseMetadata::from_preamble(request_version, preamble),
+            failed_deposits,
+        ))
+    }
 
-        let item: T = if preamble.is_chunked() && len_hint.is_none() {
-            let mut chunked_fd = HttpChunkedTransferReader::from_reader(fd, max_len);
-            read_next(&mut chunked_fd)?
-        } else {
-            let content_length_opt = match (preamble.content_length, len_hint) {
-                (Some(l), _) => Some(l as u32),
-                (None, Some(l)) => Some(l as u32),
-                (None, None) => None,
-            };
-            if let Some(content_length) = content_length_opt {
-                if (content_length as u64) > max_len {
-                    return Err(net_error::DeserializeError(
-                        "Invalid Content-Length header: too long".to_string(),
-                    ));
-                }
+    fn parse_get_account_transactions<R: Read>(
+        _protocol: &mut StacksHttp,
+        request_version: HttpVersion,
+        preamble: &HttpResponsePreamble,
+        fd: &mut R,
+        len_hint: Option<usize>,
+    ) -> Result<HttpResponseType, net_error> {
+        let transactions =
+            HttpResponseType::parse_json(preamble, fd, len_hint, MAX_MESSAGE_LEN as u64)?;
+        Ok(HttpResponseType::GetAccountTransactions(
+            HttpResponseMetadata::from_preamble(request_version, preamble),
+            transactions,
+        ))
+    }
 
-                let mut bound_fd = BoundReader::from_reader(fd, content_length as u64);
-                read_next(&mut bound_fd)?
-            } else {
-                // unsupported headers
-                trace!("preamble: {:?}", preamble);
-                return Err(net_error::DeserializeError(
-                    "Invalid headers: need either Transfer-Encoding or Content-Length".to_string(),
-                ));
-            }
-        };
+    fn parse_get_burn_ops<R: Read>(
+        _protocol: &mut StacksHttp,
+        request_version: HttpVersion,
+        preamble: &HttpResponsePreamble,
+        fd: &mut R,
+        len_hint: Option<usize>,
+    ) -> Result<HttpResponseType, net_error> {
+        let burn_ops =
+            HttpResponseType::parse_json(preamble, fd, len_hint, MAX_MESSAGE_LEN as u64)?;
+        Ok(HttpResponseType::GetBurnOps(
+            HttpResponseMetadata::from_preamble(request_version, preamble),
+            burn_ops,
+        ))
+    }
 
-        Ok(item)
+    fn parse_get_forks<R: Read>(
+        _protocol: &mut StacksHttp,
+        request_version: HttpVersion,
+        preamble: &HttpResponsePreamble,
+        fd: &mut R,
+        len_hint: Option<usize>,
+    ) -> Result<HttpResponseType, net_error> {
+        let fork_tree =
+            HttpResponseType::parse_json(preamble, fd, len_hint, MAX_MESSAGE_LEN as u64)?;
+        Ok(HttpResponseType::GetForks(
+            HttpResponseMetadata::from_preamble(request_version, preamble),
+            fork_tree,
+        ))
     }
 
-    fn parse_json<R: Read, T: serde::de::DeserializeOwned>(
+    fn parse_get_contract_costs<R: Read>(
+        _protocol: &mut StacksHttp,
+        request_version: HttpVersion,
         preamble: &HttpResponsePreamble,
         fd: &mut R,
         len_hint: Option<usize>,
-        max_len: u64,
-    ) -> Result<T, net_error> {
-        // content-type has to be JSON
-        if preamble.content_type != HttpContentType::JSON {
-            return Err(net_error::DeserializeError(
-                "Invalid content-type: expected application/json".to_string(),
-            ));
-        }
+    ) -> Result<HttpResponseType, net_error> {
+        let contract_costs =
+            HttpResponseType::parse_json(preamble, fd, len_hint, MAX_MESSAGE_LEN as u64)?;
+        Ok(HttpResponseType::GetContractCosts(
+            HttpResponseMetadata::from_preamble(request_version, preamble),
+            contract_costs,
+        ))
+    }
 
-        let item_result: Result<T, serde_json::Error> = if preamble.is_chunked()
-            && len_hint.is_none()
-        {
-            let chunked_fd = HttpChunkedTransferReader::from_reader(fd, max_len);
-            serde_json::from_reader(chunked_fd)
-        } else {
-            let content_length_opt = match (preamble.content_length, len_hint) {
-                (Some(l), _) => Some(l as u32),
-                (None, Some(l)) => Some(l as u32),
-                (None, None) => None,
-            };
-            if let Some(content_length) = content_length_opt {
-                if (content_length as u64) > max_len {
-                    return Err(net_error::DeserializeError(
-                        "Invalid Content-Length header: too long".to_string(),
-                    ));
-                }
-                let bound_fd = BoundReader::from_reader(fd, content_length as u64);
-                serde_json::from_reader(bound_fd)
-            } else {
-                // unsupported headers
-                trace!("preamble: {:?}", preamble);
-                return Err(net_error::DeserializeError(
-                    "Invalid headers: need either Transfer-Encoding or Content-Length".to_string(),
-                ));
-            }
-        };
+    fn parse_get_block_commits<R: Read>(
+        _protocol: &mut StacksHttp,
+        request_version: HttpVersion,
+        preamble: &HttpResponsePreamble,
+        fd: &mut R,
+        len_hint: Option<usize>,
+    ) -> Result<HttpResponseType, net_error> {
+        let block_commits =
+            HttpResponseType::parse_json(preamble, fd, len_hint, MAX_MESSAGE_LEN as u64)?;
+        Ok(HttpResponseType::GetBlockCommits(
+            HttpResponseMetadata::from_preamble(request_version, preamble),
+            block_commits,
+        ))
+    }
 
-        item_result.map_err(|e| {
-            if e.is_eof() {
-                net_error::UnderflowError(format!("Not enough bytes to parse JSON"))
-            } else {
-                net_error::DeserializeError(format!("Failed to parse JSON: {:?}", &e))
-            }
-        })
+    fn parse_get_rewards_report<R: Read>(
+        _protocol: &mut StacksHttp,
+        request_version: HttpVersion,
+        preamble: &HttpResponsePreamble,
+        fd: &mut R,
+        len_hint: Option<usize>,
+    ) -> Result<HttpResponseType, net_error> {
+        let rewards_report =
+            HttpResponseType::parse_json(preamble, fd, len_hint, MAX_MESSAGE_LEN as u64)?;
+        Ok(HttpResponseType::GetRewardsReport(
+            HttpResponseMetadata::from_preamble(request_version, preamble),
+            rewards_report,
+        ))
+    }
+
+    fn parse_get_event_replay<R: Read>(
+        _protocol: &mut StacksHttp,
+        request_version: HttpVersion,
+        preamble: &HttpResponsePreamble,
+        fd: &mut R,
+        len_hint: Option<usize>,
+    ) -> Result<HttpResponseType, net_error> {
+        let event_replay =
+            HttpResponseType::parse_json(preamble, fd, len_hint, MAX_MESSAGE_LEN as u64)?;
+        Ok(HttpResponseType::GetEventReplay(
+            HttpResponseMetadata::from_preamble(request_version, preamble),
+            event_replay,
+        ))
+    }
+
+    fn parse_get_microblock_stream<R: Read>(
+        _protocol: &mut StacksHttp,
+        request_version: HttpVersion,
+        preamble: &HttpResponsePreamble,
+        fd: &mut R,
+        len_hint: Option<usize>,
+    ) -> Result<HttpResponseType, net_error> {
+        let stream = HttpResponseType::parse_json(preamble, fd, len_hint, MAX_MESSAGE_LEN as u64)?;
+        Ok(HttpResponseType::GetMicroblockStream(
+            HttpResponseMetadata::from_preamble(request_version, preamble),
+            stream,
+        ))
     }
 
-    fn parse_text<R: Read>(
+    fn parse_get_subnet_info<R: Read>(
+        _protocol: &mut StacksHttp,
+        request_version: HttpVersion,
         preamble: &HttpResponsePreamble,
         fd: &mut R,
         len_hint: Option<usize>,
-        max_len: u64,
-    ) -> Result<Vec<u8>, net_error> {
-        // content-type has to be text/plain
-        if preamble.content_type != HttpContentType::Text {
-            return Err(net_error::DeserializeError(
-                "Invalid content-type: expected text/plain".to_string(),
-            ));
-        }
-        let buf = if preamble.is_chunked() && len_hint.is_none() {
-            let mut chunked_fd = HttpChunkedTransferReader::from_reader(fd, max_len);
-            let mut buf = vec![];
-            chunked_fd
-                .read_to_end(&mut buf)
-                .map_err(net_error::ReadError)?;
-            buf
-        } else {
-            let content_length_opt = match (preamble.content_length, len_hint) {
-                (Some(l), _) => Some(l as u32),
-                (None, Some(l)) => Some(l as u32),
-                (None, None) => None,
-            };
-            if let Some(len) = content_length_opt {
-                let mut buf = vec![0u8; len as usize];
-                fd.read_exact(&mut buf).map_err(net_error::ReadError)?;
-                buf
-            } else {
-                // unsupported headers
-                trace!("preamble: {:?}", preamble);
-                return Err(net_error::DeserializeError(
-                    "Invalid headers: need either Transfer-Encoding or Content-Length".to_string(),
-                ));
-            }
-        };
-
-        Ok(buf)
+    ) -> Result<HttpResponseType, net_error> {
+        let info = HttpResponseType::parse_json(preamble, fd, len_hint, MAX_MESSAGE_LEN as u64)?;
+        Ok(HttpResponseType::GetSubnetInfo(
+            HttpResponseMetadata::from_preamble(request_version, preamble),
+            info,
+        ))
     }
 
-    // len_hint is given by the StacksHttp protocol implementation
-    pub fn parse<R: Read>(
-        protocol: &mut StacksHttp,
+    fn parse_health_check<R: Read>(
+        _protocol: &mut StacksHttp,
         request_version: HttpVersion,
         preamble: &HttpResponsePreamble,
-        request_path: String,
         fd: &mut R,
         len_hint: Option<usize>,
     ) -> Result<HttpResponseType, net_error> {
-        if preamble.status_code >= 400 {
-            return HttpResponseType::parse_error(protocol, request_version, preamble, fd);
-        }
-
-        // TODO: make this static somehow
-        let RESPONSE_METHODS: &[(
-            &Regex,
-            &dyn Fn(
-                &mut StacksHttp,
-                HttpVersion,
-                &HttpResponsePreamble,
-                &mut R,
-                Option<usize>,
-            ) -> Result<HttpResponseType, net_error>,
-        )] = &[
-            (&PATH_GETINFO, &HttpResponseType::parse_peerinfo),
-            (&PATH_GETPOXINFO, &HttpResponseType::parse_poxinfo),
-            (&PATH_GETNEIGHBORS, &HttpResponseType::parse_neighbors),
-            (&PATH_GETHEADERS, &HttpResponseType::parse_headers),
-            (&PATH_GETBLOCK, &HttpResponseType::parse_block),
-            (&PATH_GET_DATA_VAR, &HttpResponseType::parse_get_data_var),
-            (&PATH_GET_MAP_ENTRY, &HttpResponseType::parse_get_map_entry),
-            (
-                &PATH_GETMICROBLOCKS_INDEXED,
-                &HttpResponseType::parse_microblocks,
-            ),
-            (
-                &PATH_GETMICROBLOCKS_CONFIRMED,
-                &HttpResponseType::parse_microblocks,
-            ),
-            (
-                &PATH_GETMICROBLOCKS_UNCONFIRMED,
-                &HttpResponseType::parse_microblocks_unconfirmed,
-            ),
-            (
-                &PATH_GETTRANSACTION_UNCONFIRMED,
-                &HttpResponseType::parse_transaction_unconfirmed,
-            ),
-            (&PATH_POSTTRANSACTION, &HttpResponseType::parse_txid),
-            (
-                &PATH_POSTBLOCK,
-                &HttpResponseType::parse_stacks_block_accepted,
-            ),
-            (
-                &PATH_POSTMICROBLOCK,
-                &HttpResponseType::parse_microblock_hash,
-            ),
-            (&PATH_GET_ACCOUNT, &HttpResponseType::parse_get_account),
-            (
-                &PATH_GET_CONTRACT_SRC,
-                &HttpResponseType::parse_get_contract_src,
-            ),
-            (
-                &PATH_GET_IS_TRAIT_IMPLEMENTED,
-                &HttpResponseType::parse_get_is_trait_implemented,
-            ),
-            (
-                &PATH_GET_CONTRACT_ABI,
-                &HttpResponseType::parse_get_contract_abi,
-            ),
-            (
-                &PATH_POST_CALL_READ_ONLY,
-                &HttpResponseType::parse_call_read_only,
-            ),
-            (
-                &PATH_GET_ATTACHMENT,
-                &HttpResponseType::parse_get_attachment,
-            ),
-            (
-                &PATH_GET_ATTACHMENTS_INV,
-                &HttpResponseType::parse_get_attachments_inv,
-            ),
-            (
-                &PATH_POST_MEMPOOL_QUERY,
-                &HttpResponseType::parse_post_mempool_query,
-            ),
-        ];
-
-        // use url::Url to parse path and query string
-        //   Url will refuse to parse just a path, so create a dummy URL
-        let local_url = format!("http://local{}", &request_path);
-        let url = Url::parse(&local_url).map_err(|_e| {
-            net_error::DeserializeError("Http request path could not be parsed".to_string())
-        })?;
+        let health = HttpResponseType::parse_json(preamble, fd, len_hint, MAX_MESSAGE_LEN as u64)?;
+        Ok(HttpResponseType::HealthCheck(
+            HttpResponseMetadata::from_preamble(request_version, preamble),
+            health,
+        ))
+    }
 
-        let decoded_path = percent_decode_str(url.path()).decode_utf8().map_err(|_e| {
-            net_error::DeserializeError(
-                "Http response path could not be parsed as UTF-8".to_string(),
-            )
-        })?;
+    fn parse_get_mempool_preview<R: Read>(
+        _protocol: &mut StacksHttp,
+        request_version: HttpVersion,
+        preamble: &HttpResponsePreamble,
+        fd: &mut R,
+        len_hint: Option<usize>,
+    ) -> Result<HttpResponseType, net_error> {
+        let preview = HttpResponseType::parse_json(preamble, fd, len_hint, MAX_MESSAGE_LEN as u64)?;
+        Ok(HttpResponseType::GetMempoolPreview(
+            HttpResponseMetadata::from_preamble(request_version, preamble),
+            preview,
+        ))
+    }
 
-        for (regex, parser) in RESPONSE_METHODS.iter() {
-            match HttpResponseType::try_parse(
-                protocol,
-                regex,
-                request_version,
-                preamble,
-                &decoded_path.to_string(),
-                fd,
-                len_hint,
-                parser,
-            ) {
-                Ok(Some(request)) => {
-                    return Ok(request);
-                }
-                Ok(None) => {
-                    continue;
-                }
-                Err(e) => {
-                    test_debug!("Failed to parse {}: {:?}", &request_path, &e);
-                    return Err(e);
-                }
-            }
-        }
+    fn parse_get_rejected_txs<R: Read>(
+        _protocol: &mut StacksHttp,
+        request_version: HttpVersion,
+        preamble: &HttpResponsePreamble,
+        fd: &mut R,
+        len_hint: Option<usize>,
+    ) -> Result<HttpResponseType, net_error> {
+        let rejected_txs =
+            HttpResponseType::parse_json(preamble, fd, len_hint, MAX_MESSAGE_LEN as u64)?;
+        Ok(HttpResponseType::GetRejectedTxs(
+            HttpResponseMetadata::from_preamble(request_version, preamble),
+            rejected_txs,
+        ))
+    }
 
-        test_debug!(
-            "Failed to match request path '{}' to a handler",
-            &request_path
-        );
-        return Err(net_error::DeserializeError(
-            "Http response could not be parsed".to_string(),
-        ));
+    fn parse_get_mempool_stats<R: Read>(
+        _protocol: &mut StacksHttp,
+        request_version: HttpVersion,
+        preamble: &HttpResponsePreamble,
+        fd: &mut R,
+        len_hint: Option<usize>,
+    ) -> Result<HttpResponseType, net_error> {
+        let stats = HttpResponseType::parse_json(preamble, fd, len_hint, MAX_MESSAGE_LEN as u64)?;
+        Ok(HttpResponseType::GetMempoolStats(
+            HttpResponseMetadata::from_preamble(request_version, preamble),
+            stats,
+        ))
     }
 
-    fn parse_peerinfo<R: Read>(
+    fn parse_simulate_transaction<R: Read>(
         _protocol: &mut StacksHttp,
         request_version: HttpVersion,
         preamble: &HttpResponsePreamble,
         fd: &mut R,
         len_hint: Option<usize>,
     ) -> Result<HttpResponseType, net_error> {
-        let peer_info =
+        let simulation =
             HttpResponseType::parse_json(preamble, fd, len_hint, MAX_MESSAGE_LEN as u64)?;
-        Ok(HttpResponseType::PeerInfo(
+        Ok(HttpResponseType::SimulateTransaction(
             HttpResponseMetadata::from_preamble(request_version, preamble),
-            peer_info,
+            simulation,
         ))
     }
 
-    fn parse_poxinfo<R: Read>(
+    fn parse_block_validation<R: Read>(
         _protocol: &mut StacksHttp,
         request_version: HttpVersion,
         preamble: &HttpResponsePreamble,
         fd: &mut R,
         len_hint: Option<usize>,
     ) -> Result<HttpResponseType, net_error> {
-        let pox_info =
-            HttpResponseType::parse_json(preamble, fd, len_hint, MAX_MESSAGE_LEN as u64)?;
-        Ok(HttpResponseType::PoxInfo(
+        let verdict = HttpResponseType::parse_json(preamble, fd, len_hint, MAX_MESSAGE_LEN as u64)?;
+        Ok(HttpResponseType::BlockValidation(
             HttpResponseMetadata::from_preamble(request_version, preamble),
-            pox_info,
+            verdict,
         ))
     }
 
-    fn parse_neighbors<R: Read>(
+    fn parse_get_contract_publish_policy<R: Read>(
         _protocol: &mut StacksHttp,
         request_version: HttpVersion,
         preamble: &HttpResponsePreamble,
         fd: &mut R,
         len_hint: Option<usize>,
     ) -> Result<HttpResponseType, net_error> {
-        let neighbors_data =
-            HttpResponseType::parse_json(preamble, fd, len_hint, MAX_MESSAGE_LEN as u64)?;
-        Ok(HttpResponseType::Neighbors(
+        let policy = HttpResponseType::parse_json(preamble, fd, len_hint, MAX_MESSAGE_LEN as u64)?;
+        Ok(HttpResponseType::GetContractPublishPolicy(
             HttpResponseMetadata::from_preamble(request_version, preamble),
-            neighbors_data,
+            policy,
         ))
     }
 
-    fn parse_headers<R: Read>(
+    fn parse_get_subnet_fee_rate<R: Read>(
         _protocol: &mut StacksHttp,
         request_version: HttpVersion,
         preamble: &HttpResponsePreamble,
         fd: &mut R,
         len_hint: Option<usize>,
     ) -> Result<HttpResponseType, net_error> {
-        let headers: Vec<ExtendedStacksHeader> =
+        let fee_rate =
             HttpResponseType::parse_json(preamble, fd, len_hint, MAX_MESSAGE_LEN as u64)?;
-        Ok(HttpResponseType::Headers(
+        Ok(HttpResponseType::GetSubnetFeeRate(
             HttpResponseMetadata::from_preamble(request_version, preamble),
-            headers,
+            fee_rate,
         ))
     }
 
-    fn parse_block<R: Read>(
+    fn parse_miner_pause_status<R: Read>(
         _protocol: &mut StacksHttp,
         request_version: HttpVersion,
         preamble: &HttpResponsePreamble,
         fd: &mut R,
         len_hint: Option<usize>,
     ) -> Result<HttpResponseType, net_error> {
-        let block: StacksBlock =
-            HttpResponseType::parse_bytestream(preamble, fd, len_hint, MAX_MESSAGE_LEN as u64)?;
-        Ok(HttpResponseType::Block(
+        let status = HttpResponseType::parse_json(preamble, fd, len_hint, MAX_MESSAGE_LEN as u64)?;
+        Ok(HttpResponseType::MinerPauseStatus(
             HttpResponseMetadata::from_preamble(request_version, preamble),
-            block,
+            status,
         ))
     }
 
-    fn parse_microblocks<R: Read>(
+    fn parse_mempool_filters<R: Read>(
         _protocol: &mut StacksHttp,
         request_version: HttpVersion,
         preamble: &HttpResponsePreamble,
         fd: &mut R,
         len_hint: Option<usize>,
     ) -> Result<HttpResponseType, net_error> {
-        let microblocks: Vec<StacksMicroblock> =
-            HttpResponseType::parse_bytestream(preamble, fd, len_hint, MAX_MESSAGE_LEN as u64)?;
-        Ok(HttpResponseType::Microblocks(
+        let filters: MemPoolFiltersResponse =
+            HttpResponseType::parse_json(preamble, fd, len_hint, MAX_MESSAGE_LEN as u64)?;
+        Ok(HttpResponseType::MemPoolFilters(
             HttpResponseMetadata::from_preamble(request_version, preamble),
-            microblocks,
+            filters,
         ))
     }
 
-    fn parse_get_account<R: Read>(
+    fn parse_mempool_filter<R: Read>(
         _protocol: &mut StacksHttp,
         request_version: HttpVersion,
         preamble: &HttpResponsePreamble,
         fd: &mut R,
         len_hint: Option<usize>,
     ) -> Result<HttpResponseType, net_error> {
-        let account_entry =
+        let filter = HttpResponseType::parse_json(preamble, fd, len_hint, MAX_MESSAGE_LEN as u64)?;
+        Ok(HttpResponseType::MemPoolFilter(
+            HttpResponseMetadata::from_preamble(request_version, preamble),
+            filter,
+        ))
+    }
+
+    fn parse_mining_key_rotation<R: Read>(
+        _protocol: &mut StacksHttp,
+        request_version: HttpVersion,
+        preamble: &HttpResponsePreamble,
+        fd: &mut R,
+        len_hint: Option<usize>,
+    ) -> Result<HttpResponseType, net_error> {
+        let rotation =
             HttpResponseType::parse_json(preamble, fd, len_hint, MAX_MESSAGE_LEN as u64)?;
-        Ok(HttpResponseType::GetAccount(
+        Ok(HttpResponseType::MiningKeyRotation(
             HttpResponseMetadata::from_preamble(request_version, preamble),
-            account_entry,
+            rotation,
         ))
     }
 
@@ -3869,11 +6033,26 @@ impl HttpResponseType {
         let abi = HttpResponseType::parse_json(preamble, fd, len_hint, MAX_MESSAGE_LEN as u64)?;
         Ok(HttpResponseType::GetContractABI(
             HttpResponseMetadata::from_preamble(request_version, preamble),
-            abi,
+            abi,
+        ))
+    }
+
+    fn parse_call_read_only<R: Read>(
+        _protocol: &mut StacksHttp,
+        request_version: HttpVersion,
+        preamble: &HttpResponsePreamble,
+        fd: &mut R,
+        len_hint: Option<usize>,
+    ) -> Result<HttpResponseType, net_error> {
+        let call_data =
+            HttpResponseType::parse_json(preamble, fd, len_hint, MAX_MESSAGE_LEN as u64)?;
+        Ok(HttpResponseType::CallReadOnlyFunction(
+            HttpResponseMetadata::from_preamble(request_version, preamble),
+            call_data,
         ))
     }
 
-    fn parse_call_read_only<R: Read>(
+    fn parse_call_read_only_batch<R: Read>(
         _protocol: &mut StacksHttp,
         request_version: HttpVersion,
         preamble: &HttpResponsePreamble,
@@ -3882,7 +6061,7 @@ impl HttpResponseType {
     ) -> Result<HttpResponseType, net_error> {
         let call_data =
             HttpResponseType::parse_json(preamble, fd, len_hint, MAX_MESSAGE_LEN as u64)?;
-        Ok(HttpResponseType::CallReadOnlyFunction(
+        Ok(HttpResponseType::CallReadOnlyFunctionBatch(
             HttpResponseMetadata::from_preamble(request_version, preamble),
             call_data,
         ))
@@ -4189,6 +6368,7 @@ impl HttpResponseType {
             403 => "Forbidden",
             404 => "Not Found",
             406 => "Not Acceptable",
+            429 => "Too Many Requests",
             500 => "Internal Server Error",
             503 => "Service Temporarily Unavailable",
             _ => "Error",
@@ -4238,6 +6418,7 @@ impl HttpResponseType {
             HttpResponseType::GetContractSrc(md, _) => md,
             HttpResponseType::GetIsTraitImplemented(md, _) => md,
             HttpResponseType::CallReadOnlyFunction(md, _) => md,
+            HttpResponseType::CallReadOnlyFunctionBatch(md, _) => md,
             HttpResponseType::UnconfirmedTransaction(md, _) => md,
             HttpResponseType::GetAttachment(md, _) => md,
             HttpResponseType::GetAttachmentsInv(md, _) => md,
@@ -4246,6 +6427,32 @@ impl HttpResponseType {
             HttpResponseType::OptionsPreflight(md) => md,
             HttpResponseType::TransactionFeeEstimation(md, _) => md,
             HttpResponseType::GetWithdrawal(md, _) => md,
+            HttpResponseType::GetWithdrawalsBySender(md, _) => md,
+            HttpResponseType::GetWithdrawalClaim(md, _) => md,
+            HttpResponseType::GetFailedDepositsBySender(md, _) => md,
+            HttpResponseType::GetAccountTransactions(md, _) => md,
+            HttpResponseType::GetBurnOps(md, _) => md,
+            HttpResponseType::GetForks(md, _) => md,
+            HttpResponseType::GetContractCosts(md, _) => md,
+            HttpResponseType::GetBlockCommits(md, _) => md,
+            HttpResponseType::GetRewardsReport(md, _) => md,
+            HttpResponseType::GetEventReplay(md, _) => md,
+            HttpResponseType::GetSubnetInfo(md, _) => md,
+            HttpResponseType::GetMicroblockStream(md, _) => md,
+            HttpResponseType::HealthCheck(md, _) => md,
+            HttpResponseType::GetMempoolPreview(md, _) => md,
+            HttpResponseType::GetRejectedTxs(md, _) => md,
+            HttpResponseType::GetMempoolStats(md, _) => md,
+            HttpResponseType::GetMempoolAccount(md, _) => md,
+            HttpResponseType::GetAccountNextNonce(md, _) => md,
+            HttpResponseType::SimulateTransaction(md, _) => md,
+            HttpResponseType::BlockValidation(md, _) => md,
+            HttpResponseType::GetContractPublishPolicy(md, _) => md,
+            HttpResponseType::MinerPauseStatus(md, _) => md,
+            HttpResponseType::MiningKeyRotation(md, _) => md,
+            HttpResponseType::MemPoolFilters(md, _) => md,
+            HttpResponseType::MemPoolFilter(md, _) => md,
+            HttpResponseType::GetSubnetFeeRate(md, _) => md,
             // errors
             HttpResponseType::BadRequestJSON(md, _) => md,
             HttpResponseType::BadRequest(md, _) => md,
@@ -4253,6 +6460,7 @@ impl HttpResponseType {
             HttpResponseType::PaymentRequired(md, _) => md,
             HttpResponseType::Forbidden(md, _) => md,
             HttpResponseType::NotFound(md, _) => md,
+            HttpResponseType::TooManyRequests(md, _) => md,
             HttpResponseType::ServerError(md, _) => md,
             HttpResponseType::ServiceUnavailable(md, _) => md,
             HttpResponseType::Error(md, _, _) => md,
@@ -4321,6 +6529,40 @@ impl HttpResponseType {
         }
     }
 
+    /// Send a 200 response with a JSON body, signed if the protocol has a response signer.  The
+    /// signer is used up, so it can't sign a later response on the same connection.
+    fn send_signed_json<W: Write, T: serde::ser::Serialize>(
+        protocol: &mut StacksHttp,
+        md: &HttpResponseMetadata,
+        fd: &mut W,
+        message: &T,
+    ) -> Result<(), net_error> {
+        let signer = match protocol.response_signer.take() {
+            Some(signer) => signer,
+            None => {
+                HttpResponsePreamble::ok_JSON_from_md(fd, md)?;
+                return HttpResponseType::send_json(protocol, md, fd, message);
+            }
+        };
+        // the signature goes in the headers, so the body must be serialized first
+        let body = serde_json::to_vec(message)
+            .map_err(|e| net_error::SerializeError(format!("Failed to send as JSON: {:?}", &e)))?;
+        let signature = signer.sign(&body)?;
+        HttpResponsePreamble::new_serialized(
+            fd,
+            200,
+            "OK",
+            Some(body.len() as u32),
+            &HttpContentType::JSON,
+            md.request_id,
+            |ref mut fd| {
+                keep_alive_headers(fd, md)?;
+                signature.write_headers(fd)
+            },
+        )?;
+        fd.write_all(&body).map_err(net_error::WriteError)
+    }
+
     pub fn send<W: Write>(&self, protocol: &mut StacksHttp, fd: &mut W) -> Result<(), net_error> {
         match *self {
             HttpResponseType::GetAccount(ref md, ref account_data) => {
@@ -4351,6 +6593,10 @@ impl HttpResponseType {
                 HttpResponsePreamble::ok_JSON_from_md(fd, md)?;
                 HttpResponseType::send_json(protocol, md, fd, data)?;
             }
+            HttpResponseType::CallReadOnlyFunctionBatch(ref md, ref data) => {
+                HttpResponsePreamble::ok_JSON_from_md(fd, md)?;
+                HttpResponseType::send_json(protocol, md, fd, data)?;
+            }
             HttpResponseType::GetDataVar(ref md, ref var_data) => {
                 HttpResponsePreamble::ok_JSON_from_md(fd, md)?;
                 HttpResponseType::send_json(protocol, md, fd, var_data)?;
@@ -4360,8 +6606,7 @@ impl HttpResponseType {
                 HttpResponseType::send_json(protocol, md, fd, map_data)?;
             }
             HttpResponseType::PeerInfo(ref md, ref peer_info) => {
-                HttpResponsePreamble::ok_JSON_from_md(fd, md)?;
-                HttpResponseType::send_json(protocol, md, fd, peer_info)?;
+                HttpResponseType::send_signed_json(protocol, md, fd, peer_info)?;
             }
             HttpResponseType::PoxInfo(ref md, ref pox_info) => {
                 HttpResponsePreamble::ok_JSON_from_md(fd, md)?;
@@ -4574,6 +6819,7 @@ impl HttpResponseType {
             HttpResponseType::PaymentRequired(_, ref msg) => self.error_response(fd, 402, msg)?,
             HttpResponseType::Forbidden(_, ref msg) => self.error_response(fd, 403, msg)?,
             HttpResponseType::NotFound(_, ref msg) => self.error_response(fd, 404, msg)?,
+            HttpResponseType::TooManyRequests(_, ref msg) => self.error_response(fd, 429, msg)?,
             HttpResponseType::ServerError(_, ref msg) => self.error_response(fd, 500, msg)?,
             HttpResponseType::ServiceUnavailable(_, ref msg) => {
                 self.error_response(fd, 503, msg)?
@@ -4582,8 +6828,120 @@ impl HttpResponseType {
                 self.error_response(fd, *error_code, msg)?
             }
             HttpResponseType::GetWithdrawal(ref md, ref json) => {
+                HttpResponseType::send_signed_json(protocol, md, fd, json)?;
+            }
+            HttpResponseType::GetWithdrawalsBySender(ref md, ref data) => {
+                HttpResponsePreamble::ok_JSON_from_md(fd, md)?;
+                HttpResponseType::send_json(protocol, md, fd, data)?;
+            }
+            HttpResponseType::GetWithdrawalClaim(ref md, ref data) => {
+                HttpResponseType::send_signed_json(protocol, md, fd, data)?;
+            }
+            HttpResponseType::GetFailedDepositsBySender(ref md, ref data) => {
+                HttpResponsePreamble::ok_JSON_from_md(fd, md)?;
+                HttpResponseType::send_json(protocol, md, fd, data)?;
+            }
+            HttpResponseType::GetAccountTransactions(ref md, ref data) => {
+                HttpResponsePreamble::ok_JSON_from_md(fd, md)?;
+                HttpResponseType::send_json(protocol, md, fd, data)?;
+            }
+            HttpResponseType::GetBurnOps(ref md, ref data) => {
+                HttpResponsePreamble::ok_JSON_from_md(fd, md)?;
+                HttpResponseType::send_json(protocol, md, fd, data)?;
+            }
+            HttpResponseType::GetForks(ref md, ref data) => {
+                HttpResponsePreamble::ok_JSON_from_md(fd, md)?;
+                HttpResponseType::send_json(protocol, md, fd, data)?;
+            }
+            HttpResponseType::GetContractCosts(ref md, ref data) => {
+                HttpResponsePreamble::ok_JSON_from_md(fd, md)?;
+                HttpResponseType::send_json(protocol, md, fd, data)?;
+            }
+            HttpResponseType::GetBlockCommits(ref md, ref data) => {
+                HttpResponseType::send_signed_json(protocol, md, fd, data)?;
+            }
+            HttpResponseType::GetRewardsReport(ref md, ref data) => {
+                HttpResponsePreamble::ok_JSON_from_md(fd, md)?;
+                HttpResponseType::send_json(protocol, md, fd, data)?;
+            }
+            HttpResponseType::GetEventReplay(ref md, ref data) => {
+                HttpResponsePreamble::ok_JSON_from_md(fd, md)?;
+                HttpResponseType::send_json(protocol, md, fd, data)?;
+            }
+            HttpResponseType::GetSubnetInfo(ref md, ref data) => {
+                HttpResponseType::send_signed_json(protocol, md, fd, data)?;
+            }
+            HttpResponseType::GetMicroblockStream(ref md, ref data) => {
+                HttpResponsePreamble::ok_JSON_from_md(fd, md)?;
+                HttpResponseType::send_json(protocol, md, fd, data)?;
+            }
+            HttpResponseType::HealthCheck(ref md, ref data) => {
+                if data.ok {
+                    HttpResponsePreamble::ok_JSON_from_md(fd, md)?;
+                } else {
+                    HttpResponsePreamble::new_serialized(
+                        fd,
+                        503,
+                        HttpResponseType::error_reason(503),
+                        md.content_length.clone(),
+                        &HttpContentType::JSON,
+                        md.request_id,
+                        |ref mut fd| keep_alive_headers(fd, md),
+                    )?;
+                }
+                HttpResponseType::send_json(protocol, md, fd, data)?;
+            }
+            HttpResponseType::GetMempoolPreview(ref md, ref data) => {
+                HttpResponsePreamble::ok_JSON_from_md(fd, md)?;
+                HttpResponseType::send_json(protocol, md, fd, data)?;
+            }
+            HttpResponseType::GetRejectedTxs(ref md, ref data) => {
+                HttpResponsePreamble::ok_JSON_from_md(fd, md)?;
+                HttpResponseType::send_json(protocol, md, fd, data)?;
+            }
+            HttpResponseType::GetMempoolStats(ref md, ref data) => {
+                HttpResponsePreamble::ok_JSON_from_md(fd, md)?;
+                HttpResponseType::send_json(protocol, md, fd, data)?;
+            }
+            HttpResponseType::GetMempoolAccount(ref md, ref data) => {
+                HttpResponsePreamble::ok_JSON_from_md(fd, md)?;
+                HttpResponseType::send_json(protocol, md, fd, data)?;
+            }
+            HttpResponseType::GetAccountNextNonce(ref md, ref data) => {
+                HttpResponsePreamble::ok_JSON_from_md(fd, md)?;
+                HttpResponseType::send_json(protocol, md, fd, data)?;
+            }
+            HttpResponseType::SimulateTransaction(ref md, ref data) => {
+                HttpResponsePreamble::ok_JSON_from_md(fd, md)?;
+                HttpResponseType::send_json(protocol, md, fd, data)?;
+            }
+            HttpResponseType::BlockValidation(ref md, ref data) => {
+                HttpResponsePreamble::ok_JSON_from_md(fd, md)?;
+                HttpResponseType::send_json(protocol, md, fd, data)?;
+            }
+            HttpResponseType::GetContractPublishPolicy(ref md, ref data) => {
+                HttpResponsePreamble::ok_JSON_from_md(fd, md)?;
+                HttpResponseType::send_json(protocol, md, fd, data)?;
+            }
+            HttpResponseType::MinerPauseStatus(ref md, ref data) => {
+                HttpResponsePreamble::ok_JSON_from_md(fd, md)?;
+                HttpResponseType::send_json(protocol, md, fd, data)?;
+            }
+            HttpResponseType::MiningKeyRotation(ref md, ref data) => {
+                HttpResponsePreamble::ok_JSON_from_md(fd, md)?;
+                HttpResponseType::send_json(protocol, md, fd, data)?;
+            }
+            HttpResponseType::MemPoolFilters(ref md, ref data) => {
+                HttpResponsePreamble::ok_JSON_from_md(fd, md)?;
+                HttpResponseType::send_json(protocol, md, fd, data)?;
+            }
+            HttpResponseType::MemPoolFilter(ref md, ref data) => {
+                HttpResponsePreamble::ok_JSON_from_md(fd, md)?;
+                HttpResponseType::send_json(protocol, md, fd, data)?;
+            }
+            HttpResponseType::GetSubnetFeeRate(ref md, ref data) => {
                 HttpResponsePreamble::ok_JSON_from_md(fd, md)?;
-                HttpResponseType::send_json(protocol, md, fd, json)?;
+                HttpResponseType::send_json(protocol, md, fd, data)?;
             }
             HttpResponseType::BlockProposalValid {
                 metadata: ref md,
@@ -4682,17 +7040,31 @@ impl MessageSequence for StacksHttpMessage {
                 HttpRequestType::GetTransactionUnconfirmed(_, _) => {
                     "HTTP(GetTransactionUnconfirmed)"
                 }
-                HttpRequestType::PostTransaction(_, _, _) => "HTTP(PostTransaction)",
+                HttpRequestType::PostTransaction(..) => "HTTP(PostTransaction)",
+                HttpRequestType::SimulateTransaction(..) => "HTTP(SimulateTransaction)",
                 HttpRequestType::PostBlock(..) => "HTTP(PostBlock)",
+                HttpRequestType::ValidateBlock(..) => "HTTP(ValidateBlock)",
                 HttpRequestType::PostMicroblock(..) => "HTTP(PostMicroblock)",
                 HttpRequestType::GetAccount(..) => "HTTP(GetAccount)",
+                HttpRequestType::GetMempoolAccount(..) => "HTTP(GetMempoolAccount)",
+                HttpRequestType::GetAccountNextNonce(..) => "HTTP(GetAccountNextNonce)",
+                HttpRequestType::GetContractPublishPolicy(..) => "HTTP(GetContractPublishPolicy)",
+                HttpRequestType::SetMinerPaused(..) => "HTTP(SetMinerPaused)",
+                HttpRequestType::RotateMiningKey(..) => "HTTP(RotateMiningKey)",
+                HttpRequestType::GetMemPoolFilters(..) => "HTTP(GetMemPoolFilters)",
+                HttpRequestType::InstallMemPoolFilter(..) => "HTTP(InstallMemPoolFilter)",
+                HttpRequestType::RemoveMemPoolFilter(..) => "HTTP(RemoveMemPoolFilter)",
                 HttpRequestType::GetDataVar(..) => "HTTP(GetDataVar)",
                 HttpRequestType::GetMapEntry(..) => "HTTP(GetMapEntry)",
                 HttpRequestType::GetTransferCost(_) => "HTTP(GetTransferCost)",
+                HttpRequestType::GetSubnetFeeRate(_) => "HTTP(GetSubnetFeeRate)",
                 HttpRequestType::GetContractABI(..) => "HTTP(GetContractABI)",
                 HttpRequestType::GetContractSrc(..) => "HTTP(GetContractSrc)",
                 HttpRequestType::GetIsTraitImplemented(..) => "HTTP(GetIsTraitImplemented)",
                 HttpRequestType::CallReadOnlyFunction(..) => "HTTP(CallReadOnlyFunction)",
+                HttpRequestType::CallReadOnlyFunctionBatch { .. } => {
+                    "HTTP(CallReadOnlyFunctionBatch)"
+                }
                 HttpRequestType::GetAttachment(..) => "HTTP(GetAttachment)",
                 HttpRequestType::GetAttachmentsInv(..) => "HTTP(GetAttachmentsInv)",
                 HttpRequestType::MemPoolQuery(..) => "HTTP(MemPoolQuery)",
@@ -4700,9 +7072,31 @@ impl MessageSequence for StacksHttpMessage {
                 HttpRequestType::ClientError(..) => "HTTP(ClientError)",
                 HttpRequestType::FeeRateEstimate(_, _, _) => "HTTP(FeeRateEstimate)",
                 HttpRequestType::GetWithdrawalStx { .. } => "HTTP(GetWithdrawalStx)",
+                HttpRequestType::GetWithdrawalsBySender { .. } => "HTTP(GetWithdrawalsBySender)",
+                HttpRequestType::GetWithdrawalClaim { .. } => "HTTP(GetWithdrawalClaim)",
+                HttpRequestType::GetFailedDepositsBySender { .. } => {
+                    "HTTP(GetFailedDepositsBySender)"
+                }
+                HttpRequestType::GetAccountTransactions { .. } => "HTTP(GetAccountTransactions)",
+                HttpRequestType::GetBurnOps { .. } => "HTTP(GetBurnOps)",
+                HttpRequestType::GetForks { .. } => "HTTP(GetForks)",
+                HttpRequestType::GetContractCosts { .. } => "HTTP(GetContractCosts)",
+                HttpRequestType::GetBlockCommits { .. } => "HTTP(GetBlockCommits)",
+                HttpRequestType::GetRewardsReport { .. } => "HTTP(GetRewardsReport)",
+                HttpRequestType::GetEventReplay { .. } => "HTTP(GetEventReplay)",
+                HttpRequestType::GetSubnetInfo(..) => "HTTP(GetSubnetInfo)",
+                HttpRequestType::GetMicroblockStream { .. } => "HTTP(GetMicroblockStream)",
+                HttpRequestType::GetHealthLive(..) => "HTTP(GetHealthLive)",
+                HttpRequestType::GetHealthReady(..) => "HTTP(GetHealthReady)",
+                HttpRequestType::GetMempoolPreview { .. } => "HTTP(GetMempoolPreview)",
+                HttpRequestType::GetRejectedTxs { .. } => "HTTP(GetRejectedTxs)",
+                HttpRequestType::GetMempoolStats(..) => "HTTP(GetMempoolStats)",
                 HttpRequestType::BlockProposal(_, _) => "HTTP(BlockProposal)",
                 HttpRequestType::GetWithdrawalFt { .. } => "HTTP(GetWithdrawalFt)",
                 HttpRequestType::GetWithdrawalNft { .. } => "HTTP(GetWithdrawalNft)",
+                HttpRequestType::GetWithdrawalStxTransfer { .. } => {
+                    "HTTP(GetWithdrawalStxTransfer)"
+                }
             },
             StacksHttpMessage::Response(ref res) => match res {
                 HttpResponseType::TokenTransferCost(_, _) => "HTTP(TokenTransferCost)",
@@ -4713,6 +7107,9 @@ impl MessageSequence for StacksHttpMessage {
                 HttpResponseType::GetContractSrc(..) => "HTTP(GetContractSrc)",
                 HttpResponseType::GetIsTraitImplemented(..) => "HTTP(GetIsTraitImplemented)",
                 HttpResponseType::CallReadOnlyFunction(..) => "HTTP(CallReadOnlyFunction)",
+                HttpResponseType::CallReadOnlyFunctionBatch(..) => {
+                    "HTTP(CallReadOnlyFunctionBatch)"
+                }
                 HttpResponseType::GetAttachment(_, _) => "HTTP(GetAttachment)",
                 HttpResponseType::GetAttachmentsInv(_, _) => "HTTP(GetAttachmentsInv)",
                 HttpResponseType::PeerInfo(_, _) => "HTTP(PeerInfo)",
@@ -4738,6 +7135,7 @@ impl MessageSequence for StacksHttpMessage {
                 HttpResponseType::PaymentRequired(_, _) => "HTTP(402)",
                 HttpResponseType::Forbidden(_, _) => "HTTP(403)",
                 HttpResponseType::NotFound(_, _) => "HTTP(404)",
+                HttpResponseType::TooManyRequests(_, _) => "HTTP(429)",
                 HttpResponseType::ServerError(_, _) => "HTTP(500)",
                 HttpResponseType::ServiceUnavailable(_, _) => "HTTP(503)",
                 HttpResponseType::Error(_, _, _) => "HTTP(other)",
@@ -4745,6 +7143,36 @@ impl MessageSequence for StacksHttpMessage {
                     "HTTP(TransactionFeeEstimation)"
                 }
                 HttpResponseType::GetWithdrawal(_, _) => "HTTP(GetWithdrawal)",
+                HttpResponseType::GetWithdrawalsBySender(_, _) => "HTTP(GetWithdrawalsBySender)",
+                HttpResponseType::GetWithdrawalClaim(_, _) => "HTTP(GetWithdrawalClaim)",
+                HttpResponseType::GetFailedDepositsBySender(_, _) => {
+                    "HTTP(GetFailedDepositsBySender)"
+                }
+                HttpResponseType::GetAccountTransactions(_, _) => "HTTP(GetAccountTransactions)",
+                HttpResponseType::GetBurnOps(_, _) => "HTTP(GetBurnOps)",
+                HttpResponseType::GetForks(_, _) => "HTTP(GetForks)",
+                HttpResponseType::GetContractCosts(_, _) => "HTTP(GetContractCosts)",
+                HttpResponseType::GetBlockCommits(_, _) => "HTTP(GetBlockCommits)",
+                HttpResponseType::GetRewardsReport(_, _) => "HTTP(GetRewardsReport)",
+                HttpResponseType::GetEventReplay(_, _) => "HTTP(GetEventReplay)",
+                HttpResponseType::GetSubnetInfo(_, _) => "HTTP(GetSubnetInfo)",
+                HttpResponseType::GetMicroblockStream(_, _) => "HTTP(GetMicroblockStream)",
+                HttpResponseType::HealthCheck(_, _) => "HTTP(HealthCheck)",
+                HttpResponseType::GetMempoolPreview(_, _) => "HTTP(GetMempoolPreview)",
+                HttpResponseType::GetRejectedTxs(_, _) => "HTTP(GetRejectedTxs)",
+                HttpResponseType::GetMempoolStats(_, _) => "HTTP(GetMempoolStats)",
+                HttpResponseType::GetMempoolAccount(_, _) => "HTTP(GetMempoolAccount)",
+                HttpResponseType::GetAccountNextNonce(_, _) => "HTTP(GetAccountNextNonce)",
+                HttpResponseType::SimulateTransaction(_, _) => "HTTP(SimulateTransaction)",
+                HttpResponseType::BlockValidation(_, _) => "HTTP(BlockValidation)",
+                HttpResponseType::GetContractPublishPolicy(_, _) => {
+                    "HTTP(GetContractPublishPolicy)"
+                }
+                HttpResponseType::MinerPauseStatus(_, _) => "HTTP(MinerPauseStatus)",
+                HttpResponseType::MiningKeyRotation(_, _) => "HTTP(MiningKeyRotation)",
+                HttpResponseType::MemPoolFilters(_, _) => "HTTP(MemPoolFilters)",
+                HttpResponseType::MemPoolFilter(_, _) => "HTTP(MemPoolFilter)",
+                HttpResponseType::GetSubnetFeeRate(_, _) => "HTTP(GetSubnetFeeRate)",
                 HttpResponseType::BlockProposalValid { .. }
                 | HttpResponseType::BlockProposalInvalid { .. } => "HTTP(BlockProposal)",
             },
@@ -4851,6 +7279,8 @@ pub struct StacksHttp {
     chunk_size: usize,
     /// Maximum size of call arguments
     pub maximum_call_argument_size: u32,
+    /// How to sign the response to the request being handled, if responses are signed
+    response_signer: Option<RPCResponseSigner>,
 }
 
 impl StacksHttp {
@@ -4862,6 +7292,7 @@ impl StacksHttp {
             request_path: None,
             chunk_size: 8192,
             maximum_call_argument_size: 20 * BOUND_VALUE_SERIALIZATION_HEX,
+            response_signer: None,
         }
     }
 
@@ -4869,6 +7300,11 @@ impl StacksHttp {
         self.chunk_size = size;
     }
 
+    /// Set how to sign the response to the request about to be handled, or that it is unsigned
+    pub fn set_response_signer(&mut self, signer: Option<RPCResponseSigner>) {
+        self.response_signer = signer;
+    }
+
     pub fn num_pending(&self) -> usize {
         if self.reply.is_some() {
             1
@@ -5276,6 +7712,7 @@ mod test {
     use crate::chainstate::stacks::TransactionPayload;
     use crate::chainstate::stacks::TransactionPostConditionMode;
     use crate::chainstate::stacks::TransactionVersion;
+    use crate::core::mempool_filters::MemPoolFilterKind;
     use crate::net::codec::test::check_codec_and_corruption;
     use crate::net::test::*;
     use crate::net::RPCNeighbor;
@@ -6224,12 +8661,14 @@ mod test {
             ),
             keep_alive: true,
             canonical_stacks_tip_height: None,
+            api_key: None,
         };
         let http_request_metadata_dns = HttpRequestMetadata {
             version: HttpVersion::Http11,
             peer: PeerHost::DNS("www.foo.com".to_string(), 80),
             keep_alive: true,
             canonical_stacks_tip_height: None,
+            api_key: None,
         };
 
         let tests = vec![
@@ -6243,6 +8682,8 @@ mod test {
                 http_request_metadata_dns.clone(),
                 make_test_transaction(),
                 None,
+                None,
+                None,
             ),
             HttpRequestType::OptionsPreflight(http_request_metadata_ip.clone(), "/".to_string()),
         ];
@@ -6339,6 +8780,378 @@ mod test {
         }
     }
 
+    #[test]
+    fn test_http_miner_pause_request_codec() {
+        let tests = vec![
+            (true, Some("secret-token".to_string())),
+            (false, Some("secret-token".to_string())),
+            (true, None),
+        ];
+        for (paused, auth_token) in tests {
+            let req = HttpRequestType::SetMinerPaused(
+                HttpRequestMetadata::from_host(PeerHost::DNS("localhost".to_string(), 20443), None),
+                paused,
+                auth_token.clone(),
+            );
+            let mut bytes = vec![];
+            let mut http = StacksHttp::new("127.0.0.1:20443".parse().unwrap());
+            http.write_message(&mut bytes, &StacksHttpMessage::Request(req.clone()))
+                .unwrap();
+
+            let expected_path = if paused {
+                "POST /v2/admin/miner/pause "
+            } else {
+                "POST /v2/admin/miner/resume "
+            };
+            assert!(str::from_utf8(&bytes).unwrap().starts_with(expected_path));
+
+            let mut http = StacksHttp::new("127.0.0.1:20443".parse().unwrap());
+            let (preamble, offset) = http.read_preamble(&bytes).unwrap();
+            let (msg, _) = http.read_payload(&preamble, &bytes[offset..]).unwrap();
+            match msg {
+                StacksHttpMessage::Request(HttpRequestType::SetMinerPaused(
+                    _,
+                    parsed_paused,
+                    parsed_auth_token,
+                )) => {
+                    assert_eq!(parsed_paused, paused);
+                    assert_eq!(parsed_auth_token, auth_token);
+                }
+                _ => panic!("Expected a SetMinerPaused request, got {:?}", &msg),
+            }
+        }
+    }
+
+    #[test]
+    fn test_http_miner_rotate_key_request_codec() {
+        for auth_token in [Some("secret-token".to_string()), None] {
+            let req = HttpRequestType::RotateMiningKey(
+                HttpRequestMetadata::from_host(PeerHost::DNS("localhost".to_string(), 20443), None),
+                auth_token.clone(),
+            );
+            let mut bytes = vec![];
+            let mut http = StacksHttp::new("127.0.0.1:20443".parse().unwrap());
+            http.write_message(&mut bytes, &StacksHttpMessage::Request(req.clone()))
+                .unwrap();
+            assert!(str::from_utf8(&bytes)
+                .unwrap()
+                .starts_with("POST /v2/admin/miner/rotate_key "));
+
+            let mut http = StacksHttp::new("127.0.0.1:20443".parse().unwrap());
+            let (preamble, offset) = http.read_preamble(&bytes).unwrap();
+            let (msg, _) = http.read_payload(&preamble, &bytes[offset..]).unwrap();
+            match msg {
+                StacksHttpMessage::Request(HttpRequestType::RotateMiningKey(
+                    _,
+                    parsed_auth_token,
+                )) => {
+                    assert_eq!(parsed_auth_token, auth_token);
+                }
+                _ => panic!("Expected a RotateMiningKey request, got {:?}", &msg),
+            }
+        }
+    }
+
+    #[test]
+    fn test_http_mempool_filter_request_codec() {
+        let md =
+            HttpRequestMetadata::from_host(PeerHost::DNS("localhost".to_string(), 20443), None);
+        let auth_token = Some("secret-token".to_string());
+        let body = MemPoolFilterRequestBody {
+            kind: MemPoolFilterKind::Contract,
+            value: "ST2ZRX0K27GW0SP3GJCEMHD95TQGJMKB7G9Y0X1MH.spam".to_string(),
+            ttl_secs: 3600,
+            reason: "spam".to_string(),
+        };
+        let tests = vec![
+            (
+                HttpRequestType::GetMemPoolFilters(md.clone(), auth_token.clone()),
+                "GET /v2/admin/mempool/filters ",
+            ),
+            (
+                HttpRequestType::InstallMemPoolFilter(md.clone(), body, auth_token.clone()),
+                "POST /v2/admin/mempool/filter ",
+            ),
+            (
+                HttpRequestType::RemoveMemPoolFilter(md.clone(), 7, auth_token.clone()),
+                "DELETE /v2/admin/mempool/filter/7 ",
+            ),
+        ];
+        for (req, expected_start) in tests {
+            let mut bytes = vec![];
+            let mut http = StacksHttp::new("127.0.0.1:20443".parse().unwrap());
+            http.write_message(&mut bytes, &StacksHttpMessage::Request(req.clone()))
+                .unwrap();
+            assert!(str::from_utf8(&bytes).unwrap().starts_with(expected_start));
+
+            let mut http = StacksHttp::new("127.0.0.1:20443".parse().unwrap());
+            let (preamble, offset) = http.read_preamble(&bytes).unwrap();
+            let (msg, _) = http.read_payload(&preamble, &bytes[offset..]).unwrap();
+            match msg {
+                StacksHttpMessage::Request(mut parsed) => {
+                    parsed.metadata_mut().keep_alive = req.metadata().keep_alive;
+                    parsed.metadata_mut().peer = req.metadata().peer.clone();
+                    parsed.metadata_mut().canonical_stacks_tip_height =
+                        req.metadata().canonical_stacks_tip_height;
+                    assert_eq!(parsed, req);
+                }
+                _ => panic!("Expected a request, got {:?}", &msg),
+            }
+        }
+
+        // filter values must parse as the kind of filter they are for
+        let bad_body = "{\"kind\":\"origin\",\"value\":\"not-an-address\",\"ttl_secs\":60,\"reason\":\"spam\"}";
+        let request = format!(
+            "POST /v2/admin/mempool/filter HTTP/1.1\r\nUser-Agent: stacks/2.0\r\nHost: localhost:20443\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+            bad_body.len(),
+            bad_body
+        );
+        let mut http = StacksHttp::new("127.0.0.1:20443".parse().unwrap());
+        let (preamble, offset) = http.read_preamble(request.as_bytes()).unwrap();
+        assert!(http
+            .read_payload(&preamble, &request.as_bytes()[offset..])
+            .is_err());
+    }
+
+    #[test]
+    fn test_http_rejected_txs_request_codec() {
+        let md =
+            HttpRequestMetadata::from_host(PeerHost::DNS("localhost".to_string(), 20443), None);
+        let tests = vec![
+            HttpRequestType::GetRejectedTxs {
+                metadata: md.clone(),
+                query: RejectedTxsQuery::Txid(Txid([0x11; 32])),
+                limit: 1,
+            },
+            HttpRequestType::GetRejectedTxs {
+                metadata: md.clone(),
+                query: RejectedTxsQuery::Address(
+                    StacksAddress::from_string("ST2ZRX0K27GW0SP3GJCEMHD95TQGJMKB7G9Y0X1MH")
+                        .unwrap(),
+                ),
+                limit: 20,
+            },
+        ];
+        for req in tests {
+            let mut bytes = vec![];
+            let mut http = StacksHttp::new("127.0.0.1:20443".parse().unwrap());
+            http.write_message(&mut bytes, &StacksHttpMessage::Request(req.clone()))
+                .unwrap();
+
+            let mut http = StacksHttp::new("127.0.0.1:20443".parse().unwrap());
+            let (preamble, offset) = http.read_preamble(&bytes).unwrap();
+            let (msg, _) = http.read_payload(&preamble, &bytes[offset..]).unwrap();
+            match msg {
+                StacksHttpMessage::Request(mut parsed) => {
+                    parsed.metadata_mut().keep_alive = req.metadata().keep_alive;
+                    parsed.metadata_mut().peer = req.metadata().peer.clone();
+                    parsed.metadata_mut().canonical_stacks_tip_height =
+                        req.metadata().canonical_stacks_tip_height;
+                    assert_eq!(parsed, req);
+                }
+                _ => panic!("Expected a request, got {:?}", &msg),
+            }
+        }
+
+        // the txid may carry a 0x prefix, and the limit is clamped
+        let request = format!(
+            "GET /v2/mempool/rejected?txid=0x{}&limit=100000 HTTP/1.1\r\nUser-Agent: stacks/2.0\r\nHost: localhost:20443\r\n\r\n",
+            Txid([0x22; 32])
+        );
+        let mut http = StacksHttp::new("127.0.0.1:20443".parse().unwrap());
+        let (preamble, offset) = http.read_preamble(request.as_bytes()).unwrap();
+        let (msg, _) = http
+            .read_payload(&preamble, &request.as_bytes()[offset..])
+            .unwrap();
+        match msg {
+            StacksHttpMessage::Request(HttpRequestType::GetRejectedTxs {
+                query, limit, ..
+            }) => {
+                assert_eq!(query, RejectedTxsQuery::Txid(Txid([0x22; 32])));
+                assert_eq!(limit, MAX_REJECTED_TXS_RESULTS);
+            }
+            _ => panic!("Expected a GetRejectedTxs request, got {:?}", &msg),
+        }
+
+        // exactly one of a txid or an address must be given
+        let bad_queries = vec![
+            "/v2/mempool/rejected".to_string(),
+            format!(
+                "/v2/mempool/rejected?txid={}&address=ST2ZRX0K27GW0SP3GJCEMHD95TQGJMKB7G9Y0X1MH",
+                Txid([0x22; 32])
+            ),
+            "/v2/mempool/rejected?txid=not-a-txid".to_string(),
+        ];
+        for bad_query in bad_queries {
+            let request = format!(
+                "GET {} HTTP/1.1\r\nUser-Agent: stacks/2.0\r\nHost: localhost:20443\r\n\r\n",
+                bad_query
+            );
+            let mut http = StacksHttp::new("127.0.0.1:20443".parse().unwrap());
+            let (preamble, offset) = http.read_preamble(request.as_bytes()).unwrap();
+            assert!(http
+                .read_payload(&preamble, &request.as_bytes()[offset..])
+                .is_err());
+        }
+    }
+
+    #[test]
+    fn test_http_rewards_report_request_codec() {
+        let read_request = |path: &str| {
+            let request = format!(
+                "GET {} HTTP/1.1\r\nUser-Agent: stacks/2.0\r\nHost: localhost:20443\r\n\r\n",
+                path
+            );
+            let mut http = StacksHttp::new("127.0.0.1:20443".parse().unwrap());
+            let (preamble, offset) = http.read_preamble(request.as_bytes()).unwrap();
+            http.read_payload(&preamble, &request.as_bytes()[offset..])
+                .map(|(msg, _)| msg)
+        };
+
+        match read_request("/v2/rewards/report?from_height=10&to_height=20").unwrap() {
+            StacksHttpMessage::Request(HttpRequestType::GetRewardsReport {
+                from_height,
+                to_height,
+                ..
+            }) => {
+                assert_eq!(from_height, 10);
+                assert_eq!(to_height, 20);
+            }
+            msg => panic!("Expected a GetRewardsReport request, got {:?}", &msg),
+        }
+
+        // long ranges are cut short
+        match read_request("/v2/rewards/report?from_height=10&to_height=1000000").unwrap() {
+            StacksHttpMessage::Request(HttpRequestType::GetRewardsReport {
+                from_height,
+                to_height,
+                ..
+            }) => {
+                assert_eq!(from_height, 10);
+                assert_eq!(to_height, 10 + MAX_REWARDS_REPORT_BLOCKS - 1);
+            }
+            msg => panic!("Expected a GetRewardsReport request, got {:?}", &msg),
+        }
+
+        for bad_path in [
+            "/v2/rewards/report",
+            "/v2/rewards/report?from_height=10",
+            "/v2/rewards/report?from_height=20&to_height=10",
+        ] {
+            assert!(read_request(bad_path).is_err());
+        }
+    }
+
+    #[test]
+    fn test_http_event_replay_request_codec() {
+        let read_request = |path: &str| {
+            let request = format!(
+                "GET {} HTTP/1.1\r\nUser-Agent: stacks/2.0\r\nHost: localhost:20443\r\n\r\n",
+                path
+            );
+            let mut http = StacksHttp::new("127.0.0.1:20443".parse().unwrap());
+            let (preamble, offset) = http.read_preamble(request.as_bytes()).unwrap();
+            http.read_payload(&preamble, &request.as_bytes()[offset..])
+                .map(|(msg, _)| msg)
+        };
+
+        // all events unless keys are given
+        match read_request("/v2/events/replay?from_height=10&to_height=15").unwrap() {
+            StacksHttpMessage::Request(HttpRequestType::GetEventReplay {
+                from_height,
+                to_height,
+                keys,
+                ..
+            }) => {
+                assert_eq!(from_height, 10);
+                assert_eq!(to_height, 15);
+                assert_eq!(keys, vec!["*".to_string()]);
+            }
+            msg => panic!("Expected a GetEventReplay request, got {:?}", &msg),
+        }
+
+        // long ranges are cut short, and the keys survive a round trip through the path
+        let request = match read_request(
+            "/v2/events/replay?from_height=10&to_height=1000&keys=stx,burn_blocks,ST000000000000000000002AMW42H.subnet::print",
+        )
+        .unwrap()
+        {
+            StacksHttpMessage::Request(request) => request,
+            msg => panic!("Expected a request, got {:?}", &msg),
+        };
+        match read_request(&request.request_path()).unwrap() {
+            StacksHttpMessage::Request(HttpRequestType::GetEventReplay {
+                from_height,
+                to_height,
+                keys,
+                ..
+            }) => {
+                assert_eq!(from_height, 10);
+                assert_eq!(to_height, 10 + MAX_EVENT_REPLAY_BLOCKS - 1);
+                assert_eq!(
+                    keys,
+                    vec![
+                        "stx".to_string(),
+                        "burn_blocks".to_string(),
+                        "ST000000000000000000002AMW42H.subnet::print".to_string()
+                    ]
+                );
+            }
+            msg => panic!("Expected a GetEventReplay request, got {:?}", &msg),
+        }
+
+        for bad_path in [
+            "/v2/events/replay?from_height=10",
+            "/v2/events/replay?from_height=20&to_height=10",
+            "/v2/events/replay?from_height=10&to_height=20&keys=",
+        ] {
+            assert!(read_request(bad_path).is_err());
+        }
+    }
+
+    #[test]
+    fn test_http_microblock_stream_request_codec() {
+        let read_request = |path: &str| {
+            let request = format!(
+                "GET {} HTTP/1.1\r\nUser-Agent: stacks/2.0\r\nHost: localhost:20443\r\n\r\n",
+                path
+            );
+            let mut http = StacksHttp::new("127.0.0.1:20443".parse().unwrap());
+            let (preamble, offset) = http.read_preamble(request.as_bytes()).unwrap();
+            http.read_payload(&preamble, &request.as_bytes()[offset..])
+                .map(|(msg, _)| msg)
+        };
+
+        // the stream endpoint doesn't shadow the indexed microblock endpoint, or vice versa
+        let request = match read_request(
+            "/v2/microblocks/stream/1111111111111111111111111111111111111111111111111111111111111111",
+        )
+        .unwrap()
+        {
+            StacksHttpMessage::Request(request) => request,
+            msg => panic!("Expected a request, got {:?}", &msg),
+        };
+        match read_request(&request.request_path()).unwrap() {
+            StacksHttpMessage::Request(HttpRequestType::GetMicroblockStream {
+                parent_block_id,
+                ..
+            }) => {
+                assert_eq!(parent_block_id, StacksBlockId([0x11; 32]));
+            }
+            msg => panic!("Expected a GetMicroblockStream request, got {:?}", &msg),
+        }
+        match read_request(
+            "/v2/microblocks/1111111111111111111111111111111111111111111111111111111111111111",
+        )
+        .unwrap()
+        {
+            StacksHttpMessage::Request(HttpRequestType::GetMicroblocksIndexed(..)) => {}
+            msg => panic!("Expected a GetMicroblocksIndexed request, got {:?}", &msg),
+        }
+
+        assert!(read_request("/v2/microblocks/stream/1111").is_err());
+    }
+
     #[test]
     fn test_http_request_type_codec_err() {
         let bad_content_lengths = vec![
@@ -6346,6 +9159,8 @@ mod test {
             "GET /v2/blocks/1111111111111111111111111111111111111111111111111111111111111111 HTTP/1.1\r\nUser-Agent: stacks/2.0\r\nHost: bad:123\r\nContent-Length: 1\r\n\r\nb",
             "GET /v2/microblocks/1111111111111111111111111111111111111111111111111111111111111111 HTTP/1.1\r\nUser-Agent: stacks/2.0\r\nHost: bad:123\r\nContent-Length: 1\r\n\r\nb",
             "POST /v2/transactions HTTP/1.1\r\nUser-Agent: stacks/2.0\r\nHost: bad:123\r\nContent-Length: 0\r\n\r\n",
+            "POST /v2/transactions/simulate HTTP/1.1\r\nUser-Agent: stacks/2.0\r\nHost: bad:123\r\nContent-Length: 0\r\n\r\n",
+            "POST /v2/blocks/validate/1111111111111111111111111111111111111111 HTTP/1.1\r\nUser-Agent: stacks/2.0\r\nHost: bad:123\r\nContent-Length: 0\r\n\r\n",
         ];
         for bad_content_length in bad_content_lengths {
             let mut http = StacksHttp::new("127.0.0.1:20443".parse().unwrap());
@@ -6366,6 +9181,8 @@ mod test {
 
         let bad_content_types = vec![
             "POST /v2/transactions HTTP/1.1\r\nUser-Agent: stacks/2.0\r\nHost: bad:123\r\nContent-Length: 1\r\n\r\nb",
+            "POST /v2/transactions/simulate HTTP/1.1\r\nUser-Agent: stacks/2.0\r\nHost: bad:123\r\nContent-Length: 1\r\n\r\nb",
+            "POST /v2/blocks/validate/1111111111111111111111111111111111111111 HTTP/1.1\r\nUser-Agent: stacks/2.0\r\nHost: bad:123\r\nContent-Length: 1\r\n\r\nb",
         ];
         for bad_content_type in bad_content_types {
             let mut http = StacksHttp::new("127.0.0.1:20443".parse().unwrap());
@@ -6556,6 +9373,13 @@ mod test {
                 ),
                 "/v2/neighbors".to_string(),
             ),
+            (
+                HttpResponseType::TooManyRequests(
+                    HttpResponseMetadata::new(HttpVersion::Http11, 123, Some(0), true, None),
+                    "".to_string(),
+                ),
+                "/v2/neighbors".to_string(),
+            ),
             (
                 HttpResponseType::ServerError(
                     HttpResponseMetadata::new(HttpVersion::Http11, 123, Some(0), true, None),
@@ -6614,6 +9438,13 @@ mod test {
                 ),
                 "/v2/neighbors".to_string(),
             ),
+            (
+                HttpResponseType::TooManyRequests(
+                    HttpResponseMetadata::new(HttpVersion::Http11, 123, Some(3), true, None),
+                    "foo".to_string(),
+                ),
+                "/v2/neighbors".to_string(),
+            ),
             (
                 HttpResponseType::ServerError(
                     HttpResponseMetadata::new(HttpVersion::Http11, 123, Some(3), true, None),
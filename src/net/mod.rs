@@ -44,9 +44,15 @@ use serde_json;
 use url;
 
 use crate::burnchains::Txid;
+use crate::chainstate::burn::operations::BlockstackOperationType;
 use crate::chainstate::burn::ConsensusHash;
 use crate::chainstate::coordinator::Error as coordinator_error;
+use crate::chainstate::stacks::db::block_costs::RecentContractCosts;
 use crate::chainstate::stacks::db::blocks::MemPoolRejection;
+use crate::chainstate::stacks::db::commits::BlockCommits;
+use crate::chainstate::stacks::db::event_replay::EventReplay;
+use crate::chainstate::stacks::db::forks::ForkTree;
+use crate::chainstate::stacks::db::rewards_report::RewardsReport;
 use crate::chainstate::stacks::index::Error as marf_error;
 use crate::chainstate::stacks::miner::SignedProposal;
 use crate::chainstate::stacks::Error as chainstate_error;
@@ -56,6 +62,8 @@ use crate::chainstate::stacks::{
 };
 use crate::clarity_vm::clarity::Error as clarity_error;
 use crate::core::mempool::*;
+use crate::core::mempool_filters::{MemPoolFilter, MemPoolFilterKind};
+use crate::core::mempool_stats::MemPoolStats;
 use crate::net::atlas::{Attachment, AttachmentInstance};
 use crate::net::http::HttpReservedHeader;
 use crate::util_lib::bloom::{BloomFilter, BloomNodeHasher};
@@ -96,6 +104,8 @@ pub use self::http::StacksHttp;
 
 use crate::core::StacksEpoch;
 
+/// Implements `RPCApiKeys`, which gates transaction submission behind configured API keys.
+pub mod api_keys;
 /// Implements `ASEntry4` object, which is used in db.rs to store the AS number of an IP address.
 pub mod asn;
 /// Implements the Atlas network. This network uses the infrastructure created in `src/net` to
@@ -128,7 +138,10 @@ pub mod p2p;
 /// p2p server and the http server.
 pub mod poll;
 pub mod prune;
+pub mod ratelimit;
 pub mod relay;
+/// Implements `RPCResponseSigner`, which signs the RPC responses bridges act on with the node key.
+pub mod response_signing;
 pub mod rpc;
 pub mod server;
 
@@ -1195,6 +1208,8 @@ pub struct HttpRequestMetadata {
     pub peer: PeerHost,
     pub keep_alive: bool,
     pub canonical_stacks_tip_height: Option<u64>,
+    /// the API key sent in the request's `X-API-Key` header, if any
+    pub api_key: Option<String>,
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -1262,6 +1277,454 @@ pub struct WithdrawalResponse {
     pub sibling_hashes: String,
 }
 
+/// A withdrawal in a block's withdrawal root, with what is needed to request its Merkle proof
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WithdrawalEntryResponse {
+    pub block_height: u64,
+    /// the block whose withdrawal root includes the withdrawal
+    pub index_block_hash: String,
+    pub withdrawal_id: u32,
+    /// "stx", "ft", "nft", "sft", or "stx-transfer"
+    #[serde(rename = "type")]
+    pub asset_type: String,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub asset_contract: Option<String>,
+    /// the token ID of an NFT or SFT withdrawal, as a decimal string
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub asset_id: Option<String>,
+    /// the amount withdrawn, as a decimal string, except for NFT withdrawals
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub amount: Option<String>,
+    pub withdrawal_root: String,
+}
+
+/// The subnet contract call which claims a withdrawal on the L1
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WithdrawalClaimCall {
+    pub contract_id: String,
+    pub function_name: String,
+    /// hex-encoded Clarity values
+    pub function_args: Vec<String>,
+    /// the hex-encoded transaction payload, ready to be signed into an L1 transaction
+    pub payload: String,
+}
+
+/// Whether a withdrawal can be claimed on the L1 yet, and how
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WithdrawalClaimResponse {
+    pub withdrawal: WithdrawalEntryResponse,
+    /// the withdrawal's Merkle proof
+    pub proof: WithdrawalResponse,
+    /// the L1 block whose block-commit chose the withdrawal's block
+    pub l1_commit_height: u64,
+    /// how many L1 blocks, starting with that one, this node has seen
+    pub l1_confirmations: u64,
+    /// whether the subnet contract on the L1 has recorded the withdrawal root, or `None` if the
+    /// L1 could not be asked
+    pub root_committed: Option<bool>,
+    /// true once the subnet contract has recorded the withdrawal root
+    pub claimable: bool,
+    /// the call which claims the withdrawal, unless it is an STX transfer to another subnet, or
+    /// the L1 contract of the withdrawn asset is unknown
+    pub contract_call: Option<WithdrawalClaimCall>,
+    /// the L1's estimate of the claim transaction's fee, in uSTX
+    pub estimated_fee: Option<u64>,
+}
+
+/// A page of a principal's withdrawals, newest first
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WithdrawalsResponse {
+    pub withdrawals: Vec<WithdrawalEntryResponse>,
+    /// the `offset` to request the next page with, if there are more withdrawals
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_offset: Option<u64>,
+}
+
+/// A deposit that failed to materialize on the subnet, and the withdrawal that refunds it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FailedDepositResponse {
+    pub block_height: u64,
+    /// the block that processed the deposit, and whose withdrawal root includes the refund
+    pub index_block_hash: String,
+    /// the L1 transaction that made the deposit
+    pub txid: String,
+    pub burn_header_hash: String,
+    /// "ft", "nft", or "sft"
+    #[serde(rename = "type")]
+    pub asset_type: String,
+    pub l1_contract: String,
+    pub subnet_contract: String,
+    /// the token ID of an NFT or SFT deposit, as a decimal string
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub asset_id: Option<String>,
+    /// the amount deposited, as a decimal string, except for NFT deposits
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub amount: Option<String>,
+    /// the deposit's Clarity result, e.g. `(err u1)`
+    pub result: String,
+    /// the refund's withdrawal ID, for requesting its Merkle proof
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub refund_withdrawal_id: Option<u32>,
+}
+
+/// A transaction touching a principal, from the node's account index
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AccountTransactionResponse {
+    /// the Stacks transaction, or the L1 transaction of a burnchain operation such as a deposit
+    pub txid: String,
+    pub block_height: u64,
+    pub index_block_hash: String,
+    pub tx_index: u32,
+    /// how the transaction touches the principal: "origin", "sponsor", "contract", "sender",
+    /// and/or "recipient"
+    pub roles: Vec<String>,
+}
+
+/// A page of the transactions touching a principal, newest first
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AccountTransactionsResponse {
+    pub transactions: Vec<AccountTransactionResponse>,
+    /// the `offset` to request the next page with, if there are more transactions
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_offset: Option<u64>,
+}
+
+/// A page of a principal's failed deposits, newest first
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FailedDepositsResponse {
+    pub failed_deposits: Vec<FailedDepositResponse>,
+    /// the `offset` to request the next page with, if there are more failed deposits
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_offset: Option<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MempoolAccountTxEntry {
+    pub txid: String,
+    pub nonce: u64,
+    pub fee: u64,
+}
+
+/// An inclusive range of origin nonces for which the mempool has no transaction
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MempoolNonceGap {
+    pub start: u64,
+    pub end: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MempoolAccountResponse {
+    pub principal: String,
+    /// the account nonce as of the chain tip
+    pub nonce: u64,
+    /// pending transactions whose nonces follow on from `nonce` without a gap, and so can be mined
+    pub chained_txs: Vec<MempoolAccountTxEntry>,
+    /// pending transactions which cannot be mined until the gaps before them are filled
+    pub stranded_txs: Vec<MempoolAccountTxEntry>,
+    pub nonce_gaps: Vec<MempoolNonceGap>,
+    /// the nonce the account's next transaction must use
+    pub lowest_missing_nonce: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AccountNextNonceResponse {
+    pub principal: String,
+    /// the account nonce as of the chain tip
+    pub confirmed_nonce: u64,
+    /// the highest account nonce the mempool saw when it last checked the account's pending
+    /// transactions against the chain, if it has checked any.  This lags `confirmed_nonce`
+    /// until the miner next walks the mempool.
+    pub last_known_nonce: Option<u64>,
+    /// the nonce to give the account's next transaction: the lowest nonce at or above
+    /// `confirmed_nonce` that none of its pending transactions, sent or sponsored, use
+    pub next_nonce: u64,
+    /// one past the account's highest pending nonce.  This equals `next_nonce` unless the
+    /// account's pending transactions are stuck behind `missing_nonces`.
+    pub possible_next_nonce: u64,
+    /// the nonces the account's stuck transactions are waiting on
+    pub missing_nonces: Vec<MempoolNonceGap>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MempoolPreviewEntry {
+    pub txid: String,
+    pub origin_address: String,
+    pub origin_nonce: u64,
+    pub fee: u64,
+    /// the transaction's estimated fee rate, if it has one
+    pub fee_rate: Option<f64>,
+    /// if true, the walk took this transaction from those without a fee-rate estimate, rather
+    /// than from those ordered by fee rate
+    pub no_estimate: bool,
+}
+
+/// The order in which a miner building on `tip` would currently consider mempool transactions
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MempoolPreviewResponse {
+    pub tip: StacksBlockId,
+    pub candidates: Vec<MempoolPreviewEntry>,
+}
+
+/// Which of the mempool's rejected transactions to look up
+#[derive(Debug, Clone, PartialEq)]
+pub enum RejectedTxsQuery {
+    Txid(Txid),
+    /// the transactions an address sent or sponsored
+    Address(StacksAddress),
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RejectedTxEntry {
+    pub txid: String,
+    pub origin_address: String,
+    pub sponsor_address: String,
+    /// the reason code `POST /v2/transactions` answered the rejection with, and its details
+    pub reason: String,
+    pub reason_data: Option<serde_json::Value>,
+    /// the chain tip the transaction was checked against
+    pub consensus_hash: String,
+    pub block_header_hash: String,
+    /// when the transaction was rejected, in seconds since the Unix epoch
+    pub rejected_at: u64,
+}
+
+/// The mempool's kept rejections of the transactions a `RejectedTxsQuery` asked for, newest
+/// first
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RejectedTxsResponse {
+    pub rejected_txs: Vec<RejectedTxEntry>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MinerPauseStatusResponse {
+    /// if true, the miner is not submitting block-commits
+    pub paused: bool,
+}
+
+/// A change of mining key scheduled by the admin key-rotation endpoint
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MiningKeyRotationResponse {
+    /// the L1 address of the key the miner signs with now
+    pub current_miner: String,
+    /// the L1 address of the key the miner will sign with from `switch_burn_height`
+    pub next_miner: String,
+    pub switch_burn_height: u64,
+    /// the L1 transaction registering the next key with the subnet contract
+    pub txid: String,
+}
+
+/// A mempool filter for the admin endpoint to install
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MemPoolFilterRequestBody {
+    pub kind: MemPoolFilterKind,
+    /// the address, contract identifier or function name to filter
+    pub value: String,
+    /// how many seconds the filter applies for
+    pub ttl_secs: u64,
+    /// why the filter is installed, recorded in the audit log
+    pub reason: String,
+}
+
+/// The operator's unexpired mempool filters
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MemPoolFiltersResponse {
+    pub filters: Vec<MemPoolFilter>,
+}
+
+/// The fee market's minimum fee rate at the chain tip, for wallets to quote fees with
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SubnetFeeRateResponse {
+    /// the minimum fee rate, in microSTX per byte, the mempool admits
+    pub min_fee_rate: u64,
+    /// the percentage of a block's budget the fee market aims for
+    pub target_utilization: u64,
+    /// how full the chain tip was, as a percentage of its budget
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_block_utilization: Option<u64>,
+    /// the chain tip the fee rate applies to
+    pub index_block_hash: String,
+}
+
+/// The burnchain operations this node recognized in one L1 block of the canonical fork
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BurnOpsResponse {
+    pub burn_block_height: u64,
+    pub burn_header_hash: String,
+    /// the block-commits, deposits, and other operations, each with the txid of the L1
+    /// transaction that produced it
+    pub ops: Vec<BlockstackOperationType>,
+}
+
+/// The subnet this node follows, so that clients can check they are talking to the right one
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SubnetInfoResponse {
+    /// the subnet's contract on the L1
+    pub l1_contract_identifier: String,
+    /// the L1 block the node starts reading the subnet contract's events from
+    pub first_burn_block_height: u64,
+    pub first_burn_header_hash: String,
+    pub chain_id: u32,
+    pub network_id: u32,
+    pub parent_network_id: u32,
+    /// the Stacks epoch and Clarity version subnet blocks are evaluated in
+    pub stacks_epoch: String,
+    pub clarity_version: String,
+    /// the hash of the genesis chainstate the subnet was booted from
+    pub genesis_chainstate_hash: Sha256Sum,
+    /// this node's version number, which upgrade signals are checked against
+    #[serde(default)]
+    pub node_version: String,
+    /// the upgrade signal in effect at the canonical L1 tip, if any
+    #[serde(default)]
+    pub upgrade: Option<UpgradeSignalInfo>,
+    /// the upgrade signals whose target heights are after the canonical L1 tip
+    #[serde(default)]
+    pub pending_upgrades: Vec<UpgradeSignalInfo>,
+}
+
+/// An upgrade signal from the subnet's L1 contract: from `target_height` on, subnet blocks must
+/// be produced by nodes running at least `required_version`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UpgradeSignalInfo {
+    pub target_height: u64,
+    pub required_version: String,
+    /// whether this node's version meets `required_version`
+    pub version_met: bool,
+}
+
+/// The outcome of a liveness or readiness probe.  It is sent with status 200 if the node passed,
+/// and 503 if it did not.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HealthCheckResponse {
+    pub ok: bool,
+    /// why the node failed the probe; empty if it passed
+    pub failures: Vec<String>,
+    /// the height of the last L1 block the node has processed
+    pub burn_block_height: Option<u64>,
+    /// the height of the latest L1 block the node has received
+    pub l1_tip_height: Option<u64>,
+    /// whether the miner's key is loaded; `None` if this node does not mine
+    pub mining_key_loaded: Option<bool>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ContractPublishPolicyResponse {
+    /// if false, anyone may publish contracts through this node
+    pub restricted: bool,
+    pub allowed_publishers: Vec<String>,
+    /// hex-encoded SHA512/256 hashes of allowed contract code bodies
+    pub allowed_code_hashes: Vec<String>,
+}
+
+/// What a microblock transaction did, once an anchored block has confirmed it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MicroblockTransactionReceipt {
+    /// hex-serialized Clarity value the transaction returned
+    pub result: String,
+    pub post_condition_aborted: bool,
+    pub stx_burned: u128,
+    pub execution_cost: ExecutionCost,
+    pub events: Vec<serde_json::Value>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MicroblockStreamTransaction {
+    pub txid: String,
+    /// hex-serialized transaction
+    pub tx: String,
+    /// `None` unless the stream is confirmed
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub receipt: Option<MicroblockTransactionReceipt>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MicroblockStreamEntry {
+    pub microblock_hash: String,
+    pub sequence: u16,
+    pub parent_microblock_hash: String,
+    pub txs: Vec<MicroblockStreamTransaction>,
+}
+
+/// The microblock stream built on an anchored block, and how far it has been confirmed
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MicroblockStreamResponse {
+    /// the anchored block the stream builds on
+    pub parent_index_block_hash: String,
+    /// `confirmed`, `unconfirmed` or `orphaned`
+    pub status: String,
+    /// the anchored block which confirmed the stream, if `confirmed`
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub confirmed_by: Option<String>,
+    pub microblocks: Vec<MicroblockStreamEntry>,
+}
+
+/// The outcome of executing a transaction on top of a chain tip without persisting it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TransactionSimulationResponse {
+    pub txid: String,
+    /// the chain tip the transaction was executed on top of
+    pub tip: String,
+    /// whether a miner would include the transaction in a block
+    pub okay: bool,
+    /// hex-serialized Clarity value the transaction returned, if `okay`
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub result: Option<String>,
+    /// why the transaction cannot be mined, if not `okay`
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cause: Option<String>,
+    pub post_condition_aborted: bool,
+    pub fee: u64,
+    pub execution_cost: ExecutionCost,
+    pub events: Vec<serde_json::Value>,
+}
+
+/// A transaction in a proposed block that could not be processed
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InvalidBlockTransaction {
+    /// position of the transaction in the block
+    pub index: u32,
+    pub txid: String,
+    pub error: String,
+}
+
+/// The verdict on a proposed block, processed on top of its parent without being stored
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BlockValidationResponse {
+    pub block_hash: String,
+    /// whether the node would accept the block
+    pub valid: bool,
+    /// why the block would be rejected, if not `valid`
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    /// the state root the block produces, if processing got far enough to compute it
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub state_index_root: Option<String>,
+    /// the withdrawal root the block produces, if processing got far enough to compute it
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub withdrawal_merkle_root: Option<String>,
+    pub cost: ExecutionCost,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub first_invalid_tx: Option<InvalidBlockTransaction>,
+}
+
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum UnconfirmedTransactionStatus {
     Microblock {
@@ -1334,6 +1797,7 @@ impl HttpRequestMetadata {
             peer: PeerHost::from_host_port(host, port),
             keep_alive: true,
             canonical_stacks_tip_height,
+            api_key: None,
         }
     }
 
@@ -1346,6 +1810,7 @@ impl HttpRequestMetadata {
             peer: peer_host,
             keep_alive: true,
             canonical_stacks_tip_height,
+            api_key: None,
         }
     }
 
@@ -1364,6 +1829,7 @@ impl HttpRequestMetadata {
             peer: preamble.host.clone(),
             keep_alive: preamble.keep_alive,
             canonical_stacks_tip_height,
+            api_key: preamble.headers.get("x-api-key").cloned(),
         }
     }
 }
@@ -1374,6 +1840,29 @@ pub struct CallReadOnlyRequestBody {
     pub arguments: Vec<String>,
 }
 
+/// One call in a `CallReadOnlyBatchRequestBody`
+#[derive(Serialize, Deserialize)]
+pub struct CallReadOnlyBatchEntry {
+    /// the fully-qualified contract identifier, e.g. `SP000000000000000000002Q6VF78.pox`
+    pub contract: String,
+    pub function: String,
+    pub arguments: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct CallReadOnlyBatchRequestBody {
+    pub sender: String,
+    pub calls: Vec<CallReadOnlyBatchEntry>,
+}
+
+/// A read-only function call in a batch, with its arguments decoded
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReadOnlyCall {
+    pub contract: QualifiedContractIdentifier,
+    pub function: ClarityName,
+    pub arguments: Vec<Value>,
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct FeeRateEstimateRequestBody {
     #[serde(default)]
@@ -1432,8 +1921,18 @@ pub enum HttpRequestType {
     GetMicroblocksConfirmed(HttpRequestMetadata, StacksBlockId),
     GetMicroblocksUnconfirmed(HttpRequestMetadata, StacksBlockId, u16),
     GetTransactionUnconfirmed(HttpRequestMetadata, Txid),
-    PostTransaction(HttpRequestMetadata, StacksTransaction, Option<Attachment>),
+    /// post a transaction, with the expiration height and bundle ID it was submitted with
+    PostTransaction(
+        HttpRequestMetadata,
+        StacksTransaction,
+        Option<Attachment>,
+        Option<u64>,
+        Option<String>,
+    ),
+    SimulateTransaction(HttpRequestMetadata, StacksTransaction, TipRequest),
     PostBlock(HttpRequestMetadata, ConsensusHash, StacksBlock),
+    /// validate a proposed block on top of the parent block elected at the given consensus hash
+    ValidateBlock(HttpRequestMetadata, ConsensusHash, StacksBlock),
     PostMicroblock(HttpRequestMetadata, StacksMicroblock, TipRequest),
     GetWithdrawalStx {
         metadata: HttpRequestMetadata,
@@ -1458,7 +1957,127 @@ pub enum HttpRequestType {
         contract_identifier: QualifiedContractIdentifier,
         id: u128,
     },
+    /// the proof of an STX transfer from this subnet to `destination_subnet`
+    GetWithdrawalStxTransfer {
+        metadata: HttpRequestMetadata,
+        withdraw_block_height: u64,
+        sender: PrincipalData,
+        withdrawal_id: u32,
+        amount: u128,
+        recipient: PrincipalData,
+        destination_subnet: QualifiedContractIdentifier,
+    },
+    /// a page of the withdrawals `sender` made in the canonical fork
+    GetWithdrawalsBySender {
+        metadata: HttpRequestMetadata,
+        sender: PrincipalData,
+        offset: u64,
+        limit: u64,
+    },
+    /// whether the withdrawal `withdrawal_id` of the canonical block at `block_height` can be
+    /// claimed on the L1 yet, and the contract call which claims it
+    GetWithdrawalClaim {
+        metadata: HttpRequestMetadata,
+        block_height: u64,
+        withdrawal_id: u32,
+    },
+    /// a page of the transactions touching `principal` in the canonical fork, if the node keeps
+    /// an account index
+    GetAccountTransactions {
+        metadata: HttpRequestMetadata,
+        principal: PrincipalData,
+        offset: u64,
+        limit: u64,
+    },
+    /// a page of `sender`'s deposits that failed and were refunded in the canonical fork
+    GetFailedDepositsBySender {
+        metadata: HttpRequestMetadata,
+        sender: PrincipalData,
+        offset: u64,
+        limit: u64,
+    },
+    /// the burnchain operations recognized at L1 height `burn_height` in the canonical fork
+    GetBurnOps {
+        metadata: HttpRequestMetadata,
+        burn_height: u64,
+    },
+    /// the tree of L2 blocks committed in the last `burn_blocks` L1 blocks, on every L1 fork
+    GetForks {
+        metadata: HttpRequestMetadata,
+        burn_blocks: u64,
+    },
+    /// the execution cost of each contract's transactions over the canonical fork's last
+    /// `blocks` blocks
+    GetContractCosts {
+        metadata: HttpRequestMetadata,
+        blocks: u64,
+    },
+    /// the L1 commits of the `count` canonical L2 blocks starting at height `l2_height`
+    GetBlockCommits {
+        metadata: HttpRequestMetadata,
+        l2_height: u64,
+        count: u64,
+    },
+    /// what each miner earned from the canonical L2 blocks from `from_height` up to and
+    /// including `to_height`
+    GetRewardsReport {
+        metadata: HttpRequestMetadata,
+        from_height: u64,
+        to_height: u64,
+    },
+    /// the event observer payloads for the canonical L2 blocks from `from_height` up to and
+    /// including `to_height`, for an observer subscribed with the event keys `keys`
+    GetEventReplay {
+        metadata: HttpRequestMetadata,
+        from_height: u64,
+        to_height: u64,
+        keys: Vec<String>,
+    },
+    GetSubnetInfo(HttpRequestMetadata),
+    /// the microblock stream built on the anchored block `parent_block_id`, with the receipts
+    /// of its transactions once it is confirmed
+    GetMicroblockStream {
+        metadata: HttpRequestMetadata,
+        parent_block_id: StacksBlockId,
+    },
+    /// whether the node is up, for liveness probes
+    GetHealthLive(HttpRequestMetadata),
+    /// whether the node is synced with the L1 and able to serve, for readiness probes
+    GetHealthReady(HttpRequestMetadata),
+    /// the first `max_results` transactions a miner would consider, in order
+    GetMempoolPreview {
+        metadata: HttpRequestMetadata,
+        max_results: u64,
+    },
+    /// the mempool's kept rejections of a transaction, or of up to `limit` transactions an
+    /// address sent or sponsored
+    GetRejectedTxs {
+        metadata: HttpRequestMetadata,
+        query: RejectedTxsQuery,
+        limit: u64,
+    },
+    /// counts of the mempool's transactions, by fee rate, age and payload type
+    GetMempoolStats(HttpRequestMetadata),
     GetAccount(HttpRequestMetadata, PrincipalData, TipRequest, bool),
+    GetMempoolAccount(HttpRequestMetadata, StacksAddress, TipRequest),
+    GetAccountNextNonce(HttpRequestMetadata, StacksAddress, TipRequest),
+    GetContractPublishPolicy(HttpRequestMetadata),
+    GetSubnetFeeRate(HttpRequestMetadata),
+    /// Pause (`true`) or resume (`false`) the miner, with the bearer token the request carried
+    SetMinerPaused(HttpRequestMetadata, bool, Option<String>),
+    /// Load the next mining key from the node's config and schedule the switch to it, with the
+    /// bearer token the request carried
+    RotateMiningKey(HttpRequestMetadata, Option<String>),
+    /// List the operator's unexpired mempool filters, with the bearer token the request carried
+    GetMemPoolFilters(HttpRequestMetadata, Option<String>),
+    /// Install a mempool filter, with the bearer token the request carried
+    InstallMemPoolFilter(
+        HttpRequestMetadata,
+        MemPoolFilterRequestBody,
+        Option<String>,
+    ),
+    /// Remove the mempool filter with this ID, with the bearer token the request carried
+    RemoveMemPoolFilter(HttpRequestMetadata, u64, Option<String>),
     GetDataVar(
         HttpRequestMetadata,
         StacksAddress,
@@ -1486,6 +2105,13 @@ pub enum HttpRequestType {
         Vec<Value>,
         TipRequest,
     ),
+    /// several read-only function calls from `sender`, all evaluated at the same chain tip
+    CallReadOnlyFunctionBatch {
+        metadata: HttpRequestMetadata,
+        sender: PrincipalData,
+        calls: Vec<ReadOnlyCall>,
+        tip: TipRequest,
+    },
     GetTransferCost(HttpRequestMetadata),
     GetContractSrc(
         HttpRequestMetadata,
@@ -1613,8 +2239,35 @@ pub enum HttpResponseType {
     GetDataVar(HttpResponseMetadata, DataVarResponse),
     GetMapEntry(HttpResponseMetadata, MapEntryResponse),
     CallReadOnlyFunction(HttpResponseMetadata, CallReadOnlyResponse),
+    CallReadOnlyFunctionBatch(HttpResponseMetadata, Vec<CallReadOnlyResponse>),
     GetAccount(HttpResponseMetadata, AccountEntryResponse),
     GetWithdrawal(HttpResponseMetadata, WithdrawalResponse),
+    GetWithdrawalsBySender(HttpResponseMetadata, WithdrawalsResponse),
+    GetWithdrawalClaim(HttpResponseMetadata, WithdrawalClaimResponse),
+    GetFailedDepositsBySender(HttpResponseMetadata, FailedDepositsResponse),
+    GetAccountTransactions(HttpResponseMetadata, AccountTransactionsResponse),
+    GetMempoolAccount(HttpResponseMetadata, MempoolAccountResponse),
+    GetAccountNextNonce(HttpResponseMetadata, AccountNextNonceResponse),
+    SimulateTransaction(HttpResponseMetadata, TransactionSimulationResponse),
+    BlockValidation(HttpResponseMetadata, BlockValidationResponse),
+    GetContractPublishPolicy(HttpResponseMetadata, ContractPublishPolicyResponse),
+    MinerPauseStatus(HttpResponseMetadata, MinerPauseStatusResponse),
+    MiningKeyRotation(HttpResponseMetadata, MiningKeyRotationResponse),
+    MemPoolFilters(HttpResponseMetadata, MemPoolFiltersResponse),
+    MemPoolFilter(HttpResponseMetadata, MemPoolFilter),
+    GetSubnetFeeRate(HttpResponseMetadata, SubnetFeeRateResponse),
+    GetBurnOps(HttpResponseMetadata, BurnOpsResponse),
+    GetForks(HttpResponseMetadata, ForkTree),
+    GetContractCosts(HttpResponseMetadata, RecentContractCosts),
+    GetBlockCommits(HttpResponseMetadata, BlockCommits),
+    GetRewardsReport(HttpResponseMetadata, RewardsReport),
+    GetEventReplay(HttpResponseMetadata, EventReplay),
+    GetSubnetInfo(HttpResponseMetadata, SubnetInfoResponse),
+    GetMicroblockStream(HttpResponseMetadata, MicroblockStreamResponse),
+    HealthCheck(HttpResponseMetadata, HealthCheckResponse),
+    GetMempoolPreview(HttpResponseMetadata, MempoolPreviewResponse),
+    GetRejectedTxs(HttpResponseMetadata, RejectedTxsResponse),
+    GetMempoolStats(HttpResponseMetadata, MemPoolStats),
     GetContractABI(HttpResponseMetadata, ContractInterface),
     GetContractSrc(HttpResponseMetadata, ContractSrcResponse),
     GetIsTraitImplemented(HttpResponseMetadata, GetIsTraitImplementedResponse),
@@ -1633,6 +2286,7 @@ pub enum HttpResponseType {
     Forbidden(HttpResponseMetadata, String),
     NotFound(HttpResponseMetadata, String),
     ServerError(HttpResponseMetadata, String),
+    TooManyRequests(HttpResponseMetadata, String),
     ServiceUnavailable(HttpResponseMetadata, String),
     Error(HttpResponseMetadata, u16, String),
     BlockProposalValid {
@@ -1916,6 +2570,45 @@ pub const MAX_MICROBLOCKS_UNCONFIRMED: usize = 1024;
 // maximum number of block headers we'll get streamed to us
 pub const MAX_HEADERS: usize = 2100;
 
+// maximum number of withdrawals we'll return in one page of a principal's withdrawals
+pub const MAX_WITHDRAWALS_PAGE_SIZE: u64 = 50;
+
+// maximum number of failed deposits we'll return in one page of a principal's failed deposits
+pub const MAX_FAILED_DEPOSITS_PAGE_SIZE: u64 = 50;
+
+// maximum number of transactions we'll return in one page of a principal's account history
+pub const MAX_ACCOUNT_TRANSACTIONS_PAGE_SIZE: u64 = 50;
+
+// number of L1 blocks whose L2 blocks we'll return in a fork tree, if the client doesn't say
+pub const DEFAULT_FORK_TREE_BURN_BLOCKS: u64 = 20;
+
+// maximum number of L1 blocks whose L2 blocks we'll return in a fork tree
+pub const MAX_FORK_TREE_BURN_BLOCKS: u64 = 200;
+
+// number of blocks we'll sum contract costs over, if the client doesn't say
+pub const DEFAULT_CONTRACT_COSTS_BLOCKS: u64 = 100;
+
+// number of L2 heights we'll return the commits of, if the client doesn't say
+pub const DEFAULT_BLOCK_COMMITS: u64 = 1;
+
+// number of transactions we'll return in a mempool preview, if the client doesn't say
+pub const DEFAULT_MEMPOOL_PREVIEW_RESULTS: u64 = 50;
+
+// maximum number of transactions we'll return in a mempool preview
+pub const MAX_MEMPOOL_PREVIEW_RESULTS: u64 = 500;
+
+// maximum time we'll spend walking the mempool for a preview, in milliseconds
+pub const MEMPOOL_PREVIEW_WALK_TIME_MS: u64 = 1_000;
+
+// number of an address's rejected transactions we'll return, if the client doesn't say
+pub const DEFAULT_REJECTED_TXS_RESULTS: u64 = 50;
+
+// maximum number of an address's rejected transactions we'll return
+pub const MAX_REJECTED_TXS_RESULTS: u64 = 500;
+
+// maximum number of read-only function calls in one batch
+pub const MAX_CALL_READ_BATCH_SIZE: usize = 32;
+
 // how long a peer will be denied for if it misbehaves
 #[cfg(test)]
 pub const DENY_BAN_DURATION: u64 = 30; // seconds
@@ -2107,6 +2800,9 @@ pub mod test {
     use crate::chainstate::coordinator::tests::*;
     use crate::chainstate::coordinator::*;
     use crate::chainstate::stacks::boot::*;
+    use crate::chainstate::stacks::db::activations::FeatureActivations;
+    use crate::chainstate::stacks::db::coinbase_schedule::CoinbaseSchedule;
+    use crate::chainstate::stacks::db::withdrawal_policy::QueuedWithdrawal;
     use crate::chainstate::stacks::db::StacksChainState;
     use crate::chainstate::stacks::db::*;
     use crate::chainstate::stacks::miner::test::*;
@@ -2209,6 +2905,22 @@ pub mod test {
                             "sender": op.sender,
                             "l1_contract_id": op.l1_contract_id,
                             "subnet_contract_id": op.subnet_contract_id,
+                            "token_uri": op.token_uri,
+                        }),
+                    )
+                    .unwrap();
+                    Ok(())
+                }
+                BlockstackOperationType::DepositSft(ref op) => {
+                    serde_json::to_writer(
+                        fd,
+                        &json!({
+                            "op": "deposit_sft",
+                            "id": op.id,
+                            "amount": op.amount,
+                            "sender": op.sender,
+                            "l1_contract_id": op.l1_contract_id,
+                            "subnet_contract_id": op.subnet_contract_id,
                         }),
                     )
                     .unwrap();
@@ -2253,6 +2965,30 @@ pub mod test {
                     .unwrap();
                     Ok(())
                 }
+                BlockstackOperationType::SetBlockLimit(ref op) => {
+                    serde_json::to_writer(
+                        fd,
+                        &json!({
+                            "op": "set_block_limit",
+                            "activation_height": op.activation_height,
+                            "block_limit": op.block_limit,
+                        }),
+                    )
+                    .unwrap();
+                    Ok(())
+                }
+                BlockstackOperationType::UpgradeSignal(ref op) => {
+                    serde_json::to_writer(
+                        fd,
+                        &json!({
+                            "op": "upgrade_signal",
+                            "target_height": op.target_height,
+                            "required_version": op.required_version,
+                        }),
+                    )
+                    .unwrap();
+                    Ok(())
+                }
             }
         }
 
@@ -2478,6 +3214,8 @@ pub mod test {
             parent_burn_block_timestamp: u64,
             _anchor_block_cost: &ExecutionCost,
             _confirmed_mblock_cost: &ExecutionCost,
+            _withdrawals_queued: &Vec<QueuedWithdrawal>,
+            _withdrawals_released: &Vec<QueuedWithdrawal>,
         ) {
             self.blocks.lock().unwrap().push(TestEventObserverBlock {
                 block: block.clone(),
@@ -2501,6 +3239,15 @@ pub mod test {
             // pass
         }
 
+        fn announce_rejected_block(
+            &self,
+            _consensus_hash: &ConsensusHash,
+            _block_hash: &BlockHeaderHash,
+            _reason: &str,
+        ) {
+            // pass
+        }
+
         fn dispatch_boot_receipts(&mut self, _receipts: Vec<StacksTransactionReceipt>) {
             // pass
         }
@@ -2531,6 +3278,9 @@ pub mod test {
         pub spending_account: TestMiner,
         pub setup_code: String,
         pub epochs: Option<Vec<StacksEpoch>>,
+        pub feature_activations: FeatureActivations,
+        pub coinbase_schedule: CoinbaseSchedule,
+        pub deposit_confirmations: u64,
     }
 
     impl TestPeerConfig {
@@ -2577,6 +3327,9 @@ pub mod test {
                 spending_account: spending_account,
                 setup_code: "".into(),
                 epochs: None,
+                feature_activations: FeatureActivations::default(),
+                coinbase_schedule: CoinbaseSchedule::default(),
+                deposit_confirmations: 0,
             }
         }
 
@@ -2813,6 +3566,9 @@ pub mod test {
                 config.initial_balances.clone(),
                 Some(Box::new(post_flight_callback)),
             );
+            boot_data.feature_activations = config.feature_activations.clone();
+            boot_data.coinbase_schedule = config.coinbase_schedule.clone();
+            boot_data.deposit_confirmations = config.deposit_confirmations;
 
             if !config.initial_lockups.is_empty() {
                 let lockups = config.initial_lockups.clone();
@@ -48,11 +48,13 @@ use crate::chainstate::burn::BlockSnapshot;
 use crate::chainstate::stacks::db::StacksChainState;
 use crate::chainstate::stacks::{MAX_BLOCK_LEN, MAX_TRANSACTION_LEN};
 use crate::monitoring::{update_inbound_neighbors, update_outbound_neighbors};
+use crate::net::api_keys::RPCApiKeys;
 use crate::net::asn::ASEntry4;
 use crate::net::atlas::AtlasDB;
 use crate::net::atlas::{AttachmentInstance, AttachmentsDownloader};
 use crate::net::chat::ConversationP2P;
 use crate::net::chat::NeighborStats;
+use crate::net::chat::{BLOCK_POINT_LIFETIME, NUM_BLOCK_POINTS};
 use crate::net::connection::ConnectionOptions;
 use crate::net::connection::NetworkReplyHandle;
 use crate::net::connection::ReplyHandleP2P;
@@ -64,6 +66,7 @@ use crate::net::neighbors::*;
 use crate::net::poll::NetworkPollState;
 use crate::net::poll::NetworkState;
 use crate::net::prune::*;
+use crate::net::ratelimit::RPCRateLimiter;
 use crate::net::relay::RelayerStats;
 use crate::net::relay::*;
 use crate::net::relay::*;
@@ -97,6 +100,7 @@ pub enum NetworkRequest {
     ), // announce to all wanting neighbors that we have these confirmed microblock streams
     Relay(NeighborKey, StacksMessage),
     Broadcast(Vec<RelayData>, StacksMessageType),
+    FetchBlocks(u64), // have the downloader fetch the blocks from this burn block height on, now
 }
 
 /// Handle for other threads to use to issue p2p network requests.
@@ -182,6 +186,13 @@ impl NetworkHandle {
         let req = NetworkRequest::Broadcast(relay_hints, msg);
         self.send_request(req)
     }
+
+    /// Have the downloader fetch the blocks of the sortitions from `burn_block_height` on
+    /// straight away, rather than at its next scan.  Called when a block is found missing.
+    pub fn fetch_blocks(&mut self, burn_block_height: u64) -> Result<(), net_error> {
+        let req = NetworkRequest::FetchBlocks(burn_block_height);
+        self.send_request(req)
+    }
 }
 
 impl NetworkHandleServer {
@@ -302,9 +313,11 @@ pub struct PeerNetwork {
     // outstanding request to perform a mempool sync
     // * mempool_sync_deadline is when the next mempool sync must start
     // * mempool_sync_timeout is when the current mempool sync must stop
+    // * mempool_sync_rx_counts tracks the (timestamp, num bytes) of recent mempool sync pages
     mempool_state: MempoolSyncState,
     mempool_sync_deadline: u64,
     mempool_sync_timeout: u64,
+    mempool_sync_rx_counts: VecDeque<(u64, u64)>,
 
     // how often we pruned a given inbound/outbound peer
     pub prune_outbound_counts: HashMap<NeighborKey, u64>,
@@ -313,6 +326,12 @@ pub struct PeerNetwork {
     // http endpoint, used for driving HTTP conversations (some of which we initiate)
     pub http: Option<HttpPeer>,
 
+    // per-client rate limits on RPC requests.  Lives here rather than in `http`, since `http` is
+    // taken out of the network while its conversations are driven.
+    pub rpc_rate_limiter: RPCRateLimiter,
+    // the API keys which may submit transactions, and their rate limits
+    pub rpc_api_keys: RPCApiKeys,
+
     // our own neighbor address that we bind on
     bind_nk: NeighborKey,
 
@@ -362,6 +381,8 @@ impl PeerNetwork {
         epochs: Vec<StacksEpoch>,
     ) -> PeerNetwork {
         let http = HttpPeer::new(connection_opts.clone(), 0);
+        let rpc_rate_limiter = RPCRateLimiter::new(&connection_opts);
+        let rpc_api_keys = RPCApiKeys::new(&connection_opts);
         let pub_ip = connection_opts.public_ip_address.clone();
         let pub_ip_learned = pub_ip.is_none();
         local_peer.public_ip_address = pub_ip.clone();
@@ -427,11 +448,14 @@ impl PeerNetwork {
             mempool_state: MempoolSyncState::PickOutboundPeer,
             mempool_sync_deadline: 0,
             mempool_sync_timeout: 0,
+            mempool_sync_rx_counts: VecDeque::new(),
 
             prune_outbound_counts: HashMap::new(),
             prune_inbound_counts: HashMap::new(),
 
             http: Some(http),
+            rpc_rate_limiter,
+            rpc_api_keys,
             bind_nk: NeighborKey {
                 network_id: 0,
                 peer_version: 0,
@@ -1039,6 +1063,19 @@ impl PeerNetwork {
             NetworkRequest::Relay(neighbor_key, msg) => self
                 .relay_signed_message(&neighbor_key, msg)
                 .and_then(|_| Ok(())),
+            NetworkRequest::FetchBlocks(burn_block_height) => {
+                let sortition_height =
+                    burn_block_height.saturating_sub(self.burnchain.first_block_height);
+                if let Some(ref mut downloader) = self.block_downloader {
+                    debug!(
+                        "{:?}: wake up downloader to fetch missing blocks from sortition height {}",
+                        &self.local_peer, sortition_height
+                    );
+                    downloader.hint_block_sortition_height_available(sortition_height, false, true);
+                    self.have_data_to_download = true;
+                }
+                Ok(())
+            }
             NetworkRequest::Broadcast(relay_hints, msg) => {
                 // pick some neighbors. Note that only some messages can be broadcasted.
                 let neighbor_keys = match msg {
@@ -1285,6 +1322,7 @@ impl PeerNetwork {
     /// Check to see if we can register the given socket
     /// * we can't have registered this neighbor already
     /// * if this is inbound, we can't add more than self.num_clients
+    /// * if this is outbound, we can't add more than self.max_outbound_neighbors
     pub fn can_register_peer(
         &mut self,
         neighbor_key: &NeighborKey,
@@ -1333,6 +1371,18 @@ impl PeerNetwork {
             return Err(net_error::TooManyPeers);
         }
 
+        if outbound
+            && self.connection_opts.max_outbound_neighbors > 0
+            && num_outbound >= self.connection_opts.max_outbound_neighbors
+        {
+            // too many outbounds
+            info!(
+                "{:?}: Too many outbound connections ({} >= {})",
+                &self.local_peer, num_outbound, self.connection_opts.max_outbound_neighbors
+            );
+            return Err(net_error::TooManyPeers);
+        }
+
         Ok(())
     }
 
@@ -3148,6 +3198,32 @@ impl PeerNetwork {
         self.mempool_sync_timeout = 0;
     }
 
+    /// Remember how many bytes of transactions a mempool sync page gave us
+    fn mempool_sync_add_rx(&mut self, txs: &[StacksTransaction]) {
+        use stacks_common::codec::StacksMessageCodec;
+        let num_bytes: u64 = txs
+            .iter()
+            .map(|tx| tx.serialize_to_vec().len() as u64)
+            .sum();
+        self.mempool_sync_rx_counts
+            .push_back((get_epoch_time_secs(), num_bytes));
+        while self.mempool_sync_rx_counts.len() > NUM_BLOCK_POINTS {
+            self.mempool_sync_rx_counts.pop_front();
+        }
+    }
+
+    /// Get the rate, in bytes/sec, at which we've recently downloaded mempool sync pages
+    pub fn get_mempool_sync_bandwidth(&self) -> f64 {
+        NeighborStats::get_bandwidth(&self.mempool_sync_rx_counts, BLOCK_POINT_LIFETIME)
+    }
+
+    /// Have we downloaded mempool sync pages faster than `max_mempool_sync_bandwidth` allows?
+    fn mempool_sync_throttled(&self) -> bool {
+        self.connection_opts.max_mempool_sync_bandwidth > 0
+            && self.get_mempool_sync_bandwidth()
+                > (self.connection_opts.max_mempool_sync_bandwidth as f64)
+    }
+
     /// Pick a peer to mempool sync with.
     /// Returns Ok(None) if we're done syncing the mempool.
     /// Returns Ok(Some(..)) if we're not done, and can proceed
@@ -3176,7 +3252,10 @@ impl PeerNetwork {
             idx = (idx + 1) % self.peers.len();
 
             if let Some(convo) = self.peers.get(&event_id) {
-                if !convo.is_authenticated() || !convo.is_outbound() {
+                if !convo.is_authenticated() {
+                    continue;
+                }
+                if !convo.is_outbound() && !self.connection_opts.mempool_sync_inbound {
                     continue;
                 }
                 if !ConversationP2P::supports_mempool_query(convo.peer_services) {
@@ -3473,7 +3552,18 @@ impl PeerNetwork {
                     }
                 }
                 MempoolSyncState::SendQuery(ref url, ref addr, ref page_id) => {
-                    // 3. ask for the remote peer's mempool's novel txs
+                    // 3. ask for the remote peer's mempool's novel txs, once we're within our
+                    // bandwidth budget
+                    if self.mempool_sync_throttled() {
+                        debug!(
+                            "{:?}: Mempool sync exceeded max bandwidth of {} bytes/sec (currently at {}); will query {} later",
+                            &self.local_peer,
+                            self.connection_opts.max_mempool_sync_bandwidth,
+                            self.get_mempool_sync_bandwidth(),
+                            url
+                        );
+                        return Ok((false, None));
+                    }
                     debug!(
                         "{:?}: Mempool sync will query {} for mempool transactions at {}",
                         &self.local_peer, url, page_id
@@ -3511,6 +3601,7 @@ impl PeerNetwork {
                                 txs.len(),
                                 &next_page_id_opt
                             );
+                            self.mempool_sync_add_rx(&txs);
 
                             // done! got data
                             let ret = match next_page_id_opt {
@@ -5311,6 +5402,57 @@ impl PeerNetwork {
         debug!("<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<< End Network Dispatch <<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<");
         Ok(network_result)
     }
+
+    /// Serve only the HTTP RPC interface: keep the burnchain view up to date and answer RPC
+    /// requests, but don't talk to any peers.  Used by a read-only replica of another node's
+    /// databases, which must never write to them, and which has no relayer to hand blocks or
+    /// transactions to.  The p2p socket is bound but never serviced, so bind it to an address
+    /// no peer will connect to.
+    pub fn run_http_only(
+        &mut self,
+        sortdb: &SortitionDB,
+        chainstate: &mut StacksChainState,
+        mempool: &mut MemPoolDB,
+        poll_timeout: u64,
+        handler_args: &RPCHandlerArgs,
+    ) -> Result<(), net_error> {
+        let mut poll_states = match self.network {
+            None => {
+                debug!("{:?}: network not connected", &self.local_peer);
+                Err(net_error::NotConnected)
+            }
+            Some(ref mut network) => network.poll(poll_timeout),
+        }?;
+        let http_poll_state = poll_states
+            .remove(&self.http_network_handle)
+            .expect("BUG: no poll state for http network handle");
+
+        // update local-peer, burnchain, and PoX views for /v2/info
+        self.refresh_local_peer()?;
+        self.refresh_burnchain_view(sortdb, chainstate, false)?;
+        self.refresh_sortition_view(sortdb)?;
+
+        PeerNetwork::with_network_state(self, |ref mut network, ref mut network_state| {
+            let http_stacks_msgs = PeerNetwork::with_http(network, |ref mut net, ref mut http| {
+                http.run(
+                    network_state,
+                    net,
+                    sortdb,
+                    chainstate,
+                    mempool,
+                    http_poll_state,
+                    handler_args,
+                )
+            })?;
+            if !http_stacks_msgs.is_empty() {
+                debug!(
+                    "Dropping {} messages from HTTP clients, since this node does not relay",
+                    http_stacks_msgs.len()
+                );
+            }
+            Ok(())
+        })
+    }
 }
 
 #[cfg(test)]
@@ -5497,6 +5639,113 @@ mod test {
         })
     }
 
+    #[test]
+    fn test_can_register_peer_max_outbound_neighbors() {
+        let neighbor_1 = make_test_neighbor(2500);
+        let neighbor_2 = make_test_neighbor(2501);
+        let mut p2p = make_test_p2p_network(&vec![]);
+        p2p.connection_opts.max_outbound_neighbors = 1;
+
+        assert!(p2p.can_register_peer(&neighbor_1.addr, true).is_ok());
+
+        let convo = ConversationP2P::new(
+            p2p.local_peer.network_id,
+            p2p.peer_version,
+            &p2p.burnchain,
+            &"127.0.0.1:2500".parse().unwrap(),
+            &p2p.connection_opts,
+            true,
+            1,
+            p2p.epochs.clone(),
+        );
+        p2p.peers.insert(1, convo);
+        p2p.events.insert(neighbor_1.addr.clone(), 1);
+
+        // at the outbound cap, but inbound connections are still accepted
+        assert_eq!(
+            p2p.can_register_peer(&neighbor_2.addr, true),
+            Err(net_error::TooManyPeers)
+        );
+        assert!(p2p.can_register_peer(&neighbor_2.addr, false).is_ok());
+
+        // 0 lifts the cap
+        p2p.connection_opts.max_outbound_neighbors = 0;
+        assert!(p2p.can_register_peer(&neighbor_2.addr, true).is_ok());
+    }
+
+    #[test]
+    fn test_connect_peer_max_outbound_neighbors() {
+        with_timeout(100, || {
+            let neighbor_1 = make_test_neighbor(2510);
+            let neighbor_2 = make_test_neighbor(2511);
+            let mut p2p = make_test_p2p_network(&vec![]);
+            p2p.connection_opts.max_outbound_neighbors = 1;
+
+            use std::net::TcpListener;
+            let listener_1 = TcpListener::bind("127.0.0.1:2510").unwrap();
+            let listener_2 = TcpListener::bind("127.0.0.1:2511").unwrap();
+
+            // start fake neighbor endpoints, which will each accept once and wait 5 seconds
+            let endpoint_threads: Vec<_> = vec![listener_1, listener_2]
+                .into_iter()
+                .map(|listener| {
+                    thread::spawn(move || {
+                        let (_sock, addr) = listener.accept().unwrap();
+                        test_debug!("Accepted {:?}", &addr);
+                        thread::sleep(time::Duration::from_millis(5000));
+                    })
+                })
+                .collect();
+
+            p2p.bind(
+                &"127.0.0.1:2520".parse().unwrap(),
+                &"127.0.0.1:2521".parse().unwrap(),
+            )
+            .unwrap();
+            p2p.connect_peer(&neighbor_1.addr).unwrap();
+            p2p.connect_peer(&neighbor_2.addr).unwrap();
+
+            // start dispatcher
+            let p2p_thread = thread::spawn(move || {
+                for i in 0..5 {
+                    test_debug!("dispatch batch {}", i);
+
+                    p2p.dispatch_requests();
+                    let mut poll_states = match p2p.network {
+                        None => {
+                            panic!("network not connected");
+                        }
+                        Some(ref mut network) => network.poll(100).unwrap(),
+                    };
+
+                    let mut p2p_poll_state = poll_states.remove(&p2p.p2p_network_handle).unwrap();
+
+                    p2p.process_new_sockets(&mut p2p_poll_state).unwrap();
+                    p2p.process_connecting_sockets(&mut p2p_poll_state);
+
+                    thread::sleep(time::Duration::from_millis(1000));
+                }
+
+                // both connections completed, but only the first one was registered
+                assert_eq!(p2p.connecting.len(), 0);
+                assert_eq!(p2p.peers.len(), 1);
+                assert_eq!(PeerNetwork::count_outbound_conversations(&p2p.peers), 1);
+
+                // the refused socket was deregistered -- only server events and the one
+                // registered peer remain
+                assert_eq!(p2p.network.as_ref().unwrap().num_events(), 3);
+            });
+
+            p2p_thread.join().unwrap();
+            test_debug!("dispatcher thread joined");
+
+            for endpoint_thread in endpoint_threads {
+                endpoint_thread.join().unwrap();
+            }
+            test_debug!("fake endpoint threads joined");
+        })
+    }
+
     // tests relay_signed_message()
     #[test]
     #[ignore]
@@ -6142,4 +6391,28 @@ mod test {
             }
         });
     }
+
+    #[test]
+    fn test_mempool_sync_bandwidth_limit() {
+        let mut peer_config = TestPeerConfig::new("test_mempool_sync_bandwidth_limit", 2218, 2219);
+        peer_config.connection_opts.max_mempool_sync_bandwidth = 1000;
+        let mut peer = TestPeer::new(peer_config);
+
+        // nothing downloaded yet
+        assert!(!peer.network.mempool_sync_throttled());
+
+        let now = get_epoch_time_secs();
+        peer.network
+            .mempool_sync_rx_counts
+            .push_back((now - 2, 4000));
+        peer.network.mempool_sync_rx_counts.push_back((now, 4000));
+
+        // 8000 bytes in 2 seconds
+        assert_eq!(peer.network.get_mempool_sync_bandwidth(), 4000.0);
+        assert!(peer.network.mempool_sync_throttled());
+
+        // no limit
+        peer.network.connection_opts.max_mempool_sync_bandwidth = 0;
+        assert!(!peer.network.mempool_sync_throttled());
+    }
 }
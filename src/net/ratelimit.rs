@@ -0,0 +1,239 @@
+// Copyright (C) 2013-2020 Blockstack PBC, a public benefit corporation
+// Copyright (C) 2020 Stacks Open Internet Foundation
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Per-client rate limiting of RPC requests.  Each client IP address gets a token bucket for
+//! each class of request, which refills at the configured rate up to the configured burst size.
+//! A request which finds its bucket empty is answered with a 429 instead of being handled.
+
+use std::collections::HashMap;
+use std::net::IpAddr;
+
+use crate::net::connection::ConnectionOptions;
+use crate::net::HttpRequestType;
+
+/// Once this many buckets are tracked, the ones which have refilled completely are forgotten
+const PRUNE_THRESHOLD: usize = 4096;
+
+/// The classes of RPC request, which are rate-limited separately
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RPCRequestClass {
+    /// requests which only read node state
+    Read,
+    /// requests which submit transactions or blocks
+    Submit,
+    /// event replays and microblock streams, which re-execute blocks
+    Replay,
+}
+
+impl RPCRequestClass {
+    pub fn of(req: &HttpRequestType) -> RPCRequestClass {
+        match req {
+            HttpRequestType::PostTransaction(..)
+            | HttpRequestType::PostBlock(..)
+            | HttpRequestType::PostMicroblock(..) => RPCRequestClass::Submit,
+            HttpRequestType::GetEventReplay { .. }
+            | HttpRequestType::GetMicroblockStream { .. } => RPCRequestClass::Replay,
+            _ => RPCRequestClass::Read,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RPCRequestClass::Read => "read",
+            RPCRequestClass::Submit => "submit",
+            RPCRequestClass::Replay => "replay",
+        }
+    }
+}
+
+/// How many requests per second a client may make, and how many it may make at once
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RateLimit {
+    /// 0 means unlimited
+    pub rate: u64,
+    pub burst: u64,
+}
+
+impl RateLimit {
+    fn capacity(&self) -> f64 {
+        self.burst.max(1) as f64
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct TokenBucket {
+    tokens: f64,
+    last_refill_ms: u128,
+}
+
+impl TokenBucket {
+    pub(crate) fn new(limit: &RateLimit, now_ms: u128) -> TokenBucket {
+        TokenBucket {
+            tokens: limit.capacity(),
+            last_refill_ms: now_ms,
+        }
+    }
+
+    pub(crate) fn refill(&mut self, limit: &RateLimit, now_ms: u128) {
+        let elapsed_ms = now_ms.saturating_sub(self.last_refill_ms);
+        self.tokens = (self.tokens + (elapsed_ms as f64) * (limit.rate as f64) / 1000.0)
+            .min(limit.capacity());
+        self.last_refill_ms = now_ms;
+    }
+
+    /// Refill the bucket, and take a token from it if it has one
+    pub(crate) fn try_take(&mut self, limit: &RateLimit, now_ms: u128) -> bool {
+        self.refill(limit, now_ms);
+        if self.tokens < 1.0 {
+            return false;
+        }
+        self.tokens -= 1.0;
+        true
+    }
+}
+
+pub struct RPCRateLimiter {
+    read_limit: RateLimit,
+    submit_limit: RateLimit,
+    replay_limit: RateLimit,
+    buckets: HashMap<(IpAddr, RPCRequestClass), TokenBucket>,
+}
+
+impl RPCRateLimiter {
+    pub fn new(conn_opts: &ConnectionOptions) -> RPCRateLimiter {
+        RPCRateLimiter {
+            read_limit: RateLimit {
+                rate: conn_opts.rpc_read_rate_limit,
+                burst: conn_opts.rpc_read_burst,
+            },
+            submit_limit: RateLimit {
+                rate: conn_opts.rpc_submit_rate_limit,
+                burst: conn_opts.rpc_submit_burst,
+            },
+            replay_limit: RateLimit {
+                rate: conn_opts.rpc_replay_rate_limit,
+                burst: conn_opts.rpc_replay_burst,
+            },
+            buckets: HashMap::new(),
+        }
+    }
+
+    fn limit(&self, class: RPCRequestClass) -> RateLimit {
+        match class {
+            RPCRequestClass::Read => self.read_limit,
+            RPCRequestClass::Submit => self.submit_limit,
+            RPCRequestClass::Replay => self.replay_limit,
+        }
+    }
+
+    /// May the client at `ip` make a request of class `class` at time `now_ms`?  If so, the
+    /// request is charged to the client's bucket.
+    pub fn try_acquire(&mut self, ip: IpAddr, class: RPCRequestClass, now_ms: u128) -> bool {
+        let limit = self.limit(class);
+        if limit.rate == 0 {
+            return true;
+        }
+        if self.buckets.len() >= PRUNE_THRESHOLD {
+            self.prune(now_ms);
+        }
+
+        let bucket = self
+            .buckets
+            .entry((ip, class))
+            .or_insert_with(|| TokenBucket::new(&limit, now_ms));
+        bucket.try_take(&limit, now_ms)
+    }
+
+    /// Forget the buckets which have refilled completely, since a fresh bucket is the same.
+    pub fn prune(&mut self, now_ms: u128) {
+        let read_limit = self.read_limit;
+        let submit_limit = self.submit_limit;
+        let replay_limit = self.replay_limit;
+        self.buckets.retain(|(_, class), bucket| {
+            let limit = match class {
+                RPCRequestClass::Read => read_limit,
+                RPCRequestClass::Submit => submit_limit,
+                RPCRequestClass::Replay => replay_limit,
+            };
+            bucket.refill(&limit, now_ms);
+            bucket.tokens < limit.capacity()
+        });
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_rpc_rate_limiter() {
+        let mut conn_opts = ConnectionOptions::default();
+        conn_opts.rpc_read_rate_limit = 10;
+        conn_opts.rpc_read_burst = 3;
+        conn_opts.rpc_submit_rate_limit = 1;
+        conn_opts.rpc_submit_burst = 1;
+        let mut limiter = RPCRateLimiter::new(&conn_opts);
+
+        let client: IpAddr = "1.2.3.4".parse().unwrap();
+        let other_client: IpAddr = "5.6.7.8".parse().unwrap();
+
+        // a full bucket admits a burst
+        for _ in 0..3 {
+            assert!(limiter.try_acquire(client, RPCRequestClass::Read, 1000));
+        }
+        assert!(!limiter.try_acquire(client, RPCRequestClass::Read, 1000));
+
+        // other clients and classes have their own buckets
+        assert!(limiter.try_acquire(other_client, RPCRequestClass::Read, 1000));
+        assert!(limiter.try_acquire(client, RPCRequestClass::Submit, 1000));
+        assert!(!limiter.try_acquire(client, RPCRequestClass::Submit, 1500));
+
+        // 10 reads/sec refills a token every 100ms
+        assert!(!limiter.try_acquire(client, RPCRequestClass::Read, 1050));
+        assert!(limiter.try_acquire(client, RPCRequestClass::Read, 1100));
+        assert!(!limiter.try_acquire(client, RPCRequestClass::Read, 1100));
+        assert!(limiter.try_acquire(client, RPCRequestClass::Submit, 2000));
+
+        // only the buckets which haven't refilled are kept
+        limiter.prune(1200);
+        assert_eq!(limiter.buckets.len(), 2);
+        limiter.prune(5000);
+        assert!(limiter.buckets.is_empty());
+
+        // no limit
+        conn_opts.rpc_read_rate_limit = 0;
+        let mut limiter = RPCRateLimiter::new(&conn_opts);
+        for _ in 0..100 {
+            assert!(limiter.try_acquire(client, RPCRequestClass::Read, 1000));
+        }
+        assert!(limiter.buckets.is_empty());
+    }
+
+    #[test]
+    fn test_rpc_rate_limiter_replay_by_default() {
+        let conn_opts = ConnectionOptions::default();
+        let mut limiter = RPCRateLimiter::new(&conn_opts);
+        let client: IpAddr = "1.2.3.4".parse().unwrap();
+
+        // reads are unlimited, but event replays are not
+        for _ in 0..10 {
+            assert!(limiter.try_acquire(client, RPCRequestClass::Read, 1000));
+        }
+        assert!(limiter.try_acquire(client, RPCRequestClass::Replay, 1000));
+        assert!(!limiter.try_acquire(client, RPCRequestClass::Replay, 1500));
+        assert!(limiter.try_acquire(client, RPCRequestClass::Replay, 2000));
+    }
+}
@@ -53,6 +53,7 @@ use stacks_common::util::hash::Sha512Trunc256Sum;
 
 use crate::chainstate::coordinator::BlockEventDispatcher;
 use crate::chainstate::stacks::db::unconfirmed::ProcessedUnconfirmedState;
+use crate::monitoring;
 use crate::monitoring::update_stacks_tip_height;
 use crate::types::chainstate::SortitionId;
 use stacks_common::codec::MAX_PAYLOAD_LEN;
@@ -64,10 +65,14 @@ pub const MAX_RELAYER_STATS: usize = 4096;
 pub const MAX_RECENT_MESSAGES: usize = 256;
 pub const MAX_RECENT_MESSAGE_AGE: usize = 600; // seconds; equal to the expected epoch length
 pub const RELAY_DUPLICATE_INFERENCE_WARMUP: usize = 128;
+pub const BLOCK_GAP_TIMEOUT: u64 = 600; // seconds; how long a missing block is waited for
 
 pub struct Relayer {
     /// Connection to the p2p thread
     p2p: NetworkHandle,
+    /// Blocks we have never received, but whose children we have, as (consensus hash, block
+    /// hash), with when each was found missing
+    block_gaps: HashMap<(ConsensusHash, BlockHeaderHash), u64>,
 }
 
 #[derive(Debug)]
@@ -439,7 +444,10 @@ impl RelayerStats {
 
 impl Relayer {
     pub fn new(handle: NetworkHandle) -> Relayer {
-        Relayer { p2p: handle }
+        Relayer {
+            p2p: handle,
+            block_gaps: HashMap::new(),
+        }
     }
 
     pub fn from_p2p(network: &mut PeerNetwork) -> Relayer {
@@ -1082,17 +1090,18 @@ impl Relayer {
         }
 
         // garbage-collect
+        let mut mempool_tx = mempool.tx_begin()?;
         if chain_height > MEMPOOL_MAX_TRANSACTION_AGE {
             let min_height = chain_height.saturating_sub(MEMPOOL_MAX_TRANSACTION_AGE);
-            let mut mempool_tx = mempool.tx_begin()?;
 
             debug!(
                 "Remove all transactions beneath block height {}",
                 min_height
             );
             MemPoolDB::garbage_collect(&mut mempool_tx, min_height, event_observer)?;
-            mempool_tx.commit()?;
         }
+        MemPoolDB::garbage_collect_expired(&mut mempool_tx, chain_height, event_observer)?;
+        mempool_tx.commit()?;
         update_stacks_tip_height(chain_height as i64);
 
         Ok(ret)
@@ -1200,6 +1209,105 @@ impl Relayer {
         }
     }
 
+    /// Find the blocks missing from our copy of the chain, judging by `new_blocks`: the parents
+    /// of new blocks which we have never received, most likely because we missed their
+    /// broadcast.  Returns the consensus hash and block hash of each missing parent.
+    pub fn find_block_gaps(
+        chainstate: &StacksChainState,
+        new_blocks: &HashMap<ConsensusHash, StacksBlock>,
+    ) -> Result<Vec<(ConsensusHash, BlockHeaderHash)>, net_error> {
+        let mut gaps = vec![];
+        for (consensus_hash, block) in new_blocks.iter() {
+            if block.is_first_mined() {
+                continue;
+            }
+            let index_block_hash =
+                StacksBlockHeader::make_index_block_hash(consensus_hash, &block.block_hash());
+            let staging_block = match StacksChainState::load_staging_block_info(
+                chainstate.db(),
+                &index_block_hash,
+            )? {
+                Some(staging_block) => staging_block,
+                None => continue,
+            };
+            if StacksChainState::get_staging_block_status(
+                chainstate.db(),
+                &staging_block.parent_consensus_hash,
+                &staging_block.parent_anchored_block_hash,
+            )?
+            .is_none()
+            {
+                gaps.push((
+                    staging_block.parent_consensus_hash,
+                    staging_block.parent_anchored_block_hash,
+                ));
+            }
+        }
+        Ok(gaps)
+    }
+
+    /// Have the p2p thread fetch the blocks missing from the gaps `new_blocks` reveal straight
+    /// away, instead of stalling until the next inventory sync finds them.  Forget the gaps which
+    /// have since been filled, or which have been waited on for `BLOCK_GAP_TIMEOUT` seconds.
+    fn fill_block_gaps(
+        &mut self,
+        sortdb: &SortitionDB,
+        chainstate: &StacksChainState,
+        new_blocks: &HashMap<ConsensusHash, StacksBlock>,
+    ) -> Result<(), net_error> {
+        let now = get_epoch_time_secs();
+        self.block_gaps
+            .retain(|(consensus_hash, block_hash), detected_at| {
+                match StacksChainState::get_staging_block_status(
+                    chainstate.db(),
+                    consensus_hash,
+                    block_hash,
+                ) {
+                    Ok(Some(_)) => {
+                        debug!(
+                            "Received missing block {}/{} after {}s",
+                            consensus_hash,
+                            block_hash,
+                            now.saturating_sub(*detected_at)
+                        );
+                        monitoring::increment_block_gaps_filled();
+                        false
+                    }
+                    _ => *detected_at + BLOCK_GAP_TIMEOUT > now,
+                }
+            });
+
+        let mut fetch_from = None;
+        for (consensus_hash, block_hash) in Relayer::find_block_gaps(chainstate, new_blocks)? {
+            if self
+                .block_gaps
+                .contains_key(&(consensus_hash.clone(), block_hash.clone()))
+            {
+                continue;
+            }
+            let sn =
+                match SortitionDB::get_block_snapshot_consensus(sortdb.conn(), &consensus_hash)? {
+                    Some(sn) => sn,
+                    None => continue,
+                };
+            info!(
+                "Missing block {}/{} (burn height {}), which a received block builds on; will fetch it from peers",
+                &consensus_hash, &block_hash, sn.block_height
+            );
+            monitoring::increment_block_gaps_detected();
+            self.block_gaps.insert((consensus_hash, block_hash), now);
+            fetch_from = Some(cmp::min(
+                fetch_from.unwrap_or(sn.block_height),
+                sn.block_height,
+            ));
+        }
+
+        if let Some(burn_block_height) = fetch_from {
+            self.p2p.fetch_blocks(burn_block_height)?;
+        }
+        Ok(())
+    }
+
     /// Given a network result, consume and store all data.
     /// * Add all blocks and microblocks to staging.
     /// * Forward BlocksAvailable messages to neighbors for newly-discovered anchored blocks
@@ -1238,6 +1346,12 @@ impl Relayer {
 
                 // only relay if not ibd
                 if !ibd {
+                    // fetch the parents we missed of new blocks, rather than stall until an
+                    // inventory sync notices them
+                    if let Err(e) = self.fill_block_gaps(sortdb, chainstate, &new_blocks) {
+                        warn!("Failed to fill gaps in the chain: {:?}", &e);
+                    }
+
                     // have the p2p thread tell our neighbors about newly-discovered blocks
                     let new_block_chs = new_blocks.iter().map(|(ch, _)| ch.clone()).collect();
                     let available = Relayer::load_blocks_available_data(sortdb, new_block_chs)?;
@@ -2153,6 +2267,79 @@ mod test {
         assert_eq!(*ranking.get(&nk_3).unwrap(), 4 - 2 + 1);
     }
 
+    #[test]
+    fn test_relayer_fill_block_gaps() {
+        let peer_1_config = TestPeerConfig::new("test_relayer_fill_block_gaps", 3290, 3291);
+        let peer_2_config = TestPeerConfig::new("test_relayer_fill_block_gaps", 3292, 3293);
+        let mut peer_1 = TestPeer::new(peer_1_config);
+        let mut peer_2 = TestPeer::new(peer_2_config);
+
+        // peer 1 mines two blocks, and peer 2 sees both sortitions
+        let mut block_data = vec![];
+        for _ in 0..2 {
+            let (mut burn_ops, stacks_block, microblocks) = peer_1.make_default_tenure();
+            let (_, burn_header_hash, consensus_hash) =
+                peer_1.next_burnchain_block(burn_ops.clone());
+            peer_1.process_stacks_epoch_at_tip(&stacks_block, &microblocks);
+
+            TestPeer::set_ops_burn_header_hash(&mut burn_ops, &burn_header_hash);
+            peer_2.next_burnchain_block_raw(burn_ops);
+            block_data.push((consensus_hash, stacks_block));
+        }
+        let (parent_ch, parent_block) = block_data[0].clone();
+        let (child_ch, child_block) = block_data[1].clone();
+        let parent_key = (parent_ch.clone(), parent_block.block_hash());
+
+        // peer 2 gets only the second block
+        peer_2.preprocess_stacks_block(&child_block).unwrap();
+
+        let mut new_blocks = HashMap::new();
+        new_blocks.insert(child_ch.clone(), child_block.clone());
+        assert_eq!(
+            Relayer::find_block_gaps(peer_2.chainstate(), &new_blocks).unwrap(),
+            vec![parent_key.clone()]
+        );
+        assert!(Relayer::find_block_gaps(peer_1.chainstate(), &new_blocks)
+            .unwrap()
+            .is_empty());
+
+        let (chan_in, chan_out) = std::sync::mpsc::sync_channel(16);
+        let mut relayer = Relayer::new(NetworkHandle::new(chan_in));
+
+        // the downloader is told to fetch from the missing block's sortition on
+        let sortdb = peer_2.sortdb.take().unwrap();
+        relayer
+            .fill_block_gaps(&sortdb, peer_2.chainstate(), &new_blocks)
+            .unwrap();
+        let parent_sn = SortitionDB::get_block_snapshot_consensus(sortdb.conn(), &parent_ch)
+            .unwrap()
+            .unwrap();
+        match chan_out.try_recv().unwrap() {
+            NetworkRequest::FetchBlocks(burn_block_height) => {
+                assert_eq!(burn_block_height, parent_sn.block_height);
+            }
+            _ => panic!("Expected FetchBlocks"),
+        }
+        assert!(relayer.block_gaps.contains_key(&parent_key));
+
+        // a gap which is already being filled isn't asked for again
+        relayer
+            .fill_block_gaps(&sortdb, peer_2.chainstate(), &new_blocks)
+            .unwrap();
+        assert!(chan_out.try_recv().is_err());
+        peer_2.sortdb = Some(sortdb);
+
+        // once the missing block arrives, the gap is forgotten
+        peer_2.preprocess_stacks_block(&parent_block).unwrap();
+        let sortdb = peer_2.sortdb.take().unwrap();
+        relayer
+            .fill_block_gaps(&sortdb, peer_2.chainstate(), &HashMap::new())
+            .unwrap();
+        assert!(relayer.block_gaps.is_empty());
+        assert!(chan_out.try_recv().is_err());
+        peer_2.sortdb = Some(sortdb);
+    }
+
     #[test]
     #[ignore]
     fn test_get_blocks_and_microblocks_3_peers_push_available() {
@@ -0,0 +1,229 @@
+// Copyright (C) 2013-2020 Blockstack PBC, a public benefit corporation
+// Copyright (C) 2020 Stacks Open Internet Foundation
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Signed RPC responses.  If `sign_rpc_responses` is set, the node signs the responses a bridge
+//! acts on -- withdrawal proofs and claims (`/v2/withdrawal/...`), block-commit mappings
+//! (`/v2/hyperchain/commits`), and node status (`/v2/info` and `/v2/hyperchain/info`) -- with its
+//! node key, the key whose public half `/v2/info` reports as `node_public_key`.  The signature
+//! covers the chain ID, the canonical tip the response was made against, the request's path and
+//! the response body, and travels in the response's headers with the chain ID, tip and public
+//! key, so clients which don't check it see the same body as before.
+
+use std::io::Write;
+
+use crate::burnchains::{PrivateKey, PublicKey};
+use crate::codec::Error as codec_error;
+use crate::net::Error as net_error;
+use stacks_common::types::chainstate::StacksBlockId;
+use stacks_common::util::hash::Sha512Trunc256Sum;
+use stacks_common::util::secp256k1::{MessageSignature, Secp256k1PrivateKey, Secp256k1PublicKey};
+
+pub const HEADER_NODE_KEY: &str = "X-Subnet-Node-Key";
+pub const HEADER_CHAIN_ID: &str = "X-Subnet-Chain-Id";
+pub const HEADER_TIP: &str = "X-Subnet-Tip";
+pub const HEADER_SIGNATURE: &str = "X-Subnet-Signature";
+
+/// Domain separator, so a response signature can't be mistaken for any other the node key makes
+const RESPONSE_SIGNATURE_DOMAIN: &[u8] = b"subnet-rpc-response";
+
+/// What the node signs one response with
+#[derive(Debug, Clone, PartialEq)]
+pub struct RPCResponseSigner {
+    pub private_key: Secp256k1PrivateKey,
+    pub chain_id: u32,
+    /// the canonical tip when the request was handled
+    pub tip: StacksBlockId,
+    /// the path of the request being answered, without its query string
+    pub request_path: String,
+}
+
+/// The signed envelope of a response, as carried in its headers
+#[derive(Debug, Clone, PartialEq)]
+pub struct RPCResponseSignature {
+    pub node_public_key: Secp256k1PublicKey,
+    pub chain_id: u32,
+    pub tip: StacksBlockId,
+    pub signature: MessageSignature,
+}
+
+/// The path of `path_and_query`, without its query string
+pub fn strip_query(path_and_query: &str) -> &str {
+    path_and_query
+        .split_once('?')
+        .map(|(path, _)| path)
+        .unwrap_or(path_and_query)
+}
+
+/// The digest a response is signed over
+pub fn response_digest(
+    chain_id: u32,
+    tip: &StacksBlockId,
+    request_path: &str,
+    body: &[u8],
+) -> Sha512Trunc256Sum {
+    let mut bytes = RESPONSE_SIGNATURE_DOMAIN.to_vec();
+    bytes.extend_from_slice(&chain_id.to_be_bytes());
+    bytes.extend_from_slice(tip.as_bytes());
+    bytes.extend_from_slice(&(request_path.len() as u32).to_be_bytes());
+    bytes.extend_from_slice(request_path.as_bytes());
+    bytes.extend_from_slice(body);
+    Sha512Trunc256Sum::from_data(&bytes)
+}
+
+impl RPCResponseSigner {
+    /// Sign a response whose body is `body`
+    pub fn sign(&self, body: &[u8]) -> Result<RPCResponseSignature, net_error> {
+        let digest = response_digest(self.chain_id, &self.tip, &self.request_path, body);
+        let signature = self
+            .private_key
+            .sign(digest.as_bytes())
+            .map_err(|e| net_error::SigningError(e.to_string()))?;
+        Ok(RPCResponseSignature {
+            node_public_key: Secp256k1PublicKey::from_private(&self.private_key),
+            chain_id: self.chain_id,
+            tip: self.tip.clone(),
+            signature,
+        })
+    }
+}
+
+impl RPCResponseSignature {
+    /// Write the signature's HTTP headers
+    pub fn write_headers<W: Write>(&self, fd: &mut W) -> Result<(), codec_error> {
+        let headers = [
+            (HEADER_NODE_KEY, self.node_public_key.to_hex()),
+            (HEADER_CHAIN_ID, self.chain_id.to_string()),
+            (HEADER_TIP, self.tip.to_hex()),
+            (HEADER_SIGNATURE, self.signature.to_hex()),
+        ];
+        for (name, value) in headers.iter() {
+            fd.write_all(format!("{}: {}\r\n", name, value).as_bytes())
+                .map_err(codec_error::WriteError)?;
+        }
+        Ok(())
+    }
+
+    /// Read a signature from a response's headers.  `get_header` looks up a header by name,
+    /// case-insensitively.  Returns `None` if the response is unsigned.
+    pub fn from_headers<'a, F>(get_header: F) -> Result<Option<RPCResponseSignature>, String>
+    where
+        F: Fn(&str) -> Option<&'a str>,
+    {
+        let signature = match get_header(HEADER_SIGNATURE) {
+            Some(signature) => signature,
+            None => return Ok(None),
+        };
+        let get_required = |name: &str| {
+            get_header(name).ok_or_else(|| format!("signed response has no {} header", name))
+        };
+        let node_public_key = Secp256k1PublicKey::from_hex(get_required(HEADER_NODE_KEY)?)
+            .map_err(|_| format!("invalid {} header", HEADER_NODE_KEY))?;
+        let chain_id = get_required(HEADER_CHAIN_ID)?
+            .parse::<u32>()
+            .map_err(|_| format!("invalid {} header", HEADER_CHAIN_ID))?;
+        let tip = StacksBlockId::from_hex(get_required(HEADER_TIP)?)
+            .map_err(|_| format!("invalid {} header", HEADER_TIP))?;
+        let signature = MessageSignature::from_hex(signature)
+            .map_err(|_| format!("invalid {} header", HEADER_SIGNATURE))?;
+        Ok(Some(RPCResponseSignature {
+            node_public_key,
+            chain_id,
+            tip,
+            signature,
+        }))
+    }
+
+    /// Check that this signs `body`, the response to a request for `request_path`.  This only
+    /// shows that the holder of `node_public_key` sent it; the caller must check that it trusts
+    /// that key, and that the chain ID is the one it expects.
+    pub fn verify(&self, request_path: &str, body: &[u8]) -> Result<(), String> {
+        let digest = response_digest(self.chain_id, &self.tip, strip_query(request_path), body);
+        match self
+            .node_public_key
+            .verify(digest.as_bytes(), &self.signature)
+        {
+            Ok(true) => Ok(()),
+            Ok(false) => Err("response signature does not match".to_string()),
+            Err(e) => Err(format!("invalid response signature: {}", e)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_response_signature_roundtrip() {
+        let private_key = Secp256k1PrivateKey::new();
+        let signer = RPCResponseSigner {
+            private_key: private_key.clone(),
+            chain_id: 0x80000000,
+            tip: StacksBlockId([0x11; 32]),
+            request_path: "/v2/withdrawal/claim/10/0".to_string(),
+        };
+        let body = br#"{"withdrawal_root":"0x00"}"#;
+        let signature = signer.sign(body).unwrap();
+        assert_eq!(
+            signature.node_public_key,
+            Secp256k1PublicKey::from_private(&private_key)
+        );
+
+        // the headers round-trip
+        let mut headers = vec![];
+        signature.write_headers(&mut headers).unwrap();
+        let headers = String::from_utf8(headers).unwrap();
+        let parsed = RPCResponseSignature::from_headers(|name| {
+            headers.lines().find_map(|line| {
+                let (key, value) = line.split_once(": ")?;
+                if key.eq_ignore_ascii_case(name) {
+                    Some(value.trim())
+                } else {
+                    None
+                }
+            })
+        })
+        .unwrap()
+        .unwrap();
+        assert_eq!(parsed, signature);
+
+        // the query string isn't signed
+        parsed.verify("/v2/withdrawal/claim/10/0", body).unwrap();
+        parsed
+            .verify("/v2/withdrawal/claim/10/0?x=1", body)
+            .unwrap();
+
+        // but the path, body, chain and tip are
+        assert!(parsed.verify("/v2/withdrawal/claim/10/1", body).is_err());
+        assert!(parsed
+            .verify(
+                "/v2/withdrawal/claim/10/0",
+                br#"{"withdrawal_root":"0x01"}"#
+            )
+            .is_err());
+        let mut other_chain = parsed.clone();
+        other_chain.chain_id = 0x80000001;
+        assert!(other_chain
+            .verify("/v2/withdrawal/claim/10/0", body)
+            .is_err());
+        let mut other_tip = parsed.clone();
+        other_tip.tip = StacksBlockId([0x22; 32]);
+        assert!(other_tip.verify("/v2/withdrawal/claim/10/0", body).is_err());
+
+        // unsigned responses have no signature
+        assert_eq!(RPCResponseSignature::from_headers(|_| None), Ok(None));
+    }
+}
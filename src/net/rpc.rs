@@ -17,6 +17,7 @@
  along with Blockstack. If not, see <http://www.gnu.org/licenses/>.
 */
 
+use std::collections::BTreeSet;
 use std::collections::HashMap;
 use std::collections::HashSet;
 use std::collections::VecDeque;
@@ -24,6 +25,7 @@ use std::io;
 use std::io::prelude::*;
 use std::io::{Read, Seek, SeekFrom, Write};
 use std::net::SocketAddr;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::time::Instant;
 use std::{convert::TryFrom, fmt};
 
@@ -38,25 +40,40 @@ use rand::thread_rng;
 use rusqlite::{DatabaseName, NO_PARAMS};
 use stacks_common::util::secp256k1::Secp256k1PrivateKey;
 
+use crate::burnchains::db::BurnchainDB;
 use crate::burnchains::Burnchain;
 use crate::burnchains::BurnchainView;
 use crate::burnchains::*;
 use crate::chainstate::burn::db::sortdb::SortitionDB;
 use crate::chainstate::burn::ConsensusHash;
+use crate::chainstate::stacks::db::account_index::AccountTransaction;
 use crate::chainstate::stacks::db::blocks::CheckError;
+use crate::chainstate::stacks::db::commits::commit_confirmations;
+use crate::chainstate::stacks::db::contract_policy::ContractPublishPolicy;
+use crate::chainstate::stacks::db::event_replay::{EventReplay, EventReplayFormatter};
+use crate::chainstate::stacks::db::failed_deposits::FailedDeposit;
+use crate::chainstate::stacks::db::fee_market::FEE_MARKET_TARGET_UTILIZATION;
+use crate::chainstate::stacks::db::microblock_stream::MicroblockStream;
+use crate::chainstate::stacks::db::rewards_report::CommitFeeLedger;
+use crate::chainstate::stacks::db::withdrawal_index::WithdrawalEntry;
 use crate::chainstate::stacks::db::{
-    blocks::MINIMUM_TX_FEE_RATE_PER_BYTE, StacksChainState, StreamCursor,
+    blocks::ProposedBlockVerdict, blocks::MINIMUM_TX_FEE_RATE_PER_BYTE, StacksChainState,
+    StreamCursor,
 };
+use crate::chainstate::stacks::miner::TransactionSuccess;
 use crate::chainstate::stacks::Error as chain_error;
 use crate::chainstate::stacks::*;
 use crate::clarity_vm::clarity::ClarityConnection;
+use crate::clarity_vm::clarity::ClarityReadOnlyConnection;
 use crate::clarity_vm::withdrawal;
 use crate::codec::StacksMessageCodec;
 use crate::core::mempool::*;
+use crate::core::{SUBNETS_CLARITY_VERSION, SUBNETS_STACKS_EPOCH};
 use crate::cost_estimates::metrics::CostMetric;
 use crate::cost_estimates::CostEstimator;
 use crate::cost_estimates::FeeEstimator;
 use crate::monitoring;
+use crate::net::api_keys::{ApiKeyCheck, RPCApiKeys};
 use crate::net::atlas::{AtlasDB, Attachment, MAX_ATTACHMENT_INV_PAGES_PER_REQUEST};
 use crate::net::connection::ConnectionHttp;
 use crate::net::connection::ConnectionOptions;
@@ -65,15 +82,30 @@ use crate::net::db::PeerDB;
 use crate::net::http::*;
 use crate::net::p2p::PeerMap;
 use crate::net::p2p::PeerNetwork;
+use crate::net::ratelimit::RPCRequestClass;
 use crate::net::relay::Relayer;
+use crate::net::response_signing::{strip_query, RPCResponseSigner};
+use crate::net::AccountTransactionResponse;
+use crate::net::AccountTransactionsResponse;
+use crate::net::BlockValidationResponse;
 use crate::net::BlocksDatum;
+use crate::net::BurnOpsResponse;
+use crate::net::ContractPublishPolicyResponse;
 use crate::net::Error as net_error;
+use crate::net::FailedDepositResponse;
+use crate::net::FailedDepositsResponse;
+use crate::net::HealthCheckResponse;
 use crate::net::HttpRequestMetadata;
 use crate::net::HttpRequestType;
 use crate::net::HttpResponseMetadata;
 use crate::net::HttpResponseType;
+use crate::net::InvalidBlockTransaction;
+use crate::net::MemPoolFilterRequestBody;
+use crate::net::MemPoolFiltersResponse;
 use crate::net::MemPoolSyncData;
 use crate::net::MicroblocksData;
+use crate::net::MinerPauseStatusResponse;
+use crate::net::MiningKeyRotationResponse;
 use crate::net::NeighborAddress;
 use crate::net::NeighborsData;
 use crate::net::PeerAddress;
@@ -81,13 +113,22 @@ use crate::net::PeerHost;
 use crate::net::ProtocolFamily;
 use crate::net::RPCFeeEstimate;
 use crate::net::RPCFeeEstimateResponse;
+use crate::net::ReadOnlyCall;
 use crate::net::StacksHttp;
 use crate::net::StacksHttpMessage;
 use crate::net::StacksMessageType;
+use crate::net::SubnetFeeRateResponse;
+use crate::net::SubnetInfoResponse;
+use crate::net::TransactionSimulationResponse;
 use crate::net::UnconfirmedTransactionResponse;
 use crate::net::UnconfirmedTransactionStatus;
+use crate::net::UpgradeSignalInfo;
 use crate::net::UrlString;
+use crate::net::WithdrawalClaimCall;
+use crate::net::WithdrawalClaimResponse;
+use crate::net::WithdrawalEntryResponse;
 use crate::net::WithdrawalResponse;
+use crate::net::WithdrawalsResponse;
 use crate::net::HTTP_REQUEST_ID_RESERVED;
 use crate::net::MAX_HEADERS;
 use crate::net::MAX_NEIGHBORS_DATA_LEN;
@@ -95,10 +136,19 @@ use crate::net::{
     AccountEntryResponse, AttachmentPage, CallReadOnlyResponse, ContractSrcResponse,
     DataVarResponse, GetAttachmentResponse, GetAttachmentsInvResponse, MapEntryResponse,
 };
+use crate::net::{
+    AccountNextNonceResponse, MempoolAccountResponse, MempoolAccountTxEntry, MempoolNonceGap,
+};
 use crate::net::{BlocksData, GetIsTraitImplementedResponse};
 use crate::net::{ClientError, TipRequest};
+use crate::net::{MempoolPreviewEntry, MempoolPreviewResponse, MEMPOOL_PREVIEW_WALK_TIME_MS};
+use crate::net::{
+    MicroblockStreamEntry, MicroblockStreamResponse, MicroblockStreamTransaction,
+    MicroblockTransactionReceipt,
+};
 use crate::net::{RPCNeighbor, RPCNeighborsInfo};
 use crate::net::{RPCPeerInfoData, RPCPoxInfoData};
+use crate::net::{RejectedTxEntry, RejectedTxsQuery, RejectedTxsResponse};
 use crate::util_lib::db::DBConn;
 use crate::util_lib::db::Error as db_error;
 use clarity::vm::database::clarity_store::make_contract_hash_key;
@@ -116,16 +166,20 @@ use clarity::vm::{
     types::{PrincipalData, QualifiedContractIdentifier, StandardPrincipalData},
     ClarityName, ContractName, SymbolicExpression, Value,
 };
-use stacks_common::util::get_epoch_time_secs;
 use stacks_common::util::hash::Hash160;
 use stacks_common::util::hash::{hex_bytes, to_hex};
+use stacks_common::util::{get_epoch_time_ms, get_epoch_time_secs};
 
 use crate::chainstate::stacks::StacksBlockHeader;
 use crate::clarity_vm::database::marf::MarfedKV;
 use stacks_common::types::chainstate::BlockHeaderHash;
-use stacks_common::types::chainstate::{BurnchainHeaderHash, StacksAddress, StacksBlockId};
+use stacks_common::types::chainstate::{
+    BurnchainHeaderHash, SortitionId, StacksAddress, StacksBlockId,
+};
 use stacks_common::types::{StacksEpochId, StacksPublicKeyBuffer};
 
+use crate::chainstate::burn::operations::upgrade_signal::version_meets;
+use crate::subnet_node_version;
 use crate::{
     chainstate::burn::operations::leader_block_commit::OUTPUTS_PER_COMMIT, types, util,
     util::hash::Sha256Sum, version_string,
@@ -137,6 +191,53 @@ use super::{RPCPoxCurrentCycleInfo, RPCPoxNextCycleInfo};
 
 pub const STREAM_CHUNK_SIZE: u64 = 4096;
 
+/// Rotates the miner's signing key for the admin key-rotation endpoint.  Implemented by the
+/// node, which owns the miner's keys and its connection to the L1.
+pub trait MiningKeyRotator {
+    /// Load the next mining key from the node's config, register it with the L1 contract, and
+    /// schedule the miner to switch to it.  Errors are reported to the client.
+    fn rotate_mining_key(&self) -> Result<MiningKeyRotationResponse, String>;
+}
+
+/// Why a read-only replica could not forward a transaction to its primary node
+pub enum TransactionForwardError {
+    /// The primary node rejected the transaction; holds the primary's JSON response
+    Rejected(serde_json::Value),
+    /// The primary node could not be reached, or did not answer as expected
+    Unreachable(String),
+}
+
+/// Forwards the transactions POSTed to a read-only replica to the primary node whose databases
+/// it serves.  Implemented by the node, which knows the primary's RPC endpoint.
+pub trait TransactionForwarder {
+    /// Submit `tx`, with its attachment, expiration height and bundle ID if any, to the primary
+    /// node's mempool.
+    fn forward_transaction(
+        &self,
+        tx: &StacksTransaction,
+        attachment: Option<&Attachment>,
+        expiration_height: Option<u64>,
+        bundle_id: Option<&str>,
+    ) -> Result<Txid, TransactionForwardError>;
+}
+
+/// Asks the L1 about claiming a withdrawal there, for the withdrawal-claim endpoint.
+/// Implemented by the node, which knows the subnet contract and the L1's RPC endpoint.
+pub trait L1WithdrawalOracle {
+    /// The subnet contract on the L1, whose functions claim withdrawals
+    fn subnet_contract(&self) -> QualifiedContractIdentifier;
+
+    /// Has the subnet contract recorded `withdrawal_root`, so that the withdrawals in its tree
+    /// can be claimed?
+    fn is_withdrawal_root_committed(
+        &self,
+        withdrawal_root: &Sha512Trunc256Sum,
+    ) -> Result<bool, String>;
+
+    /// The L1's estimate of the fee, in uSTX, of a transaction with `payload`
+    fn estimate_claim_fee(&self, payload: &TransactionPayload) -> Result<u64, String>;
+}
+
 #[derive(Default)]
 pub struct RPCHandlerArgs<'a> {
     pub exit_at_block_height: Option<&'a u64>,
@@ -145,6 +246,25 @@ pub struct RPCHandlerArgs<'a> {
     pub cost_estimator: Option<&'a dyn CostEstimator>,
     pub fee_estimator: Option<&'a dyn FeeEstimator>,
     pub cost_metric: Option<&'a dyn CostMetric>,
+    /// set to pause the miner's block-commits; `None` if this node does not mine
+    pub miner_paused: Option<&'a AtomicBool>,
+    /// rotates the miner's key; `None` if this node does not mine
+    pub mining_key_rotator: Option<&'a dyn MiningKeyRotator>,
+    /// whether the miner's key is loaded; `None` if this node does not mine
+    pub mining_key_loaded: Option<bool>,
+    /// set if this node serves another node's databases read-only.  Blocks and microblocks
+    /// POSTed to it are refused, and transactions are passed to `tx_forwarder`.
+    pub read_only: bool,
+    /// forwards transactions POSTed to a read-only replica; if `None`, they are refused
+    pub tx_forwarder: Option<&'a dyn TransactionForwarder>,
+    /// asks the L1 whether withdrawals can be claimed yet; if `None`, the withdrawal-claim
+    /// endpoint reports only what this node knows
+    pub l1_oracle: Option<&'a dyn L1WithdrawalOracle>,
+    /// the L1 fees of the block-commits this node sent; if `None`, rewards reports leave
+    /// mining costs out
+    pub commit_fees: Option<&'a dyn CommitFeeLedger>,
+    /// makes the event observer payloads for event replays; if `None`, replays are refused
+    pub event_replay: Option<&'a dyn EventReplayFormatter>,
 }
 
 pub struct ConversationHttp {
@@ -218,12 +338,7 @@ impl RPCPeerInfoData {
         exit_at_block_height: &Option<&u64>,
         genesis_chainstate_hash: &Sha256Sum,
     ) -> RPCPeerInfoData {
-        let server_version = version_string(
-            "subnet-node",
-            option_env!("SUBNET_NODE_VERSION")
-                .or(option_env!("CARGO_PKG_VERSION"))
-                .unwrap_or("0.0.0.0"),
-        );
+        let server_version = version_string("subnet-node", subnet_node_version());
         let (unconfirmed_tip, unconfirmed_seq) = match chainstate.unconfirmed_state {
             Some(ref unconfirmed) => {
                 if unconfirmed.num_mined_txs() > 0 {
@@ -326,6 +441,363 @@ impl RPCNeighborsInfo {
     }
 }
 
+impl MempoolAccountResponse {
+    /// Sort an account's pending transactions into those that can be mined on top of the
+    /// account's current `nonce`, and those stranded behind a nonce gap.
+    /// `txs` must be ordered by origin nonce.  Transactions with a nonce below `nonce` have
+    /// already been mined (or replaced) and are ignored.
+    pub fn from_pending_txs(
+        address: &StacksAddress,
+        nonce: u64,
+        txs: &[MemPoolTxMetadata],
+    ) -> MempoolAccountResponse {
+        let mut chained_txs = vec![];
+        let mut stranded_txs = vec![];
+        let mut nonce_gaps = vec![];
+        let mut next_nonce = nonce;
+
+        for tx in txs.iter() {
+            if tx.origin_nonce < next_nonce {
+                continue;
+            }
+            if tx.origin_nonce > next_nonce {
+                nonce_gaps.push(MempoolNonceGap {
+                    start: next_nonce,
+                    end: tx.origin_nonce - 1,
+                });
+            }
+
+            let entry = MempoolAccountTxEntry {
+                txid: format!("0x{}", &tx.txid),
+                nonce: tx.origin_nonce,
+                fee: tx.tx_fee,
+            };
+            if nonce_gaps.is_empty() {
+                chained_txs.push(entry);
+            } else {
+                stranded_txs.push(entry);
+            }
+            next_nonce = tx.origin_nonce + 1;
+        }
+
+        let lowest_missing_nonce = nonce_gaps
+            .first()
+            .map(|gap| gap.start)
+            .unwrap_or(next_nonce);
+
+        MempoolAccountResponse {
+            principal: address.to_string(),
+            nonce,
+            chained_txs,
+            stranded_txs,
+            nonce_gaps,
+            lowest_missing_nonce,
+        }
+    }
+}
+
+impl AccountNextNonceResponse {
+    /// Find the nonce an account's next transaction should use, given its nonce at the chain
+    /// tip, the pending transactions it originated (`origin_txs`), and those it sponsors for
+    /// other origins (`sponsored_txs`).  A nonce is in use if the account sent or sponsored a
+    /// pending transaction with it.  Pending nonces below `confirmed_nonce` have already been
+    /// mined (or replaced) and are ignored.
+    pub fn from_pending_txs(
+        address: &StacksAddress,
+        confirmed_nonce: u64,
+        origin_txs: &[MemPoolTxMetadata],
+        sponsored_txs: &[MemPoolTxMetadata],
+    ) -> AccountNextNonceResponse {
+        let mut pending_nonces = BTreeSet::new();
+        let mut last_known_nonce = None;
+        for tx in origin_txs.iter() {
+            pending_nonces.insert(tx.origin_nonce);
+            last_known_nonce = last_known_nonce.max(tx.last_known_origin_nonce);
+        }
+        for tx in sponsored_txs.iter() {
+            pending_nonces.insert(tx.sponsor_nonce);
+            last_known_nonce = last_known_nonce.max(tx.last_known_sponsor_nonce);
+        }
+
+        let mut missing_nonces = vec![];
+        let mut possible_next_nonce = confirmed_nonce;
+        for nonce in pending_nonces.range(confirmed_nonce..) {
+            if *nonce > possible_next_nonce {
+                missing_nonces.push(MempoolNonceGap {
+                    start: possible_next_nonce,
+                    end: *nonce - 1,
+                });
+            }
+            possible_next_nonce = *nonce + 1;
+        }
+        let next_nonce = missing_nonces
+            .first()
+            .map(|gap| gap.start)
+            .unwrap_or(possible_next_nonce);
+
+        AccountNextNonceResponse {
+            principal: address.to_string(),
+            confirmed_nonce,
+            last_known_nonce,
+            next_nonce,
+            possible_next_nonce,
+            missing_nonces,
+        }
+    }
+}
+
+impl UpgradeSignalInfo {
+    fn new(target_height: u64, required_version: String) -> UpgradeSignalInfo {
+        let version_met = version_meets(subnet_node_version(), &required_version).unwrap_or(false);
+        UpgradeSignalInfo {
+            target_height,
+            required_version,
+            version_met,
+        }
+    }
+}
+
+impl SubnetInfoResponse {
+    pub fn from_network(
+        network: &PeerNetwork,
+        sortdb: &SortitionDB,
+        chainstate: &StacksChainState,
+        genesis_chainstate_hash: &Sha256Sum,
+    ) -> Result<SubnetInfoResponse, net_error> {
+        let burn_tip = SortitionDB::get_canonical_burn_chain_tip(sortdb.conn())?;
+        let ic = sortdb.index_conn();
+        let upgrade =
+            SortitionDB::get_upgrade_signal_at(&ic, &burn_tip.sortition_id, burn_tip.block_height)?
+                .map(|(target_height, required_version)| {
+                    UpgradeSignalInfo::new(target_height, required_version)
+                });
+        let pending_upgrades = SortitionDB::get_upgrade_signals(&ic, &burn_tip.sortition_id)?
+            .into_iter()
+            .filter(|(target_height, _)| *target_height > burn_tip.block_height)
+            .map(|(target_height, required_version)| {
+                UpgradeSignalInfo::new(target_height, required_version)
+            })
+            .collect();
+        Ok(SubnetInfoResponse {
+            l1_contract_identifier: network.burnchain.subnet_governing_contract.to_string(),
+            first_burn_block_height: network.burnchain.first_block_height,
+            first_burn_header_hash: format!("{}", &network.burnchain.first_block_hash),
+            chain_id: chainstate.chain_id,
+            network_id: network.local_peer.network_id,
+            parent_network_id: network.local_peer.parent_network_id,
+            stacks_epoch: SUBNETS_STACKS_EPOCH.to_string(),
+            clarity_version: SUBNETS_CLARITY_VERSION.to_string(),
+            genesis_chainstate_hash: genesis_chainstate_hash.clone(),
+            node_version: subnet_node_version().to_string(),
+            upgrade,
+            pending_upgrades,
+        })
+    }
+}
+
+impl ContractPublishPolicyResponse {
+    /// Report `policy`, with its allow-lists sorted so that responses are stable.
+    pub fn from_policy(policy: &ContractPublishPolicy) -> ContractPublishPolicyResponse {
+        let mut allowed_publishers: Vec<_> = policy
+            .allowed_publishers
+            .iter()
+            .map(|addr| addr.to_string())
+            .collect();
+        allowed_publishers.sort();
+        let mut allowed_code_hashes: Vec<_> = policy
+            .allowed_code_hashes
+            .iter()
+            .map(|code_hash| code_hash.to_hex())
+            .collect();
+        allowed_code_hashes.sort();
+
+        ContractPublishPolicyResponse {
+            restricted: policy.restricted,
+            allowed_publishers,
+            allowed_code_hashes,
+        }
+    }
+}
+
+impl TransactionSimulationResponse {
+    /// Report the outcome of simulating `tx` on top of `tip`
+    pub fn from_outcome(
+        tip: &StacksBlockId,
+        tx: &StacksTransaction,
+        outcome: Result<TransactionSuccess, chain_error>,
+    ) -> TransactionSimulationResponse {
+        let txid = tx.txid();
+        match outcome {
+            Ok(success) => {
+                let receipt = success.receipt;
+                let committed = !receipt.post_condition_aborted;
+                let events = receipt
+                    .events
+                    .iter()
+                    .enumerate()
+                    .map(|(event_index, event)| event.json_serialize(event_index, &txid, committed))
+                    .collect();
+                TransactionSimulationResponse {
+                    txid: format!("0x{}", &txid),
+                    tip: format!("{}", tip),
+                    okay: true,
+                    result: Some(format!("0x{}", receipt.result.serialize_to_hex())),
+                    cause: None,
+                    post_condition_aborted: receipt.post_condition_aborted,
+                    fee: success.fee,
+                    execution_cost: receipt.execution_cost,
+                    events,
+                }
+            }
+            Err(e) => TransactionSimulationResponse {
+                txid: format!("0x{}", &txid),
+                tip: format!("{}", tip),
+                okay: false,
+                result: None,
+                cause: Some(e.to_string()),
+                post_condition_aborted: false,
+                fee: tx.get_tx_fee(),
+                execution_cost: ExecutionCost::zero(),
+                events: vec![],
+            },
+        }
+    }
+}
+
+impl MicroblockStreamResponse {
+    /// Report `stream`, with each transaction's receipt if the stream is confirmed
+    pub fn from_stream(stream: &MicroblockStream) -> MicroblockStreamResponse {
+        let receipts: HashMap<_, _> = stream
+            .tx_receipts
+            .iter()
+            .map(|receipt| (receipt.transaction.txid(), receipt))
+            .collect();
+        let microblocks = stream
+            .microblocks
+            .iter()
+            .map(|microblock| MicroblockStreamEntry {
+                microblock_hash: format!("{}", &microblock.block_hash()),
+                sequence: microblock.header.sequence,
+                parent_microblock_hash: format!("{}", &microblock.header.prev_block),
+                txs: microblock
+                    .txs
+                    .iter()
+                    .map(|tx| {
+                        let txid = tx.txid();
+                        let receipt = receipts.get(&txid).map(|receipt| {
+                            let committed = !receipt.post_condition_aborted;
+                            MicroblockTransactionReceipt {
+                                result: format!("0x{}", receipt.result.serialize_to_hex()),
+                                post_condition_aborted: receipt.post_condition_aborted,
+                                stx_burned: receipt.stx_burned,
+                                execution_cost: receipt.execution_cost.clone(),
+                                events: receipt
+                                    .events
+                                    .iter()
+                                    .enumerate()
+                                    .map(|(event_index, event)| {
+                                        event.json_serialize(event_index, &txid, committed)
+                                    })
+                                    .collect(),
+                            }
+                        });
+                        MicroblockStreamTransaction {
+                            txid: format!("0x{}", &txid),
+                            tx: to_hex(&tx.serialize_to_vec()),
+                            receipt,
+                        }
+                    })
+                    .collect(),
+            })
+            .collect();
+
+        MicroblockStreamResponse {
+            parent_index_block_hash: format!("{}", &stream.parent_block_id),
+            status: stream.status.name().to_string(),
+            confirmed_by: stream.confirmed_by.as_ref().map(|id| format!("{}", id)),
+            microblocks,
+        }
+    }
+}
+
+impl BlockValidationResponse {
+    /// Report the verdict on the proposed block `block_hash`
+    pub fn from_verdict(
+        block_hash: &BlockHeaderHash,
+        verdict: ProposedBlockVerdict,
+    ) -> BlockValidationResponse {
+        BlockValidationResponse {
+            block_hash: format!("{}", block_hash),
+            valid: verdict.is_valid(),
+            reason: verdict.invalid_reason,
+            state_index_root: verdict.state_index_root.map(|root| format!("{}", root)),
+            withdrawal_merkle_root: verdict
+                .withdrawal_merkle_root
+                .map(|root| format!("{}", root)),
+            cost: verdict.cost,
+            first_invalid_tx: verdict.first_invalid_tx.map(|(index, txid, error)| {
+                InvalidBlockTransaction {
+                    index,
+                    txid: format!("0x{}", &txid),
+                    error,
+                }
+            }),
+        }
+    }
+}
+
+impl WithdrawalEntryResponse {
+    /// Report an indexed withdrawal
+    pub fn from_entry(entry: &WithdrawalEntry) -> WithdrawalEntryResponse {
+        WithdrawalEntryResponse {
+            block_height: entry.block_height,
+            index_block_hash: format!("{}", &entry.index_block_hash),
+            withdrawal_id: entry.withdrawal_id,
+            asset_type: entry.asset_type.clone(),
+            asset_contract: entry.asset_contract.as_ref().map(|c| c.to_string()),
+            asset_id: entry.asset_id.map(|id| id.to_string()),
+            amount: entry.amount.map(|amount| amount.to_string()),
+            withdrawal_root: entry.withdrawal_root.to_hex(),
+        }
+    }
+}
+
+impl AccountTransactionResponse {
+    /// Report an indexed transaction touching a principal
+    pub fn from_account_transaction(account_tx: &AccountTransaction) -> AccountTransactionResponse {
+        AccountTransactionResponse {
+            txid: format!("{}", &account_tx.txid),
+            block_height: account_tx.block_height,
+            index_block_hash: format!("{}", &account_tx.index_block_hash),
+            tx_index: account_tx.tx_index,
+            roles: account_tx
+                .roles
+                .iter()
+                .map(|role| role.as_str().to_string())
+                .collect(),
+        }
+    }
+}
+
+impl FailedDepositResponse {
+    /// Report a failed deposit
+    pub fn from_failed_deposit(failed_deposit: &FailedDeposit) -> FailedDepositResponse {
+        FailedDepositResponse {
+            block_height: failed_deposit.block_height,
+            index_block_hash: format!("{}", &failed_deposit.index_block_hash),
+            txid: format!("{}", &failed_deposit.txid),
+            burn_header_hash: format!("{}", &failed_deposit.burn_header_hash),
+            asset_type: failed_deposit.asset_type.clone(),
+            l1_contract: failed_deposit.l1_contract.to_string(),
+            subnet_contract: failed_deposit.subnet_contract.to_string(),
+            asset_id: failed_deposit.asset_id.map(|id| id.to_string()),
+            amount: failed_deposit.amount.map(|amount| amount.to_string()),
+            result: failed_deposit.result.clone(),
+            refund_withdrawal_id: failed_deposit.refund_withdrawal_id,
+        }
+    }
+}
+
 impl ConversationHttp {
     pub fn new(
         peer_addr: SocketAddr,
@@ -929,114 +1401,506 @@ impl ConversationHttp {
         response.send(http, fd).map(|_| ())
     }
 
-    fn handle_validate_block_proposal<W: Write>(
+    /// Handle a GET on the fee market's minimum fee rate at the chain tip `tip`
+    fn handle_get_subnet_fee_rate<W: Write>(
         http: &mut StacksHttp,
         fd: &mut W,
         req: &HttpRequestType,
-        chainstate: &mut StacksChainState,
-        sortdb: &SortitionDB,
-        signed_proposal: &miner::SignedProposal,
-        validator_key: Option<&Secp256k1PrivateKey>,
-        signing_contract: Option<&QualifiedContractIdentifier>,
-        options: &ConnectionOptions,
+        chainstate: &StacksChainState,
+        tip: &StacksBlockId,
         canonical_stacks_tip_height: u64,
     ) -> Result<(), net_error> {
         let response_metadata =
             HttpResponseMetadata::from_http_request_type(req, Some(canonical_stacks_tip_height));
-        let validator_key = match validator_key {
-            Some(key) => key,
-            None => {
-                let response = HttpResponseType::BlockProposalInvalid {
-                    metadata: response_metadata,
-                    error_message:
-                        "Cannot validate block proposal: Not configured with validation key".into(),
-                };
-                return response.send(http, fd);
+        let response = match StacksChainState::get_fee_rate_floor(chainstate.db(), tip) {
+            Ok(floor_opt) => HttpResponseType::GetSubnetFeeRate(
+                response_metadata,
+                SubnetFeeRateResponse {
+                    min_fee_rate: floor_opt
+                        .as_ref()
+                        .map(|floor| floor.min_fee_rate)
+                        .unwrap_or(MINIMUM_TX_FEE_RATE_PER_BYTE),
+                    target_utilization: FEE_MARKET_TARGET_UTILIZATION,
+                    last_block_utilization: floor_opt.as_ref().map(|floor| floor.utilization),
+                    index_block_hash: format!("{}", tip),
+                },
+            ),
+            Err(e) => {
+                warn!("Failed to load fee rate floor at {}: {:?}", tip, &e);
+                HttpResponseType::ServerError(
+                    response_metadata,
+                    format!("Failed to load fee rate floor at {}", tip),
+                )
             }
         };
+        response.send(http, fd).map(|_| ())
+    }
 
-        let signing_contract = match signing_contract {
-            Some(key) => key,
-            None => {
-                let response = HttpResponseType::BlockProposalInvalid {
-                    metadata: response_metadata,
-                    error_message:
-                        "Cannot validate block proposal: Not configured with a multiparty contract"
-                            .into(),
-                };
-                return response.send(http, fd);
-            }
+    /// Handle a GET for the subnet this node follows
+    fn handle_get_subnet_info<W: Write>(
+        http: &mut StacksHttp,
+        fd: &mut W,
+        req: &HttpRequestType,
+        network: &PeerNetwork,
+        sortdb: &SortitionDB,
+        chainstate: &StacksChainState,
+        handler_args: &RPCHandlerArgs,
+        canonical_stacks_tip_height: u64,
+    ) -> Result<(), net_error> {
+        let response_metadata =
+            HttpResponseMetadata::from_http_request_type(req, Some(canonical_stacks_tip_height));
+        let response = match SubnetInfoResponse::from_network(
+            network,
+            sortdb,
+            chainstate,
+            &handler_args.genesis_chainstate_hash,
+        ) {
+            Ok(subnet_info) => HttpResponseType::GetSubnetInfo(response_metadata, subnet_info),
+            Err(e) => HttpResponseType::ServerError(
+                response_metadata,
+                format!("Failed to load upgrade signals: {:?}", &e),
+            ),
         };
+        response.send(http, fd).map(|_| ())
+    }
 
-        let pubk_recovered = match signed_proposal.recover_signer_pk() {
-            Ok(key) => key,
+    /// Handle a GET on the liveness probe.  The node is live if its RPC server answers at all.
+    fn handle_get_health_live<W: Write>(
+        http: &mut StacksHttp,
+        fd: &mut W,
+        req: &HttpRequestType,
+        canonical_stacks_tip_height: u64,
+    ) -> Result<(), net_error> {
+        let response_metadata =
+            HttpResponseMetadata::from_http_request_type(req, Some(canonical_stacks_tip_height));
+        let response = HttpResponseType::HealthCheck(
+            response_metadata,
+            HealthCheckResponse {
+                ok: true,
+                failures: vec![],
+                burn_block_height: None,
+                l1_tip_height: None,
+                mining_key_loaded: None,
+            },
+        );
+        response.send(http, fd).map(|_| ())
+    }
+
+    /// Handle a GET on the readiness probe.  The node is ready if its burnchain DB and
+    /// chainstate can be read, it has processed the L1 blocks it received to within
+    /// `health_max_burn_block_lag` blocks, and, if it mines, its mining key is loaded.
+    fn handle_get_health_ready<W: Write>(
+        http: &mut StacksHttp,
+        fd: &mut W,
+        req: &HttpRequestType,
+        network: &PeerNetwork,
+        sortdb: &SortitionDB,
+        chainstate: &StacksChainState,
+        handler_args: &RPCHandlerArgs,
+        canonical_stacks_tip_height: u64,
+    ) -> Result<(), net_error> {
+        let response_metadata =
+            HttpResponseMetadata::from_http_request_type(req, Some(canonical_stacks_tip_height));
+        let mut failures = vec![];
+
+        let l1_tip_res = BurnchainDB::open(&network.burnchain.get_burnchaindb_path(), false)
+            .and_then(|db| db.get_canonical_chain_tip());
+        let l1_tip_height = match l1_tip_res {
+            Ok(header) => Some(header.block_height),
             Err(e) => {
-                let response = HttpResponseType::BlockProposalInvalid {
-                    metadata: response_metadata,
-                    error_message: format!("Cannot validate block proposal: {e}"),
-                };
-                return response.send(http, fd);
+                failures.push(format!("burnchain DB is unreachable: {:?}", &e));
+                None
             }
         };
-
-        // TODO: Replace with lookup in `HashSet`
-        if !options.allowed_block_proposers.contains(&pubk_recovered) {
-            let response = HttpResponseType::BlockProposalInvalid {
-                metadata: response_metadata,
-                error_message:
-                    "Cannot validate block proposal: Not signed by approved block proposer".into(),
-            };
-            return response.send(http, fd);
-        }
-
-        let proposal = match signed_proposal.decode() {
-            Ok(p) => p,
+        let burn_block_height = match SortitionDB::get_canonical_burn_chain_tip(sortdb.conn()) {
+            Ok(tip) => Some(tip.block_height),
             Err(e) => {
-                let response = HttpResponseType::BlockProposalInvalid {
-                    metadata: response_metadata,
-                    error_message: format!("Cannot validate block proposal: {e}"),
-                };
-                return response.send(http, fd);
+                failures.push(format!("sortition DB is unreachable: {:?}", &e));
+                None
             }
         };
+        if let Err(e) = chainstate.get_stacks_chain_tip(sortdb) {
+            failures.push(format!("chainstate is unreachable: {:?}", &e));
+        }
 
-        let response = match proposal.validate(chainstate, &sortdb.index_conn()) {
-            Ok(_) => {
-                let signature = proposal.sign(validator_key, signing_contract.clone());
-                HttpResponseType::BlockProposalValid {
-                    metadata: response_metadata,
-                    signature,
-                }
+        if let (Some(l1_tip_height), Some(burn_block_height)) = (l1_tip_height, burn_block_height) {
+            let max_lag = network.connection_opts.health_max_burn_block_lag;
+            if l1_tip_height.saturating_sub(burn_block_height) > max_lag {
+                failures.push(format!(
+                    "processed L1 block {} is more than {} blocks behind the L1 tip {}",
+                    burn_block_height, max_lag, l1_tip_height
+                ));
             }
-            Err(e) => HttpResponseType::BlockProposalInvalid {
-                metadata: response_metadata,
-                error_message: e.to_string(),
+        }
+        if handler_args.mining_key_loaded == Some(false) {
+            failures.push("mining key is not loaded".to_string());
+        }
+
+        let response = HttpResponseType::HealthCheck(
+            response_metadata,
+            HealthCheckResponse {
+                ok: failures.is_empty(),
+                failures,
+                burn_block_height,
+                l1_tip_height,
+                mining_key_loaded: handler_args.mining_key_loaded,
             },
-        };
-        response.send(http, fd)
+        );
+        response.send(http, fd).map(|_| ())
     }
 
-    fn handle_get_withdrawal_stx_entry<W: Write>(
+    /// Handle a GET on the policy restricting who may publish contracts through this node
+    fn handle_get_contract_publish_policy<W: Write>(
         http: &mut StacksHttp,
         fd: &mut W,
         req: &HttpRequestType,
-        chainstate: &mut StacksChainState,
-        canonical_tip: &StacksBlockId,
-        requested_block_height: u64,
-        sender: &PrincipalData,
-        withdrawal_id: u32,
-        amount: u128,
+        chainstate: &StacksChainState,
         canonical_stacks_tip_height: u64,
     ) -> Result<(), net_error> {
-        let withdrawal_key = withdrawal::make_key_for_stx_withdrawal(
-            sender,
-            withdrawal_id,
-            amount,
-            requested_block_height,
+        let response_metadata =
+            HttpResponseMetadata::from_http_request_type(req, Some(canonical_stacks_tip_height));
+        let response = HttpResponseType::GetContractPublishPolicy(
+            response_metadata,
+            ContractPublishPolicyResponse::from_policy(chainstate.get_contract_publish_policy()),
         );
-        Self::handle_get_generic_withdrawal_entry(
-            http,
-            fd,
+        response.send(http, fd).map(|_| ())
+    }
+
+    /// Check that a request to an admin endpoint carried the node's admin auth token.  Returns
+    /// the error response to send if it did not, or if admin endpoints are disabled.
+    fn check_admin_auth(
+        response_metadata: &HttpResponseMetadata,
+        admin_auth_token: Option<&String>,
+        request_auth_token: Option<&String>,
+    ) -> Option<HttpResponseType> {
+        let admin_auth_token = match admin_auth_token {
+            Some(token) => token,
+            None => {
+                return Some(HttpResponseType::Forbidden(
+                    response_metadata.clone(),
+                    "Admin endpoints are disabled on this node".to_string(),
+                ));
+            }
+        };
+        let authorized = match request_auth_token {
+            // compare without short-circuiting, so the response time does not leak the token
+            Some(token) => {
+                token.len() == admin_auth_token.len()
+                    && token
+                        .bytes()
+                        .zip(admin_auth_token.bytes())
+                        .fold(0u8, |acc, (a, b)| acc | (a ^ b))
+                        == 0
+            }
+            None => false,
+        };
+        if !authorized {
+            return Some(HttpResponseType::Unauthorized(
+                response_metadata.clone(),
+                "Missing or invalid admin auth token".to_string(),
+            ));
+        }
+        None
+    }
+
+    /// Handle a POST to pause or resume the miner.  The request must carry the node's admin
+    /// auth token.
+    fn handle_set_miner_paused<W: Write>(
+        http: &mut StacksHttp,
+        fd: &mut W,
+        req: &HttpRequestType,
+        admin_auth_token: Option<&String>,
+        request_auth_token: Option<&String>,
+        miner_paused: Option<&AtomicBool>,
+        paused: bool,
+        canonical_stacks_tip_height: u64,
+    ) -> Result<(), net_error> {
+        let response_metadata =
+            HttpResponseMetadata::from_http_request_type(req, Some(canonical_stacks_tip_height));
+        if let Some(response) = ConversationHttp::check_admin_auth(
+            &response_metadata,
+            admin_auth_token,
+            request_auth_token,
+        ) {
+            return response.send(http, fd).map(|_| ());
+        }
+        let miner_paused = match miner_paused {
+            Some(miner_paused) => miner_paused,
+            None => {
+                let response = HttpResponseType::BadRequestJSON(
+                    response_metadata,
+                    json!({ "error": "This node is not a miner" }),
+                );
+                return response.send(http, fd).map(|_| ());
+            }
+        };
+        let was_paused = miner_paused.swap(paused, Ordering::SeqCst);
+        if was_paused != paused {
+            if paused {
+                info!("Miner paused by admin request; block-commits will not be submitted");
+            } else {
+                info!("Miner resumed by admin request");
+            }
+        }
+        let response = HttpResponseType::MinerPauseStatus(
+            response_metadata,
+            MinerPauseStatusResponse { paused },
+        );
+        response.send(http, fd).map(|_| ())
+    }
+
+    /// Handle a POST to rotate the miner's key.  The request must carry the node's admin auth
+    /// token.
+    fn handle_rotate_mining_key<W: Write>(
+        http: &mut StacksHttp,
+        fd: &mut W,
+        req: &HttpRequestType,
+        admin_auth_token: Option<&String>,
+        request_auth_token: Option<&String>,
+        mining_key_rotator: Option<&dyn MiningKeyRotator>,
+        canonical_stacks_tip_height: u64,
+    ) -> Result<(), net_error> {
+        let response_metadata =
+            HttpResponseMetadata::from_http_request_type(req, Some(canonical_stacks_tip_height));
+        if let Some(response) = ConversationHttp::check_admin_auth(
+            &response_metadata,
+            admin_auth_token,
+            request_auth_token,
+        ) {
+            return response.send(http, fd).map(|_| ());
+        }
+        let response = match mining_key_rotator {
+            Some(rotator) => match rotator.rotate_mining_key() {
+                Ok(rotation) => HttpResponseType::MiningKeyRotation(response_metadata, rotation),
+                Err(e) => {
+                    warn!("Failed to rotate mining key: {}", &e);
+                    HttpResponseType::BadRequestJSON(response_metadata, json!({ "error": e }))
+                }
+            },
+            None => HttpResponseType::BadRequestJSON(
+                response_metadata,
+                json!({ "error": "This node is not a miner" }),
+            ),
+        };
+        response.send(http, fd).map(|_| ())
+    }
+
+    /// Handle a GET for the operator's unexpired mempool filters.  The request must carry the
+    /// node's admin auth token.
+    fn handle_get_mempool_filters<W: Write>(
+        http: &mut StacksHttp,
+        fd: &mut W,
+        req: &HttpRequestType,
+        admin_auth_token: Option<&String>,
+        request_auth_token: Option<&String>,
+        mempool: &mut MemPoolDB,
+        canonical_stacks_tip_height: u64,
+    ) -> Result<(), net_error> {
+        let response_metadata =
+            HttpResponseMetadata::from_http_request_type(req, Some(canonical_stacks_tip_height));
+        if let Some(response) = ConversationHttp::check_admin_auth(
+            &response_metadata,
+            admin_auth_token,
+            request_auth_token,
+        ) {
+            return response.send(http, fd).map(|_| ());
+        }
+        let response = match mempool.prune_expired_filters() {
+            Ok(filters) => HttpResponseType::MemPoolFilters(
+                response_metadata,
+                MemPoolFiltersResponse { filters },
+            ),
+            Err(e) => {
+                warn!("Failed to load mempool filters: {:?}", &e);
+                HttpResponseType::ServerError(
+                    response_metadata,
+                    "Failed to load mempool filters".to_string(),
+                )
+            }
+        };
+        response.send(http, fd).map(|_| ())
+    }
+
+    /// Handle a POST to install a mempool filter.  The request must carry the node's admin auth
+    /// token.
+    fn handle_install_mempool_filter<W: Write>(
+        http: &mut StacksHttp,
+        fd: &mut W,
+        req: &HttpRequestType,
+        admin_auth_token: Option<&String>,
+        request_auth_token: Option<&String>,
+        mempool: &mut MemPoolDB,
+        body: &MemPoolFilterRequestBody,
+        canonical_stacks_tip_height: u64,
+    ) -> Result<(), net_error> {
+        let response_metadata =
+            HttpResponseMetadata::from_http_request_type(req, Some(canonical_stacks_tip_height));
+        if let Some(response) = ConversationHttp::check_admin_auth(
+            &response_metadata,
+            admin_auth_token,
+            request_auth_token,
+        ) {
+            return response.send(http, fd).map(|_| ());
+        }
+        let response =
+            match mempool.install_filter(body.kind, &body.value, body.ttl_secs, &body.reason) {
+                Ok(filter) => HttpResponseType::MemPoolFilter(response_metadata, filter),
+                Err(e) => {
+                    warn!("Failed to install mempool filter: {:?}", &e);
+                    HttpResponseType::ServerError(
+                        response_metadata,
+                        "Failed to install mempool filter".to_string(),
+                    )
+                }
+            };
+        response.send(http, fd).map(|_| ())
+    }
+
+    /// Handle a DELETE of a mempool filter.  The request must carry the node's admin auth token.
+    fn handle_remove_mempool_filter<W: Write>(
+        http: &mut StacksHttp,
+        fd: &mut W,
+        req: &HttpRequestType,
+        admin_auth_token: Option<&String>,
+        request_auth_token: Option<&String>,
+        mempool: &mut MemPoolDB,
+        filter_id: u64,
+        canonical_stacks_tip_height: u64,
+    ) -> Result<(), net_error> {
+        let response_metadata =
+            HttpResponseMetadata::from_http_request_type(req, Some(canonical_stacks_tip_height));
+        if let Some(response) = ConversationHttp::check_admin_auth(
+            &response_metadata,
+            admin_auth_token,
+            request_auth_token,
+        ) {
+            return response.send(http, fd).map(|_| ());
+        }
+        let response = match mempool.remove_filter(filter_id) {
+            Ok(Some(filter)) => HttpResponseType::MemPoolFilter(response_metadata, filter),
+            Ok(None) => HttpResponseType::NotFound(
+                response_metadata,
+                format!("No such mempool filter: {}", filter_id),
+            ),
+            Err(e) => {
+                warn!("Failed to remove mempool filter {}: {:?}", filter_id, &e);
+                HttpResponseType::ServerError(
+                    response_metadata,
+                    "Failed to remove mempool filter".to_string(),
+                )
+            }
+        };
+        response.send(http, fd).map(|_| ())
+    }
+
+    fn handle_validate_block_proposal<W: Write>(
+        http: &mut StacksHttp,
+        fd: &mut W,
+        req: &HttpRequestType,
+        chainstate: &mut StacksChainState,
+        sortdb: &SortitionDB,
+        signed_proposal: &miner::SignedProposal,
+        validator_key: Option<&Secp256k1PrivateKey>,
+        signing_contract: Option<&QualifiedContractIdentifier>,
+        options: &ConnectionOptions,
+        canonical_stacks_tip_height: u64,
+    ) -> Result<(), net_error> {
+        let response_metadata =
+            HttpResponseMetadata::from_http_request_type(req, Some(canonical_stacks_tip_height));
+        let validator_key = match validator_key {
+            Some(key) => key,
+            None => {
+                let response = HttpResponseType::BlockProposalInvalid {
+                    metadata: response_metadata,
+                    error_message:
+                        "Cannot validate block proposal: Not configured with validation key".into(),
+                };
+                return response.send(http, fd);
+            }
+        };
+
+        let signing_contract = match signing_contract {
+            Some(key) => key,
+            None => {
+                let response = HttpResponseType::BlockProposalInvalid {
+                    metadata: response_metadata,
+                    error_message:
+                        "Cannot validate block proposal: Not configured with a multiparty contract"
+                            .into(),
+                };
+                return response.send(http, fd);
+            }
+        };
+
+        let pubk_recovered = match signed_proposal.recover_signer_pk() {
+            Ok(key) => key,
+            Err(e) => {
+                let response = HttpResponseType::BlockProposalInvalid {
+                    metadata: response_metadata,
+                    error_message: format!("Cannot validate block proposal: {e}"),
+                };
+                return response.send(http, fd);
+            }
+        };
+
+        // TODO: Replace with lookup in `HashSet`
+        if !options.allowed_block_proposers.contains(&pubk_recovered) {
+            let response = HttpResponseType::BlockProposalInvalid {
+                metadata: response_metadata,
+                error_message:
+                    "Cannot validate block proposal: Not signed by approved block proposer".into(),
+            };
+            return response.send(http, fd);
+        }
+
+        let proposal = match signed_proposal.decode() {
+            Ok(p) => p,
+            Err(e) => {
+                let response = HttpResponseType::BlockProposalInvalid {
+                    metadata: response_metadata,
+                    error_message: format!("Cannot validate block proposal: {e}"),
+                };
+                return response.send(http, fd);
+            }
+        };
+
+        let response = match proposal.validate(chainstate, &sortdb.index_conn()) {
+            Ok(_) => {
+                let signature = proposal.sign(validator_key, signing_contract.clone());
+                HttpResponseType::BlockProposalValid {
+                    metadata: response_metadata,
+                    signature,
+                }
+            }
+            Err(e) => HttpResponseType::BlockProposalInvalid {
+                metadata: response_metadata,
+                error_message: e.to_string(),
+            },
+        };
+        response.send(http, fd)
+    }
+
+    fn handle_get_withdrawal_stx_entry<W: Write>(
+        http: &mut StacksHttp,
+        fd: &mut W,
+        req: &HttpRequestType,
+        chainstate: &mut StacksChainState,
+        canonical_tip: &StacksBlockId,
+        requested_block_height: u64,
+        sender: &PrincipalData,
+        withdrawal_id: u32,
+        amount: u128,
+        canonical_stacks_tip_height: u64,
+    ) -> Result<(), net_error> {
+        let withdrawal_key = withdrawal::make_key_for_stx_withdrawal(
+            sender,
+            withdrawal_id,
+            amount,
+            requested_block_height,
+        );
+        Self::handle_get_generic_withdrawal_entry(
+            http,
+            fd,
             req,
             chainstate,
             canonical_tip,
@@ -1110,6 +1974,40 @@ impl ConversationHttp {
         )
     }
 
+    fn handle_get_withdrawal_stx_transfer_entry<W: Write>(
+        http: &mut StacksHttp,
+        fd: &mut W,
+        req: &HttpRequestType,
+        chainstate: &mut StacksChainState,
+        canonical_tip: &StacksBlockId,
+        requested_block_height: u64,
+        sender: &PrincipalData,
+        withdrawal_id: u32,
+        amount: u128,
+        recipient: &PrincipalData,
+        destination_subnet: &QualifiedContractIdentifier,
+        canonical_stacks_tip_height: u64,
+    ) -> Result<(), net_error> {
+        let withdrawal_key = withdrawal::make_key_for_stx_transfer_withdrawal(
+            sender,
+            recipient,
+            destination_subnet,
+            withdrawal_id,
+            amount,
+            requested_block_height,
+        );
+        Self::handle_get_generic_withdrawal_entry(
+            http,
+            fd,
+            req,
+            chainstate,
+            canonical_tip,
+            requested_block_height,
+            withdrawal_key,
+            canonical_stacks_tip_height,
+        )
+    }
+
     fn handle_get_generic_withdrawal_entry<W: Write>(
         http: &mut StacksHttp,
         fd: &mut W,
@@ -1143,32 +2041,56 @@ impl ConversationHttp {
             }
         };
 
-        let block_info_result = StacksChainState::get_stacks_block_header_info_by_index_block_hash(
-            chainstate.db(),
-            &requested_block,
-        );
-        let withdrawal_tree = match block_info_result {
-            Ok(Some(block_info)) => block_info.withdrawal_tree,
-            Err(_) | Ok(None) => {
-                return HttpResponseType::NotFound(
-                    response_metadata,
-                    "Supplied block not found".into(),
+        // the archive keeps every block's tree, however old
+        let withdrawal_tree =
+            match StacksChainState::get_withdrawal_tree(chainstate.db(), &requested_block) {
+                Ok(Some(withdrawal_tree)) => withdrawal_tree,
+                Err(_) | Ok(None) => {
+                    return HttpResponseType::NotFound(
+                        response_metadata,
+                        "Supplied block not found".into(),
+                    )
+                    .send(http, fd)
+                    .map(|_| ())
+                }
+            };
+
+        let response = match ConversationHttp::make_withdrawal_proof(
+            &withdrawal_tree,
+            &withdrawal_key_bytes,
+            &requested_block,
+        ) {
+            Ok((withdrawal_root, withdrawal_leaf_hash, sibling_hashes)) => {
+                ConversationHttp::make_withdrawal_response(
+                    &withdrawal_root,
+                    &withdrawal_leaf_hash,
+                    &sibling_hashes,
                 )
-                .send(http, fd)
-                .map(|_| ())
+            }
+            Err(msg) => {
+                return HttpResponseType::NotFound(response_metadata, msg)
+                    .send(http, fd)
+                    .map(|_| ())
             }
         };
 
-        let merkle_path = match withdrawal_tree.path(&withdrawal_key_bytes) {
+        HttpResponseType::GetWithdrawal(response_metadata, response)
+            .send(http, fd)
+            .map(|_| ())
+    }
+
+    /// Make the Merkle proof of the withdrawal whose key serializes to `withdrawal_key_bytes` in
+    /// the withdrawal tree of the block `block_id`: the Clarity values of the tree's root, the
+    /// withdrawal's leaf hash, and the sibling hashes the subnet contract checks it with.
+    /// Errors with the reason there is no proof.
+    fn make_withdrawal_proof(
+        withdrawal_tree: &MerkleTree<Sha512Trunc256Sum>,
+        withdrawal_key_bytes: &[u8],
+        block_id: &StacksBlockId,
+    ) -> Result<(Value, Value, Value), String> {
+        let merkle_path = match withdrawal_tree.path(withdrawal_key_bytes) {
             Some(path) => path,
-            None => {
-                return HttpResponseType::NotFound(
-                    response_metadata,
-                    "Supplied withdrawal key not found".into(),
-                )
-                .send(http, fd)
-                .map(|_| ())
-            }
+            None => return Err("Supplied withdrawal key not found".into()),
         };
 
         let tuple_vec: Vec<_> = merkle_path
@@ -1196,30 +2118,30 @@ impl ConversationHttp {
             Ok(list) => list,
             Err(_) => {
                 error!("Failed to construct a valid Clarity list type out of withdrawal merkle path";
-                       "l2_block_id" => %requested_block);
-                return HttpResponseType::NotFound(
-                    response_metadata,
-                    "Withdrawal merkle tree at this block height is invalid".into(),
-                )
-                .send(http, fd)
-                .map(|_| ());
+                       "l2_block_id" => %block_id);
+                return Err("Withdrawal merkle tree at this block height is invalid".into());
             }
         };
 
         let withdrawal_root = withdrawal::buffer_from_hash(withdrawal_tree.root());
         let withdrawal_leaf_hash = withdrawal::buffer_from_hash(
-            MerkleTree::<Sha512Trunc256Sum>::get_leaf_hash(&withdrawal_key_bytes),
+            MerkleTree::<Sha512Trunc256Sum>::get_leaf_hash(withdrawal_key_bytes),
         );
 
-        let response = WithdrawalResponse {
+        Ok((withdrawal_root, withdrawal_leaf_hash, sibling_hashes))
+    }
+
+    /// Report a withdrawal's Merkle proof, as made by `make_withdrawal_proof`
+    fn make_withdrawal_response(
+        withdrawal_root: &Value,
+        withdrawal_leaf_hash: &Value,
+        sibling_hashes: &Value,
+    ) -> WithdrawalResponse {
+        WithdrawalResponse {
             withdrawal_root: format!("0x{}", withdrawal_root.serialize_to_hex()),
             withdrawal_leaf_hash: format!("0x{}", withdrawal_leaf_hash.serialize_to_hex()),
             sibling_hashes: format!("0x{}", sibling_hashes.serialize_to_hex()),
-        };
-
-        HttpResponseType::GetWithdrawal(response_metadata, response)
-            .send(http, fd)
-            .map(|_| ())
+        }
     }
 
     /// Handle a GET on an existing account, given the current chain tip.  Optionally supplies a
@@ -1302,2955 +2224,6126 @@ impl ConversationHttp {
         response.send(http, fd).map(|_| ())
     }
 
-    /// Handle a GET on a smart contract's data var, given the current chain tip.  Optionally
-    /// supplies a MARF proof for the value.
-    fn handle_get_data_var<W: Write>(
+    /// Handle a GET on an account's pending mempool transactions, reporting any gaps in their
+    /// nonces relative to the account's nonce at the given chain tip.
+    fn handle_get_mempool_account<W: Write>(
         http: &mut StacksHttp,
         fd: &mut W,
         req: &HttpRequestType,
         sortdb: &SortitionDB,
         chainstate: &mut StacksChainState,
+        mempool: &MemPoolDB,
         tip: &StacksBlockId,
-        contract_addr: &StacksAddress,
-        contract_name: &ContractName,
-        var_name: &ClarityName,
-        with_proof: bool,
+        address: &StacksAddress,
         canonical_stacks_tip_height: u64,
     ) -> Result<(), net_error> {
         let response_metadata =
             HttpResponseMetadata::from_http_request_type(req, Some(canonical_stacks_tip_height));
-        let contract_identifier =
-            QualifiedContractIdentifier::new(contract_addr.clone().into(), contract_name.clone());
-
-        let response =
-            match chainstate.maybe_read_only_clarity_tx(&sortdb.index_conn(), tip, |clarity_tx| {
+        let account = address.to_account_principal();
+        let nonce_opt =
+            chainstate.maybe_read_only_clarity_tx(&sortdb.index_conn(), tip, |clarity_tx| {
                 clarity_tx.with_clarity_db_readonly(|clarity_db| {
-                    let key = ClarityDatabase::make_key_for_trip(
-                        &contract_identifier,
-                        StoreType::Variable,
-                        var_name,
-                    );
-
-                    let (value_hex, marf_proof): (String, _) = if with_proof {
-                        clarity_db
-                            .get_with_proof(&key)
-                            .map(|(a, b)| (a, Some(format!("0x{}", to_hex(&b)))))?
-                    } else {
-                        clarity_db.get(&key).map(|a| (a, None))?
-                    };
-
-                    let data = format!("0x{}", value_hex);
-                    Some(DataVarResponse { data, marf_proof })
+                    let key = ClarityDatabase::make_key_for_account_nonce(&account);
+                    clarity_db.get(&key).unwrap_or(0)
                 })
-            }) {
-                Ok(Some(Some(data))) => HttpResponseType::GetDataVar(response_metadata, data),
-                Ok(Some(None)) => {
-                    HttpResponseType::NotFound(response_metadata, "Data var not found".into())
-                }
-                Ok(None) | Err(_) => {
-                    HttpResponseType::NotFound(response_metadata, "Chain tip not found".into())
-                }
-            };
+            });
+
+        let response = match nonce_opt {
+            Ok(Some(nonce)) => {
+                let txs = MemPoolDB::get_tx_metadata_by_origin(mempool.conn(), address)?;
+                HttpResponseType::GetMempoolAccount(
+                    response_metadata,
+                    MempoolAccountResponse::from_pending_txs(address, nonce, &txs),
+                )
+            }
+            Ok(None) | Err(_) => {
+                HttpResponseType::NotFound(response_metadata, "Chain tip not found".into())
+            }
+        };
 
         response.send(http, fd).map(|_| ())
     }
 
-    /// Handle a GET on a smart contract's data map, given the current chain tip.  Optionally
-    /// supplies a MARF proof for the value.
-    fn handle_get_map_entry<W: Write>(
+    /// Handle a GET on the nonce an account's next transaction should use, given its nonce at
+    /// the given chain tip and the transactions it has pending in the mempool.
+    fn handle_get_account_next_nonce<W: Write>(
         http: &mut StacksHttp,
         fd: &mut W,
         req: &HttpRequestType,
         sortdb: &SortitionDB,
         chainstate: &mut StacksChainState,
+        mempool: &MemPoolDB,
         tip: &StacksBlockId,
-        contract_addr: &StacksAddress,
-        contract_name: &ContractName,
-        map_name: &ClarityName,
-        key: &Value,
-        with_proof: bool,
+        address: &StacksAddress,
         canonical_stacks_tip_height: u64,
     ) -> Result<(), net_error> {
         let response_metadata =
             HttpResponseMetadata::from_http_request_type(req, Some(canonical_stacks_tip_height));
-        let contract_identifier =
-            QualifiedContractIdentifier::new(contract_addr.clone().into(), contract_name.clone());
-
-        let response =
-            match chainstate.maybe_read_only_clarity_tx(&sortdb.index_conn(), tip, |clarity_tx| {
+        let account = address.to_account_principal();
+        let nonce_opt =
+            chainstate.maybe_read_only_clarity_tx(&sortdb.index_conn(), tip, |clarity_tx| {
                 clarity_tx.with_clarity_db_readonly(|clarity_db| {
-                    let key = ClarityDatabase::make_key_for_data_map_entry(
-                        &contract_identifier,
-                        map_name,
-                        key,
-                    );
-                    let (value_hex, marf_proof): (String, _) = if with_proof {
-                        clarity_db
-                            .get_with_proof(&key)
-                            .map(|(a, b)| (a, Some(format!("0x{}", to_hex(&b)))))
-                            .unwrap_or_else(|| {
-                                test_debug!("No value for '{}' in {}", &key, tip);
-                                (Value::none().serialize_to_hex(), Some("".into()))
-                            })
-                    } else {
-                        clarity_db.get(&key).map(|a| (a, None)).unwrap_or_else(|| {
-                            test_debug!("No value for '{}' in {}", &key, tip);
-                            (Value::none().serialize_to_hex(), None)
-                        })
-                    };
-
-                    let data = format!("0x{}", value_hex);
-                    MapEntryResponse { data, marf_proof }
+                    let key = ClarityDatabase::make_key_for_account_nonce(&account);
+                    clarity_db.get(&key).unwrap_or(0)
                 })
-            }) {
-                Ok(Some(data)) => HttpResponseType::GetMapEntry(response_metadata, data),
-                Ok(None) | Err(_) => {
-                    HttpResponseType::NotFound(response_metadata, "Chain tip not found".into())
-                }
-            };
+            });
+
+        let response = match nonce_opt {
+            Ok(Some(nonce)) => {
+                let origin_txs = MemPoolDB::get_tx_metadata_by_origin(mempool.conn(), address)?;
+                let sponsored_txs = MemPoolDB::get_tx_metadata_by_sponsor(mempool.conn(), address)?;
+                HttpResponseType::GetAccountNextNonce(
+                    response_metadata,
+                    AccountNextNonceResponse::from_pending_txs(
+                        address,
+                        nonce,
+                        &origin_txs,
+                        &sponsored_txs,
+                    ),
+                )
+            }
+            Ok(None) | Err(_) => {
+                HttpResponseType::NotFound(response_metadata, "Chain tip not found".into())
+            }
+        };
 
         response.send(http, fd).map(|_| ())
     }
 
-    /// Handle a POST to run a read-only function call with the given parameters on the given chain
-    /// tip.  Returns the result of the function call.  Returns a CallReadOnlyResponse on success.
-    fn handle_readonly_function_call<W: Write>(
+    /// Handle a GET for the first `max_results` mempool transactions a miner building on `tip`
+    /// would consider, in the order it would consider them.  The walk runs for at most
+    /// `MEMPOOL_PREVIEW_WALK_TIME_MS`, and leaves the mempool as it found it.
+    fn handle_get_mempool_preview<W: Write>(
         http: &mut StacksHttp,
         fd: &mut W,
         req: &HttpRequestType,
         sortdb: &SortitionDB,
         chainstate: &mut StacksChainState,
+        mempool: &mut MemPoolDB,
         tip: &StacksBlockId,
-        contract_addr: &StacksAddress,
-        contract_name: &ContractName,
-        function: &ClarityName,
-        sender: &PrincipalData,
-        sponsor: Option<&PrincipalData>,
-        args: &[Value],
-        options: &ConnectionOptions,
+        max_results: u64,
         canonical_stacks_tip_height: u64,
     ) -> Result<(), net_error> {
         let response_metadata =
             HttpResponseMetadata::from_http_request_type(req, Some(canonical_stacks_tip_height));
-        let contract_identifier =
-            QualifiedContractIdentifier::new(contract_addr.clone().into(), contract_name.clone());
-
-        let args: Vec<_> = args
-            .iter()
-            .map(|x| SymbolicExpression::atom_value(x.clone()))
-            .collect();
-        let mainnet = chainstate.mainnet;
-        let chain_id = chainstate.chain_id;
-        let mut cost_limit = options.read_only_call_limit.clone();
-        cost_limit.write_length = 0;
-        cost_limit.write_count = 0;
+        let mut settings = MemPoolWalkSettings::default();
+        settings.max_walk_time_ms = MEMPOOL_PREVIEW_WALK_TIME_MS;
 
-        let data_opt_res =
+        let preview_res =
             chainstate.maybe_read_only_clarity_tx(&sortdb.index_conn(), tip, |clarity_tx| {
-                let epoch = clarity_tx.get_epoch();
-                let cost_track = clarity_tx
-                    .with_clarity_db_readonly(|clarity_db| {
-                        LimitedCostTracker::new_mid_block(
-                            mainnet, chain_id, cost_limit, clarity_db, epoch,
-                        )
-                    })
-                    .map_err(|_| {
-                        ClarityRuntimeError::from(InterpreterError::CostContractLoadFailure)
-                    })?;
-
-                let clarity_version = clarity_tx
-                    .with_analysis_db_readonly(|analysis_db| {
-                        analysis_db.get_clarity_version(&contract_identifier)
-                    })
-                    .map_err(|_| {
-                        ClarityRuntimeError::from(CheckErrors::NoSuchContract(
-                            contract_identifier.to_string(),
-                        ))
-                    })?;
-
-                clarity_tx.with_readonly_clarity_env(
-                    mainnet,
-                    chain_id,
-                    clarity_version,
-                    sender.clone(),
-                    sponsor.cloned(),
-                    cost_track,
-                    |env| {
-                        // we want to execute any function as long as no actual writes are made as
-                        // opposed to be limited to purely calling `define-read-only` functions,
-                        // so use `read_only = false`.  This broadens the number of functions that
-                        // can be called, and also circumvents limitations on `define-read-only`
-                        // functions that can not use `contrac-call?`, even when calling other
-                        // read-only functions
-                        env.execute_contract(&contract_identifier, function.as_str(), &args, false)
-                    },
+                mempool.preview_candidates(
+                    clarity_tx,
+                    canonical_stacks_tip_height,
+                    settings,
+                    max_results as usize,
                 )
             });
 
-        let response = match data_opt_res {
-            Ok(Some(Ok(data))) => HttpResponseType::CallReadOnlyFunction(
-                response_metadata,
-                CallReadOnlyResponse {
-                    okay: true,
-                    result: Some(format!("0x{}", data.serialize_to_hex())),
-                    cause: None,
-                },
-            ),
-            Ok(Some(Err(e))) => match e {
-                Unchecked(CheckErrors::CostBalanceExceeded(actual_cost, _))
-                    if actual_cost.write_count > 0 =>
-                {
-                    HttpResponseType::CallReadOnlyFunction(
-                        response_metadata,
-                        CallReadOnlyResponse {
-                            okay: false,
-                            result: None,
-                            cause: Some("NotReadOnly".to_string()),
-                        },
-                    )
-                }
-                _ => HttpResponseType::CallReadOnlyFunction(
+        let response = match preview_res {
+            Ok(Some(Ok(candidates))) => {
+                let candidates = candidates
+                    .into_iter()
+                    .map(|candidate| MempoolPreviewEntry {
+                        txid: format!("0x{}", &candidate.tx.metadata.txid),
+                        origin_address: candidate.tx.metadata.origin_address.to_string(),
+                        origin_nonce: candidate.tx.metadata.origin_nonce,
+                        fee: candidate.tx.metadata.tx_fee,
+                        fee_rate: candidate.fee_rate,
+                        no_estimate: candidate.update_estimate,
+                    })
+                    .collect();
+                HttpResponseType::GetMempoolPreview(
                     response_metadata,
-                    CallReadOnlyResponse {
-                        okay: false,
-                        result: None,
-                        cause: Some(e.to_string()),
+                    MempoolPreviewResponse {
+                        tip: tip.clone(),
+                        candidates,
                     },
-                ),
-            },
-            Ok(None) | Err(_) => {
-                HttpResponseType::NotFound(response_metadata, "Chain tip not found".into())
+                )
+            }
+            Ok(None) => HttpResponseType::NotFound(response_metadata, "Chain tip not found".into()),
+            Ok(Some(Err(e))) | Err(e) => {
+                warn!("Failed to preview mempool: {:?}", &e);
+                HttpResponseType::ServerError(
+                    response_metadata,
+                    "Failed to preview mempool".to_string(),
+                )
             }
         };
+
         response.send(http, fd).map(|_| ())
     }
 
-    /// Handle a GET to fetch a contract's source code, given the chain tip.  Optionally returns a
-    /// MARF proof as well.
-    fn handle_get_contract_src<W: Write>(
+    /// Handle a GET for a summary of the mempool's contents
+    fn handle_get_mempool_stats<W: Write>(
         http: &mut StacksHttp,
         fd: &mut W,
         req: &HttpRequestType,
-        sortdb: &SortitionDB,
-        chainstate: &mut StacksChainState,
-        tip: &StacksBlockId,
-        contract_addr: &StacksAddress,
-        contract_name: &ContractName,
-        with_proof: bool,
+        mempool: &MemPoolDB,
         canonical_stacks_tip_height: u64,
     ) -> Result<(), net_error> {
         let response_metadata =
             HttpResponseMetadata::from_http_request_type(req, Some(canonical_stacks_tip_height));
-        let contract_identifier =
-            QualifiedContractIdentifier::new(contract_addr.clone().into(), contract_name.clone());
-
-        let response =
-            match chainstate.maybe_read_only_clarity_tx(&sortdb.index_conn(), tip, |clarity_tx| {
-                clarity_tx.with_clarity_db_readonly(|db| {
-                    let source = db.get_contract_src(&contract_identifier)?;
-                    let contract_commit_key = make_contract_hash_key(&contract_identifier);
-                    let (contract_commit, proof) = if with_proof {
-                        db.get_with_proof::<ContractCommitment>(&contract_commit_key)
-                            .map(|(a, b)| (a, Some(format!("0x{}", to_hex(&b)))))
-                            .expect("BUG: obtained source, but couldn't get contract commit")
-                    } else {
-                        db.get::<ContractCommitment>(&contract_commit_key)
-                            .map(|a| (a, None))
-                            .expect("BUG: obtained source, but couldn't get contract commit")
-                    };
-
-                    let publish_height = contract_commit.block_height;
-                    Some(ContractSrcResponse {
-                        source,
-                        publish_height,
-                        marf_proof: proof,
-                    })
-                })
-            }) {
-                Ok(Some(Some(data))) => HttpResponseType::GetContractSrc(response_metadata, data),
-                Ok(Some(None)) => HttpResponseType::NotFound(
+        let now = mempool.get_clock().now_secs();
+        let response = match MemPoolDB::get_stats(mempool.conn(), now) {
+            Ok(stats) => HttpResponseType::GetMempoolStats(response_metadata, stats),
+            Err(e) => {
+                warn!("Failed to load mempool stats: {:?}", &e);
+                HttpResponseType::ServerError(
                     response_metadata,
-                    "No contract source data found".into(),
-                ),
-                Ok(None) | Err(_) => {
-                    HttpResponseType::NotFound(response_metadata, "Chain tip not found".into())
-                }
-            };
-
+                    "Failed to load mempool stats".to_string(),
+                )
+            }
+        };
         response.send(http, fd).map(|_| ())
     }
 
-    /// Handle a GET to fetch whether or not a contract implements a certain trait
-    fn handle_get_is_trait_implemented<W: Write>(
+    /// Handle a GET for the mempool's kept rejections of a transaction, or of up to `limit` of
+    /// the transactions an address sent or sponsored.  Answers 404 if the mempool does not keep
+    /// rejected transactions.
+    fn handle_get_rejected_txs<W: Write>(
         http: &mut StacksHttp,
         fd: &mut W,
         req: &HttpRequestType,
-        sortdb: &SortitionDB,
-        chainstate: &mut StacksChainState,
-        tip: &StacksBlockId,
-        contract_addr: &StacksAddress,
-        contract_name: &ContractName,
-        trait_id: &TraitIdentifier,
+        mempool: &MemPoolDB,
+        query: &RejectedTxsQuery,
+        limit: u64,
         canonical_stacks_tip_height: u64,
     ) -> Result<(), net_error> {
         let response_metadata =
             HttpResponseMetadata::from_http_request_type(req, Some(canonical_stacks_tip_height));
-        let contract_identifier =
-            QualifiedContractIdentifier::new(contract_addr.clone().into(), contract_name.clone());
+        if mempool.get_rejected_txs_retention().is_none() {
+            let response = HttpResponseType::NotFound(
+                response_metadata,
+                "This node does not keep rejected transactions".to_string(),
+            );
+            return response.send(http, fd).map(|_| ());
+        }
 
-        let response =
-            match chainstate.maybe_read_only_clarity_tx(&sortdb.index_conn(), tip, |clarity_tx| {
-                clarity_tx.with_clarity_db_readonly(|db| {
-                    let analysis = db.load_contract_analysis(&contract_identifier)?;
-                    if analysis.implemented_traits.contains(trait_id) {
-                        Some(GetIsTraitImplementedResponse {
-                            is_implemented: true,
+        let rejected_res = match query {
+            RejectedTxsQuery::Txid(txid) => MemPoolDB::get_rejected_tx(mempool.conn(), txid)
+                .map(|rejected| rejected.into_iter().collect()),
+            RejectedTxsQuery::Address(address) => {
+                MemPoolDB::get_rejected_txs_by_address(mempool.conn(), address, limit)
+            }
+        };
+        let response = match rejected_res {
+            Ok(rejected_txs) => HttpResponseType::GetRejectedTxs(
+                response_metadata,
+                RejectedTxsResponse {
+                    rejected_txs: rejected_txs
+                        .into_iter()
+                        .map(|rejected| RejectedTxEntry {
+                            txid: format!("0x{}", &rejected.txid),
+                            origin_address: rejected.origin_address.to_string(),
+                            sponsor_address: rejected.sponsor_address.to_string(),
+                            reason: rejected.reason,
+                            reason_data: rejected.reason_data,
+                            consensus_hash: rejected.consensus_hash.to_string(),
+                            block_header_hash: rejected.block_header_hash.to_string(),
+                            rejected_at: rejected.rejected_at,
                         })
-                    } else {
-                        let trait_defining_contract =
-                            db.load_contract_analysis(&trait_id.contract_identifier)?;
-                        let trait_definition =
-                            trait_defining_contract.get_defined_trait(&trait_id.name)?;
-                        let is_implemented = analysis
-                            .check_trait_compliance(
-                                &StacksEpochId::Epoch21,
-                                trait_id,
-                                trait_definition,
-                            )
-                            .is_ok();
-                        Some(GetIsTraitImplementedResponse { is_implemented })
-                    }
-                })
-            }) {
-                Ok(Some(Some(data))) => {
-                    HttpResponseType::GetIsTraitImplemented(response_metadata, data)
-                }
-                Ok(Some(None)) => HttpResponseType::NotFound(
+                        .collect(),
+                },
+            ),
+            Err(e) => {
+                warn!("Failed to load rejected transactions: {:?}", &e);
+                HttpResponseType::ServerError(
                     response_metadata,
-                    "No contract analysis found or trait definition not found".into(),
-                ),
-                Ok(None) | Err(_) => {
-                    HttpResponseType::NotFound(response_metadata, "Chain tip not found".into())
-                }
-            };
+                    "Failed to load rejected transactions".to_string(),
+                )
+            }
+        };
 
         response.send(http, fd).map(|_| ())
     }
 
-    /// Handle a GET to fetch a contract's analysis data, given the chain tip.  Note that this isn't
-    /// something that's anchored to the blockchain, and can be different across different versions
-    /// of Stacks -- callers must trust the Stacks node to return correct analysis data.
-    /// Callers who don't trust the Stacks node should just fetch the contract source
-    /// code and analyze it offline.
-    fn handle_get_contract_abi<W: Write>(
+    /// Handle a GET for a page of `sender`'s withdrawals in the fork ending at `tip`.  Fetches one
+    /// more than the page holds, to tell whether there is a next page.
+    fn handle_get_withdrawals_by_sender<W: Write>(
         http: &mut StacksHttp,
         fd: &mut W,
         req: &HttpRequestType,
-        sortdb: &SortitionDB,
         chainstate: &mut StacksChainState,
         tip: &StacksBlockId,
-        contract_addr: &StacksAddress,
-        contract_name: &ContractName,
+        sender: &PrincipalData,
+        offset: u64,
+        limit: u64,
         canonical_stacks_tip_height: u64,
     ) -> Result<(), net_error> {
         let response_metadata =
             HttpResponseMetadata::from_http_request_type(req, Some(canonical_stacks_tip_height));
-        let contract_identifier =
-            QualifiedContractIdentifier::new(contract_addr.clone().into(), contract_name.clone());
-
-        let response =
-            match chainstate.maybe_read_only_clarity_tx(&sortdb.index_conn(), tip, |clarity_tx| {
-                let epoch = clarity_tx.get_epoch();
-                clarity_tx.with_analysis_db_readonly(|db| {
-                    let contract = db.load_contract(&contract_identifier, &epoch)?;
-                    contract.contract_interface
-                })
-            }) {
-                Ok(Some(Some(data))) => HttpResponseType::GetContractABI(response_metadata, data),
-                Ok(Some(None)) => HttpResponseType::NotFound(
+        let response = match chainstate.get_withdrawals_by_sender(
+            tip,
+            sender,
+            offset,
+            limit.saturating_add(1),
+        ) {
+            Ok(mut entries) => {
+                let next_offset = if entries.len() as u64 > limit {
+                    entries.truncate(limit as usize);
+                    Some(offset.saturating_add(limit))
+                } else {
+                    None
+                };
+                HttpResponseType::GetWithdrawalsBySender(
                     response_metadata,
-                    "No contract interface data found".into(),
-                ),
-                Ok(None) | Err(_) => {
-                    HttpResponseType::NotFound(response_metadata, "Chain tip not found".into())
-                }
-            };
+                    WithdrawalsResponse {
+                        withdrawals: entries
+                            .iter()
+                            .map(WithdrawalEntryResponse::from_entry)
+                            .collect(),
+                        next_offset,
+                    },
+                )
+            }
+            Err(e) => {
+                warn!("Failed to load withdrawals of {}: {:?}", sender, &e);
+                HttpResponseType::ServerError(
+                    response_metadata,
+                    format!("Failed to load withdrawals of {}", sender),
+                )
+            }
+        };
 
         response.send(http, fd).map(|_| ())
     }
 
-    /// Handle a GET unconfirmed microblock stream.  Start streaming the reply.
-    /// The response's preamble (but not the block data) will be synchronously written to the fd
-    /// (so use a fd that can buffer!)
-    /// Return a StreamCursor struct for the block that we're sending, so we can continue to
-    /// make progress sending it.
-    fn handle_getmicroblocks_unconfirmed<W: Write>(
+    /// Handle a GET on whether the withdrawal `withdrawal_id` of the block at `block_height` in
+    /// the fork ending at `tip` can be claimed on the L1 yet.  Reports the withdrawal's proof,
+    /// how deep in the L1 its block's commit is, and the contract call which claims it.  The L1
+    /// is asked, through `l1_oracle`, whether the subnet contract has recorded the withdrawal
+    /// root, and what the claim would cost; without it, neither is known.
+    fn handle_get_withdrawal_claim<W: Write>(
         http: &mut StacksHttp,
         fd: &mut W,
         req: &HttpRequestType,
-        index_anchor_block_hash: &StacksBlockId,
-        min_seq: u16,
-        chainstate: &StacksChainState,
+        sortdb: &SortitionDB,
+        chainstate: &mut StacksChainState,
+        tip: &StacksBlockId,
+        block_height: u64,
+        withdrawal_id: u32,
+        l1_oracle: Option<&dyn L1WithdrawalOracle>,
+        burn_tip_height: u64,
         canonical_stacks_tip_height: u64,
-    ) -> Result<Option<StreamCursor>, net_error> {
+    ) -> Result<(), net_error> {
         let response_metadata =
             HttpResponseMetadata::from_http_request_type(req, Some(canonical_stacks_tip_height));
-
-        // do we have this unconfirmed microblock stream?
-        match chainstate.has_any_staging_microblock_indexed(index_anchor_block_hash, min_seq) {
-            Ok(false) => {
-                // nope
-                let response = HttpResponseType::NotFound(
-                    response_metadata,
-                    format!(
-                        "No such unconfirmed microblock stream for {} at or after {}",
-                        index_anchor_block_hash.to_hex(),
-                        min_seq
-                    ),
-                );
-                response.send(http, fd).and_then(|_| Ok(None))
+        let entry = match chainstate.get_withdrawal(tip, block_height, withdrawal_id) {
+            Ok(Some(entry)) => entry,
+            Ok(None) => {
+                return HttpResponseType::NotFound(response_metadata, "No such withdrawal".into())
+                    .send(http, fd)
+                    .map(|_| ())
             }
             Err(e) => {
-                // nope
                 warn!(
-                    "Failed to serve confirmed microblock stream {:?}: {:?}",
-                    req, &e
+                    "Failed to load withdrawal {} at height {}: {:?}",
+                    withdrawal_id, block_height, &e
                 );
-                let response = HttpResponseType::ServerError(
+                return HttpResponseType::ServerError(
                     response_metadata,
-                    format!(
-                        "Failed to query unconfirmed microblock stream for {} at or after {}",
-                        index_anchor_block_hash.to_hex(),
-                        min_seq
-                    ),
-                );
-                response.send(http, fd).and_then(|_| Ok(None))
+                    "Failed to load withdrawal".into(),
+                )
+                .send(http, fd)
+                .map(|_| ());
             }
-            Ok(true) => {
-                // yup! start streaming it back
-                let (response, stream_opt) = match StreamCursor::new_microblock_unconfirmed(
-                    chainstate,
-                    index_anchor_block_hash.clone(),
-                    min_seq,
-                ) {
-                    Ok(stream) => (
-                        HttpResponseType::MicroblockStream(response_metadata),
-                        Some(stream),
-                    ),
-                    Err(chain_error::NoSuchBlockError) => (
-                        HttpResponseType::NotFound(
-                            response_metadata,
-                            format!(
-                                "No such unconfirmed microblock stream starting with {}",
-                                index_anchor_block_hash.to_hex()
-                            ),
-                        ),
-                        None,
-                    ),
-                    Err(_e) => {
-                        debug!(
-                            "Failed to load unconfirmed microblock stream {}: {:?}",
-                            &index_anchor_block_hash, &_e
+        };
+
+        let withdrawal_key = match entry.make_key() {
+            Some(key) => key,
+            None => {
+                return HttpResponseType::BadRequestJSON(
+                    response_metadata,
+                    json!({
+                        "error": "withdrawal is not claimed on the L1",
+                        "asset_type": &entry.asset_type,
+                    }),
+                )
+                .send(http, fd)
+                .map(|_| ())
+            }
+        };
+
+        let header_info = match StacksChainState::get_stacks_block_header_info_by_index_block_hash(
+            chainstate.db(),
+            &entry.index_block_hash,
+        ) {
+            Ok(Some(header_info)) => header_info,
+            Err(_) | Ok(None) => {
+                return HttpResponseType::NotFound(
+                    response_metadata,
+                    "Supplied block not found".into(),
+                )
+                .send(http, fd)
+                .map(|_| ())
+            }
+        };
+
+        let withdrawal_tree =
+            match StacksChainState::get_withdrawal_tree(chainstate.db(), &entry.index_block_hash) {
+                Ok(Some(withdrawal_tree)) => withdrawal_tree,
+                Err(_) | Ok(None) => {
+                    return HttpResponseType::NotFound(
+                        response_metadata,
+                        "Supplied block not found".into(),
+                    )
+                    .send(http, fd)
+                    .map(|_| ())
+                }
+            };
+
+        let (withdrawal_root, withdrawal_leaf_hash, sibling_hashes) =
+            match ConversationHttp::make_withdrawal_proof(
+                &withdrawal_tree,
+                &withdrawal_key.serialize_to_vec(),
+                &entry.index_block_hash,
+            ) {
+                Ok(proof) => proof,
+                Err(msg) => {
+                    return HttpResponseType::NotFound(response_metadata, msg)
+                        .send(http, fd)
+                        .map(|_| ())
+                }
+            };
+        let proof = ConversationHttp::make_withdrawal_response(
+            &withdrawal_root,
+            &withdrawal_leaf_hash,
+            &sibling_hashes,
+        );
+
+        let l1_commit_height = match SortitionDB::get_block_snapshot_consensus(
+            sortdb.conn(),
+            &header_info.consensus_hash,
+        ) {
+            Ok(Some(snapshot)) => snapshot.block_height,
+            Err(_) | Ok(None) => {
+                return HttpResponseType::NotFound(
+                    response_metadata,
+                    "No sortition for the withdrawal's block".into(),
+                )
+                .send(http, fd)
+                .map(|_| ())
+            }
+        };
+        let l1_confirmations = commit_confirmations(l1_commit_height, burn_tip_height);
+
+        let root_committed = l1_oracle.and_then(|oracle| {
+            oracle
+                .is_withdrawal_root_committed(&withdrawal_tree.root())
+                .map_err(|e| {
+                    warn!("Failed to ask the L1 whether a withdrawal root is committed";
+                          "l2_block_id" => %entry.index_block_hash,
+                          "error" => &e);
+                })
+                .ok()
+        });
+
+        let l1_asset_contract = match entry.asset_contract {
+            Some(ref l2_contract_id) if entry.asset_type != "stx" => {
+                match SortitionDB::get_l1_asset_contract(sortdb.conn(), l2_contract_id) {
+                    Ok(l1_contract_id) => l1_contract_id,
+                    Err(e) => {
+                        warn!(
+                            "Failed to load the L1 contract of {}: {:?}",
+                            l2_contract_id, &e
                         );
-                        (
-                            HttpResponseType::ServerError(
-                                response_metadata,
-                                format!(
-                                    "Failed to query unconfirmed microblock stream {}",
-                                    index_anchor_block_hash.to_hex()
-                                ),
-                            ),
-                            None,
-                        )
+                        None
                     }
-                };
-                response.send(http, fd).and_then(|_| Ok(stream_opt))
+                }
             }
+            _ => None,
+        };
+
+        let mut contract_call = None;
+        let mut estimated_fee = None;
+        if let (Some(oracle), Some((function_name, function_args))) = (
+            l1_oracle,
+            entry.make_claim_call(
+                l1_asset_contract.as_ref(),
+                withdrawal_root,
+                withdrawal_leaf_hash,
+                sibling_hashes,
+            ),
+        ) {
+            let subnet_contract = oracle.subnet_contract();
+            let function_args_hex = function_args
+                .iter()
+                .map(|arg| arg.serialize_to_hex())
+                .collect();
+            let payload = TransactionPayload::ContractCall(TransactionContractCall {
+                address: StacksAddress::from(subnet_contract.issuer.clone()),
+                contract_name: subnet_contract.name.clone(),
+                function_name: ClarityName::from(function_name),
+                function_args,
+            });
+            estimated_fee = oracle
+                .estimate_claim_fee(&payload)
+                .map_err(|e| {
+                    warn!("Failed to estimate the fee of a withdrawal claim";
+                          "l2_block_id" => %entry.index_block_hash,
+                          "withdrawal_id" => withdrawal_id,
+                          "error" => &e);
+                })
+                .ok();
+            contract_call = Some(WithdrawalClaimCall {
+                contract_id: subnet_contract.to_string(),
+                function_name: function_name.to_string(),
+                function_args: function_args_hex,
+                payload: to_hex(&payload.serialize_to_vec()),
+            });
         }
+
+        HttpResponseType::GetWithdrawalClaim(
+            response_metadata,
+            WithdrawalClaimResponse {
+                withdrawal: WithdrawalEntryResponse::from_entry(&entry),
+                proof,
+                l1_commit_height,
+                l1_confirmations,
+                claimable: root_committed == Some(true),
+                root_committed,
+                contract_call,
+                estimated_fee,
+            },
+        )
+        .send(http, fd)
+        .map(|_| ())
     }
 
-    /// Handle a GET unconfirmed transaction.
-    /// The response will be synchronously written to the fd.
-    fn handle_gettransaction_unconfirmed<W: Write>(
+    /// Handle a GET for a page of `sender`'s failed deposits in the fork ending at `tip`.  Fetches
+    /// one more than the page holds, to tell whether there is a next page.
+    fn handle_get_failed_deposits_by_sender<W: Write>(
         http: &mut StacksHttp,
         fd: &mut W,
         req: &HttpRequestType,
-        chainstate: &StacksChainState,
-        mempool: &MemPoolDB,
-        txid: &Txid,
+        chainstate: &mut StacksChainState,
+        tip: &StacksBlockId,
+        sender: &PrincipalData,
+        offset: u64,
+        limit: u64,
         canonical_stacks_tip_height: u64,
     ) -> Result<(), net_error> {
         let response_metadata =
             HttpResponseMetadata::from_http_request_type(req, Some(canonical_stacks_tip_height));
-
-        // present in the unconfirmed state?
-        if let Some(ref unconfirmed) = chainstate.unconfirmed_state.as_ref() {
-            if let Some((transaction, mblock_hash, seq)) =
-                unconfirmed.get_unconfirmed_transaction(txid)
-            {
-                let response = HttpResponseType::UnconfirmedTransaction(
+        let response = match chainstate.get_failed_deposits_by_sender(
+            tip,
+            sender,
+            offset,
+            limit.saturating_add(1),
+        ) {
+            Ok(mut failed_deposits) => {
+                let next_offset = if failed_deposits.len() as u64 > limit {
+                    failed_deposits.truncate(limit as usize);
+                    Some(offset.saturating_add(limit))
+                } else {
+                    None
+                };
+                HttpResponseType::GetFailedDepositsBySender(
                     response_metadata,
-                    UnconfirmedTransactionResponse {
-                        status: UnconfirmedTransactionStatus::Microblock {
-                            block_hash: mblock_hash,
-                            seq: seq,
-                        },
-                        tx: to_hex(&transaction.serialize_to_vec()),
+                    FailedDepositsResponse {
+                        failed_deposits: failed_deposits
+                            .iter()
+                            .map(FailedDepositResponse::from_failed_deposit)
+                            .collect(),
+                        next_offset,
                     },
-                );
-                return response.send(http, fd).map(|_| ());
+                )
             }
-        }
+            Err(e) => {
+                warn!("Failed to load failed deposits of {}: {:?}", sender, &e);
+                HttpResponseType::ServerError(
+                    response_metadata,
+                    format!("Failed to load failed deposits of {}", sender),
+                )
+            }
+        };
 
-        // present in the mempool?
-        if let Some(txinfo) = MemPoolDB::get_tx(mempool.conn(), txid)? {
-            let response = HttpResponseType::UnconfirmedTransaction(
+        response.send(http, fd).map(|_| ())
+    }
+
+    /// Handle a GET for a page of the transactions touching `principal` in the fork ending at
+    /// `tip`.  Only nodes which keep an account index can serve it.
+    fn handle_get_account_transactions<W: Write>(
+        http: &mut StacksHttp,
+        fd: &mut W,
+        req: &HttpRequestType,
+        chainstate: &mut StacksChainState,
+        tip: &StacksBlockId,
+        principal: &PrincipalData,
+        offset: u64,
+        limit: u64,
+        canonical_stacks_tip_height: u64,
+    ) -> Result<(), net_error> {
+        let response_metadata =
+            HttpResponseMetadata::from_http_request_type(req, Some(canonical_stacks_tip_height));
+        if !chainstate.has_account_index() {
+            let response = HttpResponseType::NotFound(
                 response_metadata,
-                UnconfirmedTransactionResponse {
-                    status: UnconfirmedTransactionStatus::Mempool,
-                    tx: to_hex(&txinfo.tx.serialize_to_vec()),
-                },
+                "This node does not keep an account index".to_string(),
             );
             return response.send(http, fd).map(|_| ());
         }
 
-        // not found
-        let response = HttpResponseType::NotFound(
-            response_metadata,
-            format!("No such unconfirmed transaction {}", txid),
-        );
-        return response.send(http, fd).map(|_| ());
+        let response = match chainstate.get_account_transactions(
+            tip,
+            principal,
+            offset,
+            limit.saturating_add(1),
+        ) {
+            Ok(mut account_txs) => {
+                let next_offset = if account_txs.len() as u64 > limit {
+                    account_txs.truncate(limit as usize);
+                    Some(offset.saturating_add(limit))
+                } else {
+                    None
+                };
+                HttpResponseType::GetAccountTransactions(
+                    response_metadata,
+                    AccountTransactionsResponse {
+                        transactions: account_txs
+                            .iter()
+                            .map(AccountTransactionResponse::from_account_transaction)
+                            .collect(),
+                        next_offset,
+                    },
+                )
+            }
+            Err(e) => {
+                warn!("Failed to load transactions of {}: {:?}", principal, &e);
+                HttpResponseType::ServerError(
+                    response_metadata,
+                    format!("Failed to load transactions of {}", principal),
+                )
+            }
+        };
+
+        response.send(http, fd).map(|_| ())
     }
 
-    /// Load up the canonical Stacks chain tip.  Note that this is subject to both burn chain block
-    /// Stacks block availability -- different nodes with different partial replicas of the Stacks chain state
-    /// will return different values here.
-    ///
-    /// # Warn
-    /// - There is a potential race condition. If this function is loading the latest unconfirmed
-    /// tip, that tip may get invalidated by the time it is used in `maybe_read_only_clarity_tx`,
-    /// which is used to load clarity state at a particular tip (which would lead to a 404 error).
-    /// If this race condition occurs frequently, we can modify `maybe_read_only_clarity_tx` to
-    /// re-load the unconfirmed chain tip. Refer to issue #2997.
-    ///
-    /// # Inputs
-    /// - `tip_req` is given by the HTTP request as the optional query parameter for the chain tip
-    /// hash.  It will be UseLatestAnchoredTip if there was no parameter given. If it is set to
-    /// `latest`, the parameter will be set to UseLatestUnconfirmedTip.
-    fn handle_load_stacks_chain_tip<W: Write>(
+    /// Handle a GET for the tree of L2 blocks committed in the last `burn_blocks` L1 blocks
+    fn handle_get_forks<W: Write>(
         http: &mut StacksHttp,
         fd: &mut W,
         req: &HttpRequestType,
-        tip_req: &TipRequest,
         sortdb: &SortitionDB,
-        chainstate: &mut StacksChainState,
+        chainstate: &StacksChainState,
+        burn_blocks: u64,
         canonical_stacks_tip_height: u64,
-    ) -> Result<Option<StacksBlockId>, net_error> {
-        match tip_req {
-            TipRequest::UseLatestUnconfirmedTip => {
-                let unconfirmed_chain_tip_opt = match &mut chainstate.unconfirmed_state {
-                    Some(unconfirmed_state) => {
-                        match unconfirmed_state.get_unconfirmed_state_if_exists() {
-                            Ok(res) => res,
-                            Err(msg) => {
-                                let response_metadata =
-                                    HttpResponseMetadata::from_http_request_type(
-                                        req,
-                                        Some(canonical_stacks_tip_height),
-                                    );
-                                let response = HttpResponseType::NotFound(response_metadata, msg);
-                                return response.send(http, fd).and_then(|_| Ok(None));
-                            }
-                        }
-                    }
-                    None => None,
-                };
-
-                if let Some(unconfirmed_chain_tip) = unconfirmed_chain_tip_opt {
-                    Ok(Some(unconfirmed_chain_tip))
-                } else {
-                    match chainstate.get_stacks_chain_tip(sortdb)? {
-                        Some(tip) => Ok(Some(StacksBlockHeader::make_index_block_hash(
-                            &tip.consensus_hash,
-                            &tip.anchored_block_hash,
-                        ))),
-                        None => {
-                            let response_metadata = HttpResponseMetadata::from_http_request_type(
-                                req,
-                                Some(canonical_stacks_tip_height),
-                            );
-                            warn!("Failed to load Stacks chain tip");
-                            let response = HttpResponseType::NotFound(
-                                response_metadata,
-                                format!("Failed to load Stacks chain tip"),
-                            );
-                            response.send(http, fd).and_then(|_| Ok(None))
-                        }
-                    }
-                }
-            }
-            TipRequest::SpecificTip(tip) => Ok(Some(*tip).clone()),
-            TipRequest::UseLatestAnchoredTip => match chainstate.get_stacks_chain_tip(sortdb)? {
-                Some(tip) => Ok(Some(StacksBlockHeader::make_index_block_hash(
-                    &tip.consensus_hash,
-                    &tip.anchored_block_hash,
-                ))),
-                None => {
-                    let response_metadata = HttpResponseMetadata::from_http_request_type(
-                        req,
-                        Some(canonical_stacks_tip_height),
-                    );
-                    warn!("Failed to load Stacks chain tip");
-                    let response = HttpResponseType::ServerError(
+    ) -> Result<(), net_error> {
+        let response_metadata =
+            HttpResponseMetadata::from_http_request_type(req, Some(canonical_stacks_tip_height));
+        let response =
+            match StacksChainState::get_fork_tree(chainstate.db(), sortdb.conn(), burn_blocks) {
+                Ok(fork_tree) => HttpResponseType::GetForks(response_metadata, fork_tree),
+                Err(e) => {
+                    warn!("Failed to load fork tree: {:?}", &e);
+                    HttpResponseType::ServerError(
                         response_metadata,
-                        format!("Failed to load Stacks chain tip"),
-                    );
-                    response.send(http, fd).and_then(|_| Ok(None))
+                        "Failed to load fork tree".to_string(),
+                    )
                 }
-            },
-        }
+            };
+
+        response.send(http, fd).map(|_| ())
     }
 
-    fn handle_load_stacks_chain_tip_hashes<W: Write>(
+    /// Handle a GET for the execution cost of each contract over the canonical fork's last
+    /// `blocks` blocks
+    fn handle_get_contract_costs<W: Write>(
         http: &mut StacksHttp,
         fd: &mut W,
         req: &HttpRequestType,
-        tip: StacksBlockId,
+        sortdb: &SortitionDB,
         chainstate: &StacksChainState,
+        blocks: u64,
         canonical_stacks_tip_height: u64,
-    ) -> Result<Option<(ConsensusHash, BlockHeaderHash)>, net_error> {
-        match chainstate.get_block_header_hashes(&tip)? {
-            Some((ch, bl)) => {
-                return Ok(Some((ch, bl)));
-            }
-            None => {}
-        }
+    ) -> Result<(), net_error> {
         let response_metadata =
             HttpResponseMetadata::from_http_request_type(req, Some(canonical_stacks_tip_height));
-        warn!("Failed to load Stacks chain tip");
-        let response = HttpResponseType::ServerError(
-            response_metadata,
-            format!("Failed to load Stacks chain tip"),
-        );
-        response.send(http, fd).and_then(|_| Ok(None))
+        let response = match chainstate.get_recent_contract_costs(sortdb, blocks) {
+            Ok(contract_costs) => {
+                HttpResponseType::GetContractCosts(response_metadata, contract_costs)
+            }
+            Err(e) => {
+                warn!("Failed to load contract costs: {:?}", &e);
+                HttpResponseType::ServerError(
+                    response_metadata,
+                    "Failed to load contract costs".to_string(),
+                )
+            }
+        };
+
+        response.send(http, fd).map(|_| ())
     }
 
-    fn handle_post_fee_rate_estimate<W: Write>(
+    /// Handle a GET for the L1 commits of the `count` canonical L2 blocks starting at height
+    /// `l2_height`
+    fn handle_get_block_commits<W: Write>(
         http: &mut StacksHttp,
         fd: &mut W,
         req: &HttpRequestType,
-        handler_args: &RPCHandlerArgs,
         sortdb: &SortitionDB,
-        tx: &TransactionPayload,
-        estimated_len: u64,
+        chainstate: &StacksChainState,
+        l2_height: u64,
+        count: u64,
         canonical_stacks_tip_height: u64,
     ) -> Result<(), net_error> {
         let response_metadata =
             HttpResponseMetadata::from_http_request_type(req, Some(canonical_stacks_tip_height));
-        let tip = SortitionDB::get_canonical_burn_chain_tip(sortdb.conn())?;
-        let stacks_epoch = SortitionDB::get_stacks_epoch(sortdb.conn(), tip.block_height)?
-                .ok_or_else(|| {
-                    warn!(
-                        "Failed to get fee rate estimate because could not load Stacks epoch for canonical burn height = {}",
-                        tip.block_height
-                    );
-                    net_error::ChainstateError("Could not load Stacks epoch for canonical burn height".into())
-                })?;
-        if let Some((cost_estimator, fee_estimator, metric)) = handler_args.get_estimators_ref() {
-            let estimated_cost = match cost_estimator.estimate_cost(tx, &stacks_epoch.epoch_id) {
-                Ok(x) => x,
-                Err(e) => {
-                    debug!(
-                        "Estimator RPC endpoint failed to estimate tx: {}",
-                        tx.name()
-                    );
-                    return HttpResponseType::BadRequestJSON(response_metadata, e.into_json())
-                        .send(http, fd);
-                }
-            };
-
-            let scalar_cost =
-                metric.from_cost_and_len(&estimated_cost, &stacks_epoch.block_limit, estimated_len);
-            let fee_rates = match fee_estimator.get_rate_estimates() {
-                Ok(x) => x,
-                Err(e) => {
-                    debug!(
-                        "Estimator RPC endpoint failed to estimate fees for tx: {}",
-                        tx.name()
-                    );
-                    return HttpResponseType::BadRequestJSON(response_metadata, e.into_json())
-                        .send(http, fd);
-                }
-            };
-
-            let mut estimations = RPCFeeEstimate::estimate_fees(scalar_cost, fee_rates).to_vec();
-
-            let minimum_fee = estimated_len * MINIMUM_TX_FEE_RATE_PER_BYTE;
-
-            for estimate in estimations.iter_mut() {
-                if estimate.fee < minimum_fee {
-                    estimate.fee = minimum_fee;
-                }
+        let response = match chainstate.get_block_commits(sortdb, l2_height, count) {
+            Ok(block_commits) => {
+                HttpResponseType::GetBlockCommits(response_metadata, block_commits)
+            }
+            Err(e) => {
+                warn!("Failed to load block commits: {:?}", &e);
+                HttpResponseType::ServerError(
+                    response_metadata,
+                    "Failed to load block commits".to_string(),
+                )
             }
+        };
 
-            let response = HttpResponseType::TransactionFeeEstimation(
-                response_metadata,
-                RPCFeeEstimateResponse {
-                    estimated_cost,
-                    estimations,
-                    estimated_cost_scalar: scalar_cost,
-                    cost_scalar_change_by_byte: metric.change_per_byte(),
-                },
-            );
-            response.send(http, fd)
-        } else {
-            debug!("Fee and cost estimation not configured on this stacks node");
-            let response = HttpResponseType::BadRequestJSON(
-                response_metadata,
-                json!({
-                    "error": "Fee and Cost Estimation not configured on this Stacks node",
-                    "reason": "CostEstimationDisabled",
-                }),
-            );
-            response.send(http, fd)
-        }
+        response.send(http, fd).map(|_| ())
     }
 
-    /// Handle a transaction.  Directly submit it to the mempool so the client can see any
-    /// rejection reasons up-front (different from how the peer network handles it).  Indicate
-    /// whether or not the transaction was accepted (and thus needs to be forwarded) in the return
-    /// value.
-    fn handle_post_transaction<W: Write>(
+    /// Handle a GET for what each miner earned from the canonical L2 blocks from `from_height`
+    /// up to and including `to_height`, and what their commits cost if `commit_fees` knows
+    fn handle_get_rewards_report<W: Write>(
         http: &mut StacksHttp,
         fd: &mut W,
         req: &HttpRequestType,
-        chainstate: &mut StacksChainState,
         sortdb: &SortitionDB,
-        consensus_hash: ConsensusHash,
-        block_hash: BlockHeaderHash,
-        mempool: &mut MemPoolDB,
-        tx: StacksTransaction,
-        atlasdb: &mut AtlasDB,
-        attachment: Option<Attachment>,
-        event_observer: Option<&dyn MemPoolEventDispatcher>,
+        chainstate: &StacksChainState,
+        from_height: u64,
+        to_height: u64,
+        commit_fees: Option<&dyn CommitFeeLedger>,
         canonical_stacks_tip_height: u64,
-    ) -> Result<bool, net_error> {
-        let txid = tx.txid();
+    ) -> Result<(), net_error> {
         let response_metadata =
             HttpResponseMetadata::from_http_request_type(req, Some(canonical_stacks_tip_height));
-        let (response, accepted) = if mempool.has_tx(&txid) {
-            debug!("Mempool already has POSTed transaction {}", &txid);
-            (
-                HttpResponseType::TransactionID(response_metadata, txid),
-                false,
-            )
-        } else {
-            let tip = SortitionDB::get_canonical_burn_chain_tip(sortdb.conn())?;
-            let stacks_epoch = sortdb
-                .index_conn()
-                .get_stacks_epoch(tip.block_height as u32)
-                .ok_or_else(|| {
-                    warn!(
-                        "Failed to store transaction because could not load Stacks epoch for canonical burn height = {}",
-                        tip.block_height
-                    );
-                    net_error::ChainstateError("Could not load Stacks epoch for canonical burn height".into())
-                })?;
-
-            match mempool.submit(
-                chainstate,
-                &consensus_hash,
-                &block_hash,
-                &tx,
-                event_observer,
-                &stacks_epoch.block_limit,
-                &stacks_epoch.epoch_id,
-            ) {
-                Ok(_) => {
-                    debug!("Mempool accepted POSTed transaction {}", &txid);
-                    (
-                        HttpResponseType::TransactionID(response_metadata, txid),
-                        true,
-                    )
-                }
+        let response =
+            match chainstate.get_rewards_report(sortdb, from_height, to_height, commit_fees) {
+                Ok(report) => HttpResponseType::GetRewardsReport(response_metadata, report),
                 Err(e) => {
-                    debug!("Mempool rejected POSTed transaction {}: {:?}", &txid, &e);
-                    (
-                        HttpResponseType::BadRequestJSON(response_metadata, e.into_json(&txid)),
-                        false,
+                    warn!("Failed to make rewards report: {:?}", &e);
+                    HttpResponseType::ServerError(
+                        response_metadata,
+                        "Failed to make rewards report".to_string(),
                     )
                 }
-            }
-        };
-
-        if let Some(ref attachment) = attachment {
-            if let TransactionPayload::ContractCall(ref contract_call) = tx.payload {
-                if atlasdb
-                    .should_keep_attachment(&contract_call.to_clarity_contract_id(), &attachment)
-                {
-                    atlasdb
-                        .insert_uninstantiated_attachment(attachment)
-                        .map_err(|e| net_error::DBError(e))?;
-                }
-            }
-        }
+            };
 
-        response.send(http, fd).and_then(|_| Ok(accepted))
+        response.send(http, fd).map(|_| ())
     }
 
-    /// Handle a block.  Directly submit a Stacks block to this node's chain state.
-    /// Indicate whether or not the block was accepted (i.e. it was new, and valid)
-    fn handle_post_block<W: Write>(
+    /// Handle a GET for the payloads the event dispatcher sent about the canonical L2 blocks
+    /// from `from_height` up to and including `to_height`, to an observer subscribed with
+    /// `keys`.  Each block is re-executed, so the range is kept short by the request parser, and
+    /// the request rate by the `Replay` rate limit.
+    fn handle_get_event_replay<W: Write>(
         http: &mut StacksHttp,
         fd: &mut W,
         req: &HttpRequestType,
         sortdb: &SortitionDB,
         chainstate: &mut StacksChainState,
-        consensus_hash: &ConsensusHash,
-        block: &StacksBlock,
+        from_height: u64,
+        to_height: u64,
+        keys: &[String],
+        formatter: Option<&dyn EventReplayFormatter>,
         canonical_stacks_tip_height: u64,
-    ) -> Result<bool, net_error> {
+    ) -> Result<(), net_error> {
+        // no content length, so the payloads are sent chunk-encoded
         let response_metadata =
             HttpResponseMetadata::from_http_request_type(req, Some(canonical_stacks_tip_height));
-        // is this a consensus hash we recognize?
-        let (response, accepted) =
-            match SortitionDB::get_sortition_id_by_consensus(&sortdb.conn(), consensus_hash) {
-                Ok(Some(_)) => {
-                    // we recognize this consensus hash
-                    let ic = sortdb.index_conn();
-                    match Relayer::process_new_anchored_block(
-                        &ic,
-                        chainstate,
-                        consensus_hash,
-                        block,
-                        0,
-                    ) {
-                        Ok(true) => {
-                            debug!(
-                                "Accepted Stacks block {}/{}",
-                                consensus_hash,
-                                &block.block_hash()
-                            );
-                            (
-                                HttpResponseType::StacksBlockAccepted(
-                                    response_metadata,
-                                    StacksBlockHeader::make_index_block_hash(
-                                        consensus_hash,
-                                        &block.block_hash(),
-                                    ),
-                                    true,
-                                ),
-                                true,
-                            )
-                        }
-                        Ok(false) => {
-                            debug!(
-                                "Did not accept Stacks block {}/{}",
-                                consensus_hash,
-                                &block.block_hash()
-                            );
-                            (
-                                HttpResponseType::StacksBlockAccepted(
-                                    response_metadata,
-                                    StacksBlockHeader::make_index_block_hash(
-                                        consensus_hash,
-                                        &block.block_hash(),
-                                    ),
-                                    false,
-                                ),
-                                false,
-                            )
-                        }
-                        Err(e) => {
-                            error!(
-                                "Failed to process anchored block {}/{}: {:?}",
-                                consensus_hash,
-                                &block.block_hash(),
-                                &e
-                            );
-                            (
-                                HttpResponseType::ServerError(
-                                    response_metadata,
-                                    format!(
-                                        "Failed to process anchored block {}/{}: {:?}",
-                                        consensus_hash,
-                                        &block.block_hash(),
-                                        &e
-                                    ),
-                                ),
-                                false,
-                            )
-                        }
-                    }
-                }
-                Ok(None) => {
-                    debug!(
-                        "Unrecognized consensus hash {} for block {}",
-                        consensus_hash,
-                        &block.block_hash()
-                    );
-                    (
-                        HttpResponseType::NotFound(
-                            response_metadata,
-                            format!("No such consensus hash '{}'", consensus_hash),
-                        ),
-                        false,
-                    )
-                }
-                Err(e) => {
-                    error!(
-                        "Failed to query sortition ID by consensus '{}'",
-                        consensus_hash
-                    );
-                    (
-                        HttpResponseType::ServerError(
-                            response_metadata,
-                            format!(
-                                "Failed to query sortition ID for consensus hash '{}': {:?}",
-                                consensus_hash, &e
-                            ),
-                        ),
-                        false,
-                    )
-                }
-            };
-        response.send(http, fd).and_then(|_| Ok(accepted))
+        let formatter = match formatter {
+            Some(formatter) => formatter,
+            None => {
+                let response = HttpResponseType::BadRequestJSON(
+                    response_metadata,
+                    json!({ "error": "This node does not replay events" }),
+                );
+                return response.send(http, fd).map(|_| ());
+            }
+        };
+        if let Err(msg) = formatter.check_keys(keys) {
+            let response = HttpResponseType::BadRequestJSON(
+                response_metadata,
+                json!({ "error": format!("Invalid event keys: {}", msg) }),
+            );
+            return response.send(http, fd).map(|_| ());
+        }
+
+        let response = match chainstate
+            .replay_events(sortdb, from_height, to_height)
+            .map_err(|e| format!("{:?}", &e))
+            .and_then(|(canonical_stacks_tip, to_height, events)| {
+                let payloads = formatter.make_payloads(&events, keys)?;
+                Ok(EventReplay {
+                    canonical_stacks_tip,
+                    from_height,
+                    to_height,
+                    payloads,
+                })
+            }) {
+            Ok(replay) => HttpResponseType::GetEventReplay(response_metadata, replay),
+            Err(e) => {
+                warn!("Failed to replay events: {}", &e);
+                HttpResponseType::ServerError(
+                    response_metadata,
+                    "Failed to replay events".to_string(),
+                )
+            }
+        };
+
+        response.send(http, fd).map(|_| ())
     }
 
-    /// Handle a microblock.  Directly submit it to the microblock store so the client can see any
-    /// rejection reasons up-front (different from how the peer network handles it).  Indicate
-    /// whether or not the microblock was accepted (and thus needs to be forwarded) in the return
-    /// value.
-    fn handle_post_microblock<W: Write>(
+    /// Handle a GET for the microblock stream built on the anchored block `parent_block_id`.
+    /// A confirmed stream's receipts come from re-executing the block which confirmed it, so
+    /// the request rate is kept down by the `Replay` rate limit.
+    fn handle_get_microblock_stream<W: Write>(
         http: &mut StacksHttp,
         fd: &mut W,
         req: &HttpRequestType,
-        consensus_hash: &ConsensusHash,
-        block_hash: &BlockHeaderHash,
+        sortdb: &SortitionDB,
         chainstate: &mut StacksChainState,
-        microblock: &StacksMicroblock,
+        parent_block_id: &StacksBlockId,
         canonical_stacks_tip_height: u64,
-    ) -> Result<bool, net_error> {
+    ) -> Result<(), net_error> {
         let response_metadata =
             HttpResponseMetadata::from_http_request_type(req, Some(canonical_stacks_tip_height));
-        let (response, accepted) =
-            match chainstate.preprocess_streamed_microblock(consensus_hash, block_hash, microblock)
-            {
-                Ok(accepted) => {
-                    if accepted {
-                        debug!(
-                            "Accepted uploaded microblock {}/{}-{}",
-                            &consensus_hash,
-                            &block_hash,
-                            &microblock.block_hash()
-                        );
-                    } else {
-                        debug!(
-                            "Did not accept microblock {}/{}-{}",
-                            &consensus_hash,
-                            &block_hash,
-                            &microblock.block_hash()
-                        );
-                    }
-
-                    (
-                        HttpResponseType::MicroblockHash(
-                            response_metadata,
-                            microblock.block_hash(),
-                        ),
-                        accepted,
-                    )
-                }
-                Err(e) => (
-                    HttpResponseType::BadRequestJSON(response_metadata, e.into_json()),
-                    false,
-                ),
-            };
+        let response = match chainstate.get_microblock_stream(sortdb, parent_block_id) {
+            Ok(Some(stream)) => HttpResponseType::GetMicroblockStream(
+                response_metadata,
+                MicroblockStreamResponse::from_stream(&stream),
+            ),
+            Ok(None) => HttpResponseType::NotFound(
+                response_metadata,
+                format!("No such anchored block {}", parent_block_id),
+            ),
+            Err(e) => {
+                warn!(
+                    "Failed to load the microblock stream of {}: {:?}",
+                    parent_block_id, &e
+                );
+                HttpResponseType::ServerError(
+                    response_metadata,
+                    "Failed to load microblock stream".to_string(),
+                )
+            }
+        };
 
-        response.send(http, fd).and_then(|_| Ok(accepted))
+        response.send(http, fd).map(|_| ())
     }
 
-    /// Handle a request for mempool transactions in bulk
-    fn handle_mempool_query<W: Write>(
+    /// Handle a GET for the burnchain operations recognized at L1 height `burn_height` in the
+    /// canonical burnchain fork ending at `burn_tip`
+    fn handle_get_burn_ops<W: Write>(
         http: &mut StacksHttp,
         fd: &mut W,
         req: &HttpRequestType,
         sortdb: &SortitionDB,
-        chainstate: &StacksChainState,
-        query: MemPoolSyncData,
-        max_txs: u64,
+        burnchain: &Burnchain,
+        burn_tip: &SortitionId,
+        burn_tip_height: u64,
+        burn_height: u64,
         canonical_stacks_tip_height: u64,
-        page_id: Option<Txid>,
-    ) -> Result<StreamCursor, net_error> {
+    ) -> Result<(), net_error> {
         let response_metadata =
             HttpResponseMetadata::from_http_request_type(req, Some(canonical_stacks_tip_height));
-        let response = HttpResponseType::MemPoolTxStream(response_metadata);
-        let height = chainstate
-            .get_stacks_chain_tip(sortdb)?
-            .map(|blk| blk.height)
-            .unwrap_or(0);
+        if burn_height > burn_tip_height {
+            let response = HttpResponseType::NotFound(
+                response_metadata,
+                format!("No burnchain block at height {}", burn_height),
+            );
+            return response.send(http, fd).map(|_| ());
+        }
 
-        debug!(
-            "Begin mempool query";
-            "page_id" => %page_id.map(|txid| format!("{}", &txid)).unwrap_or("(none".to_string()),
-            "block_height" => height,
-            "max_txs" => max_txs
-        );
+        let snapshot_res =
+            SortitionDB::get_ancestor_snapshot(&sortdb.index_conn(), burn_height, burn_tip);
+        let response = match snapshot_res {
+            Ok(Some(snapshot)) => {
+                let block_res = BurnchainDB::open(&burnchain.get_burnchaindb_path(), false)
+                    .and_then(|db| db.get_burnchain_block(&snapshot.burn_header_hash));
+                match block_res {
+                    Ok(block_data) => HttpResponseType::GetBurnOps(
+                        response_metadata,
+                        BurnOpsResponse {
+                            burn_block_height: snapshot.block_height,
+                            burn_header_hash: format!("{}", &snapshot.burn_header_hash),
+                            ops: block_data.ops,
+                        },
+                    ),
+                    Err(e) => {
+                        // the first sortition has no burnchain block behind it
+                        debug!(
+                            "No burnchain block {} at height {}: {:?}",
+                            &snapshot.burn_header_hash, burn_height, &e
+                        );
+                        HttpResponseType::NotFound(
+                            response_metadata,
+                            format!("No burnchain operations at height {}", burn_height),
+                        )
+                    }
+                }
+            }
+            Ok(None) => HttpResponseType::NotFound(
+                response_metadata,
+                format!("No burnchain block at height {}", burn_height),
+            ),
+            Err(e) => {
+                warn!(
+                    "Failed to load burnchain block at height {}: {:?}",
+                    burn_height, &e
+                );
+                HttpResponseType::ServerError(
+                    response_metadata,
+                    format!("Failed to load burnchain block at height {}", burn_height),
+                )
+            }
+        };
 
-        let stream = StreamCursor::new_tx_stream(query, max_txs, height, page_id);
-        response.send(http, fd).and_then(|_| Ok(stream))
+        response.send(http, fd).map(|_| ())
     }
 
-    /// Handle an external HTTP request.
-    /// Some requests, such as those for blocks, will create new reply streams.  This method adds
-    /// those new streams into the `reply_streams` set.
-    /// Returns a StacksMessageType option -- it's Some(...) if we need to forward a message to the
-    /// peer network (like a transaction or a block or microblock)
-    pub fn handle_request(
-        &mut self,
-        req: HttpRequestType,
-        network: &mut PeerNetwork,
+    /// Handle a POST to simulate a transaction: execute it the way the miner would in a new block
+    /// on top of the given chain tip, without persisting anything, and report its result, events
+    /// and execution cost.
+    fn handle_post_simulate_transaction<W: Write>(
+        http: &mut StacksHttp,
+        fd: &mut W,
+        req: &HttpRequestType,
         sortdb: &SortitionDB,
         chainstate: &mut StacksChainState,
-        mempool: &mut MemPoolDB,
-        handler_opts: &RPCHandlerArgs,
-    ) -> Result<Option<StacksMessageType>, net_error> {
-        let mut reply = self.connection.make_relay_handle(self.conn_id)?;
-        let keep_alive = req.metadata().keep_alive;
-        let mut ret = None;
-
-        let stream_opt = match req {
-            HttpRequestType::GetInfo(ref _md) => {
-                ConversationHttp::handle_getinfo(
-                    &mut self.connection.protocol,
-                    &mut reply,
-                    &req,
-                    network,
-                    chainstate,
-                    handler_opts,
-                    network.burnchain_tip.canonical_stacks_tip_height,
-                )?;
-                None
+        tip: &StacksBlockId,
+        tx: &StacksTransaction,
+        canonical_stacks_tip_height: u64,
+    ) -> Result<(), net_error> {
+        let response_metadata =
+            HttpResponseMetadata::from_http_request_type(req, Some(canonical_stacks_tip_height));
+        // only anchored blocks can be built on, so an unconfirmed microblock tip isn't found here
+        let parent_header = match StacksChainState::get_stacks_block_header_info_by_index_block_hash(
+            chainstate.db(),
+            tip,
+        ) {
+            Ok(Some(header)) => header,
+            Ok(None) | Err(_) => {
+                let response =
+                    HttpResponseType::NotFound(response_metadata, "Chain tip not found".into());
+                return response.send(http, fd).map(|_| ());
             }
-            HttpRequestType::GetNeighbors(ref _md) => {
-                ConversationHttp::handle_getneighbors(
-                    &mut self.connection.protocol,
-                    &mut reply,
-                    &req,
-                    network,
-                    network.burnchain_tip.canonical_stacks_tip_height,
-                )?;
-                None
+        };
+
+        let response = match StacksBlockBuilder::simulate_tx(
+            chainstate,
+            &sortdb.index_conn(),
+            &parent_header,
+            tx,
+        ) {
+            Ok(outcome) => HttpResponseType::SimulateTransaction(
+                response_metadata,
+                TransactionSimulationResponse::from_outcome(tip, tx, outcome),
+            ),
+            Err(e) => {
+                warn!(
+                    "Failed to set up block to simulate tx {}: {:?}",
+                    &tx.txid(),
+                    &e
+                );
+                HttpResponseType::ServerError(
+                    response_metadata,
+                    format!("Failed to set up block to simulate in: {}", &e),
+                )
             }
-            HttpRequestType::GetHeaders(ref _md, ref quantity, ref tip_req) => {
-                if let Some(tip) = ConversationHttp::handle_load_stacks_chain_tip(
-                    &mut self.connection.protocol,
-                    &mut reply,
-                    &req,
-                    tip_req,
-                    sortdb,
-                    chainstate,
-                    network.burnchain_tip.canonical_stacks_tip_height,
-                )? {
-                    ConversationHttp::handle_getheaders(
-                        &mut self.connection.protocol,
-                        &mut reply,
-                        &req,
-                        &tip,
-                        *quantity,
-                        chainstate,
-                        network.burnchain_tip.canonical_stacks_tip_height,
-                    )?
-                } else {
-                    None
+        };
+
+        response.send(http, fd).map(|_| ())
+    }
+
+    /// Handle a GET on a smart contract's data var, given the current chain tip.  Optionally
+    /// supplies a MARF proof for the value.
+    fn handle_get_data_var<W: Write>(
+        http: &mut StacksHttp,
+        fd: &mut W,
+        req: &HttpRequestType,
+        sortdb: &SortitionDB,
+        chainstate: &mut StacksChainState,
+        tip: &StacksBlockId,
+        contract_addr: &StacksAddress,
+        contract_name: &ContractName,
+        var_name: &ClarityName,
+        with_proof: bool,
+        canonical_stacks_tip_height: u64,
+    ) -> Result<(), net_error> {
+        let response_metadata =
+            HttpResponseMetadata::from_http_request_type(req, Some(canonical_stacks_tip_height));
+        let contract_identifier =
+            QualifiedContractIdentifier::new(contract_addr.clone().into(), contract_name.clone());
+
+        let response =
+            match chainstate.maybe_read_only_clarity_tx(&sortdb.index_conn(), tip, |clarity_tx| {
+                clarity_tx.with_clarity_db_readonly(|clarity_db| {
+                    let key = ClarityDatabase::make_key_for_trip(
+                        &contract_identifier,
+                        StoreType::Variable,
+                        var_name,
+                    );
+
+                    let (value_hex, marf_proof): (String, _) = if with_proof {
+                        clarity_db
+                            .get_with_proof(&key)
+                            .map(|(a, b)| (a, Some(format!("0x{}", to_hex(&b)))))?
+                    } else {
+                        clarity_db.get(&key).map(|a| (a, None))?
+                    };
+
+                    let data = format!("0x{}", value_hex);
+                    Some(DataVarResponse { data, marf_proof })
+                })
+            }) {
+                Ok(Some(Some(data))) => HttpResponseType::GetDataVar(response_metadata, data),
+                Ok(Some(None)) => {
+                    HttpResponseType::NotFound(response_metadata, "Data var not found".into())
                 }
-            }
-            HttpRequestType::GetBlock(ref _md, ref index_block_hash) => {
-                ConversationHttp::handle_getblock(
-                    &mut self.connection.protocol,
-                    &mut reply,
-                    &req,
-                    index_block_hash,
-                    chainstate,
-                    network.burnchain_tip.canonical_stacks_tip_height,
-                )?
-            }
-            HttpRequestType::GetMicroblocksIndexed(ref _md, ref index_head_hash) => {
-                ConversationHttp::handle_getmicroblocks_indexed(
-                    &mut self.connection.protocol,
-                    &mut reply,
-                    &req,
-                    index_head_hash,
-                    chainstate,
-                    network.burnchain_tip.canonical_stacks_tip_height,
-                )?
-            }
-            HttpRequestType::GetMicroblocksConfirmed(ref _md, ref anchor_index_block_hash) => {
-                ConversationHttp::handle_getmicroblocks_confirmed(
-                    &mut self.connection.protocol,
-                    &mut reply,
-                    &req,
-                    anchor_index_block_hash,
-                    chainstate,
-                    network.burnchain_tip.canonical_stacks_tip_height,
-                )?
-            }
-            HttpRequestType::GetMicroblocksUnconfirmed(
-                ref _md,
-                ref index_anchor_block_hash,
-                ref min_seq,
-            ) => ConversationHttp::handle_getmicroblocks_unconfirmed(
-                &mut self.connection.protocol,
-                &mut reply,
-                &req,
-                index_anchor_block_hash,
-                *min_seq,
-                chainstate,
-                network.burnchain_tip.canonical_stacks_tip_height,
-            )?,
-            HttpRequestType::GetTransactionUnconfirmed(ref _md, ref txid) => {
-                ConversationHttp::handle_gettransaction_unconfirmed(
-                    &mut self.connection.protocol,
-                    &mut reply,
-                    &req,
-                    chainstate,
-                    mempool,
-                    txid,
-                    network.burnchain_tip.canonical_stacks_tip_height,
-                )?;
-                None
-            }
-            HttpRequestType::GetAccount(ref _md, ref principal, ref tip_req, ref with_proof) => {
-                if let Some(tip) = ConversationHttp::handle_load_stacks_chain_tip(
-                    &mut self.connection.protocol,
-                    &mut reply,
-                    &req,
-                    tip_req,
-                    sortdb,
-                    chainstate,
-                    network.burnchain_tip.canonical_stacks_tip_height,
-                )? {
-                    ConversationHttp::handle_get_account_entry(
-                        &mut self.connection.protocol,
-                        &mut reply,
-                        &req,
-                        sortdb,
-                        chainstate,
-                        &tip,
-                        principal,
-                        *with_proof,
-                        network.burnchain_tip.canonical_stacks_tip_height,
-                    )?;
+                Ok(None) | Err(_) => {
+                    HttpResponseType::NotFound(response_metadata, "Chain tip not found".into())
                 }
-                None
-            }
-            HttpRequestType::GetDataVar(
-                ref _md,
-                ref contract_addr,
-                ref contract_name,
-                ref var_name,
-                ref tip_req,
-                ref with_proof,
-            ) => {
-                if let Some(tip) = ConversationHttp::handle_load_stacks_chain_tip(
-                    &mut self.connection.protocol,
-                    &mut reply,
-                    &req,
-                    tip_req,
-                    sortdb,
-                    chainstate,
-                    network.burnchain_tip.canonical_stacks_tip_height,
-                )? {
-                    ConversationHttp::handle_get_data_var(
-                        &mut self.connection.protocol,
-                        &mut reply,
-                        &req,
-                        sortdb,
-                        chainstate,
-                        &tip,
-                        contract_addr,
-                        contract_name,
-                        var_name,
-                        *with_proof,
-                        network.burnchain_tip.canonical_stacks_tip_height,
-                    )?;
-                }
-                None
-            }
-            HttpRequestType::GetMapEntry(
-                ref _md,
-                ref contract_addr,
-                ref contract_name,
-                ref map_name,
-                ref key,
-                ref tip_req,
-                ref with_proof,
-            ) => {
-                if let Some(tip) = ConversationHttp::handle_load_stacks_chain_tip(
-                    &mut self.connection.protocol,
-                    &mut reply,
-                    &req,
-                    tip_req,
-                    sortdb,
-                    chainstate,
-                    network.burnchain_tip.canonical_stacks_tip_height,
-                )? {
-                    ConversationHttp::handle_get_map_entry(
-                        &mut self.connection.protocol,
-                        &mut reply,
-                        &req,
-                        sortdb,
-                        chainstate,
-                        &tip,
-                        contract_addr,
-                        contract_name,
+            };
+
+        response.send(http, fd).map(|_| ())
+    }
+
+    /// Handle a GET on a smart contract's data map, given the current chain tip.  Optionally
+    /// supplies a MARF proof for the value.
+    fn handle_get_map_entry<W: Write>(
+        http: &mut StacksHttp,
+        fd: &mut W,
+        req: &HttpRequestType,
+        sortdb: &SortitionDB,
+        chainstate: &mut StacksChainState,
+        tip: &StacksBlockId,
+        contract_addr: &StacksAddress,
+        contract_name: &ContractName,
+        map_name: &ClarityName,
+        key: &Value,
+        with_proof: bool,
+        canonical_stacks_tip_height: u64,
+    ) -> Result<(), net_error> {
+        let response_metadata =
+            HttpResponseMetadata::from_http_request_type(req, Some(canonical_stacks_tip_height));
+        let contract_identifier =
+            QualifiedContractIdentifier::new(contract_addr.clone().into(), contract_name.clone());
+
+        let response =
+            match chainstate.maybe_read_only_clarity_tx(&sortdb.index_conn(), tip, |clarity_tx| {
+                clarity_tx.with_clarity_db_readonly(|clarity_db| {
+                    let key = ClarityDatabase::make_key_for_data_map_entry(
+                        &contract_identifier,
                         map_name,
                         key,
-                        *with_proof,
-                        network.burnchain_tip.canonical_stacks_tip_height,
-                    )?;
+                    );
+                    let (value_hex, marf_proof): (String, _) = if with_proof {
+                        clarity_db
+                            .get_with_proof(&key)
+                            .map(|(a, b)| (a, Some(format!("0x{}", to_hex(&b)))))
+                            .unwrap_or_else(|| {
+                                test_debug!("No value for '{}' in {}", &key, tip);
+                                (Value::none().serialize_to_hex(), Some("".into()))
+                            })
+                    } else {
+                        clarity_db.get(&key).map(|a| (a, None)).unwrap_or_else(|| {
+                            test_debug!("No value for '{}' in {}", &key, tip);
+                            (Value::none().serialize_to_hex(), None)
+                        })
+                    };
+
+                    let data = format!("0x{}", value_hex);
+                    MapEntryResponse { data, marf_proof }
+                })
+            }) {
+                Ok(Some(data)) => HttpResponseType::GetMapEntry(response_metadata, data),
+                Ok(None) | Err(_) => {
+                    HttpResponseType::NotFound(response_metadata, "Chain tip not found".into())
                 }
-                None
-            }
-            HttpRequestType::GetTransferCost(ref _md) => {
-                ConversationHttp::handle_token_transfer_cost(
-                    &mut self.connection.protocol,
-                    &mut reply,
-                    &req,
-                    network.burnchain_tip.canonical_stacks_tip_height,
-                )?;
-                None
-            }
-            HttpRequestType::GetContractABI(
-                ref _md,
-                ref contract_addr,
-                ref contract_name,
-                ref tip_req,
-            ) => {
-                if let Some(tip) = ConversationHttp::handle_load_stacks_chain_tip(
-                    &mut self.connection.protocol,
-                    &mut reply,
-                    &req,
-                    tip_req,
-                    sortdb,
-                    chainstate,
-                    network.burnchain_tip.canonical_stacks_tip_height,
-                )? {
-                    ConversationHttp::handle_get_contract_abi(
-                        &mut self.connection.protocol,
-                        &mut reply,
-                        &req,
-                        sortdb,
-                        chainstate,
-                        &tip,
-                        contract_addr,
-                        contract_name,
-                        network.burnchain_tip.canonical_stacks_tip_height,
-                    )?;
+            };
+
+        response.send(http, fd).map(|_| ())
+    }
+
+    /// Run one read-only function call on `clarity_tx`, with its own cost tracker limited to
+    /// `cost_limit`.
+    fn run_readonly_function_call(
+        clarity_tx: &mut ClarityReadOnlyConnection,
+        mainnet: bool,
+        chain_id: u32,
+        cost_limit: ExecutionCost,
+        contract_identifier: &QualifiedContractIdentifier,
+        function: &ClarityName,
+        sender: &PrincipalData,
+        sponsor: Option<&PrincipalData>,
+        args: &[Value],
+    ) -> Result<Value, ClarityRuntimeError> {
+        let args: Vec<_> = args
+            .iter()
+            .map(|x| SymbolicExpression::atom_value(x.clone()))
+            .collect();
+
+        let epoch = clarity_tx.get_epoch();
+        let cost_track = clarity_tx
+            .with_clarity_db_readonly(|clarity_db| {
+                LimitedCostTracker::new_mid_block(mainnet, chain_id, cost_limit, clarity_db, epoch)
+            })
+            .map_err(|_| ClarityRuntimeError::from(InterpreterError::CostContractLoadFailure))?;
+
+        let clarity_version = clarity_tx
+            .with_analysis_db_readonly(|analysis_db| {
+                analysis_db.get_clarity_version(contract_identifier)
+            })
+            .map_err(|_| {
+                ClarityRuntimeError::from(CheckErrors::NoSuchContract(
+                    contract_identifier.to_string(),
+                ))
+            })?;
+
+        clarity_tx.with_readonly_clarity_env(
+            mainnet,
+            chain_id,
+            clarity_version,
+            sender.clone(),
+            sponsor.cloned(),
+            cost_track,
+            |env| {
+                // we want to execute any function as long as no actual writes are made as
+                // opposed to be limited to purely calling `define-read-only` functions,
+                // so use `read_only = false`.  This broadens the number of functions that
+                // can be called, and also circumvents limitations on `define-read-only`
+                // functions that can not use `contrac-call?`, even when calling other
+                // read-only functions
+                env.execute_contract(contract_identifier, function.as_str(), &args, false)
+            },
+        )
+    }
+
+    /// Report the outcome of a read-only function call
+    fn readonly_function_call_response(
+        result: Result<Value, ClarityRuntimeError>,
+    ) -> CallReadOnlyResponse {
+        match result {
+            Ok(data) => CallReadOnlyResponse {
+                okay: true,
+                result: Some(format!("0x{}", data.serialize_to_hex())),
+                cause: None,
+            },
+            Err(Unchecked(CheckErrors::CostBalanceExceeded(actual_cost, _)))
+                if actual_cost.write_count > 0 =>
+            {
+                CallReadOnlyResponse {
+                    okay: false,
+                    result: None,
+                    cause: Some("NotReadOnly".to_string()),
                 }
-                None
             }
-            HttpRequestType::FeeRateEstimate(ref _md, ref tx, estimated_len) => {
-                ConversationHttp::handle_post_fee_rate_estimate(
-                    &mut self.connection.protocol,
-                    &mut reply,
-                    &req,
-                    handler_opts,
-                    sortdb,
-                    tx,
-                    estimated_len,
-                    network.burnchain_tip.canonical_stacks_tip_height,
-                )?;
-                None
+            Err(e) => CallReadOnlyResponse {
+                okay: false,
+                result: None,
+                cause: Some(e.to_string()),
+            },
+        }
+    }
+
+    /// Handle a POST to run a read-only function call with the given parameters on the given chain
+    /// tip.  Returns the result of the function call.  Returns a CallReadOnlyResponse on success.
+    fn handle_readonly_function_call<W: Write>(
+        http: &mut StacksHttp,
+        fd: &mut W,
+        req: &HttpRequestType,
+        sortdb: &SortitionDB,
+        chainstate: &mut StacksChainState,
+        tip: &StacksBlockId,
+        contract_addr: &StacksAddress,
+        contract_name: &ContractName,
+        function: &ClarityName,
+        sender: &PrincipalData,
+        sponsor: Option<&PrincipalData>,
+        args: &[Value],
+        options: &ConnectionOptions,
+        canonical_stacks_tip_height: u64,
+    ) -> Result<(), net_error> {
+        let response_metadata =
+            HttpResponseMetadata::from_http_request_type(req, Some(canonical_stacks_tip_height));
+        let contract_identifier =
+            QualifiedContractIdentifier::new(contract_addr.clone().into(), contract_name.clone());
+
+        let mainnet = chainstate.mainnet;
+        let chain_id = chainstate.chain_id;
+        let mut cost_limit = options.read_only_call_limit.clone();
+        cost_limit.write_length = 0;
+        cost_limit.write_count = 0;
+
+        let data_opt_res =
+            chainstate.maybe_read_only_clarity_tx(&sortdb.index_conn(), tip, |clarity_tx| {
+                ConversationHttp::run_readonly_function_call(
+                    clarity_tx,
+                    mainnet,
+                    chain_id,
+                    cost_limit,
+                    &contract_identifier,
+                    function,
+                    sender,
+                    sponsor,
+                    args,
+                )
+            });
+
+        let response = match data_opt_res {
+            Ok(Some(result)) => HttpResponseType::CallReadOnlyFunction(
+                response_metadata,
+                ConversationHttp::readonly_function_call_response(result),
+            ),
+            Ok(None) | Err(_) => {
+                HttpResponseType::NotFound(response_metadata, "Chain tip not found".into())
             }
-            HttpRequestType::CallReadOnlyFunction(
-                ref _md,
-                ref ctrct_addr,
-                ref ctrct_name,
-                ref as_sender,
-                ref func_name,
-                ref args,
-                ref tip_req,
-            ) => {
-                if let Some(tip) = ConversationHttp::handle_load_stacks_chain_tip(
-                    &mut self.connection.protocol,
-                    &mut reply,
-                    &req,
-                    tip_req,
-                    sortdb,
-                    chainstate,
-                    network.burnchain_tip.canonical_stacks_tip_height,
-                )? {
-                    ConversationHttp::handle_readonly_function_call(
-                        &mut self.connection.protocol,
-                        &mut reply,
-                        &req,
-                        sortdb,
-                        chainstate,
-                        &tip,
-                        ctrct_addr,
-                        ctrct_name,
-                        func_name,
-                        as_sender,
-                        None,
-                        args,
-                        &self.connection.options,
-                        network.burnchain_tip.canonical_stacks_tip_height,
-                    )?;
-                }
-                None
-            }
-            HttpRequestType::GetContractSrc(
-                ref _md,
-                ref contract_addr,
-                ref contract_name,
-                ref tip_req,
-                ref with_proof,
-            ) => {
-                if let Some(tip) = ConversationHttp::handle_load_stacks_chain_tip(
-                    &mut self.connection.protocol,
-                    &mut reply,
-                    &req,
-                    tip_req,
-                    sortdb,
-                    chainstate,
-                    network.burnchain_tip.canonical_stacks_tip_height,
-                )? {
-                    ConversationHttp::handle_get_contract_src(
-                        &mut self.connection.protocol,
-                        &mut reply,
-                        &req,
-                        sortdb,
-                        chainstate,
-                        &tip,
-                        contract_addr,
-                        contract_name,
-                        *with_proof,
-                        network.burnchain_tip.canonical_stacks_tip_height,
-                    )?;
-                }
-                None
-            }
-            HttpRequestType::PostTransaction(ref _md, ref tx, ref attachment) => {
-                match chainstate.get_stacks_chain_tip(sortdb)? {
-                    Some(tip) => {
-                        let accepted = ConversationHttp::handle_post_transaction(
-                            &mut self.connection.protocol,
-                            &mut reply,
-                            &req,
-                            chainstate,
-                            sortdb,
-                            tip.consensus_hash,
-                            tip.anchored_block_hash,
-                            mempool,
-                            tx.clone(),
-                            &mut network.atlasdb,
-                            attachment.clone(),
-                            handler_opts.event_observer.as_deref(),
-                            network.burnchain_tip.canonical_stacks_tip_height,
-                        )?;
-                        if accepted {
-                            // forward to peer network
-                            ret = Some(StacksMessageType::Transaction(tx.clone()));
-                        }
-                    }
-                    None => {
-                        let response_metadata = HttpResponseMetadata::from_http_request_type(
-                            &req,
-                            Some(network.burnchain_tip.canonical_stacks_tip_height),
-                        );
-                        warn!("Failed to load Stacks chain tip");
-                        let response = HttpResponseType::ServerError(
-                            response_metadata,
-                            format!("Failed to load Stacks chain tip"),
+        };
+        response.send(http, fd).map(|_| ())
+    }
+
+    /// Handle a POST to run a batch of read-only function calls on the given chain tip.  All the
+    /// calls are run on one read-only Clarity connection, so they see the same state and only
+    /// open the chain tip once.  Each call still has its own cost limit.  Returns a
+    /// CallReadOnlyResponse for each call, in order.
+    fn handle_readonly_function_call_batch<W: Write>(
+        http: &mut StacksHttp,
+        fd: &mut W,
+        req: &HttpRequestType,
+        sortdb: &SortitionDB,
+        chainstate: &mut StacksChainState,
+        tip: &StacksBlockId,
+        sender: &PrincipalData,
+        calls: &[ReadOnlyCall],
+        options: &ConnectionOptions,
+        canonical_stacks_tip_height: u64,
+    ) -> Result<(), net_error> {
+        let response_metadata =
+            HttpResponseMetadata::from_http_request_type(req, Some(canonical_stacks_tip_height));
+
+        let mainnet = chainstate.mainnet;
+        let chain_id = chainstate.chain_id;
+        let mut cost_limit = options.read_only_call_limit.clone();
+        cost_limit.write_length = 0;
+        cost_limit.write_count = 0;
+
+        let data_opt_res =
+            chainstate.maybe_read_only_clarity_tx(&sortdb.index_conn(), tip, |clarity_tx| {
+                calls
+                    .iter()
+                    .map(|call| {
+                        let result = ConversationHttp::run_readonly_function_call(
+                            clarity_tx,
+                            mainnet,
+                            chain_id,
+                            cost_limit.clone(),
+                            &call.contract,
+                            &call.function,
+                            sender,
+                            None,
+                            &call.arguments,
                         );
-                        response.send(&mut self.connection.protocol, &mut reply)?;
-                    }
-                }
-                None
-            }
-            HttpRequestType::GetAttachment(ref _md, ref content_hash) => {
-                ConversationHttp::handle_getattachment(
-                    &mut self.connection.protocol,
-                    &mut reply,
-                    &req,
-                    &mut network.atlasdb,
-                    content_hash.clone(),
-                    network.burnchain_tip.canonical_stacks_tip_height,
-                )?;
-                None
-            }
-            HttpRequestType::GetAttachmentsInv(
-                ref _md,
-                ref index_block_hash,
-                ref pages_indexes,
-            ) => {
-                ConversationHttp::handle_getattachmentsinv(
-                    &mut self.connection.protocol,
-                    &mut reply,
-                    &req,
-                    &mut network.atlasdb,
-                    &index_block_hash,
-                    pages_indexes,
-                    &self.connection.options,
-                    network.burnchain_tip.canonical_stacks_tip_height,
-                )?;
-                None
+                        ConversationHttp::readonly_function_call_response(result)
+                    })
+                    .collect::<Vec<_>>()
+            });
+
+        let response = match data_opt_res {
+            Ok(Some(results)) => {
+                HttpResponseType::CallReadOnlyFunctionBatch(response_metadata, results)
             }
-            HttpRequestType::PostBlock(ref _md, ref consensus_hash, ref block) => {
-                let accepted = ConversationHttp::handle_post_block(
-                    &mut self.connection.protocol,
-                    &mut reply,
-                    &req,
-                    sortdb,
-                    chainstate,
-                    consensus_hash,
-                    block,
-                    network.burnchain_tip.canonical_stacks_tip_height,
-                )?;
-                if accepted {
-                    // inform the peer network so it can announce its presence
-                    ret = Some(StacksMessageType::Blocks(BlocksData {
-                        blocks: vec![BlocksDatum(consensus_hash.clone(), block.clone())],
-                    }));
-                }
-                None
+            Ok(None) | Err(_) => {
+                HttpResponseType::NotFound(response_metadata, "Chain tip not found".into())
             }
-            HttpRequestType::PostMicroblock(ref _md, ref mblock, ref tip_req) => {
-                if let Some(tip) = ConversationHttp::handle_load_stacks_chain_tip(
-                    &mut self.connection.protocol,
-                    &mut reply,
-                    &req,
-                    tip_req,
-                    sortdb,
-                    chainstate,
-                    network.burnchain_tip.canonical_stacks_tip_height,
-                )? {
-                    if let Some((consensus_hash, block_hash)) =
-                        ConversationHttp::handle_load_stacks_chain_tip_hashes(
-                            &mut self.connection.protocol,
-                            &mut reply,
-                            &req,
-                            tip,
-                            chainstate,
-                            network.burnchain_tip.canonical_stacks_tip_height,
-                        )?
-                    {
-                        let accepted = ConversationHttp::handle_post_microblock(
-                            &mut self.connection.protocol,
-                            &mut reply,
-                            &req,
-                            &consensus_hash,
-                            &block_hash,
-                            chainstate,
-                            mblock,
-                            network.burnchain_tip.canonical_stacks_tip_height,
-                        )?;
-                        if accepted {
-                            // forward to peer network
-                            let tip = StacksBlockHeader::make_index_block_hash(
-                                &consensus_hash,
-                                &block_hash,
-                            );
-                            ret = Some(StacksMessageType::Microblocks(MicroblocksData {
-                                index_anchor_block: tip,
-                                microblocks: vec![(*mblock).clone()],
-                            }));
-                        }
-                    }
+        };
+        response.send(http, fd).map(|_| ())
+    }
+
+    /// Handle a GET to fetch a contract's source code, given the chain tip.  Optionally returns a
+    /// MARF proof as well.
+    fn handle_get_contract_src<W: Write>(
+        http: &mut StacksHttp,
+        fd: &mut W,
+        req: &HttpRequestType,
+        sortdb: &SortitionDB,
+        chainstate: &mut StacksChainState,
+        tip: &StacksBlockId,
+        contract_addr: &StacksAddress,
+        contract_name: &ContractName,
+        with_proof: bool,
+        canonical_stacks_tip_height: u64,
+    ) -> Result<(), net_error> {
+        let response_metadata =
+            HttpResponseMetadata::from_http_request_type(req, Some(canonical_stacks_tip_height));
+        let contract_identifier =
+            QualifiedContractIdentifier::new(contract_addr.clone().into(), contract_name.clone());
+
+        let response =
+            match chainstate.maybe_read_only_clarity_tx(&sortdb.index_conn(), tip, |clarity_tx| {
+                clarity_tx.with_clarity_db_readonly(|db| {
+                    let source = db.get_contract_src(&contract_identifier)?;
+                    let contract_commit_key = make_contract_hash_key(&contract_identifier);
+                    let (contract_commit, proof) = if with_proof {
+                        db.get_with_proof::<ContractCommitment>(&contract_commit_key)
+                            .map(|(a, b)| (a, Some(format!("0x{}", to_hex(&b)))))
+                            .expect("BUG: obtained source, but couldn't get contract commit")
+                    } else {
+                        db.get::<ContractCommitment>(&contract_commit_key)
+                            .map(|a| (a, None))
+                            .expect("BUG: obtained source, but couldn't get contract commit")
+                    };
+
+                    let publish_height = contract_commit.block_height;
+                    Some(ContractSrcResponse {
+                        source,
+                        publish_height,
+                        marf_proof: proof,
+                    })
+                })
+            }) {
+                Ok(Some(Some(data))) => HttpResponseType::GetContractSrc(response_metadata, data),
+                Ok(Some(None)) => HttpResponseType::NotFound(
+                    response_metadata,
+                    "No contract source data found".into(),
+                ),
+                Ok(None) | Err(_) => {
+                    HttpResponseType::NotFound(response_metadata, "Chain tip not found".into())
                 }
-                None
-            }
-            HttpRequestType::MemPoolQuery(ref _md, ref query, ref page_id_opt) => {
-                Some(ConversationHttp::handle_mempool_query(
-                    &mut self.connection.protocol,
-                    &mut reply,
-                    &req,
-                    sortdb,
-                    chainstate,
-                    query.clone(),
-                    network.connection_opts.mempool_max_tx_query,
-                    network.burnchain_tip.canonical_stacks_tip_height,
-                    page_id_opt.clone(),
-                )?)
+            };
+
+        response.send(http, fd).map(|_| ())
+    }
+
+    /// Handle a GET to fetch whether or not a contract implements a certain trait
+    fn handle_get_is_trait_implemented<W: Write>(
+        http: &mut StacksHttp,
+        fd: &mut W,
+        req: &HttpRequestType,
+        sortdb: &SortitionDB,
+        chainstate: &mut StacksChainState,
+        tip: &StacksBlockId,
+        contract_addr: &StacksAddress,
+        contract_name: &ContractName,
+        trait_id: &TraitIdentifier,
+        canonical_stacks_tip_height: u64,
+    ) -> Result<(), net_error> {
+        let response_metadata =
+            HttpResponseMetadata::from_http_request_type(req, Some(canonical_stacks_tip_height));
+        let contract_identifier =
+            QualifiedContractIdentifier::new(contract_addr.clone().into(), contract_name.clone());
+
+        let response =
+            match chainstate.maybe_read_only_clarity_tx(&sortdb.index_conn(), tip, |clarity_tx| {
+                clarity_tx.with_clarity_db_readonly(|db| {
+                    let analysis = db.load_contract_analysis(&contract_identifier)?;
+                    if analysis.implemented_traits.contains(trait_id) {
+                        Some(GetIsTraitImplementedResponse {
+                            is_implemented: true,
+                        })
+                    } else {
+                        let trait_defining_contract =
+                            db.load_contract_analysis(&trait_id.contract_identifier)?;
+                        let trait_definition =
+                            trait_defining_contract.get_defined_trait(&trait_id.name)?;
+                        let is_implemented = analysis
+                            .check_trait_compliance(
+                                &StacksEpochId::Epoch21,
+                                trait_id,
+                                trait_definition,
+                            )
+                            .is_ok();
+                        Some(GetIsTraitImplementedResponse { is_implemented })
+                    }
+                })
+            }) {
+                Ok(Some(Some(data))) => {
+                    HttpResponseType::GetIsTraitImplemented(response_metadata, data)
+                }
+                Ok(Some(None)) => HttpResponseType::NotFound(
+                    response_metadata,
+                    "No contract analysis found or trait definition not found".into(),
+                ),
+                Ok(None) | Err(_) => {
+                    HttpResponseType::NotFound(response_metadata, "Chain tip not found".into())
+                }
+            };
+
+        response.send(http, fd).map(|_| ())
+    }
+
+    /// Handle a GET to fetch a contract's analysis data, given the chain tip.  Note that this isn't
+    /// something that's anchored to the blockchain, and can be different across different versions
+    /// of Stacks -- callers must trust the Stacks node to return correct analysis data.
+    /// Callers who don't trust the Stacks node should just fetch the contract source
+    /// code and analyze it offline.
+    fn handle_get_contract_abi<W: Write>(
+        http: &mut StacksHttp,
+        fd: &mut W,
+        req: &HttpRequestType,
+        sortdb: &SortitionDB,
+        chainstate: &mut StacksChainState,
+        tip: &StacksBlockId,
+        contract_addr: &StacksAddress,
+        contract_name: &ContractName,
+        canonical_stacks_tip_height: u64,
+    ) -> Result<(), net_error> {
+        let response_metadata =
+            HttpResponseMetadata::from_http_request_type(req, Some(canonical_stacks_tip_height));
+        let contract_identifier =
+            QualifiedContractIdentifier::new(contract_addr.clone().into(), contract_name.clone());
+
+        let response =
+            match chainstate.maybe_read_only_clarity_tx(&sortdb.index_conn(), tip, |clarity_tx| {
+                let epoch = clarity_tx.get_epoch();
+                clarity_tx.with_analysis_db_readonly(|db| {
+                    let contract = db.load_contract(&contract_identifier, &epoch)?;
+                    contract.contract_interface
+                })
+            }) {
+                Ok(Some(Some(data))) => HttpResponseType::GetContractABI(response_metadata, data),
+                Ok(Some(None)) => HttpResponseType::NotFound(
+                    response_metadata,
+                    "No contract interface data found".into(),
+                ),
+                Ok(None) | Err(_) => {
+                    HttpResponseType::NotFound(response_metadata, "Chain tip not found".into())
+                }
+            };
+
+        response.send(http, fd).map(|_| ())
+    }
+
+    /// Handle a GET unconfirmed microblock stream.  Start streaming the reply.
+    /// The response's preamble (but not the block data) will be synchronously written to the fd
+    /// (so use a fd that can buffer!)
+    /// Return a StreamCursor struct for the block that we're sending, so we can continue to
+    /// make progress sending it.
+    fn handle_getmicroblocks_unconfirmed<W: Write>(
+        http: &mut StacksHttp,
+        fd: &mut W,
+        req: &HttpRequestType,
+        index_anchor_block_hash: &StacksBlockId,
+        min_seq: u16,
+        chainstate: &StacksChainState,
+        canonical_stacks_tip_height: u64,
+    ) -> Result<Option<StreamCursor>, net_error> {
+        let response_metadata =
+            HttpResponseMetadata::from_http_request_type(req, Some(canonical_stacks_tip_height));
+
+        // do we have this unconfirmed microblock stream?
+        match chainstate.has_any_staging_microblock_indexed(index_anchor_block_hash, min_seq) {
+            Ok(false) => {
+                // nope
+                let response = HttpResponseType::NotFound(
+                    response_metadata,
+                    format!(
+                        "No such unconfirmed microblock stream for {} at or after {}",
+                        index_anchor_block_hash.to_hex(),
+                        min_seq
+                    ),
+                );
+                response.send(http, fd).and_then(|_| Ok(None))
             }
-            HttpRequestType::OptionsPreflight(ref _md, ref _path) => {
-                let response_metadata = HttpResponseMetadata::from_http_request_type(
-                    &req,
-                    Some(network.burnchain_tip.canonical_stacks_tip_height),
+            Err(e) => {
+                // nope
+                warn!(
+                    "Failed to serve confirmed microblock stream {:?}: {:?}",
+                    req, &e
                 );
-                let response = HttpResponseType::OptionsPreflight(response_metadata);
-                response
-                    .send(&mut self.connection.protocol, &mut reply)
-                    .map(|_| ())?;
-                None
+                let response = HttpResponseType::ServerError(
+                    response_metadata,
+                    format!(
+                        "Failed to query unconfirmed microblock stream for {} at or after {}",
+                        index_anchor_block_hash.to_hex(),
+                        min_seq
+                    ),
+                );
+                response.send(http, fd).and_then(|_| Ok(None))
             }
-            HttpRequestType::GetIsTraitImplemented(
-                ref _md,
-                ref contract_addr,
-                ref contract_name,
-                ref trait_id,
-                ref tip_req,
-            ) => {
-                if let Some(tip) = ConversationHttp::handle_load_stacks_chain_tip(
-                    &mut self.connection.protocol,
-                    &mut reply,
-                    &req,
-                    tip_req,
-                    sortdb,
+            Ok(true) => {
+                // yup! start streaming it back
+                let (response, stream_opt) = match StreamCursor::new_microblock_unconfirmed(
                     chainstate,
-                    network.burnchain_tip.canonical_stacks_tip_height,
-                )? {
-                    ConversationHttp::handle_get_is_trait_implemented(
-                        &mut self.connection.protocol,
-                        &mut reply,
-                        &req,
-                        sortdb,
-                        chainstate,
-                        &tip,
-                        contract_addr,
-                        contract_name,
-                        trait_id,
-                        network.burnchain_tip.canonical_stacks_tip_height,
-                    )?;
-                }
-                None
-            }
-            HttpRequestType::ClientError(ref _md, ref err) => {
-                let response_metadata = HttpResponseMetadata::from_http_request_type(
-                    &req,
-                    Some(network.burnchain_tip.canonical_stacks_tip_height),
-                );
-                let response = match err {
-                    ClientError::Message(s) => HttpResponseType::BadRequestJSON(
-                        response_metadata,
-                        serde_json::Value::String(s.to_string()),
+                    index_anchor_block_hash.clone(),
+                    min_seq,
+                ) {
+                    Ok(stream) => (
+                        HttpResponseType::MicroblockStream(response_metadata),
+                        Some(stream),
                     ),
-                    ClientError::NotFound(path) => {
-                        HttpResponseType::NotFound(response_metadata, path.clone())
+                    Err(chain_error::NoSuchBlockError) => (
+                        HttpResponseType::NotFound(
+                            response_metadata,
+                            format!(
+                                "No such unconfirmed microblock stream starting with {}",
+                                index_anchor_block_hash.to_hex()
+                            ),
+                        ),
+                        None,
+                    ),
+                    Err(_e) => {
+                        debug!(
+                            "Failed to load unconfirmed microblock stream {}: {:?}",
+                            &index_anchor_block_hash, &_e
+                        );
+                        (
+                            HttpResponseType::ServerError(
+                                response_metadata,
+                                format!(
+                                    "Failed to query unconfirmed microblock stream {}",
+                                    index_anchor_block_hash.to_hex()
+                                ),
+                            ),
+                            None,
+                        )
                     }
                 };
+                response.send(http, fd).and_then(|_| Ok(stream_opt))
+            }
+        }
+    }
+
+    /// Handle a GET unconfirmed transaction.
+    /// The response will be synchronously written to the fd.
+    fn handle_gettransaction_unconfirmed<W: Write>(
+        http: &mut StacksHttp,
+        fd: &mut W,
+        req: &HttpRequestType,
+        chainstate: &StacksChainState,
+        mempool: &MemPoolDB,
+        txid: &Txid,
+        canonical_stacks_tip_height: u64,
+    ) -> Result<(), net_error> {
+        let response_metadata =
+            HttpResponseMetadata::from_http_request_type(req, Some(canonical_stacks_tip_height));
+
+        // present in the unconfirmed state?
+        if let Some(ref unconfirmed) = chainstate.unconfirmed_state.as_ref() {
+            if let Some((transaction, mblock_hash, seq)) =
+                unconfirmed.get_unconfirmed_transaction(txid)
+            {
+                let response = HttpResponseType::UnconfirmedTransaction(
+                    response_metadata,
+                    UnconfirmedTransactionResponse {
+                        status: UnconfirmedTransactionStatus::Microblock {
+                            block_hash: mblock_hash,
+                            seq: seq,
+                        },
+                        tx: to_hex(&transaction.serialize_to_vec()),
+                    },
+                );
+                return response.send(http, fd).map(|_| ());
+            }
+        }
+
+        // present in the mempool?
+        if let Some(txinfo) = MemPoolDB::get_tx(mempool.conn(), txid)? {
+            let response = HttpResponseType::UnconfirmedTransaction(
+                response_metadata,
+                UnconfirmedTransactionResponse {
+                    status: UnconfirmedTransactionStatus::Mempool,
+                    tx: to_hex(&txinfo.tx.serialize_to_vec()),
+                },
+            );
+            return response.send(http, fd).map(|_| ());
+        }
+
+        // not found
+        let response = HttpResponseType::NotFound(
+            response_metadata,
+            format!("No such unconfirmed transaction {}", txid),
+        );
+        return response.send(http, fd).map(|_| ());
+    }
+
+    /// Load up the canonical Stacks chain tip.  Note that this is subject to both burn chain block
+    /// Stacks block availability -- different nodes with different partial replicas of the Stacks chain state
+    /// will return different values here.
+    ///
+    /// # Warn
+    /// - There is a potential race condition. If this function is loading the latest unconfirmed
+    /// tip, that tip may get invalidated by the time it is used in `maybe_read_only_clarity_tx`,
+    /// which is used to load clarity state at a particular tip (which would lead to a 404 error).
+    /// If this race condition occurs frequently, we can modify `maybe_read_only_clarity_tx` to
+    /// re-load the unconfirmed chain tip. Refer to issue #2997.
+    ///
+    /// # Inputs
+    /// - `tip_req` is given by the HTTP request as the optional query parameter for the chain tip
+    /// hash.  It will be UseLatestAnchoredTip if there was no parameter given. If it is set to
+    /// `latest`, the parameter will be set to UseLatestUnconfirmedTip.
+    fn handle_load_stacks_chain_tip<W: Write>(
+        http: &mut StacksHttp,
+        fd: &mut W,
+        req: &HttpRequestType,
+        tip_req: &TipRequest,
+        sortdb: &SortitionDB,
+        chainstate: &mut StacksChainState,
+        canonical_stacks_tip_height: u64,
+    ) -> Result<Option<StacksBlockId>, net_error> {
+        match tip_req {
+            TipRequest::UseLatestUnconfirmedTip => {
+                let unconfirmed_chain_tip_opt = match &mut chainstate.unconfirmed_state {
+                    Some(unconfirmed_state) => {
+                        match unconfirmed_state.get_unconfirmed_state_if_exists() {
+                            Ok(res) => res,
+                            Err(msg) => {
+                                let response_metadata =
+                                    HttpResponseMetadata::from_http_request_type(
+                                        req,
+                                        Some(canonical_stacks_tip_height),
+                                    );
+                                let response = HttpResponseType::NotFound(response_metadata, msg);
+                                return response.send(http, fd).and_then(|_| Ok(None));
+                            }
+                        }
+                    }
+                    None => None,
+                };
+
+                if let Some(unconfirmed_chain_tip) = unconfirmed_chain_tip_opt {
+                    Ok(Some(unconfirmed_chain_tip))
+                } else {
+                    match chainstate.get_stacks_chain_tip(sortdb)? {
+                        Some(tip) => Ok(Some(StacksBlockHeader::make_index_block_hash(
+                            &tip.consensus_hash,
+                            &tip.anchored_block_hash,
+                        ))),
+                        None => {
+                            let response_metadata = HttpResponseMetadata::from_http_request_type(
+                                req,
+                                Some(canonical_stacks_tip_height),
+                            );
+                            warn!("Failed to load Stacks chain tip");
+                            let response = HttpResponseType::NotFound(
+                                response_metadata,
+                                format!("Failed to load Stacks chain tip"),
+                            );
+                            response.send(http, fd).and_then(|_| Ok(None))
+                        }
+                    }
+                }
+            }
+            TipRequest::SpecificTip(tip) => Ok(Some(*tip).clone()),
+            TipRequest::UseLatestAnchoredTip => match chainstate.get_stacks_chain_tip(sortdb)? {
+                Some(tip) => Ok(Some(StacksBlockHeader::make_index_block_hash(
+                    &tip.consensus_hash,
+                    &tip.anchored_block_hash,
+                ))),
+                None => {
+                    let response_metadata = HttpResponseMetadata::from_http_request_type(
+                        req,
+                        Some(canonical_stacks_tip_height),
+                    );
+                    warn!("Failed to load Stacks chain tip");
+                    let response = HttpResponseType::ServerError(
+                        response_metadata,
+                        format!("Failed to load Stacks chain tip"),
+                    );
+                    response.send(http, fd).and_then(|_| Ok(None))
+                }
+            },
+        }
+    }
+
+    fn handle_load_stacks_chain_tip_hashes<W: Write>(
+        http: &mut StacksHttp,
+        fd: &mut W,
+        req: &HttpRequestType,
+        tip: StacksBlockId,
+        chainstate: &StacksChainState,
+        canonical_stacks_tip_height: u64,
+    ) -> Result<Option<(ConsensusHash, BlockHeaderHash)>, net_error> {
+        match chainstate.get_block_header_hashes(&tip)? {
+            Some((ch, bl)) => {
+                return Ok(Some((ch, bl)));
+            }
+            None => {}
+        }
+        let response_metadata =
+            HttpResponseMetadata::from_http_request_type(req, Some(canonical_stacks_tip_height));
+        warn!("Failed to load Stacks chain tip");
+        let response = HttpResponseType::ServerError(
+            response_metadata,
+            format!("Failed to load Stacks chain tip"),
+        );
+        response.send(http, fd).and_then(|_| Ok(None))
+    }
+
+    fn handle_post_fee_rate_estimate<W: Write>(
+        http: &mut StacksHttp,
+        fd: &mut W,
+        req: &HttpRequestType,
+        handler_args: &RPCHandlerArgs,
+        sortdb: &SortitionDB,
+        tx: &TransactionPayload,
+        estimated_len: u64,
+        canonical_stacks_tip_height: u64,
+    ) -> Result<(), net_error> {
+        let response_metadata =
+            HttpResponseMetadata::from_http_request_type(req, Some(canonical_stacks_tip_height));
+        let tip = SortitionDB::get_canonical_burn_chain_tip(sortdb.conn())?;
+        let stacks_epoch = sortdb.index_conn().get_stacks_epoch(tip.block_height as u32)
+                .ok_or_else(|| {
+                    warn!(
+                        "Failed to get fee rate estimate because could not load Stacks epoch for canonical burn height = {}",
+                        tip.block_height
+                    );
+                    net_error::ChainstateError("Could not load Stacks epoch for canonical burn height".into())
+                })?;
+        if let Some((cost_estimator, fee_estimator, metric)) = handler_args.get_estimators_ref() {
+            let estimated_cost = match cost_estimator.estimate_cost(tx, &stacks_epoch.epoch_id) {
+                Ok(x) => x,
+                Err(e) => {
+                    debug!(
+                        "Estimator RPC endpoint failed to estimate tx: {}",
+                        tx.name()
+                    );
+                    return HttpResponseType::BadRequestJSON(response_metadata, e.into_json())
+                        .send(http, fd);
+                }
+            };
+
+            let scalar_cost =
+                metric.from_cost_and_len(&estimated_cost, &stacks_epoch.block_limit, estimated_len);
+            let fee_rates = match fee_estimator.get_rate_estimates() {
+                Ok(x) => x,
+                Err(e) => {
+                    debug!(
+                        "Estimator RPC endpoint failed to estimate fees for tx: {}",
+                        tx.name()
+                    );
+                    return HttpResponseType::BadRequestJSON(response_metadata, e.into_json())
+                        .send(http, fd);
+                }
+            };
+
+            let mut estimations = RPCFeeEstimate::estimate_fees(scalar_cost, fee_rates).to_vec();
+
+            let minimum_fee = estimated_len * MINIMUM_TX_FEE_RATE_PER_BYTE;
+
+            for estimate in estimations.iter_mut() {
+                if estimate.fee < minimum_fee {
+                    estimate.fee = minimum_fee;
+                }
+            }
+
+            let response = HttpResponseType::TransactionFeeEstimation(
+                response_metadata,
+                RPCFeeEstimateResponse {
+                    estimated_cost,
+                    estimations,
+                    estimated_cost_scalar: scalar_cost,
+                    cost_scalar_change_by_byte: metric.change_per_byte(),
+                },
+            );
+            response.send(http, fd)
+        } else {
+            debug!("Fee and cost estimation not configured on this stacks node");
+            let response = HttpResponseType::BadRequestJSON(
+                response_metadata,
+                json!({
+                    "error": "Fee and Cost Estimation not configured on this Stacks node",
+                    "reason": "CostEstimationDisabled",
+                }),
+            );
+            response.send(http, fd)
+        }
+    }
+
+    /// Handle a transaction.  Directly submit it to the mempool so the client can see any
+    /// rejection reasons up-front (different from how the peer network handles it).  Indicate
+    /// whether or not the transaction was accepted (and thus needs to be forwarded) in the return
+    /// value.  If the client gave an `expiration_height`, the transaction is dropped from the
+    /// mempool if it has not been mined by then.  If it gave a `bundle_id`, the transaction is
+    /// mined in the same block as the rest of that bundle, or not at all.
+    fn handle_post_transaction<W: Write>(
+        http: &mut StacksHttp,
+        fd: &mut W,
+        req: &HttpRequestType,
+        chainstate: &mut StacksChainState,
+        sortdb: &SortitionDB,
+        consensus_hash: ConsensusHash,
+        block_hash: BlockHeaderHash,
+        mempool: &mut MemPoolDB,
+        tx: StacksTransaction,
+        atlasdb: &mut AtlasDB,
+        attachment: Option<Attachment>,
+        expiration_height: Option<u64>,
+        bundle_id: Option<&str>,
+        event_observer: Option<&dyn MemPoolEventDispatcher>,
+        canonical_stacks_tip_height: u64,
+    ) -> Result<bool, net_error> {
+        let txid = tx.txid();
+        let response_metadata =
+            HttpResponseMetadata::from_http_request_type(req, Some(canonical_stacks_tip_height));
+        let (response, accepted) = if mempool.has_tx(&txid) {
+            debug!("Mempool already has POSTed transaction {}", &txid);
+            (
+                HttpResponseType::TransactionID(response_metadata, txid),
+                false,
+            )
+        } else {
+            let tip = SortitionDB::get_canonical_burn_chain_tip(sortdb.conn())?;
+            let stacks_epoch = sortdb
+                .index_conn()
+                .get_stacks_epoch(tip.block_height as u32)
+                .ok_or_else(|| {
+                    warn!(
+                        "Failed to store transaction because could not load Stacks epoch for canonical burn height = {}",
+                        tip.block_height
+                    );
+                    net_error::ChainstateError("Could not load Stacks epoch for canonical burn height".into())
+                })?;
+
+            match mempool.submit_with_options(
+                chainstate,
+                &consensus_hash,
+                &block_hash,
+                &tx,
+                event_observer,
+                &stacks_epoch.block_limit,
+                &stacks_epoch.epoch_id,
+                expiration_height,
+                bundle_id,
+            ) {
+                Ok(_) => {
+                    debug!("Mempool accepted POSTed transaction {}", &txid);
+                    (
+                        HttpResponseType::TransactionID(response_metadata, txid),
+                        true,
+                    )
+                }
+                Err(e) => {
+                    debug!("Mempool rejected POSTed transaction {}: {:?}", &txid, &e);
+                    (
+                        HttpResponseType::BadRequestJSON(response_metadata, e.into_json(&txid)),
+                        false,
+                    )
+                }
+            }
+        };
+
+        if let Some(ref attachment) = attachment {
+            if let TransactionPayload::ContractCall(ref contract_call) = tx.payload {
+                if atlasdb
+                    .should_keep_attachment(&contract_call.to_clarity_contract_id(), &attachment)
+                {
+                    atlasdb
+                        .insert_uninstantiated_attachment(attachment)
+                        .map_err(|e| net_error::DBError(e))?;
+                }
+            }
+        }
+
+        response.send(http, fd).and_then(|_| Ok(accepted))
+    }
+
+    /// Handle a transaction POSTed to a read-only replica.  It is forwarded to the primary node
+    /// if there is a `tx_forwarder`, and refused otherwise.
+    fn handle_forward_transaction<W: Write>(
+        http: &mut StacksHttp,
+        fd: &mut W,
+        req: &HttpRequestType,
+        tx: &StacksTransaction,
+        attachment: Option<&Attachment>,
+        expiration_height: Option<u64>,
+        bundle_id: Option<&str>,
+        tx_forwarder: Option<&dyn TransactionForwarder>,
+        canonical_stacks_tip_height: u64,
+    ) -> Result<(), net_error> {
+        let response_metadata =
+            HttpResponseMetadata::from_http_request_type(req, Some(canonical_stacks_tip_height));
+        let response = match tx_forwarder {
+            Some(forwarder) => {
+                match forwarder.forward_transaction(tx, attachment, expiration_height, bundle_id) {
+                    Ok(txid) => {
+                        debug!("Forwarded POSTed transaction {} to the primary node", &txid);
+                        HttpResponseType::TransactionID(response_metadata, txid)
+                    }
+                    Err(TransactionForwardError::Rejected(reason)) => {
+                        HttpResponseType::BadRequestJSON(response_metadata, reason)
+                    }
+                    Err(TransactionForwardError::Unreachable(e)) => {
+                        warn!(
+                            "Failed to forward POSTed transaction to the primary node: {}",
+                            &e
+                        );
+                        HttpResponseType::ServiceUnavailable(
+                            response_metadata,
+                            format!("Failed to forward transaction to the primary node: {}", e),
+                        )
+                    }
+                }
+            }
+            None => HttpResponseType::Forbidden(
+                response_metadata,
+                "This node is a read-only replica and does not accept transactions".into(),
+            ),
+        };
+        response.send(http, fd).map(|_| ())
+    }
+
+    /// Refuse a block or microblock POSTed to a read-only replica.
+    fn handle_read_only_refusal<W: Write>(
+        http: &mut StacksHttp,
+        fd: &mut W,
+        req: &HttpRequestType,
+        canonical_stacks_tip_height: u64,
+    ) -> Result<(), net_error> {
+        let response_metadata =
+            HttpResponseMetadata::from_http_request_type(req, Some(canonical_stacks_tip_height));
+        let response = HttpResponseType::Forbidden(
+            response_metadata,
+            "This node is a read-only replica and does not accept blocks or microblocks".into(),
+        );
+        response.send(http, fd).map(|_| ())
+    }
+
+    /// Handle a block.  Directly submit a Stacks block to this node's chain state.
+    /// Indicate whether or not the block was accepted (i.e. it was new, and valid)
+    fn handle_post_block<W: Write>(
+        http: &mut StacksHttp,
+        fd: &mut W,
+        req: &HttpRequestType,
+        sortdb: &SortitionDB,
+        chainstate: &mut StacksChainState,
+        consensus_hash: &ConsensusHash,
+        block: &StacksBlock,
+        canonical_stacks_tip_height: u64,
+    ) -> Result<bool, net_error> {
+        let response_metadata =
+            HttpResponseMetadata::from_http_request_type(req, Some(canonical_stacks_tip_height));
+        // is this a consensus hash we recognize?
+        let (response, accepted) =
+            match SortitionDB::get_sortition_id_by_consensus(&sortdb.conn(), consensus_hash) {
+                Ok(Some(_)) => {
+                    // we recognize this consensus hash
+                    let ic = sortdb.index_conn();
+                    match Relayer::process_new_anchored_block(
+                        &ic,
+                        chainstate,
+                        consensus_hash,
+                        block,
+                        0,
+                    ) {
+                        Ok(true) => {
+                            debug!(
+                                "Accepted Stacks block {}/{}",
+                                consensus_hash,
+                                &block.block_hash()
+                            );
+                            (
+                                HttpResponseType::StacksBlockAccepted(
+                                    response_metadata,
+                                    StacksBlockHeader::make_index_block_hash(
+                                        consensus_hash,
+                                        &block.block_hash(),
+                                    ),
+                                    true,
+                                ),
+                                true,
+                            )
+                        }
+                        Ok(false) => {
+                            debug!(
+                                "Did not accept Stacks block {}/{}",
+                                consensus_hash,
+                                &block.block_hash()
+                            );
+                            (
+                                HttpResponseType::StacksBlockAccepted(
+                                    response_metadata,
+                                    StacksBlockHeader::make_index_block_hash(
+                                        consensus_hash,
+                                        &block.block_hash(),
+                                    ),
+                                    false,
+                                ),
+                                false,
+                            )
+                        }
+                        Err(e) => {
+                            error!(
+                                "Failed to process anchored block {}/{}: {:?}",
+                                consensus_hash,
+                                &block.block_hash(),
+                                &e
+                            );
+                            (
+                                HttpResponseType::ServerError(
+                                    response_metadata,
+                                    format!(
+                                        "Failed to process anchored block {}/{}: {:?}",
+                                        consensus_hash,
+                                        &block.block_hash(),
+                                        &e
+                                    ),
+                                ),
+                                false,
+                            )
+                        }
+                    }
+                }
+                Ok(None) => {
+                    debug!(
+                        "Unrecognized consensus hash {} for block {}",
+                        consensus_hash,
+                        &block.block_hash()
+                    );
+                    (
+                        HttpResponseType::NotFound(
+                            response_metadata,
+                            format!("No such consensus hash '{}'", consensus_hash),
+                        ),
+                        false,
+                    )
+                }
+                Err(e) => {
+                    error!(
+                        "Failed to query sortition ID by consensus '{}'",
+                        consensus_hash
+                    );
+                    (
+                        HttpResponseType::ServerError(
+                            response_metadata,
+                            format!(
+                                "Failed to query sortition ID for consensus hash '{}': {:?}",
+                                consensus_hash, &e
+                            ),
+                        ),
+                        false,
+                    )
+                }
+            };
+        response.send(http, fd).and_then(|_| Ok(accepted))
+    }
+
+    /// Handle a POST to validate a proposed block: process it on top of its parent, elected at
+    /// `parent_consensus_hash`, without storing it, and report whether it is valid, along with
+    /// the state root and cost it produces, or the first transaction that fails.
+    fn handle_post_validate_block<W: Write>(
+        http: &mut StacksHttp,
+        fd: &mut W,
+        req: &HttpRequestType,
+        sortdb: &SortitionDB,
+        chainstate: &mut StacksChainState,
+        parent_consensus_hash: &ConsensusHash,
+        block: &StacksBlock,
+        canonical_stacks_tip_height: u64,
+    ) -> Result<(), net_error> {
+        let response_metadata =
+            HttpResponseMetadata::from_http_request_type(req, Some(canonical_stacks_tip_height));
+        let block_hash = block.block_hash();
+        let response = match chainstate.validate_proposed_block(
+            &sortdb.index_conn(),
+            parent_consensus_hash,
+            block,
+        ) {
+            Ok(verdict) => HttpResponseType::BlockValidation(
+                response_metadata,
+                BlockValidationResponse::from_verdict(&block_hash, verdict),
+            ),
+            Err(chain_error::NoSuchBlockError) => HttpResponseType::NotFound(
+                response_metadata,
+                format!(
+                    "Parent block {}/{} not found",
+                    parent_consensus_hash, &block.header.parent_block
+                ),
+            ),
+            Err(e) => {
+                warn!(
+                    "Failed to validate proposed block {}: {:?}",
+                    &block_hash, &e
+                );
+                HttpResponseType::ServerError(
+                    response_metadata,
+                    format!("Failed to validate block: {}", &e),
+                )
+            }
+        };
+
+        response.send(http, fd).map(|_| ())
+    }
+
+    /// Handle a microblock.  Directly submit it to the microblock store so the client can see any
+    /// rejection reasons up-front (different from how the peer network handles it).  Indicate
+    /// whether or not the microblock was accepted (and thus needs to be forwarded) in the return
+    /// value.
+    fn handle_post_microblock<W: Write>(
+        http: &mut StacksHttp,
+        fd: &mut W,
+        req: &HttpRequestType,
+        consensus_hash: &ConsensusHash,
+        block_hash: &BlockHeaderHash,
+        chainstate: &mut StacksChainState,
+        microblock: &StacksMicroblock,
+        canonical_stacks_tip_height: u64,
+    ) -> Result<bool, net_error> {
+        let response_metadata =
+            HttpResponseMetadata::from_http_request_type(req, Some(canonical_stacks_tip_height));
+        let (response, accepted) =
+            match chainstate.preprocess_streamed_microblock(consensus_hash, block_hash, microblock)
+            {
+                Ok(accepted) => {
+                    if accepted {
+                        debug!(
+                            "Accepted uploaded microblock {}/{}-{}",
+                            &consensus_hash,
+                            &block_hash,
+                            &microblock.block_hash()
+                        );
+                    } else {
+                        debug!(
+                            "Did not accept microblock {}/{}-{}",
+                            &consensus_hash,
+                            &block_hash,
+                            &microblock.block_hash()
+                        );
+                    }
+
+                    (
+                        HttpResponseType::MicroblockHash(
+                            response_metadata,
+                            microblock.block_hash(),
+                        ),
+                        accepted,
+                    )
+                }
+                Err(e) => (
+                    HttpResponseType::BadRequestJSON(response_metadata, e.into_json()),
+                    false,
+                ),
+            };
+
+        response.send(http, fd).and_then(|_| Ok(accepted))
+    }
+
+    /// Handle a request for mempool transactions in bulk
+    fn handle_mempool_query<W: Write>(
+        http: &mut StacksHttp,
+        fd: &mut W,
+        req: &HttpRequestType,
+        sortdb: &SortitionDB,
+        chainstate: &StacksChainState,
+        query: MemPoolSyncData,
+        max_txs: u64,
+        canonical_stacks_tip_height: u64,
+        page_id: Option<Txid>,
+    ) -> Result<StreamCursor, net_error> {
+        let response_metadata =
+            HttpResponseMetadata::from_http_request_type(req, Some(canonical_stacks_tip_height));
+        let response = HttpResponseType::MemPoolTxStream(response_metadata);
+        let height = chainstate
+            .get_stacks_chain_tip(sortdb)?
+            .map(|blk| blk.height)
+            .unwrap_or(0);
+
+        debug!(
+            "Begin mempool query";
+            "page_id" => %page_id.map(|txid| format!("{}", &txid)).unwrap_or("(none".to_string()),
+            "block_height" => height,
+            "max_txs" => max_txs
+        );
+
+        let stream = StreamCursor::new_tx_stream(query, max_txs, height, page_id);
+        response.send(http, fd).and_then(|_| Ok(stream))
+    }
+
+    /// Handle an external HTTP request.
+    /// Some requests, such as those for blocks, will create new reply streams.  This method adds
+    /// those new streams into the `reply_streams` set.
+    /// Returns a StacksMessageType option -- it's Some(...) if we need to forward a message to the
+    /// peer network (like a transaction or a block or microblock)
+    pub fn handle_request(
+        &mut self,
+        req: HttpRequestType,
+        network: &mut PeerNetwork,
+        sortdb: &SortitionDB,
+        chainstate: &mut StacksChainState,
+        mempool: &mut MemPoolDB,
+        handler_opts: &RPCHandlerArgs,
+    ) -> Result<Option<StacksMessageType>, net_error> {
+        let mut reply = self.connection.make_relay_handle(self.conn_id)?;
+        let keep_alive = req.metadata().keep_alive;
+        let mut ret = None;
+
+        let response_signer = if self.connection.options.sign_rpc_responses {
+            Some(RPCResponseSigner {
+                private_key: network.local_peer.private_key.clone(),
+                chain_id: chainstate.chain_id,
+                tip: StacksBlockHeader::make_index_block_hash(
+                    &network.burnchain_tip.canonical_stacks_tip_consensus_hash,
+                    &network.burnchain_tip.canonical_stacks_tip_hash,
+                ),
+                request_path: strip_query(&req.request_path()).to_string(),
+            })
+        } else {
+            None
+        };
+        self.connection
+            .protocol
+            .set_response_signer(response_signer);
+
+        let stream_opt = match req {
+            HttpRequestType::GetInfo(ref _md) => {
+                ConversationHttp::handle_getinfo(
+                    &mut self.connection.protocol,
+                    &mut reply,
+                    &req,
+                    network,
+                    chainstate,
+                    handler_opts,
+                    network.burnchain_tip.canonical_stacks_tip_height,
+                )?;
+                None
+            }
+            HttpRequestType::GetNeighbors(ref _md) => {
+                ConversationHttp::handle_getneighbors(
+                    &mut self.connection.protocol,
+                    &mut reply,
+                    &req,
+                    network,
+                    network.burnchain_tip.canonical_stacks_tip_height,
+                )?;
+                None
+            }
+            HttpRequestType::GetHeaders(ref _md, ref quantity, ref tip_req) => {
+                if let Some(tip) = ConversationHttp::handle_load_stacks_chain_tip(
+                    &mut self.connection.protocol,
+                    &mut reply,
+                    &req,
+                    tip_req,
+                    sortdb,
+                    chainstate,
+                    network.burnchain_tip.canonical_stacks_tip_height,
+                )? {
+                    ConversationHttp::handle_getheaders(
+                        &mut self.connection.protocol,
+                        &mut reply,
+                        &req,
+                        &tip,
+                        *quantity,
+                        chainstate,
+                        network.burnchain_tip.canonical_stacks_tip_height,
+                    )?
+                } else {
+                    None
+                }
+            }
+            HttpRequestType::GetBlock(ref _md, ref index_block_hash) => {
+                ConversationHttp::handle_getblock(
+                    &mut self.connection.protocol,
+                    &mut reply,
+                    &req,
+                    index_block_hash,
+                    chainstate,
+                    network.burnchain_tip.canonical_stacks_tip_height,
+                )?
+            }
+            HttpRequestType::GetMicroblocksIndexed(ref _md, ref index_head_hash) => {
+                ConversationHttp::handle_getmicroblocks_indexed(
+                    &mut self.connection.protocol,
+                    &mut reply,
+                    &req,
+                    index_head_hash,
+                    chainstate,
+                    network.burnchain_tip.canonical_stacks_tip_height,
+                )?
+            }
+            HttpRequestType::GetMicroblocksConfirmed(ref _md, ref anchor_index_block_hash) => {
+                ConversationHttp::handle_getmicroblocks_confirmed(
+                    &mut self.connection.protocol,
+                    &mut reply,
+                    &req,
+                    anchor_index_block_hash,
+                    chainstate,
+                    network.burnchain_tip.canonical_stacks_tip_height,
+                )?
+            }
+            HttpRequestType::GetMicroblocksUnconfirmed(
+                ref _md,
+                ref index_anchor_block_hash,
+                ref min_seq,
+            ) => ConversationHttp::handle_getmicroblocks_unconfirmed(
+                &mut self.connection.protocol,
+                &mut reply,
+                &req,
+                index_anchor_block_hash,
+                *min_seq,
+                chainstate,
+                network.burnchain_tip.canonical_stacks_tip_height,
+            )?,
+            HttpRequestType::GetTransactionUnconfirmed(ref _md, ref txid) => {
+                ConversationHttp::handle_gettransaction_unconfirmed(
+                    &mut self.connection.protocol,
+                    &mut reply,
+                    &req,
+                    chainstate,
+                    mempool,
+                    txid,
+                    network.burnchain_tip.canonical_stacks_tip_height,
+                )?;
+                None
+            }
+            HttpRequestType::GetAccount(ref _md, ref principal, ref tip_req, ref with_proof) => {
+                if let Some(tip) = ConversationHttp::handle_load_stacks_chain_tip(
+                    &mut self.connection.protocol,
+                    &mut reply,
+                    &req,
+                    tip_req,
+                    sortdb,
+                    chainstate,
+                    network.burnchain_tip.canonical_stacks_tip_height,
+                )? {
+                    ConversationHttp::handle_get_account_entry(
+                        &mut self.connection.protocol,
+                        &mut reply,
+                        &req,
+                        sortdb,
+                        chainstate,
+                        &tip,
+                        principal,
+                        *with_proof,
+                        network.burnchain_tip.canonical_stacks_tip_height,
+                    )?;
+                }
+                None
+            }
+            HttpRequestType::SimulateTransaction(ref _md, ref tx, ref tip_req) => {
+                if let Some(tip) = ConversationHttp::handle_load_stacks_chain_tip(
+                    &mut self.connection.protocol,
+                    &mut reply,
+                    &req,
+                    tip_req,
+                    sortdb,
+                    chainstate,
+                    network.burnchain_tip.canonical_stacks_tip_height,
+                )? {
+                    ConversationHttp::handle_post_simulate_transaction(
+                        &mut self.connection.protocol,
+                        &mut reply,
+                        &req,
+                        sortdb,
+                        chainstate,
+                        &tip,
+                        tx,
+                        network.burnchain_tip.canonical_stacks_tip_height,
+                    )?;
+                }
+                None
+            }
+            HttpRequestType::GetMempoolAccount(ref _md, ref address, ref tip_req) => {
+                if let Some(tip) = ConversationHttp::handle_load_stacks_chain_tip(
+                    &mut self.connection.protocol,
+                    &mut reply,
+                    &req,
+                    tip_req,
+                    sortdb,
+                    chainstate,
+                    network.burnchain_tip.canonical_stacks_tip_height,
+                )? {
+                    ConversationHttp::handle_get_mempool_account(
+                        &mut self.connection.protocol,
+                        &mut reply,
+                        &req,
+                        sortdb,
+                        chainstate,
+                        mempool,
+                        &tip,
+                        address,
+                        network.burnchain_tip.canonical_stacks_tip_height,
+                    )?;
+                }
+                None
+            }
+            HttpRequestType::GetAccountNextNonce(ref _md, ref address, ref tip_req) => {
+                if let Some(tip) = ConversationHttp::handle_load_stacks_chain_tip(
+                    &mut self.connection.protocol,
+                    &mut reply,
+                    &req,
+                    tip_req,
+                    sortdb,
+                    chainstate,
+                    network.burnchain_tip.canonical_stacks_tip_height,
+                )? {
+                    ConversationHttp::handle_get_account_next_nonce(
+                        &mut self.connection.protocol,
+                        &mut reply,
+                        &req,
+                        sortdb,
+                        chainstate,
+                        mempool,
+                        &tip,
+                        address,
+                        network.burnchain_tip.canonical_stacks_tip_height,
+                    )?;
+                }
+                None
+            }
+            HttpRequestType::GetDataVar(
+                ref _md,
+                ref contract_addr,
+                ref contract_name,
+                ref var_name,
+                ref tip_req,
+                ref with_proof,
+            ) => {
+                if let Some(tip) = ConversationHttp::handle_load_stacks_chain_tip(
+                    &mut self.connection.protocol,
+                    &mut reply,
+                    &req,
+                    tip_req,
+                    sortdb,
+                    chainstate,
+                    network.burnchain_tip.canonical_stacks_tip_height,
+                )? {
+                    ConversationHttp::handle_get_data_var(
+                        &mut self.connection.protocol,
+                        &mut reply,
+                        &req,
+                        sortdb,
+                        chainstate,
+                        &tip,
+                        contract_addr,
+                        contract_name,
+                        var_name,
+                        *with_proof,
+                        network.burnchain_tip.canonical_stacks_tip_height,
+                    )?;
+                }
+                None
+            }
+            HttpRequestType::GetMapEntry(
+                ref _md,
+                ref contract_addr,
+                ref contract_name,
+                ref map_name,
+                ref key,
+                ref tip_req,
+                ref with_proof,
+            ) => {
+                if let Some(tip) = ConversationHttp::handle_load_stacks_chain_tip(
+                    &mut self.connection.protocol,
+                    &mut reply,
+                    &req,
+                    tip_req,
+                    sortdb,
+                    chainstate,
+                    network.burnchain_tip.canonical_stacks_tip_height,
+                )? {
+                    ConversationHttp::handle_get_map_entry(
+                        &mut self.connection.protocol,
+                        &mut reply,
+                        &req,
+                        sortdb,
+                        chainstate,
+                        &tip,
+                        contract_addr,
+                        contract_name,
+                        map_name,
+                        key,
+                        *with_proof,
+                        network.burnchain_tip.canonical_stacks_tip_height,
+                    )?;
+                }
+                None
+            }
+            HttpRequestType::GetTransferCost(ref _md) => {
+                ConversationHttp::handle_token_transfer_cost(
+                    &mut self.connection.protocol,
+                    &mut reply,
+                    &req,
+                    network.burnchain_tip.canonical_stacks_tip_height,
+                )?;
+                None
+            }
+            HttpRequestType::GetSubnetFeeRate(ref _md) => {
+                if let Some(tip) = ConversationHttp::handle_load_stacks_chain_tip(
+                    &mut self.connection.protocol,
+                    &mut reply,
+                    &req,
+                    &TipRequest::UseLatestAnchoredTip,
+                    sortdb,
+                    chainstate,
+                    network.burnchain_tip.canonical_stacks_tip_height,
+                )? {
+                    ConversationHttp::handle_get_subnet_fee_rate(
+                        &mut self.connection.protocol,
+                        &mut reply,
+                        &req,
+                        chainstate,
+                        &tip,
+                        network.burnchain_tip.canonical_stacks_tip_height,
+                    )?;
+                }
+                None
+            }
+            HttpRequestType::GetMempoolPreview { max_results, .. } => {
+                if let Some(tip) = ConversationHttp::handle_load_stacks_chain_tip(
+                    &mut self.connection.protocol,
+                    &mut reply,
+                    &req,
+                    &TipRequest::UseLatestAnchoredTip,
+                    sortdb,
+                    chainstate,
+                    network.burnchain_tip.canonical_stacks_tip_height,
+                )? {
+                    ConversationHttp::handle_get_mempool_preview(
+                        &mut self.connection.protocol,
+                        &mut reply,
+                        &req,
+                        sortdb,
+                        chainstate,
+                        mempool,
+                        &tip,
+                        max_results,
+                        network.burnchain_tip.canonical_stacks_tip_height,
+                    )?;
+                }
+                None
+            }
+            HttpRequestType::GetRejectedTxs {
+                ref query, limit, ..
+            } => {
+                ConversationHttp::handle_get_rejected_txs(
+                    &mut self.connection.protocol,
+                    &mut reply,
+                    &req,
+                    mempool,
+                    query,
+                    limit,
+                    network.burnchain_tip.canonical_stacks_tip_height,
+                )?;
+                None
+            }
+            HttpRequestType::GetMempoolStats(ref _md) => {
+                ConversationHttp::handle_get_mempool_stats(
+                    &mut self.connection.protocol,
+                    &mut reply,
+                    &req,
+                    mempool,
+                    network.burnchain_tip.canonical_stacks_tip_height,
+                )?;
+                None
+            }
+            HttpRequestType::GetMicroblockStream {
+                ref parent_block_id,
+                ..
+            } => {
+                ConversationHttp::handle_get_microblock_stream(
+                    &mut self.connection.protocol,
+                    &mut reply,
+                    &req,
+                    sortdb,
+                    chainstate,
+                    parent_block_id,
+                    network.burnchain_tip.canonical_stacks_tip_height,
+                )?;
+                None
+            }
+            HttpRequestType::GetSubnetInfo(ref _md) => {
+                ConversationHttp::handle_get_subnet_info(
+                    &mut self.connection.protocol,
+                    &mut reply,
+                    &req,
+                    network,
+                    sortdb,
+                    chainstate,
+                    handler_opts,
+                    network.burnchain_tip.canonical_stacks_tip_height,
+                )?;
+                None
+            }
+            HttpRequestType::GetHealthLive(ref _md) => {
+                ConversationHttp::handle_get_health_live(
+                    &mut self.connection.protocol,
+                    &mut reply,
+                    &req,
+                    network.burnchain_tip.canonical_stacks_tip_height,
+                )?;
+                None
+            }
+            HttpRequestType::GetHealthReady(ref _md) => {
+                ConversationHttp::handle_get_health_ready(
+                    &mut self.connection.protocol,
+                    &mut reply,
+                    &req,
+                    network,
+                    sortdb,
+                    chainstate,
+                    handler_opts,
+                    network.burnchain_tip.canonical_stacks_tip_height,
+                )?;
+                None
+            }
+            HttpRequestType::GetContractPublishPolicy(ref _md) => {
+                ConversationHttp::handle_get_contract_publish_policy(
+                    &mut self.connection.protocol,
+                    &mut reply,
+                    &req,
+                    chainstate,
+                    network.burnchain_tip.canonical_stacks_tip_height,
+                )?;
+                None
+            }
+            HttpRequestType::SetMinerPaused(ref _md, paused, ref auth_token) => {
+                ConversationHttp::handle_set_miner_paused(
+                    &mut self.connection.protocol,
+                    &mut reply,
+                    &req,
+                    self.connection.options.admin_auth_token.as_ref(),
+                    auth_token.as_ref(),
+                    handler_opts.miner_paused,
+                    paused,
+                    network.burnchain_tip.canonical_stacks_tip_height,
+                )?;
+                None
+            }
+            HttpRequestType::RotateMiningKey(ref _md, ref auth_token) => {
+                ConversationHttp::handle_rotate_mining_key(
+                    &mut self.connection.protocol,
+                    &mut reply,
+                    &req,
+                    self.connection.options.admin_auth_token.as_ref(),
+                    auth_token.as_ref(),
+                    handler_opts.mining_key_rotator,
+                    network.burnchain_tip.canonical_stacks_tip_height,
+                )?;
+                None
+            }
+            HttpRequestType::GetMemPoolFilters(ref _md, ref auth_token) => {
+                ConversationHttp::handle_get_mempool_filters(
+                    &mut self.connection.protocol,
+                    &mut reply,
+                    &req,
+                    self.connection.options.admin_auth_token.as_ref(),
+                    auth_token.as_ref(),
+                    mempool,
+                    network.burnchain_tip.canonical_stacks_tip_height,
+                )?;
+                None
+            }
+            HttpRequestType::InstallMemPoolFilter(ref _md, ref body, ref auth_token) => {
+                ConversationHttp::handle_install_mempool_filter(
+                    &mut self.connection.protocol,
+                    &mut reply,
+                    &req,
+                    self.connection.options.admin_auth_token.as_ref(),
+                    auth_token.as_ref(),
+                    mempool,
+                    body,
+                    network.burnchain_tip.canonical_stacks_tip_height,
+                )?;
+                None
+            }
+            HttpRequestType::RemoveMemPoolFilter(ref _md, filter_id, ref auth_token) => {
+                ConversationHttp::handle_remove_mempool_filter(
+                    &mut self.connection.protocol,
+                    &mut reply,
+                    &req,
+                    self.connection.options.admin_auth_token.as_ref(),
+                    auth_token.as_ref(),
+                    mempool,
+                    filter_id,
+                    network.burnchain_tip.canonical_stacks_tip_height,
+                )?;
+                None
+            }
+            HttpRequestType::GetContractABI(
+                ref _md,
+                ref contract_addr,
+                ref contract_name,
+                ref tip_req,
+            ) => {
+                if let Some(tip) = ConversationHttp::handle_load_stacks_chain_tip(
+                    &mut self.connection.protocol,
+                    &mut reply,
+                    &req,
+                    tip_req,
+                    sortdb,
+                    chainstate,
+                    network.burnchain_tip.canonical_stacks_tip_height,
+                )? {
+                    ConversationHttp::handle_get_contract_abi(
+                        &mut self.connection.protocol,
+                        &mut reply,
+                        &req,
+                        sortdb,
+                        chainstate,
+                        &tip,
+                        contract_addr,
+                        contract_name,
+                        network.burnchain_tip.canonical_stacks_tip_height,
+                    )?;
+                }
+                None
+            }
+            HttpRequestType::FeeRateEstimate(ref _md, ref tx, estimated_len) => {
+                ConversationHttp::handle_post_fee_rate_estimate(
+                    &mut self.connection.protocol,
+                    &mut reply,
+                    &req,
+                    handler_opts,
+                    sortdb,
+                    tx,
+                    estimated_len,
+                    network.burnchain_tip.canonical_stacks_tip_height,
+                )?;
+                None
+            }
+            HttpRequestType::CallReadOnlyFunction(
+                ref _md,
+                ref ctrct_addr,
+                ref ctrct_name,
+                ref as_sender,
+                ref func_name,
+                ref args,
+                ref tip_req,
+            ) => {
+                if let Some(tip) = ConversationHttp::handle_load_stacks_chain_tip(
+                    &mut self.connection.protocol,
+                    &mut reply,
+                    &req,
+                    tip_req,
+                    sortdb,
+                    chainstate,
+                    network.burnchain_tip.canonical_stacks_tip_height,
+                )? {
+                    ConversationHttp::handle_readonly_function_call(
+                        &mut self.connection.protocol,
+                        &mut reply,
+                        &req,
+                        sortdb,
+                        chainstate,
+                        &tip,
+                        ctrct_addr,
+                        ctrct_name,
+                        func_name,
+                        as_sender,
+                        None,
+                        args,
+                        &self.connection.options,
+                        network.burnchain_tip.canonical_stacks_tip_height,
+                    )?;
+                }
+                None
+            }
+            HttpRequestType::CallReadOnlyFunctionBatch {
+                ref sender,
+                ref calls,
+                ref tip,
+                ..
+            } => {
+                if let Some(tip) = ConversationHttp::handle_load_stacks_chain_tip(
+                    &mut self.connection.protocol,
+                    &mut reply,
+                    &req,
+                    tip,
+                    sortdb,
+                    chainstate,
+                    network.burnchain_tip.canonical_stacks_tip_height,
+                )? {
+                    ConversationHttp::handle_readonly_function_call_batch(
+                        &mut self.connection.protocol,
+                        &mut reply,
+                        &req,
+                        sortdb,
+                        chainstate,
+                        &tip,
+                        sender,
+                        calls,
+                        &self.connection.options,
+                        network.burnchain_tip.canonical_stacks_tip_height,
+                    )?;
+                }
+                None
+            }
+            HttpRequestType::GetContractSrc(
+                ref _md,
+                ref contract_addr,
+                ref contract_name,
+                ref tip_req,
+                ref with_proof,
+            ) => {
+                if let Some(tip) = ConversationHttp::handle_load_stacks_chain_tip(
+                    &mut self.connection.protocol,
+                    &mut reply,
+                    &req,
+                    tip_req,
+                    sortdb,
+                    chainstate,
+                    network.burnchain_tip.canonical_stacks_tip_height,
+                )? {
+                    ConversationHttp::handle_get_contract_src(
+                        &mut self.connection.protocol,
+                        &mut reply,
+                        &req,
+                        sortdb,
+                        chainstate,
+                        &tip,
+                        contract_addr,
+                        contract_name,
+                        *with_proof,
+                        network.burnchain_tip.canonical_stacks_tip_height,
+                    )?;
+                }
+                None
+            }
+            HttpRequestType::PostTransaction(
+                ref _md,
+                ref tx,
+                ref attachment,
+                expiration_height,
+                ref bundle_id,
+            ) if handler_opts.read_only => {
+                ConversationHttp::handle_forward_transaction(
+                    &mut self.connection.protocol,
+                    &mut reply,
+                    &req,
+                    tx,
+                    attachment.as_ref(),
+                    expiration_height,
+                    bundle_id.as_deref(),
+                    handler_opts.tx_forwarder,
+                    network.burnchain_tip.canonical_stacks_tip_height,
+                )?;
+                None
+            }
+            HttpRequestType::PostBlock(..) | HttpRequestType::PostMicroblock(..)
+                if handler_opts.read_only =>
+            {
+                ConversationHttp::handle_read_only_refusal(
+                    &mut self.connection.protocol,
+                    &mut reply,
+                    &req,
+                    network.burnchain_tip.canonical_stacks_tip_height,
+                )?;
+                None
+            }
+            HttpRequestType::PostTransaction(
+                ref _md,
+                ref tx,
+                ref attachment,
+                expiration_height,
+                ref bundle_id,
+            ) => {
+                match chainstate.get_stacks_chain_tip(sortdb)? {
+                    Some(tip) => {
+                        let accepted = ConversationHttp::handle_post_transaction(
+                            &mut self.connection.protocol,
+                            &mut reply,
+                            &req,
+                            chainstate,
+                            sortdb,
+                            tip.consensus_hash,
+                            tip.anchored_block_hash,
+                            mempool,
+                            tx.clone(),
+                            &mut network.atlasdb,
+                            attachment.clone(),
+                            expiration_height,
+                            bundle_id.as_deref(),
+                            handler_opts.event_observer.as_deref(),
+                            network.burnchain_tip.canonical_stacks_tip_height,
+                        )?;
+                        if accepted {
+                            // forward to peer network
+                            ret = Some(StacksMessageType::Transaction(tx.clone()));
+                        }
+                    }
+                    None => {
+                        let response_metadata = HttpResponseMetadata::from_http_request_type(
+                            &req,
+                            Some(network.burnchain_tip.canonical_stacks_tip_height),
+                        );
+                        warn!("Failed to load Stacks chain tip");
+                        let response = HttpResponseType::ServerError(
+                            response_metadata,
+                            format!("Failed to load Stacks chain tip"),
+                        );
+                        response.send(&mut self.connection.protocol, &mut reply)?;
+                    }
+                }
+                None
+            }
+            HttpRequestType::GetAttachment(ref _md, ref content_hash) => {
+                ConversationHttp::handle_getattachment(
+                    &mut self.connection.protocol,
+                    &mut reply,
+                    &req,
+                    &mut network.atlasdb,
+                    content_hash.clone(),
+                    network.burnchain_tip.canonical_stacks_tip_height,
+                )?;
+                None
+            }
+            HttpRequestType::GetAttachmentsInv(
+                ref _md,
+                ref index_block_hash,
+                ref pages_indexes,
+            ) => {
+                ConversationHttp::handle_getattachmentsinv(
+                    &mut self.connection.protocol,
+                    &mut reply,
+                    &req,
+                    &mut network.atlasdb,
+                    &index_block_hash,
+                    pages_indexes,
+                    &self.connection.options,
+                    network.burnchain_tip.canonical_stacks_tip_height,
+                )?;
+                None
+            }
+            HttpRequestType::PostBlock(ref _md, ref consensus_hash, ref block) => {
+                let accepted = ConversationHttp::handle_post_block(
+                    &mut self.connection.protocol,
+                    &mut reply,
+                    &req,
+                    sortdb,
+                    chainstate,
+                    consensus_hash,
+                    block,
+                    network.burnchain_tip.canonical_stacks_tip_height,
+                )?;
+                if accepted {
+                    // inform the peer network so it can announce its presence
+                    ret = Some(StacksMessageType::Blocks(BlocksData {
+                        blocks: vec![BlocksDatum(consensus_hash.clone(), block.clone())],
+                    }));
+                }
+                None
+            }
+            HttpRequestType::ValidateBlock(ref _md, ref parent_consensus_hash, ref block) => {
+                ConversationHttp::handle_post_validate_block(
+                    &mut self.connection.protocol,
+                    &mut reply,
+                    &req,
+                    sortdb,
+                    chainstate,
+                    parent_consensus_hash,
+                    block,
+                    network.burnchain_tip.canonical_stacks_tip_height,
+                )?;
+                None
+            }
+            HttpRequestType::PostMicroblock(ref _md, ref mblock, ref tip_req) => {
+                if let Some(tip) = ConversationHttp::handle_load_stacks_chain_tip(
+                    &mut self.connection.protocol,
+                    &mut reply,
+                    &req,
+                    tip_req,
+                    sortdb,
+                    chainstate,
+                    network.burnchain_tip.canonical_stacks_tip_height,
+                )? {
+                    if let Some((consensus_hash, block_hash)) =
+                        ConversationHttp::handle_load_stacks_chain_tip_hashes(
+                            &mut self.connection.protocol,
+                            &mut reply,
+                            &req,
+                            tip,
+                            chainstate,
+                            network.burnchain_tip.canonical_stacks_tip_height,
+                        )?
+                    {
+                        let accepted = ConversationHttp::handle_post_microblock(
+                            &mut self.connection.protocol,
+                            &mut reply,
+                            &req,
+                            &consensus_hash,
+                            &block_hash,
+                            chainstate,
+                            mblock,
+                            network.burnchain_tip.canonical_stacks_tip_height,
+                        )?;
+                        if accepted {
+                            // forward to peer network
+                            let tip = StacksBlockHeader::make_index_block_hash(
+                                &consensus_hash,
+                                &block_hash,
+                            );
+                            ret = Some(StacksMessageType::Microblocks(MicroblocksData {
+                                index_anchor_block: tip,
+                                microblocks: vec![(*mblock).clone()],
+                            }));
+                        }
+                    }
+                }
+                None
+            }
+            HttpRequestType::MemPoolQuery(ref _md, ref query, ref page_id_opt) => {
+                Some(ConversationHttp::handle_mempool_query(
+                    &mut self.connection.protocol,
+                    &mut reply,
+                    &req,
+                    sortdb,
+                    chainstate,
+                    query.clone(),
+                    network.connection_opts.mempool_max_tx_query,
+                    network.burnchain_tip.canonical_stacks_tip_height,
+                    page_id_opt.clone(),
+                )?)
+            }
+            HttpRequestType::OptionsPreflight(ref _md, ref _path) => {
+                let response_metadata = HttpResponseMetadata::from_http_request_type(
+                    &req,
+                    Some(network.burnchain_tip.canonical_stacks_tip_height),
+                );
+                let response = HttpResponseType::OptionsPreflight(response_metadata);
+                response
+                    .send(&mut self.connection.protocol, &mut reply)
+                    .map(|_| ())?;
+                None
+            }
+            HttpRequestType::GetIsTraitImplemented(
+                ref _md,
+                ref contract_addr,
+                ref contract_name,
+                ref trait_id,
+                ref tip_req,
+            ) => {
+                if let Some(tip) = ConversationHttp::handle_load_stacks_chain_tip(
+                    &mut self.connection.protocol,
+                    &mut reply,
+                    &req,
+                    tip_req,
+                    sortdb,
+                    chainstate,
+                    network.burnchain_tip.canonical_stacks_tip_height,
+                )? {
+                    ConversationHttp::handle_get_is_trait_implemented(
+                        &mut self.connection.protocol,
+                        &mut reply,
+                        &req,
+                        sortdb,
+                        chainstate,
+                        &tip,
+                        contract_addr,
+                        contract_name,
+                        trait_id,
+                        network.burnchain_tip.canonical_stacks_tip_height,
+                    )?;
+                }
+                None
+            }
+            HttpRequestType::ClientError(ref _md, ref err) => {
+                let response_metadata = HttpResponseMetadata::from_http_request_type(
+                    &req,
+                    Some(network.burnchain_tip.canonical_stacks_tip_height),
+                );
+                let response = match err {
+                    ClientError::Message(s) => HttpResponseType::BadRequestJSON(
+                        response_metadata,
+                        serde_json::Value::String(s.to_string()),
+                    ),
+                    ClientError::NotFound(path) => {
+                        HttpResponseType::NotFound(response_metadata, path.clone())
+                    }
+                };
+
+                response
+                    .send(&mut self.connection.protocol, &mut reply)
+                    .map(|_| ())?;
+                None
+            }
+            HttpRequestType::GetWithdrawalsBySender {
+                ref sender,
+                offset,
+                limit,
+                ..
+            } => {
+                if let Some(tip) = ConversationHttp::handle_load_stacks_chain_tip(
+                    &mut self.connection.protocol,
+                    &mut reply,
+                    &req,
+                    &TipRequest::UseLatestAnchoredTip,
+                    sortdb,
+                    chainstate,
+                    network.burnchain_tip.canonical_stacks_tip_height,
+                )? {
+                    ConversationHttp::handle_get_withdrawals_by_sender(
+                        &mut self.connection.protocol,
+                        &mut reply,
+                        &req,
+                        chainstate,
+                        &tip,
+                        sender,
+                        offset,
+                        limit,
+                        network.burnchain_tip.canonical_stacks_tip_height,
+                    )?;
+                }
+                None
+            }
+            HttpRequestType::GetWithdrawalClaim {
+                block_height,
+                withdrawal_id,
+                ..
+            } => {
+                if let Some(tip) = ConversationHttp::handle_load_stacks_chain_tip(
+                    &mut self.connection.protocol,
+                    &mut reply,
+                    &req,
+                    &TipRequest::UseLatestAnchoredTip,
+                    sortdb,
+                    chainstate,
+                    network.burnchain_tip.canonical_stacks_tip_height,
+                )? {
+                    ConversationHttp::handle_get_withdrawal_claim(
+                        &mut self.connection.protocol,
+                        &mut reply,
+                        &req,
+                        sortdb,
+                        chainstate,
+                        &tip,
+                        block_height,
+                        withdrawal_id,
+                        handler_opts.l1_oracle,
+                        network.burnchain_tip.block_height,
+                        network.burnchain_tip.canonical_stacks_tip_height,
+                    )?;
+                }
+                None
+            }
+            HttpRequestType::GetAccountTransactions {
+                ref principal,
+                offset,
+                limit,
+                ..
+            } => {
+                if let Some(tip) = ConversationHttp::handle_load_stacks_chain_tip(
+                    &mut self.connection.protocol,
+                    &mut reply,
+                    &req,
+                    &TipRequest::UseLatestAnchoredTip,
+                    sortdb,
+                    chainstate,
+                    network.burnchain_tip.canonical_stacks_tip_height,
+                )? {
+                    ConversationHttp::handle_get_account_transactions(
+                        &mut self.connection.protocol,
+                        &mut reply,
+                        &req,
+                        chainstate,
+                        &tip,
+                        principal,
+                        offset,
+                        limit,
+                        network.burnchain_tip.canonical_stacks_tip_height,
+                    )?;
+                }
+                None
+            }
+            HttpRequestType::GetFailedDepositsBySender {
+                ref sender,
+                offset,
+                limit,
+                ..
+            } => {
+                if let Some(tip) = ConversationHttp::handle_load_stacks_chain_tip(
+                    &mut self.connection.protocol,
+                    &mut reply,
+                    &req,
+                    &TipRequest::UseLatestAnchoredTip,
+                    sortdb,
+                    chainstate,
+                    network.burnchain_tip.canonical_stacks_tip_height,
+                )? {
+                    ConversationHttp::handle_get_failed_deposits_by_sender(
+                        &mut self.connection.protocol,
+                        &mut reply,
+                        &req,
+                        chainstate,
+                        &tip,
+                        sender,
+                        offset,
+                        limit,
+                        network.burnchain_tip.canonical_stacks_tip_height,
+                    )?;
+                }
+                None
+            }
+            HttpRequestType::GetBurnOps { burn_height, .. } => {
+                ConversationHttp::handle_get_burn_ops(
+                    &mut self.connection.protocol,
+                    &mut reply,
+                    &req,
+                    sortdb,
+                    &network.burnchain,
+                    &network.burnchain_tip.sortition_id,
+                    network.burnchain_tip.block_height,
+                    burn_height,
+                    network.burnchain_tip.canonical_stacks_tip_height,
+                )?;
+                None
+            }
+            HttpRequestType::GetForks { burn_blocks, .. } => {
+                ConversationHttp::handle_get_forks(
+                    &mut self.connection.protocol,
+                    &mut reply,
+                    &req,
+                    sortdb,
+                    chainstate,
+                    burn_blocks,
+                    network.burnchain_tip.canonical_stacks_tip_height,
+                )?;
+                None
+            }
+            HttpRequestType::GetContractCosts { blocks, .. } => {
+                ConversationHttp::handle_get_contract_costs(
+                    &mut self.connection.protocol,
+                    &mut reply,
+                    &req,
+                    sortdb,
+                    chainstate,
+                    blocks,
+                    network.burnchain_tip.canonical_stacks_tip_height,
+                )?;
+                None
+            }
+            HttpRequestType::GetBlockCommits {
+                l2_height, count, ..
+            } => {
+                ConversationHttp::handle_get_block_commits(
+                    &mut self.connection.protocol,
+                    &mut reply,
+                    &req,
+                    sortdb,
+                    chainstate,
+                    l2_height,
+                    count,
+                    network.burnchain_tip.canonical_stacks_tip_height,
+                )?;
+                None
+            }
+            HttpRequestType::GetRewardsReport {
+                from_height,
+                to_height,
+                ..
+            } => {
+                ConversationHttp::handle_get_rewards_report(
+                    &mut self.connection.protocol,
+                    &mut reply,
+                    &req,
+                    sortdb,
+                    chainstate,
+                    from_height,
+                    to_height,
+                    handler_opts.commit_fees,
+                    network.burnchain_tip.canonical_stacks_tip_height,
+                )?;
+                None
+            }
+            HttpRequestType::GetEventReplay {
+                from_height,
+                to_height,
+                ref keys,
+                ..
+            } => {
+                ConversationHttp::handle_get_event_replay(
+                    &mut self.connection.protocol,
+                    &mut reply,
+                    &req,
+                    sortdb,
+                    chainstate,
+                    from_height,
+                    to_height,
+                    keys,
+                    handler_opts.event_replay,
+                    network.burnchain_tip.canonical_stacks_tip_height,
+                )?;
+                None
+            }
+            HttpRequestType::GetWithdrawalStx {
+                withdraw_block_height,
+                ref sender,
+                withdrawal_id,
+                amount,
+                ..
+            } => {
+                if let Some(tip) = ConversationHttp::handle_load_stacks_chain_tip(
+                    &mut self.connection.protocol,
+                    &mut reply,
+                    &req,
+                    &TipRequest::UseLatestAnchoredTip,
+                    sortdb,
+                    chainstate,
+                    network.burnchain_tip.canonical_stacks_tip_height,
+                )? {
+                    ConversationHttp::handle_get_withdrawal_stx_entry(
+                        &mut self.connection.protocol,
+                        &mut reply,
+                        &req,
+                        chainstate,
+                        &tip,
+                        withdraw_block_height,
+                        &sender.clone(),
+                        withdrawal_id,
+                        amount,
+                        network.burnchain_tip.canonical_stacks_tip_height,
+                    )?;
+                }
+                None
+            }
+            HttpRequestType::BlockProposal(_, ref proposal) => {
+                let validator_key = self.connection.options.subnet_validator.as_ref();
+                let signing_contract = self.connection.options.subnet_signing_contract.as_ref();
+
+                // TODO(#135): add sender validation. This method should only
+                //  be sent by the leader: we should not accept proposals
+                //  not signed by the leader
+                ConversationHttp::handle_validate_block_proposal(
+                    &mut self.connection.protocol,
+                    &mut reply,
+                    &req,
+                    chainstate,
+                    sortdb,
+                    &proposal,
+                    validator_key,
+                    signing_contract,
+                    &self.connection.options,
+                    network.burnchain_tip.canonical_stacks_tip_height,
+                )?;
+                None
+            }
+
+            HttpRequestType::GetWithdrawalFt {
+                withdraw_block_height,
+                ref sender,
+                withdrawal_id,
+                amount,
+                ref contract_identifier,
+                ..
+            } => {
+                if let Some(tip) = ConversationHttp::handle_load_stacks_chain_tip(
+                    &mut self.connection.protocol,
+                    &mut reply,
+                    &req,
+                    &TipRequest::UseLatestAnchoredTip,
+                    sortdb,
+                    chainstate,
+                    network.burnchain_tip.canonical_stacks_tip_height,
+                )? {
+                    ConversationHttp::handle_get_withdrawal_ft_entry(
+                        &mut self.connection.protocol,
+                        &mut reply,
+                        &req,
+                        chainstate,
+                        &tip,
+                        withdraw_block_height,
+                        &sender.clone(),
+                        withdrawal_id,
+                        contract_identifier,
+                        amount,
+                        network.burnchain_tip.canonical_stacks_tip_height,
+                    )?;
+                }
+                None
+            }
+
+            HttpRequestType::GetWithdrawalNft {
+                withdraw_block_height,
+                ref sender,
+                withdrawal_id,
+                id,
+                ref contract_identifier,
+                ..
+            } => {
+                if let Some(tip) = ConversationHttp::handle_load_stacks_chain_tip(
+                    &mut self.connection.protocol,
+                    &mut reply,
+                    &req,
+                    &TipRequest::UseLatestAnchoredTip,
+                    sortdb,
+                    chainstate,
+                    network.burnchain_tip.canonical_stacks_tip_height,
+                )? {
+                    ConversationHttp::handle_get_withdrawal_nft_entry(
+                        &mut self.connection.protocol,
+                        &mut reply,
+                        &req,
+                        chainstate,
+                        &tip,
+                        withdraw_block_height,
+                        &sender.clone(),
+                        withdrawal_id,
+                        contract_identifier,
+                        id,
+                        network.burnchain_tip.canonical_stacks_tip_height,
+                    )?;
+                }
+                None
+            }
+
+            HttpRequestType::GetWithdrawalStxTransfer {
+                withdraw_block_height,
+                ref sender,
+                withdrawal_id,
+                amount,
+                ref recipient,
+                ref destination_subnet,
+                ..
+            } => {
+                if let Some(tip) = ConversationHttp::handle_load_stacks_chain_tip(
+                    &mut self.connection.protocol,
+                    &mut reply,
+                    &req,
+                    &TipRequest::UseLatestAnchoredTip,
+                    sortdb,
+                    chainstate,
+                    network.burnchain_tip.canonical_stacks_tip_height,
+                )? {
+                    ConversationHttp::handle_get_withdrawal_stx_transfer_entry(
+                        &mut self.connection.protocol,
+                        &mut reply,
+                        &req,
+                        chainstate,
+                        &tip,
+                        withdraw_block_height,
+                        sender,
+                        withdrawal_id,
+                        amount,
+                        recipient,
+                        destination_subnet,
+                        network.burnchain_tip.canonical_stacks_tip_height,
+                    )?;
+                }
+                None
+            }
+        };
+
+        match stream_opt {
+            None => {
+                self.reply_streams.push_back((reply, None, keep_alive));
+            }
+            Some(stream) => {
+                self.reply_streams.push_back((
+                    reply,
+                    Some((
+                        HttpChunkedTransferWriterState::new(STREAM_CHUNK_SIZE as usize),
+                        stream,
+                    )),
+                    keep_alive,
+                ));
+            }
+        }
+        Ok(ret)
+    }
+
+    /// Make progress on outbound requests.
+    fn send_outbound_responses(
+        &mut self,
+        mempool: &MemPoolDB,
+        chainstate: &mut StacksChainState,
+    ) -> Result<(), net_error> {
+        // send out streamed responses in the order they were requested
+        let mut drained_handle = false;
+        let mut drained_stream = false;
+        let mut broken = false;
+        let mut do_keep_alive = true;
+
+        test_debug!(
+            "{:?}: {} HTTP replies pending",
+            &self,
+            self.reply_streams.len()
+        );
+        let _self_str = format!("{}", &self);
+
+        match self.reply_streams.front_mut() {
+            Some((ref mut reply, ref mut stream_opt, ref keep_alive)) => {
+                do_keep_alive = *keep_alive;
+
+                // if we're streaming, make some progress on the stream
+                match stream_opt {
+                    Some((ref mut http_chunk_state, ref mut stream)) => {
+                        let mut encoder =
+                            HttpChunkedTransferWriter::from_writer_state(reply, http_chunk_state);
+                        match stream.stream_to(mempool, chainstate, &mut encoder, STREAM_CHUNK_SIZE)
+                        {
+                            Ok(nw) => {
+                                test_debug!("{}: Streamed {} bytes", &_self_str, nw);
+                                if nw == 0 {
+                                    // EOF -- finish chunk and stop sending.
+                                    if !encoder.corked() {
+                                        encoder.flush().map_err(|e| {
+                                            test_debug!(
+                                                "{}: Write error on encoder flush: {:?}",
+                                                &_self_str,
+                                                &e
+                                            );
+                                            net_error::WriteError(e)
+                                        })?;
+
+                                        encoder.cork();
+
+                                        test_debug!("{}: Stream indicates EOF", &_self_str);
+                                    }
+
+                                    // try moving some data to the connection only once we're done
+                                    // streaming
+                                    match reply.try_flush() {
+                                        Ok(res) => {
+                                            test_debug!(
+                                                "{}: Streamed reply is drained?: {}",
+                                                &_self_str,
+                                                res
+                                            );
+                                            drained_handle = res;
+                                        }
+                                        Err(e) => {
+                                            // dead
+                                            warn!(
+                                                "{}: Broken HTTP connection: {:?}",
+                                                &_self_str, &e
+                                            );
+                                            broken = true;
+                                        }
+                                    }
+                                    drained_stream = true;
+                                }
+                            }
+                            Err(e) => {
+                                // broken -- terminate the stream.
+                                // For example, if we're streaming an unconfirmed block or
+                                // microblock, the data can get moved to the chunk store out from
+                                // under the stream.
+                                warn!(
+                                    "{}: Failed to send to HTTP connection: {:?}",
+                                    &_self_str, &e
+                                );
+                                broken = true;
+                            }
+                        }
+                    }
+                    None => {
+                        // not streamed; all data is buffered
+                        drained_stream = true;
+
+                        // try moving some data to the connection
+                        match reply.try_flush() {
+                            Ok(res) => {
+                                test_debug!("{}: Reply is drained", &_self_str);
+                                drained_handle = res;
+                            }
+                            Err(e) => {
+                                // dead
+                                warn!("{}: Broken HTTP connection: {:?}", &_self_str, &e);
+                                broken = true;
+                            }
+                        }
+                    }
+                }
+            }
+            None => {}
+        }
+
+        if broken || (drained_handle && drained_stream) {
+            // done with this stream
+            test_debug!(
+                "{:?}: done with stream (broken={}, drained_handle={}, drained_stream={})",
+                &self,
+                broken,
+                drained_handle,
+                drained_stream
+            );
+            self.total_reply_count += 1;
+            self.reply_streams.pop_front();
+
+            if !do_keep_alive {
+                // encountered "Connection: close"
+                self.keep_alive = false;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn try_send_recv_response(
+        req: ReplyHandleHttp,
+    ) -> Result<HttpResponseType, Result<ReplyHandleHttp, net_error>> {
+        match req.try_send_recv() {
+            Ok(message) => match message {
+                StacksHttpMessage::Request(_) => {
+                    warn!("Received response: not a HTTP response");
+                    return Err(Err(net_error::InvalidMessage));
+                }
+                StacksHttpMessage::Response(http_response) => Ok(http_response),
+            },
+            Err(res) => Err(res),
+        }
+    }
+
+    /// Make progress on our request/response
+    fn recv_inbound_response(&mut self) -> Result<(), net_error> {
+        // make progress on our pending request (if it exists).
+        let inprogress = self.pending_request.is_some();
+        let is_pending = self.pending_response.is_none();
+
+        let pending_request = self.pending_request.take();
+        let response = match pending_request {
+            None => Ok(self.pending_response.take()),
+            Some(req) => match ConversationHttp::try_send_recv_response(req) {
+                Ok(response) => Ok(Some(response)),
+                Err(res) => match res {
+                    Ok(handle) => {
+                        // try again
+                        self.pending_request = Some(handle);
+                        Ok(self.pending_response.take())
+                    }
+                    Err(e) => Err(e),
+                },
+            },
+        }?;
+
+        self.pending_response = response;
+
+        if inprogress && self.pending_request.is_none() {
+            test_debug!(
+                "{:?},id={}: HTTP request finished",
+                &self.peer_host,
+                self.conn_id
+            );
+        }
+
+        if is_pending && self.pending_response.is_some() {
+            test_debug!(
+                "{:?},id={}: HTTP response finished",
+                &self.peer_host,
+                self.conn_id
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Try to get our response
+    pub fn try_get_response(&mut self) -> Option<HttpResponseType> {
+        self.pending_response.take()
+    }
+
+    /// Make progress on in-flight messages.
+    pub fn try_flush(
+        &mut self,
+        mempool: &MemPoolDB,
+        chainstate: &mut StacksChainState,
+    ) -> Result<(), net_error> {
+        self.send_outbound_responses(mempool, chainstate)?;
+        self.recv_inbound_response()?;
+        Ok(())
+    }
+
+    /// Is the connection idle?
+    pub fn is_idle(&self) -> bool {
+        self.pending_response.is_none()
+            && self.connection.inbox_len() == 0
+            && self.connection.outbox_len() == 0
+            && self.reply_streams.len() == 0
+    }
+
+    /// Is the conversation out of pending data?
+    /// Don't consider it drained if we haven't received anything yet
+    pub fn is_drained(&self) -> bool {
+        ((self.total_request_count > 0 && self.total_reply_count > 0)
+            || self.pending_error_response.is_some())
+            && self.is_idle()
+    }
+
+    /// Should the connection be kept alive even if drained?
+    pub fn is_keep_alive(&self) -> bool {
+        self.keep_alive
+    }
+
+    /// When was the last time we got an inbound request?
+    pub fn get_last_request_time(&self) -> u64 {
+        self.last_request_timestamp
+    }
+
+    /// When was the last time we sent data as part of an outbound response?
+    pub fn get_last_response_time(&self) -> u64 {
+        self.last_response_timestamp
+    }
+
+    /// When was this converation conencted?
+    pub fn get_connection_time(&self) -> u64 {
+        self.connection_time
+    }
+
+    /// Refuse a request from a client that has exceeded its rate limit, with a 429
+    fn reply_throttled(
+        &mut self,
+        req: &HttpRequestType,
+        network: &PeerNetwork,
+    ) -> Result<(), net_error> {
+        let mut reply = self.connection.make_relay_handle(self.conn_id)?;
+        let response_metadata = HttpResponseMetadata::from_http_request_type(
+            req,
+            Some(network.burnchain_tip.canonical_stacks_tip_height),
+        );
+        let response = HttpResponseType::TooManyRequests(
+            response_metadata,
+            "Rate limit exceeded; try again later".to_string(),
+        );
+        response.send(&mut self.connection.protocol, &mut reply)?;
+        self.reply_streams
+            .push_back((reply, None, req.metadata().keep_alive));
+        Ok(())
+    }
+
+    /// Refuse a request which needs an API key but did not carry a valid one, with a 401
+    fn reply_unauthorized(
+        &mut self,
+        req: &HttpRequestType,
+        network: &PeerNetwork,
+    ) -> Result<(), net_error> {
+        let mut reply = self.connection.make_relay_handle(self.conn_id)?;
+        let response_metadata = HttpResponseMetadata::from_http_request_type(
+            req,
+            Some(network.burnchain_tip.canonical_stacks_tip_height),
+        );
+        let response = HttpResponseType::Unauthorized(
+            response_metadata,
+            "Missing or invalid API key".to_string(),
+        );
+        response.send(&mut self.connection.protocol, &mut reply)?;
+        self.reply_streams
+            .push_back((reply, None, req.metadata().keep_alive));
+        Ok(())
+    }
+
+    /// Make progress on in-flight requests and replies.
+    /// Returns the list of transactions we'll need to forward to the peer network
+    pub fn chat(
+        &mut self,
+        network: &mut PeerNetwork,
+        sortdb: &SortitionDB,
+        chainstate: &mut StacksChainState,
+        mempool: &mut MemPoolDB,
+        handler_args: &RPCHandlerArgs,
+    ) -> Result<Vec<StacksMessageType>, net_error> {
+        // if we have an in-flight error, then don't take any more requests.
+        if self.pending_error_response.is_some() {
+            return Ok(vec![]);
+        }
+
+        // handle in-bound HTTP request(s)
+        let num_inbound = self.connection.inbox_len();
+        let mut ret = vec![];
+        test_debug!("{:?}: {} HTTP requests pending", &self, num_inbound);
+
+        for _i in 0..num_inbound {
+            let msg = match self.connection.next_inbox_message() {
+                None => {
+                    continue;
+                }
+                Some(m) => m,
+            };
+
+            match msg {
+                StacksHttpMessage::Request(req) => {
+                    // new request
+                    self.total_request_count += 1;
+                    self.last_request_timestamp = get_epoch_time_secs();
+                    if req.metadata().canonical_stacks_tip_height.is_some() {
+                        test_debug!(
+                            "Request metadata: canonical stacks tip height is {:?}",
+                            &req.metadata().canonical_stacks_tip_height
+                        );
+                        self.canonical_stacks_tip_height =
+                            req.metadata().canonical_stacks_tip_height;
+                    }
+
+                    let class = RPCRequestClass::of(&req);
+                    if !network.rpc_rate_limiter.try_acquire(
+                        self.peer_addr.ip(),
+                        class,
+                        get_epoch_time_ms(),
+                    ) {
+                        debug!("Throttled HTTPRequest"; "path" => %req.get_path(), "class" => class.as_str(), "conn_id" => self.conn_id, "peer_addr" => &self.peer_addr);
+                        monitoring::increment_rpc_requests_throttled(class.as_str());
+                        self.reply_throttled(&req, network)?;
+                        continue;
+                    }
+
+                    if network.rpc_api_keys.is_enabled() && RPCApiKeys::requires_api_key(&req) {
+                        match network
+                            .rpc_api_keys
+                            .check(req.metadata().api_key.as_deref(), get_epoch_time_ms())
+                        {
+                            ApiKeyCheck::Accepted(name) => {
+                                monitoring::increment_rpc_api_key_requests(&name, "accepted");
+                            }
+                            ApiKeyCheck::Throttled(name) => {
+                                debug!("Throttled HTTPRequest"; "path" => %req.get_path(), "api_key" => &name, "conn_id" => self.conn_id, "peer_addr" => &self.peer_addr);
+                                monitoring::increment_rpc_api_key_requests(&name, "throttled");
+                                self.reply_throttled(&req, network)?;
+                                continue;
+                            }
+                            ApiKeyCheck::Missing | ApiKeyCheck::Invalid => {
+                                debug!("Unauthorized HTTPRequest"; "path" => %req.get_path(), "conn_id" => self.conn_id, "peer_addr" => &self.peer_addr);
+                                monitoring::increment_rpc_api_key_requests(
+                                    "unknown",
+                                    "unauthorized",
+                                );
+                                self.reply_unauthorized(&req, network)?;
+                                continue;
+                            }
+                        }
+                    }
+
+                    let start_time = Instant::now();
+                    let path = req.get_path();
+                    let msg_opt = monitoring::instrument_http_request_handler(req, |req| {
+                        self.handle_request(req, network, sortdb, chainstate, mempool, handler_args)
+                    })?;
+
+                    debug!("Processed HTTPRequest"; "path" => %path, "processing_time_ms" => start_time.elapsed().as_millis(), "conn_id" => self.conn_id, "peer_addr" => &self.peer_addr);
+
+                    if let Some(msg) = msg_opt {
+                        ret.push(msg);
+                    }
+                }
+                StacksHttpMessage::Response(resp) => {
+                    // Is there someone else waiting for this message?  If so, pass it along.
+                    // (this _should_ be our pending_request handle)
+                    if resp.metadata().canonical_stacks_tip_height.is_some() {
+                        test_debug!(
+                            "Response metadata: canonical stacks tip height is {:?}",
+                            &resp.metadata().canonical_stacks_tip_height
+                        );
+                        self.canonical_stacks_tip_height =
+                            resp.metadata().canonical_stacks_tip_height;
+                    }
+                    match self
+                        .connection
+                        .fulfill_request(StacksHttpMessage::Response(resp))
+                    {
+                        None => {
+                            test_debug!("{:?}: Fulfilled pending HTTP request", &self);
+                        }
+                        Some(_msg) => {
+                            // unsolicited; discard
+                            test_debug!("{:?}: Dropping unsolicited HTTP response", &self);
+                        }
+                    }
+                }
+            }
+        }
+
+        Ok(ret)
+    }
+
+    /// Remove all timed-out messages, and ding the remote peer as unhealthy
+    pub fn clear_timeouts(&mut self) -> () {
+        self.connection.drain_timeouts();
+    }
+
+    /// Load data into our HTTP connection
+    pub fn recv<R: Read>(&mut self, r: &mut R) -> Result<usize, net_error> {
+        let mut total_recv = 0;
+        loop {
+            let nrecv = match self.connection.recv_data(r) {
+                Ok(nr) => nr,
+                Err(e) => {
+                    debug!("{:?}: failed to recv: {:?}", self, &e);
+                    return Err(e);
+                }
+            };
+
+            total_recv += nrecv;
+            if nrecv > 0 {
+                self.last_request_timestamp = get_epoch_time_secs();
+            } else {
+                break;
+            }
+        }
+        monitoring::update_inbound_rpc_bandwidth(total_recv as i64);
+        Ok(total_recv)
+    }
+
+    /// Write data out of our HTTP connection.  Write as much as we can
+    pub fn send<W: Write>(
+        &mut self,
+        w: &mut W,
+        mempool: &MemPoolDB,
+        chainstate: &mut StacksChainState,
+    ) -> Result<usize, net_error> {
+        let mut total_sz = 0;
+        loop {
+            // prime the Write
+            self.try_flush(mempool, chainstate)?;
+
+            let sz = match self.connection.send_data(w) {
+                Ok(sz) => sz,
+                Err(e) => {
+                    info!("{:?}: failed to send on HTTP conversation: {:?}", self, &e);
+                    return Err(e);
+                }
+            };
+
+            total_sz += sz;
+            if sz > 0 {
+                self.last_response_timestamp = get_epoch_time_secs();
+            } else {
+                break;
+            }
+        }
+        monitoring::update_inbound_rpc_bandwidth(total_sz as i64);
+        Ok(total_sz)
+    }
+
+    /// Make a new getinfo request to this endpoint
+    pub fn new_getinfo(&self, stacks_height: Option<u64>) -> HttpRequestType {
+        HttpRequestType::GetInfo(HttpRequestMetadata::from_host(
+            self.peer_host.clone(),
+            stacks_height,
+        ))
+    }
+
+    /// Make a new getneighbors request to this endpoint
+    pub fn new_getneighbors(&self) -> HttpRequestType {
+        HttpRequestType::GetNeighbors(HttpRequestMetadata::from_host(self.peer_host.clone(), None))
+    }
+
+    /// Make a new getheaders request to this endpoint
+    pub fn new_getheaders(&self, quantity: u64, tip_req: TipRequest) -> HttpRequestType {
+        HttpRequestType::GetHeaders(
+            HttpRequestMetadata::from_host(self.peer_host.clone(), None),
+            quantity,
+            tip_req,
+        )
+    }
+
+    /// Make a new getblock request to this endpoint
+    pub fn new_getblock(&self, index_block_hash: StacksBlockId) -> HttpRequestType {
+        HttpRequestType::GetBlock(
+            HttpRequestMetadata::from_host(self.peer_host.clone(), None),
+            index_block_hash,
+        )
+    }
+
+    /// Make a new get-microblocks request to this endpoint
+    pub fn new_getmicroblocks_indexed(
+        &self,
+        index_microblock_hash: StacksBlockId,
+    ) -> HttpRequestType {
+        HttpRequestType::GetMicroblocksIndexed(
+            HttpRequestMetadata::from_host(self.peer_host.clone(), None),
+            index_microblock_hash,
+        )
+    }
+
+    /// Make a new get-microblocks-confirmed request to this endpoint
+    pub fn new_getmicroblocks_confirmed(
+        &self,
+        index_anchor_block_hash: StacksBlockId,
+    ) -> HttpRequestType {
+        HttpRequestType::GetMicroblocksConfirmed(
+            HttpRequestMetadata::from_host(self.peer_host.clone(), None),
+            index_anchor_block_hash,
+        )
+    }
+
+    /// Make a new get-microblocks request for unconfirmed microblocks
+    pub fn new_getmicroblocks_unconfirmed(
+        &self,
+        anchored_index_block_hash: StacksBlockId,
+        min_seq: u16,
+    ) -> HttpRequestType {
+        HttpRequestType::GetMicroblocksUnconfirmed(
+            HttpRequestMetadata::from_host(self.peer_host.clone(), None),
+            anchored_index_block_hash,
+            min_seq,
+        )
+    }
+
+    /// Make a new get-unconfirmed-tx request
+    pub fn new_gettransaction_unconfirmed(&self, txid: Txid) -> HttpRequestType {
+        HttpRequestType::GetTransactionUnconfirmed(
+            HttpRequestMetadata::from_host(self.peer_host.clone(), None),
+            txid,
+        )
+    }
+
+    /// Make a new post-transaction request
+    pub fn new_post_transaction(&self, tx: StacksTransaction) -> HttpRequestType {
+        HttpRequestType::PostTransaction(
+            HttpRequestMetadata::from_host(self.peer_host.clone(), None),
+            tx,
+            None,
+            None,
+            None,
+        )
+    }
+
+    /// Make a new post-block request
+    pub fn new_post_block(&self, ch: ConsensusHash, block: StacksBlock) -> HttpRequestType {
+        HttpRequestType::PostBlock(
+            HttpRequestMetadata::from_host(self.peer_host.clone(), None),
+            ch,
+            block,
+        )
+    }
+
+    /// Make a new request to validate a proposed block on top of the parent block elected at
+    /// `parent_ch`
+    pub fn new_validate_block(
+        &self,
+        parent_ch: ConsensusHash,
+        block: StacksBlock,
+    ) -> HttpRequestType {
+        HttpRequestType::ValidateBlock(
+            HttpRequestMetadata::from_host(self.peer_host.clone(), None),
+            parent_ch,
+            block,
+        )
+    }
+
+    /// Make a new post-microblock request
+    pub fn new_post_microblock(
+        &self,
+        mblock: StacksMicroblock,
+        tip_req: TipRequest,
+    ) -> HttpRequestType {
+        HttpRequestType::PostMicroblock(
+            HttpRequestMetadata::from_host(self.peer_host.clone(), None),
+            mblock,
+            tip_req,
+        )
+    }
+
+    /// Make a new request for an account
+    pub fn new_getaccount(
+        &self,
+        principal: PrincipalData,
+        tip_req: TipRequest,
+        with_proof: bool,
+    ) -> HttpRequestType {
+        HttpRequestType::GetAccount(
+            HttpRequestMetadata::from_host(self.peer_host.clone(), None),
+            principal,
+            tip_req,
+            with_proof,
+        )
+    }
+
+    /// Make a new request to simulate a transaction
+    pub fn new_simulate_transaction(
+        &self,
+        tx: StacksTransaction,
+        tip_req: TipRequest,
+    ) -> HttpRequestType {
+        HttpRequestType::SimulateTransaction(
+            HttpRequestMetadata::from_host(self.peer_host.clone(), None),
+            tx,
+            tip_req,
+        )
+    }
+
+    /// Make a new request for a page of a principal's withdrawals
+    pub fn new_get_withdrawals_by_sender(
+        &self,
+        sender: PrincipalData,
+        offset: u64,
+        limit: u64,
+    ) -> HttpRequestType {
+        HttpRequestType::GetWithdrawalsBySender {
+            metadata: HttpRequestMetadata::from_host(self.peer_host.clone(), None),
+            sender,
+            offset,
+            limit,
+        }
+    }
+
+    /// Make a new request for whether a withdrawal can be claimed on the L1 yet
+    pub fn new_get_withdrawal_claim(
+        &self,
+        block_height: u64,
+        withdrawal_id: u32,
+    ) -> HttpRequestType {
+        HttpRequestType::GetWithdrawalClaim {
+            metadata: HttpRequestMetadata::from_host(self.peer_host.clone(), None),
+            block_height,
+            withdrawal_id,
+        }
+    }
+
+    /// Make a new request for a page of the transactions touching a principal
+    pub fn new_get_account_transactions(
+        &self,
+        principal: PrincipalData,
+        offset: u64,
+        limit: u64,
+    ) -> HttpRequestType {
+        HttpRequestType::GetAccountTransactions {
+            metadata: HttpRequestMetadata::from_host(self.peer_host.clone(), None),
+            principal,
+            offset,
+            limit,
+        }
+    }
+
+    /// Make a new request for a page of a principal's failed deposits
+    pub fn new_get_failed_deposits_by_sender(
+        &self,
+        sender: PrincipalData,
+        offset: u64,
+        limit: u64,
+    ) -> HttpRequestType {
+        HttpRequestType::GetFailedDepositsBySender {
+            metadata: HttpRequestMetadata::from_host(self.peer_host.clone(), None),
+            sender,
+            offset,
+            limit,
+        }
+    }
+
+    /// Make a new request for the burnchain operations recognized at an L1 height
+    pub fn new_get_burn_ops(&self, burn_height: u64) -> HttpRequestType {
+        HttpRequestType::GetBurnOps {
+            metadata: HttpRequestMetadata::from_host(self.peer_host.clone(), None),
+            burn_height,
+        }
+    }
+
+    /// Make a new request for the tree of L2 blocks committed in the last `burn_blocks` L1 blocks
+    pub fn new_get_forks(&self, burn_blocks: u64) -> HttpRequestType {
+        HttpRequestType::GetForks {
+            metadata: HttpRequestMetadata::from_host(self.peer_host.clone(), None),
+            burn_blocks,
+        }
+    }
+
+    /// Make a new request for the costs of the contracts used in the last `blocks` blocks
+    pub fn new_get_contract_costs(&self, blocks: u64) -> HttpRequestType {
+        HttpRequestType::GetContractCosts {
+            metadata: HttpRequestMetadata::from_host(self.peer_host.clone(), None),
+            blocks,
+        }
+    }
+
+    /// Make a new request for the L1 commits of the `count` canonical L2 blocks starting at
+    /// height `l2_height`
+    pub fn new_get_block_commits(&self, l2_height: u64, count: u64) -> HttpRequestType {
+        HttpRequestType::GetBlockCommits {
+            metadata: HttpRequestMetadata::from_host(self.peer_host.clone(), None),
+            l2_height,
+            count,
+        }
+    }
+
+    /// Make a new request for what each miner earned from the canonical L2 blocks from
+    /// `from_height` up to and including `to_height`
+    pub fn new_get_rewards_report(&self, from_height: u64, to_height: u64) -> HttpRequestType {
+        HttpRequestType::GetRewardsReport {
+            metadata: HttpRequestMetadata::from_host(self.peer_host.clone(), None),
+            from_height,
+            to_height,
+        }
+    }
+
+    /// Make a new request for the event observer payloads of the canonical L2 blocks from
+    /// `from_height` up to and including `to_height`, for an observer subscribed with `keys`
+    pub fn new_get_event_replay(
+        &self,
+        from_height: u64,
+        to_height: u64,
+        keys: Vec<String>,
+    ) -> HttpRequestType {
+        HttpRequestType::GetEventReplay {
+            metadata: HttpRequestMetadata::from_host(self.peer_host.clone(), None),
+            from_height,
+            to_height,
+            keys,
+        }
+    }
+
+    /// Make a new request for the order a miner would consider mempool transactions in
+    pub fn new_get_mempool_preview(&self, max_results: u64) -> HttpRequestType {
+        HttpRequestType::GetMempoolPreview {
+            metadata: HttpRequestMetadata::from_host(self.peer_host.clone(), None),
+            max_results,
+        }
+    }
+
+    /// Make a new request for the mempool's kept rejections of the transactions `query` names
+    pub fn new_get_rejected_txs(&self, query: RejectedTxsQuery, limit: u64) -> HttpRequestType {
+        HttpRequestType::GetRejectedTxs {
+            metadata: HttpRequestMetadata::from_host(self.peer_host.clone(), None),
+            query,
+            limit,
+        }
+    }
+
+    /// Make a new request for a summary of the mempool's contents
+    pub fn new_get_mempool_stats(&self) -> HttpRequestType {
+        HttpRequestType::GetMempoolStats(HttpRequestMetadata::from_host(
+            self.peer_host.clone(),
+            None,
+        ))
+    }
+
+    /// Make a new request for an account's pending mempool transactions
+    pub fn new_get_mempool_account(
+        &self,
+        address: StacksAddress,
+        tip_req: TipRequest,
+    ) -> HttpRequestType {
+        HttpRequestType::GetMempoolAccount(
+            HttpRequestMetadata::from_host(self.peer_host.clone(), None),
+            address,
+            tip_req,
+        )
+    }
+
+    /// Make a new request for the nonce an account's next transaction should use
+    pub fn new_get_account_next_nonce(
+        &self,
+        address: StacksAddress,
+        tip_req: TipRequest,
+    ) -> HttpRequestType {
+        HttpRequestType::GetAccountNextNonce(
+            HttpRequestMetadata::from_host(self.peer_host.clone(), None),
+            address,
+            tip_req,
+        )
+    }
+
+    /// Make a new request for the fee market's minimum fee rate
+    pub fn new_get_subnet_fee_rate(&self) -> HttpRequestType {
+        HttpRequestType::GetSubnetFeeRate(HttpRequestMetadata::from_host(
+            self.peer_host.clone(),
+            None,
+        ))
+    }
+
+    /// Make a new request for the subnet this node follows
+    pub fn new_get_subnet_info(&self) -> HttpRequestType {
+        HttpRequestType::GetSubnetInfo(HttpRequestMetadata::from_host(self.peer_host.clone(), None))
+    }
+
+    /// Make a new request for the microblock stream built on the anchored block
+    /// `parent_block_id`
+    pub fn new_get_microblock_stream(&self, parent_block_id: StacksBlockId) -> HttpRequestType {
+        HttpRequestType::GetMicroblockStream {
+            metadata: HttpRequestMetadata::from_host(self.peer_host.clone(), None),
+            parent_block_id,
+        }
+    }
+
+    /// Make a new liveness probe
+    pub fn new_get_health_live(&self) -> HttpRequestType {
+        HttpRequestType::GetHealthLive(HttpRequestMetadata::from_host(self.peer_host.clone(), None))
+    }
+
+    /// Make a new readiness probe
+    pub fn new_get_health_ready(&self) -> HttpRequestType {
+        HttpRequestType::GetHealthReady(HttpRequestMetadata::from_host(
+            self.peer_host.clone(),
+            None,
+        ))
+    }
+
+    /// Make a new request for this node's contract publish policy
+    pub fn new_get_contract_publish_policy(&self) -> HttpRequestType {
+        HttpRequestType::GetContractPublishPolicy(HttpRequestMetadata::from_host(
+            self.peer_host.clone(),
+            None,
+        ))
+    }
+
+    /// Make a new request to pause or resume this node's miner
+    pub fn new_set_miner_paused(
+        &self,
+        paused: bool,
+        auth_token: Option<String>,
+    ) -> HttpRequestType {
+        HttpRequestType::SetMinerPaused(
+            HttpRequestMetadata::from_host(self.peer_host.clone(), None),
+            paused,
+            auth_token,
+        )
+    }
+
+    /// Make a new request to rotate this node's mining key
+    pub fn new_rotate_mining_key(&self, auth_token: Option<String>) -> HttpRequestType {
+        HttpRequestType::RotateMiningKey(
+            HttpRequestMetadata::from_host(self.peer_host.clone(), None),
+            auth_token,
+        )
+    }
+
+    /// Make a new request for this node's unexpired mempool filters
+    pub fn new_get_mempool_filters(&self, auth_token: Option<String>) -> HttpRequestType {
+        HttpRequestType::GetMemPoolFilters(
+            HttpRequestMetadata::from_host(self.peer_host.clone(), None),
+            auth_token,
+        )
+    }
+
+    /// Make a new request to install a mempool filter
+    pub fn new_install_mempool_filter(
+        &self,
+        body: MemPoolFilterRequestBody,
+        auth_token: Option<String>,
+    ) -> HttpRequestType {
+        HttpRequestType::InstallMemPoolFilter(
+            HttpRequestMetadata::from_host(self.peer_host.clone(), None),
+            body,
+            auth_token,
+        )
+    }
+
+    /// Make a new request to remove a mempool filter
+    pub fn new_remove_mempool_filter(
+        &self,
+        filter_id: u64,
+        auth_token: Option<String>,
+    ) -> HttpRequestType {
+        HttpRequestType::RemoveMemPoolFilter(
+            HttpRequestMetadata::from_host(self.peer_host.clone(), None),
+            filter_id,
+            auth_token,
+        )
+    }
+
+    /// Make a new request for a data var
+    pub fn new_getdatavar(
+        &self,
+        contract_addr: StacksAddress,
+        contract_name: ContractName,
+        var_name: ClarityName,
+        tip_req: TipRequest,
+        with_proof: bool,
+    ) -> HttpRequestType {
+        HttpRequestType::GetDataVar(
+            HttpRequestMetadata::from_host(self.peer_host.clone(), None),
+            contract_addr,
+            contract_name,
+            var_name,
+            tip_req,
+            with_proof,
+        )
+    }
+
+    /// Make a new request for a data map
+    pub fn new_getmapentry(
+        &self,
+        contract_addr: StacksAddress,
+        contract_name: ContractName,
+        map_name: ClarityName,
+        key: Value,
+        tip_req: TipRequest,
+        with_proof: bool,
+    ) -> HttpRequestType {
+        HttpRequestType::GetMapEntry(
+            HttpRequestMetadata::from_host(self.peer_host.clone(), None),
+            contract_addr,
+            contract_name,
+            map_name,
+            key,
+            tip_req,
+            with_proof,
+        )
+    }
 
-                response
-                    .send(&mut self.connection.protocol, &mut reply)
-                    .map(|_| ())?;
-                None
-            }
-            HttpRequestType::GetWithdrawalStx {
-                withdraw_block_height,
-                ref sender,
-                withdrawal_id,
-                amount,
-                ..
-            } => {
-                if let Some(tip) = ConversationHttp::handle_load_stacks_chain_tip(
-                    &mut self.connection.protocol,
-                    &mut reply,
-                    &req,
-                    &TipRequest::UseLatestAnchoredTip,
-                    sortdb,
-                    chainstate,
-                    network.burnchain_tip.canonical_stacks_tip_height,
-                )? {
-                    ConversationHttp::handle_get_withdrawal_stx_entry(
-                        &mut self.connection.protocol,
-                        &mut reply,
-                        &req,
-                        chainstate,
-                        &tip,
-                        withdraw_block_height,
-                        &sender.clone(),
-                        withdrawal_id,
-                        amount,
-                        network.burnchain_tip.canonical_stacks_tip_height,
-                    )?;
-                }
-                None
-            }
-            HttpRequestType::BlockProposal(_, ref proposal) => {
-                let validator_key = self.connection.options.subnet_validator.as_ref();
-                let signing_contract = self.connection.options.subnet_signing_contract.as_ref();
+    /// Make a new request to get a contract's source
+    pub fn new_getcontractsrc(
+        &self,
+        contract_addr: StacksAddress,
+        contract_name: ContractName,
+        tip_req: TipRequest,
+        with_proof: bool,
+    ) -> HttpRequestType {
+        HttpRequestType::GetContractSrc(
+            HttpRequestMetadata::from_host(self.peer_host.clone(), None),
+            contract_addr,
+            contract_name,
+            tip_req,
+            with_proof,
+        )
+    }
 
-                // TODO(#135): add sender validation. This method should only
-                //  be sent by the leader: we should not accept proposals
-                //  not signed by the leader
-                ConversationHttp::handle_validate_block_proposal(
-                    &mut self.connection.protocol,
-                    &mut reply,
-                    &req,
-                    chainstate,
-                    sortdb,
-                    &proposal,
-                    validator_key,
-                    signing_contract,
-                    &self.connection.options,
-                    network.burnchain_tip.canonical_stacks_tip_height,
-                )?;
-                None
-            }
+    /// Make a new request to get a contract's ABI
+    pub fn new_getcontractabi(
+        &self,
+        contract_addr: StacksAddress,
+        contract_name: ContractName,
+        tip_req: TipRequest,
+    ) -> HttpRequestType {
+        HttpRequestType::GetContractABI(
+            HttpRequestMetadata::from_host(self.peer_host.clone(), None),
+            contract_addr,
+            contract_name,
+            tip_req,
+        )
+    }
 
-            HttpRequestType::GetWithdrawalFt {
-                withdraw_block_height,
-                ref sender,
-                withdrawal_id,
-                amount,
-                ref contract_identifier,
-                ..
-            } => {
-                if let Some(tip) = ConversationHttp::handle_load_stacks_chain_tip(
-                    &mut self.connection.protocol,
-                    &mut reply,
-                    &req,
-                    &TipRequest::UseLatestAnchoredTip,
-                    sortdb,
-                    chainstate,
-                    network.burnchain_tip.canonical_stacks_tip_height,
-                )? {
-                    ConversationHttp::handle_get_withdrawal_ft_entry(
-                        &mut self.connection.protocol,
-                        &mut reply,
-                        &req,
-                        chainstate,
-                        &tip,
-                        withdraw_block_height,
-                        &sender.clone(),
-                        withdrawal_id,
-                        contract_identifier,
-                        amount,
-                        network.burnchain_tip.canonical_stacks_tip_height,
-                    )?;
-                }
-                None
-            }
+    /// Make a new request to run a read-only function
+    pub fn new_callreadonlyfunction(
+        &self,
+        contract_addr: StacksAddress,
+        contract_name: ContractName,
+        sender: PrincipalData,
+        function_name: ClarityName,
+        function_args: Vec<Value>,
+        tip_req: TipRequest,
+    ) -> HttpRequestType {
+        HttpRequestType::CallReadOnlyFunction(
+            HttpRequestMetadata::from_host(self.peer_host.clone(), None),
+            contract_addr,
+            contract_name,
+            sender,
+            function_name,
+            function_args,
+            tip_req,
+        )
+    }
 
-            HttpRequestType::GetWithdrawalNft {
-                withdraw_block_height,
-                ref sender,
-                withdrawal_id,
-                id,
-                ref contract_identifier,
-                ..
-            } => {
-                if let Some(tip) = ConversationHttp::handle_load_stacks_chain_tip(
-                    &mut self.connection.protocol,
-                    &mut reply,
-                    &req,
-                    &TipRequest::UseLatestAnchoredTip,
-                    sortdb,
-                    chainstate,
-                    network.burnchain_tip.canonical_stacks_tip_height,
-                )? {
-                    ConversationHttp::handle_get_withdrawal_nft_entry(
-                        &mut self.connection.protocol,
-                        &mut reply,
-                        &req,
-                        chainstate,
-                        &tip,
-                        withdraw_block_height,
-                        &sender.clone(),
-                        withdrawal_id,
-                        contract_identifier,
-                        id,
-                        network.burnchain_tip.canonical_stacks_tip_height,
-                    )?;
-                }
-                None
-            }
-        };
+    /// Make a new request to run a batch of read-only functions
+    pub fn new_callreadonlyfunctionbatch(
+        &self,
+        sender: PrincipalData,
+        calls: Vec<ReadOnlyCall>,
+        tip: TipRequest,
+    ) -> HttpRequestType {
+        HttpRequestType::CallReadOnlyFunctionBatch {
+            metadata: HttpRequestMetadata::from_host(self.peer_host.clone(), None),
+            sender,
+            calls,
+            tip,
+        }
+    }
+
+    /// Make a new request for attachment inventory page
+    pub fn new_getattachmentsinv(
+        &self,
+        index_block_hash: StacksBlockId,
+        pages_indexes: HashSet<u32>,
+    ) -> HttpRequestType {
+        HttpRequestType::GetAttachmentsInv(
+            HttpRequestMetadata::from_host(self.peer_host.clone(), None),
+            index_block_hash,
+            pages_indexes,
+        )
+    }
+
+    /// Make a new request for mempool contents
+    pub fn new_mempool_query(
+        &self,
+        query: MemPoolSyncData,
+        page_id_opt: Option<Txid>,
+    ) -> HttpRequestType {
+        HttpRequestType::MemPoolQuery(
+            HttpRequestMetadata::from_host(self.peer_host.clone(), None),
+            query,
+            page_id_opt,
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::cell::RefCell;
+    use std::convert::TryInto;
+    use std::iter::FromIterator;
+
+    use crate::burnchains::Burnchain;
+    use crate::burnchains::BurnchainView;
+    use crate::burnchains::*;
+    use crate::chainstate::burn::ConsensusHash;
+    use crate::chainstate::stacks::db::account_index::AccountRole;
+    use crate::chainstate::stacks::db::blocks::test::*;
+    use crate::chainstate::stacks::db::forks::ForkStatus;
+    use crate::chainstate::stacks::db::StacksChainState;
+    use crate::chainstate::stacks::db::StreamCursor;
+    use crate::chainstate::stacks::events::StacksTransactionReceipt;
+    use crate::chainstate::stacks::miner::*;
+    use crate::chainstate::stacks::test::*;
+    use crate::chainstate::stacks::Error as chain_error;
+    use crate::chainstate::stacks::*;
+    use crate::net::codec::*;
+    use crate::net::http::*;
+    use crate::net::test::*;
+    use crate::net::*;
+    use clarity::vm::types::*;
+    use stacks_common::address::*;
+    use stacks_common::util::get_epoch_time_secs;
+    use stacks_common::util::hash::hex_bytes;
+    use stacks_common::util::pipe::*;
+
+    use crate::chainstate::burn::operations::BlockstackOperationType;
+    use crate::chainstate::stacks::C32_ADDRESS_VERSION_TESTNET_SINGLESIG;
+    use crate::types::chainstate::BlockHeaderHash;
+    use crate::types::chainstate::BurnchainHeaderHash;
+
+    use crate::core::mempool::{BLOOM_COUNTER_ERROR_RATE, MAX_BLOOM_COUNTER_TXS};
+
+    use super::*;
+
+    const TEST_CONTRACT: &'static str = "
+        (define-data-var bar int 0)
+        (define-map unit-map { account: principal } { units: int })
+        (define-public (get-bar) (ok (var-get bar)))
+        (define-public (set-bar (x int) (y int))
+          (begin (var-set bar (/ x y)) (ok (var-get bar))))
+        (define-public (add-unit)
+          (begin
+            (map-set unit-map { account: tx-sender } { units: 1 } )
+            (var-set bar 1)
+            (ok 1)))
+        (begin
+          (map-set unit-map { account: 'ST2DS4MSWSGJ3W9FBC6BVT0Y92S345HY8N3T6AV7R } { units: 123 }))";
 
-        match stream_opt {
-            None => {
-                self.reply_streams.push_back((reply, None, keep_alive));
-            }
-            Some(stream) => {
-                self.reply_streams.push_back((
-                    reply,
-                    Some((
-                        HttpChunkedTransferWriterState::new(STREAM_CHUNK_SIZE as usize),
-                        stream,
-                    )),
-                    keep_alive,
-                ));
+    const TEST_CONTRACT_UNCONFIRMED: &'static str = "(define-read-only (ro-test) (ok 1))";
+
+    fn convo_send_recv(
+        sender: &mut ConversationHttp,
+        sender_mempool: &MemPoolDB,
+        sender_chainstate: &mut StacksChainState,
+        receiver: &mut ConversationHttp,
+        receiver_mempool: &MemPoolDB,
+        receiver_chainstate: &mut StacksChainState,
+    ) -> () {
+        let (mut pipe_read, mut pipe_write) = Pipe::new();
+        pipe_read.set_nonblocking(true);
+
+        loop {
+            let res = true;
+
+            sender.try_flush(sender_mempool, sender_chainstate).unwrap();
+            receiver
+                .try_flush(sender_mempool, receiver_chainstate)
+                .unwrap();
+
+            pipe_write.try_flush().unwrap();
+
+            let all_relays_flushed =
+                receiver.num_pending_outbound() == 0 && sender.num_pending_outbound() == 0;
+
+            let nw = sender
+                .send(&mut pipe_write, sender_mempool, sender_chainstate)
+                .unwrap();
+            let nr = receiver.recv(&mut pipe_read).unwrap();
+
+            test_debug!(
+                "res = {}, all_relays_flushed = {} ({},{}), nr = {}, nw = {}",
+                res,
+                all_relays_flushed,
+                receiver.num_pending_outbound(),
+                sender.num_pending_outbound(),
+                nr,
+                nw
+            );
+            if res && all_relays_flushed && nr == 0 && nw == 0 {
+                test_debug!("Breaking send_recv");
+                break;
             }
         }
-        Ok(ret)
     }
 
-    /// Make progress on outbound requests.
-    fn send_outbound_responses(
-        &mut self,
-        mempool: &MemPoolDB,
-        chainstate: &mut StacksChainState,
-    ) -> Result<(), net_error> {
-        // send out streamed responses in the order they were requested
-        let mut drained_handle = false;
-        let mut drained_stream = false;
-        let mut broken = false;
-        let mut do_keep_alive = true;
+    /// General testing function to test RPC calls.
+    /// This function sets up two peers, a client and a server.
+    /// It takes in a function of type F that generates the request to be sent to the server
+    /// It takes in another function of type C that verifies that the result from
+    /// the server is as expected.
+    /// The parameter `include_microblocks` determines whether a microblock stream is mined or not.
+    fn test_rpc<F, C>(
+        test_name: &str,
+        peer_1_p2p: u16,
+        peer_1_http: u16,
+        peer_2_p2p: u16,
+        peer_2_http: u16,
+        include_microblocks: bool,
+        make_request: F,
+        check_result: C,
+    ) -> ()
+    where
+        F: FnOnce(
+            &mut TestPeer,
+            &mut ConversationHttp,
+            &mut TestPeer,
+            &mut ConversationHttp,
+        ) -> HttpRequestType,
+        C: FnOnce(
+            &HttpRequestType,
+            &HttpResponseType,
+            &mut TestPeer,
+            &mut TestPeer,
+            &ConversationHttp,
+            &ConversationHttp,
+        ) -> bool,
+    {
+        test_rpc_with_handler_args(
+            test_name,
+            peer_1_p2p,
+            peer_1_http,
+            peer_2_p2p,
+            peer_2_http,
+            include_microblocks,
+            &RPCHandlerArgs::default(),
+            make_request,
+            check_result,
+        )
+    }
 
-        test_debug!(
-            "{:?}: {} HTTP replies pending",
-            &self,
-            self.reply_streams.len()
-        );
-        let _self_str = format!("{}", &self);
+    /// Like `test_rpc`, but the server handles the request with `server_handler_args`
+    fn test_rpc_with_handler_args<F, C>(
+        test_name: &str,
+        peer_1_p2p: u16,
+        peer_1_http: u16,
+        peer_2_p2p: u16,
+        peer_2_http: u16,
+        include_microblocks: bool,
+        server_handler_args: &RPCHandlerArgs,
+        make_request: F,
+        check_result: C,
+    ) -> ()
+    where
+        F: FnOnce(
+            &mut TestPeer,
+            &mut ConversationHttp,
+            &mut TestPeer,
+            &mut ConversationHttp,
+        ) -> HttpRequestType,
+        C: FnOnce(
+            &HttpRequestType,
+            &HttpResponseType,
+            &mut TestPeer,
+            &mut TestPeer,
+            &ConversationHttp,
+            &ConversationHttp,
+        ) -> bool,
+    {
+        let mut peer_1_config = TestPeerConfig::new(test_name, peer_1_p2p, peer_1_http);
+        let mut peer_2_config = TestPeerConfig::new(test_name, peer_2_p2p, peer_2_http);
 
-        match self.reply_streams.front_mut() {
-            Some((ref mut reply, ref mut stream_opt, ref keep_alive)) => {
-                do_keep_alive = *keep_alive;
+        // ST2DS4MSWSGJ3W9FBC6BVT0Y92S345HY8N3T6AV7R
+        let privk1 = StacksPrivateKey::from_hex(
+            "9f1f85a512a96a244e4c0d762788500687feb97481639572e3bffbd6860e6ab001",
+        )
+        .unwrap();
 
-                // if we're streaming, make some progress on the stream
-                match stream_opt {
-                    Some((ref mut http_chunk_state, ref mut stream)) => {
-                        let mut encoder =
-                            HttpChunkedTransferWriter::from_writer_state(reply, http_chunk_state);
-                        match stream.stream_to(mempool, chainstate, &mut encoder, STREAM_CHUNK_SIZE)
-                        {
-                            Ok(nw) => {
-                                test_debug!("{}: Streamed {} bytes", &_self_str, nw);
-                                if nw == 0 {
-                                    // EOF -- finish chunk and stop sending.
-                                    if !encoder.corked() {
-                                        encoder.flush().map_err(|e| {
-                                            test_debug!(
-                                                "{}: Write error on encoder flush: {:?}",
-                                                &_self_str,
-                                                &e
-                                            );
-                                            net_error::WriteError(e)
-                                        })?;
+        // STVN97YYA10MY5F6KQJHKNYJNM24C4A1AT39WRW
+        let privk2 = StacksPrivateKey::from_hex(
+            "94c319327cc5cd04da7147d32d836eb2e4c44f4db39aa5ede7314a761183d0c701",
+        )
+        .unwrap();
+        let microblock_privkey = StacksPrivateKey::new();
+        let microblock_pubkeyhash =
+            Hash160::from_node_public_key(&StacksPublicKey::from_private(&microblock_privkey));
 
-                                        encoder.cork();
+        let addr1 = StacksAddress::from_public_keys(
+            C32_ADDRESS_VERSION_TESTNET_SINGLESIG,
+            &AddressHashMode::SerializeP2PKH,
+            1,
+            &vec![StacksPublicKey::from_private(&privk1)],
+        )
+        .unwrap();
+        let addr2 = StacksAddress::from_public_keys(
+            C32_ADDRESS_VERSION_TESTNET_SINGLESIG,
+            &AddressHashMode::SerializeP2PKH,
+            1,
+            &vec![StacksPublicKey::from_private(&privk2)],
+        )
+        .unwrap();
 
-                                        test_debug!("{}: Stream indicates EOF", &_self_str);
-                                    }
+        peer_1_config.initial_balances = vec![
+            (addr1.to_account_principal(), 1000000000),
+            (addr2.to_account_principal(), 1000000000),
+        ];
 
-                                    // try moving some data to the connection only once we're done
-                                    // streaming
-                                    match reply.try_flush() {
-                                        Ok(res) => {
-                                            test_debug!(
-                                                "{}: Streamed reply is drained?: {}",
-                                                &_self_str,
-                                                res
-                                            );
-                                            drained_handle = res;
-                                        }
-                                        Err(e) => {
-                                            // dead
-                                            warn!(
-                                                "{}: Broken HTTP connection: {:?}",
-                                                &_self_str, &e
-                                            );
-                                            broken = true;
-                                        }
-                                    }
-                                    drained_stream = true;
-                                }
-                            }
-                            Err(e) => {
-                                // broken -- terminate the stream.
-                                // For example, if we're streaming an unconfirmed block or
-                                // microblock, the data can get moved to the chunk store out from
-                                // under the stream.
-                                warn!(
-                                    "{}: Failed to send to HTTP connection: {:?}",
-                                    &_self_str, &e
-                                );
-                                broken = true;
-                            }
-                        }
-                    }
-                    None => {
-                        // not streamed; all data is buffered
-                        drained_stream = true;
+        peer_2_config.initial_balances = vec![
+            (addr1.to_account_principal(), 1000000000),
+            (addr2.to_account_principal(), 1000000000),
+        ];
+
+        peer_1_config.add_neighbor(&peer_2_config.to_neighbor());
+        peer_2_config.add_neighbor(&peer_1_config.to_neighbor());
+
+        let mut peer_1 = TestPeer::new(peer_1_config);
+        let mut peer_2 = TestPeer::new(peer_2_config);
+
+        // mine one block with a contract in it
+        // first the coinbase
+        // make a coinbase for this miner
+        let mut tx_coinbase = StacksTransaction::new(
+            TransactionVersion::Testnet,
+            TransactionAuth::from_p2pkh(&privk1).unwrap(),
+            TransactionPayload::Coinbase(CoinbasePayload([0x00; 32])),
+        );
+        tx_coinbase.chain_id = 0x80000000;
+        tx_coinbase.anchor_mode = TransactionAnchorMode::OnChainOnly;
+        tx_coinbase.auth.set_origin_nonce(0);
 
-                        // try moving some data to the connection
-                        match reply.try_flush() {
-                            Ok(res) => {
-                                test_debug!("{}: Reply is drained", &_self_str);
-                                drained_handle = res;
-                            }
-                            Err(e) => {
-                                // dead
-                                warn!("{}: Broken HTTP connection: {:?}", &_self_str, &e);
-                                broken = true;
-                            }
-                        }
-                    }
-                }
-            }
-            None => {}
-        }
+        let mut tx_signer = StacksTransactionSigner::new(&tx_coinbase);
+        tx_signer.sign_origin(&privk1).unwrap();
+        let tx_coinbase_signed = tx_signer.get_tx().unwrap();
 
-        if broken || (drained_handle && drained_stream) {
-            // done with this stream
-            test_debug!(
-                "{:?}: done with stream (broken={}, drained_handle={}, drained_stream={})",
-                &self,
-                broken,
-                drained_handle,
-                drained_stream
-            );
-            self.total_reply_count += 1;
-            self.reply_streams.pop_front();
+        // next the contract
+        let contract = TEST_CONTRACT.clone();
+        let mut tx_contract = StacksTransaction::new(
+            TransactionVersion::Testnet,
+            TransactionAuth::from_p2pkh(&privk1).unwrap(),
+            TransactionPayload::new_smart_contract(
+                &format!("hello-world"),
+                &contract.to_string(),
+                None,
+            )
+            .unwrap(),
+        );
 
-            if !do_keep_alive {
-                // encountered "Connection: close"
-                self.keep_alive = false;
-            }
-        }
-        Ok(())
-    }
+        tx_contract.chain_id = 0x80000000;
+        tx_contract.auth.set_origin_nonce(1);
+        tx_contract.set_tx_fee(0);
 
-    pub fn try_send_recv_response(
-        req: ReplyHandleHttp,
-    ) -> Result<HttpResponseType, Result<ReplyHandleHttp, net_error>> {
-        match req.try_send_recv() {
-            Ok(message) => match message {
-                StacksHttpMessage::Request(_) => {
-                    warn!("Received response: not a HTTP response");
-                    return Err(Err(net_error::InvalidMessage));
-                }
-                StacksHttpMessage::Response(http_response) => Ok(http_response),
-            },
-            Err(res) => Err(res),
-        }
-    }
+        let mut tx_signer = StacksTransactionSigner::new(&tx_contract);
+        tx_signer.sign_origin(&privk1).unwrap();
+        let tx_contract_signed = tx_signer.get_tx().unwrap();
 
-    /// Make progress on our request/response
-    fn recv_inbound_response(&mut self) -> Result<(), net_error> {
-        // make progress on our pending request (if it exists).
-        let inprogress = self.pending_request.is_some();
-        let is_pending = self.pending_response.is_none();
+        // update account and state in a microblock that will be unconfirmed
+        let mut tx_cc = StacksTransaction::new(
+            TransactionVersion::Testnet,
+            TransactionAuth::from_p2pkh(&privk1).unwrap(),
+            TransactionPayload::new_contract_call(addr1.clone(), "hello-world", "add-unit", vec![])
+                .unwrap(),
+        );
 
-        let pending_request = self.pending_request.take();
-        let response = match pending_request {
-            None => Ok(self.pending_response.take()),
-            Some(req) => match ConversationHttp::try_send_recv_response(req) {
-                Ok(response) => Ok(Some(response)),
-                Err(res) => match res {
-                    Ok(handle) => {
-                        // try again
-                        self.pending_request = Some(handle);
-                        Ok(self.pending_response.take())
-                    }
-                    Err(e) => Err(e),
-                },
-            },
-        }?;
+        tx_cc.chain_id = 0x80000000;
+        tx_cc.auth.set_origin_nonce(2);
+        tx_cc.set_tx_fee(123);
 
-        self.pending_response = response;
+        let mut tx_signer = StacksTransactionSigner::new(&tx_cc);
+        tx_signer.sign_origin(&privk1).unwrap();
+        let tx_cc_signed = tx_signer.get_tx().unwrap();
+        let tx_cc_len = {
+            let mut bytes = vec![];
+            tx_cc_signed.consensus_serialize(&mut bytes).unwrap();
+            bytes.len() as u64
+        };
 
-        if inprogress && self.pending_request.is_none() {
-            test_debug!(
-                "{:?},id={}: HTTP request finished",
-                &self.peer_host,
-                self.conn_id
-            );
-        }
+        // make an unconfirmed contract
+        let unconfirmed_contract = TEST_CONTRACT_UNCONFIRMED.clone();
+        let mut tx_unconfirmed_contract = StacksTransaction::new(
+            TransactionVersion::Testnet,
+            TransactionAuth::from_p2pkh(&privk1).unwrap(),
+            TransactionPayload::new_smart_contract(
+                &format!("hello-world-unconfirmed"),
+                &unconfirmed_contract.to_string(),
+                None,
+            )
+            .unwrap(),
+        );
 
-        if is_pending && self.pending_response.is_some() {
-            test_debug!(
-                "{:?},id={}: HTTP response finished",
-                &self.peer_host,
-                self.conn_id
-            );
-        }
+        tx_unconfirmed_contract.chain_id = 0x80000000;
+        tx_unconfirmed_contract.auth.set_origin_nonce(3);
+        tx_unconfirmed_contract.set_tx_fee(0);
 
-        Ok(())
-    }
+        let mut tx_signer = StacksTransactionSigner::new(&tx_unconfirmed_contract);
+        tx_signer.sign_origin(&privk1).unwrap();
+        let tx_unconfirmed_contract_signed = tx_signer.get_tx().unwrap();
+        let tx_unconfirmed_contract_len = {
+            let mut bytes = vec![];
+            tx_unconfirmed_contract_signed
+                .consensus_serialize(&mut bytes)
+                .unwrap();
+            bytes.len() as u64
+        };
 
-    /// Try to get our response
-    pub fn try_get_response(&mut self) -> Option<HttpResponseType> {
-        self.pending_response.take()
-    }
+        let tip =
+            SortitionDB::get_canonical_burn_chain_tip(&peer_1.sortdb.as_ref().unwrap().conn())
+                .unwrap();
+        let mut anchor_cost = ExecutionCost::zero();
+        let mut anchor_size = 0;
 
-    /// Make progress on in-flight messages.
-    pub fn try_flush(
-        &mut self,
-        mempool: &MemPoolDB,
-        chainstate: &mut StacksChainState,
-    ) -> Result<(), net_error> {
-        self.send_outbound_responses(mempool, chainstate)?;
-        self.recv_inbound_response()?;
-        Ok(())
-    }
+        // make a block and a microblock.
+        // Put the coinbase and smart-contract in the anchored block.
+        // Put the contract-call in the microblock
+        let (burn_ops, stacks_block, microblocks) = peer_1.make_tenure(
+            |ref mut miner, ref mut sortdb, ref mut chainstate, vrf_proof, ref parent_opt, _| {
+                let parent_tip = match parent_opt {
+                    None => StacksChainState::get_genesis_header_info(chainstate.db()).unwrap(),
+                    Some(block) => {
+                        let ic = sortdb.index_conn();
+                        let snapshot = SortitionDB::get_block_snapshot_for_winning_stacks_block(
+                            &ic,
+                            &tip.sortition_id,
+                            &block.block_hash(),
+                        )
+                        .unwrap()
+                        .unwrap(); // succeeds because we don't fork
+                        StacksChainState::get_anchored_block_header_info(
+                            chainstate.db(),
+                            &snapshot.consensus_hash,
+                            &snapshot.winning_stacks_block_hash,
+                        )
+                        .unwrap()
+                        .unwrap()
+                    }
+                };
 
-    /// Is the connection idle?
-    pub fn is_idle(&self) -> bool {
-        self.pending_response.is_none()
-            && self.connection.inbox_len() == 0
-            && self.connection.outbox_len() == 0
-            && self.reply_streams.len() == 0
-    }
+                let block_builder = StacksBlockBuilder::make_regtest_block_builder(
+                    &parent_tip,
+                    vrf_proof,
+                    tip.total_burn,
+                    microblock_pubkeyhash,
+                )
+                .unwrap();
+                let (anchored_block, anchored_block_size, anchored_block_cost) =
+                    StacksBlockBuilder::make_anchored_block_from_txs(
+                        block_builder,
+                        chainstate,
+                        &sortdb.index_conn(),
+                        vec![tx_coinbase_signed.clone(), tx_contract_signed.clone()],
+                    )
+                    .unwrap();
 
-    /// Is the conversation out of pending data?
-    /// Don't consider it drained if we haven't received anything yet
-    pub fn is_drained(&self) -> bool {
-        ((self.total_request_count > 0 && self.total_reply_count > 0)
-            || self.pending_error_response.is_some())
-            && self.is_idle()
-    }
+                anchor_size = anchored_block_size;
+                anchor_cost = anchored_block_cost;
 
-    /// Should the connection be kept alive even if drained?
-    pub fn is_keep_alive(&self) -> bool {
-        self.keep_alive
-    }
+                (anchored_block, vec![])
+            },
+        );
 
-    /// When was the last time we got an inbound request?
-    pub fn get_last_request_time(&self) -> u64 {
-        self.last_request_timestamp
-    }
+        let (_, _, consensus_hash) = peer_1.next_burnchain_block(burn_ops.clone());
+        peer_2.next_burnchain_block(burn_ops.clone());
 
-    /// When was the last time we sent data as part of an outbound response?
-    pub fn get_last_response_time(&self) -> u64 {
-        self.last_response_timestamp
-    }
+        peer_1.process_stacks_epoch_at_tip(&stacks_block, &vec![]);
+        peer_2.process_stacks_epoch_at_tip(&stacks_block, &vec![]);
+
+        // begin microblock section
+        if include_microblocks {
+            // build 1-block microblock stream with the contract-call and the unconfirmed contract
+            let microblock = {
+                let sortdb = peer_1.sortdb.take().unwrap();
+                Relayer::setup_unconfirmed_state(peer_1.chainstate(), &sortdb).unwrap();
+                let mblock = {
+                    let sort_iconn = sortdb.index_conn();
+                    let mut microblock_builder = StacksMicroblockBuilder::new(
+                        stacks_block.block_hash(),
+                        consensus_hash.clone(),
+                        peer_1.chainstate(),
+                        &sort_iconn,
+                        BlockBuilderSettings::max_value(),
+                    )
+                    .unwrap();
+                    let microblock = microblock_builder
+                        .mine_next_microblock_from_txs(
+                            vec![
+                                (tx_cc_signed, tx_cc_len),
+                                (tx_unconfirmed_contract_signed, tx_unconfirmed_contract_len),
+                            ],
+                            &microblock_privkey,
+                            None,
+                        )
+                        .unwrap();
+                    microblock
+                };
+                peer_1.sortdb = Some(sortdb);
+                mblock
+            };
 
-    /// When was this converation conencted?
-    pub fn get_connection_time(&self) -> u64 {
-        self.connection_time
-    }
+            // store microblock stream
+            peer_1
+                .chainstate()
+                .preprocess_streamed_microblock(
+                    &consensus_hash,
+                    &stacks_block.block_hash(),
+                    &microblock,
+                )
+                .unwrap();
+            peer_2
+                .chainstate()
+                .preprocess_streamed_microblock(
+                    &consensus_hash,
+                    &stacks_block.block_hash(),
+                    &microblock,
+                )
+                .unwrap();
 
-    /// Make progress on in-flight requests and replies.
-    /// Returns the list of transactions we'll need to forward to the peer network
-    pub fn chat(
-        &mut self,
-        network: &mut PeerNetwork,
-        sortdb: &SortitionDB,
-        chainstate: &mut StacksChainState,
-        mempool: &mut MemPoolDB,
-        handler_args: &RPCHandlerArgs,
-    ) -> Result<Vec<StacksMessageType>, net_error> {
-        // if we have an in-flight error, then don't take any more requests.
-        if self.pending_error_response.is_some() {
-            return Ok(vec![]);
+            // process microblock stream to generate unconfirmed state
+            let canonical_tip = StacksBlockHeader::make_index_block_hash(
+                &consensus_hash,
+                &stacks_block.block_hash(),
+            );
+            let sortdb1 = peer_1.sortdb.take().unwrap();
+            let sortdb2 = peer_2.sortdb.take().unwrap();
+            peer_1
+                .chainstate()
+                .reload_unconfirmed_state(&sortdb1.index_conn(), canonical_tip.clone())
+                .unwrap();
+            peer_2
+                .chainstate()
+                .reload_unconfirmed_state(&sortdb2.index_conn(), canonical_tip.clone())
+                .unwrap();
+            peer_1.sortdb = Some(sortdb1);
+            peer_2.sortdb = Some(sortdb2);
         }
+        // end microblock section
 
-        // handle in-bound HTTP request(s)
-        let num_inbound = self.connection.inbox_len();
-        let mut ret = vec![];
-        test_debug!("{:?}: {} HTTP requests pending", &self, num_inbound);
-
-        for _i in 0..num_inbound {
-            let msg = match self.connection.next_inbox_message() {
-                None => {
-                    continue;
-                }
-                Some(m) => m,
+        // stuff some transactions into peer_2's mempool
+        // (relates to mempool query tests)
+        let mut mempool = peer_2.mempool.take().unwrap();
+        let mut mempool_tx = mempool.tx_begin().unwrap();
+        for i in 0..10 {
+            let pk = StacksPrivateKey::new();
+            let addr = StacksAddress::from_public_keys(
+                C32_ADDRESS_VERSION_TESTNET_SINGLESIG,
+                &AddressHashMode::SerializeP2PKH,
+                1,
+                &vec![StacksPublicKey::from_private(&StacksPrivateKey::new())],
+            )
+            .unwrap();
+            let mut tx = StacksTransaction {
+                version: TransactionVersion::Testnet,
+                chain_id: 0x80000000,
+                auth: TransactionAuth::from_p2pkh(&pk).unwrap(),
+                anchor_mode: TransactionAnchorMode::Any,
+                post_condition_mode: TransactionPostConditionMode::Allow,
+                post_conditions: vec![],
+                payload: TransactionPayload::TokenTransfer(
+                    addr.to_account_principal(),
+                    123,
+                    TokenTransferMemo([0u8; 34]),
+                ),
             };
+            tx.set_tx_fee(1000);
+            tx.set_origin_nonce(0);
 
-            match msg {
-                StacksHttpMessage::Request(req) => {
-                    // new request
-                    self.total_request_count += 1;
-                    self.last_request_timestamp = get_epoch_time_secs();
-                    if req.metadata().canonical_stacks_tip_height.is_some() {
-                        test_debug!(
-                            "Request metadata: canonical stacks tip height is {:?}",
-                            &req.metadata().canonical_stacks_tip_height
-                        );
-                        self.canonical_stacks_tip_height =
-                            req.metadata().canonical_stacks_tip_height;
-                    }
-                    let start_time = Instant::now();
-                    let path = req.get_path();
-                    let msg_opt = monitoring::instrument_http_request_handler(req, |req| {
-                        self.handle_request(req, network, sortdb, chainstate, mempool, handler_args)
-                    })?;
-
-                    debug!("Processed HTTPRequest"; "path" => %path, "processing_time_ms" => start_time.elapsed().as_millis(), "conn_id" => self.conn_id, "peer_addr" => &self.peer_addr);
+            let txid = tx.txid();
+            let tx_bytes = tx.serialize_to_vec();
+            let origin_addr = tx.origin_address();
+            let origin_nonce = tx.get_origin_nonce();
+            let sponsor_addr = tx.sponsor_address().unwrap_or(origin_addr.clone());
+            let sponsor_nonce = tx.get_sponsor_nonce().unwrap_or(origin_nonce);
+            let tx_fee = tx.get_tx_fee();
 
-                    if let Some(msg) = msg_opt {
-                        ret.push(msg);
-                    }
-                }
-                StacksHttpMessage::Response(resp) => {
-                    // Is there someone else waiting for this message?  If so, pass it along.
-                    // (this _should_ be our pending_request handle)
-                    if resp.metadata().canonical_stacks_tip_height.is_some() {
-                        test_debug!(
-                            "Response metadata: canonical stacks tip height is {:?}",
-                            &resp.metadata().canonical_stacks_tip_height
-                        );
-                        self.canonical_stacks_tip_height =
-                            resp.metadata().canonical_stacks_tip_height;
-                    }
-                    match self
-                        .connection
-                        .fulfill_request(StacksHttpMessage::Response(resp))
-                    {
-                        None => {
-                            test_debug!("{:?}: Fulfilled pending HTTP request", &self);
-                        }
-                        Some(_msg) => {
-                            // unsolicited; discard
-                            test_debug!("{:?}: Dropping unsolicited HTTP response", &self);
-                        }
-                    }
-                }
-            }
+            // should succeed
+            MemPoolDB::try_add_tx(
+                &mut mempool_tx,
+                peer_1.chainstate(),
+                &consensus_hash,
+                &stacks_block.block_hash(),
+                txid.clone(),
+                tx_bytes,
+                tx_fee,
+                stacks_block.header.total_work.work,
+                &origin_addr,
+                origin_nonce,
+                &sponsor_addr,
+                sponsor_nonce,
+                None,
+            )
+            .unwrap();
         }
+        mempool_tx.commit().unwrap();
+        peer_2.mempool.replace(mempool);
 
-        Ok(ret)
-    }
+        let peer_1_sortdb = peer_1.sortdb.take().unwrap();
+        let peer_1_stacks_node = peer_1.stacks_node.take().unwrap();
+        let _ = peer_1
+            .network
+            .refresh_burnchain_view(&peer_1_sortdb, &peer_1_stacks_node.chainstate, false)
+            .unwrap();
+        peer_1.sortdb = Some(peer_1_sortdb);
+        peer_1.stacks_node = Some(peer_1_stacks_node);
 
-    /// Remove all timed-out messages, and ding the remote peer as unhealthy
-    pub fn clear_timeouts(&mut self) -> () {
-        self.connection.drain_timeouts();
-    }
+        let peer_2_sortdb = peer_2.sortdb.take().unwrap();
+        let peer_2_stacks_node = peer_2.stacks_node.take().unwrap();
+        let _ = peer_2
+            .network
+            .refresh_burnchain_view(&peer_2_sortdb, &peer_2_stacks_node.chainstate, false)
+            .unwrap();
+        peer_2.sortdb = Some(peer_2_sortdb);
+        peer_2.stacks_node = Some(peer_2_stacks_node);
 
-    /// Load data into our HTTP connection
-    pub fn recv<R: Read>(&mut self, r: &mut R) -> Result<usize, net_error> {
-        let mut total_recv = 0;
-        loop {
-            let nrecv = match self.connection.recv_data(r) {
-                Ok(nr) => nr,
-                Err(e) => {
-                    debug!("{:?}: failed to recv: {:?}", self, &e);
-                    return Err(e);
-                }
-            };
+        let view_1 = peer_1.get_burnchain_view().unwrap();
+        let view_2 = peer_2.get_burnchain_view().unwrap();
 
-            total_recv += nrecv;
-            if nrecv > 0 {
-                self.last_request_timestamp = get_epoch_time_secs();
-            } else {
-                break;
-            }
-        }
-        monitoring::update_inbound_rpc_bandwidth(total_recv as i64);
-        Ok(total_recv)
-    }
+        let mut convo_1 = ConversationHttp::new(
+            format!("127.0.0.1:{}", peer_1_http)
+                .parse::<SocketAddr>()
+                .unwrap(),
+            Some(UrlString::try_from(format!("http://peer1.com")).unwrap()),
+            peer_1.to_peer_host(),
+            &peer_1.config.connection_opts,
+            0,
+        );
 
-    /// Write data out of our HTTP connection.  Write as much as we can
-    pub fn send<W: Write>(
-        &mut self,
-        w: &mut W,
-        mempool: &MemPoolDB,
-        chainstate: &mut StacksChainState,
-    ) -> Result<usize, net_error> {
-        let mut total_sz = 0;
-        loop {
-            // prime the Write
-            self.try_flush(mempool, chainstate)?;
+        let mut convo_2 = ConversationHttp::new(
+            format!("127.0.0.1:{}", peer_2_http)
+                .parse::<SocketAddr>()
+                .unwrap(),
+            Some(UrlString::try_from(format!("http://peer2.com")).unwrap()),
+            peer_2.to_peer_host(),
+            &peer_2.config.connection_opts,
+            1,
+        );
 
-            let sz = match self.connection.send_data(w) {
-                Ok(sz) => sz,
-                Err(e) => {
-                    info!("{:?}: failed to send on HTTP conversation: {:?}", self, &e);
-                    return Err(e);
-                }
-            };
+        let req = make_request(&mut peer_1, &mut convo_1, &mut peer_2, &mut convo_2);
 
-            total_sz += sz;
-            if sz > 0 {
-                self.last_response_timestamp = get_epoch_time_secs();
-            } else {
-                break;
-            }
-        }
-        monitoring::update_inbound_rpc_bandwidth(total_sz as i64);
-        Ok(total_sz)
-    }
+        convo_1.send_request(req.clone()).unwrap();
+        let mut peer_1_mempool = peer_1.mempool.take().unwrap();
+        let peer_2_mempool = peer_2.mempool.take().unwrap();
 
-    /// Make a new getinfo request to this endpoint
-    pub fn new_getinfo(&self, stacks_height: Option<u64>) -> HttpRequestType {
-        HttpRequestType::GetInfo(HttpRequestMetadata::from_host(
-            self.peer_host.clone(),
-            stacks_height,
-        ))
-    }
+        test_debug!("convo1 sends to convo2");
+        convo_send_recv(
+            &mut convo_1,
+            &peer_1_mempool,
+            peer_1.chainstate(),
+            &mut convo_2,
+            &peer_2_mempool,
+            peer_2.chainstate(),
+        );
 
-    /// Make a new getneighbors request to this endpoint
-    pub fn new_getneighbors(&self) -> HttpRequestType {
-        HttpRequestType::GetNeighbors(HttpRequestMetadata::from_host(self.peer_host.clone(), None))
-    }
+        // hack around the borrow-checker
+        let mut peer_1_sortdb = peer_1.sortdb.take().unwrap();
+        let mut peer_1_stacks_node = peer_1.stacks_node.take().unwrap();
 
-    /// Make a new getheaders request to this endpoint
-    pub fn new_getheaders(&self, quantity: u64, tip_req: TipRequest) -> HttpRequestType {
-        HttpRequestType::GetHeaders(
-            HttpRequestMetadata::from_host(self.peer_host.clone(), None),
-            quantity,
-            tip_req,
-        )
-    }
+        Relayer::setup_unconfirmed_state(&mut peer_1_stacks_node.chainstate, &peer_1_sortdb)
+            .unwrap();
 
-    /// Make a new getblock request to this endpoint
-    pub fn new_getblock(&self, index_block_hash: StacksBlockId) -> HttpRequestType {
-        HttpRequestType::GetBlock(
-            HttpRequestMetadata::from_host(self.peer_host.clone(), None),
-            index_block_hash,
-        )
-    }
+        convo_1
+            .chat(
+                &mut peer_1.network,
+                &mut peer_1_sortdb,
+                &mut peer_1_stacks_node.chainstate,
+                &mut peer_1_mempool,
+                &RPCHandlerArgs::default(),
+            )
+            .unwrap();
 
-    /// Make a new get-microblocks request to this endpoint
-    pub fn new_getmicroblocks_indexed(
-        &self,
-        index_microblock_hash: StacksBlockId,
-    ) -> HttpRequestType {
-        HttpRequestType::GetMicroblocksIndexed(
-            HttpRequestMetadata::from_host(self.peer_host.clone(), None),
-            index_microblock_hash,
-        )
-    }
+        peer_1.sortdb = Some(peer_1_sortdb);
+        peer_1.stacks_node = Some(peer_1_stacks_node);
+        peer_1.mempool = Some(peer_1_mempool);
+        peer_2.mempool = Some(peer_2_mempool);
 
-    /// Make a new get-microblocks-confirmed request to this endpoint
-    pub fn new_getmicroblocks_confirmed(
-        &self,
-        index_anchor_block_hash: StacksBlockId,
-    ) -> HttpRequestType {
-        HttpRequestType::GetMicroblocksConfirmed(
-            HttpRequestMetadata::from_host(self.peer_host.clone(), None),
-            index_anchor_block_hash,
-        )
-    }
+        test_debug!("convo2 sends to convo1");
 
-    /// Make a new get-microblocks request for unconfirmed microblocks
-    pub fn new_getmicroblocks_unconfirmed(
-        &self,
-        anchored_index_block_hash: StacksBlockId,
-        min_seq: u16,
-    ) -> HttpRequestType {
-        HttpRequestType::GetMicroblocksUnconfirmed(
-            HttpRequestMetadata::from_host(self.peer_host.clone(), None),
-            anchored_index_block_hash,
-            min_seq,
-        )
-    }
+        // hack around the borrow-checker
+        let mut peer_2_sortdb = peer_2.sortdb.take().unwrap();
+        let mut peer_2_stacks_node = peer_2.stacks_node.take().unwrap();
+        let mut peer_2_mempool = peer_2.mempool.take().unwrap();
 
-    /// Make a new get-unconfirmed-tx request
-    pub fn new_gettransaction_unconfirmed(&self, txid: Txid) -> HttpRequestType {
-        HttpRequestType::GetTransactionUnconfirmed(
-            HttpRequestMetadata::from_host(self.peer_host.clone(), None),
-            txid,
-        )
-    }
+        let _ = peer_2
+            .network
+            .refresh_burnchain_view(&peer_2_sortdb, &peer_2_stacks_node.chainstate, false)
+            .unwrap();
 
-    /// Make a new post-transaction request
-    pub fn new_post_transaction(&self, tx: StacksTransaction) -> HttpRequestType {
-        HttpRequestType::PostTransaction(
-            HttpRequestMetadata::from_host(self.peer_host.clone(), None),
-            tx,
-            None,
-        )
-    }
+        Relayer::setup_unconfirmed_state(&mut peer_2_stacks_node.chainstate, &peer_2_sortdb)
+            .unwrap();
 
-    /// Make a new post-block request
-    pub fn new_post_block(&self, ch: ConsensusHash, block: StacksBlock) -> HttpRequestType {
-        HttpRequestType::PostBlock(
-            HttpRequestMetadata::from_host(self.peer_host.clone(), None),
-            ch,
-            block,
-        )
-    }
+        convo_2
+            .chat(
+                &mut peer_2.network,
+                &mut peer_2_sortdb,
+                &mut peer_2_stacks_node.chainstate,
+                &mut peer_2_mempool,
+                server_handler_args,
+            )
+            .unwrap();
 
-    /// Make a new post-microblock request
-    pub fn new_post_microblock(
-        &self,
-        mblock: StacksMicroblock,
-        tip_req: TipRequest,
-    ) -> HttpRequestType {
-        HttpRequestType::PostMicroblock(
-            HttpRequestMetadata::from_host(self.peer_host.clone(), None),
-            mblock,
-            tip_req,
-        )
-    }
+        peer_2.sortdb = Some(peer_2_sortdb);
+        peer_2.stacks_node = Some(peer_2_stacks_node);
+        let mut peer_1_mempool = peer_1.mempool.take().unwrap();
 
-    /// Make a new request for an account
-    pub fn new_getaccount(
-        &self,
-        principal: PrincipalData,
-        tip_req: TipRequest,
-        with_proof: bool,
-    ) -> HttpRequestType {
-        HttpRequestType::GetAccount(
-            HttpRequestMetadata::from_host(self.peer_host.clone(), None),
-            principal,
-            tip_req,
-            with_proof,
-        )
-    }
+        convo_send_recv(
+            &mut convo_2,
+            &peer_2_mempool,
+            peer_2.chainstate(),
+            &mut convo_1,
+            &peer_1_mempool,
+            peer_1.chainstate(),
+        );
 
-    /// Make a new request for a data var
-    pub fn new_getdatavar(
-        &self,
-        contract_addr: StacksAddress,
-        contract_name: ContractName,
-        var_name: ClarityName,
-        tip_req: TipRequest,
-        with_proof: bool,
-    ) -> HttpRequestType {
-        HttpRequestType::GetDataVar(
-            HttpRequestMetadata::from_host(self.peer_host.clone(), None),
-            contract_addr,
-            contract_name,
-            var_name,
-            tip_req,
-            with_proof,
-        )
-    }
+        test_debug!("flush convo1");
 
-    /// Make a new request for a data map
-    pub fn new_getmapentry(
-        &self,
-        contract_addr: StacksAddress,
-        contract_name: ContractName,
-        map_name: ClarityName,
-        key: Value,
-        tip_req: TipRequest,
-        with_proof: bool,
-    ) -> HttpRequestType {
-        HttpRequestType::GetMapEntry(
-            HttpRequestMetadata::from_host(self.peer_host.clone(), None),
-            contract_addr,
-            contract_name,
-            map_name,
-            key,
-            tip_req,
-            with_proof,
-        )
-    }
+        // hack around the borrow-checker
+        convo_send_recv(
+            &mut convo_1,
+            &peer_1_mempool,
+            peer_1.chainstate(),
+            &mut convo_2,
+            &peer_2_mempool,
+            peer_2.chainstate(),
+        );
 
-    /// Make a new request to get a contract's source
-    pub fn new_getcontractsrc(
-        &self,
-        contract_addr: StacksAddress,
-        contract_name: ContractName,
-        tip_req: TipRequest,
-        with_proof: bool,
-    ) -> HttpRequestType {
-        HttpRequestType::GetContractSrc(
-            HttpRequestMetadata::from_host(self.peer_host.clone(), None),
-            contract_addr,
-            contract_name,
-            tip_req,
-            with_proof,
-        )
-    }
+        peer_2.mempool = Some(peer_2_mempool);
+
+        let mut peer_1_sortdb = peer_1.sortdb.take().unwrap();
+        let mut peer_1_stacks_node = peer_1.stacks_node.take().unwrap();
 
-    /// Make a new request to get a contract's ABI
-    pub fn new_getcontractabi(
-        &self,
-        contract_addr: StacksAddress,
-        contract_name: ContractName,
-        tip_req: TipRequest,
-    ) -> HttpRequestType {
-        HttpRequestType::GetContractABI(
-            HttpRequestMetadata::from_host(self.peer_host.clone(), None),
-            contract_addr,
-            contract_name,
-            tip_req,
-        )
-    }
+        let _ = peer_1
+            .network
+            .refresh_burnchain_view(&peer_1_sortdb, &peer_1_stacks_node.chainstate, false)
+            .unwrap();
 
-    /// Make a new request to run a read-only function
-    pub fn new_callreadonlyfunction(
-        &self,
-        contract_addr: StacksAddress,
-        contract_name: ContractName,
-        sender: PrincipalData,
-        function_name: ClarityName,
-        function_args: Vec<Value>,
-        tip_req: TipRequest,
-    ) -> HttpRequestType {
-        HttpRequestType::CallReadOnlyFunction(
-            HttpRequestMetadata::from_host(self.peer_host.clone(), None),
-            contract_addr,
-            contract_name,
-            sender,
-            function_name,
-            function_args,
-            tip_req,
-        )
-    }
+        Relayer::setup_unconfirmed_state(&mut peer_1_stacks_node.chainstate, &peer_1_sortdb)
+            .unwrap();
 
-    /// Make a new request for attachment inventory page
-    pub fn new_getattachmentsinv(
-        &self,
-        index_block_hash: StacksBlockId,
-        pages_indexes: HashSet<u32>,
-    ) -> HttpRequestType {
-        HttpRequestType::GetAttachmentsInv(
-            HttpRequestMetadata::from_host(self.peer_host.clone(), None),
-            index_block_hash,
-            pages_indexes,
-        )
-    }
+        convo_1
+            .chat(
+                &mut peer_1.network,
+                &mut peer_1_sortdb,
+                &mut peer_1_stacks_node.chainstate,
+                &mut peer_1_mempool,
+                &RPCHandlerArgs::default(),
+            )
+            .unwrap();
 
-    /// Make a new request for mempool contents
-    pub fn new_mempool_query(
-        &self,
-        query: MemPoolSyncData,
-        page_id_opt: Option<Txid>,
-    ) -> HttpRequestType {
-        HttpRequestType::MemPoolQuery(
-            HttpRequestMetadata::from_host(self.peer_host.clone(), None),
-            query,
-            page_id_opt,
-        )
-    }
-}
+        convo_1
+            .try_flush(&peer_1_mempool, &mut peer_1_stacks_node.chainstate)
+            .unwrap();
 
-#[cfg(test)]
-mod test {
-    use std::cell::RefCell;
-    use std::convert::TryInto;
-    use std::iter::FromIterator;
+        peer_1.sortdb = Some(peer_1_sortdb);
+        peer_1.stacks_node = Some(peer_1_stacks_node);
+        peer_1.mempool = Some(peer_1_mempool);
 
-    use crate::burnchains::Burnchain;
-    use crate::burnchains::BurnchainView;
-    use crate::burnchains::*;
-    use crate::chainstate::burn::ConsensusHash;
-    use crate::chainstate::stacks::db::blocks::test::*;
-    use crate::chainstate::stacks::db::StacksChainState;
-    use crate::chainstate::stacks::db::StreamCursor;
-    use crate::chainstate::stacks::miner::*;
-    use crate::chainstate::stacks::test::*;
-    use crate::chainstate::stacks::Error as chain_error;
-    use crate::chainstate::stacks::*;
-    use crate::net::codec::*;
-    use crate::net::http::*;
-    use crate::net::test::*;
-    use crate::net::*;
-    use clarity::vm::types::*;
-    use stacks_common::address::*;
-    use stacks_common::util::get_epoch_time_secs;
-    use stacks_common::util::hash::hex_bytes;
-    use stacks_common::util::pipe::*;
+        // should have gotten a reply
+        let resp_opt = convo_1.try_get_response();
+        assert!(resp_opt.is_some());
 
-    use crate::chainstate::stacks::C32_ADDRESS_VERSION_TESTNET_SINGLESIG;
-    use crate::types::chainstate::BlockHeaderHash;
-    use crate::types::chainstate::BurnchainHeaderHash;
+        let resp = resp_opt.unwrap();
+        assert!(check_result(
+            &req,
+            &resp,
+            &mut peer_1,
+            &mut peer_2,
+            &convo_1,
+            &convo_2
+        ));
+    }
 
-    use crate::core::mempool::{BLOOM_COUNTER_ERROR_RATE, MAX_BLOOM_COUNTER_TXS};
+    /// This test tests two things:
+    /// (1) the get info RPC call
+    /// (2) whether the ConversationHttp object gets correctly updated with a peer's canonical
+    /// stacks tip height, which is sent in HTTP headers as part of the request/response
+    #[test]
+    #[ignore]
+    fn test_rpc_getinfo() {
+        let peer_server_info = RefCell::new(None);
+        let client_stacks_height = 17;
+        test_rpc(
+            "test_rpc_getinfo",
+            40000,
+            40001,
+            50000,
+            50001,
+            true,
+            |ref mut peer_client,
+             ref mut convo_client,
+             ref mut peer_server,
+             ref mut convo_server| {
+                let peer_info = RPCPeerInfoData::from_network(
+                    &peer_server.network,
+                    &peer_server.stacks_node.as_ref().unwrap().chainstate,
+                    &None,
+                    &Sha256Sum::zero(),
+                );
 
-    use super::*;
+                *peer_server_info.borrow_mut() = Some(peer_info);
 
-    const TEST_CONTRACT: &'static str = "
-        (define-data-var bar int 0)
-        (define-map unit-map { account: principal } { units: int })
-        (define-public (get-bar) (ok (var-get bar)))
-        (define-public (set-bar (x int) (y int))
-          (begin (var-set bar (/ x y)) (ok (var-get bar))))
-        (define-public (add-unit)
-          (begin
-            (map-set unit-map { account: tx-sender } { units: 1 } )
-            (var-set bar 1)
-            (ok 1)))
-        (begin
-          (map-set unit-map { account: 'ST2DS4MSWSGJ3W9FBC6BVT0Y92S345HY8N3T6AV7R } { units: 123 }))";
+                convo_client.new_getinfo(Some(client_stacks_height))
+            },
+            |ref http_request,
+             ref http_response,
+             ref mut peer_client,
+             ref mut peer_server,
+             ref convo_client,
+             ref convo_server| {
+                let req_md = http_request.metadata().clone();
+                assert_eq!(convo_client.canonical_stacks_tip_height, Some(1));
+                assert_eq!(
+                    convo_server.canonical_stacks_tip_height,
+                    Some(client_stacks_height)
+                );
+                match http_response {
+                    HttpResponseType::PeerInfo(response_md, peer_data) => {
+                        assert_eq!(Some((*peer_data).clone()), *peer_server_info.borrow());
+                        assert!(peer_data.node_public_key.is_some());
+                        assert!(peer_data.node_public_key_hash.is_some());
+                        assert_eq!(
+                            peer_data.node_public_key_hash,
+                            Some(Hash160::from_node_public_key(
+                                &peer_data
+                                    .node_public_key
+                                    .clone()
+                                    .unwrap()
+                                    .to_public_key()
+                                    .unwrap()
+                            ))
+                        );
+                        true
+                    }
+                    _ => {
+                        error!("Invalid response: {:?}", &http_response);
+                        false
+                    }
+                }
+            },
+        );
+    }
 
-    const TEST_CONTRACT_UNCONFIRMED: &'static str = "(define-read-only (ro-test) (ok 1))";
+    #[test]
+    #[ignore]
+    fn test_rpc_getneighbors() {
+        test_rpc(
+            "test_rpc_getneighbors",
+            40010,
+            40011,
+            50010,
+            50011,
+            true,
+            |ref mut peer_client,
+             ref mut convo_client,
+             ref mut peer_server,
+             ref mut convo_server| { convo_client.new_getneighbors() },
+            |ref http_request,
+             ref http_response,
+             ref mut peer_client,
+             ref mut peer_server,
+             ref convo_client,
+             ref convo_server| {
+                let req_md = http_request.metadata().clone();
+                match http_response {
+                    HttpResponseType::Neighbors(response_md, neighbor_info) => {
+                        assert_eq!(neighbor_info.sample.len(), 1);
+                        assert_eq!(neighbor_info.sample[0].port, peer_client.config.server_port); // we see ourselves as the neighbor
+                        true
+                    }
+                    _ => {
+                        error!("Invalid response: {:?}", &http_response);
+                        false
+                    }
+                }
+            },
+        );
+    }
+
+    #[test]
+    #[ignore]
+    fn test_rpc_getheaders() {
+        let server_blocks_cell = RefCell::new(None);
+
+        test_rpc(
+            "test_rpc_getheaders",
+            40012,
+            40013,
+            50012,
+            50013,
+            true,
+            |ref mut peer_client,
+             ref mut convo_client,
+             ref mut peer_server,
+             ref mut convo_server| {
+                // have "server" peer store a few continuous block to staging
+                let mut blocks: Vec<StacksBlock> = vec![];
+                let mut index_block_hashes = vec![];
+                for i in 0..25 {
+                    let mut peer_server_block = make_codec_test_block(25);
 
-    fn convo_send_recv(
-        sender: &mut ConversationHttp,
-        sender_mempool: &MemPoolDB,
-        sender_chainstate: &mut StacksChainState,
-        receiver: &mut ConversationHttp,
-        receiver_mempool: &MemPoolDB,
-        receiver_chainstate: &mut StacksChainState,
-    ) -> () {
-        let (mut pipe_read, mut pipe_write) = Pipe::new();
-        pipe_read.set_nonblocking(true);
+                    peer_server_block.header.total_work.work = (i + 1) as u64;
+                    peer_server_block.header.total_work.burn = (i + 1) as u64;
+                    peer_server_block.header.parent_block = blocks
+                        .last()
+                        .map(|blk| blk.block_hash())
+                        .unwrap_or(BlockHeaderHash([0u8; 32]));
 
-        loop {
-            let res = true;
+                    let peer_server_consensus_hash = ConsensusHash([(i + 1) as u8; 20]);
+                    let index_block_hash = StacksBlockHeader::make_index_block_hash(
+                        &peer_server_consensus_hash,
+                        &peer_server_block.block_hash(),
+                    );
 
-            sender.try_flush(sender_mempool, sender_chainstate).unwrap();
-            receiver
-                .try_flush(sender_mempool, receiver_chainstate)
-                .unwrap();
+                    test_debug!("Store peer server index block {:?}", &index_block_hash);
+                    store_staging_block(
+                        peer_server.chainstate(),
+                        &peer_server_consensus_hash,
+                        &peer_server_block,
+                        &ConsensusHash([i as u8; 20]),
+                        456,
+                        123,
+                    );
+                    set_block_processed(
+                        peer_server.chainstate(),
+                        &peer_server_consensus_hash,
+                        &peer_server_block.block_hash(),
+                        true,
+                    );
 
-            pipe_write.try_flush().unwrap();
+                    index_block_hashes.push(index_block_hash);
+                    blocks.push(peer_server_block);
+                }
 
-            let all_relays_flushed =
-                receiver.num_pending_outbound() == 0 && sender.num_pending_outbound() == 0;
+                let rev_blocks: Vec<_> = blocks.into_iter().rev().collect();
+                let rev_ibhs: Vec<_> = index_block_hashes.into_iter().rev().collect();
 
-            let nw = sender
-                .send(&mut pipe_write, sender_mempool, sender_chainstate)
-                .unwrap();
-            let nr = receiver.recv(&mut pipe_read).unwrap();
+                let tip = rev_ibhs[0].clone();
+                *server_blocks_cell.borrow_mut() = Some((rev_blocks, rev_ibhs));
 
-            test_debug!(
-                "res = {}, all_relays_flushed = {} ({},{}), nr = {}, nw = {}",
-                res,
-                all_relays_flushed,
-                receiver.num_pending_outbound(),
-                sender.num_pending_outbound(),
-                nr,
-                nw
-            );
-            if res && all_relays_flushed && nr == 0 && nw == 0 {
-                test_debug!("Breaking send_recv");
-                break;
-            }
-        }
+                // now ask for it
+                convo_client.new_getheaders(25, TipRequest::SpecificTip(tip))
+            },
+            |ref http_request,
+             ref http_response,
+             ref mut peer_client,
+             ref mut peer_server,
+             ref convo_client,
+             ref convo_server| {
+                let req_md = http_request.metadata().clone();
+                match http_response {
+                    HttpResponseType::Headers(response_md, headers) => {
+                        assert_eq!(headers.len(), 25);
+                        let expected = server_blocks_cell.borrow().clone().unwrap();
+                        for (i, h) in headers.iter().enumerate() {
+                            assert_eq!(h.header, expected.0[i].header);
+                            assert_eq!(h.consensus_hash, ConsensusHash([(25 - i) as u8; 20]));
+                            if i + 1 < headers.len() {
+                                assert_eq!(h.parent_block_id, expected.1[i + 1]);
+                            }
+                        }
+                        true
+                    }
+                    _ => {
+                        error!("Invalid response: {:?}", &http_response);
+                        false
+                    }
+                }
+            },
+        );
     }
 
-    /// General testing function to test RPC calls.
-    /// This function sets up two peers, a client and a server.
-    /// It takes in a function of type F that generates the request to be sent to the server
-    /// It takes in another function of type C that verifies that the result from
-    /// the server is as expected.
-    /// The parameter `include_microblocks` determines whether a microblock stream is mined or not.
-    fn test_rpc<F, C>(
-        test_name: &str,
-        peer_1_p2p: u16,
-        peer_1_http: u16,
-        peer_2_p2p: u16,
-        peer_2_http: u16,
-        include_microblocks: bool,
-        make_request: F,
-        check_result: C,
-    ) -> ()
-    where
-        F: FnOnce(
-            &mut TestPeer,
-            &mut ConversationHttp,
-            &mut TestPeer,
-            &mut ConversationHttp,
-        ) -> HttpRequestType,
-        C: FnOnce(
-            &HttpRequestType,
-            &HttpResponseType,
-            &mut TestPeer,
-            &mut TestPeer,
-            &ConversationHttp,
-            &ConversationHttp,
-        ) -> bool,
-    {
-        let mut peer_1_config = TestPeerConfig::new(test_name, peer_1_p2p, peer_1_http);
-        let mut peer_2_config = TestPeerConfig::new(test_name, peer_2_p2p, peer_2_http);
+    #[test]
+    #[ignore]
+    fn test_rpc_unconfirmed_getblock() {
+        let server_block_cell = RefCell::new(None);
 
-        // ST2DS4MSWSGJ3W9FBC6BVT0Y92S345HY8N3T6AV7R
-        let privk1 = StacksPrivateKey::from_hex(
-            "9f1f85a512a96a244e4c0d762788500687feb97481639572e3bffbd6860e6ab001",
-        )
-        .unwrap();
+        test_rpc(
+            "test_rpc_unconfirmed_getblock",
+            40020,
+            40021,
+            50020,
+            50021,
+            true,
+            |ref mut peer_client,
+             ref mut convo_client,
+             ref mut peer_server,
+             ref mut convo_server| {
+                // have "server" peer store a block to staging
+                let peer_server_block = make_codec_test_block(25);
+                let peer_server_consensus_hash = ConsensusHash([0x02; 20]);
+                let index_block_hash = StacksBlockHeader::make_index_block_hash(
+                    &peer_server_consensus_hash,
+                    &peer_server_block.block_hash(),
+                );
 
-        // STVN97YYA10MY5F6KQJHKNYJNM24C4A1AT39WRW
-        let privk2 = StacksPrivateKey::from_hex(
-            "94c319327cc5cd04da7147d32d836eb2e4c44f4db39aa5ede7314a761183d0c701",
-        )
-        .unwrap();
-        let microblock_privkey = StacksPrivateKey::new();
-        let microblock_pubkeyhash =
-            Hash160::from_node_public_key(&StacksPublicKey::from_private(&microblock_privkey));
+                test_debug!("Store peer server index block {:?}", &index_block_hash);
+                store_staging_block(
+                    peer_server.chainstate(),
+                    &peer_server_consensus_hash,
+                    &peer_server_block,
+                    &ConsensusHash([0x03; 20]),
+                    456,
+                    123,
+                );
 
-        let addr1 = StacksAddress::from_public_keys(
-            C32_ADDRESS_VERSION_TESTNET_SINGLESIG,
-            &AddressHashMode::SerializeP2PKH,
-            1,
-            &vec![StacksPublicKey::from_private(&privk1)],
-        )
-        .unwrap();
-        let addr2 = StacksAddress::from_public_keys(
-            C32_ADDRESS_VERSION_TESTNET_SINGLESIG,
-            &AddressHashMode::SerializeP2PKH,
-            1,
-            &vec![StacksPublicKey::from_private(&privk2)],
-        )
-        .unwrap();
+                *server_block_cell.borrow_mut() = Some(peer_server_block);
 
-        peer_1_config.initial_balances = vec![
-            (addr1.to_account_principal(), 1000000000),
-            (addr2.to_account_principal(), 1000000000),
-        ];
+                // now ask for it
+                convo_client.new_getblock(index_block_hash)
+            },
+            |ref http_request,
+             ref http_response,
+             ref mut peer_client,
+             ref mut peer_server,
+             ref convo_client,
+             ref convo_server| {
+                let req_md = http_request.metadata().clone();
+                match http_response {
+                    HttpResponseType::Block(response_md, block_info) => {
+                        assert_eq!(
+                            block_info.block_hash(),
+                            (*server_block_cell.borrow()).as_ref().unwrap().block_hash()
+                        );
+                        true
+                    }
+                    _ => {
+                        error!("Invalid response: {:?}", &http_response);
+                        false
+                    }
+                }
+            },
+        );
+    }
 
-        peer_2_config.initial_balances = vec![
-            (addr1.to_account_principal(), 1000000000),
-            (addr2.to_account_principal(), 1000000000),
-        ];
+    #[test]
+    #[ignore]
+    fn test_rpc_confirmed_getblock() {
+        let server_block_cell = RefCell::new(None);
 
-        peer_1_config.add_neighbor(&peer_2_config.to_neighbor());
-        peer_2_config.add_neighbor(&peer_1_config.to_neighbor());
+        test_rpc(
+            "test_rpc_confirmed_getblock",
+            40030,
+            40031,
+            50030,
+            50031,
+            true,
+            |ref mut peer_client,
+             ref mut convo_client,
+             ref mut peer_server,
+             ref mut convo_server| {
+                // have "server" peer store a block to staging
+                let peer_server_block = make_codec_test_block(25);
+                let peer_server_consensus_hash = ConsensusHash([0x02; 20]);
+                let index_block_hash = StacksBlockHeader::make_index_block_hash(
+                    &peer_server_consensus_hash,
+                    &peer_server_block.block_hash(),
+                );
 
-        let mut peer_1 = TestPeer::new(peer_1_config);
-        let mut peer_2 = TestPeer::new(peer_2_config);
+                test_debug!("Store peer server index block {:?}", &index_block_hash);
+                store_staging_block(
+                    peer_server.chainstate(),
+                    &peer_server_consensus_hash,
+                    &peer_server_block,
+                    &ConsensusHash([0x03; 20]),
+                    456,
+                    123,
+                );
+                set_block_processed(
+                    peer_server.chainstate(),
+                    &peer_server_consensus_hash,
+                    &peer_server_block.block_hash(),
+                    true,
+                );
 
-        // mine one block with a contract in it
-        // first the coinbase
-        // make a coinbase for this miner
-        let mut tx_coinbase = StacksTransaction::new(
-            TransactionVersion::Testnet,
-            TransactionAuth::from_p2pkh(&privk1).unwrap(),
-            TransactionPayload::Coinbase(CoinbasePayload([0x00; 32])),
+                *server_block_cell.borrow_mut() = Some(peer_server_block);
+
+                // now ask for it
+                convo_client.new_getblock(index_block_hash)
+            },
+            |ref http_request,
+             ref http_response,
+             ref mut peer_client,
+             ref mut peer_server,
+             ref convo_client,
+             ref convo_server| {
+                let req_md = http_request.metadata().clone();
+                match http_response {
+                    HttpResponseType::Block(response_md, block_info) => {
+                        assert_eq!(
+                            block_info.block_hash(),
+                            (*server_block_cell.borrow()).as_ref().unwrap().block_hash()
+                        );
+                        true
+                    }
+                    _ => {
+                        error!("Invalid response: {:?}", &http_response);
+                        false
+                    }
+                }
+            },
         );
-        tx_coinbase.chain_id = 0x80000000;
-        tx_coinbase.anchor_mode = TransactionAnchorMode::OnChainOnly;
-        tx_coinbase.auth.set_origin_nonce(0);
+    }
 
-        let mut tx_signer = StacksTransactionSigner::new(&tx_coinbase);
-        tx_signer.sign_origin(&privk1).unwrap();
-        let tx_coinbase_signed = tx_signer.get_tx().unwrap();
+    #[test]
+    #[ignore]
+    fn test_rpc_get_indexed_microblocks() {
+        let server_microblocks_cell = RefCell::new(vec![]);
 
-        // next the contract
-        let contract = TEST_CONTRACT.clone();
-        let mut tx_contract = StacksTransaction::new(
-            TransactionVersion::Testnet,
-            TransactionAuth::from_p2pkh(&privk1).unwrap(),
-            TransactionPayload::new_smart_contract(
-                &format!("hello-world"),
-                &contract.to_string(),
-                None,
-            )
-            .unwrap(),
-        );
+        test_rpc(
+            "test_rpc_indexed_microblocks",
+            40040,
+            40041,
+            50040,
+            50041,
+            true,
+            |ref mut peer_client,
+             ref mut convo_client,
+             ref mut peer_server,
+             ref mut convo_server| {
+                let privk = StacksPrivateKey::from_hex(
+                    "eb05c83546fdd2c79f10f5ad5434a90dd28f7e3acb7c092157aa1bc3656b012c01",
+                )
+                .unwrap();
 
-        tx_contract.chain_id = 0x80000000;
-        tx_contract.auth.set_origin_nonce(1);
-        tx_contract.set_tx_fee(0);
+                let parent_block = make_codec_test_block(25);
+                let parent_consensus_hash = ConsensusHash([0x02; 20]);
+                let parent_index_block_hash = StacksBlockHeader::make_index_block_hash(
+                    &parent_consensus_hash,
+                    &parent_block.block_hash(),
+                );
 
-        let mut tx_signer = StacksTransactionSigner::new(&tx_contract);
-        tx_signer.sign_origin(&privk1).unwrap();
-        let tx_contract_signed = tx_signer.get_tx().unwrap();
+                let mut mblocks = make_sample_microblock_stream(&privk, &parent_block.block_hash());
+                mblocks.truncate(15);
 
-        // update account and state in a microblock that will be unconfirmed
-        let mut tx_cc = StacksTransaction::new(
-            TransactionVersion::Testnet,
-            TransactionAuth::from_p2pkh(&privk1).unwrap(),
-            TransactionPayload::new_contract_call(addr1.clone(), "hello-world", "add-unit", vec![])
-                .unwrap(),
-        );
+                let mut child_block = make_codec_test_block(25);
+                let child_consensus_hash = ConsensusHash([0x03; 20]);
 
-        tx_cc.chain_id = 0x80000000;
-        tx_cc.auth.set_origin_nonce(2);
-        tx_cc.set_tx_fee(123);
+                child_block.header.parent_block = parent_block.block_hash();
+                child_block.header.parent_microblock =
+                    mblocks.last().as_ref().unwrap().block_hash();
+                child_block.header.parent_microblock_sequence =
+                    mblocks.last().as_ref().unwrap().header.sequence;
 
-        let mut tx_signer = StacksTransactionSigner::new(&tx_cc);
-        tx_signer.sign_origin(&privk1).unwrap();
-        let tx_cc_signed = tx_signer.get_tx().unwrap();
-        let tx_cc_len = {
-            let mut bytes = vec![];
-            tx_cc_signed.consensus_serialize(&mut bytes).unwrap();
-            bytes.len() as u64
-        };
+                store_staging_block(
+                    peer_server.chainstate(),
+                    &parent_consensus_hash,
+                    &parent_block,
+                    &ConsensusHash([0x01; 20]),
+                    456,
+                    123,
+                );
+                set_block_processed(
+                    peer_server.chainstate(),
+                    &parent_consensus_hash,
+                    &parent_block.block_hash(),
+                    true,
+                );
 
-        // make an unconfirmed contract
-        let unconfirmed_contract = TEST_CONTRACT_UNCONFIRMED.clone();
-        let mut tx_unconfirmed_contract = StacksTransaction::new(
-            TransactionVersion::Testnet,
-            TransactionAuth::from_p2pkh(&privk1).unwrap(),
-            TransactionPayload::new_smart_contract(
-                &format!("hello-world-unconfirmed"),
-                &unconfirmed_contract.to_string(),
-                None,
-            )
-            .unwrap(),
-        );
+                store_staging_block(
+                    peer_server.chainstate(),
+                    &child_consensus_hash,
+                    &child_block,
+                    &parent_consensus_hash,
+                    456,
+                    123,
+                );
+                set_block_processed(
+                    peer_server.chainstate(),
+                    &child_consensus_hash,
+                    &child_block.block_hash(),
+                    true,
+                );
 
-        tx_unconfirmed_contract.chain_id = 0x80000000;
-        tx_unconfirmed_contract.auth.set_origin_nonce(3);
-        tx_unconfirmed_contract.set_tx_fee(0);
+                let index_microblock_hash = StacksBlockHeader::make_index_block_hash(
+                    &parent_consensus_hash,
+                    &mblocks.last().as_ref().unwrap().block_hash(),
+                );
 
-        let mut tx_signer = StacksTransactionSigner::new(&tx_unconfirmed_contract);
-        tx_signer.sign_origin(&privk1).unwrap();
-        let tx_unconfirmed_contract_signed = tx_signer.get_tx().unwrap();
-        let tx_unconfirmed_contract_len = {
-            let mut bytes = vec![];
-            tx_unconfirmed_contract_signed
-                .consensus_serialize(&mut bytes)
-                .unwrap();
-            bytes.len() as u64
-        };
+                for mblock in mblocks.iter() {
+                    store_staging_microblock(
+                        peer_server.chainstate(),
+                        &parent_consensus_hash,
+                        &parent_block.block_hash(),
+                        &mblock,
+                    );
+                }
 
-        let tip =
-            SortitionDB::get_canonical_burn_chain_tip(&peer_1.sortdb.as_ref().unwrap().conn())
-                .unwrap();
-        let mut anchor_cost = ExecutionCost::zero();
-        let mut anchor_size = 0;
+                set_microblocks_processed(
+                    peer_server.chainstate(),
+                    &child_consensus_hash,
+                    &child_block.block_hash(),
+                    &mblocks.last().as_ref().unwrap().block_hash(),
+                );
 
-        // make a block and a microblock.
-        // Put the coinbase and smart-contract in the anchored block.
-        // Put the contract-call in the microblock
-        let (burn_ops, stacks_block, microblocks) = peer_1.make_tenure(
-            |ref mut miner, ref mut sortdb, ref mut chainstate, vrf_proof, ref parent_opt, _| {
-                let parent_tip = match parent_opt {
-                    None => StacksChainState::get_genesis_header_info(chainstate.db()).unwrap(),
-                    Some(block) => {
-                        let ic = sortdb.index_conn();
-                        let snapshot = SortitionDB::get_block_snapshot_for_winning_stacks_block(
-                            &ic,
-                            &tip.sortition_id,
-                            &block.block_hash(),
-                        )
-                        .unwrap()
-                        .unwrap(); // succeeds because we don't fork
-                        StacksChainState::get_anchored_block_header_info(
-                            chainstate.db(),
-                            &snapshot.consensus_hash,
-                            &snapshot.winning_stacks_block_hash,
-                        )
-                        .unwrap()
-                        .unwrap()
+                *server_microblocks_cell.borrow_mut() = mblocks;
+
+                convo_client.new_getmicroblocks_indexed(index_microblock_hash)
+            },
+            |ref http_request,
+             ref http_response,
+             ref mut peer_client,
+             ref mut peer_server,
+             ref convo_client,
+             ref convo_server| {
+                let req_md = http_request.metadata().clone();
+                match (*http_response).clone() {
+                    HttpResponseType::Microblocks(_, mut microblocks) => {
+                        microblocks.reverse();
+                        assert_eq!(microblocks.len(), (*server_microblocks_cell.borrow()).len());
+                        assert_eq!(microblocks, *server_microblocks_cell.borrow());
+                        true
                     }
-                };
+                    _ => {
+                        error!("Invalid response: {:?}", http_response);
+                        false
+                    }
+                }
+            },
+        );
+    }
+
+    #[test]
+    #[ignore]
+    fn test_rpc_get_confirmed_microblocks() {
+        let server_microblocks_cell = RefCell::new(vec![]);
 
-                let block_builder = StacksBlockBuilder::make_regtest_block_builder(
-                    &parent_tip,
-                    vrf_proof,
-                    tip.total_burn,
-                    microblock_pubkeyhash,
+        test_rpc(
+            "test_rpc_confirmed_microblocks",
+            40042,
+            40043,
+            50042,
+            50043,
+            true,
+            |ref mut peer_client,
+             ref mut convo_client,
+             ref mut peer_server,
+             ref mut convo_server| {
+                let privk = StacksPrivateKey::from_hex(
+                    "eb05c83546fdd2c79f10f5ad5434a90dd28f7e3acb7c092157aa1bc3656b012c01",
                 )
                 .unwrap();
-                let (anchored_block, anchored_block_size, anchored_block_cost) =
-                    StacksBlockBuilder::make_anchored_block_from_txs(
-                        block_builder,
-                        chainstate,
-                        &sortdb.index_conn(),
-                        vec![tx_coinbase_signed.clone(), tx_contract_signed.clone()],
-                    )
-                    .unwrap();
-
-                anchor_size = anchored_block_size;
-                anchor_cost = anchored_block_cost;
 
-                (anchored_block, vec![])
-            },
-        );
+                let parent_block = make_codec_test_block(25);
+                let parent_consensus_hash = ConsensusHash([0x02; 20]);
 
-        let (_, _, consensus_hash) = peer_1.next_burnchain_block(burn_ops.clone());
-        peer_2.next_burnchain_block(burn_ops.clone());
+                let mut mblocks = make_sample_microblock_stream(&privk, &parent_block.block_hash());
+                mblocks.truncate(15);
 
-        peer_1.process_stacks_epoch_at_tip(&stacks_block, &vec![]);
-        peer_2.process_stacks_epoch_at_tip(&stacks_block, &vec![]);
+                let mut child_block = make_codec_test_block(25);
+                let child_consensus_hash = ConsensusHash([0x03; 20]);
 
-        // begin microblock section
-        if include_microblocks {
-            // build 1-block microblock stream with the contract-call and the unconfirmed contract
-            let microblock = {
-                let sortdb = peer_1.sortdb.take().unwrap();
-                Relayer::setup_unconfirmed_state(peer_1.chainstate(), &sortdb).unwrap();
-                let mblock = {
-                    let sort_iconn = sortdb.index_conn();
-                    let mut microblock_builder = StacksMicroblockBuilder::new(
-                        stacks_block.block_hash(),
-                        consensus_hash.clone(),
-                        peer_1.chainstate(),
-                        &sort_iconn,
-                        BlockBuilderSettings::max_value(),
-                    )
-                    .unwrap();
-                    let microblock = microblock_builder
-                        .mine_next_microblock_from_txs(
-                            vec![
-                                (tx_cc_signed, tx_cc_len),
-                                (tx_unconfirmed_contract_signed, tx_unconfirmed_contract_len),
-                            ],
-                            &microblock_privkey,
-                            None,
-                        )
-                        .unwrap();
-                    microblock
-                };
-                peer_1.sortdb = Some(sortdb);
-                mblock
-            };
+                child_block.header.parent_block = parent_block.block_hash();
+                child_block.header.parent_microblock =
+                    mblocks.last().as_ref().unwrap().block_hash();
+                child_block.header.parent_microblock_sequence =
+                    mblocks.last().as_ref().unwrap().header.sequence;
 
-            // store microblock stream
-            peer_1
-                .chainstate()
-                .preprocess_streamed_microblock(
-                    &consensus_hash,
-                    &stacks_block.block_hash(),
-                    &microblock,
-                )
-                .unwrap();
-            peer_2
-                .chainstate()
-                .preprocess_streamed_microblock(
-                    &consensus_hash,
-                    &stacks_block.block_hash(),
-                    &microblock,
-                )
-                .unwrap();
+                let child_index_block_hash = StacksBlockHeader::make_index_block_hash(
+                    &child_consensus_hash,
+                    &child_block.block_hash(),
+                );
 
-            // process microblock stream to generate unconfirmed state
-            let canonical_tip = StacksBlockHeader::make_index_block_hash(
-                &consensus_hash,
-                &stacks_block.block_hash(),
-            );
-            let sortdb1 = peer_1.sortdb.take().unwrap();
-            let sortdb2 = peer_2.sortdb.take().unwrap();
-            peer_1
-                .chainstate()
-                .reload_unconfirmed_state(&sortdb1.index_conn(), canonical_tip.clone())
-                .unwrap();
-            peer_2
-                .chainstate()
-                .reload_unconfirmed_state(&sortdb2.index_conn(), canonical_tip.clone())
-                .unwrap();
-            peer_1.sortdb = Some(sortdb1);
-            peer_2.sortdb = Some(sortdb2);
-        }
-        // end microblock section
+                store_staging_block(
+                    peer_server.chainstate(),
+                    &parent_consensus_hash,
+                    &parent_block,
+                    &ConsensusHash([0x01; 20]),
+                    456,
+                    123,
+                );
+                set_block_processed(
+                    peer_server.chainstate(),
+                    &parent_consensus_hash,
+                    &parent_block.block_hash(),
+                    true,
+                );
 
-        // stuff some transactions into peer_2's mempool
-        // (relates to mempool query tests)
-        let mut mempool = peer_2.mempool.take().unwrap();
-        let mut mempool_tx = mempool.tx_begin().unwrap();
-        for i in 0..10 {
-            let pk = StacksPrivateKey::new();
-            let addr = StacksAddress::from_public_keys(
-                C32_ADDRESS_VERSION_TESTNET_SINGLESIG,
-                &AddressHashMode::SerializeP2PKH,
-                1,
-                &vec![StacksPublicKey::from_private(&StacksPrivateKey::new())],
-            )
-            .unwrap();
-            let mut tx = StacksTransaction {
-                version: TransactionVersion::Testnet,
-                chain_id: 0x80000000,
-                auth: TransactionAuth::from_p2pkh(&pk).unwrap(),
-                anchor_mode: TransactionAnchorMode::Any,
-                post_condition_mode: TransactionPostConditionMode::Allow,
-                post_conditions: vec![],
-                payload: TransactionPayload::TokenTransfer(
-                    addr.to_account_principal(),
+                store_staging_block(
+                    peer_server.chainstate(),
+                    &child_consensus_hash,
+                    &child_block,
+                    &parent_consensus_hash,
+                    456,
                     123,
-                    TokenTransferMemo([0u8; 34]),
-                ),
-            };
-            tx.set_tx_fee(1000);
-            tx.set_origin_nonce(0);
+                );
+                set_block_processed(
+                    peer_server.chainstate(),
+                    &child_consensus_hash,
+                    &child_block.block_hash(),
+                    true,
+                );
 
-            let txid = tx.txid();
-            let tx_bytes = tx.serialize_to_vec();
-            let origin_addr = tx.origin_address();
-            let origin_nonce = tx.get_origin_nonce();
-            let sponsor_addr = tx.sponsor_address().unwrap_or(origin_addr.clone());
-            let sponsor_nonce = tx.get_sponsor_nonce().unwrap_or(origin_nonce);
-            let tx_fee = tx.get_tx_fee();
+                for mblock in mblocks.iter() {
+                    store_staging_microblock(
+                        peer_server.chainstate(),
+                        &parent_consensus_hash,
+                        &parent_block.block_hash(),
+                        &mblock,
+                    );
+                }
 
-            // should succeed
-            MemPoolDB::try_add_tx(
-                &mut mempool_tx,
-                peer_1.chainstate(),
-                &consensus_hash,
-                &stacks_block.block_hash(),
-                txid.clone(),
-                tx_bytes,
-                tx_fee,
-                stacks_block.header.total_work.work,
-                &origin_addr,
-                origin_nonce,
-                &sponsor_addr,
-                sponsor_nonce,
-                None,
-            )
-            .unwrap();
-        }
-        mempool_tx.commit().unwrap();
-        peer_2.mempool.replace(mempool);
+                set_microblocks_processed(
+                    peer_server.chainstate(),
+                    &child_consensus_hash,
+                    &child_block.block_hash(),
+                    &mblocks.last().as_ref().unwrap().block_hash(),
+                );
 
-        let peer_1_sortdb = peer_1.sortdb.take().unwrap();
-        let peer_1_stacks_node = peer_1.stacks_node.take().unwrap();
-        let _ = peer_1
-            .network
-            .refresh_burnchain_view(&peer_1_sortdb, &peer_1_stacks_node.chainstate, false)
-            .unwrap();
-        peer_1.sortdb = Some(peer_1_sortdb);
-        peer_1.stacks_node = Some(peer_1_stacks_node);
+                *server_microblocks_cell.borrow_mut() = mblocks;
 
-        let peer_2_sortdb = peer_2.sortdb.take().unwrap();
-        let peer_2_stacks_node = peer_2.stacks_node.take().unwrap();
-        let _ = peer_2
-            .network
-            .refresh_burnchain_view(&peer_2_sortdb, &peer_2_stacks_node.chainstate, false)
-            .unwrap();
-        peer_2.sortdb = Some(peer_2_sortdb);
-        peer_2.stacks_node = Some(peer_2_stacks_node);
+                convo_client.new_getmicroblocks_confirmed(child_index_block_hash)
+            },
+            |ref http_request,
+             ref http_response,
+             ref mut peer_client,
+             ref mut peer_server,
+             ref convo_client,
+             ref convo_server| {
+                let req_md = http_request.metadata().clone();
+                match (*http_response).clone() {
+                    HttpResponseType::Microblocks(_, mut microblocks) => {
+                        microblocks.reverse();
+                        assert_eq!(microblocks.len(), (*server_microblocks_cell.borrow()).len());
+                        assert_eq!(microblocks, *server_microblocks_cell.borrow());
+                        true
+                    }
+                    _ => {
+                        error!("Invalid response: {:?}", &http_response);
+                        false
+                    }
+                }
+            },
+        );
+    }
+
+    #[test]
+    #[ignore]
+    fn test_rpc_unconfirmed_microblocks() {
+        let server_microblocks_cell = RefCell::new(vec![]);
 
-        let view_1 = peer_1.get_burnchain_view().unwrap();
-        let view_2 = peer_2.get_burnchain_view().unwrap();
+        test_rpc(
+            "test_rpc_unconfirmed_microblocks",
+            40050,
+            40051,
+            50050,
+            50051,
+            true,
+            |ref mut peer_client,
+             ref mut convo_client,
+             ref mut peer_server,
+             ref mut convo_server| {
+                let privk = StacksPrivateKey::from_hex(
+                    "eb05c83546fdd2c79f10f5ad5434a90dd28f7e3acb7c092157aa1bc3656b012c01",
+                )
+                .unwrap();
 
-        let mut convo_1 = ConversationHttp::new(
-            format!("127.0.0.1:{}", peer_1_http)
-                .parse::<SocketAddr>()
-                .unwrap(),
-            Some(UrlString::try_from(format!("http://peer1.com")).unwrap()),
-            peer_1.to_peer_host(),
-            &peer_1.config.connection_opts,
-            0,
-        );
+                let consensus_hash = ConsensusHash([0x02; 20]);
+                let anchored_block_hash = BlockHeaderHash([0x03; 32]);
+                let index_block_hash =
+                    StacksBlockHeader::make_index_block_hash(&consensus_hash, &anchored_block_hash);
 
-        let mut convo_2 = ConversationHttp::new(
-            format!("127.0.0.1:{}", peer_2_http)
-                .parse::<SocketAddr>()
-                .unwrap(),
-            Some(UrlString::try_from(format!("http://peer2.com")).unwrap()),
-            peer_2.to_peer_host(),
-            &peer_2.config.connection_opts,
-            1,
-        );
+                let mut mblocks = make_sample_microblock_stream(&privk, &anchored_block_hash);
+                mblocks.truncate(15);
 
-        let req = make_request(&mut peer_1, &mut convo_1, &mut peer_2, &mut convo_2);
+                for mblock in mblocks.iter() {
+                    store_staging_microblock(
+                        peer_server.chainstate(),
+                        &consensus_hash,
+                        &anchored_block_hash,
+                        &mblock,
+                    );
+                }
 
-        convo_1.send_request(req.clone()).unwrap();
-        let mut peer_1_mempool = peer_1.mempool.take().unwrap();
-        let peer_2_mempool = peer_2.mempool.take().unwrap();
+                *server_microblocks_cell.borrow_mut() = mblocks;
 
-        test_debug!("convo1 sends to convo2");
-        convo_send_recv(
-            &mut convo_1,
-            &peer_1_mempool,
-            peer_1.chainstate(),
-            &mut convo_2,
-            &peer_2_mempool,
-            peer_2.chainstate(),
+                // start at seq 5
+                convo_client.new_getmicroblocks_unconfirmed(index_block_hash, 5)
+            },
+            |ref http_request,
+             ref http_response,
+             ref mut peer_client,
+             ref mut peer_server,
+             ref convo_client,
+             ref convo_server| {
+                let req_md = http_request.metadata().clone();
+                match http_response {
+                    HttpResponseType::Microblocks(response_md, microblocks) => {
+                        assert_eq!(microblocks.len(), 10);
+                        assert_eq!(
+                            *microblocks,
+                            (*server_microblocks_cell.borrow())[5..].to_vec()
+                        );
+                        true
+                    }
+                    _ => {
+                        error!("Invalid response: {:?}", &http_response);
+                        false
+                    }
+                }
+            },
         );
+    }
 
-        // hack around the borrow-checker
-        let mut peer_1_sortdb = peer_1.sortdb.take().unwrap();
-        let mut peer_1_stacks_node = peer_1.stacks_node.take().unwrap();
-
-        Relayer::setup_unconfirmed_state(&mut peer_1_stacks_node.chainstate, &peer_1_sortdb)
-            .unwrap();
+    #[test]
+    #[ignore]
+    fn test_rpc_unconfirmed_transaction() {
+        let last_txid = RefCell::new(Txid([0u8; 32]));
+        let last_mblock = RefCell::new(BlockHeaderHash([0u8; 32]));
 
-        convo_1
-            .chat(
-                &mut peer_1.network,
-                &mut peer_1_sortdb,
-                &mut peer_1_stacks_node.chainstate,
-                &mut peer_1_mempool,
-                &RPCHandlerArgs::default(),
-            )
-            .unwrap();
+        test_rpc(
+            "test_rpc_unconfirmed_transaction",
+            40052,
+            40053,
+            50052,
+            50053,
+            true,
+            |ref mut peer_client,
+             ref mut convo_client,
+             ref mut peer_server,
+             ref mut convo_server| {
+                let privk = StacksPrivateKey::from_hex(
+                    "eb05c83546fdd2c79f10f5ad5434a90dd28f7e3acb7c092157aa1bc3656b012c01",
+                )
+                .unwrap();
 
-        peer_1.sortdb = Some(peer_1_sortdb);
-        peer_1.stacks_node = Some(peer_1_stacks_node);
-        peer_1.mempool = Some(peer_1_mempool);
-        peer_2.mempool = Some(peer_2_mempool);
+                let sortdb = peer_server.sortdb.take().unwrap();
+                Relayer::setup_unconfirmed_state(peer_server.chainstate(), &sortdb).unwrap();
+                peer_server.sortdb = Some(sortdb);
 
-        test_debug!("convo2 sends to convo1");
+                assert!(peer_server.chainstate().unconfirmed_state.is_some());
+                let (txid, mblock_hash) = match peer_server.chainstate().unconfirmed_state {
+                    Some(ref unconfirmed) => {
+                        assert!(unconfirmed.mined_txs.len() > 0);
+                        let mut txid = Txid([0u8; 32]);
+                        let mut mblock_hash = BlockHeaderHash([0u8; 32]);
+                        for (next_txid, (_, mbh, ..)) in unconfirmed.mined_txs.iter() {
+                            txid = next_txid.clone();
+                            mblock_hash = mbh.clone();
+                            break;
+                        }
+                        (txid, mblock_hash)
+                    }
+                    None => {
+                        panic!("No unconfirmed state");
+                    }
+                };
 
-        // hack around the borrow-checker
-        let mut peer_2_sortdb = peer_2.sortdb.take().unwrap();
-        let mut peer_2_stacks_node = peer_2.stacks_node.take().unwrap();
-        let mut peer_2_mempool = peer_2.mempool.take().unwrap();
+                *last_txid.borrow_mut() = txid.clone();
+                *last_mblock.borrow_mut() = mblock_hash.clone();
 
-        let _ = peer_2
-            .network
-            .refresh_burnchain_view(&peer_2_sortdb, &peer_2_stacks_node.chainstate, false)
-            .unwrap();
+                convo_client.new_gettransaction_unconfirmed(txid)
+            },
+            |ref http_request,
+             ref http_response,
+             ref mut peer_client,
+             ref mut peer_server,
+             ref convo_client,
+             ref convo_server| {
+                let req_md = http_request.metadata().clone();
+                match http_response {
+                    HttpResponseType::UnconfirmedTransaction(response_md, unconfirmed_resp) => {
+                        assert_eq!(
+                            unconfirmed_resp.status,
+                            UnconfirmedTransactionStatus::Microblock {
+                                block_hash: (*last_mblock.borrow()).clone(),
+                                seq: 0
+                            }
+                        );
+                        let tx = StacksTransaction::consensus_deserialize(
+                            &mut &hex_bytes(&unconfirmed_resp.tx).unwrap()[..],
+                        )
+                        .unwrap();
+                        assert_eq!(tx.txid(), *last_txid.borrow());
+                        true
+                    }
+                    _ => {
+                        error!("Invalid response: {:?}", &http_response);
+                        false
+                    }
+                }
+            },
+        );
+    }
 
-        Relayer::setup_unconfirmed_state(&mut peer_2_stacks_node.chainstate, &peer_2_sortdb)
-            .unwrap();
+    #[test]
+    #[ignore]
+    fn test_rpc_missing_getblock() {
+        test_rpc(
+            "test_rpc_missing_getblock",
+            40060,
+            40061,
+            50060,
+            50061,
+            true,
+            |ref mut peer_client,
+             ref mut convo_client,
+             ref mut peer_server,
+             ref mut convo_server| {
+                let peer_server_block_hash = BlockHeaderHash([0x04; 32]);
+                let peer_server_consensus_hash = ConsensusHash([0x02; 20]);
+                let index_block_hash = StacksBlockHeader::make_index_block_hash(
+                    &peer_server_consensus_hash,
+                    &peer_server_block_hash,
+                );
 
-        convo_2
-            .chat(
-                &mut peer_2.network,
-                &mut peer_2_sortdb,
-                &mut peer_2_stacks_node.chainstate,
-                &mut peer_2_mempool,
-                &RPCHandlerArgs::default(),
-            )
-            .unwrap();
+                // now ask for it
+                convo_client.new_getblock(index_block_hash)
+            },
+            |ref http_request,
+             ref http_response,
+             ref mut peer_client,
+             ref mut peer_server,
+             ref convo_client,
+             ref convo_server| {
+                let req_md = http_request.metadata().clone();
+                match http_response {
+                    HttpResponseType::NotFound(response_md, msg) => true,
+                    _ => {
+                        error!("Invalid response: {:?}", &http_response);
+                        false
+                    }
+                }
+            },
+        );
+    }
 
-        peer_2.sortdb = Some(peer_2_sortdb);
-        peer_2.stacks_node = Some(peer_2_stacks_node);
-        let mut peer_1_mempool = peer_1.mempool.take().unwrap();
+    #[test]
+    #[ignore]
+    fn test_rpc_missing_index_getmicroblocks() {
+        test_rpc(
+            "test_rpc_missing_index_getmicroblocks",
+            40070,
+            40071,
+            50070,
+            50071,
+            true,
+            |ref mut peer_client,
+             ref mut convo_client,
+             ref mut peer_server,
+             ref mut convo_server| {
+                let peer_server_block_hash = BlockHeaderHash([0x04; 32]);
+                let peer_server_consensus_hash = ConsensusHash([0x02; 20]);
+                let index_block_hash = StacksBlockHeader::make_index_block_hash(
+                    &peer_server_consensus_hash,
+                    &peer_server_block_hash,
+                );
 
-        convo_send_recv(
-            &mut convo_2,
-            &peer_2_mempool,
-            peer_2.chainstate(),
-            &mut convo_1,
-            &peer_1_mempool,
-            peer_1.chainstate(),
+                // now ask for it
+                convo_client.new_getmicroblocks_indexed(index_block_hash)
+            },
+            |ref http_request,
+             ref http_response,
+             ref mut peer_client,
+             ref mut peer_server,
+             ref convo_client,
+             ref convo_server| {
+                let req_md = http_request.metadata().clone();
+                match http_response {
+                    HttpResponseType::NotFound(response_md, msg) => true,
+                    _ => {
+                        error!("Invalid response: {:?}", &http_response);
+                        false
+                    }
+                }
+            },
         );
+    }
 
-        test_debug!("flush convo1");
+    #[test]
+    #[ignore]
+    fn test_rpc_missing_confirmed_getmicroblocks() {
+        test_rpc(
+            "test_rpc_missing_confirmed_getmicroblocks",
+            40072,
+            40073,
+            50072,
+            50073,
+            true,
+            |ref mut peer_client,
+             ref mut convo_client,
+             ref mut peer_server,
+             ref mut convo_server| {
+                let peer_server_block_hash = BlockHeaderHash([0x04; 32]);
+                let peer_server_consensus_hash = ConsensusHash([0x02; 20]);
+                let index_block_hash = StacksBlockHeader::make_index_block_hash(
+                    &peer_server_consensus_hash,
+                    &peer_server_block_hash,
+                );
 
-        // hack around the borrow-checker
-        convo_send_recv(
-            &mut convo_1,
-            &peer_1_mempool,
-            peer_1.chainstate(),
-            &mut convo_2,
-            &peer_2_mempool,
-            peer_2.chainstate(),
+                // now ask for it
+                convo_client.new_getmicroblocks_confirmed(index_block_hash)
+            },
+            |ref http_request,
+             ref http_response,
+             ref mut peer_client,
+             ref mut peer_server,
+             ref convo_client,
+             ref convo_server| {
+                let req_md = http_request.metadata().clone();
+                match http_response {
+                    HttpResponseType::NotFound(response_md, msg) => true,
+                    _ => {
+                        error!("Invalid response: {:?}", &http_response);
+                        false
+                    }
+                }
+            },
         );
+    }
 
-        peer_2.mempool = Some(peer_2_mempool);
+    #[test]
+    #[ignore]
+    fn test_rpc_missing_unconfirmed_microblocks() {
+        let server_microblocks_cell = RefCell::new(vec![]);
 
-        let mut peer_1_sortdb = peer_1.sortdb.take().unwrap();
-        let mut peer_1_stacks_node = peer_1.stacks_node.take().unwrap();
+        test_rpc(
+            "test_rpc_missing_unconfirmed_microblocks",
+            40080,
+            40081,
+            50080,
+            50081,
+            true,
+            |ref mut peer_client,
+             ref mut convo_client,
+             ref mut peer_server,
+             ref mut convo_server| {
+                let privk = StacksPrivateKey::from_hex(
+                    "eb05c83546fdd2c79f10f5ad5434a90dd28f7e3acb7c092157aa1bc3656b012c01",
+                )
+                .unwrap();
 
-        let _ = peer_1
-            .network
-            .refresh_burnchain_view(&peer_1_sortdb, &peer_1_stacks_node.chainstate, false)
-            .unwrap();
+                let consensus_hash = ConsensusHash([0x02; 20]);
+                let anchored_block_hash = BlockHeaderHash([0x03; 32]);
+                let index_block_hash =
+                    StacksBlockHeader::make_index_block_hash(&consensus_hash, &anchored_block_hash);
 
-        Relayer::setup_unconfirmed_state(&mut peer_1_stacks_node.chainstate, &peer_1_sortdb)
-            .unwrap();
+                let mut mblocks = make_sample_microblock_stream(&privk, &anchored_block_hash);
+                mblocks.truncate(15);
 
-        convo_1
-            .chat(
-                &mut peer_1.network,
-                &mut peer_1_sortdb,
-                &mut peer_1_stacks_node.chainstate,
-                &mut peer_1_mempool,
-                &RPCHandlerArgs::default(),
-            )
-            .unwrap();
+                for mblock in mblocks.iter() {
+                    store_staging_microblock(
+                        peer_server.chainstate(),
+                        &consensus_hash,
+                        &anchored_block_hash,
+                        &mblock,
+                    );
+                }
 
-        convo_1
-            .try_flush(&peer_1_mempool, &mut peer_1_stacks_node.chainstate)
-            .unwrap();
+                *server_microblocks_cell.borrow_mut() = mblocks;
 
-        peer_1.sortdb = Some(peer_1_sortdb);
-        peer_1.stacks_node = Some(peer_1_stacks_node);
-        peer_1.mempool = Some(peer_1_mempool);
+                // start at seq 16 (which doesn't exist)
+                convo_client.new_getmicroblocks_unconfirmed(index_block_hash, 16)
+            },
+            |ref http_request,
+             ref http_response,
+             ref mut peer_client,
+             ref mut peer_server,
+             ref convo_client,
+             ref convo_server| {
+                let req_md = http_request.metadata().clone();
+                match http_response {
+                    HttpResponseType::NotFound(response_md, msg) => true,
+                    _ => {
+                        error!("Invalid response: {:?}", &http_response);
+                        false
+                    }
+                }
+            },
+        );
+    }
 
-        // should have gotten a reply
-        let resp_opt = convo_1.try_get_response();
-        assert!(resp_opt.is_some());
+    #[test]
+    #[ignore]
+    fn test_rpc_get_contract_src() {
+        // Test v2/contracts/source (aka GetContractSrc) endpoint.
+        // In this test, we don't set any tip parameters, and allow the endpoint to execute against
+        // the canonical Stacks tip.
+        // The contract source we are querying for exists in the anchored state, so we expect the
+        // query to succeed.
+        test_rpc(
+            "test_rpc_get_contract_src",
+            40090,
+            40091,
+            50090,
+            50091,
+            true,
+            |ref mut peer_client,
+             ref mut convo_client,
+             ref mut peer_server,
+             ref mut convo_server| {
+                convo_client.new_getcontractsrc(
+                    StacksAddress::from_string("ST2DS4MSWSGJ3W9FBC6BVT0Y92S345HY8N3T6AV7R")
+                        .unwrap(),
+                    "hello-world".try_into().unwrap(),
+                    TipRequest::UseLatestAnchoredTip,
+                    false,
+                )
+            },
+            |ref http_request,
+             ref http_response,
+             ref mut peer_client,
+             ref mut peer_server,
+             ref convo_client,
+             ref convo_server| {
+                let req_md = http_request.metadata().clone();
+                match http_response {
+                    HttpResponseType::GetContractSrc(response_md, data) => {
+                        assert_eq!(data.source, TEST_CONTRACT);
+                        true
+                    }
+                    _ => {
+                        error!("Invalid response; {:?}", &http_response);
+                        false
+                    }
+                }
+            },
+        );
+    }
 
-        let resp = resp_opt.unwrap();
-        assert!(check_result(
-            &req,
-            &resp,
-            &mut peer_1,
-            &mut peer_2,
-            &convo_1,
-            &convo_2
-        ));
+    #[test]
+    #[ignore]
+    fn test_rpc_get_contract_src_unconfirmed_with_canonical_tip() {
+        // Test v2/contracts/source (aka GetContractSrc) endpoint.
+        // In this test, we don't set any tip parameters, and allow the endpoint to execute against
+        // the canonical Stacks tip.
+        // The contract source we are querying for only exists in the unconfirmed state, so we
+        // expect the query to fail.
+        test_rpc(
+            "test_rpc_get_contract_src_unconfirmed_with_canonical_tip",
+            40100,
+            40101,
+            50100,
+            50101,
+            true,
+            |ref mut peer_client,
+             ref mut convo_client,
+             ref mut peer_server,
+             ref mut convo_server| {
+                convo_client.new_getcontractsrc(
+                    StacksAddress::from_string("ST2DS4MSWSGJ3W9FBC6BVT0Y92S345HY8N3T6AV7R")
+                        .unwrap(),
+                    "hello-world-unconfirmed".try_into().unwrap(),
+                    TipRequest::UseLatestAnchoredTip,
+                    false,
+                )
+            },
+            |ref http_request,
+             ref http_response,
+             ref mut peer_client,
+             ref mut peer_server,
+             ref convo_client,
+             ref convo_server| {
+                let req_md = http_request.metadata().clone();
+                match http_response {
+                    HttpResponseType::NotFound(_, error_str) => {
+                        assert_eq!(error_str, "No contract source data found");
+                        true
+                    }
+                    _ => {
+                        error!("Invalid response; {:?}", &http_response);
+                        false
+                    }
+                }
+            },
+        );
     }
 
-    /// This test tests two things:
-    /// (1) the get info RPC call
-    /// (2) whether the ConversationHttp object gets correctly updated with a peer's canonical
-    /// stacks tip height, which is sent in HTTP headers as part of the request/response
     #[test]
     #[ignore]
-    fn test_rpc_getinfo() {
-        let peer_server_info = RefCell::new(None);
-        let client_stacks_height = 17;
+    fn test_rpc_get_contract_src_with_unconfirmed_tip() {
+        // Test v2/contracts/source (aka GetContractSrc) endpoint.
+        // In this test, we set `tip_req` to be the unconfirmed chain tip.
+        // The contract source we are querying for exists in the unconfirmed state, so we expect
+        // the query to succeed.
         test_rpc(
-            "test_rpc_getinfo",
-            40000,
-            40001,
-            50000,
-            50001,
+            "test_rpc_get_contract_src_with_unconfirmed_tip",
+            40102,
+            40103,
+            50102,
+            50103,
             true,
             |ref mut peer_client,
              ref mut convo_client,
              ref mut peer_server,
              ref mut convo_server| {
-                let peer_info = RPCPeerInfoData::from_network(
-                    &peer_server.network,
-                    &peer_server.stacks_node.as_ref().unwrap().chainstate,
-                    &None,
-                    &Sha256Sum::zero(),
-                );
-
-                *peer_server_info.borrow_mut() = Some(peer_info);
-
-                convo_client.new_getinfo(Some(client_stacks_height))
+                let unconfirmed_tip = peer_client
+                    .chainstate()
+                    .unconfirmed_state
+                    .as_ref()
+                    .unwrap()
+                    .unconfirmed_chain_tip
+                    .clone();
+                convo_client.new_getcontractsrc(
+                    StacksAddress::from_string("ST2DS4MSWSGJ3W9FBC6BVT0Y92S345HY8N3T6AV7R")
+                        .unwrap(),
+                    "hello-world-unconfirmed".try_into().unwrap(),
+                    TipRequest::SpecificTip(unconfirmed_tip),
+                    false,
+                )
             },
             |ref http_request,
              ref http_response,
@@ -4259,31 +8352,13 @@ mod test {
              ref convo_client,
              ref convo_server| {
                 let req_md = http_request.metadata().clone();
-                assert_eq!(convo_client.canonical_stacks_tip_height, Some(1));
-                assert_eq!(
-                    convo_server.canonical_stacks_tip_height,
-                    Some(client_stacks_height)
-                );
                 match http_response {
-                    HttpResponseType::PeerInfo(response_md, peer_data) => {
-                        assert_eq!(Some((*peer_data).clone()), *peer_server_info.borrow());
-                        assert!(peer_data.node_public_key.is_some());
-                        assert!(peer_data.node_public_key_hash.is_some());
-                        assert_eq!(
-                            peer_data.node_public_key_hash,
-                            Some(Hash160::from_node_public_key(
-                                &peer_data
-                                    .node_public_key
-                                    .clone()
-                                    .unwrap()
-                                    .to_public_key()
-                                    .unwrap()
-                            ))
-                        );
+                    HttpResponseType::GetContractSrc(response_md, data) => {
+                        assert_eq!(data.source, TEST_CONTRACT_UNCONFIRMED);
                         true
                     }
                     _ => {
-                        error!("Invalid response: {:?}", &http_response);
+                        error!("Invalid response; {:?}", &http_response);
                         false
                     }
                 }
@@ -4293,18 +8368,30 @@ mod test {
 
     #[test]
     #[ignore]
-    fn test_rpc_getneighbors() {
+    fn test_rpc_get_contract_src_use_latest_tip() {
+        // Test v2/contracts/source (aka GetContractSrc) endpoint.
+        // In this test, we set `tip_req` to UseLatestUnconfirmedTip.
+        // The contract source we are querying for exists in the unconfirmed state, so we expect
+        // the query to succeed.
         test_rpc(
-            "test_rpc_getneighbors",
-            40010,
-            40011,
-            50010,
-            50011,
+            "test_rpc_get_contract_src_use_latest_tip",
+            40104,
+            40105,
+            50104,
+            50105,
             true,
             |ref mut peer_client,
              ref mut convo_client,
              ref mut peer_server,
-             ref mut convo_server| { convo_client.new_getneighbors() },
+             ref mut convo_server| {
+                convo_client.new_getcontractsrc(
+                    StacksAddress::from_string("ST2DS4MSWSGJ3W9FBC6BVT0Y92S345HY8N3T6AV7R")
+                        .unwrap(),
+                    "hello-world-unconfirmed".try_into().unwrap(),
+                    TipRequest::UseLatestAnchoredTip,
+                    false,
+                )
+            },
             |ref http_request,
              ref http_response,
              ref mut peer_client,
@@ -4313,13 +8400,12 @@ mod test {
              ref convo_server| {
                 let req_md = http_request.metadata().clone();
                 match http_response {
-                    HttpResponseType::Neighbors(response_md, neighbor_info) => {
-                        assert_eq!(neighbor_info.sample.len(), 1);
-                        assert_eq!(neighbor_info.sample[0].port, peer_client.config.server_port); // we see ourselves as the neighbor
+                    HttpResponseType::GetContractSrc(response_md, data) => {
+                        assert_eq!(data.source, TEST_CONTRACT_UNCONFIRMED);
                         true
                     }
                     _ => {
-                        error!("Invalid response: {:?}", &http_response);
+                        error!("Invalid response; {:?}", &http_response);
                         false
                     }
                 }
@@ -4329,67 +8415,25 @@ mod test {
 
     #[test]
     #[ignore]
-    fn test_rpc_getheaders() {
-        let server_blocks_cell = RefCell::new(None);
-
+    fn test_rpc_get_account() {
         test_rpc(
-            "test_rpc_getheaders",
-            40012,
-            40013,
-            50012,
-            50013,
+            "test_rpc_get_account",
+            40110,
+            40111,
+            50110,
+            50111,
             true,
             |ref mut peer_client,
              ref mut convo_client,
              ref mut peer_server,
              ref mut convo_server| {
-                // have "server" peer store a few continuous block to staging
-                let mut blocks: Vec<StacksBlock> = vec![];
-                let mut index_block_hashes = vec![];
-                for i in 0..25 {
-                    let mut peer_server_block = make_codec_test_block(25);
-
-                    peer_server_block.header.total_work.work = (i + 1) as u64;
-                    peer_server_block.header.total_work.burn = (i + 1) as u64;
-                    peer_server_block.header.parent_block = blocks
-                        .last()
-                        .map(|blk| blk.block_hash())
-                        .unwrap_or(BlockHeaderHash([0u8; 32]));
-
-                    let peer_server_consensus_hash = ConsensusHash([(i + 1) as u8; 20]);
-                    let index_block_hash = StacksBlockHeader::make_index_block_hash(
-                        &peer_server_consensus_hash,
-                        &peer_server_block.block_hash(),
-                    );
-
-                    test_debug!("Store peer server index block {:?}", &index_block_hash);
-                    store_staging_block(
-                        peer_server.chainstate(),
-                        &peer_server_consensus_hash,
-                        &peer_server_block,
-                        &ConsensusHash([i as u8; 20]),
-                        456,
-                        123,
-                    );
-                    set_block_processed(
-                        peer_server.chainstate(),
-                        &peer_server_consensus_hash,
-                        &peer_server_block.block_hash(),
-                        true,
-                    );
-
-                    index_block_hashes.push(index_block_hash);
-                    blocks.push(peer_server_block);
-                }
-
-                let rev_blocks: Vec<_> = blocks.into_iter().rev().collect();
-                let rev_ibhs: Vec<_> = index_block_hashes.into_iter().rev().collect();
-
-                let tip = rev_ibhs[0].clone();
-                *server_blocks_cell.borrow_mut() = Some((rev_blocks, rev_ibhs));
-
-                // now ask for it
-                convo_client.new_getheaders(25, TipRequest::SpecificTip(tip))
+                convo_client.new_getaccount(
+                    StacksAddress::from_string("ST2DS4MSWSGJ3W9FBC6BVT0Y92S345HY8N3T6AV7R")
+                        .unwrap()
+                        .to_account_principal(),
+                    TipRequest::UseLatestAnchoredTip,
+                    false,
+                )
             },
             |ref http_request,
              ref http_response,
@@ -4399,20 +8443,14 @@ mod test {
              ref convo_server| {
                 let req_md = http_request.metadata().clone();
                 match http_response {
-                    HttpResponseType::Headers(response_md, headers) => {
-                        assert_eq!(headers.len(), 25);
-                        let expected = server_blocks_cell.borrow().clone().unwrap();
-                        for (i, h) in headers.iter().enumerate() {
-                            assert_eq!(h.header, expected.0[i].header);
-                            assert_eq!(h.consensus_hash, ConsensusHash([(25 - i) as u8; 20]));
-                            if i + 1 < headers.len() {
-                                assert_eq!(h.parent_block_id, expected.1[i + 1]);
-                            }
-                        }
+                    HttpResponseType::GetAccount(response_md, data) => {
+                        assert_eq!(data.nonce, 2);
+                        let balance = u128::from_str_radix(&data.balance[2..], 16).unwrap();
+                        assert_eq!(balance, 1000000000);
                         true
                     }
                     _ => {
-                        error!("Invalid response: {:?}", &http_response);
+                        error!("Invalid response; {:?}", &http_response);
                         false
                     }
                 }
@@ -4421,112 +8459,404 @@ mod test {
     }
 
     #[test]
-    #[ignore]
-    fn test_rpc_unconfirmed_getblock() {
-        let server_block_cell = RefCell::new(None);
-
-        test_rpc(
-            "test_rpc_unconfirmed_getblock",
-            40020,
-            40021,
-            50020,
-            50021,
-            true,
-            |ref mut peer_client,
-             ref mut convo_client,
-             ref mut peer_server,
-             ref mut convo_server| {
-                // have "server" peer store a block to staging
-                let peer_server_block = make_codec_test_block(25);
-                let peer_server_consensus_hash = ConsensusHash([0x02; 20]);
-                let index_block_hash = StacksBlockHeader::make_index_block_hash(
-                    &peer_server_consensus_hash,
-                    &peer_server_block.block_hash(),
-                );
+    fn test_mempool_account_nonce_gaps() {
+        let addr = StacksAddress::from_string("ST2DS4MSWSGJ3W9FBC6BVT0Y92S345HY8N3T6AV7R").unwrap();
+        let make_tx = |nonce: u64| MemPoolTxMetadata {
+            txid: Txid([nonce as u8; 32]),
+            len: 100,
+            tx_fee: 1000 + nonce,
+            consensus_hash: ConsensusHash([0x01; 20]),
+            block_header_hash: BlockHeaderHash([0x02; 32]),
+            block_height: 1,
+            origin_address: addr.clone(),
+            origin_nonce: nonce,
+            sponsor_address: addr.clone(),
+            sponsor_nonce: nonce,
+            last_known_origin_nonce: None,
+            last_known_sponsor_nonce: None,
+            accept_time: 0,
+            expiration_height: None,
+            bundle_id: None,
+        };
+
+        // no pending transactions
+        let resp = MempoolAccountResponse::from_pending_txs(&addr, 3, &[]);
+        assert!(resp.chained_txs.is_empty());
+        assert!(resp.nonce_gaps.is_empty());
+        assert_eq!(resp.lowest_missing_nonce, 3);
+
+        // a stale tx, a chain of two, then gaps at 6 and 8-9
+        let txs: Vec<_> = [2, 3, 4, 5, 7, 10].iter().map(|n| make_tx(*n)).collect();
+        let resp = MempoolAccountResponse::from_pending_txs(&addr, 3, &txs);
+        assert_eq!(resp.principal, addr.to_string());
+        assert_eq!(resp.nonce, 3);
+        assert_eq!(
+            resp.chained_txs
+                .iter()
+                .map(|tx| tx.nonce)
+                .collect::<Vec<_>>(),
+            vec![3, 4, 5]
+        );
+        assert_eq!(
+            resp.stranded_txs
+                .iter()
+                .map(|tx| tx.nonce)
+                .collect::<Vec<_>>(),
+            vec![7, 10]
+        );
+        assert_eq!(
+            resp.nonce_gaps,
+            vec![
+                MempoolNonceGap { start: 6, end: 6 },
+                MempoolNonceGap { start: 8, end: 9 }
+            ]
+        );
+        assert_eq!(resp.lowest_missing_nonce, 6);
+
+        // a gap right at the account nonce strands everything
+        let resp = MempoolAccountResponse::from_pending_txs(&addr, 1, &txs[..2]);
+        assert!(resp.chained_txs.is_empty());
+        assert_eq!(resp.stranded_txs.len(), 2);
+        assert_eq!(resp.nonce_gaps, vec![MempoolNonceGap { start: 1, end: 1 }]);
+        assert_eq!(resp.lowest_missing_nonce, 1);
+    }
+
+    #[test]
+    fn test_account_next_nonce() {
+        let addr = StacksAddress::from_string("ST2DS4MSWSGJ3W9FBC6BVT0Y92S345HY8N3T6AV7R").unwrap();
+        let other_addr =
+            StacksAddress::from_string("ST1PQHQKV0RJXZFY1DGX8MNSNYVE3VGZJSRTPGZGM").unwrap();
+        let make_tx =
+            |origin: &StacksAddress, origin_nonce: u64, sponsor_nonce: u64| MemPoolTxMetadata {
+                txid: Txid([origin_nonce as u8; 32]),
+                len: 100,
+                tx_fee: 1000,
+                consensus_hash: ConsensusHash([0x01; 20]),
+                block_header_hash: BlockHeaderHash([0x02; 32]),
+                block_height: 1,
+                origin_address: origin.clone(),
+                origin_nonce,
+                sponsor_address: addr.clone(),
+                sponsor_nonce,
+                last_known_origin_nonce: Some(origin_nonce),
+                last_known_sponsor_nonce: Some(sponsor_nonce),
+                accept_time: 0,
+                expiration_height: None,
+                bundle_id: None,
+            };
+
+        // nothing pending
+        let resp = AccountNextNonceResponse::from_pending_txs(&addr, 3, &[], &[]);
+        assert_eq!(resp.next_nonce, 3);
+        assert_eq!(resp.possible_next_nonce, 3);
+        assert_eq!(resp.last_known_nonce, None);
+        assert!(resp.missing_nonces.is_empty());
+
+        // sends at 2 (already mined), 3 and 6, and a sponsorship at 4, leave 5 missing
+        let origin_txs: Vec<_> = [2, 3, 6].iter().map(|n| make_tx(&addr, *n, *n)).collect();
+        let sponsored_txs = vec![make_tx(&other_addr, 0, 4)];
+        let resp =
+            AccountNextNonceResponse::from_pending_txs(&addr, 3, &origin_txs, &sponsored_txs);
+        assert_eq!(resp.principal, addr.to_string());
+        assert_eq!(resp.confirmed_nonce, 3);
+        assert_eq!(resp.last_known_nonce, Some(6));
+        assert_eq!(resp.next_nonce, 5);
+        assert_eq!(resp.possible_next_nonce, 7);
+        assert_eq!(
+            resp.missing_nonces,
+            vec![MempoolNonceGap { start: 5, end: 5 }]
+        );
+
+        // without the sponsorship, 4 is missing too
+        let resp = AccountNextNonceResponse::from_pending_txs(&addr, 3, &origin_txs, &[]);
+        assert_eq!(resp.next_nonce, 4);
+        assert_eq!(
+            resp.missing_nonces,
+            vec![MempoolNonceGap { start: 4, end: 5 }]
+        );
+    }
+
+    #[test]
+    fn test_contract_publish_policy_response() {
+        let resp = ContractPublishPolicyResponse::from_policy(&ContractPublishPolicy::default());
+        assert!(!resp.restricted);
+        assert!(resp.allowed_publishers.is_empty());
+        assert!(resp.allowed_code_hashes.is_empty());
+
+        let addr_1 =
+            StacksAddress::from_string("ST2DS4MSWSGJ3W9FBC6BVT0Y92S345HY8N3T6AV7R").unwrap();
+        let addr_2 =
+            StacksAddress::from_string("ST1PQHQKV0RJXZFY1DGX8MNSNYVE3VGZJSRTPGZGM").unwrap();
+        let code_hash = ContractPublishPolicy::hash_code_body("(define-read-only (hello) u1)");
+        let policy = ContractPublishPolicy {
+            restricted: true,
+            allowed_publishers: vec![addr_1.clone(), addr_2.clone()].into_iter().collect(),
+            allowed_code_hashes: vec![code_hash.clone()].into_iter().collect(),
+        };
+        let resp = ContractPublishPolicyResponse::from_policy(&policy);
+        assert!(resp.restricted);
+        assert_eq!(
+            resp.allowed_publishers,
+            vec![addr_2.to_string(), addr_1.to_string()]
+        );
+        assert_eq!(resp.allowed_code_hashes, vec![code_hash.to_hex()]);
+    }
+
+    #[test]
+    fn test_transaction_simulation_response() {
+        let recipient =
+            StacksAddress::from_string("ST2DS4MSWSGJ3W9FBC6BVT0Y92S345HY8N3T6AV7R").unwrap();
+        let mut tx = StacksTransaction {
+            version: TransactionVersion::Testnet,
+            chain_id: 0x80000000,
+            auth: TransactionAuth::from_p2pkh(&StacksPrivateKey::new()).unwrap(),
+            anchor_mode: TransactionAnchorMode::Any,
+            post_condition_mode: TransactionPostConditionMode::Allow,
+            post_conditions: vec![],
+            payload: TransactionPayload::TokenTransfer(
+                recipient.to_account_principal(),
+                123,
+                TokenTransferMemo([0u8; 34]),
+            ),
+        };
+        tx.set_tx_fee(200);
+        let tip = StacksBlockId([0x11; 32]);
+        let cost = ExecutionCost {
+            runtime: 1,
+            read_count: 2,
+            read_length: 3,
+            write_count: 4,
+            write_length: 5,
+        };
+
+        let receipt = StacksTransactionReceipt::from_stx_transfer(
+            tx.clone(),
+            vec![],
+            Value::okay_true(),
+            cost.clone(),
+        );
+        let success = TransactionSuccess {
+            tx: tx.clone(),
+            fee: 200,
+            receipt,
+        };
+        let resp = TransactionSimulationResponse::from_outcome(&tip, &tx, Ok(success));
+        assert!(resp.okay);
+        assert_eq!(resp.txid, format!("0x{}", &tx.txid()));
+        assert_eq!(resp.tip, format!("{}", &tip));
+        assert_eq!(
+            resp.result,
+            Some(format!("0x{}", Value::okay_true().serialize_to_hex()))
+        );
+        assert!(resp.cause.is_none());
+        assert!(!resp.post_condition_aborted);
+        assert_eq!(resp.fee, 200);
+        assert_eq!(resp.execution_cost, cost);
+
+        // a transaction the miner would reject reports why, and costs nothing
+        let resp = TransactionSimulationResponse::from_outcome(
+            &tip,
+            &tx,
+            Err(chain_error::InvalidStacksTransaction(
+                "bad nonce".into(),
+                false,
+            )),
+        );
+        assert!(!resp.okay);
+        assert!(resp.result.is_none());
+        assert!(resp.cause.unwrap().contains("bad nonce"));
+        assert_eq!(resp.execution_cost, ExecutionCost::zero());
+        assert!(resp.events.is_empty());
+    }
+
+    #[test]
+    fn test_block_validation_response() {
+        let block_hash = BlockHeaderHash([0x22; 32]);
+        let state_index_root = stacks_common::types::chainstate::TrieHash([0x33; 32]);
+        let withdrawal_merkle_root = Sha512Trunc256Sum([0x44; 32]);
+        let cost = ExecutionCost {
+            runtime: 1,
+            read_count: 2,
+            read_length: 3,
+            write_count: 4,
+            write_length: 5,
+        };
+
+        let resp = BlockValidationResponse::from_verdict(
+            &block_hash,
+            ProposedBlockVerdict {
+                invalid_reason: None,
+                first_invalid_tx: None,
+                state_index_root: Some(state_index_root.clone()),
+                withdrawal_merkle_root: Some(withdrawal_merkle_root.clone()),
+                cost: cost.clone(),
+            },
+        );
+        assert!(resp.valid);
+        assert_eq!(resp.block_hash, format!("{}", &block_hash));
+        assert!(resp.reason.is_none());
+        assert_eq!(
+            resp.state_index_root,
+            Some(format!("{}", &state_index_root))
+        );
+        assert_eq!(
+            resp.withdrawal_merkle_root,
+            Some(format!("{}", &withdrawal_merkle_root))
+        );
+        assert_eq!(resp.cost, cost);
+        assert!(resp.first_invalid_tx.is_none());
+
+        // a block that stops at an invalid transaction reports it, and has no roots
+        let txid = Txid([0x55; 32]);
+        let resp = BlockValidationResponse::from_verdict(
+            &block_hash,
+            ProposedBlockVerdict {
+                invalid_reason: Some(format!("Transaction {} is invalid", &txid)),
+                first_invalid_tx: Some((2, txid.clone(), "bad nonce".into())),
+                state_index_root: None,
+                withdrawal_merkle_root: None,
+                cost: cost.clone(),
+            },
+        );
+        assert!(!resp.valid);
+        assert!(resp.reason.unwrap().contains(&txid.to_string()));
+        assert!(resp.state_index_root.is_none());
+        assert!(resp.withdrawal_merkle_root.is_none());
+        assert_eq!(
+            resp.first_invalid_tx,
+            Some(InvalidBlockTransaction {
+                index: 2,
+                txid: format!("0x{}", &txid),
+                error: "bad nonce".into(),
+            })
+        );
+
+        // unset roots are left out of the JSON
+        let json = serde_json::to_value(&resp).unwrap();
+        assert!(json.get("state_index_root").is_none());
+        assert_eq!(json["first_invalid_tx"]["index"], 2);
+    }
+
+    #[test]
+    fn test_withdrawal_entry_response() {
+        let sender = PrincipalData::parse("ST1PQHQKV0RJXZFY1DGX8MNSNYVE3VGZJSRTPGZGM").unwrap();
+        let contract = QualifiedContractIdentifier::parse(
+            "ST1PQHQKV0RJXZFY1DGX8MNSNYVE3VGZJSRTPGZGM.simple-nft",
+        )
+        .unwrap();
+        let entry = WithdrawalEntry {
+            index_block_hash: StacksBlockId([0x11; 32]),
+            block_height: 7,
+            withdrawal_id: 3,
+            sender,
+            asset_type: "nft".into(),
+            asset_contract: Some(contract.clone()),
+            asset_id: Some(u128::MAX),
+            amount: None,
+            withdrawal_root: Sha512Trunc256Sum([0x22; 32]),
+        };
+
+        let resp = WithdrawalEntryResponse::from_entry(&entry);
+        assert_eq!(resp.block_height, 7);
+        assert_eq!(
+            resp.index_block_hash,
+            format!("{}", &entry.index_block_hash)
+        );
+        assert_eq!(resp.withdrawal_id, 3);
+        assert_eq!(resp.asset_contract, Some(contract.to_string()));
+        // token IDs and amounts may not fit in a JSON number
+        assert_eq!(resp.asset_id, Some(u128::MAX.to_string()));
+        assert!(resp.amount.is_none());
+        assert_eq!(resp.withdrawal_root, "22".repeat(32));
+
+        let json = serde_json::to_value(&resp).unwrap();
+        assert_eq!(json["type"], "nft");
+        assert!(json.get("amount").is_none());
+    }
+
+    #[test]
+    fn test_account_transaction_response() {
+        let account_tx = AccountTransaction {
+            principal: PrincipalData::parse("ST1PQHQKV0RJXZFY1DGX8MNSNYVE3VGZJSRTPGZGM").unwrap(),
+            txid: Txid([0x33; 32]),
+            index_block_hash: StacksBlockId([0x11; 32]),
+            block_height: 7,
+            tx_index: 2,
+            roles: vec![AccountRole::Origin, AccountRole::Recipient],
+        };
 
-                test_debug!("Store peer server index block {:?}", &index_block_hash);
-                store_staging_block(
-                    peer_server.chainstate(),
-                    &peer_server_consensus_hash,
-                    &peer_server_block,
-                    &ConsensusHash([0x03; 20]),
-                    456,
-                    123,
-                );
+        let resp = AccountTransactionResponse::from_account_transaction(&account_tx);
+        assert_eq!(resp.txid, "33".repeat(32));
+        assert_eq!(resp.block_height, 7);
+        assert_eq!(resp.index_block_hash, "11".repeat(32));
+        assert_eq!(resp.tx_index, 2);
+        assert_eq!(resp.roles, vec!["origin", "recipient"]);
+    }
 
-                *server_block_cell.borrow_mut() = Some(peer_server_block);
+    #[test]
+    fn test_failed_deposit_response() {
+        let failed_deposit = FailedDeposit {
+            index_block_hash: StacksBlockId([0x11; 32]),
+            block_height: 7,
+            txid: Txid([0x33; 32]),
+            burn_header_hash: BurnchainHeaderHash([0x44; 32]),
+            sender: PrincipalData::parse("ST1PQHQKV0RJXZFY1DGX8MNSNYVE3VGZJSRTPGZGM").unwrap(),
+            asset_type: "ft".into(),
+            l1_contract: QualifiedContractIdentifier::parse(
+                "ST1PQHQKV0RJXZFY1DGX8MNSNYVE3VGZJSRTPGZGM.l1-ft",
+            )
+            .unwrap(),
+            subnet_contract: QualifiedContractIdentifier::parse(
+                "ST1PQHQKV0RJXZFY1DGX8MNSNYVE3VGZJSRTPGZGM.subnet-ft",
+            )
+            .unwrap(),
+            asset_id: None,
+            amount: Some(u128::MAX),
+            result: "(err none)".into(),
+            refund_withdrawal_id: Some(2),
+        };
 
-                // now ask for it
-                convo_client.new_getblock(index_block_hash)
-            },
-            |ref http_request,
-             ref http_response,
-             ref mut peer_client,
-             ref mut peer_server,
-             ref convo_client,
-             ref convo_server| {
-                let req_md = http_request.metadata().clone();
-                match http_response {
-                    HttpResponseType::Block(response_md, block_info) => {
-                        assert_eq!(
-                            block_info.block_hash(),
-                            (*server_block_cell.borrow()).as_ref().unwrap().block_hash()
-                        );
-                        true
-                    }
-                    _ => {
-                        error!("Invalid response: {:?}", &http_response);
-                        false
-                    }
-                }
-            },
+        let resp = FailedDepositResponse::from_failed_deposit(&failed_deposit);
+        assert_eq!(resp.block_height, 7);
+        assert_eq!(resp.txid, "33".repeat(32));
+        assert_eq!(resp.burn_header_hash, "44".repeat(32));
+        assert_eq!(
+            resp.subnet_contract,
+            "ST1PQHQKV0RJXZFY1DGX8MNSNYVE3VGZJSRTPGZGM.subnet-ft"
         );
+        assert_eq!(resp.amount, Some(u128::MAX.to_string()));
+        assert_eq!(resp.result, "(err none)");
+        assert_eq!(resp.refund_withdrawal_id, Some(2));
+
+        let json = serde_json::to_value(&resp).unwrap();
+        assert_eq!(json["type"], "ft");
+        assert!(json.get("asset_id").is_none());
     }
 
+    /// In this test, the query parameter `tip_req` is set to UseLatestUnconfirmedTip, and so we expect the
+    /// tip used for the query to be the latest microblock.
+    /// We check that the account state matches the state in the most recent microblock.
     #[test]
     #[ignore]
-    fn test_rpc_confirmed_getblock() {
-        let server_block_cell = RefCell::new(None);
-
+    fn test_rpc_get_account_use_latest_tip() {
         test_rpc(
-            "test_rpc_confirmed_getblock",
-            40030,
-            40031,
-            50030,
-            50031,
+            "test_rpc_get_account_use_latest_tip",
+            40112,
+            40113,
+            50112,
+            50113,
             true,
             |ref mut peer_client,
              ref mut convo_client,
              ref mut peer_server,
              ref mut convo_server| {
-                // have "server" peer store a block to staging
-                let peer_server_block = make_codec_test_block(25);
-                let peer_server_consensus_hash = ConsensusHash([0x02; 20]);
-                let index_block_hash = StacksBlockHeader::make_index_block_hash(
-                    &peer_server_consensus_hash,
-                    &peer_server_block.block_hash(),
-                );
-
-                test_debug!("Store peer server index block {:?}", &index_block_hash);
-                store_staging_block(
-                    peer_server.chainstate(),
-                    &peer_server_consensus_hash,
-                    &peer_server_block,
-                    &ConsensusHash([0x03; 20]),
-                    456,
-                    123,
-                );
-                set_block_processed(
-                    peer_server.chainstate(),
-                    &peer_server_consensus_hash,
-                    &peer_server_block.block_hash(),
-                    true,
-                );
-
-                *server_block_cell.borrow_mut() = Some(peer_server_block);
-
-                // now ask for it
-                convo_client.new_getblock(index_block_hash)
+                convo_client.new_getaccount(
+                    StacksAddress::from_string("ST2DS4MSWSGJ3W9FBC6BVT0Y92S345HY8N3T6AV7R")
+                        .unwrap()
+                        .to_account_principal(),
+                    TipRequest::UseLatestAnchoredTip,
+                    false,
+                )
             },
             |ref http_request,
              ref http_response,
@@ -4536,15 +8866,14 @@ mod test {
              ref convo_server| {
                 let req_md = http_request.metadata().clone();
                 match http_response {
-                    HttpResponseType::Block(response_md, block_info) => {
-                        assert_eq!(
-                            block_info.block_hash(),
-                            (*server_block_cell.borrow()).as_ref().unwrap().block_hash()
-                        );
+                    HttpResponseType::GetAccount(response_md, data) => {
+                        assert_eq!(data.nonce, 4);
+                        let balance = u128::from_str_radix(&data.balance[2..], 16).unwrap();
+                        assert_eq!(balance, 999999877);
                         true
                     }
                     _ => {
-                        error!("Invalid response: {:?}", &http_response);
+                        error!("Invalid response; {:?}", &http_response);
                         false
                     }
                 }
@@ -4552,100 +8881,31 @@ mod test {
         );
     }
 
+    /// In this test, the query parameter `tip_req` is set to UseLatestUnconfirmedTip, but we did not generate
+    /// microblocks in the rpc test. Thus, we expect the tip used for the query to be the previous
+    /// anchor block (which is the latest tip).
+    /// We check that the account state matches the state in the previous anchor block.
     #[test]
     #[ignore]
-    fn test_rpc_get_indexed_microblocks() {
-        let server_microblocks_cell = RefCell::new(vec![]);
-
+    fn test_rpc_get_account_use_latest_tip_no_microblocks() {
         test_rpc(
-            "test_rpc_indexed_microblocks",
-            40040,
-            40041,
-            50040,
-            50041,
-            true,
+            "test_rpc_get_account",
+            40114,
+            40115,
+            50114,
+            50115,
+            false,
             |ref mut peer_client,
              ref mut convo_client,
              ref mut peer_server,
              ref mut convo_server| {
-                let privk = StacksPrivateKey::from_hex(
-                    "eb05c83546fdd2c79f10f5ad5434a90dd28f7e3acb7c092157aa1bc3656b012c01",
+                convo_client.new_getaccount(
+                    StacksAddress::from_string("ST2DS4MSWSGJ3W9FBC6BVT0Y92S345HY8N3T6AV7R")
+                        .unwrap()
+                        .to_account_principal(),
+                    TipRequest::UseLatestAnchoredTip,
+                    false,
                 )
-                .unwrap();
-
-                let parent_block = make_codec_test_block(25);
-                let parent_consensus_hash = ConsensusHash([0x02; 20]);
-                let parent_index_block_hash = StacksBlockHeader::make_index_block_hash(
-                    &parent_consensus_hash,
-                    &parent_block.block_hash(),
-                );
-
-                let mut mblocks = make_sample_microblock_stream(&privk, &parent_block.block_hash());
-                mblocks.truncate(15);
-
-                let mut child_block = make_codec_test_block(25);
-                let child_consensus_hash = ConsensusHash([0x03; 20]);
-
-                child_block.header.parent_block = parent_block.block_hash();
-                child_block.header.parent_microblock =
-                    mblocks.last().as_ref().unwrap().block_hash();
-                child_block.header.parent_microblock_sequence =
-                    mblocks.last().as_ref().unwrap().header.sequence;
-
-                store_staging_block(
-                    peer_server.chainstate(),
-                    &parent_consensus_hash,
-                    &parent_block,
-                    &ConsensusHash([0x01; 20]),
-                    456,
-                    123,
-                );
-                set_block_processed(
-                    peer_server.chainstate(),
-                    &parent_consensus_hash,
-                    &parent_block.block_hash(),
-                    true,
-                );
-
-                store_staging_block(
-                    peer_server.chainstate(),
-                    &child_consensus_hash,
-                    &child_block,
-                    &parent_consensus_hash,
-                    456,
-                    123,
-                );
-                set_block_processed(
-                    peer_server.chainstate(),
-                    &child_consensus_hash,
-                    &child_block.block_hash(),
-                    true,
-                );
-
-                let index_microblock_hash = StacksBlockHeader::make_index_block_hash(
-                    &parent_consensus_hash,
-                    &mblocks.last().as_ref().unwrap().block_hash(),
-                );
-
-                for mblock in mblocks.iter() {
-                    store_staging_microblock(
-                        peer_server.chainstate(),
-                        &parent_consensus_hash,
-                        &parent_block.block_hash(),
-                        &mblock,
-                    );
-                }
-
-                set_microblocks_processed(
-                    peer_server.chainstate(),
-                    &child_consensus_hash,
-                    &child_block.block_hash(),
-                    &mblocks.last().as_ref().unwrap().block_hash(),
-                );
-
-                *server_microblocks_cell.borrow_mut() = mblocks;
-
-                convo_client.new_getmicroblocks_indexed(index_microblock_hash)
             },
             |ref http_request,
              ref http_response,
@@ -4654,15 +8914,15 @@ mod test {
              ref convo_client,
              ref convo_server| {
                 let req_md = http_request.metadata().clone();
-                match (*http_response).clone() {
-                    HttpResponseType::Microblocks(_, mut microblocks) => {
-                        microblocks.reverse();
-                        assert_eq!(microblocks.len(), (*server_microblocks_cell.borrow()).len());
-                        assert_eq!(microblocks, *server_microblocks_cell.borrow());
+                match http_response {
+                    HttpResponseType::GetAccount(response_md, data) => {
+                        assert_eq!(data.nonce, 2);
+                        let balance = u128::from_str_radix(&data.balance[2..], 16).unwrap();
+                        assert_eq!(balance, 1000000000);
                         true
                     }
                     _ => {
-                        error!("Invalid response: {:?}", http_response);
+                        error!("Invalid response; {:?}", &http_response);
                         false
                     }
                 }
@@ -4672,94 +8932,32 @@ mod test {
 
     #[test]
     #[ignore]
-    fn test_rpc_get_confirmed_microblocks() {
-        let server_microblocks_cell = RefCell::new(vec![]);
-
+    fn test_rpc_get_account_unconfirmed() {
         test_rpc(
-            "test_rpc_confirmed_microblocks",
-            40042,
-            40043,
-            50042,
-            50043,
+            "test_rpc_get_account_unconfirmed",
+            40120,
+            40121,
+            50120,
+            50121,
             true,
             |ref mut peer_client,
              ref mut convo_client,
              ref mut peer_server,
              ref mut convo_server| {
-                let privk = StacksPrivateKey::from_hex(
-                    "eb05c83546fdd2c79f10f5ad5434a90dd28f7e3acb7c092157aa1bc3656b012c01",
+                let unconfirmed_tip = peer_client
+                    .chainstate()
+                    .unconfirmed_state
+                    .as_ref()
+                    .unwrap()
+                    .unconfirmed_chain_tip
+                    .clone();
+                convo_client.new_getaccount(
+                    StacksAddress::from_string("ST2DS4MSWSGJ3W9FBC6BVT0Y92S345HY8N3T6AV7R")
+                        .unwrap()
+                        .to_account_principal(),
+                    TipRequest::SpecificTip(unconfirmed_tip),
+                    false,
                 )
-                .unwrap();
-
-                let parent_block = make_codec_test_block(25);
-                let parent_consensus_hash = ConsensusHash([0x02; 20]);
-
-                let mut mblocks = make_sample_microblock_stream(&privk, &parent_block.block_hash());
-                mblocks.truncate(15);
-
-                let mut child_block = make_codec_test_block(25);
-                let child_consensus_hash = ConsensusHash([0x03; 20]);
-
-                child_block.header.parent_block = parent_block.block_hash();
-                child_block.header.parent_microblock =
-                    mblocks.last().as_ref().unwrap().block_hash();
-                child_block.header.parent_microblock_sequence =
-                    mblocks.last().as_ref().unwrap().header.sequence;
-
-                let child_index_block_hash = StacksBlockHeader::make_index_block_hash(
-                    &child_consensus_hash,
-                    &child_block.block_hash(),
-                );
-
-                store_staging_block(
-                    peer_server.chainstate(),
-                    &parent_consensus_hash,
-                    &parent_block,
-                    &ConsensusHash([0x01; 20]),
-                    456,
-                    123,
-                );
-                set_block_processed(
-                    peer_server.chainstate(),
-                    &parent_consensus_hash,
-                    &parent_block.block_hash(),
-                    true,
-                );
-
-                store_staging_block(
-                    peer_server.chainstate(),
-                    &child_consensus_hash,
-                    &child_block,
-                    &parent_consensus_hash,
-                    456,
-                    123,
-                );
-                set_block_processed(
-                    peer_server.chainstate(),
-                    &child_consensus_hash,
-                    &child_block.block_hash(),
-                    true,
-                );
-
-                for mblock in mblocks.iter() {
-                    store_staging_microblock(
-                        peer_server.chainstate(),
-                        &parent_consensus_hash,
-                        &parent_block.block_hash(),
-                        &mblock,
-                    );
-                }
-
-                set_microblocks_processed(
-                    peer_server.chainstate(),
-                    &child_consensus_hash,
-                    &child_block.block_hash(),
-                    &mblocks.last().as_ref().unwrap().block_hash(),
-                );
-
-                *server_microblocks_cell.borrow_mut() = mblocks;
-
-                convo_client.new_getmicroblocks_confirmed(child_index_block_hash)
             },
             |ref http_request,
              ref http_response,
@@ -4768,15 +8966,15 @@ mod test {
              ref convo_client,
              ref convo_server| {
                 let req_md = http_request.metadata().clone();
-                match (*http_response).clone() {
-                    HttpResponseType::Microblocks(_, mut microblocks) => {
-                        microblocks.reverse();
-                        assert_eq!(microblocks.len(), (*server_microblocks_cell.borrow()).len());
-                        assert_eq!(microblocks, *server_microblocks_cell.borrow());
+                match http_response {
+                    HttpResponseType::GetAccount(response_md, data) => {
+                        assert_eq!(data.nonce, 4);
+                        let balance = u128::from_str_radix(&data.balance[2..], 16).unwrap();
+                        assert_eq!(balance, 1000000000 - 123);
                         true
                     }
                     _ => {
-                        error!("Invalid response: {:?}", &http_response);
+                        error!("Invalid response; {:?}", &http_response);
                         false
                     }
                 }
@@ -4786,46 +8984,26 @@ mod test {
 
     #[test]
     #[ignore]
-    fn test_rpc_unconfirmed_microblocks() {
-        let server_microblocks_cell = RefCell::new(vec![]);
-
+    fn test_rpc_get_data_var() {
         test_rpc(
-            "test_rpc_unconfirmed_microblocks",
-            40050,
-            40051,
-            50050,
-            50051,
+            "test_rpc_get_data_var",
+            40122,
+            40123,
+            50122,
+            50123,
             true,
             |ref mut peer_client,
              ref mut convo_client,
              ref mut peer_server,
              ref mut convo_server| {
-                let privk = StacksPrivateKey::from_hex(
-                    "eb05c83546fdd2c79f10f5ad5434a90dd28f7e3acb7c092157aa1bc3656b012c01",
+                convo_client.new_getdatavar(
+                    StacksAddress::from_string("ST2DS4MSWSGJ3W9FBC6BVT0Y92S345HY8N3T6AV7R")
+                        .unwrap(),
+                    "hello-world".try_into().unwrap(),
+                    "bar".try_into().unwrap(),
+                    TipRequest::UseLatestAnchoredTip,
+                    false,
                 )
-                .unwrap();
-
-                let consensus_hash = ConsensusHash([0x02; 20]);
-                let anchored_block_hash = BlockHeaderHash([0x03; 32]);
-                let index_block_hash =
-                    StacksBlockHeader::make_index_block_hash(&consensus_hash, &anchored_block_hash);
-
-                let mut mblocks = make_sample_microblock_stream(&privk, &anchored_block_hash);
-                mblocks.truncate(15);
-
-                for mblock in mblocks.iter() {
-                    store_staging_microblock(
-                        peer_server.chainstate(),
-                        &consensus_hash,
-                        &anchored_block_hash,
-                        &mblock,
-                    );
-                }
-
-                *server_microblocks_cell.borrow_mut() = mblocks;
-
-                // start at seq 5
-                convo_client.new_getmicroblocks_unconfirmed(index_block_hash, 5)
             },
             |ref http_request,
              ref http_response,
@@ -4835,16 +9013,15 @@ mod test {
              ref convo_server| {
                 let req_md = http_request.metadata().clone();
                 match http_response {
-                    HttpResponseType::Microblocks(response_md, microblocks) => {
-                        assert_eq!(microblocks.len(), 10);
+                    HttpResponseType::GetDataVar(response_md, data) => {
                         assert_eq!(
-                            *microblocks,
-                            (*server_microblocks_cell.borrow())[5..].to_vec()
+                            Value::try_deserialize_hex_untyped(&data.data).unwrap(),
+                            Value::Int(0)
                         );
                         true
                     }
                     _ => {
-                        error!("Invalid response: {:?}", &http_response);
+                        error!("Invalid response; {:?}", &http_response);
                         false
                     }
                 }
@@ -4854,52 +9031,33 @@ mod test {
 
     #[test]
     #[ignore]
-    fn test_rpc_unconfirmed_transaction() {
-        let last_txid = RefCell::new(Txid([0u8; 32]));
-        let last_mblock = RefCell::new(BlockHeaderHash([0u8; 32]));
-
+    fn test_rpc_get_data_var_unconfirmed() {
         test_rpc(
-            "test_rpc_unconfirmed_transaction",
-            40052,
-            40053,
-            50052,
-            50053,
+            "test_rpc_get_data_var_unconfirmed",
+            40124,
+            40125,
+            50124,
+            50125,
             true,
             |ref mut peer_client,
              ref mut convo_client,
              ref mut peer_server,
              ref mut convo_server| {
-                let privk = StacksPrivateKey::from_hex(
-                    "eb05c83546fdd2c79f10f5ad5434a90dd28f7e3acb7c092157aa1bc3656b012c01",
+                let unconfirmed_tip = peer_client
+                    .chainstate()
+                    .unconfirmed_state
+                    .as_ref()
+                    .unwrap()
+                    .unconfirmed_chain_tip
+                    .clone();
+                convo_client.new_getdatavar(
+                    StacksAddress::from_string("ST2DS4MSWSGJ3W9FBC6BVT0Y92S345HY8N3T6AV7R")
+                        .unwrap(),
+                    "hello-world".try_into().unwrap(),
+                    "bar".try_into().unwrap(),
+                    TipRequest::SpecificTip(unconfirmed_tip),
+                    false,
                 )
-                .unwrap();
-
-                let sortdb = peer_server.sortdb.take().unwrap();
-                Relayer::setup_unconfirmed_state(peer_server.chainstate(), &sortdb).unwrap();
-                peer_server.sortdb = Some(sortdb);
-
-                assert!(peer_server.chainstate().unconfirmed_state.is_some());
-                let (txid, mblock_hash) = match peer_server.chainstate().unconfirmed_state {
-                    Some(ref unconfirmed) => {
-                        assert!(unconfirmed.mined_txs.len() > 0);
-                        let mut txid = Txid([0u8; 32]);
-                        let mut mblock_hash = BlockHeaderHash([0u8; 32]);
-                        for (next_txid, (_, mbh, ..)) in unconfirmed.mined_txs.iter() {
-                            txid = next_txid.clone();
-                            mblock_hash = mbh.clone();
-                            break;
-                        }
-                        (txid, mblock_hash)
-                    }
-                    None => {
-                        panic!("No unconfirmed state");
-                    }
-                };
-
-                *last_txid.borrow_mut() = txid.clone();
-                *last_mblock.borrow_mut() = mblock_hash.clone();
-
-                convo_client.new_gettransaction_unconfirmed(txid)
             },
             |ref http_request,
              ref http_response,
@@ -4909,23 +9067,15 @@ mod test {
              ref convo_server| {
                 let req_md = http_request.metadata().clone();
                 match http_response {
-                    HttpResponseType::UnconfirmedTransaction(response_md, unconfirmed_resp) => {
+                    HttpResponseType::GetDataVar(response_md, data) => {
                         assert_eq!(
-                            unconfirmed_resp.status,
-                            UnconfirmedTransactionStatus::Microblock {
-                                block_hash: (*last_mblock.borrow()).clone(),
-                                seq: 0
-                            }
+                            Value::try_deserialize_hex_untyped(&data.data).unwrap(),
+                            Value::Int(1)
                         );
-                        let tx = StacksTransaction::consensus_deserialize(
-                            &mut &hex_bytes(&unconfirmed_resp.tx).unwrap()[..],
-                        )
-                        .unwrap();
-                        assert_eq!(tx.txid(), *last_txid.borrow());
                         true
                     }
                     _ => {
-                        error!("Invalid response: {:?}", &http_response);
+                        error!("Invalid response; {:?}", &http_response);
                         false
                     }
                 }
@@ -4935,27 +9085,26 @@ mod test {
 
     #[test]
     #[ignore]
-    fn test_rpc_missing_getblock() {
+    fn test_rpc_get_data_var_nonexistant() {
         test_rpc(
-            "test_rpc_missing_getblock",
-            40060,
-            40061,
-            50060,
-            50061,
+            "test_rpc_get_data_var_nonexistant",
+            40125,
+            40126,
+            50125,
+            50126,
             true,
             |ref mut peer_client,
              ref mut convo_client,
              ref mut peer_server,
              ref mut convo_server| {
-                let peer_server_block_hash = BlockHeaderHash([0x04; 32]);
-                let peer_server_consensus_hash = ConsensusHash([0x02; 20]);
-                let index_block_hash = StacksBlockHeader::make_index_block_hash(
-                    &peer_server_consensus_hash,
-                    &peer_server_block_hash,
-                );
-
-                // now ask for it
-                convo_client.new_getblock(index_block_hash)
+                convo_client.new_getdatavar(
+                    StacksAddress::from_string("ST2DS4MSWSGJ3W9FBC6BVT0Y92S345HY8N3T6AV7R")
+                        .unwrap(),
+                    "hello-world".try_into().unwrap(),
+                    "bar-nonexistant".try_into().unwrap(),
+                    TipRequest::UseLatestAnchoredTip,
+                    false,
+                )
             },
             |ref http_request,
              ref http_response,
@@ -4965,9 +9114,12 @@ mod test {
              ref convo_server| {
                 let req_md = http_request.metadata().clone();
                 match http_response {
-                    HttpResponseType::NotFound(response_md, msg) => true,
+                    HttpResponseType::NotFound(_, msg) => {
+                        assert_eq!(msg, "Data var not found");
+                        true
+                    }
                     _ => {
-                        error!("Invalid response: {:?}", &http_response);
+                        error!("Invalid response; {:?}", &http_response);
                         false
                     }
                 }
@@ -4977,27 +9129,32 @@ mod test {
 
     #[test]
     #[ignore]
-    fn test_rpc_missing_index_getmicroblocks() {
+    fn test_rpc_get_data_var_historical_tip() {
+        // The contract is deployed in the first block after genesis, so reading its data var
+        // at the genesis block must not find it, even though it exists at the canonical tip.
         test_rpc(
-            "test_rpc_missing_index_getmicroblocks",
-            40070,
-            40071,
-            50070,
-            50071,
+            "test_rpc_get_data_var_historical_tip",
+            40127,
+            40128,
+            50127,
+            50128,
             true,
             |ref mut peer_client,
              ref mut convo_client,
              ref mut peer_server,
              ref mut convo_server| {
-                let peer_server_block_hash = BlockHeaderHash([0x04; 32]);
-                let peer_server_consensus_hash = ConsensusHash([0x02; 20]);
-                let index_block_hash = StacksBlockHeader::make_index_block_hash(
-                    &peer_server_consensus_hash,
-                    &peer_server_block_hash,
-                );
-
-                // now ask for it
-                convo_client.new_getmicroblocks_indexed(index_block_hash)
+                let genesis_tip =
+                    StacksChainState::get_genesis_header_info(peer_server.chainstate().db())
+                        .unwrap()
+                        .index_block_hash();
+                convo_client.new_getdatavar(
+                    StacksAddress::from_string("ST2DS4MSWSGJ3W9FBC6BVT0Y92S345HY8N3T6AV7R")
+                        .unwrap(),
+                    "hello-world".try_into().unwrap(),
+                    "bar".try_into().unwrap(),
+                    TipRequest::SpecificTip(genesis_tip),
+                    false,
+                )
             },
             |ref http_request,
              ref http_response,
@@ -5005,11 +9162,13 @@ mod test {
              ref mut peer_server,
              ref convo_client,
              ref convo_server| {
-                let req_md = http_request.metadata().clone();
                 match http_response {
-                    HttpResponseType::NotFound(response_md, msg) => true,
+                    HttpResponseType::NotFound(_, msg) => {
+                        assert_eq!(msg, "Data var not found");
+                        true
+                    }
                     _ => {
-                        error!("Invalid response: {:?}", &http_response);
+                        error!("Invalid response; {:?}", &http_response);
                         false
                     }
                 }
@@ -5019,27 +9178,37 @@ mod test {
 
     #[test]
     #[ignore]
-    fn test_rpc_missing_confirmed_getmicroblocks() {
+    fn test_rpc_get_map_entry() {
+        // Test v2/map_entry (aka GetMapEntry) endpoint.
+        // In this test, we don't set any tip parameters, and we expect that querying for map data
+        // against the canonical Stacks tip will succeed.
         test_rpc(
-            "test_rpc_missing_confirmed_getmicroblocks",
-            40072,
-            40073,
-            50072,
-            50073,
+            "test_rpc_get_map_entry",
+            40130,
+            40131,
+            50130,
+            50131,
             true,
             |ref mut peer_client,
              ref mut convo_client,
              ref mut peer_server,
              ref mut convo_server| {
-                let peer_server_block_hash = BlockHeaderHash([0x04; 32]);
-                let peer_server_consensus_hash = ConsensusHash([0x02; 20]);
-                let index_block_hash = StacksBlockHeader::make_index_block_hash(
-                    &peer_server_consensus_hash,
-                    &peer_server_block_hash,
-                );
-
-                // now ask for it
-                convo_client.new_getmicroblocks_confirmed(index_block_hash)
+                let principal =
+                    StacksAddress::from_string("ST2DS4MSWSGJ3W9FBC6BVT0Y92S345HY8N3T6AV7R")
+                        .unwrap()
+                        .to_account_principal();
+                convo_client.new_getmapentry(
+                    StacksAddress::from_string("ST2DS4MSWSGJ3W9FBC6BVT0Y92S345HY8N3T6AV7R")
+                        .unwrap(),
+                    "hello-world".try_into().unwrap(),
+                    "unit-map".try_into().unwrap(),
+                    Value::Tuple(
+                        TupleData::from_data(vec![("account".into(), Value::Principal(principal))])
+                            .unwrap(),
+                    ),
+                    TipRequest::UseLatestAnchoredTip,
+                    false,
+                )
             },
             |ref http_request,
              ref http_response,
@@ -5049,9 +9218,19 @@ mod test {
              ref convo_server| {
                 let req_md = http_request.metadata().clone();
                 match http_response {
-                    HttpResponseType::NotFound(response_md, msg) => true,
+                    HttpResponseType::GetMapEntry(response_md, data) => {
+                        assert_eq!(
+                            Value::try_deserialize_hex_untyped(&data.data).unwrap(),
+                            Value::some(Value::Tuple(
+                                TupleData::from_data(vec![("units".into(), Value::Int(123))])
+                                    .unwrap()
+                            ))
+                            .unwrap()
+                        );
+                        true
+                    }
                     _ => {
-                        error!("Invalid response: {:?}", &http_response);
+                        error!("Invalid response; {:?}", &http_response);
                         false
                     }
                 }
@@ -5061,46 +9240,44 @@ mod test {
 
     #[test]
     #[ignore]
-    fn test_rpc_missing_unconfirmed_microblocks() {
-        let server_microblocks_cell = RefCell::new(vec![]);
-
+    fn test_rpc_get_map_entry_unconfirmed() {
+        // Test v2/map_entry (aka GetMapEntry) endpoint.
+        // In this test, we set `tip_req` to UseLatestUnconfirmedTip, and we expect that querying for map data
+        // against the unconfirmed state will succeed.
         test_rpc(
-            "test_rpc_missing_unconfirmed_microblocks",
-            40080,
-            40081,
-            50080,
-            50081,
+            "test_rpc_get_map_entry_unconfirmed",
+            40140,
+            40141,
+            50140,
+            50141,
             true,
             |ref mut peer_client,
              ref mut convo_client,
              ref mut peer_server,
              ref mut convo_server| {
-                let privk = StacksPrivateKey::from_hex(
-                    "eb05c83546fdd2c79f10f5ad5434a90dd28f7e3acb7c092157aa1bc3656b012c01",
+                let unconfirmed_tip = peer_client
+                    .chainstate()
+                    .unconfirmed_state
+                    .as_ref()
+                    .unwrap()
+                    .unconfirmed_chain_tip
+                    .clone();
+                let principal =
+                    StacksAddress::from_string("ST2DS4MSWSGJ3W9FBC6BVT0Y92S345HY8N3T6AV7R")
+                        .unwrap()
+                        .to_account_principal();
+                convo_client.new_getmapentry(
+                    StacksAddress::from_string("ST2DS4MSWSGJ3W9FBC6BVT0Y92S345HY8N3T6AV7R")
+                        .unwrap(),
+                    "hello-world".try_into().unwrap(),
+                    "unit-map".try_into().unwrap(),
+                    Value::Tuple(
+                        TupleData::from_data(vec![("account".into(), Value::Principal(principal))])
+                            .unwrap(),
+                    ),
+                    TipRequest::SpecificTip(unconfirmed_tip),
+                    false,
                 )
-                .unwrap();
-
-                let consensus_hash = ConsensusHash([0x02; 20]);
-                let anchored_block_hash = BlockHeaderHash([0x03; 32]);
-                let index_block_hash =
-                    StacksBlockHeader::make_index_block_hash(&consensus_hash, &anchored_block_hash);
-
-                let mut mblocks = make_sample_microblock_stream(&privk, &anchored_block_hash);
-                mblocks.truncate(15);
-
-                for mblock in mblocks.iter() {
-                    store_staging_microblock(
-                        peer_server.chainstate(),
-                        &consensus_hash,
-                        &anchored_block_hash,
-                        &mblock,
-                    );
-                }
-
-                *server_microblocks_cell.borrow_mut() = mblocks;
-
-                // start at seq 16 (which doesn't exist)
-                convo_client.new_getmicroblocks_unconfirmed(index_block_hash, 16)
             },
             |ref http_request,
              ref http_response,
@@ -5110,9 +9287,19 @@ mod test {
              ref convo_server| {
                 let req_md = http_request.metadata().clone();
                 match http_response {
-                    HttpResponseType::NotFound(response_md, msg) => true,
+                    HttpResponseType::GetMapEntry(response_md, data) => {
+                        assert_eq!(
+                            Value::try_deserialize_hex_untyped(&data.data).unwrap(),
+                            Value::some(Value::Tuple(
+                                TupleData::from_data(vec![("units".into(), Value::Int(1))])
+                                    .unwrap()
+                            ))
+                            .unwrap()
+                        );
+                        true
+                    }
                     _ => {
-                        error!("Invalid response: {:?}", &http_response);
+                        error!("Invalid response; {:?}", &http_response);
                         false
                     }
                 }
@@ -5122,27 +9309,31 @@ mod test {
 
     #[test]
     #[ignore]
-    fn test_rpc_get_contract_src() {
-        // Test v2/contracts/source (aka GetContractSrc) endpoint.
-        // In this test, we don't set any tip parameters, and allow the endpoint to execute against
-        // the canonical Stacks tip.
-        // The contract source we are querying for exists in the anchored state, so we expect the
-        // query to succeed.
+    fn test_rpc_get_map_entry_use_latest_tip() {
         test_rpc(
-            "test_rpc_get_contract_src",
-            40090,
-            40091,
-            50090,
-            50091,
+            "test_rpc_get_map_entry_use_latest_tip",
+            40142,
+            40143,
+            50142,
+            50143,
             true,
             |ref mut peer_client,
              ref mut convo_client,
              ref mut peer_server,
              ref mut convo_server| {
-                convo_client.new_getcontractsrc(
+                let principal =
+                    StacksAddress::from_string("ST2DS4MSWSGJ3W9FBC6BVT0Y92S345HY8N3T6AV7R")
+                        .unwrap()
+                        .to_account_principal();
+                convo_client.new_getmapentry(
                     StacksAddress::from_string("ST2DS4MSWSGJ3W9FBC6BVT0Y92S345HY8N3T6AV7R")
                         .unwrap(),
                     "hello-world".try_into().unwrap(),
+                    "unit-map".try_into().unwrap(),
+                    Value::Tuple(
+                        TupleData::from_data(vec![("account".into(), Value::Principal(principal))])
+                            .unwrap(),
+                    ),
                     TipRequest::UseLatestAnchoredTip,
                     false,
                 )
@@ -5155,8 +9346,15 @@ mod test {
              ref convo_server| {
                 let req_md = http_request.metadata().clone();
                 match http_response {
-                    HttpResponseType::GetContractSrc(response_md, data) => {
-                        assert_eq!(data.source, TEST_CONTRACT);
+                    HttpResponseType::GetMapEntry(response_md, data) => {
+                        assert_eq!(
+                            Value::try_deserialize_hex_untyped(&data.data).unwrap(),
+                            Value::some(Value::Tuple(
+                                TupleData::from_data(vec![("units".into(), Value::Int(1))])
+                                    .unwrap()
+                            ))
+                            .unwrap()
+                        );
                         true
                     }
                     _ => {
@@ -5170,29 +9368,26 @@ mod test {
 
     #[test]
     #[ignore]
-    fn test_rpc_get_contract_src_unconfirmed_with_canonical_tip() {
-        // Test v2/contracts/source (aka GetContractSrc) endpoint.
-        // In this test, we don't set any tip parameters, and allow the endpoint to execute against
-        // the canonical Stacks tip.
-        // The contract source we are querying for only exists in the unconfirmed state, so we
-        // expect the query to fail.
+    fn test_rpc_get_contract_abi() {
+        // Test /v2/contracts/interface (aka GetContractABI) endpoint.
+        // In this test, we don't set any tip parameters, and we expect that querying
+        // against the canonical Stacks tip will succeed.
         test_rpc(
-            "test_rpc_get_contract_src_unconfirmed_with_canonical_tip",
-            40100,
-            40101,
-            50100,
-            50101,
+            "test_rpc_get_contract_abi",
+            40150,
+            40151,
+            50150,
+            50151,
             true,
             |ref mut peer_client,
              ref mut convo_client,
              ref mut peer_server,
              ref mut convo_server| {
-                convo_client.new_getcontractsrc(
+                convo_client.new_getcontractabi(
                     StacksAddress::from_string("ST2DS4MSWSGJ3W9FBC6BVT0Y92S345HY8N3T6AV7R")
                         .unwrap(),
                     "hello-world-unconfirmed".try_into().unwrap(),
                     TipRequest::UseLatestAnchoredTip,
-                    false,
                 )
             },
             |ref http_request,
@@ -5203,8 +9398,8 @@ mod test {
              ref convo_server| {
                 let req_md = http_request.metadata().clone();
                 match http_response {
-                    HttpResponseType::NotFound(_, error_str) => {
-                        assert_eq!(error_str, "No contract source data found");
+                    HttpResponseType::NotFound(..) => {
+                        // not confirmed yet
                         true
                     }
                     _ => {
@@ -5218,17 +9413,16 @@ mod test {
 
     #[test]
     #[ignore]
-    fn test_rpc_get_contract_src_with_unconfirmed_tip() {
-        // Test v2/contracts/source (aka GetContractSrc) endpoint.
-        // In this test, we set `tip_req` to be the unconfirmed chain tip.
-        // The contract source we are querying for exists in the unconfirmed state, so we expect
-        // the query to succeed.
-        test_rpc(
-            "test_rpc_get_contract_src_with_unconfirmed_tip",
-            40102,
-            40103,
-            50102,
-            50103,
+    fn test_rpc_get_contract_abi_unconfirmed() {
+        // Test /v2/contracts/interface (aka GetContractABI) endpoint.
+        // In this test, we set `tip_req` to UseLatestUnconfirmedTip, and we expect that querying
+        // against the unconfirmed state will succeed.
+        test_rpc(
+            "test_rpc_get_contract_abi_unconfirmed",
+            40152,
+            40153,
+            50152,
+            50153,
             true,
             |ref mut peer_client,
              ref mut convo_client,
@@ -5241,12 +9435,11 @@ mod test {
                     .unwrap()
                     .unconfirmed_chain_tip
                     .clone();
-                convo_client.new_getcontractsrc(
+                convo_client.new_getcontractabi(
                     StacksAddress::from_string("ST2DS4MSWSGJ3W9FBC6BVT0Y92S345HY8N3T6AV7R")
                         .unwrap(),
                     "hello-world-unconfirmed".try_into().unwrap(),
                     TipRequest::SpecificTip(unconfirmed_tip),
-                    false,
                 )
             },
             |ref http_request,
@@ -5257,10 +9450,7 @@ mod test {
              ref convo_server| {
                 let req_md = http_request.metadata().clone();
                 match http_response {
-                    HttpResponseType::GetContractSrc(response_md, data) => {
-                        assert_eq!(data.source, TEST_CONTRACT_UNCONFIRMED);
-                        true
-                    }
+                    HttpResponseType::GetContractABI(response_md, data) => true,
                     _ => {
                         error!("Invalid response; {:?}", &http_response);
                         false
@@ -5272,28 +9462,23 @@ mod test {
 
     #[test]
     #[ignore]
-    fn test_rpc_get_contract_src_use_latest_tip() {
-        // Test v2/contracts/source (aka GetContractSrc) endpoint.
-        // In this test, we set `tip_req` to UseLatestUnconfirmedTip.
-        // The contract source we are querying for exists in the unconfirmed state, so we expect
-        // the query to succeed.
+    fn test_rpc_get_contract_abi_use_latest_tip() {
         test_rpc(
-            "test_rpc_get_contract_src_use_latest_tip",
-            40104,
-            40105,
-            50104,
-            50105,
+            "test_rpc_get_contract_abi_use_latest_tip",
+            40154,
+            40155,
+            50154,
+            50155,
             true,
             |ref mut peer_client,
              ref mut convo_client,
              ref mut peer_server,
              ref mut convo_server| {
-                convo_client.new_getcontractsrc(
+                convo_client.new_getcontractabi(
                     StacksAddress::from_string("ST2DS4MSWSGJ3W9FBC6BVT0Y92S345HY8N3T6AV7R")
                         .unwrap(),
                     "hello-world-unconfirmed".try_into().unwrap(),
                     TipRequest::UseLatestAnchoredTip,
-                    false,
                 )
             },
             |ref http_request,
@@ -5304,10 +9489,7 @@ mod test {
              ref convo_server| {
                 let req_md = http_request.metadata().clone();
                 match http_response {
-                    HttpResponseType::GetContractSrc(response_md, data) => {
-                        assert_eq!(data.source, TEST_CONTRACT_UNCONFIRMED);
-                        true
-                    }
+                    HttpResponseType::GetContractABI(response_md, data) => true,
                     _ => {
                         error!("Invalid response; {:?}", &http_response);
                         false
@@ -5319,24 +9501,31 @@ mod test {
 
     #[test]
     #[ignore]
-    fn test_rpc_get_account() {
+    fn test_rpc_call_read_only() {
+        // Test /v2/contracts/call-read (aka CallReadOnlyFunction) endpoint.
+        // In this test, we don't set any tip parameters, and we expect that querying
+        // against the canonical Stacks tip will succeed.
         test_rpc(
-            "test_rpc_get_account",
-            40110,
-            40111,
-            50110,
-            50111,
+            "test_rpc_call_read_only",
+            40170,
+            40171,
+            50170,
+            50171,
             true,
             |ref mut peer_client,
              ref mut convo_client,
              ref mut peer_server,
              ref mut convo_server| {
-                convo_client.new_getaccount(
+                convo_client.new_callreadonlyfunction(
+                    StacksAddress::from_string("ST2DS4MSWSGJ3W9FBC6BVT0Y92S345HY8N3T6AV7R")
+                        .unwrap(),
+                    "hello-world-unconfirmed".try_into().unwrap(),
                     StacksAddress::from_string("ST2DS4MSWSGJ3W9FBC6BVT0Y92S345HY8N3T6AV7R")
                         .unwrap()
                         .to_account_principal(),
+                    "ro-test".try_into().unwrap(),
+                    vec![],
                     TipRequest::UseLatestAnchoredTip,
-                    false,
                 )
             },
             |ref http_request,
@@ -5347,10 +9536,11 @@ mod test {
              ref convo_server| {
                 let req_md = http_request.metadata().clone();
                 match http_response {
-                    HttpResponseType::GetAccount(response_md, data) => {
-                        assert_eq!(data.nonce, 2);
-                        let balance = u128::from_str_radix(&data.balance[2..], 16).unwrap();
-                        assert_eq!(balance, 1000000000);
+                    HttpResponseType::CallReadOnlyFunction(response_md, data) => {
+                        assert!(data.cause.is_some());
+                        assert!(data.cause.clone().unwrap().find("NoSuchContract").is_some());
+                        assert!(!data.okay);
+                        assert!(data.result.is_none());
                         true
                     }
                     _ => {
@@ -5362,29 +9552,33 @@ mod test {
         );
     }
 
-    /// In this test, the query parameter `tip_req` is set to UseLatestUnconfirmedTip, and so we expect the
-    /// tip used for the query to be the latest microblock.
-    /// We check that the account state matches the state in the most recent microblock.
     #[test]
     #[ignore]
-    fn test_rpc_get_account_use_latest_tip() {
+    fn test_rpc_call_read_only_use_latest_tip() {
+        // Test /v2/contracts/call-read (aka CallReadOnlyFunction) endpoint.
+        // In this test, we set `tip_req` to UseLatestUnconfirmedTip, and we expect that querying
+        // against the unconfirmed state will succeed.
         test_rpc(
-            "test_rpc_get_account_use_latest_tip",
-            40112,
-            40113,
-            50112,
-            50113,
+            "test_rpc_call_read_only_use_latest_tip",
+            40172,
+            40173,
+            50172,
+            50173,
             true,
             |ref mut peer_client,
              ref mut convo_client,
              ref mut peer_server,
              ref mut convo_server| {
-                convo_client.new_getaccount(
+                convo_client.new_callreadonlyfunction(
+                    StacksAddress::from_string("ST2DS4MSWSGJ3W9FBC6BVT0Y92S345HY8N3T6AV7R")
+                        .unwrap(),
+                    "hello-world-unconfirmed".try_into().unwrap(),
                     StacksAddress::from_string("ST2DS4MSWSGJ3W9FBC6BVT0Y92S345HY8N3T6AV7R")
                         .unwrap()
                         .to_account_principal(),
+                    "ro-test".try_into().unwrap(),
+                    vec![],
                     TipRequest::UseLatestAnchoredTip,
-                    false,
                 )
             },
             |ref http_request,
@@ -5395,10 +9589,14 @@ mod test {
              ref convo_server| {
                 let req_md = http_request.metadata().clone();
                 match http_response {
-                    HttpResponseType::GetAccount(response_md, data) => {
-                        assert_eq!(data.nonce, 4);
-                        let balance = u128::from_str_radix(&data.balance[2..], 16).unwrap();
-                        assert_eq!(balance, 999999877);
+                    HttpResponseType::CallReadOnlyFunction(response_md, data) => {
+                        assert!(data.okay);
+                        assert_eq!(
+                            Value::try_deserialize_hex_untyped(&data.result.clone().unwrap())
+                                .unwrap(),
+                            Value::okay(Value::Int(1)).unwrap()
+                        );
+                        assert!(data.cause.is_none());
                         true
                     }
                     _ => {
@@ -5410,30 +9608,37 @@ mod test {
         );
     }
 
-    /// In this test, the query parameter `tip_req` is set to UseLatestUnconfirmedTip, but we did not generate
-    /// microblocks in the rpc test. Thus, we expect the tip used for the query to be the previous
-    /// anchor block (which is the latest tip).
-    /// We check that the account state matches the state in the previous anchor block.
     #[test]
     #[ignore]
-    fn test_rpc_get_account_use_latest_tip_no_microblocks() {
+    fn test_rpc_call_read_only_unconfirmed() {
         test_rpc(
-            "test_rpc_get_account",
-            40114,
-            40115,
-            50114,
-            50115,
-            false,
+            "test_rpc_call_read_only_unconfirmed",
+            40180,
+            40181,
+            50180,
+            50181,
+            true,
             |ref mut peer_client,
              ref mut convo_client,
              ref mut peer_server,
              ref mut convo_server| {
-                convo_client.new_getaccount(
+                let unconfirmed_tip = peer_client
+                    .chainstate()
+                    .unconfirmed_state
+                    .as_ref()
+                    .unwrap()
+                    .unconfirmed_chain_tip
+                    .clone();
+                convo_client.new_callreadonlyfunction(
+                    StacksAddress::from_string("ST2DS4MSWSGJ3W9FBC6BVT0Y92S345HY8N3T6AV7R")
+                        .unwrap(),
+                    "hello-world-unconfirmed".try_into().unwrap(),
                     StacksAddress::from_string("ST2DS4MSWSGJ3W9FBC6BVT0Y92S345HY8N3T6AV7R")
                         .unwrap()
                         .to_account_principal(),
-                    TipRequest::UseLatestAnchoredTip,
-                    false,
+                    "ro-test".try_into().unwrap(),
+                    vec![],
+                    TipRequest::SpecificTip(unconfirmed_tip),
                 )
             },
             |ref http_request,
@@ -5444,10 +9649,14 @@ mod test {
              ref convo_server| {
                 let req_md = http_request.metadata().clone();
                 match http_response {
-                    HttpResponseType::GetAccount(response_md, data) => {
-                        assert_eq!(data.nonce, 2);
-                        let balance = u128::from_str_radix(&data.balance[2..], 16).unwrap();
-                        assert_eq!(balance, 1000000000);
+                    HttpResponseType::CallReadOnlyFunction(response_md, data) => {
+                        assert!(data.okay);
+                        assert_eq!(
+                            Value::try_deserialize_hex_untyped(&data.result.clone().unwrap())
+                                .unwrap(),
+                            Value::okay(Value::Int(1)).unwrap()
+                        );
+                        assert!(data.cause.is_none());
                         true
                     }
                     _ => {
@@ -5461,13 +9670,13 @@ mod test {
 
     #[test]
     #[ignore]
-    fn test_rpc_get_account_unconfirmed() {
+    fn test_rpc_call_read_only_batch() {
         test_rpc(
-            "test_rpc_get_account_unconfirmed",
-            40120,
-            40121,
-            50120,
-            50121,
+            "test_rpc_call_read_only_batch",
+            40220,
+            40221,
+            50220,
+            50221,
             true,
             |ref mut peer_client,
              ref mut convo_client,
@@ -5480,12 +9689,27 @@ mod test {
                     .unwrap()
                     .unconfirmed_chain_tip
                     .clone();
-                convo_client.new_getaccount(
+                let call = ReadOnlyCall {
+                    contract: QualifiedContractIdentifier::parse(
+                        "ST2DS4MSWSGJ3W9FBC6BVT0Y92S345HY8N3T6AV7R.hello-world-unconfirmed",
+                    )
+                    .unwrap(),
+                    function: "ro-test".try_into().unwrap(),
+                    arguments: vec![],
+                };
+                let missing_call = ReadOnlyCall {
+                    contract: QualifiedContractIdentifier::parse(
+                        "ST2DS4MSWSGJ3W9FBC6BVT0Y92S345HY8N3T6AV7R.no-such-contract",
+                    )
+                    .unwrap(),
+                    ..call.clone()
+                };
+                convo_client.new_callreadonlyfunctionbatch(
                     StacksAddress::from_string("ST2DS4MSWSGJ3W9FBC6BVT0Y92S345HY8N3T6AV7R")
                         .unwrap()
                         .to_account_principal(),
+                    vec![call.clone(), missing_call, call],
                     TipRequest::SpecificTip(unconfirmed_tip),
-                    false,
                 )
             },
             |ref http_request,
@@ -5494,12 +9718,27 @@ mod test {
              ref mut peer_server,
              ref convo_client,
              ref convo_server| {
-                let req_md = http_request.metadata().clone();
                 match http_response {
-                    HttpResponseType::GetAccount(response_md, data) => {
-                        assert_eq!(data.nonce, 4);
-                        let balance = u128::from_str_radix(&data.balance[2..], 16).unwrap();
-                        assert_eq!(balance, 1000000000 - 123);
+                    HttpResponseType::CallReadOnlyFunctionBatch(response_md, data) => {
+                        // results come back in the order the calls were made
+                        assert_eq!(data.len(), 3);
+                        for ix in [0, 2] {
+                            assert!(data[ix].okay);
+                            assert_eq!(
+                                Value::try_deserialize_hex_untyped(
+                                    &data[ix].result.clone().unwrap()
+                                )
+                                .unwrap(),
+                                Value::okay(Value::Int(1)).unwrap()
+                            );
+                        }
+                        assert!(!data[1].okay);
+                        assert!(data[1]
+                            .cause
+                            .clone()
+                            .unwrap()
+                            .find("NoSuchContract")
+                            .is_some());
                         true
                     }
                     _ => {
@@ -5513,40 +9752,108 @@ mod test {
 
     #[test]
     #[ignore]
-    fn test_rpc_get_data_var() {
+    fn test_rpc_getattachmentsinv_limit_reached() {
         test_rpc(
-            "test_rpc_get_data_var",
-            40122,
-            40123,
-            50122,
-            50123,
+            "test_rpc_getattachmentsinv",
+            40190,
+            40191,
+            50190,
+            50191,
+            true,
+            |ref mut peer_client,
+             ref mut convo_client,
+             ref mut peer_server,
+             ref mut convo_server| {
+                let pages_indexes = HashSet::from_iter(vec![1, 2, 3, 4, 5, 6, 7, 8, 9]);
+                convo_client.new_getattachmentsinv(StacksBlockId([0x00; 32]), pages_indexes)
+            },
+            |ref http_request,
+             ref http_response,
+             ref mut peer_client,
+             ref mut peer_server,
+             ref convo_client,
+             ref convo_server| {
+                let req_md = http_request.metadata().clone();
+                println!("{:?}", http_response);
+                match http_response {
+                    HttpResponseType::BadRequest(_, msg) => {
+                        assert_eq!(
+                            msg,
+                            "Number of attachment inv pages is limited by 8 per request"
+                        );
+                        true
+                    }
+                    _ => false,
+                }
+            },
+        );
+    }
+
+    #[test]
+    #[ignore]
+    fn test_rpc_mempool_query_txtags() {
+        test_rpc(
+            "test_rpc_mempool_query_txtags",
+            40813,
+            40814,
+            50813,
+            50814,
+            false,
+            |ref mut peer_client,
+             ref mut convo_client,
+             ref mut peer_server,
+             ref mut convo_server| {
+                convo_client.new_mempool_query(
+                    MemPoolSyncData::TxTags([0u8; 32], vec![]),
+                    Some(Txid([0u8; 32])),
+                )
+            },
+            |ref http_request,
+             ref http_response,
+             ref mut peer_client,
+             ref mut peer_server,
+             ref convo_client,
+             ref convo_server| {
+                let req_md = http_request.metadata().clone();
+                println!("{:?}", http_response);
+                match http_response {
+                    HttpResponseType::MemPoolTxs(_, _, txs) => {
+                        // got everything
+                        assert_eq!(txs.len(), 10);
+                        true
+                    }
+                    _ => false,
+                }
+            },
+        );
+    }
+
+    #[test]
+    #[ignore]
+    fn test_rpc_get_subnet_fee_rate() {
+        test_rpc(
+            "test_rpc_get_subnet_fee_rate",
+            40200,
+            40201,
+            50200,
+            50201,
             true,
             |ref mut peer_client,
              ref mut convo_client,
              ref mut peer_server,
-             ref mut convo_server| {
-                convo_client.new_getdatavar(
-                    StacksAddress::from_string("ST2DS4MSWSGJ3W9FBC6BVT0Y92S345HY8N3T6AV7R")
-                        .unwrap(),
-                    "hello-world".try_into().unwrap(),
-                    "bar".try_into().unwrap(),
-                    TipRequest::UseLatestAnchoredTip,
-                    false,
-                )
-            },
+             ref mut convo_server| { convo_client.new_get_subnet_fee_rate() },
             |ref http_request,
              ref http_response,
              ref mut peer_client,
              ref mut peer_server,
              ref convo_client,
              ref convo_server| {
-                let req_md = http_request.metadata().clone();
                 match http_response {
-                    HttpResponseType::GetDataVar(response_md, data) => {
-                        assert_eq!(
-                            Value::try_deserialize_hex_untyped(&data.data).unwrap(),
-                            Value::Int(0)
-                        );
+                    HttpResponseType::GetSubnetFeeRate(response_md, data) => {
+                        // the test chain's blocks are nearly empty
+                        assert_eq!(data.min_fee_rate, MINIMUM_TX_FEE_RATE_PER_BYTE);
+                        assert_eq!(data.target_utilization, FEE_MARKET_TARGET_UTILIZATION);
+                        assert!(data.last_block_utilization.is_some());
                         true
                     }
                     _ => {
@@ -5560,33 +9867,23 @@ mod test {
 
     #[test]
     #[ignore]
-    fn test_rpc_get_data_var_unconfirmed() {
+    fn test_rpc_get_burn_ops() {
         test_rpc(
-            "test_rpc_get_data_var_unconfirmed",
-            40124,
-            40125,
-            50124,
-            50125,
+            "test_rpc_get_burn_ops",
+            40210,
+            40211,
+            50210,
+            50211,
             true,
             |ref mut peer_client,
              ref mut convo_client,
              ref mut peer_server,
              ref mut convo_server| {
-                let unconfirmed_tip = peer_client
-                    .chainstate()
-                    .unconfirmed_state
-                    .as_ref()
-                    .unwrap()
-                    .unconfirmed_chain_tip
-                    .clone();
-                convo_client.new_getdatavar(
-                    StacksAddress::from_string("ST2DS4MSWSGJ3W9FBC6BVT0Y92S345HY8N3T6AV7R")
-                        .unwrap(),
-                    "hello-world".try_into().unwrap(),
-                    "bar".try_into().unwrap(),
-                    TipRequest::SpecificTip(unconfirmed_tip),
-                    false,
+                let burn_tip = SortitionDB::get_canonical_burn_chain_tip(
+                    peer_server.sortdb.as_ref().unwrap().conn(),
                 )
+                .unwrap();
+                convo_client.new_get_burn_ops(burn_tip.block_height)
             },
             |ref http_request,
              ref http_response,
@@ -5594,12 +9891,26 @@ mod test {
              ref mut peer_server,
              ref convo_client,
              ref convo_server| {
-                let req_md = http_request.metadata().clone();
+                let burn_tip = SortitionDB::get_canonical_burn_chain_tip(
+                    peer_server.sortdb.as_ref().unwrap().conn(),
+                )
+                .unwrap();
                 match http_response {
-                    HttpResponseType::GetDataVar(response_md, data) => {
+                    HttpResponseType::GetBurnOps(response_md, data) => {
+                        assert_eq!(data.burn_block_height, burn_tip.block_height);
                         assert_eq!(
-                            Value::try_deserialize_hex_untyped(&data.data).unwrap(),
-                            Value::Int(1)
+                            data.burn_header_hash,
+                            format!("{}", &burn_tip.burn_header_hash)
+                        );
+                        // the block-commit that won the tip's sortition is reported
+                        assert_eq!(
+                            data.ops.iter().any(|op| match op {
+                                BlockstackOperationType::LeaderBlockCommit(commit) => {
+                                    commit.block_header_hash == burn_tip.winning_stacks_block_hash
+                                }
+                                _ => false,
+                            }),
+                            burn_tip.sortition
                         );
                         true
                     }
@@ -5614,37 +9925,43 @@ mod test {
 
     #[test]
     #[ignore]
-    fn test_rpc_get_data_var_nonexistant() {
+    fn test_rpc_get_forks() {
         test_rpc(
-            "test_rpc_get_data_var_nonexistant",
-            40125,
-            40126,
-            50125,
-            50126,
+            "test_rpc_get_forks",
+            40230,
+            40231,
+            50230,
+            50231,
             true,
             |ref mut peer_client,
              ref mut convo_client,
              ref mut peer_server,
-             ref mut convo_server| {
-                convo_client.new_getdatavar(
-                    StacksAddress::from_string("ST2DS4MSWSGJ3W9FBC6BVT0Y92S345HY8N3T6AV7R")
-                        .unwrap(),
-                    "hello-world".try_into().unwrap(),
-                    "bar-nonexistant".try_into().unwrap(),
-                    TipRequest::UseLatestAnchoredTip,
-                    false,
-                )
-            },
+             ref mut convo_server| { convo_client.new_get_forks(5) },
             |ref http_request,
              ref http_response,
              ref mut peer_client,
              ref mut peer_server,
              ref convo_client,
              ref convo_server| {
-                let req_md = http_request.metadata().clone();
+                let burn_tip = SortitionDB::get_canonical_burn_chain_tip(
+                    peer_server.sortdb.as_ref().unwrap().conn(),
+                )
+                .unwrap();
                 match http_response {
-                    HttpResponseType::NotFound(_, msg) => {
-                        assert_eq!(msg, "Data var not found");
+                    HttpResponseType::GetForks(response_md, data) => {
+                        assert_eq!(data.canonical_burn_tip, burn_tip.burn_header_hash);
+                        let tip_burn_block = data
+                            .burn_blocks
+                            .iter()
+                            .find(|block| block.burn_header_hash == burn_tip.burn_header_hash)
+                            .unwrap();
+                        assert!(tip_burn_block.canonical);
+                        // the canonical Stacks tip is in the tree, and is marked canonical
+                        assert!(data.burn_blocks.iter().any(|burn_block| burn_block
+                            .blocks
+                            .iter()
+                            .any(|block| block.index_block_hash == data.canonical_stacks_tip
+                                && block.status == ForkStatus::Canonical)));
                         true
                     }
                     _ => {
@@ -5658,55 +9975,46 @@ mod test {
 
     #[test]
     #[ignore]
-    fn test_rpc_get_map_entry() {
-        // Test v2/map_entry (aka GetMapEntry) endpoint.
-        // In this test, we don't set any tip parameters, and we expect that querying for map data
-        // against the canonical Stacks tip will succeed.
+    fn test_rpc_get_contract_costs() {
         test_rpc(
-            "test_rpc_get_map_entry",
-            40130,
-            40131,
-            50130,
-            50131,
+            "test_rpc_get_contract_costs",
+            40820,
+            40821,
+            50820,
+            50821,
             true,
             |ref mut peer_client,
              ref mut convo_client,
              ref mut peer_server,
-             ref mut convo_server| {
-                let principal =
-                    StacksAddress::from_string("ST2DS4MSWSGJ3W9FBC6BVT0Y92S345HY8N3T6AV7R")
-                        .unwrap()
-                        .to_account_principal();
-                convo_client.new_getmapentry(
-                    StacksAddress::from_string("ST2DS4MSWSGJ3W9FBC6BVT0Y92S345HY8N3T6AV7R")
-                        .unwrap(),
-                    "hello-world".try_into().unwrap(),
-                    "unit-map".try_into().unwrap(),
-                    Value::Tuple(
-                        TupleData::from_data(vec![("account".into(), Value::Principal(principal))])
-                            .unwrap(),
-                    ),
-                    TipRequest::UseLatestAnchoredTip,
-                    false,
-                )
-            },
+             ref mut convo_server| { convo_client.new_get_contract_costs(5) },
             |ref http_request,
              ref http_response,
              ref mut peer_client,
              ref mut peer_server,
              ref convo_client,
              ref convo_server| {
-                let req_md = http_request.metadata().clone();
+                let (tip_consensus_hash, tip_block_hash) =
+                    SortitionDB::get_canonical_stacks_chain_tip_hash(
+                        peer_server.sortdb.as_ref().unwrap().conn(),
+                    )
+                    .unwrap();
                 match http_response {
-                    HttpResponseType::GetMapEntry(response_md, data) => {
+                    HttpResponseType::GetContractCosts(response_md, data) => {
                         assert_eq!(
-                            Value::try_deserialize_hex_untyped(&data.data).unwrap(),
-                            Value::some(Value::Tuple(
-                                TupleData::from_data(vec![("units".into(), Value::Int(123))])
-                                    .unwrap()
-                            ))
-                            .unwrap()
+                            data.canonical_stacks_tip,
+                            StacksBlockHeader::make_index_block_hash(
+                                &tip_consensus_hash,
+                                &tip_block_hash
+                            )
                         );
+                        assert!(data.num_blocks >= 1 && data.num_blocks <= 5);
+                        // the test peers' last block published the hello-world contract
+                        assert!(data.contracts.iter().any(|contract| contract
+                            .contract_id
+                            .name
+                            .as_str()
+                            == "hello-world"
+                            && contract.num_txs >= 1));
                         true
                     }
                     _ => {
@@ -5720,62 +10028,60 @@ mod test {
 
     #[test]
     #[ignore]
-    fn test_rpc_get_map_entry_unconfirmed() {
-        // Test v2/map_entry (aka GetMapEntry) endpoint.
-        // In this test, we set `tip_req` to UseLatestUnconfirmedTip, and we expect that querying for map data
-        // against the unconfirmed state will succeed.
+    fn test_rpc_get_block_commits() {
         test_rpc(
-            "test_rpc_get_map_entry_unconfirmed",
-            40140,
-            40141,
-            50140,
-            50141,
+            "test_rpc_get_block_commits",
+            40822,
+            40823,
+            50822,
+            50823,
             true,
             |ref mut peer_client,
              ref mut convo_client,
              ref mut peer_server,
-             ref mut convo_server| {
-                let unconfirmed_tip = peer_client
-                    .chainstate()
-                    .unconfirmed_state
-                    .as_ref()
-                    .unwrap()
-                    .unconfirmed_chain_tip
-                    .clone();
-                let principal =
-                    StacksAddress::from_string("ST2DS4MSWSGJ3W9FBC6BVT0Y92S345HY8N3T6AV7R")
-                        .unwrap()
-                        .to_account_principal();
-                convo_client.new_getmapentry(
-                    StacksAddress::from_string("ST2DS4MSWSGJ3W9FBC6BVT0Y92S345HY8N3T6AV7R")
-                        .unwrap(),
-                    "hello-world".try_into().unwrap(),
-                    "unit-map".try_into().unwrap(),
-                    Value::Tuple(
-                        TupleData::from_data(vec![("account".into(), Value::Principal(principal))])
-                            .unwrap(),
-                    ),
-                    TipRequest::SpecificTip(unconfirmed_tip),
-                    false,
-                )
-            },
+             ref mut convo_server| { convo_client.new_get_block_commits(0, 10) },
             |ref http_request,
              ref http_response,
              ref mut peer_client,
              ref mut peer_server,
              ref convo_client,
              ref convo_server| {
-                let req_md = http_request.metadata().clone();
+                let sortdb = peer_server.sortdb.as_ref().unwrap();
+                let (tip_consensus_hash, tip_block_hash) =
+                    SortitionDB::get_canonical_stacks_chain_tip_hash(sortdb.conn()).unwrap();
+                let tip_snapshot =
+                    SortitionDB::get_block_snapshot_consensus(sortdb.conn(), &tip_consensus_hash)
+                        .unwrap()
+                        .unwrap();
                 match http_response {
-                    HttpResponseType::GetMapEntry(response_md, data) => {
+                    HttpResponseType::GetBlockCommits(response_md, data) => {
+                        // the boot block is left out, and every later block is included
+                        assert_eq!(data.commits.len() as u64, data.canonical_stacks_tip_height);
+                        assert!(data
+                            .commits
+                            .iter()
+                            .enumerate()
+                            .all(|(i, commit)| commit.l2_height == (i as u64) + 1));
+                        let tip_commit = data.commits.last().unwrap();
                         assert_eq!(
-                            Value::try_deserialize_hex_untyped(&data.data).unwrap(),
-                            Value::some(Value::Tuple(
-                                TupleData::from_data(vec![("units".into(), Value::Int(1))])
-                                    .unwrap()
-                            ))
-                            .unwrap()
+                            tip_commit.index_block_hash,
+                            StacksBlockHeader::make_index_block_hash(
+                                &tip_consensus_hash,
+                                &tip_block_hash
+                            )
                         );
+                        assert_eq!(tip_commit.block_hash, tip_block_hash);
+                        assert_eq!(tip_commit.l1_txid, tip_snapshot.winning_block_txid);
+                        assert_eq!(tip_commit.l1_block_height, tip_snapshot.block_height);
+                        assert_eq!(tip_commit.l1_block_hash, tip_snapshot.burn_header_hash);
+                        // the server recorded each block's commit as it processed the block, and
+                        //  did not wait for confirmations to do so
+                        assert_eq!(data.required_confirmations, 0);
+                        for commit in data.commits.iter() {
+                            let at_processing = commit.confirmations_at_processing.unwrap();
+                            assert!(at_processing >= 1);
+                            assert!(commit.l1_confirmations >= at_processing);
+                        }
                         true
                     }
                     _ => {
@@ -5789,52 +10095,48 @@ mod test {
 
     #[test]
     #[ignore]
-    fn test_rpc_get_map_entry_use_latest_tip() {
+    fn test_rpc_get_rewards_report() {
         test_rpc(
-            "test_rpc_get_map_entry_use_latest_tip",
-            40142,
-            40143,
-            50142,
-            50143,
+            "test_rpc_get_rewards_report",
+            40826,
+            40827,
+            50826,
+            50827,
             true,
             |ref mut peer_client,
              ref mut convo_client,
              ref mut peer_server,
-             ref mut convo_server| {
-                let principal =
-                    StacksAddress::from_string("ST2DS4MSWSGJ3W9FBC6BVT0Y92S345HY8N3T6AV7R")
-                        .unwrap()
-                        .to_account_principal();
-                convo_client.new_getmapentry(
-                    StacksAddress::from_string("ST2DS4MSWSGJ3W9FBC6BVT0Y92S345HY8N3T6AV7R")
-                        .unwrap(),
-                    "hello-world".try_into().unwrap(),
-                    "unit-map".try_into().unwrap(),
-                    Value::Tuple(
-                        TupleData::from_data(vec![("account".into(), Value::Principal(principal))])
-                            .unwrap(),
-                    ),
-                    TipRequest::UseLatestAnchoredTip,
-                    false,
-                )
-            },
+             ref mut convo_server| { convo_client.new_get_rewards_report(0, 100) },
             |ref http_request,
              ref http_response,
              ref mut peer_client,
              ref mut peer_server,
              ref convo_client,
              ref convo_server| {
-                let req_md = http_request.metadata().clone();
+                let sortdb = peer_server.sortdb.as_ref().unwrap();
+                let (tip_consensus_hash, tip_block_hash) =
+                    SortitionDB::get_canonical_stacks_chain_tip_hash(sortdb.conn()).unwrap();
                 match http_response {
-                    HttpResponseType::GetMapEntry(response_md, data) => {
+                    HttpResponseType::GetRewardsReport(response_md, data) => {
                         assert_eq!(
-                            Value::try_deserialize_hex_untyped(&data.data).unwrap(),
-                            Value::some(Value::Tuple(
-                                TupleData::from_data(vec![("units".into(), Value::Int(1))])
-                                    .unwrap()
-                            ))
-                            .unwrap()
+                            data.canonical_stacks_tip,
+                            StacksBlockHeader::make_index_block_hash(
+                                &tip_consensus_hash,
+                                &tip_block_hash
+                            )
                         );
+                        assert_eq!(data.from_height, 0);
+                        // every block but the boot block has a miner, and the report stops at
+                        // the tip
+                        assert!(data.to_height > 0 && data.to_height < 100);
+                        let blocks_mined: u64 =
+                            data.miners.iter().map(|miner| miner.blocks_mined).sum();
+                        assert_eq!(blocks_mined, data.to_height);
+                        // no commit fees are known without a ledger
+                        assert!(data
+                            .miners
+                            .iter()
+                            .all(|miner| miner.commits_with_known_fee == 0 && miner.net.is_none()));
                         true
                     }
                     _ => {
@@ -5848,27 +10150,19 @@ mod test {
 
     #[test]
     #[ignore]
-    fn test_rpc_get_contract_abi() {
-        // Test /v2/contracts/interface (aka GetContractABI) endpoint.
-        // In this test, we don't set any tip parameters, and we expect that querying
-        // against the canonical Stacks tip will succeed.
+    fn test_rpc_get_event_replay_unavailable() {
         test_rpc(
-            "test_rpc_get_contract_abi",
-            40150,
-            40151,
-            50150,
-            50151,
+            "test_rpc_get_event_replay_unavailable",
+            40828,
+            40829,
+            50828,
+            50829,
             true,
             |ref mut peer_client,
              ref mut convo_client,
              ref mut peer_server,
              ref mut convo_server| {
-                convo_client.new_getcontractabi(
-                    StacksAddress::from_string("ST2DS4MSWSGJ3W9FBC6BVT0Y92S345HY8N3T6AV7R")
-                        .unwrap(),
-                    "hello-world-unconfirmed".try_into().unwrap(),
-                    TipRequest::UseLatestAnchoredTip,
-                )
+                convo_client.new_get_event_replay(1, 5, vec!["*".to_string()])
             },
             |ref http_request,
              ref http_response,
@@ -5876,10 +10170,10 @@ mod test {
              ref mut peer_server,
              ref convo_client,
              ref convo_server| {
-                let req_md = http_request.metadata().clone();
+                // the test peers have no event replay formatter
                 match http_response {
-                    HttpResponseType::NotFound(..) => {
-                        // not confirmed yet
+                    HttpResponseType::BadRequestJSON(_, data) => {
+                        assert_eq!(data["error"], "This node does not replay events");
                         true
                     }
                     _ => {
@@ -5893,48 +10187,33 @@ mod test {
 
     #[test]
     #[ignore]
-    fn test_rpc_get_contract_abi_unconfirmed() {
-        // Test /v2/contracts/interface (aka GetContractABI) endpoint.
-        // In this test, we set `tip_req` to UseLatestUnconfirmedTip, and we expect that querying
-        // against the unconfirmed state will succeed.
+    fn test_rpc_get_microblock_stream_unknown_block() {
         test_rpc(
-            "test_rpc_get_contract_abi_unconfirmed",
-            40152,
-            40153,
-            50152,
-            50153,
+            "test_rpc_get_microblock_stream_unknown_block",
+            40830,
+            40831,
+            50830,
+            50831,
             true,
             |ref mut peer_client,
              ref mut convo_client,
              ref mut peer_server,
              ref mut convo_server| {
-                let unconfirmed_tip = peer_client
-                    .chainstate()
-                    .unconfirmed_state
-                    .as_ref()
-                    .unwrap()
-                    .unconfirmed_chain_tip
-                    .clone();
-                convo_client.new_getcontractabi(
-                    StacksAddress::from_string("ST2DS4MSWSGJ3W9FBC6BVT0Y92S345HY8N3T6AV7R")
-                        .unwrap(),
-                    "hello-world-unconfirmed".try_into().unwrap(),
-                    TipRequest::SpecificTip(unconfirmed_tip),
-                )
+                convo_client.new_get_microblock_stream(StacksBlockId([0x11; 32]))
             },
             |ref http_request,
              ref http_response,
              ref mut peer_client,
              ref mut peer_server,
              ref convo_client,
-             ref convo_server| {
-                let req_md = http_request.metadata().clone();
-                match http_response {
-                    HttpResponseType::GetContractABI(response_md, data) => true,
-                    _ => {
-                        error!("Invalid response; {:?}", &http_response);
-                        false
-                    }
+             ref convo_server| match http_response {
+                HttpResponseType::NotFound(_, msg) => {
+                    assert!(msg.contains("No such anchored block"));
+                    true
+                }
+                _ => {
+                    error!("Invalid response; {:?}", &http_response);
+                    false
                 }
             },
         );
@@ -5942,34 +10221,30 @@ mod test {
 
     #[test]
     #[ignore]
-    fn test_rpc_get_contract_abi_use_latest_tip() {
+    fn test_rpc_get_withdrawal_claim_missing() {
         test_rpc(
-            "test_rpc_get_contract_abi_use_latest_tip",
-            40154,
-            40155,
-            50154,
-            50155,
+            "test_rpc_get_withdrawal_claim_missing",
+            40824,
+            40825,
+            50824,
+            50825,
             true,
             |ref mut peer_client,
              ref mut convo_client,
              ref mut peer_server,
-             ref mut convo_server| {
-                convo_client.new_getcontractabi(
-                    StacksAddress::from_string("ST2DS4MSWSGJ3W9FBC6BVT0Y92S345HY8N3T6AV7R")
-                        .unwrap(),
-                    "hello-world-unconfirmed".try_into().unwrap(),
-                    TipRequest::UseLatestAnchoredTip,
-                )
-            },
+             ref mut convo_server| { convo_client.new_get_withdrawal_claim(1, 0) },
             |ref http_request,
              ref http_response,
              ref mut peer_client,
              ref mut peer_server,
              ref convo_client,
              ref convo_server| {
-                let req_md = http_request.metadata().clone();
+                // the test chain makes no withdrawals
                 match http_response {
-                    HttpResponseType::GetContractABI(response_md, data) => true,
+                    HttpResponseType::NotFound(_, msg) => {
+                        assert_eq!(msg, "No such withdrawal");
+                        true
+                    }
                     _ => {
                         error!("Invalid response; {:?}", &http_response);
                         false
@@ -5981,46 +10256,38 @@ mod test {
 
     #[test]
     #[ignore]
-    fn test_rpc_call_read_only() {
-        // Test /v2/contracts/call-read (aka CallReadOnlyFunction) endpoint.
-        // In this test, we don't set any tip parameters, and we expect that querying
-        // against the canonical Stacks tip will succeed.
+    fn test_rpc_get_subnet_info() {
         test_rpc(
-            "test_rpc_call_read_only",
-            40170,
-            40171,
-            50170,
-            50171,
+            "test_rpc_get_subnet_info",
+            40240,
+            40241,
+            50240,
+            50241,
             true,
             |ref mut peer_client,
              ref mut convo_client,
              ref mut peer_server,
-             ref mut convo_server| {
-                convo_client.new_callreadonlyfunction(
-                    StacksAddress::from_string("ST2DS4MSWSGJ3W9FBC6BVT0Y92S345HY8N3T6AV7R")
-                        .unwrap(),
-                    "hello-world-unconfirmed".try_into().unwrap(),
-                    StacksAddress::from_string("ST2DS4MSWSGJ3W9FBC6BVT0Y92S345HY8N3T6AV7R")
-                        .unwrap()
-                        .to_account_principal(),
-                    "ro-test".try_into().unwrap(),
-                    vec![],
-                    TipRequest::UseLatestAnchoredTip,
-                )
-            },
+             ref mut convo_server| { convo_client.new_get_subnet_info() },
             |ref http_request,
              ref http_response,
              ref mut peer_client,
              ref mut peer_server,
              ref convo_client,
              ref convo_server| {
-                let req_md = http_request.metadata().clone();
+                let burnchain = &peer_server.network.burnchain;
                 match http_response {
-                    HttpResponseType::CallReadOnlyFunction(response_md, data) => {
-                        assert!(data.cause.is_some());
-                        assert!(data.cause.clone().unwrap().find("NoSuchContract").is_some());
-                        assert!(!data.okay);
-                        assert!(data.result.is_none());
+                    HttpResponseType::GetSubnetInfo(response_md, data) => {
+                        assert_eq!(
+                            data.l1_contract_identifier,
+                            burnchain.subnet_governing_contract.to_string()
+                        );
+                        assert_eq!(data.first_burn_block_height, burnchain.first_block_height);
+                        assert_eq!(
+                            data.first_burn_header_hash,
+                            format!("{}", &burnchain.first_block_hash)
+                        );
+                        assert_eq!(data.stacks_epoch, SUBNETS_STACKS_EPOCH.to_string());
+                        assert_eq!(data.clarity_version, SUBNETS_CLARITY_VERSION.to_string());
                         true
                     }
                     _ => {
@@ -6034,49 +10301,28 @@ mod test {
 
     #[test]
     #[ignore]
-    fn test_rpc_call_read_only_use_latest_tip() {
-        // Test /v2/contracts/call-read (aka CallReadOnlyFunction) endpoint.
-        // In this test, we set `tip_req` to UseLatestUnconfirmedTip, and we expect that querying
-        // against the unconfirmed state will succeed.
+    fn test_rpc_get_health() {
         test_rpc(
-            "test_rpc_call_read_only_use_latest_tip",
-            40172,
-            40173,
-            50172,
-            50173,
+            "test_rpc_get_health_live",
+            40290,
+            40291,
+            50290,
+            50291,
             true,
             |ref mut peer_client,
              ref mut convo_client,
              ref mut peer_server,
-             ref mut convo_server| {
-                convo_client.new_callreadonlyfunction(
-                    StacksAddress::from_string("ST2DS4MSWSGJ3W9FBC6BVT0Y92S345HY8N3T6AV7R")
-                        .unwrap(),
-                    "hello-world-unconfirmed".try_into().unwrap(),
-                    StacksAddress::from_string("ST2DS4MSWSGJ3W9FBC6BVT0Y92S345HY8N3T6AV7R")
-                        .unwrap()
-                        .to_account_principal(),
-                    "ro-test".try_into().unwrap(),
-                    vec![],
-                    TipRequest::UseLatestAnchoredTip,
-                )
-            },
+             ref mut convo_server| { convo_client.new_get_health_live() },
             |ref http_request,
              ref http_response,
              ref mut peer_client,
              ref mut peer_server,
              ref convo_client,
              ref convo_server| {
-                let req_md = http_request.metadata().clone();
                 match http_response {
-                    HttpResponseType::CallReadOnlyFunction(response_md, data) => {
-                        assert!(data.okay);
-                        assert_eq!(
-                            Value::try_deserialize_hex_untyped(&data.result.clone().unwrap())
-                                .unwrap(),
-                            Value::okay(Value::Int(1)).unwrap()
-                        );
-                        assert!(data.cause.is_none());
+                    HttpResponseType::HealthCheck(response_md, data) => {
+                        assert!(data.ok);
+                        assert!(data.failures.is_empty());
                         true
                     }
                     _ => {
@@ -6086,57 +10332,33 @@ mod test {
                 }
             },
         );
-    }
 
-    #[test]
-    #[ignore]
-    fn test_rpc_call_read_only_unconfirmed() {
         test_rpc(
-            "test_rpc_call_read_only_unconfirmed",
-            40180,
-            40181,
-            50180,
-            50181,
+            "test_rpc_get_health_ready",
+            40292,
+            40293,
+            50292,
+            50293,
             true,
             |ref mut peer_client,
              ref mut convo_client,
              ref mut peer_server,
-             ref mut convo_server| {
-                let unconfirmed_tip = peer_client
-                    .chainstate()
-                    .unconfirmed_state
-                    .as_ref()
-                    .unwrap()
-                    .unconfirmed_chain_tip
-                    .clone();
-                convo_client.new_callreadonlyfunction(
-                    StacksAddress::from_string("ST2DS4MSWSGJ3W9FBC6BVT0Y92S345HY8N3T6AV7R")
-                        .unwrap(),
-                    "hello-world-unconfirmed".try_into().unwrap(),
-                    StacksAddress::from_string("ST2DS4MSWSGJ3W9FBC6BVT0Y92S345HY8N3T6AV7R")
-                        .unwrap()
-                        .to_account_principal(),
-                    "ro-test".try_into().unwrap(),
-                    vec![],
-                    TipRequest::SpecificTip(unconfirmed_tip),
-                )
-            },
+             ref mut convo_server| { convo_client.new_get_health_ready() },
             |ref http_request,
              ref http_response,
              ref mut peer_client,
              ref mut peer_server,
              ref convo_client,
              ref convo_server| {
-                let req_md = http_request.metadata().clone();
+                let sortdb = peer_server.sortdb.as_ref().unwrap();
+                let burn_tip = SortitionDB::get_canonical_burn_chain_tip(sortdb.conn()).unwrap();
                 match http_response {
-                    HttpResponseType::CallReadOnlyFunction(response_md, data) => {
-                        assert!(data.okay);
-                        assert_eq!(
-                            Value::try_deserialize_hex_untyped(&data.result.clone().unwrap())
-                                .unwrap(),
-                            Value::okay(Value::Int(1)).unwrap()
-                        );
-                        assert!(data.cause.is_none());
+                    HttpResponseType::HealthCheck(response_md, data) => {
+                        assert!(data.ok);
+                        assert_eq!(data.burn_block_height, Some(burn_tip.block_height));
+                        assert!(data.l1_tip_height.is_some());
+                        // the test peer does not mine
+                        assert_eq!(data.mining_key_loaded, None);
                         true
                     }
                     _ => {
@@ -6150,38 +10372,52 @@ mod test {
 
     #[test]
     #[ignore]
-    fn test_rpc_getattachmentsinv_limit_reached() {
+    fn test_rpc_get_mempool_preview() {
         test_rpc(
-            "test_rpc_getattachmentsinv",
-            40190,
-            40191,
-            50190,
-            50191,
+            "test_rpc_get_mempool_preview",
+            40250,
+            40251,
+            50250,
+            50251,
             true,
             |ref mut peer_client,
              ref mut convo_client,
              ref mut peer_server,
-             ref mut convo_server| {
-                let pages_indexes = HashSet::from_iter(vec![1, 2, 3, 4, 5, 6, 7, 8, 9]);
-                convo_client.new_getattachmentsinv(StacksBlockId([0x00; 32]), pages_indexes)
-            },
+             ref mut convo_server| { convo_client.new_get_mempool_preview(5) },
             |ref http_request,
              ref http_response,
              ref mut peer_client,
              ref mut peer_server,
              ref convo_client,
              ref convo_server| {
-                let req_md = http_request.metadata().clone();
-                println!("{:?}", http_response);
+                let mempool = peer_server.mempool.as_ref().unwrap();
                 match http_response {
-                    HttpResponseType::BadRequest(_, msg) => {
-                        assert_eq!(
-                            msg,
-                            "Number of attachment inv pages is limited by 8 per request"
-                        );
+                    HttpResponseType::GetMempoolPreview(response_md, data) => {
+                        // the server's mempool holds 10 transactions from distinct senders, none
+                        // of which have a fee-rate estimate
+                        assert_eq!(data.candidates.len(), 5);
+                        let txids: HashSet<_> =
+                            data.candidates.iter().map(|c| c.txid.clone()).collect();
+                        assert_eq!(txids.len(), 5);
+                        for candidate in data.candidates.iter() {
+                            assert!(candidate.no_estimate);
+                            assert_eq!(candidate.fee_rate, None);
+                            assert_eq!(candidate.origin_nonce, 0);
+                            let txid = Txid::from_hex(&candidate.txid[2..]).unwrap();
+                            assert!(MemPoolDB::db_has_tx(mempool.conn(), &txid).unwrap());
+                        }
+                        // the preview left the nonce cache empty
+                        let cached_nonces: i64 = mempool
+                            .conn()
+                            .query_row("SELECT COUNT(*) FROM nonces", NO_PARAMS, |row| row.get(0))
+                            .unwrap();
+                        assert_eq!(cached_nonces, 0);
                         true
                     }
-                    _ => false,
+                    _ => {
+                        error!("Invalid response; {:?}", &http_response);
+                        false
+                    }
                 }
             },
         );
@@ -6189,38 +10425,47 @@ mod test {
 
     #[test]
     #[ignore]
-    fn test_rpc_mempool_query_txtags() {
+    fn test_rpc_get_mempool_stats() {
         test_rpc(
-            "test_rpc_mempool_query_txtags",
-            40813,
-            40814,
-            50813,
-            50814,
-            false,
+            "test_rpc_get_mempool_stats",
+            40260,
+            40261,
+            50260,
+            50261,
+            true,
             |ref mut peer_client,
              ref mut convo_client,
              ref mut peer_server,
-             ref mut convo_server| {
-                convo_client.new_mempool_query(
-                    MemPoolSyncData::TxTags([0u8; 32], vec![]),
-                    Some(Txid([0u8; 32])),
-                )
-            },
+             ref mut convo_server| { convo_client.new_get_mempool_stats() },
             |ref http_request,
              ref http_response,
              ref mut peer_client,
              ref mut peer_server,
              ref convo_client,
              ref convo_server| {
-                let req_md = http_request.metadata().clone();
-                println!("{:?}", http_response);
+                let mempool = peer_server.mempool.as_ref().unwrap();
                 match http_response {
-                    HttpResponseType::MemPoolTxs(_, _, txs) => {
-                        // got everything
-                        assert_eq!(txs.len(), 10);
+                    HttpResponseType::GetMempoolStats(response_md, stats) => {
+                        // the server's mempool holds 10 transactions, just admitted, none of
+                        // which have a fee-rate estimate
+                        assert_eq!(stats.tx_count, 10);
+                        let total_bytes: i64 = mempool
+                            .conn()
+                            .query_row("SELECT SUM(length) FROM mempool", NO_PARAMS, |row| {
+                                row.get(0)
+                            })
+                            .unwrap();
+                        assert_eq!(stats.total_bytes, total_bytes as u64);
+                        assert_eq!(stats.no_fee_rate_estimate, 10);
+                        assert!(stats.fee_rates.iter().all(|bucket| bucket.count == 0));
+                        assert_eq!(stats.ages[0].count, 10);
+                        assert_eq!(stats.payload_types.values().sum::<u64>(), 10);
                         true
                     }
-                    _ => false,
+                    _ => {
+                        error!("Invalid response; {:?}", &http_response);
+                        false
+                    }
                 }
             },
         );
@@ -6270,6 +10515,218 @@ mod test {
         );
     }
 
+    /// Forwards transactions nowhere, answering with `reject` if it is set
+    struct TestTxForwarder {
+        forwarded: RefCell<Vec<Txid>>,
+        reject: Option<serde_json::Value>,
+    }
+
+    impl TransactionForwarder for TestTxForwarder {
+        fn forward_transaction(
+            &self,
+            tx: &StacksTransaction,
+            _attachment: Option<&Attachment>,
+            _expiration_height: Option<u64>,
+            _bundle_id: Option<&str>,
+        ) -> Result<Txid, TransactionForwardError> {
+            if let Some(ref reason) = self.reject {
+                return Err(TransactionForwardError::Rejected(reason.clone()));
+            }
+            self.forwarded.borrow_mut().push(tx.txid());
+            Ok(tx.txid())
+        }
+    }
+
+    fn make_test_transfer() -> StacksTransaction {
+        let privk = StacksPrivateKey::new();
+        let mut tx = StacksTransaction::new(
+            TransactionVersion::Testnet,
+            TransactionAuth::from_p2pkh(&privk).unwrap(),
+            TransactionPayload::TokenTransfer(
+                PrincipalData::parse("ST2DS4MSWSGJ3W9FBC6BVT0Y92S345HY8N3T6AV7R").unwrap(),
+                123,
+                TokenTransferMemo([0u8; 34]),
+            ),
+        );
+        tx.chain_id = 0x80000000;
+        tx.set_tx_fee(1000);
+        tx.set_origin_nonce(0);
+        let mut tx_signer = StacksTransactionSigner::new(&tx);
+        tx_signer.sign_origin(&privk).unwrap();
+        tx_signer.get_tx().unwrap()
+    }
+
+    #[test]
+    #[ignore]
+    fn test_rpc_read_only_forwards_transaction() {
+        let tx = make_test_transfer();
+        let txid = tx.txid();
+        let forwarder = TestTxForwarder {
+            forwarded: RefCell::new(vec![]),
+            reject: None,
+        };
+        let handler_args = RPCHandlerArgs {
+            read_only: true,
+            tx_forwarder: Some(&forwarder),
+            ..RPCHandlerArgs::default()
+        };
+        test_rpc_with_handler_args(
+            "test_rpc_read_only_forwards_transaction",
+            40840,
+            40841,
+            50840,
+            50841,
+            false,
+            &handler_args,
+            |ref mut peer_client,
+             ref mut convo_client,
+             ref mut peer_server,
+             ref mut convo_server| { convo_client.new_post_transaction(tx.clone()) },
+            |ref http_request,
+             ref http_response,
+             ref mut peer_client,
+             ref mut peer_server,
+             ref convo_client,
+             ref convo_server| {
+                // the primary's txid is passed back, and the replica's mempool is untouched
+                assert!(
+                    MemPoolDB::get_tx(peer_server.mempool.as_ref().unwrap().conn(), &txid)
+                        .unwrap()
+                        .is_none()
+                );
+                match http_response {
+                    HttpResponseType::TransactionID(_, response_txid) => *response_txid == txid,
+                    _ => {
+                        error!("Invalid response; {:?}", &http_response);
+                        false
+                    }
+                }
+            },
+        );
+        assert_eq!(*forwarder.forwarded.borrow(), vec![txid]);
+    }
+
+    #[test]
+    #[ignore]
+    fn test_rpc_read_only_forward_rejected() {
+        let tx = make_test_transfer();
+        let forwarder = TestTxForwarder {
+            forwarded: RefCell::new(vec![]),
+            reject: Some(json!({"error": "transaction rejected", "reason": "BadNonce"})),
+        };
+        let handler_args = RPCHandlerArgs {
+            read_only: true,
+            tx_forwarder: Some(&forwarder),
+            ..RPCHandlerArgs::default()
+        };
+        test_rpc_with_handler_args(
+            "test_rpc_read_only_forward_rejected",
+            40842,
+            40843,
+            50842,
+            50843,
+            false,
+            &handler_args,
+            |ref mut peer_client,
+             ref mut convo_client,
+             ref mut peer_server,
+             ref mut convo_server| { convo_client.new_post_transaction(tx.clone()) },
+            |ref http_request,
+             ref http_response,
+             ref mut peer_client,
+             ref mut peer_server,
+             ref convo_client,
+             ref convo_server| {
+                // the primary's reason is passed back as is
+                match http_response {
+                    HttpResponseType::BadRequest(_, msg) => msg.contains("BadNonce"),
+                    _ => {
+                        error!("Invalid response; {:?}", &http_response);
+                        false
+                    }
+                }
+            },
+        );
+    }
+
+    #[test]
+    #[ignore]
+    fn test_rpc_read_only_refuses_posts() {
+        let tx = make_test_transfer();
+        let txid = tx.txid();
+        let handler_args = RPCHandlerArgs {
+            read_only: true,
+            ..RPCHandlerArgs::default()
+        };
+
+        // without a primary to forward to, transactions are refused
+        test_rpc_with_handler_args(
+            "test_rpc_read_only_refuses_transactions",
+            40844,
+            40845,
+            50844,
+            50845,
+            false,
+            &handler_args,
+            |ref mut peer_client,
+             ref mut convo_client,
+             ref mut peer_server,
+             ref mut convo_server| { convo_client.new_post_transaction(tx.clone()) },
+            |ref http_request,
+             ref http_response,
+             ref mut peer_client,
+             ref mut peer_server,
+             ref convo_client,
+             ref convo_server| {
+                assert!(
+                    MemPoolDB::get_tx(peer_server.mempool.as_ref().unwrap().conn(), &txid)
+                        .unwrap()
+                        .is_none()
+                );
+                match http_response {
+                    HttpResponseType::Forbidden(_, msg) => {
+                        msg.contains("does not accept transactions")
+                    }
+                    _ => {
+                        error!("Invalid response; {:?}", &http_response);
+                        false
+                    }
+                }
+            },
+        );
+
+        // blocks are always refused
+        test_rpc_with_handler_args(
+            "test_rpc_read_only_refuses_blocks",
+            40846,
+            40847,
+            50846,
+            50847,
+            false,
+            &handler_args,
+            |ref mut peer_client,
+             ref mut convo_client,
+             ref mut peer_server,
+             ref mut convo_server| {
+                convo_client.new_post_block(ConsensusHash([0x11; 20]), StacksBlock::genesis_block())
+            },
+            |ref http_request,
+             ref http_response,
+             ref mut peer_client,
+             ref mut peer_server,
+             ref convo_client,
+             ref convo_server| match http_response {
+                HttpResponseType::Forbidden(_, msg) => {
+                    msg.contains("does not accept blocks or microblocks")
+                }
+                _ => {
+                    error!("Invalid response; {:?}", &http_response);
+                    false
+                }
+            },
+        );
+    }
+
     #[test]
     fn test_getinfo_compat() {
         let old_getinfo_json = r#"{"peer_version":402653189,"pox_consensus":"b712eb731b613eebae814a8f416c5c15bc8391ec","burn_block_height":727631,"stable_pox_consensus":"53b5ed79842080500d7d83daa36aa1069dedf983","stable_burn_block_height":727624,"server_version":"stacks-node 0.0.1 (feat/faster-inv-generation:68f33190a, release build, linux [x86_64])","network_id":1,"parent_network_id":3652501241,"stacks_tip_height":52537,"stacks_tip":"b3183f2ac588e12319ff0fde78f97e62c92a218d87828c35710c29aaf7adbedc","stacks_tip_consensus_hash":"b712eb731b613eebae814a8f416c5c15bc8391ec","genesis_chainstate_hash":"74237aa39aa50a83de11a4f53e9d3bb7d43461d1de9873f402e5453ae60bc59b","unanchored_tip":"e76f68d607480e9984b4062b2691fb60a88423177898f5780b40ace17ae8982a","unanchored_seq":0,"exit_at_block_height":null, "l1_subnet_governing_contract":"ST1PQHQKV0RJXZFY1DGX8MNSNYVE3VGZJSRTPGZGM.subnet"}"#;
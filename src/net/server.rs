@@ -189,11 +189,11 @@ impl HttpPeer {
 
         // how many other conversations are connected?
         let num_inbound = self.count_inbound_ip_addrs(peer_addr);
-        if num_inbound > self.connection_opts.max_http_clients {
+        if num_inbound > self.connection_opts.max_http_clients_per_host {
             // too many
             debug!(
                 "HTTP: too many inbound HTTP peers from {:?} ({} > {})",
-                peer_addr, num_inbound, self.connection_opts.max_http_clients
+                peer_addr, num_inbound, self.connection_opts.max_http_clients_per_host
             );
             return Err(net_error::TooManyPeers);
         }
@@ -1221,6 +1221,8 @@ mod test {
                     ),
                     signed_contract_tx,
                     None,
+                    None,
+                    None,
                 );
                 request.metadata_mut().keep_alive = false;
 
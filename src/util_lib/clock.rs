@@ -0,0 +1,111 @@
+// Copyright (C) 2013-2020 Blockstack PBC, a public benefit corporation
+// Copyright (C) 2020 Stacks Open Internet Foundation
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Wall-clock time, behind a trait so that tests can control it.  The mempool, the block
+//! builders and the node's run loop read the time from a `Clock` they are given, which is a
+//! `SystemClock` unless a test swaps in a `ManualClock` and advances it by hand, e.g. to expire
+//! a miner's deadline or a microblock interval without waiting for it.
+
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use stacks_common::util::get_epoch_time_ms;
+
+/// A source of wall-clock time
+pub trait Clock: fmt::Debug + Send + Sync {
+    /// Milliseconds since the Unix epoch
+    fn now_ms(&self) -> u128;
+
+    /// Seconds since the Unix epoch
+    fn now_secs(&self) -> u64 {
+        (self.now_ms() / 1000) as u64
+    }
+}
+
+/// The system's clock
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now_ms(&self) -> u128 {
+        get_epoch_time_ms()
+    }
+}
+
+/// A clock which only moves when it is told to.  Clones share the same time, so a test can
+/// keep one and hand the others to the code under test.
+#[derive(Debug, Clone)]
+pub struct ManualClock {
+    now_ms: Arc<AtomicU64>,
+}
+
+impl ManualClock {
+    /// A clock stopped at `now_ms`
+    pub fn new(now_ms: u64) -> ManualClock {
+        ManualClock {
+            now_ms: Arc::new(AtomicU64::new(now_ms)),
+        }
+    }
+
+    /// A clock stopped at the system's current time
+    pub fn at_system_time() -> ManualClock {
+        ManualClock::new(get_epoch_time_ms() as u64)
+    }
+
+    pub fn set_ms(&self, now_ms: u64) {
+        self.now_ms.store(now_ms, Ordering::SeqCst);
+    }
+
+    pub fn advance_ms(&self, ms: u64) {
+        self.now_ms.fetch_add(ms, Ordering::SeqCst);
+    }
+
+    pub fn advance_secs(&self, secs: u64) {
+        self.advance_ms(secs.saturating_mul(1000));
+    }
+}
+
+impl Clock for ManualClock {
+    fn now_ms(&self) -> u128 {
+        u128::from(self.now_ms.load(Ordering::SeqCst))
+    }
+}
+
+/// The clock to use when none is given
+pub fn system_clock() -> Arc<dyn Clock> {
+    Arc::new(SystemClock)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_manual_clock() {
+        let clock = ManualClock::new(1_500);
+        let shared: Arc<dyn Clock> = Arc::new(clock.clone());
+        assert_eq!(shared.now_ms(), 1_500);
+        assert_eq!(shared.now_secs(), 1);
+
+        clock.advance_ms(600);
+        assert_eq!(shared.now_ms(), 2_100);
+        clock.advance_secs(10);
+        assert_eq!(shared.now_secs(), 12);
+        clock.set_ms(0);
+        assert_eq!(shared.now_ms(), 0);
+    }
+}
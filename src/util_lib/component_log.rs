@@ -0,0 +1,207 @@
+// Copyright (C) 2013-2020 Blockstack PBC, a public benefit corporation
+// Copyright (C) 2020 Stacks Open Internet Foundation
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Per-component logging.  Records logged with the `component_*!` macros go through a logger
+//! for their component, which tags them with the component's name and the node's current L2 and
+//! burnchain heights, and drops them if they are below the component's log level, before
+//! handing them to the global logger.  The global logger's own level still applies, so it must
+//! be at least as verbose as the most verbose component.
+
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+
+use slog::{Drain, FnValue, Level, Logger, Never, OwnedKVList, Record};
+use stacks_common::util::log::LOGGER;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LogComponent {
+    Miner,
+    Burnchain,
+    Relayer,
+    Mempool,
+}
+
+impl LogComponent {
+    pub const ALL: [LogComponent; 4] = [
+        LogComponent::Miner,
+        LogComponent::Burnchain,
+        LogComponent::Relayer,
+        LogComponent::Mempool,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match *self {
+            LogComponent::Miner => "miner",
+            LogComponent::Burnchain => "burnchain",
+            LogComponent::Relayer => "relayer",
+            LogComponent::Mempool => "mempool",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<LogComponent> {
+        LogComponent::ALL
+            .iter()
+            .find(|component| component.name() == name)
+            .cloned()
+    }
+
+    fn index(&self) -> usize {
+        *self as usize
+    }
+}
+
+/// Parse a log level name, as used in the node's config
+pub fn parse_level(name: &str) -> Option<Level> {
+    match name.to_lowercase().as_str() {
+        "critical" => Some(Level::Critical),
+        "error" => Some(Level::Error),
+        "warn" | "warning" => Some(Level::Warning),
+        "info" => Some(Level::Info),
+        "debug" => Some(Level::Debug),
+        "trace" => Some(Level::Trace),
+        _ => None,
+    }
+}
+
+static L2_HEIGHT: AtomicU64 = AtomicU64::new(0);
+static BURN_HEIGHT: AtomicU64 = AtomicU64::new(0);
+
+// Every component passes everything through to the global logger until configured otherwise.
+// `Level::Trace.as_usize()` is 6.
+static COMPONENT_LEVELS: [AtomicUsize; 4] = [
+    AtomicUsize::new(6),
+    AtomicUsize::new(6),
+    AtomicUsize::new(6),
+    AtomicUsize::new(6),
+];
+
+pub fn set_component_level(component: LogComponent, level: Level) {
+    COMPONENT_LEVELS[component.index()].store(level.as_usize(), Ordering::SeqCst);
+}
+
+pub fn get_component_level(component: LogComponent) -> Level {
+    Level::from_usize(COMPONENT_LEVELS[component.index()].load(Ordering::SeqCst))
+        .unwrap_or(Level::Trace)
+}
+
+/// Record the height of the node's canonical L2 chain tip, for tagging log records
+pub fn set_l2_height(height: u64) {
+    L2_HEIGHT.store(height, Ordering::SeqCst);
+}
+
+/// Record the height of the node's burnchain tip, for tagging log records
+pub fn set_burn_height(height: u64) {
+    BURN_HEIGHT.store(height, Ordering::SeqCst);
+}
+
+/// Drops records below its component's level, and passes the rest to the global logger
+struct ComponentFilter {
+    component: LogComponent,
+}
+
+impl Drain for ComponentFilter {
+    type Ok = ();
+    type Err = Never;
+
+    fn log(&self, record: &Record, values: &OwnedKVList) -> Result<(), Never> {
+        if record
+            .level()
+            .is_at_least(get_component_level(self.component))
+        {
+            LOGGER.log(record, values)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+fn make_component_logger(component: LogComponent) -> Logger {
+    Logger::root(
+        ComponentFilter { component },
+        o!(
+            "component" => component.name(),
+            "l2_height" => FnValue(|_| L2_HEIGHT.load(Ordering::SeqCst)),
+            "burn_height" => FnValue(|_| BURN_HEIGHT.load(Ordering::SeqCst))
+        ),
+    )
+}
+
+lazy_static! {
+    static ref COMPONENT_LOGGERS: Vec<Logger> = LogComponent::ALL
+        .iter()
+        .map(|component| make_component_logger(*component))
+        .collect();
+}
+
+pub fn component_logger(component: LogComponent) -> &'static Logger {
+    &COMPONENT_LOGGERS[component.index()]
+}
+
+#[macro_export]
+macro_rules! component_trace {
+    ($component:ident, $($arg:tt)*) => ({
+        slog_trace!($crate::util_lib::component_log::component_logger($crate::util_lib::component_log::LogComponent::$component), $($arg)*)
+    })
+}
+
+#[macro_export]
+macro_rules! component_debug {
+    ($component:ident, $($arg:tt)*) => ({
+        slog_debug!($crate::util_lib::component_log::component_logger($crate::util_lib::component_log::LogComponent::$component), $($arg)*)
+    })
+}
+
+#[macro_export]
+macro_rules! component_info {
+    ($component:ident, $($arg:tt)*) => ({
+        slog_info!($crate::util_lib::component_log::component_logger($crate::util_lib::component_log::LogComponent::$component), $($arg)*)
+    })
+}
+
+#[macro_export]
+macro_rules! component_warn {
+    ($component:ident, $($arg:tt)*) => ({
+        slog_warn!($crate::util_lib::component_log::component_logger($crate::util_lib::component_log::LogComponent::$component), $($arg)*)
+    })
+}
+
+#[macro_export]
+macro_rules! component_error {
+    ($component:ident, $($arg:tt)*) => ({
+        slog_error!($crate::util_lib::component_log::component_logger($crate::util_lib::component_log::LogComponent::$component), $($arg)*)
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_component_names_and_levels() {
+        for component in LogComponent::ALL.iter() {
+            assert_eq!(LogComponent::from_name(component.name()), Some(*component));
+        }
+        assert_eq!(LogComponent::from_name("p2p"), None);
+
+        assert_eq!(parse_level("DEBUG"), Some(Level::Debug));
+        assert_eq!(parse_level("warn"), Some(Level::Warning));
+        assert_eq!(parse_level("verbose"), None);
+
+        assert_eq!(get_component_level(LogComponent::Mempool), Level::Trace);
+        set_component_level(LogComponent::Mempool, Level::Warning);
+        assert_eq!(get_component_level(LogComponent::Mempool), Level::Warning);
+        set_component_level(LogComponent::Mempool, Level::Trace);
+    }
+}
@@ -630,6 +630,27 @@ impl<'a, C, T: MarfTrieId> IndexDBConn<'a, C, T> {
     }
 }
 
+/// Read values from the fork index through a shared reference, with either an index connection or
+/// an index transaction.
+pub trait IndexDBRead<T: MarfTrieId> {
+    /// Get a value from the fork index
+    fn read_indexed(&self, header_hash: &T, key: &str) -> Result<Option<String>, Error>;
+}
+
+impl<'a, C, T: MarfTrieId> IndexDBRead<T> for IndexDBConn<'a, C, T> {
+    fn read_indexed(&self, header_hash: &T, key: &str) -> Result<Option<String>, Error> {
+        self.get_indexed(header_hash, key)
+    }
+}
+
+impl<'a, C: Clone, T: MarfTrieId> IndexDBRead<T> for IndexDBTx<'a, C, T> {
+    /// Reads through this transaction's own index, so values written in it are visible.
+    fn read_indexed(&self, header_hash: &T, key: &str) -> Result<Option<String>, Error> {
+        let marf_value = self.index().get_readonly(header_hash, key);
+        load_indexed_value(self.index().sqlite_conn(), header_hash, key, marf_value)
+    }
+}
+
 impl<'a, C, T: MarfTrieId> Deref for IndexDBConn<'a, C, T> {
     type Target = DBConn;
     fn deref(&self) -> &DBConn {
@@ -766,9 +787,20 @@ fn get_indexed<T: MarfTrieId, M: MarfConnection<T>>(
     header_hash: &T,
     key: &str,
 ) -> Result<Option<String>, Error> {
-    match index.get(header_hash, key) {
+    let marf_value = index.get(header_hash, key);
+    load_indexed_value(index.sqlite_conn(), header_hash, key, marf_value)
+}
+
+/// Load the value of an index lookup of `key` off of `header_hash`
+fn load_indexed_value<T: MarfTrieId>(
+    conn: &DBConn,
+    header_hash: &T,
+    key: &str,
+    marf_value: Result<Option<MARFValue>, MARFError>,
+) -> Result<Option<String>, Error> {
+    match marf_value {
         Ok(Some(marf_value)) => {
-            let value = load_indexed(index.sqlite_conn(), &marf_value)?
+            let value = load_indexed(conn, &marf_value)?
                 .expect(&format!("FATAL: corrupt index: key '{}' from {} is present in the index but missing a value in the DB", &key, &header_hash));
             Ok(Some(value))
         }
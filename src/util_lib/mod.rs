@@ -2,6 +2,10 @@
 pub mod db;
 pub mod bloom;
 pub mod boot;
+pub mod clock;
+#[macro_use]
+pub mod component_log;
+pub mod snapshot;
 pub mod strings;
 
 #[cfg(test)]
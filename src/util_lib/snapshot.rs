@@ -0,0 +1,374 @@
+// Copyright (C) 2013-2020 Blockstack PBC, a public benefit corporation
+// Copyright (C) 2020 Stacks Open Internet Foundation
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Snapshots of a node's databases, used to bootstrap a follower without replaying the whole
+//! chain.  A snapshot is a directory holding a copy of the database files under `data/`, and a
+//! `manifest.json` which records the chain the snapshot belongs to and the size and SHA-256 hash
+//! of every file.  The SHA-256 hash of the manifest itself identifies the snapshot, so that a
+//! follower can check it against a hash published out-of-band.
+
+use std::error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::io::{Read, Write};
+use std::path::{Component, Path, PathBuf};
+
+use sha2::{Digest, Sha256};
+use stacks_common::util::hash::to_hex;
+
+pub const SNAPSHOT_MANIFEST_NAME: &str = "manifest.json";
+pub const SNAPSHOT_DATA_DIR: &str = "data";
+pub const SNAPSHOT_VERSION: u32 = 1;
+
+#[derive(Debug)]
+pub enum Error {
+    /// I/O error
+    IOError(io::Error),
+    /// The manifest could not be read or written
+    SerializationError(serde_json::Error),
+    /// The snapshot is malformed, or does not match its manifest
+    InvalidSnapshot(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Error::IOError(ref e) => fmt::Display::fmt(e, f),
+            Error::SerializationError(ref e) => fmt::Display::fmt(e, f),
+            Error::InvalidSnapshot(ref s) => write!(f, "Invalid snapshot: {}", s),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn cause(&self) -> Option<&dyn error::Error> {
+        match *self {
+            Error::IOError(ref e) => Some(e),
+            Error::SerializationError(ref e) => Some(e),
+            Error::InvalidSnapshot(_) => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Error {
+        Error::IOError(e)
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(e: serde_json::Error) -> Error {
+        Error::SerializationError(e)
+    }
+}
+
+/// One file in a snapshot
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SnapshotFile {
+    /// path relative to the snapshotted directory, `/`-separated
+    pub path: String,
+    pub size: u64,
+    /// hex-encoded SHA-256 hash of the file's contents
+    pub sha256: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SnapshotManifest {
+    pub version: u32,
+    pub mainnet: bool,
+    pub chain_id: u32,
+    /// the L1 contract the subnet is anchored to
+    pub subnet_contract: String,
+    /// height and index block hash of the canonical Stacks chain tip when the snapshot was taken
+    pub block_height: u64,
+    pub index_block_hash: String,
+    pub files: Vec<SnapshotFile>,
+}
+
+impl SnapshotManifest {
+    pub fn new(
+        mainnet: bool,
+        chain_id: u32,
+        subnet_contract: String,
+        block_height: u64,
+        index_block_hash: String,
+    ) -> SnapshotManifest {
+        SnapshotManifest {
+            version: SNAPSHOT_VERSION,
+            mainnet,
+            chain_id,
+            subnet_contract,
+            block_height,
+            index_block_hash,
+            files: vec![],
+        }
+    }
+}
+
+/// Copy `src` to `dest`, returning the number of bytes copied and their hex-encoded SHA-256
+/// hash.
+fn copy_and_hash(src: &Path, dest: &Path) -> Result<(u64, String), Error> {
+    if let Some(parent) = dest.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut reader = fs::File::open(src)?;
+    let mut writer = fs::File::create(dest)?;
+    let mut hasher = Sha256::new();
+    let mut buf = vec![0u8; 1 << 20];
+    let mut size = 0u64;
+    loop {
+        let nread = reader.read(&mut buf)?;
+        if nread == 0 {
+            break;
+        }
+        hasher.update(&buf[0..nread]);
+        writer.write_all(&buf[0..nread])?;
+        size += nread as u64;
+    }
+    writer.sync_all()?;
+    Ok((size, to_hex(&hasher.finalize())))
+}
+
+/// Get the size and hex-encoded SHA-256 hash of the file at `path`.
+pub fn hash_file(path: &Path) -> Result<(u64, String), Error> {
+    let mut reader = fs::File::open(path)?;
+    let mut hasher = Sha256::new();
+    let mut buf = vec![0u8; 1 << 20];
+    let mut size = 0u64;
+    loop {
+        let nread = reader.read(&mut buf)?;
+        if nread == 0 {
+            break;
+        }
+        hasher.update(&buf[0..nread]);
+        size += nread as u64;
+    }
+    Ok((size, to_hex(&hasher.finalize())))
+}
+
+/// List the files under `root`/`dir`, as paths relative to `root`, skipping any path whose
+/// `/`-separated form starts with one of `excluded` (so that excluding a database also excludes
+/// its `-wal` and `-shm` files).
+fn list_files(
+    root: &Path,
+    dir: &Path,
+    excluded: &[&str],
+    files: &mut Vec<PathBuf>,
+) -> Result<(), Error> {
+    for entry in fs::read_dir(root.join(dir))? {
+        let entry = entry?;
+        let rel_path = dir.join(entry.file_name());
+        let manifest_path = manifest_path_string(&rel_path);
+        if excluded
+            .iter()
+            .any(|prefix| manifest_path.starts_with(prefix))
+        {
+            continue;
+        }
+        if entry.file_type()?.is_dir() {
+            list_files(root, &rel_path, excluded, files)?;
+        } else {
+            files.push(rel_path);
+        }
+    }
+    Ok(())
+}
+
+fn manifest_path_string(path: &Path) -> String {
+    path.components()
+        .map(|c| c.as_os_str().to_string_lossy().to_string())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Turn a manifest path back into a relative path, refusing anything which could escape the
+/// directory it is joined to.
+fn manifest_path_to_relative(path: &str) -> Result<PathBuf, Error> {
+    let rel_path: PathBuf = path.split('/').collect();
+    if path.is_empty()
+        || rel_path
+            .components()
+            .any(|c| !matches!(c, Component::Normal(_)))
+    {
+        return Err(Error::InvalidSnapshot(format!(
+            "file path `{}` is not a plain relative path",
+            path
+        )));
+    }
+    Ok(rel_path)
+}
+
+/// Copy the directories `dirs` under `src_root`, minus the paths in `excluded` (both relative to
+/// `src_root`), into a new snapshot at `dest`, and write its manifest.  The databases must not be
+/// in use.  Returns the manifest and its hex-encoded SHA-256 hash.
+pub fn create_snapshot(
+    src_root: &Path,
+    dirs: &[&str],
+    excluded: &[&str],
+    dest: &Path,
+    mut manifest: SnapshotManifest,
+) -> Result<(SnapshotManifest, String), Error> {
+    if dest.exists() {
+        return Err(Error::InvalidSnapshot(format!(
+            "{} already exists",
+            dest.display()
+        )));
+    }
+
+    let mut rel_paths = vec![];
+    for dir in dirs.iter() {
+        list_files(src_root, Path::new(dir), excluded, &mut rel_paths)?;
+    }
+    rel_paths.sort();
+
+    let data_dir = dest.join(SNAPSHOT_DATA_DIR);
+    fs::create_dir_all(&data_dir)?;
+    manifest.files.clear();
+    for rel_path in rel_paths.iter() {
+        let (size, sha256) = copy_and_hash(&src_root.join(rel_path), &data_dir.join(rel_path))?;
+        debug!("Snapshotted {} ({} bytes)", rel_path.display(), size);
+        manifest.files.push(SnapshotFile {
+            path: manifest_path_string(rel_path),
+            size,
+            sha256,
+        });
+    }
+
+    let manifest_bytes = serde_json::to_vec_pretty(&manifest)?;
+    fs::write(dest.join(SNAPSHOT_MANIFEST_NAME), &manifest_bytes)?;
+    let mut hasher = Sha256::new();
+    hasher.update(&manifest_bytes);
+    Ok((manifest, to_hex(&hasher.finalize())))
+}
+
+/// Read the manifest of the snapshot at `snapshot_dir`.  Returns the manifest and its
+/// hex-encoded SHA-256 hash.
+pub fn read_manifest(snapshot_dir: &Path) -> Result<(SnapshotManifest, String), Error> {
+    let manifest_bytes = fs::read(snapshot_dir.join(SNAPSHOT_MANIFEST_NAME))?;
+    let manifest: SnapshotManifest = serde_json::from_slice(&manifest_bytes)?;
+    if manifest.version != SNAPSHOT_VERSION {
+        return Err(Error::InvalidSnapshot(format!(
+            "unsupported snapshot version {}",
+            manifest.version
+        )));
+    }
+    let mut hasher = Sha256::new();
+    hasher.update(&manifest_bytes);
+    Ok((manifest, to_hex(&hasher.finalize())))
+}
+
+/// Copy the files of the snapshot at `snapshot_dir` into `dest_root`, checking each one against
+/// `manifest`.  Fails on the first file whose size or hash does not match; the files copied so
+/// far are left in place.
+pub fn restore_snapshot(
+    snapshot_dir: &Path,
+    manifest: &SnapshotManifest,
+    dest_root: &Path,
+) -> Result<(), Error> {
+    let data_dir = snapshot_dir.join(SNAPSHOT_DATA_DIR);
+    for file in manifest.files.iter() {
+        let rel_path = manifest_path_to_relative(&file.path)?;
+        let (size, sha256) = copy_and_hash(&data_dir.join(&rel_path), &dest_root.join(&rel_path))?;
+        if size != file.size || sha256 != file.sha256 {
+            return Err(Error::InvalidSnapshot(format!(
+                "{} has size {} and hash {}, but the manifest expects {} and {}",
+                &file.path, size, &sha256, file.size, &file.sha256
+            )));
+        }
+        debug!("Restored {} ({} bytes)", &file.path, size);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn test_dir(name: &str) -> PathBuf {
+        let path = PathBuf::from(format!("/tmp/stacks-node-tests/snapshot/{}", name));
+        if path.exists() {
+            fs::remove_dir_all(&path).unwrap();
+        }
+        path
+    }
+
+    #[test]
+    fn test_snapshot_roundtrip() {
+        let root = test_dir("test_snapshot_roundtrip");
+        let src = root.join("src");
+        fs::create_dir_all(src.join("chainstate/vm/clarity")).unwrap();
+        fs::create_dir_all(src.join("chainstate/estimates")).unwrap();
+        fs::create_dir_all(src.join("burnchain")).unwrap();
+        fs::write(src.join("chainstate/vm/clarity/marf.sqlite"), b"marf").unwrap();
+        fs::write(src.join("chainstate/mempool.sqlite"), b"mempool").unwrap();
+        fs::write(src.join("chainstate/mempool.sqlite-wal"), b"mempool").unwrap();
+        fs::write(src.join("chainstate/estimates/fee.sqlite"), b"fees").unwrap();
+        fs::write(src.join("burnchain/sortition"), b"sortition").unwrap();
+        fs::write(src.join("peer.sqlite"), b"peer").unwrap();
+
+        let manifest =
+            SnapshotManifest::new(false, 0x80000000, "ST000.subnet".into(), 10, "00".into());
+        let snapshot = root.join("snapshot");
+        let (manifest, manifest_hash) = create_snapshot(
+            &src,
+            &["chainstate", "burnchain"],
+            &["chainstate/mempool.sqlite", "chainstate/estimates"],
+            &snapshot,
+            manifest,
+        )
+        .unwrap();
+        let paths: Vec<_> = manifest.files.iter().map(|f| f.path.as_str()).collect();
+        assert_eq!(
+            paths,
+            vec!["burnchain/sortition", "chainstate/vm/clarity/marf.sqlite"]
+        );
+
+        // a snapshot can't be written over an existing one
+        assert!(create_snapshot(&src, &["chainstate"], &[], &snapshot, manifest.clone()).is_err());
+
+        let (snapshot_manifest, snapshot_hash) = read_manifest(&snapshot).unwrap();
+        assert_eq!(snapshot_manifest, manifest);
+        assert_eq!(snapshot_hash, manifest_hash);
+
+        let dest = root.join("dest");
+        restore_snapshot(&snapshot, &snapshot_manifest, &dest).unwrap();
+        assert_eq!(
+            fs::read(dest.join("burnchain/sortition")).unwrap(),
+            b"sortition"
+        );
+        assert_eq!(
+            fs::read(dest.join("chainstate/vm/clarity/marf.sqlite")).unwrap(),
+            b"marf"
+        );
+        assert!(!dest.join("chainstate/mempool.sqlite").exists());
+
+        // tampered data is caught
+        fs::write(snapshot.join("data/burnchain/sortition"), b"tampered").unwrap();
+        match restore_snapshot(&snapshot, &snapshot_manifest, &root.join("dest2")) {
+            Err(Error::InvalidSnapshot(msg)) => assert!(msg.contains("burnchain/sortition")),
+            res => panic!("Expected a hash mismatch, got {:?}", res),
+        }
+
+        // as are paths which escape the destination
+        let mut bad_manifest = snapshot_manifest.clone();
+        bad_manifest.files[0].path = "../escape".into();
+        assert!(matches!(
+            restore_snapshot(&snapshot, &bad_manifest, &root.join("dest3")),
+            Err(Error::InvalidSnapshot(_))
+        ));
+    }
+}
@@ -0,0 +1,171 @@
+//! A subscription to the node's websocket event stream, served at its `[node] ws_bind`.
+
+use std::net::TcpStream;
+
+use tungstenite::stream::MaybeTlsStream;
+use tungstenite::{Message, WebSocket};
+
+use crate::Error;
+
+/// The kinds of event the stream carries
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum EventTopic {
+    NewBlock,
+    Microblock,
+    MempoolTxReceived,
+    MempoolTxsDropped,
+}
+
+impl EventTopic {
+    pub const ALL: [EventTopic; 4] = [
+        EventTopic::NewBlock,
+        EventTopic::Microblock,
+        EventTopic::MempoolTxReceived,
+        EventTopic::MempoolTxsDropped,
+    ];
+
+    pub fn from_name(name: &str) -> Option<EventTopic> {
+        EventTopic::ALL
+            .iter()
+            .find(|topic| topic.as_str() == name)
+            .copied()
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EventTopic::NewBlock => "new_block",
+            EventTopic::Microblock => "microblock",
+            EventTopic::MempoolTxReceived => "mempool_tx_received",
+            EventTopic::MempoolTxsDropped => "mempool_txs_dropped",
+        }
+    }
+}
+
+/// One event from the stream.  `payload` is the same JSON the node POSTs to its event
+/// observers for this topic.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StreamEvent {
+    pub topic: EventTopic,
+    pub payload: serde_json::Value,
+}
+
+/// A message the node sends on the stream: an event, the reply to a subscription request, or an
+/// error
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum StreamMessage {
+    Event {
+        topic: String,
+        payload: serde_json::Value,
+    },
+    Subscribed {
+        subscribed: Vec<String>,
+    },
+    Error {
+        error: String,
+    },
+}
+
+impl StreamMessage {
+    fn parse(text: &str) -> Result<StreamMessage, Error> {
+        serde_json::from_str(text).map_err(|e| {
+            Error::InvalidResponse(format!("Invalid event stream message {}: {}", text, e))
+        })
+    }
+}
+
+/// A connection to the node's event stream
+pub struct EventSubscription {
+    socket: WebSocket<MaybeTlsStream<TcpStream>>,
+}
+
+impl EventSubscription {
+    /// Connect to the event stream at `ws_url`, e.g. `ws://127.0.0.1:30445`, and subscribe to
+    /// `topics`
+    pub fn connect(ws_url: &str, topics: &[EventTopic]) -> Result<EventSubscription, Error> {
+        let (socket, _response) = tungstenite::connect(ws_url)?;
+        let mut subscription = EventSubscription { socket };
+        subscription.subscribe(topics)?;
+        Ok(subscription)
+    }
+
+    /// Subscribe to `topics`, and get every topic this connection is now subscribed to
+    pub fn subscribe(&mut self, topics: &[EventTopic]) -> Result<Vec<EventTopic>, Error> {
+        self.change_subscriptions(topics, &[])
+    }
+
+    /// Unsubscribe from `topics`, and get every topic this connection is still subscribed to
+    pub fn unsubscribe(&mut self, topics: &[EventTopic]) -> Result<Vec<EventTopic>, Error> {
+        self.change_subscriptions(&[], topics)
+    }
+
+    fn change_subscriptions(
+        &mut self,
+        subscribe: &[EventTopic],
+        unsubscribe: &[EventTopic],
+    ) -> Result<Vec<EventTopic>, Error> {
+        let names = |topics: &[EventTopic]| -> Vec<&str> {
+            topics.iter().map(|topic| topic.as_str()).collect()
+        };
+        let request = json!({
+            "subscribe": names(subscribe),
+            "unsubscribe": names(unsubscribe),
+        });
+        self.socket
+            .write_message(Message::Text(request.to_string()))?;
+
+        // events already in flight may arrive before the reply; they belong to the old
+        // subscriptions, so they are dropped
+        loop {
+            match StreamMessage::parse(&self.read_text()?)? {
+                StreamMessage::Subscribed { subscribed } => {
+                    return subscribed
+                        .iter()
+                        .map(|name| {
+                            EventTopic::from_name(name).ok_or_else(|| {
+                                Error::InvalidResponse(format!("Unknown topic: {}", name))
+                            })
+                        })
+                        .collect();
+                }
+                StreamMessage::Error { error } => return Err(Error::Stream(error)),
+                StreamMessage::Event { .. } => continue,
+            }
+        }
+    }
+
+    /// Wait for the next event.  If the client fell too far behind the stream, this is
+    /// `Error::Stream`, and the events it missed are lost; the subscription can still be read.
+    pub fn next_event(&mut self) -> Result<StreamEvent, Error> {
+        match StreamMessage::parse(&self.read_text()?)? {
+            StreamMessage::Event { topic, payload } => {
+                let topic = EventTopic::from_name(&topic)
+                    .ok_or_else(|| Error::InvalidResponse(format!("Unknown topic: {}", topic)))?;
+                Ok(StreamEvent { topic, payload })
+            }
+            StreamMessage::Error { error } => Err(Error::Stream(error)),
+            StreamMessage::Subscribed { .. } => Err(Error::InvalidResponse(
+                "Unexpected subscription reply".to_string(),
+            )),
+        }
+    }
+
+    /// Read the next text message, skipping pings and other control messages
+    fn read_text(&mut self) -> Result<String, Error> {
+        loop {
+            match self.socket.read_message()? {
+                Message::Text(text) => return Ok(text),
+                Message::Close(_) => {
+                    return Err(Error::WebSocket(tungstenite::Error::ConnectionClosed))
+                }
+                _ => continue,
+            }
+        }
+    }
+
+    /// Close the connection
+    pub fn close(mut self) -> Result<(), Error> {
+        self.socket.close(None)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,616 @@
+//! A typed client for a subnet node's RPC interface.
+//!
+//! `SubnetRpcClient` calls the node's HTTP endpoints with the same request and response types
+//! the node serves them with, so the two cannot drift apart: the node's own integration tests
+//! talk to it through this crate.  `EventSubscription` receives the node's websocket event
+//! stream, and `NonceRescue` unsticks an account's stranded transactions.
+
+extern crate reqwest;
+extern crate serde;
+#[macro_use]
+extern crate serde_derive;
+#[macro_use]
+extern crate serde_json;
+extern crate stacks;
+extern crate tungstenite;
+
+use std::error;
+use std::fmt;
+
+use reqwest::blocking::{Client, Response};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use stacks::burnchains::Txid;
+use stacks::chainstate::stacks::{StacksPublicKey, StacksTransaction, TransactionVersion};
+use stacks::codec::StacksMessageCodec;
+use stacks::core::mempool_stats::MemPoolStats;
+use stacks::net::response_signing::RPCResponseSignature;
+use stacks::net::{
+    AccountEntryResponse, AccountNextNonceResponse, CallReadOnlyRequestBody, CallReadOnlyResponse,
+    ContractSrcResponse, DataVarResponse, FailedDepositsResponse, MapEntryResponse,
+    RPCPeerInfoData, RPCPoxInfoData, RejectedTxsResponse, SubnetInfoResponse, TipRequest,
+    WithdrawalClaimResponse, WithdrawalResponse, WithdrawalsResponse,
+};
+use stacks::types::chainstate::StacksAddress;
+use stacks::util::secp256k1::Secp256k1PublicKey;
+use stacks::vm::types::{PrincipalData, QualifiedContractIdentifier};
+use stacks::vm::Value as ClarityValue;
+
+pub mod events;
+pub mod nonce_rescue;
+
+#[cfg(test)]
+mod tests;
+
+pub use crate::events::{EventSubscription, EventTopic, StreamEvent};
+pub use crate::nonce_rescue::NonceRescue;
+
+#[derive(Debug)]
+pub enum Error {
+    /// The node could not be reached, or its response could not be read or decoded
+    Http(reqwest::Error),
+    /// The node answered with an error status, e.g. 400 for a rejected transaction.  `body` is
+    /// the node's explanation.
+    Status { status: u16, body: String },
+    /// The node's response decoded, but did not hold what the endpoint returns
+    InvalidResponse(String),
+    /// The event stream's websocket failed
+    WebSocket(tungstenite::Error),
+    /// The node reported an error on the event stream, e.g. that the client fell behind
+    Stream(String),
+    /// A signed response was unsigned, signed by another key or for another chain, or its
+    /// signature did not match
+    Signature(String),
+    /// A transaction the client builds could not be made, or its signature did not verify
+    InvalidTransaction(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Http(e) => fmt::Display::fmt(e, f),
+            Error::Status { status, body } => write!(f, "HTTP {}: {}", status, body),
+            Error::InvalidResponse(msg) => write!(f, "Invalid response: {}", msg),
+            Error::WebSocket(e) => fmt::Display::fmt(e, f),
+            Error::Stream(msg) => write!(f, "Event stream error: {}", msg),
+            Error::Signature(msg) => write!(f, "Bad response signature: {}", msg),
+            Error::InvalidTransaction(msg) => write!(f, "Invalid transaction: {}", msg),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Error::Http(e) => Some(e),
+            Error::WebSocket(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<reqwest::Error> for Error {
+    fn from(e: reqwest::Error) -> Error {
+        Error::Http(e)
+    }
+}
+
+impl From<tungstenite::Error> for Error {
+    fn from(e: tungstenite::Error) -> Error {
+        Error::WebSocket(e)
+    }
+}
+
+/// An account's STX, as reported by `/v2/accounts`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Account {
+    pub balance: u128,
+    pub locked: u128,
+    pub unlock_height: u64,
+    pub nonce: u64,
+}
+
+impl Account {
+    pub fn from_response(response: &AccountEntryResponse) -> Result<Account, Error> {
+        Ok(Account {
+            balance: parse_hex_u128(&response.balance)?,
+            locked: parse_hex_u128(&response.locked)?,
+            unlock_height: response.unlock_height,
+            nonce: response.nonce,
+        })
+    }
+}
+
+/// Parse a `0x`-prefixed, big-endian hex integer, as `/v2/accounts` reports balances
+fn parse_hex_u128(hex: &str) -> Result<u128, Error> {
+    let digits = hex.strip_prefix("0x").unwrap_or(hex);
+    u128::from_str_radix(digits, 16)
+        .map_err(|_e| Error::InvalidResponse(format!("Invalid hex integer: {}", hex)))
+}
+
+/// Deserialize a hex-encoded Clarity value from a response
+pub fn parse_clarity_value(hex: &str) -> Result<ClarityValue, Error> {
+    ClarityValue::try_deserialize_hex_untyped(hex)
+        .map_err(|e| Error::InvalidResponse(format!("Invalid Clarity value {}: {:?}", hex, e)))
+}
+
+/// The querystring selecting the block a read is made against, without MARF proofs
+fn tip_query(tip: &TipRequest) -> String {
+    match tip {
+        TipRequest::UseLatestUnconfirmedTip => "?tip=latest&proof=0".to_string(),
+        TipRequest::UseLatestAnchoredTip => "?tip=anchored&proof=0".to_string(),
+        TipRequest::SpecificTip(tip) => format!("?tip={}&proof=0", tip),
+    }
+}
+
+/// The path segments naming a contract, `<address>/<name>`
+fn contract_path(contract: &QualifiedContractIdentifier) -> String {
+    format!(
+        "{}/{}",
+        StacksAddress::from(contract.issuer.clone()),
+        contract.name.as_str()
+    )
+}
+
+/// The querystring of a paged endpoint
+fn page_query(offset: Option<u64>, limit: Option<u64>) -> String {
+    let mut params = vec![];
+    if let Some(offset) = offset {
+        params.push(format!("offset={}", offset));
+    }
+    if let Some(limit) = limit {
+        params.push(format!("limit={}", limit));
+    }
+    if params.is_empty() {
+        "".to_string()
+    } else {
+        format!("?{}", params.join("&"))
+    }
+}
+
+/// A response, with the signature the node sent it with, if any.  See `docs/rpc-endpoints.md`
+/// for which responses a node signs, and `SubnetRpcClient::get_signed`.
+#[derive(Debug, Clone)]
+pub struct SignedResponse<T> {
+    pub response: T,
+    pub signature: Option<RPCResponseSignature>,
+    request_path: String,
+    body: Vec<u8>,
+}
+
+impl<T> SignedResponse<T> {
+    /// Check that the response was signed by `node_public_key`, for the subnet `chain_id`, and
+    /// return it if so
+    pub fn verify(&self, node_public_key: &Secp256k1PublicKey, chain_id: u32) -> Result<&T, Error> {
+        let signature = self
+            .signature
+            .as_ref()
+            .ok_or_else(|| Error::Signature("response is not signed".to_string()))?;
+        if signature.node_public_key != *node_public_key {
+            return Err(Error::Signature(format!(
+                "response is signed by {}, not {}",
+                signature.node_public_key.to_hex(),
+                node_public_key.to_hex()
+            )));
+        }
+        if signature.chain_id != chain_id {
+            return Err(Error::Signature(format!(
+                "response is signed for chain {}, not {}",
+                signature.chain_id, chain_id
+            )));
+        }
+        signature
+            .verify(&self.request_path, &self.body)
+            .map_err(Error::Signature)?;
+        Ok(&self.response)
+    }
+}
+
+/// A blocking client for one node's RPC interface
+#[derive(Debug, Clone)]
+pub struct SubnetRpcClient {
+    http_origin: String,
+    client: Client,
+}
+
+impl SubnetRpcClient {
+    /// Make a client for the node whose RPC interface is at `http_origin`, e.g.
+    /// `http://127.0.0.1:20443`
+    pub fn new(http_origin: &str) -> SubnetRpcClient {
+        SubnetRpcClient::with_client(http_origin, Client::new())
+    }
+
+    /// Make a client which sends its requests with `client`, e.g. to set timeouts
+    pub fn with_client(http_origin: &str, client: Client) -> SubnetRpcClient {
+        SubnetRpcClient {
+            http_origin: http_origin.trim_end_matches('/').to_string(),
+            client,
+        }
+    }
+
+    pub fn http_origin(&self) -> &str {
+        &self.http_origin
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!("{}{}", &self.http_origin, path)
+    }
+
+    /// Decode a successful response's JSON, or report the node's error
+    fn decode<T: DeserializeOwned>(response: Response) -> Result<T, Error> {
+        let status = response.status();
+        if !status.is_success() {
+            return Err(Error::Status {
+                status: status.as_u16(),
+                body: response.text()?,
+            });
+        }
+        Ok(response.json()?)
+    }
+
+    fn get<T: DeserializeOwned>(&self, path: &str) -> Result<T, Error> {
+        let response = self.client.get(&self.url(path)).send()?;
+        SubnetRpcClient::decode(response)
+    }
+
+    /// `GET` the endpoint at `path`, keeping the signature of its response if the node signed
+    /// it, e.g. `get_signed::<WithdrawalClaimResponse>("/v2/withdrawal/claim/10/0")`.  The
+    /// signature is not checked: call `SignedResponse::verify` with the key to trust.
+    pub fn get_signed<T: DeserializeOwned>(&self, path: &str) -> Result<SignedResponse<T>, Error> {
+        let response = self.client.get(&self.url(path)).send()?;
+        let status = response.status();
+        if !status.is_success() {
+            return Err(Error::Status {
+                status: status.as_u16(),
+                body: response.text()?,
+            });
+        }
+        let signature = RPCResponseSignature::from_headers(|name| {
+            response
+                .headers()
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+        })
+        .map_err(Error::Signature)?;
+        let body = response.bytes()?.to_vec();
+        let decoded = serde_json::from_slice(&body)
+            .map_err(|e| Error::InvalidResponse(format!("Invalid JSON: {}", e)))?;
+        Ok(SignedResponse {
+            response: decoded,
+            signature,
+            request_path: path.to_string(),
+            body,
+        })
+    }
+
+    fn post_json<B: Serialize, T: DeserializeOwned>(
+        &self,
+        path: &str,
+        body: &B,
+    ) -> Result<T, Error> {
+        let response = self.client.post(&self.url(path)).json(body).send()?;
+        SubnetRpcClient::decode(response)
+    }
+
+    /// `GET /v2/info`
+    pub fn get_info(&self) -> Result<RPCPeerInfoData, Error> {
+        self.get("/v2/info")
+    }
+
+    /// `GET /v2/pox`
+    pub fn get_pox_info(&self) -> Result<RPCPoxInfoData, Error> {
+        self.get("/v2/pox")
+    }
+
+    /// `GET /v2/hyperchain/info`: the subnet the node follows
+    pub fn get_subnet_info(&self) -> Result<SubnetInfoResponse, Error> {
+        self.get("/v2/hyperchain/info")
+    }
+
+    /// `GET /v2/accounts/[Principal]`
+    pub fn get_account(
+        &self,
+        principal: &PrincipalData,
+        tip: &TipRequest,
+    ) -> Result<Account, Error> {
+        let response: AccountEntryResponse =
+            self.get(&format!("/v2/accounts/{}{}", principal, tip_query(tip)))?;
+        Account::from_response(&response)
+    }
+
+    /// `GET /v2/accounts/[Stacks Address]/next_nonce`: the nonce `address`'s next transaction
+    /// should use, and any nonces its pending transactions are stuck behind
+    pub fn get_next_nonce(
+        &self,
+        address: &StacksAddress,
+        tip: &TipRequest,
+    ) -> Result<AccountNextNonceResponse, Error> {
+        self.get(&format!(
+            "/v2/accounts/{}/next_nonce{}",
+            address,
+            tip_query(tip)
+        ))
+    }
+
+    /// If the account whose key is `origin` has pending transactions stuck behind a missing
+    /// nonce, build an unsigned rescue for the lowest one, paying `fee` and sending
+    /// `nonce_rescue::RESCUE_TRANSFER_AMOUNT` to `recipient`.  Returns `None` if nothing is stuck.
+    pub fn make_nonce_rescue(
+        &self,
+        version: TransactionVersion,
+        chain_id: u32,
+        origin: &StacksPublicKey,
+        fee: u64,
+        recipient: PrincipalData,
+    ) -> Result<Option<NonceRescue>, Error> {
+        let address = nonce_rescue::origin_address(version, origin)?;
+        let next_nonce = self.get_next_nonce(&address, &TipRequest::UseLatestUnconfirmedTip)?;
+        match next_nonce.missing_nonces.first() {
+            Some(gap) => {
+                NonceRescue::new(version, chain_id, origin, gap.start, fee, recipient).map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// `GET /v2/data_var/[Stacks Address]/[Contract Name]/[Var Name]`
+    pub fn get_data_var(
+        &self,
+        contract: &QualifiedContractIdentifier,
+        var_name: &str,
+        tip: &TipRequest,
+    ) -> Result<ClarityValue, Error> {
+        let response: DataVarResponse = self.get(&format!(
+            "/v2/data_var/{}/{}{}",
+            contract_path(contract),
+            var_name,
+            tip_query(tip)
+        ))?;
+        parse_clarity_value(&response.data)
+    }
+
+    /// `POST /v2/map_entry/[Stacks Address]/[Contract Name]/[Map Name]`.  The entry is returned
+    /// as a Clarity optional, which is `none` if the map has no entry for `key`.
+    pub fn get_map_entry(
+        &self,
+        contract: &QualifiedContractIdentifier,
+        map_name: &str,
+        key: &ClarityValue,
+        tip: &TipRequest,
+    ) -> Result<ClarityValue, Error> {
+        let response: MapEntryResponse = self.post_json(
+            &format!(
+                "/v2/map_entry/{}/{}{}",
+                contract_path(contract),
+                map_name,
+                tip_query(tip)
+            ),
+            &format!("0x{}", key.serialize_to_hex()),
+        )?;
+        parse_clarity_value(&response.data)
+    }
+
+    /// `GET /v2/contracts/source/[Stacks Address]/[Contract Name]`
+    pub fn get_contract_source(
+        &self,
+        contract: &QualifiedContractIdentifier,
+        tip: &TipRequest,
+    ) -> Result<ContractSrcResponse, Error> {
+        self.get(&format!(
+            "/v2/contracts/source/{}{}",
+            contract_path(contract),
+            tip_query(tip)
+        ))
+    }
+
+    /// `POST /v2/contracts/call-read/[Stacks Address]/[Contract Name]/[Function Name]`.  A
+    /// call which fails in the VM is still answered: its `okay` is false, and its `cause` says
+    /// why.
+    pub fn call_read_only(
+        &self,
+        contract: &QualifiedContractIdentifier,
+        function_name: &str,
+        sender: &PrincipalData,
+        args: &[ClarityValue],
+        tip: &TipRequest,
+    ) -> Result<CallReadOnlyResponse, Error> {
+        let body = CallReadOnlyRequestBody {
+            sender: sender.to_string(),
+            arguments: args.iter().map(|arg| arg.serialize_to_hex()).collect(),
+        };
+        self.post_json(
+            &format!(
+                "/v2/contracts/call-read/{}/{}{}",
+                contract_path(contract),
+                function_name,
+                tip_query(tip)
+            ),
+            &body,
+        )
+    }
+
+    /// Call a read-only function, and get the value it returned.  A call which fails in the VM
+    /// is reported as `Error::InvalidResponse`, with its cause.
+    pub fn call_read_only_value(
+        &self,
+        contract: &QualifiedContractIdentifier,
+        function_name: &str,
+        sender: &PrincipalData,
+        args: &[ClarityValue],
+        tip: &TipRequest,
+    ) -> Result<ClarityValue, Error> {
+        let response = self.call_read_only(contract, function_name, sender, args, tip)?;
+        match (response.okay, response.result) {
+            (true, Some(result)) => parse_clarity_value(&result),
+            _ => Err(Error::InvalidResponse(format!(
+                "Call to {}::{} failed: {}",
+                contract,
+                function_name,
+                response.cause.unwrap_or_default()
+            ))),
+        }
+    }
+
+    /// `POST /v2/transactions` with a serialized transaction.  A rejected transaction is
+    /// reported as `Error::Status`, with the node's JSON explanation as its body.
+    pub fn submit_raw_transaction(&self, tx_bytes: &[u8]) -> Result<Txid, Error> {
+        let response = self
+            .client
+            .post(&self.url("/v2/transactions"))
+            .header("Content-Type", "application/octet-stream")
+            .body(tx_bytes.to_vec())
+            .send()?;
+        let txid: String = SubnetRpcClient::decode(response)?;
+        Txid::from_hex(&txid)
+            .map_err(|_e| Error::InvalidResponse(format!("Invalid txid: {}", txid)))
+    }
+
+    /// `POST /v2/transactions`
+    pub fn submit_transaction(&self, tx: &StacksTransaction) -> Result<Txid, Error> {
+        self.submit_raw_transaction(&tx.serialize_to_vec())
+    }
+
+    /// `GET /v2/withdrawal/stx/[Block Height]/[Withdrawer]/[Withdrawal ID]/[Amount]`
+    pub fn get_stx_withdrawal(
+        &self,
+        block_height: u64,
+        sender: &PrincipalData,
+        withdrawal_id: u32,
+        amount: u128,
+    ) -> Result<WithdrawalResponse, Error> {
+        self.get(&format!(
+            "/v2/withdrawal/stx/{}/{}/{}/{}",
+            block_height, sender, withdrawal_id, amount
+        ))
+    }
+
+    /// `GET /v2/withdrawal/ft/[Block Height]/[Withdrawer]/[Withdrawal ID]/[Contract Address]/[Contract Name]/[Amount]`
+    pub fn get_ft_withdrawal(
+        &self,
+        block_height: u64,
+        sender: &PrincipalData,
+        withdrawal_id: u32,
+        contract: &QualifiedContractIdentifier,
+        amount: u128,
+    ) -> Result<WithdrawalResponse, Error> {
+        self.get(&format!(
+            "/v2/withdrawal/ft/{}/{}/{}/{}/{}",
+            block_height,
+            sender,
+            withdrawal_id,
+            contract_path(contract),
+            amount
+        ))
+    }
+
+    /// `GET /v2/withdrawal/nft/[Block Height]/[Withdrawer]/[Withdrawal ID]/[Contract Address]/[Contract Name]/[Asset ID]`
+    pub fn get_nft_withdrawal(
+        &self,
+        block_height: u64,
+        sender: &PrincipalData,
+        withdrawal_id: u32,
+        contract: &QualifiedContractIdentifier,
+        asset_id: u128,
+    ) -> Result<WithdrawalResponse, Error> {
+        self.get(&format!(
+            "/v2/withdrawal/nft/{}/{}/{}/{}/{}",
+            block_height,
+            sender,
+            withdrawal_id,
+            contract_path(contract),
+            asset_id
+        ))
+    }
+
+    /// `GET /v2/withdrawal/stx-transfer/...`: an STX transfer to the subnet whose L1 contract
+    /// is `destination`
+    pub fn get_stx_transfer_withdrawal(
+        &self,
+        block_height: u64,
+        sender: &PrincipalData,
+        withdrawal_id: u32,
+        amount: u128,
+        recipient: &PrincipalData,
+        destination: &QualifiedContractIdentifier,
+    ) -> Result<WithdrawalResponse, Error> {
+        self.get(&format!(
+            "/v2/withdrawal/stx-transfer/{}/{}/{}/{}/{}/{}",
+            block_height,
+            sender,
+            withdrawal_id,
+            amount,
+            recipient,
+            contract_path(destination)
+        ))
+    }
+
+    /// `GET /v2/hyperchain/withdrawals/[Principal]`: a page of `principal`'s withdrawals
+    pub fn get_withdrawals(
+        &self,
+        principal: &PrincipalData,
+        offset: Option<u64>,
+        limit: Option<u64>,
+    ) -> Result<WithdrawalsResponse, Error> {
+        self.get(&format!(
+            "/v2/hyperchain/withdrawals/{}{}",
+            principal,
+            page_query(offset, limit)
+        ))
+    }
+
+    /// `GET /v2/withdrawal/claim/[Block Height]/[Withdrawal ID]`
+    pub fn get_withdrawal_claim(
+        &self,
+        block_height: u64,
+        withdrawal_id: u32,
+    ) -> Result<WithdrawalClaimResponse, Error> {
+        self.get(&format!(
+            "/v2/withdrawal/claim/{}/{}",
+            block_height, withdrawal_id
+        ))
+    }
+
+    /// `GET /v2/hyperchain/failed_deposits/[Principal]`: a page of `principal`'s failed
+    /// deposits
+    pub fn get_failed_deposits(
+        &self,
+        principal: &PrincipalData,
+        offset: Option<u64>,
+        limit: Option<u64>,
+    ) -> Result<FailedDepositsResponse, Error> {
+        self.get(&format!(
+            "/v2/hyperchain/failed_deposits/{}{}",
+            principal,
+            page_query(offset, limit)
+        ))
+    }
+
+    /// `GET /v2/mempool/rejected?txid=`: why the node's mempool rejected `txid`, if it keeps
+    /// rejected transactions and still has this one
+    pub fn get_rejected_tx(&self, txid: &Txid) -> Result<RejectedTxsResponse, Error> {
+        self.get(&format!("/v2/mempool/rejected?txid={}", txid))
+    }
+
+    /// `GET /v2/mempool/rejected?address=`: the node's kept rejections of the transactions
+    /// `address` sent or sponsored, newest first
+    pub fn get_rejected_txs_by_address(
+        &self,
+        address: &StacksAddress,
+        limit: Option<u64>,
+    ) -> Result<RejectedTxsResponse, Error> {
+        let limit_query = limit
+            .map(|limit| format!("&limit={}", limit))
+            .unwrap_or_default();
+        self.get(&format!(
+            "/v2/mempool/rejected?address={}{}",
+            address, limit_query
+        ))
+    }
+
+    /// `GET /v2/mempool/stats`: counts of the node's mempool transactions, by fee rate, age and
+    /// payload type
+    pub fn get_mempool_stats(&self) -> Result<MemPoolStats, Error> {
+        self.get("/v2/mempool/stats")
+    }
+}
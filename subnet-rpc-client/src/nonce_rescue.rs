@@ -0,0 +1,118 @@
+//! Unsticking an account whose pending transactions are stranded behind a missing nonce.
+//!
+//! A transaction can't be mined until every lower nonce of its origin (or sponsor) has been, so
+//! if one is dropped from the mempool, the account's later transactions wait forever.
+//! `/v2/accounts/[Stacks Address]/next_nonce` reports the missing nonces, and a `NonceRescue`
+//! is the smallest transaction that uses one up.  A transfer to its own sender would be smaller
+//! still, but the node refuses those, so the rescue sends `RESCUE_TRANSFER_AMOUNT` to a
+//! recipient of the caller's choosing, e.g. another of its own accounts.
+//!
+//! The rescue is built unsigned, so the origin key can stay wherever it is kept: sign
+//! `NonceRescue::sighash()` with it, then pass the signature to `attach_signature`.
+
+use stacks::burnchains::Txid;
+use stacks::chainstate::stacks::{
+    StacksPublicKey, StacksTransaction, TokenTransferMemo, TransactionAuth, TransactionAuthFlags,
+    TransactionPayload, TransactionSpendingCondition, TransactionVersion,
+};
+use stacks::types::chainstate::StacksAddress;
+use stacks::util::secp256k1::MessageSignature;
+use stacks::vm::types::PrincipalData;
+
+use crate::Error;
+
+/// What a rescue transfer sends, in microSTX
+pub const RESCUE_TRANSFER_AMOUNT: u64 = 1;
+
+/// The single-signature address `origin` spends from
+pub fn origin_address(
+    version: TransactionVersion,
+    origin: &StacksPublicKey,
+) -> Result<StacksAddress, Error> {
+    let spending_condition = TransactionSpendingCondition::new_singlesig_p2pkh(origin.clone())
+        .ok_or_else(|| Error::InvalidTransaction("Cannot spend from the origin key".into()))?;
+    Ok(match version {
+        TransactionVersion::Mainnet => spending_condition.address_mainnet(),
+        TransactionVersion::Testnet => spending_condition.address_testnet(),
+    })
+}
+
+/// An unsigned STX transfer which fills one missing nonce of a single-signature account
+#[derive(Debug, Clone, PartialEq)]
+pub struct NonceRescue {
+    tx: StacksTransaction,
+    sighash: Txid,
+}
+
+impl NonceRescue {
+    /// Build a rescue spending `nonce` of the account whose key is `origin`, paying `fee` and
+    /// sending `RESCUE_TRANSFER_AMOUNT` to `recipient`, which must not be the account itself.
+    pub fn new(
+        version: TransactionVersion,
+        chain_id: u32,
+        origin: &StacksPublicKey,
+        nonce: u64,
+        fee: u64,
+        recipient: PrincipalData,
+    ) -> Result<NonceRescue, Error> {
+        let mut spending_condition =
+            TransactionSpendingCondition::new_singlesig_p2pkh(origin.clone()).ok_or_else(|| {
+                Error::InvalidTransaction("Cannot spend from the origin key".into())
+            })?;
+        spending_condition.set_nonce(nonce);
+        spending_condition.set_tx_fee(fee);
+
+        let mut tx = StacksTransaction::new(
+            version,
+            TransactionAuth::Standard(spending_condition),
+            TransactionPayload::TokenTransfer(
+                recipient.clone(),
+                RESCUE_TRANSFER_AMOUNT,
+                TokenTransferMemo([0u8; 34]),
+            ),
+        );
+        tx.chain_id = chain_id;
+        if recipient == PrincipalData::from(tx.origin_address()) {
+            return Err(Error::InvalidTransaction(
+                "A rescue cannot send to its own origin".into(),
+            ));
+        }
+
+        // the digest a single-signature origin signs: the transaction with its authorization
+        //  cleared, followed by the origin's auth flag, fee and nonce
+        let mut initial = tx.clone();
+        initial.auth = initial.auth.into_initial_sighash_auth();
+        let sighash = TransactionSpendingCondition::make_sighash_presign(
+            &initial.txid(),
+            &TransactionAuthFlags::AuthStandard,
+            fee,
+            nonce,
+        );
+        Ok(NonceRescue { tx, sighash })
+    }
+
+    /// The digest the origin key must sign
+    pub fn sighash(&self) -> &Txid {
+        &self.sighash
+    }
+
+    /// The rescue, without its signature
+    pub fn unsigned_tx(&self) -> &StacksTransaction {
+        &self.tx
+    }
+
+    /// Add the origin key's signature of `sighash()`, and check it.  The signed transaction is
+    /// ready for `SubnetRpcClient::submit_transaction`.
+    pub fn attach_signature(self, signature: MessageSignature) -> Result<StacksTransaction, Error> {
+        let mut tx = self.tx;
+        match tx.auth {
+            TransactionAuth::Standard(TransactionSpendingCondition::Singlesig(ref mut cond)) => {
+                cond.set_signature(signature)
+            }
+            _ => unreachable!("a rescue is always single-signature"),
+        }
+        tx.verify()
+            .map_err(|e| Error::InvalidTransaction(format!("Bad rescue signature: {:?}", e)))?;
+        Ok(tx)
+    }
+}
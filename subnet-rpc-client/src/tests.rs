@@ -0,0 +1,162 @@
+use stacks::burnchains::PrivateKey;
+use stacks::chainstate::stacks::{
+    StacksPrivateKey, StacksPublicKey, TokenTransferMemo, TransactionPayload, TransactionVersion,
+};
+use stacks::net::response_signing::RPCResponseSigner;
+use stacks::net::{AccountEntryResponse, TipRequest};
+use stacks::types::chainstate::StacksBlockId;
+use stacks::util::secp256k1::{Secp256k1PrivateKey, Secp256k1PublicKey};
+use stacks::vm::types::PrincipalData;
+
+use crate::events::EventTopic;
+use crate::nonce_rescue::{origin_address, NonceRescue, RESCUE_TRANSFER_AMOUNT};
+use crate::{page_query, tip_query, Account, SignedResponse};
+
+#[test]
+fn test_account_from_response() {
+    let response = AccountEntryResponse {
+        balance: "0x0000000000000000000000000000fa00".to_string(),
+        locked: "0x00000000000000000000000000000000".to_string(),
+        unlock_height: 0,
+        nonce: 3,
+        balance_proof: None,
+        nonce_proof: None,
+    };
+    assert_eq!(
+        Account::from_response(&response).unwrap(),
+        Account {
+            balance: 0xfa00,
+            locked: 0,
+            unlock_height: 0,
+            nonce: 3,
+        }
+    );
+
+    let bad_response = AccountEntryResponse {
+        balance: "0xnope".to_string(),
+        ..response
+    };
+    assert!(Account::from_response(&bad_response).is_err());
+}
+
+#[test]
+fn test_query_strings() {
+    assert_eq!(
+        tip_query(&TipRequest::UseLatestUnconfirmedTip),
+        "?tip=latest&proof=0"
+    );
+    assert_eq!(
+        tip_query(&TipRequest::UseLatestAnchoredTip),
+        "?tip=anchored&proof=0"
+    );
+    assert_eq!(
+        tip_query(&TipRequest::SpecificTip(StacksBlockId([0x11; 32]))),
+        format!("?tip={}&proof=0", "11".repeat(32))
+    );
+
+    assert_eq!(page_query(None, None), "");
+    assert_eq!(page_query(Some(50), None), "?offset=50");
+    assert_eq!(page_query(Some(50), Some(10)), "?offset=50&limit=10");
+}
+
+#[test]
+fn test_event_topic_names() {
+    for topic in EventTopic::ALL.iter() {
+        assert_eq!(EventTopic::from_name(topic.as_str()), Some(*topic));
+    }
+    assert_eq!(EventTopic::from_name("new_blocks"), None);
+}
+
+#[test]
+fn test_signed_response_verify() {
+    let private_key = Secp256k1PrivateKey::new();
+    let public_key = Secp256k1PublicKey::from_private(&private_key);
+    let signer = RPCResponseSigner {
+        private_key,
+        chain_id: 0x80000000,
+        tip: StacksBlockId([0x11; 32]),
+        request_path: "/v2/hyperchain/info".to_string(),
+    };
+    let body = br#"{"chain_id":2147483648}"#.to_vec();
+    let signed = SignedResponse {
+        response: (),
+        signature: Some(signer.sign(&body).unwrap()),
+        request_path: "/v2/hyperchain/info".to_string(),
+        body,
+    };
+    assert!(signed.verify(&public_key, 0x80000000).is_ok());
+
+    // another key or chain is refused
+    let other_key = Secp256k1PublicKey::from_private(&Secp256k1PrivateKey::new());
+    assert!(signed.verify(&other_key, 0x80000000).is_err());
+    assert!(signed.verify(&public_key, 0x80000001).is_err());
+
+    // as is a tampered body, or no signature at all
+    let tampered = SignedResponse {
+        body: br#"{"chain_id":1}"#.to_vec(),
+        ..signed.clone()
+    };
+    assert!(tampered.verify(&public_key, 0x80000000).is_err());
+    let unsigned = SignedResponse {
+        signature: None,
+        ..signed
+    };
+    assert!(unsigned.verify(&public_key, 0x80000000).is_err());
+}
+
+#[test]
+fn test_nonce_rescue() {
+    let privk = StacksPrivateKey::new();
+    let pubk = StacksPublicKey::from_private(&privk);
+    let origin = origin_address(TransactionVersion::Testnet, &pubk).unwrap();
+    let recipient: PrincipalData = origin_address(
+        TransactionVersion::Testnet,
+        &StacksPublicKey::from_private(&StacksPrivateKey::new()),
+    )
+    .unwrap()
+    .into();
+
+    // a rescue can't send to its own origin
+    assert!(NonceRescue::new(
+        TransactionVersion::Testnet,
+        0x80000000,
+        &pubk,
+        5,
+        180,
+        origin.clone().into()
+    )
+    .is_err());
+
+    let rescue = NonceRescue::new(
+        TransactionVersion::Testnet,
+        0x80000000,
+        &pubk,
+        5,
+        180,
+        recipient.clone(),
+    )
+    .unwrap();
+    let unsigned = rescue.unsigned_tx();
+    assert_eq!(unsigned.origin_address(), origin);
+    assert_eq!(unsigned.get_origin_nonce(), 5);
+    assert_eq!(unsigned.get_tx_fee(), 180);
+    assert_eq!(unsigned.chain_id, 0x80000000);
+    assert_eq!(
+        unsigned.payload,
+        TransactionPayload::TokenTransfer(
+            recipient,
+            RESCUE_TRANSFER_AMOUNT,
+            TokenTransferMemo([0u8; 34])
+        )
+    );
+
+    // another key's signature is refused
+    let other_privk = StacksPrivateKey::new();
+    let bad_signature = other_privk.sign(rescue.sighash().as_bytes()).unwrap();
+    assert!(rescue.clone().attach_signature(bad_signature).is_err());
+
+    // the origin key's signature makes a valid transaction
+    let signature = privk.sign(rescue.sighash().as_bytes()).unwrap();
+    let tx = rescue.attach_signature(signature).unwrap();
+    tx.verify().unwrap();
+}
@@ -0,0 +1,122 @@
+use std::fs;
+
+use serde_json::json;
+use stacks::chainstate::burn::db::sortdb::SortitionDB;
+use stacks::chainstate::stacks::db::block_costs::CostUtilization;
+use stacks::chainstate::stacks::db::StacksChainState;
+use stacks::vm::database::BurnStateDB;
+
+use crate::run_loop::RunLoopError;
+use crate::Config;
+
+/// Report how much of the active block limit each of the last `num_blocks` canonical L2 blocks
+/// used, per cost dimension, along with the `top_k` contracts that cost the most to call or
+/// publish over those blocks.  The report is printed as JSON.  Contract costs are found by
+/// re-executing each block's transactions, so they are approximate.
+pub fn analyze_block_costs(
+    config: &Config,
+    num_blocks: u64,
+    top_k: usize,
+) -> Result<(), RunLoopError> {
+    let chainstate_path = config.get_chainstate_path_str();
+    if fs::metadata(&chainstate_path).is_err() {
+        return Err(RunLoopError::Chainstate(format!(
+            "no chainstate at {}",
+            &chainstate_path
+        )));
+    }
+    let sortdb = SortitionDB::open(&config.get_burn_db_file_path(), false).map_err(|e| {
+        RunLoopError::Chainstate(format!("failed to open sortition database: {:?}", &e))
+    })?;
+    let (mut chainstate, _) = StacksChainState::open(
+        config.is_mainnet(),
+        config.node.chain_id,
+        &chainstate_path,
+        Some(config.node.get_marf_opts()),
+    )
+    .map_err(|e| {
+        RunLoopError::Chainstate(format!("failed to open chainstate database: {:?}", &e))
+    })?;
+
+    // the block limit the L1 contract has set, if any, or else the epoch's
+    let burn_tip = SortitionDB::get_canonical_burn_chain_tip(sortdb.conn()).map_err(|e| {
+        RunLoopError::Chainstate(format!("failed to load burn chain tip: {:?}", &e))
+    })?;
+    let block_limit = sortdb
+        .index_conn()
+        .get_stacks_epoch(burn_tip.block_height as u32)
+        .ok_or_else(|| {
+            RunLoopError::Chainstate(format!(
+                "no Stacks epoch for burn height {}",
+                burn_tip.block_height
+            ))
+        })?
+        .block_limit;
+
+    let block_costs = chainstate
+        .get_recent_block_costs(&sortdb, num_blocks)
+        .map_err(|e| RunLoopError::Chainstate(format!("failed to load block costs: {:?}", &e)))?;
+    let contract_costs = chainstate
+        .get_contract_costs(&sortdb, &block_costs, &block_limit)
+        .map_err(|e| RunLoopError::Chainstate(format!("failed to re-execute blocks: {:?}", &e)))?;
+
+    let blocks: Vec<_> = block_costs
+        .iter()
+        .map(|block| {
+            json!({
+                "block_height": block.block_height,
+                "index_block_hash": format!("{}", &block.index_block_hash),
+                "cost": block.cost,
+                "utilization": CostUtilization::new(&block.cost, &block_limit),
+            })
+        })
+        .collect();
+
+    // the mean utilization of each dimension, and the number of blocks which used at least
+    // 90% of the limit in some dimension
+    let num_full = block_costs
+        .iter()
+        .filter(|block| CostUtilization::new(&block.cost, &block_limit).max() >= 0.9)
+        .count();
+    let mean = |f: fn(&CostUtilization) -> f64| {
+        if block_costs.is_empty() {
+            return 0.0;
+        }
+        block_costs
+            .iter()
+            .map(|block| f(&CostUtilization::new(&block.cost, &block_limit)))
+            .sum::<f64>()
+            / block_costs.len() as f64
+    };
+    let mean_utilization = CostUtilization {
+        runtime: mean(|u| u.runtime),
+        read_count: mean(|u| u.read_count),
+        read_length: mean(|u| u.read_length),
+        write_count: mean(|u| u.write_count),
+        write_length: mean(|u| u.write_length),
+    };
+
+    let contracts: Vec<_> = contract_costs
+        .iter()
+        .take(top_k)
+        .map(|contract| {
+            json!({
+                "contract_id": format!("{}", &contract.contract_id),
+                "tx_total": contract.num_txs,
+                "cost": contract.cost,
+                "utilization": CostUtilization::new(&contract.cost, &block_limit),
+            })
+        })
+        .collect();
+
+    let report = json!({
+        "block_limit": block_limit,
+        "blocks_total": block_costs.len(),
+        "blocks_near_limit": num_full,
+        "mean_utilization": mean_utilization,
+        "blocks": blocks,
+        "top_contracts": contracts,
+    });
+    println!("{}", &report.to_string());
+    Ok(())
+}
@@ -0,0 +1,97 @@
+use std::time::Duration;
+
+use serde_json::json;
+use stacks::chainstate::stacks::TransactionPayload;
+use stacks::codec::StacksMessageCodec;
+use stacks::net::rpc::L1WithdrawalOracle;
+use stacks::net::{MapEntryResponse, RPCFeeEstimateResponse};
+use stacks::util::hash::{to_hex, Sha512Trunc256Sum};
+use stacks::vm::types::QualifiedContractIdentifier;
+use stacks::vm::Value as ClarityValue;
+
+use crate::config::BurnchainConfig;
+
+/// How long to wait on the L1 before reporting a withdrawal claim without its answer
+const L1_QUERY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Answers the withdrawal-claim endpoint's questions by querying the L1 node's RPC interface:
+/// whether the subnet contract's `withdrawal-roots-map` holds a withdrawal root, and what the
+/// L1's fee estimator expects a claim to cost.
+pub struct L1ClaimOracle {
+    config: BurnchainConfig,
+}
+
+impl L1ClaimOracle {
+    pub fn new(config: &BurnchainConfig) -> L1ClaimOracle {
+        L1ClaimOracle {
+            config: config.clone(),
+        }
+    }
+
+    fn client() -> Result<reqwest::blocking::Client, String> {
+        reqwest::blocking::Client::builder()
+            .timeout(L1_QUERY_TIMEOUT)
+            .build()
+            .map_err(|e| e.to_string())
+    }
+}
+
+impl L1WithdrawalOracle for L1ClaimOracle {
+    fn subnet_contract(&self) -> QualifiedContractIdentifier {
+        self.config.contract_identifier.clone()
+    }
+
+    fn is_withdrawal_root_committed(
+        &self,
+        withdrawal_root: &Sha512Trunc256Sum,
+    ) -> Result<bool, String> {
+        let contract_id = &self.config.contract_identifier;
+        let url = format!(
+            "{}/v2/map_entry/{}/{}/withdrawal-roots-map?proof=0",
+            self.config.get_rpc_url(),
+            &contract_id.issuer,
+            &contract_id.name
+        );
+        let key = ClarityValue::buff_from(withdrawal_root.as_bytes().to_vec())
+            .map_err(|e| e.to_string())?;
+        let response: MapEntryResponse = L1ClaimOracle::client()?
+            .post(&url)
+            .json(&format!("0x{}", key.serialize_to_hex()))
+            .send()
+            .and_then(|response| response.error_for_status())
+            .and_then(|response| response.json())
+            .map_err(|e| e.to_string())?;
+        let data = ClarityValue::try_deserialize_hex_untyped(&response.data)
+            .map_err(|e| format!("L1 answered with an invalid map entry: {:?}", e))?;
+        match data {
+            ClarityValue::Optional(entry) => match entry.data {
+                Some(value) => Ok(*value == ClarityValue::Bool(true)),
+                None => Ok(false),
+            },
+            other => Err(format!(
+                "L1 answered with an unexpected map entry: {}",
+                other
+            )),
+        }
+    }
+
+    fn estimate_claim_fee(&self, payload: &TransactionPayload) -> Result<u64, String> {
+        let url = format!("{}/v2/fees/transaction", self.config.get_rpc_url());
+        let body = json!({
+            "transaction_payload": format!("0x{}", to_hex(&payload.serialize_to_vec())),
+        });
+        let response: RPCFeeEstimateResponse = L1ClaimOracle::client()?
+            .post(&url)
+            .json(&body)
+            .send()
+            .and_then(|response| response.error_for_status())
+            .and_then(|response| response.json())
+            .map_err(|e| e.to_string())?;
+        // take the middle estimate, as the block committers do
+        response
+            .estimations
+            .get(response.estimations.len() / 2)
+            .map(|estimate| estimate.fee)
+            .ok_or_else(|| "L1 returned no fee estimates".to_string())
+    }
+}
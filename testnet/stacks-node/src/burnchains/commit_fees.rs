@@ -0,0 +1,70 @@
+use std::path::Path;
+
+use rusqlite::{OpenFlags, ToSql, NO_PARAMS};
+use stacks::burnchains::Txid;
+use stacks::chainstate::stacks::db::rewards_report::CommitFeeLedger;
+use stacks::util_lib::db::{query_row, sqlite_open, u64_to_sql, DBConn, Error as DBError};
+
+const COMMIT_FEES_SCHEMA: &str = "CREATE TABLE IF NOT EXISTS commit_fees(
+    txid TEXT PRIMARY KEY,
+    fee INTEGER NOT NULL,
+    l1_height INTEGER NOT NULL
+);";
+
+/// The L1 fees of the block-commits this node has broadcast, so that rewards reports can tell
+/// what mining cost.  A commit whose fee is bumped is recorded again, under its new txid.
+pub struct CommitFeeDB {
+    conn: DBConn,
+}
+
+impl CommitFeeDB {
+    /// Open the database at `path` for recording commits, creating it if it doesn't exist
+    pub fn open(path: &str) -> Result<CommitFeeDB, DBError> {
+        let conn = sqlite_open(
+            path,
+            OpenFlags::SQLITE_OPEN_READ_WRITE | OpenFlags::SQLITE_OPEN_CREATE,
+            true,
+        )?;
+        conn.execute(COMMIT_FEES_SCHEMA, NO_PARAMS)?;
+        Ok(CommitFeeDB { conn })
+    }
+
+    /// Open the database at `path` for reading, if the node has recorded any commits there
+    pub fn open_existing(path: &str) -> Result<Option<CommitFeeDB>, DBError> {
+        if !Path::new(path).exists() {
+            return Ok(None);
+        }
+        let conn = sqlite_open(path, OpenFlags::SQLITE_OPEN_READ_ONLY, true)?;
+        Ok(Some(CommitFeeDB { conn }))
+    }
+
+    /// Record that the commit `txid`, paying `fee`, was broadcast at L1 height `l1_height`
+    pub fn record_commit(&self, txid: &Txid, fee: u64, l1_height: u64) -> Result<(), DBError> {
+        let args: &[&dyn ToSql] = &[txid, &u64_to_sql(fee)?, &u64_to_sql(l1_height)?];
+        self.conn.execute(
+            "INSERT OR REPLACE INTO commit_fees (txid, fee, l1_height) VALUES (?1, ?2, ?3)",
+            args,
+        )?;
+        Ok(())
+    }
+}
+
+impl CommitFeeLedger for CommitFeeDB {
+    fn get_commit_fee(&self, txid: &Txid) -> Option<u64> {
+        let args: &[&dyn ToSql] = &[txid];
+        match query_row::<u64, _>(
+            &self.conn,
+            "SELECT fee FROM commit_fees WHERE txid = ?1",
+            args,
+        ) {
+            Ok(fee) => fee,
+            Err(e) => {
+                warn!(
+                    "Failed to look up the fee of block-commit {}: {:?}",
+                    txid, &e
+                );
+                None
+            }
+        }
+    }
+}
@@ -0,0 +1,208 @@
+use std::cmp;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::Instant;
+
+use stacks::burnchains::Txid;
+use stacks::chainstate::stacks::StacksTransaction;
+use stacks_common::types::chainstate::StacksAddress;
+
+use super::commit_signer::CommitSigner;
+use super::commitment::Error;
+use crate::config::BurnchainConfig;
+
+/// Have `signer` re-sign `tx` with the same nonce and the fee `fee`.
+pub fn resign_with_fee(
+    tx: &StacksTransaction,
+    fee: u64,
+    signer: &dyn CommitSigner,
+) -> Result<StacksTransaction, Error> {
+    let nonce = tx.get_origin_nonce();
+    let mut unsigned_tx = tx.clone();
+    unsigned_tx.auth.clear();
+    unsigned_tx.set_origin_nonce(nonce);
+    unsigned_tx.set_tx_fee(fee);
+    signer.sign_commit(&unsigned_tx)
+}
+
+/// A block-commit transaction that was broadcast to the L1, but hasn't been confirmed yet.
+#[derive(Clone)]
+pub struct PendingCommit {
+    pub tx: StacksTransaction,
+    pub txid: Txid,
+    /// the signer the commit was signed with, for re-signing it with a higher fee
+    pub signer: Arc<dyn CommitSigner>,
+    /// The fee the commit was first broadcast with.  Fee bumps are relative to this.
+    pub initial_fee: u64,
+    /// When the commit was first broadcast
+    pub first_broadcast: Instant,
+    /// The L1 block height at which the commit was last broadcast
+    pub last_broadcast_height: u64,
+}
+
+impl PendingCommit {
+    pub fn sender(&self) -> StacksAddress {
+        self.tx.origin_address()
+    }
+
+    pub fn nonce(&self) -> u64 {
+        self.tx.get_origin_nonce()
+    }
+
+    pub fn fee(&self) -> u64 {
+        self.tx.get_tx_fee()
+    }
+
+    /// Re-sign this commit's transaction with the same nonce and a new fee.
+    pub fn with_fee(&self, fee: u64) -> Result<StacksTransaction, Error> {
+        resign_with_fee(&self.tx, fee, self.signer.as_ref())
+    }
+}
+
+/// Tracks the miner's unconfirmed block-commits, so that commits which get stuck in the L1
+/// mempool can be replaced with higher-fee versions that reuse their nonce.
+pub struct CommitManager {
+    /// pending commits, keyed by sender and nonce
+    pending: HashMap<(StacksAddress, u64), PendingCommit>,
+    /// percentage of a commit's initial fee to add on each bump
+    rbf_fee_increment: u64,
+    /// the most a commit's fee is bumped to, as a percentage of its initial fee
+    max_rbf: u64,
+    /// how many L1 blocks to wait for a commit before bumping its fee
+    rbf_after_blocks: u64,
+}
+
+impl CommitManager {
+    pub fn new(config: &BurnchainConfig) -> CommitManager {
+        CommitManager {
+            pending: HashMap::new(),
+            rbf_fee_increment: config.rbf_fee_increment,
+            max_rbf: config.max_rbf,
+            rbf_after_blocks: config.rbf_after_blocks,
+        }
+    }
+
+    /// Start tracking a commit that was just broadcast at L1 height `l1_height`.  If it replaces
+    /// a pending commit with the same nonce, the pending commit's first broadcast time carries
+    /// over, so confirmation latency covers the whole wait.
+    pub fn track(
+        &mut self,
+        tx: StacksTransaction,
+        txid: Txid,
+        signer: Arc<dyn CommitSigner>,
+        l1_height: u64,
+    ) {
+        let key = (tx.origin_address(), tx.get_origin_nonce());
+        let first_broadcast = self
+            .pending
+            .get(&key)
+            .map(|replaced| replaced.first_broadcast)
+            .unwrap_or_else(Instant::now);
+        let pending = PendingCommit {
+            initial_fee: tx.get_tx_fee(),
+            tx,
+            txid,
+            signer,
+            first_broadcast,
+            last_broadcast_height: l1_height,
+        };
+        self.pending.insert(key, pending);
+    }
+
+    /// Is the manager waiting on any commits?
+    pub fn has_pending(&self) -> bool {
+        !self.pending.is_empty()
+    }
+
+    /// The distinct senders of the pending commits
+    pub fn senders(&self) -> Vec<StacksAddress> {
+        let senders: HashSet<_> = self
+            .pending
+            .keys()
+            .map(|(sender, _)| sender.clone())
+            .collect();
+        senders.into_iter().collect()
+    }
+
+    /// Stop tracking `sender`'s commits whose nonces are below `account_nonce`, since the L1 has
+    /// confirmed them (or a replacement), and return them.
+    pub fn confirm(&mut self, sender: &StacksAddress, account_nonce: u64) -> Vec<PendingCommit> {
+        let confirmed_keys: Vec<_> = self
+            .pending
+            .keys()
+            .filter(|(pending_sender, nonce)| pending_sender == sender && *nonce < account_nonce)
+            .cloned()
+            .collect();
+        let mut confirmed: Vec<_> = confirmed_keys
+            .into_iter()
+            .filter_map(|key| self.pending.remove(&key))
+            .collect();
+        confirmed.sort_by_key(|commit| commit.nonce());
+        confirmed
+    }
+
+    /// The fee to replace `commit` with, or `None` if its fee is already at the cap.
+    pub fn bumped_fee(&self, commit: &PendingCommit) -> Option<u64> {
+        let max_fee = commit.initial_fee.saturating_mul(self.max_rbf) / 100;
+        let increment = cmp::max(
+            commit.initial_fee.saturating_mul(self.rbf_fee_increment) / 100,
+            1,
+        );
+        let fee = cmp::min(commit.fee().saturating_add(increment), max_fee);
+        if fee > commit.fee() {
+            Some(fee)
+        } else {
+            None
+        }
+    }
+
+    /// The fee a new commit with `sender` and `nonce` must pay to replace a pending commit with
+    /// the same nonce, if the L1 would otherwise reject it for not paying more.
+    pub fn replacement_fee(&self, sender: &StacksAddress, nonce: u64, fee: u64) -> Option<u64> {
+        let replaced = self.pending.get(&(sender.clone(), nonce))?;
+        if fee > replaced.fee() {
+            return None;
+        }
+        let increment = cmp::max(
+            replaced.initial_fee.saturating_mul(self.rbf_fee_increment) / 100,
+            1,
+        );
+        Some(replaced.fee().saturating_add(increment))
+    }
+
+    /// Stop tracking the commits that have waited at least `rbf_after_blocks` L1 blocks since
+    /// they were last broadcast, and return them, lowest nonce first.  Callers hand them back to
+    /// `rebroadcast` once they're sent again.
+    pub fn take_stuck(&mut self, l1_height: u64) -> Vec<PendingCommit> {
+        let stuck_keys: Vec<_> = self
+            .pending
+            .iter()
+            .filter(|(_, commit)| {
+                l1_height.saturating_sub(commit.last_broadcast_height) >= self.rbf_after_blocks
+            })
+            .map(|(key, _)| key.clone())
+            .collect();
+        let mut stuck: Vec<_> = stuck_keys
+            .into_iter()
+            .filter_map(|key| self.pending.remove(&key))
+            .collect();
+        stuck.sort_by_key(|commit| commit.nonce());
+        stuck
+    }
+
+    /// Resume tracking a commit returned by `take_stuck`, after rebroadcasting it as `tx` at L1
+    /// height `l1_height`.
+    pub fn rebroadcast(
+        &mut self,
+        mut commit: PendingCommit,
+        tx: StacksTransaction,
+        txid: Txid,
+        l1_height: u64,
+    ) {
+        commit.tx = tx;
+        commit.txid = txid;
+        commit.last_broadcast_height = l1_height;
+        self.pending
+            .insert((commit.sender(), commit.nonce()), commit);
+    }
+}
@@ -0,0 +1,319 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use rand::RngCore;
+use stacks::chainstate::stacks::{
+    StacksPrivateKey, StacksPublicKey, StacksTransaction, StacksTransactionSigner,
+};
+use stacks::codec::StacksMessageCodec;
+use stacks::util::hash::{hex_bytes, to_hex, Sha256Sum};
+use stacks::util::secp256k1::MessageSignature;
+
+use super::commitment::Error;
+use crate::config::{CommitSignerConfig, MinerConfig};
+
+#[cfg(test)]
+use std::sync::atomic::{AtomicBool, Ordering};
+#[cfg(test)]
+use std::sync::Mutex;
+
+/// How long to wait on a remote signer
+const REMOTE_SIGNER_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Prefixes of the messages signed in a remote signer exchange, so that a signed request can't
+/// be passed off as a signed response, or the other way around
+const REQUEST_DOMAIN: &[u8] = b"subnet-commit-sign-request";
+const RESPONSE_DOMAIN: &[u8] = b"subnet-commit-sign-response";
+
+/// Signs the L1 transactions that carry the miner's block-commits, so that the key which pays
+/// for and authorizes them need not be held by the node.
+pub trait CommitSigner: Send + Sync {
+    /// The public key commits are signed with.  The commit transactions' origin, which is the L1
+    /// account that pays for them, is derived from it.
+    fn get_public_key(&self) -> StacksPublicKey;
+    /// Sign the origin of `unsigned_tx`, whose spending condition is for `get_public_key()`.
+    fn sign_commit(&self, unsigned_tx: &StacksTransaction) -> Result<StacksTransaction, Error>;
+}
+
+fn sign_origin(
+    unsigned_tx: &StacksTransaction,
+    secret_key: &StacksPrivateKey,
+) -> Result<StacksTransaction, Error> {
+    let mut tx_signer = StacksTransactionSigner::new(unsigned_tx);
+    tx_signer
+        .sign_origin(secret_key)
+        .map_err(|e| Error::SignerFailure(format!("{:?}", &e)))?;
+    tx_signer
+        .get_tx()
+        .ok_or_else(|| Error::SignerFailure("signer produced no transaction".into()))
+}
+
+/// Signs commits with a key the node holds
+pub struct LocalCommitSigner {
+    secret_key: StacksPrivateKey,
+}
+
+impl LocalCommitSigner {
+    pub fn new(secret_key: StacksPrivateKey) -> LocalCommitSigner {
+        LocalCommitSigner { secret_key }
+    }
+}
+
+impl CommitSigner for LocalCommitSigner {
+    fn get_public_key(&self) -> StacksPublicKey {
+        StacksPublicKey::from_private(&self.secret_key)
+    }
+
+    fn sign_commit(&self, unsigned_tx: &StacksTransaction) -> Result<StacksTransaction, Error> {
+        sign_origin(unsigned_tx, &self.secret_key)
+    }
+}
+
+fn signing_hash(domain: &[u8], request_id: &[u8], tx: &StacksTransaction) -> Sha256Sum {
+    let mut message = domain.to_vec();
+    message.extend_from_slice(request_id);
+    message.extend_from_slice(&tx.serialize_to_vec());
+    Sha256Sum::from_data(&message)
+}
+
+fn decode_tx(tx_hex: &str) -> Result<StacksTransaction, Error> {
+    let tx_bytes = hex_bytes(tx_hex)
+        .map_err(|_| Error::SignerFailure("transaction is not hex-encoded".into()))?;
+    StacksTransaction::consensus_deserialize(&mut &tx_bytes[..])
+        .map_err(|e| Error::SignerFailure(format!("invalid transaction: {}", &e)))
+}
+
+fn check_signature(
+    hash: &Sha256Sum,
+    signature_hex: &str,
+    public_key: &StacksPublicKey,
+) -> Result<(), Error> {
+    let signature = MessageSignature::from_hex(signature_hex)
+        .map_err(|_| Error::SignerFailure("signature is not hex-encoded".into()))?;
+    let signer = StacksPublicKey::recover_to_pubkey(hash.as_bytes(), &signature)
+        .map_err(|e| Error::SignerFailure(format!("invalid signature: {}", e)))?;
+    if signer.to_bytes_compressed() != public_key.to_bytes_compressed() {
+        return Err(Error::SignerFailure("signed by an unexpected key".into()));
+    }
+    Ok(())
+}
+
+/// The body a remote signer is POSTed.  `signature` is the node's signature, with its signer
+/// authentication key, over the request ID and the unsigned transaction.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RemoteSignRequest {
+    pub request_id: String,
+    pub tx: String,
+    pub signature: String,
+}
+
+/// A remote signer's answer.  `signature` is the signer's signature, with the commit key, over
+/// the request ID and the signed transaction, so that answers can't be replayed.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RemoteSignResponse {
+    pub request_id: String,
+    pub tx: String,
+    pub signature: String,
+}
+
+impl RemoteSignRequest {
+    pub fn new(
+        unsigned_tx: &StacksTransaction,
+        auth_key: &StacksPrivateKey,
+    ) -> Result<RemoteSignRequest, Error> {
+        let mut request_id = [0u8; 32];
+        rand::thread_rng().fill_bytes(&mut request_id);
+        let hash = signing_hash(REQUEST_DOMAIN, &request_id, unsigned_tx);
+        let signature = auth_key
+            .sign(hash.as_bytes())
+            .map_err(|e| Error::SignerFailure(e.to_string()))?;
+        Ok(RemoteSignRequest {
+            request_id: to_hex(&request_id),
+            tx: to_hex(&unsigned_tx.serialize_to_vec()),
+            signature: signature.to_hex(),
+        })
+    }
+
+    /// Check that the request was signed by `auth_public_key`, and return the transaction to
+    /// sign.  For signer implementations.
+    pub fn check(&self, auth_public_key: &StacksPublicKey) -> Result<StacksTransaction, Error> {
+        let request_id = hex_bytes(&self.request_id)
+            .map_err(|_| Error::SignerFailure("request ID is not hex-encoded".into()))?;
+        let unsigned_tx = decode_tx(&self.tx)?;
+        let hash = signing_hash(REQUEST_DOMAIN, &request_id, &unsigned_tx);
+        check_signature(&hash, &self.signature, auth_public_key)?;
+        Ok(unsigned_tx)
+    }
+}
+
+impl RemoteSignResponse {
+    /// Answer the request `request_id` with `signed_tx`.  For signer implementations.
+    pub fn new(
+        request_id: &str,
+        signed_tx: &StacksTransaction,
+        commit_key: &StacksPrivateKey,
+    ) -> Result<RemoteSignResponse, Error> {
+        let request_id_bytes = hex_bytes(request_id)
+            .map_err(|_| Error::SignerFailure("request ID is not hex-encoded".into()))?;
+        let hash = signing_hash(RESPONSE_DOMAIN, &request_id_bytes, signed_tx);
+        let signature = commit_key
+            .sign(hash.as_bytes())
+            .map_err(|e| Error::SignerFailure(e.to_string()))?;
+        Ok(RemoteSignResponse {
+            request_id: request_id.to_string(),
+            tx: to_hex(&signed_tx.serialize_to_vec()),
+            signature: signature.to_hex(),
+        })
+    }
+}
+
+/// Is `signed_tx` the transaction `unsigned_tx`, but signed?
+fn is_signed_version_of(signed_tx: &StacksTransaction, unsigned_tx: &StacksTransaction) -> bool {
+    signed_tx.version == unsigned_tx.version
+        && signed_tx.chain_id == unsigned_tx.chain_id
+        && signed_tx.anchor_mode == unsigned_tx.anchor_mode
+        && signed_tx.post_condition_mode == unsigned_tx.post_condition_mode
+        && signed_tx.post_conditions == unsigned_tx.post_conditions
+        && signed_tx.payload == unsigned_tx.payload
+        && signed_tx.origin_address() == unsigned_tx.origin_address()
+        && signed_tx.get_origin_nonce() == unsigned_tx.get_origin_nonce()
+        && signed_tx.get_tx_fee() == unsigned_tx.get_tx_fee()
+}
+
+/// Signs commits through an external signer daemon, which holds the commit key.  Each request
+/// is signed with the node's signer authentication key, and each answer must be signed with the
+/// commit key and carry the request's ID.
+pub struct RemoteCommitSigner {
+    url: String,
+    public_key: StacksPublicKey,
+    auth_key: StacksPrivateKey,
+}
+
+impl RemoteCommitSigner {
+    pub fn new(
+        url: &str,
+        public_key: StacksPublicKey,
+        auth_key: StacksPrivateKey,
+    ) -> RemoteCommitSigner {
+        RemoteCommitSigner {
+            url: url.to_string(),
+            public_key,
+            auth_key,
+        }
+    }
+
+    /// Check the signer's answer to `request`, for `unsigned_tx`, and return the signed
+    /// transaction.
+    pub fn check_response(
+        &self,
+        request: &RemoteSignRequest,
+        unsigned_tx: &StacksTransaction,
+        response: &RemoteSignResponse,
+    ) -> Result<StacksTransaction, Error> {
+        if response.request_id != request.request_id {
+            return Err(Error::SignerFailure(format!(
+                "answer is for request {}, not {}",
+                &response.request_id, &request.request_id
+            )));
+        }
+        let request_id = hex_bytes(&request.request_id)
+            .map_err(|_| Error::SignerFailure("request ID is not hex-encoded".into()))?;
+        let signed_tx = decode_tx(&response.tx)?;
+        let hash = signing_hash(RESPONSE_DOMAIN, &request_id, &signed_tx);
+        check_signature(&hash, &response.signature, &self.public_key)?;
+        if !is_signed_version_of(&signed_tx, unsigned_tx) {
+            return Err(Error::SignerFailure(
+                "signed transaction differs from the one sent".into(),
+            ));
+        }
+        signed_tx.verify().map_err(|e| {
+            Error::SignerFailure(format!("invalid transaction signature: {:?}", &e))
+        })?;
+        Ok(signed_tx)
+    }
+}
+
+impl CommitSigner for RemoteCommitSigner {
+    fn get_public_key(&self) -> StacksPublicKey {
+        self.public_key.clone()
+    }
+
+    fn sign_commit(&self, unsigned_tx: &StacksTransaction) -> Result<StacksTransaction, Error> {
+        let request = RemoteSignRequest::new(unsigned_tx, &self.auth_key)?;
+        let unreachable = |e: reqwest::Error| Error::SignerFailure(e.to_string());
+        let response = reqwest::blocking::Client::builder()
+            .timeout(REMOTE_SIGNER_TIMEOUT)
+            .build()
+            .map_err(unreachable)?
+            .post(&self.url)
+            .json(&request)
+            .send()
+            .map_err(unreachable)?;
+        if !response.status().is_success() {
+            let status = response.status();
+            let reason = response.text().unwrap_or_default();
+            return Err(Error::SignerFailure(format!(
+                "remote signer refused to sign ({}): {}",
+                status, reason
+            )));
+        }
+        let answer: RemoteSignResponse = response.json().map_err(unreachable)?;
+        self.check_response(&request, unsigned_tx, &answer)
+    }
+}
+
+/// Signs commits with a fixed key and records each transaction it signs, or refuses to sign
+/// once `refuse` is set.
+#[cfg(test)]
+pub struct TestCommitSigner {
+    secret_key: StacksPrivateKey,
+    pub signed: Mutex<Vec<StacksTransaction>>,
+    pub refuse: AtomicBool,
+}
+
+#[cfg(test)]
+impl TestCommitSigner {
+    pub fn new(secret_key: StacksPrivateKey) -> TestCommitSigner {
+        TestCommitSigner {
+            secret_key,
+            signed: Mutex::new(vec![]),
+            refuse: AtomicBool::new(false),
+        }
+    }
+}
+
+#[cfg(test)]
+impl CommitSigner for TestCommitSigner {
+    fn get_public_key(&self) -> StacksPublicKey {
+        StacksPublicKey::from_private(&self.secret_key)
+    }
+
+    fn sign_commit(&self, unsigned_tx: &StacksTransaction) -> Result<StacksTransaction, Error> {
+        if self.refuse.load(Ordering::SeqCst) {
+            return Err(Error::SignerFailure("test signer refused to sign".into()));
+        }
+        let signed_tx = sign_origin(unsigned_tx, &self.secret_key)?;
+        self.signed.lock().unwrap().push(signed_tx.clone());
+        Ok(signed_tx)
+    }
+}
+
+/// Make the signer `[miner] signer` selects.  The local signer signs with `mining_key`, the
+/// node's current mining key.
+pub fn make_commit_signer(
+    config: &MinerConfig,
+    mining_key: &StacksPrivateKey,
+) -> Arc<dyn CommitSigner> {
+    match config.signer {
+        CommitSignerConfig::Local => Arc::new(LocalCommitSigner::new(mining_key.clone())),
+        CommitSignerConfig::Remote {
+            ref url,
+            ref public_key,
+            ref auth_key,
+        } => Arc::new(RemoteCommitSigner::new(
+            url,
+            public_key.clone(),
+            auth_key.clone(),
+        )),
+    }
+}
@@ -3,9 +3,8 @@ use serde_json::json;
 use stacks::address::AddressHashMode;
 use stacks::chainstate::stacks::miner::SignedProposal;
 use stacks::chainstate::stacks::{
-    StacksPrivateKey, StacksPublicKey, StacksTransaction, StacksTransactionSigner, TransactionAuth,
-    TransactionContractCall, TransactionPostConditionMode, TransactionSpendingCondition,
-    TransactionVersion,
+    StacksPublicKey, StacksTransaction, TransactionAuth, TransactionContractCall,
+    TransactionPostConditionMode, TransactionSpendingCondition, TransactionVersion,
 };
 use stacks::net::http::HttpBlockProposalRejected;
 use stacks::net::RPCFeeEstimateResponse;
@@ -18,13 +17,13 @@ use stacks_common::types::StacksEpochId;
 use stacks_common::util::hash::{to_hex, Sha512Trunc256Sum};
 
 use crate::config::BurnchainConfig;
-use crate::operations::BurnchainOpSigner;
 use crate::stacks_common::codec::StacksMessageCodec;
 
+use super::commit_signer::CommitSigner;
 use super::ClaritySignature;
 
 /// Default fee to pay for a miner commitment, in case no estimate is available.
-const DEFAULT_MINER_COMMITMENT_FEE: u64 = 100_000u64;
+pub(crate) const DEFAULT_MINER_COMMITMENT_FEE: u64 = 100_000u64;
 
 pub trait Layer1Committer {
     /// Return the number of signatures that need to be included alongside a commit transaction
@@ -46,7 +45,7 @@ pub trait Layer1Committer {
         target_height: u64,
         withdrawal_merkle_root: Sha512Trunc256Sum,
         signatures: Vec<ClaritySignature>,
-        op_signer: &mut BurnchainOpSigner,
+        signer: &dyn CommitSigner,
     ) -> Result<StacksTransaction, Error>;
 }
 
@@ -84,9 +83,10 @@ pub enum Error {
     NoSuchParticipant,
     BlockProposalRequest(String),
     BlockProposalRejected(String),
+    SignerFailure(String),
 }
 
-fn l1_addr_from_signer(is_mainnet: bool, signer: &BurnchainOpSigner) -> StacksAddress {
+fn l1_addr_from_signer(is_mainnet: bool, signer: &dyn CommitSigner) -> StacksAddress {
     let hash_mode = AddressHashMode::SerializeP2PKH;
     let addr_version = if is_mainnet {
         hash_mode.to_version_mainnet()
@@ -97,7 +97,7 @@ fn l1_addr_from_signer(is_mainnet: bool, signer: &BurnchainOpSigner) -> StacksAd
         .expect("Failed to make Stacks address from public key")
 }
 
-fn l1_get_nonce(l1_rpc_interface: &str, address: &StacksAddress) -> Result<u64, Error> {
+pub(crate) fn l1_get_nonce(l1_rpc_interface: &str, address: &StacksAddress) -> Result<u64, Error> {
     let url = format!("{}/v2/accounts/{}?proof=0", l1_rpc_interface, address);
     let response_json: RpcAccountResponse = reqwest::blocking::get(url)
         .map_err(|e| Error::NonceGetFailure(e.to_string()))?
@@ -150,7 +150,8 @@ pub fn compute_fee_from_response_and_transaction(
             Ok(adjusted)
         }
         Err(e) => {
-            warn!(
+            component_warn!(
+                Burnchain,
                 "Failure getting response from L1 on recommended fee rate: {:?}",
                 &e
             );
@@ -182,7 +183,11 @@ fn calculate_l1_fee_for_transaction(
     let payload_hex = format!("0x{}", to_hex(&payload_data));
     let body = json!({ "transaction_payload": payload_hex.clone() });
     let res = client.post(&path).json(&body).send().map_err(|e| {
-        warn!("Error getting response from L1 about fee rate: {:?}", &e);
+        component_warn!(
+            Burnchain,
+            "Error getting response from L1 about fee rate: {:?}",
+            &e
+        );
         FeeCalculationError::L1ResponseFailure
     })?;
     let json_response: reqwest::Result<RPCFeeEstimateResponse> =
@@ -203,6 +208,7 @@ impl std::fmt::Display for Error {
                 write!(f, "Failure during block proposal request: {}", e)
             }
             Error::BlockProposalRejected(e) => write!(f, "Rejected block proposal: {}", e),
+            Error::SignerFailure(e) => write!(f, "Failed to sign commitment: {}", e),
             Error::BadCommitment => write!(f, "Submitted commitment contents are not valid"),
             Error::NoSuchParticipant => write!(
                 f,
@@ -227,9 +233,10 @@ impl MultiPartyCommitter {
         }
     }
 
+    /// Make the unsigned commit transaction
     fn make_mine_contract_call(
         &self,
-        sender: &StacksPrivateKey,
+        sender: &StacksPublicKey,
         sender_nonce: u64,
         tx_fee: u64,
         commit_to: BlockHeaderHash,
@@ -285,10 +292,9 @@ impl MultiPartyCommitter {
             function_args: vec![block_data_val.into(), signatures_val],
         };
 
-        let mut sender_spending_condition = TransactionSpendingCondition::new_singlesig_p2pkh(
-            StacksPublicKey::from_private(sender),
-        )
-        .expect("Failed to create p2pkh spending condition from public key.");
+        let mut sender_spending_condition =
+            TransactionSpendingCondition::new_singlesig_p2pkh(sender.clone())
+                .expect("Failed to create p2pkh spending condition from public key.");
         sender_spending_condition.set_nonce(sender_nonce);
         sender_spending_condition.set_tx_fee(tx_fee);
         let auth = TransactionAuth::Standard(sender_spending_condition);
@@ -298,12 +304,7 @@ impl MultiPartyCommitter {
         unsigned_tx.post_condition_mode = TransactionPostConditionMode::Allow;
         unsigned_tx.chain_id = self.config.chain_id;
 
-        let mut tx_signer = StacksTransactionSigner::new(&unsigned_tx);
-        tx_signer.sign_origin(sender).unwrap();
-
-        Ok(tx_signer
-            .get_tx()
-            .expect("Failed to get signed transaction from signer"))
+        Ok(unsigned_tx)
     }
 
     pub fn make_commit_tx(
@@ -314,19 +315,20 @@ impl MultiPartyCommitter {
         target_height: u64,
         withdrawal_merkle_root: Sha512Trunc256Sum,
         signatures: Vec<ClaritySignature>,
-        op_signer: &mut BurnchainOpSigner,
+        signer: &dyn CommitSigner,
     ) -> Result<StacksTransaction, Error> {
         // figure out the miner's nonce
-        let miner_address = l1_addr_from_signer(self.config.is_mainnet(), op_signer);
+        let miner_address = l1_addr_from_signer(self.config.is_mainnet(), signer);
         let nonce = l1_get_nonce(&self.config.get_rpc_url(), &miner_address).map_err(|e| {
-            error!("Failed to obtain miner nonce: {}", e);
+            component_error!(Burnchain, "Failed to obtain miner nonce: {}", e);
             e
         })?;
+        let sender = signer.get_public_key();
 
         // fee estimate
         let pre_transaction = self
             .make_mine_contract_call(
-                op_signer.get_sk(),
+                &sender,
                 nonce,
                 DEFAULT_MINER_COMMITMENT_FEE,
                 committed_block_hash,
@@ -337,31 +339,44 @@ impl MultiPartyCommitter {
                 signatures.clone(),
             )
             .map_err(|e| {
-                error!("Failed to construct contract call operation: {}", e);
+                component_error!(
+                    Burnchain,
+                    "Failed to construct contract call operation: {}",
+                    e
+                );
                 e
             })?;
         let computed_fee =
             calculate_l1_fee_for_transaction(&pre_transaction, &self.config.get_rpc_url())
                 .map_err(|e| {
-                    error!("Failed to get L1 fee estimate: {:?}", &e);
+                    component_error!(Burnchain, "Failed to get L1 fee estimate: {:?}", &e);
                     e
                 })
                 .unwrap_or(DEFAULT_MINER_COMMITMENT_FEE);
 
-        // create the call
-        self.make_mine_contract_call(
-            op_signer.get_sk(),
-            nonce,
-            computed_fee,
-            committed_block_hash,
-            committed_block_height,
-            target_tip,
-            target_height,
-            withdrawal_merkle_root,
-            signatures,
-        )
-        .map_err(|e| {
-            error!("Failed to construct contract call operation: {}", e);
+        // create the call, and have it signed
+        let unsigned_tx = self
+            .make_mine_contract_call(
+                &sender,
+                nonce,
+                computed_fee,
+                committed_block_hash,
+                committed_block_height,
+                target_tip,
+                target_height,
+                withdrawal_merkle_root,
+                signatures,
+            )
+            .map_err(|e| {
+                component_error!(
+                    Burnchain,
+                    "Failed to construct contract call operation: {}",
+                    e
+                );
+                e
+            })?;
+        signer.sign_commit(&unsigned_tx).map_err(|e| {
+            component_error!(Burnchain, "Failed to sign block-commit: {}", e);
             e
         })
     }
@@ -441,7 +456,7 @@ impl Layer1Committer for MultiPartyCommitter {
         target_height: u64,
         withdrawal_merkle_root: Sha512Trunc256Sum,
         signatures: Vec<ClaritySignature>,
-        op_signer: &mut BurnchainOpSigner,
+        signer: &dyn CommitSigner,
     ) -> Result<StacksTransaction, Error> {
         self.make_commit_tx(
             committed_block_hash,
@@ -450,7 +465,7 @@ impl Layer1Committer for MultiPartyCommitter {
             target_height,
             withdrawal_merkle_root,
             signatures,
-            op_signer,
+            signer,
         )
     }
 }
@@ -468,7 +483,7 @@ impl Layer1Committer for DirectCommitter {
         target_height: u64,
         withdrawal_merkle_root: Sha512Trunc256Sum,
         _signatures: Vec<ClaritySignature>,
-        op_signer: &mut BurnchainOpSigner,
+        signer: &dyn CommitSigner,
     ) -> Result<StacksTransaction, Error> {
         self.make_commit_tx(
             committed_block_hash,
@@ -476,7 +491,7 @@ impl Layer1Committer for DirectCommitter {
             target_tip,
             target_height,
             withdrawal_merkle_root,
-            op_signer,
+            signer,
         )
     }
 
@@ -490,9 +505,10 @@ impl Layer1Committer for DirectCommitter {
 }
 
 impl DirectCommitter {
+    /// Make the unsigned commit transaction
     fn make_mine_contract_call(
         &self,
-        sender: &StacksPrivateKey,
+        sender: &StacksPublicKey,
         sender_nonce: u64,
         tx_fee: u64,
         commit_to: BlockHeaderHash,
@@ -526,10 +542,9 @@ impl DirectCommitter {
             ],
         };
 
-        let mut sender_spending_condition = TransactionSpendingCondition::new_singlesig_p2pkh(
-            StacksPublicKey::from_private(sender),
-        )
-        .expect("Failed to create p2pkh spending condition from public key.");
+        let mut sender_spending_condition =
+            TransactionSpendingCondition::new_singlesig_p2pkh(sender.clone())
+                .expect("Failed to create p2pkh spending condition from public key.");
         sender_spending_condition.set_nonce(sender_nonce);
         sender_spending_condition.set_tx_fee(tx_fee);
         let auth = TransactionAuth::Standard(sender_spending_condition);
@@ -539,12 +554,7 @@ impl DirectCommitter {
         unsigned_tx.post_condition_mode = TransactionPostConditionMode::Allow;
         unsigned_tx.chain_id = self.config.chain_id;
 
-        let mut tx_signer = StacksTransactionSigner::new(&unsigned_tx);
-        tx_signer.sign_origin(sender).unwrap();
-
-        Ok(tx_signer
-            .get_tx()
-            .expect("Failed to get signed transaction from signer"))
+        Ok(unsigned_tx)
     }
 
     pub fn make_commit_tx(
@@ -554,19 +564,20 @@ impl DirectCommitter {
         target_tip: BurnchainHeaderHash,
         target_height: u64,
         withdrawal_merkle_root: Sha512Trunc256Sum,
-        op_signer: &mut BurnchainOpSigner,
+        signer: &dyn CommitSigner,
     ) -> Result<StacksTransaction, Error> {
         // figure out the miner's nonce
-        let miner_address = l1_addr_from_signer(self.config.is_mainnet(), op_signer);
+        let miner_address = l1_addr_from_signer(self.config.is_mainnet(), signer);
         let nonce = l1_get_nonce(&self.config.get_rpc_url(), &miner_address).map_err(|e| {
-            error!("Failed to obtain miner nonce: {}", e);
+            component_error!(Burnchain, "Failed to obtain miner nonce: {}", e);
             e
         })?;
+        let sender = signer.get_public_key();
 
         // calculate a fee estimate
         let pre_transaction = self
             .make_mine_contract_call(
-                op_signer.get_sk(),
+                &sender,
                 nonce,
                 DEFAULT_MINER_COMMITMENT_FEE,
                 committed_block_hash,
@@ -576,30 +587,43 @@ impl DirectCommitter {
                 withdrawal_merkle_root,
             )
             .map_err(|e| {
-                error!("Failed to construct contract call operation: {}", e);
+                component_error!(
+                    Burnchain,
+                    "Failed to construct contract call operation: {}",
+                    e
+                );
                 e
             })?;
         let computed_fee =
             calculate_l1_fee_for_transaction(&pre_transaction, &self.config.get_rpc_url())
                 .map_err(|e| {
-                    error!("Failed to get L1 fee estimate: {:?}", &e);
+                    component_error!(Burnchain, "Failed to get L1 fee estimate: {:?}", &e);
                     e
                 })
                 .unwrap_or(DEFAULT_MINER_COMMITMENT_FEE);
 
-        // create the call
-        self.make_mine_contract_call(
-            op_signer.get_sk(),
-            nonce,
-            computed_fee,
-            committed_block_hash,
-            committed_block_height,
-            target_tip,
-            target_height,
-            withdrawal_merkle_root,
-        )
-        .map_err(|e| {
-            error!("Failed to construct contract call operation: {}", e);
+        // create the call, and have it signed
+        let unsigned_tx = self
+            .make_mine_contract_call(
+                &sender,
+                nonce,
+                computed_fee,
+                committed_block_hash,
+                committed_block_height,
+                target_tip,
+                target_height,
+                withdrawal_merkle_root,
+            )
+            .map_err(|e| {
+                component_error!(
+                    Burnchain,
+                    "Failed to construct contract call operation: {}",
+                    e
+                );
+                e
+            })?;
+        signer.sign_commit(&unsigned_tx).map_err(|e| {
+            component_error!(Burnchain, "Failed to sign block-commit: {}", e);
             e
         })
     }
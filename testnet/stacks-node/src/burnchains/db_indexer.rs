@@ -162,7 +162,7 @@ fn process_reorg(
     old_tip: &BurnBlockIndexRow,
 ) -> Result<u64, BurnchainError> {
     // Step 1: Set `is_canonical` to true for ancestors of the new tip.
-    info!(
+    component_info!(Burnchain,
         "Processing Stacks (L1) chain reorg";
         "old_tip_id" => %old_tip.header_hash,
         "old_tip_height" => old_tip.height,
@@ -235,13 +235,13 @@ impl BurnchainChannel for DBBurnBlockInputChannel {
     /// Add `new_block` to the `block_index` database.
     fn push_block(&self, new_block: NewBlock) -> Result<(), BurnchainError> {
         if self.config.first_burn_header_height > new_block.block_height {
-            debug!("BurnchainChannel skipping new_block event before first_burn_header_height";
+            component_debug!(Burnchain, "BurnchainChannel skipping new_block event before first_burn_header_height";
                     "first_burn_height" => %self.config.first_burn_header_height,
                     "new_block_height" => new_block.block_height,
             );
             return Ok(());
         }
-        debug!("BurnchainChannel: push_block"; "new_block_ht" => new_block.block_height, "new_block_id" => %new_block.index_block_hash);
+        component_debug!(Burnchain, "BurnchainChannel: push_block"; "new_block_ht" => new_block.block_height, "new_block_id" => %new_block.index_block_hash);
         // Re-open the connection.
         let open_flags = OpenFlags::SQLITE_OPEN_READ_WRITE;
         let mut connection = sqlite_open(&self.output_db_path, open_flags, true)?;
@@ -289,7 +289,7 @@ impl BurnchainChannel for DBBurnBlockInputChannel {
             "INSERT INTO block_index (height, header_hash, parent_header_hash, time_stamp, is_canonical, block) VALUES (?, ?, ?, ?, ?, ?)",
             params,
         ) {
-            warn!("Failed to write block header to block index, probably a duplicate event"; "error" => ?e);
+            component_warn!(Burnchain, "Failed to write block header to block index, probably a duplicate event"; "error" => ?e);
             return Ok(())
         }
 
@@ -443,7 +443,11 @@ impl DBBurnchainIndexer {
         config: BurnchainConfig,
         readwrite: bool,
     ) -> Result<DBBurnchainIndexer, Error> {
-        debug!("Creating DBBurnchainIndexer with config: {:?}", &config);
+        component_debug!(
+            Burnchain,
+            "Creating DBBurnchainIndexer with config: {:?}",
+            &config
+        );
 
         let indexer_base_db_path = create_indexer_base_db_path(burnstate_db_path);
         let connection = connect_db_and_maybe_instantiate(&indexer_base_db_path, readwrite)?;
@@ -454,6 +458,12 @@ impl DBBurnchainIndexer {
             connection,
         })
     }
+
+    /// The L1 index block hash and height of the highest L1 block this indexer has received
+    pub fn get_highest_l1_block(&self) -> Result<Option<(StacksBlockId, u64)>, BurnchainError> {
+        Ok(get_canonical_chain_tip(&self.connection)?
+            .map(|row| (StacksBlockId(row.header_hash.0), row.height)))
+    }
 }
 
 pub struct DBBurnchainParser {
@@ -5,8 +5,8 @@ use std::time::Instant;
 
 use clarity::vm::errors::{Error as ClarityError, RuntimeErrorType as ClarityRuntimeError};
 use clarity::vm::types::{
-    SequenceSubtype, StringSubtype, StringUTF8Length, TupleTypeSignature, TypeSignature,
-    Value as ClarityValue,
+    QualifiedContractIdentifier, SequenceSubtype, StringSubtype, StringUTF8Length,
+    TupleTypeSignature, TypeSignature, Value as ClarityValue,
 };
 use stacks::burnchains::db::BurnchainDB;
 use stacks::burnchains::events::NewBlock;
@@ -17,21 +17,28 @@ use stacks::chainstate::coordinator::comm::CoordinatorChannels;
 use stacks::chainstate::stacks::index::ClarityMarfTrieId;
 use stacks::chainstate::stacks::miner::SignedProposal;
 use stacks::chainstate::stacks::StacksTransaction;
+use stacks::chainstate::stacks::{
+    C32_ADDRESS_VERSION_MAINNET_MULTISIG, C32_ADDRESS_VERSION_MAINNET_SINGLESIG,
+    C32_ADDRESS_VERSION_TESTNET_MULTISIG, C32_ADDRESS_VERSION_TESTNET_SINGLESIG,
+};
 use stacks::codec::StacksMessageCodec;
-use stacks::core::StacksEpoch;
-use stacks::net::CallReadOnlyRequestBody;
+use stacks::core::{StacksEpoch, LAYER_1_CHAIN_ID_MAINNET, LAYER_1_CHAIN_ID_TESTNET};
+use stacks::net::{AccountEntryResponse, CallReadOnlyRequestBody, RPCPeerInfoData};
 use stacks::util::hash::hex_bytes;
 use stacks::util::sleep_ms;
 use stacks::util_lib::boot::boot_code_addr;
 use stacks_common::types::chainstate::{BlockHeaderHash, BurnchainHeaderHash, StacksBlockId};
 
-use super::commitment::{Layer1Committer, MultiPartyCommitter};
+use super::commit_fees::CommitFeeDB;
+use super::commit_manager::{resign_with_fee, CommitManager};
+use super::commit_signer::CommitSigner;
+use super::commitment::{l1_get_nonce, Layer1Committer, MultiPartyCommitter};
 use super::db_indexer::DBBurnchainIndexer;
+use super::l1_rpc::L1RpcFailover;
 use super::{burnchain_from_config, BurnchainChannel, ClaritySignature, Error};
 
 use crate::burnchains::commitment::DirectCommitter;
-use crate::config::CommitStrategy;
-use crate::operations::BurnchainOpSigner;
+use crate::config::{BurnchainConfig, CommitStrategy};
 use crate::util::hash::Sha512Trunc256Sum;
 use crate::{BurnchainController, BurnchainTip, Config};
 
@@ -54,8 +61,14 @@ pub struct L1Controller {
     chain_tip: Option<BurnchainTip>,
 
     committer: Box<dyn Layer1Committer + Send>,
+    commit_manager: CommitManager,
 
     l1_contract_check_passed: bool,
+
+    rpc_failover: L1RpcFailover,
+
+    /// the L1 fees of the commits this node sends; opened when the first one is sent
+    commit_fees: Option<CommitFeeDB>,
 }
 
 /// Semver version of a Clarity contract
@@ -157,6 +170,154 @@ impl BurnchainChannel for L1Channel {
     }
 }
 
+/// The functions of the subnet contract which the node calls, or whose events it follows, and
+/// whether each is read-only
+const SUBNET_CONTRACT_FUNCTIONS: &[(&str, bool)] = &[
+    ("get-version", true),
+    ("commit-block", false),
+    ("deposit-stx", false),
+    ("withdraw-stx", false),
+    ("deposit-ft-asset", false),
+    ("withdraw-ft-asset", false),
+    ("deposit-nft-asset", false),
+    ("withdraw-nft-asset", false),
+    ("register-new-ft-contract", false),
+    ("register-new-nft-contract", false),
+];
+
+/// The parts of a contract's interface, as served at `/v2/contracts/interface`, which the node
+/// checks
+#[derive(Deserialize)]
+pub struct L1ContractInterface {
+    pub functions: Vec<L1ContractFunction>,
+}
+
+#[derive(Deserialize)]
+pub struct L1ContractFunction {
+    pub name: String,
+    /// `public`, `read_only` or `private`
+    pub access: String,
+}
+
+/// Describe the functions of the subnet contract which `interface` lacks, or which it defines
+/// with the wrong access.  Empty if `interface` is usable.
+pub fn missing_subnet_contract_functions(interface: &L1ContractInterface) -> Vec<String> {
+    SUBNET_CONTRACT_FUNCTIONS
+        .iter()
+        .filter_map(|(name, read_only)| {
+            let expected_access = if *read_only { "read_only" } else { "public" };
+            match interface.functions.iter().find(|f| f.name == *name) {
+                None => Some(name.to_string()),
+                Some(f) if f.access != expected_access => Some(format!(
+                    "{} (is {}, should be {})",
+                    name, f.access, expected_access
+                )),
+                Some(_) => None,
+            }
+        })
+        .collect()
+}
+
+/// Check that the address `contract_id` is deployed at belongs to the same network (mainnet or
+/// testnet) as the L1 node whose chain ID is `l1_chain_id`.  L1 nodes with any other chain ID
+/// are not checked.
+pub fn check_contract_address_network(
+    contract_id: &QualifiedContractIdentifier,
+    l1_chain_id: u32,
+) -> Result<(), Error> {
+    let address_is_mainnet = match contract_id.issuer.0 {
+        C32_ADDRESS_VERSION_MAINNET_SINGLESIG | C32_ADDRESS_VERSION_MAINNET_MULTISIG => true,
+        C32_ADDRESS_VERSION_TESTNET_SINGLESIG | C32_ADDRESS_VERSION_TESTNET_MULTISIG => false,
+        _ => {
+            return Err(Error::BurnchainContractInterface(format!(
+                "contract {} is not at a Stacks address",
+                contract_id
+            )));
+        }
+    };
+    let l1_is_mainnet = match l1_chain_id {
+        LAYER_1_CHAIN_ID_MAINNET => true,
+        LAYER_1_CHAIN_ID_TESTNET => false,
+        _ => return Ok(()),
+    };
+    if address_is_mainnet != l1_is_mainnet {
+        let network_name = |mainnet| if mainnet { "mainnet" } else { "testnet" };
+        return Err(Error::BurnchainContractInterface(format!(
+            "contract {} is at a {} address, but the L1 node is a {} node",
+            contract_id,
+            network_name(address_is_mainnet),
+            network_name(l1_is_mainnet)
+        )));
+    }
+    Ok(())
+}
+
+/// Check that the L1 node's RPC interface is reachable, and that the subnet contract this node
+/// is configured to follow is deployed there and has the functions the node uses.  Returns the
+/// height of the L1 chain tip.
+///
+/// A contract which can never be the subnet contract, because it is at an address of the other
+/// network or lacks some of those functions, is an `Error::BurnchainContractInterface`.  A
+/// contract which is not deployed (yet) is an `Error::BurnchainContractCheck`, which says
+/// whether its address has sent any transactions, to tell a wrong address from a deployment
+/// which has not confirmed yet.
+pub fn check_l1_connection(config: &BurnchainConfig) -> Result<u64, Error> {
+    let client = reqwest::blocking::Client::new();
+    let info = client
+        .get(format!("{}/v2/info", config.get_rpc_url()))
+        .send()?
+        .error_for_status()?
+        .json::<RPCPeerInfoData>()?;
+
+    check_contract_address_network(&config.contract_identifier, info.network_id)?;
+
+    let contract_url = format!(
+        "{}/v2/contracts/interface/{}/{}",
+        config.get_rpc_url(),
+        &config.contract_identifier.issuer,
+        &config.contract_identifier.name
+    );
+    let response = client.get(contract_url).send()?;
+    if response.status() == reqwest::StatusCode::NOT_FOUND {
+        let account_url = format!(
+            "{}/v2/accounts/{}?proof=0",
+            config.get_rpc_url(),
+            &config.contract_identifier.issuer
+        );
+        let account = client
+            .get(account_url)
+            .send()?
+            .error_for_status()?
+            .json::<AccountEntryResponse>()?;
+        let hint = if account.nonce == 0 {
+            format!(
+                "{} has not sent any transactions, so the address is likely wrong",
+                &config.contract_identifier.issuer
+            )
+        } else {
+            format!(
+                "{} has sent {} transactions, so either the contract name is wrong or its deployment has not confirmed yet",
+                &config.contract_identifier.issuer, account.nonce
+            )
+        };
+        return Err(Error::BurnchainContractCheck(format!(
+            "contract {} does not exist on the L1 node; {}",
+            &config.contract_identifier, hint
+        )));
+    }
+    let interface = response.error_for_status()?.json::<L1ContractInterface>()?;
+    let missing = missing_subnet_contract_functions(&interface);
+    if !missing.is_empty() {
+        return Err(Error::BurnchainContractInterface(format!(
+            "contract {} is not a subnet contract; it lacks {}",
+            &config.contract_identifier,
+            missing.join(", ")
+        )));
+    }
+
+    Ok(info.stacks_tip_height)
+}
+
 impl L1Controller {
     pub fn new(config: Config, coordinator: CoordinatorChannels) -> Result<L1Controller, Error> {
         let indexer = DBBurnchainIndexer::new(
@@ -181,6 +342,8 @@ impl L1Controller {
                 other_participants.clone(),
             )),
         };
+        let rpc_failover = L1RpcFailover::new(&config.burnchain);
+        let commit_manager = CommitManager::new(&config.burnchain);
         let l1_controller = L1Controller {
             burnchain,
             config,
@@ -191,7 +354,10 @@ impl L1Controller {
             coordinator,
             chain_tip: None,
             committer,
+            commit_manager,
             l1_contract_check_passed: false,
+            rpc_failover,
+            commit_fees: None,
         };
         Ok(l1_controller)
     }
@@ -240,7 +406,7 @@ impl L1Controller {
                 }
                 Err(e) => {
                     // keep trying
-                    error!("Unable to sync with burnchain: {}", e);
+                    component_error!(Burnchain, "Unable to sync with burnchain: {}", e);
                     match e {
                         BurnchainError::CoordinatorClosed => return Err(Error::CoordinatorClosed),
                         BurnchainError::TrySyncAgain => {
@@ -269,7 +435,7 @@ impl L1Controller {
         };
 
         self.chain_tip = Some(burnchain_tip.clone());
-        debug!("Done receiving blocks");
+        component_debug!(Burnchain, "Done receiving blocks");
 
         Ok((burnchain_tip, burnchain_height))
     }
@@ -285,14 +451,52 @@ impl L1Controller {
         self.config.burnchain.get_rpc_url()
     }
 
-    pub fn l1_submit_tx(&self, tx: StacksTransaction) -> Result<Txid, Error> {
+    /// Check the L1 RPC endpoint in use, and fail over to another configured endpoint if it's
+    /// unhealthy.  Unless `force` is set, this only checks periodically.
+    fn check_l1_rpc(&mut self, force: bool) -> Result<(), Error> {
+        // the first L1 block's hash isn't a real L1 block, so it can't be looked up on the L1
+        let first_height = self.config.burnchain.first_burn_header_height;
+        let l1_tip = self
+            .indexer
+            .get_highest_l1_block()?
+            .filter(|(_, height)| *height > first_height);
+        if force {
+            self.rpc_failover.check(l1_tip)
+        } else {
+            self.rpc_failover.maybe_check(l1_tip)
+        }
+    }
+
+    fn post_l1_tx(&self, tx: &StacksTransaction) -> reqwest::Result<reqwest::blocking::Response> {
         let client = reqwest::blocking::Client::new();
         let url = format!("{}/v2/transactions", self.l1_rpc_interface());
-        let res = client
+        client
             .post(url)
             .header("Content-Type", "application/octet-stream")
             .body(tx.serialize_to_vec())
-            .send()?;
+            .send()
+    }
+
+    /// Submit `tx` to the L1.  If the L1 RPC endpoint can't be reached, fail over to another
+    /// configured endpoint and try once more.
+    pub fn l1_submit_tx(&mut self, tx: StacksTransaction) -> Result<Txid, Error> {
+        let res = match self.post_l1_tx(&tx) {
+            Ok(res) if !res.status().is_server_error() => res,
+            Ok(res) => {
+                component_warn!(
+                    Burnchain,
+                    "L1 RPC endpoint failed to accept transaction: {}",
+                    res.status()
+                );
+                self.check_l1_rpc(true)?;
+                self.post_l1_tx(&tx)?
+            }
+            Err(e) => {
+                component_warn!(Burnchain, "Failed to send transaction to L1: {}", &e);
+                self.check_l1_rpc(true)?;
+                self.post_l1_tx(&tx)?
+            }
+        };
 
         if res.status().is_success() {
             let res: String = res.json().unwrap();
@@ -302,6 +506,109 @@ impl L1Controller {
         }
     }
 
+    /// Check on the block-commits this node broadcast, now that the L1 is at `l1_height`.
+    /// Confirmed commits are reported and dropped.  Commits that have waited too long are
+    /// rebroadcast with the same nonce and a higher fee, up to the configured cap.
+    /// Record the fee of the commit `tx`, broadcast as `txid`, for rewards reports
+    fn record_commit_fee(&mut self, tx: &StacksTransaction, txid: &Txid, l1_height: u64) {
+        if self.commit_fees.is_none() {
+            match CommitFeeDB::open(&self.config.get_commit_fees_db_path()) {
+                Ok(db) => self.commit_fees = Some(db),
+                Err(e) => {
+                    component_warn!(Burnchain, "Failed to open the commit fee DB: {:?}", &e);
+                    return;
+                }
+            }
+        }
+        if let Some(ref db) = self.commit_fees {
+            if let Err(e) = db.record_commit(txid, tx.get_tx_fee(), l1_height) {
+                component_warn!(
+                    Burnchain,
+                    "Failed to record the fee of block-commit {}: {:?}",
+                    txid,
+                    &e
+                );
+            }
+        }
+    }
+
+    fn manage_pending_commits(&mut self, l1_height: u64) {
+        if !self.commit_manager.has_pending() {
+            return;
+        }
+
+        for sender in self.commit_manager.senders() {
+            let account_nonce = match l1_get_nonce(&self.l1_rpc_interface(), &sender) {
+                Ok(nonce) => nonce,
+                Err(e) => {
+                    component_warn!(Burnchain, "Failed to check for confirmed commits: {}", e);
+                    return;
+                }
+            };
+            for commit in self.commit_manager.confirm(&sender, account_nonce) {
+                let confirm_time = commit.first_broadcast.elapsed();
+                component_info!(
+                    Burnchain,
+                    "Block-commit confirmed on L1";
+                    "txid" => %commit.txid,
+                    "nonce" => commit.nonce(),
+                    "fee" => commit.fee(),
+                    "confirm_time_secs" => confirm_time.as_secs()
+                );
+                stacks::monitoring::update_miner_commit_confirm_time(confirm_time.as_secs());
+            }
+        }
+
+        for commit in self.commit_manager.take_stuck(l1_height) {
+            let (tx, bumped) = match self.commit_manager.bumped_fee(&commit) {
+                Some(fee) => match commit.with_fee(fee) {
+                    Ok(tx) => (tx, true),
+                    Err(e) => {
+                        component_warn!(
+                            Burnchain,
+                            "Failed to re-sign stuck block-commit {}: {}",
+                            &commit.txid,
+                            &e
+                        );
+                        (commit.tx.clone(), false)
+                    }
+                },
+                // already paying the most we're willing to: just make sure the L1 still has it
+                None => (commit.tx.clone(), false),
+            };
+            match self.l1_submit_tx(tx.clone()) {
+                Ok(txid) => {
+                    if bumped {
+                        component_info!(
+                            Burnchain,
+                            "Bumped the fee of a stuck block-commit";
+                            "txid" => %txid,
+                            "replaces" => %commit.txid,
+                            "nonce" => tx.get_origin_nonce(),
+                            "fee" => tx.get_tx_fee()
+                        );
+                        stacks::monitoring::increment_miner_commit_fee_bumps();
+                        self.record_commit_fee(&tx, &txid, l1_height);
+                    }
+                    self.commit_manager.rebroadcast(commit, tx, txid, l1_height);
+                }
+                Err(e) => {
+                    // the L1 rejects a rebroadcast of a transaction it already has, so keep
+                    // waiting on the commit
+                    component_debug!(
+                        Burnchain,
+                        "Failed to rebroadcast block-commit {}: {}",
+                        &commit.txid,
+                        &e
+                    );
+                    let txid = commit.txid.clone();
+                    let tx = commit.tx.clone();
+                    self.commit_manager.rebroadcast(commit, tx, txid, l1_height);
+                }
+            }
+        }
+    }
+
     /// Return the Semver version of the `subnet.clar` contract this node is configured to use
     fn get_l1_contract_version(&self) -> Result<ContractVersion, Error> {
         let burn_conf = &self.config.burnchain;
@@ -411,7 +718,7 @@ impl L1Controller {
                 // Error, transform into `Error::BurnchainContractCheck`
                 Err(e) => Err(Error::BurnchainContractCheck(e.to_string())),
                 Ok(version) => {
-                    info!("Found supported L1 contract version: {version}");
+                    component_info!(Burnchain, "Found supported L1 contract version: {version}");
                     self.l1_contract_check_passed = true;
                     Ok(())
                 }
@@ -446,7 +753,7 @@ impl BurnchainController for L1Controller {
         self.committer
             .propose_block_to(participant_index, proposal)
             .map_err(|e| {
-                warn!("Block proposal failed"; "error" => %e);
+                component_warn!(Burnchain, "Block proposal failed"; "error" => %e);
                 Error::BadCommitment(e)
             })
     }
@@ -459,25 +766,47 @@ impl BurnchainController for L1Controller {
         target_height: u64,
         withdrawal_merkle_root: Sha512Trunc256Sum,
         signatures: Vec<super::ClaritySignature>,
-        op_signer: &mut BurnchainOpSigner,
+        signer: Arc<dyn CommitSigner>,
     ) -> Result<Txid, Error> {
         self.l1_contract_ok()?;
 
-        let tx = self.committer.make_commit_tx(
+        let mut tx = self.committer.make_commit_tx(
             committed_block_hash,
             committed_block_height,
             target_tip,
             target_height,
             withdrawal_merkle_root,
             signatures,
-            op_signer,
+            signer.as_ref(),
         )?;
 
-        self.l1_submit_tx(tx)
+        // an earlier commit with this nonce may still be pending, in which case the L1 only
+        // accepts this one if it pays more
+        if let Some(fee) = self.commit_manager.replacement_fee(
+            &tx.origin_address(),
+            tx.get_origin_nonce(),
+            tx.get_tx_fee(),
+        ) {
+            tx = resign_with_fee(&tx, fee, signer.as_ref())?;
+        }
+
+        let txid = self.l1_submit_tx(tx.clone())?;
+        let l1_height = self.indexer.get_highest_header_height().unwrap_or(0);
+        self.record_commit_fee(&tx, &txid, l1_height);
+        self.commit_manager
+            .track(tx, txid.clone(), signer, l1_height);
+        Ok(txid)
     }
 
     fn sync(&mut self, target_block_height_opt: Option<u64>) -> Result<(BurnchainTip, u64), Error> {
-        self.receive_blocks(true, target_block_height_opt)
+        // L1 blocks arrive through the event observer, so a failed RPC check doesn't stop syncing
+        if let Err(e) = self.check_l1_rpc(false) {
+            component_warn!(Burnchain, "No usable L1 RPC endpoint: {}", &e);
+        }
+        let (burnchain_tip, burnchain_height) =
+            self.receive_blocks(true, target_block_height_opt)?;
+        self.manage_pending_commits(burnchain_height);
+        Ok((burnchain_tip, burnchain_height))
     }
 
     fn get_chain_tip(&self) -> BurnchainTip {
@@ -565,6 +894,15 @@ impl BurnchainController for L1Controller {
         }
     }
 
+    fn reconnect(&mut self) -> Result<(), Error> {
+        // fail over to another L1 endpoint if the active one is down, then make sure the one in
+        // use is reachable and still has the subnet contract
+        self.check_l1_rpc(true)?;
+        check_l1_connection(&self.config.burnchain)?;
+        self.l1_contract_check_passed = false;
+        Ok(())
+    }
+
     #[cfg(test)]
     fn bootstrap_chain(&mut self, _blocks_count: u64) {
         todo!()
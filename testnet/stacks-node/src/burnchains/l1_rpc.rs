@@ -0,0 +1,159 @@
+use std::time::{Duration, Instant};
+
+use stacks::net::RPCPeerInfoData;
+use stacks::types::chainstate::StacksBlockId;
+use stacks::util::hash::Sha256Sum;
+
+use super::Error;
+use crate::config::BurnchainConfig;
+
+/// How long to wait on an L1 endpoint's health check before treating it as down
+const HEALTH_CHECK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How often `L1RpcFailover::maybe_check` actually checks the active endpoint
+const HEALTH_CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Keeps the subnet node's L1 RPC traffic on a healthy endpoint when `[burnchain] rpc_endpoints`
+/// lists more than one.  The active endpoint is shared through the `BurnchainConfig`, so every
+/// user of `BurnchainConfig::get_rpc_url` (including the block committers) follows a switch.
+///
+/// Before switching to another endpoint, it must be on the same L1 chain (same network ID and
+/// genesis chainstate hash as the first endpoint that answered), and must already have the last
+/// L1 block the subnet node processed, so that the node never submits against a node that is
+/// behind it or on a different chain.
+pub struct L1RpcFailover {
+    config: BurnchainConfig,
+    /// `(network_id, genesis_chainstate_hash)` of the L1 chain, learned from the first healthy
+    /// endpoint
+    chain_identity: Option<(u32, Sha256Sum)>,
+    last_check: Option<Instant>,
+}
+
+impl L1RpcFailover {
+    pub fn new(config: &BurnchainConfig) -> L1RpcFailover {
+        L1RpcFailover {
+            config: config.clone(),
+            chain_identity: None,
+            last_check: None,
+        }
+    }
+
+    /// Check the active endpoint if it hasn't been checked within `HEALTH_CHECK_INTERVAL`.
+    pub fn maybe_check(&mut self, l1_tip: Option<(StacksBlockId, u64)>) -> Result<(), Error> {
+        if let Some(last_check) = self.last_check {
+            if last_check.elapsed() < HEALTH_CHECK_INTERVAL {
+                return Ok(());
+            }
+        }
+        self.check(l1_tip)
+    }
+
+    /// Check that the active endpoint is healthy, and if not, switch to the first other endpoint
+    /// which is healthy and consistent with `l1_tip`, the L1 index block hash and height of the
+    /// last L1 block this node processed.  Errors if no endpoint is usable, in which case the
+    /// active endpoint is left as it was.
+    pub fn check(&mut self, l1_tip: Option<(StacksBlockId, u64)>) -> Result<(), Error> {
+        let endpoints = self.config.get_rpc_urls();
+        if endpoints.len() <= 1 {
+            return Ok(());
+        }
+        self.last_check = Some(Instant::now());
+
+        let active = self.config.get_active_rpc_endpoint();
+        match self.check_endpoint(&endpoints[active], None) {
+            Ok(()) => return Ok(()),
+            Err(e) => {
+                component_warn!(
+                    Burnchain,
+                    "L1 RPC endpoint {} is unhealthy: {}",
+                    &endpoints[active],
+                    &e
+                );
+            }
+        }
+
+        for offset in 1..endpoints.len() {
+            let candidate = (active + offset) % endpoints.len();
+            match self.check_endpoint(&endpoints[candidate], l1_tip.as_ref()) {
+                Ok(()) => {
+                    component_warn!(
+                        Burnchain,
+                        "Failing over L1 RPC from {} to {}",
+                        &endpoints[active],
+                        &endpoints[candidate]
+                    );
+                    self.config.set_active_rpc_endpoint(candidate);
+                    return Ok(());
+                }
+                Err(e) => {
+                    component_info!(
+                        Burnchain,
+                        "Not failing over to L1 RPC endpoint {}: {}",
+                        &endpoints[candidate],
+                        &e
+                    );
+                }
+            }
+        }
+
+        Err(Error::RPCError(format!(
+            "none of the {} configured L1 RPC endpoints is usable",
+            endpoints.len()
+        )))
+    }
+
+    /// Check that the endpoint at `url` responds, and is on the L1 chain this node follows.  If
+    /// `l1_tip` is given, also check that it has that block and is at least at its height.
+    fn check_endpoint(
+        &mut self,
+        url: &str,
+        l1_tip: Option<&(StacksBlockId, u64)>,
+    ) -> Result<(), Error> {
+        let client = reqwest::blocking::Client::builder()
+            .timeout(HEALTH_CHECK_TIMEOUT)
+            .build()?;
+        let info = client
+            .get(format!("{}/v2/info", url))
+            .send()?
+            .error_for_status()?
+            .json::<RPCPeerInfoData>()?;
+
+        let identity = (info.network_id, info.genesis_chainstate_hash.clone());
+        match self.chain_identity {
+            Some(ref expected) => {
+                if expected != &identity {
+                    return Err(Error::RPCError(format!(
+                        "endpoint is on network {} with genesis chainstate {}, expected network {} with genesis chainstate {}",
+                        identity.0,
+                        identity.1.to_hex(),
+                        expected.0,
+                        expected.1.to_hex()
+                    )));
+                }
+            }
+            None => {
+                self.chain_identity = Some(identity);
+            }
+        }
+
+        if let Some((block_id, height)) = l1_tip {
+            if info.stacks_tip_height < *height {
+                return Err(Error::RPCError(format!(
+                    "endpoint is at height {}, behind this node's L1 tip at height {}",
+                    info.stacks_tip_height, height
+                )));
+            }
+            let response = client
+                .get(format!("{}/v2/blocks/{}", url, block_id))
+                .send()?;
+            if !response.status().is_success() {
+                return Err(Error::RPCError(format!(
+                    "endpoint does not have this node's L1 tip {} (status {})",
+                    block_id,
+                    response.status()
+                )));
+            }
+        }
+        Ok(())
+    }
+}
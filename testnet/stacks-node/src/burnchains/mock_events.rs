@@ -3,6 +3,7 @@ use std::collections::HashMap;
 use std::convert::TryInto;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
+use std::thread;
 use std::time::Instant;
 
 use stacks::burnchains::db::BurnchainDB;
@@ -22,13 +23,14 @@ use stacks::chainstate::stacks::index::ClarityMarfTrieId;
 use stacks::chainstate::stacks::miner::SignedProposal;
 use stacks::core::StacksEpoch;
 use stacks::types::chainstate::{BlockHeaderHash, BurnchainHeaderHash, StacksBlockId};
-use stacks::util::sleep_ms;
+use stacks::util::{get_epoch_time_secs, sleep_ms};
 use stacks::vm::types::{QualifiedContractIdentifier, TupleData};
 use stacks::vm::Value as ClarityValue;
 
-use crate::operations::BurnchainOpSigner;
+use crate::config::BURNCHAIN_NAME_SIMULATED;
 use crate::{BurnchainController, BurnchainTip, Config};
 
+use super::commit_signer::CommitSigner;
 use super::db_indexer::DBBurnchainIndexer;
 use super::ClaritySignature;
 use super::{burnchain_from_config, BurnchainChannel, Error};
@@ -41,6 +43,7 @@ pub struct MockChannel {
 }
 
 pub struct MockController {
+    config: Config,
     /// This is the simulated contract identifier
     contract_identifier: QualifiedContractIdentifier,
     burnchain: Burnchain,
@@ -59,6 +62,11 @@ pub struct MockController {
     next_commit_and_withdrawal_root: Arc<Mutex<Option<(BlockHeaderHash, Sha512Trunc256Sum)>>>,
     burn_block_to_height: HashMap<u64, u64>,
     burn_block_to_parent: HashMap<u64, u64>,
+
+    /// If the L1 is simulated, how often `start()`'s block producer makes a block
+    simulated_block_time_ms: Option<u64>,
+    /// Cleared when this controller is dropped, to stop its block producer
+    producer_running: Arc<AtomicBool>,
 }
 
 pub struct MockIndexer {
@@ -192,8 +200,14 @@ impl MockController {
         .expect("Failed to initialize DBBurnchainIndexer.");
         let burnchain = burnchain_from_config(&config.get_burn_db_path(), &config.burnchain)
             .expect("Creation of burnchain has failed.");
+        let simulated_block_time_ms = if config.burnchain.chain == BURNCHAIN_NAME_SIMULATED {
+            Some(config.burnchain.simulated_block_time_ms)
+        } else {
+            None
+        };
 
         MockController {
+            config,
             contract_identifier,
             burnchain,
             indexer,
@@ -206,7 +220,45 @@ impl MockController {
             next_commit_and_withdrawal_root: NEXT_COMMIT_AND_WTIHDRAWAL_ROOT.clone(),
             burn_block_to_height: HashMap::new(),
             burn_block_to_parent: HashMap::new(),
+            simulated_block_time_ms,
+            producer_running: Arc::new(AtomicBool::new(true)),
+        }
+    }
+
+    /// Continue the simulated L1 from the highest block already indexed, so that a restarted
+    /// node extends the chain it made before.  A simulated L1 never forks, so each block's
+    /// number is its height.
+    fn resume_simulated_chain(&mut self) -> Result<(), Error> {
+        let tip_height = self
+            .indexer
+            .get_highest_header_height()
+            .map_err(Error::IndexerError)?;
+        if tip_height > 0 {
+            self.burn_block_to_height.insert(tip_height, tip_height);
         }
+        *self.next_burn_block.lock().unwrap() = tip_height + 1;
+        Ok(())
+    }
+
+    /// Make a simulated L1 block every `block_time_ms`, staging in each the block-commit this
+    /// controller was last given, until this controller is dropped.  The first block is made
+    /// right away.
+    fn spawn_block_producer(&self, block_time_ms: u64) -> Result<(), Error> {
+        let mut producer = MockController::new(self.config.clone(), self.coordinator.clone());
+        producer.resume_simulated_chain()?;
+        let running = self.producer_running.clone();
+        info!("Simulating the L1, with a block every {}ms", block_time_ms);
+        thread::Builder::new()
+            .name("simulated-l1".into())
+            .spawn(move || {
+                while running.load(Ordering::SeqCst) {
+                    producer.next_block(None);
+                    sleep_ms(block_time_ms);
+                }
+                debug!("Simulated L1 block producer exiting");
+            })
+            .expect("FATAL: failed to spawn the simulated L1 block producer");
+        Ok(())
     }
 
     /// Produce the next mocked layer-1 block. If `next_commit` is staged,
@@ -285,9 +337,15 @@ impl MockController {
         let index_block_hash =
             StacksBlockId(make_mock_byte_string(this_burn_block.try_into().unwrap()));
 
+        // a simulated L1's blocks carry the time they're made, as a real L1's do
+        let burn_block_time = if self.simulated_block_time_ms.is_some() {
+            get_epoch_time_secs()
+        } else {
+            this_burn_block
+        };
         let new_block = NewBlock {
             block_height,
-            burn_block_time: this_burn_block,
+            burn_block_time,
             index_block_hash,
             parent_index_block_hash,
             events: tx_event.into_iter().collect(),
@@ -398,11 +456,20 @@ impl MockController {
     }
 }
 
+impl Drop for MockController {
+    fn drop(&mut self) {
+        self.producer_running.store(false, Ordering::SeqCst);
+    }
+}
+
 impl BurnchainController for MockController {
     fn start(
         &mut self,
         target_block_height_opt: Option<u64>,
     ) -> Result<(BurnchainTip, u64), Error> {
+        if let Some(block_time_ms) = self.simulated_block_time_ms {
+            self.spawn_block_producer(block_time_ms)?;
+        }
         self.receive_blocks(
             false,
             target_block_height_opt.map_or_else(|| Some(1), |x| Some(x)),
@@ -423,7 +490,7 @@ impl BurnchainController for MockController {
         _target_block_height: u64,
         withdrawal_merkle_root: Sha512Trunc256Sum,
         _signatures: Vec<ClaritySignature>,
-        _op_signer: &mut BurnchainOpSigner,
+        _signer: Arc<dyn CommitSigner>,
     ) -> Result<Txid, Error> {
         let mut next_commit_and_withdrawal_root =
             self.next_commit_and_withdrawal_root.lock().unwrap();
@@ -443,6 +510,11 @@ impl BurnchainController for MockController {
         self.receive_blocks(true, target_block_height_opt)
     }
 
+    fn reconnect(&mut self) -> Result<(), Error> {
+        // mock blocks are produced in-process, so there is nothing to reconnect to
+        Ok(())
+    }
+
     fn get_chain_tip(&self) -> BurnchainTip {
         self.chain_tip.as_ref().unwrap().clone()
     }
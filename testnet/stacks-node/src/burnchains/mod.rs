@@ -1,11 +1,10 @@
 use crate::config::BurnchainConfig;
 
-use super::operations::BurnchainOpSigner;
-
 use std::fmt;
 use std::sync::Arc;
 use std::time::Instant;
 
+use self::commit_signer::CommitSigner;
 use self::commitment::Error as CommitmentError;
 use clarity::vm::errors::Error as ClarityError;
 use reqwest::Error as ReqwestError;
@@ -33,9 +32,27 @@ pub mod l1_events;
 
 pub mod db_indexer;
 
+/// This module fails over between L1 RPC endpoints
+pub mod l1_rpc;
+
 /// This module defines structs for producing block commitments
 pub mod commitment;
 
+/// This module tracks unconfirmed block commitments and bumps their fees
+pub mod commit_manager;
+
+/// This module records the L1 fees of the block commitments this node sends
+pub mod commit_fees;
+
+/// This module signs block commitments, with a local key or through a remote signer
+pub mod commit_signer;
+
+/// This module alerts when the burnchain tip stops advancing
+pub mod stale_tip;
+
+/// This module asks the L1 whether withdrawals can be claimed there yet
+pub mod claim_oracle;
+
 #[cfg(test)]
 mod tests;
 
@@ -46,6 +63,9 @@ pub enum Error {
     BurnchainContractCheck(String),
     /// Was able to check burnchain contract version and is unsupported
     BurnchainContractVersion(String),
+    /// The burnchain contract can never be the subnet contract: it is at an address of another
+    /// network, or lacks functions the node uses
+    BurnchainContractInterface(String),
     Clarity(ClarityError),
     CoordinatorClosed,
     IndexerError(burnchains::Error),
@@ -65,6 +85,9 @@ impl fmt::Display for Error {
             Error::BurnchainContractVersion(ref e) => {
                 write!(f, "Burnchain contract unsupported version: {e}")
             }
+            Error::BurnchainContractInterface(ref e) => {
+                write!(f, "Burnchain contract is unusable: {e}")
+            }
             Error::Clarity(ref e) => write!(f, "Clarity Error: {e}"),
             Error::CoordinatorClosed => write!(f, "ChainsCoordinator closed"),
             Error::IndexerError(ref e) => write!(f, "Indexer error: {e:?}"),
@@ -118,7 +141,7 @@ pub trait BurnchainController {
         target_burn_block_height: u64,
         withdrawal_merkle_root: Sha512Trunc256Sum,
         signatures: Vec<ClaritySignature>,
-        op_signer: &mut BurnchainOpSigner,
+        signer: Arc<dyn CommitSigner>,
     ) -> Result<Txid, Error>;
 
     /// Returns the number of signatures necessary to provide
@@ -149,6 +172,10 @@ pub trait BurnchainController {
         target_sortition_height: Option<u64>,
     ) -> Result<BurnchainTip, Error>;
 
+    /// Re-establish contact with the burnchain after it has stopped producing new blocks.
+    /// Returns an error if the burnchain is still unreachable.
+    fn reconnect(&mut self) -> Result<(), Error>;
+
     #[cfg(test)]
     fn bootstrap_chain(&mut self, blocks_count: u64);
 }
@@ -219,6 +246,10 @@ impl BurnchainController for PanicController {
         panic!()
     }
 
+    fn reconnect(&mut self) -> Result<(), Error> {
+        panic!()
+    }
+
     fn submit_commit(
         &mut self,
         _committed_block_hash: BlockHeaderHash,
@@ -227,7 +258,7 @@ impl BurnchainController for PanicController {
         _target_block_height: u64,
         _withdrawal_merkle_root: Sha512Trunc256Sum,
         _signatures: Vec<ClaritySignature>,
-        _op_signer: &mut BurnchainOpSigner,
+        _signer: Arc<dyn CommitSigner>,
     ) -> Result<Txid, Error> {
         panic!()
     }
@@ -0,0 +1,120 @@
+use std::thread;
+use std::time::{Duration, Instant};
+
+use serde_json::json;
+
+use crate::config::BurnchainConfig;
+
+/// How long to wait on the stale-tip webhook before giving up on it
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Notices when the burnchain tip stops advancing.  If the L1 node crashes or the network
+/// between it and this node's L1 observer fails, no more L1 blocks arrive and the miner stops
+/// producing blocks without reporting any error.  The run loop feeds this watchdog every
+/// burnchain height it observes, and once no new height has been seen for
+/// `[burnchain] stale_tip_alert_secs`, the watchdog raises an alert: an error log, a metric,
+/// and a POST to `[burnchain] stale_tip_webhook` if set.  The alert repeats every
+/// `stale_tip_alert_secs` until the tip advances again.
+pub struct StaleTipWatchdog {
+    alert_after: Duration,
+    webhook: Option<String>,
+    /// highest burnchain height observed so far
+    tip_height: u64,
+    /// when `tip_height` was first observed
+    tip_observed_at: Instant,
+    /// when the last alert for the current tip was raised, if any
+    last_alert: Option<Instant>,
+}
+
+impl StaleTipWatchdog {
+    /// Make a watchdog for a burnchain currently at `tip_height`.  Returns `None` if
+    /// `stale_tip_alert_secs` is 0.
+    pub fn new(config: &BurnchainConfig, tip_height: u64) -> Option<StaleTipWatchdog> {
+        if config.stale_tip_alert_secs == 0 {
+            return None;
+        }
+        Some(StaleTipWatchdog {
+            alert_after: Duration::from_secs(config.stale_tip_alert_secs),
+            webhook: config.stale_tip_webhook.clone(),
+            tip_height,
+            tip_observed_at: Instant::now(),
+            last_alert: None,
+        })
+    }
+
+    /// Record that the burnchain is at `tip_height`.  Returns true if an alert was raised, in
+    /// which case the caller should try to reconnect to the burnchain.
+    pub fn observe(&mut self, tip_height: u64) -> bool {
+        self.observe_at(tip_height, Instant::now())
+    }
+
+    /// Record that the burnchain was at `tip_height` as of `now`.
+    pub fn observe_at(&mut self, tip_height: u64, now: Instant) -> bool {
+        if tip_height > self.tip_height {
+            if self.last_alert.is_some() {
+                component_info!(
+                    Burnchain,
+                    "Burnchain tip is advancing again";
+                    "tip_height" => tip_height,
+                    "stale_secs" => now.saturating_duration_since(self.tip_observed_at).as_secs()
+                );
+            }
+            self.tip_height = tip_height;
+            self.tip_observed_at = now;
+            self.last_alert = None;
+            stacks::monitoring::update_burnchain_tip_age(0);
+            return false;
+        }
+
+        let tip_age = now.saturating_duration_since(self.tip_observed_at);
+        stacks::monitoring::update_burnchain_tip_age(tip_age.as_secs());
+        if tip_age < self.alert_after {
+            return false;
+        }
+        if let Some(last_alert) = self.last_alert {
+            if now.saturating_duration_since(last_alert) < self.alert_after {
+                return false;
+            }
+        }
+
+        self.last_alert = Some(now);
+        self.alert(tip_age);
+        true
+    }
+
+    fn alert(&self, tip_age: Duration) {
+        component_error!(
+            Burnchain,
+            "No new burnchain block observed; the L1 node or the connection to it may have stalled";
+            "tip_height" => self.tip_height,
+            "stale_secs" => tip_age.as_secs()
+        );
+        stacks::monitoring::increment_stale_burnchain_tip_alerts();
+
+        let url = match self.webhook {
+            Some(ref url) => url.clone(),
+            None => return,
+        };
+        let body = json!({
+            "alert": "stale_burnchain_tip",
+            "burn_block_height": self.tip_height,
+            "stale_secs": tip_age.as_secs(),
+        });
+        // don't hold up the run loop on a slow webhook
+        let spawned = thread::Builder::new()
+            .name("stale-tip-webhook".into())
+            .spawn(move || {
+                let result = reqwest::blocking::Client::builder()
+                    .timeout(WEBHOOK_TIMEOUT)
+                    .build()
+                    .and_then(|client| client.post(&url).json(&body).send())
+                    .and_then(|response| response.error_for_status());
+                if let Err(e) = result {
+                    component_warn!(Burnchain, "Failed to send stale-tip alert"; "url" => &url, "error" => %e);
+                }
+            });
+        if let Err(e) = spawned {
+            component_warn!(Burnchain, "Failed to spawn stale-tip webhook thread"; "error" => %e);
+        }
+    }
+}
@@ -0,0 +1,23 @@
+use stacks::burnchains::Txid;
+use stacks::chainstate::stacks::db::rewards_report::CommitFeeLedger;
+
+use crate::burnchains::commit_fees::CommitFeeDB;
+use crate::burnchains::tests::random_sortdb_test_dir;
+
+#[test]
+fn test_commit_fee_db() {
+    let dir = random_sortdb_test_dir();
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = format!("{}/commit_fees.sqlite", &dir);
+
+    assert!(CommitFeeDB::open_existing(&path).unwrap().is_none());
+
+    let db = CommitFeeDB::open(&path).unwrap();
+    db.record_commit(&Txid([1; 32]), 1000, 10).unwrap();
+    db.record_commit(&Txid([2; 32]), 2000, 12).unwrap();
+    assert_eq!(db.get_commit_fee(&Txid([1; 32])), Some(1000));
+    assert_eq!(db.get_commit_fee(&Txid([3; 32])), None);
+
+    let reader = CommitFeeDB::open_existing(&path).unwrap().unwrap();
+    assert_eq!(reader.get_commit_fee(&Txid([2; 32])), Some(2000));
+}
@@ -0,0 +1,100 @@
+use std::sync::Arc;
+
+use crate::burnchains::commit_manager::CommitManager;
+use crate::burnchains::commit_signer::TestCommitSigner;
+use crate::config::BurnchainConfig;
+use stacks::chainstate::stacks::{
+    StacksPrivateKey, StacksPublicKey, StacksTransaction, StacksTransactionSigner,
+    TokenTransferMemo, TransactionAuth, TransactionPayload, TransactionSpendingCondition,
+    TransactionVersion,
+};
+use stacks_common::types::chainstate::StacksAddress;
+
+fn make_test_tx(sender: &StacksPrivateKey, nonce: u64, fee: u64) -> StacksTransaction {
+    let mut spending_condition =
+        TransactionSpendingCondition::new_singlesig_p2pkh(StacksPublicKey::from_private(sender))
+            .unwrap();
+    spending_condition.set_nonce(nonce);
+    spending_condition.set_tx_fee(fee);
+    let unsigned_tx = StacksTransaction::new(
+        TransactionVersion::Testnet,
+        TransactionAuth::Standard(spending_condition),
+        TransactionPayload::TokenTransfer(
+            StacksAddress::burn_address(false).into(),
+            1,
+            TokenTransferMemo([0; 34]),
+        ),
+    );
+    let mut tx_signer = StacksTransactionSigner::new(&unsigned_tx);
+    tx_signer.sign_origin(sender).unwrap();
+    tx_signer.get_tx().unwrap()
+}
+
+fn make_test_manager() -> CommitManager {
+    let mut config = BurnchainConfig::default();
+    config.rbf_fee_increment = 10;
+    config.max_rbf = 125;
+    config.rbf_after_blocks = 2;
+    CommitManager::new(&config)
+}
+
+#[test]
+fn test_commit_manager_bumps_stuck_commits() {
+    let sk = StacksPrivateKey::new();
+    let signer = Arc::new(TestCommitSigner::new(sk.clone()));
+    let mut manager = make_test_manager();
+
+    let tx = make_test_tx(&sk, 3, 1000);
+    manager.track(tx.clone(), tx.txid(), signer.clone(), 100);
+    assert!(manager.has_pending());
+    assert_eq!(manager.senders(), vec![tx.origin_address()]);
+
+    // not stuck until it has waited `rbf_after_blocks`
+    assert!(manager.take_stuck(101).is_empty());
+    let mut stuck = manager.take_stuck(102);
+    assert_eq!(stuck.len(), 1);
+    assert!(!manager.has_pending());
+
+    // each bump adds 10% of the initial fee, up to 125% of it
+    let mut fees = vec![];
+    let mut height = 102;
+    while let Some(commit) = stuck.pop() {
+        let fee = match manager.bumped_fee(&commit) {
+            Some(fee) => fee,
+            None => {
+                manager.rebroadcast(commit.clone(), commit.tx.clone(), commit.txid, height);
+                break;
+            }
+        };
+        let bumped_tx = commit.with_fee(fee).unwrap();
+        bumped_tx.verify().unwrap();
+        assert_eq!(bumped_tx.get_origin_nonce(), 3);
+        assert_eq!(bumped_tx.origin_address(), tx.origin_address());
+        assert_eq!(bumped_tx.payload, tx.payload);
+        fees.push(bumped_tx.get_tx_fee());
+
+        let txid = bumped_tx.txid();
+        manager.rebroadcast(commit, bumped_tx, txid, height);
+        height += 2;
+        stuck = manager.take_stuck(height);
+    }
+    assert_eq!(fees, vec![1100, 1200, 1250]);
+    // every bump was re-signed by the commit's signer
+    assert_eq!(signer.signed.lock().unwrap().len(), 3);
+
+    // a new commit with the same nonce must outbid the pending one
+    assert_eq!(
+        manager.replacement_fee(&tx.origin_address(), 3, 1000),
+        Some(1350)
+    );
+    assert_eq!(manager.replacement_fee(&tx.origin_address(), 3, 1251), None);
+    assert_eq!(manager.replacement_fee(&tx.origin_address(), 4, 1000), None);
+
+    // once the account nonce passes the commit's, it's confirmed
+    assert!(manager.confirm(&tx.origin_address(), 3).is_empty());
+    let confirmed = manager.confirm(&tx.origin_address(), 4);
+    assert_eq!(confirmed.len(), 1);
+    assert_eq!(confirmed[0].fee(), 1250);
+    assert_eq!(confirmed[0].initial_fee, 1000);
+    assert!(!manager.has_pending());
+}
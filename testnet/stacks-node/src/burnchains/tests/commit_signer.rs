@@ -0,0 +1,99 @@
+use std::sync::atomic::Ordering;
+
+use crate::burnchains::commit_signer::{
+    CommitSigner, LocalCommitSigner, RemoteCommitSigner, RemoteSignRequest, RemoteSignResponse,
+    TestCommitSigner,
+};
+use stacks::chainstate::stacks::{
+    StacksPrivateKey, StacksPublicKey, StacksTransaction, TokenTransferMemo, TransactionAuth,
+    TransactionPayload, TransactionSpendingCondition, TransactionVersion,
+};
+use stacks_common::types::chainstate::StacksAddress;
+
+fn make_unsigned_tx(sender: &StacksPublicKey, nonce: u64, fee: u64) -> StacksTransaction {
+    let mut spending_condition =
+        TransactionSpendingCondition::new_singlesig_p2pkh(sender.clone()).unwrap();
+    spending_condition.set_nonce(nonce);
+    spending_condition.set_tx_fee(fee);
+    StacksTransaction::new(
+        TransactionVersion::Testnet,
+        TransactionAuth::Standard(spending_condition),
+        TransactionPayload::TokenTransfer(
+            StacksAddress::burn_address(false).into(),
+            1,
+            TokenTransferMemo([0; 34]),
+        ),
+    )
+}
+
+#[test]
+fn test_local_and_test_signers() {
+    let sk = StacksPrivateKey::new();
+    let local = LocalCommitSigner::new(sk.clone());
+    let unsigned_tx = make_unsigned_tx(&local.get_public_key(), 1, 1000);
+    let signed_tx = local.sign_commit(&unsigned_tx).unwrap();
+    signed_tx.verify().unwrap();
+    assert_eq!(signed_tx.origin_address(), unsigned_tx.origin_address());
+
+    let test_signer = TestCommitSigner::new(sk);
+    assert_eq!(test_signer.sign_commit(&unsigned_tx).unwrap(), signed_tx);
+    test_signer.refuse.store(true, Ordering::SeqCst);
+    assert!(test_signer.sign_commit(&unsigned_tx).is_err());
+    assert_eq!(test_signer.signed.lock().unwrap().len(), 1);
+}
+
+#[test]
+fn test_remote_signer_exchange() {
+    let commit_key = StacksPrivateKey::new();
+    let auth_key = StacksPrivateKey::new();
+    let commit_public_key = StacksPublicKey::from_private(&commit_key);
+    let remote = RemoteCommitSigner::new(
+        "http://127.0.0.1:30000/sign",
+        commit_public_key.clone(),
+        auth_key.clone(),
+    );
+    let unsigned_tx = make_unsigned_tx(&commit_public_key, 5, 2000);
+
+    // the daemon checks that the node sent the request, and answers it
+    let request = RemoteSignRequest::new(&unsigned_tx, &auth_key).unwrap();
+    assert_eq!(
+        request
+            .check(&StacksPublicKey::from_private(&auth_key))
+            .unwrap(),
+        unsigned_tx
+    );
+    assert!(request
+        .check(&StacksPublicKey::from_private(&commit_key))
+        .is_err());
+    let signed_tx = LocalCommitSigner::new(commit_key.clone())
+        .sign_commit(&unsigned_tx)
+        .unwrap();
+    let response = RemoteSignResponse::new(&request.request_id, &signed_tx, &commit_key).unwrap();
+    assert_eq!(
+        remote
+            .check_response(&request, &unsigned_tx, &response)
+            .unwrap(),
+        signed_tx
+    );
+
+    // an answer to a different request is refused
+    let other_request = RemoteSignRequest::new(&unsigned_tx, &auth_key).unwrap();
+    assert!(remote
+        .check_response(&other_request, &unsigned_tx, &response)
+        .is_err());
+
+    // so is an answer signed with another key
+    let forged = RemoteSignResponse::new(&request.request_id, &signed_tx, &auth_key).unwrap();
+    assert!(remote
+        .check_response(&request, &unsigned_tx, &forged)
+        .is_err());
+
+    // and so is a signature over a different transaction, such as one with a higher fee
+    let other_tx = LocalCommitSigner::new(commit_key.clone())
+        .sign_commit(&make_unsigned_tx(&commit_public_key, 5, 9000))
+        .unwrap();
+    let swapped = RemoteSignResponse::new(&request.request_id, &other_tx, &commit_key).unwrap();
+    assert!(remote
+        .check_response(&request, &unsigned_tx, &swapped)
+        .is_err());
+}
@@ -0,0 +1,71 @@
+use stacks::core::{LAYER_1_CHAIN_ID_MAINNET, LAYER_1_CHAIN_ID_TESTNET};
+use stacks::vm::types::QualifiedContractIdentifier;
+
+use crate::burnchains::l1_events::{
+    check_contract_address_network, missing_subnet_contract_functions, L1ContractFunction,
+    L1ContractInterface,
+};
+use crate::burnchains::Error;
+
+fn interface(functions: &[(&str, &str)]) -> L1ContractInterface {
+    L1ContractInterface {
+        functions: functions
+            .iter()
+            .map(|(name, access)| L1ContractFunction {
+                name: name.to_string(),
+                access: access.to_string(),
+            })
+            .collect(),
+    }
+}
+
+#[test]
+fn test_missing_subnet_contract_functions() {
+    let mut functions = vec![
+        ("get-version", "read_only"),
+        ("commit-block", "public"),
+        ("deposit-stx", "public"),
+        ("withdraw-stx", "public"),
+        ("deposit-ft-asset", "public"),
+        ("withdraw-ft-asset", "public"),
+        ("deposit-nft-asset", "public"),
+        ("withdraw-nft-asset", "public"),
+        ("register-new-ft-contract", "public"),
+        ("register-new-nft-contract", "public"),
+        ("update-miner", "public"),
+    ];
+    assert!(missing_subnet_contract_functions(&interface(&functions)).is_empty());
+
+    functions.retain(|(name, _)| *name != "deposit-stx");
+    functions[1] = ("commit-block", "read_only");
+    assert_eq!(
+        missing_subnet_contract_functions(&interface(&functions)),
+        vec![
+            "commit-block (is read_only, should be public)".to_string(),
+            "deposit-stx".to_string()
+        ]
+    );
+}
+
+#[test]
+fn test_check_contract_address_network() {
+    let testnet_contract =
+        QualifiedContractIdentifier::parse("ST1PQHQKV0RJXZFY1DGX8MNSNYVE3VGZJSRTPGZGM.subnet")
+            .unwrap();
+    let mainnet_contract =
+        QualifiedContractIdentifier::parse("SP2J6ZY48GV1EZ5V2V5RB9MP66SW86PYKKNRV9EJ7.subnet")
+            .unwrap();
+
+    assert!(check_contract_address_network(&testnet_contract, LAYER_1_CHAIN_ID_TESTNET).is_ok());
+    assert!(check_contract_address_network(&mainnet_contract, LAYER_1_CHAIN_ID_MAINNET).is_ok());
+    assert!(matches!(
+        check_contract_address_network(&testnet_contract, LAYER_1_CHAIN_ID_MAINNET),
+        Err(Error::BurnchainContractInterface(_))
+    ));
+    assert!(matches!(
+        check_contract_address_network(&mainnet_contract, LAYER_1_CHAIN_ID_TESTNET),
+        Err(Error::BurnchainContractInterface(_))
+    ));
+    // L1 nodes of other chains aren't checked
+    assert!(check_contract_address_network(&mainnet_contract, 0x12345678).is_ok());
+}
@@ -0,0 +1,30 @@
+use crate::burnchains::l1_rpc::L1RpcFailover;
+use crate::config::BurnchainConfig;
+
+#[test]
+fn test_l1_rpc_failover_without_usable_endpoints() {
+    // nothing listens on this port, but with a single endpoint there's nothing to fail over to
+    let mut config = BurnchainConfig::default();
+    config.peer_host = "127.0.0.1".into();
+    config.rpc_port = 1;
+    let mut failover = L1RpcFailover::new(&config);
+    failover.check(None).unwrap();
+    assert_eq!(config.get_rpc_url(), "http://127.0.0.1:1");
+
+    // with several endpoints, none of which respond, the check fails and the active endpoint
+    // doesn't move
+    config.rpc_endpoints = vec!["127.0.0.1:1".into(), "127.0.0.1:2".into()];
+    let mut failover = L1RpcFailover::new(&config);
+    assert!(failover.check(None).is_err());
+    assert_eq!(config.get_active_rpc_endpoint(), 0);
+    assert_eq!(config.get_rpc_url(), "http://127.0.0.1:1");
+
+    // a failover elsewhere is seen by every clone of the config
+    let committer_config = config.clone();
+    config.set_active_rpc_endpoint(1);
+    assert_eq!(committer_config.get_rpc_url(), "http://127.0.0.1:2");
+    assert_eq!(
+        committer_config.get_rpc_urls(),
+        vec!["http://127.0.0.1:1", "http://127.0.0.1:2"]
+    );
+}
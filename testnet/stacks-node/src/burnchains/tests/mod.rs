@@ -10,8 +10,14 @@ use stacks::{
     vm::types::{QualifiedContractIdentifier, TupleData},
 };
 
+mod commit_fees;
+mod commit_manager;
+mod commit_signer;
 mod commitment;
 pub mod db_indexer;
+mod l1_events;
+mod l1_rpc;
+mod stale_tip;
 
 pub fn random_sortdb_test_dir() -> String {
     let mut rng = rand::thread_rng();
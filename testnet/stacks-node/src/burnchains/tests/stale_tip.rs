@@ -0,0 +1,38 @@
+use std::time::{Duration, Instant};
+
+use crate::burnchains::stale_tip::StaleTipWatchdog;
+use crate::config::BurnchainConfig;
+
+#[test]
+fn test_stale_tip_watchdog() {
+    let mut config = BurnchainConfig::default();
+    config.stale_tip_alert_secs = 0;
+    assert!(StaleTipWatchdog::new(&config, 10).is_none());
+
+    config.stale_tip_alert_secs = 60;
+    let mut watchdog = StaleTipWatchdog::new(&config, 10).unwrap();
+    let start = Instant::now();
+
+    // no alert before the tip is stale
+    assert!(!watchdog.observe_at(10, start + Duration::from_secs(59)));
+    // the tip advances, so the staleness clock restarts
+    assert!(!watchdog.observe_at(11, start + Duration::from_secs(59)));
+    assert!(!watchdog.observe_at(11, start + Duration::from_secs(118)));
+
+    // alert once the tip hasn't moved for stale_tip_alert_secs...
+    assert!(watchdog.observe_at(11, start + Duration::from_secs(119)));
+    // ...but not on every observation after that
+    assert!(!watchdog.observe_at(11, start + Duration::from_secs(120)));
+    assert!(!watchdog.observe_at(11, start + Duration::from_secs(178)));
+    // it repeats while the tip stays stale
+    assert!(watchdog.observe_at(11, start + Duration::from_secs(179)));
+
+    // a lower or equal height isn't progress
+    assert!(!watchdog.observe_at(5, start + Duration::from_secs(200)));
+    assert!(watchdog.observe_at(5, start + Duration::from_secs(239)));
+
+    // once the tip moves, a fresh alert needs another stale_tip_alert_secs
+    assert!(!watchdog.observe_at(12, start + Duration::from_secs(240)));
+    assert!(!watchdog.observe_at(12, start + Duration::from_secs(299)));
+    assert!(watchdog.observe_at(12, start + Duration::from_secs(300)));
+}
@@ -0,0 +1,37 @@
+use std::fs;
+
+use stacks::chainstate::stacks::db::block_compression::BlockCompression;
+use stacks::chainstate::stacks::db::StacksChainState;
+use stacks::util::get_epoch_time_ms;
+
+use crate::run_loop::RunLoopError;
+use crate::Config;
+
+/// Rewrite the stored blocks and microblocks of the node whose working directory is
+/// `config.node.working_dir` with `compression`, and print how much space that saved.  The node
+/// must not be running.
+pub fn compact_blocks(config: &Config, compression: BlockCompression) -> Result<(), RunLoopError> {
+    let chainstate_path = config.get_chainstate_path_str();
+    if fs::metadata(&chainstate_path).is_err() {
+        return Err(RunLoopError::Chainstate(format!(
+            "no chainstate at {}",
+            &chainstate_path
+        )));
+    }
+
+    let start = get_epoch_time_ms();
+    let stats = StacksChainState::compact_blocks(&chainstate_path, compression).map_err(|e| {
+        RunLoopError::Chainstate(format!("failed to compact block storage: {:?}", &e))
+    })?;
+    println!(
+        "Rewrote {} blocks and {} microblocks with {} compression in {}ms: {} bytes before, {} bytes after ({:.1}% saved)",
+        stats.blocks_compacted,
+        stats.microblocks_compacted,
+        compression.name(),
+        get_epoch_time_ms().saturating_sub(start),
+        stats.bytes_before,
+        stats.bytes_after,
+        stats.percent_saved()
+    );
+    Ok(())
+}
@@ -1,19 +1,34 @@
-use std::convert::TryInto;
+use std::collections::HashMap;
+use std::convert::{TryFrom, TryInto};
+use std::env;
 use std::fs;
 use std::net::{SocketAddr, ToSocketAddrs};
 use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
 
 use rand::RngCore;
 
 use stacks::chainstate::coordinator::comm::CoordinatorChannels;
+use stacks::chainstate::stacks::db::activations::FeatureActivations;
+use stacks::chainstate::stacks::db::block_compression::BlockCompression;
+use stacks::chainstate::stacks::db::coinbase_schedule::{CoinbaseRecipient, CoinbaseSchedule};
+use stacks::chainstate::stacks::db::contract_policy::ContractPublishPolicy;
+use stacks::chainstate::stacks::db::withdrawal_policy::WithdrawalPolicy;
 use stacks::chainstate::stacks::index::marf::MARFOpenOpts;
 use stacks::chainstate::stacks::index::storage::TrieHashCalculationMode;
-use stacks::chainstate::stacks::miner::BlockBuilderSettings;
+use stacks::chainstate::stacks::miner::{max_tx_cost, BlockBuilderSettings};
 use stacks::chainstate::stacks::StacksPrivateKey;
 use stacks::chainstate::stacks::TransactionAnchorMode;
 use stacks::chainstate::stacks::MAX_BLOCK_LEN;
 use stacks::core::mempool::MemPoolWalkSettings;
-use stacks::core::{StacksEpoch, NETWORK_ID_TESTNET, SUBNET_CHAIN_ID};
+use stacks::core::mempool::{MemPoolAdmissionLimits, TRANSACTION_PAYLOAD_NAMES};
+use stacks::core::mempool_journal::MemPoolArrivalJournal;
+use stacks::core::mempool_rejections::RejectedTxsRetention;
+use stacks::core::{
+    peer_version_with_magic, subnet_chain_id_for_contract, subnet_network_magic_for_contract,
+};
+use stacks::core::{StacksEpoch, NETWORK_ID_TESTNET, SUBNET_BLOCK_LIMIT, SUBNET_CHAIN_ID};
 use stacks::core::{
     LAYER_1_CHAIN_ID_MAINNET, LAYER_1_CHAIN_ID_TESTNET, PEER_VERSION_MAINNET, PEER_VERSION_TESTNET,
 };
@@ -25,27 +40,48 @@ use stacks::cost_estimates::metrics::ProportionalDotProduct;
 use stacks::cost_estimates::CostEstimator;
 use stacks::cost_estimates::FeeEstimator;
 use stacks::cost_estimates::PessimisticEstimator;
+use stacks::net::api_keys::RPCApiKey;
 use stacks::net::connection::ConnectionOptions;
+use stacks::net::ratelimit::RateLimit;
 use stacks::net::{Neighbor, NeighborKey, PeerAddress};
+use stacks::types::chainstate::StacksAddress;
 use stacks::util::get_epoch_time_ms;
-use stacks::util::hash::hex_bytes;
+use stacks::util::hash::{hex_bytes, Sha256Sum, Sha512Trunc256Sum};
 use stacks::util::secp256k1::Secp256k1PrivateKey;
 use stacks::util::secp256k1::Secp256k1PublicKey;
+use stacks::util_lib::clock::{system_clock, Clock};
+use stacks::util_lib::component_log::{self, LogComponent};
+use stacks::vm::costs::ExecutionCost;
 use stacks::vm::types::{AssetIdentifier, PrincipalData, QualifiedContractIdentifier};
+use stacks::vm::ClarityName;
+use stacks::vm::Value as ClarityValue;
 
 use crate::burnchains::commitment::MultiMinerParticipant;
 use crate::burnchains::l1_events::L1Controller;
 use crate::burnchains::mock_events::MockController;
+use crate::ephemeral_dir::{EphemeralWorkingDir, EPHEMERAL_WORKING_DIR};
 use crate::BurnchainController;
 
+use slog::Level;
+
 const DEFAULT_MAX_RBF_RATE: u64 = 150; // 1.5x
 const DEFAULT_RBF_FEE_RATE_INCREMENT: u64 = 5;
+const DEFAULT_RBF_AFTER_BLOCKS: u64 = 2;
+/// Alert if no new L1 block has been observed for this many seconds
+const DEFAULT_STALE_TIP_ALERT_SECS: u64 = 600;
 const INV_REWARD_CYCLES_TESTNET: u64 = 6;
 
 pub const BURNCHAIN_NAME_STACKS_TESTNET_L1: &str = "stacks_layer_1";
 pub const BURNCHAIN_NAME_STACKS_MAINNET_L1: &str = "stacks_layer_1::mainnet";
 pub const BURNCHAIN_NAME_MOCKSTACK: &str = "mockstack";
+/// A mocked L1 whose blocks the node mines itself, every `simulated_block_time_ms`
+pub const BURNCHAIN_NAME_SIMULATED: &str = "simulated";
+/// How often a simulated L1 makes a block, by default
+pub const DEFAULT_SIMULATED_BLOCK_TIME_MS: u64 = 10_000;
 pub const DEFAULT_L1_OBSERVER_PORT: u16 = 50303;
+/// How many received L1 blocks may wait to be indexed before the observer stops acknowledging
+/// the L1 node's deliveries
+pub const DEFAULT_L1_OBSERVER_QUEUE_SIZE: usize = 32;
 
 pub const SUBNET_SUBDIR_NAME: &str = "subnet";
 
@@ -58,6 +94,7 @@ pub struct ConfigFile {
     pub connection_options: Option<ConnectionOptionsFile>,
     pub fee_estimation: Option<FeeEstimationConfigFile>,
     pub miner: Option<MinerConfigFile>,
+    pub mempool: Option<MemPoolConfigFile>,
 }
 
 impl ConfigFile {
@@ -69,6 +106,203 @@ impl ConfigFile {
     pub fn from_str(content: &str) -> ConfigFile {
         toml::from_str(content).unwrap()
     }
+
+    /// Read and parse the config file at `path`, without panicking
+    pub fn try_from_path(path: &str) -> Result<ConfigFile, String> {
+        let content_str = fs::read_to_string(path)
+            .map_err(|e| format!("failed to read config file {}: {}", path, &e))?;
+        toml::from_str(&content_str)
+            .map_err(|e| format!("failed to parse config file {}: {}", path, &e))
+    }
+
+    /// Get the `[node]` logging settings.  These are needed before the rest of the config is
+    /// loaded, since the global logger is set up by the first line logged.
+    pub fn get_log_config(&self) -> Result<LogConfig, String> {
+        let mut log_config = LogConfig::default();
+        let node = match self.node {
+            Some(ref node) => node,
+            None => {
+                return Ok(log_config);
+            }
+        };
+        match node.log_format.as_deref() {
+            None | Some("text") => {}
+            Some("json") => {
+                if !cfg!(feature = "slog_json") {
+                    return Err("[node] log_format = \"json\" requires a node built with the `slog_json` feature".to_string());
+                }
+                log_config.json = true;
+            }
+            Some(other) => {
+                return Err(format!(
+                    "[node] log_format `{}` is not supported (expected `text` or `json`)",
+                    other
+                ));
+            }
+        }
+        if let Some(ref level) = node.log_level {
+            log_config.level = component_log::parse_level(level)
+                .ok_or_else(|| format!("[node] log_level `{}` is not a log level", level))?;
+        }
+        if let Some(ref log_levels) = node.log_levels {
+            let mut components: Vec<_> = log_levels.iter().collect();
+            components.sort();
+            for (name, level) in components.into_iter() {
+                let component = LogComponent::from_name(name).ok_or_else(|| {
+                    format!(
+                        "[node.log_levels] `{}` is not a log component (expected one of miner, burnchain, relayer, mempool)",
+                        name
+                    )
+                })?;
+                let level = component_log::parse_level(level).ok_or_else(|| {
+                    format!("[node.log_levels] {} `{}` is not a log level", name, level)
+                })?;
+                log_config.component_levels.push((component, level));
+            }
+        }
+        Ok(log_config)
+    }
+
+    /// Check the settings which `Config::from_config_file` would otherwise panic on.
+    pub fn validate(&self) -> Result<(), String> {
+        if let Some(ref node) = self.node {
+            if let Some(chain_id) = node.chain_id {
+                if chain_id == LAYER_1_CHAIN_ID_MAINNET || chain_id == LAYER_1_CHAIN_ID_TESTNET {
+                    return Err(format!(
+                        "[node] chain_id {:#x} is a layer 1 chain ID; use the subnet's own chain ID",
+                        chain_id
+                    ));
+                }
+            }
+            if let Some(ref network_magic) = node.network_magic {
+                parse_network_magic(network_magic)?;
+            }
+            for (name, seed) in [
+                ("seed", &node.seed),
+                ("local_peer_seed", &node.local_peer_seed),
+            ] {
+                if let Some(seed) = seed {
+                    hex_bytes(seed)
+                        .map_err(|_| format!("[node] {} must be a hex-encoded string", name))?;
+                }
+            }
+            for (name, addr) in [
+                ("rpc_bind", &node.rpc_bind),
+                ("p2p_bind", &node.p2p_bind),
+                ("p2p_address", &node.p2p_address),
+            ] {
+                if let Some(addr) = addr {
+                    resolve_socket_addr(&format!("[node] {}", name), addr)?;
+                }
+            }
+            if node.follower == Some(true) && node.miner == Some(true) {
+                return Err(
+                    "[node] follower and miner cannot both be set; a follower node never mines"
+                        .to_string(),
+                );
+            }
+            if node.commit_confirmations.unwrap_or(0) > 1 && node.miner == Some(true) {
+                return Err(
+                    "[node] commit_confirmations cannot be set on a miner, which must build on its own blocks as soon as their commits land"
+                        .to_string(),
+                );
+            }
+            if let Some(ref mining_key) = node.mining_key {
+                Secp256k1PrivateKey::from_hex(mining_key)
+                    .map_err(|_| "[node] mining_key is not a valid private key".to_string())?;
+            }
+            if let Some(ref recipient) = node.coinbase_recipient {
+                if StacksAddress::from_string(recipient).is_none() {
+                    return Err(format!(
+                        "[node] coinbase_recipient `{}` is not a valid address",
+                        recipient
+                    ));
+                }
+            }
+            for url in node.forward_txs_to.iter().flatten() {
+                if !url.starts_with("http://") && !url.starts_with("https://") {
+                    return Err(format!(
+                        "[node] forward_txs_to entry `{}` is not an http:// or https:// URL",
+                        url
+                    ));
+                }
+            }
+        }
+        self.get_log_config()?;
+
+        if let Some(ref miner) = self.miner {
+            CommitSignerConfig::from_config_file(miner)?;
+        }
+
+        if let Some(ref mempool) = self.mempool {
+            for payload_type in mempool.allowed_payload_types.iter().flatten() {
+                if !TRANSACTION_PAYLOAD_NAMES.contains(&payload_type.as_str()) {
+                    return Err(format!(
+                        "[mempool] allowed_payload_types entry `{}` is not a payload type (expected one of {})",
+                        payload_type,
+                        TRANSACTION_PAYLOAD_NAMES.join(", ")
+                    ));
+                }
+            }
+        }
+
+        if let Some(ref burnchain) = self.burnchain {
+            match burnchain.contract_identifier {
+                Some(ref contract_identifier) => {
+                    QualifiedContractIdentifier::parse(contract_identifier).map_err(|_| {
+                        format!(
+                            "[burnchain] contract_identifier `{}` is not a valid contract identifier (expected `<address>.<contract-name>`)",
+                            contract_identifier
+                        )
+                    })?;
+                }
+                None => {
+                    return Err("[burnchain] contract_identifier is missing; set it to the L1 subnet contract this node follows, e.g. `ST1PQHQKV0RJXZFY1DGX8MNSNYVE3VGZJSRTPGZGM.subnet`".to_string());
+                }
+            }
+            if let Some(ref rpc_endpoints) = burnchain.rpc_endpoints {
+                if rpc_endpoints.is_empty() {
+                    return Err("[burnchain] rpc_endpoints is empty; list at least one `host:port`, or remove it to use peer_host and rpc_port".to_string());
+                }
+                for endpoint in rpc_endpoints.iter() {
+                    let valid = match endpoint.rsplit_once(':') {
+                        Some((host, port)) => !host.is_empty() && port.parse::<u16>().is_ok(),
+                        None => false,
+                    };
+                    if !valid {
+                        return Err(format!(
+                            "[burnchain] rpc_endpoints entry `{}` is not a valid host:port",
+                            endpoint
+                        ));
+                    }
+                }
+            }
+            if burnchain.mode.as_deref() == Some(BURNCHAIN_NAME_SIMULATED) {
+                if let Some(ref chain) = burnchain.chain {
+                    if chain != BURNCHAIN_NAME_SIMULATED {
+                        return Err(format!(
+                            "[burnchain] mode = \"{}\" conflicts with chain = \"{}\"; remove one of them",
+                            BURNCHAIN_NAME_SIMULATED, chain
+                        ));
+                    }
+                }
+            }
+            if burnchain.simulated_block_time_ms == Some(0) {
+                return Err(
+                    "[burnchain] simulated_block_time_ms must be greater than 0".to_string()
+                );
+            }
+        }
+
+        if let Some(ref opts) = self.connection_options {
+            if let Some(ref public_ip_address) = opts.public_ip_address {
+                resolve_socket_addr("[connection_options] public_ip_address", public_ip_address)?;
+            }
+        }
+
+        Config::make_events_observers(self.events_observer.clone())?;
+        Ok(())
+    }
 }
 
 #[derive(Clone)]
@@ -80,7 +314,11 @@ pub struct Config {
     pub events_observers: Vec<EventObserverConfig>,
     pub connection_options: ConnectionOptions,
     pub miner: MinerConfig,
+    pub mempool: MemPoolConfig,
     pub estimation: FeeEstimationConfig,
+    /// The clock the node's mempool, miner and run loop read the time from.  Tests may replace
+    /// it with a `ManualClock` to run timeouts out without waiting.
+    pub clock: Arc<dyn Clock>,
 }
 
 lazy_static! {
@@ -102,7 +340,9 @@ lazy_static! {
         soft_max_neighbors_per_org: 32, // soft limit on the number of neighbors per AS we permit (TODO: for now it must be greater than num_neighbors)
         soft_max_clients_per_host: 4,   // soft limit on how many inbound p2p connections per host we permit
         max_http_clients: 1000,         // maximum number of HTTP connections
+        max_http_clients_per_host: 1000, // maximum number of HTTP connections per host we permit
         max_neighbors_of_neighbor: 10,  // maximum number of neighbors we'll handshake with when doing a neighbor walk (I/O for this can be expensive, so keep small-ish)
+        max_outbound_neighbors: 0,      // hard cap on outbound p2p connections (0 means unlimited)
         walk_interval: 60,              // how often, in seconds, we do a neighbor walk
         inv_sync_interval: 45,          // how often, in seconds, we refresh block inventories
         inv_reward_cycles: 3,           // how many reward cycles to look back on, for mainnet
@@ -110,13 +350,123 @@ lazy_static! {
         dns_timeout: 15_000,
         max_inflight_blocks: 6,
         max_inflight_attachments: 6,
+        mempool_sync_inbound: true,     // pull transactions from followers, too, so the miner sees them
         .. std::default::Default::default()
     };
 }
 
+/// Parse the hex-encoded 2-byte `[node] network_magic` setting
+fn parse_network_magic(network_magic: &str) -> Result<[u8; 2], String> {
+    match hex_bytes(network_magic) {
+        Ok(bytes) if bytes.len() == 2 => Ok([bytes[0], bytes[1]]),
+        _ => Err(format!(
+            "[node] network_magic `{}` must be 2 hex-encoded bytes",
+            network_magic
+        )),
+    }
+}
+
+/// Resolve the `setting` address `addr`: an `IP:port`, with IPv6 IPs in brackets (e.g.
+/// `[::]:20443`), or a `hostname:port`, which is resolved to the first address the resolver
+/// returns.  Hostnames are only resolved when this is called, so a node resolves its bind and
+/// advertised addresses once, at boot.
+pub fn resolve_socket_addr(setting: &str, addr: &str) -> Result<SocketAddr, String> {
+    if let Ok(sockaddr) = addr.parse::<SocketAddr>() {
+        return Ok(sockaddr);
+    }
+    addr.to_socket_addrs()
+        .map_err(|e| {
+            format!(
+                "{} `{}` is not a valid IP:port or resolvable host:port: {}",
+                setting, addr, e
+            )
+        })?
+        .next()
+        .ok_or_else(|| format!("{} `{}` did not resolve to any address", setting, addr))
+}
+
 impl Config {
+    /// Read just the `[[events_observer]]` sections of the config file at `path`.  Used to
+    /// reload the event observers of a running node, so errors are returned instead of panicking.
+    pub fn events_observers_from_path(path: &str) -> Result<Vec<EventObserverConfig>, String> {
+        let content = fs::read_to_string(path)
+            .map_err(|e| format!("failed to read config file {}: {:?}", path, &e))?;
+        let config_file: ConfigFile = toml::from_str(&content)
+            .map_err(|e| format!("failed to parse config file {}: {:?}", path, &e))?;
+        Config::make_events_observers(config_file.events_observer)
+    }
+
+    /// Read `[node] next_mining_key` and `[node] mining_key_switch_height` from the config file
+    /// at `path`.  Used to rotate the mining key of a running node, so errors are returned
+    /// instead of panicking.
+    pub fn next_mining_key_from_path(path: &str) -> Result<(StacksPrivateKey, u64), String> {
+        let content = fs::read_to_string(path)
+            .map_err(|e| format!("failed to read config file {}: {:?}", path, &e))?;
+        let config_file: ConfigFile = toml::from_str(&content)
+            .map_err(|e| format!("failed to parse config file {}: {:?}", path, &e))?;
+        let node = config_file
+            .node
+            .ok_or_else(|| format!("config file {} has no [node] section", path))?;
+        let next_key = node
+            .next_mining_key
+            .ok_or_else(|| "[node] next_mining_key is not set".to_string())?;
+        let next_key = Secp256k1PrivateKey::from_hex(&next_key)
+            .map_err(|_| "[node] next_mining_key is not a valid private key".to_string())?;
+        let switch_height = node
+            .mining_key_switch_height
+            .ok_or_else(|| "[node] mining_key_switch_height is not set".to_string())?;
+        Ok((next_key, switch_height))
+    }
+
+    fn make_events_observers(
+        raw_observers: Option<Vec<EventObserverConfigFile>>,
+    ) -> Result<Vec<EventObserverConfig>, String> {
+        let mut events_observers = vec![];
+        for observer in raw_observers.unwrap_or_default() {
+            let mut events_keys = vec![];
+            for raw_key in observer.events_keys.iter() {
+                let key = EventKeyType::from_string(raw_key).ok_or_else(|| {
+                    format!(
+                        "invalid events_key '{}' for observer {}",
+                        raw_key, &observer.endpoint
+                    )
+                })?;
+                events_keys.push(key);
+            }
+
+            events_observers.push(EventObserverConfig {
+                endpoint: format!("{}", observer.endpoint),
+                events_keys,
+            });
+        }
+
+        // check for observer config in env vars
+        match std::env::var("STACKS_EVENT_OBSERVER") {
+            Ok(val) => events_observers.push(EventObserverConfig {
+                endpoint: val,
+                events_keys: vec![EventKeyType::AnyEvent],
+            }),
+            _ => (),
+        };
+
+        Ok(events_observers)
+    }
+
+    /// Like `from_config_file`, but returns an error instead of panicking on an invalid setting
+    pub fn try_from_config_file(config_file: ConfigFile) -> Result<Config, String> {
+        config_file.validate()?;
+        Ok(Config::from_config_file(config_file))
+    }
+
     pub fn from_config_file(config_file: ConfigFile) -> Config {
         let default_node_config = NodeConfig::default();
+        let (chain_id_opt, network_id_opt, network_magic_opt) = match config_file.node {
+            Some(ref node) => (node.chain_id, node.network_id, node.network_magic.clone()),
+            None => (None, None, None),
+        };
+        let config_file_log_config = config_file
+            .get_log_config()
+            .expect("Bad logging configured in [node]");
         let (mut node, bootstrap_node, deny_nodes) = match config_file.node {
             Some(node) => {
                 let rpc_bind = node.rpc_bind.unwrap_or(default_node_config.rpc_bind);
@@ -128,7 +478,6 @@ impl Config {
                 }
                 let node_config = NodeConfig {
                     name: node.name.unwrap_or(default_node_config.name),
-                    chain_id: node.chain_id.unwrap_or(default_node_config.chain_id),
                     seed: match node.seed {
                         Some(seed) => {
                             hex_bytes(&seed).expect("Seed should be a hex encoded string")
@@ -157,6 +506,7 @@ impl Config {
                     },
                     miner: node.miner.unwrap_or(default_node_config.miner),
                     mock_mining: node.mock_mining.unwrap_or(default_node_config.mock_mining),
+                    follower: node.follower.unwrap_or(default_node_config.follower),
                     mine_microblocks: node
                         .mine_microblocks
                         .unwrap_or(default_node_config.mine_microblocks),
@@ -170,6 +520,7 @@ impl Config {
                         .wait_time_for_microblocks
                         .unwrap_or(default_node_config.wait_time_for_microblocks),
                     prometheus_bind: node.prometheus_bind,
+                    ws_bind: node.ws_bind,
                     marf_cache_strategy: node.marf_cache_strategy,
                     marf_defer_hashing: node
                         .marf_defer_hashing
@@ -181,18 +532,100 @@ impl Config {
                     wait_before_first_anchored_block: node
                         .wait_before_first_anchored_block
                         .unwrap_or(default_node_config.wait_before_first_anchored_block),
+                    event_queue: node.event_queue.unwrap_or(default_node_config.event_queue),
+                    prune_horizon: node.prune_horizon,
+                    mempool_persist_path: node.mempool_persist_path,
+                    shutdown_grace_period_secs: node
+                        .shutdown_grace_period_secs
+                        .unwrap_or(default_node_config.shutdown_grace_period_secs),
+                    block_validation_threads: node
+                        .block_validation_threads
+                        .unwrap_or(default_node_config.block_validation_threads),
+                    account_index: node
+                        .account_index
+                        .unwrap_or(default_node_config.account_index),
+                    block_compression: match node.block_compression {
+                        Some(name) => BlockCompression::from_name(&name).unwrap_or_else(|| {
+                            panic!(
+                                "Config file error: [node] block_compression `{}` is not supported (expected `none` or `zstd`)",
+                                name
+                            )
+                        }),
+                        None => default_node_config.block_compression,
+                    },
+                    commit_confirmations: node
+                        .commit_confirmations
+                        .unwrap_or(default_node_config.commit_confirmations),
+                    large_withdrawal_threshold: node
+                        .large_withdrawal_threshold
+                        .unwrap_or(default_node_config.large_withdrawal_threshold),
+                    large_withdrawal_delay: node
+                        .large_withdrawal_delay
+                        .unwrap_or(default_node_config.large_withdrawal_delay),
+                    contract_publisher_allow_list: node.contract_publisher_allow_list.map(
+                        |publishers| {
+                            publishers
+                                .iter()
+                                .map(|addr| {
+                                    StacksAddress::from_string(addr).expect(
+                                        "Bad address configured in contract_publisher_allow_list",
+                                    )
+                                })
+                                .collect()
+                        },
+                    ),
+                    contract_code_hash_allow_list: node.contract_code_hash_allow_list.map(
+                        |code_hashes| {
+                            code_hashes
+                                .iter()
+                                .map(|code_hash| {
+                                    Sha512Trunc256Sum::from_hex(code_hash).expect(
+                                        "Bad hash configured in contract_code_hash_allow_list",
+                                    )
+                                })
+                                .collect()
+                        },
+                    ),
+                    forward_txs_to: node
+                        .forward_txs_to
+                        .unwrap_or(default_node_config.forward_txs_to),
+                    coinbase_reward: node
+                        .coinbase_reward
+                        .unwrap_or(default_node_config.coinbase_reward),
+                    coinbase_halving_interval: node
+                        .coinbase_halving_interval
+                        .unwrap_or(default_node_config.coinbase_halving_interval),
+                    coinbase_recipient: node.coinbase_recipient.map(|addr| {
+                        StacksAddress::from_string(&addr)
+                            .expect("Bad address configured in coinbase_recipient")
+                    }),
+                    time_oracle_activation_height: node.time_oracle_activation_height,
+                    subnet_transfers_activation_height: node.subnet_transfers_activation_height,
+                    deposit_refunds_activation_height: node.deposit_refunds_activation_height,
+                    log: config_file_log_config,
+                    halt_for_upgrades: node
+                        .halt_for_upgrades
+                        .unwrap_or(default_node_config.halt_for_upgrades),
                     ..default_node_config
                 };
                 (node_config, node.bootstrap_node, node.deny_nodes)
             }
             None => (default_node_config, None, None),
         };
+        if node.working_dir == EPHEMERAL_WORKING_DIR {
+            node.use_ephemeral_working_dir()
+                .unwrap_or_else(|e| panic!("Config file error: {}", e));
+        }
 
         let default_burnchain_config = BurnchainConfig::default();
 
-        let burnchain = match config_file.burnchain {
+        let mut burnchain = match config_file.burnchain {
             Some(burnchain) => {
-                let chain = burnchain.chain.unwrap_or(default_burnchain_config.chain);
+                let chain = if burnchain.mode.as_deref() == Some(BURNCHAIN_NAME_SIMULATED) {
+                    BURNCHAIN_NAME_SIMULATED.to_string()
+                } else {
+                    burnchain.chain.unwrap_or(default_burnchain_config.chain)
+                };
                 BurnchainConfig {
                     chain: chain.clone(),
                     chain_id: if &chain == BURNCHAIN_NAME_STACKS_MAINNET_L1 {
@@ -203,6 +636,9 @@ impl Config {
                     observer_port: burnchain
                         .observer_port
                         .unwrap_or(default_burnchain_config.observer_port),
+                    observer_queue_size: burnchain
+                        .observer_queue_size
+                        .unwrap_or(default_burnchain_config.observer_queue_size),
                     peer_version: if &chain == BURNCHAIN_NAME_STACKS_MAINNET_L1 {
                         PEER_VERSION_MAINNET
                     } else {
@@ -237,6 +673,9 @@ impl Config {
                     rpc_port: burnchain
                         .rpc_port
                         .unwrap_or(default_burnchain_config.rpc_port),
+                    rpc_endpoints: burnchain
+                        .rpc_endpoints
+                        .unwrap_or(default_burnchain_config.rpc_endpoints),
                     rpc_ssl: burnchain
                         .rpc_ssl
                         .unwrap_or(default_burnchain_config.rpc_ssl),
@@ -253,6 +692,9 @@ impl Config {
                     rbf_fee_increment: burnchain
                         .rbf_fee_increment
                         .unwrap_or(default_burnchain_config.rbf_fee_increment),
+                    rbf_after_blocks: burnchain
+                        .rbf_after_blocks
+                        .unwrap_or(default_burnchain_config.rbf_after_blocks),
                     epochs: match burnchain.epochs {
                         Some(epochs) => Some(epochs),
                         None => default_burnchain_config.epochs,
@@ -266,6 +708,16 @@ impl Config {
                     first_burn_header_height: burnchain
                         .first_burn_header_height
                         .unwrap_or(default_burnchain_config.first_burn_header_height),
+                    stale_tip_alert_secs: burnchain
+                        .stale_tip_alert_secs
+                        .unwrap_or(default_burnchain_config.stale_tip_alert_secs),
+                    stale_tip_webhook: burnchain.stale_tip_webhook,
+                    deposit_confirmations: burnchain
+                        .deposit_confirmations
+                        .unwrap_or(default_burnchain_config.deposit_confirmations),
+                    simulated_block_time_ms: burnchain
+                        .simulated_block_time_ms
+                        .unwrap_or(default_burnchain_config.simulated_block_time_ms),
                     ..BurnchainConfig::default()
                 }
             }
@@ -294,16 +746,67 @@ impl Config {
                 candidate_retry_cache_size: miner
                     .candidate_retry_cache_size
                     .unwrap_or(miner_default_config.candidate_retry_cache_size),
+                max_tx_cost_fraction: match miner.max_tx_cost_fraction {
+                    Some(fraction) if !(fraction > 0.0 && fraction <= 1.0) => panic!(
+                        "Config file error: [miner] max_tx_cost_fraction must be above 0 and at most 1"
+                    ),
+                    fraction => fraction,
+                },
+                signer: CommitSignerConfig::from_config_file(miner)
+                    .expect("Bad commit signer configured in [miner]"),
+                scheduled_calls: miner
+                    .scheduled_calls
+                    .iter()
+                    .flatten()
+                    .map(ScheduledCallConfig::from_config_file)
+                    .collect::<Result<Vec<_>, _>>()
+                    .expect("Bad scheduled call configured in [miner]"),
             },
             None => miner_default_config,
         };
 
+        let mempool = match config_file.mempool {
+            Some(ref mempool) => MemPoolConfig {
+                max_tx_size: mempool.max_tx_size,
+                allowed_payload_types: mempool.allowed_payload_types.clone(),
+                max_contract_code_size: mempool.max_contract_code_size,
+                rejected_txs_retention: if mempool.record_rejected_txs.unwrap_or(false) {
+                    let default_retention = RejectedTxsRetention::default();
+                    Some(RejectedTxsRetention {
+                        max_count: mempool
+                            .rejected_txs_max_count
+                            .unwrap_or(default_retention.max_count),
+                        max_age_secs: mempool
+                            .rejected_txs_max_age_secs
+                            .unwrap_or(default_retention.max_age_secs),
+                    })
+                } else {
+                    None
+                },
+                arrival_journal: mempool.arrival_journal.clone(),
+            },
+            None => MemPoolConfig::default(),
+        };
+
+        // Unless configured otherwise, derive the subnet's network identifiers from its L1
+        // contract, so that distinct subnets neither accept each other's transactions nor peer
+        // with each other.
+        node.chain_id = chain_id_opt
+            .unwrap_or_else(|| subnet_chain_id_for_contract(&burnchain.contract_identifier));
+        node.network_id = network_id_opt.unwrap_or(node.chain_id);
+        let network_magic = match network_magic_opt {
+            Some(network_magic) => parse_network_magic(&network_magic)
+                .unwrap_or_else(|e| panic!("Config file error: {}", e)),
+            None => subnet_network_magic_for_contract(&burnchain.contract_identifier),
+        };
+        burnchain.peer_version = peer_version_with_magic(burnchain.peer_version, network_magic);
+
         if let Some(bootstrap_node) = bootstrap_node {
-            node.set_bootstrap_nodes(bootstrap_node, node.chain_id, burnchain.peer_version);
+            node.set_bootstrap_nodes(bootstrap_node, node.network_id, burnchain.peer_version);
         }
 
         if let Some(deny_nodes) = deny_nodes {
-            node.set_deny_nodes(deny_nodes, node.chain_id, burnchain.peer_version);
+            node.set_deny_nodes(deny_nodes, node.network_id, burnchain.peer_version);
         }
 
         let initial_balances_len = config_file
@@ -315,42 +818,18 @@ impl Config {
             panic!();
         }
 
-        let mut events_observers = match config_file.events_observer {
-            Some(raw_observers) => {
-                let mut observers = vec![];
-                for observer in raw_observers {
-                    let events_keys: Vec<EventKeyType> = observer
-                        .events_keys
-                        .iter()
-                        .map(|e| EventKeyType::from_string(e).unwrap())
-                        .collect();
-
-                    let endpoint = format!("{}", observer.endpoint);
-
-                    observers.push(EventObserverConfig {
-                        endpoint,
-                        events_keys,
-                    });
-                }
-                observers
-            }
-            None => vec![],
-        };
-
-        // check for observer config in env vars
-        match std::env::var("STACKS_EVENT_OBSERVER") {
-            Ok(val) => events_observers.push(EventObserverConfig {
-                endpoint: val,
-                events_keys: vec![EventKeyType::AnyEvent],
-            }),
-            _ => (),
-        };
+        let events_observers = Config::make_events_observers(config_file.events_observer)
+            .unwrap_or_else(|e| panic!("Config file error: {}", e));
 
         let connection_options = match config_file.connection_options {
             Some(opts) => {
                 let ip_addr = match opts.public_ip_address {
                     Some(public_ip_address) => {
-                        let addr = public_ip_address.parse::<SocketAddr>().unwrap();
+                        let addr = resolve_socket_addr(
+                            "[connection_options] public_ip_address",
+                            &public_ip_address,
+                        )
+                        .unwrap_or_else(|e| panic!("Config file error: {}", e));
                         debug!("addr.parse {:?}", addr);
                         Some((PeerAddress::from_socketaddr(&addr), addr.port()))
                     }
@@ -439,6 +918,9 @@ impl Config {
                                 .clone()
                         },
                     ),
+                    max_outbound_neighbors: opts.max_outbound_neighbors.unwrap_or_else(|| {
+                        HELIUM_DEFAULT_CONNECTION_OPTIONS.max_outbound_neighbors
+                    }),
                     walk_interval: opts
                         .walk_interval
                         .unwrap_or_else(|| HELIUM_DEFAULT_CONNECTION_OPTIONS.walk_interval.clone()),
@@ -482,11 +964,59 @@ impl Config {
                     max_http_clients: opts.max_http_clients.unwrap_or_else(|| {
                         HELIUM_DEFAULT_CONNECTION_OPTIONS.max_http_clients.clone()
                     }),
+                    max_http_clients_per_host: opts.max_http_clients_per_host.unwrap_or_else(
+                        || HELIUM_DEFAULT_CONNECTION_OPTIONS.max_http_clients_per_host,
+                    ),
+                    rpc_read_rate_limit: opts
+                        .rpc_read_rate_limit
+                        .unwrap_or_else(|| HELIUM_DEFAULT_CONNECTION_OPTIONS.rpc_read_rate_limit),
+                    rpc_read_burst: opts
+                        .rpc_read_burst
+                        .unwrap_or_else(|| HELIUM_DEFAULT_CONNECTION_OPTIONS.rpc_read_burst),
+                    rpc_submit_rate_limit: opts
+                        .rpc_submit_rate_limit
+                        .unwrap_or_else(|| HELIUM_DEFAULT_CONNECTION_OPTIONS.rpc_submit_rate_limit),
+                    rpc_submit_burst: opts
+                        .rpc_submit_burst
+                        .unwrap_or_else(|| HELIUM_DEFAULT_CONNECTION_OPTIONS.rpc_submit_burst),
+                    rpc_replay_rate_limit: opts
+                        .rpc_replay_rate_limit
+                        .unwrap_or_else(|| HELIUM_DEFAULT_CONNECTION_OPTIONS.rpc_replay_rate_limit),
+                    rpc_replay_burst: opts
+                        .rpc_replay_burst
+                        .unwrap_or_else(|| HELIUM_DEFAULT_CONNECTION_OPTIONS.rpc_replay_burst),
+                    health_max_burn_block_lag: opts.health_max_burn_block_lag.unwrap_or_else(
+                        || HELIUM_DEFAULT_CONNECTION_OPTIONS.health_max_burn_block_lag,
+                    ),
                     connect_timeout: opts.connect_timeout.unwrap_or(10),
                     handshake_timeout: opts.connect_timeout.unwrap_or(5),
                     max_sockets: opts.max_sockets.unwrap_or(800) as usize,
                     antientropy_public: opts.antientropy_public.unwrap_or(true),
+                    mempool_sync_interval: opts
+                        .mempool_sync_interval
+                        .unwrap_or_else(|| HELIUM_DEFAULT_CONNECTION_OPTIONS.mempool_sync_interval),
+                    mempool_max_tx_query: opts
+                        .mempool_max_tx_query
+                        .unwrap_or_else(|| HELIUM_DEFAULT_CONNECTION_OPTIONS.mempool_max_tx_query),
+                    mempool_sync_timeout: opts
+                        .mempool_sync_timeout
+                        .unwrap_or_else(|| HELIUM_DEFAULT_CONNECTION_OPTIONS.mempool_sync_timeout),
+                    mempool_sync_inbound: opts
+                        .mempool_sync_inbound
+                        .unwrap_or_else(|| HELIUM_DEFAULT_CONNECTION_OPTIONS.mempool_sync_inbound),
+                    max_mempool_sync_bandwidth: opts.max_mempool_sync_bandwidth.unwrap_or_else(
+                        || HELIUM_DEFAULT_CONNECTION_OPTIONS.max_mempool_sync_bandwidth,
+                    ),
                     subnet_validator: node.mining_key.clone(),
+                    admin_auth_token: opts.admin_auth_token.clone(),
+                    rpc_api_keys: opts
+                        .rpc_api_keys
+                        .iter()
+                        .flatten()
+                        .map(RPCApiKeyConfigFile::to_api_key)
+                        .collect::<Result<Vec<_>, _>>()
+                        .expect("Bad API key configured in [connection_options]"),
+                    sign_rpc_responses: opts.sign_rpc_responses.unwrap_or(false),
                     ..ConnectionOptions::default()
                 };
                 if let CommitStrategy::MultiMiner {
@@ -522,6 +1052,7 @@ impl Config {
             connection_options,
             estimation,
             miner,
+            mempool,
             ..Config::default()
         }
     }
@@ -578,6 +1109,13 @@ impl Config {
         path.to_str().expect("Unable to produce path").to_string()
     }
 
+    /// The database of the L1 fees of the block-commits this node has sent
+    pub fn get_commit_fees_db_path(&self) -> String {
+        let mut path = self.get_burnchain_path();
+        path.push("commit_fees.sqlite");
+        path.to_str().expect("Unable to produce path").to_string()
+    }
+
     pub fn get_spv_headers_file_path(&self) -> String {
         let mut path = self.get_burnchain_path();
         path.set_file_name("headers.sqlite");
@@ -590,6 +1128,12 @@ impl Config {
         path.to_str().expect("Unable to produce path").to_string()
     }
 
+    pub fn get_event_queue_db_file_path(&self) -> String {
+        let mut path = self.get_chainstate_path();
+        path.set_file_name("event_queue.sqlite");
+        path.to_str().expect("Unable to produce path").to_string()
+    }
+
     pub fn get_atlas_db_file_path(&self) -> String {
         let mut path = self.get_chainstate_path();
         path.set_file_name("atlas.sqlite");
@@ -633,7 +1177,7 @@ impl Config {
     pub fn add_bootstrap_node(&mut self, bootstrap_node: &str) {
         self.node.add_bootstrap_node(
             bootstrap_node,
-            self.node.chain_id,
+            self.node.network_id,
             self.burnchain.peer_version,
         );
     }
@@ -668,8 +1212,31 @@ impl Config {
                 nonce_cache_size: self.miner.nonce_cache_size,
                 candidate_retry_cache_size: self.miner.candidate_retry_cache_size,
             },
+            clock: self.clock.clone(),
+            max_tx_cost_fraction: self.miner.max_tx_cost_fraction,
         }
     }
+
+    /// The subnet's block limit: that of the last configured epoch, or the default one
+    pub fn block_limit(&self) -> ExecutionCost {
+        self.burnchain
+            .epochs
+            .as_ref()
+            .and_then(|epochs| epochs.last())
+            .map(|epoch| epoch.block_limit.clone())
+            .unwrap_or(SUBNET_BLOCK_LIMIT)
+    }
+
+    /// The `[mempool]` admission limits, with contract publishes which could never fit under
+    /// `[miner] max_tx_cost_fraction` kept out too
+    pub fn get_mempool_admission_limits(&self) -> MemPoolAdmissionLimits {
+        let mut limits = self.mempool.get_admission_limits();
+        limits.max_tx_write_length = self
+            .miner
+            .max_tx_cost_fraction
+            .map(|fraction| max_tx_cost(&self.block_limit(), fraction).write_length);
+        limits
+    }
 }
 
 impl std::default::Default for Config {
@@ -688,6 +1255,8 @@ impl std::default::Default for Config {
             connection_options,
             estimation,
             miner: MinerConfig::default(),
+            mempool: MemPoolConfig::default(),
+            clock: system_clock(),
             #[cfg(test)]
             initial_balances: vec![],
         }
@@ -709,12 +1278,16 @@ pub enum CommitStrategy {
 
 #[derive(Clone, Debug)]
 pub struct BurnchainConfig {
-    /// The name of the L1 chain that this subnet runs on: this is either "stacks_layer_1" or
-    /// "mockstack".
+    /// The name of the L1 chain that this subnet runs on: this is either "stacks_layer_1",
+    /// "mockstack", whose blocks are made by the caller (e.g., a test), or "simulated", whose
+    /// blocks the node makes itself.
     pub chain: String,
     /// This controls the listening port that this node's L1 event observer will run on. This is how
     /// the subnet node receives events from L1.
     pub observer_port: u16,
+    /// How many blocks the L1 event observer may have received but not yet indexed.  Once this
+    /// many are waiting, the observer holds off answering the L1 node until one is indexed.
+    pub observer_queue_size: usize,
     /// The `chain_id` is used to differentiate transactions between
     /// different Stacks blockchains (e.g., Stacks testnet vs. Stacks mainnet).
     /// This configuration variable specifies the `chain_id` used in the L1
@@ -730,6 +1303,12 @@ pub struct BurnchainConfig {
     pub peer_port: u16,
     /// This is the rpc port for the L1 node this subnet node communicates with
     pub rpc_port: u16,
+    /// The `host:port` RPC endpoints of L1 nodes to fail over between.  If empty, the node only
+    /// uses `peer_host` and `rpc_port`.
+    pub rpc_endpoints: Vec<String>,
+    /// Index into `rpc_endpoints` of the endpoint currently in use.  This is shared between all
+    /// clones of the config, so that a failover applies to every user of `get_rpc_url`.
+    pub active_rpc_endpoint: Arc<AtomicUsize>,
     /// Whether or not to use SSL for L1 rpc communications
     pub rpc_ssl: bool,
     /// The number of ms before synchronous L1 communications timeout
@@ -738,11 +1317,15 @@ pub struct BurnchainConfig {
     pub process_exit_at_block_height: Option<u64>,
     /// How frequently to poll the L1 chain for more information (not used for the event observer interface)
     pub poll_time_secs: u64,
-    /// The maximum number of replace-by-fee iterations this
-    /// subnet node will send for each miner commit.
+    /// The most replace-by-fee will raise a miner commit's fee to, as a percentage of the fee
+    /// it was first broadcast with.
     pub max_rbf: u64,
-    /// How much to increment the fee for each iteration of replace-by-fee for miner commitments
+    /// How much to increment the fee for each iteration of replace-by-fee for miner commitments,
+    /// as a percentage of the fee it was first broadcast with.
     pub rbf_fee_increment: u64,
+    /// How many L1 blocks to wait for a miner commit to confirm before replacing it with a
+    /// higher fee.
+    pub rbf_after_blocks: u64,
     /// Custom override for the definitions of the epochs. This will only be applied for testnet and
     /// regtest nodes.
     pub epochs: Option<Vec<StacksEpoch>>,
@@ -756,6 +1339,19 @@ pub struct BurnchainConfig {
     /// the miner should directly submit to the subnet contract, or they need to
     /// submit through another contract (e.g., a multi-party commit contract
     pub commit_strategy: CommitStrategy,
+    /// How many seconds may pass without a new L1 block before the node raises a stale-tip
+    /// alert and tries to reconnect to the L1.  0 disables the check.
+    pub stale_tip_alert_secs: u64,
+    /// If set, stale-tip alerts are also POSTed as JSON to this URL.
+    pub stale_tip_webhook: Option<String>,
+    /// How many L1 blocks must confirm a deposit before the subnet materializes it: a deposit
+    /// in L1 block H is materialized by the subnet block anchored to L1 height H + this.  This
+    /// is recorded in the chainstate when it is booted, so every node of a subnet must boot
+    /// with the same value.
+    pub deposit_confirmations: u64,
+    /// If `chain` is "simulated", how many milliseconds pass between the node's simulated L1
+    /// blocks.
+    pub simulated_block_time_ms: u64,
 }
 
 impl Default for BurnchainConfig {
@@ -766,20 +1362,28 @@ impl Default for BurnchainConfig {
             network_id: NETWORK_ID_TESTNET,
             peer_version: PEER_VERSION_TESTNET,
             observer_port: DEFAULT_L1_OBSERVER_PORT,
+            observer_queue_size: DEFAULT_L1_OBSERVER_QUEUE_SIZE,
             peer_host: "0.0.0.0".to_string(),
             peer_port: 8333,
             rpc_port: 8332,
+            rpc_endpoints: vec![],
+            active_rpc_endpoint: Arc::new(AtomicUsize::new(0)),
             rpc_ssl: false,
             timeout: 300,
             process_exit_at_block_height: None,
             poll_time_secs: 10, // TODO: this is a testnet specific value.
             max_rbf: DEFAULT_MAX_RBF_RATE,
             rbf_fee_increment: DEFAULT_RBF_FEE_RATE_INCREMENT,
+            rbf_after_blocks: DEFAULT_RBF_AFTER_BLOCKS,
             epochs: None,
             contract_identifier: QualifiedContractIdentifier::transient(),
             first_burn_header_height: 0u64,
             anchor_mode: TransactionAnchorMode::Any,
             commit_strategy: CommitStrategy::Direct,
+            stale_tip_alert_secs: DEFAULT_STALE_TIP_ALERT_SECS,
+            stale_tip_webhook: None,
+            deposit_confirmations: 0,
+            simulated_block_time_ms: DEFAULT_SIMULATED_BLOCK_TIME_MS,
         }
     }
 }
@@ -796,12 +1400,35 @@ impl BurnchainConfig {
         self.chain_id == LAYER_1_CHAIN_ID_MAINNET
     }
 
+    /// The URL of the L1 RPC endpoint currently in use
     pub fn get_rpc_url(&self) -> String {
+        let mut urls = self.get_rpc_urls();
+        let active = self.get_active_rpc_endpoint().min(urls.len() - 1);
+        urls.swap_remove(active)
+    }
+
+    /// The URLs of all the configured L1 RPC endpoints
+    pub fn get_rpc_urls(&self) -> Vec<String> {
         let scheme = match self.rpc_ssl {
             true => "https://",
             false => "http://",
         };
-        format!("{}{}:{}", scheme, self.peer_host, self.rpc_port)
+        if self.rpc_endpoints.is_empty() {
+            vec![format!("{}{}:{}", scheme, self.peer_host, self.rpc_port)]
+        } else {
+            self.rpc_endpoints
+                .iter()
+                .map(|endpoint| format!("{}{}", scheme, endpoint))
+                .collect()
+        }
+    }
+
+    pub fn get_active_rpc_endpoint(&self) -> usize {
+        self.active_rpc_endpoint.load(Ordering::SeqCst)
+    }
+
+    pub fn set_active_rpc_endpoint(&self, index: usize) {
+        self.active_rpc_endpoint.store(index, Ordering::SeqCst);
     }
 
     pub fn get_rpc_socket_addr(&self) -> SocketAddr {
@@ -817,32 +1444,54 @@ impl BurnchainConfig {
 pub struct BurnchainConfigFile {
     /// String-valued unique identifier, e.g., "mainnet", "testnet".
     pub chain: Option<String>,
+    /// Ignored, as in a Stacks node's config, unless it is "simulated", which is the same as
+    /// setting `chain = "simulated"`.
+    pub mode: Option<String>,
     pub observer_port: Option<u16>,
+    pub observer_queue_size: Option<usize>,
     pub peer_host: Option<String>,
     pub peer_port: Option<u16>,
     pub rpc_port: Option<u16>,
+    pub rpc_endpoints: Option<Vec<String>>,
     pub rpc_ssl: Option<bool>,
     pub timeout: Option<u32>,
     pub process_exit_at_block_height: Option<u64>,
     pub poll_time_secs: Option<u64>,
     pub rbf_fee_increment: Option<u64>,
     pub max_rbf: Option<u64>,
+    pub rbf_after_blocks: Option<u64>,
     pub epochs: Option<Vec<StacksEpoch>>,
     pub contract_identifier: Option<String>,
     pub first_burn_header_height: Option<u64>,
+    pub stale_tip_alert_secs: Option<u64>,
+    pub stale_tip_webhook: Option<String>,
+    pub deposit_confirmations: Option<u64>,
+    pub simulated_block_time_ms: Option<u64>,
 }
 
 #[derive(Clone, Debug, Default)]
 pub struct NodeConfig {
     pub name: String,
-    /// u32-valued identifier of the chain. This is also the `network_id` for L2.
+    /// u32-valued identifier of the chain, which every transaction must carry.  Defaults to one
+    /// derived from the L1 contract identifier.
     pub chain_id: u32,
+    /// The `network_id` that peers exchange in the p2p handshake.  Defaults to `chain_id`.
+    pub network_id: u32,
     /// Value to initialize the keychain, only used if `mining_key` is not set.
     pub seed: Vec<u8>,
+    /// `":memory:"` in the config file asks for an ephemeral working directory; see
+    /// `use_ephemeral_working_dir`.
     pub working_dir: String,
+    /// The ephemeral working directory, if `working_dir` is one.  It is removed once the last
+    /// copy of the config is dropped.
+    pub ephemeral_dir: Option<Arc<EphemeralWorkingDir>>,
+    /// The bind and advertised addresses are each an `IP:port`, with IPv6 IPs in brackets (e.g.
+    /// `[::]:20443`), or a `hostname:port`, resolved at boot; see `resolve_socket_addr`.
     pub rpc_bind: String,
     pub p2p_bind: String,
     pub data_url: String,
+    /// The address advertised to peers in the p2p handshake, unless
+    /// `[connection_options] public_ip_address` is set.  The port advertised is `p2p_bind`'s.
     pub p2p_address: String,
     pub local_peer_seed: Vec<u8>,
     pub bootstrap_node: Vec<Neighbor>,
@@ -852,6 +1501,11 @@ pub struct NodeConfig {
     /// If true, only do "mock mining", in which the miner doesn't actually send commitments.
     /// Otherwise, if this is a miner, send commitments.
     pub mock_mining: bool,
+    /// If true, this node never mines, and only takes on L2 data committed to in the L1
+    /// contract: it neither accepts nor relays anchored blocks whose hash does not match their
+    /// sortition's L1 block-commit, nor unconfirmed microblocks pushed or uploaded to it.
+    /// Cannot be combined with `miner`.
+    pub follower: bool,
     /// If true, mine micro-blocks, otherwise don't.
     pub mine_microblocks: bool,
     /// Try to mine a new micro-block every `microblock_frequency` milliseconds.
@@ -866,6 +1520,9 @@ pub struct NodeConfig {
     /// anchored block for that burn block.
     pub wait_before_first_anchored_block: u64,
     pub prometheus_bind: Option<String>,
+    /// If set, serve a websocket at this address which streams new blocks, microblocks and
+    /// mempool events to the clients subscribed to them.
+    pub ws_bind: Option<String>,
     pub marf_cache_strategy: Option<String>,
     pub marf_defer_hashing: bool,
     pub pox_sync_sample_secs: u64,
@@ -874,6 +1531,138 @@ pub struct NodeConfig {
     ///  as the validation key when running as a subnet 'validator' (i.e.,
     ///  the follower in the two-phase commit protocol)
     pub mining_key: Option<StacksPrivateKey>,
+    /// If true, persist event observer payloads to a queue before delivering them, so that
+    /// observers which are temporarily unreachable receive them once they come back.
+    pub event_queue: bool,
+    /// If set, prune block data and orphaned MARF tries more than this many blocks below the
+    /// chain tip each time the node boots.
+    pub prune_horizon: Option<u64>,
+    /// If set, the node writes its pending transactions to this file when it shuts down
+    /// gracefully, and re-admits them when it next boots.
+    pub mempool_persist_path: Option<String>,
+    /// On SIGINT or SIGTERM, the node finishes the block it is processing, flushes its
+    /// databases, and exits.  If that takes longer than this, it exits anyway, with an error
+    /// status.  0 waits as long as it takes.
+    pub shutdown_grace_period_secs: u64,
+    /// Number of worker threads which check the signatures of downloaded blocks' transactions
+    /// ahead of block processing.  0 checks them during block processing instead.
+    pub block_validation_threads: usize,
+    /// If true, index the transactions touching each principal as blocks are processed, and
+    /// serve them at `/v2/accounts/:principal/transactions`.  Only blocks processed while this
+    /// is set are indexed.
+    pub account_index: bool,
+    /// How the bytes of the blocks and microblocks the node stores are compressed.  Blocks and
+    /// microblocks stored before this was changed are still read; run `compact-blocks` to
+    /// rewrite them.
+    pub block_compression: BlockCompression,
+    /// If more than 1, a block is only processed once the L1 block-commit which chose it has
+    /// this many confirmations, counting the L1 block which includes it.  Followers set this so
+    /// that a shallow L1 reorg cannot take back a block they have already processed.
+    pub commit_confirmations: u64,
+    /// STX and FT withdrawals (and STX transfers to other subnets) of more than this amount are
+    /// held back for `large_withdrawal_delay` blocks before being included in a withdrawal root.
+    /// Every node of a subnet must use the same withdrawal policy, so it is recorded in the
+    /// chainstate when it is booted, and changing it afterwards has no effect.
+    pub large_withdrawal_threshold: u64,
+    /// 0 disables holding back large withdrawals
+    pub large_withdrawal_delay: u64,
+    /// If either allow-list is set, only contracts published by one of
+    /// `contract_publisher_allow_list` or whose code hashes to one of
+    /// `contract_code_hash_allow_list` are admitted to the mempool and mined.
+    pub contract_publisher_allow_list: Option<Vec<StacksAddress>>,
+    pub contract_code_hash_allow_list: Option<Vec<Sha512Trunc256Sum>>,
+    /// The RPC URLs (e.g. `http://10.0.0.1:20443`) of miners to forward each transaction this
+    /// node admits to its mempool to, so that transactions submitted to a follower reach the
+    /// miners right away.  Unreachable miners are retried a few times.
+    pub forward_txs_to: Vec<String>,
+    /// `.subnet-token` minted by each block, halved every `coinbase_halving_interval` blocks (0
+    /// never halves it), and paid with the block's fees to `coinbase_recipient`, or to the block's
+    /// miner if it is not set.  Recorded in the chainstate at genesis, so every node of a subnet
+    /// must boot with the same values, and changing them later has no effect.
+    pub coinbase_reward: u64,
+    pub coinbase_halving_interval: u64,
+    pub coinbase_recipient: Option<StacksAddress>,
+    /// The subnet block height from which each block records the timestamp of its L1 block in
+    /// the `.time-oracle` boot contract, which the block at this height installs.  Unset never
+    /// installs it.  Recorded in the chainstate at genesis (or, for a chainstate booted before
+    /// activation heights were recorded, the first time the node starts on it), so every node of
+    /// a subnet must use the same value.
+    pub time_oracle_activation_height: Option<u64>,
+    /// The subnet block height at which the `.subnet-transfers` boot contract, whose
+    /// `stx-transfer-to-subnet?` moves STX to another subnet, is installed.  Unset never installs
+    /// it.  Recorded in the chainstate like `time_oracle_activation_height`.
+    pub subnet_transfers_activation_height: Option<u64>,
+    /// The subnet block height from which FT, NFT and SFT deposits whose subnet deposit function
+    /// can't be called at all are refunded instead of dropped.  Unset never refunds them.
+    /// Recorded in the chainstate like `time_oracle_activation_height`.
+    pub deposit_refunds_activation_height: Option<u64>,
+    pub log: LogConfig,
+    /// If true, stop mining blocks and microblocks built on L1 blocks at or after the target
+    /// height of an upgrade signal from the L1 contract whose required version this node does
+    /// not meet.  Either way, the node logs and reports the signals it sees.
+    pub halt_for_upgrades: bool,
+}
+
+/// How the node logs: as text or JSON, and at which level, overall and per component
+#[derive(Clone, Debug, PartialEq)]
+pub struct LogConfig {
+    pub json: bool,
+    /// level of the components which have no level of their own
+    pub level: Level,
+    pub component_levels: Vec<(LogComponent, Level)>,
+}
+
+impl Default for LogConfig {
+    fn default() -> LogConfig {
+        LogConfig {
+            json: false,
+            level: Level::Info,
+            component_levels: vec![],
+        }
+    }
+}
+
+impl LogConfig {
+    /// The most verbose level any component logs at
+    pub fn max_level(&self) -> Level {
+        self.component_levels
+            .iter()
+            .map(|(_, level)| *level)
+            .fold(
+                self.level,
+                |acc, level| {
+                    if level.is_at_least(acc) {
+                        acc
+                    } else {
+                        level
+                    }
+                },
+            )
+    }
+
+    /// Set up logging.  This must be called before anything is logged: the global logger reads
+    /// its format and level from the environment when it logs its first line.  The global
+    /// logger only filters at info, debug or trace, and lines which are not from one of the
+    /// components pass through at the most verbose level any component uses.
+    pub fn apply(&self) {
+        if self.json {
+            env::set_var("STACKS_LOG_JSON", "1");
+        }
+        match self.max_level() {
+            Level::Trace => env::set_var("STACKS_LOG_TRACE", "1"),
+            Level::Debug => env::set_var("STACKS_LOG_DEBUG", "1"),
+            _ => {}
+        }
+        for component in LogComponent::ALL.iter() {
+            let level = self
+                .component_levels
+                .iter()
+                .find(|(c, _)| c == component)
+                .map(|(_, level)| *level)
+                .unwrap_or(self.level);
+            component_log::set_component_level(*component, level);
+        }
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -1018,7 +1807,7 @@ impl Config {
         coordinator: CoordinatorChannels,
     ) -> Result<Box<dyn BurnchainController + Send>, super::burnchains::Error> {
         match self.burnchain.chain.as_str() {
-            BURNCHAIN_NAME_MOCKSTACK => {
+            BURNCHAIN_NAME_MOCKSTACK | BURNCHAIN_NAME_SIMULATED => {
                 Ok(Box::new(MockController::new(self.clone(), coordinator)))
             }
             BURNCHAIN_NAME_STACKS_MAINNET_L1 | BURNCHAIN_NAME_STACKS_TESTNET_L1 => {
@@ -1130,6 +1919,32 @@ impl FeeEstimationConfig {
 }
 
 impl NodeConfig {
+    /// The address the node's p2p server listens on
+    pub fn get_p2p_bind_addr(&self) -> Result<SocketAddr, String> {
+        resolve_socket_addr("[node] p2p_bind", &self.p2p_bind)
+    }
+
+    /// The address the node's RPC server listens on
+    pub fn get_rpc_bind_addr(&self) -> Result<SocketAddr, String> {
+        resolve_socket_addr("[node] rpc_bind", &self.rpc_bind)
+    }
+
+    /// The address the node advertises to its peers, unless
+    /// `[connection_options] public_ip_address` is set
+    pub fn get_p2p_advertised_addr(&self) -> Result<SocketAddr, String> {
+        resolve_socket_addr("[node] p2p_address", &self.p2p_address)
+    }
+
+    /// Keep the node's databases in a fresh directory on a RAM-backed filesystem, which is
+    /// removed once the node is done with it, instead of in `working_dir`.  For ephemeral test
+    /// networks.
+    pub fn use_ephemeral_working_dir(&mut self) -> Result<(), String> {
+        let dir = EphemeralWorkingDir::create()?;
+        self.working_dir = dir.path_str();
+        self.ephemeral_dir = Some(Arc::new(dir));
+        Ok(())
+    }
+
     fn default() -> NodeConfig {
         let mut rng = rand::thread_rng();
         let mut buf = [0u8; 8];
@@ -1151,8 +1966,10 @@ impl NodeConfig {
         NodeConfig {
             name: name.to_string(),
             chain_id: SUBNET_CHAIN_ID,
+            network_id: SUBNET_CHAIN_ID,
             seed: seed.to_vec(),
             working_dir: format!("/tmp/{}", testnet_id),
+            ephemeral_dir: None,
             rpc_bind: format!("0.0.0.0:{}", rpc_port),
             p2p_bind: format!("0.0.0.0:{}", p2p_port),
             data_url: format!("http://127.0.0.1:{}", rpc_port),
@@ -1162,17 +1979,40 @@ impl NodeConfig {
             local_peer_seed: local_peer_seed.to_vec(),
             miner: false,
             mock_mining: false,
+            follower: false,
             mine_microblocks: true,
             microblock_frequency: 15_000,
             max_microblocks: u16::MAX as u64,
             wait_time_for_microblocks: 0,
             wait_before_first_anchored_block: 0,
             prometheus_bind: None,
+            ws_bind: None,
             marf_cache_strategy: None,
             marf_defer_hashing: true,
             pox_sync_sample_secs: 30,
             use_test_genesis_chainstate: None,
             mining_key: None,
+            event_queue: false,
+            prune_horizon: None,
+            mempool_persist_path: None,
+            shutdown_grace_period_secs: 60,
+            block_validation_threads: 0,
+            account_index: false,
+            block_compression: BlockCompression::None,
+            commit_confirmations: 0,
+            large_withdrawal_threshold: u64::MAX,
+            large_withdrawal_delay: 0,
+            contract_publisher_allow_list: None,
+            contract_code_hash_allow_list: None,
+            forward_txs_to: vec![],
+            coinbase_reward: 0,
+            coinbase_halving_interval: 0,
+            coinbase_recipient: None,
+            time_oracle_activation_height: None,
+            subnet_transfers_activation_height: None,
+            deposit_refunds_activation_height: None,
+            log: LogConfig::default(),
+            halt_for_upgrades: false,
         }
     }
 
@@ -1262,6 +2102,51 @@ impl NodeConfig {
             false,
         )
     }
+
+    pub fn get_withdrawal_policy(&self) -> WithdrawalPolicy {
+        WithdrawalPolicy {
+            large_withdrawal_threshold: u128::from(self.large_withdrawal_threshold),
+            large_withdrawal_delay: self.large_withdrawal_delay,
+        }
+    }
+
+    pub fn get_feature_activations(&self) -> FeatureActivations {
+        FeatureActivations {
+            time_oracle: self.time_oracle_activation_height,
+            subnet_transfers: self.subnet_transfers_activation_height,
+            deposit_refunds: self.deposit_refunds_activation_height,
+        }
+    }
+
+    pub fn get_coinbase_schedule(&self) -> CoinbaseSchedule {
+        CoinbaseSchedule {
+            amount_per_block: u128::from(self.coinbase_reward),
+            halving_interval: self.coinbase_halving_interval,
+            recipient: match self.coinbase_recipient {
+                Some(ref addr) => CoinbaseRecipient::Fixed(addr.clone()),
+                None => CoinbaseRecipient::Miner,
+            },
+        }
+    }
+
+    pub fn get_contract_publish_policy(&self) -> ContractPublishPolicy {
+        ContractPublishPolicy {
+            restricted: self.contract_publisher_allow_list.is_some()
+                || self.contract_code_hash_allow_list.is_some(),
+            allowed_publishers: self
+                .contract_publisher_allow_list
+                .iter()
+                .flatten()
+                .cloned()
+                .collect(),
+            allowed_code_hashes: self
+                .contract_code_hash_allow_list
+                .iter()
+                .flatten()
+                .cloned()
+                .collect(),
+        }
+    }
 }
 
 #[derive(Clone, Debug, Default)]
@@ -1273,6 +2158,73 @@ pub struct MinerConfig {
     pub probability_pick_no_estimate_tx: u8,
     pub nonce_cache_size: u64,
     pub candidate_retry_cache_size: u64,
+    /// the most of the block limit, in each dimension, one transaction may cost.  See
+    /// `BlockBuilderSettings::max_tx_cost_fraction`.
+    pub max_tx_cost_fraction: Option<f64>,
+    /// what signs the miner's block-commits
+    pub signer: CommitSignerConfig,
+    /// contract calls the miner makes itself, every so many burn blocks
+    pub scheduled_calls: Vec<ScheduledCallConfig>,
+}
+
+/// What signs the miner's block-commit transactions
+#[derive(Clone, Debug, PartialEq)]
+pub enum CommitSignerConfig {
+    /// The node signs them with its mining key
+    Local,
+    /// An external signer daemon signs them.  Requests are POSTed to `url`, signed with
+    /// `auth_key`, and the answers must be signed with the commit key, `public_key`.
+    Remote {
+        url: String,
+        public_key: Secp256k1PublicKey,
+        auth_key: Secp256k1PrivateKey,
+    },
+}
+
+impl CommitSignerConfig {
+    /// Load `[miner] signer` and the settings of the signer it selects
+    fn from_config_file(miner: &MinerConfigFile) -> Result<CommitSignerConfig, String> {
+        match miner.signer.as_deref() {
+            None | Some("local") => Ok(CommitSignerConfig::Local),
+            Some("remote") => {
+                let url = miner
+                    .signer_url
+                    .clone()
+                    .ok_or_else(|| "[miner] signer = \"remote\" requires signer_url".to_string())?;
+                let public_key = miner
+                    .signer_public_key
+                    .as_ref()
+                    .ok_or_else(|| {
+                        "[miner] signer = \"remote\" requires signer_public_key".to_string()
+                    })
+                    .and_then(|key| {
+                        Secp256k1PublicKey::from_hex(key).map_err(|_| {
+                            "[miner] signer_public_key is not a valid public key".to_string()
+                        })
+                    })?;
+                let auth_key = miner
+                    .signer_auth_key
+                    .as_ref()
+                    .ok_or_else(|| {
+                        "[miner] signer = \"remote\" requires signer_auth_key".to_string()
+                    })
+                    .and_then(|key| {
+                        Secp256k1PrivateKey::from_hex(key).map_err(|_| {
+                            "[miner] signer_auth_key is not a valid private key".to_string()
+                        })
+                    })?;
+                Ok(CommitSignerConfig::Remote {
+                    url,
+                    public_key,
+                    auth_key,
+                })
+            }
+            Some(other) => Err(format!(
+                "[miner] signer `{}` is not supported (expected `local` or `remote`)",
+                other
+            )),
+        }
+    }
 }
 
 impl MinerConfig {
@@ -1285,10 +2237,87 @@ impl MinerConfig {
             probability_pick_no_estimate_tx: 5,
             nonce_cache_size: 10_000,
             candidate_retry_cache_size: 10_000,
+            max_tx_cost_fraction: None,
+            signer: CommitSignerConfig::Local,
+            scheduled_calls: vec![],
         }
     }
 }
 
+/// A contract call the miner submits to its own mempool every `interval` burn blocks, signed
+/// with `signer_key`, e.g. to update a price feed
+#[derive(Clone, Debug)]
+pub struct ScheduledCallConfig {
+    pub contract: QualifiedContractIdentifier,
+    pub function: ClarityName,
+    pub args: Vec<ClarityValue>,
+    pub interval: u64,
+    pub signer_key: StacksPrivateKey,
+    /// the fee paid for each call, in uSTX
+    pub fee: u64,
+    /// the most the node spends on this call's fees, in uSTX, until it restarts
+    pub budget: Option<u64>,
+    /// if false, the call is sent in `Deny` post-condition mode, so it aborts if it moves any of
+    /// the signer's assets
+    pub allow_asset_transfers: bool,
+}
+
+impl ScheduledCallConfig {
+    fn from_config_file(call: &ScheduledCallConfigFile) -> Result<ScheduledCallConfig, String> {
+        let contract = QualifiedContractIdentifier::parse(&call.contract).map_err(|_| {
+            format!(
+                "[[miner.scheduled_calls]] contract `{}` is not a contract identifier",
+                &call.contract
+            )
+        })?;
+        let function = ClarityName::try_from(call.function.clone()).map_err(|_| {
+            format!(
+                "[[miner.scheduled_calls]] function `{}` is not a function name",
+                &call.function
+            )
+        })?;
+        let args = call
+            .args
+            .as_ref()
+            .map(|args| {
+                args.iter()
+                    .map(|arg| {
+                        ClarityValue::try_deserialize_hex_untyped(arg).map_err(|_| {
+                            format!(
+                                "[[miner.scheduled_calls]] argument `{}` is not a hex-encoded Clarity value",
+                                arg
+                            )
+                        })
+                    })
+                    .collect::<Result<Vec<_>, _>>()
+            })
+            .transpose()?
+            .unwrap_or_default();
+        if call.interval == 0 {
+            return Err(format!(
+                "[[miner.scheduled_calls]] interval of {}.{} must be at least 1",
+                &contract, &function
+            ));
+        }
+        let signer_key = StacksPrivateKey::from_hex(&call.signer_key).map_err(|_| {
+            format!(
+                "[[miner.scheduled_calls]] signer_key of {}.{} is not a valid private key",
+                &contract, &function
+            )
+        })?;
+        Ok(ScheduledCallConfig {
+            contract,
+            function,
+            args,
+            interval: call.interval,
+            signer_key,
+            fee: call.fee,
+            budget: call.budget,
+            allow_asset_transfers: call.allow_asset_transfers.unwrap_or(false),
+        })
+    }
+}
+
 #[derive(Clone, Default, Deserialize)]
 pub struct ConnectionOptionsFile {
     pub inbox_maxlen: Option<usize>,
@@ -1302,6 +2331,14 @@ pub struct ConnectionOptionsFile {
     pub num_neighbors: Option<u64>,
     pub num_clients: Option<u64>,
     pub max_http_clients: Option<u64>,
+    pub max_http_clients_per_host: Option<u64>,
+    pub rpc_read_rate_limit: Option<u64>,
+    pub rpc_read_burst: Option<u64>,
+    pub rpc_submit_rate_limit: Option<u64>,
+    pub rpc_submit_burst: Option<u64>,
+    pub rpc_replay_rate_limit: Option<u64>,
+    pub rpc_replay_burst: Option<u64>,
+    pub health_max_burn_block_lag: Option<u64>,
     pub soft_num_neighbors: Option<u64>,
     pub soft_num_clients: Option<u64>,
     pub max_neighbors_per_host: Option<u64>,
@@ -1309,6 +2346,9 @@ pub struct ConnectionOptionsFile {
     pub soft_max_neighbors_per_host: Option<u64>,
     pub soft_max_neighbors_per_org: Option<u64>,
     pub soft_max_clients_per_host: Option<u64>,
+    /// Hard cap on the number of outbound p2p connections, including the short-lived ones a
+    /// neighbor walk opens.  0 means unlimited.
+    pub max_outbound_neighbors: Option<u64>,
     pub max_sockets: Option<u64>,
     pub walk_interval: Option<u64>,
     pub dns_timeout: Option<u64>,
@@ -1330,12 +2370,52 @@ pub struct ConnectionOptionsFile {
     pub disable_block_download: Option<bool>,
     pub force_disconnect_interval: Option<u64>,
     pub antientropy_public: Option<bool>,
+    pub mempool_sync_interval: Option<u64>,
+    pub mempool_max_tx_query: Option<u64>,
+    pub mempool_sync_timeout: Option<u64>,
+    pub mempool_sync_inbound: Option<bool>,
+    pub max_mempool_sync_bandwidth: Option<u64>,
+    pub admin_auth_token: Option<String>,
+    pub rpc_api_keys: Option<Vec<RPCApiKeyConfigFile>>,
+    pub sign_rpc_responses: Option<bool>,
+}
+
+#[derive(Clone, Deserialize, Default)]
+pub struct RPCApiKeyConfigFile {
+    pub name: String,
+    /// hex-encoded SHA-256 of the key
+    pub key_sha256: String,
+    /// requests per second, once the burst is used up.  0 or unset means unlimited.
+    pub rate_limit: Option<u64>,
+    pub burst: Option<u64>,
+}
+
+impl RPCApiKeyConfigFile {
+    fn to_api_key(&self) -> Result<RPCApiKey, String> {
+        let key_hash = Sha256Sum::from_hex(&self.key_sha256).map_err(|_| {
+            format!(
+                "[[connection_options.rpc_api_keys]] key_sha256 of `{}` is not a hex-encoded SHA-256 hash",
+                &self.name
+            )
+        })?;
+        Ok(RPCApiKey {
+            name: self.name.clone(),
+            key_hash,
+            rate_limit: RateLimit {
+                rate: self.rate_limit.unwrap_or(0),
+                burst: self.burst.unwrap_or(0),
+            },
+        })
+    }
 }
 
 #[derive(Clone, Deserialize, Default)]
 pub struct NodeConfigFile {
     pub name: Option<String>,
     pub chain_id: Option<u32>,
+    pub network_id: Option<u32>,
+    /// Hex-encoded 2-byte network magic, carried in the p2p peer version
+    pub network_magic: Option<String>,
     pub seed: Option<String>,
     pub deny_nodes: Option<String>,
     pub working_dir: Option<String>,
@@ -1347,17 +2427,49 @@ pub struct NodeConfigFile {
     pub local_peer_seed: Option<String>,
     pub miner: Option<bool>,
     pub mock_mining: Option<bool>,
+    pub follower: Option<bool>,
     pub mine_microblocks: Option<bool>,
     pub microblock_frequency: Option<u64>,
     pub max_microblocks: Option<u64>,
     pub wait_time_for_microblocks: Option<u64>,
     pub wait_before_first_anchored_block: Option<u64>,
     pub prometheus_bind: Option<String>,
+    pub ws_bind: Option<String>,
     pub marf_cache_strategy: Option<String>,
     pub marf_defer_hashing: Option<bool>,
     pub pox_sync_sample_secs: Option<u64>,
     pub use_test_genesis_chainstate: Option<bool>,
     pub mining_key: Option<String>,
+    /// The key to rotate the miner to.  Only read when the admin key-rotation RPC is called.
+    pub next_mining_key: Option<String>,
+    /// The L1 block height at which the miner starts signing with `next_mining_key`.
+    pub mining_key_switch_height: Option<u64>,
+    pub event_queue: Option<bool>,
+    pub prune_horizon: Option<u64>,
+    pub mempool_persist_path: Option<String>,
+    pub shutdown_grace_period_secs: Option<u64>,
+    pub block_validation_threads: Option<usize>,
+    pub account_index: Option<bool>,
+    /// `none` (the default) or `zstd`
+    pub block_compression: Option<String>,
+    pub commit_confirmations: Option<u64>,
+    pub large_withdrawal_threshold: Option<u64>,
+    pub large_withdrawal_delay: Option<u64>,
+    pub contract_publisher_allow_list: Option<Vec<String>>,
+    pub contract_code_hash_allow_list: Option<Vec<String>>,
+    pub forward_txs_to: Option<Vec<String>>,
+    pub coinbase_reward: Option<u64>,
+    pub coinbase_halving_interval: Option<u64>,
+    pub coinbase_recipient: Option<String>,
+    pub time_oracle_activation_height: Option<u64>,
+    pub subnet_transfers_activation_height: Option<u64>,
+    pub deposit_refunds_activation_height: Option<u64>,
+    /// `text` (the default) or `json`
+    pub log_format: Option<String>,
+    pub log_level: Option<String>,
+    /// log level per component: `miner`, `burnchain`, `relayer` or `mempool`
+    pub log_levels: Option<HashMap<String, String>>,
+    pub halt_for_upgrades: Option<bool>,
 }
 
 #[derive(Clone, Deserialize)]
@@ -1385,6 +2497,57 @@ impl Default for FeeEstimationConfigFile {
     }
 }
 
+/// Limits on which transactions the mempool admits.  See `MemPoolAdmissionLimits`.
+#[derive(Clone, Debug, Default)]
+pub struct MemPoolConfig {
+    /// The largest serialized transaction admitted, in bytes.
+    pub max_tx_size: Option<u64>,
+    /// If set, only transactions with these payload types (`TokenTransfer`, `ContractCall`,
+    /// `SmartContract`, `PoisonMicroblock` or `Coinbase`) are admitted.
+    pub allowed_payload_types: Option<Vec<String>>,
+    /// The largest contract code body admitted, in bytes.
+    pub max_contract_code_size: Option<u64>,
+    /// If set, the mempool keeps the transactions it rejects, and why, for this long.
+    pub rejected_txs_retention: Option<RejectedTxsRetention>,
+    /// If set, every transaction submitted to the mempool is appended to this file, with the
+    /// time it arrived, for `replay-mempool` to reproduce the mempool from.
+    pub arrival_journal: Option<String>,
+}
+
+impl MemPoolConfig {
+    pub fn get_admission_limits(&self) -> MemPoolAdmissionLimits {
+        MemPoolAdmissionLimits {
+            max_tx_size: self.max_tx_size,
+            allowed_payload_types: self
+                .allowed_payload_types
+                .as_ref()
+                .map(|payload_types| payload_types.iter().cloned().collect()),
+            max_contract_code_size: self.max_contract_code_size,
+            max_tx_write_length: None,
+        }
+    }
+
+    /// Open the mempool arrival journal, if one is configured
+    pub fn open_arrival_journal(&self) -> Option<MemPoolArrivalJournal> {
+        self.arrival_journal.as_ref().map(|path| {
+            MemPoolArrivalJournal::open(path).unwrap_or_else(|e| {
+                panic!("Failed to open mempool arrival journal {}: {:?}", path, &e)
+            })
+        })
+    }
+}
+
+#[derive(Clone, Deserialize, Default)]
+pub struct MemPoolConfigFile {
+    pub max_tx_size: Option<u64>,
+    pub allowed_payload_types: Option<Vec<String>>,
+    pub max_contract_code_size: Option<u64>,
+    pub record_rejected_txs: Option<bool>,
+    pub rejected_txs_max_count: Option<u64>,
+    pub rejected_txs_max_age_secs: Option<u64>,
+    pub arrival_journal: Option<String>,
+}
+
 #[derive(Clone, Deserialize, Default)]
 pub struct MinerConfigFile {
     pub min_tx_fee: Option<u64>,
@@ -1394,6 +2557,32 @@ pub struct MinerConfigFile {
     pub probability_pick_no_estimate_tx: Option<u8>,
     pub nonce_cache_size: Option<u64>,
     pub candidate_retry_cache_size: Option<u64>,
+    /// e.g. `0.3` to cap each transaction at 30% of the block limit
+    pub max_tx_cost_fraction: Option<f64>,
+    /// `local` (the default) or `remote`
+    pub signer: Option<String>,
+    pub signer_url: Option<String>,
+    /// hex-encoded public key of the commit key the remote signer holds
+    pub signer_public_key: Option<String>,
+    /// hex-encoded private key the node signs remote signer requests with
+    pub signer_auth_key: Option<String>,
+    pub scheduled_calls: Option<Vec<ScheduledCallConfigFile>>,
+}
+
+#[derive(Clone, Deserialize, Default)]
+pub struct ScheduledCallConfigFile {
+    /// the contract identifier, e.g. `ST1PQHQKV0RJXZFY1DGX8MNSNYVE3VGZJSRTPGZGM.price-feed`
+    pub contract: String,
+    pub function: String,
+    /// hex-encoded Clarity values
+    pub args: Option<Vec<String>>,
+    /// how many burn blocks apart the calls are made
+    pub interval: u64,
+    /// hex-encoded private key the calls are signed with
+    pub signer_key: String,
+    pub fee: u64,
+    pub budget: Option<u64>,
+    pub allow_asset_transfers: Option<bool>,
 }
 
 #[derive(Clone, Deserialize, Default)]
@@ -1423,7 +2612,7 @@ pub enum EventKeyType {
 }
 
 impl EventKeyType {
-    fn from_string(raw_key: &str) -> Option<EventKeyType> {
+    pub fn from_string(raw_key: &str) -> Option<EventKeyType> {
         if raw_key == "*" {
             return Some(EventKeyType::AnyEvent);
         }
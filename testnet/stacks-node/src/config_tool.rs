@@ -0,0 +1,327 @@
+use rand::RngCore;
+use stacks::util::hash::to_hex;
+use stacks::util::secp256k1::Secp256k1PrivateKey;
+use stacks::vm::types::QualifiedContractIdentifier;
+
+use crate::config::{
+    resolve_socket_addr, BURNCHAIN_NAME_SIMULATED, BURNCHAIN_NAME_STACKS_TESTNET_L1,
+    DEFAULT_L1_OBSERVER_PORT, DEFAULT_SIMULATED_BLOCK_TIME_MS,
+};
+use crate::neon;
+use crate::run_loop::RunLoopError;
+use crate::{Config, ConfigFile};
+
+/// The subnet node's own RPC and p2p ports.  These differ from a Stacks node's defaults, so that
+/// the subnet node can run on the same host as the L1 node it follows.
+const SUBNET_RPC_PORT: u16 = 30443;
+const SUBNET_P2P_PORT: u16 = 30444;
+
+/// The RPC port of the L1 node a generated testnet config follows
+const L1_RPC_PORT: u16 = 20443;
+
+/// Hosts which name this machine, so an L1 node there can't share a port with this node
+const LOCAL_HOSTS: &[&str] = &["127.0.0.1", "localhost", "0.0.0.0", "::1"];
+
+/// The L1 that a config written by `config new` follows
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ConfigMode {
+    /// A simulated L1, which the node makes blocks for itself, for local development
+    Mocknet,
+    /// A Stacks testnet node running on this host
+    Testnet,
+}
+
+impl ConfigMode {
+    pub fn from_name(name: &str) -> Option<ConfigMode> {
+        match name {
+            "mocknet" => Some(ConfigMode::Mocknet),
+            "testnet" => Some(ConfigMode::Testnet),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ConfigMode::Mocknet => "mocknet",
+            ConfigMode::Testnet => "testnet",
+        }
+    }
+}
+
+/// Write a config file for a subnet miner following the subnet contract `l1_contract`, with a
+/// freshly generated mining key and peer seed.
+pub fn new_config(l1_contract: &str, mode: ConfigMode) -> Result<String, String> {
+    let contract_id = QualifiedContractIdentifier::parse(l1_contract).map_err(|_| {
+        format!(
+            "`{}` is not a valid contract identifier (expected `<address>.<contract-name>`)",
+            l1_contract
+        )
+    })?;
+
+    let mining_key = Secp256k1PrivateKey::new();
+    let mut local_peer_seed = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut local_peer_seed);
+
+    let burnchain_settings = match mode {
+        ConfigMode::Mocknet => format!(
+            "\
+chain = \"{chain}\"
+# How often the node makes a simulated L1 block
+simulated_block_time_ms = {block_time_ms}",
+            chain = BURNCHAIN_NAME_SIMULATED,
+            block_time_ms = DEFAULT_SIMULATED_BLOCK_TIME_MS,
+        ),
+        ConfigMode::Testnet => format!(
+            "\
+chain = \"{chain}\"
+peer_host = \"127.0.0.1\"
+rpc_port = {rpc_port}
+rpc_ssl = false
+# The L1 node must send its events to this port, with an [[events_observer]] section whose
+# endpoint is \"<this host>:{observer_port}\" and whose events_keys include \"*\".
+observer_port = {observer_port}",
+            chain = BURNCHAIN_NAME_STACKS_TESTNET_L1,
+            rpc_port = L1_RPC_PORT,
+            observer_port = DEFAULT_L1_OBSERVER_PORT,
+        ),
+    };
+
+    Ok(format!(
+        "\
+# Written by `subnet-node config new --mode {mode}`.  Check it with `subnet-node config validate`.
+
+[node]
+# Where the node keeps its chainstate.  Move this out of /tmp to keep it across reboots.
+working_dir = \"/tmp/subnet-{contract_name}\"
+rpc_bind = \"0.0.0.0:{rpc_port}\"
+p2p_bind = \"0.0.0.0:{p2p_port}\"
+data_url = \"http://127.0.0.1:{rpc_port}\"
+miner = true
+# The key this miner signs its block commits to the L1 with.  Keep it secret.
+mining_key = \"{mining_key}\"
+local_peer_seed = \"{local_peer_seed}\"
+wait_time_for_microblocks = 1_000
+wait_before_first_anchored_block = 2_000
+
+[burnchain]
+{burnchain_settings}
+contract_identifier = \"{contract_id}\"
+# The L1 height at which the subnet contract was deployed.  The node reads the L1 from here.
+first_burn_header_height = 1
+",
+        mode = mode.as_str(),
+        contract_name = contract_id.name,
+        rpc_port = SUBNET_RPC_PORT,
+        p2p_port = SUBNET_P2P_PORT,
+        mining_key = mining_key.to_hex(),
+        local_peer_seed = to_hex(&local_peer_seed),
+        burnchain_settings = burnchain_settings,
+        contract_id = contract_id,
+    ))
+}
+
+/// The port of a `host:port` address, resolving the host if it is a name
+fn bind_port(setting: &str, bind: &str) -> Result<u16, String> {
+    resolve_socket_addr(setting, bind).map(|addr| addr.port())
+}
+
+/// Check that none of the sockets this node listens on, nor the RPC port of an L1 node on this
+/// host, share a port.
+pub fn check_port_collisions(conf: &Config) -> Result<(), String> {
+    let mut ports: Vec<(String, u16)> = vec![
+        (
+            "[node] rpc_bind".to_string(),
+            bind_port("[node] rpc_bind", &conf.node.rpc_bind)?,
+        ),
+        (
+            "[node] p2p_bind".to_string(),
+            bind_port("[node] p2p_bind", &conf.node.p2p_bind)?,
+        ),
+    ];
+    if let Some(ref prometheus_bind) = conf.node.prometheus_bind {
+        ports.push((
+            "[node] prometheus_bind".to_string(),
+            bind_port("[node] prometheus_bind", prometheus_bind)?,
+        ));
+    }
+    if let Some(ref ws_bind) = conf.node.ws_bind {
+        ports.push((
+            "[node] ws_bind".to_string(),
+            bind_port("[node] ws_bind", ws_bind)?,
+        ));
+    }
+    if conf.burnchain.spawn_l1_observer() {
+        ports.push((
+            "[burnchain] observer_port".to_string(),
+            conf.burnchain.observer_port,
+        ));
+        if conf.burnchain.rpc_endpoints.is_empty() {
+            if LOCAL_HOSTS.contains(&conf.burnchain.peer_host.as_str()) {
+                ports.push(("[burnchain] rpc_port".to_string(), conf.burnchain.rpc_port));
+            }
+        } else {
+            for endpoint in conf.burnchain.rpc_endpoints.iter() {
+                let (host, port) = match endpoint.rsplit_once(':') {
+                    Some((host, port)) => (host, port),
+                    None => continue,
+                };
+                if !LOCAL_HOSTS.contains(&host) {
+                    continue;
+                }
+                if let Ok(port) = port.parse::<u16>() {
+                    ports.push((format!("[burnchain] rpc_endpoints `{}`", endpoint), port));
+                }
+            }
+        }
+    }
+
+    for (i, (setting, port)) in ports.iter().enumerate() {
+        if let Some((other_setting, _)) =
+            ports[..i].iter().find(|(_, other_port)| other_port == port)
+        {
+            return Err(format!(
+                "{} and {} both use port {}",
+                other_setting, setting, port
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Check the config file at `path` before the node is started: that it parses, that its keys
+/// and contract identifier are well-formed, that its ports don't collide, and that the L1 node
+/// is reachable and hosts the subnet contract.
+pub fn validate_config(path: &str) -> Result<(), RunLoopError> {
+    let config_file = ConfigFile::try_from_path(path).map_err(RunLoopError::InvalidConfig)?;
+    let conf = Config::try_from_config_file(config_file).map_err(RunLoopError::InvalidConfig)?;
+    check_port_collisions(&conf).map_err(RunLoopError::InvalidConfig)?;
+    neon::RunLoop::check_l1(&conf)
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+
+    use super::*;
+    use crate::ephemeral_dir::EphemeralWorkingDir;
+
+    const L1_CONTRACT: &str = "ST1PQHQKV0RJXZFY1DGX8MNSNYVE3VGZJSRTPGZGM.subnet-v3-0-1";
+
+    fn parse_config(content: &str) -> Config {
+        let config_file: ConfigFile = toml::from_str(content).unwrap();
+        Config::try_from_config_file(config_file).unwrap()
+    }
+
+    #[test]
+    fn test_new_config() {
+        for mode in [ConfigMode::Mocknet, ConfigMode::Testnet] {
+            assert_eq!(ConfigMode::from_name(mode.as_str()), Some(mode));
+
+            let conf = parse_config(&new_config(L1_CONTRACT, mode).unwrap());
+            assert_eq!(
+                conf.burnchain.contract_identifier,
+                QualifiedContractIdentifier::parse(L1_CONTRACT).unwrap()
+            );
+            assert!(conf.node.miner);
+            assert_eq!(bind_port("", &conf.node.rpc_bind), Ok(SUBNET_RPC_PORT));
+            assert_eq!(bind_port("", &conf.node.p2p_bind), Ok(SUBNET_P2P_PORT));
+            assert_eq!(check_port_collisions(&conf), Ok(()));
+            match mode {
+                ConfigMode::Mocknet => {
+                    assert_eq!(conf.burnchain.chain, BURNCHAIN_NAME_SIMULATED);
+                }
+                ConfigMode::Testnet => {
+                    assert_eq!(conf.burnchain.chain, BURNCHAIN_NAME_STACKS_TESTNET_L1);
+                    assert_eq!(conf.burnchain.rpc_port, L1_RPC_PORT);
+                }
+            }
+        }
+        assert_eq!(ConfigMode::from_name("mainnet"), None);
+
+        // each config gets its own keys
+        assert_ne!(
+            new_config(L1_CONTRACT, ConfigMode::Mocknet),
+            new_config(L1_CONTRACT, ConfigMode::Mocknet)
+        );
+
+        let err = new_config("not-a-contract", ConfigMode::Mocknet).unwrap_err();
+        assert!(
+            err.contains("is not a valid contract identifier"),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn test_check_port_collisions() {
+        let mocknet_conf = new_config(L1_CONTRACT, ConfigMode::Mocknet).unwrap();
+
+        let mut conf = parse_config(&mocknet_conf);
+        conf.node.p2p_bind = format!("0.0.0.0:{}", SUBNET_RPC_PORT);
+        assert_eq!(
+            check_port_collisions(&conf),
+            Err(format!(
+                "[node] rpc_bind and [node] p2p_bind both use port {}",
+                SUBNET_RPC_PORT
+            ))
+        );
+
+        // a simulated L1 has no RPC port to collide with
+        let mut conf = parse_config(&mocknet_conf);
+        conf.burnchain.rpc_port = SUBNET_RPC_PORT;
+        assert_eq!(check_port_collisions(&conf), Ok(()));
+
+        // an L1 node on this host can't share a port with this node...
+        let mut conf = parse_config(&new_config(L1_CONTRACT, ConfigMode::Testnet).unwrap());
+        conf.burnchain.rpc_port = SUBNET_P2P_PORT;
+        assert_eq!(
+            check_port_collisions(&conf),
+            Err(format!(
+                "[node] p2p_bind and [burnchain] rpc_port both use port {}",
+                SUBNET_P2P_PORT
+            ))
+        );
+
+        // ...but one on another host can
+        conf.burnchain.peer_host = "l1.example.com".into();
+        assert_eq!(check_port_collisions(&conf), Ok(()));
+
+        conf.burnchain.rpc_endpoints = vec![format!("localhost:{}", SUBNET_RPC_PORT)];
+        assert_eq!(
+            check_port_collisions(&conf),
+            Err(format!(
+                "[node] rpc_bind and [burnchain] rpc_endpoints `localhost:{}` both use port {}",
+                SUBNET_RPC_PORT, SUBNET_RPC_PORT
+            ))
+        );
+    }
+
+    #[test]
+    fn test_validate_config() {
+        let dir = EphemeralWorkingDir::create().unwrap();
+        let path = dir.path().join("subnet-miner.toml");
+        let path = path.to_str().unwrap();
+
+        match validate_config(path) {
+            Err(RunLoopError::InvalidConfig(_)) => {}
+            res => panic!("Expected InvalidConfig, got {:?}", res),
+        }
+
+        // a config for a simulated L1 needs no L1 node to validate
+        let content = new_config(L1_CONTRACT, ConfigMode::Mocknet).unwrap();
+        fs::write(path, &content).unwrap();
+        validate_config(path).unwrap();
+
+        let content = content.replace(
+            &format!("p2p_bind = \"0.0.0.0:{}\"", SUBNET_P2P_PORT),
+            &format!("p2p_bind = \"0.0.0.0:{}\"", SUBNET_RPC_PORT),
+        );
+        fs::write(path, &content).unwrap();
+        match validate_config(path) {
+            Err(RunLoopError::InvalidConfig(msg)) => {
+                assert!(msg.contains("both use port"), "{}", msg);
+            }
+            res => panic!("Expected InvalidConfig, got {:?}", res),
+        }
+    }
+}
@@ -0,0 +1,315 @@
+use std::time::{Duration, Instant};
+
+use stacks::address::AddressHashMode;
+use stacks::chainstate::stacks::{
+    StacksPrivateKey, StacksPublicKey, StacksTransaction, StacksTransactionSigner,
+    TransactionAnchorMode, TransactionAuth, TransactionSmartContract, TransactionSpendingCondition,
+    TransactionVersion,
+};
+use stacks::codec::StacksMessageCodec;
+use stacks::core::LAYER_1_CHAIN_ID_MAINNET;
+use stacks::net::{AccountEntryResponse, RPCPeerInfoData};
+use stacks::types::chainstate::StacksAddress;
+use stacks::util::sleep_ms;
+use stacks::util_lib::strings::StacksString;
+use stacks::vm::types::QualifiedContractIdentifier;
+use stacks::vm::ContractName;
+
+/// The subnet contract template, and the values of its template variables which don't depend on
+/// the network, as `core-contracts/contracts/process_template.sh` would fill it in
+const SUBNET_TEMPLATE: &str =
+    include_str!("../../../core-contracts/contracts/templates/subnet.clar.mustache");
+const COMMON_TEMPLATE_VALUES: &str =
+    include_str!("../../../core-contracts/contracts/config/common.yaml");
+
+/// The contract defining the subnet's own traits, under the name the subnet contract expects
+const SUBNET_TRAITS_CONTRACT: &str = "subnet-traits-v1";
+const SUBNET_TRAITS: &str =
+    include_str!("../../../core-contracts/contracts/templates/helper/subnet-traits.clar");
+
+/// The contract defining the SIP-009 NFT and SIP-010 FT traits, which is deployed alongside the
+/// subnet contract so that the subnet contract doesn't depend on where the L1 keeps them
+const SIP_TRAITS_CONTRACT: &str = "sip-traits";
+const SIP_TRAITS: &str =
+    include_str!("../../../core-contracts/contracts/templates/helper/sip-traits.clar");
+
+/// The fee paid for each publish, in uSTX, unless `--fee` is given
+pub const DEFAULT_PUBLISH_FEE: u64 = 1_000_000;
+
+/// How long to wait for the publishes to confirm on the L1.  L1 blocks follow Bitcoin blocks, so
+/// this allows for a few slow ones.
+const CONFIRMATION_TIMEOUT: Duration = Duration::from_secs(3600);
+
+/// How often to check whether the publishes have confirmed
+const CONFIRMATION_POLL_MS: u64 = 10_000;
+
+/// The value of each template variable in `yaml`, a file of `name: value` lines like those in
+/// `core-contracts/contracts/config`
+fn parse_template_values(yaml: &str) -> Vec<(String, String)> {
+    yaml.lines()
+        .filter_map(|line| line.split_once(':'))
+        .map(|(name, value)| {
+            let value = value.trim();
+            let value = value
+                .strip_prefix('"')
+                .and_then(|value| value.strip_suffix('"'))
+                .unwrap_or(value);
+            (name.trim().to_string(), value.to_string())
+        })
+        .collect()
+}
+
+/// Fill in the subnet contract template, with the traits taken from the trait contracts
+/// deployed alongside it
+pub fn render_subnet_contract() -> Result<String, String> {
+    let mut values = parse_template_values(COMMON_TEMPLATE_VALUES);
+    values.extend(
+        [
+            ("nft_trait", format!(".{}.nft-trait", SIP_TRAITS_CONTRACT)),
+            ("ft_trait", format!(".{}.ft-trait", SIP_TRAITS_CONTRACT)),
+            (
+                "mint_from_subnet_trait",
+                format!(".{}.mint-from-subnet-trait", SUBNET_TRAITS_CONTRACT),
+            ),
+            (
+                "sft_trait",
+                format!(".{}.sft-trait", SUBNET_TRAITS_CONTRACT),
+            ),
+            (
+                "subnet_stx_receiver_trait",
+                format!(".{}.subnet-stx-receiver-trait", SUBNET_TRAITS_CONTRACT),
+            ),
+        ]
+        .into_iter()
+        .map(|(name, value)| (name.to_string(), value)),
+    );
+
+    let mut contract = SUBNET_TEMPLATE.to_string();
+    for (name, value) in values.iter() {
+        contract = contract.replace(&format!("{{{{{{{}}}}}}}", name), value);
+    }
+    if let Some(start) = contract.find("{{") {
+        let end = contract[start..]
+            .find("}}")
+            .map(|end| start + end + 2)
+            .unwrap_or(contract.len());
+        return Err(format!(
+            "subnet contract template variable {} has no value",
+            &contract[start..end]
+        ));
+    }
+    Ok(contract)
+}
+
+/// The default name of the subnet contract, from the contract templates' config
+pub fn default_contract_name() -> String {
+    parse_template_values(COMMON_TEMPLATE_VALUES)
+        .into_iter()
+        .find(|(name, _)| name == "subnet_contract")
+        .map(|(_, value)| value)
+        .unwrap_or_else(|| "subnet".to_string())
+}
+
+fn make_contract_publish(
+    key: &StacksPrivateKey,
+    mainnet: bool,
+    chain_id: u32,
+    nonce: u64,
+    fee: u64,
+    name: &str,
+    code: &str,
+) -> Result<StacksTransaction, String> {
+    let version = if mainnet {
+        TransactionVersion::Mainnet
+    } else {
+        TransactionVersion::Testnet
+    };
+    let payload = TransactionSmartContract {
+        name: ContractName::try_from(name.to_string())
+            .map_err(|_| format!("`{}` is not a valid contract name", name))?,
+        code_body: StacksString::from_string(&code.to_string())
+            .ok_or_else(|| format!("contract {} is not printable ASCII", name))?,
+    };
+
+    let mut spending_condition =
+        TransactionSpendingCondition::new_singlesig_p2pkh(StacksPublicKey::from_private(key))
+            .expect("Failed to create p2pkh spending condition from public key.");
+    spending_condition.set_nonce(nonce);
+    spending_condition.set_tx_fee(fee);
+    let auth = TransactionAuth::Standard(spending_condition);
+
+    let mut unsigned_tx = StacksTransaction::new(version, auth, payload.into());
+    unsigned_tx.anchor_mode = TransactionAnchorMode::OnChainOnly;
+    unsigned_tx.chain_id = chain_id;
+
+    let mut tx_signer = StacksTransactionSigner::new(&unsigned_tx);
+    tx_signer
+        .sign_origin(key)
+        .map_err(|e| format!("failed to sign the publish of {}: {:?}", name, &e))?;
+    tx_signer
+        .get_tx()
+        .ok_or_else(|| format!("failed to sign the publish of {}", name))
+}
+
+/// Is `contract_id` deployed on the L1 node at `l1_rpc`?
+fn contract_exists(
+    client: &reqwest::blocking::Client,
+    l1_rpc: &str,
+    contract_id: &QualifiedContractIdentifier,
+) -> Result<bool, String> {
+    let url = format!(
+        "{}/v2/contracts/interface/{}/{}",
+        l1_rpc, &contract_id.issuer, &contract_id.name
+    );
+    let response = client
+        .get(url)
+        .send()
+        .map_err(|e| format!("failed to look up {} on the L1: {}", contract_id, e))?;
+    if response.status() == reqwest::StatusCode::NOT_FOUND {
+        return Ok(false);
+    }
+    response
+        .error_for_status()
+        .map_err(|e| format!("failed to look up {} on the L1: {}", contract_id, e))?;
+    Ok(true)
+}
+
+fn get_nonce(
+    client: &reqwest::blocking::Client,
+    l1_rpc: &str,
+    address: &StacksAddress,
+) -> Result<u64, String> {
+    let url = format!("{}/v2/accounts/{}?proof=0", l1_rpc, address);
+    client
+        .get(url)
+        .send()
+        .and_then(|response| response.error_for_status())
+        .and_then(|response| response.json::<AccountEntryResponse>())
+        .map(|account| account.nonce)
+        .map_err(|e| format!("failed to get the nonce of {} from the L1: {}", address, e))
+}
+
+/// Publish the subnet contract named `contract_name`, and the trait contracts it uses, to the
+/// L1 node at `l1_rpc`, from the address of `key_hex`, in the order they depend on each other.
+/// Contracts which that address has already published are left alone, so a deployment which
+/// was cut short can be finished by running this again.  Waits for the publishes to confirm,
+/// and returns the subnet contract's identifier.
+pub fn deploy_l1_contracts(
+    key_hex: &str,
+    l1_rpc: &str,
+    contract_name: &str,
+    fee: u64,
+) -> Result<QualifiedContractIdentifier, String> {
+    let key = StacksPrivateKey::from_hex(key_hex)
+        .map_err(|_| "--key is not a hex-encoded private key".to_string())?;
+    let l1_rpc = l1_rpc.trim_end_matches('/');
+    let subnet_contract = render_subnet_contract()?;
+
+    let client = reqwest::blocking::Client::new();
+    let info = client
+        .get(format!("{}/v2/info", l1_rpc))
+        .send()
+        .and_then(|response| response.error_for_status())
+        .and_then(|response| response.json::<RPCPeerInfoData>())
+        .map_err(|e| format!("failed to reach the L1 node at {}: {}", l1_rpc, e))?;
+    let mainnet = info.network_id == LAYER_1_CHAIN_ID_MAINNET;
+    let hash_mode = AddressHashMode::SerializeP2PKH;
+    let address_version = if mainnet {
+        hash_mode.to_version_mainnet()
+    } else {
+        hash_mode.to_version_testnet()
+    };
+    let address = StacksAddress::from_public_keys(
+        address_version,
+        &hash_mode,
+        1,
+        &vec![StacksPublicKey::from_private(&key)],
+    )
+    .expect("Failed to make Stacks address from public key");
+
+    // the subnet contract uses the traits, so it must come last
+    let contracts = [
+        (SUBNET_TRAITS_CONTRACT, SUBNET_TRAITS),
+        (SIP_TRAITS_CONTRACT, SIP_TRAITS),
+        (contract_name, subnet_contract.as_str()),
+    ];
+    let mut nonce = get_nonce(&client, l1_rpc, &address)?;
+    let mut published = vec![];
+    for (name, code) in contracts.iter() {
+        let contract_id = QualifiedContractIdentifier::new(
+            address.clone().into(),
+            ContractName::try_from(name.to_string())
+                .map_err(|_| format!("`{}` is not a valid contract name", name))?,
+        );
+        if contract_exists(&client, l1_rpc, &contract_id)? {
+            println!("{} is already published", &contract_id);
+            continue;
+        }
+
+        let tx = make_contract_publish(&key, mainnet, info.network_id, nonce, fee, name, code)?;
+        let response = client
+            .post(format!("{}/v2/transactions", l1_rpc))
+            .header("Content-Type", "application/octet-stream")
+            .body(tx.serialize_to_vec())
+            .send()
+            .map_err(|e| format!("failed to submit the publish of {}: {}", &contract_id, e))?;
+        if !response.status().is_success() {
+            return Err(format!(
+                "the L1 node rejected the publish of {}: {}",
+                &contract_id,
+                response.text().unwrap_or_default()
+            ));
+        }
+        println!(
+            "Submitted {} (txid {}, nonce {})",
+            &contract_id,
+            &tx.txid(),
+            nonce
+        );
+        published.push(contract_id);
+        nonce += 1;
+    }
+
+    // the publishes confirm in nonce order, so once the last one has, they all have
+    let start = Instant::now();
+    while !published.is_empty() && get_nonce(&client, l1_rpc, &address)? < nonce {
+        if start.elapsed() > CONFIRMATION_TIMEOUT {
+            return Err(format!(
+                "the publishes did not confirm within {}s; run this again to pick up where it left off",
+                CONFIRMATION_TIMEOUT.as_secs()
+            ));
+        }
+        println!("Waiting for the publishes to confirm...");
+        sleep_ms(CONFIRMATION_POLL_MS);
+    }
+    for contract_id in published.iter() {
+        if !contract_exists(&client, l1_rpc, contract_id)? {
+            return Err(format!(
+                "the publish of {} confirmed but failed; check the transaction on the L1",
+                contract_id
+            ));
+        }
+        println!("Published {}", contract_id);
+    }
+
+    Ok(QualifiedContractIdentifier::new(
+        address.into(),
+        ContractName::try_from(contract_name.to_string())
+            .map_err(|_| format!("`{}` is not a valid contract name", contract_name))?,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_subnet_contract() {
+        let contract = render_subnet_contract().unwrap();
+        assert!(!contract.contains("{{"));
+        assert!(contract.contains("(use-trait nft-trait .sip-traits.nft-trait)"));
+        assert!(contract.contains("(use-trait ft-trait .sip-traits.ft-trait)"));
+        assert!(contract.contains("(impl-trait .subnet-traits-v1.subnet-stx-receiver-trait)"));
+        assert_eq!(default_contract_name(), "subnet-v3-0-1");
+    }
+}
@@ -0,0 +1,121 @@
+//! The working directory of a node configured with `working_dir = ":memory:"`, for ephemeral
+//! test networks.  The node's databases and MARF are kept in a fresh directory on a RAM-backed
+//! filesystem where there is one, which is removed once the node's config is dropped, or at the
+//! latest when the process exits, so that nothing is left behind on disk.
+
+use std::collections::HashSet;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::{Mutex, Once};
+
+use rand::RngCore;
+use stacks::util::hash::to_hex;
+
+/// The `working_dir` which asks for an ephemeral working directory
+pub const EPHEMERAL_WORKING_DIR: &str = ":memory:";
+
+/// Where ephemeral working directories are made, if it exists: a tmpfs on Linux
+const RAM_BACKED_DIR: &str = "/dev/shm";
+
+lazy_static! {
+    /// The ephemeral working directories which have not been removed yet
+    static ref LIVE_DIRS: Mutex<HashSet<PathBuf>> = Mutex::new(HashSet::new());
+}
+
+static REGISTER_EXIT_CLEANUP: Once = Once::new();
+
+/// Remove the ephemeral working directories which are still around when the process exits
+extern "C" fn remove_live_dirs() {
+    let dirs = match LIVE_DIRS.lock() {
+        Ok(mut dirs) => dirs.drain().collect(),
+        Err(_) => vec![],
+    };
+    for dir in dirs {
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
+
+/// An ephemeral working directory, removed when this is dropped
+#[derive(Debug)]
+pub struct EphemeralWorkingDir {
+    path: PathBuf,
+}
+
+impl EphemeralWorkingDir {
+    /// Make a new, empty ephemeral working directory
+    pub fn create() -> Result<EphemeralWorkingDir, String> {
+        let parent = if Path::new(RAM_BACKED_DIR).is_dir() {
+            PathBuf::from(RAM_BACKED_DIR)
+        } else {
+            env::temp_dir()
+        };
+        let mut suffix = [0u8; 8];
+        rand::thread_rng().fill_bytes(&mut suffix);
+        let path = parent.join(format!(
+            "subnet-node-ephemeral-{}-{}",
+            process::id(),
+            to_hex(&suffix)
+        ));
+        fs::create_dir_all(&path).map_err(|e| {
+            format!(
+                "Failed to create ephemeral working directory {}: {}",
+                path.display(),
+                e
+            )
+        })?;
+
+        REGISTER_EXIT_CLEANUP.call_once(|| unsafe {
+            libc::atexit(remove_live_dirs);
+        });
+        LIVE_DIRS
+            .lock()
+            .expect("FATAL: ephemeral working directories lock is poisoned")
+            .insert(path.clone());
+
+        info!("Using ephemeral working directory"; "path" => %path.display());
+        Ok(EphemeralWorkingDir { path })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn path_str(&self) -> String {
+        self.path
+            .to_str()
+            .expect("BUG: ephemeral working directory path is not UTF-8")
+            .to_string()
+    }
+}
+
+impl Drop for EphemeralWorkingDir {
+    fn drop(&mut self) {
+        if let Ok(mut dirs) = LIVE_DIRS.lock() {
+            dirs.remove(&self.path);
+        }
+        if let Err(e) = fs::remove_dir_all(&self.path) {
+            warn!("Failed to remove ephemeral working directory";
+                  "path" => %self.path.display(),
+                  "error" => %e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_ephemeral_working_dir_removed_on_drop() {
+        let dir = EphemeralWorkingDir::create().unwrap();
+        let path = dir.path().to_path_buf();
+        fs::write(path.join("db.sqlite"), b"data").unwrap();
+        assert!(LIVE_DIRS.lock().unwrap().contains(&path));
+
+        drop(dir);
+        assert!(!path.exists());
+        assert!(!LIVE_DIRS.lock().unwrap().contains(&path));
+    }
+}
@@ -1,9 +1,10 @@
+use std::cmp;
 use std::collections::hash_map::Entry;
 use std::thread::sleep;
 use std::time::Duration;
 use std::{
     collections::{HashMap, HashSet},
-    sync::{Arc, Mutex},
+    sync::{Arc, Mutex, RwLock},
 };
 
 use async_h1::client;
@@ -13,6 +14,11 @@ use serde_json::json;
 
 use stacks::burnchains::Txid;
 use stacks::chainstate::coordinator::BlockEventDispatcher;
+use stacks::chainstate::stacks::db::block_costs::ContractCost;
+use stacks::chainstate::stacks::db::event_replay::{
+    EventReplayFormatter, EventReplayPayload, ReplayedEvent,
+};
+use stacks::chainstate::stacks::db::withdrawal_policy::QueuedWithdrawal;
 use stacks::chainstate::stacks::db::StacksHeaderInfo;
 use stacks::chainstate::stacks::events::{
     StacksTransactionEvent, StacksTransactionReceipt, TransactionOrigin,
@@ -34,15 +40,19 @@ use stacks::vm::events::{FTEventType, NFTEventType, STXEventType};
 use stacks::vm::types::{AssetIdentifier, QualifiedContractIdentifier, Value};
 
 use super::config::{EventKeyType, EventObserverConfig};
+use super::event_queue::EventQueueDB;
+use super::event_stream::{EventStream, StreamTopic};
 use stacks::chainstate::burn::operations::BlockstackOperationType;
 use stacks::chainstate::burn::ConsensusHash;
 use stacks::chainstate::stacks::db::unconfirmed::ProcessedUnconfirmedState;
 use stacks::chainstate::stacks::miner::TransactionEvent;
 use stacks::chainstate::stacks::TransactionPayload;
 
-#[derive(Debug, Clone)]
+#[derive(Clone)]
 struct EventObserver {
     endpoint: String,
+    /// If set, payloads are persisted here until the observer acknowledges them.
+    queue: Option<Arc<Mutex<EventQueueDB>>>,
 }
 
 struct ReceiptPayloadInfo<'a> {
@@ -66,6 +76,7 @@ pub const PATH_MINED_BLOCK: &str = "mined_block";
 pub const PATH_MINED_MICROBLOCK: &str = "mined_microblock";
 pub const PATH_BURN_BLOCK_SUBMIT: &str = "new_burn_block";
 pub const PATH_BLOCK_PROCESSED: &str = "new_block";
+pub const PATH_BLOCK_REJECTED: &str = "rejected_block";
 pub const PATH_ATTACHMENT_PROCESSED: &str = "attachments/new";
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -77,6 +88,9 @@ pub struct MinedBlockEvent {
     pub anchored_cost: ExecutionCost,
     pub confirmed_microblocks_cost: ExecutionCost,
     pub tx_events: Vec<TransactionEvent>,
+    /// the contracts whose transactions in the block used the most runtime, highest first
+    #[serde(default)]
+    pub top_contracts: Vec<ContractCost>,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -88,8 +102,90 @@ pub struct MinedMicroblockEvent {
     pub anchor_block: BlockHeaderHash,
 }
 
+/// How many contracts a mined block event lists in `top_contracts`
+const MINED_BLOCK_TOP_CONTRACTS: usize = 5;
+
+/// Initial delay between delivery attempts to an unreachable observer
+const EVENT_QUEUE_INITIAL_BACKOFF_MS: u64 = 1_000;
+/// Upper bound on the delay between delivery attempts to an unreachable observer
+const EVENT_QUEUE_MAX_BACKOFF_MS: u64 = 30_000;
+/// How many times to try to deliver a queued payload before leaving it for the next flush
+const EVENT_QUEUE_MAX_ATTEMPTS_PER_FLUSH: u64 = 5;
+/// How many queued payloads to load from the queue at a time
+const EVENT_QUEUE_FLUSH_BATCH_SIZE: u64 = 64;
+
+/// Serialize a transaction receipt the way event observers receive it in a new block
+pub(crate) fn tx_receipt_json(
+    receipt: &StacksTransactionReceipt,
+    tx_index: u32,
+) -> serde_json::Value {
+    EventObserver::make_new_block_txs_payload(receipt, tx_index)
+}
+
 impl EventObserver {
+    fn make_url(&self, path: &str) -> Url {
+        let joined_components = match path.starts_with("/") {
+            true => format!("{}{}", &self.endpoint, path),
+            false => format!("{}/{}", &self.endpoint, path),
+        };
+        let url = format!("http://{}", joined_components);
+        Url::parse(&url).expect(&format!(
+            "Event dispatcher: unable to parse {} as a URL",
+            url
+        ))
+    }
+
+    /// Make a single attempt to POST `body` to the observer.
+    /// Returns true if the observer acknowledged it.
+    fn try_send_body(&self, body: &Vec<u8>, url: &Url) -> bool {
+        let mut req = Request::new(Method::Post, url.clone());
+        req.append_header("Content-Type", "application/json");
+        req.set_body(body.clone());
+
+        let response = async_std::task::block_on(async {
+            let stream = match TcpStream::connect(self.endpoint.clone()).await {
+                Ok(stream) => stream,
+                Err(err) => {
+                    warn!("Event dispatcher: connection failed  - {:?}", err);
+                    return None;
+                }
+            };
+
+            match client::connect(stream, req).await {
+                Ok(response) => Some(response),
+                Err(err) => {
+                    warn!("Event dispatcher: rpc invocation failed  - {:?}", err);
+                    return None;
+                }
+            }
+        });
+
+        if let Some(response) = response {
+            if response.status().is_success() {
+                debug!(
+                    "Event dispatcher: Successful POST"; "url" => %url
+                );
+                return true;
+            } else {
+                error!(
+                    "Event dispatcher: Failed POST"; "url" => %url, "err" => ?response
+                );
+            }
+        }
+        false
+    }
+
     fn send_payload(&self, payload: &serde_json::Value, path: &str) {
+        if let Some(queue) = self.queue.as_ref() {
+            let mut queue = queue.lock().expect("FATAL: event queue lock is poisoned");
+            if let Err(e) = queue.enqueue(&self.endpoint, path, payload) {
+                error!("Event dispatcher: failed to enqueue payload - {:?}", &e; "endpoint" => &self.endpoint, "path" => path);
+                return;
+            }
+            self.flush_queue(&mut queue);
+            return;
+        }
+
         let body = match serde_json::to_vec(&payload) {
             Ok(body) => body,
             Err(err) => {
@@ -98,57 +194,77 @@ impl EventObserver {
             }
         };
 
-        let url = {
-            let joined_components = match path.starts_with("/") {
-                true => format!("{}{}", &self.endpoint, path),
-                false => format!("{}/{}", &self.endpoint, path),
-            };
-            let url = format!("http://{}", joined_components);
-            Url::parse(&url).expect(&format!(
-                "Event dispatcher: unable to parse {} as a URL",
-                url
-            ))
-        };
+        let url = self.make_url(path);
+        let backoff = Duration::from_millis(EVENT_QUEUE_INITIAL_BACKOFF_MS);
 
-        let backoff = Duration::from_millis((1.0 * 1_000.0) as u64);
+        loop {
+            if self.try_send_body(&body, &url) {
+                break;
+            }
+            sleep(backoff);
+        }
+    }
 
+    /// Deliver this observer's queued payloads, oldest first. Each payload is retried with
+    /// exponential backoff; if it still cannot be delivered, it (and everything queued after it)
+    /// stays in the queue until the next flush, so the observer receives every payload
+    /// at least once and in order.
+    fn flush_queue(&self, queue: &mut EventQueueDB) {
         loop {
-            let body = body.clone();
-            let mut req = Request::new(Method::Post, url.clone());
-            req.append_header("Content-Type", "application/json");
-            req.set_body(body);
-
-            let response = async_std::task::block_on(async {
-                let stream = match TcpStream::connect(self.endpoint.clone()).await {
-                    Ok(stream) => stream,
+            let pending = match queue.get_pending(&self.endpoint, EVENT_QUEUE_FLUSH_BATCH_SIZE) {
+                Ok(pending) => pending,
+                Err(e) => {
+                    error!("Event dispatcher: failed to load queued payloads - {:?}", &e; "endpoint" => &self.endpoint);
+                    return;
+                }
+            };
+            if pending.is_empty() {
+                return;
+            }
+
+            for event in pending.into_iter() {
+                let body = match serde_json::to_vec(&event.payload) {
+                    Ok(body) => body,
                     Err(err) => {
-                        warn!("Event dispatcher: connection failed  - {:?}", err);
-                        return None;
+                        error!("Event dispatcher: serialization failed  - {:?}", err);
+                        return;
                     }
                 };
-
-                match client::connect(stream, req).await {
-                    Ok(response) => Some(response),
-                    Err(err) => {
-                        warn!("Event dispatcher: rpc invocation failed  - {:?}", err);
-                        return None;
+                let url = self.make_url(&event.path);
+
+                let mut backoff_ms = EVENT_QUEUE_INITIAL_BACKOFF_MS;
+                let mut delivered = false;
+                for attempt in 0..EVENT_QUEUE_MAX_ATTEMPTS_PER_FLUSH {
+                    if attempt > 0 {
+                        sleep(Duration::from_millis(backoff_ms));
+                        backoff_ms = cmp::min(backoff_ms * 2, EVENT_QUEUE_MAX_BACKOFF_MS);
+                    }
+                    if self.try_send_body(&body, &url) {
+                        delivered = true;
+                        break;
+                    }
+                    if let Err(e) = queue.record_attempt(event.seq) {
+                        warn!(
+                            "Event dispatcher: failed to record delivery attempt - {:?}",
+                            &e
+                        );
                     }
                 }
-            });
-
-            if let Some(response) = response {
-                if response.status().is_success() {
-                    debug!(
-                        "Event dispatcher: Successful POST"; "url" => %url
-                    );
-                    break;
-                } else {
-                    error!(
-                        "Event dispatcher: Failed POST"; "url" => %url, "err" => ?response
-                    );
+
+                if !delivered {
+                    warn!("Event dispatcher: observer unreachable; leaving payloads queued";
+                          "endpoint" => &self.endpoint,
+                          "seq" => event.seq,
+                          "pending" => queue.count_pending(&self.endpoint).unwrap_or(0));
+                    return;
+                }
+
+                if let Err(e) = queue.mark_delivered(&self.endpoint, event.seq) {
+                    // the payload may be re-sent on the next flush, which is permitted
+                    error!("Event dispatcher: failed to mark payload as delivered - {:?}", &e; "endpoint" => &self.endpoint, "seq" => event.seq);
+                    return;
                 }
             }
-            sleep(backoff);
         }
     }
 
@@ -241,6 +357,49 @@ impl EventObserver {
         }
     }
 
+    /// Returns json payload describing withdrawals held back by the withdrawal policy
+    fn make_queued_withdrawals_payload(withdrawals: &Vec<QueuedWithdrawal>) -> serde_json::Value {
+        let withdrawals_vec = withdrawals
+            .iter()
+            .map(|withdrawal| {
+                let value = Value::Tuple(withdrawal.data.clone());
+                json!({
+                    "value": value,
+                    "raw_value": format!("0x{}", bytes_to_hex(&value.serialize_to_vec())),
+                    "queued_height": withdrawal.queued_height,
+                    "release_height": withdrawal.release_height,
+                })
+            })
+            .collect();
+        serde_json::Value::Array(withdrawals_vec)
+    }
+
+    fn make_matured_rewards_payload(
+        mature_rewards: &Vec<MinerReward>,
+        mature_rewards_info: Option<&MinerRewardInfo>,
+    ) -> serde_json::Value {
+        let mature_rewards_vec = if let Some(rewards_info) = mature_rewards_info {
+            mature_rewards
+                .iter()
+                .map(|reward| {
+                    json!({
+                        "recipient": reward.address.to_string(),
+                        "coinbase_amount": reward.coinbase.to_string(),
+                        "tx_fees_anchored": reward.tx_fees_anchored.to_string(),
+                        "tx_fees_streamed_confirmed": reward.tx_fees_streamed_confirmed.to_string(),
+                        "tx_fees_streamed_produced": reward.tx_fees_streamed_produced.to_string(),
+                        "from_stacks_block_hash": format!("0x{}", rewards_info.from_stacks_block_hash),
+                        "from_index_consensus_hash": format!("0x{}", StacksBlockId::new(&rewards_info.from_block_consensus_hash,
+                                                                                        &rewards_info.from_stacks_block_hash)),
+                    })
+                })
+                .collect()
+        } else {
+            vec![]
+        };
+        serde_json::Value::Array(mature_rewards_vec)
+    }
+
     /// Returns json payload to send for new block or microblock event
     fn make_new_block_txs_payload(
         receipt: &StacksTransactionReceipt,
@@ -286,9 +445,8 @@ impl EventObserver {
         self.send_payload(payload, PATH_MEMPOOL_TX_SUBMIT);
     }
 
-    /// Serializes new microblocks data into a JSON payload and sends it off to the correct path
-    fn send_new_microblocks(
-        &self,
+    /// Serializes new microblocks data into a JSON payload
+    fn make_new_microblocks_payload(
         parent_index_block_hash: StacksBlockId,
         filtered_events: Vec<(usize, &(bool, Txid, &StacksTransactionEvent))>,
         serialized_txs: &Vec<serde_json::Value>,
@@ -304,16 +462,18 @@ impl EventObserver {
             })
             .collect();
 
-        let payload = json!({
+        json!({
             "parent_index_block_hash": format!("0x{}", parent_index_block_hash),
             "events": serialized_events,
             "transactions": serialized_txs,
             "burn_block_hash": format!("0x{}", burn_block_hash),
             "burn_block_height": burn_block_height,
             "burn_block_timestamp": burn_block_timestamp,
-        });
+        })
+    }
 
-        self.send_payload(&payload, PATH_MICROBLOCK_SUBMIT);
+    fn send_new_microblocks(&self, payload: &serde_json::Value) {
+        self.send_payload(payload, PATH_MICROBLOCK_SUBMIT);
     }
 
     fn send_dropped_mempool_txs(&self, payload: &serde_json::Value) {
@@ -332,8 +492,8 @@ impl EventObserver {
         self.send_payload(payload, PATH_BURN_BLOCK_SUBMIT);
     }
 
-    fn send(
-        &self,
+    /// Serializes a new block's data into a JSON payload
+    fn make_new_block_payload(
         filtered_events: Vec<(usize, &(bool, Txid, &StacksTransactionEvent))>,
         block: &StacksBlock,
         metadata: &StacksHeaderInfo,
@@ -347,7 +507,9 @@ impl EventObserver {
         parent_burn_block_timestamp: u64,
         anchored_consumed: &ExecutionCost,
         mblock_confirmed_consumed: &ExecutionCost,
-    ) {
+        withdrawals_queued: &serde_json::Value,
+        withdrawals_released: &serde_json::Value,
+    ) -> serde_json::Value {
         // Serialize events to JSON
         let serialized_events: Vec<serde_json::Value> = filtered_events
             .iter()
@@ -366,7 +528,7 @@ impl EventObserver {
         }
 
         // Wrap events
-        let payload = json!({
+        json!({
             "block_hash": format!("0x{}", block.block_hash()),
             "block_height": metadata.stacks_block_height,
             "burn_block_hash": format!("0x{}", metadata.burn_header_hash),
@@ -386,15 +548,23 @@ impl EventObserver {
             "parent_burn_block_timestamp": parent_burn_block_timestamp,
             "anchored_cost": anchored_consumed,
             "confirmed_microblocks_cost": mblock_confirmed_consumed,
-        });
+            "withdrawals_queued": withdrawals_queued.clone(),
+            "withdrawals_released": withdrawals_released.clone(),
+        })
+    }
+
+    fn send_new_block(&self, payload: &serde_json::Value) {
+        self.send_payload(payload, PATH_BLOCK_PROCESSED);
+    }
 
-        // Send payload
-        self.send_payload(&payload, PATH_BLOCK_PROCESSED);
+    fn send_rejected_block(&self, payload: &serde_json::Value) {
+        self.send_payload(payload, PATH_BLOCK_REJECTED);
     }
 }
 
-#[derive(Clone)]
-pub struct EventDispatcher {
+/// The registered event observers, and the events each one is subscribed to.
+#[derive(Clone, Default)]
+struct EventObserverSet {
     registered_observers: Vec<EventObserver>,
     contract_events_observers_lookup: HashMap<(QualifiedContractIdentifier, String), HashSet<u16>>,
     assets_observers_lookup: HashMap<AssetIdentifier, HashSet<u16>>,
@@ -406,7 +576,18 @@ pub struct EventDispatcher {
     any_event_observers_lookup: HashSet<u16>,
     miner_observers_lookup: HashSet<u16>,
     mined_microblocks_observers_lookup: HashSet<u16>,
+}
+
+#[derive(Clone)]
+pub struct EventDispatcher {
+    /// Shared by every clone of this dispatcher, so that replacing the observer set takes effect
+    /// in all of the node's threads at once.
+    observers: Arc<RwLock<Arc<EventObserverSet>>>,
     boot_receipts: Arc<Mutex<Option<Vec<StacksTransactionReceipt>>>>,
+    /// Persistent outbound queue shared by all observers, if enabled.
+    event_queue: Option<Arc<Mutex<EventQueueDB>>>,
+    /// The websocket event stream, if enabled.
+    event_stream: Option<EventStream>,
 }
 
 impl MemPoolEventDispatcher for EventDispatcher {
@@ -466,6 +647,8 @@ impl BlockEventDispatcher for EventDispatcher {
         parent_burn_block_timestamp: u64,
         anchored_consumed: &ExecutionCost,
         mblock_confirmed_consumed: &ExecutionCost,
+        withdrawals_queued: &Vec<QueuedWithdrawal>,
+        withdrawals_released: &Vec<QueuedWithdrawal>,
     ) {
         self.process_chain_tip(
             block,
@@ -480,6 +663,8 @@ impl BlockEventDispatcher for EventDispatcher {
             parent_burn_block_timestamp,
             anchored_consumed,
             mblock_confirmed_consumed,
+            withdrawals_queued,
+            withdrawals_released,
         )
     }
 
@@ -500,6 +685,15 @@ impl BlockEventDispatcher for EventDispatcher {
         )
     }
 
+    fn announce_rejected_block(
+        &self,
+        consensus_hash: &ConsensusHash,
+        block_hash: &BlockHeaderHash,
+        reason: &str,
+    ) {
+        self.process_rejected_block(consensus_hash, block_hash, reason)
+    }
+
     fn dispatch_boot_receipts(&mut self, receipts: Vec<StacksTransactionReceipt>) {
         self.process_boot_receipts(receipts)
     }
@@ -508,21 +702,26 @@ impl BlockEventDispatcher for EventDispatcher {
 impl EventDispatcher {
     pub fn new() -> EventDispatcher {
         EventDispatcher {
-            registered_observers: vec![],
-            contract_events_observers_lookup: HashMap::new(),
-            assets_observers_lookup: HashMap::new(),
-            stx_observers_lookup: HashSet::new(),
-            withdrawal_observers_lookup: HashSet::new(),
-            any_event_observers_lookup: HashSet::new(),
-            burn_block_observers_lookup: HashSet::new(),
-            mempool_observers_lookup: HashSet::new(),
-            microblock_observers_lookup: HashSet::new(),
+            observers: Arc::new(RwLock::new(Arc::new(EventObserverSet::default()))),
             boot_receipts: Arc::new(Mutex::new(None)),
-            miner_observers_lookup: HashSet::new(),
-            mined_microblocks_observers_lookup: HashSet::new(),
+            event_queue: None,
+            event_stream: None,
         }
     }
 
+    /// Make a dispatcher which persists each payload to the queue at `queue_path` before
+    /// delivering it, so that observers receive payloads at least once, even if they (or this
+    /// node) go down.
+    pub fn new_with_queue(queue_path: &str) -> EventDispatcher {
+        let queue = EventQueueDB::open(queue_path).expect(&format!(
+            "FATAL: failed to open event observer queue at {}",
+            queue_path
+        ));
+        let mut dispatcher = EventDispatcher::new();
+        dispatcher.event_queue = Some(Arc::new(Mutex::new(queue)));
+        dispatcher
+    }
+
     pub fn process_burn_block(
         &self,
         burn_block: &BurnchainHeaderHash,
@@ -531,14 +730,19 @@ impl EventDispatcher {
         burns: u64,
         recipient_info: Vec<StacksAddress>,
     ) {
+        let observers = self.observers();
         // lazily assemble payload only if we have observers
-        let interested_observers: Vec<_> = self
+        let interested_observers: Vec<_> = observers
             .registered_observers
             .iter()
             .enumerate()
             .filter(|(obs_id, _observer)| {
-                self.burn_block_observers_lookup.contains(&(*obs_id as u16))
-                    || self.any_event_observers_lookup.contains(&(*obs_id as u16))
+                observers
+                    .burn_block_observers_lookup
+                    .contains(&(*obs_id as u16))
+                    || observers
+                        .any_event_observers_lookup
+                        .contains(&(*obs_id as u16))
             })
             .collect();
         if interested_observers.len() < 1 {
@@ -558,108 +762,6 @@ impl EventDispatcher {
         }
     }
 
-    /// Iterates through tx receipts, and then the events corresponding to each receipt to
-    /// generate a dispatch matrix & event vector.
-    ///
-    /// # Returns
-    /// - dispatch_matrix: a vector where each index corresponds to the hashset of event indexes
-    ///     that each respective event observer is subscribed to
-    /// - events: a vector of all events from all the tx receipts
-    fn create_dispatch_matrix_and_event_vector<'a>(
-        &self,
-        receipts: &'a Vec<StacksTransactionReceipt>,
-    ) -> (
-        Vec<HashSet<usize>>,
-        Vec<(bool, Txid, &'a StacksTransactionEvent)>,
-    ) {
-        let mut dispatch_matrix: Vec<HashSet<usize>> = self
-            .registered_observers
-            .iter()
-            .map(|_| HashSet::new())
-            .collect();
-        let mut events: Vec<(bool, Txid, &StacksTransactionEvent)> = vec![];
-        let mut i: usize = 0;
-
-        for receipt in receipts {
-            let tx_hash = receipt.transaction.txid();
-            for event in receipt.events.iter() {
-                match event {
-                    StacksTransactionEvent::SmartContractEvent(event_data) => {
-                        if let Some(observer_indexes) =
-                            self.contract_events_observers_lookup.get(&event_data.key)
-                        {
-                            for o_i in observer_indexes {
-                                dispatch_matrix[*o_i as usize].insert(i);
-                            }
-                            for o_i in &self.withdrawal_observers_lookup {
-                                dispatch_matrix[*o_i as usize].insert(i);
-                            }
-                        }
-                    }
-                    StacksTransactionEvent::STXEvent(STXEventType::STXTransferEvent(_))
-                    | StacksTransactionEvent::STXEvent(STXEventType::STXMintEvent(_))
-                    | StacksTransactionEvent::STXEvent(STXEventType::STXBurnEvent(_))
-                    | StacksTransactionEvent::STXEvent(STXEventType::STXLockEvent(_)) => {
-                        for o_i in &self.stx_observers_lookup {
-                            dispatch_matrix[*o_i as usize].insert(i);
-                        }
-                    }
-                    StacksTransactionEvent::NFTEvent(NFTEventType::NFTTransferEvent(
-                        event_data,
-                    )) => {
-                        self.update_dispatch_matrix_if_observer_subscribed(
-                            &event_data.asset_identifier,
-                            i,
-                            &mut dispatch_matrix,
-                        );
-                    }
-                    StacksTransactionEvent::NFTEvent(NFTEventType::NFTMintEvent(event_data)) => {
-                        self.update_dispatch_matrix_if_observer_subscribed(
-                            &event_data.asset_identifier,
-                            i,
-                            &mut dispatch_matrix,
-                        );
-                    }
-                    StacksTransactionEvent::NFTEvent(NFTEventType::NFTBurnEvent(event_data)) => {
-                        self.update_dispatch_matrix_if_observer_subscribed(
-                            &event_data.asset_identifier,
-                            i,
-                            &mut dispatch_matrix,
-                        );
-                    }
-                    StacksTransactionEvent::FTEvent(FTEventType::FTTransferEvent(event_data)) => {
-                        self.update_dispatch_matrix_if_observer_subscribed(
-                            &event_data.asset_identifier,
-                            i,
-                            &mut dispatch_matrix,
-                        );
-                    }
-                    StacksTransactionEvent::FTEvent(FTEventType::FTMintEvent(event_data)) => {
-                        self.update_dispatch_matrix_if_observer_subscribed(
-                            &event_data.asset_identifier,
-                            i,
-                            &mut dispatch_matrix,
-                        );
-                    }
-                    StacksTransactionEvent::FTEvent(FTEventType::FTBurnEvent(event_data)) => {
-                        self.update_dispatch_matrix_if_observer_subscribed(
-                            &event_data.asset_identifier,
-                            i,
-                            &mut dispatch_matrix,
-                        );
-                    }
-                }
-                events.push((!receipt.post_condition_aborted, tx_hash, event));
-                for o_i in &self.any_event_observers_lookup {
-                    dispatch_matrix[*o_i as usize].insert(i);
-                }
-                i += 1;
-            }
-        }
-
-        (dispatch_matrix, events)
-    }
-
     pub fn process_chain_tip(
         &self,
         block: &StacksBlock,
@@ -674,7 +776,10 @@ impl EventDispatcher {
         parent_burn_block_timestamp: u64,
         anchored_consumed: &ExecutionCost,
         mblock_confirmed_consumed: &ExecutionCost,
+        withdrawals_queued: &Vec<QueuedWithdrawal>,
+        withdrawals_released: &Vec<QueuedWithdrawal>,
     ) {
+        let observers = self.observers();
         let boot_receipts = if metadata.stacks_block_height == 1 {
             let mut boot_receipts_result = self
                 .boot_receipts
@@ -694,30 +799,17 @@ impl EventDispatcher {
             .chain(boot_receipts.iter().cloned())
             .collect();
 
-        let (dispatch_matrix, events) = self.create_dispatch_matrix_and_event_vector(&all_receipts);
-
-        if dispatch_matrix.len() > 0 {
-            let mature_rewards_vec = if let Some(rewards_info) = mature_rewards_info {
-                mature_rewards
-                    .iter()
-                    .map(|reward| {
-                        json!({
-                            "recipient": reward.address.to_string(),
-                            "coinbase_amount": reward.coinbase.to_string(),
-                            "tx_fees_anchored": reward.tx_fees_anchored.to_string(),
-                            "tx_fees_streamed_confirmed": reward.tx_fees_streamed_confirmed.to_string(),
-                            "tx_fees_streamed_produced": reward.tx_fees_streamed_produced.to_string(),
-                            "from_stacks_block_hash": format!("0x{}", rewards_info.from_stacks_block_hash),
-                            "from_index_consensus_hash": format!("0x{}", StacksBlockId::new(&rewards_info.from_block_consensus_hash,
-                                                                                            &rewards_info.from_stacks_block_hash)),
-                        })
-                    })
-                    .collect()
-            } else {
-                vec![]
-            };
+        let (dispatch_matrix, events) =
+            observers.create_dispatch_matrix_and_event_vector(&all_receipts);
+        let event_stream = self.subscribed_event_stream(StreamTopic::NewBlock);
 
-            let mature_rewards = serde_json::Value::Array(mature_rewards_vec);
+        if dispatch_matrix.len() > 0 || event_stream.is_some() {
+            let mature_rewards =
+                EventObserver::make_matured_rewards_payload(mature_rewards, mature_rewards_info);
+            let withdrawals_queued =
+                EventObserver::make_queued_withdrawals_payload(withdrawals_queued);
+            let withdrawals_released =
+                EventObserver::make_queued_withdrawals_payload(withdrawals_released);
 
             for (observer_id, filtered_events_ids) in dispatch_matrix.iter().enumerate() {
                 let filtered_events: Vec<_> = filtered_events_ids
@@ -725,7 +817,7 @@ impl EventDispatcher {
                     .map(|event_id| (*event_id, &events[*event_id]))
                     .collect();
 
-                self.registered_observers[observer_id].send(
+                let payload = EventObserver::make_new_block_payload(
                     filtered_events,
                     block,
                     metadata,
@@ -739,7 +831,31 @@ impl EventDispatcher {
                     parent_burn_block_timestamp,
                     anchored_consumed,
                     mblock_confirmed_consumed,
+                    &withdrawals_queued,
+                    &withdrawals_released,
                 );
+                observers.registered_observers[observer_id].send_new_block(&payload);
+            }
+
+            if let Some(event_stream) = event_stream {
+                let payload = EventObserver::make_new_block_payload(
+                    events.iter().enumerate().collect(),
+                    block,
+                    metadata,
+                    receipts,
+                    parent_index_hash,
+                    &boot_receipts,
+                    &winner_txid,
+                    &mature_rewards,
+                    parent_burn_block_hash,
+                    parent_burn_block_height,
+                    parent_burn_block_timestamp,
+                    anchored_consumed,
+                    mblock_confirmed_consumed,
+                    &withdrawals_queued,
+                    &withdrawals_released,
+                );
+                event_stream.publish(StreamTopic::NewBlock, &payload);
             }
         }
     }
@@ -752,17 +868,23 @@ impl EventDispatcher {
         parent_index_block_hash: StacksBlockId,
         processed_unconfirmed_state: ProcessedUnconfirmedState,
     ) {
+        let observers = self.observers();
         // lazily assemble payload only if we have observers
-        let interested_observers: Vec<_> = self
+        let interested_observers: Vec<_> = observers
             .registered_observers
             .iter()
             .enumerate()
             .filter(|(obs_id, _observer)| {
-                self.microblock_observers_lookup.contains(&(*obs_id as u16))
-                    || self.any_event_observers_lookup.contains(&(*obs_id as u16))
+                observers
+                    .microblock_observers_lookup
+                    .contains(&(*obs_id as u16))
+                    || observers
+                        .any_event_observers_lookup
+                        .contains(&(*obs_id as u16))
             })
             .collect();
-        if interested_observers.len() < 1 {
+        let event_stream = self.subscribed_event_stream(StreamTopic::Microblock);
+        if interested_observers.len() < 1 && event_stream.is_none() {
             return;
         }
         let flattened_receipts = processed_unconfirmed_state
@@ -771,7 +893,7 @@ impl EventDispatcher {
             .flat_map(|(_, _, r)| r.clone())
             .collect();
         let (dispatch_matrix, events) =
-            self.create_dispatch_matrix_and_event_vector(&flattened_receipts);
+            observers.create_dispatch_matrix_and_event_vector(&flattened_receipts);
 
         // Serialize receipts
         let mut tx_index;
@@ -793,7 +915,7 @@ impl EventDispatcher {
                 .map(|event_id| (*event_id, &events[*event_id]))
                 .collect();
 
-            observer.send_new_microblocks(
+            let payload = EventObserver::make_new_microblocks_payload(
                 parent_index_block_hash,
                 filtered_events,
                 &serialized_txs,
@@ -801,21 +923,40 @@ impl EventDispatcher {
                 processed_unconfirmed_state.burn_block_height,
                 processed_unconfirmed_state.burn_block_timestamp,
             );
+            observer.send_new_microblocks(&payload);
+        }
+
+        if let Some(event_stream) = event_stream {
+            let payload = EventObserver::make_new_microblocks_payload(
+                parent_index_block_hash,
+                events.iter().enumerate().collect(),
+                &serialized_txs,
+                processed_unconfirmed_state.burn_block_hash,
+                processed_unconfirmed_state.burn_block_height,
+                processed_unconfirmed_state.burn_block_timestamp,
+            );
+            event_stream.publish(StreamTopic::Microblock, &payload);
         }
     }
 
     pub fn process_new_mempool_txs(&self, txs: Vec<StacksTransaction>) {
+        let observers = self.observers();
         // lazily assemble payload only if we have observers
-        let interested_observers: Vec<_> = self
+        let interested_observers: Vec<_> = observers
             .registered_observers
             .iter()
             .enumerate()
             .filter(|(obs_id, _observer)| {
-                self.mempool_observers_lookup.contains(&(*obs_id as u16))
-                    || self.any_event_observers_lookup.contains(&(*obs_id as u16))
+                observers
+                    .mempool_observers_lookup
+                    .contains(&(*obs_id as u16))
+                    || observers
+                        .any_event_observers_lookup
+                        .contains(&(*obs_id as u16))
             })
             .collect();
-        if interested_observers.len() < 1 {
+        let event_stream = self.subscribed_event_stream(StreamTopic::MempoolTxReceived);
+        if interested_observers.len() < 1 && event_stream.is_none() {
             return;
         }
 
@@ -824,6 +965,9 @@ impl EventDispatcher {
         for (_, observer) in interested_observers.iter() {
             observer.send_new_mempool_txs(&payload);
         }
+        if let Some(event_stream) = event_stream {
+            event_stream.publish(StreamTopic::MempoolTxReceived, &payload);
+        }
     }
 
     pub fn process_mined_block_event(
@@ -835,16 +979,30 @@ impl EventDispatcher {
         confirmed_microblock_cost: &ExecutionCost,
         tx_events: Vec<TransactionEvent>,
     ) {
-        let interested_observers: Vec<_> = self
+        let observers = self.observers();
+        let interested_observers: Vec<_> = observers
             .registered_observers
             .iter()
             .enumerate()
-            .filter(|(obs_id, _observer)| self.miner_observers_lookup.contains(&(*obs_id as u16)))
+            .filter(|(obs_id, _observer)| {
+                observers.miner_observers_lookup.contains(&(*obs_id as u16))
+            })
             .collect();
         if interested_observers.len() < 1 {
             return;
         }
 
+        let txs: HashMap<_, _> = block.txs.iter().map(|tx| (tx.txid(), tx)).collect();
+        let mut top_contracts = ContractCost::aggregate(tx_events.iter().filter_map(|tx_event| {
+            match tx_event {
+                TransactionEvent::Success(success) => txs
+                    .get(&success.txid)
+                    .map(|tx| (*tx, &success.execution_cost)),
+                TransactionEvent::ProcessingError(_) | TransactionEvent::Skipped(_) => None,
+            }
+        }));
+        top_contracts.truncate(MINED_BLOCK_TOP_CONTRACTS);
+
         let payload = serde_json::to_value(MinedBlockEvent {
             target_burn_height,
             block_hash: block.block_hash().to_string(),
@@ -853,6 +1011,7 @@ impl EventDispatcher {
             anchored_cost: consumed.clone(),
             confirmed_microblocks_cost: confirmed_microblock_cost.clone(),
             tx_events,
+            top_contracts,
         })
         .unwrap();
 
@@ -868,12 +1027,14 @@ impl EventDispatcher {
         anchor_block_consensus_hash: ConsensusHash,
         anchor_block: BlockHeaderHash,
     ) {
-        let interested_observers: Vec<_> = self
+        let observers = self.observers();
+        let interested_observers: Vec<_> = observers
             .registered_observers
             .iter()
             .enumerate()
             .filter(|(obs_id, _observer)| {
-                self.mined_microblocks_observers_lookup
+                observers
+                    .mined_microblocks_observers_lookup
                     .contains(&(*obs_id as u16))
             })
             .collect();
@@ -895,18 +1056,49 @@ impl EventDispatcher {
         }
     }
 
+    /// Tell every observer that a block was found to be invalid, and why.  Like new blocks,
+    /// rejected blocks are sent to all observers.
+    pub fn process_rejected_block(
+        &self,
+        consensus_hash: &ConsensusHash,
+        block_hash: &BlockHeaderHash,
+        reason: &str,
+    ) {
+        let observers = self.observers();
+        if observers.registered_observers.is_empty() {
+            return;
+        }
+
+        let payload = json!({
+            "block_hash": format!("0x{}", block_hash),
+            "consensus_hash": format!("0x{}", consensus_hash),
+            "index_block_hash": format!("0x{}", StacksBlockId::new(consensus_hash, block_hash)),
+            "reason": reason,
+        });
+
+        for observer in observers.registered_observers.iter() {
+            observer.send_rejected_block(&payload);
+        }
+    }
+
     pub fn process_dropped_mempool_txs(&self, txs: Vec<Txid>, reason: MemPoolDropReason) {
+        let observers = self.observers();
         // lazily assemble payload only if we have observers
-        let interested_observers: Vec<_> = self
+        let interested_observers: Vec<_> = observers
             .registered_observers
             .iter()
             .enumerate()
             .filter(|(obs_id, _observer)| {
-                self.mempool_observers_lookup.contains(&(*obs_id as u16))
-                    || self.any_event_observers_lookup.contains(&(*obs_id as u16))
+                observers
+                    .mempool_observers_lookup
+                    .contains(&(*obs_id as u16))
+                    || observers
+                        .any_event_observers_lookup
+                        .contains(&(*obs_id as u16))
             })
             .collect();
-        if interested_observers.len() < 1 {
+        let event_stream = self.subscribed_event_stream(StreamTopic::MempoolTxsDropped);
+        if interested_observers.len() < 1 && event_stream.is_none() {
             return;
         }
 
@@ -923,10 +1115,15 @@ impl EventDispatcher {
         for (_, observer) in interested_observers.iter() {
             observer.send_dropped_mempool_txs(&payload);
         }
+        if let Some(event_stream) = event_stream {
+            event_stream.publish(StreamTopic::MempoolTxsDropped, &payload);
+        }
     }
 
     pub fn process_new_attachments(&self, attachments: &Vec<(AttachmentInstance, Attachment)>) {
-        let interested_observers: Vec<_> = self.registered_observers.iter().enumerate().collect();
+        let observers = self.observers();
+        let interested_observers: Vec<_> =
+            observers.registered_observers.iter().enumerate().collect();
         if interested_observers.len() < 1 {
             return;
         }
@@ -946,6 +1143,254 @@ impl EventDispatcher {
         self.boot_receipts = Arc::new(Mutex::new(Some(receipts)));
     }
 
+    pub fn register_observer(&mut self, conf: &EventObserverConfig) {
+        let mut observers = (*self.observers()).clone();
+        observers.register_observer(conf, self.event_queue.clone());
+        *self
+            .observers
+            .write()
+            .expect("FATAL: event observer set lock is poisoned") = Arc::new(observers);
+    }
+
+    /// Replace the registered observers with those in `confs`.  Payloads already being
+    /// delivered go to the old observer set; everything after goes to the new one.
+    pub fn reload_observers(&self, confs: &[EventObserverConfig]) {
+        let mut observers = EventObserverSet::default();
+        for conf in confs.iter() {
+            observers.register_observer(conf, self.event_queue.clone());
+        }
+        *self
+            .observers
+            .write()
+            .expect("FATAL: event observer set lock is poisoned") = Arc::new(observers);
+        info!("Reloaded event observers"; "count" => confs.len());
+    }
+
+    /// Stream this dispatcher's new blocks, microblocks and mempool events to `event_stream`'s
+    /// websocket clients, in addition to its observers.
+    pub fn set_event_stream(&mut self, event_stream: EventStream) {
+        self.event_stream = Some(event_stream);
+    }
+
+    pub fn get_event_stream(&self) -> Option<EventStream> {
+        self.event_stream.clone()
+    }
+
+    /// The event stream, if any of its clients are subscribed to `topic`
+    fn subscribed_event_stream(&self, topic: StreamTopic) -> Option<&EventStream> {
+        self.event_stream
+            .as_ref()
+            .filter(|event_stream| event_stream.has_subscribers(topic))
+    }
+
+    /// Get the current observer set
+    fn observers(&self) -> Arc<EventObserverSet> {
+        self.observers
+            .read()
+            .expect("FATAL: event observer set lock is poisoned")
+            .clone()
+    }
+}
+
+/// Makes the payloads of event replays, for observers backfilling from `/v2/events/replay`,
+/// just as the event dispatcher makes them for an observer subscribed with the same keys
+pub struct EventReplayer;
+
+impl EventReplayer {
+    fn parse_keys(keys: &[String]) -> Result<Vec<EventKeyType>, String> {
+        keys.iter()
+            .map(|key| {
+                EventKeyType::from_string(key).ok_or_else(|| format!("unknown event key `{}`", key))
+            })
+            .collect()
+    }
+}
+
+impl EventReplayFormatter for EventReplayer {
+    fn check_keys(&self, keys: &[String]) -> Result<(), String> {
+        EventReplayer::parse_keys(keys).map(|_| ())
+    }
+
+    fn make_payloads(
+        &self,
+        events: &[ReplayedEvent],
+        keys: &[String],
+    ) -> Result<Vec<EventReplayPayload>, String> {
+        // a set of just the one observer the payloads are for
+        let mut observers = EventObserverSet::default();
+        observers.subscribe(0, &EventReplayer::parse_keys(keys)?);
+        observers.registered_observers.push(EventObserver {
+            endpoint: String::new(),
+            queue: None,
+        });
+        let wants_burn_blocks = observers.burn_block_observers_lookup.contains(&0)
+            || observers.any_event_observers_lookup.contains(&0);
+
+        let mut payloads = vec![];
+        for event in events.iter() {
+            match event {
+                ReplayedEvent::BurnBlock(burn_block) => {
+                    if !wants_burn_blocks {
+                        continue;
+                    }
+                    payloads.push(EventReplayPayload {
+                        path: PATH_BURN_BLOCK_SUBMIT.to_string(),
+                        payload: EventObserver::make_new_burn_block_payload(
+                            &burn_block.burn_header_hash,
+                            burn_block.block_height,
+                            vec![],
+                            burn_block.burns,
+                            vec![],
+                        ),
+                    });
+                }
+                ReplayedEvent::Block(block) => {
+                    let reexecuted = &block.reexecuted;
+                    let (dispatch_matrix, events) =
+                        observers.create_dispatch_matrix_and_event_vector(&reexecuted.tx_receipts);
+                    let mut event_ids: Vec<_> = dispatch_matrix[0].iter().cloned().collect();
+                    event_ids.sort();
+                    let filtered_events = event_ids
+                        .into_iter()
+                        .map(|event_id| (event_id, &events[event_id]))
+                        .collect();
+                    payloads.push(EventReplayPayload {
+                        path: PATH_BLOCK_PROCESSED.to_string(),
+                        payload: EventObserver::make_new_block_payload(
+                            filtered_events,
+                            &block.block,
+                            &block.header,
+                            &reexecuted.tx_receipts,
+                            &block.parent_block_id,
+                            &vec![],
+                            &block.winner_txid,
+                            &EventObserver::make_matured_rewards_payload(
+                                &reexecuted.matured_rewards,
+                                reexecuted.matured_rewards_info.as_ref(),
+                            ),
+                            block.parent_burn_block_hash,
+                            block.parent_burn_block_height,
+                            block.parent_burn_block_timestamp,
+                            &reexecuted.anchored_block_cost,
+                            &reexecuted.parent_microblocks_cost,
+                            &EventObserver::make_queued_withdrawals_payload(
+                                &reexecuted.withdrawals_queued,
+                            ),
+                            &EventObserver::make_queued_withdrawals_payload(
+                                &reexecuted.withdrawals_released,
+                            ),
+                        ),
+                    });
+                }
+            }
+        }
+        Ok(payloads)
+    }
+}
+
+impl EventObserverSet {
+    /// Iterates through tx receipts, and then the events corresponding to each receipt to
+    /// generate a dispatch matrix & event vector.
+    ///
+    /// # Returns
+    /// - dispatch_matrix: a vector where each index corresponds to the hashset of event indexes
+    ///     that each respective event observer is subscribed to
+    /// - events: a vector of all events from all the tx receipts
+    fn create_dispatch_matrix_and_event_vector<'a>(
+        &self,
+        receipts: &'a Vec<StacksTransactionReceipt>,
+    ) -> (
+        Vec<HashSet<usize>>,
+        Vec<(bool, Txid, &'a StacksTransactionEvent)>,
+    ) {
+        let mut dispatch_matrix: Vec<HashSet<usize>> = self
+            .registered_observers
+            .iter()
+            .map(|_| HashSet::new())
+            .collect();
+        let mut events: Vec<(bool, Txid, &StacksTransactionEvent)> = vec![];
+        let mut i: usize = 0;
+
+        for receipt in receipts {
+            let tx_hash = receipt.transaction.txid();
+            for event in receipt.events.iter() {
+                match event {
+                    StacksTransactionEvent::SmartContractEvent(event_data) => {
+                        if let Some(observer_indexes) =
+                            self.contract_events_observers_lookup.get(&event_data.key)
+                        {
+                            for o_i in observer_indexes {
+                                dispatch_matrix[*o_i as usize].insert(i);
+                            }
+                            for o_i in &self.withdrawal_observers_lookup {
+                                dispatch_matrix[*o_i as usize].insert(i);
+                            }
+                        }
+                    }
+                    StacksTransactionEvent::STXEvent(STXEventType::STXTransferEvent(_))
+                    | StacksTransactionEvent::STXEvent(STXEventType::STXMintEvent(_))
+                    | StacksTransactionEvent::STXEvent(STXEventType::STXBurnEvent(_))
+                    | StacksTransactionEvent::STXEvent(STXEventType::STXLockEvent(_)) => {
+                        for o_i in &self.stx_observers_lookup {
+                            dispatch_matrix[*o_i as usize].insert(i);
+                        }
+                    }
+                    StacksTransactionEvent::NFTEvent(NFTEventType::NFTTransferEvent(
+                        event_data,
+                    )) => {
+                        self.update_dispatch_matrix_if_observer_subscribed(
+                            &event_data.asset_identifier,
+                            i,
+                            &mut dispatch_matrix,
+                        );
+                    }
+                    StacksTransactionEvent::NFTEvent(NFTEventType::NFTMintEvent(event_data)) => {
+                        self.update_dispatch_matrix_if_observer_subscribed(
+                            &event_data.asset_identifier,
+                            i,
+                            &mut dispatch_matrix,
+                        );
+                    }
+                    StacksTransactionEvent::NFTEvent(NFTEventType::NFTBurnEvent(event_data)) => {
+                        self.update_dispatch_matrix_if_observer_subscribed(
+                            &event_data.asset_identifier,
+                            i,
+                            &mut dispatch_matrix,
+                        );
+                    }
+                    StacksTransactionEvent::FTEvent(FTEventType::FTTransferEvent(event_data)) => {
+                        self.update_dispatch_matrix_if_observer_subscribed(
+                            &event_data.asset_identifier,
+                            i,
+                            &mut dispatch_matrix,
+                        );
+                    }
+                    StacksTransactionEvent::FTEvent(FTEventType::FTMintEvent(event_data)) => {
+                        self.update_dispatch_matrix_if_observer_subscribed(
+                            &event_data.asset_identifier,
+                            i,
+                            &mut dispatch_matrix,
+                        );
+                    }
+                    StacksTransactionEvent::FTEvent(FTEventType::FTBurnEvent(event_data)) => {
+                        self.update_dispatch_matrix_if_observer_subscribed(
+                            &event_data.asset_identifier,
+                            i,
+                            &mut dispatch_matrix,
+                        );
+                    }
+                }
+                events.push((!receipt.post_condition_aborted, tx_hash, event));
+                for o_i in &self.any_event_observers_lookup {
+                    dispatch_matrix[*o_i as usize].insert(i);
+                }
+                i += 1;
+            }
+        }
+
+        (dispatch_matrix, events)
+    }
+
     fn update_dispatch_matrix_if_observer_subscribed(
         &self,
         asset_identifier: &AssetIdentifier,
@@ -959,15 +1404,9 @@ impl EventDispatcher {
         }
     }
 
-    pub fn register_observer(&mut self, conf: &EventObserverConfig) {
-        info!("Registering event observer at: {}", conf.endpoint);
-        let event_observer = EventObserver {
-            endpoint: conf.endpoint.clone(),
-        };
-
-        let observer_index = self.registered_observers.len() as u16;
-
-        for event_key_type in conf.events_keys.iter() {
+    /// Subscribe the observer at `observer_index` to the events `events_keys` name
+    fn subscribe(&mut self, observer_index: u16, events_keys: &[EventKeyType]) {
+        for event_key_type in events_keys.iter() {
             match event_key_type {
                 EventKeyType::SmartContractEvent(event_key) => {
                     match self
@@ -1023,7 +1462,148 @@ impl EventDispatcher {
                 }
             }
         }
+    }
+
+    fn register_observer(
+        &mut self,
+        conf: &EventObserverConfig,
+        queue: Option<Arc<Mutex<EventQueueDB>>>,
+    ) {
+        info!("Registering event observer at: {}", conf.endpoint);
+        let event_observer = EventObserver {
+            endpoint: conf.endpoint.clone(),
+            queue: queue.clone(),
+        };
+
+        let observer_index = self.registered_observers.len() as u16;
+
+        self.subscribe(observer_index, &conf.events_keys);
+
+        if let Some(queue) = queue.as_ref() {
+            let mut queue = queue.lock().expect("FATAL: event queue lock is poisoned");
+            match queue.count_pending(&event_observer.endpoint) {
+                Ok(0) => {}
+                Ok(pending) => {
+                    info!("Delivering queued payloads to event observer";
+                          "endpoint" => &event_observer.endpoint,
+                          "pending" => pending,
+                          "high_water_mark" => ?queue.get_high_water_mark(&event_observer.endpoint).ok().flatten());
+                    event_observer.flush_queue(&mut queue);
+                }
+                Err(e) => {
+                    error!("Failed to query event observer queue - {:?}", &e);
+                }
+            }
+        }
 
         self.registered_observers.push(event_observer);
     }
 }
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+
+    use super::*;
+    use crate::ephemeral_dir::EphemeralWorkingDir;
+    use crate::Config;
+
+    fn observer_endpoints(dispatcher: &EventDispatcher) -> Vec<String> {
+        dispatcher
+            .observers()
+            .registered_observers
+            .iter()
+            .map(|observer| observer.endpoint.clone())
+            .collect()
+    }
+
+    #[test]
+    fn test_reload_observers_from_path() {
+        let dir = EphemeralWorkingDir::create().unwrap();
+        let config_path = dir.path().join("config.toml");
+        let config_path = config_path.to_str().unwrap();
+
+        let mut dispatcher = EventDispatcher::new();
+        dispatcher.register_observer(&EventObserverConfig {
+            endpoint: "localhost:3700".into(),
+            events_keys: vec![EventKeyType::AnyEvent],
+        });
+        assert_eq!(observer_endpoints(&dispatcher), vec!["localhost:3700"]);
+
+        // add an observer, and change the events the existing one is subscribed to
+        fs::write(
+            config_path,
+            r#"
+[[events_observer]]
+endpoint = "localhost:3700"
+events_keys = ["burn_blocks"]
+
+[[events_observer]]
+endpoint = "localhost:3701"
+events_keys = ["memtx"]
+"#,
+        )
+        .unwrap();
+        let confs = Config::events_observers_from_path(config_path).unwrap();
+        dispatcher.reload_observers(&confs);
+
+        assert_eq!(
+            observer_endpoints(&dispatcher),
+            vec!["localhost:3700", "localhost:3701"]
+        );
+        let observers = dispatcher.observers();
+        assert!(observers.any_event_observers_lookup.is_empty());
+        assert_eq!(observers.burn_block_observers_lookup, HashSet::from([0]));
+        assert_eq!(observers.mempool_observers_lookup, HashSet::from([1]));
+
+        // remove an observer
+        fs::write(
+            config_path,
+            r#"
+[[events_observer]]
+endpoint = "localhost:3701"
+events_keys = ["memtx"]
+"#,
+        )
+        .unwrap();
+        let confs = Config::events_observers_from_path(config_path).unwrap();
+        dispatcher.reload_observers(&confs);
+
+        assert_eq!(observer_endpoints(&dispatcher), vec!["localhost:3701"]);
+        let observers = dispatcher.observers();
+        assert!(observers.burn_block_observers_lookup.is_empty());
+        assert_eq!(observers.mempool_observers_lookup, HashSet::from([0]));
+
+        // remove them all
+        fs::write(config_path, "").unwrap();
+        let confs = Config::events_observers_from_path(config_path).unwrap();
+        dispatcher.reload_observers(&confs);
+        assert!(observer_endpoints(&dispatcher).is_empty());
+    }
+
+    #[test]
+    fn test_events_observers_from_path_errors() {
+        let dir = EphemeralWorkingDir::create().unwrap();
+        let config_path = dir.path().join("config.toml");
+        let config_path = config_path.to_str().unwrap();
+
+        let err = Config::events_observers_from_path(config_path).unwrap_err();
+        assert!(err.starts_with("failed to read config file"), "{}", err);
+
+        fs::write(
+            config_path,
+            r#"
+[[events_observer]]
+endpoint = "localhost:3700"
+events_keys = ["not-a-key"]
+"#,
+        )
+        .unwrap();
+        let err = Config::events_observers_from_path(config_path).unwrap_err();
+        assert!(err.contains("invalid events_key 'not-a-key'"), "{}", err);
+
+        fs::write(config_path, "[[events_observer]]\nendpoint = 3700\n").unwrap();
+        let err = Config::events_observers_from_path(config_path).unwrap_err();
+        assert!(err.starts_with("failed to parse config file"), "{}", err);
+    }
+}
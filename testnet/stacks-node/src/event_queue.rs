@@ -0,0 +1,236 @@
+use std::{fs, io};
+
+use rusqlite::{OptionalExtension, Row, ToSql};
+
+use stacks::util_lib::db::{ensure_base_directory_exists, sqlite_open, Error as db_error};
+use stacks::util_lib::db::{query_row, query_rows, tx_begin_immediate, u64_to_sql};
+use stacks::util_lib::db::{DBConn, FromColumn, FromRow};
+
+/// Schema for the outbound event observer queue.
+const EVENT_QUEUE_SCHEMA: &'static [&'static str] = &[
+    r#"
+    -- Payloads which have been generated for an observer, but not yet acknowledged by it.
+    -- `seq` is monotonically increasing, so payloads are delivered in the order they were
+    -- enqueued.
+    CREATE TABLE pending_events(
+        seq INTEGER PRIMARY KEY AUTOINCREMENT,
+        endpoint TEXT NOT NULL,
+        path TEXT NOT NULL,
+        payload TEXT NOT NULL,
+        attempts INTEGER NOT NULL DEFAULT 0
+    );
+    "#,
+    r#"
+    -- The sequence number of the last payload each observer acknowledged.
+    CREATE TABLE high_water_marks(
+        endpoint TEXT PRIMARY KEY NOT NULL,
+        seq INTEGER NOT NULL
+    );
+    "#,
+    "CREATE INDEX IF NOT EXISTS pending_by_endpoint ON pending_events(endpoint, seq);",
+];
+
+/// A payload waiting to be delivered to an event observer.
+#[derive(Debug, Clone, PartialEq)]
+pub struct QueuedEvent {
+    pub seq: u64,
+    pub endpoint: String,
+    pub path: String,
+    pub payload: serde_json::Value,
+    pub attempts: u64,
+}
+
+impl FromRow<QueuedEvent> for QueuedEvent {
+    fn from_row<'a>(row: &'a Row) -> Result<QueuedEvent, db_error> {
+        let seq = u64::from_column(row, "seq")?;
+        let endpoint: String = row.get_unwrap("endpoint");
+        let path: String = row.get_unwrap("path");
+        let payload_str: String = row.get_unwrap("payload");
+        let payload = serde_json::from_str(&payload_str).map_err(|_| db_error::ParseError)?;
+        let attempts = u64::from_column(row, "attempts")?;
+
+        Ok(QueuedEvent {
+            seq,
+            endpoint,
+            path,
+            payload,
+            attempts,
+        })
+    }
+}
+
+/// Sqlite-backed outbound queue for event observer payloads. Payloads are written here before
+/// they are POSTed, and removed only once the observer acknowledges them, so an observer that
+/// is down (or a node that restarts) does not lose events.
+pub struct EventQueueDB {
+    conn: DBConn,
+}
+
+impl EventQueueDB {
+    /// Open the queue at `path`, instantiating it if it does not exist.
+    pub fn open(path: &str) -> Result<EventQueueDB, db_error> {
+        ensure_base_directory_exists(path)?;
+
+        let create_flag = match fs::metadata(path) {
+            Ok(_) => false,
+            Err(e) => {
+                if e.kind() == io::ErrorKind::NotFound {
+                    true
+                } else {
+                    return Err(db_error::IOError(e));
+                }
+            }
+        };
+
+        let open_flags = if create_flag {
+            rusqlite::OpenFlags::SQLITE_OPEN_READ_WRITE | rusqlite::OpenFlags::SQLITE_OPEN_CREATE
+        } else {
+            rusqlite::OpenFlags::SQLITE_OPEN_READ_WRITE
+        };
+
+        let mut conn = sqlite_open(path, open_flags, false)?;
+        if create_flag {
+            let tx = tx_begin_immediate(&mut conn)?;
+            for cmd in EVENT_QUEUE_SCHEMA {
+                tx.execute_batch(cmd)?;
+            }
+            tx.commit()?;
+        }
+
+        Ok(EventQueueDB { conn })
+    }
+
+    /// Store a payload for `endpoint`. Returns its sequence number.
+    pub fn enqueue(
+        &mut self,
+        endpoint: &str,
+        path: &str,
+        payload: &serde_json::Value,
+    ) -> Result<u64, db_error> {
+        let payload_str = serde_json::to_string(payload).map_err(|_| db_error::ParseError)?;
+        let args: &[&dyn ToSql] = &[&endpoint, &path, &payload_str];
+        self.conn.execute(
+            "INSERT INTO pending_events (endpoint, path, payload) VALUES (?1, ?2, ?3)",
+            args,
+        )?;
+        Ok(self.conn.last_insert_rowid() as u64)
+    }
+
+    /// Get up to `limit` undelivered payloads for `endpoint`, oldest first.
+    pub fn get_pending(&self, endpoint: &str, limit: u64) -> Result<Vec<QueuedEvent>, db_error> {
+        let args: &[&dyn ToSql] = &[&endpoint, &u64_to_sql(limit)?];
+        query_rows(
+            &self.conn,
+            "SELECT * FROM pending_events WHERE endpoint = ?1 ORDER BY seq ASC LIMIT ?2",
+            args,
+        )
+    }
+
+    /// How many payloads are waiting to be delivered to `endpoint`?
+    pub fn count_pending(&self, endpoint: &str) -> Result<u64, db_error> {
+        let count: Option<u64> = query_row(
+            &self.conn,
+            "SELECT COUNT(*) FROM pending_events WHERE endpoint = ?1",
+            &[&endpoint],
+        )?;
+        Ok(count.unwrap_or(0))
+    }
+
+    /// Record a failed delivery attempt for the payload `seq`.
+    pub fn record_attempt(&mut self, seq: u64) -> Result<(), db_error> {
+        self.conn.execute(
+            "UPDATE pending_events SET attempts = attempts + 1 WHERE seq = ?1",
+            &[&u64_to_sql(seq)?],
+        )?;
+        Ok(())
+    }
+
+    /// Record that `endpoint` acknowledged the payload `seq`. The payload is dropped from the
+    /// queue, and the endpoint's high-water mark advances to `seq`.
+    pub fn mark_delivered(&mut self, endpoint: &str, seq: u64) -> Result<(), db_error> {
+        let tx = tx_begin_immediate(&mut self.conn)?;
+        let seq = u64_to_sql(seq)?;
+        tx.execute(
+            "DELETE FROM pending_events WHERE endpoint = ?1 AND seq = ?2",
+            &[&endpoint as &dyn ToSql, &seq],
+        )?;
+        // payloads are delivered in order, so `seq` is always the highest acknowledged payload
+        tx.execute(
+            "INSERT OR REPLACE INTO high_water_marks (endpoint, seq) VALUES (?1, ?2)",
+            &[&endpoint as &dyn ToSql, &seq],
+        )?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Get the sequence number of the last payload `endpoint` acknowledged, if any.
+    pub fn get_high_water_mark(&self, endpoint: &str) -> Result<Option<u64>, db_error> {
+        let hwm: Option<i64> = self
+            .conn
+            .query_row(
+                "SELECT seq FROM high_water_marks WHERE endpoint = ?1",
+                &[&endpoint],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(hwm.map(|seq| seq as u64))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde_json::json;
+
+    fn make_queue(name: &str) -> EventQueueDB {
+        let path = format!("/tmp/subnet-node-tests/event_queue/{}.sqlite", name);
+        if fs::metadata(&path).is_ok() {
+            fs::remove_file(&path).unwrap();
+        }
+        EventQueueDB::open(&path).unwrap()
+    }
+
+    #[test]
+    fn test_enqueue_and_deliver_in_order() {
+        let mut queue = make_queue("test_enqueue_and_deliver_in_order");
+        let a = queue
+            .enqueue("localhost:3700", "new_block", &json!({"n": 1}))
+            .unwrap();
+        let b = queue
+            .enqueue("localhost:3700", "new_burn_block", &json!({"n": 2}))
+            .unwrap();
+        queue
+            .enqueue("localhost:3701", "new_block", &json!({"n": 3}))
+            .unwrap();
+
+        assert_eq!(queue.count_pending("localhost:3700").unwrap(), 2);
+        assert_eq!(queue.count_pending("localhost:3701").unwrap(), 1);
+        assert_eq!(queue.get_high_water_mark("localhost:3700").unwrap(), None);
+
+        let pending = queue.get_pending("localhost:3700", 10).unwrap();
+        assert_eq!(pending.len(), 2);
+        assert_eq!(pending[0].seq, a);
+        assert_eq!(pending[0].path, "new_block");
+        assert_eq!(pending[0].payload, json!({"n": 1}));
+        assert_eq!(pending[1].seq, b);
+
+        queue.record_attempt(a).unwrap();
+        assert_eq!(
+            queue.get_pending("localhost:3700", 1).unwrap()[0].attempts,
+            1
+        );
+
+        queue.mark_delivered("localhost:3700", a).unwrap();
+        assert_eq!(
+            queue.get_high_water_mark("localhost:3700").unwrap(),
+            Some(a)
+        );
+        let pending = queue.get_pending("localhost:3700", 10).unwrap();
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].seq, b);
+
+        // other observers are unaffected
+        assert_eq!(queue.count_pending("localhost:3701").unwrap(), 1);
+        assert_eq!(queue.get_high_water_mark("localhost:3701").unwrap(), None);
+    }
+}
@@ -0,0 +1,353 @@
+use std::collections::HashSet;
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+
+use futures_util::{SinkExt, StreamExt};
+use serde_json::json;
+use tokio::sync::broadcast;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::oneshot;
+use tokio::sync::oneshot::Receiver;
+use tokio::sync::oneshot::Sender;
+use tokio::task::JoinError;
+use warp;
+use warp::ws::{Message, WebSocket, Ws};
+use warp::Filter;
+
+/// How many messages a websocket client may fall behind the stream before it misses some
+const STREAM_CAPACITY: usize = 1024;
+
+/// The kinds of message a websocket client can subscribe to.  Each message's payload is the
+/// same JSON that the event dispatcher POSTs to its observers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum StreamTopic {
+    NewBlock = 0,
+    Microblock = 1,
+    MempoolTxReceived = 2,
+    MempoolTxsDropped = 3,
+}
+
+impl StreamTopic {
+    pub const ALL: [StreamTopic; 4] = [
+        StreamTopic::NewBlock,
+        StreamTopic::Microblock,
+        StreamTopic::MempoolTxReceived,
+        StreamTopic::MempoolTxsDropped,
+    ];
+
+    pub fn from_name(name: &str) -> Option<StreamTopic> {
+        StreamTopic::ALL
+            .iter()
+            .find(|topic| topic.as_str() == name)
+            .copied()
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            StreamTopic::NewBlock => "new_block",
+            StreamTopic::Microblock => "microblock",
+            StreamTopic::MempoolTxReceived => "mempool_tx_received",
+            StreamTopic::MempoolTxsDropped => "mempool_txs_dropped",
+        }
+    }
+}
+
+struct StreamMessage {
+    topic: StreamTopic,
+    text: String,
+}
+
+/// The events which the websocket server streams to its clients.  Clones share the same stream.
+#[derive(Clone)]
+pub struct EventStream {
+    sender: broadcast::Sender<Arc<StreamMessage>>,
+    /// How many clients are subscribed to each topic, so that payloads nobody wants are never
+    /// assembled.
+    subscribers: Arc<[AtomicUsize; 4]>,
+}
+
+impl EventStream {
+    pub fn new() -> EventStream {
+        let (sender, _) = broadcast::channel(STREAM_CAPACITY);
+        EventStream {
+            sender,
+            subscribers: Arc::new(Default::default()),
+        }
+    }
+
+    /// Is any client subscribed to `topic`?
+    pub fn has_subscribers(&self, topic: StreamTopic) -> bool {
+        self.subscribers[topic as usize].load(Ordering::SeqCst) > 0
+    }
+
+    /// Send `payload` to the clients subscribed to `topic`
+    pub fn publish(&self, topic: StreamTopic, payload: &serde_json::Value) {
+        if !self.has_subscribers(topic) {
+            return;
+        }
+        let text = json!({
+            "topic": topic.as_str(),
+            "payload": payload,
+        })
+        .to_string();
+        // fails only if no client is connected
+        let _ = self.sender.send(Arc::new(StreamMessage { topic, text }));
+    }
+}
+
+/// A message from a client changing its subscriptions, e.g.
+/// `{"subscribe": ["new_block"], "unsubscribe": ["mempool_tx_received"]}`
+#[derive(Debug, Deserialize)]
+struct SubscriptionRequest {
+    #[serde(default)]
+    subscribe: Vec<String>,
+    #[serde(default)]
+    unsubscribe: Vec<String>,
+}
+
+/// The topics one client is subscribed to.  These are counted in the stream's subscriber
+/// counts until the client disconnects.
+struct Subscriptions {
+    stream: EventStream,
+    topics: HashSet<StreamTopic>,
+}
+
+impl Subscriptions {
+    fn new(stream: EventStream) -> Subscriptions {
+        Subscriptions {
+            stream,
+            topics: HashSet::new(),
+        }
+    }
+
+    fn contains(&self, topic: StreamTopic) -> bool {
+        self.topics.contains(&topic)
+    }
+
+    fn subscribe(&mut self, topic: StreamTopic) {
+        if self.topics.insert(topic) {
+            self.stream.subscribers[topic as usize].fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    fn unsubscribe(&mut self, topic: StreamTopic) {
+        if self.topics.remove(&topic) {
+            self.stream.subscribers[topic as usize].fetch_sub(1, Ordering::SeqCst);
+        }
+    }
+
+    fn parse_topics(names: &[String]) -> Result<Vec<StreamTopic>, String> {
+        names
+            .iter()
+            .map(|name| {
+                StreamTopic::from_name(name).ok_or_else(|| {
+                    let topic_names: Vec<_> = StreamTopic::ALL
+                        .iter()
+                        .map(|topic| topic.as_str())
+                        .collect();
+                    format!(
+                        "unknown topic `{}`; expected one of {}",
+                        name,
+                        topic_names.join(", ")
+                    )
+                })
+            })
+            .collect()
+    }
+
+    /// Apply a client's subscription request, and make the reply: the topics the client is
+    /// now subscribed to, or why the request was rejected.  A rejected request changes nothing.
+    fn handle_request(&mut self, request: &str) -> serde_json::Value {
+        let result = serde_json::from_str::<SubscriptionRequest>(request)
+            .map_err(|e| format!("malformed subscription request: {}", e))
+            .and_then(|request| {
+                Ok((
+                    Subscriptions::parse_topics(&request.subscribe)?,
+                    Subscriptions::parse_topics(&request.unsubscribe)?,
+                ))
+            });
+        let (subscribe, unsubscribe) = match result {
+            Ok(topics) => topics,
+            Err(msg) => return json!({ "error": msg }),
+        };
+
+        for topic in subscribe {
+            self.subscribe(topic);
+        }
+        for topic in unsubscribe {
+            self.unsubscribe(topic);
+        }
+
+        let mut topics: Vec<_> = self.topics.iter().copied().collect();
+        topics.sort();
+        let topic_names: Vec<_> = topics.iter().map(|topic| topic.as_str()).collect();
+        json!({ "subscribed": topic_names })
+    }
+}
+
+impl Drop for Subscriptions {
+    fn drop(&mut self) {
+        let topics: Vec<_> = self.topics.iter().copied().collect();
+        for topic in topics {
+            self.unsubscribe(topic);
+        }
+    }
+}
+
+/// Adds in `stream` to downstream functions.
+fn with_stream(
+    stream: EventStream,
+) -> impl Filter<Extract = (EventStream,), Error = Infallible> + Clone {
+    warp::any().map(move || stream.clone())
+}
+
+/// Relay a client's subscribed messages to it, and its subscription requests to the stream,
+/// until it disconnects.
+async fn handle_client(websocket: WebSocket, stream: EventStream) {
+    let (mut ws_sender, mut ws_receiver) = websocket.split();
+    let mut messages = stream.sender.subscribe();
+    let mut subscriptions = Subscriptions::new(stream);
+
+    loop {
+        tokio::select! {
+            request = ws_receiver.next() => {
+                let request = match request {
+                    Some(Ok(request)) => request,
+                    Some(Err(e)) => {
+                        debug!("Websocket client failed: {:?}", &e);
+                        break;
+                    }
+                    None => break,
+                };
+                if request.is_close() {
+                    break;
+                }
+                let request = match request.to_str() {
+                    Ok(request) => request,
+                    // pings and binary messages
+                    Err(_) => continue,
+                };
+                let reply = subscriptions.handle_request(request);
+                if ws_sender.send(Message::text(reply.to_string())).await.is_err() {
+                    break;
+                }
+            }
+            message = messages.recv() => {
+                let text = match message {
+                    Ok(message) => {
+                        if !subscriptions.contains(message.topic) {
+                            continue;
+                        }
+                        message.text.clone()
+                    }
+                    Err(RecvError::Lagged(skipped)) => {
+                        warn!("Websocket client fell behind the event stream"; "skipped" => skipped);
+                        json!({ "error": format!("missed {} messages", skipped) }).to_string()
+                    }
+                    Err(RecvError::Closed) => break,
+                };
+                if ws_sender.send(Message::text(text)).await.is_err() {
+                    break;
+                }
+            }
+        }
+    }
+}
+
+/// Define and run the `warp` server.
+async fn serve(
+    signal_receiver: Receiver<()>,
+    stream: EventStream,
+    bind_addr: SocketAddr,
+) -> Result<(), JoinError> {
+    let clients = warp::ws()
+        .and(with_stream(stream))
+        .map(|ws: Ws, stream: EventStream| {
+            ws.on_upgrade(move |websocket| handle_client(websocket, stream))
+        });
+
+    let server = match warp::serve(clients).try_bind_with_graceful_shutdown(bind_addr, async {
+        signal_receiver.await.ok();
+    }) {
+        Ok((_addr, server)) => server,
+        Err(e) => {
+            error!("Failed to bind websocket server"; "ws_bind" => %bind_addr, "err" => %e);
+            return Ok(());
+        }
+    };
+
+    info!("Serving event stream websocket"; "ws_bind" => %bind_addr);
+    tokio::task::spawn(server).await
+}
+
+/// Spawn a thread with a `warp` server, which streams `stream` to websocket clients at
+/// `bind_addr`.
+pub fn spawn(stream: EventStream, bind_addr: SocketAddr) -> Sender<()> {
+    let (signal_sender, signal_receiver) = oneshot::channel();
+    thread::Builder::new()
+        .name("event-stream".into())
+        .spawn(move || {
+            let rt = tokio::runtime::Runtime::new().expect("Failed to initialize tokio");
+            rt.block_on(serve(signal_receiver, stream, bind_addr))
+                .expect("block_on failed");
+        })
+        .expect("`spawn` has failed.");
+    signal_sender
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_subscriptions() {
+        let stream = EventStream::new();
+        let mut client = Subscriptions::new(stream.clone());
+        let mut other_client = Subscriptions::new(stream.clone());
+
+        assert_eq!(
+            client.handle_request(r#"{"subscribe": ["new_block", "mempool_tx_received"]}"#),
+            json!({ "subscribed": ["new_block", "mempool_tx_received"] })
+        );
+        assert_eq!(
+            other_client.handle_request(r#"{"subscribe": ["new_block"]}"#),
+            json!({ "subscribed": ["new_block"] })
+        );
+        assert!(stream.has_subscribers(StreamTopic::NewBlock));
+        assert!(stream.has_subscribers(StreamTopic::MempoolTxReceived));
+        assert!(!stream.has_subscribers(StreamTopic::Microblock));
+
+        // subscribing twice counts once
+        client.handle_request(r#"{"subscribe": ["new_block"]}"#);
+        assert_eq!(
+            stream.subscribers[StreamTopic::NewBlock as usize].load(Ordering::SeqCst),
+            2
+        );
+
+        assert_eq!(
+            client.handle_request(
+                r#"{"subscribe": ["microblock"], "unsubscribe": ["mempool_tx_received"]}"#
+            ),
+            json!({ "subscribed": ["new_block", "microblock"] })
+        );
+        assert!(!stream.has_subscribers(StreamTopic::MempoolTxReceived));
+
+        // a bad request changes nothing
+        assert!(client
+            .handle_request(r#"{"subscribe": ["mempool_txs_dropped", "new_blocks"]}"#)
+            .get("error")
+            .is_some());
+        assert!(client.handle_request("subscribe").get("error").is_some());
+        assert!(!stream.has_subscribers(StreamTopic::MempoolTxsDropped));
+
+        // disconnecting drops a client's subscriptions
+        drop(client);
+        assert!(stream.has_subscribers(StreamTopic::NewBlock));
+        assert!(!stream.has_subscribers(StreamTopic::Microblock));
+        drop(other_client);
+        assert!(!stream.has_subscribers(StreamTopic::NewBlock));
+    }
+}
@@ -0,0 +1,415 @@
+use std::collections::HashSet;
+use std::fs;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use serde_json::json;
+use stacks::chainstate::burn::db::sortdb::SortitionDB;
+use stacks::chainstate::stacks::db::replay::ReplayedBlock;
+use stacks::chainstate::stacks::db::StacksChainState;
+use stacks::chainstate::stacks::StacksBlockHeader;
+use stacks::types::chainstate::StacksBlockId;
+use stacks::util_lib::snapshot::hash_file;
+
+use crate::event_dispatcher::tx_receipt_json;
+use crate::replay::open_for_replay;
+use crate::run_loop::RunLoopError;
+use crate::Config;
+
+/// Version of the export's file layout and record schemas, recorded in its manifest
+pub const EXPORT_VERSION: u32 = 1;
+
+const BLOCKS_FILE: &str = "blocks.jsonl";
+const TRANSACTIONS_FILE: &str = "transactions.jsonl";
+const TRANSACTIONS_CSV_FILE: &str = "transactions.csv";
+const EVENTS_FILE: &str = "events.jsonl";
+const MANIFEST_FILE: &str = "manifest.json";
+/// Directory under the export, removed once it is done, holding the chainstate the node's
+/// blocks are re-executed into to recover their events
+const REPLAY_DIR: &str = ".replay";
+
+const TRANSACTIONS_CSV_HEADER: &str =
+    "block_height,index_block_hash,tx_index,txid,status,microblock_hash,raw_tx";
+
+/// What `export_blocks` wrote
+pub struct ExportSummary {
+    pub num_blocks: u64,
+    pub num_txs: u64,
+    pub num_events: u64,
+    /// hex-encoded SHA-256 hash of the export's manifest
+    pub manifest_hash: String,
+}
+
+/// The export's output files, and the number of records written to each
+struct ExportWriter {
+    blocks: BufWriter<fs::File>,
+    transactions: BufWriter<fs::File>,
+    transactions_csv: Option<BufWriter<fs::File>>,
+    events: BufWriter<fs::File>,
+    num_blocks: u64,
+    num_txs: u64,
+    num_events: u64,
+}
+
+fn io_error(path: &str, e: std::io::Error) -> RunLoopError {
+    RunLoopError::Chainstate(format!("failed to write {}: {}", path, &e))
+}
+
+fn create_output(dest: &Path, name: &str) -> Result<BufWriter<fs::File>, RunLoopError> {
+    let file = fs::File::create(dest.join(name)).map_err(|e| io_error(name, e))?;
+    Ok(BufWriter::new(file))
+}
+
+/// Add the height and index block hash of the block `record` belongs to
+fn with_block(
+    mut record: serde_json::Value,
+    block_height: u64,
+    index_block_hash: &str,
+) -> serde_json::Value {
+    if let Some(fields) = record.as_object_mut() {
+        fields.insert("block_height".into(), json!(block_height));
+        fields.insert("index_block_hash".into(), json!(index_block_hash));
+    }
+    record
+}
+
+impl ExportWriter {
+    fn new(dest: &Path, csv: bool) -> Result<ExportWriter, RunLoopError> {
+        let transactions_csv = if csv {
+            let mut csv = create_output(dest, TRANSACTIONS_CSV_FILE)?;
+            writeln!(csv, "{}", TRANSACTIONS_CSV_HEADER)
+                .map_err(|e| io_error(TRANSACTIONS_CSV_FILE, e))?;
+            Some(csv)
+        } else {
+            None
+        };
+        Ok(ExportWriter {
+            blocks: create_output(dest, BLOCKS_FILE)?,
+            transactions: create_output(dest, TRANSACTIONS_FILE)?,
+            transactions_csv,
+            events: create_output(dest, EVENTS_FILE)?,
+            num_blocks: 0,
+            num_txs: 0,
+            num_events: 0,
+        })
+    }
+
+    /// Write out `block`, its transactions, and their events.  Transactions are serialized as
+    /// event observers receive them, and events as in the observers' `events` lists, each with
+    /// the block's height and index block hash added.
+    fn write_block(
+        &mut self,
+        source: &StacksChainState,
+        block: &ReplayedBlock,
+    ) -> Result<(), RunLoopError> {
+        let header = StacksChainState::get_stacks_block_header_info_by_index_block_hash(
+            source.db(),
+            &block.index_block_hash,
+        )
+        .map_err(|e| RunLoopError::Chainstate(format!("failed to load header: {:?}", &e)))?
+        .ok_or_else(|| {
+            RunLoopError::Chainstate(format!("no header for block {}", &block.index_block_hash))
+        })?;
+        let parent_block_id =
+            StacksChainState::get_parent_block_id(source.db(), &block.index_block_hash).map_err(
+                |e| RunLoopError::Chainstate(format!("failed to load parent block: {:?}", &e)),
+            )?;
+
+        let index_block_hash = format!("0x{}", &block.index_block_hash);
+        let block_record = json!({
+            "block_height": block.block_height,
+            "index_block_hash": &index_block_hash,
+            "block_hash": format!("0x{}", &header.anchored_header.block_hash()),
+            "consensus_hash": format!("0x{}", &header.consensus_hash),
+            "parent_index_block_hash": parent_block_id.map(|id| format!("0x{}", &id)),
+            "burn_block_hash": format!("0x{}", &header.burn_header_hash),
+            "burn_block_height": header.burn_header_height,
+            "burn_block_timestamp": header.burn_header_timestamp,
+            "withdrawal_root": format!("0x{}", &header.withdrawal_tree.root()),
+            "tx_count": block.tx_receipts.len(),
+            "anchored_cost": &block.anchored_block_cost,
+            "confirmed_microblocks_cost": &block.parent_microblocks_cost,
+        });
+        writeln!(self.blocks, "{}", &block_record).map_err(|e| io_error(BLOCKS_FILE, e))?;
+        self.num_blocks += 1;
+
+        let mut event_index = 0;
+        for (tx_index, receipt) in block.tx_receipts.iter().enumerate() {
+            let tx_record = with_block(
+                tx_receipt_json(receipt, tx_index as u32),
+                block.block_height,
+                &index_block_hash,
+            );
+            if let Some(ref mut csv) = self.transactions_csv {
+                let field = |name: &str| tx_record[name].as_str().unwrap_or("").to_string();
+                writeln!(
+                    csv,
+                    "{},{},{},{},{},{},{}",
+                    block.block_height,
+                    &index_block_hash,
+                    tx_index,
+                    field("txid"),
+                    field("status"),
+                    field("microblock_hash"),
+                    field("raw_tx")
+                )
+                .map_err(|e| io_error(TRANSACTIONS_CSV_FILE, e))?;
+            }
+            writeln!(self.transactions, "{}", &tx_record)
+                .map_err(|e| io_error(TRANSACTIONS_FILE, e))?;
+            self.num_txs += 1;
+
+            let txid = receipt.transaction.txid();
+            for event in receipt.events.iter() {
+                let event_record = with_block(
+                    event.json_serialize(event_index, &txid, !receipt.post_condition_aborted),
+                    block.block_height,
+                    &index_block_hash,
+                );
+                writeln!(self.events, "{}", &event_record).map_err(|e| io_error(EVENTS_FILE, e))?;
+                event_index += 1;
+                self.num_events += 1;
+            }
+        }
+        Ok(())
+    }
+
+    fn finish(mut self) -> Result<(u64, u64, u64), RunLoopError> {
+        self.blocks.flush().map_err(|e| io_error(BLOCKS_FILE, e))?;
+        self.transactions
+            .flush()
+            .map_err(|e| io_error(TRANSACTIONS_FILE, e))?;
+        if let Some(ref mut csv) = self.transactions_csv {
+            csv.flush()
+                .map_err(|e| io_error(TRANSACTIONS_CSV_FILE, e))?;
+        }
+        self.events.flush().map_err(|e| io_error(EVENTS_FILE, e))?;
+        Ok((self.num_blocks, self.num_txs, self.num_events))
+    }
+}
+
+/// Get the index block hashes of the canonical Stacks blocks from `from_height` to
+/// `to_height` (or the chain tip), and the height of the highest one.
+fn canonical_block_ids(
+    chainstate: &StacksChainState,
+    sortdb: &SortitionDB,
+    from_height: u64,
+    to_height: Option<u64>,
+) -> Result<(HashSet<StacksBlockId>, u64), RunLoopError> {
+    let (tip_consensus_hash, tip_block_hash) =
+        SortitionDB::get_canonical_stacks_chain_tip_hash(sortdb.conn())
+            .map_err(|e| RunLoopError::Chainstate(format!("failed to load chain tip: {:?}", &e)))?;
+    let mut block_id =
+        StacksBlockHeader::make_index_block_hash(&tip_consensus_hash, &tip_block_hash);
+
+    let mut block_ids = HashSet::new();
+    let mut last_height = None;
+    loop {
+        let header = match StacksChainState::get_stacks_block_header_info_by_index_block_hash(
+            chainstate.db(),
+            &block_id,
+        )
+        .map_err(|e| RunLoopError::Chainstate(format!("failed to load header: {:?}", &e)))?
+        {
+            Some(header) => header,
+            None => break,
+        };
+        let height = header.stacks_block_height;
+        if height < from_height || height == 0 {
+            break;
+        }
+        if to_height.map(|to| height <= to).unwrap_or(true) {
+            block_ids.insert(block_id.clone());
+            if last_height.is_none() {
+                last_height = Some(height);
+            }
+        }
+        block_id = match StacksChainState::get_parent_block_id(chainstate.db(), &block_id)
+            .map_err(|e| RunLoopError::Chainstate(format!("failed to load parent: {:?}", &e)))?
+        {
+            Some(parent_id) => parent_id,
+            None => break,
+        };
+    }
+
+    match last_height {
+        Some(last_height) => Ok((block_ids, last_height)),
+        None => Err(RunLoopError::Chainstate(format!(
+            "no canonical blocks between heights {} and {}",
+            from_height,
+            to_height
+                .map(|to| to.to_string())
+                .unwrap_or_else(|| "the chain tip".to_string())
+        ))),
+    }
+}
+
+/// Export the canonical L2 blocks from `from_height` to `to_height` (or the chain tip), with
+/// their transactions and events, into the new directory `dest_path`, as newline-delimited JSON
+/// files, plus a CSV of the transactions with `csv`.  Files are written block by block as the
+/// blocks are read, and a `manifest.json` with the size and SHA-256 hash of each file is written
+/// last.  Events are not stored, so the node's blocks are re-executed from genesis into a scratch
+/// chainstate to recover them.  The node must not be running.
+pub fn export_blocks(
+    config: &Config,
+    dest_path: &str,
+    from_height: u64,
+    to_height: Option<u64>,
+    csv: bool,
+) -> Result<ExportSummary, RunLoopError> {
+    if fs::metadata(dest_path).is_ok() {
+        return Err(RunLoopError::Chainstate(format!(
+            "{} already exists; the export must be written to a new directory",
+            dest_path
+        )));
+    }
+    let dest = Path::new(dest_path);
+    fs::create_dir_all(dest).map_err(|e| io_error(dest_path, e))?;
+
+    let replay_path = dest.join(REPLAY_DIR);
+    let (source, mut replay, mut sortdb) = open_for_replay(config, &replay_path.to_string_lossy())?;
+    let (block_ids, last_height) = canonical_block_ids(&source, &sortdb, from_height, to_height)?;
+    info!(
+        "Exporting {} blocks, from height {} to {}",
+        block_ids.len(),
+        from_height,
+        last_height
+    );
+
+    let mut writer = ExportWriter::new(dest, csv)?;
+    let mut write_error = None;
+    replay
+        .replay_blocks(&source, &mut sortdb, Some(last_height), |block| {
+            if write_error.is_some() || !block_ids.contains(&block.index_block_hash) {
+                return;
+            }
+            if let Err(e) = writer.write_block(&source, block) {
+                write_error = Some(e);
+            } else if block.block_height % 100 == 0 {
+                info!("Exported block {}", block.block_height);
+            }
+        })
+        .map_err(|e| RunLoopError::Chainstate(format!("failed to re-execute blocks: {:?}", &e)))?;
+    if let Some(e) = write_error {
+        return Err(e);
+    }
+    let (num_blocks, num_txs, num_events) = writer.finish()?;
+
+    drop(replay);
+    fs::remove_dir_all(&replay_path).map_err(|e| io_error(&replay_path.to_string_lossy(), e))?;
+
+    let mut outputs = vec![
+        (BLOCKS_FILE, num_blocks),
+        (TRANSACTIONS_FILE, num_txs),
+        (EVENTS_FILE, num_events),
+    ];
+    if csv {
+        outputs.push((TRANSACTIONS_CSV_FILE, num_txs));
+    }
+    let mut files = vec![];
+    for (name, num_records) in outputs.into_iter() {
+        let (size, sha256) = hash_file(&dest.join(name))
+            .map_err(|e| RunLoopError::Chainstate(format!("failed to hash {}: {}", name, &e)))?;
+        files.push(json!({
+            "path": name,
+            "records": num_records,
+            "size": size,
+            "sha256": sha256,
+        }));
+    }
+    let manifest = json!({
+        "version": EXPORT_VERSION,
+        "mainnet": config.is_mainnet(),
+        "chain_id": config.node.chain_id,
+        "subnet_contract": config.burnchain.contract_identifier.to_string(),
+        "from_height": from_height,
+        "to_height": last_height,
+        "blocks": num_blocks,
+        "transactions": num_txs,
+        "events": num_events,
+        "files": files,
+    });
+    let manifest_path = dest.join(MANIFEST_FILE);
+    let manifest_bytes = serde_json::to_vec_pretty(&manifest)
+        .map_err(|e| RunLoopError::Chainstate(format!("failed to encode manifest: {}", &e)))?;
+    fs::write(&manifest_path, &manifest_bytes).map_err(|e| io_error(MANIFEST_FILE, e))?;
+    let (_, manifest_hash) = hash_file(&manifest_path)
+        .map_err(|e| RunLoopError::Chainstate(format!("failed to hash manifest: {}", &e)))?;
+
+    Ok(ExportSummary {
+        num_blocks,
+        num_txs,
+        num_events,
+        manifest_hash,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ephemeral_dir::EphemeralWorkingDir;
+    use crate::tests::neon_integrations::mockstack_test_conf;
+
+    #[test]
+    fn test_with_block() {
+        let record = with_block(json!({"txid": "0x01"}), 5, "0x02");
+        assert_eq!(
+            record,
+            json!({"txid": "0x01", "block_height": 5, "index_block_hash": "0x02"})
+        );
+
+        // only objects get the block's fields
+        assert_eq!(with_block(json!("0x01"), 5, "0x02"), json!("0x01"));
+    }
+
+    #[test]
+    fn test_export_writer_empty() {
+        let dir = EphemeralWorkingDir::create().unwrap();
+        let writer = ExportWriter::new(dir.path(), true).unwrap();
+        assert_eq!(writer.finish().unwrap(), (0, 0, 0));
+
+        for name in [BLOCKS_FILE, TRANSACTIONS_FILE, EVENTS_FILE] {
+            assert_eq!(fs::read_to_string(dir.path().join(name)).unwrap(), "");
+        }
+        assert_eq!(
+            fs::read_to_string(dir.path().join(TRANSACTIONS_CSV_FILE)).unwrap(),
+            format!("{}\n", TRANSACTIONS_CSV_HEADER)
+        );
+
+        // without csv, no CSV file is written
+        let dir = EphemeralWorkingDir::create().unwrap();
+        ExportWriter::new(dir.path(), false)
+            .unwrap()
+            .finish()
+            .unwrap();
+        assert!(!dir.path().join(TRANSACTIONS_CSV_FILE).exists());
+    }
+
+    #[test]
+    fn test_export_blocks_errors() {
+        let dir = EphemeralWorkingDir::create().unwrap();
+        let (mut conf, _) = mockstack_test_conf();
+        conf.node.working_dir = dir.path().join("node").to_str().unwrap().to_string();
+
+        // the export must not overwrite anything
+        let dest_path = dir.path().join("export");
+        fs::create_dir_all(&dest_path).unwrap();
+        let dest_path = dest_path.to_str().unwrap();
+        match export_blocks(&conf, dest_path, 0, None, false) {
+            Err(RunLoopError::Chainstate(msg)) => {
+                assert!(msg.contains("already exists"), "{}", msg);
+            }
+            res => panic!("Expected Chainstate, got {:?}", res.map(|_| ())),
+        }
+
+        // there's nothing to export from a node which has never run
+        fs::remove_dir_all(dest_path).unwrap();
+        match export_blocks(&conf, dest_path, 0, None, false) {
+            Err(RunLoopError::Chainstate(msg)) => {
+                assert!(msg.starts_with("no chainstate at"), "{}", msg);
+            }
+            res => panic!("Expected Chainstate, got {:?}", res.map(|_| ())),
+        }
+        assert!(!Path::new(dest_path).join(MANIFEST_FILE).exists());
+    }
+}
@@ -0,0 +1,42 @@
+use std::fs;
+
+use rusqlite::OpenFlags;
+use stacks::chainstate::burn::db::sortdb::SortitionDB;
+use stacks::chainstate::stacks::db::StacksChainState;
+use stacks::util_lib::db::sqlite_open;
+
+use crate::run_loop::RunLoopError;
+use crate::Config;
+
+/// Print, as JSON, the tree of L2 blocks committed in the last `num_burn_blocks` L1 blocks of the
+/// node whose working directory is `working_dir`, on every L1 fork the node has seen.  Each
+/// block is annotated with why it is or isn't on the canonical chain.  The databases are opened
+/// read-only, so this can run while the node does.
+pub fn get_forks(working_dir: &str, num_burn_blocks: u64) -> Result<(), RunLoopError> {
+    let mut config = Config::default();
+    config.node.working_dir = working_dir.to_string();
+
+    let index_path = StacksChainState::header_index_root_path(config.get_chainstate_path());
+    if fs::metadata(&index_path).is_err() {
+        return Err(RunLoopError::Chainstate(format!(
+            "no chainstate at {}",
+            &config.get_chainstate_path_str()
+        )));
+    }
+    let chainstate_conn = sqlite_open(&index_path, OpenFlags::SQLITE_OPEN_READ_ONLY, false)
+        .map_err(|e| {
+            RunLoopError::Chainstate(format!("failed to open chainstate database: {:?}", &e))
+        })?;
+    let sortdb = SortitionDB::open(&config.get_burn_db_file_path(), false).map_err(|e| {
+        RunLoopError::Chainstate(format!("failed to open sortition database: {:?}", &e))
+    })?;
+
+    let fork_tree =
+        StacksChainState::get_fork_tree(&chainstate_conn, sortdb.conn(), num_burn_blocks)
+            .map_err(|e| RunLoopError::Chainstate(format!("failed to load fork tree: {:?}", &e)))?;
+    println!(
+        "{}",
+        serde_json::to_string_pretty(&fork_tree).expect("Failed to serialize fork tree")
+    );
+    Ok(())
+}
@@ -0,0 +1,72 @@
+use std::fs;
+
+use stacks::chainstate::burn::db::sortdb::SortitionDB;
+use stacks::chainstate::stacks::db::fsck::FsckReport;
+use stacks::chainstate::stacks::db::StacksChainState;
+
+use crate::run_loop::RunLoopError;
+use crate::Config;
+
+/// Check the chainstate and sortition DB of the node whose working directory is
+/// `config.node.working_dir` for consistency, re-executing the last `num_blocks` canonical
+/// blocks to confirm their MARF roots.  Nothing is written, but the chainstate is opened
+/// read-write to re-execute blocks, so the node must not be running.
+pub fn fsck(config: &Config, num_blocks: u64) -> Result<FsckReport, RunLoopError> {
+    let chainstate_path = config.get_chainstate_path_str();
+    if fs::metadata(&chainstate_path).is_err() {
+        return Err(RunLoopError::Chainstate(format!(
+            "no chainstate at {}",
+            &chainstate_path
+        )));
+    }
+    let sortdb = SortitionDB::open(&config.get_burn_db_file_path(), false).map_err(|e| {
+        RunLoopError::Chainstate(format!("failed to open sortition database: {:?}", &e))
+    })?;
+    let (mut chainstate, _) = StacksChainState::open(
+        config.is_mainnet(),
+        config.node.chain_id,
+        &chainstate_path,
+        Some(config.node.get_marf_opts()),
+    )
+    .map_err(|e| {
+        RunLoopError::Chainstate(format!("failed to open chainstate database: {:?}", &e))
+    })?;
+
+    chainstate
+        .fsck(&sortdb, num_blocks)
+        .map_err(|e| RunLoopError::Chainstate(format!("failed to check chainstate: {:?}", &e)))
+}
+
+/// Print `report`'s problems and repair plan for an operator to read
+pub fn print_report(report: &FsckReport) {
+    println!(
+        "Checked {} staged blocks and {} headers, and re-executed {} blocks from canonical tip {} ({} skipped: block data pruned)",
+        report.staging_blocks_checked,
+        report.headers_checked,
+        report.blocks_reexecuted,
+        &report.canonical_stacks_tip,
+        report.blocks_skipped
+    );
+    if report.problems.is_empty() {
+        println!("No problems found");
+        return;
+    }
+    println!("{} problems found:", report.problems.len());
+    for problem in report.problems.iter() {
+        println!(
+            "  {}",
+            serde_json::to_string(problem).expect("Failed to serialize problem")
+        );
+    }
+    println!("Repair plan:");
+    for block_id in report.repair_plan.orphan_cleanup.iter() {
+        println!("  orphan staged block {}", block_id);
+    }
+    if let Some(height) = report.repair_plan.reprocess_from_height {
+        println!(
+            "  reprocess from height {}: replay-blocks --until-height={} into a fresh working directory, then let the node process the rest",
+            height,
+            height.saturating_sub(1)
+        );
+    }
+}
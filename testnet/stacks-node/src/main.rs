@@ -10,33 +10,60 @@ extern crate serde_json;
 #[macro_use]
 extern crate stacks_common;
 
+#[macro_use]
 extern crate stacks;
 
 #[allow(unused_imports)]
 #[macro_use(o, slog_log, slog_trace, slog_debug, slog_info, slog_warn, slog_error)]
 extern crate slog;
 
+use stacks::chainstate::stacks::db::block_compression::BlockCompression;
+use stacks::types::chainstate::StacksBlockId;
 pub use stacks::util;
 use stacks::util::hash::hex_bytes;
 
 pub mod monitoring;
 
+pub mod block_costs;
 pub mod burnchains;
+pub mod compact_blocks;
 pub mod config;
+pub mod config_tool;
+pub mod deploy_l1_contracts;
+pub mod ephemeral_dir;
 pub mod event_dispatcher;
+pub mod event_queue;
+pub mod event_stream;
+pub mod export;
+pub mod forks;
+pub mod fsck;
 pub mod genesis_data;
 pub mod keychain;
+pub mod mempool_io;
+pub mod mempool_replay;
+pub mod mining_key_rotation;
 pub mod neon_node;
 pub mod node;
 pub mod operations;
+pub mod record_trace;
+pub mod replay;
+pub mod rewards_report;
+pub mod rpc_only;
 pub mod run_loop;
+pub mod scheduled_calls;
+pub mod snapshot;
+pub mod state_diff;
 pub mod syncctl;
+pub mod tx_forwarder;
+pub mod verify_genesis;
+pub mod withdrawal_archive;
 
 pub use self::burnchains::{BurnchainController, BurnchainTip};
 pub use self::config::{Config, ConfigFile};
 pub use self::event_dispatcher::EventDispatcher;
 pub use self::keychain::Keychain;
 pub use self::run_loop::neon;
+use self::run_loop::RunLoopError;
 pub use node::ChainTip;
 
 use pico_args::Arguments;
@@ -71,6 +98,8 @@ fn main() {
         process::exit(1);
     }));
 
+    configure_logging();
+
     let mut args = Arguments::from_env();
     let subcommand = args.subcommand().unwrap().unwrap_or_default();
 
@@ -87,12 +116,620 @@ fn main() {
         );
     }
 
-    let config_file = match subcommand.as_str() {
+    let (config_file, config_path, check_config) = match subcommand.as_str() {
         "start" => {
             let config_path: String = args.value_from_str("--config").unwrap();
+            let check_config = args.contains("--check-config");
+            let restore_snapshot: Option<String> = args
+                .opt_value_from_str("--restore-snapshot")
+                .expect("Failed to parse --restore-snapshot argument");
+            let snapshot_hash: Option<String> = args
+                .opt_value_from_str("--snapshot-sha256")
+                .expect("Failed to parse --snapshot-sha256 argument");
+            args.finish().unwrap();
+            info!("Loading config at path {}", config_path);
+            let config_file = ConfigFile::try_from_path(&config_path).unwrap_or_else(|e| {
+                error!("{}", RunLoopError::InvalidConfig(e));
+                process::exit(1);
+            });
+            if let Some(snapshot_path) = restore_snapshot {
+                let conf = Config::try_from_config_file(config_file.clone()).unwrap_or_else(|e| {
+                    error!("{}", RunLoopError::InvalidConfig(e));
+                    process::exit(1);
+                });
+                if let Err(e) =
+                    snapshot::restore_snapshot(&conf, &snapshot_path, snapshot_hash.as_deref())
+                {
+                    error!("{}", e);
+                    process::exit(1);
+                }
+            }
+            (config_file, config_path, check_config)
+        }
+        "snapshot" => {
+            let action = args.subcommand().unwrap().unwrap_or_default();
+            if action != "create" {
+                print_help();
+                process::exit(1);
+            }
+            let config_path: String = args.value_from_str("--config").unwrap();
+            let dest_path: String = args.value_from_str("--dest").unwrap();
+            let height: Option<u64> = args
+                .opt_value_from_str("--height")
+                .expect("Failed to parse --height argument");
+            args.finish().unwrap();
+            info!("Loading config at path {}", config_path);
+            let conf = ConfigFile::try_from_path(&config_path)
+                .and_then(Config::try_from_config_file)
+                .unwrap_or_else(|e| {
+                    error!("{}", RunLoopError::InvalidConfig(e));
+                    process::exit(1);
+                });
+            match snapshot::create_snapshot(&conf, &dest_path, height) {
+                Ok(manifest_hash) => {
+                    println!("{}", &manifest_hash);
+                }
+                Err(e) => {
+                    error!("{}", e);
+                    process::exit(1);
+                }
+            }
+            return;
+        }
+        "replay-blocks" => {
+            let config_path: String = args.value_from_str("--config").unwrap();
+            let dest_path: String = args.value_from_str("--dest").unwrap();
+            let until_height: Option<u64> = args
+                .opt_value_from_str("--until-height")
+                .expect("Failed to parse --until-height argument");
+            let profile = args.contains("--profile");
+            args.finish().unwrap();
+            info!("Loading config at path {}", config_path);
+            let conf = ConfigFile::try_from_path(&config_path)
+                .and_then(Config::try_from_config_file)
+                .unwrap_or_else(|e| {
+                    error!("{}", RunLoopError::InvalidConfig(e));
+                    process::exit(1);
+                });
+            if let Err(e) = replay::replay_blocks(&conf, &dest_path, until_height, profile) {
+                error!("{}", e);
+                process::exit(1);
+            }
+            return;
+        }
+        "record-trace" => {
+            let config_path: String = args.value_from_str("--config").unwrap();
+            let dest_path: String = args.value_from_str("--dest").unwrap();
+            args.finish().unwrap();
+            info!("Loading config at path {}", config_path);
+            let conf = ConfigFile::try_from_path(&config_path)
+                .and_then(Config::try_from_config_file)
+                .unwrap_or_else(|e| {
+                    error!("{}", RunLoopError::InvalidConfig(e));
+                    process::exit(1);
+                });
+            match record_trace::record_trace(&conf, &dest_path) {
+                Ok(summary) => {
+                    println!(
+                        "Recorded {} burnchain blocks and {} Stacks blocks to {}",
+                        summary.num_burn_blocks, summary.num_stacks_blocks, &dest_path
+                    );
+                }
+                Err(e) => {
+                    error!("{}", e);
+                    process::exit(1);
+                }
+            }
+            return;
+        }
+        "export" => {
+            let config_path: String = args.value_from_str("--config").unwrap();
+            let dest_path: String = args.value_from_str("--dest").unwrap();
+            let from_height: u64 = args
+                .opt_value_from_str("--from-height")
+                .expect("Failed to parse --from-height argument")
+                .unwrap_or(1);
+            let to_height: Option<u64> = args
+                .opt_value_from_str("--to-height")
+                .expect("Failed to parse --to-height argument");
+            let csv = args.contains("--csv");
+            args.finish().unwrap();
+            info!("Loading config at path {}", config_path);
+            let conf = ConfigFile::try_from_path(&config_path)
+                .and_then(Config::try_from_config_file)
+                .unwrap_or_else(|e| {
+                    error!("{}", RunLoopError::InvalidConfig(e));
+                    process::exit(1);
+                });
+            match export::export_blocks(&conf, &dest_path, from_height, to_height, csv) {
+                Ok(summary) => {
+                    println!(
+                        "Exported {} blocks, {} transactions and {} events; manifest sha256 {}",
+                        summary.num_blocks,
+                        summary.num_txs,
+                        summary.num_events,
+                        &summary.manifest_hash
+                    );
+                }
+                Err(e) => {
+                    error!("{}", e);
+                    process::exit(1);
+                }
+            }
+            return;
+        }
+        "rpc-only" => {
+            let working_dir: String = args.value_from_str("--working-dir").unwrap();
+            let config_path: Option<String> = args
+                .opt_value_from_str("--config")
+                .expect("Failed to parse --config argument");
+            let rpc_bind: Option<String> = args
+                .opt_value_from_str("--rpc-bind")
+                .expect("Failed to parse --rpc-bind argument");
+            let primary: Option<String> = args
+                .opt_value_from_str("--primary")
+                .expect("Failed to parse --primary argument");
+            args.finish().unwrap();
+            let mut conf = match config_path {
+                Some(config_path) => {
+                    info!("Loading config at path {}", config_path);
+                    ConfigFile::try_from_path(&config_path)
+                        .and_then(Config::try_from_config_file)
+                        .unwrap_or_else(|e| {
+                            error!("{}", RunLoopError::InvalidConfig(e));
+                            process::exit(1);
+                        })
+                }
+                None => Config::default(),
+            };
+            conf.node.working_dir = working_dir;
+            if let Some(rpc_bind) = rpc_bind {
+                conf.node.rpc_bind = rpc_bind;
+            }
+            if let Err(e) = rpc_only::run_rpc_only(&conf, primary.as_deref()) {
+                error!("{}", e);
+                process::exit(1);
+            }
+            return;
+        }
+        "analyze-block-costs" => {
+            let config_path: String = args.value_from_str("--config").unwrap();
+            let num_blocks: u64 = args.value_from_str("--blocks").unwrap();
+            let top_k: usize = args
+                .opt_value_from_str("--top")
+                .expect("Failed to parse --top argument")
+                .unwrap_or(10);
+            args.finish().unwrap();
+            info!("Loading config at path {}", config_path);
+            let conf = ConfigFile::try_from_path(&config_path)
+                .and_then(Config::try_from_config_file)
+                .unwrap_or_else(|e| {
+                    error!("{}", RunLoopError::InvalidConfig(e));
+                    process::exit(1);
+                });
+            if let Err(e) = block_costs::analyze_block_costs(&conf, num_blocks, top_k) {
+                error!("{}", e);
+                process::exit(1);
+            }
+            return;
+        }
+        "get-forks" => {
+            let num_burn_blocks: u64 = args
+                .opt_value_from_str("--burn-blocks")
+                .expect("Failed to parse --burn-blocks argument")
+                .unwrap_or(20);
+            let free_args = args.free().unwrap();
+            let working_dir = match free_args.as_slice() {
+                [working_dir] => working_dir.clone(),
+                _ => {
+                    print_help();
+                    process::exit(1);
+                }
+            };
+            if let Err(e) = forks::get_forks(&working_dir, num_burn_blocks) {
+                error!("{}", e);
+                process::exit(1);
+            }
+            return;
+        }
+        "fsck" => {
+            let config_path: Option<String> = args
+                .opt_value_from_str("--config")
+                .expect("Failed to parse --config argument");
+            let num_blocks: u64 = args
+                .opt_value_from_str("--blocks")
+                .expect("Failed to parse --blocks argument")
+                .unwrap_or(10);
+            let free_args = args.free().unwrap();
+            let working_dir = match free_args.as_slice() {
+                [working_dir] => working_dir.clone(),
+                _ => {
+                    print_help();
+                    process::exit(1);
+                }
+            };
+            let mut conf = match config_path {
+                Some(config_path) => {
+                    info!("Loading config at path {}", config_path);
+                    ConfigFile::try_from_path(&config_path)
+                        .and_then(Config::try_from_config_file)
+                        .unwrap_or_else(|e| {
+                            error!("{}", RunLoopError::InvalidConfig(e));
+                            process::exit(1);
+                        })
+                }
+                None => Config::default(),
+            };
+            conf.node.working_dir = working_dir;
+            match fsck::fsck(&conf, num_blocks) {
+                Ok(report) => {
+                    fsck::print_report(&report);
+                    if !report.problems.is_empty() {
+                        process::exit(1);
+                    }
+                }
+                Err(e) => {
+                    error!("{}", e);
+                    process::exit(1);
+                }
+            }
+            return;
+        }
+        "state-diff" => {
+            let config_path: Option<String> = args
+                .opt_value_from_str("--config")
+                .expect("Failed to parse --config argument");
+            let contract_prefix: Option<String> = args
+                .opt_value_from_str("--contract")
+                .expect("Failed to parse --contract argument");
+            let free_args = args.free().unwrap();
+            let (working_dir, from_height, to_height) = match free_args.as_slice() {
+                [working_dir, from_height, to_height] => {
+                    match (from_height.parse::<u64>(), to_height.parse::<u64>()) {
+                        (Ok(from_height), Ok(to_height)) if from_height < to_height => {
+                            (working_dir.clone(), from_height, to_height)
+                        }
+                        _ => {
+                            print_help();
+                            process::exit(1);
+                        }
+                    }
+                }
+                _ => {
+                    print_help();
+                    process::exit(1);
+                }
+            };
+            let mut conf = match config_path {
+                Some(config_path) => {
+                    info!("Loading config at path {}", config_path);
+                    ConfigFile::try_from_path(&config_path)
+                        .and_then(Config::try_from_config_file)
+                        .unwrap_or_else(|e| {
+                            error!("{}", RunLoopError::InvalidConfig(e));
+                            process::exit(1);
+                        })
+                }
+                None => Config::default(),
+            };
+            conf.node.working_dir = working_dir;
+            if let Err(e) =
+                state_diff::state_diff(&conf, from_height, to_height, contract_prefix.as_deref())
+            {
+                error!("{}", e);
+                process::exit(1);
+            }
+            return;
+        }
+        "rewards-report" => {
+            let config_path: Option<String> = args
+                .opt_value_from_str("--config")
+                .expect("Failed to parse --config argument");
+            let free_args = args.free().unwrap();
+            let (working_dir, from_height, to_height) = match free_args.as_slice() {
+                [working_dir, from_height, to_height] => {
+                    match (from_height.parse::<u64>(), to_height.parse::<u64>()) {
+                        (Ok(from_height), Ok(to_height)) if from_height <= to_height => {
+                            (working_dir.clone(), from_height, to_height)
+                        }
+                        _ => {
+                            print_help();
+                            process::exit(1);
+                        }
+                    }
+                }
+                _ => {
+                    print_help();
+                    process::exit(1);
+                }
+            };
+            let mut conf = match config_path {
+                Some(config_path) => {
+                    info!("Loading config at path {}", config_path);
+                    ConfigFile::try_from_path(&config_path)
+                        .and_then(Config::try_from_config_file)
+                        .unwrap_or_else(|e| {
+                            error!("{}", RunLoopError::InvalidConfig(e));
+                            process::exit(1);
+                        })
+                }
+                None => Config::default(),
+            };
+            conf.node.working_dir = working_dir;
+            if let Err(e) = rewards_report::rewards_report(&conf, from_height, to_height) {
+                error!("{}", e);
+                process::exit(1);
+            }
+            return;
+        }
+        "compact-blocks" => {
+            let config_path: Option<String> = args
+                .opt_value_from_str("--config")
+                .expect("Failed to parse --config argument");
+            let compression: Option<String> = args
+                .opt_value_from_str("--compression")
+                .expect("Failed to parse --compression argument");
+            let compression = match compression {
+                Some(name) => BlockCompression::from_name(&name).unwrap_or_else(|| {
+                    print_help();
+                    process::exit(1);
+                }),
+                None => BlockCompression::Zstd,
+            };
+            let free_args = args.free().unwrap();
+            let working_dir = match free_args.as_slice() {
+                [working_dir] => working_dir.clone(),
+                _ => {
+                    print_help();
+                    process::exit(1);
+                }
+            };
+            let mut conf = match config_path {
+                Some(config_path) => {
+                    info!("Loading config at path {}", config_path);
+                    ConfigFile::try_from_path(&config_path)
+                        .and_then(Config::try_from_config_file)
+                        .unwrap_or_else(|e| {
+                            error!("{}", RunLoopError::InvalidConfig(e));
+                            process::exit(1);
+                        })
+                }
+                None => Config::default(),
+            };
+            conf.node.working_dir = working_dir;
+            if let Err(e) = compact_blocks::compact_blocks(&conf, compression) {
+                error!("{}", e);
+                process::exit(1);
+            }
+            return;
+        }
+        "mempool" => {
+            let action = args.subcommand().unwrap().unwrap_or_default();
+            let config_path: String = args.value_from_str("--config").unwrap();
+            let free_args = args.free().unwrap();
+            let path = match (action.as_str(), free_args.as_slice()) {
+                ("export", [path]) | ("import", [path]) => path.clone(),
+                _ => {
+                    print_help();
+                    process::exit(1);
+                }
+            };
+            info!("Loading config at path {}", config_path);
+            let conf = ConfigFile::try_from_path(&config_path)
+                .and_then(Config::try_from_config_file)
+                .unwrap_or_else(|e| {
+                    error!("{}", RunLoopError::InvalidConfig(e));
+                    process::exit(1);
+                });
+            let result = if action == "export" {
+                mempool_io::export_mempool(&conf, &path).map(|num_txs| {
+                    println!("Exported {} transactions", num_txs);
+                })
+            } else {
+                mempool_io::import_mempool(&conf, &path).map(|summary| {
+                    println!(
+                        "Imported {} transactions; {} already present, {} rejected",
+                        summary.imported, summary.already_present, summary.rejected
+                    );
+                })
+            };
+            if let Err(e) = result {
+                error!("{}", e);
+                process::exit(1);
+            }
+            return;
+        }
+        "replay-mempool" => {
+            let config_path: String = args.value_from_str("--config").unwrap();
+            let tip: String = args.value_from_str("--at-tip").unwrap();
+            let free_args = args.free().unwrap();
+            let journal_path = match free_args.as_slice() {
+                [journal_path] => journal_path.clone(),
+                _ => {
+                    print_help();
+                    process::exit(1);
+                }
+            };
+            let tip = match StacksBlockId::from_hex(&tip) {
+                Ok(tip) => tip,
+                Err(_) => {
+                    print_help();
+                    process::exit(1);
+                }
+            };
+            info!("Loading config at path {}", config_path);
+            let conf = ConfigFile::try_from_path(&config_path)
+                .and_then(Config::try_from_config_file)
+                .unwrap_or_else(|e| {
+                    error!("{}", RunLoopError::InvalidConfig(e));
+                    process::exit(1);
+                });
+            match mempool_replay::replay_mempool(&conf, &journal_path, &tip) {
+                Ok(summary) => {
+                    println!(
+                        "Admitted {} transactions; {} rejected",
+                        summary.admitted,
+                        summary.rejected.len()
+                    );
+                    for (txid, reason) in summary.rejected.iter() {
+                        println!("rejected {}: {}", txid, reason);
+                    }
+                    for (seq, txids) in summary.microblocks.iter().enumerate() {
+                        println!("microblock {}: {} transactions", seq, txids.len());
+                        for txid in txids.iter() {
+                            println!("  {}", txid);
+                        }
+                    }
+                }
+                Err(e) => {
+                    error!("{}", e);
+                    process::exit(1);
+                }
+            }
+            return;
+        }
+        "withdrawal-archive" => {
+            let action = args.subcommand().unwrap().unwrap_or_default();
+            let config_path: String = args.value_from_str("--config").unwrap();
+            let free_args = args.free().unwrap();
+            let path = match (action.as_str(), free_args.as_slice()) {
+                ("export", [path]) | ("import", [path]) => path.clone(),
+                _ => {
+                    print_help();
+                    process::exit(1);
+                }
+            };
+            info!("Loading config at path {}", config_path);
+            let conf = ConfigFile::try_from_path(&config_path)
+                .and_then(Config::try_from_config_file)
+                .unwrap_or_else(|e| {
+                    error!("{}", RunLoopError::InvalidConfig(e));
+                    process::exit(1);
+                });
+            let result = if action == "export" {
+                withdrawal_archive::export_withdrawal_archive(&conf, &path).map(|num_trees| {
+                    println!("Exported {} withdrawal trees", num_trees);
+                })
+            } else {
+                withdrawal_archive::import_withdrawal_archive(&conf, &path).map(|summary| {
+                    println!(
+                        "Imported {} withdrawal trees; {} already present",
+                        summary.imported, summary.already_present
+                    );
+                })
+            };
+            if let Err(e) = result {
+                error!("{}", e);
+                process::exit(1);
+            }
+            return;
+        }
+        "config" => {
+            let action = args.subcommand().unwrap().unwrap_or_default();
+            match action.as_str() {
+                "new" => {
+                    let l1_contract: String = args.value_from_str("--l1-contract").unwrap();
+                    let mode_name: String = args.value_from_str("--mode").unwrap();
+                    args.finish().unwrap();
+                    let mode =
+                        config_tool::ConfigMode::from_name(&mode_name).unwrap_or_else(|| {
+                            error!(
+                                "Unknown --mode `{}`; expected `mocknet` or `testnet`",
+                                &mode_name
+                            );
+                            process::exit(1);
+                        });
+                    match config_tool::new_config(&l1_contract, mode) {
+                        Ok(config_toml) => {
+                            print!("{}", &config_toml);
+                        }
+                        Err(e) => {
+                            error!("{}", e);
+                            process::exit(1);
+                        }
+                    }
+                }
+                "validate" => {
+                    let free_args = args.free().unwrap();
+                    let config_path = match free_args.as_slice() {
+                        [path] => path.clone(),
+                        _ => {
+                            print_help();
+                            process::exit(1);
+                        }
+                    };
+                    info!("Loading config at path {}", config_path);
+                    if let Err(e) = config_tool::validate_config(&config_path) {
+                        error!("{}", e);
+                        process::exit(1);
+                    }
+                    println!("Config file {} is valid", &config_path);
+                }
+                _ => {
+                    print_help();
+                    process::exit(1);
+                }
+            }
+            return;
+        }
+        "deploy-l1-contracts" => {
+            let key: String = args.value_from_str("--key").unwrap();
+            let l1_rpc: String = args.value_from_str("--l1-rpc").unwrap();
+            let contract_name: Option<String> = args
+                .opt_value_from_str("--contract-name")
+                .expect("Failed to parse --contract-name argument");
+            let fee: Option<u64> = args
+                .opt_value_from_str("--fee")
+                .expect("Failed to parse --fee argument");
+            args.finish().unwrap();
+            let contract_name =
+                contract_name.unwrap_or_else(deploy_l1_contracts::default_contract_name);
+            match deploy_l1_contracts::deploy_l1_contracts(
+                &key,
+                &l1_rpc,
+                &contract_name,
+                fee.unwrap_or(deploy_l1_contracts::DEFAULT_PUBLISH_FEE),
+            ) {
+                Ok(contract_id) => {
+                    println!("\n[burnchain]\ncontract_identifier = \"{}\"", &contract_id);
+                }
+                Err(e) => {
+                    error!("{}", e);
+                    process::exit(1);
+                }
+            }
+            return;
+        }
+        "verify-genesis" => {
+            let config_path: String = args.value_from_str("--config").unwrap();
+            let spec_path: Option<String> = args
+                .opt_value_from_str("--spec")
+                .expect("Failed to parse --spec argument");
+            let expected: Option<String> = args
+                .opt_value_from_str("--expected")
+                .expect("Failed to parse --expected argument");
             args.finish().unwrap();
             info!("Loading config at path {}", config_path);
-            ConfigFile::from_path(&config_path)
+            let conf = ConfigFile::try_from_path(&config_path)
+                .and_then(Config::try_from_config_file)
+                .unwrap_or_else(|e| {
+                    error!("{}", RunLoopError::InvalidConfig(e));
+                    process::exit(1);
+                });
+            let result = match spec_path {
+                Some(spec_path) => verify_genesis::GenesisSpec::from_path(&spec_path),
+                None => Ok(verify_genesis::GenesisSpec::default()),
+            }
+            .and_then(|spec| verify_genesis::verify_genesis(&conf, &spec, expected.as_deref()));
+            match result {
+                Ok(true) => {}
+                Ok(false) => process::exit(1),
+                Err(e) => {
+                    error!("{}", e);
+                    process::exit(1);
+                }
+            }
+            return;
         }
         "version" => {
             println!("{}", &version());
@@ -130,13 +767,53 @@ fn main() {
         }
     };
 
-    let conf = Config::from_config_file(config_file);
+    let conf = Config::try_from_config_file(config_file).unwrap_or_else(|e| {
+        error!("{}", RunLoopError::InvalidConfig(e));
+        process::exit(1);
+    });
     debug!("node configuration {:?}", &conf.node);
     debug!("burnchain configuration {:?}", &conf.burnchain);
     debug!("connection configuration {:?}", &conf.connection_options);
 
+    if check_config {
+        match neon::RunLoop::check_l1(&conf) {
+            Ok(()) => {
+                println!("Config file {} is valid", &config_path);
+                return;
+            }
+            Err(e) => {
+                error!("{}", e);
+                process::exit(1);
+            }
+        }
+    }
+
     let mut run_loop = neon::RunLoop::new(conf);
-    run_loop.start(None, mine_start.unwrap_or(0));
+    run_loop.set_config_path(config_path);
+    if let Err(e) = run_loop.start(None, mine_start.unwrap_or(0)) {
+        error!("{}", e);
+        process::exit(1);
+    }
+}
+
+/// Apply the logging settings of the `--config` file, if there is one.  The global logger is set
+/// up by the first line logged, so this has to happen before the subcommand's arguments are
+/// parsed.  Errors in the config file are ignored here and reported once it is loaded.
+fn configure_logging() {
+    let argv: Vec<String> = env::args().collect();
+    let config_path = argv.iter().enumerate().find_map(|(i, arg)| {
+        if arg == "--config" {
+            argv.get(i + 1).cloned()
+        } else {
+            arg.strip_prefix("--config=").map(|path| path.to_string())
+        }
+    });
+    if let Some(log_config) = config_path
+        .and_then(|path| ConfigFile::try_from_path(&path).ok())
+        .and_then(|config_file| config_file.get_log_config().ok())
+    {
+        log_config.apply();
+    }
 }
 
 fn version() -> String {
@@ -179,9 +856,200 @@ testnet\t\tStart a node that will join and stream blocks from the public testnet
 start\t\tStart a node with a config of your own. Can be used for joining a network, starting new chain, etc.
 \t\tArguments:
 \t\t  --config: path of the config (such as https://github.com/blockstack/stacks-blockchain/blob/master/testnet/stacks-node/conf/testnet-follower-conf.toml).
+\t\t  --check-config: validate the config, and check that the L1 node is reachable and hosts the subnet contract, then exit.
+\t\t  --restore-snapshot: optional; before starting, restore the chainstate from a snapshot made with `snapshot create`.
+\t\t    The node must not have a chainstate yet; it then syncs the blocks after the snapshot from the L1 and its peers.
+\t\t  --snapshot-sha256: optional; the manifest hash printed by `snapshot create`, to check the snapshot against.
 \t\tExample:
 \t\t  stacks-node start --config=/path/to/config.toml
 
+replay-blocks\tRe-execute the node's processed blocks from genesis into a new chainstate, checking that each
+\t\tblock reproduces the state and withdrawal roots the node stored. The node must not be running.
+\t\tArguments:
+\t\t  --config: path of the node's config.
+\t\t  --dest: directory for the new chainstate; must not exist.
+\t\t  --until-height: optional; stop after the block at this height.
+\t\t  --profile: print each block's execution cost and processing time as CSV.
+\t\tExample:
+\t\t  stacks-node replay-blocks --config=/path/to/config.toml --dest=/tmp/replay
+
+record-trace\tRecord the burnchain blocks and Stacks blocks the node has processed, with the consensus hashes
+\t\tand state roots it computed, as a replayable trace. Check a trace in under
+\t\tsrc/chainstate/coordinator/trace_fixtures to have the unit tests replay it through the coordinator. The
+\t\tnode may be running.
+\t\tArguments:
+\t\t  --config: path of the node's config.
+\t\t  --dest: file for the trace; must not exist.
+\t\tExample:
+\t\t  stacks-node record-trace --config=/path/to/config.toml --dest=/tmp/testnet-trace.jsonl
+
+export\t\tWrite the canonical blocks in a height range, with their transactions and events, to a new directory as
+\t\tnewline-delimited JSON (blocks.jsonl, transactions.jsonl, events.jsonl), plus a manifest.json holding the
+\t\tsize and SHA-256 hash of each file. Transactions and events have the schema event observers receive.
+\t\tEvents are recovered by re-executing the node's blocks from genesis. The node must not be running.
+\t\tArguments:
+\t\t  --config: path of the node's config.
+\t\t  --dest: directory for the export; must not exist.
+\t\t  --from-height: optional; first block height to export (default 1).
+\t\t  --to-height: optional; last block height to export (default: the chain tip).
+\t\t  --csv: also write the transactions to transactions.csv.
+\t\tExample:
+\t\t  stacks-node export --config=/path/to/config.toml --dest=/tmp/export --from-height=1000 --csv
+
+rpc-only\tServe the read RPC endpoints from another node's databases, opened read-only, so that reads can be
+\t\tscaled independently of that node. POSTed blocks and microblocks are refused; POSTed transactions are
+\t\tforwarded to the primary node if --primary is given, and refused otherwise. The primary node must have
+\t\tcreated its databases first.
+\t\tArguments:
+\t\t  --working-dir: the primary node's working directory.
+\t\t  --config: optional; config for the chain and RPC settings (default: the built-in defaults).
+\t\t  --rpc-bind: optional; address to serve RPC on (default: [node] rpc_bind).
+\t\t  --primary: optional; RPC URL of the primary node, e.g. http://127.0.0.1:20443.
+\t\tExample:
+\t\t  stacks-node rpc-only --working-dir=/var/subnet --config=/path/to/config.toml --rpc-bind=0.0.0.0:30443 --primary=http://127.0.0.1:20443
+
+analyze-block-costs\tReport, as JSON, the share of the subnet block limit used in each cost dimension by the
+\t\tlast N canonical blocks, and the contracts that cost the most to call or publish over those blocks.
+\t\tContract costs are approximate: they are found by re-executing each block's transactions.
+\t\tArguments:
+\t\t  --config: path of the node's config.
+\t\t  --blocks: number of blocks to analyze, walking back from the chain tip.
+\t\t  --top: optional; number of contracts to report (default 10).
+\t\tExample:
+\t\t  stacks-node analyze-block-costs --config=/path/to/config.toml --blocks=1000 --top=20
+
+get-forks <working-dir>\tReport, as JSON, the tree of subnet blocks committed in the last N L1 blocks, on every
+\t\tL1 fork the node in <working-dir> has seen. Each block is marked canonical, or else with why it is not:
+\t\tits L1 block is on a non-canonical L1 fork, it was superseded by a longer chain committed after it,
+\t\tit was orphaned, or it is not processed yet. The same tree is served at /v2/hyperchain/forks.
+\t\tArguments:
+\t\t  --burn-blocks: optional; number of L1 blocks to report, walking back from the L1 tip (default 20).
+\t\tExample:
+\t\t  stacks-node get-forks /tmp/subnet-node --burn-blocks=50
+
+fsck <working-dir>\tCheck the chainstate of the node in <working-dir> for consistency: staged blocks against the
+\t\tsortition DB, every block header against its parent, and the MARF roots of the last N canonical blocks,
+\t\tby re-executing them. Prints the problems found and a repair plan: staged blocks to orphan, and the
+\t\theight to reprocess the chain from. Nothing is written, but the node must not be running. Exits with
+\t\tstatus 1 if any problem is found.
+\t\tArguments:
+\t\t  --config: optional; config for the chain settings (default: the built-in defaults).
+\t\t  --blocks: optional; number of canonical blocks to re-execute, walking back from the tip (default 10).
+\t\tExample:
+\t\t  stacks-node fsck /tmp/subnet-node --config=/path/to/config.toml --blocks=100
+
+state-diff <working-dir> <height-A> <height-B>\tReport, as JSON, the Clarity state keys added or changed by the
+\t\tcanonical blocks above height A, up to and including height B, of the node in <working-dir>, with their
+\t\tvalues at both heights. The keys are found by walking the MARF, and named where they can be: STX balances
+\t\tand nonces, published contracts, and the data vars, map entries and token balances of the contracts the
+\t\tblocks' transactions call. Keys that can't be named are reported by their hash. Nothing is written.
+\t\tArguments:
+\t\t  --config: optional; config for the chain settings (default: the built-in defaults).
+\t\t  --contract: optional; only report the keys of contracts whose identifier starts with this prefix.
+\t\tExample:
+\t\t  stacks-node state-diff /tmp/subnet-node 1000 1100 --contract=ST1PQHQKV0RJXZFY1DGX8MNSNYVE3VGZJSRTPGZGM.
+
+rewards-report <working-dir> <from-height> <to-height>\tReport, as JSON, what each miner earned from the canonical
+\t\tblocks at heights <from-height> up to and including <to-height> of the node in <working-dir>: its blocks
+\t\tmined, coinbase, anchored and microblock transaction fees, and total. If the node in <working-dir> mined,
+\t\tthe L1 fees of the block-commits it sent are reported too, with its net income. Rewards are counted at
+\t\tthe height of the block which earned them, before they mature. Nothing is written.
+\t\tArguments:
+\t\t  --config: optional; config for the chain settings (default: the built-in defaults).
+\t\tExample:
+\t\t  stacks-node rewards-report /tmp/subnet-node 1000 1100
+
+compact-blocks <working-dir>\tRewrite the stored blocks and microblocks of the node in <working-dir> with the given
+\t\tcompression, and report the space saved. Use it to compress the blocks stored before setting
+\t\t[node] block_compression = \"zstd\", which only applies to the blocks stored from then on. Blocks and
+\t\tmicroblocks are read whether they are compressed or not. The node must not be running.
+\t\tArguments:
+\t\t  --config: optional; config for the chain settings (default: the built-in defaults).
+\t\t  --compression: optional; `zstd` (the default) or `none`.
+\t\tExample:
+\t\t  stacks-node compact-blocks /tmp/subnet-node --config=/path/to/config.toml
+
+snapshot create\tPackage the node's chainstate and sortition DB, at its current chain tip, into a snapshot directory
+\t\twith a SHA-256 hash of every file, for new followers to start from. Prints the hash of the snapshot's
+\t\tmanifest. The node must not be running.
+\t\tArguments:
+\t\t  --config: path of the node's config.
+\t\t  --dest: directory for the snapshot; must not exist.
+\t\t  --height: optional; fail unless the chain tip is at this height.
+\t\tExample:
+\t\t  stacks-node snapshot create --config=/path/to/config.toml --dest=/tmp/snapshot
+
+mempool export <file>\tWrite the node's pending transactions to <file>, one JSON object per line holding the
+\t\ttransaction's consensus serialization and the metadata the mempool kept for it.
+mempool import <file>\tRe-admit the transactions in a file written by `mempool export`. Each is checked against
+\t\tthe node's chain tip, so transactions mined or invalidated since the export are dropped.
+\t\tSee also the `mempool_persist_path` node setting, which does both across restarts.
+\t\tArguments:
+\t\t  --config: path of the node's config.
+\t\tExample:
+\t\t  stacks-node mempool export --config=/path/to/config.toml /tmp/mempool.jsonl
+
+replay-mempool <journal>\tSubmit the transactions in a mempool arrival journal (see the `[mempool] arrival_journal`
+\t\tsetting) to a scratch mempool, in the order and at the times they arrived, checking each against the block
+\t\t--at-tip, then assemble microblocks off of that block from it as the miner would. Prints which transactions
+\t\twere rejected, and the transactions of each microblock in order, to reproduce reported block-construction
+\t\tproblems. Nothing is broadcast. The node must not be running.
+\t\tArguments:
+\t\t  --config: path of the node's config.
+\t\t  --at-tip: the index block hash of the block to replay against.
+\t\tExample:
+\t\t  stacks-node replay-mempool --config=/path/to/config.toml --at-tip=<index block hash> /tmp/arrivals.jsonl
+
+withdrawal-archive export <file>\tWrite the node's archive of withdrawal Merkle trees to <file>, one JSON object per
+\t\tline holding a block's index block hash, height, withdrawal root and tree. The archive holds the tree of
+\t\tevery block the node has processed, and is never pruned.
+withdrawal-archive import <file>\tAdd the trees in a file written by `withdrawal-archive export` to the node's
+\t\tarchive, so that it can serve Merkle proofs of the withdrawals in them, e.g. after moving to a new node.
+\t\tNothing is imported if a tree doesn't hash to its root, or doesn't match a block the node has processed.
+\t\tThe node must not be running.
+\t\tArguments:
+\t\t  --config: path of the node's config.
+\t\tExample:
+\t\t  stacks-node withdrawal-archive export --config=/path/to/config.toml /tmp/withdrawals.jsonl
+
+config new\tPrint a config file for a subnet miner, with a freshly generated mining key and peer seed, which
+\t\tthe node can start with once `first_burn_header_height` is set.
+\t\tArguments:
+\t\t  --l1-contract: the identifier of the subnet contract on the L1, e.g. ST1PQHQKV0RJXZFY1DGX8MNSNYVE3VGZJSRTPGZGM.subnet.
+\t\t  --mode: `mocknet` to run on a simulated L1 which the node makes blocks for itself, or `testnet` to follow a Stacks testnet node on this host.
+\t\tExample:
+\t\t  stacks-node config new --l1-contract=ST1PQHQKV0RJXZFY1DGX8MNSNYVE3VGZJSRTPGZGM.subnet --mode=testnet > subnet.toml
+config validate <file>\tCheck a config file before starting the node: that it parses, that its keys and contract
+\t\tidentifier are well-formed, that no two of its ports collide, and that the L1 node is reachable and
+\t\thosts the subnet contract.
+\t\tExample:
+\t\t  stacks-node config validate subnet.toml
+
+deploy-l1-contracts\tPublish a new subnet contract to the L1, with the trait contracts it uses (subnet-traits-v1, and
+\t\tsip-traits for the SIP-009 and SIP-010 traits), in the order they depend on each other, from the address
+\t\tof --key. Waits for the publishes to confirm, then prints the [burnchain] contract_identifier to put in
+\t\tthe node's config. Contracts the address has already published are skipped, so an interrupted deployment
+\t\tcan be finished by running the command again.
+\t\tArguments:
+\t\t  --key: hex-encoded private key to publish from; its address becomes the contract's miner and admin.
+\t\t  --l1-rpc: RPC URL of the L1 node, e.g. http://127.0.0.1:20443.
+\t\t  --contract-name: optional; name of the subnet contract (default: the templates' subnet_contract).
+\t\t  --fee: optional; fee of each publish, in uSTX (default 1000000).
+\t\tExample:
+\t\t  stacks-node deploy-l1-contracts --key=<hex> --l1-rpc=http://127.0.0.1:20443 --contract-name=my-subnet
+
+verify-genesis\tBoot a throwaway chainstate from the config and a genesis specification, and compare the root hash
+\t\tof its genesis Clarity state with the expected one, printing both and marking the digits that differ.
+\t\tExits non-zero on a mismatch.
+\t\tArguments:
+\t\t  --config: path of the node's config.
+\t\t  --spec: optional; TOML file of `[[balance]]` entries (`address`, `amount`) to seed and `[[contract]]`
+\t\t    entries (`name`, `path`) for the boot address to deploy after the boot code. Without it, the node's
+\t\t    own genesis is computed.
+\t\t  --expected: optional; hex root hash to compare against (default: the built-in SUBNET_GENESIS_ROOT_HASH).
+\t\tExample:
+\t\t  stacks-node verify-genesis --config=/path/to/config.toml --spec=genesis.toml --expected=455f...560b
+
 version\t\tDisplay information about the current version and our release cycle.
 
 key-for-seed\tOutput the associated secret key for a burnchain signer created with a given seed.
@@ -0,0 +1,126 @@
+use std::fs;
+use std::io::{BufReader, BufWriter, Write};
+
+use stacks::chainstate::burn::db::sortdb::SortitionDB;
+use stacks::chainstate::stacks::db::StacksChainState;
+use stacks::core::mempool::{MemPoolDB, MemPoolImportSummary};
+use stacks::cost_estimates::metrics::UnitMetric;
+use stacks::cost_estimates::UnitEstimator;
+
+use crate::run_loop::RunLoopError;
+use crate::Config;
+
+fn open_mempool(config: &Config) -> Result<MemPoolDB, RunLoopError> {
+    let chainstate_path = config.get_chainstate_path_str();
+    if fs::metadata(&chainstate_path).is_err() {
+        return Err(RunLoopError::Chainstate(format!(
+            "no chainstate at {}",
+            &chainstate_path
+        )));
+    }
+    let cost_estimator = config
+        .make_cost_estimator()
+        .unwrap_or_else(|| Box::new(UnitEstimator));
+    let metric = config
+        .make_cost_metric()
+        .unwrap_or_else(|| Box::new(UnitMetric));
+    let mut mempool = MemPoolDB::open(
+        config.is_mainnet(),
+        config.node.chain_id,
+        &chainstate_path,
+        cost_estimator,
+        metric,
+    )
+    .map_err(|e| RunLoopError::Chainstate(format!("failed to open mempool: {:?}", &e)))?;
+    mempool.set_admission_limits(config.get_mempool_admission_limits());
+    mempool.set_rejected_txs_retention(config.mempool.rejected_txs_retention.clone());
+    mempool.set_clock(config.clock.clone());
+    Ok(mempool)
+}
+
+/// Write the node's pending transactions to `dest_path`, replacing it if it exists.  Returns the
+/// number of transactions written.
+pub fn export_mempool(config: &Config, dest_path: &str) -> Result<u64, RunLoopError> {
+    let mempool = open_mempool(config)?;
+
+    // write to a temporary file first, so an interrupted export leaves no partial file behind
+    let tmp_path = format!("{}.tmp", dest_path);
+    let num_txs = {
+        let file = fs::File::create(&tmp_path).map_err(|e| {
+            RunLoopError::Chainstate(format!("failed to create {}: {:?}", &tmp_path, &e))
+        })?;
+        let mut fd = BufWriter::new(file);
+        let num_txs = mempool
+            .export_txs(&mut fd)
+            .map_err(|e| RunLoopError::Chainstate(format!("failed to export mempool: {:?}", &e)))?;
+        fd.flush().map_err(|e| {
+            RunLoopError::Chainstate(format!("failed to write {}: {:?}", &tmp_path, &e))
+        })?;
+        num_txs
+    };
+    fs::rename(&tmp_path, dest_path).map_err(|e| {
+        RunLoopError::Chainstate(format!("failed to write {}: {:?}", dest_path, &e))
+    })?;
+
+    info!("Exported {} mempool transactions to {}", num_txs, dest_path);
+    Ok(num_txs)
+}
+
+/// Re-admit the transactions in `source_path`, as written by `export_mempool`, into the node's
+/// mempool.  Each is checked against the node's canonical chain tip, so transactions mined or
+/// invalidated since the export are dropped.
+pub fn import_mempool(
+    config: &Config,
+    source_path: &str,
+) -> Result<MemPoolImportSummary, RunLoopError> {
+    let mut mempool = open_mempool(config)?;
+    let sortdb = SortitionDB::open(&config.get_burn_db_file_path(), false).map_err(|e| {
+        RunLoopError::Chainstate(format!("failed to open sortition database: {:?}", &e))
+    })?;
+    let (mut chainstate, _) = StacksChainState::open(
+        config.is_mainnet(),
+        config.node.chain_id,
+        &config.get_chainstate_path_str(),
+        Some(config.node.get_marf_opts()),
+    )
+    .map_err(|e| {
+        RunLoopError::Chainstate(format!("failed to open chainstate database: {:?}", &e))
+    })?;
+
+    let (consensus_hash, block_hash) =
+        SortitionDB::get_canonical_stacks_chain_tip_hash(sortdb.conn())
+            .map_err(|e| RunLoopError::Chainstate(format!("failed to load chain tip: {:?}", &e)))?;
+    let burn_tip = SortitionDB::get_canonical_burn_chain_tip(sortdb.conn())
+        .map_err(|e| RunLoopError::Chainstate(format!("failed to load burnchain tip: {:?}", &e)))?;
+    let stacks_epoch = sortdb
+        .index_conn()
+        .get_stacks_epoch(burn_tip.block_height as u32)
+        .ok_or_else(|| {
+            RunLoopError::Chainstate(format!(
+                "no Stacks epoch for burnchain height {}",
+                burn_tip.block_height
+            ))
+        })?;
+
+    let file = fs::File::open(source_path).map_err(|e| {
+        RunLoopError::Chainstate(format!("failed to open {}: {:?}", source_path, &e))
+    })?;
+    let summary = mempool
+        .import_txs(
+            &mut chainstate,
+            &consensus_hash,
+            &block_hash,
+            BufReader::new(file),
+            &stacks_epoch.block_limit,
+            &stacks_epoch.epoch_id,
+        )
+        .map_err(|e| {
+            RunLoopError::Chainstate(format!("failed to import {}: {:?}", source_path, &e))
+        })?;
+
+    info!(
+        "Imported {} mempool transactions from {}; {} were already present and {} were rejected",
+        summary.imported, source_path, summary.already_present, summary.rejected
+    );
+    Ok(summary)
+}
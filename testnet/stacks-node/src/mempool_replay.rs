@@ -0,0 +1,181 @@
+use std::fs;
+use std::io::BufReader;
+
+use stacks::burnchains::Txid;
+use stacks::chainstate::burn::db::sortdb::SortitionDB;
+use stacks::chainstate::stacks::db::StacksChainState;
+use stacks::chainstate::stacks::miner::StacksMicroblockBuilder;
+use stacks::chainstate::stacks::Error as ChainstateError;
+use stacks::core::mempool::MemPoolDB;
+use stacks::core::mempool_journal::read_arrival_journal;
+use stacks::cost_estimates::metrics::UnitMetric;
+use stacks::cost_estimates::UnitEstimator;
+use stacks::types::chainstate::StacksBlockId;
+use stacks::util::secp256k1::Secp256k1PrivateKey;
+use stacks::util_lib::clock::ManualClock;
+
+use crate::ephemeral_dir::EphemeralWorkingDir;
+use crate::run_loop::RunLoopError;
+use crate::{Config, EventDispatcher};
+
+/// What `replay_mempool` did
+pub struct MempoolReplaySummary {
+    pub admitted: u64,
+    /// the transactions the scratch mempool refused, and why
+    pub rejected: Vec<(Txid, String)>,
+    /// the transactions of each microblock assembled from the scratch mempool, in order
+    pub microblocks: Vec<Vec<Txid>>,
+}
+
+/// Submit the transactions journaled at `journal_path`, in the order and at the times they
+/// arrived, to a scratch mempool, checking each against the block `tip`, and then assemble
+/// microblocks off of `tip` from it as the node's miner would, until it has nothing left to mine.
+/// Nothing is broadcast, and the node's own mempool isn't touched, but the node must not be
+/// running, since assembly loads the unconfirmed state off of `tip`.
+pub fn replay_mempool(
+    config: &Config,
+    journal_path: &str,
+    tip: &StacksBlockId,
+) -> Result<MempoolReplaySummary, RunLoopError> {
+    let file = fs::File::open(journal_path).map_err(|e| {
+        RunLoopError::Chainstate(format!("failed to open {}: {:?}", journal_path, &e))
+    })?;
+    let arrivals = read_arrival_journal(BufReader::new(file)).map_err(|e| {
+        RunLoopError::Chainstate(format!("failed to read {}: {:?}", journal_path, &e))
+    })?;
+
+    let chainstate_path = config.get_chainstate_path_str();
+    if fs::metadata(&chainstate_path).is_err() {
+        return Err(RunLoopError::Chainstate(format!(
+            "no chainstate at {}",
+            &chainstate_path
+        )));
+    }
+    let sortdb = SortitionDB::open(&config.get_burn_db_file_path(), false).map_err(|e| {
+        RunLoopError::Chainstate(format!("failed to open sortition database: {:?}", &e))
+    })?;
+    let (mut chainstate, _) = StacksChainState::open(
+        config.is_mainnet(),
+        config.node.chain_id,
+        &chainstate_path,
+        Some(config.node.get_marf_opts()),
+    )
+    .map_err(|e| {
+        RunLoopError::Chainstate(format!("failed to open chainstate database: {:?}", &e))
+    })?;
+
+    let tip_info =
+        StacksChainState::get_stacks_block_header_info_by_index_block_hash(chainstate.db(), tip)
+            .map_err(|e| RunLoopError::Chainstate(format!("failed to load {}: {:?}", tip, &e)))?
+            .ok_or_else(|| RunLoopError::Chainstate(format!("no processed block {}", tip)))?;
+    let consensus_hash = tip_info.consensus_hash.clone();
+    let block_hash = tip_info.anchored_header.block_hash();
+    let stacks_epoch = sortdb
+        .index_conn()
+        .get_stacks_epoch(tip_info.burn_header_height)
+        .ok_or_else(|| {
+            RunLoopError::Chainstate(format!(
+                "no Stacks epoch for burnchain height {}",
+                tip_info.burn_header_height
+            ))
+        })?;
+
+    let scratch_dir = EphemeralWorkingDir::create().map_err(RunLoopError::Chainstate)?;
+    let cost_estimator = config
+        .make_cost_estimator()
+        .unwrap_or_else(|| Box::new(UnitEstimator));
+    let metric = config
+        .make_cost_metric()
+        .unwrap_or_else(|| Box::new(UnitMetric));
+    let mut mempool = MemPoolDB::open(
+        config.is_mainnet(),
+        config.node.chain_id,
+        &scratch_dir.path_str(),
+        cost_estimator,
+        metric,
+    )
+    .map_err(|e| RunLoopError::Chainstate(format!("failed to open scratch mempool: {:?}", &e)))?;
+    mempool.set_admission_limits(config.get_mempool_admission_limits());
+
+    let clock = ManualClock::new(0);
+    let results = mempool
+        .replay_arrivals(
+            &mut chainstate,
+            &consensus_hash,
+            &block_hash,
+            &arrivals,
+            &stacks_epoch.block_limit,
+            &stacks_epoch.epoch_id,
+            &clock,
+        )
+        .map_err(|e| {
+            RunLoopError::Chainstate(format!("failed to replay {}: {:?}", journal_path, &e))
+        })?;
+    let mut summary = MempoolReplaySummary {
+        admitted: 0,
+        rejected: vec![],
+        microblocks: vec![],
+    };
+    for (txid, result) in results.into_iter() {
+        match result {
+            Ok(()) => summary.admitted += 1,
+            Err(rejection) => {
+                let reason = rejection.reason().0.to_string();
+                summary.rejected.push((txid, reason));
+            }
+        }
+    }
+
+    let burn_dbconn = sortdb.index_conn();
+    chainstate
+        .reload_unconfirmed_state(&burn_dbconn, tip.clone())
+        .map_err(|e| {
+            RunLoopError::Chainstate(format!(
+                "failed to load unconfirmed state off of {}: {:?}",
+                tip, &e
+            ))
+        })?;
+    let mut builder = StacksMicroblockBuilder::new(
+        block_hash,
+        consensus_hash,
+        &mut chainstate,
+        &burn_dbconn,
+        config.make_block_builder_settings(0, true),
+    )
+    .map_err(|e| {
+        RunLoopError::Chainstate(format!(
+            "failed to start microblock assembly off of {}: {:?}",
+            tip, &e
+        ))
+    })?;
+    // the key only signs the microblocks, which are thrown away
+    let microblock_key = Secp256k1PrivateKey::new();
+    let event_dispatcher = EventDispatcher::new();
+    loop {
+        match builder.mine_next_microblock(&mut mempool, &microblock_key, &event_dispatcher) {
+            Ok(microblock) => {
+                summary
+                    .microblocks
+                    .push(microblock.txs.iter().map(|tx| tx.txid()).collect());
+            }
+            Err(ChainstateError::NoTransactionsToMine) => break,
+            Err(e) => {
+                return Err(RunLoopError::Chainstate(format!(
+                    "microblock assembly failed: {:?}",
+                    &e
+                )))
+            }
+        }
+    }
+
+    info!(
+        "Replayed {} mempool arrivals from {} off of {}: {} admitted, {} rejected, {} microblocks assembled",
+        arrivals.len(),
+        journal_path,
+        tip,
+        summary.admitted,
+        summary.rejected.len(),
+        summary.microblocks.len()
+    );
+    Ok(summary)
+}
@@ -0,0 +1,199 @@
+use std::sync::{Arc, Mutex};
+
+use stacks::address::AddressHashMode;
+use stacks::burnchains::Txid;
+use stacks::chainstate::stacks::{
+    StacksPrivateKey, StacksPublicKey, StacksTransaction, StacksTransactionSigner, TransactionAuth,
+    TransactionContractCall, TransactionPostConditionMode, TransactionSpendingCondition,
+    TransactionVersion,
+};
+use stacks::codec::StacksMessageCodec;
+use stacks::net::rpc::MiningKeyRotator;
+use stacks::net::MiningKeyRotationResponse;
+use stacks::types::chainstate::StacksAddress;
+use stacks::vm::types::PrincipalData;
+use stacks::vm::ClarityName;
+use stacks::vm::Value as ClarityValue;
+
+use crate::burnchains::commitment::{l1_get_nonce, DEFAULT_MINER_COMMITMENT_FEE};
+use crate::config::{BurnchainConfig, CommitStrategy};
+use crate::Config;
+
+/// The key the miner signs with, and the key it will switch to once a scheduled rotation
+/// reaches its switch height.  Shared between the p2p thread, which schedules rotations, and
+/// the relayer, which carries them out.
+pub struct MiningKeySchedule {
+    current: StacksPrivateKey,
+    next: Option<(StacksPrivateKey, u64)>,
+}
+
+impl MiningKeySchedule {
+    pub fn new(current: StacksPrivateKey) -> MiningKeySchedule {
+        MiningKeySchedule {
+            current,
+            next: None,
+        }
+    }
+
+    /// If a rotation is scheduled at or below `burn_height`, make the next key current and
+    /// return it.
+    pub fn take_due(&mut self, burn_height: u64) -> Option<StacksPrivateKey> {
+        match self.next {
+            Some((_, switch_height)) if switch_height <= burn_height => {
+                let (next_key, _) = self.next.take().expect("FATAL: rotation vanished");
+                self.current = next_key.clone();
+                Some(next_key)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Handles the admin request to rotate the mining key.  The next key and the switch height are
+/// read from the config file, the rotation is registered with the L1 subnet contract using the
+/// current key, and the relayer switches keys once the L1 reaches the switch height.  Both keys
+/// are accepted by the contract for a few blocks around the switch, so no commits are lost.
+pub struct NodeMiningKeyRotator {
+    config_path: String,
+    burnchain: BurnchainConfig,
+    schedule: Arc<Mutex<MiningKeySchedule>>,
+}
+
+impl NodeMiningKeyRotator {
+    /// Returns `None` if the node's config doesn't support key rotation: it must have been
+    /// loaded from a file, have a `[node] mining_key`, and commit directly to the subnet
+    /// contract.
+    pub fn new(
+        config: &Config,
+        config_path: Option<String>,
+        schedule: Option<Arc<Mutex<MiningKeySchedule>>>,
+    ) -> Option<NodeMiningKeyRotator> {
+        if !matches!(config.burnchain.commit_strategy, CommitStrategy::Direct) {
+            return None;
+        }
+        Some(NodeMiningKeyRotator {
+            config_path: config_path?,
+            burnchain: config.burnchain.clone(),
+            schedule: schedule?,
+        })
+    }
+
+    fn l1_address(&self, key: &StacksPrivateKey) -> StacksAddress {
+        let hash_mode = AddressHashMode::SerializeP2PKH;
+        let version = if self.burnchain.is_mainnet() {
+            hash_mode.to_version_mainnet()
+        } else {
+            hash_mode.to_version_testnet()
+        };
+        StacksAddress::from_public_keys(
+            version,
+            &hash_mode,
+            1,
+            &vec![StacksPublicKey::from_private(key)],
+        )
+        .expect("Failed to make Stacks address from public key")
+    }
+
+    /// Build the L1 `rotate-miner` contract call, signed with `current_key`.
+    fn make_rotate_tx(
+        &self,
+        current_key: &StacksPrivateKey,
+        nonce: u64,
+        next_miner: &StacksAddress,
+        switch_height: u64,
+    ) -> StacksTransaction {
+        let version = if self.burnchain.is_mainnet() {
+            TransactionVersion::Mainnet
+        } else {
+            TransactionVersion::Testnet
+        };
+        let payload = TransactionContractCall {
+            address: self.burnchain.contract_identifier.issuer.clone().into(),
+            contract_name: self.burnchain.contract_identifier.name.clone(),
+            function_name: ClarityName::from("rotate-miner"),
+            function_args: vec![
+                ClarityValue::Principal(PrincipalData::from(next_miner.clone())),
+                ClarityValue::UInt(switch_height.into()),
+            ],
+        };
+
+        let mut spending_condition = TransactionSpendingCondition::new_singlesig_p2pkh(
+            StacksPublicKey::from_private(current_key),
+        )
+        .expect("Failed to create p2pkh spending condition from public key.");
+        spending_condition.set_nonce(nonce);
+        spending_condition.set_tx_fee(DEFAULT_MINER_COMMITMENT_FEE);
+        let auth = TransactionAuth::Standard(spending_condition);
+
+        let mut unsigned_tx = StacksTransaction::new(version, auth, payload.into());
+        unsigned_tx.anchor_mode = self.burnchain.anchor_mode.clone();
+        unsigned_tx.post_condition_mode = TransactionPostConditionMode::Allow;
+        unsigned_tx.chain_id = self.burnchain.chain_id;
+
+        let mut tx_signer = StacksTransactionSigner::new(&unsigned_tx);
+        tx_signer.sign_origin(current_key).unwrap();
+        tx_signer
+            .get_tx()
+            .expect("Failed to get signed transaction from signer")
+    }
+
+    fn post_l1_tx(&self, tx: &StacksTransaction) -> Result<Txid, String> {
+        let url = format!("{}/v2/transactions", self.burnchain.get_rpc_url());
+        let res = reqwest::blocking::Client::new()
+            .post(url)
+            .header("Content-Type", "application/octet-stream")
+            .body(tx.serialize_to_vec())
+            .send()
+            .map_err(|e| format!("failed to send rotation to the L1: {}", e))?;
+        if res.status().is_success() {
+            let txid: String = res.json().map_err(|e| e.to_string())?;
+            Txid::from_hex(&txid).map_err(|e| e.to_string())
+        } else {
+            Err(format!(
+                "the L1 rejected the rotation: {}",
+                res.text().unwrap_or_default()
+            ))
+        }
+    }
+}
+
+impl MiningKeyRotator for NodeMiningKeyRotator {
+    fn rotate_mining_key(&self) -> Result<MiningKeyRotationResponse, String> {
+        let mut schedule = self
+            .schedule
+            .lock()
+            .map_err(|_| "mining key schedule is poisoned".to_string())?;
+        if let Some((_, switch_height)) = schedule.next {
+            return Err(format!(
+                "a key rotation is already scheduled at L1 height {}",
+                switch_height
+            ));
+        }
+
+        let (next_key, switch_height) = Config::next_mining_key_from_path(&self.config_path)?;
+        let current_miner = self.l1_address(&schedule.current);
+        let next_miner = self.l1_address(&next_key);
+        if current_miner == next_miner {
+            return Err("[node] next_mining_key is the current mining key".to_string());
+        }
+
+        let nonce = l1_get_nonce(&self.burnchain.get_rpc_url(), &current_miner)
+            .map_err(|e| e.to_string())?;
+        let tx = self.make_rotate_tx(&schedule.current, nonce, &next_miner, switch_height);
+        let txid = self.post_l1_tx(&tx)?;
+
+        info!("Scheduled mining key rotation";
+              "current_miner" => %current_miner,
+              "next_miner" => %next_miner,
+              "switch_burn_height" => switch_height,
+              "txid" => %txid);
+        schedule.next = Some((next_key, switch_height));
+
+        Ok(MiningKeyRotationResponse {
+            current_miner: current_miner.to_string(),
+            next_miner: next_miner.to_string(),
+            switch_burn_height: switch_height,
+            txid: txid.to_hex(),
+        })
+    }
+}
@@ -9,12 +9,18 @@ use std::sync::{atomic::Ordering, Arc, Mutex};
 use std::time::Duration;
 use std::{thread, thread::JoinHandle};
 
+use crate::burnchains::claim_oracle::L1ClaimOracle;
+use crate::burnchains::commit_fees::CommitFeeDB;
+use crate::burnchains::commit_signer::make_commit_signer;
 use crate::burnchains::BurnchainController;
 use stacks::burnchains::BurnchainParameters;
 use stacks::chainstate::burn::db::sortdb::SortitionDB;
+use stacks::chainstate::burn::operations::upgrade_signal::version_meets;
 use stacks::chainstate::burn::BlockSnapshot;
 use stacks::chainstate::burn::ConsensusHash;
 use stacks::chainstate::coordinator::comm::CoordinatorChannels;
+use stacks::chainstate::stacks::db::event_replay::EventReplayFormatter;
+use stacks::chainstate::stacks::db::rewards_report::CommitFeeLedger;
 use stacks::chainstate::stacks::db::unconfirmed::UnconfirmedTxMap;
 use stacks::chainstate::stacks::db::{StacksChainState, MINER_REWARD_MATURITY};
 use stacks::chainstate::stacks::miner::{AssembledBlockInfo, Proposal};
@@ -29,7 +35,7 @@ use stacks::chainstate::stacks::{
     TransactionAnchorMode, TransactionPayload, TransactionVersion,
 };
 use stacks::codec::StacksMessageCodec;
-use stacks::core::mempool::MemPoolDB;
+use stacks::core::mempool::{MemPoolDB, MemPoolEventDispatcher};
 use stacks::core::FIRST_BURNCHAIN_CONSENSUS_HASH;
 use stacks::cost_estimates::metrics::UnitMetric;
 use stacks::cost_estimates::UnitEstimator;
@@ -40,8 +46,8 @@ use stacks::net::{
     dns::DNSResolver,
     p2p::PeerNetwork,
     relay::Relayer,
-    rpc::RPCHandlerArgs,
-    Error as NetError, NetworkResult, PeerAddress, ServiceFlags,
+    rpc::{L1WithdrawalOracle, MiningKeyRotator, RPCHandlerArgs},
+    BlocksDatum, Error as NetError, NetworkResult, PeerAddress, ServiceFlags,
 };
 use stacks::types::chainstate::{BlockHeaderHash, BurnchainHeaderHash, SortitionId, StacksAddress};
 use stacks::util::get_epoch_time_ms;
@@ -49,13 +55,18 @@ use stacks::util::get_epoch_time_secs;
 use stacks::util::hash::{to_hex, Hash160, Sha256Sum};
 use stacks::util::secp256k1::Secp256k1PrivateKey;
 use stacks::util::vrf::VRFProof;
+use stacks::util_lib::component_log;
 use stacks::util_lib::strings::{UrlString, VecDisplay};
 use stacks::vm::costs::ExecutionCost;
 use stacks::{burnchains::BurnchainSigner, chainstate::stacks::db::StacksHeaderInfo};
 
+use crate::event_dispatcher::EventReplayer;
+use crate::mining_key_rotation::NodeMiningKeyRotator;
 use crate::node::ChainTip;
 use crate::run_loop::neon::Counters;
 use crate::run_loop::neon::RunLoop;
+use crate::scheduled_calls::ScheduledCalls;
+use crate::tx_forwarder::MinerTxForwarder;
 
 use super::{BurnchainTip, Config, EventDispatcher, Keychain};
 use stacks::monitoring;
@@ -125,10 +136,14 @@ fn fault_injection_long_tenure() {
 #[cfg(not(test))]
 fn fault_injection_long_tenure() {}
 
-enum Error {
+/// Why `mine_one_block` did not produce a block
+#[derive(Debug, PartialEq)]
+pub enum Error {
     HeaderNotFoundForChainTip,
     SnapshotNotFoundForChainTip,
     BurnchainTipChanged,
+    /// the block builder failed to assemble the block
+    BlockAssemblyFailed,
 }
 
 struct MiningTenureInformation {
@@ -140,6 +155,41 @@ struct MiningTenureInformation {
     coinbase_nonce: u64,
 }
 
+/// A block assembled by `mine_one_block`, with what its block-commit needs
+pub struct MinedBlockResult {
+    /// the consensus hash of the sortition that selected the block's parent
+    pub parent_consensus_hash: ConsensusHash,
+    pub parent_block_hash: BlockHeaderHash,
+    /// the total amount burned in the sortition that selected the block's parent
+    pub parent_block_total_burn: u64,
+    pub anchored_block: StacksBlock,
+    /// the parent's microblocks which the block confirms
+    pub microblocks_confirmed: Vec<StacksMicroblock>,
+    /// the key which signs the microblocks of this block's tenure, and its hash
+    pub microblock_secret_key: Secp256k1PrivateKey,
+    pub microblock_pubkey_hash: Hash160,
+    /// the burnchain tip the block was assembled at
+    pub burn_tip: BurnchainHeaderHash,
+    pub burn_tip_height: u32,
+}
+
+impl MinedBlockResult {
+    /// The proposal to send to the other participants of a multi-party miner
+    pub fn make_proposal(&self, is_mainnet: bool) -> Proposal {
+        Proposal {
+            parent_block_hash: self.parent_block_hash.clone(),
+            parent_consensus_hash: self.parent_consensus_hash.clone(),
+            block: self.anchored_block.clone(),
+            microblocks_confirmed: self.microblocks_confirmed.clone(),
+            burn_tip: self.burn_tip.clone(),
+            burn_tip_height: self.burn_tip_height,
+            total_burn: self.parent_block_total_burn,
+            is_mainnet,
+            microblock_pubkey_hash: self.microblock_pubkey_hash.clone(),
+        }
+    }
+}
+
 /// Process artifacts from the tenure.
 /// At this point, we're modifying the chainstate, and merging the artifacts from the previous tenure.
 fn inner_process_tenure(
@@ -210,6 +260,182 @@ fn inner_generate_coinbase_tx(
     tx_signer.get_tx().unwrap()
 }
 
+/// Assemble the next anchored block off of the canonical subnet chain tip (or the genesis block,
+/// if there is none yet) at the burnchain tip `burn_block`, on the calling thread.  This is the
+/// whole of a tenure's block assembly, minus the block-commit, so tests and tools can mine
+/// blocks one at a time and make assertions about each.
+///
+/// Anchored subnet blocks hold only the coinbase and confirm their parent's microblocks, so no
+/// mempool is needed: transactions are mined into microblocks by `mine_one_microblock`.
+/// The microblock keypair of `keychain` is rotated for the new tenure.
+pub fn mine_one_block(
+    config: &Config,
+    chain_state: &mut StacksChainState,
+    burn_db: &SortitionDB,
+    burn_block: &BlockSnapshot,
+    keychain: &mut Keychain,
+    event_dispatcher: Option<&dyn MemPoolEventDispatcher>,
+) -> Result<MinedBlockResult, Error> {
+    let MiningTenureInformation {
+        mut stacks_parent_header,
+        parent_consensus_hash,
+        parent_block_total_burn,
+        coinbase_nonce,
+        ..
+    } = if let Some(stacks_tip) = chain_state
+        .get_stacks_chain_tip(burn_db)
+        .expect("FATAL: could not query chain tip")
+    {
+        let miner_address = keychain.origin_address(config.is_mainnet()).unwrap();
+        StacksNode::get_mining_tenure_information(
+            chain_state,
+            burn_db,
+            burn_block,
+            miner_address,
+            &stacks_tip.consensus_hash,
+            &stacks_tip.anchored_block_hash,
+        )?
+    } else {
+        component_info!(
+            Miner,
+            "No subnet chain tip known, will return a genesis block"
+        );
+        let burnchain_params =
+            // TODO(subnets): set burnchain parameters with subnet configuration
+            BurnchainParameters::from_params(&config.burnchain.chain, "mainnet")
+                .expect("Bitcoin network unsupported");
+
+        let chain_tip = ChainTip::genesis(
+            &burnchain_params.first_block_hash,
+            burnchain_params.first_block_height.into(),
+            burnchain_params.first_block_timestamp.into(),
+        );
+
+        MiningTenureInformation {
+            stacks_parent_header: chain_tip.metadata,
+            parent_consensus_hash: FIRST_BURNCHAIN_CONSENSUS_HASH.clone(),
+            parent_block_total_burn: 0,
+            coinbase_nonce: 0,
+        }
+    };
+
+    component_debug!(
+        Miner,
+        "Relayer will try to mine off of {}/{}",
+        &parent_consensus_hash,
+        &stacks_parent_header.burn_header_hash
+    );
+
+    // Generates a new secret key for signing the trail of microblocks
+    // of the upcoming tenure.
+    let microblock_secret_key = keychain.rotate_microblock_keypair(burn_block.block_height);
+    let mblock_pubkey_hash =
+        Hash160::from_node_public_key(&StacksPublicKey::from_private(&microblock_secret_key));
+
+    let coinbase_tx = inner_generate_coinbase_tx(
+        keychain,
+        coinbase_nonce,
+        config.is_mainnet(),
+        config.node.chain_id,
+    );
+
+    // find the longest microblock tail we can build off of
+    let microblock_info_opt =
+        match StacksChainState::load_descendant_staging_microblock_stream_with_poison(
+            chain_state.db(),
+            &StacksBlockHeader::make_index_block_hash(
+                &parent_consensus_hash,
+                &stacks_parent_header.anchored_header.block_hash(),
+            ),
+            0,
+            u16::MAX,
+        ) {
+            Ok(x) => {
+                let num_mblocks = x.as_ref().map(|(mblocks, ..)| mblocks.len()).unwrap_or(0);
+                component_debug!(
+                    Miner,
+                    "Loaded {} microblocks descending from {}/{}",
+                    num_mblocks,
+                    &parent_consensus_hash,
+                    &stacks_parent_header.anchored_header.block_hash()
+                );
+                x
+            }
+            Err(e) => {
+                component_warn!(
+                    Miner,
+                    "Failed to load descendant microblock stream from {}/{}: {:?}",
+                    &parent_consensus_hash,
+                    &stacks_parent_header.anchored_header.block_hash(),
+                    &e
+                );
+                None
+            }
+        };
+
+    if let Some((ref microblocks, _)) = &microblock_info_opt {
+        if let Some(ref tail) = microblocks.last() {
+            component_debug!(
+                Miner,
+                "Confirm microblock stream tailed at {} (seq {})",
+                &tail.block_hash(),
+                tail.header.sequence
+            );
+        }
+
+        // try and confirm as many microblocks as we can (but note that the stream itself may
+        // be too long; we'll try again if that happens).
+        stacks_parent_header.microblock_tail =
+            microblocks.last().clone().map(|blk| blk.header.clone());
+    }
+
+    let built_info = match StacksBlockBuilder::build_empty_anchored_block(
+        chain_state,
+        &burn_db.index_conn(),
+        &stacks_parent_header,
+        parent_block_total_burn,
+        VRFProof::empty(),
+        mblock_pubkey_hash,
+        &coinbase_tx,
+        event_dispatcher,
+    ) {
+        Ok(block) => block,
+        Err(e) => {
+            component_error!(Miner, "Failure mining anchored block: {}", e);
+            return Err(Error::BlockAssemblyFailed);
+        }
+    };
+
+    let AssembledBlockInfo {
+        block: anchored_block,
+        mblocks_confirmed,
+        burn_tip,
+        burn_tip_height,
+        ..
+    } = built_info;
+
+    let block_height = anchored_block.header.total_work.work;
+    component_debug!(
+        Miner,
+        "Assembled subnet block";
+        "height" => block_height,
+        "tx_count" => anchored_block.txs.len(),
+        "block_hash" => %anchored_block.block_hash(),
+    );
+
+    Ok(MinedBlockResult {
+        parent_block_hash: stacks_parent_header.anchored_header.block_hash(),
+        parent_consensus_hash,
+        parent_block_total_burn,
+        anchored_block,
+        microblocks_confirmed: mblocks_confirmed,
+        microblock_secret_key,
+        microblock_pubkey_hash: mblock_pubkey_hash,
+        burn_tip,
+        burn_tip_height,
+    })
+}
+
 /// Mine and broadcast a single microblock using the provided function.
 fn mine_microblock_with_fn<F>(
     microblock_state: &mut MicroblockMinerState,
@@ -223,7 +449,8 @@ where
         &Secp256k1PrivateKey,
     ) -> Result<StacksMicroblock, ChainstateError>,
 {
-    debug!(
+    component_debug!(
+        Miner,
         "Try to mine one microblock off of {}/{} (total: {})",
         &microblock_state.parent_consensus_hash,
         &microblock_state.parent_block_hash,
@@ -250,7 +477,7 @@ where
                     &microblock_state.parent_block_hash,
                     &e
                 );
-                error!("{}", msg);
+                component_error!(Miner, "{}", msg);
                 return Err(e);
             }
         };
@@ -261,7 +488,8 @@ where
         let new_cost_so_far = microblock_miner.get_cost_so_far().expect("BUG: cannot read cost so far from miner -- indicates that the underlying Clarity Tx is somehow in use still.");
         let t2 = get_epoch_time_ms();
 
-        info!(
+        component_info!(
+            Miner,
             "Mined microblock {} ({}) with {} transactions in {}ms",
             mblock.block_hash(),
             mblock.header.sequence,
@@ -275,7 +503,7 @@ where
     let (mined_microblock, new_cost) = match mint_result {
         Ok(x) => x,
         Err(e) => {
-            warn!("Failed to mine microblock: {}", e);
+            component_warn!(Miner, "Failed to mine microblock: {}", e);
             return Err(e);
         }
     };
@@ -323,7 +551,8 @@ fn mine_coalesced_microblock(
         let txs_and_lens: Vec<(StacksTransaction, u64)> = prev_microblocks
             .iter()
             .flat_map(|mb| {
-                info!(
+                component_info!(
+                    Miner,
                     "Migrating orphaned microblock to next block: {}",
                     mb.block_hash()
                 );
@@ -342,6 +571,42 @@ fn mine_coalesced_microblock(
     mine_microblock_with_fn(microblock_state, sortdb, chainstate, mine_fn)
 }
 
+/// Should the miner stop producing blocks built at burn height `burn_height`, in the fork of
+/// `sortition_id`, because `[node] halt_for_upgrades` is set and an upgrade signal from the L1
+/// contract requires a newer version than this node's by then?
+fn halted_for_upgrade(
+    config: &Config,
+    sortdb: &SortitionDB,
+    sortition_id: &SortitionId,
+    burn_height: u64,
+) -> bool {
+    if !config.node.halt_for_upgrades {
+        return false;
+    }
+    let (target_height, required_version) =
+        match SortitionDB::get_upgrade_signal_at(&sortdb.index_conn(), sortition_id, burn_height) {
+            Ok(Some(upgrade_signal)) => upgrade_signal,
+            Ok(None) => return false,
+            Err(e) => {
+                component_warn!(Miner, "Failed to load upgrade signals: {:?}", &e);
+                return false;
+            }
+        };
+    let node_version = stacks::subnet_node_version();
+    if version_meets(node_version, &required_version).unwrap_or(false) {
+        return false;
+    }
+    component_warn!(
+        Miner,
+        "Not mining: the L1 contract requires a newer subnet node version";
+        "burn_height" => burn_height,
+        "target_height" => target_height,
+        "required_version" => %required_version,
+        "node_version" => node_version,
+    );
+    true
+}
+
 fn try_mine_microblock(
     config: &Config,
     microblock_miner_state: &mut Option<MicroblockMinerState>,
@@ -355,11 +620,23 @@ fn try_mine_microblock(
     let bhh = winning_tip.1;
     let microblock_privkey = winning_tip.2;
 
+    let burn_tip = SortitionDB::get_canonical_burn_chain_tip(sortdb.conn())?;
+    if halted_for_upgrade(
+        config,
+        sortdb,
+        &burn_tip.sortition_id,
+        burn_tip.block_height,
+    ) {
+        return Ok(None);
+    }
+
     let mut next_microblock = None;
     if microblock_miner_state.is_none() {
-        debug!(
+        component_debug!(
+            Miner,
             "Instantiate microblock mining state off of {}/{}",
-            &ch, &bhh
+            &ch,
+            &bhh
         );
         // we won a block! proceed to build a microblock tail if we've stored it
         match StacksChainState::get_anchored_block_header_info(chainstate.db(), &ch, &bhh) {
@@ -383,15 +660,20 @@ fn try_mine_microblock(
                 });
             }
             Ok(None) => {
-                warn!(
+                component_warn!(
+                    Miner,
                     "No such anchored block: {}/{}.  Cannot mine microblocks",
-                    ch, bhh
+                    ch,
+                    bhh
                 );
             }
             Err(e) => {
-                warn!(
+                component_warn!(
+                    Miner,
                     "Failed to get anchored block cost for {}/{}: {:?}",
-                    ch, bhh, &e
+                    ch,
+                    bhh,
+                    &e
                 );
             }
         }
@@ -401,14 +683,14 @@ fn try_mine_microblock(
         if microblock_miner.parent_consensus_hash == ch && microblock_miner.parent_block_hash == bhh
         {
             if microblock_miner.last_mined + (microblock_miner.frequency as u128)
-                < get_epoch_time_ms()
+                < config.clock.now_ms()
             {
                 // opportunistically try and mine, but only if there are no attachable blocks in
                 // recent history (i.e. in the last 10 minutes)
                 let num_attachable = StacksChainState::count_attachable_staging_blocks(
                     chainstate.db(),
                     1,
-                    get_epoch_time_secs() - 600,
+                    config.clock.now_secs().saturating_sub(600),
                 )?;
                 if num_attachable == 0 {
                     match mine_one_microblock(
@@ -423,17 +705,17 @@ fn try_mine_microblock(
                             next_microblock = Some(microblock);
                         }
                         Err(ChainstateError::NoTransactionsToMine) => {
-                            debug!("Will keep polling mempool for transactions to include in a microblock");
+                            component_debug!(Miner, "Will keep polling mempool for transactions to include in a microblock");
                         }
                         Err(e) => {
-                            warn!("Failed to mine one microblock: {:?}", &e);
+                            component_warn!(Miner, "Failed to mine one microblock: {:?}", &e);
                         }
                     }
                 } else {
-                    debug!("Will not mine microblocks yet -- have {} attachable blocks that arrived in the last 10 minutes", num_attachable);
+                    component_debug!(Miner, "Will not mine microblocks yet -- have {} attachable blocks that arrived in the last 10 minutes", num_attachable);
                 }
             }
-            microblock_miner.last_mined = get_epoch_time_ms();
+            microblock_miner.last_mined = config.clock.now_ms();
             microblock_miner_state.replace(microblock_miner);
         }
         // otherwise, we're not the sortition winner, and the microblock miner state can be
@@ -459,9 +741,11 @@ fn run_microblock_tenure(
     let parent_consensus_hash = &miner_tip.0;
     let parent_block_hash = &miner_tip.1;
 
-    debug!(
+    component_debug!(
+        Miner,
         "Run microblock tenure for {}/{}",
-        parent_consensus_hash, parent_block_hash,
+        parent_consensus_hash,
+        parent_block_hash,
     );
 
     // Mine microblocks, if we're active
@@ -476,7 +760,7 @@ fn run_microblock_tenure(
     ) {
         Ok(x) => x,
         Err(e) => {
-            warn!("Failed to mine next microblock: {:?}", &e);
+            component_warn!(Miner, "Failed to mine next microblock: {:?}", &e);
             None
         }
     };
@@ -492,9 +776,12 @@ fn run_microblock_tenure(
             .as_ref()
             .map(|ref unconfirmed| unconfirmed.num_microblocks())
             .unwrap_or(0);
-        debug!(
+        component_debug!(
+            Miner,
             "Mined one microblock: {} seq {} (total processed: {})",
-            &microblock_hash, next_microblock.header.sequence, num_mblocks
+            &microblock_hash,
+            next_microblock.header.sequence,
+            num_mblocks
         );
         counters.set_microblocks_processed(num_mblocks);
 
@@ -509,15 +796,99 @@ fn run_microblock_tenure(
             parent_block_hash,
             next_microblock.clone(),
         ) {
-            error!(
+            component_error!(
+                Miner,
                 "Failure trying to broadcast microblock {}: {}",
-                microblock_hash, e
+                microblock_hash,
+                e
             );
         }
     }
     next_microblock_opt
 }
 
+/// Drop the microblocks which peers and clients streamed to us.  No L1 commit vouches for them
+/// until an anchored block confirms them, at which point we download the stream instead.
+fn drop_unconfirmed_microblocks(net_result: &mut NetworkResult) {
+    let num_pushed: usize = net_result
+        .pushed_microblocks
+        .values()
+        .map(|mblocks_datas| mblocks_datas.len())
+        .sum();
+    let num_uploaded = net_result.uploaded_microblocks.len();
+    if num_pushed + num_uploaded > 0 {
+        component_debug!(
+            Relayer,
+            "Relayer: follower node drops {} pushed and {} uploaded unconfirmed microblock messages",
+            num_pushed,
+            num_uploaded
+        );
+    }
+    net_result.pushed_microblocks.clear();
+    net_result.uploaded_microblocks.clear();
+}
+
+/// Is `block` the block which the L1 block-commit of the sortition `consensus_hash` committed to?
+/// A block whose sortition we have not processed yet is not (yet) committed.
+fn is_committed_block(
+    sortdb: &SortitionDB,
+    consensus_hash: &ConsensusHash,
+    block: &StacksBlock,
+) -> bool {
+    match SortitionDB::get_block_snapshot_consensus(sortdb.conn(), consensus_hash) {
+        Ok(Some(sn)) => {
+            sn.pox_valid && sn.sortition && sn.winning_stacks_block_hash == block.block_hash()
+        }
+        Ok(None) => false,
+        Err(e) => {
+            component_warn!(
+                Relayer,
+                "Relayer: failed to load sortition {}: {:?}",
+                consensus_hash,
+                &e
+            );
+            false
+        }
+    }
+}
+
+/// Drop the anchored blocks which peers and clients sent us, but which no L1 block-commit
+/// vouches for, so that a follower node neither accepts nor relays them.  Blocks whose sortition
+/// we have not processed yet are downloaded again once it is.
+fn drop_uncommitted_blocks(sortdb: &SortitionDB, net_result: &mut NetworkResult) {
+    let mut num_dropped = 0;
+    net_result.blocks.retain(|(consensus_hash, block, _)| {
+        let committed = is_committed_block(sortdb, consensus_hash, block);
+        if !committed {
+            num_dropped += 1;
+        }
+        committed
+    });
+    for blocks_data in net_result
+        .pushed_blocks
+        .values_mut()
+        .flatten()
+        .chain(net_result.uploaded_blocks.iter_mut())
+    {
+        blocks_data
+            .blocks
+            .retain(|BlocksDatum(consensus_hash, block)| {
+                let committed = is_committed_block(sortdb, consensus_hash, block);
+                if !committed {
+                    num_dropped += 1;
+                }
+                committed
+            });
+    }
+    if num_dropped > 0 {
+        component_debug!(
+            Relayer,
+            "Relayer: follower node drops {} anchored blocks which no L1 block-commit vouches for",
+            num_dropped
+        );
+    }
+}
+
 /// Grant the p2p thread a copy of the unconfirmed microblock transaction list, so it can serve it
 /// out via the unconfirmed transaction API.
 /// Not the prettiest way to do this, but the least disruptive way to do this.
@@ -574,6 +945,14 @@ fn spawn_peer(
     let mut sync_comms = runloop.get_pox_sync_comms();
     let event_dispatcher = runloop.get_event_dispatcher();
     let should_keep_running = runloop.get_termination_switch();
+    let miner_paused = runloop.get_miner_pause_switch();
+    let is_miner = runloop.is_miner();
+    let mining_key_rotator = NodeMiningKeyRotator::new(
+        runloop.config(),
+        runloop.get_config_path(),
+        runloop.get_mining_key_schedule(),
+    );
+    let l1_oracle = L1ClaimOracle::new(&runloop.config().burnchain);
 
     let is_mainnet = config.is_mainnet();
     let burn_db_path = config.get_burn_db_file_path();
@@ -591,6 +970,9 @@ fn spawn_peer(
         Some(config.node.get_marf_opts()),
     )
     .map_err(|e| NetError::ChainstateError(e.to_string()))?;
+    chainstate.set_contract_publish_policy(config.node.get_contract_publish_policy());
+    chainstate.set_account_index(config.node.account_index);
+    chainstate.set_block_compression(config.node.block_compression);
 
     // buffer up blocks to store without stalling the p2p thread
     let mut results_with_data = VecDeque::new();
@@ -615,6 +997,10 @@ fn spawn_peer(
                 metric,
             )
             .expect("Database failure opening mempool");
+            mem_pool.set_admission_limits(config.get_mempool_admission_limits());
+            mem_pool.set_rejected_txs_retention(config.mempool.rejected_txs_retention.clone());
+            mem_pool.set_arrival_journal(config.mempool.open_arrival_journal());
+            mem_pool.set_clock(config.clock.clone());
 
             let cost_estimator = config
                 .make_cost_estimator()
@@ -623,6 +1009,15 @@ fn spawn_peer(
                 .make_cost_metric()
                 .unwrap_or_else(|| Box::new(UnitMetric));
 
+            // the fees of the miner's commits, for rewards reports
+            let commit_fees = if is_miner && config.burnchain.spawn_l1_observer() {
+                CommitFeeDB::open(&config.get_commit_fees_db_path())
+                    .map_err(|e| warn!("Failed to open the commit fee DB: {:?}", &e))
+                    .ok()
+            } else {
+                None
+            };
+
             let handler_args = RPCHandlerArgs {
                 exit_at_block_height: exit_at_block_height.as_ref(),
                 genesis_chainstate_hash: Sha256Sum::from_hex(stx_genesis::GENESIS_CHAINSTATE_HASH)
@@ -631,6 +1026,27 @@ fn spawn_peer(
                 cost_estimator: Some(cost_estimator.as_ref()),
                 cost_metric: Some(metric.as_ref()),
                 fee_estimator: fee_estimator.as_ref().map(|x| x.as_ref()),
+                miner_paused: if is_miner {
+                    Some(miner_paused.as_ref())
+                } else {
+                    None
+                },
+                mining_key_rotator: match mining_key_rotator {
+                    Some(ref rotator) if is_miner => Some(rotator as &dyn MiningKeyRotator),
+                    _ => None,
+                },
+                mining_key_loaded: if is_miner {
+                    Some(config.node.mining_key.is_some())
+                } else {
+                    None
+                },
+                l1_oracle: if config.burnchain.spawn_l1_observer() {
+                    Some(&l1_oracle as &dyn L1WithdrawalOracle)
+                } else {
+                    None
+                },
+                commit_fees: commit_fees.as_ref().map(|db| db as &dyn CommitFeeLedger),
+                event_replay: Some(&EventReplayer as &dyn EventReplayFormatter),
                 ..RPCHandlerArgs::default()
             };
 
@@ -706,14 +1122,14 @@ fn spawn_peer(
 
                         // only do this on the Ok() path, even if we're mining, because an error in
                         // network dispatching is likely due to resource exhaustion
-                        if mblock_deadline < get_epoch_time_ms() {
+                        if mblock_deadline < config.clock.now_ms() {
                             info!("P2P: schedule microblock tenure");
                             results_with_data.push_back(RelayerDirective::RunMicroblockTenure(
                                 this.burnchain_tip.clone(),
-                                get_epoch_time_ms(),
+                                config.clock.now_ms(),
                             ));
                             mblock_deadline =
-                                get_epoch_time_ms() + (config.node.microblock_frequency as u128);
+                                config.clock.now_ms() + (config.node.microblock_frequency as u128);
                         }
                     }
                     Err(e) => {
@@ -813,6 +1229,8 @@ fn spawn_miner_relayer(
     let event_dispatcher = runloop.get_event_dispatcher();
     let counters = runloop.get_counters();
     let sync_comms = runloop.get_pox_sync_comms();
+    let miner_paused = runloop.get_miner_pause_switch();
+    let mining_key_schedule = runloop.get_mining_key_schedule();
 
     let is_mainnet = config.is_mainnet();
     let chain_id = config.node.chain_id;
@@ -834,6 +1252,8 @@ fn spawn_miner_relayer(
         Some(config.node.get_marf_opts()),
     )
     .map_err(|e| NetError::ChainstateError(e.to_string()))?;
+    chainstate.set_contract_publish_policy(config.node.get_contract_publish_policy());
+    chainstate.set_block_compression(config.node.block_compression);
 
     let mut last_mined_blocks: HashMap<
         BlockHeaderHash,
@@ -855,11 +1275,21 @@ fn spawn_miner_relayer(
 
         let mut mem_pool = MemPoolDB::open(is_mainnet, chain_id, &stacks_chainstate_path, cost_estimator, metric)
             .expect("Database failure opening mempool");
+        mem_pool.set_admission_limits(config.get_mempool_admission_limits());
+        mem_pool.set_rejected_txs_retention(config.mempool.rejected_txs_retention.clone());
+        mem_pool.set_arrival_journal(config.mempool.open_arrival_journal());
+        mem_pool.set_clock(config.clock.clone());
+        let tx_forwarder = MinerTxForwarder::spawn(&config.node.forward_txs_to);
+        let mut scheduled_calls = ScheduledCalls::new(&config);
 
         while let Ok(mut directive) = relay_channel.recv() {
             match directive {
                 RelayerDirective::HandleNetResult(ref mut net_result) => {
-                    debug!("Relayer: Handle network result");
+                    component_debug!(Relayer, "Relayer: Handle network result");
+                    if config.node.follower {
+                        drop_unconfirmed_microblocks(net_result);
+                        drop_uncommitted_blocks(&sortdb, net_result);
+                    }
                     let net_receipts = relayer
                         .process_network_result(
                             &local_peer,
@@ -875,6 +1305,9 @@ fn spawn_miner_relayer(
 
                     let mempool_txs_added = net_receipts.mempool_txs_added.len();
                     if mempool_txs_added > 0 {
+                        if let Some(tx_forwarder) = tx_forwarder.as_ref() {
+                            tx_forwarder.forward(&mem_pool, &net_receipts.mempool_txs_added);
+                        }
                         event_dispatcher.process_new_mempool_txs(net_receipts.mempool_txs_added);
                     }
 
@@ -884,7 +1317,7 @@ fn spawn_miner_relayer(
                             let canonical_tip = unconfirmed_state.confirmed_chain_tip.clone();
                             event_dispatcher.process_new_microblocks(canonical_tip, net_receipts.processed_unconfirmed_state);
                         } else {
-                            warn!("Relayer: oops, unconfirmed state is uninitialized but there are microblock events");
+                            component_warn!(Relayer, "Relayer: oops, unconfirmed state is uninitialized but there are microblock events");
                         }
                     }
 
@@ -897,7 +1330,8 @@ fn spawn_miner_relayer(
                     send_unconfirmed_txs(&chainstate, unconfirmed_txs.clone());
                 }
                 RelayerDirective::ProcessTenure(consensus_hash, burn_hash, block_header_hash) => {
-                    info!(
+                    component_info!(
+                        Relayer,
                         "Relayer: Process tenure {}/{} in {}",
                         &consensus_hash, &block_header_hash, &burn_hash
                     );
@@ -912,8 +1346,8 @@ fn spawn_miner_relayer(
                         {
                             // we won!
                             let reward_block_height = mined_block.header.total_work.work + MINER_REWARD_MATURITY;
-                            info!("Won sortition! Mining reward will be received in {} blocks (block #{})", MINER_REWARD_MATURITY, reward_block_height);
-                            info!("Won sortition!";
+                            component_info!(Relayer, "Won sortition! Mining reward will be received in {} blocks (block #{})", MINER_REWARD_MATURITY, reward_block_height);
+                            component_info!(Relayer, "Won sortition!";
                                     "stacks_header" => %block_header_hash,
                                     "burn_hash" => %mined_burn_hash,
                             );
@@ -930,18 +1364,21 @@ fn spawn_miner_relayer(
                             ) {
                                 Ok(coordinator_running) => {
                                     if !coordinator_running {
-                                        warn!(
+                                        component_warn!(
+                                            Relayer,
                                             "Coordinator stopped, stopping relayer thread..."
                                         );
                                         return;
                                     }
                                 }
                                 Err(e) => {
-                                    warn!(
+                                    component_warn!(
+                                        Relayer,
                                         "Error processing my tenure, bad block produced: {}",
                                         e
                                     );
-                                    warn!(
+                                    component_warn!(
+                                        Relayer,
                                         "Bad block";
                                         "stacks_header" => %block_header_hash,
                                         "data" => %to_hex(&mined_block.serialize_to_vec()),
@@ -964,7 +1401,7 @@ fn spawn_miner_relayer(
                             };
 
                             if let Err(e) = relayer.advertize_blocks(blocks_available, block_data) {
-                                warn!("Failed to advertise new block: {}", e);
+                                component_warn!(Relayer, "Failed to advertise new block: {}", e);
                             }
 
                             let snapshot = SortitionDB::get_block_snapshot_consensus(
@@ -974,7 +1411,8 @@ fn spawn_miner_relayer(
                             .expect("Failed to obtain snapshot for block")
                             .expect("Failed to obtain snapshot for block");
                             if !snapshot.pox_valid {
-                                warn!(
+                                component_warn!(
+                                    Relayer,
                                     "Snapshot for {} is no longer valid; discarding {}...",
                                     &consensus_hash,
                                     &mined_block.block_hash()
@@ -988,11 +1426,12 @@ fn spawn_miner_relayer(
                                 if let Err(e) = relayer
                                     .broadcast_block(snapshot.consensus_hash, mined_block)
                                 {
-                                    warn!("Failed to push new block: {}", e);
+                                    component_warn!(Relayer, "Failed to push new block: {}", e);
                                 }
 
                                 // proceed to mine microblocks
-                                debug!(
+                                component_debug!(
+                                    Relayer,
                                     "Microblock miner tip is now {}/{} ({})",
                                     &consensus_hash, &block_header_hash, StacksBlockHeader::make_index_block_hash(&consensus_hash, &block_header_hash)
                                 );
@@ -1039,11 +1478,12 @@ fn spawn_miner_relayer(
                                         &event_dispatcher,
                                     ) {
                                         Ok(next_microblock) => {
-                                            info!(
+                                            component_info!(
+                                                Relayer,
                                                 "mined microblock from unconfirmed blocks: {}",
                                                 next_microblock.block_hash()
                                             );
-                                            microblock_miner.last_mined = get_epoch_time_ms();
+                                            microblock_miner.last_mined = config.clock.now_ms();
 
                                             // apply it
                                             let microblock_hash = next_microblock.block_hash();
@@ -1055,7 +1495,8 @@ fn spawn_miner_relayer(
                                                 .as_ref()
                                                 .map(|ref unconfirmed| unconfirmed.num_microblocks())
                                                 .unwrap_or(0);
-                                            debug!(
+                                            component_debug!(
+                                                Relayer,
                                                 "Mined one microblock: {} seq {} (total processed: {})",
                                                 &microblock_hash, next_microblock.header.sequence, num_mblocks
                                             );
@@ -1075,25 +1516,27 @@ fn spawn_miner_relayer(
                                                 &microblock_miner.parent_block_hash,
                                                 next_microblock.clone(),
                                             ) {
-                                                error!(
+                                                component_error!(
+                                                    Relayer,
                                                     "Failure trying to broadcast microblock {microblock_hash}: {e}"
                                                 );
                                             }
                                         }
                                         Err(e) => {
-                                            error!("Failed to mine microblock: {e}");
+                                            component_error!(Relayer, "Failed to mine microblock: {e}");
                                         }
                                     };
                                 }
                             }
                         } else {
-                            debug!("Did not win sortition, my blocks [burn_hash= {}, block_hash= {}], their blocks [parent_consenus_hash= {}, burn_hash= {}, block_hash ={}]",
+                            component_debug!(Relayer, "Did not win sortition, my blocks [burn_hash= {}, block_hash= {}], their blocks [parent_consenus_hash= {}, burn_hash= {}, block_hash ={}]",
                                 mined_burn_hash, mined_block.block_hash(), parent_consensus_hash, burn_hash, block_header_hash);
 
                             miner_tip = None;
                         }
                     } else {
-                        warn!(
+                        component_warn!(
+                            Relayer,
                             "Tenure block not recognized";
                             "block_header_hash" => block_header_hash.to_string(),
                             "last_mined_blocks" => ?last_mined_blocks.keys(),
@@ -1101,9 +1544,41 @@ fn spawn_miner_relayer(
                     }
                 }
                 RelayerDirective::RunTenure => {
+                    if miner_paused.load(Ordering::SeqCst) {
+                        component_debug!(Relayer, "Relayer: miner is paused, not running tenure");
+                        continue;
+                    }
                     let burn_tenure_snapshot = SortitionDB::get_canonical_burn_chain_tip(sortdb.conn())
                         .expect("FATAL: failed to query sortition DB for canonical burn chain tip");
 
+                    let next_mining_key = mining_key_schedule.as_ref().and_then(|schedule| {
+                        schedule
+                            .lock()
+                            .expect("FATAL: mining key schedule is poisoned")
+                            .take_due(burn_tenure_snapshot.block_height)
+                    });
+                    if let Some(next_mining_key) = next_mining_key {
+                        keychain = Keychain::single_signer(next_mining_key);
+                        component_info!(
+                            Relayer,
+                            "Relayer: switched to the rotated mining key";
+                            "height" => burn_tenure_snapshot.block_height,
+                        );
+                    }
+
+                    if !scheduled_calls.is_empty() {
+                        let submitted = scheduled_calls.submit_due(
+                            burn_tenure_snapshot.block_height,
+                            &mut chainstate,
+                            &sortdb,
+                            &mut mem_pool,
+                            Some(&event_dispatcher),
+                        );
+                        if !submitted.is_empty() {
+                            event_dispatcher.process_new_mempool_txs(submitted);
+                        }
+                    }
+
                     let burn_header_hash = burn_tenure_snapshot.burn_header_hash.clone();
 
                     let burn_chain_tip = burn_tenure_snapshot
@@ -1115,7 +1590,8 @@ fn spawn_miner_relayer(
 
                     let parent_bhh = burn_tenure_snapshot.parent_burn_header_hash.clone();
 
-                    info!(
+                    component_info!(
+                        Relayer,
                         "Relayer: Run tenure";
                         "height" => burn_tenure_snapshot.block_height,
                         "burn_header_hash" => %burn_chain_tip,
@@ -1139,22 +1615,22 @@ fn spawn_miner_relayer(
                     }
 
                     let last_tenure_issue_time = get_epoch_time_ms();
-                    debug!("Relayer: RunTenure finished at {} (in {}ms)", last_tenure_issue_time, last_tenure_issue_time.saturating_sub(tenure_begin));
+                    component_debug!(Relayer, "Relayer: RunTenure finished at {} (in {}ms)", last_tenure_issue_time, last_tenure_issue_time.saturating_sub(tenure_begin));
                 }
                 RelayerDirective::RunMicroblockTenure(burnchain_tip, tenure_issue_ms) => {
                     if last_microblock_tenure_time > tenure_issue_ms {
-                        debug!("Drop stale RunMicroblockTenure for {}/{}: last_microblock_tenure_time = {}, tenure_issue_ms = {}", &burnchain_tip.consensus_hash, &burnchain_tip.winning_stacks_block_hash, last_microblock_tenure_time, tenure_issue_ms);
+                        component_debug!(Relayer, "Drop stale RunMicroblockTenure for {}/{}: last_microblock_tenure_time = {}, tenure_issue_ms = {}", &burnchain_tip.consensus_hash, &burnchain_tip.winning_stacks_block_hash, last_microblock_tenure_time, tenure_issue_ms);
                         // stale request
                         continue;
                     }
                     if let Some(cur_sortition) = get_last_sortition(&last_sortition) {
                         if burnchain_tip.sortition_id != cur_sortition.sortition_id {
-                            debug!("Drop stale RunMicroblockTenure for {}/{}: current sortition is for {} ({})", &burnchain_tip.consensus_hash, &burnchain_tip.winning_stacks_block_hash, &cur_sortition.consensus_hash, &cur_sortition.burn_header_hash);
+                            component_debug!(Relayer, "Drop stale RunMicroblockTenure for {}/{}: current sortition is for {} ({})", &burnchain_tip.consensus_hash, &burnchain_tip.winning_stacks_block_hash, &cur_sortition.consensus_hash, &cur_sortition.burn_header_hash);
                             continue;
                         }
                     }
 
-                    debug!("Relayer: Run microblock tenure");
+                    component_debug!(Relayer, "Relayer: Run microblock tenure");
 
                     // unconfirmed state must be consistent with the chain tip, as must the
                     // microblock mining state.
@@ -1165,7 +1641,7 @@ fn spawn_miner_relayer(
                                 microblock_miner_state = Some(miner_state);
                             }
                             else {
-                                debug!("Relayer: reset microblock miner state");
+                                component_debug!(Relayer, "Relayer: reset microblock miner state");
                                 microblock_miner_state = None;
                                 counters.set_microblocks_processed(0);
                             }
@@ -1189,10 +1665,10 @@ fn spawn_miner_relayer(
 
                         // synchronize unconfirmed tx index to p2p thread
                         send_unconfirmed_txs(&chainstate, unconfirmed_txs.clone());
-                        last_microblock_tenure_time = get_epoch_time_ms();
+                        last_microblock_tenure_time = config.clock.now_ms();
                     }
                     else {
-                        debug!("Relayer: reset unconfirmed state to 0 microblocks");
+                        component_debug!(Relayer, "Relayer: reset unconfirmed state to 0 microblocks");
                         counters.set_microblocks_processed(0);
                         microblock_miner_state = None;
                     }
@@ -1200,7 +1676,7 @@ fn spawn_miner_relayer(
                 RelayerDirective::Exit => break
             }
         }
-        debug!("Relayer exit!");
+        component_debug!(Relayer, "Relayer exit!");
     }).unwrap();
 
     Ok(relayer_handle)
@@ -1274,18 +1750,18 @@ impl StacksNode {
             warn!("Without a peer to bootstrap from, the node will start mining a new chain");
         }
 
-        let p2p_sock: SocketAddr = config.node.p2p_bind.parse().expect(&format!(
-            "Failed to parse socket: {}",
-            &config.node.p2p_bind
-        ));
-        let rpc_sock = config.node.rpc_bind.parse().expect(&format!(
-            "Failed to parse socket: {}",
-            &config.node.rpc_bind
-        ));
-        let p2p_addr: SocketAddr = config.node.p2p_address.parse().expect(&format!(
-            "Failed to parse socket: {}",
-            &config.node.p2p_address
-        ));
+        let p2p_sock = config
+            .node
+            .get_p2p_bind_addr()
+            .unwrap_or_else(|e| panic!("{}", e));
+        let rpc_sock = config
+            .node
+            .get_rpc_bind_addr()
+            .unwrap_or_else(|e| panic!("{}", e));
+        let p2p_addr = config
+            .node
+            .get_p2p_advertised_addr()
+            .unwrap_or_else(|e| panic!("{}", e));
         let node_privkey = {
             let mut re_hashed_seed = config.node.local_peer_seed.clone();
             let my_private_key = loop {
@@ -1304,7 +1780,7 @@ impl StacksNode {
         let mut peerdb = PeerDB::connect(
             &config.get_peer_db_file_path(),
             true,
-            config.node.chain_id,
+            config.node.network_id,
             config.burnchain.network_id,
             Some(node_privkey),
             config.connection_options.private_key_lifetime.clone(),
@@ -1469,6 +1945,7 @@ impl StacksNode {
 
     /// Tell the relayer to fire off a tenure and a block commit op,
     /// if it is time to do so.
+    /// returns _false_ if the relayer hung up the channel.
     pub fn relayer_issue_tenure(&mut self) -> bool {
         if !self.is_miner {
             // node is a follower, don't try to issue a tenure
@@ -1476,20 +1953,25 @@ impl StacksNode {
         }
 
         if let Some(burnchain_tip) = get_last_sortition(&self.last_sortition) {
-            // Just immediately send the `RunTenure` directive to the relayer.
-            let channel_accepted = self
-                .relay_channel
-                .try_send(RelayerDirective::RunTenure)
-                .is_ok();
+            // Just immediately send the `RunTenure` directive to the relayer.  If its buffer is
+            // full, it is busy, and the next pass of the run loop will try again.
+            let channel_accepted = match self.relay_channel.try_send(RelayerDirective::RunTenure) {
+                Ok(()) | Err(TrySendError::Full(_)) => true,
+                Err(TrySendError::Disconnected(_)) => false,
+            };
 
-            debug!(
+            component_debug!(
+                Miner,
                 "relayer_issue_tenure invoked";
                 "received_at_burn_hash" => %burnchain_tip.burn_header_hash,
                 "received_at_burn_height" => %burnchain_tip.block_height,
             );
             channel_accepted
         } else {
-            warn!("Tenure: Do not know the last burn block. As a miner, this is bad.");
+            component_warn!(
+                Miner,
+                "Tenure: Do not know the last burn block. As a miner, this is bad."
+            );
             true
         }
     }
@@ -1504,7 +1986,8 @@ impl StacksNode {
         }
 
         if let Some(snapshot) = get_last_sortition(&self.last_sortition) {
-            debug!(
+            component_debug!(
+                Miner,
                 "Tenure: Notify sortition!";
                 "consensus_hash" => %snapshot.consensus_hash,
                 "burn_block_hash" => %snapshot.burn_header_hash,
@@ -1523,14 +2006,14 @@ impl StacksNode {
                     .is_ok();
             }
         } else {
-            debug!("Tenure: Notify sortition! No last burn block");
+            component_debug!(Miner, "Tenure: Notify sortition! No last burn block");
         }
         true
     }
 
     fn get_mining_tenure_information(
         chain_state: &mut StacksChainState,
-        burn_db: &mut SortitionDB,
+        burn_db: &SortitionDB,
         check_burn_block: &BlockSnapshot,
         miner_address: StacksAddress,
         mine_tip_ch: &ConsensusHash,
@@ -1543,7 +2026,8 @@ impl StacksNode {
         )
         .unwrap()
         .ok_or_else(|| {
-            error!(
+            component_error!(
+                Miner,
                 "Could not mine new tenure, since could not find header for known chain tip.";
                 "tip_consensus_hash" => %mine_tip_ch,
                 "tip_stacks_block_hash" => %mine_tip_bh
@@ -1562,7 +2046,8 @@ impl StacksNode {
         let parent_block = SortitionDB::get_block_snapshot(burn_db.conn(), parent_sortition_id)
             .expect("SortitionDB failure.")
             .ok_or_else(|| {
-                error!(
+                component_error!(
+                    Miner,
                     "Failed to find block snapshot for the parent sortition";
                     "parent_sortition_id" => %parent_sortition_id
                 );
@@ -1574,7 +2059,8 @@ impl StacksNode {
             .expect("FATAL: failed to query sortition DB for canonical burn chain tip");
 
         if burn_chain_tip.consensus_hash != check_burn_block.consensus_hash {
-            debug!(
+            component_debug!(
+                Miner,
                 "New canonical burn chain tip detected. Will not try to mine.";
                 "new_consensus_hash" => %burn_chain_tip.consensus_hash,
                 "old_consensus_hash" => %check_burn_block.consensus_hash,
@@ -1584,7 +2070,7 @@ impl StacksNode {
             return Err(Error::BurnchainTipChanged);
         }
 
-        debug!("Mining tenure's last consensus hash: {} (height {} hash {}), stacks tip consensus hash: {} (height {} hash {})",
+        component_debug!(Miner, "Mining tenure's last consensus hash: {} (height {} hash {}), stacks tip consensus hash: {} (height {} hash {})",
                &check_burn_block.consensus_hash, check_burn_block.block_height, &check_burn_block.burn_header_hash,
                mine_tip_ch, parent_snapshot.block_height, &parent_snapshot.burn_header_hash);
 
@@ -1622,173 +2108,49 @@ impl StacksNode {
         burnchain_controller: &mut (dyn BurnchainController + Send),
         event_dispatcher: &EventDispatcher,
     ) -> Option<(AssembledAnchorBlock, Secp256k1PrivateKey)> {
-        let MiningTenureInformation {
-            mut stacks_parent_header,
-            parent_consensus_hash,
-            parent_block_total_burn,
-            coinbase_nonce,
-            ..
-        } = if let Some(stacks_tip) = chain_state
-            .get_stacks_chain_tip(burn_db)
-            .expect("FATAL: could not query chain tip")
-        {
-            let miner_address = keychain.origin_address(config.is_mainnet()).unwrap();
-            Self::get_mining_tenure_information(
-                chain_state,
-                burn_db,
-                &burn_block,
-                miner_address,
-                &stacks_tip.consensus_hash,
-                &stacks_tip.anchored_block_hash,
-            )
-            .ok()?
-        } else {
-            info!("No subnet chain tip known, will return a genesis block");
-            let burnchain_params =
-                // TODO(subnets): set burnchain parameters with subnet configuration
-                BurnchainParameters::from_params(&config.burnchain.chain, "mainnet")
-                    .expect("Bitcoin network unsupported");
-
-            let chain_tip = ChainTip::genesis(
-                &burnchain_params.first_block_hash,
-                burnchain_params.first_block_height.into(),
-                burnchain_params.first_block_timestamp.into(),
-            );
-
-            MiningTenureInformation {
-                stacks_parent_header: chain_tip.metadata,
-                parent_consensus_hash: FIRST_BURNCHAIN_CONSENSUS_HASH.clone(),
-                parent_block_total_burn: 0,
-                coinbase_nonce: 0,
-            }
-        };
-
-        debug!(
-            "Relayer will try to mine off of {}/{}",
-            &parent_consensus_hash, &stacks_parent_header.burn_header_hash
-        );
-
-        // Generates a new secret key for signing the trail of microblocks
-        // of the upcoming tenure.
-        let microblock_secret_key = keychain.rotate_microblock_keypair(burn_block.block_height);
-        let mblock_pubkey_hash =
-            Hash160::from_node_public_key(&StacksPublicKey::from_private(&microblock_secret_key));
-
-        let coinbase_tx = inner_generate_coinbase_tx(
-            keychain,
-            coinbase_nonce,
-            config.is_mainnet(),
-            config.node.chain_id,
-        );
-
-        // find the longest microblock tail we can build off of
-        let microblock_info_opt =
-            match StacksChainState::load_descendant_staging_microblock_stream_with_poison(
-                chain_state.db(),
-                &StacksBlockHeader::make_index_block_hash(
-                    &parent_consensus_hash,
-                    &stacks_parent_header.anchored_header.block_hash(),
-                ),
-                0,
-                u16::MAX,
-            ) {
-                Ok(x) => {
-                    let num_mblocks = x.as_ref().map(|(mblocks, ..)| mblocks.len()).unwrap_or(0);
-                    debug!(
-                        "Loaded {} microblocks descending from {}/{}",
-                        num_mblocks,
-                        &parent_consensus_hash,
-                        &stacks_parent_header.anchored_header.block_hash()
-                    );
-                    x
-                }
-                Err(e) => {
-                    warn!(
-                        "Failed to load descendant microblock stream from {}/{}: {:?}",
-                        &parent_consensus_hash,
-                        &stacks_parent_header.anchored_header.block_hash(),
-                        &e
-                    );
-                    None
-                }
-            };
-
-        if let Some((ref microblocks, _)) = &microblock_info_opt {
-            if let Some(ref tail) = microblocks.last() {
-                debug!(
-                    "Confirm microblock stream tailed at {} (seq {})",
-                    &tail.block_hash(),
-                    tail.header.sequence
-                );
-            }
-
-            // try and confirm as many microblocks as we can (but note that the stream itself may
-            // be too long; we'll try again if that happens).
-            stacks_parent_header.microblock_tail =
-                microblocks.last().clone().map(|blk| blk.header.clone());
+        if halted_for_upgrade(
+            config,
+            burn_db,
+            &burn_block.sortition_id,
+            burn_block.block_height,
+        ) {
+            return None;
         }
 
-        let built_info = match StacksBlockBuilder::build_empty_anchored_block(
+        let mined = match mine_one_block(
+            config,
             chain_state,
-            &burn_db.index_conn(),
-            &stacks_parent_header,
-            parent_block_total_burn,
-            VRFProof::empty(),
-            mblock_pubkey_hash,
-            &coinbase_tx,
+            burn_db,
+            &burn_block,
+            keychain,
             Some(event_dispatcher),
         ) {
-            Ok(block) => block,
+            Ok(mined) => mined,
             Err(e) => {
-                error!("Failure mining anchored block: {}", e);
+                component_debug!(Miner, "Did not mine an anchored block: {:?}", e);
                 return None;
             }
         };
 
-        let AssembledBlockInfo {
-            block: anchored_block,
-            mblocks_confirmed,
-            burn_tip,
-            burn_tip_height,
-            ..
-        } = built_info;
-
-        let block_height = anchored_block.header.total_work.work;
-        debug!(
-            "Assembled subnet block";
-            "height" => block_height,
-            "tx_count" => anchored_block.txs.len(),
-            "block_hash" => %anchored_block.block_hash(),
-        );
-
         // collect required contents for commit
-        let committed_block_hash = anchored_block.block_hash();
+        let block_height = mined.anchored_block.header.total_work.work;
+        let committed_block_hash = mined.anchored_block.block_hash();
         let target_burn_hash = burn_block.burn_header_hash.clone();
         let target_burn_height = burn_block.block_height;
-        let withdrawal_merkle_root = anchored_block.header.withdrawal_merkle_root;
+        let withdrawal_merkle_root = mined.anchored_block.header.withdrawal_merkle_root;
 
-        let mut op_signer = keychain.generate_op_signer();
+        let op_signer = keychain.generate_op_signer();
         let required_signatures = burnchain_controller.commit_required_signatures();
         let signatures = if required_signatures > 0 {
             // if we need to collect signatures, assemble the proposal and send to other participants
-            let proposal = Proposal {
-                parent_block_hash: stacks_parent_header.anchored_header.block_hash(),
-                parent_consensus_hash: stacks_parent_header.consensus_hash.clone(),
-                block: anchored_block.clone(),
-                microblocks_confirmed: mblocks_confirmed,
-                burn_tip,
-                burn_tip_height,
-                total_burn: parent_block_total_burn,
-                is_mainnet: config.is_mainnet(),
-                microblock_pubkey_hash: mblock_pubkey_hash.clone(),
-            };
+            let proposal = mined.make_proposal(config.is_mainnet());
 
             // Sign the proposal. Proposals will only be considered if signed by leader
             let privk = op_signer.get_sk();
             let proposal = match proposal.sign_for_authentication(privk) {
                 Ok(p) => p,
                 Err(e) => {
-                    error!("Failure signing block: {e}");
+                    component_error!(Miner, "Failure signing block: {e}");
                     return None;
                 }
             };
@@ -1798,7 +2160,7 @@ impl StacksNode {
                     match burnchain_controller.propose_block(participant_index, &proposal) {
                         Ok(signature) => Some(signature),
                         Err(rejection) => {
-                            warn!("Failed to obtain approval"; "error" => %rejection);
+                            component_warn!(Miner, "Failed to obtain approval"; "error" => %rejection);
                             None
                         }
                     }
@@ -1809,7 +2171,8 @@ impl StacksNode {
         };
 
         if signatures.len() < required_signatures as usize {
-            error!(
+            component_error!(
+                Miner,
                 "Failed to obtain enough signatures for multi-party mining";
                 "signatures_obtained" => signatures.len(),
                 "required" => required_signatures
@@ -1817,6 +2180,13 @@ impl StacksNode {
             return None;
         }
 
+        let MinedBlockResult {
+            parent_consensus_hash,
+            anchored_block,
+            microblock_secret_key,
+            ..
+        } = mined;
+
         let cur_burn_chain_tip = SortitionDB::get_canonical_burn_chain_tip(burn_db.conn())
             .expect("FATAL: failed to query sortition DB for canonical burn chain tip");
 
@@ -1830,7 +2200,8 @@ impl StacksNode {
                 || parent_consensus_hash != stacks_tip.consensus_hash
                 || cur_burn_chain_tip.sortition_id != burn_block.sortition_id
             {
-                debug!(
+                component_debug!(
+                    Miner,
                     "Cancel block-commit; chain tip(s) have changed";
                     "block_hash" => %anchored_block.block_hash(),
                     "tx_count" => anchored_block.txs.len(),
@@ -1852,7 +2223,8 @@ impl StacksNode {
             }
         }
 
-        info!(
+        component_info!(
+            Miner,
             "Submit block-commit";
             "block_hash" => %anchored_block.block_hash(),
             "tx_count" => anchored_block.txs.len(),
@@ -1873,19 +2245,23 @@ impl StacksNode {
             target_burn_height,
             withdrawal_merkle_root,
             signatures,
-            &mut op_signer,
+            make_commit_signer(&config.miner, op_signer.get_sk()),
         );
 
         match res {
             Ok(x) => {
-                info!("Submitted miner commitment L1 transaction"; "txid" => %x);
+                component_info!(Miner, "Submitted miner commitment L1 transaction"; "txid" => %x);
             }
             Err(e) => {
                 if !config.node.mock_mining {
-                    warn!("Failed to submit miner commitment L1 transaction: {}", e);
+                    component_warn!(
+                        Miner,
+                        "Failed to submit miner commitment L1 transaction: {}",
+                        e
+                    );
                     return None;
                 } else {
-                    debug!("Mock-mining enabled; not sending L1 transaction");
+                    component_debug!(Miner, "Mock-mining enabled; not sending L1 transaction");
                 }
             }
         }
@@ -1916,6 +2292,8 @@ impl StacksNode {
             .expect("Failed to obtain block snapshot for processed burn block.")
             .expect("Failed to obtain block snapshot for processed burn block.");
         let block_height = block_snapshot.block_height;
+        component_log::set_burn_height(block_height);
+        component_log::set_l2_height(block_snapshot.canonical_stacks_tip_height);
 
         let block_commits =
             SortitionDB::get_block_commits_by_block(&ic, &block_snapshot.sortition_id)
@@ -1925,16 +2303,20 @@ impl StacksNode {
 
         for op in block_commits.into_iter() {
             if op.txid == block_snapshot.winning_block_txid {
-                info!(
+                component_info!(
+                    Burnchain,
                     "Received burnchain block #{} including block_commit_op (winning) ({})",
-                    block_height, &op.block_header_hash
+                    block_height,
+                    &op.block_header_hash
                 );
                 last_sortitioned_block = Some(block_snapshot.clone());
             } else {
                 if self.is_miner {
-                    info!(
+                    component_info!(
+                        Burnchain,
                         "Received burnchain block #{} including block_commit_op ({})",
-                        block_height, &op.block_header_hash
+                        block_height,
+                        &op.block_header_hash
                     );
                 }
             }
@@ -1951,3 +2333,194 @@ impl StacksNode {
         self.p2p_thread_handle.join().unwrap();
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::burnchains::burnchain_from_config;
+    use crate::burnchains::db_indexer::DBBurnchainIndexer;
+    use crate::tests::neon_integrations::mockstack_test_conf;
+    use stacks::chainstate::burn::db::sortdb::SortitionHandleTx;
+    use stacks::core::FIRST_STACKS_BLOCK_HASH;
+    use stacks::net::{BlocksData, NeighborKey};
+
+    fn make_test_dbs(config: &Config) -> (SortitionDB, StacksChainState) {
+        let indexer = DBBurnchainIndexer::new(
+            &config.get_burnchain_path_str(),
+            config.burnchain.clone(),
+            true,
+        )
+        .unwrap();
+        let mut burnchain =
+            burnchain_from_config(&config.get_burn_db_path(), &config.burnchain).unwrap();
+        let (sortdb, _) = burnchain.connect_db(&indexer, true).unwrap();
+
+        let mut boot_data = RunLoop::make_boot_data(&config, &burnchain);
+        let (chainstate, _) = StacksChainState::open_and_exec(
+            config.is_mainnet(),
+            config.node.chain_id,
+            &config.get_chainstate_path_str(),
+            Some(&mut boot_data),
+            Some(config.node.get_marf_opts()),
+        )
+        .unwrap();
+        (sortdb, chainstate)
+    }
+
+    #[test]
+    fn test_mine_one_block_genesis() {
+        let (config, miner_account) = mockstack_test_conf();
+        let (sortdb, mut chainstate) = make_test_dbs(&config);
+
+        let burn_block = SortitionDB::get_canonical_burn_chain_tip(sortdb.conn()).unwrap();
+        let mut keychain = Keychain::default(config.node.seed.clone());
+
+        let mined = mine_one_block(
+            &config,
+            &mut chainstate,
+            &sortdb,
+            &burn_block,
+            &mut keychain,
+            None,
+        )
+        .unwrap();
+
+        // there is no subnet chain tip yet, so the block builds off of the genesis block
+        assert_eq!(mined.parent_consensus_hash, FIRST_BURNCHAIN_CONSENSUS_HASH);
+        assert_eq!(mined.parent_block_hash, FIRST_STACKS_BLOCK_HASH);
+        assert_eq!(mined.parent_block_total_burn, 0);
+        assert_eq!(
+            mined.anchored_block.header.parent_block,
+            FIRST_STACKS_BLOCK_HASH
+        );
+        assert_eq!(mined.anchored_block.header.total_work.work, 1);
+
+        // the block holds only the miner's coinbase, and confirms no microblocks
+        assert_eq!(mined.anchored_block.txs.len(), 1);
+        let coinbase = &mined.anchored_block.txs[0];
+        match coinbase.payload {
+            TransactionPayload::Coinbase(_) => {}
+            ref payload => panic!("Expected a coinbase, got {:?}", payload),
+        }
+        assert_eq!(coinbase.origin_address(), miner_account);
+        assert_eq!(coinbase.get_origin_nonce(), 0);
+        assert_eq!(coinbase.chain_id, config.node.chain_id);
+        assert!(mined.microblocks_confirmed.is_empty());
+
+        // the microblock key was rotated for this tenure, and the block commits to it
+        let pubkey_hash = |privk: &Secp256k1PrivateKey| {
+            Hash160::from_node_public_key(&StacksPublicKey::from_private(privk))
+        };
+        let rotated_key = keychain.get_microblock_key().unwrap();
+        assert_eq!(
+            pubkey_hash(&mined.microblock_secret_key),
+            pubkey_hash(&rotated_key)
+        );
+        assert_eq!(
+            mined.microblock_pubkey_hash,
+            pubkey_hash(&mined.microblock_secret_key)
+        );
+        assert_eq!(
+            mined.anchored_block.header.microblock_pubkey_hash,
+            mined.microblock_pubkey_hash
+        );
+
+        // the block was assembled at the burnchain tip
+        assert_eq!(mined.burn_tip, burn_block.burn_header_hash);
+        assert_eq!(mined.burn_tip_height as u64, burn_block.block_height);
+    }
+
+    #[test]
+    fn test_follower_drops_uncommitted_blocks() {
+        let (config, _) = mockstack_test_conf();
+        let (mut sortdb, mut chainstate) = make_test_dbs(&config);
+
+        let burn_block = SortitionDB::get_canonical_burn_chain_tip(sortdb.conn()).unwrap();
+        let mut keychain = Keychain::default(config.node.seed.clone());
+        let committed_block = mine_one_block(
+            &config,
+            &mut chainstate,
+            &sortdb,
+            &burn_block,
+            &mut keychain,
+            None,
+        )
+        .unwrap()
+        .anchored_block;
+
+        // a different block in the same sortition, which the L1 never committed to
+        let mut mismatched_block = committed_block.clone();
+        mismatched_block.header.total_work.work += 1;
+        assert_ne!(mismatched_block.block_hash(), committed_block.block_hash());
+
+        // process a sortition whose L1 block-commit commits to `committed_block`
+        let mut snapshot = burn_block.clone();
+        snapshot.block_height += 1;
+        snapshot.parent_burn_header_hash = burn_block.burn_header_hash.clone();
+        snapshot.burn_header_hash = BurnchainHeaderHash([0x01; 32]);
+        snapshot.consensus_hash = ConsensusHash([0x01; 20]);
+        snapshot.sortition_id = SortitionId::stubbed(&snapshot.burn_header_hash);
+        snapshot.parent_sortition_id = burn_block.sortition_id.clone();
+        snapshot.sortition = true;
+        snapshot.num_sortitions += 1;
+        snapshot.winning_stacks_block_hash = committed_block.block_hash();
+        {
+            let mut tx = SortitionHandleTx::begin(&mut sortdb, &burn_block.sortition_id).unwrap();
+            tx.append_chain_tip_snapshot(&burn_block, &snapshot, &vec![], None, None)
+                .unwrap();
+            tx.commit().unwrap();
+        }
+
+        // a sortition we have not processed yet
+        let unknown_consensus_hash = ConsensusHash([0x02; 20]);
+
+        let neighbor = NeighborKey {
+            peer_version: 0,
+            network_id: 0,
+            addrbytes: PeerAddress([0u8; 16]),
+            port: 20444,
+        };
+        let mut net_result = NetworkResult::new(0, 0, 0);
+        net_result.blocks = vec![
+            (snapshot.consensus_hash.clone(), committed_block.clone(), 0),
+            (snapshot.consensus_hash.clone(), mismatched_block.clone(), 0),
+            (unknown_consensus_hash.clone(), committed_block.clone(), 0),
+        ];
+        net_result.pushed_blocks.insert(
+            neighbor.clone(),
+            vec![BlocksData {
+                blocks: vec![
+                    BlocksDatum(snapshot.consensus_hash.clone(), mismatched_block.clone()),
+                    BlocksDatum(snapshot.consensus_hash.clone(), committed_block.clone()),
+                ],
+            }],
+        );
+        net_result.uploaded_blocks = vec![BlocksData {
+            blocks: vec![
+                BlocksDatum(unknown_consensus_hash.clone(), committed_block.clone()),
+                BlocksDatum(snapshot.consensus_hash.clone(), mismatched_block.clone()),
+            ],
+        }];
+
+        drop_uncommitted_blocks(&sortdb, &mut net_result);
+
+        // only the committed block in its own sortition survives
+        assert_eq!(
+            net_result.blocks,
+            vec![(snapshot.consensus_hash.clone(), committed_block.clone(), 0)]
+        );
+        assert_eq!(
+            net_result.pushed_blocks.get(&neighbor).unwrap(),
+            &vec![BlocksData {
+                blocks: vec![BlocksDatum(
+                    snapshot.consensus_hash.clone(),
+                    committed_block.clone()
+                )],
+            }]
+        );
+        assert_eq!(
+            net_result.uploaded_blocks,
+            vec![BlocksData { blocks: vec![] }]
+        );
+    }
+}
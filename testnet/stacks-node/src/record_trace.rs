@@ -0,0 +1,71 @@
+use std::fs;
+use std::io::BufWriter;
+
+use stacks::chainstate::coordinator::trace::{self, TraceEntry};
+use stacks::chainstate::stacks::db::StacksChainState;
+
+use crate::burnchains::burnchain_from_config;
+use crate::run_loop::RunLoopError;
+use crate::Config;
+
+/// What `record_trace` wrote
+pub struct TraceSummary {
+    pub num_burn_blocks: u64,
+    pub num_stacks_blocks: u64,
+}
+
+/// Record the burnchain blocks and Stacks blocks the node has processed, and the consensus
+/// hashes and state roots it computed for them, as a trace at `dest_path`, which must not exist.
+/// The trace can be replayed with `stacks::chainstate::coordinator::trace::replay_trace`, or
+/// checked in as a fixture under `src/chainstate/coordinator/trace_fixtures`.  The databases
+/// are only read, so the node may be running.
+pub fn record_trace(config: &Config, dest_path: &str) -> Result<TraceSummary, RunLoopError> {
+    if fs::metadata(dest_path).is_ok() {
+        return Err(RunLoopError::Chainstate(format!(
+            "{} already exists",
+            dest_path
+        )));
+    }
+
+    let burnchain = burnchain_from_config(&config.get_burn_db_path(), &config.burnchain)
+        .map_err(|e| RunLoopError::Chainstate(format!("failed to load burnchain: {:?}", &e)))?;
+    let (sortdb, burnchain_db) = burnchain.open_db(false).map_err(|e| {
+        RunLoopError::Chainstate(format!("failed to open burnchain databases: {:?}", &e))
+    })?;
+    let chainstate = StacksChainState::open_readonly(
+        config.is_mainnet(),
+        config.node.chain_id,
+        &config.get_chainstate_path_str(),
+        Some(config.node.get_marf_opts()),
+    )
+    .map_err(|e| {
+        RunLoopError::Chainstate(format!("failed to open chainstate database: {:?}", &e))
+    })?;
+
+    let entries = trace::record_trace(
+        &burnchain,
+        config.get_initial_balances(),
+        &burnchain_db,
+        &sortdb,
+        &chainstate,
+    )
+    .map_err(|e| RunLoopError::Chainstate(format!("failed to record trace: {}", &e)))?;
+
+    let file = fs::File::create(dest_path)
+        .map_err(|e| RunLoopError::Chainstate(format!("failed to create {}: {}", dest_path, &e)))?;
+    trace::write_trace(&mut BufWriter::new(file), &entries)
+        .map_err(|e| RunLoopError::Chainstate(format!("failed to write trace: {}", &e)))?;
+
+    let mut summary = TraceSummary {
+        num_burn_blocks: 0,
+        num_stacks_blocks: 0,
+    };
+    for entry in entries.iter() {
+        match entry {
+            TraceEntry::BurnBlock(_) => summary.num_burn_blocks += 1,
+            TraceEntry::StacksBlock(_) => summary.num_stacks_blocks += 1,
+            TraceEntry::Boot(_) => {}
+        }
+    }
+    Ok(summary)
+}
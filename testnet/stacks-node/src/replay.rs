@@ -0,0 +1,130 @@
+use std::fs;
+
+use stacks::chainstate::burn::db::sortdb::SortitionDB;
+use stacks::chainstate::stacks::db::replay::ReplayedBlock;
+use stacks::chainstate::stacks::db::StacksChainState;
+use stacks::util::get_epoch_time_ms;
+use stacks::vm::costs::ExecutionCost;
+
+use crate::burnchains::burnchain_from_config;
+use crate::neon::RunLoop;
+use crate::run_loop::RunLoopError;
+use crate::Config;
+
+/// Re-execute the node's processed L2 blocks, from genesis, against a new chainstate at
+/// `dest_path`, checking that each block reproduces the state root and withdrawal root the
+/// node stored for it.  The node must not be running.  With `profile`, each block's execution
+/// cost and processing time is printed as it is replayed.
+pub fn replay_blocks(
+    config: &Config,
+    dest_path: &str,
+    until_height: Option<u64>,
+    profile: bool,
+) -> Result<u64, RunLoopError> {
+    let (source, mut dest, mut sortdb) = open_for_replay(config, dest_path)?;
+
+    if profile {
+        println!("height,index_block_hash,txs,wall_time_ms,runtime,read_count,read_length,write_count,write_length");
+    }
+    let start = get_epoch_time_ms();
+    let mut total_cost = ExecutionCost::zero();
+    let num_replayed = dest
+        .replay_blocks(&source, &mut sortdb, until_height, |block| {
+            let cost = replayed_block_cost(block);
+            total_cost = sum_costs(&total_cost, &cost);
+            if profile {
+                println!(
+                    "{},{},{},{},{},{},{},{},{}",
+                    block.block_height,
+                    &block.index_block_hash,
+                    block.num_txs,
+                    block.wall_time_ms,
+                    cost.runtime,
+                    cost.read_count,
+                    cost.read_length,
+                    cost.write_count,
+                    cost.write_length
+                );
+            } else if block.block_height % 100 == 0 {
+                info!("Replayed block {}", block.block_height);
+            }
+        })
+        .map_err(|e| RunLoopError::Chainstate(format!("replay failed: {:?}", &e)))?;
+
+    info!(
+        "Replayed {} blocks in {}ms with total cost {:?}; state matches the node's chainstate",
+        num_replayed,
+        get_epoch_time_ms().saturating_sub(start),
+        &total_cost
+    );
+    Ok(num_replayed)
+}
+
+/// Open the node's chainstate and sortition DB, and boot a new chainstate at `dest_path` to
+/// replay the node's blocks into.  Returns the node's chainstate, the new chainstate, and the
+/// sortition DB.
+pub(crate) fn open_for_replay(
+    config: &Config,
+    dest_path: &str,
+) -> Result<(StacksChainState, StacksChainState, SortitionDB), RunLoopError> {
+    let source_path = config.get_chainstate_path_str();
+    if fs::metadata(&source_path).is_err() {
+        return Err(RunLoopError::Chainstate(format!(
+            "no chainstate at {}",
+            &source_path
+        )));
+    }
+    if fs::metadata(dest_path).is_ok() {
+        return Err(RunLoopError::Chainstate(format!(
+            "{} already exists; blocks must be replayed into a new directory",
+            dest_path
+        )));
+    }
+
+    let burnchain = burnchain_from_config(&config.get_burn_db_path(), &config.burnchain)
+        .map_err(|e| RunLoopError::Chainstate(format!("failed to load burnchain: {:?}", &e)))?;
+    let sortdb = SortitionDB::open(&config.get_burn_db_file_path(), true).map_err(|e| {
+        RunLoopError::Chainstate(format!("failed to open sortition database: {:?}", &e))
+    })?;
+
+    let (source, _) = StacksChainState::open(
+        config.is_mainnet(),
+        config.node.chain_id,
+        &source_path,
+        Some(config.node.get_marf_opts()),
+    )
+    .map_err(|e| {
+        RunLoopError::Chainstate(format!("failed to open chainstate database: {:?}", &e))
+    })?;
+
+    let mut boot_data = RunLoop::make_boot_data(config, &burnchain);
+    let (mut dest, _) = StacksChainState::open_and_exec(
+        config.is_mainnet(),
+        config.node.chain_id,
+        dest_path,
+        Some(&mut boot_data),
+        Some(config.node.get_marf_opts()),
+    )
+    .map_err(|e| {
+        RunLoopError::Chainstate(format!("failed to instantiate new chainstate: {:?}", &e))
+    })?;
+    dest.set_account_index(config.node.account_index);
+    dest.set_block_compression(config.node.block_compression);
+
+    Ok((source, dest, sortdb))
+}
+
+/// The cost of a replayed block together with the parent microblocks it confirmed
+fn replayed_block_cost(block: &ReplayedBlock) -> ExecutionCost {
+    sum_costs(&block.anchored_block_cost, &block.parent_microblocks_cost)
+}
+
+fn sum_costs(a: &ExecutionCost, b: &ExecutionCost) -> ExecutionCost {
+    ExecutionCost {
+        runtime: a.runtime.saturating_add(b.runtime),
+        read_count: a.read_count.saturating_add(b.read_count),
+        read_length: a.read_length.saturating_add(b.read_length),
+        write_count: a.write_count.saturating_add(b.write_count),
+        write_length: a.write_length.saturating_add(b.write_length),
+    }
+}
@@ -0,0 +1,57 @@
+use std::fs;
+
+use stacks::chainstate::burn::db::sortdb::SortitionDB;
+use stacks::chainstate::stacks::db::rewards_report::CommitFeeLedger;
+use stacks::chainstate::stacks::db::StacksChainState;
+
+use crate::burnchains::commit_fees::CommitFeeDB;
+use crate::run_loop::RunLoopError;
+use crate::Config;
+
+/// Print, as JSON, what each miner earned from the canonical blocks at heights `from_height`
+/// up to and including `to_height` of the node whose working directory is
+/// `config.node.working_dir`.  If that node recorded the L1 fees of the block-commits it sent,
+/// these are printed too, with each miner's net income.  Nothing is written.
+pub fn rewards_report(
+    config: &Config,
+    from_height: u64,
+    to_height: u64,
+) -> Result<(), RunLoopError> {
+    let chainstate_path = config.get_chainstate_path_str();
+    if fs::metadata(&chainstate_path).is_err() {
+        return Err(RunLoopError::Chainstate(format!(
+            "no chainstate at {}",
+            &chainstate_path
+        )));
+    }
+    let sortdb = SortitionDB::open(&config.get_burn_db_file_path(), false).map_err(|e| {
+        RunLoopError::Chainstate(format!("failed to open sortition database: {:?}", &e))
+    })?;
+    let (chainstate, _) = StacksChainState::open(
+        config.is_mainnet(),
+        config.node.chain_id,
+        &chainstate_path,
+        Some(config.node.get_marf_opts()),
+    )
+    .map_err(|e| {
+        RunLoopError::Chainstate(format!("failed to open chainstate database: {:?}", &e))
+    })?;
+    let commit_fees =
+        CommitFeeDB::open_existing(&config.get_commit_fees_db_path()).map_err(|e| {
+            RunLoopError::Chainstate(format!("failed to open commit fee database: {:?}", &e))
+        })?;
+
+    let report = chainstate
+        .get_rewards_report(
+            &sortdb,
+            from_height,
+            to_height,
+            commit_fees.as_ref().map(|db| db as &dyn CommitFeeLedger),
+        )
+        .map_err(|e| RunLoopError::Chainstate(format!("failed to report rewards: {:?}", &e)))?;
+    println!(
+        "{}",
+        serde_json::to_string_pretty(&report).expect("Failed to serialize rewards report")
+    );
+    Ok(())
+}
@@ -0,0 +1,225 @@
+use std::convert::TryFrom;
+use std::fmt;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use stacks::burnchains::Txid;
+use stacks::chainstate::burn::db::sortdb::SortitionDB;
+use stacks::chainstate::stacks::db::StacksChainState;
+use stacks::chainstate::stacks::StacksTransaction;
+use stacks::codec::StacksMessageCodec;
+use stacks::core::mempool::MemPoolDB;
+use stacks::cost_estimates::metrics::UnitMetric;
+use stacks::cost_estimates::UnitEstimator;
+use stacks::deps::ctrlc as termination;
+use stacks::net::atlas::{AtlasConfig, AtlasDB, Attachment};
+use stacks::net::db::PeerDB;
+use stacks::net::p2p::PeerNetwork;
+use stacks::net::relay::Relayer;
+use stacks::net::rpc::{RPCHandlerArgs, TransactionForwardError, TransactionForwarder};
+use stacks::net::{PeerAddress, PostTransactionRequestBody};
+use stacks::util::hash::{to_hex, Sha256Sum};
+use stacks::util_lib::strings::UrlString;
+
+use crate::burnchains::burnchain_from_config;
+use crate::run_loop::RunLoopError;
+use crate::Config;
+
+/// How long to wait on the primary node when forwarding a transaction to it
+const FORWARD_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How long each pass of the RPC server waits for requests
+const POLL_TIMEOUT_MS: u64 = 1000;
+
+/// Forwards the transactions POSTed to a read-only replica to the primary node's
+/// `/v2/transactions` endpoint, and relays its answer back to the client.  Also used to forward
+/// the transactions a follower admits to its miners (see `tx_forwarder`).
+pub struct PrimaryTxForwarder {
+    rpc_url: String,
+}
+
+impl PrimaryTxForwarder {
+    pub fn new(rpc_url: &str) -> PrimaryTxForwarder {
+        PrimaryTxForwarder {
+            rpc_url: rpc_url.trim_end_matches('/').to_string(),
+        }
+    }
+}
+
+impl TransactionForwarder for PrimaryTxForwarder {
+    fn forward_transaction(
+        &self,
+        tx: &StacksTransaction,
+        attachment: Option<&Attachment>,
+        expiration_height: Option<u64>,
+        bundle_id: Option<&str>,
+    ) -> Result<Txid, TransactionForwardError> {
+        let unreachable = |e: reqwest::Error| TransactionForwardError::Unreachable(e.to_string());
+        let client = reqwest::blocking::Client::builder()
+            .timeout(FORWARD_TIMEOUT)
+            .build()
+            .map_err(unreachable)?;
+        let mut query = vec![];
+        if let Some(height) = expiration_height {
+            query.push(format!("expiration_height={}", height));
+        }
+        if let Some(bundle_id) = bundle_id {
+            query.push(format!("bundle_id={}", bundle_id));
+        }
+        let url = if query.is_empty() {
+            format!("{}/v2/transactions", &self.rpc_url)
+        } else {
+            format!("{}/v2/transactions?{}", &self.rpc_url, query.join("&"))
+        };
+        let request = match attachment {
+            Some(attachment) => client.post(&url).json(&PostTransactionRequestBody {
+                tx: to_hex(&tx.serialize_to_vec()),
+                attachment: Some(to_hex(&attachment.content)),
+            }),
+            None => client
+                .post(&url)
+                .header("Content-Type", "application/octet-stream")
+                .body(tx.serialize_to_vec()),
+        };
+        let response = request.send().map_err(unreachable)?;
+        if response.status() == reqwest::StatusCode::BAD_REQUEST {
+            let reason = response.json::<serde_json::Value>().map_err(unreachable)?;
+            return Err(TransactionForwardError::Rejected(reason));
+        }
+        let txid_hex = response
+            .error_for_status()
+            .and_then(|response| response.json::<String>())
+            .map_err(unreachable)?;
+        Txid::from_hex(&txid_hex).map_err(|_| {
+            TransactionForwardError::Unreachable(format!(
+                "primary node answered with an invalid txid `{}`",
+                txid_hex
+            ))
+        })
+    }
+}
+
+fn open_error<E: fmt::Debug>(what: &str, e: E) -> RunLoopError {
+    RunLoopError::Chainstate(format!("failed to open {}: {:?}", what, &e))
+}
+
+/// Serve the read RPC endpoints from the databases of the node whose working directory is
+/// `config.node.working_dir`, without writing to them, so that reads can be scaled
+/// independently of that node.  The chainstate, sortition DB, mempool, peer DB and Atlas DB are
+/// opened read-only, and must already have been created by the primary node.  Blocks and
+/// microblocks POSTed to this server are refused, and transactions are forwarded to
+/// `primary_rpc_url`, or refused if it is not given.  Runs until the process is signalled.
+pub fn run_rpc_only(config: &Config, primary_rpc_url: Option<&str>) -> Result<(), RunLoopError> {
+    let rpc_sock = config
+        .node
+        .get_rpc_bind_addr()
+        .map_err(RunLoopError::InvalidConfig)?;
+    // the p2p socket is never serviced, so keep it where no peer will find it
+    let p2p_sock: SocketAddr = "127.0.0.1:0".parse().unwrap();
+
+    let sortdb = SortitionDB::open(&config.get_burn_db_file_path(), false)
+        .map_err(|e| open_error("sortition database", e))?;
+    let mut chainstate = StacksChainState::open_readonly(
+        config.is_mainnet(),
+        config.node.chain_id,
+        &config.get_chainstate_path_str(),
+        Some(config.node.get_marf_opts()),
+    )
+    .map_err(|e| open_error("chainstate", e))?;
+    chainstate.set_account_index(config.node.account_index);
+    let mut mempool = MemPoolDB::open_readonly(
+        &config.get_chainstate_path_str(),
+        Box::new(UnitEstimator),
+        Box::new(UnitMetric),
+    )
+    .map_err(|e| open_error("mempool", e))?;
+
+    let peerdb = PeerDB::connect(
+        &config.get_peer_db_file_path(),
+        false,
+        config.node.network_id,
+        config.burnchain.network_id,
+        None,
+        config.connection_options.private_key_lifetime,
+        PeerAddress::from_socketaddr(&p2p_sock),
+        p2p_sock.port(),
+        UrlString::try_from(config.node.data_url.clone())
+            .map_err(|e| RunLoopError::InvalidConfig(format!("[node] data_url: {:?}", &e)))?,
+        &vec![],
+        None,
+    )
+    .map_err(|e| open_error("peer database", e))?;
+    let atlasdb = AtlasDB::connect(
+        AtlasConfig::default(config.is_mainnet()),
+        &config.get_atlas_db_file_path(),
+        false,
+    )
+    .map_err(|e| open_error("Atlas database", e))?;
+    let local_peer =
+        PeerDB::get_local_peer(peerdb.conn()).map_err(|e| open_error("local peer", e))?;
+
+    let burnchain = burnchain_from_config(&config.get_burn_db_path(), &config.burnchain)
+        .map_err(|e| RunLoopError::InvalidConfig(format!("invalid burnchain: {:?}", &e)))?;
+    let epochs = SortitionDB::get_stacks_epochs(sortdb.conn())
+        .map_err(|e| open_error("Stacks epochs", e))?;
+    let view = SortitionDB::get_canonical_burn_chain_tip(sortdb.conn())
+        .and_then(|tip| SortitionDB::get_burnchain_view(sortdb.conn(), &burnchain, &tip))
+        .map_err(|e| open_error("burnchain view", e))?;
+
+    let mut network = PeerNetwork::new(
+        peerdb,
+        atlasdb,
+        local_peer,
+        config.burnchain.peer_version,
+        burnchain,
+        view,
+        config.connection_options.clone(),
+        epochs,
+    );
+    network.bind(&p2p_sock, &rpc_sock).map_err(|e| {
+        RunLoopError::InvalidConfig(format!("failed to bind {}: {:?}", rpc_sock, &e))
+    })?;
+
+    let tx_forwarder = primary_rpc_url.map(PrimaryTxForwarder::new);
+    let handler_args = RPCHandlerArgs {
+        genesis_chainstate_hash: Sha256Sum::from_hex(stx_genesis::GENESIS_CHAINSTATE_HASH).unwrap(),
+        read_only: true,
+        tx_forwarder: tx_forwarder
+            .as_ref()
+            .map(|forwarder| forwarder as &dyn TransactionForwarder),
+        ..RPCHandlerArgs::default()
+    };
+
+    let should_keep_running = Arc::new(AtomicBool::new(true));
+    let keep_running_writer = should_keep_running.clone();
+    if let Err(e) = termination::set_handler(move |_sig_id| {
+        keep_running_writer.store(false, Ordering::SeqCst);
+    }) {
+        warn!("Failed to set termination handler: {}", e);
+    }
+
+    info!(
+        "Serving read-only RPC from {} on {}",
+        &config.node.working_dir, &rpc_sock;
+        "primary" => primary_rpc_url.unwrap_or("none; transactions are refused")
+    );
+    while should_keep_running.load(Ordering::SeqCst) {
+        // pick up microblocks the primary has processed since the last pass
+        if let Err(e) = Relayer::setup_unconfirmed_state_readonly(&mut chainstate, &sortdb) {
+            debug!("Failed to load unconfirmed state: {:?}", &e);
+        }
+        if let Err(e) = network.run_http_only(
+            &sortdb,
+            &mut chainstate,
+            &mut mempool,
+            POLL_TIMEOUT_MS,
+            &handler_args,
+        ) {
+            warn!("Failed to serve RPC requests: {:?}", &e);
+        }
+    }
+    info!("Exiting read-only RPC server");
+    Ok(())
+}
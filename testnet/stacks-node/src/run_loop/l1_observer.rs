@@ -1,9 +1,19 @@
+//! The L1 event observer: a `warp` server which the L1 node's event dispatcher delivers its
+//! blocks to.  Receiving a block and indexing it happen on separate threads, joined by a bounded
+//! queue: the server only parses each block and queues it, and the `l1-block-processor` thread
+//! indexes the queued blocks in order.  A slow index write therefore doesn't hold up the L1's
+//! deliveries until the queue fills, at which point the server stops acknowledging them, so the
+//! L1 node holds on to its blocks and retries rather than the node buffering without bound.
+
 use std::convert::Infallible;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
+use std::time::Instant;
 
 use stacks::burnchains::events::NewBlock;
 use stacks::burnchains::indexer::BurnchainChannel;
 use std::thread;
+use tokio::sync::mpsc;
 use tokio::sync::oneshot;
 use tokio::sync::oneshot::Receiver;
 use tokio::sync::oneshot::Sender;
@@ -11,30 +21,72 @@ use tokio::task::JoinError;
 use warp;
 use warp::Filter;
 
-/// Adds in `channel` to downstream functions.
-fn with_db(
-    channel: Arc<dyn BurnchainChannel>,
-) -> impl Filter<Extract = (Arc<dyn BurnchainChannel>,), Error = std::convert::Infallible> + Clone {
-    warp::any().map(move || channel.clone())
+/// The sending half of the queue of received blocks, and the number of blocks in it
+#[derive(Clone)]
+struct BlockQueue {
+    sender: mpsc::Sender<NewBlock>,
+    depth: Arc<AtomicU64>,
+}
+
+/// Adds in `queue` to downstream functions.
+fn with_queue(
+    queue: BlockQueue,
+) -> impl Filter<Extract = (BlockQueue,), Error = std::convert::Infallible> + Clone {
+    warp::any().map(move || queue.clone())
 }
 
 /// Route handler.
 async fn handle_new_block(
     block: serde_json::Value,
-    channel: Arc<dyn BurnchainChannel>,
+    queue: BlockQueue,
 ) -> Result<impl warp::Reply, Infallible> {
+    let received_at = Instant::now();
     let parsed_block: NewBlock =
         serde_json::from_str(&block.to_string()).expect("Failed to parse events JSON");
-    info!("handle_new_block receives new block {:?}", &parsed_block);
-    match channel.push_block(parsed_block) {
-        Ok(_) => {}
-        // TODO: It might be possible to return an error from this method for more graceful
-        // failure.
-        Err(e) => panic!("error {:?}", &e),
-    };
+    component_info!(
+        Burnchain,
+        "handle_new_block receives new block {:?}",
+        &parsed_block
+    );
+    // waits for room in the queue, so the L1 node isn't answered until the block is queued
+    if queue.sender.send(parsed_block).await.is_err() {
+        component_error!(Burnchain, "L1 block processor has stopped");
+        return Ok(warp::http::StatusCode::SERVICE_UNAVAILABLE);
+    }
+    let depth = queue.depth.fetch_add(1, Ordering::SeqCst) + 1;
+    stacks::monitoring::update_l1_observer_queue_depth(depth);
+    stacks::monitoring::update_l1_block_fetch_latency(received_at.elapsed().as_secs_f64());
     Ok(warp::http::StatusCode::OK)
 }
 
+/// Index the blocks in the queue, in the order they were received, until the server shuts down.
+fn process_blocks(
+    mut receiver: mpsc::Receiver<NewBlock>,
+    depth: Arc<AtomicU64>,
+    channel: Arc<dyn BurnchainChannel>,
+) {
+    while let Some(block) = receiver.blocking_recv() {
+        let remaining = depth.fetch_sub(1, Ordering::SeqCst).saturating_sub(1);
+        stacks::monitoring::update_l1_observer_queue_depth(remaining);
+        let block_height = block.block_height;
+        let process_start = Instant::now();
+        match channel.push_block(block) {
+            Ok(_) => {}
+            // TODO: It might be possible to return an error from this method for more graceful
+            // failure.
+            Err(e) => panic!("error {:?}", &e),
+        };
+        component_debug!(
+            Burnchain,
+            "Indexed L1 block";
+            "block_height" => block_height,
+            "process_time_ms" => process_start.elapsed().as_millis() as u64,
+            "queue_depth" => remaining
+        );
+    }
+    component_info!(Burnchain, "L1 block processor stopped");
+}
+
 async fn handle_any() -> Result<impl warp::Reply, Infallible> {
     Ok(warp::http::StatusCode::OK)
 }
@@ -42,13 +94,13 @@ async fn handle_any() -> Result<impl warp::Reply, Infallible> {
 /// Define and run the `warp` server.
 async fn serve(
     signal_receiver: Receiver<()>,
-    channel: Arc<dyn BurnchainChannel>,
+    queue: BlockQueue,
     observer_port: u16,
 ) -> Result<(), JoinError> {
     let new_blocks = warp::path!("new_block")
         .and(warp::post())
         .and(warp::body::json())
-        .and(with_db(channel))
+        .and(with_queue(queue))
         .and_then(handle_new_block);
 
     // create a fall-through handler so that if any of the
@@ -56,27 +108,192 @@ async fn serve(
     // to the dispatcher
     let all = new_blocks.or(warp::post().and_then(handle_any));
 
-    info!("Binding warp server.");
+    component_info!(Burnchain, "Binding warp server.");
     let (_addr, server) =
         warp::serve(all).bind_with_graceful_shutdown(([0, 0, 0, 0], observer_port), async {
             signal_receiver.await.ok();
         });
 
     // Spawn the server into a runtime
-    info!("Spawning warp server");
+    component_info!(Burnchain, "Spawning warp server");
     tokio::task::spawn(server).await
 }
 
-/// Spawn a thread with a `warp` server.
-pub fn spawn(channel: Arc<dyn BurnchainChannel>, observer_port: u16) -> Sender<()> {
+/// Spawn a thread with a `warp` server, and a thread which pushes the blocks it receives into
+/// `channel`.  At most `queue_size` received blocks wait to be pushed.
+pub fn spawn(
+    channel: Arc<dyn BurnchainChannel>,
+    observer_port: u16,
+    queue_size: usize,
+) -> Sender<()> {
     let (signal_sender, signal_receiver) = oneshot::channel();
+    let (block_sender, block_receiver) = mpsc::channel(queue_size.max(1));
+    let depth = Arc::new(AtomicU64::new(0));
+    let queue = BlockQueue {
+        sender: block_sender,
+        depth: depth.clone(),
+    };
+
+    // stops once the server does, and drops the queue's sender
+    thread::Builder::new()
+        .name("l1-block-processor".into())
+        .spawn(move || process_blocks(block_receiver, depth, channel))
+        .expect("`spawn` has failed.");
     thread::Builder::new()
         .name("l1-observer".into())
         .spawn(move || {
             let rt = tokio::runtime::Runtime::new().expect("Failed to initialize tokio");
-            rt.block_on(serve(signal_receiver, channel, observer_port))
+            rt.block_on(serve(signal_receiver, queue, observer_port))
                 .expect("block_on failed");
         })
         .expect("`spawn` has failed.");
     signal_sender
 }
+
+#[cfg(test)]
+mod test {
+    use std::net::TcpListener;
+    use std::sync::mpsc as std_mpsc;
+    use std::sync::Mutex;
+    use std::time::Duration;
+
+    use stacks::types::chainstate::StacksBlockId;
+
+    use super::*;
+
+    /// Records the heights of the blocks pushed into it.  If it has a gate, each push first
+    /// waits for the gate to be opened once, or dropped.
+    struct TestChannel {
+        gate: Option<Mutex<std_mpsc::Receiver<()>>>,
+        pushed: Mutex<Vec<u64>>,
+    }
+
+    impl BurnchainChannel for TestChannel {
+        fn push_block(&self, new_block: NewBlock) -> Result<(), stacks::burnchains::Error> {
+            if let Some(ref gate) = self.gate {
+                let _ = gate.lock().unwrap().recv();
+            }
+            self.pushed.lock().unwrap().push(new_block.block_height);
+            Ok(())
+        }
+    }
+
+    fn make_block(block_height: u64) -> NewBlock {
+        NewBlock {
+            block_height,
+            burn_block_time: 0,
+            index_block_hash: StacksBlockId([block_height as u8; 32]),
+            parent_index_block_hash: StacksBlockId([block_height as u8 - 1; 32]),
+            events: vec![],
+        }
+    }
+
+    fn block_json(block_height: u64) -> serde_json::Value {
+        serde_json::json!({
+            "block_height": block_height,
+            "burn_block_time": 0,
+            "index_block_hash": format!("0x{}", StacksBlockId([block_height as u8; 32])),
+            "parent_index_block_hash": format!("0x{}", StacksBlockId([block_height as u8 - 1; 32])),
+            "events": [],
+        })
+    }
+
+    /// Wait for the block at `block_height` to be pushed, and return the heights pushed so far
+    fn wait_for_pushed(channel: &TestChannel, block_height: u64) -> Vec<u64> {
+        let deadline = Instant::now() + Duration::from_secs(30);
+        loop {
+            let pushed = channel.pushed.lock().unwrap().clone();
+            if pushed.contains(&block_height) {
+                return pushed;
+            }
+            assert!(
+                Instant::now() < deadline,
+                "Timed out waiting for blocks to be pushed"
+            );
+            thread::sleep(Duration::from_millis(100));
+        }
+    }
+
+    #[test]
+    fn test_process_blocks_in_order() {
+        let (sender, receiver) = mpsc::channel(4);
+        let depth = Arc::new(AtomicU64::new(0));
+        for block_height in 1..=4 {
+            sender.try_send(make_block(block_height)).unwrap();
+            depth.fetch_add(1, Ordering::SeqCst);
+        }
+        // the queue is bounded
+        assert!(sender.try_send(make_block(5)).is_err());
+        drop(sender);
+
+        let channel = Arc::new(TestChannel {
+            gate: None,
+            pushed: Mutex::new(vec![]),
+        });
+        // returns once the queue's sender is gone and the queue is drained
+        process_blocks(receiver, depth.clone(), channel.clone());
+        assert_eq!(*channel.pushed.lock().unwrap(), vec![1, 2, 3, 4]);
+        assert_eq!(depth.load(Ordering::SeqCst), 0);
+    }
+
+    #[test]
+    fn test_observer_backpressure() {
+        let observer_port = TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let (gate_sender, gate_receiver) = std_mpsc::channel();
+        let channel = Arc::new(TestChannel {
+            gate: Some(Mutex::new(gate_receiver)),
+            pushed: Mutex::new(vec![]),
+        });
+        let stop = spawn(channel.clone(), observer_port, 1);
+
+        let url = format!("http://127.0.0.1:{}/new_block", observer_port);
+        let client = reqwest::blocking::Client::builder()
+            .timeout(Duration::from_secs(2))
+            .build()
+            .unwrap();
+        let post = |block_height| client.post(&url).json(&block_json(block_height)).send();
+
+        // wait for the server to come up.  Events other than new blocks are acknowledged and
+        // dropped.
+        let deadline = Instant::now() + Duration::from_secs(30);
+        while client
+            .post(&format!(
+                "http://127.0.0.1:{}/new_burn_block",
+                observer_port
+            ))
+            .json(&serde_json::json!({}))
+            .send()
+            .is_err()
+        {
+            assert!(
+                Instant::now() < deadline,
+                "Timed out waiting for the observer"
+            );
+            thread::sleep(Duration::from_millis(100));
+        }
+
+        // the processor holds block 1 and the queue holds block 2...
+        assert_eq!(post(1).unwrap().status(), reqwest::StatusCode::OK);
+        assert_eq!(post(2).unwrap().status(), reqwest::StatusCode::OK);
+        // ...so block 3 isn't acknowledged until the processor catches up
+        assert!(post(3).is_err());
+        assert!(channel.pushed.lock().unwrap().is_empty());
+
+        // open the gate for good
+        drop(gate_sender);
+        assert_eq!(wait_for_pushed(&channel, 2)[..2], [1, 2]);
+
+        // and blocks are acknowledged again, and processed in the order they were received.
+        // Block 3 may or may not have been queued once its request was abandoned.
+        assert_eq!(post(4).unwrap().status(), reqwest::StatusCode::OK);
+        let pushed = wait_for_pushed(&channel, 4);
+        assert_eq!(pushed[..2], [1, 2]);
+        assert_eq!(pushed.last(), Some(&4));
+
+        stop.send(()).unwrap();
+    }
+}
@@ -1,6 +1,9 @@
 pub mod l1_observer;
 pub mod neon;
 
+use std::fmt;
+
+use crate::burnchains::Error as BurnchainControllerError;
 use crate::{BurnchainController, BurnchainTip, ChainTip};
 
 use stacks::chainstate::stacks::db::StacksChainState;
@@ -129,3 +132,48 @@ pub struct RegisteredKey {
     pub op_vtxindex: u32,
     pub vrf_public_key: VRFPublicKey,
 }
+
+/// Why the node could not start, or stopped.  Each message says what to fix.
+#[derive(Debug)]
+pub enum RunLoopError {
+    /// The config file could not be read, or one of its settings is missing or invalid
+    InvalidConfig(String),
+    /// The L1 node's RPC interface could not be reached
+    L1Unreachable(String, String),
+    /// The burnchain controller failed
+    Burnchain(BurnchainControllerError),
+    /// The chainstate databases could not be opened or upgraded
+    Chainstate(String),
+    /// `start()` was called more than once
+    AlreadyStarted,
+    /// The block relayer and miner thread stopped while the run loop was still running
+    RelayerHungUp,
+}
+
+impl fmt::Display for RunLoopError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RunLoopError::InvalidConfig(ref msg) => write!(f, "Invalid configuration: {}", msg),
+            RunLoopError::L1Unreachable(ref url, ref msg) => write!(
+                f,
+                "Could not reach the L1 node's RPC interface at {} ({}). Check `peer_host` and `rpc_port` (or `rpc_endpoints`) and `rpc_ssl` in the [burnchain] config section, and that the L1 node is running",
+                url, msg
+            ),
+            RunLoopError::Burnchain(ref e) => write!(f, "Burnchain error: {}", e),
+            RunLoopError::Chainstate(ref msg) => write!(f, "Chainstate error: {}", msg),
+            RunLoopError::AlreadyStarted => {
+                write!(f, "Run loop already started, can only start once after initialization")
+            }
+            RunLoopError::RelayerHungUp => write!(
+                f,
+                "The block relayer and miner thread stopped unexpectedly; the log above says why"
+            ),
+        }
+    }
+}
+
+impl From<BurnchainControllerError> for RunLoopError {
+    fn from(e: BurnchainControllerError) -> RunLoopError {
+        RunLoopError::Burnchain(e)
+    }
+}
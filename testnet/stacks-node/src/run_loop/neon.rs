@@ -6,11 +6,18 @@ use std::sync::atomic::AtomicU64;
 
 use std::sync::mpsc::sync_channel;
 use std::sync::mpsc::Receiver;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 use std::thread;
 use std::thread::JoinHandle;
 
 use std::collections::HashSet;
+use std::fs;
+use std::net::ToSocketAddrs;
+use std::path::PathBuf;
+use std::process;
+use std::time::Duration;
+
+use rusqlite::{OpenFlags, NO_PARAMS};
 
 use stacks::deps::ctrlc as termination;
 use stacks::deps::ctrlc::SignalId;
@@ -25,9 +32,19 @@ use stacks::chainstate::coordinator::{
 use stacks::chainstate::stacks::db::{ChainStateBootData, StacksChainState};
 use stacks::net::atlas::ATTACHMENTS_CHANNEL_SIZE;
 use stacks::net::atlas::{AtlasConfig, AttachmentInstance};
+use stacks::util_lib::db::sqlite_open;
 use stacks::util_lib::db::Error as db_error;
 use tokio::sync::oneshot::Sender;
 
+use crate::burnchains::l1_events::check_l1_connection;
+use crate::burnchains::l1_rpc::L1RpcFailover;
+use crate::burnchains::stale_tip::StaleTipWatchdog;
+use crate::burnchains::Error as BurnchainControllerError;
+use crate::config::SUBNET_SUBDIR_NAME;
+use crate::event_stream;
+use crate::event_stream::EventStream;
+use crate::mempool_io;
+use crate::mining_key_rotation::MiningKeySchedule;
 use crate::run_loop::l1_observer;
 
 use crate::monitoring::start_serving_monitoring_metrics;
@@ -35,7 +52,7 @@ use crate::neon_node::StacksNode;
 use crate::syncctl::{PoxSyncWatchdog, PoxSyncWatchdogComms};
 use crate::{BurnchainController, Config, EventDispatcher};
 
-use super::RunLoopCallbacks;
+use super::{RunLoopCallbacks, RunLoopError};
 use libc;
 pub const STDERR: i32 = 2;
 pub const SORTITION_PROCESS_INCREMENTS: u64 = 1000;
@@ -126,10 +143,17 @@ pub struct RunLoop {
     counters: Counters,
     coordinator_channels: Option<(CoordinatorReceivers, CoordinatorChannels)>,
     should_keep_running: Arc<AtomicBool>,
+    /// set while the miner is paused by the admin RPC
+    miner_paused: Arc<AtomicBool>,
+    /// the mining key and any scheduled rotation of it, if `[node] mining_key` is set
+    mining_key_schedule: Option<Arc<Mutex<MiningKeySchedule>>>,
     event_dispatcher: EventDispatcher,
     pox_watchdog: Option<PoxSyncWatchdog>, // can't be instantiated until .start() is called
     is_miner: Option<bool>,                // not known until .start() is called
     burnchain: Option<Burnchain>,          // not known until .start() is called
+    /// where `config` was loaded from, if anywhere.  Event observers are re-read from here on
+    /// SIGHUP.
+    config_path: Option<String>,
 }
 
 /// Write to stderr in an async-safe manner.
@@ -156,10 +180,23 @@ impl RunLoop {
         let channels = CoordinatorCommunication::instantiate();
         let should_keep_running = Arc::new(AtomicBool::new(true));
 
-        let mut event_dispatcher = EventDispatcher::new();
+        let mut event_dispatcher = if config.node.event_queue {
+            EventDispatcher::new_with_queue(&config.get_event_queue_db_file_path())
+        } else {
+            EventDispatcher::new()
+        };
         for observer in config.events_observers.iter() {
             event_dispatcher.register_observer(observer);
         }
+        if config.node.ws_bind.is_some() {
+            event_dispatcher.set_event_stream(EventStream::new());
+        }
+
+        let mining_key_schedule = config
+            .node
+            .mining_key
+            .clone()
+            .map(|key| Arc::new(Mutex::new(MiningKeySchedule::new(key))));
 
         Self {
             config,
@@ -167,13 +204,22 @@ impl RunLoop {
             callbacks: RunLoopCallbacks::new(),
             counters: Counters::new(),
             should_keep_running: should_keep_running,
+            miner_paused: Arc::new(AtomicBool::new(false)),
+            mining_key_schedule,
             event_dispatcher,
             pox_watchdog: None,
             is_miner: None,
             burnchain: None,
+            config_path: None,
         }
     }
 
+    /// Remember the path the config was loaded from, so that the event observers can be
+    /// reloaded from it on SIGHUP.
+    pub fn set_config_path(&mut self, config_path: String) {
+        self.config_path = Some(config_path);
+    }
+
     pub fn get_coordinator_channel(&self) -> Option<CoordinatorChannels> {
         self.coordinator_channels.as_ref().map(|x| x.1.clone())
     }
@@ -225,6 +271,22 @@ impl RunLoop {
         self.should_keep_running.clone()
     }
 
+    /// While this is set, the miner does not run tenures or submit block-commits.
+    pub fn get_miner_pause_switch(&self) -> Arc<AtomicBool> {
+        self.miner_paused.clone()
+    }
+
+    /// The mining key schedule shared by the key-rotation RPC and the relayer, if the node
+    /// mines with a configured `[node] mining_key`.
+    pub fn get_mining_key_schedule(&self) -> Option<Arc<Mutex<MiningKeySchedule>>> {
+        self.mining_key_schedule.clone()
+    }
+
+    /// Where the config was loaded from, if anywhere.
+    pub fn get_config_path(&self) -> Option<String> {
+        self.config_path.clone()
+    }
+
     pub fn get_burnchain(&self) -> Burnchain {
         self.burnchain
             .clone()
@@ -238,9 +300,14 @@ impl RunLoop {
     }
 
     /// Set up termination handler.  Have a signal set the `should_keep_running` atomic bool to
-    /// false.  Panics of called more than once.
+    /// false, and exit the process if the node hasn't shut down within the configured grace
+    /// period.  SIGHUP instead reloads the event observers from the config file, if there is
+    /// one.  Panics of called more than once.
     fn setup_termination_handler(&self) {
         let keep_running_writer = self.should_keep_running.clone();
+        let grace_period_secs = self.config.node.shutdown_grace_period_secs;
+        let config_path = self.config_path.clone();
+        let event_dispatcher = self.event_dispatcher.clone();
         let install = termination::set_handler(move |sig_id| match sig_id {
             SignalId::Bus => {
                 let msg = "Caught SIGBUS; crashing immediately and dumping core\n";
@@ -249,10 +316,37 @@ impl RunLoop {
                     libc::abort();
                 }
             }
+            SignalId::Hangup => {
+                // this runs on the signal handling thread, not in the signal handler itself
+                let config_path = match config_path.as_ref() {
+                    Some(path) => path,
+                    None => {
+                        warn!("Caught SIGHUP, but the node was not started from a config file; ignoring");
+                        return;
+                    }
+                };
+                info!("Caught SIGHUP; reloading event observers"; "config_path" => config_path);
+                match Config::events_observers_from_path(config_path) {
+                    Ok(observers) => event_dispatcher.reload_observers(&observers),
+                    Err(e) => {
+                        error!("Failed to reload event observers; keeping the current ones"; "error" => e)
+                    }
+                }
+            }
             _ => {
                 let msg = format!("Graceful termination request received (signal `{}`), will complete the ongoing runloop cycles and terminate\n", sig_id);
                 async_safe_write_stderr(&msg);
-                keep_running_writer.store(false, Ordering::SeqCst);
+                // only the first signal starts the grace period
+                if keep_running_writer.swap(false, Ordering::SeqCst) && grace_period_secs > 0 {
+                    thread::spawn(move || {
+                        thread::sleep(Duration::from_secs(grace_period_secs));
+                        error!(
+                            "Node did not shut down within the grace period; exiting anyway";
+                            "shutdown_grace_period_secs" => grace_period_secs
+                        );
+                        process::exit(1);
+                    });
+                }
             }
         });
 
@@ -271,25 +365,87 @@ impl RunLoop {
         if self.config.node.miner {
             info!("Will run as a Miner node");
             true
+        } else if self.config.node.follower {
+            info!("Will run as a Follower node, processing only L2 data committed on the L1");
+            false
         } else {
             info!("Will run as a Follower node");
             false
         }
     }
 
+    /// Check that the L1 node this node follows is reachable, that the subnet contract is
+    /// deployed on it, and that `first_burn_header_height` is not past its chain tip.
+    /// This is a no-op for mocked burnchains.
+    pub fn check_l1(config: &Config) -> Result<(), RunLoopError> {
+        if !config.burnchain.spawn_l1_observer() {
+            return Ok(());
+        }
+        let mut l1_check = check_l1_connection(&config.burnchain);
+        if let Err(BurnchainControllerError::RPCError(_)) = l1_check {
+            // with several L1 endpoints configured, start on any one of them that is usable
+            if config.burnchain.get_rpc_urls().len() > 1
+                && L1RpcFailover::new(&config.burnchain).check(None).is_ok()
+            {
+                l1_check = check_l1_connection(&config.burnchain);
+            }
+        }
+        let l1_tip_height =
+            l1_check.map_err(|e| RunLoop::l1_check_error(config.burnchain.get_rpc_url(), e))?;
+        if config.burnchain.first_burn_header_height > l1_tip_height {
+            return Err(RunLoopError::InvalidConfig(format!(
+                "[burnchain] first_burn_header_height is {}, but the L1 chain tip is at height {}; set it to the L1 height at which the subnet contract was deployed",
+                config.burnchain.first_burn_header_height, l1_tip_height
+            )));
+        }
+        Ok(())
+    }
+
+    /// Say what to fix, given why `check_l1_connection()` against the L1 node at `rpc_url` failed.
+    fn l1_check_error(rpc_url: String, e: BurnchainControllerError) -> RunLoopError {
+        match e {
+            BurnchainControllerError::RPCError(e) => RunLoopError::L1Unreachable(rpc_url, e),
+            BurnchainControllerError::BurnchainContractCheck(e)
+            | BurnchainControllerError::BurnchainContractInterface(e) => {
+                RunLoopError::InvalidConfig(format!("{}; check [burnchain] contract_identifier", e))
+            }
+            e => e.into(),
+        }
+    }
+
+    /// Before the run loop starts, make sure that the L1 contract the node is configured to
+    /// follow can be the subnet contract.  Only a contract which certainly can't stops the node:
+    /// the L1 node may still be booting, or the contract not deployed yet, which the burnchain
+    /// controller waits out, so those are only reported.
+    fn preflight_l1_contract(config: &Config) -> Result<(), RunLoopError> {
+        if !config.burnchain.spawn_l1_observer() {
+            return Ok(());
+        }
+        match check_l1_connection(&config.burnchain) {
+            Ok(_) => Ok(()),
+            Err(BurnchainControllerError::BurnchainContractInterface(e)) => {
+                Err(RunLoopError::InvalidConfig(format!(
+                    "{}; check [burnchain] contract_identifier",
+                    e
+                )))
+            }
+            Err(e) => {
+                warn!("L1 preflight check failed, will keep retrying: {}", e);
+                Ok(())
+            }
+        }
+    }
+
     /// Instantiate the burnchain client and databases.
     /// Fetches headers and instantiates the burnchain.
-    /// Panics on failure.
     fn instantiate_burnchain_state(
         &mut self,
         _burnchain_opt: Option<Burnchain>,
         coordinator_senders: CoordinatorChannels,
-    ) -> (Box<dyn BurnchainController>, Option<Sender<()>>) {
+    ) -> Result<(Box<dyn BurnchainController>, Option<Sender<()>>), RunLoopError> {
         // Initialize and start the burnchain.
-        let mut burnchain_controller = self
-            .config
-            .make_burnchain_controller(coordinator_senders)
-            .expect("couldn't create burnchain controller");
+        let mut burnchain_controller =
+            self.config.make_burnchain_controller(coordinator_senders)?;
 
         info!(
             "Should we span an L1 observer? self.config.burnchain.spawn_l1_observer(): {}",
@@ -299,6 +455,7 @@ impl RunLoop {
             Some(l1_observer::spawn(
                 burnchain_controller.get_channel(),
                 self.config.burnchain.observer_port,
+                self.config.burnchain.observer_queue_size,
             ))
         } else {
             None
@@ -314,13 +471,15 @@ impl RunLoop {
         ) {
             Ok(_) => {}
             Err(coord_error::DBError(db_error::TooOldForEpoch)) => {
-                error!(
-                    "FATAL: chainstate database(s) are not compatible with the current system epoch"
-                );
-                panic!();
+                return Err(RunLoopError::Chainstate(
+                    "chainstate database(s) are not compatible with the current system epoch; resync from an empty working_dir".to_string(),
+                ));
             }
             Err(e) => {
-                panic!("FATAL: unable to query filesystem or databases: {:?}", &e);
+                return Err(RunLoopError::Chainstate(format!(
+                    "unable to query filesystem or databases: {:?}",
+                    &e
+                )));
             }
         }
 
@@ -329,8 +488,9 @@ impl RunLoop {
         let burnchain_config = burnchain_controller.get_burnchain();
         let target_burnchain_block_height = match burnchain_config
             .get_highest_burnchain_block()
-            .expect("FATAL: failed to access burnchain database")
-        {
+            .map_err(|e| {
+                RunLoopError::Chainstate(format!("failed to access burnchain database: {:?}", &e))
+            })? {
             Some(burnchain_tip) => {
                 // database exists already, and has blocks -- just sync to its tip.
                 let target_height = burnchain_tip.block_height + 1;
@@ -345,23 +505,57 @@ impl RunLoop {
             }
         };
 
-        match burnchain_controller.start(Some(target_burnchain_block_height)) {
-            Ok(_) => {}
-            Err(e) => {
-                error!("Burnchain controller stopped: {}", e);
-                panic!();
-            }
-        };
+        burnchain_controller.start(Some(target_burnchain_block_height))?;
 
         // if the chainstate DBs don't exist, this will instantiate them
-        if let Err(e) = burnchain_controller.connect_dbs() {
-            error!("Failed to connect to burnchain databases: {}", e);
-            panic!();
-        };
+        burnchain_controller.connect_dbs()?;
 
         // TODO (hack) instantiate the sortdb in the burnchain
         let _ = burnchain_controller.sortdb_mut();
-        (burnchain_controller, l1_observer_signal)
+        Ok((burnchain_controller, l1_observer_signal))
+    }
+
+    /// Make the data which boots a new chainstate for this config.  Every chainstate booted from
+    /// the same config has the same genesis state.
+    pub fn make_boot_data(config: &Config, burnchain_config: &Burnchain) -> ChainStateBootData {
+        ChainStateBootData {
+            initial_balances: config.get_initial_balances(),
+            post_flight_callback: None,
+            first_burnchain_block_hash: burnchain_config.first_block_hash,
+            first_burnchain_block_height: burnchain_config.first_block_height as u32,
+            first_burnchain_block_timestamp: burnchain_config.first_block_timestamp,
+            pox_constants: burnchain_config.pox_constants.clone(),
+            get_bulk_initial_lockups: None,
+            get_bulk_initial_balances: None,
+            get_bulk_initial_namespaces: None,
+            get_bulk_initial_names: None,
+            coinbase_schedule: config.node.get_coinbase_schedule(),
+            deposit_confirmations: config.burnchain.deposit_confirmations,
+            withdrawal_policy: config.node.get_withdrawal_policy(),
+            feature_activations: config.node.get_feature_activations(),
+            check_genesis_root_hash: true,
+        }
+    }
+
+    /// Re-admit the transactions the node persisted when it last shut down, if
+    /// `mempool_persist_path` is set.  The file is removed once it is imported, so a node which
+    /// then crashes does not re-import a stale list on its next boot.
+    fn import_persisted_mempool(&self) {
+        let path = match self.config.node.mempool_persist_path {
+            Some(ref path) => path,
+            None => return,
+        };
+        if fs::metadata(path).is_err() {
+            return;
+        }
+        match mempool_io::import_mempool(&self.config, path) {
+            Ok(_) => {
+                if let Err(e) = fs::remove_file(path) {
+                    warn!("Failed to remove imported mempool file {}: {:?}", path, &e);
+                }
+            }
+            Err(e) => warn!("Failed to import persisted mempool: {}", e),
+        }
     }
 
     /// Instantiate the Stacks chain state and start the chains coordinator thread.
@@ -371,36 +565,109 @@ impl RunLoop {
         &mut self,
         burnchain_config: &Burnchain,
         coordinator_receivers: CoordinatorReceivers,
-    ) -> (JoinHandle<()>, Receiver<HashSet<AttachmentInstance>>) {
-        // load up genesis balances
-        let initial_balances = self.config.get_initial_balances();
-
+    ) -> Result<(JoinHandle<()>, Receiver<HashSet<AttachmentInstance>>), RunLoopError> {
         // load up genesis Atlas attachments
         let mut atlas_config = AtlasConfig::default(self.config.is_mainnet());
         atlas_config.genesis_attachments = None;
 
         // instantiate chainstate
-        let mut boot_data = ChainStateBootData {
-            initial_balances,
-            post_flight_callback: None,
-            first_burnchain_block_hash: burnchain_config.first_block_hash,
-            first_burnchain_block_height: burnchain_config.first_block_height as u32,
-            first_burnchain_block_timestamp: burnchain_config.first_block_timestamp,
-            pox_constants: burnchain_config.pox_constants.clone(),
-            get_bulk_initial_lockups: None,
-            get_bulk_initial_balances: None,
-            get_bulk_initial_namespaces: None,
-            get_bulk_initial_names: None,
-        };
+        let mut boot_data = RunLoop::make_boot_data(&self.config, burnchain_config);
+
+        if let Some(horizon) = self.config.node.prune_horizon {
+            if fs::metadata(&self.config.get_chainstate_path_str()).is_ok() {
+                if let Err(e) =
+                    StacksChainState::prune(&self.config.get_chainstate_path_str(), horizon)
+                {
+                    warn!("Failed to prune chainstate: {:?}", &e);
+                }
+            }
+        }
 
-        let (chain_state_db, receipts) = StacksChainState::open_and_exec(
+        let (mut chain_state_db, receipts) = StacksChainState::open_and_exec(
             self.config.is_mainnet(),
             self.config.node.chain_id,
             &self.config.get_chainstate_path_str(),
             Some(&mut boot_data),
             Some(self.config.node.get_marf_opts()),
         )
-        .unwrap();
+        .map_err(|e| {
+            RunLoopError::Chainstate(format!("failed to open chainstate database: {:?}", &e))
+        })?;
+        chain_state_db.set_block_validation_threads(self.config.node.block_validation_threads);
+        chain_state_db.set_account_index(self.config.node.account_index);
+        chain_state_db.set_block_compression(self.config.node.block_compression);
+        chain_state_db.set_required_commit_confirmations(self.config.node.commit_confirmations);
+        match StacksChainState::get_coinbase_schedule(chain_state_db.db()) {
+            Ok(schedule) if schedule != self.config.node.get_coinbase_schedule() => {
+                warn!(
+                    "Configured coinbase schedule differs from the one the chainstate was booted with; using the chainstate's";
+                    "schedule" => ?schedule
+                );
+            }
+            Ok(_) => {}
+            Err(e) => warn!("Failed to load coinbase schedule: {:?}", &e),
+        }
+        match StacksChainState::get_deposit_confirmations(chain_state_db.db()) {
+            Ok(confirmations) if confirmations != self.config.burnchain.deposit_confirmations => {
+                warn!(
+                    "Configured deposit confirmations differ from those the chainstate was booted with; using the chainstate's";
+                    "deposit_confirmations" => confirmations
+                );
+            }
+            Ok(_) => {}
+            Err(e) => warn!("Failed to load deposit confirmations: {:?}", &e),
+        }
+        let configured_policy = self.config.node.get_withdrawal_policy();
+        match StacksChainState::get_recorded_withdrawal_policy(chain_state_db.db()) {
+            Ok(Some(policy)) if policy != configured_policy => {
+                warn!(
+                    "Configured withdrawal policy differs from the one the chainstate was booted with; using the chainstate's";
+                    "policy" => ?policy
+                );
+            }
+            Ok(Some(_)) => {}
+            Ok(None) => {
+                // booted before policies were recorded, so record the one it has run with
+                let recorded = chain_state_db.db_tx_begin().and_then(|tx| {
+                    StacksChainState::insert_withdrawal_policy(&tx, &configured_policy)?;
+                    tx.commit()?;
+                    Ok(())
+                });
+                if let Err(e) = recorded {
+                    return Err(RunLoopError::Chainstate(format!(
+                        "failed to record withdrawal policy: {:?}",
+                        &e
+                    )));
+                }
+                chain_state_db.set_withdrawal_policy(configured_policy);
+            }
+            Err(e) => warn!("Failed to load withdrawal policy: {:?}", &e),
+        }
+        let configured_activations = self.config.node.get_feature_activations();
+        match StacksChainState::get_recorded_feature_activations(chain_state_db.db()) {
+            Ok(Some(activations)) if activations != configured_activations => {
+                warn!(
+                    "Configured feature activation heights differ from those the chainstate was booted with; using the chainstate's";
+                    "activations" => ?activations
+                );
+            }
+            Ok(Some(_)) => {}
+            Ok(None) => {
+                // booted before activation heights were recorded, so record the configured ones
+                let recorded = chain_state_db.db_tx_begin().and_then(|tx| {
+                    StacksChainState::insert_feature_activations(&tx, &configured_activations)?;
+                    tx.commit()?;
+                    Ok(())
+                });
+                if let Err(e) = recorded {
+                    return Err(RunLoopError::Chainstate(format!(
+                        "failed to record feature activation heights: {:?}",
+                        &e
+                    )));
+                }
+            }
+            Err(e) => warn!("Failed to load feature activation heights: {:?}", &e),
+        }
         self.event_dispatcher.dispatch_boot_receipts(receipts);
 
         // NOTE: re-instantiate AtlasConfig so we don't have to keep the genesis attachments around
@@ -429,7 +696,7 @@ impl RunLoop {
             })
             .expect("FATAL: failed to start chains coordinator thread");
 
-        (coordinator_thread_handle, attachments_rx)
+        Ok((coordinator_thread_handle, attachments_rx))
     }
 
     /// Instantiate the PoX watchdog
@@ -452,6 +719,22 @@ impl RunLoop {
         }
     }
 
+    /// Serve the event dispatcher's event stream to websocket clients at `[node] ws_bind`, if
+    /// set.  Returns the signal which stops the server.
+    fn start_event_stream(&self) -> Option<Sender<()>> {
+        let ws_bind = self.config.node.ws_bind.as_ref()?;
+        let event_stream = self.event_dispatcher.get_event_stream()?;
+        let bind_addr = ws_bind
+            .to_socket_addrs()
+            .ok()
+            .and_then(|mut addrs| addrs.next())
+            .expect(&format!(
+                "FATAL: [node] ws_bind `{}` is not a valid host:port",
+                ws_bind
+            ));
+        Some(event_stream::spawn(event_stream, bind_addr))
+    }
+
     /// Get the sortition DB's highest block height
     fn get_sortition_db_height(sortdb: &SortitionDB, burnchain_config: &Burnchain) -> u64 {
         let sortition_db_height = {
@@ -477,21 +760,65 @@ impl RunLoop {
         sortition_db_height
     }
 
+    /// Move the write-ahead log of each of the node's sqlite databases into the database
+    /// itself, so that the databases are complete on their own once the node has exited.  Called
+    /// once the threads which write to them have stopped.
+    fn checkpoint_databases(&self) {
+        let mut dirs = vec![PathBuf::from(&self.config.node.working_dir).join(SUBNET_SUBDIR_NAME)];
+        while let Some(dir) = dirs.pop() {
+            let entries = match fs::read_dir(&dir) {
+                Ok(entries) => entries,
+                Err(e) => {
+                    warn!("Failed to list databases to checkpoint"; "dir" => %dir.display(), "error" => %e);
+                    continue;
+                }
+            };
+            for path in entries
+                .filter_map(|entry| entry.ok())
+                .map(|entry| entry.path())
+            {
+                if path.is_dir() {
+                    dirs.push(path);
+                    continue;
+                }
+                let db_path = match path.to_str().and_then(|path| path.strip_suffix("-wal")) {
+                    Some(db_path) => db_path.to_string(),
+                    None => continue,
+                };
+                let result = sqlite_open(&db_path, OpenFlags::SQLITE_OPEN_READ_WRITE, false)
+                    .and_then(|conn| {
+                        conn.query_row("PRAGMA wal_checkpoint(TRUNCATE)", NO_PARAMS, |_| Ok(()))
+                    });
+                match result {
+                    Ok(()) => debug!("Checkpointed database"; "path" => &db_path),
+                    Err(e) => {
+                        warn!("Failed to checkpoint database"; "path" => &db_path, "error" => ?e)
+                    }
+                }
+            }
+        }
+    }
+
     /// Starts the node runloop.
     ///
     /// This function will block by looping infinitely.
     /// It will start the burnchain (separate thread), set-up a channel in
     /// charge of coordinating the new blocks coming from the burnchain and
     /// the nodes, taking turns on tenures.  
-    pub fn start(&mut self, burnchain_opt: Option<Burnchain>, mut mine_start: u64) {
+    pub fn start(
+        &mut self,
+        burnchain_opt: Option<Burnchain>,
+        mut mine_start: u64,
+    ) -> Result<(), RunLoopError> {
+        Self::preflight_l1_contract(&self.config)?;
         let (coordinator_receivers, coordinator_senders) = self
             .coordinator_channels
             .take()
-            .expect("Run loop already started, can only start once after initialization.");
+            .ok_or(RunLoopError::AlreadyStarted)?;
 
         self.setup_termination_handler();
         let (mut burnchain, l1_observer_signal) =
-            self.instantiate_burnchain_state(burnchain_opt, coordinator_senders.clone());
+            self.instantiate_burnchain_state(burnchain_opt, coordinator_senders.clone())?;
 
         let burnchain_config = burnchain.get_burnchain();
         self.burnchain = Some(burnchain_config.clone());
@@ -501,7 +828,7 @@ impl RunLoop {
 
         // have headers; boot up the chains coordinator and instantiate the chain state
         let (coordinator_thread_handle, attachments_rx) =
-            self.spawn_chains_coordinator(&burnchain_config, coordinator_receivers);
+            self.spawn_chains_coordinator(&burnchain_config, coordinator_receivers)?;
         self.instantiate_pox_watchdog();
 
         // We announce a new burn block so that the chains coordinator
@@ -510,9 +837,9 @@ impl RunLoop {
         coordinator_senders.announce_new_burn_block();
 
         // Wait for some sortitions!
-        let mut burnchain_tip = burnchain
-            .wait_for_sortitions(None)
-            .expect("Unable to get burnchain tip");
+        let mut burnchain_tip = burnchain.wait_for_sortitions(None)?;
+
+        self.import_persisted_mempool();
 
         // Boot up the p2p network and relayer, and figure out how many sortitions we have so far
         // (it could be non-zero if the node is resuming from chainstate)
@@ -528,6 +855,7 @@ impl RunLoop {
         // Start the runloop
         debug!("Begin run loop");
         self.start_prometheus();
+        let event_stream_signal = self.start_event_stream();
         self.counters.bump_blocks_processed();
 
         let mut burnchain_height = sortition_db_height;
@@ -542,6 +870,13 @@ impl RunLoop {
             sortition_db_height
         );
 
+        // L1 blocks are pushed to the L1 observer, so if the L1 node stalls, nothing else notices
+        let mut stale_tip_watchdog = if self.config.burnchain.spawn_l1_observer() {
+            StaleTipWatchdog::new(&self.config.burnchain, burnchain.get_headers_height())
+        } else {
+            None
+        };
+
         let mut last_tenure_sortition_height = 0;
         loop {
             if !self.should_keep_running.load(Ordering::SeqCst) {
@@ -554,10 +889,21 @@ impl RunLoop {
                 coordinator_senders.stop_chains_coordinator();
                 coordinator_thread_handle.join().unwrap();
                 l1_observer_signal.map(|signal| signal.send(()).unwrap());
+                event_stream_signal.map(|signal| signal.send(()).ok());
                 node.join();
 
+                if let Some(ref path) = self.config.node.mempool_persist_path {
+                    if let Err(e) = mempool_io::export_mempool(&self.config, path) {
+                        warn!("Failed to persist mempool: {}", e);
+                    }
+                }
+                // an ephemeral working directory is about to be discarded anyway
+                if self.config.node.ephemeral_dir.is_none() {
+                    self.checkpoint_databases();
+                }
+
                 info!("Exiting stacks-node");
-                break;
+                return Ok(());
             }
 
             let remote_chain_height = burnchain.get_headers_height();
@@ -605,6 +951,15 @@ impl RunLoop {
                 burnchain_tip = next_burnchain_tip;
                 burnchain_height = cmp::min(burnchain_height + 1, tip_burnchain_height);
 
+                if let Some(ref mut watchdog) = stale_tip_watchdog {
+                    if watchdog.observe(tip_burnchain_height) {
+                        match burnchain.reconnect() {
+                            Ok(()) => info!("Reconnected to the burnchain; waiting for new blocks"),
+                            Err(e) => warn!("Failed to reconnect to the burnchain: {}", e),
+                        }
+                    }
+                }
+
                 let sortition_tip = &burnchain_tip.block_snapshot.sortition_id;
                 let next_sortition_height = burnchain_tip.block_snapshot.block_height;
 
@@ -650,7 +1005,7 @@ impl RunLoop {
                         if !node.relayer_sortition_notify() {
                             // relayer hung up, exit.
                             error!("Block relayer and miner hung up, exiting.");
-                            return;
+                            return Err(RunLoopError::RelayerHungUp);
                         }
                     }
 
@@ -706,7 +1061,7 @@ impl RunLoop {
                         if !node.relayer_issue_tenure() {
                             // relayer hung up, exit.
                             error!("Block relayer and miner hung up, exiting.");
-                            continue;
+                            return Err(RunLoopError::RelayerHungUp);
                         }
                     }
                 }
@@ -714,3 +1069,191 @@ impl RunLoop {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+
+    use stacks::chainstate::burn::ConsensusHash;
+    use stacks::core::LAYER_1_CHAIN_ID_MAINNET;
+    use stacks::net::RPCPeerInfoData;
+    use stacks::types::chainstate::BlockHeaderHash;
+    use stacks::util::hash::Sha256Sum;
+    use stacks::vm::types::QualifiedContractIdentifier;
+
+    use super::*;
+    use crate::config::BURNCHAIN_NAME_STACKS_TESTNET_L1;
+    use crate::ephemeral_dir::EphemeralWorkingDir;
+    use crate::tests::new_test_conf;
+
+    /// A config which follows a testnet L1 node at `rpc_port`, with a testnet subnet contract
+    fn make_l1_conf(rpc_port: u16) -> Config {
+        let mut conf = new_test_conf();
+        conf.burnchain.chain = BURNCHAIN_NAME_STACKS_TESTNET_L1.into();
+        conf.burnchain.peer_host = "127.0.0.1".into();
+        conf.burnchain.rpc_port = rpc_port;
+        conf.burnchain.rpc_endpoints = vec![];
+        conf.burnchain.contract_identifier = QualifiedContractIdentifier::parse(
+            "ST1PQHQKV0RJXZFY1DGX8MNSNYVE3VGZJSRTPGZGM.subnet-v3-0-1",
+        )
+        .unwrap();
+        conf
+    }
+
+    #[test]
+    fn test_check_l1_unreachable() {
+        // find a port nothing listens on
+        let rpc_port = TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let conf = make_l1_conf(rpc_port);
+
+        match RunLoop::check_l1(&conf) {
+            Err(RunLoopError::L1Unreachable(url, _)) => {
+                assert_eq!(url, conf.burnchain.get_rpc_url());
+            }
+            res => panic!("Expected L1Unreachable, got {:?}", res),
+        }
+    }
+
+    #[test]
+    fn test_check_l1_wrong_network() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let conf = make_l1_conf(listener.local_addr().unwrap().port());
+
+        // the L1 node is a mainnet node
+        let info = RPCPeerInfoData {
+            peer_version: 0,
+            pox_consensus: ConsensusHash([0; 20]),
+            burn_block_height: 10,
+            stable_pox_consensus: ConsensusHash([0; 20]),
+            stable_burn_block_height: 10,
+            server_version: "test".into(),
+            network_id: LAYER_1_CHAIN_ID_MAINNET,
+            parent_network_id: 0,
+            stacks_tip_height: 10,
+            stacks_tip: BlockHeaderHash([0; 32]),
+            stacks_tip_consensus_hash: ConsensusHash([0; 20]),
+            genesis_chainstate_hash: Sha256Sum::from_data(&[]),
+            unanchored_tip: None,
+            unanchored_seq: None,
+            exit_at_block_height: None,
+            node_public_key: None,
+            node_public_key_hash: None,
+            l1_subnet_governing_contract: "".into(),
+        };
+        let body = serde_json::to_string(&info).unwrap();
+        let server = thread::spawn(move || {
+            let (mut sock, _) = listener.accept().unwrap();
+            let mut request = [0u8; 4096];
+            assert!(sock.read(&mut request).unwrap() > 0);
+            write!(
+                sock,
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            )
+            .unwrap();
+        });
+
+        match RunLoop::check_l1(&conf) {
+            Err(RunLoopError::InvalidConfig(msg)) => {
+                assert!(
+                    msg.contains("is at a testnet address, but the L1 node is a mainnet node"),
+                    "{}",
+                    msg
+                );
+                assert!(msg.contains("check [burnchain] contract_identifier"));
+            }
+            res => panic!("Expected InvalidConfig, got {:?}", res),
+        }
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn test_l1_check_error() {
+        let url = "http://127.0.0.1:20443".to_string();
+        match RunLoop::l1_check_error(
+            url.clone(),
+            BurnchainControllerError::RPCError("connection refused".into()),
+        ) {
+            RunLoopError::L1Unreachable(err_url, msg) => {
+                assert_eq!(err_url, url);
+                assert_eq!(msg, "connection refused");
+            }
+            e => panic!("Expected L1Unreachable, got {:?}", e),
+        }
+
+        for e in [
+            BurnchainControllerError::BurnchainContractCheck("no such contract".into()),
+            BurnchainControllerError::BurnchainContractInterface("not a subnet".into()),
+        ] {
+            match RunLoop::l1_check_error(url.clone(), e) {
+                RunLoopError::InvalidConfig(msg) => {
+                    assert!(msg.ends_with("; check [burnchain] contract_identifier"));
+                }
+                e => panic!("Expected InvalidConfig, got {:?}", e),
+            }
+        }
+
+        // anything else is passed through as a burnchain error
+        match RunLoop::l1_check_error(url, BurnchainControllerError::CoordinatorClosed) {
+            RunLoopError::Burnchain(BurnchainControllerError::CoordinatorClosed) => {}
+            e => panic!("Expected Burnchain, got {:?}", e),
+        }
+
+        // every error says what went wrong
+        assert!(RunLoopError::RelayerHungUp
+            .to_string()
+            .contains("relayer and miner thread stopped unexpectedly"));
+    }
+
+    #[test]
+    fn test_checkpoint_databases() {
+        let dir = EphemeralWorkingDir::create().unwrap();
+        let mut conf = new_test_conf();
+        conf.node.working_dir = dir.path().to_str().unwrap().to_string();
+
+        // nothing to checkpoint before the node has made its databases
+        RunLoop::new(conf.clone()).checkpoint_databases();
+
+        let subnet_dir = dir.path().join(SUBNET_SUBDIR_NAME);
+        let db_dir = subnet_dir.join("chainstate");
+        fs::create_dir_all(&db_dir).unwrap();
+        let db_path = db_dir.join("test.sqlite");
+        let wal_path = db_dir.join("test.sqlite-wal");
+
+        // keep a connection open, as a node which hasn't exited yet would, so that sqlite doesn't
+        // checkpoint the WAL itself on close
+        let conn = sqlite_open(
+            &db_path,
+            OpenFlags::SQLITE_OPEN_READ_WRITE | OpenFlags::SQLITE_OPEN_CREATE,
+            false,
+        )
+        .unwrap();
+        conn.execute_batch("CREATE TABLE test (value INTEGER); INSERT INTO test VALUES (1);")
+            .unwrap();
+        assert!(fs::metadata(&wal_path).unwrap().len() > 0);
+
+        // a WAL without its database can't be checkpointed, but doesn't stop the others
+        let orphan_wal_path = subnet_dir.join("orphan.sqlite-wal");
+        fs::write(&orphan_wal_path, b"not a WAL").unwrap();
+
+        RunLoop::new(conf).checkpoint_databases();
+
+        assert_eq!(fs::metadata(&wal_path).unwrap().len(), 0);
+        assert!(orphan_wal_path.exists());
+        assert!(!subnet_dir.join("orphan.sqlite").exists());
+
+        // the database holds its rows by itself
+        drop(conn);
+        let conn = sqlite_open(&db_path, OpenFlags::SQLITE_OPEN_READ_ONLY, false).unwrap();
+        let value: i64 = conn
+            .query_row("SELECT value FROM test", NO_PARAMS, |row| row.get(0))
+            .unwrap();
+        assert_eq!(value, 1);
+    }
+}
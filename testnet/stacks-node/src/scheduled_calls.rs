@@ -0,0 +1,458 @@
+use stacks::address::AddressHashMode;
+use stacks::burnchains::Txid;
+use stacks::chainstate::burn::db::sortdb::SortitionDB;
+use stacks::chainstate::stacks::db::StacksChainState;
+use stacks::chainstate::stacks::events::StacksTransactionReceipt;
+use stacks::chainstate::stacks::miner::StacksBlockBuilder;
+use stacks::chainstate::stacks::{
+    StacksBlockHeader, StacksPublicKey, StacksTransaction, StacksTransactionSigner,
+    TransactionAnchorMode, TransactionAuth, TransactionContractCall, TransactionPostConditionMode,
+    TransactionSpendingCondition, TransactionVersion,
+};
+use stacks::core::mempool::{MemPoolDB, MemPoolEventDispatcher};
+use stacks::types::chainstate::StacksAddress;
+use stacks::vm::Value as ClarityValue;
+
+use crate::config::ScheduledCallConfig;
+use crate::Config;
+
+/// Each consecutive failure doubles the wait before a call is tried again, up to this many of
+/// its intervals
+const MAX_BACKOFF_INTERVALS: u64 = 32;
+/// A submitted call which no anchored block has confirmed this many burn blocks later is given
+/// up on, and counts as a failure
+const PENDING_TIMEOUT_BURN_BLOCKS: u64 = 10;
+
+/// A call in the mempool, or in microblocks, which no anchored block has confirmed yet
+#[derive(Debug, Clone, PartialEq)]
+struct PendingCall {
+    txid: Txid,
+    nonce: u64,
+    submitted_height: u64,
+}
+
+/// One of the miner's scheduled contract calls: when it is next due, what it has spent, and
+/// whether its last submission is still pending
+pub struct ScheduledCall {
+    config: ScheduledCallConfig,
+    /// the address the calls are signed by
+    address: StacksAddress,
+    /// `None` until the first attempt, which is made at the first tenure the miner runs
+    next_due_height: Option<u64>,
+    consecutive_failures: u32,
+    fees_spent: u64,
+    pending: Option<PendingCall>,
+}
+
+impl ScheduledCall {
+    pub fn new(config: ScheduledCallConfig, mainnet: bool) -> ScheduledCall {
+        let hash_mode = AddressHashMode::SerializeP2PKH;
+        let version = if mainnet {
+            hash_mode.to_version_mainnet()
+        } else {
+            hash_mode.to_version_testnet()
+        };
+        let address = StacksAddress::from_public_keys(
+            version,
+            &hash_mode,
+            1,
+            &vec![StacksPublicKey::from_private(&config.signer_key)],
+        )
+        .expect("Failed to make Stacks address from public key");
+        ScheduledCall {
+            config,
+            address,
+            next_due_height: None,
+            consecutive_failures: 0,
+            fees_spent: 0,
+            pending: None,
+        }
+    }
+
+    fn name(&self) -> String {
+        format!("{}::{}", &self.config.contract, &self.config.function)
+    }
+
+    /// Would another call take the fees spent past the budget?
+    fn is_exhausted(&self) -> bool {
+        match self.config.budget {
+            Some(budget) => self.fees_spent.saturating_add(self.config.fee) > budget,
+            None => false,
+        }
+    }
+
+    fn is_due(&self, burn_height: u64) -> bool {
+        !self.is_exhausted()
+            && self
+                .next_due_height
+                .map(|due_height| burn_height >= due_height)
+                .unwrap_or(true)
+    }
+
+    /// Check on the pending call, given the signer's nonce at the canonical anchored tip.  It
+    /// is done with once an anchored block confirms it, and is given up on, as a failure, once
+    /// it has waited `PENDING_TIMEOUT_BURN_BLOCKS`.  Returns true if it is still pending.
+    fn settle_pending(&mut self, burn_height: u64, anchored_nonce: u64) -> bool {
+        let pending = match self.pending {
+            Some(ref pending) => pending.clone(),
+            None => return false,
+        };
+        if anchored_nonce > pending.nonce {
+            self.pending = None;
+            self.consecutive_failures = 0;
+            return false;
+        }
+        if burn_height >= pending.submitted_height + PENDING_TIMEOUT_BURN_BLOCKS {
+            self.pending = None;
+            self.record_failure(
+                burn_height,
+                &format!(
+                    "{} was not confirmed within {} burn blocks",
+                    &pending.txid, PENDING_TIMEOUT_BURN_BLOCKS
+                ),
+            );
+            return false;
+        }
+        true
+    }
+
+    fn record_submitted(&mut self, burn_height: u64, txid: Txid, nonce: u64) {
+        self.fees_spent = self.fees_spent.saturating_add(self.config.fee);
+        self.next_due_height = Some(burn_height + self.config.interval);
+        self.pending = Some(PendingCall {
+            txid,
+            nonce,
+            submitted_height: burn_height,
+        });
+        if self.is_exhausted() {
+            warn!("Scheduled contract call has spent its budget, and will not be made again";
+                  "call" => self.name(),
+                  "fees_spent" => self.fees_spent);
+        }
+    }
+
+    fn record_failure(&mut self, burn_height: u64, reason: &str) {
+        self.consecutive_failures = self.consecutive_failures.saturating_add(1);
+        let backoff_intervals = 1u64
+            .checked_shl(self.consecutive_failures)
+            .unwrap_or(u64::MAX)
+            .min(MAX_BACKOFF_INTERVALS);
+        let next_due_height = burn_height + self.config.interval * backoff_intervals;
+        self.next_due_height = Some(next_due_height);
+        warn!("Scheduled contract call failed";
+              "call" => self.name(),
+              "reason" => reason,
+              "consecutive_failures" => self.consecutive_failures,
+              "next_due_height" => next_due_height);
+    }
+
+    fn make_tx(&self, nonce: u64, mainnet: bool, chain_id: u32) -> StacksTransaction {
+        let version = if mainnet {
+            TransactionVersion::Mainnet
+        } else {
+            TransactionVersion::Testnet
+        };
+        let payload = TransactionContractCall {
+            address: self.config.contract.issuer.clone().into(),
+            contract_name: self.config.contract.name.clone(),
+            function_name: self.config.function.clone(),
+            function_args: self.config.args.clone(),
+        };
+
+        let mut spending_condition = TransactionSpendingCondition::new_singlesig_p2pkh(
+            StacksPublicKey::from_private(&self.config.signer_key),
+        )
+        .expect("Failed to create p2pkh spending condition from public key.");
+        spending_condition.set_nonce(nonce);
+        spending_condition.set_tx_fee(self.config.fee);
+        let auth = TransactionAuth::Standard(spending_condition);
+
+        let mut unsigned_tx = StacksTransaction::new(version, auth, payload.into());
+        unsigned_tx.anchor_mode = TransactionAnchorMode::Any;
+        unsigned_tx.post_condition_mode = if self.config.allow_asset_transfers {
+            TransactionPostConditionMode::Allow
+        } else {
+            TransactionPostConditionMode::Deny
+        };
+        unsigned_tx.chain_id = chain_id;
+
+        let mut tx_signer = StacksTransactionSigner::new(&unsigned_tx);
+        tx_signer.sign_origin(&self.config.signer_key).unwrap();
+        tx_signer
+            .get_tx()
+            .expect("Failed to get signed transaction from signer")
+    }
+}
+
+/// Why a simulated call would fail, if it would
+fn simulated_failure(receipt: &StacksTransactionReceipt) -> Option<String> {
+    if receipt.post_condition_aborted {
+        return Some("aborted by its post-conditions".to_string());
+    }
+    match receipt.result {
+        ClarityValue::Response(ref response) if !response.committed => {
+            Some(format!("returned {}", &receipt.result))
+        }
+        _ => None,
+    }
+}
+
+/// The contract calls the miner makes itself, e.g. to keep a price feed or a lottery going.
+/// Each call is submitted to the miner's own mempool every `interval` burn blocks, signed with
+/// its own key, so the miner mines it into its next microblocks.  Before it is submitted, the
+/// call is simulated on the canonical chain tip: a call which would fail, or which the mempool
+/// rejects, is not submitted, and is retried after a backoff.  Calls signed with the same key
+/// take turns, one per anchored block.
+pub struct ScheduledCalls {
+    calls: Vec<ScheduledCall>,
+    mainnet: bool,
+    chain_id: u32,
+}
+
+impl ScheduledCalls {
+    pub fn new(config: &Config) -> ScheduledCalls {
+        ScheduledCalls {
+            calls: config
+                .miner
+                .scheduled_calls
+                .iter()
+                .map(|call| ScheduledCall::new(call.clone(), config.is_mainnet()))
+                .collect(),
+            mainnet: config.is_mainnet(),
+            chain_id: config.node.chain_id,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.calls.is_empty()
+    }
+
+    /// Submit the calls due at `burn_height` to `mem_pool`, and return the submitted
+    /// transactions
+    pub fn submit_due(
+        &mut self,
+        burn_height: u64,
+        chainstate: &mut StacksChainState,
+        sortdb: &SortitionDB,
+        mem_pool: &mut MemPoolDB,
+        event_observer: Option<&dyn MemPoolEventDispatcher>,
+    ) -> Vec<StacksTransaction> {
+        let tip = match chainstate.get_stacks_chain_tip(sortdb) {
+            Ok(Some(tip)) => tip,
+            Ok(None) => return vec![],
+            Err(e) => {
+                warn!(
+                    "Failed to load the chain tip for scheduled contract calls: {:?}",
+                    &e
+                );
+                return vec![];
+            }
+        };
+        let tip_header = match StacksChainState::get_anchored_block_header_info(
+            chainstate.db(),
+            &tip.consensus_hash,
+            &tip.anchored_block_hash,
+        ) {
+            Ok(Some(header)) => header,
+            Ok(None) | Err(_) => {
+                warn!(
+                    "Failed to load the header of {}/{} for scheduled contract calls",
+                    &tip.consensus_hash, &tip.anchored_block_hash
+                );
+                return vec![];
+            }
+        };
+        let tip_index_hash =
+            StacksBlockHeader::make_index_block_hash(&tip.consensus_hash, &tip.anchored_block_hash);
+        let epoch = match SortitionDB::get_stacks_epoch(sortdb.conn(), burn_height) {
+            Ok(Some(epoch)) => epoch,
+            Ok(None) | Err(_) => {
+                warn!(
+                    "Failed to load the Stacks epoch at burn height {} for scheduled contract calls",
+                    burn_height
+                );
+                return vec![];
+            }
+        };
+
+        let mut submitted = vec![];
+        for i in 0..self.calls.len() {
+            let address = self.calls[i].address.clone();
+            let nonce = match chainstate.maybe_read_only_clarity_tx(
+                &sortdb.index_conn(),
+                &tip_index_hash,
+                |clarity_tx| {
+                    clarity_tx.with_clarity_db_readonly(|clarity_db| {
+                        clarity_db.get_account_nonce(&address.clone().into())
+                    })
+                },
+            ) {
+                Ok(Some(nonce)) => nonce,
+                Ok(None) | Err(_) => {
+                    warn!(
+                        "Failed to read the nonce of {} at {} for a scheduled contract call",
+                        &address, &tip_index_hash
+                    );
+                    continue;
+                }
+            };
+
+            if self.calls[i].settle_pending(burn_height, nonce)
+                || !self.calls[i].is_due(burn_height)
+            {
+                continue;
+            }
+            if self
+                .calls
+                .iter()
+                .any(|other| other.address == address && other.pending.is_some())
+            {
+                continue;
+            }
+
+            let call = &mut self.calls[i];
+            let tx = call.make_tx(nonce, self.mainnet, self.chain_id);
+            match StacksBlockBuilder::simulate_tx(
+                chainstate,
+                &sortdb.index_conn(),
+                &tip_header,
+                &tx,
+            ) {
+                Ok(Ok(success)) => {
+                    if let Some(reason) = simulated_failure(&success.receipt) {
+                        call.record_failure(burn_height, &reason);
+                        continue;
+                    }
+                }
+                Ok(Err(e)) => {
+                    call.record_failure(burn_height, &e.to_string());
+                    continue;
+                }
+                Err(e) => {
+                    warn!(
+                        "Failed to set up a block to simulate scheduled contract call {} in: {}",
+                        call.name(),
+                        &e
+                    );
+                    continue;
+                }
+            }
+
+            let txid = tx.txid();
+            if let Err(e) = mem_pool.submit(
+                chainstate,
+                &tip.consensus_hash,
+                &tip.anchored_block_hash,
+                &tx,
+                event_observer,
+                &epoch.block_limit,
+                &epoch.epoch_id,
+            ) {
+                call.record_failure(burn_height, &e.into_json(&txid).to_string());
+                continue;
+            }
+
+            info!("Submitted scheduled contract call";
+                  "call" => call.name(),
+                  "txid" => %txid,
+                  "nonce" => nonce,
+                  "burn_height" => burn_height);
+            call.record_submitted(burn_height, txid, nonce);
+            submitted.push(tx);
+        }
+        submitted
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use stacks::chainstate::stacks::StacksPrivateKey;
+    use stacks::vm::types::QualifiedContractIdentifier;
+    use stacks::vm::ClarityName;
+
+    fn make_call(interval: u64, fee: u64, budget: Option<u64>) -> ScheduledCall {
+        ScheduledCall::new(
+            ScheduledCallConfig {
+                contract: QualifiedContractIdentifier::parse(
+                    "ST1PQHQKV0RJXZFY1DGX8MNSNYVE3VGZJSRTPGZGM.price-feed",
+                )
+                .unwrap(),
+                function: ClarityName::from("update-price"),
+                args: vec![ClarityValue::UInt(100)],
+                interval,
+                signer_key: StacksPrivateKey::new(),
+                fee,
+                budget,
+                allow_asset_transfers: false,
+            },
+            false,
+        )
+    }
+
+    #[test]
+    fn test_scheduled_call_interval() {
+        let mut call = make_call(5, 1_000, None);
+        assert!(call.is_due(100));
+
+        call.record_submitted(100, Txid([1; 32]), 7);
+        assert_eq!(call.fees_spent, 1_000);
+        assert!(!call.is_due(104));
+        assert!(call.is_due(105));
+
+        // the call waits until an anchored block confirms the previous one
+        assert!(call.settle_pending(105, 7));
+        assert!(!call.settle_pending(106, 8));
+        assert!(call.pending.is_none());
+        assert!(call.is_due(106));
+    }
+
+    #[test]
+    fn test_scheduled_call_backoff() {
+        let mut call = make_call(5, 1_000, None);
+
+        call.record_failure(100, "returned (err u1)");
+        assert_eq!(call.next_due_height, Some(110));
+        call.record_failure(110, "returned (err u1)");
+        assert_eq!(call.next_due_height, Some(130));
+        for _ in 0..10 {
+            call.record_failure(130, "returned (err u1)");
+        }
+        assert_eq!(call.next_due_height, Some(130 + 5 * MAX_BACKOFF_INTERVALS));
+
+        // a call that is never confirmed is a failure too
+        call.record_submitted(300, Txid([2; 32]), 3);
+        assert!(call.settle_pending(309, 3));
+        assert!(!call.settle_pending(310, 3));
+        assert!(call.pending.is_none());
+        assert_eq!(call.consecutive_failures, 13);
+
+        // and a confirmed one resets the backoff
+        call.record_submitted(400, Txid([3; 32]), 3);
+        assert!(!call.settle_pending(401, 4));
+        assert_eq!(call.consecutive_failures, 0);
+    }
+
+    #[test]
+    fn test_scheduled_call_budget() {
+        let mut call = make_call(1, 400, Some(1_000));
+        call.record_submitted(1, Txid([1; 32]), 0);
+        call.record_submitted(2, Txid([2; 32]), 1);
+        assert_eq!(call.fees_spent, 800);
+        // a third call would overspend
+        assert!(!call.is_due(3));
+        assert!(!call.is_due(1_000));
+    }
+
+    #[test]
+    fn test_scheduled_call_tx() {
+        let call = make_call(5, 1_000, None);
+        let tx = call.make_tx(4, false, 0x55005500);
+        assert_eq!(tx.origin_address(), call.address);
+        assert_eq!(tx.get_origin_nonce(), 4);
+        assert_eq!(tx.get_tx_fee(), 1_000);
+        assert_eq!(tx.chain_id, 0x55005500);
+        assert_eq!(tx.post_condition_mode, TransactionPostConditionMode::Deny);
+        tx.verify().unwrap();
+    }
+}
@@ -0,0 +1,194 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use stacks::chainstate::burn::db::sortdb::SortitionDB;
+use stacks::chainstate::stacks::db::StacksChainState;
+use stacks::chainstate::stacks::StacksBlockHeader;
+use stacks::core::{FIRST_BURNCHAIN_CONSENSUS_HASH, FIRST_STACKS_BLOCK_HASH};
+use stacks::util_lib::snapshot::{self, SnapshotManifest};
+
+use crate::run_loop::RunLoopError;
+use crate::Config;
+
+/// The directories under the node's `subnet` directory which make up a snapshot
+const SNAPSHOT_DIRS: &[&str] = &["chainstate", "burnchain"];
+
+/// Node-local state which is left out of snapshots: pending transactions and fee estimates
+const SNAPSHOT_EXCLUDED: &[&str] = &["chainstate/mempool.sqlite", "chainstate/estimates"];
+
+/// The directory holding the node's chainstate and burnchain directories
+fn subnet_dir(config: &Config) -> PathBuf {
+    config
+        .get_chainstate_path()
+        .parent()
+        .expect("BUG: chainstate path has no parent")
+        .to_path_buf()
+}
+
+/// Package the node's chainstate and sortition DB into a snapshot at `dest_path`.  The snapshot
+/// is taken at the node's canonical Stacks chain tip; if `height` is given, this fails unless the
+/// tip is at that height.  The node must not be running.  Returns the snapshot's manifest hash.
+pub fn create_snapshot(
+    config: &Config,
+    dest_path: &str,
+    height: Option<u64>,
+) -> Result<String, RunLoopError> {
+    let chainstate_path = config.get_chainstate_path_str();
+    if fs::metadata(&chainstate_path).is_err() {
+        return Err(RunLoopError::Chainstate(format!(
+            "no chainstate at {}",
+            &chainstate_path
+        )));
+    }
+
+    let manifest = {
+        let sortdb = SortitionDB::open(&config.get_burn_db_file_path(), false).map_err(|e| {
+            RunLoopError::Chainstate(format!("failed to open sortition database: {:?}", &e))
+        })?;
+        let (chainstate, _) = StacksChainState::open(
+            config.is_mainnet(),
+            config.node.chain_id,
+            &chainstate_path,
+            Some(config.node.get_marf_opts()),
+        )
+        .map_err(|e| {
+            RunLoopError::Chainstate(format!("failed to open chainstate database: {:?}", &e))
+        })?;
+        let (tip_height, tip_block_id) = match chainstate
+            .get_stacks_chain_tip(&sortdb)
+            .map_err(|e| RunLoopError::Chainstate(format!("failed to load chain tip: {:?}", &e)))?
+        {
+            Some(tip) => (
+                tip.height,
+                StacksBlockHeader::make_index_block_hash(
+                    &tip.consensus_hash,
+                    &tip.anchored_block_hash,
+                ),
+            ),
+            None => (
+                0,
+                StacksBlockHeader::make_index_block_hash(
+                    &FIRST_BURNCHAIN_CONSENSUS_HASH,
+                    &FIRST_STACKS_BLOCK_HASH,
+                ),
+            ),
+        };
+        if let Some(height) = height {
+            if height != tip_height {
+                return Err(RunLoopError::Chainstate(format!(
+                    "chain tip is at height {}, not {}; stop the node at height {} to snapshot it",
+                    tip_height, height, height
+                )));
+            }
+        }
+        SnapshotManifest::new(
+            config.is_mainnet(),
+            config.node.chain_id,
+            config.burnchain.contract_identifier.to_string(),
+            tip_height,
+            format!("{}", &tip_block_id),
+        )
+    };
+
+    let (manifest, manifest_hash) = snapshot::create_snapshot(
+        &subnet_dir(config),
+        SNAPSHOT_DIRS,
+        SNAPSHOT_EXCLUDED,
+        Path::new(dest_path),
+        manifest,
+    )
+    .map_err(|e| RunLoopError::Chainstate(format!("failed to create snapshot: {}", &e)))?;
+
+    info!(
+        "Created snapshot at {} of block {} at height {} ({} files)",
+        dest_path,
+        &manifest.index_block_hash,
+        manifest.block_height,
+        manifest.files.len();
+        "manifest_sha256" => %manifest_hash
+    );
+    Ok(manifest_hash)
+}
+
+/// Restore the snapshot at `snapshot_path` into the node's working directory, so that the node
+/// boots from it and only syncs the blocks that came after it.  The snapshot must be for the
+/// chain and subnet contract in `config`, and if `expected_hash` is given, its manifest must have
+/// that SHA-256 hash.  Fails if the node already has a chainstate.
+///
+/// Files are restored into a staging directory, and only moved into place once every one of
+/// them has been checked, so a failed restore leaves no partial chainstate behind.
+pub fn restore_snapshot(
+    config: &Config,
+    snapshot_path: &str,
+    expected_hash: Option<&str>,
+) -> Result<(), RunLoopError> {
+    let subnet_dir = subnet_dir(config);
+    for dir in SNAPSHOT_DIRS.iter() {
+        if subnet_dir.join(dir).exists() {
+            return Err(RunLoopError::Chainstate(format!(
+                "{} already exists; snapshots can only be restored into a new working directory",
+                subnet_dir.join(dir).display()
+            )));
+        }
+    }
+
+    let snapshot_dir = Path::new(snapshot_path);
+    let (manifest, manifest_hash) = snapshot::read_manifest(snapshot_dir)
+        .map_err(|e| RunLoopError::Chainstate(format!("failed to read snapshot: {}", &e)))?;
+    if let Some(expected_hash) = expected_hash {
+        if !manifest_hash.eq_ignore_ascii_case(expected_hash) {
+            return Err(RunLoopError::Chainstate(format!(
+                "snapshot manifest has hash {}, expected {}",
+                &manifest_hash, expected_hash
+            )));
+        }
+    }
+    let subnet_contract = config.burnchain.contract_identifier.to_string();
+    if manifest.mainnet != config.is_mainnet()
+        || manifest.chain_id != config.node.chain_id
+        || manifest.subnet_contract != subnet_contract
+    {
+        return Err(RunLoopError::Chainstate(format!(
+            "snapshot is for chain ID {} (mainnet: {}) and subnet contract {}, but this node is configured for chain ID {} (mainnet: {}) and subnet contract {}",
+            manifest.chain_id,
+            manifest.mainnet,
+            &manifest.subnet_contract,
+            config.node.chain_id,
+            config.is_mainnet(),
+            &subnet_contract
+        )));
+    }
+
+    let staging_dir = subnet_dir.join("snapshot-restore");
+    if staging_dir.exists() {
+        fs::remove_dir_all(&staging_dir).map_err(|e| {
+            RunLoopError::Chainstate(format!("failed to clear {}: {}", staging_dir.display(), &e))
+        })?;
+    }
+    info!(
+        "Restoring snapshot of block {} at height {} from {}",
+        &manifest.index_block_hash, manifest.block_height, snapshot_path;
+        "manifest_sha256" => %manifest_hash
+    );
+    if let Err(e) = snapshot::restore_snapshot(snapshot_dir, &manifest, &staging_dir) {
+        let _ = fs::remove_dir_all(&staging_dir);
+        return Err(RunLoopError::Chainstate(format!(
+            "failed to restore snapshot: {}",
+            &e
+        )));
+    }
+    for dir in SNAPSHOT_DIRS.iter() {
+        if !staging_dir.join(dir).exists() {
+            continue;
+        }
+        fs::rename(staging_dir.join(dir), subnet_dir.join(dir)).map_err(|e| {
+            RunLoopError::Chainstate(format!("failed to move restored {}: {}", dir, &e))
+        })?;
+    }
+    let _ = fs::remove_dir_all(&staging_dir);
+    info!(
+        "Restored snapshot at height {}; the node will sync from there",
+        manifest.block_height
+    );
+    Ok(())
+}
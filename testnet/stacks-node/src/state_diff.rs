@@ -0,0 +1,48 @@
+use std::fs;
+
+use stacks::chainstate::burn::db::sortdb::SortitionDB;
+use stacks::chainstate::stacks::db::StacksChainState;
+
+use crate::run_loop::RunLoopError;
+use crate::Config;
+
+/// Print, as JSON, the Clarity keys added or changed between the canonical blocks at heights
+/// `from_height` and `to_height` of the node whose working directory is
+/// `config.node.working_dir`, with their values at both heights.  If `contract_prefix` is
+/// given, only the keys of contracts whose identifier starts with it are printed.  Nothing is
+/// written.
+pub fn state_diff(
+    config: &Config,
+    from_height: u64,
+    to_height: u64,
+    contract_prefix: Option<&str>,
+) -> Result<(), RunLoopError> {
+    let chainstate_path = config.get_chainstate_path_str();
+    if fs::metadata(&chainstate_path).is_err() {
+        return Err(RunLoopError::Chainstate(format!(
+            "no chainstate at {}",
+            &chainstate_path
+        )));
+    }
+    let sortdb = SortitionDB::open(&config.get_burn_db_file_path(), false).map_err(|e| {
+        RunLoopError::Chainstate(format!("failed to open sortition database: {:?}", &e))
+    })?;
+    let (mut chainstate, _) = StacksChainState::open(
+        config.is_mainnet(),
+        config.node.chain_id,
+        &chainstate_path,
+        Some(config.node.get_marf_opts()),
+    )
+    .map_err(|e| {
+        RunLoopError::Chainstate(format!("failed to open chainstate database: {:?}", &e))
+    })?;
+
+    let diff = chainstate
+        .get_state_diff(&sortdb, from_height, to_height, contract_prefix)
+        .map_err(|e| RunLoopError::Chainstate(format!("failed to diff state: {:?}", &e)))?;
+    println!(
+        "{}",
+        serde_json::to_string_pretty(&diff).expect("Failed to serialize state diff")
+    );
+    Ok(())
+}
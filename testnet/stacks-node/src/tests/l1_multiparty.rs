@@ -118,7 +118,7 @@ fn l1_multiparty_1_of_n_integration_test() {
 
     let mut run_loop = neon::RunLoop::new(config.clone());
     let termination_switch = run_loop.get_termination_switch();
-    let run_loop_thread = thread::spawn(move || run_loop.start(None, 0));
+    let run_loop_thread = thread::spawn(move || run_loop.start(None, 0).unwrap());
 
     // Give the run loop time to start.
     thread::sleep(Duration::from_millis(2_000));
@@ -244,11 +244,11 @@ fn l1_multiparty_2_of_2_integration_test() {
 
     let mut leader_run_loop = neon::RunLoop::new(leader_config.clone());
     let leader_termination_switch = leader_run_loop.get_termination_switch();
-    let leader_run_loop_thread = thread::spawn(move || leader_run_loop.start(None, 0));
+    let leader_run_loop_thread = thread::spawn(move || leader_run_loop.start(None, 0).unwrap());
 
     let mut follower_run_loop = neon::RunLoop::new(follower_config.clone());
     let follower_termination_switch = follower_run_loop.get_termination_switch();
-    let follower_run_loop_thread = thread::spawn(move || follower_run_loop.start(None, 0));
+    let follower_run_loop_thread = thread::spawn(move || follower_run_loop.start(None, 0).unwrap());
 
     // Give the run loop time to start.
     thread::sleep(Duration::from_millis(2_000));
@@ -31,7 +31,7 @@ use stacks::clarity_vm::withdrawal::{
 };
 use stacks::codec::StacksMessageCodec;
 use stacks::core::LAYER_1_CHAIN_ID_TESTNET;
-use stacks::net::CallReadOnlyRequestBody;
+use stacks::net::TipRequest;
 use stacks::util::hash::hex_bytes;
 use stacks::vm::costs::ExecutionCost;
 use stacks::vm::types::{QualifiedContractIdentifier, TupleData};
@@ -41,6 +41,7 @@ use std::convert::{TryFrom, TryInto};
 use std::env;
 use std::io::{BufRead, BufReader};
 use std::sync::atomic::Ordering;
+use subnet_rpc_client::SubnetRpcClient;
 
 use std::time::{Duration, Instant};
 
@@ -81,27 +82,27 @@ pub fn call_read_only(
     function_name: &str,
     args: Vec<String>,
 ) -> serde_json::Value {
-    let client = reqwest::blocking::Client::new();
-
-    let path = format!(
-        "{}/v2/contracts/call-read/{}/{}/{}",
-        &http_origin, addr, contract_name, function_name
+    let contract_id = QualifiedContractIdentifier::new(
+        addr.clone().into(),
+        ContractName::try_from(contract_name.to_string()).unwrap(),
     );
     let principal: PrincipalData = addr.clone().into();
-    let body = CallReadOnlyRequestBody {
-        sender: principal.to_string(),
-        arguments: args,
-    };
-
-    let read_info = client
-        .post(&path)
-        .json(&body)
-        .send()
-        .unwrap()
-        .json::<serde_json::Value>()
+    let args: Vec<Value> = args
+        .iter()
+        .map(|arg| Value::try_deserialize_hex_untyped(arg).unwrap())
+        .collect();
+
+    let read_info = SubnetRpcClient::new(http_origin)
+        .call_read_only(
+            &contract_id,
+            function_name,
+            &principal,
+            &args,
+            &TipRequest::UseLatestUnconfirmedTip,
+        )
         .unwrap();
 
-    read_info
+    serde_json::to_value(&read_info).unwrap()
 }
 
 pub fn deserialize_value(hex: &str, expected: &TypeSignature) -> Value {
@@ -180,7 +181,7 @@ impl Drop for StacksL1Controller {
 }
 
 /// Longest time to wait for a stacks block before aborting.
-const PANIC_TIMEOUT_SECS: u64 = 600;
+pub const PANIC_TIMEOUT_SECS: u64 = 600;
 
 /// Height of the current stacks tip.
 fn get_stacks_tip_height(sortition_db: &SortitionDB) -> i64 {
@@ -246,6 +247,126 @@ pub fn wait_for_target_l1_block(sortition_db: &SortitionDB, target: u64) -> bool
     true
 }
 
+/// Height of the L1 chain tip, as reported by the L1 node's RPC interface, or `None`
+/// if the L1 node is not answering yet.
+pub fn get_l1_tip_height(l1_rpc_origin: &str) -> Option<u64> {
+    let client = reqwest::blocking::Client::new();
+    let path = format!("{}/v2/info", l1_rpc_origin);
+    let info = client
+        .get(&path)
+        .send()
+        .ok()?
+        .json::<serde_json::Value>()
+        .ok()?;
+    info["stacks_tip_height"].as_u64()
+}
+
+/// Wait until the L1 chain tip has reached `target`, and the subnet has processed that
+/// L1 block into its sortition DB. Returns the L1 height the subnet has processed.
+pub fn wait_for_l1_height(l1_rpc_origin: &str, sortition_db: &SortitionDB, target: u64) -> u64 {
+    info!("wait_for_l1_height started"; "target" => target);
+    let start = Instant::now();
+    loop {
+        let l1_height = get_l1_tip_height(l1_rpc_origin).unwrap_or(0);
+        let processed_height = SortitionDB::get_canonical_burn_chain_tip(&sortition_db.conn())
+            .expect("Could not read from SortitionDB.")
+            .block_height;
+        if l1_height >= target && processed_height >= target {
+            info!("wait_for_l1_height finished"; "target" => target, "processed_height" => processed_height);
+            return processed_height;
+        }
+        if start.elapsed() > Duration::from_secs(PANIC_TIMEOUT_SECS) {
+            panic!(
+                "Timed out waiting for L1 height {}: L1 is at {}, subnet has processed {}",
+                target, l1_height, processed_height
+            );
+        }
+        thread::sleep(Duration::from_millis(100));
+    }
+}
+
+/// The mocknet L1 mines on its own timer and cannot be asked for a block, so this
+/// waits for the L1 to mine its next block and for the subnet to process it.
+/// Returns the new L1 height.
+pub fn mine_l1_block_now(l1_rpc_origin: &str, sortition_db: &SortitionDB) -> u64 {
+    let start = Instant::now();
+    let current = loop {
+        if let Some(height) = get_l1_tip_height(l1_rpc_origin) {
+            break height;
+        }
+        if start.elapsed() > Duration::from_secs(PANIC_TIMEOUT_SECS) {
+            panic!("Timed out waiting for the L1 node to answer, aborting test.");
+        }
+        thread::sleep(Duration::from_millis(100));
+    };
+    wait_for_l1_height(l1_rpc_origin, sortition_db, current + 1)
+}
+
+/// Is the transaction `txid` still in the mempool or an unconfirmed microblock stream
+/// of the node at `http_origin`?
+fn is_tx_unconfirmed(http_origin: &str, txid: &str) -> bool {
+    let client = reqwest::blocking::Client::new();
+    let path = format!("{}/v2/transactions/unconfirmed/{}", http_origin, txid);
+    match client.get(&path).send() {
+        Ok(res) => res.status().is_success(),
+        // the node is not answering, so we cannot tell
+        Err(_) => true,
+    }
+}
+
+/// Wait until the transaction `txid`, previously accepted by the node at `http_origin`,
+/// has been mined into an anchored block on that node's chain.
+pub fn wait_for_tx_confirmation(http_origin: &str, txid: &str) {
+    info!("wait_for_tx_confirmation started"; "txid" => txid, "origin" => http_origin);
+    let start = Instant::now();
+    while is_tx_unconfirmed(http_origin, txid) {
+        if start.elapsed() > Duration::from_secs(PANIC_TIMEOUT_SECS) {
+            panic!("Timed out waiting for transaction {} to be mined", txid);
+        }
+        thread::sleep(Duration::from_millis(100));
+    }
+    info!("wait_for_tx_confirmation finished"; "txid" => txid, "origin" => http_origin);
+}
+
+/// Wait until the L1 transaction `txid` has been mined, the subnet has processed the L1
+/// block containing it, and a subnet block that includes its effects has been processed.
+pub fn wait_for_l1_tx_in_subnet(l1_rpc_origin: &str, sortition_db: &SortitionDB, txid: &str) {
+    wait_for_tx_confirmation(l1_rpc_origin, txid);
+    let l1_height =
+        get_l1_tip_height(l1_rpc_origin).expect("L1 node stopped answering after mining a tx");
+    wait_for_l1_height(l1_rpc_origin, sortition_db, l1_height);
+    wait_for_subnet_tip_at_l1_height(sortition_db, l1_height);
+}
+
+/// Wait until the subnet's canonical tip was committed at or after L1 height `target`,
+/// which means it has processed every L1 operation up to that height.
+pub fn wait_for_subnet_tip_at_l1_height(sortition_db: &SortitionDB, target: u64) {
+    info!("wait_for_subnet_tip_at_l1_height started"; "target" => target);
+    let start = Instant::now();
+    loop {
+        let tip_snapshot = SortitionDB::get_canonical_burn_chain_tip(&sortition_db.conn())
+            .expect("Could not read from SortitionDB.");
+        let committed_height = SortitionDB::get_block_snapshot_consensus(
+            &sortition_db.conn(),
+            &tip_snapshot.canonical_stacks_tip_consensus_hash,
+        )
+        .expect("Could not read from SortitionDB.")
+        .map(|snapshot| snapshot.block_height)
+        .unwrap_or(0);
+        if committed_height >= target {
+            break;
+        }
+        if start.elapsed() > Duration::from_secs(PANIC_TIMEOUT_SECS) {
+            panic!(
+                "Timed out waiting for a subnet block committed at L1 height {}, aborting test.",
+                target
+            );
+        }
+        thread::sleep(Duration::from_millis(100));
+    }
+    info!("wait_for_subnet_tip_at_l1_height finished"; "target" => target);
+}
+
 /// Deserializes the `StacksTransaction` objects from `blocks` and returns all those that
 /// match `test_fn`.
 fn select_transactions_where(
@@ -356,6 +477,7 @@ fn l1_basic_listener_test() {
 
     // Start Stacks L1.
     let l1_toml_file = "../../contrib/conf/stacks-l1-mocknet.toml";
+    let l1_rpc_origin = "http://127.0.0.1:20443";
     let mut stacks_l1_controller = StacksL1Controller::new(l1_toml_file.to_string(), true);
     let _stacks_res = stacks_l1_controller
         .start_process()
@@ -371,14 +493,18 @@ fn l1_basic_listener_test() {
 
     let mut run_loop = neon::RunLoop::new(config.clone());
     let termination_switch = run_loop.get_termination_switch();
-    let run_loop_thread = thread::spawn(move || run_loop.start(None, 0));
+    let run_loop_thread = thread::spawn(move || run_loop.start(None, 0).unwrap());
 
-    // Sleep to give the run loop time to listen to blocks.
-    thread::sleep(Duration::from_millis(45000));
+    // Give the run loop time to start.
+    thread::sleep(Duration::from_millis(2_000));
 
     // The burnchain should have registered what the listener recorded.
     let burnchain = Burnchain::new(&config.get_burn_db_path(), &config.burnchain.chain).unwrap();
-    let (_sortition_db, burndb) = burnchain.open_db(true).unwrap();
+    let (sortition_db, burndb) = burnchain.open_db(true).unwrap();
+
+    // Let the listener hear a few L1 blocks, then one more.
+    wait_for_l1_height(l1_rpc_origin, &sortition_db, 3);
+    mine_l1_block_now(l1_rpc_origin, &sortition_db);
 
     let tip = burndb
         .get_canonical_chain_tip()
@@ -416,7 +542,7 @@ fn l1_integration_test() {
 
     let mut run_loop = neon::RunLoop::new(config.clone());
     let termination_switch = run_loop.get_termination_switch();
-    let run_loop_thread = thread::spawn(move || run_loop.start(None, 0));
+    let run_loop_thread = thread::spawn(move || run_loop.start(None, 0).unwrap());
 
     // Give the run loop time to start.
     thread::sleep(Duration::from_millis(2_000));
@@ -496,7 +622,7 @@ fn l1_deposit_and_withdraw_asset_integration_test() {
 
     let mut run_loop = neon::RunLoop::new(config.clone());
     let termination_switch = run_loop.get_termination_switch();
-    let run_loop_thread = thread::spawn(move || run_loop.start(None, 0));
+    let run_loop_thread = thread::spawn(move || run_loop.start(None, 0).unwrap());
 
     // Give the run loop time to start.
     thread::sleep(Duration::from_millis(2_000));
@@ -511,9 +637,8 @@ fn l1_deposit_and_withdraw_asset_integration_test() {
     let burnchain = Burnchain::new(&config.get_burn_db_path(), &config.burnchain.chain).unwrap();
     let (sortition_db, burndb) = burnchain.open_db(true).unwrap();
 
-    // Sleep to give the L1 chain time to start
-    thread::sleep(Duration::from_millis(10_000));
-    wait_for_target_l1_block(&sortition_db, MOCKNET_EPOCH_2_1);
+    // Wait for the L1 chain to start and reach epoch 2.1
+    wait_for_l1_height(l1_rpc_origin, &sortition_db, MOCKNET_EPOCH_2_1 + 1);
 
     l1_nonce = publish_subnet_contracts_to_l1(
         l1_nonce,
@@ -632,11 +757,15 @@ fn l1_deposit_and_withdraw_asset_integration_test() {
     );
     l1_nonce += 1;
 
-    submit_tx(&l2_rpc_origin, &subnet_ft_publish);
-    submit_tx(&l2_rpc_origin, &subnet_nft_publish);
-    submit_tx(l1_rpc_origin, &l1_mint_ft_tx);
-    submit_tx(l1_rpc_origin, &l1_mint_nft_tx);
-    wait_to_confirm_subnet_transactions(&sortition_db);
+    let subnet_ft_publish_txid = submit_tx(&l2_rpc_origin, &subnet_ft_publish);
+    let subnet_nft_publish_txid = submit_tx(&l2_rpc_origin, &subnet_nft_publish);
+    let l1_mint_ft_txid = submit_tx(l1_rpc_origin, &l1_mint_ft_tx);
+    let l1_mint_nft_txid = submit_tx(l1_rpc_origin, &l1_mint_nft_tx);
+    // Wait until the subnet has processed the submitted transactions
+    wait_for_tx_confirmation(&l2_rpc_origin, &subnet_ft_publish_txid);
+    wait_for_tx_confirmation(&l2_rpc_origin, &subnet_nft_publish_txid);
+    wait_for_l1_tx_in_subnet(l1_rpc_origin, &sortition_db, &l1_mint_ft_txid);
+    wait_for_l1_tx_in_subnet(l1_rpc_origin, &sortition_db, &l1_mint_nft_txid);
 
     // Register the contract
     let subnet_setup_ft_tx = make_contract_call(
@@ -669,11 +798,12 @@ fn l1_deposit_and_withdraw_asset_integration_test() {
     );
     l1_nonce += 1;
 
-    submit_tx(l1_rpc_origin, &subnet_setup_ft_tx);
-    submit_tx(l1_rpc_origin, &subnet_setup_nft_tx);
+    let subnet_setup_ft_txid = submit_tx(l1_rpc_origin, &subnet_setup_ft_tx);
+    let subnet_setup_nft_txid = submit_tx(l1_rpc_origin, &subnet_setup_nft_tx);
 
-    wait_for_next_stacks_block(&sortition_db);
-    wait_for_next_stacks_block(&sortition_db);
+    // Wait until the subnet has processed the submitted transactions
+    wait_for_l1_tx_in_subnet(l1_rpc_origin, &sortition_db, &subnet_setup_ft_txid);
+    wait_for_l1_tx_in_subnet(l1_rpc_origin, &sortition_db, &subnet_setup_nft_txid);
 
     // Check that the user does not own any of the fungible tokens on the subnet now
     let res = call_read_only(
@@ -752,13 +882,13 @@ fn l1_deposit_and_withdraw_asset_integration_test() {
     l1_nonce += 1;
 
     // deposit ft-token into subnet contract on L1
-    submit_tx(&l1_rpc_origin, &l1_deposit_ft_tx);
+    let l1_deposit_ft_txid = submit_tx(&l1_rpc_origin, &l1_deposit_ft_tx);
     // deposit nft-token into subnet contract on L1
-    submit_tx(&l1_rpc_origin, &l1_deposit_nft_tx);
+    let l1_deposit_nft_txid = submit_tx(&l1_rpc_origin, &l1_deposit_nft_tx);
 
-    wait_for_next_stacks_block(&sortition_db);
-    wait_for_next_stacks_block(&sortition_db);
-    wait_for_next_stacks_block(&sortition_db);
+    // Wait until the subnet has processed the submitted transactions
+    wait_for_l1_tx_in_subnet(l1_rpc_origin, &sortition_db, &l1_deposit_ft_txid);
+    wait_for_l1_tx_in_subnet(l1_rpc_origin, &sortition_db, &l1_deposit_nft_txid);
 
     // Check that the user owns a fungible token on the subnet now
     let res = call_read_only(
@@ -1285,12 +1415,13 @@ fn l1_deposit_and_withdraw_asset_integration_test() {
     );
 
     // Withdraw ft-token from subnet contract on L1
-    submit_tx(&l1_rpc_origin, &l1_withdraw_ft_tx);
+    let l1_withdraw_ft_txid = submit_tx(&l1_rpc_origin, &l1_withdraw_ft_tx);
     // Withdraw nft-token from subnet contract on L1
-    submit_tx(&l1_rpc_origin, &l1_withdraw_nft_tx);
+    let l1_withdraw_nft_txid = submit_tx(&l1_rpc_origin, &l1_withdraw_nft_tx);
 
-    // Sleep to give the run loop time to mine a block
-    thread::sleep(Duration::from_secs(25));
+    // Wait for the withdrawals to be mined on L1
+    wait_for_tx_confirmation(l1_rpc_origin, &l1_withdraw_ft_txid);
+    wait_for_tx_confirmation(l1_rpc_origin, &l1_withdraw_nft_txid);
 
     // Check that the user owns the fungible token on the L1 chain now
     let res = call_read_only(
@@ -1386,7 +1517,7 @@ fn l1_deposit_and_withdraw_stx_integration_test() {
 
     let mut run_loop = neon::RunLoop::new(config.clone());
     let termination_switch = run_loop.get_termination_switch();
-    let run_loop_thread = thread::spawn(move || run_loop.start(None, 0));
+    let run_loop_thread = thread::spawn(move || run_loop.start(None, 0).unwrap());
 
     // Sleep to give the run loop time to start
     thread::sleep(Duration::from_millis(2_000));
@@ -1400,9 +1531,8 @@ fn l1_deposit_and_withdraw_stx_integration_test() {
         .expect("stacks l1 controller didn't start");
     let mut l1_nonce = 0;
 
-    // Sleep to give the L1 chain time to start
-    thread::sleep(Duration::from_millis(10_000));
-    wait_for_target_l1_block(&sortition_db, MOCKNET_EPOCH_2_1);
+    // Wait for the L1 chain to start and reach epoch 2.1
+    wait_for_l1_height(l1_rpc_origin, &sortition_db, MOCKNET_EPOCH_2_1 + 1);
 
     l1_nonce = publish_subnet_contracts_to_l1(
         l1_nonce,
@@ -1440,8 +1570,9 @@ fn l1_deposit_and_withdraw_stx_integration_test() {
     );
     l2_nonce += 1;
 
-    submit_tx(&l2_rpc_origin, &subnet_stx_publish);
-    wait_to_confirm_subnet_transactions(&sortition_db);
+    let subnet_stx_publish_txid = submit_tx(&l2_rpc_origin, &subnet_stx_publish);
+    // Wait until the subnet has processed the submitted transactions
+    wait_for_tx_confirmation(&l2_rpc_origin, &subnet_stx_publish_txid);
 
     // Check that the user does not own any additional STX on the subnet now
     let account = get_account(&l2_rpc_origin, &user_addr);
@@ -1470,11 +1601,10 @@ fn l1_deposit_and_withdraw_stx_integration_test() {
     l1_nonce += 1;
 
     // Deposit stx into subnet contract on L1
-    submit_tx(&l1_rpc_origin, &l1_deposit_stx_tx);
+    let l1_deposit_stx_txid = submit_tx(&l1_rpc_origin, &l1_deposit_stx_tx);
 
-    // Wait to give the run loop time to mine a block
-    wait_for_next_stacks_block(&sortition_db);
-    wait_for_next_stacks_block(&sortition_db);
+    // Wait until the subnet has processed the submitted transactions
+    wait_for_l1_tx_in_subnet(l1_rpc_origin, &sortition_db, &l1_deposit_stx_txid);
 
     // Check that the user owns additional STX on the subnet now
     let account = get_account(&l2_rpc_origin, &user_addr);
@@ -1769,7 +1899,7 @@ fn l2_simple_contract_calls() {
 
     let mut run_loop = neon::RunLoop::new(config.clone());
     let termination_switch = run_loop.get_termination_switch();
-    let run_loop_thread = thread::spawn(move || run_loop.start(None, 0));
+    let run_loop_thread = thread::spawn(move || run_loop.start(None, 0).unwrap());
 
     // Sleep to give the run loop time to start
     thread::sleep(Duration::from_millis(2_000));
@@ -1894,7 +2024,7 @@ fn nft_deposit_and_withdraw_integration_test() {
 
     let mut run_loop = neon::RunLoop::new(config.clone());
     let termination_switch = run_loop.get_termination_switch();
-    let run_loop_thread = thread::spawn(move || run_loop.start(None, 0));
+    let run_loop_thread = thread::spawn(move || run_loop.start(None, 0).unwrap());
 
     // Give the run loop time to start.
     thread::sleep(Duration::from_millis(2_000));
@@ -1909,9 +2039,8 @@ fn nft_deposit_and_withdraw_integration_test() {
         .expect("stacks l1 controller didn't start");
     let mut l1_nonce = 0;
 
-    // Sleep to give the L1 chain time to start
-    thread::sleep(Duration::from_millis(10_000));
-    wait_for_target_l1_block(&sortition_db, MOCKNET_EPOCH_2_1);
+    // Wait for the L1 chain to start and reach epoch 2.1
+    wait_for_l1_height(l1_rpc_origin, &sortition_db, MOCKNET_EPOCH_2_1 + 1);
 
     l1_nonce = publish_subnet_contracts_to_l1(
         l1_nonce,
@@ -1992,9 +2121,11 @@ fn nft_deposit_and_withdraw_integration_test() {
     );
     l1_nonce += 1;
 
-    submit_tx(&l2_rpc_origin, &subnet_nft_publish);
-    submit_tx(l1_rpc_origin, &subnet_setup_nft_tx);
-    wait_to_confirm_subnet_transactions(&sortition_db);
+    let subnet_nft_publish_txid = submit_tx(&l2_rpc_origin, &subnet_nft_publish);
+    let subnet_setup_nft_txid = submit_tx(l1_rpc_origin, &subnet_setup_nft_tx);
+    // Wait until the subnet has processed the submitted transactions
+    wait_for_tx_confirmation(&l2_rpc_origin, &subnet_nft_publish_txid);
+    wait_for_l1_tx_in_subnet(l1_rpc_origin, &sortition_db, &subnet_setup_nft_txid);
 
     // Mint a nft-token for user on L1 chain (ID = 1)
     let l1_mint_nft_tx = make_contract_call(
@@ -2022,9 +2153,11 @@ fn nft_deposit_and_withdraw_integration_test() {
     );
     l2_nonce += 1;
 
-    submit_tx(&l2_rpc_origin, &l2_mint_nft_tx);
-    submit_tx(l1_rpc_origin, &l1_mint_nft_tx);
-    wait_to_confirm_subnet_transactions(&sortition_db);
+    let l2_mint_nft_txid = submit_tx(&l2_rpc_origin, &l2_mint_nft_tx);
+    let l1_mint_nft_txid = submit_tx(l1_rpc_origin, &l1_mint_nft_tx);
+    // Wait until the subnet has processed the submitted transactions
+    wait_for_tx_confirmation(&l2_rpc_origin, &l2_mint_nft_txid);
+    wait_for_l1_tx_in_subnet(l1_rpc_origin, &sortition_db, &l1_mint_nft_txid);
 
     // Check that the user does not own the L1 native NFT on the subnet now
     let res = call_read_only(
@@ -2065,11 +2198,10 @@ fn nft_deposit_and_withdraw_integration_test() {
     l1_nonce += 1;
 
     // deposit nft-token into subnet contract on L1
-    submit_tx(&l1_rpc_origin, &l1_deposit_nft_tx);
+    let l1_deposit_nft_txid = submit_tx(&l1_rpc_origin, &l1_deposit_nft_tx);
 
-    // Sleep to give the run loop time to mine a block
-    wait_for_next_stacks_block(&sortition_db);
-    wait_for_next_stacks_block(&sortition_db);
+    // Wait until the subnet has processed the submitted transactions
+    wait_for_l1_tx_in_subnet(l1_rpc_origin, &sortition_db, &l1_deposit_nft_txid);
 
     // Check that the user owns the L1 native NFT on the subnet now
     let res = call_read_only(
@@ -2694,7 +2826,7 @@ fn nft_deposit_failure_and_refund_integration_test() {
 
     let mut run_loop = neon::RunLoop::new(config.clone());
     let termination_switch = run_loop.get_termination_switch();
-    let run_loop_thread = thread::spawn(move || run_loop.start(None, 0));
+    let run_loop_thread = thread::spawn(move || run_loop.start(None, 0).unwrap());
 
     // Give the run loop time to start.
     thread::sleep(Duration::from_millis(2_000));
@@ -2709,9 +2841,8 @@ fn nft_deposit_failure_and_refund_integration_test() {
         .expect("stacks l1 controller didn't start");
     let mut l1_nonce = 0;
 
-    // Sleep to give the L1 chain time to start
-    thread::sleep(Duration::from_millis(10_000));
-    wait_for_target_l1_block(&sortition_db, MOCKNET_EPOCH_2_1);
+    // Wait for the L1 chain to start and reach epoch 2.1
+    wait_for_l1_height(l1_rpc_origin, &sortition_db, MOCKNET_EPOCH_2_1 + 1);
 
     l1_nonce = publish_subnet_contracts_to_l1(
         l1_nonce,
@@ -2793,9 +2924,11 @@ fn nft_deposit_failure_and_refund_integration_test() {
     );
     l1_nonce += 1;
 
-    submit_tx(&l2_rpc_origin, &subnet_nft_publish);
-    submit_tx(l1_rpc_origin, &subnet_setup_nft_tx);
-    wait_to_confirm_subnet_transactions(&sortition_db);
+    let subnet_nft_publish_txid = submit_tx(&l2_rpc_origin, &subnet_nft_publish);
+    let subnet_setup_nft_txid = submit_tx(l1_rpc_origin, &subnet_setup_nft_tx);
+    // Wait until the subnet has processed the submitted transactions
+    wait_for_tx_confirmation(&l2_rpc_origin, &subnet_nft_publish_txid);
+    wait_for_l1_tx_in_subnet(l1_rpc_origin, &sortition_db, &subnet_setup_nft_txid);
 
     // Mint a nft-token for user on L1 chain (ID = 1)
     let l1_mint_nft_tx = make_contract_call(
@@ -2823,9 +2956,11 @@ fn nft_deposit_failure_and_refund_integration_test() {
     );
     l2_nonce += 1;
 
-    submit_tx(&l2_rpc_origin, &l2_mint_nft_tx);
-    submit_tx(l1_rpc_origin, &l1_mint_nft_tx);
-    wait_to_confirm_subnet_transactions(&sortition_db);
+    let l2_mint_nft_txid = submit_tx(&l2_rpc_origin, &l2_mint_nft_tx);
+    let l1_mint_nft_txid = submit_tx(l1_rpc_origin, &l1_mint_nft_tx);
+    // Wait until the subnet has processed the submitted transactions
+    wait_for_tx_confirmation(&l2_rpc_origin, &l2_mint_nft_txid);
+    wait_for_l1_tx_in_subnet(l1_rpc_origin, &sortition_db, &l1_mint_nft_txid);
 
     // Check that the user does not own the L1 native NFT on the subnet now
     let res = call_read_only(
@@ -2866,11 +3001,10 @@ fn nft_deposit_failure_and_refund_integration_test() {
     l1_nonce += 1;
 
     // Attempt deposit nft-token into subnet contract on L1. Should fail
-    submit_tx(&l1_rpc_origin, &l1_deposit_nft_tx);
+    let l1_deposit_nft_txid = submit_tx(&l1_rpc_origin, &l1_deposit_nft_tx);
 
-    // Sleep to give the run loop time to mine a block
-    wait_for_next_stacks_block(&sortition_db);
-    wait_for_next_stacks_block(&sortition_db);
+    // Wait until the subnet has processed the submitted transactions
+    wait_for_l1_tx_in_subnet(l1_rpc_origin, &sortition_db, &l1_deposit_nft_txid);
 
     // Check that transfer failed and user does not have NFT on L2
     let res = call_read_only(
@@ -3146,6 +3280,235 @@ fn nft_deposit_failure_and_refund_integration_test() {
     run_loop_thread.join().expect("Failed to join run loop.");
 }
 
+/// This integration test verifies that:
+/// (a) a semi-fungible token minted on L1 can be deposited into the subnet
+/// (b) the subnet contract credits the deposited amount of that token id
+#[test]
+#[allow(unused_assignments)]
+fn sft_deposit_integration_test() {
+    // running locally:
+    // STACKS_BASE_DIR=~/devel/stacks-blockchain/target/release/stacks-node STACKS_NODE_TEST=1 cargo test --workspace l1_observer_test::sft_deposit_integration_test
+    if env::var("STACKS_NODE_TEST") != Ok("1".into()) {
+        return;
+    }
+
+    // Start Stacks L1.
+    let l1_toml_file = "../../contrib/conf/stacks-l1-mocknet.toml";
+    let l1_rpc_origin = "http://127.0.0.1:20443";
+
+    // Start the L2 run loop.
+    let mut config = super::new_test_conf();
+    config.node.mining_key = Some(MOCKNET_PRIVATE_KEY_2.clone());
+    let miner_account = to_addr(&MOCKNET_PRIVATE_KEY_2);
+    let user_addr = to_addr(&MOCKNET_PRIVATE_KEY_1);
+    config.add_initial_balance(user_addr.to_string(), 10000000);
+    config.add_initial_balance(miner_account.to_string(), 10000000);
+
+    config.burnchain.first_burn_header_height = 1;
+    config.burnchain.chain = "stacks_layer_1".to_string();
+    config.burnchain.rpc_ssl = false;
+    config.burnchain.rpc_port = 20443;
+    config.burnchain.peer_host = "127.0.0.1".into();
+    config.node.wait_time_for_microblocks = 10_000;
+    config.node.rpc_bind = "127.0.0.1:30443".into();
+    config.node.p2p_bind = "127.0.0.1:30444".into();
+    let l2_rpc_origin = format!("http://{}", &config.node.rpc_bind);
+    let mut l2_nonce = 0;
+
+    config.burnchain.contract_identifier =
+        QualifiedContractIdentifier::new(user_addr.into(), "subnet-controller".into());
+
+    config.node.miner = true;
+
+    config.events_observers.push(EventObserverConfig {
+        endpoint: format!("localhost:{}", test_observer::EVENT_OBSERVER_PORT),
+        events_keys: vec![EventKeyType::AnyEvent],
+    });
+
+    test_observer::spawn();
+
+    let mut run_loop = neon::RunLoop::new(config.clone());
+    let termination_switch = run_loop.get_termination_switch();
+    let run_loop_thread = thread::spawn(move || run_loop.start(None, 0).unwrap());
+
+    // Give the run loop time to start.
+    thread::sleep(Duration::from_millis(2_000));
+
+    let burnchain = Burnchain::new(&config.get_burn_db_path(), &config.burnchain.chain).unwrap();
+    let (sortition_db, _) = burnchain.open_db(true).unwrap();
+
+    let mut stacks_l1_controller = StacksL1Controller::new(l1_toml_file.to_string(), true);
+    let _stacks_res = stacks_l1_controller
+        .start_process()
+        .expect("stacks l1 controller didn't start");
+    let mut l1_nonce = 0;
+
+    // Wait for the L1 chain to start and reach epoch 2.1
+    wait_for_l1_height(l1_rpc_origin, &sortition_db, MOCKNET_EPOCH_2_1 + 1);
+
+    l1_nonce = publish_subnet_contracts_to_l1(
+        l1_nonce,
+        &config,
+        miner_account.clone().into(),
+        user_addr.clone().into(),
+    );
+
+    // Publish a simple SFT onto L1
+    let sft_content =
+        include_str!("../../../../core-contracts/contracts/output/mocknet/helper/simple-sft.clar");
+    let sft_publish = make_contract_publish(
+        &MOCKNET_PRIVATE_KEY_1,
+        LAYER_1_CHAIN_ID_TESTNET,
+        l1_nonce,
+        1_000_000,
+        "simple-sft",
+        &sft_content,
+    );
+    l1_nonce += 1;
+    let sft_contract_id =
+        QualifiedContractIdentifier::new(user_addr.into(), ContractName::from("simple-sft"));
+
+    submit_tx(l1_rpc_origin, &sft_publish);
+
+    // Sleep to give the run loop time to listen to blocks,
+    //  and start mining L2 blocks
+    wait_for_next_stacks_block(&sortition_db);
+    wait_for_next_stacks_block(&sortition_db);
+
+    // Publish subnet contract for sft-token
+    let subnet_sft_content = include_str!(
+        "../../../../core-contracts/contracts/output/mocknet/helper/simple-sft-l2.clar"
+    );
+    let subnet_sft_publish = make_contract_publish(
+        &MOCKNET_PRIVATE_KEY_1,
+        config.node.chain_id,
+        l2_nonce,
+        1_000_000,
+        "simple-sft",
+        subnet_sft_content,
+    );
+    l2_nonce += 1;
+    let subnet_sft_contract_id =
+        QualifiedContractIdentifier::new(user_addr.into(), ContractName::from("simple-sft"));
+
+    // Setup subnet contract
+    let subnet_setup_sft_tx = make_contract_call(
+        &MOCKNET_PRIVATE_KEY_1,
+        LAYER_1_CHAIN_ID_TESTNET,
+        l1_nonce,
+        1_000_000,
+        &user_addr,
+        config.burnchain.contract_identifier.name.as_str(),
+        "register-new-sft-contract",
+        &[
+            Value::Principal(PrincipalData::Contract(sft_contract_id.clone())),
+            Value::Principal(PrincipalData::Contract(subnet_sft_contract_id.clone())),
+        ],
+    );
+    l1_nonce += 1;
+
+    let subnet_sft_publish_txid = submit_tx(&l2_rpc_origin, &subnet_sft_publish);
+    let subnet_setup_sft_txid = submit_tx(l1_rpc_origin, &subnet_setup_sft_tx);
+    // Wait until the subnet has processed the submitted transactions
+    wait_for_tx_confirmation(&l2_rpc_origin, &subnet_sft_publish_txid);
+    wait_for_l1_tx_in_subnet(l1_rpc_origin, &sortition_db, &subnet_setup_sft_txid);
+
+    // Mint 10 of sft-token id 3 for user on L1 chain
+    let l1_mint_sft_tx = make_contract_call(
+        &MOCKNET_PRIVATE_KEY_1,
+        LAYER_1_CHAIN_ID_TESTNET,
+        l1_nonce,
+        1_000_000,
+        &user_addr,
+        "simple-sft",
+        "test-mint",
+        &[
+            Value::UInt(3),
+            Value::UInt(10),
+            Value::Principal(user_addr.into()),
+        ],
+    );
+    l1_nonce += 1;
+
+    let l1_mint_sft_txid = submit_tx(l1_rpc_origin, &l1_mint_sft_tx);
+    // Wait until the subnet has processed the submitted transactions
+    wait_for_l1_tx_in_subnet(l1_rpc_origin, &sortition_db, &l1_mint_sft_txid);
+
+    // Deposit 4 of them into the subnet
+    let l1_deposit_sft_tx = make_contract_call(
+        &MOCKNET_PRIVATE_KEY_1,
+        LAYER_1_CHAIN_ID_TESTNET,
+        l1_nonce,
+        1_000_000,
+        &user_addr,
+        config.burnchain.contract_identifier.name.as_str(),
+        "deposit-sft-asset",
+        &[
+            Value::Principal(PrincipalData::Contract(sft_contract_id.clone())),
+            Value::UInt(3),
+            Value::UInt(4),
+            Value::Principal(user_addr.into()),
+        ],
+    );
+    l1_nonce += 1;
+
+    let l1_deposit_sft_txid = submit_tx(l1_rpc_origin, &l1_deposit_sft_tx);
+
+    // Wait until the subnet has processed the submitted transactions
+    wait_for_l1_tx_in_subnet(l1_rpc_origin, &sortition_db, &l1_deposit_sft_txid);
+
+    let balance_type =
+        TypeSignature::ResponseType(Box::new((TypeSignature::UIntType, TypeSignature::UIntType)));
+
+    // Check that the user owns 4 of token id 3 on the subnet now
+    let res = call_read_only(
+        &l2_rpc_origin,
+        &user_addr,
+        "simple-sft",
+        "get-balance",
+        vec![
+            Value::UInt(3).serialize_to_hex(),
+            Value::Principal(user_addr.into()).serialize_to_hex(),
+        ],
+    );
+    assert!(res.get("cause").is_none());
+    assert!(res["okay"].as_bool().unwrap());
+    let result = res["result"]
+        .as_str()
+        .unwrap()
+        .strip_prefix("0x")
+        .unwrap()
+        .to_string();
+    let balance = deserialize_value(&result, &balance_type);
+    assert_eq!(balance, Value::okay(Value::UInt(4)).unwrap());
+
+    // Check that the user kept 6 of token id 3 on L1
+    let res = call_read_only(
+        &l1_rpc_origin,
+        &user_addr,
+        "simple-sft",
+        "get-balance",
+        vec![
+            Value::UInt(3).serialize_to_hex(),
+            Value::Principal(user_addr.into()).serialize_to_hex(),
+        ],
+    );
+    assert!(res.get("cause").is_none());
+    assert!(res["okay"].as_bool().unwrap());
+    let result = res["result"]
+        .as_str()
+        .unwrap()
+        .strip_prefix("0x")
+        .unwrap()
+        .to_string();
+    let balance = deserialize_value(&result, &balance_type);
+    assert_eq!(balance, Value::okay(Value::UInt(6)).unwrap());
+
+    termination_switch.store(false, Ordering::SeqCst);
+    stacks_l1_controller.kill_process();
+    run_loop_thread.join().expect("Failed to join run loop.");
+}
+
 /// This integration test verifies that:
 /// (a) assets minted on L1 chain can be deposited into subnet
 /// (b) assets minted on subnet can be withdrawn to the L1
@@ -3195,7 +3558,7 @@ fn ft_deposit_and_withdraw_integration_test() {
 
     let mut run_loop = neon::RunLoop::new(config.clone());
     let termination_switch = run_loop.get_termination_switch();
-    let run_loop_thread = thread::spawn(move || run_loop.start(None, 0));
+    let run_loop_thread = thread::spawn(move || run_loop.start(None, 0).unwrap());
 
     // Give the run loop time to start.
     thread::sleep(Duration::from_millis(2_000));
@@ -3210,9 +3573,8 @@ fn ft_deposit_and_withdraw_integration_test() {
         .expect("stacks l1 controller didn't start");
     let mut l1_nonce = 0;
 
-    // Sleep to give the L1 chain time to start
-    thread::sleep(Duration::from_millis(10_000));
-    wait_for_target_l1_block(&sortition_db, MOCKNET_EPOCH_2_1);
+    // Wait for the L1 chain to start and reach epoch 2.1
+    wait_for_l1_height(l1_rpc_origin, &sortition_db, MOCKNET_EPOCH_2_1 + 1);
 
     l1_nonce = publish_subnet_contracts_to_l1(
         l1_nonce,
@@ -3277,8 +3639,9 @@ fn ft_deposit_and_withdraw_integration_test() {
     let subnet_ft_contract_id =
         QualifiedContractIdentifier::new(user_addr.into(), ContractName::from("simple-ft"));
 
-    submit_tx(&l2_rpc_origin, &subnet_ft_publish);
-    wait_to_confirm_subnet_transactions(&sortition_db);
+    let subnet_ft_publish_txid = submit_tx(&l2_rpc_origin, &subnet_ft_publish);
+    // Wait until the subnet has processed the submitted transactions
+    wait_for_tx_confirmation(&l2_rpc_origin, &subnet_ft_publish_txid);
 
     // Register the contract with the subnet
     let subnet_setup_ft_tx = make_contract_call(
@@ -3296,7 +3659,7 @@ fn ft_deposit_and_withdraw_integration_test() {
     );
     l1_nonce += 1;
 
-    submit_tx(l1_rpc_origin, &subnet_setup_ft_tx);
+    let subnet_setup_ft_txid = submit_tx(l1_rpc_origin, &subnet_setup_ft_tx);
 
     // Mint 2 ft-tokens for user on L1 chain
     let l1_mint_ft_tx = make_contract_call(
@@ -3324,9 +3687,12 @@ fn ft_deposit_and_withdraw_integration_test() {
     );
     l2_nonce += 1;
 
-    submit_tx(&l2_rpc_origin, &l2_mint_ft_tx);
-    submit_tx(l1_rpc_origin, &l1_mint_ft_tx);
-    wait_to_confirm_subnet_transactions(&sortition_db);
+    let l2_mint_ft_txid = submit_tx(&l2_rpc_origin, &l2_mint_ft_tx);
+    let l1_mint_ft_txid = submit_tx(l1_rpc_origin, &l1_mint_ft_tx);
+    // Wait until the subnet has processed the submitted transactions
+    wait_for_l1_tx_in_subnet(l1_rpc_origin, &sortition_db, &subnet_setup_ft_txid);
+    wait_for_tx_confirmation(&l2_rpc_origin, &l2_mint_ft_txid);
+    wait_for_l1_tx_in_subnet(l1_rpc_origin, &sortition_db, &l1_mint_ft_txid);
 
     // Check that the user does not own the L1 native ft on the subnet now
     let res = call_read_only(
@@ -3368,11 +3734,10 @@ fn ft_deposit_and_withdraw_integration_test() {
     l1_nonce += 1;
 
     // deposit 1 ft-token into subnet contract on L1
-    submit_tx(&l1_rpc_origin, &l1_deposit_ft_tx);
+    let l1_deposit_ft_txid = submit_tx(&l1_rpc_origin, &l1_deposit_ft_tx);
 
-    // Sleep to give the run loop time to mine a block
-    wait_for_next_stacks_block(&sortition_db);
-    wait_for_next_stacks_block(&sortition_db);
+    // Wait until the subnet has processed the submitted transactions
+    wait_for_l1_tx_in_subnet(l1_rpc_origin, &sortition_db, &l1_deposit_ft_txid);
 
     // Check that the user owns the L1 native ft on the subnet now
     let res = call_read_only(
@@ -3783,7 +4148,7 @@ fn ft_deposit_failure_and_refund_integration_test() {
 
     let mut run_loop = neon::RunLoop::new(config.clone());
     let termination_switch = run_loop.get_termination_switch();
-    let run_loop_thread = thread::spawn(move || run_loop.start(None, 0));
+    let run_loop_thread = thread::spawn(move || run_loop.start(None, 0).unwrap());
 
     // Give the run loop time to start.
     thread::sleep(Duration::from_millis(2_000));
@@ -3798,9 +4163,8 @@ fn ft_deposit_failure_and_refund_integration_test() {
         .expect("stacks l1 controller didn't start");
     let mut l1_nonce = 0;
 
-    // Sleep to give the L1 chain time to start
-    thread::sleep(Duration::from_millis(10_000));
-    wait_for_target_l1_block(&sortition_db, MOCKNET_EPOCH_2_1);
+    // Wait for the L1 chain to start and reach epoch 2.1
+    wait_for_l1_height(l1_rpc_origin, &sortition_db, MOCKNET_EPOCH_2_1 + 1);
 
     l1_nonce = publish_subnet_contracts_to_l1(
         l1_nonce,
@@ -3866,8 +4230,9 @@ fn ft_deposit_failure_and_refund_integration_test() {
     let subnet_ft_contract_id =
         QualifiedContractIdentifier::new(user_addr.into(), ContractName::from("simple-ft"));
 
-    submit_tx(&l2_rpc_origin, &subnet_ft_publish);
-    wait_to_confirm_subnet_transactions(&sortition_db);
+    let subnet_ft_publish_txid = submit_tx(&l2_rpc_origin, &subnet_ft_publish);
+    // Wait until the subnet has processed the submitted transactions
+    wait_for_tx_confirmation(&l2_rpc_origin, &subnet_ft_publish_txid);
 
     // Register the contract with the subnet
     let subnet_setup_ft_tx = make_contract_call(
@@ -3885,7 +4250,7 @@ fn ft_deposit_failure_and_refund_integration_test() {
     );
     l1_nonce += 1;
 
-    submit_tx(l1_rpc_origin, &subnet_setup_ft_tx);
+    let subnet_setup_ft_txid = submit_tx(l1_rpc_origin, &subnet_setup_ft_tx);
 
     // Mint 10 ft-tokens for user on L1 chain
     let l1_mint_ft_tx = make_contract_call(
@@ -3900,11 +4265,11 @@ fn ft_deposit_failure_and_refund_integration_test() {
     );
     l1_nonce += 1;
 
-    submit_tx(l1_rpc_origin, &l1_mint_ft_tx);
+    let l1_mint_ft_txid = submit_tx(l1_rpc_origin, &l1_mint_ft_tx);
 
-    // Sleep to give the run loop time to mine a block
-    wait_for_next_stacks_block(&sortition_db);
-    wait_for_next_stacks_block(&sortition_db);
+    // Wait until the subnet has processed the submitted transactions
+    wait_for_l1_tx_in_subnet(l1_rpc_origin, &sortition_db, &subnet_setup_ft_txid);
+    wait_for_l1_tx_in_subnet(l1_rpc_origin, &sortition_db, &l1_mint_ft_txid);
 
     // Check that user has 0 tokens on L2
     let res = call_read_only(
@@ -3946,11 +4311,10 @@ fn ft_deposit_failure_and_refund_integration_test() {
     l1_nonce += 1;
 
     // Deposit 3 ft-tokens into subnet contract on L1
-    submit_tx(&l1_rpc_origin, &l1_deposit_ft_tx);
+    let l1_deposit_ft_txid = submit_tx(&l1_rpc_origin, &l1_deposit_ft_tx);
 
-    // Sleep to give the run loop time to mine a block
-    wait_for_next_stacks_block(&sortition_db);
-    wait_for_next_stacks_block(&sortition_db);
+    // Wait until the subnet has processed the submitted transactions
+    wait_for_l1_tx_in_subnet(l1_rpc_origin, &sortition_db, &l1_deposit_ft_txid);
 
     // Check that the deposit failed and user still has 0 tokens on L2
     let res = call_read_only(
@@ -72,7 +72,7 @@ fn withdraw_unregistered_asset() {
 
     let mut run_loop = neon::RunLoop::new(config.clone());
     let termination_switch = run_loop.get_termination_switch();
-    let run_loop_thread = thread::spawn(move || run_loop.start(None, 0));
+    let run_loop_thread = thread::spawn(move || run_loop.start(None, 0).unwrap());
 
     // Give the run loop time to start.
     thread::sleep(Duration::from_millis(2_000));
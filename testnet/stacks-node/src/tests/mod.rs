@@ -2,6 +2,7 @@ use std::convert::TryInto;
 
 use rand::{Rng, RngCore};
 
+use stacks::address::AddressHashMode;
 use stacks::chainstate::burn::ConsensusHash;
 use stacks::chainstate::stacks::{
     db::StacksChainState, miner::BlockBuilderSettings, miner::StacksMicroblockBuilder,
@@ -13,14 +14,12 @@ use stacks::chainstate::stacks::{
 };
 use stacks::codec::StacksMessageCodec;
 use stacks::types::chainstate::StacksAddress;
-use stacks::util::get_epoch_time_secs;
 use stacks::util::hash::hex_bytes;
 use stacks::util::secp256k1::Secp256k1PrivateKey;
 use stacks::util_lib::strings::StacksString;
 use stacks::vm::database::BurnStateDB;
 use stacks::vm::types::{PrincipalData, QualifiedContractIdentifier};
 use stacks::vm::{ClarityName, ContractName, Value};
-use stacks::{address::AddressHashMode, util::hash::to_hex};
 
 use super::Config;
 
@@ -30,6 +29,7 @@ pub mod l1_observer_test;
 pub mod l2_withdrawal;
 #[allow(dead_code)]
 pub mod neon_integrations;
+pub mod withdrawal_flow;
 
 // $ cat /tmp/out.clar
 pub const STORE_CONTRACT: &str = r#"(define-map store { key: (string-ascii 32) } { value: (string-ascii 32) })
@@ -260,11 +260,9 @@ pub fn new_test_conf() -> Config {
     rng.fill_bytes(&mut buf);
 
     let mut conf = Config::default();
-    conf.node.working_dir = format!(
-        "/tmp/subnet-node-tests/integrations-neon/{}-{}",
-        to_hex(&buf),
-        get_epoch_time_secs()
-    );
+    conf.node
+        .use_ephemeral_working_dir()
+        .expect("Failed to create ephemeral working directory");
     conf.node.seed =
         hex_bytes("0000000000000000000000000000000000000000000000000000000000000000").unwrap();
     conf.add_initial_balance(
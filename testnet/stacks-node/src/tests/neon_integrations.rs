@@ -8,7 +8,7 @@ use stacks::chainstate::burn::db::sortdb::SortitionDB;
 use stacks::chainstate::burn::ConsensusHash;
 use stacks::chainstate::stacks::TransactionPayload;
 use stacks::codec::StacksMessageCodec;
-use stacks::net::{AccountEntryResponse, ContractSrcResponse, RPCPeerInfoData, WithdrawalResponse};
+use stacks::net::{RPCPeerInfoData, TipRequest, WithdrawalResponse};
 use stacks::types::chainstate::{BlockHeaderHash, StacksAddress};
 use stacks::util::get_epoch_time_secs;
 use stacks::util::hash::{hex_bytes, Hash160};
@@ -21,10 +21,13 @@ use stacks::{
     net::RPCPoxInfoData,
 };
 
+use clarity::vm::types::PrincipalData;
 use clarity::vm::Value as ClarityValue;
+use subnet_rpc_client::{parse_clarity_value, Error as RpcClientError, SubnetRpcClient};
 
 use crate::burnchains::mock_events::{reset_static_burnblock_simulator_channel, MockController};
 use crate::config::{EventKeyType, EventObserverConfig};
+use crate::export;
 use crate::neon;
 use crate::rand::Rng;
 use crate::tests::l1_observer_test::MOCKNET_PRIVATE_KEY_1;
@@ -82,6 +85,7 @@ pub mod test_observer {
 
     lazy_static! {
         pub static ref NEW_BLOCKS: Mutex<Vec<serde_json::Value>> = Mutex::new(Vec::new());
+        pub static ref REJECTED_BLOCKS: Mutex<Vec<serde_json::Value>> = Mutex::new(Vec::new());
         pub static ref MINED_BLOCKS: Mutex<Vec<MinedBlockEvent>> = Mutex::new(Vec::new());
         pub static ref MINED_MICROBLOCKS: Mutex<Vec<MinedMicroblockEvent>> = Mutex::new(Vec::new());
         pub static ref NEW_MICROBLOCKS: Mutex<Vec<serde_json::Value>> = Mutex::new(Vec::new());
@@ -105,6 +109,14 @@ pub mod test_observer {
         Ok(warp::http::StatusCode::OK)
     }
 
+    async fn handle_rejected_block(
+        block: serde_json::Value,
+    ) -> Result<impl warp::Reply, Infallible> {
+        let mut blocks = REJECTED_BLOCKS.lock().unwrap();
+        blocks.push(block);
+        Ok(warp::http::StatusCode::OK)
+    }
+
     async fn handle_microblocks(
         microblocks: serde_json::Value,
     ) -> Result<impl warp::Reply, Infallible> {
@@ -213,6 +225,10 @@ pub mod test_observer {
         NEW_BLOCKS.lock().unwrap().clone()
     }
 
+    pub fn get_rejected_blocks() -> Vec<serde_json::Value> {
+        REJECTED_BLOCKS.lock().unwrap().clone()
+    }
+
     pub fn get_microblocks() -> Vec<serde_json::Value> {
         NEW_MICROBLOCKS.lock().unwrap().clone()
     }
@@ -239,6 +255,10 @@ pub mod test_observer {
             .and(warp::post())
             .and(warp::body::json())
             .and_then(handle_block);
+        let rejected_blocks = warp::path!("rejected_block")
+            .and(warp::post())
+            .and(warp::body::json())
+            .and_then(handle_rejected_block);
         let mempool_txs = warp::path!("new_mempool_tx")
             .and(warp::post())
             .and(warp::body::json())
@@ -271,6 +291,7 @@ pub mod test_observer {
         info!("Spawning warp server");
         warp::serve(
             new_blocks
+                .or(rejected_blocks)
                 .or(mempool_txs)
                 .or(mempool_drop_txs)
                 .or(new_burn_blocks)
@@ -295,6 +316,7 @@ pub mod test_observer {
         ATTACHMENTS.lock().unwrap().clear();
         BURN_BLOCKS.lock().unwrap().clear();
         NEW_BLOCKS.lock().unwrap().clear();
+        REJECTED_BLOCKS.lock().unwrap().clear();
         MEMTXS.lock().unwrap().clear();
         MEMTXS_DROPPED.lock().unwrap().clear();
         MINED_BLOCKS.lock().unwrap().clear();
@@ -435,27 +457,21 @@ pub fn wait_for_microblocks(microblocks_processed: &Arc<AtomicU64>, timeout: u64
 
 /// returns Some(Txid string) on success, None on failure
 pub fn submit_tx_fallible(http_origin: &str, tx: &Vec<u8>) -> Option<String> {
-    let client = reqwest::blocking::Client::new();
-    let path = format!("{}/v2/transactions", http_origin);
-    let res = client
-        .post(&path)
-        .header("Content-Type", "application/octet-stream")
-        .body(tx.clone())
-        .send()
-        .unwrap();
-    if res.status().is_success() {
-        let res: String = res.json().unwrap();
-        assert_eq!(
-            res,
-            StacksTransaction::consensus_deserialize(&mut &tx[..])
-                .unwrap()
-                .txid()
-                .to_string()
-        );
-        Some(res)
-    } else {
-        eprintln!("{}", res.text().unwrap());
-        None
+    match SubnetRpcClient::new(http_origin).submit_raw_transaction(tx) {
+        Ok(txid) => {
+            assert_eq!(
+                txid,
+                StacksTransaction::consensus_deserialize(&mut &tx[..])
+                    .unwrap()
+                    .txid()
+            );
+            Some(txid.to_string())
+        }
+        Err(RpcClientError::Status { body, .. }) => {
+            eprintln!("{}", body);
+            None
+        }
+        Err(e) => panic!("Failed to submit transaction: {}", e),
     }
 }
 
@@ -466,18 +482,7 @@ pub fn submit_tx(http_origin: &str, tx: &Vec<u8>) -> String {
 
 pub fn get_chain_info(conf: &Config) -> RPCPeerInfoData {
     let http_origin = format!("http://{}", &conf.node.rpc_bind);
-    let client = reqwest::blocking::Client::new();
-
-    // get the canonical chain tip
-    let path = format!("{}/v2/info", &http_origin);
-    let tip_info = client
-        .get(&path)
-        .send()
-        .unwrap()
-        .json::<RPCPeerInfoData>()
-        .unwrap();
-
-    tip_info
+    SubnetRpcClient::new(&http_origin).get_info().unwrap()
 }
 
 fn get_tip_anchored_block(conf: &Config) -> (ConsensusHash, StacksBlock) {
@@ -546,7 +551,7 @@ fn mockstack_integration_test() {
 
     let mut btc_regtest_controller = MockController::new(conf, channel.clone());
 
-    thread::spawn(move || run_loop.start(None, 0));
+    thread::spawn(move || run_loop.start(None, 0).unwrap());
 
     // give the run loop some time to start up!
     wait_for_runloop(&blocks_processed);
@@ -626,7 +631,7 @@ fn mockstack_wait_for_first_block() {
     let burnchain = Burnchain::new(&conf.get_burn_db_path(), &conf.burnchain.chain).unwrap();
     let mut btc_regtest_controller = MockController::new(conf, channel.clone());
 
-    thread::spawn(move || run_loop.start(None, 0));
+    thread::spawn(move || run_loop.start(None, 0).unwrap());
 
     wait_for_runloop(&blocks_processed);
 
@@ -648,6 +653,113 @@ fn mockstack_wait_for_first_block() {
     channel.stop_chains_coordinator();
 }
 
+/// Test that a node following a simulated L1 makes its own L1 blocks, and mines on them with
+/// nothing else driving it.
+#[test]
+#[ignore]
+fn simulated_l1_mines_blocks() {
+    reset_static_burnblock_simulator_channel();
+    let (mut conf, _miner_account) = mockstack_test_conf();
+    conf.burnchain.chain = "simulated".into();
+    conf.burnchain.simulated_block_time_ms = 2_000;
+
+    let http_origin = format!("http://{}", &conf.node.rpc_bind);
+
+    let mut run_loop = neon::RunLoop::new(conf.clone());
+    let blocks_processed = run_loop.get_blocks_processed_arc();
+    let channel = run_loop.get_coordinator_channel().unwrap();
+
+    thread::spawn(move || run_loop.start(None, 0).unwrap());
+
+    wait_for_runloop(&blocks_processed);
+
+    let client = SubnetRpcClient::new(&http_origin);
+    let deadline = Instant::now() + Duration::from_secs(120);
+    loop {
+        let tip_height = client
+            .get_info()
+            .map(|info| info.stacks_tip_height)
+            .unwrap_or(0);
+        if tip_height >= 2 {
+            break;
+        }
+        assert!(
+            Instant::now() < deadline,
+            "Timed out waiting for blocks mined on the simulated L1"
+        );
+        thread::sleep(Duration::from_secs(1));
+    }
+
+    channel.stop_chains_coordinator();
+}
+
+/// Test that the blocks a node has mined can be exported, with their transactions, once the
+/// node has stopped.
+#[test]
+#[ignore]
+fn export_mined_blocks() {
+    reset_static_burnblock_simulator_channel();
+    let (conf, _miner_account) = mockstack_test_conf();
+
+    let mut run_loop = neon::RunLoop::new(conf.clone());
+    let blocks_processed = run_loop.get_blocks_processed_arc();
+    let channel = run_loop.get_coordinator_channel().unwrap();
+    let burnchain = Burnchain::new(&conf.get_burn_db_path(), &conf.burnchain.chain).unwrap();
+    let mut btc_regtest_controller = MockController::new(conf.clone(), channel.clone());
+
+    thread::spawn(move || run_loop.start(None, 0).unwrap());
+
+    wait_for_runloop(&blocks_processed);
+    btc_regtest_controller.next_block(None);
+    btc_regtest_controller.next_block(None);
+
+    let (sortition_db, _) = burnchain.open_db(true).unwrap();
+    for _ in 0..4 {
+        next_block_and_wait(
+            &mut btc_regtest_controller,
+            None,
+            &blocks_processed,
+            &sortition_db,
+        );
+    }
+    let tip_height = get_chain_info(&conf).stacks_tip_height;
+    assert!(tip_height >= 2);
+
+    channel.stop_chains_coordinator();
+    thread::sleep(Duration::from_secs(5));
+
+    let dest_path = format!("{}/export", &conf.node.working_dir);
+    let summary = export::export_blocks(&conf, &dest_path, 1, None, true).unwrap();
+    assert_eq!(summary.num_blocks, tip_height);
+    // every block has at least its coinbase
+    assert!(summary.num_txs >= summary.num_blocks);
+
+    let blocks = std::fs::read_to_string(format!("{}/blocks.jsonl", &dest_path)).unwrap();
+    let heights: Vec<u64> = blocks
+        .lines()
+        .map(|line| {
+            let block: serde_json::Value = serde_json::from_str(line).unwrap();
+            block["block_height"].as_u64().unwrap()
+        })
+        .collect();
+    assert_eq!(heights, (1..=tip_height).collect::<Vec<_>>());
+
+    let csv = std::fs::read_to_string(format!("{}/transactions.csv", &dest_path)).unwrap();
+    assert_eq!(csv.lines().count() as u64, summary.num_txs + 1);
+
+    let manifest: serde_json::Value = serde_json::from_str(
+        &std::fs::read_to_string(format!("{}/manifest.json", &dest_path)).unwrap(),
+    )
+    .unwrap();
+    assert_eq!(manifest["blocks"], summary.num_blocks);
+    assert_eq!(manifest["transactions"], summary.num_txs);
+    assert_eq!(manifest["to_height"], tip_height);
+    assert_eq!(manifest["files"].as_array().unwrap().len(), 4);
+
+    // the scratch chainstate the blocks were re-executed into is gone
+    assert!(std::fs::metadata(format!("{}/.replay", &dest_path)).is_err());
+}
+
 fn get_balance<F: std::fmt::Display>(http_origin: &str, account: &F) -> u128 {
     get_account(http_origin, account).balance
 }
@@ -665,18 +777,24 @@ pub struct WithdrawalEntry {
     pub siblings: ClarityValue,
 }
 
+impl WithdrawalEntry {
+    fn from_response(res: &WithdrawalResponse) -> WithdrawalEntry {
+        WithdrawalEntry {
+            leaf_hash: parse_clarity_value(&res.withdrawal_leaf_hash).unwrap(),
+            root_hash: parse_clarity_value(&res.withdrawal_root).unwrap(),
+            siblings: parse_clarity_value(&res.sibling_hashes).unwrap(),
+        }
+    }
+}
+
 pub fn get_account<F: std::fmt::Display>(http_origin: &str, account: &F) -> Account {
-    let client = reqwest::blocking::Client::new();
-    let path = format!("{}/v2/accounts/{}?proof=0", http_origin, account);
-    let res = client
-        .get(&path)
-        .send()
-        .unwrap()
-        .json::<AccountEntryResponse>()
+    let principal = PrincipalData::parse(&account.to_string()).unwrap();
+    let res = SubnetRpcClient::new(http_origin)
+        .get_account(&principal, &TipRequest::UseLatestUnconfirmedTip)
         .unwrap();
     info!("Account response: {:#?}", res);
     Account {
-        balance: u128::from_str_radix(&res.balance[2..], 16).unwrap(),
+        balance: res.balance,
         nonce: res.nonce,
     }
 }
@@ -688,24 +806,12 @@ pub fn get_withdrawal_entry<F: std::fmt::Display>(
     withdrawal_id: u32,
     amount: u64,
 ) -> WithdrawalEntry {
-    let client = reqwest::blocking::Client::new();
-    let path = format!(
-        "{}/v2/withdrawal/stx/{}/{}/{}/{}",
-        http_origin, block_height, sender, withdrawal_id, amount
-    );
-
-    let res = client
-        .get(&path)
-        .send()
-        .unwrap()
-        .json::<WithdrawalResponse>()
+    let sender = PrincipalData::parse(&sender.to_string()).unwrap();
+    let res = SubnetRpcClient::new(http_origin)
+        .get_stx_withdrawal(block_height, &sender, withdrawal_id, amount.into())
         .unwrap();
     info!("Withdrawal response: {:#?}", res);
-    WithdrawalEntry {
-        leaf_hash: ClarityValue::try_deserialize_hex_untyped(&res.withdrawal_leaf_hash).unwrap(),
-        root_hash: ClarityValue::try_deserialize_hex_untyped(&res.withdrawal_root).unwrap(),
-        siblings: ClarityValue::try_deserialize_hex_untyped(&res.sibling_hashes).unwrap(),
-    }
+    WithdrawalEntry::from_response(&res)
 }
 
 pub fn get_ft_withdrawal_entry<F: std::fmt::Display>(
@@ -716,30 +822,18 @@ pub fn get_ft_withdrawal_entry<F: std::fmt::Display>(
     contract_identifier: QualifiedContractIdentifier,
     amount: u64,
 ) -> WithdrawalEntry {
-    let client = reqwest::blocking::Client::new();
-    let path = format!(
-        "{}/v2/withdrawal/ft/{}/{}/{}/{}/{}/{}",
-        http_origin,
-        block_height,
-        sender,
-        withdrawal_id,
-        StacksAddress::from(contract_identifier.issuer),
-        contract_identifier.name.as_str(),
-        amount
-    );
-
-    let res = client
-        .get(&path)
-        .send()
-        .unwrap()
-        .json::<WithdrawalResponse>()
+    let sender = PrincipalData::parse(&sender.to_string()).unwrap();
+    let res = SubnetRpcClient::new(http_origin)
+        .get_ft_withdrawal(
+            block_height,
+            &sender,
+            withdrawal_id,
+            &contract_identifier,
+            amount.into(),
+        )
         .unwrap();
     info!("Withdrawal response: {:#?}", res);
-    WithdrawalEntry {
-        leaf_hash: ClarityValue::try_deserialize_hex_untyped(&res.withdrawal_leaf_hash).unwrap(),
-        root_hash: ClarityValue::try_deserialize_hex_untyped(&res.withdrawal_root).unwrap(),
-        siblings: ClarityValue::try_deserialize_hex_untyped(&res.sibling_hashes).unwrap(),
-    }
+    WithdrawalEntry::from_response(&res)
 }
 
 pub fn get_nft_withdrawal_entry<F: std::fmt::Display>(
@@ -750,75 +844,34 @@ pub fn get_nft_withdrawal_entry<F: std::fmt::Display>(
     contract_identifier: QualifiedContractIdentifier,
     id: u64,
 ) -> WithdrawalEntry {
-    let client = reqwest::blocking::Client::new();
-    let path = format!(
-        "{}/v2/withdrawal/nft/{}/{}/{}/{}/{}/{}",
-        http_origin,
-        block_height,
-        sender,
-        withdrawal_id,
-        StacksAddress::from(contract_identifier.issuer),
-        contract_identifier.name.as_str(),
-        id
-    );
-
-    let res = client
-        .get(&path)
-        .send()
-        .unwrap()
-        .json::<WithdrawalResponse>()
+    let sender = PrincipalData::parse(&sender.to_string()).unwrap();
+    let res = SubnetRpcClient::new(http_origin)
+        .get_nft_withdrawal(
+            block_height,
+            &sender,
+            withdrawal_id,
+            &contract_identifier,
+            id.into(),
+        )
         .unwrap();
     info!("Withdrawal response: {:#?}", res);
-    WithdrawalEntry {
-        leaf_hash: ClarityValue::try_deserialize_hex_untyped(&res.withdrawal_leaf_hash).unwrap(),
-        root_hash: ClarityValue::try_deserialize_hex_untyped(&res.withdrawal_root).unwrap(),
-        siblings: ClarityValue::try_deserialize_hex_untyped(&res.sibling_hashes).unwrap(),
-    }
+    WithdrawalEntry::from_response(&res)
 }
 
 fn get_pox_info(http_origin: &str) -> RPCPoxInfoData {
-    let client = reqwest::blocking::Client::new();
-    let path = format!("{}/v2/pox", http_origin);
-    client
-        .get(&path)
-        .send()
-        .unwrap()
-        .json::<RPCPoxInfoData>()
-        .unwrap()
+    SubnetRpcClient::new(http_origin).get_pox_info().unwrap()
 }
 
 fn get_chain_tip(http_origin: &str) -> (ConsensusHash, BlockHeaderHash) {
-    let client = reqwest::blocking::Client::new();
-    let path = format!("{}/v2/info", http_origin);
-    let res = client
-        .get(&path)
-        .send()
-        .unwrap()
-        .json::<serde_json::Value>()
-        .unwrap();
-    (
-        ConsensusHash::from_hex(
-            res.get("stacks_tip_consensus_hash")
-                .unwrap()
-                .as_str()
-                .unwrap(),
-        )
-        .unwrap(),
-        BlockHeaderHash::from_hex(res.get("stacks_tip").unwrap().as_str().unwrap()).unwrap(),
-    )
+    let res = SubnetRpcClient::new(http_origin).get_info().unwrap();
+    (res.stacks_tip_consensus_hash, res.stacks_tip)
 }
 
 fn get_chain_tip_height(http_origin: &str) -> u64 {
-    let client = reqwest::blocking::Client::new();
-    let path = format!("{}/v2/info", http_origin);
-    let res = client
-        .get(&path)
-        .send()
+    SubnetRpcClient::new(http_origin)
+        .get_info()
         .unwrap()
-        .json::<RPCPeerInfoData>()
-        .unwrap();
-
-    res.stacks_tip_height
+        .stacks_tip_height
 }
 
 fn get_contract_src(
@@ -827,24 +880,18 @@ fn get_contract_src(
     contract_name: String,
     use_latest_tip: bool,
 ) -> Result<String, String> {
-    let client = reqwest::blocking::Client::new();
-    let query_string = if use_latest_tip {
-        "?tip=latest".to_string()
-    } else {
-        "".to_string()
-    };
-    let path = format!(
-        "{}/v2/contracts/source/{}/{}{}",
-        http_origin, contract_addr, contract_name, query_string
+    let contract_id = QualifiedContractIdentifier::new(
+        contract_addr.into(),
+        ContractName::try_from(contract_name).unwrap(),
     );
-    let res = client.get(&path).send().unwrap();
-
-    if res.status().is_success() {
-        let contract_src_res = res.json::<ContractSrcResponse>().unwrap();
-        Ok(contract_src_res.source)
+    let tip = if use_latest_tip {
+        TipRequest::UseLatestUnconfirmedTip
     } else {
-        let err_str = res.text().unwrap();
-        Err(err_str)
+        TipRequest::UseLatestAnchoredTip
+    };
+    match SubnetRpcClient::new(http_origin).get_contract_source(&contract_id, &tip) {
+        Ok(contract_src_res) => Ok(contract_src_res.source),
+        Err(e) => Err(e.to_string()),
     }
 }
 
@@ -886,7 +933,7 @@ fn faucet_test() {
 
     let mut btc_regtest_controller = MockController::new(conf.clone(), channel.clone());
 
-    thread::spawn(move || run_loop.start(None, 0));
+    thread::spawn(move || run_loop.start(None, 0).unwrap());
 
     // give the run loop some time to start up!
     wait_for_runloop(&blocks_processed);
@@ -1062,7 +1109,7 @@ fn no_contract_calls_forking_integration_test() {
 
     test_observer::spawn();
     let termination_switch = run_loop.get_termination_switch();
-    let run_loop_thread = thread::spawn(move || run_loop.start(None, 0));
+    let run_loop_thread = thread::spawn(move || run_loop.start(None, 0).unwrap());
 
     // btc_regtest_controller.next_block(None);
     wait_for_runloop(&blocks_processed);
@@ -1181,7 +1228,7 @@ fn transactions_in_block_and_microblock() {
 
     let mut btc_regtest_controller = MockController::new(conf.clone(), channel.clone());
 
-    thread::spawn(move || run_loop.start(None, 0));
+    thread::spawn(move || run_loop.start(None, 0).unwrap());
 
     // give the run loop some time to start up!
     wait_for_runloop(&blocks_processed);
@@ -1451,7 +1498,7 @@ fn transactions_microblocks_then_block() {
 
     let mut btc_regtest_controller = MockController::new(conf.clone(), channel.clone());
 
-    thread::spawn(move || run_loop.start(None, 0));
+    thread::spawn(move || run_loop.start(None, 0).unwrap());
     wait_for_runloop(&blocks_processed);
 
     let (sortition_db, _) = burnchain.open_db(true).unwrap();
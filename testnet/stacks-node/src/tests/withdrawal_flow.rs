@@ -0,0 +1,344 @@
+//! Helpers which take an STX withdrawal from the subnet back to the L1: make the withdrawal on
+//! the subnet, wait for the subnet block holding it to be committed to the L1, fetch its Merkle
+//! proof from the subnet node, and claim it on the L1. `withdraw_stx_to_l1` runs the whole flow
+//! and checks the L1 balance at the end, so that a subnet deployment can smoke-test its bridge
+//! with a single call.
+
+use std::collections::HashMap;
+use std::env;
+use std::sync::atomic::Ordering;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use clarity::vm::types::{PrincipalData, QualifiedContractIdentifier};
+use clarity::vm::Value;
+use stacks::burnchains::Burnchain;
+use stacks::chainstate::stacks::StacksPrivateKey;
+use stacks::core::LAYER_1_CHAIN_ID_TESTNET;
+use stacks::types::chainstate::StacksAddress;
+
+use crate::config::{EventKeyType, EventObserverConfig};
+use crate::neon;
+use crate::tests::l1_multiparty::MOCKNET_EPOCH_2_1;
+use crate::tests::l1_observer_test::{
+    publish_subnet_contracts_to_l1, wait_for_l1_height, wait_for_l1_tx_in_subnet,
+    wait_for_next_stacks_block, wait_for_tx_confirmation, StacksL1Controller,
+    MOCKNET_PRIVATE_KEY_1, MOCKNET_PRIVATE_KEY_2, PANIC_TIMEOUT_SECS,
+};
+use crate::tests::neon_integrations::{
+    filter_map_events, get_account, get_withdrawal_entry, submit_tx, test_observer, WithdrawalEntry,
+};
+use crate::tests::{make_contract_call, make_contract_publish, to_addr};
+
+/// An STX withdrawal recorded by the subnet's `.subnet` boot contract.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StxWithdrawal {
+    /// height of the subnet block holding the withdrawal
+    pub height: u64,
+    pub withdrawal_id: u32,
+    pub amount: u64,
+    pub sender: PrincipalData,
+}
+
+/// Find the STX withdrawal made by the subnet transaction `txid` among the blocks the test
+/// observer has received.
+pub fn find_stx_withdrawal(txid: &str) -> Option<StxWithdrawal> {
+    let txid = format!("0x{}", txid.trim_start_matches("0x"));
+    let block_data = test_observer::get_blocks();
+    let mut withdrawals = filter_map_events(&block_data, |height, event| {
+        if event.get("txid")?.as_str()? != txid || event.get("type")?.as_str()? != "contract_event"
+        {
+            return None;
+        }
+        let contract_event = event.get("contract_event")?;
+        if contract_event.get("contract_identifier")?.as_str()?
+            != "ST000000000000000000002AMW42H.subnet"
+            || contract_event.get("topic")?.as_str()? != "print"
+        {
+            return None;
+        }
+        let value: Value = serde_json::from_value(contract_event.get("value")?.clone()).ok()?;
+        let data_map = value.expect_tuple();
+        if data_map.get("event").ok()?.clone().expect_ascii() != "withdraw"
+            || data_map.get("type").ok()?.clone().expect_ascii() != "stx"
+        {
+            return None;
+        }
+        Some(StxWithdrawal {
+            height,
+            withdrawal_id: data_map.get("withdrawal-id").ok()?.clone().expect_u128() as u32,
+            amount: data_map.get("amount").ok()?.clone().expect_u128() as u64,
+            sender: data_map.get("sender").ok()?.clone().expect_principal(),
+        })
+    });
+    withdrawals.pop()
+}
+
+/// Submit the subnet transaction `withdraw_tx`, which must withdraw STX through the `.subnet`
+/// boot contract, wait for it to be mined, and return the withdrawal it made.
+pub fn l2_withdraw_stx(l2_rpc_origin: &str, withdraw_tx: &Vec<u8>) -> StxWithdrawal {
+    let txid = submit_tx(l2_rpc_origin, withdraw_tx);
+    wait_for_tx_confirmation(l2_rpc_origin, &txid);
+
+    // the observer may receive the block a moment after the node has processed it
+    let start = Instant::now();
+    loop {
+        if let Some(withdrawal) = find_stx_withdrawal(&txid) {
+            info!("l2_withdraw_stx finished"; "txid" => &txid, "withdrawal" => ?withdrawal);
+            return withdrawal;
+        }
+        if start.elapsed() > Duration::from_secs(PANIC_TIMEOUT_SECS) {
+            panic!("Transaction {} was mined but made no STX withdrawal", txid);
+        }
+        thread::sleep(Duration::from_millis(100));
+    }
+}
+
+/// Has `withdrawal_root` been committed to the L1 subnet contract `subnet_contract`?
+pub fn is_withdrawal_root_committed(
+    l1_rpc_origin: &str,
+    subnet_contract: &QualifiedContractIdentifier,
+    withdrawal_root: &Value,
+) -> bool {
+    let client = reqwest::blocking::Client::new();
+    let path = format!(
+        "{}/v2/map_entry/{}/{}/withdrawal-roots-map?proof=0",
+        l1_rpc_origin, &subnet_contract.issuer, &subnet_contract.name
+    );
+    let res = match client.post(&path).json(&withdrawal_root.serialize()).send() {
+        Ok(res) => res,
+        Err(_) => return false,
+    };
+    let res = match res.json::<HashMap<String, String>>() {
+        Ok(res) => res,
+        Err(_) => return false,
+    };
+    match res.get("data") {
+        Some(data) => Value::try_deserialize_hex_untyped(&data[2..])
+            .map(|entry| entry.expect_optional().is_some())
+            .unwrap_or(false),
+        None => false,
+    }
+}
+
+/// Fetch the Merkle proof for `withdrawal` from the subnet node, then wait until the subnet
+/// block holding it has been committed to the L1, so that the withdrawal can be claimed.
+pub fn wait_for_withdrawal_root(
+    l1_rpc_origin: &str,
+    l2_rpc_origin: &str,
+    subnet_contract: &QualifiedContractIdentifier,
+    withdrawal: &StxWithdrawal,
+) -> WithdrawalEntry {
+    let entry = get_withdrawal_entry(
+        l2_rpc_origin,
+        withdrawal.height,
+        &withdrawal.sender,
+        withdrawal.withdrawal_id,
+        withdrawal.amount,
+    );
+
+    info!("wait_for_withdrawal_root started"; "height" => withdrawal.height);
+    let start = Instant::now();
+    while !is_withdrawal_root_committed(l1_rpc_origin, subnet_contract, &entry.root_hash) {
+        if start.elapsed() > Duration::from_secs(PANIC_TIMEOUT_SECS) {
+            panic!(
+                "Timed out waiting for the withdrawal root of subnet block {} to reach the L1",
+                withdrawal.height
+            );
+        }
+        thread::sleep(Duration::from_millis(500));
+    }
+    info!("wait_for_withdrawal_root finished"; "height" => withdrawal.height);
+    entry
+}
+
+/// Make the L1 `withdraw-stx` call which claims `withdrawal`, using the proof in `entry`.
+pub fn make_l1_stx_withdrawal_claim(
+    sender: &StacksPrivateKey,
+    nonce: u64,
+    tx_fee: u64,
+    subnet_contract: &QualifiedContractIdentifier,
+    withdrawal: &StxWithdrawal,
+    entry: &WithdrawalEntry,
+) -> Vec<u8> {
+    make_contract_call(
+        sender,
+        LAYER_1_CHAIN_ID_TESTNET,
+        nonce,
+        tx_fee,
+        &StacksAddress::from(subnet_contract.issuer.clone()),
+        subnet_contract.name.as_str(),
+        "withdraw-stx",
+        &[
+            Value::UInt(withdrawal.amount.into()),
+            Value::Principal(withdrawal.sender.clone()),
+            Value::UInt(withdrawal.withdrawal_id.into()),
+            Value::UInt(withdrawal.height.into()),
+            entry.root_hash.clone(),
+            entry.leaf_hash.clone(),
+            entry.siblings.clone(),
+        ],
+    )
+}
+
+/// Withdraw STX from the subnet to the L1 from start to finish: submit `l2_withdraw_tx`, wait
+/// for its withdrawal root to be committed, and claim it on the L1 with `claimer`, which must
+/// own the withdrawing account on the L1. Asserts that the claimer's L1 balance went up by the
+/// withdrawn amount, less the claim's fee, and returns the withdrawal.
+pub fn withdraw_stx_to_l1(
+    l1_rpc_origin: &str,
+    l2_rpc_origin: &str,
+    subnet_contract: &QualifiedContractIdentifier,
+    l2_withdraw_tx: &Vec<u8>,
+    claimer: &StacksPrivateKey,
+    l1_nonce: u64,
+    l1_fee: u64,
+) -> StxWithdrawal {
+    let withdrawal = l2_withdraw_stx(l2_rpc_origin, l2_withdraw_tx);
+    let claimer_addr = to_addr(claimer);
+    assert_eq!(
+        withdrawal.sender,
+        PrincipalData::from(claimer_addr.clone()),
+        "The claimer must be the account which withdrew on the subnet"
+    );
+
+    let entry =
+        wait_for_withdrawal_root(l1_rpc_origin, l2_rpc_origin, subnet_contract, &withdrawal);
+
+    let l1_balance_before = get_account(l1_rpc_origin, &claimer_addr).balance;
+    let claim = make_l1_stx_withdrawal_claim(
+        claimer,
+        l1_nonce,
+        l1_fee,
+        subnet_contract,
+        &withdrawal,
+        &entry,
+    );
+    let claim_txid = submit_tx(l1_rpc_origin, &claim);
+    wait_for_tx_confirmation(l1_rpc_origin, &claim_txid);
+
+    let l1_balance_after = get_account(l1_rpc_origin, &claimer_addr).balance;
+    assert_eq!(
+        l1_balance_after,
+        l1_balance_before + withdrawal.amount as u128 - l1_fee as u128,
+        "The withdrawn STX should be credited on the L1"
+    );
+    withdrawal
+}
+
+/// Reference flow for the helpers above: deposit STX into the subnet, then withdraw them back to
+/// the L1 with `withdraw_stx_to_l1`.
+#[test]
+fn stx_withdrawal_reference_flow() {
+    // running locally:
+    // STACKS_BASE_DIR=~/devel/stacks-blockchain/target/release/stacks-node STACKS_NODE_TEST=1 cargo test --workspace stx_withdrawal_reference_flow
+    if env::var("STACKS_NODE_TEST") != Ok("1".into()) {
+        return;
+    }
+
+    // Start Stacks L1.
+    let l1_toml_file = "../../contrib/conf/stacks-l1-mocknet.toml";
+    let l1_rpc_origin = "http://127.0.0.1:20443";
+
+    // Start the L2 run loop.
+    let mut config = super::new_l1_test_conf(&*MOCKNET_PRIVATE_KEY_2, &*MOCKNET_PRIVATE_KEY_1);
+    let miner_account = to_addr(&MOCKNET_PRIVATE_KEY_2);
+    let user_addr = to_addr(&MOCKNET_PRIVATE_KEY_1);
+    let default_fee = 1_000_000;
+    config.add_initial_balance(user_addr.to_string(), 10_000_000);
+    config.add_initial_balance(miner_account.to_string(), 10_000_000);
+    let l2_rpc_origin = format!("http://{}", &config.node.rpc_bind);
+    let subnet_contract = config.burnchain.contract_identifier.clone();
+
+    config.events_observers.push(EventObserverConfig {
+        endpoint: format!("localhost:{}", test_observer::EVENT_OBSERVER_PORT),
+        events_keys: vec![EventKeyType::AnyEvent],
+    });
+    test_observer::spawn();
+
+    let mut run_loop = neon::RunLoop::new(config.clone());
+    let termination_switch = run_loop.get_termination_switch();
+    let run_loop_thread = thread::spawn(move || run_loop.start(None, 0).unwrap());
+
+    // Sleep to give the run loop time to start
+    thread::sleep(Duration::from_millis(2_000));
+
+    let burnchain = Burnchain::new(&config.get_burn_db_path(), &config.burnchain.chain).unwrap();
+    let (sortition_db, _) = burnchain.open_db(true).unwrap();
+
+    let mut stacks_l1_controller = StacksL1Controller::new(l1_toml_file.to_string(), true);
+    let _stacks_res = stacks_l1_controller
+        .start_process()
+        .expect("stacks l1 controller didn't start");
+
+    // Wait for the L1 chain to start and reach epoch 2.1
+    wait_for_l1_height(l1_rpc_origin, &sortition_db, MOCKNET_EPOCH_2_1 + 1);
+
+    let mut l1_nonce = publish_subnet_contracts_to_l1(
+        0,
+        &config,
+        miner_account.clone().into(),
+        user_addr.clone().into(),
+    );
+
+    // Wait a couple blocks to ensure the L2 chain has started
+    wait_for_next_stacks_block(&sortition_db);
+    wait_for_next_stacks_block(&sortition_db);
+
+    // The L1 contract only pays out STX it holds in escrow, so deposit some first
+    let l1_deposit_stx_tx = make_contract_call(
+        &MOCKNET_PRIVATE_KEY_1,
+        LAYER_1_CHAIN_ID_TESTNET,
+        l1_nonce,
+        default_fee,
+        &user_addr,
+        subnet_contract.name.as_str(),
+        "deposit-stx",
+        &[Value::UInt(5), Value::Principal(user_addr.into())],
+    );
+    l1_nonce += 1;
+    let l1_deposit_stx_txid = submit_tx(l1_rpc_origin, &l1_deposit_stx_tx);
+    wait_for_l1_tx_in_subnet(l1_rpc_origin, &sortition_db, &l1_deposit_stx_txid);
+
+    let subnet_simple_stx = "
+    (define-public (subnet-withdraw-stx (amount uint) (sender principal))
+      (contract-call? 'ST000000000000000000002AMW42H.subnet stx-withdraw? amount sender)
+    )
+    ";
+    let subnet_stx_publish = make_contract_publish(
+        &MOCKNET_PRIVATE_KEY_1,
+        config.node.chain_id,
+        0,
+        default_fee,
+        "simple-stx",
+        subnet_simple_stx,
+    );
+    let subnet_stx_publish_txid = submit_tx(&l2_rpc_origin, &subnet_stx_publish);
+    wait_for_tx_confirmation(&l2_rpc_origin, &subnet_stx_publish_txid);
+
+    let l2_withdraw_stx_tx = make_contract_call(
+        &MOCKNET_PRIVATE_KEY_1,
+        config.node.chain_id,
+        1,
+        default_fee,
+        &user_addr,
+        "simple-stx",
+        "subnet-withdraw-stx",
+        &[Value::UInt(3), Value::Principal(user_addr.into())],
+    );
+    let withdrawal = withdraw_stx_to_l1(
+        l1_rpc_origin,
+        &l2_rpc_origin,
+        &subnet_contract,
+        &l2_withdraw_stx_tx,
+        &MOCKNET_PRIVATE_KEY_1,
+        l1_nonce,
+        default_fee,
+    );
+    assert_eq!(withdrawal.amount, 3);
+    assert_eq!(withdrawal.sender, PrincipalData::from(user_addr));
+
+    termination_switch.store(false, Ordering::SeqCst);
+    stacks_l1_controller.kill_process();
+    run_loop_thread.join().expect("Failed to join run loop.");
+}
@@ -0,0 +1,360 @@
+use std::collections::{HashSet, VecDeque};
+use std::sync::mpsc::{channel, Receiver, RecvTimeoutError, Sender};
+use std::thread;
+use std::time::Duration;
+
+use stacks::burnchains::Txid;
+use stacks::chainstate::stacks::StacksTransaction;
+use stacks::core::mempool::MemPoolDB;
+use stacks::net::rpc::{TransactionForwardError, TransactionForwarder};
+use stacks::util::get_epoch_time_ms;
+
+use crate::rpc_only::PrimaryTxForwarder;
+
+/// How many txids the forwarder remembers, so that a transaction admitted again after it was
+/// mined out of the mempool, or reported twice (e.g. uploaded by a client and then pushed back
+/// by a peer), is only forwarded once
+const FORWARD_DEDUPE_CAPACITY: usize = 10_000;
+/// Forwards waiting to be sent or retried beyond this many are dropped
+const MAX_PENDING_FORWARDS: usize = 10_000;
+/// How many times to try to forward a transaction to one miner before giving up on it
+const FORWARD_MAX_ATTEMPTS: u32 = 5;
+/// Delay before the first retry; each further retry waits twice as long
+const FORWARD_INITIAL_BACKOFF_MS: u128 = 1_000;
+/// Upper bound on the delay between retries
+const FORWARD_MAX_BACKOFF_MS: u128 = 30_000;
+/// How often the forwarding thread checks for retries that are due
+const FORWARD_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// A transaction newly admitted to this node's mempool, with the expiration height and bundle
+/// its sender submitted it with, which the miners need to mine it the same way
+#[derive(Debug, Clone, PartialEq)]
+pub struct AdmittedTx {
+    pub tx: StacksTransaction,
+    pub expiration_height: Option<u64>,
+    pub bundle_id: Option<String>,
+}
+
+/// A transaction waiting to be forwarded to one miner
+#[derive(Debug, Clone, PartialEq)]
+pub struct PendingForward {
+    pub admitted: AdmittedTx,
+    /// index into the forwarder's miner RPC URLs
+    pub miner: usize,
+    /// failed attempts so far
+    pub attempts: u32,
+    pub next_attempt_ms: u128,
+}
+
+/// The forwards a follower has yet to deliver to its miners, and the txids it has already
+/// queued, so each transaction goes to each miner at most once
+pub struct ForwardQueue {
+    num_miners: usize,
+    seen: HashSet<Txid>,
+    seen_order: VecDeque<Txid>,
+    pending: VecDeque<PendingForward>,
+}
+
+impl ForwardQueue {
+    pub fn new(num_miners: usize) -> ForwardQueue {
+        ForwardQueue {
+            num_miners,
+            seen: HashSet::new(),
+            seen_order: VecDeque::new(),
+            pending: VecDeque::new(),
+        }
+    }
+
+    /// Queue `tx` to be forwarded to every miner right away.  Returns false if it was already
+    /// queued recently, or if too many forwards are pending.
+    pub fn push(&mut self, admitted: AdmittedTx, now_ms: u128) -> bool {
+        let txid = admitted.tx.txid();
+        if self.seen.contains(&txid) {
+            return false;
+        }
+        if self.pending.len() + self.num_miners > MAX_PENDING_FORWARDS {
+            warn!(
+                "Too many transactions waiting to be forwarded to miners; dropping {}",
+                &txid
+            );
+            return false;
+        }
+        self.seen.insert(txid.clone());
+        self.seen_order.push_back(txid);
+        if self.seen_order.len() > FORWARD_DEDUPE_CAPACITY {
+            if let Some(oldest) = self.seen_order.pop_front() {
+                self.seen.remove(&oldest);
+            }
+        }
+        for miner in 0..self.num_miners {
+            self.pending.push_back(PendingForward {
+                admitted: admitted.clone(),
+                miner,
+                attempts: 0,
+                next_attempt_ms: now_ms,
+            });
+        }
+        true
+    }
+
+    /// Take the forwards whose next attempt is due at `now_ms`
+    pub fn take_due(&mut self, now_ms: u128) -> Vec<PendingForward> {
+        let (due, waiting): (Vec<_>, Vec<_>) = self
+            .pending
+            .drain(..)
+            .partition(|forward| forward.next_attempt_ms <= now_ms);
+        self.pending = waiting.into();
+        due
+    }
+
+    /// Schedule another attempt at a forward which failed at `now_ms`, backing off
+    /// exponentially.  Returns false if the forward has used up its attempts and was dropped.
+    pub fn retry(&mut self, mut forward: PendingForward, now_ms: u128) -> bool {
+        forward.attempts += 1;
+        if forward.attempts >= FORWARD_MAX_ATTEMPTS {
+            return false;
+        }
+        let backoff = FORWARD_INITIAL_BACKOFF_MS
+            .saturating_mul(1 << (forward.attempts - 1))
+            .min(FORWARD_MAX_BACKOFF_MS);
+        forward.next_attempt_ms = now_ms.saturating_add(backoff);
+        self.pending.push_back(forward);
+        true
+    }
+
+    pub fn num_pending(&self) -> usize {
+        self.pending.len()
+    }
+}
+
+/// Try to deliver each forward in `queue` that is due at `now_ms` with `send`, which is given
+/// the miner's index.  Forwards the miner could not be reached for are retried later; those it
+/// rejected (e.g. because it already has the transaction) are not.
+pub fn forward_due<F>(queue: &mut ForwardQueue, now_ms: u128, mut send: F)
+where
+    F: FnMut(usize, &AdmittedTx) -> Result<(), TransactionForwardError>,
+{
+    for forward in queue.take_due(now_ms) {
+        match send(forward.miner, &forward.admitted) {
+            Ok(()) => {
+                debug!(
+                    "Forwarded transaction {} to miner {}",
+                    &forward.admitted.tx.txid(),
+                    forward.miner
+                );
+            }
+            Err(TransactionForwardError::Rejected(reason)) => {
+                debug!(
+                    "Miner {} rejected forwarded transaction {}: {}",
+                    forward.miner,
+                    &forward.admitted.tx.txid(),
+                    &reason
+                );
+            }
+            Err(TransactionForwardError::Unreachable(e)) => {
+                let txid = forward.admitted.tx.txid();
+                let miner = forward.miner;
+                if !queue.retry(forward, now_ms) {
+                    warn!(
+                        "Giving up forwarding transaction {} to miner {}: {}",
+                        &txid, miner, &e
+                    );
+                }
+            }
+        }
+    }
+}
+
+/// Relays the transactions this node admits to its mempool to the miners' RPC endpoints as soon
+/// as they are admitted, so that transactions submitted to a follower reach the miners without
+/// waiting on mempool sync.  Forwarding happens on its own thread, so the relayer never waits
+/// on a miner.
+pub struct MinerTxForwarder {
+    sender: Sender<Vec<AdmittedTx>>,
+}
+
+impl MinerTxForwarder {
+    /// Start forwarding to the miners at `miner_rpc_urls` (e.g. `http://10.0.0.1:20443`).
+    /// Returns `None` if there are none.
+    pub fn spawn(miner_rpc_urls: &[String]) -> Option<MinerTxForwarder> {
+        if miner_rpc_urls.is_empty() {
+            return None;
+        }
+        let (sender, receiver) = channel();
+        let miners: Vec<_> = miner_rpc_urls
+            .iter()
+            .map(|url| PrimaryTxForwarder::new(url))
+            .collect();
+        info!(
+            "Forwarding admitted transactions to miners at {}",
+            miner_rpc_urls.join(", ")
+        );
+        thread::Builder::new()
+            .name("tx-forwarder".to_string())
+            .spawn(move || run_forwarder(miners, receiver))
+            .expect("FATAL: failed to start transaction forwarding thread");
+        Some(MinerTxForwarder { sender })
+    }
+
+    /// Queue `txs`, newly admitted to `mempool`, to be forwarded to every miner along with the
+    /// expiration heights and bundles `mempool` has for them
+    pub fn forward(&self, mempool: &MemPoolDB, txs: &[StacksTransaction]) {
+        if txs.is_empty() {
+            return;
+        }
+        let mut admitted = Vec::with_capacity(txs.len());
+        for tx in txs.iter() {
+            let metadata = match MemPoolDB::get_tx(mempool.conn(), &tx.txid()) {
+                Ok(Some(tx_info)) => tx_info.metadata,
+                Ok(None) => {
+                    // already mined or evicted
+                    continue;
+                }
+                Err(e) => {
+                    warn!(
+                        "Failed to load mempool transaction {} to forward it: {:?}",
+                        &tx.txid(),
+                        &e
+                    );
+                    continue;
+                }
+            };
+            admitted.push(AdmittedTx {
+                tx: tx.clone(),
+                expiration_height: metadata.expiration_height,
+                bundle_id: metadata.bundle_id,
+            });
+        }
+        if self.sender.send(admitted).is_err() {
+            warn!("Transaction forwarding thread has exited; not forwarding transactions");
+        }
+    }
+}
+
+fn run_forwarder(miners: Vec<PrimaryTxForwarder>, receiver: Receiver<Vec<AdmittedTx>>) {
+    let mut queue = ForwardQueue::new(miners.len());
+    loop {
+        match receiver.recv_timeout(FORWARD_POLL_INTERVAL) {
+            Ok(txs) => {
+                let now_ms = get_epoch_time_ms();
+                for admitted in txs.into_iter() {
+                    queue.push(admitted, now_ms);
+                }
+            }
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => {
+                debug!(
+                    "Transaction forwarder exiting with {} forwards pending",
+                    queue.num_pending()
+                );
+                return;
+            }
+        }
+        forward_due(&mut queue, get_epoch_time_ms(), |miner, admitted| {
+            miners[miner].forward_transaction(
+                &admitted.tx,
+                None,
+                admitted.expiration_height,
+                admitted.bundle_id.as_deref(),
+            )
+        });
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use stacks::chainstate::stacks::{
+        StacksPrivateKey, TokenTransferMemo, TransactionAuth, TransactionPayload,
+        TransactionVersion,
+    };
+    use stacks::vm::types::PrincipalData;
+
+    fn make_admitted(nonce: u64, bundle_id: Option<&str>) -> AdmittedTx {
+        let mut tx = StacksTransaction::new(
+            TransactionVersion::Testnet,
+            TransactionAuth::from_p2pkh(&StacksPrivateKey::new()).unwrap(),
+            TransactionPayload::TokenTransfer(
+                PrincipalData::parse("ST1PQHQKV0RJXZFY1DGX8MNSNYVE3VGZJSRTPGZGM").unwrap(),
+                10,
+                TokenTransferMemo([0; 34]),
+            ),
+        );
+        tx.set_origin_nonce(nonce);
+        AdmittedTx {
+            tx,
+            expiration_height: Some(100),
+            bundle_id: bundle_id.map(String::from),
+        }
+    }
+
+    #[test]
+    fn test_forward_dedupe() {
+        let mut queue = ForwardQueue::new(2);
+        let admitted = make_admitted(0, Some("swap-1"));
+        assert!(queue.push(admitted.clone(), 0));
+        assert!(!queue.push(admitted.clone(), 0));
+        assert_eq!(queue.num_pending(), 2);
+
+        // each miner gets the transaction once, with its expiration height and bundle
+        let mut sent = vec![];
+        forward_due(&mut queue, 0, |miner, forwarded| {
+            sent.push((miner, forwarded.clone()));
+            Ok(())
+        });
+        assert_eq!(sent, vec![(0, admitted.clone()), (1, admitted.clone())]);
+        assert_eq!(queue.num_pending(), 0);
+
+        // still deduped once delivered
+        assert!(!queue.push(admitted, 10));
+        assert!(queue.push(make_admitted(1, None), 10));
+    }
+
+    #[test]
+    fn test_forward_retry() {
+        let mut queue = ForwardQueue::new(2);
+        queue.push(make_admitted(0, None), 0);
+
+        // miner 0 is down and miner 1 already has the transaction
+        let mut attempts = 0;
+        let mut send = |miner: usize, _admitted: &AdmittedTx| {
+            if miner == 0 {
+                attempts += 1;
+                Err(TransactionForwardError::Unreachable(
+                    "connection refused".into(),
+                ))
+            } else {
+                Err(TransactionForwardError::Rejected(serde_json::json!({
+                    "reason": "ConflictingNonceInMempool"
+                })))
+            }
+        };
+        forward_due(&mut queue, 0, &mut send);
+        assert_eq!(queue.num_pending(), 1);
+
+        // nothing is due until the backoff passes
+        forward_due(&mut queue, FORWARD_INITIAL_BACKOFF_MS - 1, &mut send);
+        assert_eq!(queue.num_pending(), 1);
+
+        let mut now_ms = 0;
+        while queue.num_pending() > 0 {
+            now_ms += FORWARD_MAX_BACKOFF_MS;
+            forward_due(&mut queue, now_ms, &mut send);
+        }
+        assert_eq!(attempts, FORWARD_MAX_ATTEMPTS);
+    }
+
+    #[test]
+    fn test_forward_backoff() {
+        let mut queue = ForwardQueue::new(1);
+        queue.push(make_admitted(0, None), 0);
+        let mut forward = queue.take_due(0).pop().unwrap();
+        for expected in [1_000, 2_000, 4_000, 8_000] {
+            assert!(queue.retry(forward, 0));
+            forward = queue.take_due(u128::MAX).pop().unwrap();
+            assert_eq!(forward.next_attempt_ms, expected);
+        }
+        assert!(!queue.retry(forward, 0));
+        assert_eq!(queue.num_pending(), 0);
+    }
+}
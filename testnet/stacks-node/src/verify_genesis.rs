@@ -0,0 +1,228 @@
+use std::cell::RefCell;
+use std::convert::TryFrom;
+use std::fs;
+use std::process;
+use std::rc::Rc;
+
+use stacks::chainstate::stacks::db::{ClarityTx, StacksChainState};
+use stacks::chainstate::stacks::{
+    StacksTransaction, TransactionPayload, TransactionSmartContract, TransactionVersion,
+};
+use stacks::core::SUBNET_GENESIS_ROOT_HASH;
+use stacks::types::chainstate::TrieHash;
+use stacks::util_lib::boot::{boot_code_acc, boot_code_addr, boot_code_tx_auth};
+use stacks::util_lib::strings::StacksString;
+use stacks::vm::types::PrincipalData;
+use stacks::vm::{ContractName, Value};
+
+use crate::burnchains::burnchain_from_config;
+use crate::neon::RunLoop;
+use crate::run_loop::RunLoopError;
+use crate::Config;
+
+/// A custom genesis state: balances to seed, and contracts for the boot address to deploy after
+/// the boot code.  The node itself boots with an empty specification.
+#[derive(Clone, Default, Deserialize)]
+pub struct GenesisSpec {
+    #[serde(default)]
+    pub balance: Vec<GenesisBalance>,
+    #[serde(default)]
+    pub contract: Vec<GenesisContract>,
+}
+
+#[derive(Clone, Deserialize)]
+pub struct GenesisBalance {
+    pub address: String,
+    pub amount: u64,
+}
+
+#[derive(Clone, Deserialize)]
+pub struct GenesisContract {
+    pub name: String,
+    /// path to the contract's Clarity source
+    pub path: String,
+}
+
+impl GenesisSpec {
+    /// Read a genesis specification from a TOML file
+    pub fn from_path(path: &str) -> Result<GenesisSpec, RunLoopError> {
+        let content = fs::read_to_string(path).map_err(|e| {
+            RunLoopError::InvalidConfig(format!("failed to read genesis spec {}: {}", path, e))
+        })?;
+        toml::from_str(&content).map_err(|e| {
+            RunLoopError::InvalidConfig(format!("failed to parse genesis spec {}: {}", path, e))
+        })
+    }
+
+    fn get_balances(&self) -> Result<Vec<(PrincipalData, u64)>, RunLoopError> {
+        self.balance
+            .iter()
+            .map(|balance| {
+                let principal = PrincipalData::parse(&balance.address).map_err(|e| {
+                    RunLoopError::InvalidConfig(format!(
+                        "invalid genesis balance address `{}`: {:?}",
+                        &balance.address, &e
+                    ))
+                })?;
+                Ok((principal, balance.amount))
+            })
+            .collect()
+    }
+
+    fn get_contracts(&self) -> Result<Vec<TransactionSmartContract>, RunLoopError> {
+        self.contract
+            .iter()
+            .map(|contract| {
+                let name = ContractName::try_from(contract.name.clone()).map_err(|e| {
+                    RunLoopError::InvalidConfig(format!(
+                        "invalid genesis contract name `{}`: {:?}",
+                        &contract.name, &e
+                    ))
+                })?;
+                let code = fs::read_to_string(&contract.path).map_err(|e| {
+                    RunLoopError::InvalidConfig(format!(
+                        "failed to read genesis contract {}: {}",
+                        &contract.path, e
+                    ))
+                })?;
+                let code_body = StacksString::from_str(&code).ok_or_else(|| {
+                    RunLoopError::InvalidConfig(format!(
+                        "genesis contract {} is not a valid Clarity string",
+                        &contract.path
+                    ))
+                })?;
+                Ok(TransactionSmartContract { name, code_body })
+            })
+            .collect()
+    }
+}
+
+/// Boot an ephemeral chainstate from the node's config and `spec`, and compare the root hash of
+/// its genesis Clarity state with `expected` (by default, `SUBNET_GENESIS_ROOT_HASH`).  Prints
+/// both hashes, marking where they differ, and returns whether they match.
+pub fn verify_genesis(
+    config: &Config,
+    spec: &GenesisSpec,
+    expected: Option<&str>,
+) -> Result<bool, RunLoopError> {
+    let expected = expected.unwrap_or(SUBNET_GENESIS_ROOT_HASH).to_lowercase();
+    if TrieHash::from_hex(&expected).is_err() {
+        return Err(RunLoopError::InvalidConfig(format!(
+            "expected genesis root hash `{}` is not 32 hex-encoded bytes",
+            &expected
+        )));
+    }
+    let computed = compute_genesis_root_hash(config, spec)?;
+
+    println!("expected: {}", &expected);
+    println!("computed: {}", &computed);
+    if expected == computed {
+        println!("Genesis root hash matches");
+        return Ok(true);
+    }
+
+    let markers: String = expected
+        .chars()
+        .zip(computed.chars())
+        .map(|(a, b)| if a == b { ' ' } else { '^' })
+        .collect();
+    println!("          {}", markers.trim_end());
+    println!(
+        "Genesis root hash mismatch: {} of {} hex digits differ",
+        markers.matches('^').count(),
+        computed.len()
+    );
+    Ok(false)
+}
+
+/// Boot a chainstate in a temporary directory and return its genesis root hash
+fn compute_genesis_root_hash(config: &Config, spec: &GenesisSpec) -> Result<String, RunLoopError> {
+    let initial_balances = spec.get_balances()?;
+    let contracts = spec.get_contracts()?;
+
+    let burnchain = burnchain_from_config(&config.get_burn_db_path(), &config.burnchain)
+        .map_err(|e| RunLoopError::Chainstate(format!("failed to load burnchain: {:?}", &e)))?;
+    let mut boot_data = RunLoop::make_boot_data(config, &burnchain);
+    boot_data.initial_balances = initial_balances;
+    // the caller compares the root hash with the one it expects, and reports a mismatch
+    boot_data.check_genesis_root_hash = false;
+
+    let mainnet = config.is_mainnet();
+    let deploy_errors = Rc::new(RefCell::new(vec![]));
+    if !contracts.is_empty() {
+        let deploy_errors = deploy_errors.clone();
+        boot_data.post_flight_callback = Some(Box::new(move |clarity_tx: &mut ClarityTx| {
+            let boot_code_address = boot_code_addr(mainnet);
+            let boot_code_account = boot_code_acc(boot_code_address, 0);
+            let tx_version = if mainnet {
+                TransactionVersion::Mainnet
+            } else {
+                TransactionVersion::Testnet
+            };
+            for contract in contracts.into_iter() {
+                let name = contract.name.clone();
+                let tx = StacksTransaction::new(
+                    tx_version.clone(),
+                    boot_code_tx_auth(boot_code_address),
+                    TransactionPayload::SmartContract(contract, None),
+                );
+                let result = clarity_tx.connection().as_transaction(|clarity| {
+                    StacksChainState::process_transaction_payload(clarity, &tx, &boot_code_account)
+                });
+                match result {
+                    Ok(receipt) => {
+                        if let Value::Response(ref response) = receipt.result {
+                            if !response.committed {
+                                deploy_errors
+                                    .borrow_mut()
+                                    .push(format!("{}: {}", &name, &receipt.result));
+                            }
+                        }
+                    }
+                    Err(e) => deploy_errors
+                        .borrow_mut()
+                        .push(format!("{}: {:?}", &name, &e)),
+                }
+            }
+        }));
+    }
+
+    let path = std::env::temp_dir().join(format!("subnet-verify-genesis-{}", process::id()));
+    let path_str = path
+        .to_str()
+        .expect("BUG: temp dir path is not UTF-8")
+        .to_string();
+    if fs::metadata(&path).is_ok() {
+        fs::remove_dir_all(&path).map_err(|e| {
+            RunLoopError::Chainstate(format!("failed to clear {}: {}", &path_str, e))
+        })?;
+    }
+
+    let result = StacksChainState::open_and_exec(
+        mainnet,
+        config.node.chain_id,
+        &path_str,
+        Some(&mut boot_data),
+        Some(config.node.get_marf_opts()),
+    )
+    .map_err(|e| RunLoopError::Chainstate(format!("failed to boot chainstate: {:?}", &e)))
+    .and_then(|(mut chainstate, _)| {
+        chainstate.get_genesis_root_hash().map_err(|e| {
+            RunLoopError::Chainstate(format!("failed to read genesis root hash: {:?}", &e))
+        })
+    });
+
+    if let Err(e) = fs::remove_dir_all(&path) {
+        warn!("Failed to remove {}: {}", &path_str, e);
+    }
+
+    let root_hash = result?;
+    let deploy_errors = deploy_errors.borrow();
+    if !deploy_errors.is_empty() {
+        return Err(RunLoopError::Chainstate(format!(
+            "failed to deploy genesis contracts: {}",
+            deploy_errors.join("; ")
+        )));
+    }
+    Ok(root_hash.to_string())
+}
@@ -0,0 +1,79 @@
+use std::fs;
+use std::io::{BufReader, BufWriter, Write};
+
+use stacks::chainstate::stacks::db::withdrawal_archive::WithdrawalArchiveImportSummary;
+use stacks::chainstate::stacks::db::StacksChainState;
+
+use crate::run_loop::RunLoopError;
+use crate::Config;
+
+fn open_chainstate(config: &Config) -> Result<StacksChainState, RunLoopError> {
+    let chainstate_path = config.get_chainstate_path_str();
+    if fs::metadata(&chainstate_path).is_err() {
+        return Err(RunLoopError::Chainstate(format!(
+            "no chainstate at {}",
+            &chainstate_path
+        )));
+    }
+    let (chainstate, _) = StacksChainState::open(
+        config.is_mainnet(),
+        config.node.chain_id,
+        &chainstate_path,
+        Some(config.node.get_marf_opts()),
+    )
+    .map_err(|e| {
+        RunLoopError::Chainstate(format!("failed to open chainstate database: {:?}", &e))
+    })?;
+    Ok(chainstate)
+}
+
+/// Write the node's archived withdrawal trees to `dest_path`, replacing it if it exists.
+/// Returns the number of trees written.
+pub fn export_withdrawal_archive(config: &Config, dest_path: &str) -> Result<u64, RunLoopError> {
+    let chainstate = open_chainstate(config)?;
+
+    // write to a temporary file first, so an interrupted export leaves no partial file behind
+    let tmp_path = format!("{}.tmp", dest_path);
+    let num_trees = {
+        let file = fs::File::create(&tmp_path).map_err(|e| {
+            RunLoopError::Chainstate(format!("failed to create {}: {:?}", &tmp_path, &e))
+        })?;
+        let mut fd = BufWriter::new(file);
+        let num_trees = chainstate.export_withdrawal_archive(&mut fd).map_err(|e| {
+            RunLoopError::Chainstate(format!("failed to export withdrawal archive: {:?}", &e))
+        })?;
+        fd.flush().map_err(|e| {
+            RunLoopError::Chainstate(format!("failed to write {}: {:?}", &tmp_path, &e))
+        })?;
+        num_trees
+    };
+    fs::rename(&tmp_path, dest_path).map_err(|e| {
+        RunLoopError::Chainstate(format!("failed to write {}: {:?}", dest_path, &e))
+    })?;
+
+    info!("Exported {} withdrawal trees to {}", num_trees, dest_path);
+    Ok(num_trees)
+}
+
+/// Archive the withdrawal trees in `source_path`, as written by `export_withdrawal_archive`,
+/// in the node's chainstate, so it can serve proofs of the withdrawals in them.
+pub fn import_withdrawal_archive(
+    config: &Config,
+    source_path: &str,
+) -> Result<WithdrawalArchiveImportSummary, RunLoopError> {
+    let mut chainstate = open_chainstate(config)?;
+    let file = fs::File::open(source_path).map_err(|e| {
+        RunLoopError::Chainstate(format!("failed to open {}: {:?}", source_path, &e))
+    })?;
+    let summary = chainstate
+        .import_withdrawal_archive(BufReader::new(file))
+        .map_err(|e| {
+            RunLoopError::Chainstate(format!("failed to import {}: {:?}", source_path, &e))
+        })?;
+
+    info!(
+        "Imported {} withdrawal trees from {}; {} were already present",
+        summary.imported, source_path, summary.already_present
+    );
+    Ok(summary)
+}